@@ -19,63 +19,138 @@ pub type SystemResult<T> = std::result::Result<T, SystemError>;
 #[derive(Debug)]
 pub struct SystemError {
     message: String,
+    context: Vec<String>,
     backtrace: backtrace::Backtrace,
     kind: SystemErrorKind,
 }
 
 impl SystemError {
-    pub fn bug_in_sql_engine(operation: Operation, object: Object) -> SystemError {
-        fn context_message(operation: Operation, object: Object) -> String {
-            match object {
-                Object::Schema(schema_name) => format!(
-                    "It does not check '{}' existence of SCHEMA before {} one",
-                    schema_name, operation
-                ),
-                Object::Table(schema_name, table_name) => format!(
-                    "It does not check '{}.{}' existence of TABLE before {} one",
-                    schema_name, table_name, operation
-                ),
-            }
-        }
-
+    fn new(message: String, kind: SystemErrorKind) -> SystemError {
         SystemError {
-            message: format!(
-                "This is most possibly a 🐛[BUG] in sql engine.\n{}",
-                context_message(operation, object)
-            ),
+            message,
+            context: vec![],
             backtrace: backtrace::Backtrace::new(),
-            kind: SystemErrorKind::SqlEngineBug,
+            kind,
         }
     }
 
+    pub fn bug_in_sql_engine(operation: Operation, object: Object) -> SystemError {
+        SystemError::internal(format!(
+            "It does not check '{}' existence of {} before {} one",
+            object.name(),
+            object.kind(),
+            operation
+        ))
+    }
+
+    /// Violated invariant of the engine
+    pub fn internal(message: String) -> SystemError {
+        SystemError::new(
+            format!("This is most possibly a 🐛[BUG] in sql engine.\n{}", message),
+            SystemErrorKind::SqlEngineBug,
+        )
+    }
+
+    /// Statement is rejected, the user is already notified why
     pub fn runtime_check_failure(message: String) -> SystemError {
-        SystemError {
-            message,
-            backtrace: backtrace::Backtrace::new(),
-            kind: SystemErrorKind::RuntimeCheckFailure,
-        }
+        SystemError::new(message, SystemErrorKind::RuntimeCheckFailure)
+    }
+
+    /// Statement uses a feature the engine does not support, the user is
+    /// already notified about it
+    pub fn unsupported(feature: String) -> SystemError {
+        SystemError::new(
+            format!("Feature Not Supported: {}", feature),
+            SystemErrorKind::Unsupported,
+        )
+    }
+
+    /// Statement is stopped before it is complete
+    pub fn cancelled(reason: String) -> SystemError {
+        SystemError::new(reason, SystemErrorKind::Cancelled)
+    }
+
+    /// Stored data can't be read back as it was written
+    pub fn corruption(message: String) -> SystemError {
+        SystemError::new(message, SystemErrorKind::Corruption)
     }
 
     pub fn unrecoverable(message: String) -> SystemError {
-        SystemError {
-            message,
-            backtrace: backtrace::Backtrace::new(),
-            kind: SystemErrorKind::Unrecoverable,
-        }
+        SystemError::new(message, SystemErrorKind::Unrecoverable)
     }
 
     pub fn io(io_error: std::io::Error) -> SystemError {
-        SystemError {
-            message: "IO error has happened".to_owned(),
-            backtrace: backtrace::Backtrace::new(),
-            kind: SystemErrorKind::Io(io_error),
+        SystemError::new("IO error has happened".to_owned(), SystemErrorKind::Io(io_error))
+    }
+
+    pub fn connection_lost(io_error: std::io::Error) -> SystemError {
+        SystemError::new(
+            "Connection with client is lost".to_owned(),
+            SystemErrorKind::ConnectionLost(io_error),
+        )
+    }
+
+    /// Adds what the engine was doing when the error happened, the last
+    /// added context is the outermost one
+    pub fn context<C: Into<String>>(mut self, context: C) -> SystemError {
+        self.context.push(context.into());
+        self
+    }
+
+    pub fn kind(&self) -> &SystemErrorKind {
+        &self.kind
+    }
+
+    pub fn is_connection_lost(&self) -> bool {
+        matches!(self.kind, SystemErrorKind::ConnectionLost(_))
+    }
+
+    /// `true` if the error is caused by a statement of the user rather than
+    /// by the engine or its environment
+    pub fn is_user_error(&self) -> bool {
+        matches!(
+            self.kind,
+            SystemErrorKind::RuntimeCheckFailure | SystemErrorKind::Unsupported | SystemErrorKind::Cancelled
+        )
+    }
+}
+
+impl Display for SystemError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for context in self.context.iter().rev() {
+            write!(f, "{}: ", context)?;
+        }
+        write!(f, "{}", self.message)?;
+        match &self.kind {
+            SystemErrorKind::Io(io_error) | SystemErrorKind::ConnectionLost(io_error) => write!(f, ": {}", io_error),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl std::error::Error for SystemError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            SystemErrorKind::Io(io_error) | SystemErrorKind::ConnectionLost(io_error) => Some(io_error),
+            _ => None,
         }
     }
 }
 
 impl PartialEq for SystemError {
     fn eq(&self, other: &Self) -> bool {
-        self.message == other.message && self.kind == other.kind
+        self.message == other.message && self.context == other.context && self.kind == other.kind
+    }
+}
+
+/// Adds context to the error of a result
+pub trait Context<T> {
+    fn context<C: Into<String>>(self, context: C) -> SystemResult<T>;
+}
+
+impl<T> Context<T> for SystemResult<T> {
+    fn context<C: Into<String>>(self, context: C) -> SystemResult<T> {
+        self.map_err(|error| error.context(context))
     }
 }
 
@@ -83,8 +158,12 @@ impl PartialEq for SystemError {
 pub enum SystemErrorKind {
     Unrecoverable,
     RuntimeCheckFailure,
+    Unsupported,
+    Cancelled,
     SqlEngineBug,
+    Corruption,
     Io(std::io::Error),
+    ConnectionLost(std::io::Error),
 }
 
 pub enum Operation {
@@ -108,13 +187,97 @@ pub enum Object<'o> {
     Schema(&'o str),
 }
 
+impl<'o> Object<'o> {
+    fn kind(&self) -> &'static str {
+        match self {
+            Object::Table(_, _) => "TABLE",
+            Object::Schema(_) => "SCHEMA",
+        }
+    }
+
+    fn name(&self) -> String {
+        match self {
+            Object::Table(schema_name, table_name) => format!("{}.{}", schema_name, table_name),
+            Object::Schema(schema_name) => (*schema_name).to_owned(),
+        }
+    }
+}
+
+impl<'o> Display for Object<'o> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} '{}'", self.kind(), self.name())
+    }
+}
+
 impl PartialEq for SystemErrorKind {
     fn eq(&self, other: &Self) -> bool {
-        match (self, other) {
-            (SystemErrorKind::Io(_), SystemErrorKind::Io(_)) => true,
-            (SystemErrorKind::Unrecoverable, SystemErrorKind::Unrecoverable) => true,
-            (SystemErrorKind::RuntimeCheckFailure, SystemErrorKind::RuntimeCheckFailure) => true,
-            _ => false,
-        }
+        matches!(
+            (self, other),
+            (SystemErrorKind::Io(_), SystemErrorKind::Io(_))
+                | (SystemErrorKind::ConnectionLost(_), SystemErrorKind::ConnectionLost(_))
+                | (SystemErrorKind::Unrecoverable, SystemErrorKind::Unrecoverable)
+                | (
+                    SystemErrorKind::RuntimeCheckFailure,
+                    SystemErrorKind::RuntimeCheckFailure
+                )
+                | (SystemErrorKind::Unsupported, SystemErrorKind::Unsupported)
+                | (SystemErrorKind::Cancelled, SystemErrorKind::Cancelled)
+                | (SystemErrorKind::SqlEngineBug, SystemErrorKind::SqlEngineBug)
+                | (SystemErrorKind::Corruption, SystemErrorKind::Corruption)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{error::Error, io};
+
+    #[test]
+    fn context_is_displayed_outermost_first() {
+        let error = SystemError::corruption("checksum mismatch".to_owned())
+            .context("reading page 7")
+            .context("accessing TABLE 'schema_name.table_name'");
+
+        assert_eq!(
+            error.to_string(),
+            "accessing TABLE 'schema_name.table_name': reading page 7: checksum mismatch"
+        );
+    }
+
+    #[test]
+    fn context_of_result() {
+        let result: SystemResult<()> = Err(SystemError::unsupported("cursors".to_owned()));
+
+        assert_eq!(
+            result.context("declaring cursor"),
+            Err(SystemError::unsupported("cursors".to_owned()).context("declaring cursor"))
+        );
+    }
+
+    #[test]
+    fn io_error_is_the_source() {
+        let error = SystemError::io(io::Error::other("disk is full"));
+
+        assert_eq!(error.to_string(), "IO error has happened: disk is full");
+        assert_eq!(error.source().map(ToString::to_string), Some("disk is full".to_owned()));
+    }
+
+    #[test]
+    fn user_errors() {
+        assert!(SystemError::runtime_check_failure("Table Does Not Exist".to_owned()).is_user_error());
+        assert!(SystemError::unsupported("cursors".to_owned()).is_user_error());
+        assert!(SystemError::cancelled("statement timeout".to_owned()).is_user_error());
+        assert!(!SystemError::corruption("checksum mismatch".to_owned()).is_user_error());
+        assert!(!SystemError::bug_in_sql_engine(Operation::Access, Object::Schema("schema_name")).is_user_error());
+        assert!(!SystemError::io(io::Error::from(io::ErrorKind::Other)).is_user_error());
+    }
+
+    #[test]
+    fn bug_names_object() {
+        assert_eq!(
+            SystemError::bug_in_sql_engine(Operation::Drop, Object::Table("schema_name", "table_name")).to_string(),
+            "This is most possibly a 🐛[BUG] in sql engine.\nIt does not check 'schema_name.table_name' existence of TABLE before dropping one"
+        );
     }
 }
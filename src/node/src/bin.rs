@@ -15,9 +15,43 @@
 extern crate node;
 extern crate simple_logger;
 
+use node::config::{self, Config};
+use std::{env, process};
+
 fn main() {
-    simple_logger::SimpleLogger::from_env()
-        .init()
-        .expect("to initialize logger");
-    node::node::start();
+    let mut args = env::args().skip(1).collect::<Vec<String>>();
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        print!("{}", config::usage());
+        return;
+    }
+    let dump = args.first().map(String::as_str) == Some("dump");
+    if dump {
+        args.remove(0);
+    }
+    let script = if args.first().map(String::as_str) == Some("run") && args.len() > 1 {
+        args.remove(0);
+        Some(args.remove(0))
+    } else {
+        None
+    };
+    let config = match Config::load(&args) {
+        Ok(config) => config,
+        Err(error) => {
+            eprintln!("{}\n\n{}", error, config::usage());
+            process::exit(2);
+        }
+    };
+    if dump {
+        node::node::dump(&config);
+        return;
+    }
+    if let Some(script) = script {
+        if !node::node::run_script(&config, &script) {
+            process::exit(1);
+        }
+        return;
+    }
+    simple_logger::SimpleLogger::new().init().expect("to initialize logger");
+    log::set_max_level(config.log_level());
+    node::node::start(config);
 }
@@ -0,0 +1,628 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! Settings of the server. Each setting is looked up in command line flags,
+//! then in environment variables and then in the configuration file, e.g.
+//! `--max-connections 10`, `MAX_CONNECTIONS=10` and `max_connections = 10`.
+//! The configuration file is passed with `--config` or `CONFIG_FILE` and
+//! consists of `key = value` lines of TOML without tables.
+use log::LevelFilter;
+use std::{
+    collections::HashMap,
+    env, fmt, fs,
+    net::IpAddr,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Text,
+    Path,
+    Address,
+    Port,
+    Number,
+    Flag,
+    /// number of milliseconds
+    Millis,
+    LogLevel,
+}
+
+struct Setting {
+    name: &'static str,
+    kind: Kind,
+    default: Option<&'static str>,
+    description: &'static str,
+}
+
+const fn setting(name: &'static str, kind: Kind, default: Option<&'static str>, description: &'static str) -> Setting {
+    Setting {
+        name,
+        kind,
+        default,
+        description,
+    }
+}
+
+const SETTINGS: &[Setting] = &[
+    setting("HOST", Kind::Address, Some("0.0.0.0"), "address the server listens on"),
+    setting("PORT", Kind::Port, Some("5432"), "port of PostgreSQL protocol"),
+    setting(
+        "HTTP_PORT",
+        Kind::Port,
+        None,
+        "port of HTTP API on loopback address, disabled by default",
+    ),
+    setting("PERSISTENT", Kind::Flag, Some("false"), "store data on disk"),
+    setting(
+        "ROOT_PATH",
+        Kind::Path,
+        Some(""),
+        "data directory of persistent storage",
+    ),
+    setting("SECURE", Kind::Text, None, "`ssl_only` to accept TLS connections"),
+    setting(
+        "PFX_CERTIFICATE_FILE",
+        Kind::Path,
+        None,
+        "PKCS #12 identity of the server",
+    ),
+    setting("PFX_CERTIFICATE_PASSWORD", Kind::Text, None, "password of the identity"),
+    setting(
+        "CLIENT_CA_FILE",
+        Kind::Path,
+        None,
+        "authorities that sign client certificates",
+    ),
+    setting(
+        "REQUIRE_CLIENT_CERTIFICATE",
+        Kind::Flag,
+        Some("false"),
+        "reject clients without certificate",
+    ),
+    setting(
+        "HBA_CONFIG_FILE",
+        Kind::Path,
+        None,
+        "`pg_hba.conf` like host based access rules",
+    ),
+    setting(
+        "LOG_LEVEL",
+        Kind::LogLevel,
+        None,
+        "off, error, warn, info, debug or trace, `RUST_LOG` by default",
+    ),
+    setting(
+        "LOG_CONNECTIONS",
+        Kind::Flag,
+        Some("false"),
+        "log established client sessions",
+    ),
+    setting(
+        "LOG_DISCONNECTIONS",
+        Kind::Flag,
+        Some("false"),
+        "log closed client sessions",
+    ),
+    setting(
+        "LOG_MIN_DURATION_STATEMENT",
+        Kind::Millis,
+        None,
+        "log statements that run longer, milliseconds",
+    ),
+    setting(
+        "AUTO_EXPLAIN_LOG_MIN_DURATION",
+        Kind::Millis,
+        None,
+        "log plans of statements that run longer, milliseconds",
+    ),
+    setting("AUDIT_LOG_FILE", Kind::Path, None, "audit log of executed statements"),
+    setting("AUDIT_CLASSES", Kind::Text, Some("all"), "audited statement classes"),
+    setting(
+        "AUDIT_ROLE_CLASSES",
+        Kind::Text,
+        None,
+        "audited classes of roles, e.g. `reporting=read`",
+    ),
+    setting(
+        "MAX_CONNECTIONS",
+        Kind::Number,
+        Some("100"),
+        "maximum number of client connections",
+    ),
+    setting(
+        "MAX_PARALLEL_WORKERS",
+        Kind::Number,
+        Some("2"),
+        "worker threads of a query",
+    ),
+    setting(
+        "WORK_MEM",
+        Kind::Number,
+        Some("4096"),
+        "kilobytes of intermediate results of a query operator",
+    ),
+    setting(
+        "STATEMENT_TIMEOUT",
+        Kind::Millis,
+        None,
+        "cancel statements that run longer, milliseconds",
+    ),
+    setting(
+        "RESULT_CACHE_SIZE",
+        Kind::Number,
+        None,
+        "number of cached query results, disabled by default",
+    ),
+    setting(
+        "CHECKPOINT_TIMEOUT",
+        Kind::Millis,
+        Some("300000"),
+        "time between checkpoints of persistent storage, milliseconds",
+    ),
+    setting(
+        "MAX_STATEMENT_STATISTICS",
+        Kind::Number,
+        Some("5000"),
+        "statements tracked by `pg_stat_statements`",
+    ),
+    setting(
+        "AUTOVACUUM",
+        Kind::Flag,
+        Some("true"),
+        "vacuum and analyze tables automatically",
+    ),
+    setting(
+        "AUTOVACUUM_NAPTIME",
+        Kind::Millis,
+        Some("60000"),
+        "time between autovacuum rounds, milliseconds",
+    ),
+    setting(
+        "AUTOVACUUM_VACUUM_THRESHOLD",
+        Kind::Number,
+        Some("50"),
+        "dead rows that make a table vacuumed",
+    ),
+    setting(
+        "AUTOVACUUM_ANALYZE_THRESHOLD",
+        Kind::Number,
+        Some("50"),
+        "modified rows that make a table analyzed",
+    ),
+];
+
+/// Settings that a running server applies when it is sent `SIGHUP` or a
+/// superuser calls `pg_reload_conf()`, the other ones require a restart
+const RELOADABLE: &[&str] = &[
+    "HBA_CONFIG_FILE",
+    "LOG_LEVEL",
+    "LOG_CONNECTIONS",
+    "LOG_DISCONNECTIONS",
+    "LOG_MIN_DURATION_STATEMENT",
+    "AUTO_EXPLAIN_LOG_MIN_DURATION",
+    "MAX_CONNECTIONS",
+];
+
+/// Values of settings, the ones that are present are valid for their kind
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    args: Vec<String>,
+    values: HashMap<&'static str, String>,
+}
+
+impl Config {
+    /// Settings from command line `args` without the program name and
+    /// subcommand, the process environment and the configuration file
+    pub fn load(args: &[String]) -> Result<Config, String> {
+        Config::from_sources(
+            args,
+            |name| env::var(name).ok(),
+            |path| fs::read_to_string(path).map_err(|error| format!("could not read {}: {}", path.display(), error)),
+        )
+    }
+
+    fn from_sources(
+        args: &[String],
+        env: impl Fn(&str) -> Option<String>,
+        read_file: impl Fn(&Path) -> Result<String, String>,
+    ) -> Result<Config, String> {
+        let mut flags = parse_flags(args)?;
+        let mut values = HashMap::new();
+        let file = flags.remove("CONFIG_FILE").or_else(|| env("CONFIG_FILE"));
+        if let Some(file) = file {
+            let path = PathBuf::from(file);
+            let text = read_file(&path)?;
+            for (name, value) in parse_file(&text).map_err(|error| format!("{}: {}", path.display(), error))? {
+                values.insert(name, value);
+            }
+        }
+        for setting in SETTINGS {
+            if let Some(value) = env(setting.name) {
+                values.insert(setting.name, value);
+            }
+        }
+        values.extend(flags);
+        for setting in SETTINGS {
+            match values.get(setting.name) {
+                Some(value) => validate(setting, value)?,
+                None => {
+                    if let Some(default) = setting.default {
+                        values.insert(setting.name, default.to_owned());
+                    }
+                }
+            }
+        }
+        Ok(Config {
+            args: args.to_vec(),
+            values,
+        })
+    }
+
+    /// Loads settings again from the same command line flags, the current
+    /// environment and the configuration file
+    pub fn reload(&self) -> Result<Config, String> {
+        Config::load(&self.args)
+    }
+
+    /// Settings that differ in `reloaded` config but can't be changed
+    /// without a restart
+    pub fn requires_restart(&self, reloaded: &Config) -> Vec<&'static str> {
+        SETTINGS
+            .iter()
+            .map(|setting| setting.name)
+            .filter(|name| !RELOADABLE.contains(name) && self.values.get(name) != reloaded.values.get(name))
+            .collect()
+    }
+
+    pub fn text(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(String::as_str)
+    }
+
+    pub fn path(&self, name: &str) -> Option<PathBuf> {
+        self.text(name).map(PathBuf::from)
+    }
+
+    pub fn address(&self, name: &str) -> Option<IpAddr> {
+        self.text(name).map(|value| value.parse().expect("valid address"))
+    }
+
+    pub fn port(&self, name: &str) -> Option<u16> {
+        self.text(name).map(|value| value.parse().expect("valid port"))
+    }
+
+    pub fn number(&self, name: &str) -> Option<usize> {
+        self.text(name).map(|value| value.parse().expect("valid number"))
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.text(name).map(|value| flag(value) == Some(true)).unwrap_or(false)
+    }
+
+    pub fn millis(&self, name: &str) -> Option<Duration> {
+        self.text(name)
+            .map(|value| Duration::from_millis(value.parse().expect("valid number of milliseconds")))
+    }
+
+    /// `LOG_LEVEL` or `RUST_LOG` level when it is not set
+    pub fn log_level(&self) -> LevelFilter {
+        match self.text("LOG_LEVEL") {
+            Some(level) => level.parse().expect("valid log level"),
+            None => match env::var("RUST_LOG").map(|level| level.to_lowercase()).as_deref() {
+                Ok("trace") => LevelFilter::Trace,
+                Ok("debug") => LevelFilter::Debug,
+                Ok("info") => LevelFilter::Info,
+                Ok("warn") => LevelFilter::Warn,
+                _ => LevelFilter::Error,
+            },
+        }
+    }
+}
+
+/// Help text with all settings
+pub fn usage() -> String {
+    let mut usage = "usage: database [dump | run FILE] [--config FILE] [--SETTING VALUE ...]\n\nsettings:\n".to_owned();
+    for setting in SETTINGS {
+        usage.push_str(&format!(
+            "  --{:<32} {}{}\n",
+            setting.name.to_lowercase().replace('_', "-"),
+            setting.description,
+            match setting.default {
+                Some(default) if !default.is_empty() => format!(" ({})", default),
+                _ => String::new(),
+            }
+        ));
+    }
+    usage.push_str(&format!(
+        "\nsettings reloaded on SIGHUP or pg_reload_conf():\n  {}\n",
+        RELOADABLE
+            .iter()
+            .map(|name| format!("--{}", name.to_lowercase().replace('_', "-")))
+            .collect::<Vec<_>>()
+            .join(" ")
+    ));
+    usage
+}
+
+fn find(name: &str) -> Option<&'static Setting> {
+    SETTINGS.iter().find(|setting| setting.name == name)
+}
+
+fn flag(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "true" | "on" | "yes" | "1" => Some(true),
+        "false" | "off" | "no" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+fn validate(setting: &Setting, value: &str) -> Result<(), String> {
+    let valid = match setting.kind {
+        Kind::Text | Kind::Path => true,
+        Kind::Address => value.parse::<IpAddr>().is_ok(),
+        Kind::Port => value.parse::<u16>().is_ok(),
+        Kind::Number | Kind::Millis => value.parse::<usize>().is_ok(),
+        Kind::Flag => flag(value).is_some(),
+        Kind::LogLevel => value.parse::<LevelFilter>().is_ok(),
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "invalid value {:?} of {}, expected {}",
+            value, setting.name, setting.kind
+        ))
+    }
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let expected = match self {
+            Kind::Text => "a string",
+            Kind::Path => "a path",
+            Kind::Address => "an IP address",
+            Kind::Port => "a port number",
+            Kind::Number => "a number",
+            Kind::Flag => "true or false",
+            Kind::Millis => "a number of milliseconds",
+            Kind::LogLevel => "off, error, warn, info, debug or trace",
+        };
+        write!(f, "{}", expected)
+    }
+}
+
+/// `--name value`, `--name=value` and `--name` that turns a flag on
+fn parse_flags(args: &[String]) -> Result<HashMap<&'static str, String>, String> {
+    let mut flags = HashMap::new();
+    let mut args = args.iter().peekable();
+    while let Some(arg) = args.next() {
+        let arg = match arg.strip_prefix("--") {
+            Some(arg) => arg,
+            None => return Err(format!("unexpected argument {}", arg)),
+        };
+        let (name, value) = match arg.find('=') {
+            Some(equals) => (&arg[..equals], Some(arg[equals + 1..].to_owned())),
+            None => (arg, None),
+        };
+        let name = name.to_uppercase().replace('-', "_");
+        let name = if name == "CONFIG" {
+            "CONFIG_FILE"
+        } else {
+            find(&name)
+                .map(|setting| setting.name)
+                .ok_or_else(|| format!("unknown flag --{}", arg))?
+        };
+        let value = match value {
+            Some(value) => value,
+            None if find(name).map(|setting| setting.kind) == Some(Kind::Flag)
+                && args.peek().map(|next| next.starts_with("--")).unwrap_or(true) =>
+            {
+                "true".to_owned()
+            }
+            None => match args.next() {
+                Some(value) => value.clone(),
+                None => return Err(format!("missing value of --{}", arg)),
+            },
+        };
+        flags.insert(name, value);
+    }
+    Ok(flags)
+}
+
+/// `key = value` lines where values are strings, numbers or booleans
+fn parse_file(text: &str) -> Result<Vec<(&'static str, String)>, String> {
+    let mut values = vec![];
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let error = |message: &str| format!("line {}: {}", index + 1, message);
+        let equals = line.find('=').ok_or_else(|| error("expected `key = value`"))?;
+        let key = line[..equals].trim();
+        let setting = find(&key.to_uppercase())
+            .filter(|_| key.chars().all(|c| c.is_ascii_lowercase() || c == '_'))
+            .ok_or_else(|| error(&format!("unknown setting {}", key)))?;
+        let value = parse_file_value(line[equals + 1..].trim()).map_err(|message| error(&message))?;
+        values.push((setting.name, value));
+    }
+    Ok(values)
+}
+
+fn parse_file_value(text: &str) -> Result<String, String> {
+    let mut chars = text.chars();
+    let (value, rest) = match chars.next() {
+        Some('\'') => match chars.as_str().find('\'') {
+            Some(end) => (chars.as_str()[..end].to_owned(), &chars.as_str()[end + 1..]),
+            None => return Err("unterminated string".to_owned()),
+        },
+        Some('"') => {
+            let mut value = String::new();
+            loop {
+                match chars.next() {
+                    None => return Err("unterminated string".to_owned()),
+                    Some('"') => break,
+                    Some('\\') => match chars.next() {
+                        Some('"') => value.push('"'),
+                        Some('\\') => value.push('\\'),
+                        Some('n') => value.push('\n'),
+                        Some('t') => value.push('\t'),
+                        _ => return Err("invalid escape in string".to_owned()),
+                    },
+                    Some(c) => value.push(c),
+                }
+            }
+            (value, chars.as_str())
+        }
+        _ => match text.find('#') {
+            Some(comment) => (text[..comment].trim().to_owned(), ""),
+            None => (text.to_owned(), ""),
+        },
+    };
+    let rest = rest.trim();
+    if rest.is_empty() || rest.starts_with('#') {
+        Ok(value)
+    } else {
+        Err(format!("unexpected {} after value", rest))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| (*arg).to_owned()).collect()
+    }
+
+    fn config(flags: &[&str], env: &[(&str, &str)], file: &str) -> Result<Config, String> {
+        Config::from_sources(
+            &args(flags),
+            |name| {
+                env.iter()
+                    .find(|(variable, _value)| *variable == name)
+                    .map(|(_variable, value)| (*value).to_owned())
+            },
+            |_path| Ok(file.to_owned()),
+        )
+    }
+
+    #[test]
+    fn defaults() {
+        let config = config(&[], &[], "").expect("valid config");
+
+        assert_eq!(config.port("PORT"), Some(5432));
+        assert_eq!(config.address("HOST"), Some(IpAddr::from([0, 0, 0, 0])));
+        assert_eq!(config.number("MAX_CONNECTIONS"), Some(100));
+        assert_eq!(config.port("HTTP_PORT"), None);
+        assert!(!config.flag("PERSISTENT"));
+    }
+
+    #[test]
+    fn flags_override_environment_that_overrides_file() {
+        let file =
+            "# server\nport = 6000\nhost = '127.0.0.1'\nwork_mem = 1024 # kilobytes\nroot_path = \"/var/lib/data\"\n";
+        let config = config(
+            &[
+                "--config",
+                "database.toml",
+                "--port=7000",
+                "--persistent",
+                "--max-connections",
+                "5",
+            ],
+            &[("PORT", "6500"), ("WORK_MEM", "2048")],
+            file,
+        )
+        .expect("valid config");
+
+        assert_eq!(config.port("PORT"), Some(7000));
+        assert_eq!(config.number("WORK_MEM"), Some(2048));
+        assert_eq!(config.number("MAX_CONNECTIONS"), Some(5));
+        assert_eq!(config.address("HOST"), Some(IpAddr::from([127, 0, 0, 1])));
+        assert_eq!(config.path("ROOT_PATH"), Some(PathBuf::from("/var/lib/data")));
+        assert!(config.flag("PERSISTENT"));
+    }
+
+    #[test]
+    fn file_is_read_from_environment_variable() {
+        let config = config(&[], &[("CONFIG_FILE", "database.toml")], "log_connections = true").expect("valid config");
+
+        assert!(config.flag("LOG_CONNECTIONS"));
+    }
+
+    #[test]
+    fn invalid_values() {
+        assert_eq!(
+            config(&["--port", "http"], &[], ""),
+            Err("invalid value \"http\" of PORT, expected a port number".to_owned())
+        );
+        assert_eq!(
+            config(&[], &[("PERSISTENT", "maybe")], ""),
+            Err("invalid value \"maybe\" of PERSISTENT, expected true or false".to_owned())
+        );
+    }
+
+    #[test]
+    fn unknown_settings() {
+        assert_eq!(
+            config(&["--ports", "1"], &[], ""),
+            Err("unknown flag --ports".to_owned())
+        );
+        assert_eq!(
+            config(&["--config", "database.toml"], &[], "\nports = 1"),
+            Err("database.toml: line 2: unknown setting ports".to_owned())
+        );
+    }
+
+    #[test]
+    fn malformed_file() {
+        assert_eq!(
+            config(&["--config", "database.toml"], &[], "[server]"),
+            Err("database.toml: line 1: expected `key = value`".to_owned())
+        );
+        assert_eq!(
+            config(&["--config", "database.toml"], &[], "host = \"127.0.0.1"),
+            Err("database.toml: line 1: unterminated string".to_owned())
+        );
+    }
+
+    #[test]
+    fn log_level() {
+        let debug = config(&["--log-level", "debug"], &[], "").expect("valid config");
+        assert_eq!(debug.log_level(), LevelFilter::Debug);
+
+        assert_eq!(
+            config(&["--log-level", "loud"], &[], ""),
+            Err("invalid value \"loud\" of LOG_LEVEL, expected off, error, warn, info, debug or trace".to_owned())
+        );
+    }
+
+    #[test]
+    fn settings_that_require_restart() {
+        let loaded = config(
+            &[],
+            &[("CONFIG_FILE", "database.toml")],
+            "port = 6000\nlog_min_duration_statement = 100",
+        )
+        .expect("valid config");
+        let reloaded = config(
+            &[],
+            &[("CONFIG_FILE", "database.toml")],
+            "port = 6001\nlog_min_duration_statement = 10\nlog_level = 'info'",
+        )
+        .expect("valid config");
+
+        assert_eq!(loaded.requires_restart(&reloaded), vec!["PORT"]);
+    }
+}
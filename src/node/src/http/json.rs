@@ -0,0 +1,275 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! The subset of JSON that requests and responses of the HTTP API need.
+//! Numbers keep their text so that parameters reach the engine as written.
+use std::{
+    fmt::{self, Display, Formatter, Write},
+    iter::Peekable,
+    str::Chars,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Json {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub(crate) fn parse(text: &str) -> Result<Json, String> {
+        let mut chars = text.chars().peekable();
+        let value = parse_value(&mut chars)?;
+        skip_whitespace(&mut chars);
+        match chars.next() {
+            None => Ok(value),
+            Some(c) => Err(format!("unexpected character '{}' after JSON value", c)),
+        }
+    }
+
+    /// Value of `key` if this is an object that has it
+    pub(crate) fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => members
+                .iter()
+                .find(|(name, _value)| name == key)
+                .map(|(_name, value)| value),
+            _ => None,
+        }
+    }
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars>) {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+}
+
+fn parse_value(chars: &mut Peekable<Chars>) -> Result<Json, String> {
+    skip_whitespace(chars);
+    match chars.peek() {
+        None => Err("unexpected end of JSON".to_owned()),
+        Some('{') => {
+            chars.next();
+            let mut members = vec![];
+            skip_whitespace(chars);
+            if chars.next_if_eq(&'}').is_some() {
+                return Ok(Json::Object(members));
+            }
+            loop {
+                skip_whitespace(chars);
+                if chars.next() != Some('"') {
+                    return Err("expected string as name of object member".to_owned());
+                }
+                let name = parse_string(chars)?;
+                skip_whitespace(chars);
+                if chars.next() != Some(':') {
+                    return Err("expected ':' after name of object member".to_owned());
+                }
+                members.push((name, parse_value(chars)?));
+                skip_whitespace(chars);
+                match chars.next() {
+                    Some(',') => {}
+                    Some('}') => return Ok(Json::Object(members)),
+                    _ => return Err("expected ',' or '}' in object".to_owned()),
+                }
+            }
+        }
+        Some('[') => {
+            chars.next();
+            let mut elements = vec![];
+            skip_whitespace(chars);
+            if chars.next_if_eq(&']').is_some() {
+                return Ok(Json::Array(elements));
+            }
+            loop {
+                elements.push(parse_value(chars)?);
+                skip_whitespace(chars);
+                match chars.next() {
+                    Some(',') => {}
+                    Some(']') => return Ok(Json::Array(elements)),
+                    _ => return Err("expected ',' or ']' in array".to_owned()),
+                }
+            }
+        }
+        Some('"') => {
+            chars.next();
+            parse_string(chars).map(Json::String)
+        }
+        Some(c) if *c == '-' || c.is_ascii_digit() => {
+            let mut number = String::new();
+            while let Some(c) = chars.next_if(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E')) {
+                number.push(c);
+            }
+            if number.parse::<f64>().is_err() {
+                return Err(format!("invalid number {}", number));
+            }
+            Ok(Json::Number(number))
+        }
+        Some(_) => {
+            let mut word = String::new();
+            while let Some(c) = chars.next_if(|c| c.is_ascii_alphabetic()) {
+                word.push(c);
+            }
+            match word.as_str() {
+                "null" => Ok(Json::Null),
+                "true" => Ok(Json::Bool(true)),
+                "false" => Ok(Json::Bool(false)),
+                _ => Err(format!("unexpected token '{}'", word)),
+            }
+        }
+    }
+}
+
+/// Rest of a string whose opening quote is already consumed
+fn parse_string(chars: &mut Peekable<Chars>) -> Result<String, String> {
+    let mut string = String::new();
+    loop {
+        match chars.next() {
+            None => return Err("unterminated string".to_owned()),
+            Some('"') => return Ok(string),
+            Some('\\') => match chars.next() {
+                Some('"') => string.push('"'),
+                Some('\\') => string.push('\\'),
+                Some('/') => string.push('/'),
+                Some('b') => string.push('\u{8}'),
+                Some('f') => string.push('\u{c}'),
+                Some('n') => string.push('\n'),
+                Some('r') => string.push('\r'),
+                Some('t') => string.push('\t'),
+                Some('u') => {
+                    let high = parse_code_unit(chars)?;
+                    let code_point = if (0xD800..0xDC00).contains(&high) {
+                        if chars.next() != Some('\\') || chars.next() != Some('u') {
+                            return Err("unpaired surrogate in string".to_owned());
+                        }
+                        let low = parse_code_unit(chars)?;
+                        if !(0xDC00..0xE000).contains(&low) {
+                            return Err("unpaired surrogate in string".to_owned());
+                        }
+                        0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+                    } else {
+                        high
+                    };
+                    match char::from_u32(code_point) {
+                        Some(c) => string.push(c),
+                        None => return Err("invalid unicode escape in string".to_owned()),
+                    }
+                }
+                _ => return Err("invalid escape in string".to_owned()),
+            },
+            Some(c) => string.push(c),
+        }
+    }
+}
+
+fn parse_code_unit(chars: &mut Peekable<Chars>) -> Result<u32, String> {
+    let digits = chars.take(4).collect::<String>();
+    if digits.len() != 4 {
+        return Err("invalid unicode escape in string".to_owned());
+    }
+    u32::from_str_radix(&digits, 16).map_err(|_| "invalid unicode escape in string".to_owned())
+}
+
+impl Display for Json {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(value) => write!(f, "{}", value),
+            Json::Number(number) => write!(f, "{}", number),
+            Json::String(string) => write_string(f, string),
+            Json::Array(elements) => {
+                f.write_char('[')?;
+                for (index, element) in elements.iter().enumerate() {
+                    if index > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{}", element)?;
+                }
+                f.write_char(']')
+            }
+            Json::Object(members) => {
+                f.write_char('{')?;
+                for (index, (name, value)) in members.iter().enumerate() {
+                    if index > 0 {
+                        f.write_char(',')?;
+                    }
+                    write_string(f, name)?;
+                    write!(f, ":{}", value)?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut Formatter<'_>, string: &str) -> fmt::Result {
+    f.write_char('"')?;
+    for c in string.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_body() {
+        assert_eq!(
+            Json::parse(r#" {"sql": "select $1", "params": [1.5e3, "a\"é😀", null, true]} "#),
+            Ok(Json::Object(vec![
+                ("sql".to_owned(), Json::String("select $1".to_owned())),
+                (
+                    "params".to_owned(),
+                    Json::Array(vec![
+                        Json::Number("1.5e3".to_owned()),
+                        Json::String("a\"\u{e9}\u{1f600}".to_owned()),
+                        Json::Null,
+                        Json::Bool(true)
+                    ])
+                )
+            ]))
+        );
+    }
+
+    #[test]
+    fn malformed() {
+        assert!(Json::parse(r#"{"sql": "select"#).is_err());
+        assert!(Json::parse(r#"{"sql" "select"}"#).is_err());
+        assert!(Json::parse("[1, 2] 3").is_err());
+        assert!(Json::parse("nil").is_err());
+    }
+
+    #[test]
+    fn serialized_strings_are_escaped() {
+        assert_eq!(
+            Json::Object(vec![(
+                "message".to_owned(),
+                Json::Array(vec![Json::String("\"a\"\n\u{1}".to_owned()), Json::Null])
+            )])
+            .to_string(),
+            r#"{"message":["\"a\"\n\u0001",null]}"#
+        );
+    }
+}
@@ -0,0 +1,762 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! Opt-in HTTP endpoint for clients without a PostgreSQL driver. SQL is sent
+//! as JSON to `POST /query` on behalf of the role from `Basic` credentials.
+//! Host based access rules decide whether the role has to authenticate, the
+//! ones with a password authentication method accept only roles that have a
+//! password. The endpoint does not encrypt traffic, so it is served on the
+//! loopback address only, e.g. behind a proxy that terminates TLS. Results
+//! are returned as JSON:
+//!
+//! ```text
+//! {"sql": "insert into s.t values ($1, $2)", "params": [1, "a"]}
+//! {"columns": [{"name": "c", "type": "smallint"}], "rows": [[1]]}
+//! {"rows_affected": 1}
+//! {"error": {"code": "42P01", "message": "table \"s.t\" does not exist"}}
+//! ```
+use json::Json;
+use protocol::{hba::AuthMethod, results::QueryError as AccessError, scram::ScramVerifier, PasswordStore};
+use sha2::{Digest, Sha256};
+use sql_engine::{
+    catalog_manager::CatalogManager,
+    connections::Connections,
+    embedded::{Database, Outcome, QueryError, Value},
+    QueryExecutor,
+};
+use std::{
+    collections::HashMap,
+    io::{self, Read, Write},
+    net::{IpAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+mod json;
+
+const MAX_HEAD_SIZE: usize = 16 * 1024;
+const MAX_BODY_SIZE: usize = 1024 * 1024;
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+const WRITE_TIMEOUT: Duration = Duration::from_secs(30);
+/// Failed authentications after which a client address is refused until
+/// `FAILURE_WINDOW` passes since the first of them
+const MAX_FAILED_AUTHENTICATIONS: usize = 5;
+const FAILURE_WINDOW: Duration = Duration::from_secs(60);
+
+type Configure = Box<dyn Fn(QueryExecutor) -> QueryExecutor + Send + Sync>;
+/// Authentication method of a role that connects from an address, `None` if
+/// the role is not allowed to connect
+type Access = Box<dyn Fn(IpAddr, &str) -> Option<AuthMethod> + Send + Sync>;
+
+pub(crate) struct HttpApi {
+    database: Database,
+    storage: Arc<CatalogManager>,
+    connections: Arc<Connections>,
+    configure: Configure,
+    access: Access,
+    open: AtomicUsize,
+    /// digests of passwords that matched verifiers of roles, so that a
+    /// password is derived only once and not for every request
+    verified: Mutex<HashMap<String, (ScramVerifier, Vec<u8>)>>,
+    /// number of failed authentications from an address since the first one
+    failures: Mutex<HashMap<IpAddr, (usize, Instant)>>,
+}
+
+impl HttpApi {
+    /// `configure` applies server settings to executors of requests and
+    /// `access` applies host based access rules to requests
+    pub(crate) fn new(
+        storage: Arc<CatalogManager>,
+        connections: Arc<Connections>,
+        configure: Configure,
+        access: Access,
+    ) -> HttpApi {
+        HttpApi {
+            database: Database::with_storage(storage.clone()),
+            storage,
+            connections,
+            configure,
+            access,
+            open: AtomicUsize::default(),
+            verified: Mutex::default(),
+            failures: Mutex::default(),
+        }
+    }
+
+    /// Handles each connection on a dedicated thread, one request per
+    /// connection. No more connections than sessions of the server are open
+    /// at once, others are answered right away
+    pub(crate) fn serve(self: Arc<Self>, listener: TcpListener) {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => match OpenConnection::new(&self) {
+                    Some(open) => {
+                        let spawned = thread::Builder::new()
+                            .name("http".to_owned())
+                            .spawn(move || open.api.serve_connection(stream));
+                        if let Err(error) = spawned {
+                            log::error!("failed to start HTTP connection: {:?}", error);
+                        }
+                    }
+                    None => send(
+                        stream,
+                        Response::error(503, "Service Unavailable", "too many HTTP connections"),
+                    ),
+                },
+                Err(error) => log::error!("failed to accept HTTP connection: {:?}", error),
+            }
+        }
+    }
+
+    fn serve_connection(&self, mut stream: TcpStream) {
+        if let Err(error) = stream.set_read_timeout(Some(READ_TIMEOUT)) {
+            log::debug!("failed to set HTTP read timeout: {:?}", error);
+        }
+        let response = match (Request::read(&mut stream), stream.peer_addr()) {
+            (Ok(request), Ok(address)) => self.handle(address.ip(), &request),
+            (Err(response), _) => response,
+            (Ok(_), Err(error)) => {
+                log::debug!("failed to get HTTP client address: {:?}", error);
+                return;
+            }
+        };
+        send(stream, response)
+    }
+
+    fn handle(&self, address: IpAddr, request: &Request) -> Response {
+        if request.path != "/query" {
+            return Response::error(404, "Not Found", "no such endpoint");
+        }
+        if request.method != "POST" {
+            return Response::error(405, "Method Not Allowed", "only POST is allowed").with_header("Allow", "POST");
+        }
+        let user = match self.authenticate(address, request) {
+            Ok(user) => user,
+            Err(response) => return response,
+        };
+        let body = match std::str::from_utf8(&request.body)
+            .map_err(|_| "request body is not UTF-8".to_owned())
+            .and_then(Json::parse)
+        {
+            Ok(body) => body,
+            Err(message) => return Response::error(400, "Bad Request", &message),
+        };
+        let sql = match body.get("sql") {
+            Some(Json::String(sql)) => sql,
+            _ => return Response::error(400, "Bad Request", "\"sql\" string is required"),
+        };
+        let params = match body.get("params") {
+            None => None,
+            Some(Json::Array(values)) => match values.iter().map(param).collect::<Result<Vec<Option<String>>, _>>() {
+                Ok(params) => Some(params),
+                Err(message) => return Response::error(400, "Bad Request", &message),
+            },
+            Some(_) => return Response::error(400, "Bad Request", "\"params\" has to be an array"),
+        };
+
+        let _slot = match self.connections.open(&user) {
+            Ok(slot) => slot,
+            Err(error) => return Response::query_error(503, "Service Unavailable", error),
+        };
+        let mut connection = self.database.connect_with(&user, |executor| (self.configure)(executor));
+        let executed = match params {
+            None => connection.execute(sql),
+            Some(params) => connection.execute_with_params(sql, &params),
+        };
+        match executed {
+            Ok(Ok(outcome)) => Response::ok(outcome_json(outcome)),
+            Ok(Err(error)) => Response::query_error(400, "Bad Request", error),
+            Err(error) => {
+                log::error!("HTTP query {:?} failed: {:?}", sql, error);
+                Response::error(500, "Internal Server Error", "internal error")
+            }
+        }
+    }
+
+    /// Role that sent the request if host based access rules let it in,
+    /// otherwise the error response
+    fn authenticate(&self, address: IpAddr, request: &Request) -> Result<String, Response> {
+        let unauthorized = || {
+            Response::error(401, "Unauthorized", "password authentication failed")
+                .with_header("WWW-Authenticate", "Basic realm=\"database\"")
+        };
+        let (user, password) = request.credentials().ok_or_else(unauthorized)?;
+        let host = address.to_string();
+        match (self.access)(address, &user) {
+            None => Err(Response::query_error(
+                403,
+                "Forbidden",
+                AccessError::no_host_based_access_entry(host, user.clone(), user, false),
+            )),
+            Some(AuthMethod::Reject) => Err(Response::query_error(
+                403,
+                "Forbidden",
+                AccessError::connection_rejected(host, user.clone(), user),
+            )),
+            Some(AuthMethod::Cert) => Err(Response::query_error(
+                403,
+                "Forbidden",
+                AccessError::certificate_authentication_failed(user),
+            )),
+            Some(AuthMethod::Trust) => Ok(user),
+            Some(AuthMethod::Password) | Some(AuthMethod::ScramSha256) => {
+                if self.refused(address) {
+                    Err(Response::error(
+                        429,
+                        "Too Many Requests",
+                        "too many failed authentication attempts",
+                    ))
+                } else if self.password_matches(&user, &password) {
+                    self.failures.lock().expect("to acquire lock").remove(&address);
+                    Ok(user)
+                } else {
+                    self.failed(address);
+                    Err(unauthorized())
+                }
+            }
+        }
+    }
+
+    /// Passwords are compared with digests of passwords that matched the
+    /// current verifier of the role before they are derived with the
+    /// verifier, which is slow by design
+    fn password_matches(&self, user: &str, password: &str) -> bool {
+        let verifier = match self.storage.password_verifier(user) {
+            Some(verifier) => verifier,
+            None => return false,
+        };
+        let digest = Sha256::new()
+            .chain(verifier.to_string())
+            .chain(password)
+            .finalize()
+            .to_vec();
+        let mut verified = self.verified.lock().expect("to acquire lock");
+        if verified.get(user) == Some(&(verifier.clone(), digest.clone())) {
+            return true;
+        }
+        drop(verified);
+        if !verifier.matches(password) {
+            return false;
+        }
+        verified = self.verified.lock().expect("to acquire lock");
+        verified.insert(user.to_owned(), (verifier, digest));
+        true
+    }
+
+    /// Whether authentication attempts from `address` failed too many times
+    /// recently
+    fn refused(&self, address: IpAddr) -> bool {
+        match self.failures.lock().expect("to acquire lock").get(&address) {
+            Some((failed, since)) => *failed >= MAX_FAILED_AUTHENTICATIONS && since.elapsed() < FAILURE_WINDOW,
+            None => false,
+        }
+    }
+
+    fn failed(&self, address: IpAddr) {
+        let mut failures = self.failures.lock().expect("to acquire lock");
+        failures.retain(|_address, (_failed, since)| since.elapsed() < FAILURE_WINDOW);
+        let (failed, _since) = failures.entry(address).or_insert_with(|| (0, Instant::now()));
+        *failed += 1;
+    }
+}
+
+/// Connection that is accounted as open until it is dropped
+struct OpenConnection {
+    api: Arc<HttpApi>,
+}
+
+impl OpenConnection {
+    fn new(api: &Arc<HttpApi>) -> Option<OpenConnection> {
+        let max_connections = api.connections.max_connections();
+        api.open
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |open| {
+                if open < max_connections {
+                    Some(open + 1)
+                } else {
+                    None
+                }
+            })
+            .ok()
+            .map(|_open| OpenConnection { api: api.clone() })
+    }
+}
+
+impl Drop for OpenConnection {
+    fn drop(&mut self) {
+        self.api.open.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+fn send(mut stream: TcpStream, response: Response) {
+    if let Err(error) = stream.set_write_timeout(Some(WRITE_TIMEOUT)) {
+        log::debug!("failed to set HTTP write timeout: {:?}", error);
+    }
+    if let Err(error) = stream.write_all(&response.to_bytes()).and_then(|()| stream.flush()) {
+        log::debug!("failed to send HTTP response: {:?}", error);
+    }
+}
+
+/// Parameters are passed to the engine in text representation
+fn param(value: &Json) -> Result<Option<String>, String> {
+    match value {
+        Json::Null => Ok(None),
+        Json::Bool(value) => Ok(Some(value.to_string())),
+        Json::Number(number) => Ok(Some(number.clone())),
+        Json::String(string) => Ok(Some(string.clone())),
+        Json::Array(_) | Json::Object(_) => Err("parameters have to be strings, numbers, booleans or null".to_owned()),
+    }
+}
+
+fn outcome_json(outcome: Outcome) -> Json {
+    match outcome {
+        Outcome::Rows(result_set) => Json::Object(vec![
+            (
+                "columns".to_owned(),
+                Json::Array(
+                    result_set
+                        .columns()
+                        .iter()
+                        .map(|column| {
+                            Json::Object(vec![
+                                ("name".to_owned(), Json::String(column.name.clone())),
+                                ("type".to_owned(), Json::String(column.pg_type.to_string())),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+            (
+                "rows".to_owned(),
+                Json::Array(
+                    result_set
+                        .rows()
+                        .iter()
+                        .map(|row| Json::Array(row.iter().map(value_json).collect()))
+                        .collect(),
+                ),
+            ),
+        ]),
+        Outcome::Affected(affected) => {
+            Json::Object(vec![("rows_affected".to_owned(), Json::Number(affected.to_string()))])
+        }
+        Outcome::Done => Json::Object(vec![]),
+    }
+}
+
+/// Infinite and NaN floats do not have a JSON number representation
+fn value_json(value: &Value) -> Json {
+    match value {
+        Value::Null => Json::Null,
+        Value::Bool(value) => Json::Bool(*value),
+        Value::SmallInt(value) => Json::Number(value.to_string()),
+        Value::Integer(value) => Json::Number(value.to_string()),
+        Value::BigInt(value) => Json::Number(value.to_string()),
+        Value::Real(value) if value.is_finite() => Json::Number(value.to_string()),
+        Value::DoublePrecision(value) if value.is_finite() => Json::Number(value.to_string()),
+        Value::Real(value) => Json::String(value.to_string()),
+        Value::DoublePrecision(value) => Json::String(value.to_string()),
+        Value::Text(text) => Json::String(text.clone()),
+    }
+}
+
+#[derive(Debug)]
+struct Request {
+    method: String,
+    path: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Request {
+    /// Malformed requests are answered with the returned error response
+    fn read(stream: &mut impl Read) -> Result<Request, Response> {
+        let mut buffer = vec![];
+        let mut chunk = [0; 4096];
+        let head_end = loop {
+            if let Some(position) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+                break position;
+            }
+            if buffer.len() > MAX_HEAD_SIZE {
+                return Err(Response::error(
+                    431,
+                    "Request Header Fields Too Large",
+                    "request head is too large",
+                ));
+            }
+            match stream.read(&mut chunk) {
+                Ok(0) => return Err(Response::error(400, "Bad Request", "incomplete request")),
+                Ok(read) => buffer.extend_from_slice(&chunk[..read]),
+                Err(error) => return Err(read_error(error)),
+            }
+        };
+        let head = match std::str::from_utf8(&buffer[..head_end]) {
+            Ok(head) => head,
+            Err(_) => return Err(Response::error(400, "Bad Request", "request head is not UTF-8")),
+        };
+        let mut lines = head.split("\r\n");
+        let mut request_line = lines.next().unwrap_or_default().split(' ');
+        let (method, path) = match (request_line.next(), request_line.next(), request_line.next()) {
+            (Some(method), Some(path), Some(version)) if version.starts_with("HTTP/1.") => {
+                (method.to_owned(), path.to_owned())
+            }
+            _ => return Err(Response::error(400, "Bad Request", "malformed request line")),
+        };
+        let mut headers = vec![];
+        for line in lines {
+            match line.find(':') {
+                Some(colon) => headers.push((line[..colon].trim().to_lowercase(), line[colon + 1..].trim().to_owned())),
+                None => return Err(Response::error(400, "Bad Request", "malformed header")),
+            }
+        }
+        let mut request = Request {
+            method,
+            path,
+            headers,
+            body: buffer.split_off(head_end + 4),
+        };
+        let content_length = match request.header("content-length").map(str::parse::<usize>) {
+            None => 0,
+            Some(Ok(length)) => length,
+            Some(Err(_)) => return Err(Response::error(400, "Bad Request", "invalid Content-Length")),
+        };
+        if content_length > MAX_BODY_SIZE {
+            return Err(Response::error(413, "Payload Too Large", "request body is too large"));
+        }
+        if request.body.len() < content_length {
+            let read = request.body.len();
+            request.body.resize(content_length, 0);
+            if let Err(error) = stream.read_exact(&mut request.body[read..]) {
+                return Err(read_error(error));
+            }
+        }
+        request.body.truncate(content_length);
+        Ok(request)
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _value)| header == name)
+            .map(|(_header, value)| value.as_str())
+    }
+
+    /// User and password of `Basic` authorization
+    fn credentials(&self) -> Option<(String, String)> {
+        let authorization = self.header("authorization")?;
+        let mut parts = authorization.splitn(2, ' ');
+        if !parts.next()?.eq_ignore_ascii_case("basic") {
+            return None;
+        }
+        let decoded = String::from_utf8(base64::decode(parts.next()?.trim()).ok()?).ok()?;
+        let mut credentials = decoded.splitn(2, ':');
+        Some((credentials.next()?.to_owned(), credentials.next()?.to_owned()))
+    }
+}
+
+fn read_error(error: io::Error) -> Response {
+    log::debug!("failed to read HTTP request: {:?}", error);
+    Response::error(400, "Bad Request", "incomplete request")
+}
+
+#[derive(Debug, PartialEq)]
+struct Response {
+    status: u16,
+    reason: &'static str,
+    headers: Vec<(&'static str, &'static str)>,
+    body: Json,
+}
+
+impl Response {
+    fn ok(body: Json) -> Response {
+        Response {
+            status: 200,
+            reason: "OK",
+            headers: vec![],
+            body,
+        }
+    }
+
+    fn error(status: u16, reason: &'static str, message: &str) -> Response {
+        Response {
+            status,
+            reason,
+            headers: vec![],
+            body: Json::Object(vec![(
+                "error".to_owned(),
+                Json::Object(vec![("message".to_owned(), Json::String(message.to_owned()))]),
+            )]),
+        }
+    }
+
+    fn query_error(status: u16, reason: &'static str, error: QueryError) -> Response {
+        Response {
+            status,
+            reason,
+            headers: vec![],
+            body: Json::Object(vec![(
+                "error".to_owned(),
+                Json::Object(vec![
+                    ("code".to_owned(), Json::String(error.sql_state().to_owned())),
+                    ("message".to_owned(), Json::String(error.to_string())),
+                ]),
+            )]),
+        }
+    }
+
+    fn with_header(mut self, name: &'static str, value: &'static str) -> Response {
+        self.headers.push((name, value));
+        self
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let body = self.body.to_string();
+        let mut response = format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n",
+            self.status,
+            self.reason,
+            body.len()
+        );
+        for (name, value) in self.headers.iter() {
+            response.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        response.push_str("\r\n");
+        response.push_str(&body);
+        response.into_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use protocol::hba::HostBasedAccess;
+    use std::net::Ipv4Addr;
+
+    const AUTHORIZATION: &str = "Basic cG9zdGdyZXM6c2VjcmV0"; // postgres:secret
+    const WRONG_AUTHORIZATION: &str = "Basic cG9zdGdyZXM6d3Jvbmc="; // postgres:wrong
+    const LOCALHOST: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+    fn api() -> HttpApi {
+        api_with_access(Box::new(|_address, _user| Some(AuthMethod::Password)))
+    }
+
+    fn api_with_access(access: Access) -> HttpApi {
+        let storage = Arc::new(CatalogManager::in_memory().expect("no system errors"));
+        let mut connection = Database::with_storage(storage.clone()).connect("postgres");
+        for sql in &[
+            "alter role postgres password 'secret';",
+            "create schema schema_name;",
+            "create table schema_name.table_name (column_1 smallint, column_2 varchar(10));",
+        ] {
+            connection
+                .execute(sql)
+                .expect("no system errors")
+                .expect("no query errors");
+        }
+        HttpApi::new(
+            storage.clone(),
+            Connections::new(storage, 10),
+            Box::new(|executor| executor),
+            access,
+        )
+    }
+
+    fn post(body: &str) -> Request {
+        post_as(AUTHORIZATION, body)
+    }
+
+    fn post_as(authorization: &str, body: &str) -> Request {
+        request(
+            format!(
+                "POST /query HTTP/1.1\r\nAuthorization: {}\r\nContent-Length: {}\r\n\r\n{}",
+                authorization,
+                body.len(),
+                body
+            )
+            .as_str(),
+        )
+    }
+
+    fn request(text: &str) -> Request {
+        Request::read(&mut text.as_bytes()).expect("valid request")
+    }
+
+    fn body(response: &Response) -> String {
+        response.body.to_string()
+    }
+
+    #[test]
+    fn parameterized_insert_and_select() {
+        let api = api();
+
+        let inserted = api.handle(
+            LOCALHOST,
+            &post(r#"{"sql": "insert into schema_name.table_name values ($1, $2);", "params": [1, "a"]}"#),
+        );
+        let selected = api.handle(LOCALHOST, &post(r#"{"sql": "select * from schema_name.table_name;"}"#));
+
+        assert_eq!(
+            (inserted.status, body(&inserted)),
+            (200, r#"{"rows_affected":1}"#.to_owned())
+        );
+        assert_eq!(
+            (selected.status, body(&selected)),
+            (
+                200,
+                r#"{"columns":[{"name":"column_1","type":"smallint"},{"name":"column_2","type":"variable character"}],"rows":[[1,"a"]]}"#
+                    .to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn query_error() {
+        let api = api();
+
+        let response = api.handle(
+            LOCALHOST,
+            &post(r#"{"sql": "select * from schema_name.non_existent;"}"#),
+        );
+
+        assert_eq!(
+            (response.status, body(&response)),
+            (
+                400,
+                r#"{"error":{"code":"42P01","message":"table \"schema_name.non_existent\" does not exist"}}"#
+                    .to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn wrong_password() {
+        let api = api();
+
+        let response = api.handle(LOCALHOST, &post_as(WRONG_AUTHORIZATION, "{}"));
+
+        assert_eq!(response.status, 401);
+        assert_eq!(response.headers, vec![("WWW-Authenticate", "Basic realm=\"database\"")]);
+    }
+
+    #[test]
+    fn host_based_access_rules() {
+        let rules = HostBasedAccess::parse(
+            "host all postgres 127.0.0.1/32 trust\n\
+             host all postgres 10.1.0.0/16 password\n\
+             host all postgres 10.2.0.0/16 scram-sha-256\n\
+             host all postgres 10.0.0.0/8 reject",
+        )
+        .expect("valid rules");
+        let api = api_with_access(Box::new(move |address, user| rules.method(address, false, user, user)));
+        let select = r#"{"sql": "select * from schema_name.table_name;"}"#;
+
+        assert_eq!(api.handle(LOCALHOST, &post_as(WRONG_AUTHORIZATION, select)).status, 200);
+        assert_eq!(
+            body(&api.handle("10.3.2.1".parse().unwrap(), &post(select))),
+            r#"{"error":{"code":"28000","message":"pg_hba.conf rejects connection for host \"10.3.2.1\", user \"postgres\", database \"postgres\""}}"#
+        );
+        assert_eq!(api.handle("192.168.0.1".parse().unwrap(), &post(select)).status, 403);
+        assert_eq!(
+            api.handle("10.2.0.1".parse().unwrap(), &post_as(WRONG_AUTHORIZATION, select))
+                .status,
+            401
+        );
+        assert_eq!(api.handle("10.2.0.1".parse().unwrap(), &post(select)).status, 200);
+        assert_eq!(api.handle("10.1.0.1".parse().unwrap(), &post(select)).status, 200);
+    }
+
+    #[test]
+    fn failed_authentications_are_limited() {
+        let api = api();
+        let select = r#"{"sql": "select * from schema_name.table_name;"}"#;
+
+        for _ in 0..MAX_FAILED_AUTHENTICATIONS {
+            assert_eq!(api.handle(LOCALHOST, &post_as(WRONG_AUTHORIZATION, select)).status, 401);
+        }
+
+        assert_eq!(api.handle(LOCALHOST, &post(select)).status, 429);
+        assert_eq!(api.handle("127.0.0.2".parse().unwrap(), &post(select)).status, 200);
+    }
+
+    #[test]
+    fn verified_passwords_are_remembered_until_they_change() {
+        let api = api();
+
+        assert!(api.password_matches("postgres", "secret"));
+        assert!(api.verified.lock().expect("to acquire lock").contains_key("postgres"));
+        assert!(api.password_matches("postgres", "secret"));
+        assert!(!api.password_matches("postgres", "wrong"));
+
+        api.handle(
+            LOCALHOST,
+            &post(r#"{"sql": "alter role postgres password 'changed';"}"#),
+        );
+
+        assert!(!api.password_matches("postgres", "secret"));
+        assert!(api.password_matches("postgres", "changed"));
+    }
+
+    #[test]
+    fn open_connections_are_bounded() {
+        let api = Arc::new(api());
+        api.connections.set_max_connections(2);
+
+        let first = OpenConnection::new(&api);
+        let second = OpenConnection::new(&api);
+
+        assert!(first.is_some() && second.is_some());
+        assert!(OpenConnection::new(&api).is_none());
+        drop(first);
+        assert!(OpenConnection::new(&api).is_some());
+    }
+
+    #[test]
+    fn malformed_body() {
+        let api = api();
+
+        assert_eq!(api.handle(LOCALHOST, &post(r#"{"sql": "select"#)).status, 400);
+        assert_eq!(api.handle(LOCALHOST, &post(r#"{"params": []}"#)).status, 400);
+        assert_eq!(
+            api.handle(LOCALHOST, &post(r#"{"sql": "select 1", "params": [[1]]}"#))
+                .status,
+            400
+        );
+    }
+
+    #[test]
+    fn unknown_endpoint_and_method() {
+        let api = api();
+
+        assert_eq!(
+            api.handle(LOCALHOST, &request("POST /other HTTP/1.1\r\n\r\n")).status,
+            404
+        );
+        assert_eq!(
+            api.handle(LOCALHOST, &request("GET /query HTTP/1.1\r\n\r\n")).status,
+            405
+        );
+    }
+
+    #[test]
+    fn response_bytes() {
+        let response = Response::ok(Json::Object(vec![]));
+
+        assert_eq!(
+            String::from_utf8(response.to_bytes()).expect("UTF-8"),
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}"
+        );
+    }
+}
@@ -17,4 +17,6 @@ extern crate log;
 extern crate protocol;
 extern crate storage;
 
+pub mod config;
+mod http;
 pub mod node;
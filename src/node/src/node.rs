@@ -12,155 +12,664 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::{config::Config, http::HttpApi};
 use async_dup::Arc as AsyncArc;
 use async_io::Async;
-use protocol::{Command, ProtocolConfiguration, Receiver};
-use sql_engine::{catalog_manager::CatalogManager, QueryExecutor};
+use protocol::{hba::HostBasedAccess, Command, ProtocolConfiguration, Receiver, Sender};
+use signal_hook::iterator::Signals;
+use sql_engine::{
+    audit::{AuditLog, AuditPolicy, Auditor},
+    autovacuum::{self, AutovacuumSettings},
+    background::{WorkerContext, Workers},
+    cancellation::{CancellationToken, Interruption},
+    catalog_manager::{CatalogManager, SUPERUSER},
+    connections::Connections,
+    embedded::Database,
+    result_cache::ResultCache,
+    server_settings::ServerSettings,
+    statistics::StatementStatistics,
+    QueryExecutor,
+};
 use std::{
-    env,
-    net::TcpListener,
-    path::{Path, PathBuf},
+    collections::HashMap,
+    env, fs, io,
+    net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener, TcpStream},
+    path::PathBuf,
+    process,
     sync::{
-        atomic::{AtomicU8, Ordering},
-        Arc,
+        atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering},
+        Arc, Mutex, Weak,
     },
+    thread,
+    time::{Duration, Instant},
 };
 
-const PORT: u16 = 5432;
-const HOST: [u8; 4] = [0, 0, 0, 0];
-
 pub const RUNNING: u8 = 0;
 pub const STOPPED: u8 = 1;
 
-pub fn start() {
-    let persistent = env::var("PERSISTENT").is_ok();
-    let root_path = env::var("ROOT_PATH").map(PathBuf::from).unwrap_or_default();
+/// How often the statistics collector bounds statistics of statements
+const STATISTICS_COLLECTOR_INTERVAL: Duration = Duration::from_secs(10);
+/// How long background workers are waited for on shutdown
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub fn start(config: Config) {
     smol::block_on(async {
-        let storage = if persistent {
-            Arc::new(CatalogManager::persistent(root_path.join("database")).unwrap())
+        let storage = if config.flag("PERSISTENT") {
+            Arc::new(CatalogManager::persistent(data_directory(&config)).unwrap())
         } else {
             Arc::new(CatalogManager::in_memory().unwrap())
         };
-        let listener = Async::<TcpListener>::bind((HOST, PORT)).expect("OK");
+        let listener = Async::<TcpListener>::bind(socket_address(&config, "PORT")).expect("to bind PORT");
 
-        let state = Arc::new(AtomicU8::new(RUNNING));
-        let config = protocol_configuration();
+        let server = Arc::new(Server {
+            config: protocol_configuration(&config).with_password_store(storage.clone()),
+            auditor: auditor(&config).map(Arc::new),
+            connections: Connections::new(storage.clone(), number_setting(&config, "MAX_CONNECTIONS")),
+            statistics: Arc::new(StatementStatistics::default()),
+            result_cache: config.number("RESULT_CACHE_SIZE").map(ResultCache::new).map(Arc::new),
+            settings: Arc::new(ServerSettings::default()),
+            log_connections: AtomicBool::default(),
+            log_disconnections: AtomicBool::default(),
+            max_parallel_workers: number_setting(&config, "MAX_PARALLEL_WORKERS"),
+            work_mem: number_setting(&config, "WORK_MEM"),
+            statement_timeout: config.millis("STATEMENT_TIMEOUT"),
+            sessions: Mutex::default(),
+            state: AtomicU8::new(RUNNING),
+            last_session_id: AtomicU64::default(),
+            loaded: Mutex::new(config.clone()),
+            workers: Workers::new("background", storage.clone()),
+            storage,
+        });
+        start_background_workers(&server, &config);
+        server.apply(&config);
+        let reloaded = Arc::downgrade(&server);
+        server.settings.set_reload(move || {
+            if let Some(server) = reloaded.upgrade() {
+                server.reload();
+            }
+        });
+        reload_on_hangup(Arc::downgrade(&server));
+        shut_down_on_termination(server.clone());
+        if config.port("HTTP_PORT").is_some() {
+            start_http_api(
+                server.clone(),
+                SocketAddr::new(
+                    IpAddr::V4(Ipv4Addr::LOCALHOST),
+                    config.port("HTTP_PORT").expect("port is configured"),
+                ),
+            );
+        }
 
         while let Ok((tcp_stream, address)) = listener.accept().await {
-            let tcp_stream = AsyncArc::new(tcp_stream);
-            if let Ok((mut receiver, sender)) = protocol::hand_shake(tcp_stream, address, &config)
-                .await
-                .expect("no io errors")
-            {
-                if state.load(Ordering::SeqCst) == STOPPED {
-                    return;
+            if server.state.load(Ordering::SeqCst) == STOPPED {
+                return;
+            }
+            let session_id = server.next_session_id();
+            let server = server.clone();
+            let spawned = thread::Builder::new()
+                .name(format!("session-{}", session_id))
+                .spawn(move || smol::block_on(server.serve(AsyncArc::new(tcp_stream), address, session_id)));
+            if let Err(error) = spawned {
+                log::error!("failed to start session {}: {:?}", session_id, error);
+            }
+        }
+    });
+}
+
+/// Serves the HTTP API on `address` next to the PostgreSQL protocol, requests
+/// are executed with the same settings and host based access rules as
+/// sessions of the server
+fn start_http_api(server: Arc<Server>, address: SocketAddr) {
+    let listener = TcpListener::bind(address).expect("to bind HTTP_PORT");
+    let configure = {
+        let server = server.clone();
+        Box::new(move |executor| server.configure(executor, server.next_session_id()))
+    };
+    let access = {
+        let server = server.clone();
+        Box::new(move |address, user: &str| server.config.auth_method(address, false, user, user))
+    };
+    let api = Arc::new(HttpApi::new(
+        server.storage.clone(),
+        server.connections.clone(),
+        configure,
+        access,
+    ));
+    let spawned = thread::Builder::new()
+        .name("http-api".to_owned())
+        .spawn(move || api.serve(listener));
+    if let Err(error) = spawned {
+        log::error!("failed to start HTTP API: {:?}", error);
+    }
+}
+
+/// Reloads configuration of the server each time the process receives `SIGHUP`
+fn reload_on_hangup(server: Weak<Server>) {
+    let signals = match Signals::new([signal_hook::SIGHUP]) {
+        Ok(signals) => signals,
+        Err(error) => {
+            log::error!("failed to handle SIGHUP: {:?}", error);
+            return;
+        }
+    };
+    let spawned = thread::Builder::new().name("reload".to_owned()).spawn(move || {
+        for _signal in signals.forever() {
+            match server.upgrade() {
+                Some(server) => server.reload(),
+                None => return,
+            }
+        }
+    });
+    if let Err(error) = spawned {
+        log::error!("failed to start configuration reload: {:?}", error);
+    }
+}
+
+/// Starts the checkpointer of persistent storage, the statistics collector
+/// that keeps `MAX_STATEMENT_STATISTICS` statements and autovacuum
+fn start_background_workers(server: &Server, config: &Config) {
+    if config.flag("PERSISTENT") {
+        let interval = config.millis("CHECKPOINT_TIMEOUT").expect("setting has default value");
+        let started = server
+            .workers
+            .every("checkpointer", interval, |context: &WorkerContext| {
+                let flushed = context.storage().checkpoint().map_err(|error| format!("{:?}", error))?;
+                log::debug!("checkpoint complete: {} bytes flushed", flushed);
+                Ok(())
+            });
+        if let Err(error) = started {
+            log::error!("{}", error);
+        }
+    }
+    let statistics = server.statistics.clone();
+    let max_statements = number_setting(config, "MAX_STATEMENT_STATISTICS");
+    let started = server.workers.every(
+        "statistics collector",
+        STATISTICS_COLLECTOR_INTERVAL,
+        move |_context: &WorkerContext| {
+            let discarded = statistics.trim(max_statements);
+            if discarded > 0 {
+                log::debug!("{} least called statements are discarded from statistics", discarded);
+            }
+            Ok(())
+        },
+    );
+    if let Err(error) = started {
+        log::error!("{}", error);
+    }
+    if config.flag("AUTOVACUUM") {
+        let settings = AutovacuumSettings {
+            vacuum_threshold: number_setting(config, "AUTOVACUUM_VACUUM_THRESHOLD") as u64,
+            analyze_threshold: number_setting(config, "AUTOVACUUM_ANALYZE_THRESHOLD") as u64,
+            ..AutovacuumSettings::default()
+        };
+        let naptime = config.millis("AUTOVACUUM_NAPTIME").expect("setting has default value");
+        let started = server
+            .workers
+            .every("autovacuum", naptime, move |context: &WorkerContext| {
+                let done = autovacuum::run(context.storage(), &settings).map_err(|error| format!("{:?}", error))?;
+                for (schema_name, table_name, maintenance) in done {
+                    log::debug!("automatic {:?} of table {}.{}", maintenance, schema_name, table_name);
                 }
-                let state = state.clone();
-                let storage = storage.clone();
-                let sender = Arc::new(sender);
-                let s = sender.clone();
-                let mut query_executor = QueryExecutor::new(storage.clone(), s);
-                log::debug!("ready to handle query");
-
-                smol::spawn(async move {
-                    loop {
-                        match receiver.receive().await {
-                            Err(e) => {
-                                log::error!("UNEXPECTED ERROR: {:?}", e);
-                                state.store(STOPPED, Ordering::SeqCst);
-                                return;
-                            }
-                            Ok(Err(e)) => {
-                                log::error!("UNEXPECTED ERROR: {:?}", e);
-                                state.store(STOPPED, Ordering::SeqCst);
-                                return;
-                            }
-                            Ok(Ok(Command::Bind {
-                                portal_name,
-                                statement_name,
-                                param_formats,
-                                raw_params,
-                                result_formats,
-                            })) => {
-                                match query_executor.bind_prepared_statement_to_portal(
-                                    portal_name.as_str(),
-                                    statement_name.as_str(),
-                                    param_formats.as_ref(),
-                                    raw_params.as_ref(),
-                                    result_formats.as_ref(),
-                                ) {
-                                    Ok(()) => {}
-                                    Err(error) => log::error!("{:?}", error),
-                                }
-                            }
-                            Ok(Ok(Command::Continue)) => {}
-                            Ok(Ok(Command::DescribeStatement { name })) => {
-                                match query_executor.describe_prepared_statement(name.as_str()) {
-                                    Ok(()) => {}
-                                    Err(error) => log::error!("{:?}", error),
-                                }
-                            }
-                            Ok(Ok(Command::Execute { portal_name, max_rows })) => {
-                                match query_executor.execute_portal(portal_name.as_str(), max_rows) {
-                                    Ok(()) => {}
-                                    Err(error) => log::error!("{:?}", error),
-                                }
-                            }
-                            Ok(Ok(Command::Flush)) => query_executor.flush(),
-                            Ok(Ok(Command::Parse {
-                                statement_name,
-                                sql,
-                                param_types,
-                            })) => {
-                                match query_executor.parse_prepared_statement(
-                                    statement_name.as_str(),
-                                    sql.as_str(),
-                                    param_types.as_ref(),
-                                ) {
-                                    Ok(()) => {}
-                                    Err(error) => log::error!("{:?}", error),
-                                }
-                            }
-                            Ok(Ok(Command::Query { sql })) => match query_executor.execute(sql.as_str()) {
-                                Ok(()) => {
-                                    query_executor.flush();
-                                }
-                                Err(error) => log::error!("{:?}", error),
-                            },
-                            Ok(Ok(Command::Terminate)) => {
-                                log::debug!("Closing connection with client");
-                                break;
-                            }
-                        }
-                    }
-                })
-                .detach();
+                Ok(())
+            });
+        if let Err(error) = started {
+            log::error!("{}", error);
+        }
+    }
+}
+
+/// Interrupts statements of sessions, stops background workers and makes
+/// written data durable before the process exits on `SIGTERM` or `SIGINT`
+fn shut_down_on_termination(server: Arc<Server>) {
+    let signals = match Signals::new([signal_hook::SIGTERM, signal_hook::SIGINT]) {
+        Ok(signals) => signals,
+        Err(error) => {
+            log::error!("failed to handle SIGTERM and SIGINT: {:?}", error);
+            return;
+        }
+    };
+    let spawned = thread::Builder::new().name("shutdown".to_owned()).spawn(move || {
+        if signals.forever().next().is_some() {
+            log::info!("shutting down");
+            server.state.store(STOPPED, Ordering::SeqCst);
+            server.cancel_sessions(Interruption::Shutdown);
+            for name in server.workers.shutdown(Some(SHUTDOWN_TIMEOUT)) {
+                log::warn!("background worker {} did not stop in time", name);
+            }
+            if let Err(error) = server.storage.checkpoint() {
+                log::error!("final checkpoint failed: {:?}", error);
             }
+            process::exit(0);
         }
     });
+    if let Err(error) = spawned {
+        log::error!("failed to start shutdown handler: {:?}", error);
+    }
+}
+
+/// Writes SQL statements that recreate schemas and tables of the persistent
+/// database under `ROOT_PATH` with their rows into standard output. The
+/// server has to be stopped while the dump is taken.
+pub fn dump(config: &Config) {
+    let storage = CatalogManager::persistent(data_directory(config)).expect("to open database");
+    let stdout = io::stdout();
+    sql_engine::dump::dump(&storage, &mut stdout.lock()).expect("to dump database");
+}
+
+/// Executes semicolon separated statements of `file` against the persistent
+/// database under `ROOT_PATH` as the superuser and prints progress of each
+/// statement. Returns whether all statements succeeded, the server has to be
+/// stopped while the script runs.
+pub fn run_script(config: &Config, file: &str) -> bool {
+    let script = match fs::read_to_string(file) {
+        Ok(script) => script,
+        Err(error) => {
+            eprintln!("could not read {}: {}", file, error);
+            return false;
+        }
+    };
+    let database = Database::persistent(data_directory(config)).expect("to open database");
+    let mut connection = database.connect(SUPERUSER);
+    let result = connection
+        .execute_script(&script, |progress| {
+            eprintln!(
+                "[{}/{}] {:?} {}",
+                progress.index + 1,
+                progress.total,
+                progress.elapsed,
+                progress.statement
+            )
+        })
+        .expect("to execute script");
+    match result {
+        Ok(outcomes) => {
+            eprintln!("{} statements executed", outcomes.len());
+            true
+        }
+        Err(failure) => {
+            eprintln!(
+                "[{}] failed: {}\n{}",
+                failure.index + 1,
+                failure.statement,
+                failure.error
+            );
+            false
+        }
+    }
+}
+
+/// Server wide state that is shared by client sessions
+struct Server {
+    storage: Arc<CatalogManager>,
+    config: ProtocolConfiguration,
+    auditor: Option<Arc<Auditor>>,
+    connections: Arc<Connections>,
+    statistics: Arc<StatementStatistics>,
+    result_cache: Option<Arc<ResultCache>>,
+    settings: Arc<ServerSettings>,
+    log_connections: AtomicBool,
+    log_disconnections: AtomicBool,
+    max_parallel_workers: usize,
+    work_mem: usize,
+    statement_timeout: Option<Duration>,
+    /// tokens that cancel running statements of sessions by their ids
+    sessions: Mutex<HashMap<u64, CancellationToken>>,
+    state: AtomicU8,
+    last_session_id: AtomicU64,
+    /// the last successfully loaded configuration
+    loaded: Mutex<Config>,
+    /// checkpointer, statistics collector and other maintenance tasks
+    workers: Workers,
+}
+
+impl Server {
+    fn next_session_id(&self) -> u64 {
+        self.last_session_id.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    /// Applies reloadable settings of `config`. Running sessions pick them up
+    /// with their next statement, access rules, connection logging and the
+    /// connection limit apply to clients that connect afterwards.
+    fn apply(&self, config: &Config) {
+        log::set_max_level(config.log_level());
+        self.settings
+            .set_log_min_duration_statement(config.millis("LOG_MIN_DURATION_STATEMENT"));
+        self.settings
+            .set_auto_explain_log_min_duration(config.millis("AUTO_EXPLAIN_LOG_MIN_DURATION"));
+        self.log_connections
+            .store(config.flag("LOG_CONNECTIONS"), Ordering::SeqCst);
+        self.log_disconnections
+            .store(config.flag("LOG_DISCONNECTIONS"), Ordering::SeqCst);
+        self.connections
+            .set_max_connections(number_setting(config, "MAX_CONNECTIONS"));
+    }
+
+    /// Reads configuration again, the server keeps running with the previous
+    /// one if the new configuration is invalid
+    fn reload(&self) {
+        let mut loaded = self.loaded.lock().expect("to acquire configuration lock");
+        let reloaded = match loaded.reload() {
+            Ok(reloaded) => reloaded,
+            Err(error) => {
+                log::error!("configuration is not reloaded: {}", error);
+                return;
+            }
+        };
+        let rules = match host_based_access(&reloaded) {
+            Ok(rules) => rules,
+            Err(error) => {
+                log::error!("configuration is not reloaded: {}", error);
+                return;
+            }
+        };
+        self.config.reload_host_based_access(rules);
+        self.apply(&reloaded);
+        for name in loaded.requires_restart(&reloaded) {
+            log::warn!("{} can't be changed without restart", name);
+        }
+        log::info!("configuration is reloaded");
+        *loaded = reloaded;
+    }
+
+    /// Applies settings of the server to `query_executor` of a session
+    fn configure(&self, mut query_executor: QueryExecutor, session_id: u64) -> QueryExecutor {
+        query_executor = query_executor
+            .with_server_settings(self.settings.clone())
+            .with_statement_statistics(self.statistics.clone())
+            .with_max_parallel_workers(self.max_parallel_workers)
+            .with_work_mem(self.work_mem)
+            .with_statement_timeout(self.statement_timeout)
+            .with_session_id(session_id);
+        if let Some(auditor) = &self.auditor {
+            query_executor = query_executor.with_audit(auditor.clone());
+        }
+        if let Some(result_cache) = &self.result_cache {
+            query_executor = query_executor.with_result_cache(result_cache.clone());
+        }
+        query_executor
+    }
+
+    /// Authenticates the client and handles its commands. Each session runs
+    /// on a dedicated thread with its own `QueryExecutor`, so that a slow
+    /// handshake or a long running statement of one session does not hold
+    /// back the other ones.
+    async fn serve(self: Arc<Self>, tcp_stream: AsyncArc<Async<TcpStream>>, address: SocketAddr, session_id: u64) {
+        let (receiver, sender) = match protocol::hand_shake(tcp_stream, address, &self.config).await {
+            Ok(Ok(channel)) => channel,
+            Ok(Err(error)) => {
+                log::debug!("handshake with {:?} failed: {:?}", address, error);
+                return;
+            }
+            Err(error) => {
+                log::error!("handshake with {:?} failed: {:?}", address, error);
+                return;
+            }
+        };
+        let session_user = match receiver.properties().1.iter().find(|(key, _value)| key == "user") {
+            Some((_key, user)) => user.clone(),
+            None => {
+                log::error!("no user name specified in startup packet");
+                return;
+            }
+        };
+        let connection = match self.connections.open(session_user.as_str()) {
+            Ok(connection) => connection,
+            Err(error) => {
+                log::warn!("{:?} connection is rejected: {:?}", session_user, error);
+                if let Err(error) = sender.send(Err(error)).and_then(|()| sender.flush()) {
+                    log::debug!("{:?} disconnected before rejection is sent: {:?}", session_user, error);
+                }
+                return;
+            }
+        };
+        let session_log = SessionLog {
+            session_id,
+            user: session_user.clone(),
+            database: receiver
+                .properties()
+                .1
+                .iter()
+                .find(|(key, _value)| key == "database")
+                .map(|(_key, database)| database.clone())
+                .unwrap_or_else(|| session_user.clone()),
+            address,
+            secure: receiver.is_secure(),
+            started: Instant::now(),
+            log_disconnection: self.log_disconnections.load(Ordering::SeqCst),
+        };
+        if self.log_connections.load(Ordering::SeqCst) {
+            session_log.connection_authorized();
+        }
+        let query_executor = self
+            .configure(
+                QueryExecutor::new(self.storage.clone(), Arc::new(sender), session_user.as_str()),
+                session_id,
+            )
+            .with_database(&session_log.database);
+        log::debug!("ready to handle query");
+
+        let _connection = connection;
+        let _session_log = session_log;
+        self.sessions
+            .lock()
+            .expect("to acquire sessions lock")
+            .insert(session_id, query_executor.cancellation_token());
+        self.handle_commands(receiver, query_executor).await;
+        self.sessions
+            .lock()
+            .expect("to acquire sessions lock")
+            .remove(&session_id);
+    }
+
+    /// Interrupts running statements of all sessions
+    fn cancel_sessions(&self, interruption: Interruption) {
+        for token in self.sessions.lock().expect("to acquire sessions lock").values() {
+            token.cancel(interruption);
+        }
+    }
+
+    /// Handles commands of the client until it terminates the session or
+    /// the connection with it is lost
+    async fn handle_commands<R: Receiver>(&self, mut receiver: R, mut query_executor: QueryExecutor) {
+        loop {
+            let result = match receiver.receive().await {
+                // a broken session ends on its own, the server and its other
+                // sessions go on
+                Err(e) => {
+                    log::error!("UNEXPECTED ERROR: {:?}", e);
+                    return;
+                }
+                Ok(Err(e)) => {
+                    log::error!("UNEXPECTED ERROR: {:?}", e);
+                    return;
+                }
+                Ok(Ok(Command::Bind {
+                    portal_name,
+                    statement_name,
+                    param_formats,
+                    raw_params,
+                    result_formats,
+                })) => query_executor.bind_prepared_statement_to_portal(
+                    portal_name.as_str(),
+                    statement_name.as_str(),
+                    param_formats.as_ref(),
+                    raw_params.as_ref(),
+                    result_formats.as_ref(),
+                ),
+                Ok(Ok(Command::Continue)) => Ok(()),
+                Ok(Ok(Command::DescribeStatement { name })) => {
+                    query_executor.describe_prepared_statement(name.as_str())
+                }
+                Ok(Ok(Command::DescribePortal { name })) => query_executor.describe_portal(name.as_str()),
+                Ok(Ok(Command::Execute { portal_name, max_rows })) => {
+                    query_executor.execute_portal(portal_name.as_str(), max_rows)
+                }
+                Ok(Ok(Command::Flush)) => query_executor.flush(),
+                Ok(Ok(Command::Parse {
+                    statement_name,
+                    sql,
+                    param_types,
+                })) => {
+                    query_executor.parse_prepared_statement(statement_name.as_str(), sql.as_str(), param_types.as_ref())
+                }
+                Ok(Ok(Command::Query { sql })) => query_executor
+                    .execute(sql.as_str())
+                    .and_then(|()| query_executor.flush()),
+                Ok(Ok(Command::Terminate)) => {
+                    log::debug!("Closing connection with client");
+                    break;
+                }
+            };
+            match result {
+                Ok(()) => {}
+                Err(error) if error.is_connection_lost() => {
+                    log::debug!("Connection with client is lost: {:?}", error);
+                    break;
+                }
+                Err(error) if error.is_user_error() => log::debug!("{}", error),
+                Err(error) => log::error!("{}\n{:?}", error, error),
+            }
+        }
+    }
+}
+
+fn data_directory(config: &Config) -> PathBuf {
+    config.path("ROOT_PATH").unwrap_or_default().join("database")
 }
 
-fn pfx_certificate_path() -> PathBuf {
-    let file = env::var("PFX_CERTIFICATE_FILE").unwrap();
-    let path = Path::new(&file);
+fn socket_address(config: &Config, port: &str) -> SocketAddr {
+    SocketAddr::new(
+        config.address("HOST").expect("HOST has default value"),
+        config.port(port).expect("port is configured"),
+    )
+}
+
+fn number_setting(config: &Config, name: &str) -> usize {
+    config.number(name).expect("setting has default value")
+}
+
+fn pfx_certificate_path(config: &Config) -> PathBuf {
+    let path = config
+        .path("PFX_CERTIFICATE_FILE")
+        .expect("PFX_CERTIFICATE_FILE to be set");
     if path.is_absolute() {
-        return path.to_path_buf();
+        return path;
     }
 
     let current_dir = env::current_dir().unwrap();
     current_dir.as_path().join(path)
 }
 
-fn pfx_certificate_password() -> String {
-    env::var("PFX_CERTIFICATE_PASSWORD").unwrap()
+/// Logs lifecycle of a client session, disconnection is logged when the
+/// session is dropped
+struct SessionLog {
+    session_id: u64,
+    user: String,
+    database: String,
+    address: SocketAddr,
+    secure: bool,
+    started: Instant,
+    log_disconnection: bool,
+}
+
+impl SessionLog {
+    fn connection_authorized(&self) {
+        log::info!(
+            "connection authorized: session={} user={} database={} host={} port={} tls={}",
+            self.session_id,
+            self.user,
+            self.database,
+            self.address.ip(),
+            self.address.port(),
+            if self.secure { "on" } else { "off" }
+        );
+    }
+}
+
+impl Drop for SessionLog {
+    fn drop(&mut self) {
+        if self.log_disconnection {
+            let elapsed = self.started.elapsed();
+            let seconds = elapsed.as_secs();
+            log::info!(
+                "disconnection: session time: {}:{:02}:{:02}.{:03} session={} user={} database={} host={} port={}",
+                seconds / 3600,
+                seconds / 60 % 60,
+                seconds % 60,
+                elapsed.subsec_millis(),
+                self.session_id,
+                self.user,
+                self.database,
+                self.address.ip(),
+                self.address.port()
+            );
+        }
+    }
+}
+
+/// `AUDIT_CLASSES` are audited for all roles unless `AUDIT_ROLE_CLASSES`
+/// overrides them, e.g. `reporting=read;service=none`
+fn auditor(config: &Config) -> Option<Auditor> {
+    let file = config.path("AUDIT_LOG_FILE")?;
+    let classes = config.text("AUDIT_CLASSES").unwrap_or("all");
+    let mut policy = match AuditPolicy::parse(classes) {
+        Ok(policy) => policy,
+        Err(error) => panic!("AUDIT_CLASSES is invalid: {}", error),
+    };
+    for role_classes in config
+        .text("AUDIT_ROLE_CLASSES")
+        .unwrap_or_default()
+        .split(';')
+        .filter(|role_classes| !role_classes.trim().is_empty())
+    {
+        let mut parts = role_classes.splitn(2, '=');
+        let role = parts.next().unwrap_or_default().trim();
+        policy = match policy.with_role(role, parts.next().unwrap_or_default()) {
+            Ok(policy) => policy,
+            Err(error) => panic!("AUDIT_ROLE_CLASSES is invalid: {}", error),
+        };
+    }
+    Some(Auditor::new(policy, Box::new(AuditLog::open(&file).unwrap())))
+}
+
+fn host_based_access(config: &Config) -> Result<Option<HostBasedAccess>, String> {
+    let file = match config.path("HBA_CONFIG_FILE") {
+        Some(file) => file,
+        None => return Ok(None),
+    };
+    let content = fs::read_to_string(&file).map_err(|error| format!("could not read {}: {}", file.display(), error))?;
+    match HostBasedAccess::parse(content.as_str()) {
+        Ok(rules) => Ok(Some(rules)),
+        Err(error) => Err(format!("{} is invalid: {}", file.display(), error)),
+    }
 }
 
-fn protocol_configuration() -> ProtocolConfiguration {
-    match env::var("SECURE") {
-        Ok(s) => match s.to_lowercase().as_str() {
-            "ssl_only" => ProtocolConfiguration::with_ssl(pfx_certificate_path(), pfx_certificate_password()),
+fn protocol_configuration(config: &Config) -> ProtocolConfiguration {
+    let protocol_config = match config.text("SECURE") {
+        Some(s) => match s.to_lowercase().as_str() {
+            "ssl_only" => {
+                let protocol_config = ProtocolConfiguration::with_ssl(
+                    pfx_certificate_path(config),
+                    config
+                        .text("PFX_CERTIFICATE_PASSWORD")
+                        .expect("PFX_CERTIFICATE_PASSWORD to be set")
+                        .to_owned(),
+                );
+                match config.path("CLIENT_CA_FILE") {
+                    Some(ca_file) => {
+                        protocol_config.with_client_certificates(ca_file, config.flag("REQUIRE_CLIENT_CERTIFICATE"))
+                    }
+                    None => protocol_config,
+                }
+            }
             _ => ProtocolConfiguration::none(),
         },
-        _ => ProtocolConfiguration::none(),
+        None => ProtocolConfiguration::none(),
+    };
+    match host_based_access(config) {
+        Ok(Some(rules)) => protocol_config.with_host_based_access(rules),
+        Ok(None) => protocol_config,
+        Err(error) => panic!("{}", error),
     }
 }
@@ -0,0 +1,315 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{net::IpAddr, str::FromStr};
+
+/// Authentication method of a host based access record
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AuthMethod {
+    /// Allow the connection unconditionally
+    Trust,
+    /// Require the client to supply a clear text password
+    Password,
+    /// Perform `SCRAM-SHA-256` authentication to verify the user's password
+    ScramSha256,
+    /// Require verified `ssl` client certificate with common name equal to
+    /// the user name
+    Cert,
+    /// Reject the connection unconditionally
+    Reject,
+}
+
+impl FromStr for AuthMethod {
+    type Err = String;
+
+    fn from_str(method: &str) -> std::result::Result<Self, Self::Err> {
+        match method {
+            "trust" => Ok(AuthMethod::Trust),
+            "password" => Ok(AuthMethod::Password),
+            "scram-sha-256" => Ok(AuthMethod::ScramSha256),
+            "cert" => Ok(AuthMethod::Cert),
+            "reject" => Ok(AuthMethod::Reject),
+            other => Err(format!("invalid authentication method \"{}\"", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ConnectionType {
+    Host,
+    HostSsl,
+    HostNoSsl,
+}
+
+impl ConnectionType {
+    fn matches(&self, ssl: bool) -> bool {
+        match self {
+            ConnectionType::Host => true,
+            ConnectionType::HostSsl => ssl,
+            ConnectionType::HostNoSsl => !ssl,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Names {
+    All,
+    SameUser,
+    List(Vec<String>),
+}
+
+impl Names {
+    fn parse(field: &str, same_user_allowed: bool) -> Names {
+        match field {
+            "all" => Names::All,
+            "sameuser" if same_user_allowed => Names::SameUser,
+            _ => Names::List(field.split(',').map(ToOwned::to_owned).collect()),
+        }
+    }
+
+    fn matches(&self, name: &str, user: &str) -> bool {
+        match self {
+            Names::All => true,
+            Names::SameUser => name == user,
+            Names::List(names) => names.iter().any(|n| n == name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Address {
+    All,
+    Network(IpAddr, u32),
+}
+
+impl Address {
+    fn parse(field: &str) -> std::result::Result<Address, String> {
+        if field == "all" {
+            return Ok(Address::All);
+        }
+        let mut parts = field.splitn(2, '/');
+        let ip = parts
+            .next()
+            .and_then(|ip| IpAddr::from_str(ip).ok())
+            .ok_or_else(|| format!("invalid IP address \"{}\"", field))?;
+        let max_prefix = if ip.is_ipv4() { 32 } else { 128 };
+        let prefix = match parts.next() {
+            Some(prefix) => prefix
+                .parse()
+                .ok()
+                .filter(|prefix| *prefix <= max_prefix)
+                .ok_or_else(|| format!("invalid CIDR mask in address \"{}\"", field))?,
+            None => max_prefix,
+        };
+        Ok(Address::Network(ip, prefix))
+    }
+
+    fn matches(&self, address: IpAddr) -> bool {
+        match (self, address) {
+            (Address::All, _) => true,
+            (Address::Network(IpAddr::V4(network), prefix), IpAddr::V4(address)) => {
+                let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+                u32::from(*network) & mask == u32::from(address) & mask
+            }
+            (Address::Network(IpAddr::V6(network), prefix), IpAddr::V6(address)) => {
+                let mask = u128::MAX.checked_shl(128 - prefix).unwrap_or(0);
+                u128::from(*network) & mask == u128::from(address) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Record {
+    connection_type: ConnectionType,
+    databases: Names,
+    users: Names,
+    address: Address,
+    method: AuthMethod,
+}
+
+/// Host based access rules that decide which hosts, users and databases can
+/// connect and how they have to authenticate. Rules are written in format of
+/// PostgreSQL `pg_hba.conf` file:
+///
+/// ```text
+/// # TYPE     DATABASE  USER      ADDRESS       METHOD
+/// hostssl    all       all       10.0.0.0/8    scram-sha-256
+/// host       all       postgres  127.0.0.1/32  trust
+/// host       all       all       all           reject
+/// ```
+///
+/// Records are checked top to bottom, the first one that matches the
+/// connection decides authentication method.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HostBasedAccess {
+    records: Vec<Record>,
+}
+
+impl HostBasedAccess {
+    /// Parses content of `pg_hba.conf` like file. Error contains number of the
+    /// first invalid line
+    pub fn parse(content: &str) -> std::result::Result<HostBasedAccess, String> {
+        let mut records = vec![];
+        for (index, line) in content.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default();
+            let fields = line.split_whitespace().collect::<Vec<&str>>();
+            if fields.is_empty() {
+                continue;
+            }
+            records.push(Self::parse_record(&fields).map_err(|error| format!("line {}: {}", index + 1, error))?);
+        }
+        Ok(HostBasedAccess { records })
+    }
+
+    fn parse_record(fields: &[&str]) -> std::result::Result<Record, String> {
+        let connection_type = match fields[0] {
+            "host" => ConnectionType::Host,
+            "hostssl" => ConnectionType::HostSsl,
+            "hostnossl" => ConnectionType::HostNoSsl,
+            other => return Err(format!("invalid connection type \"{}\"", other)),
+        };
+        if fields.len() != 5 {
+            return Err(format!("expected 5 fields, found {}", fields.len()));
+        }
+        let method = fields[4].parse()?;
+        if method == AuthMethod::Cert && connection_type != ConnectionType::HostSsl {
+            return Err("cert authentication is only supported on hostssl connections".to_owned());
+        }
+        Ok(Record {
+            connection_type,
+            databases: Names::parse(fields[1], true),
+            users: Names::parse(fields[2], false),
+            address: Address::parse(fields[3])?,
+            method,
+        })
+    }
+
+    /// Returns authentication method of the first matching record or `None` if
+    /// there is no record for the connection
+    pub fn method(&self, address: IpAddr, ssl: bool, database: &str, user: &str) -> Option<AuthMethod> {
+        self.records
+            .iter()
+            .find(|record| {
+                record.connection_type.matches(ssl)
+                    && record.databases.matches(database, user)
+                    && record.users.matches(user, user)
+                    && record.address.matches(address)
+            })
+            .map(|record| record.method)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    const LOCALHOST_V4: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+    const LOCALHOST_V6: IpAddr = IpAddr::V6(Ipv6Addr::LOCALHOST);
+
+    #[test]
+    fn first_matching_record_wins() {
+        let hba = HostBasedAccess::parse(
+            "host all postgres 127.0.0.1/32 trust\n\
+             host all all all reject\n\
+             host all postgres all password",
+        )
+        .expect("valid config");
+
+        assert_eq!(
+            hba.method(LOCALHOST_V4, false, "database", "postgres"),
+            Some(AuthMethod::Trust)
+        );
+        assert_eq!(
+            hba.method("10.0.0.1".parse().unwrap(), false, "database", "postgres"),
+            Some(AuthMethod::Reject)
+        );
+    }
+
+    #[test]
+    fn no_matching_record() {
+        let hba = HostBasedAccess::parse("host all all 10.0.0.0/8 scram-sha-256").expect("valid config");
+
+        assert_eq!(
+            hba.method("10.1.2.3".parse().unwrap(), false, "database", "user"),
+            Some(AuthMethod::ScramSha256)
+        );
+        assert_eq!(hba.method("11.1.2.3".parse().unwrap(), false, "database", "user"), None);
+        assert_eq!(hba.method(LOCALHOST_V6, false, "database", "user"), None);
+    }
+
+    #[test]
+    fn connection_types() {
+        let hba = HostBasedAccess::parse(
+            "hostssl all all all scram-sha-256\n\
+             hostnossl all all all password",
+        )
+        .expect("valid config");
+
+        assert_eq!(
+            hba.method(LOCALHOST_V4, true, "database", "user"),
+            Some(AuthMethod::ScramSha256)
+        );
+        assert_eq!(
+            hba.method(LOCALHOST_V4, false, "database", "user"),
+            Some(AuthMethod::Password)
+        );
+    }
+
+    #[test]
+    fn database_and_user_lists() {
+        let hba = HostBasedAccess::parse(
+            "host sameuser all all trust\n\
+             host db_1,db_2 user_1,user_2 ::1 password # comment",
+        )
+        .expect("valid config");
+
+        assert_eq!(
+            hba.method(LOCALHOST_V6, false, "user_1", "user_1"),
+            Some(AuthMethod::Trust)
+        );
+        assert_eq!(
+            hba.method(LOCALHOST_V6, false, "db_2", "user_1"),
+            Some(AuthMethod::Password)
+        );
+        assert_eq!(hba.method(LOCALHOST_V6, false, "db_3", "user_1"), None);
+        assert_eq!(hba.method(LOCALHOST_V6, false, "db_1", "user_3"), None);
+    }
+
+    #[test]
+    fn invalid_records() {
+        assert_eq!(
+            HostBasedAccess::parse("# comment\nlocal all all trust"),
+            Err("line 2: invalid connection type \"local\"".to_owned())
+        );
+        assert_eq!(
+            HostBasedAccess::parse("host all all 127.0.0.1/33 trust"),
+            Err("line 1: invalid CIDR mask in address \"127.0.0.1/33\"".to_owned())
+        );
+        assert_eq!(
+            HostBasedAccess::parse("host all all all md5"),
+            Err("line 1: invalid authentication method \"md5\"".to_owned())
+        );
+        assert_eq!(
+            HostBasedAccess::parse("host all all all cert"),
+            Err("line 1: cert authentication is only supported on hostssl connections".to_owned())
+        );
+        assert_eq!(
+            HostBasedAccess::parse("host all all trust"),
+            Err("line 1: expected 5 fields, found 4".to_owned())
+        );
+    }
+}
@@ -17,41 +17,48 @@
 extern crate log;
 
 use crate::{
+    hba::{AuthMethod, HostBasedAccess},
     messages::{BackendMessage, Encryption, FrontendMessage},
-    results::QueryResult,
+    results::{QueryError, QueryEvent, QueryResult},
+    scram::{ScramVerifier, ServerExchange},
     sql_formats::PostgreSqlFormat,
     sql_types::PostgreSqlType,
+    tls::TlsStream,
 };
 use async_mutex::Mutex as AsyncMutex;
-use async_native_tls::TlsStream;
 use async_trait::async_trait;
 use blocking::Unblock;
 use byteorder::{ByteOrder, NetworkEndian};
 use futures_lite::{
     future::block_on,
-    io::{self, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ErrorKind},
+    io::{self, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
 };
 use itertools::Itertools;
 use std::{
     fs::File,
-    net::SocketAddr,
+    net::{IpAddr, SocketAddr},
     path::PathBuf,
     pin::Pin,
-    sync::Arc,
+    sync::{Arc, RwLock},
     task::{Context, Poll},
 };
 
+/// Module contains functionality to configure host based access
+pub mod hba;
 /// Module contains backend messages that could be send by server implementation
 /// to a client
 pub mod messages;
 /// Module contains functionality to represent query result
 pub mod results;
+/// Module contains functionality to create and check salted password verifiers
+pub mod scram;
 /// Module contains functionality to represent SQL format
 pub mod sql_formats;
 /// Module contains functionality to represent SQL type system
 pub mod sql_types;
 /// Module contains functionality to represent SQL data value
 pub mod sql_values;
+mod tls;
 
 /// Protocol version
 pub type Version = i32;
@@ -88,6 +95,11 @@ pub enum Error {
     UnsupportedRequest,
     /// Indicates that during handshake client sent unrecognized protocol version
     UnrecognizedVersion,
+    /// Indicates that client sent password that does not match the one stored
+    /// for the user
+    AuthenticationFailed(String),
+    /// Indicates that host based access rules do not let the user connect
+    ConnectionRejected(String),
 }
 
 /// Result of handling incoming bytes from a client
@@ -115,6 +127,11 @@ pub enum Command {
         /// The name of the prepared statement to describe.
         name: String,
     },
+    /// Client commands to describe a portal
+    DescribePortal {
+        /// The name of the portal to describe.
+        name: String,
+    },
     /// Client commands to execute a portal
     Execute {
         /// The name of the portal to execute.
@@ -134,8 +151,9 @@ pub enum Command {
         /// The SQL to parse.
         sql: String,
         /// The number of specified parameter data types can be less than the
-        /// number of parameters specified in the query.
-        param_types: Vec<PostgreSqlType>,
+        /// number of parameters specified in the query. Unspecified types
+        /// are `None`.
+        param_types: Vec<Option<PostgreSqlType>>,
     },
     /// Client commands to execute a `Query`
     Query {
@@ -176,22 +194,9 @@ where
 
         match decode_startup(message) {
             Ok(ClientHandshake::Startup(version, params)) => {
-                channel
-                    .write_all(BackendMessage::AuthenticationCleartextPassword.as_vec().as_slice())
-                    .await?;
-                let mut buffer = [0u8; 1];
-                let tag = channel.read_exact(&mut buffer).await.map(|_| buffer[0]);
-                log::debug!("client message response tag {:?}", tag);
-                log::debug!("waiting for authentication response");
-                let mut buffer = [0u8; 4];
-                let len = channel
-                    .read_exact(&mut buffer)
-                    .await
-                    .map(|_| NetworkEndian::read_u32(&buffer) as usize)?;
-                let len = len - 4;
-                let mut buffer = Vec::with_capacity(len);
-                buffer.resize(len, b'0');
-                let _message = channel.read_exact(&mut buffer).await.map(|_| buffer)?;
+                if let Err(error) = authenticate(&mut channel, address, config, &params).await? {
+                    return Ok(Err(error));
+                }
                 channel
                     .write_all(BackendMessage::AuthenticationOk.as_vec().as_slice())
                     .await?;
@@ -225,9 +230,10 @@ where
                     .write_all(BackendMessage::ReadyForQuery.as_vec().as_slice())
                     .await?;
 
+                let secure = matches!(channel, Channel::Secure(_));
                 let channel = Arc::new(AsyncMutex::new(channel));
                 return Ok(Ok((
-                    RequestReceiver::new((version, params.clone()), channel.clone()),
+                    RequestReceiver::new((version, params.clone()), secure, channel.clone()),
                     ResponseSender::new((version, params), channel),
                 )));
             }
@@ -249,21 +255,193 @@ where
     }
 }
 
+/// Authenticates a client with a method chosen by host based access rules
+async fn authenticate<RW>(
+    channel: &mut Channel<RW>,
+    address: SocketAddr,
+    config: &ProtocolConfiguration,
+    params: &Params,
+) -> io::Result<Result<()>>
+where
+    RW: AsyncRead + AsyncWrite + Unpin,
+{
+    let param = |name: &str| {
+        params
+            .iter()
+            .find(|(key, _value)| key == name)
+            .map(|(_key, value)| value.as_str())
+    };
+    let user = param("user").unwrap_or_default();
+    let database = param("database").unwrap_or(user);
+    let ssl = matches!(channel, Channel::Secure(_));
+    let host = address.ip().to_string();
+    log::debug!("authenticating {:?} to {:?} from {:?}", user, database, host);
+
+    match config.auth_method(address.ip(), ssl, database, user) {
+        None => {
+            let error = QueryError::no_host_based_access_entry(host, user.to_owned(), database.to_owned(), ssl);
+            send_error(channel, error).await?;
+            Ok(Err(Error::ConnectionRejected(user.to_owned())))
+        }
+        Some(AuthMethod::Reject) => {
+            let error = QueryError::connection_rejected(host, user.to_owned(), database.to_owned());
+            send_error(channel, error).await?;
+            Ok(Err(Error::ConnectionRejected(user.to_owned())))
+        }
+        Some(AuthMethod::Trust) => Ok(Ok(())),
+        Some(AuthMethod::Cert) => {
+            let common_name = match channel {
+                Channel::Secure(tls) => tls.peer_common_name(),
+                Channel::Plain(_) => None,
+            };
+            if common_name.as_deref() == Some(user) {
+                Ok(Ok(()))
+            } else {
+                log::debug!("certificate authentication failed for {:?}", user);
+                send_error(channel, QueryError::certificate_authentication_failed(user.to_owned())).await?;
+                Ok(Err(Error::AuthenticationFailed(user.to_owned())))
+            }
+        }
+        Some(AuthMethod::Password) => {
+            channel
+                .write_all(BackendMessage::AuthenticationCleartextPassword.as_vec().as_slice())
+                .await?;
+            log::debug!("waiting for authentication response");
+            let (_tag, message) = read_message(channel).await?;
+            let password = match decode_password(&message) {
+                Ok(password) => password,
+                Err(error) => return Ok(Err(error)),
+            };
+            if config.password_matches(user, password) {
+                Ok(Ok(()))
+            } else {
+                authentication_failed(channel, user).await
+            }
+        }
+        Some(AuthMethod::ScramSha256) => {
+            channel
+                .write_all(
+                    BackendMessage::AuthenticationSASL(vec![scram::MECHANISM.to_owned()])
+                        .as_vec()
+                        .as_slice(),
+                )
+                .await?;
+            let (_tag, message) = read_message(channel).await?;
+            let client_first = match decode_sasl_initial_response(&message) {
+                Ok(client_first) => client_first,
+                Err(error) => return Ok(Err(error)),
+            };
+            let verifier = config.password_verifier(user).unwrap_or_else(ScramVerifier::mock);
+            let exchange = match ServerExchange::start(verifier, client_first) {
+                Some(exchange) => exchange,
+                None => return authentication_failed(channel, user).await,
+            };
+            channel
+                .write_all(
+                    BackendMessage::AuthenticationSASLContinue(exchange.server_first().to_owned())
+                        .as_vec()
+                        .as_slice(),
+                )
+                .await?;
+            let (_tag, message) = read_message(channel).await?;
+            let client_final = match std::str::from_utf8(&message) {
+                Ok(client_final) => client_final,
+                Err(_) => return Ok(Err(Error::InvalidUtfString)),
+            };
+            match exchange.finish(client_final) {
+                Some(server_final) => {
+                    channel
+                        .write_all(
+                            BackendMessage::AuthenticationSASLFinal(server_final)
+                                .as_vec()
+                                .as_slice(),
+                        )
+                        .await?;
+                    Ok(Ok(()))
+                }
+                None => authentication_failed(channel, user).await,
+            }
+        }
+    }
+}
+
+async fn authentication_failed<RW>(channel: &mut Channel<RW>, user: &str) -> io::Result<Result<()>>
+where
+    RW: AsyncRead + AsyncWrite + Unpin,
+{
+    log::debug!("password authentication failed for {:?}", user);
+    send_error(channel, QueryError::password_authentication_failed(user.to_owned())).await?;
+    Ok(Err(Error::AuthenticationFailed(user.to_owned())))
+}
+
+async fn send_error<RW>(channel: &mut Channel<RW>, error: QueryError) -> io::Result<()>
+where
+    RW: AsyncRead + AsyncWrite + Unpin,
+{
+    let message: BackendMessage = error.into();
+    channel.write_all(message.as_vec().as_slice()).await?;
+    channel.flush().await
+}
+
+/// Reads tag and content of a message that client sends during authentication
+async fn read_message<RW>(channel: &mut Channel<RW>) -> io::Result<(u8, Vec<u8>)>
+where
+    RW: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut buffer = [0u8; 1];
+    let tag = channel.read_exact(&mut buffer).await.map(|_| buffer[0])?;
+    log::debug!("client message response tag {:?}", tag);
+    let mut buffer = [0u8; 4];
+    let len = channel
+        .read_exact(&mut buffer)
+        .await
+        .map(|_| NetworkEndian::read_u32(&buffer) as usize)?;
+    let len = len - 4;
+    let mut buffer = Vec::with_capacity(len);
+    buffer.resize(len, b'0');
+    let message = channel.read_exact(&mut buffer).await.map(|_| buffer)?;
+    Ok((tag, message))
+}
+
 async fn tls_channel<RW>(tcp_channel: RW, config: &ProtocolConfiguration) -> io::Result<TlsStream<RW>>
 where
     RW: AsyncRead + AsyncWrite + Unpin,
 {
     match config.ssl_config() {
         Some((path, password)) => {
-            match async_native_tls::accept(Unblock::new(File::open(path)?), password, tcp_channel).await {
-                Ok(socket) => Ok(socket),
-                Err(_err) => Err(io::Error::from(ErrorKind::ConnectionAborted)),
-            }
+            let mut identity = vec![];
+            Unblock::new(File::open(path)?).read_to_end(&mut identity).await?;
+            let client_ca = config
+                .client_certificates
+                .as_ref()
+                .map(|(ca_file, required)| (ca_file.as_path(), *required));
+            let acceptor = tls::acceptor(&identity, password, client_ca)?;
+            tls::accept(&acceptor, tcp_channel).await
         }
         None => Err(io::Error::from(io::ErrorKind::ConnectionAborted)),
     }
 }
 
+fn decode_password(message: &[u8]) -> Result<&str> {
+    let password = message.split(|b| *b == 0).next().unwrap_or_default();
+    std::str::from_utf8(password).map_err(|_| Error::InvalidUtfString)
+}
+
+fn decode_sasl_initial_response(message: &[u8]) -> Result<&str> {
+    let mechanism_end = message
+        .iter()
+        .position(|b| *b == 0)
+        .ok_or_else(|| Error::InvalidInput("SASL mechanism is not specified".to_owned()))?;
+    let mechanism = std::str::from_utf8(&message[..mechanism_end]).map_err(|_| Error::InvalidUtfString)?;
+    if mechanism != scram::MECHANISM {
+        return Err(Error::InvalidInput(format!("unsupported SASL mechanism {}", mechanism)));
+    }
+    if message.len() < mechanism_end + 5 {
+        return Err(Error::InvalidInput("SASL initial response is missing".to_owned()));
+    }
+    std::str::from_utf8(&message[mechanism_end + 5..]).map_err(|_| Error::InvalidUtfString)
+}
+
 fn decode_startup(message: Vec<u8>) -> Result<ClientHandshake> {
     let version = NetworkEndian::read_i32(&message);
     log::debug!("VERSION FOR TEST = {:#?}", version);
@@ -289,51 +467,61 @@ fn decode_startup(message: Vec<u8>) -> Result<ClientHandshake> {
 
 struct RequestReceiver<RW: AsyncRead + AsyncWrite + Unpin> {
     properties: (Version, Params),
+    secure: bool,
     channel: Arc<AsyncMutex<Channel<RW>>>,
+    /// frame data of the last received message, kept to not allocate a new
+    /// buffer for each of them
+    buffer: Vec<u8>,
 }
 
 impl<RW: AsyncRead + AsyncWrite + Unpin> RequestReceiver<RW> {
     /// Creates new Connection with properties and read-write socket
-    pub(crate) fn new(properties: (Version, Params), channel: Arc<AsyncMutex<Channel<RW>>>) -> RequestReceiver<RW> {
-        RequestReceiver { properties, channel }
-    }
-
-    /// connection properties tuple
-    pub fn properties(&self) -> &(Version, Params) {
-        &(self.properties)
+    pub(crate) fn new(
+        properties: (Version, Params),
+        secure: bool,
+        channel: Arc<AsyncMutex<Channel<RW>>>,
+    ) -> RequestReceiver<RW> {
+        RequestReceiver {
+            properties,
+            secure,
+            channel,
+            buffer: Vec::new(),
+        }
     }
 }
 
 #[async_trait]
 impl<RW: AsyncRead + AsyncWrite + Unpin> Receiver for RequestReceiver<RW> {
+    fn properties(&self) -> &(Version, Params) {
+        &(self.properties)
+    }
+
+    fn is_secure(&self) -> bool {
+        self.secure
+    }
+
     async fn receive(&mut self) -> io::Result<Result<Command>> {
+        let mut channel = self.channel.lock().await;
+
         // Parses the one-byte tag.
         let mut buffer = [0u8; 1];
-        let tag = self
-            .channel
-            .lock()
-            .await
-            .read_exact(&mut buffer)
-            .await
-            .map(|_| buffer[0])?;
+        let tag = channel.read_exact(&mut buffer).await.map(|_| buffer[0])?;
         log::debug!("TAG {:?}", tag);
 
         // Parses the frame length.
         let mut buffer = [0u8; 4];
-        let len = self
-            .channel
-            .lock()
-            .await
+        let len = channel
             .read_exact(&mut buffer)
             .await
             .map(|_| NetworkEndian::read_u32(&buffer))?;
 
         // Parses the frame data.
-        let mut buffer = Vec::with_capacity(len as usize - 4);
-        buffer.resize(len as usize - 4, b'0');
-        self.channel.lock().await.read_exact(&mut buffer).await?;
+        self.buffer.clear();
+        self.buffer.resize(len as usize - 4, b'0');
+        channel.read_exact(&mut self.buffer).await?;
+        drop(channel);
 
-        let message = match FrontendMessage::decode(tag, &buffer) {
+        let message = match FrontendMessage::decode(tag, &self.buffer) {
             Ok(msg) => msg,
             Err(err) => return Ok(Err(err)),
         };
@@ -354,6 +542,7 @@ impl<RW: AsyncRead + AsyncWrite + Unpin> Receiver for RequestReceiver<RW> {
                 result_formats,
             })),
             FrontendMessage::DescribeStatement { name } => Ok(Ok(Command::DescribeStatement { name })),
+            FrontendMessage::DescribePortal { name } => Ok(Ok(Command::DescribePortal { name })),
             FrontendMessage::Execute { portal_name, max_rows } => Ok(Ok(Command::Execute { portal_name, max_rows })),
             FrontendMessage::Flush => Ok(Ok(Command::Flush)),
             FrontendMessage::Parse {
@@ -375,6 +564,12 @@ impl<RW: AsyncRead + AsyncWrite + Unpin> Receiver for RequestReceiver<RW> {
 /// Trait to handle client to server commands for PostgreSQL Wire Protocol connection
 #[async_trait]
 pub trait Receiver: Send + Sync {
+    /// connection properties tuple
+    fn properties(&self) -> &(Version, Params);
+
+    /// whether the connection is encrypted with TLS
+    fn is_secure(&self) -> bool;
+
     /// receives and decodes a command from remote client
     async fn receive(&mut self) -> io::Result<Result<Command>>;
 }
@@ -402,46 +597,46 @@ impl<RW: AsyncRead + AsyncWrite + Unpin> ResponseSender<RW> {
 
 impl<RW: AsyncRead + AsyncWrite + Unpin> Sender for ResponseSender<RW> {
     fn flush(&self) -> io::Result<()> {
-        block_on(async {
-            self.channel.lock().await.flush().await.expect("OK");
-        });
+        block_on(async { self.channel.lock().await.flush().await })
+    }
 
-        Ok(())
+    fn accepts_encoded_rows(&self) -> bool {
+        true
     }
 
+    /// Fails with an error of the socket when the client is gone
     fn send(&self, query_result: QueryResult) -> io::Result<()> {
         log::debug!("Sending {:?}", query_result);
         block_on(async {
             match query_result {
+                Ok(QueryEvent::EncodedDataRows(rows)) => {
+                    self.channel.lock().await.write_all(rows.as_bytes()).await?;
+                }
                 Ok(event) => {
                     let messages: Vec<BackendMessage> = event.into();
                     for message in messages {
                         log::debug!("{:?}", message);
-                        self.channel
-                            .lock()
-                            .await
-                            .write_all(message.as_vec().as_slice())
-                            .await
-                            .expect("OK");
+                        self.channel.lock().await.write_all(message.as_vec().as_slice()).await?;
                     }
                 }
                 Err(error) => {
                     let message: BackendMessage = error.into();
                     log::debug!("{:?}", message);
-                    self.channel
-                        .lock()
-                        .await
-                        .write_all(message.as_vec().as_slice())
-                        .await
-                        .expect("OK");
+                    self.channel.lock().await.write_all(message.as_vec().as_slice()).await?;
                 }
             }
             log::debug!("end of the command is sent");
-        });
-        Ok(())
+            Ok(())
+        })
     }
 }
 
+/// Trait to look up credentials of roles when a client authenticates
+pub trait PasswordStore: Send + Sync {
+    /// returns verifier of `role` password or `None` if password is not set
+    fn password_verifier(&self, role: &str) -> Option<ScramVerifier>;
+}
+
 /// Trait to handle server to client query results for PostgreSQL Wire Protocol
 /// connection
 pub trait Sender: Send + Sync {
@@ -451,6 +646,12 @@ pub trait Sender: Send + Sync {
     /// Sends response messages to client. Most of the time it is a single
     /// message, select result one of the exceptional situation
     fn send(&self, query_result: QueryResult) -> io::Result<()>;
+
+    /// Whether selected rows can be sent as `QueryEvent::EncodedDataRows`,
+    /// senders that look into rows get them as `QueryEvent::DataRows`
+    fn accepts_encoded_rows(&self) -> bool {
+        false
+    }
 }
 
 impl<RW: AsyncRead + AsyncWrite + Unpin> PartialEq for RequestReceiver<RW> {
@@ -503,22 +704,68 @@ impl<RW: AsyncRead + AsyncWrite + Unpin> AsyncWrite for Channel<RW> {
 /// PostgreSQL Wire Protocol supports `ssl`/`tls` and `gss` encryption
 pub struct ProtocolConfiguration {
     ssl_conf: Option<(PathBuf, String)>,
+    password_store: Option<Arc<dyn PasswordStore>>,
+    host_based_access: RwLock<Option<HostBasedAccess>>,
+    client_certificates: Option<(PathBuf, bool)>,
 }
 
 #[allow(dead_code)]
 impl ProtocolConfiguration {
     /// Creates configuration that support neither `ssl` nor `gss` encryption
     pub fn none() -> Self {
-        Self { ssl_conf: None }
+        Self {
+            ssl_conf: None,
+            password_store: None,
+            host_based_access: RwLock::new(None),
+            client_certificates: None,
+        }
     }
 
     /// Creates configuration that support only `ssl`
     pub fn with_ssl(cert: PathBuf, password: String) -> Self {
         Self {
             ssl_conf: Some((cert, password)),
+            password_store: None,
+            host_based_access: RwLock::new(None),
+            client_certificates: None,
         }
     }
 
+    /// Checks passwords of connecting users against verifiers kept in `store`
+    pub fn with_password_store(mut self, store: Arc<dyn PasswordStore>) -> Self {
+        self.password_store = Some(store);
+        self
+    }
+
+    /// Asks clients that connect over `ssl` for certificates issued by one of
+    /// authorities in `ca_file`. If certificate is `required` connections
+    /// without it are refused during `ssl` handshake.
+    ///
+    /// Common name of a verified certificate has to match user name when host
+    /// based access rules choose `cert` authentication method
+    pub fn with_client_certificates(mut self, ca_file: PathBuf, required: bool) -> Self {
+        self.client_certificates = Some((ca_file, required));
+        self
+    }
+
+    /// Chooses authentication method of connecting clients by `rules`.
+    /// Without the rules every client authenticates with a clear text password
+    /// and users without a stored password are let in with any password
+    pub fn with_host_based_access(self, rules: HostBasedAccess) -> Self {
+        self.reload_host_based_access(Some(rules));
+        self
+    }
+
+    /// Replaces host based access rules of a running server. Clients that
+    /// connect afterwards are checked against the new `rules`, already
+    /// established connections are left as they are
+    pub fn reload_host_based_access(&self, rules: Option<HostBasedAccess>) {
+        *self
+            .host_based_access
+            .write()
+            .expect("to acquire host based access lock") = rules;
+    }
+
     /// returns `true` if support `ssl` connection
     fn ssl_support(&self) -> bool {
         self.ssl_conf.is_some()
@@ -529,6 +776,39 @@ impl ProtocolConfiguration {
         self.ssl_conf.as_ref()
     }
 
+    /// Authentication method of a client that connects from `address` or
+    /// `None` if it is not allowed to connect
+    pub fn auth_method(&self, address: IpAddr, ssl: bool, database: &str, user: &str) -> Option<AuthMethod> {
+        match self
+            .host_based_access
+            .read()
+            .expect("to acquire host based access lock")
+            .as_ref()
+        {
+            Some(rules) => rules.method(address, ssl, database, user),
+            None => Some(AuthMethod::Password),
+        }
+    }
+
+    /// stored password verifier of `user`
+    fn password_verifier(&self, user: &str) -> Option<ScramVerifier> {
+        self.password_store
+            .as_ref()
+            .and_then(|store| store.password_verifier(user))
+    }
+
+    /// returns `true` if `password` is correct for `user`
+    fn password_matches(&self, user: &str, password: &str) -> bool {
+        match self.password_verifier(user) {
+            Some(verifier) => verifier.matches(password),
+            None => self
+                .host_based_access
+                .read()
+                .expect("to acquire host based access lock")
+                .is_none(),
+        }
+    }
+
     /// returns `true` if support `gss` encrypted connection
     fn gssenc_support(&self) -> bool {
         false
@@ -22,6 +22,7 @@ const ERROR_RESPONSE: u8 = b'E';
 const SEVERITY: u8 = b'S';
 const CODE: u8 = b'C';
 const MESSAGE: u8 = b'M';
+const DETAIL: u8 = b'D';
 const EMPTY_QUERY_RESPONSE: u8 = b'I';
 const NOTICE_RESPONSE: u8 = b'N';
 const AUTHENTICATION: u8 = b'R';
@@ -33,6 +34,7 @@ const NO_DATA: u8 = b'n';
 const PARSE_COMPLETE: u8 = b'1';
 const BIND_COMPLETE: u8 = b'2';
 const CLOSE_COMPLETE: u8 = b'3';
+const PORTAL_SUSPENDED: u8 = b's';
 
 pub(crate) enum Encryption {
     AcceptSsl,
@@ -70,8 +72,9 @@ pub enum FrontendMessage {
         /// The SQL to parse.
         sql: String,
         /// The number of specified parameter data types can be less than the
-        /// number of parameters specified in the query.
-        param_types: Vec<PostgreSqlType>,
+        /// number of parameters specified in the query. Unspecified (zero OID)
+        /// or unsupported types are `None` and have to be inferred.
+        param_types: Vec<Option<PostgreSqlType>>,
     },
 
     /// Describe an existing prepared statement.
@@ -207,12 +210,24 @@ pub enum BackendMessage {
     /// result as a hex string.)
     #[allow(dead_code)]
     AuthenticationMD5Password,
+    /// The frontend must now initiate a SASL negotiation, using one of the SASL
+    /// mechanisms listed in the message.
+    AuthenticationSASL(Vec<String>),
+    /// This message contains challenge data for the previous step of SASL
+    /// negotiation.
+    AuthenticationSASLContinue(String),
+    /// SASL authentication has completed with additional mechanism-specific
+    /// data for the client.
+    AuthenticationSASLFinal(String),
     /// The authentication exchange is successfully completed.
     AuthenticationOk,
     /// Start-up is completed. The frontend can now issue commands.
     ReadyForQuery,
-    /// One of the set of rows returned by a SELECT, FETCH, etc query.
-    DataRow(Vec<String>),
+    /// One of the set of rows returned by a SELECT, FETCH, etc query. `None`
+    /// fields are SQL NULLs.
+    DataRow(Vec<Option<String>>),
+    /// Rows that are already encoded as `DataRow` messages
+    DataRows(EncodedRows),
     /// Indicates that rows are about to be returned in response to a SELECT, FETCH,
     /// etc query. The contents of this message describe the column layout of
     /// the rows. This will be followed by a DataRow message for each row being
@@ -223,9 +238,14 @@ pub enum BackendMessage {
     /// An empty query string was recognized.
     #[allow(dead_code)]
     EmptyQueryResponse,
-    /// An error has occurred. Contains (`Severity`, `Error Code`, `Error Message`)
-    /// all of them are optional
-    ErrorResponse(Option<&'static str>, Option<&'static str>, Option<String>),
+    /// An error has occurred. Contains (`Severity`, `Error Code`, `Error Message`,
+    /// `Detail`) all of them are optional
+    ErrorResponse(
+        Option<&'static str>,
+        Option<&'static str>,
+        Option<String>,
+        Option<String>,
+    ),
     /// This message informs the frontend about the current (initial) setting of
     /// backend parameters, such as client_encoding or DateStyle
     ///
@@ -245,6 +265,68 @@ pub enum BackendMessage {
     /// This message informs the frontend about the previous `Close` frontend
     /// message is successful.
     CloseComplete,
+    /// This message informs the frontend that the previous `Execute`
+    /// frontend message reached its row limit before rows of the portal
+    /// were exhausted.
+    PortalSuspended,
+}
+
+/// `DataRow` messages of rows written one after another into a single
+/// buffer, values of fields are written into the buffer as they are
+/// encoded without allocating their text first
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EncodedRows {
+    buffer: Vec<u8>,
+    rows: usize,
+}
+
+impl EncodedRows {
+    /// Rows in a buffer of `capacity` bytes
+    pub fn with_capacity(capacity: usize) -> EncodedRows {
+        EncodedRows {
+            buffer: Vec::with_capacity(capacity),
+            rows: 0,
+        }
+    }
+
+    /// Appends a row of `fields` fields, `write` writes text of a field with
+    /// the given index into the buffer and returns `false` for NULL
+    pub fn push_row<W: FnMut(usize, &mut Vec<u8>) -> bool>(&mut self, fields: usize, mut write: W) {
+        let start = self.buffer.len();
+        self.buffer.push(DATA_ROW);
+        self.buffer.extend_from_slice(&[0; 4]);
+        self.buffer.extend_from_slice(&(fields as i16).to_be_bytes());
+        for field in 0..fields {
+            let length = self.buffer.len();
+            self.buffer.extend_from_slice(&[0; 4]);
+            let len = if write(field, &mut self.buffer) {
+                (self.buffer.len() - length - 4) as i32
+            } else {
+                self.buffer.truncate(length + 4);
+                // As a special case, -1 indicates a NULL column value.
+                -1
+            };
+            self.buffer[length..length + 4].copy_from_slice(&len.to_be_bytes());
+        }
+        let len = (self.buffer.len() - start - 1) as i32;
+        self.buffer[start + 1..start + 5].copy_from_slice(&len.to_be_bytes());
+        self.rows += 1;
+    }
+
+    /// Number of rows
+    pub fn len(&self) -> usize {
+        self.rows
+    }
+
+    /// Whether there are no rows
+    pub fn is_empty(&self) -> bool {
+        self.rows == 0
+    }
+
+    /// Messages of all rows
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buffer
+    }
 }
 
 impl BackendMessage {
@@ -254,28 +336,53 @@ impl BackendMessage {
             BackendMessage::NoticeResponse => vec![NOTICE_RESPONSE],
             BackendMessage::AuthenticationCleartextPassword => vec![AUTHENTICATION, 0, 0, 0, 8, 0, 0, 0, 3],
             BackendMessage::AuthenticationMD5Password => vec![AUTHENTICATION, 0, 0, 0, 12, 0, 0, 0, 5, 1, 1, 1, 1],
+            BackendMessage::AuthenticationSASL(mechanisms) => {
+                let mut buff = Vec::new();
+                buff.extend_from_slice(&10i32.to_be_bytes());
+                for mechanism in mechanisms.iter() {
+                    buff.extend_from_slice(mechanism.as_bytes());
+                    buff.extend_from_slice(&[0]);
+                }
+                buff.extend_from_slice(&[0]);
+                let mut len_buff = Vec::new();
+                len_buff.extend_from_slice(&[AUTHENTICATION]);
+                len_buff.extend_from_slice(&(4 + buff.len() as i32).to_be_bytes());
+                len_buff.extend_from_slice(&buff);
+                len_buff
+            }
+            BackendMessage::AuthenticationSASLContinue(data) => sasl_data(11, data),
+            BackendMessage::AuthenticationSASLFinal(data) => sasl_data(12, data),
             BackendMessage::AuthenticationOk => vec![AUTHENTICATION, 0, 0, 0, 8, 0, 0, 0, 0],
             BackendMessage::ReadyForQuery => vec![READY_FOR_QUERY, 0, 0, 0, 5, EMPTY_QUERY_RESPONSE],
             BackendMessage::DataRow(row) => {
-                let mut row_buff = Vec::new();
+                let len = 6 + row
+                    .iter()
+                    .map(|field| 4 + field.as_ref().map_or(0, String::len))
+                    .sum::<usize>();
+                let mut buff = Vec::with_capacity(1 + len);
+                buff.extend_from_slice(&[DATA_ROW]);
+                buff.extend_from_slice(&(len as i32).to_be_bytes());
+                buff.extend_from_slice(&(row.len() as i16).to_be_bytes());
                 for field in row.iter() {
-                    row_buff.extend_from_slice(&(field.len() as i32).to_be_bytes());
-                    row_buff.extend_from_slice(field.as_str().as_bytes());
+                    match field {
+                        Some(field) => {
+                            buff.extend_from_slice(&(field.len() as i32).to_be_bytes());
+                            buff.extend_from_slice(field.as_bytes());
+                        }
+                        // As a special case, -1 indicates a NULL column value.
+                        None => buff.extend_from_slice(&(-1i32).to_be_bytes()),
+                    }
                 }
-                let mut len_buff = Vec::new();
-                len_buff.extend_from_slice(&[DATA_ROW]);
-                len_buff.extend_from_slice(&(6 + row_buff.len() as i32).to_be_bytes());
-                len_buff.extend_from_slice(&(row.len() as i16).to_be_bytes());
-                len_buff.extend_from_slice(&row_buff);
-                len_buff
+                buff
             }
+            BackendMessage::DataRows(rows) => rows.as_bytes().to_vec(),
             BackendMessage::RowDescription(description) => {
                 let mut buff = Vec::new();
                 for field in description.iter() {
                     buff.extend_from_slice(field.name.as_str().as_bytes());
                     buff.extend_from_slice(&[0]); // end of c string
-                    buff.extend_from_slice(&(0i32).to_be_bytes()); // table id
-                    buff.extend_from_slice(&(0i16).to_be_bytes()); // column id
+                    buff.extend_from_slice(&field.table_id.to_be_bytes());
+                    buff.extend_from_slice(&field.column_id.to_be_bytes());
                     buff.extend_from_slice(&field.type_id.to_be_bytes());
                     buff.extend_from_slice(&field.type_size.to_be_bytes());
                     buff.extend_from_slice(&(-1i32).to_be_bytes()); // type modifier
@@ -297,7 +404,7 @@ impl BackendMessage {
                 command_buff
             }
             BackendMessage::EmptyQueryResponse => vec![EMPTY_QUERY_RESPONSE, 0, 0, 0, 4],
-            BackendMessage::ErrorResponse(severity, code, message) => {
+            BackendMessage::ErrorResponse(severity, code, message, detail) => {
                 let mut error_response_buff = Vec::new();
                 error_response_buff.extend_from_slice(&[ERROR_RESPONSE]);
                 let mut message_buff = Vec::new();
@@ -316,6 +423,11 @@ impl BackendMessage {
                     message_buff.extend_from_slice(message.as_bytes());
                     message_buff.extend_from_slice(&[0]);
                 }
+                if let Some(detail) = detail.as_ref() {
+                    message_buff.extend_from_slice(&[DETAIL]);
+                    message_buff.extend_from_slice(detail.as_bytes());
+                    message_buff.extend_from_slice(&[0]);
+                }
                 error_response_buff.extend_from_slice(&(message_buff.len() as i32 + 4 + 1).to_be_bytes());
                 error_response_buff.extend_from_slice(message_buff.as_ref());
                 error_response_buff.extend_from_slice(&[0]);
@@ -349,10 +461,20 @@ impl BackendMessage {
             BackendMessage::ParseComplete => vec![PARSE_COMPLETE, 0, 0, 0, 4],
             BackendMessage::BindComplete => vec![BIND_COMPLETE, 0, 0, 0, 4],
             BackendMessage::CloseComplete => vec![CLOSE_COMPLETE, 0, 0, 0, 4],
+            BackendMessage::PortalSuspended => vec![PORTAL_SUSPENDED, 0, 0, 0, 4],
         }
     }
 }
 
+fn sasl_data(kind: i32, data: &str) -> Vec<u8> {
+    let mut buff = Vec::new();
+    buff.extend_from_slice(&[AUTHENTICATION]);
+    buff.extend_from_slice(&(8 + data.len() as i32).to_be_bytes());
+    buff.extend_from_slice(&kind.to_be_bytes());
+    buff.extend_from_slice(data.as_bytes());
+    buff
+}
+
 /// Struct description of metadata that describes how client should interpret
 /// outgoing selected data
 #[derive(Clone, Debug, PartialEq)]
@@ -363,17 +485,30 @@ pub struct ColumnMetadata {
     pub type_id: u32,
     /// PostgreSQL data type size
     pub type_size: i16,
+    /// OID of the table the column comes from, `0` for computed columns
+    pub table_id: u32,
+    /// number of the column in its table, `0` for computed columns
+    pub column_id: i16,
 }
 
 impl ColumnMetadata {
-    /// Creates new column metadata
+    /// Creates new column metadata of a computed column
     pub fn new(name: String, type_id: u32, type_size: i16) -> Self {
         Self {
             name,
             type_id,
             type_size,
+            table_id: 0,
+            column_id: 0,
         }
     }
+
+    /// Sets the table and the number of the column in it
+    pub fn with_source(mut self, table_id: u32, column_id: i16) -> Self {
+        self.table_id = table_id;
+        self.column_id = column_id;
+        self
+    }
 }
 
 /// Decodes data within messages.
@@ -422,6 +557,16 @@ impl<'a> Cursor<'a> {
         }
     }
 
+    /// Returns the next `n` bytes, advancing the cursor by `n` bytes.
+    fn read_bytes(&mut self, n: usize) -> Result<&'a [u8]> {
+        if self.buf.len() < n {
+            return Err(Error::InvalidInput(format!("not enough buffer for {} bytes", n)));
+        }
+        let (bytes, rest) = self.buf.split_at(n);
+        self.buf = rest;
+        Ok(bytes)
+    }
+
     /// Reads the next 16-bit format code, advancing the cursor by two bytes.
     fn read_format(&mut self) -> Result<PostgreSqlFormat> {
         match self.read_i16()? {
@@ -469,28 +614,27 @@ fn decode_bind(mut cursor: Cursor) -> Result<FrontendMessage> {
     let portal_name = cursor.read_cstr()?.to_owned();
     let statement_name = cursor.read_cstr()?.to_owned();
 
-    let mut param_formats = vec![];
-    for _ in 0..cursor.read_i16()? {
+    let param_formats_len = cursor.read_i16()?;
+    let mut param_formats = Vec::with_capacity(param_formats_len.max(0) as usize);
+    for _ in 0..param_formats_len {
         param_formats.push(cursor.read_format()?);
     }
 
-    let mut raw_params = vec![];
-    for _ in 0..cursor.read_i16()? {
+    let raw_params_len = cursor.read_i16()?;
+    let mut raw_params = Vec::with_capacity(raw_params_len.max(0) as usize);
+    for _ in 0..raw_params_len {
         let len = cursor.read_i32()?;
         if len == -1 {
             // As a special case, -1 indicates a NULL parameter value.
             raw_params.push(None);
         } else {
-            let mut value = vec![];
-            for _ in 0..len {
-                value.push(cursor.read_byte()?);
-            }
-            raw_params.push(Some(value));
+            raw_params.push(Some(cursor.read_bytes(len.max(0) as usize)?.to_vec()));
         }
     }
 
-    let mut result_formats = vec![];
-    for _ in 0..cursor.read_i16()? {
+    let result_formats_len = cursor.read_i16()?;
+    let mut result_formats = Vec::with_capacity(result_formats_len.max(0) as usize);
+    for _ in 0..result_formats_len {
         result_formats.push(cursor.read_format()?);
     }
 
@@ -546,8 +690,7 @@ fn decode_parse(mut cursor: Cursor) -> Result<FrontendMessage> {
     let mut param_types = vec![];
     for _ in 0..cursor.read_i16()? {
         let oid = cursor.read_u32()?;
-        let sql_type = PostgreSqlType::try_from(oid).unwrap();
-        param_types.push(sql_type);
+        param_types.push(PostgreSqlType::try_from(oid).ok());
     }
 
     Ok(FrontendMessage::Parse {
@@ -608,6 +751,19 @@ mod decoding_frontend_messages {
         );
     }
 
+    #[test]
+    fn bind_with_truncated_parameter() {
+        let buffer = [
+            112, 111, 114, 116, 97, 108, 95, 110, 97, 109, 101, 0, 115, 116, 97, 116, 101, 109, 101, 110, 116, 95, 110,
+            97, 109, 101, 0, 0, 1, 0, 1, 0, 1, 0, 0, 0, 4, 0, 0,
+        ];
+        let message = FrontendMessage::decode(b'B', &buffer);
+        assert_eq!(
+            message,
+            Err(Error::InvalidInput("not enough buffer for 4 bytes".to_owned()))
+        );
+    }
+
     #[test]
     fn close_protal() {
         let buffer = [80, 112, 111, 114, 116, 97, 108, 95, 110, 97, 109, 101, 0];
@@ -688,7 +844,23 @@ mod decoding_frontend_messages {
             Ok(FrontendMessage::Parse {
                 statement_name: "".to_owned(),
                 sql: "select * from schema_name.table_name where si_column = $1;".to_owned(),
-                param_types: vec![PostgreSqlType::Integer]
+                param_types: vec![Some(PostgreSqlType::Integer)]
+            })
+        );
+    }
+
+    #[test]
+    fn parse_with_unspecified_param_type() {
+        let buffer = [
+            0, 115, 101, 108, 101, 99, 116, 32, 36, 49, 44, 32, 36, 50, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 21,
+        ];
+        let message = FrontendMessage::decode(b'P', &buffer);
+        assert_eq!(
+            message,
+            Ok(FrontendMessage::Parse {
+                statement_name: "".to_owned(),
+                sql: "select $1, $2".to_owned(),
+                param_types: vec![None, Some(PostgreSqlType::SmallInt)]
             })
         );
     }
@@ -731,6 +903,55 @@ mod serializing_backend_messages {
         )
     }
 
+    #[test]
+    fn authentication_sasl() {
+        assert_eq!(
+            BackendMessage::AuthenticationSASL(vec!["SCRAM-SHA-256".to_owned()]).as_vec(),
+            vec![
+                AUTHENTICATION,
+                0,
+                0,
+                0,
+                23,
+                0,
+                0,
+                0,
+                10,
+                83,
+                67,
+                82,
+                65,
+                77,
+                45,
+                83,
+                72,
+                65,
+                45,
+                50,
+                53,
+                54,
+                0,
+                0
+            ]
+        )
+    }
+
+    #[test]
+    fn authentication_sasl_continue() {
+        assert_eq!(
+            BackendMessage::AuthenticationSASLContinue("r=1".to_owned()).as_vec(),
+            vec![AUTHENTICATION, 0, 0, 0, 11, 0, 0, 0, 11, 114, 61, 49]
+        )
+    }
+
+    #[test]
+    fn authentication_sasl_final() {
+        assert_eq!(
+            BackendMessage::AuthenticationSASLFinal("v=1".to_owned()).as_vec(),
+            vec![AUTHENTICATION, 0, 0, 0, 11, 0, 0, 0, 12, 118, 61, 49]
+        )
+    }
+
     #[test]
     fn authentication_ok() {
         assert_eq!(
@@ -785,11 +1006,45 @@ mod serializing_backend_messages {
     #[test]
     fn data_row() {
         assert_eq!(
-            BackendMessage::DataRow(vec!["1".to_owned(), "2".to_owned(), "3".to_owned()]).as_vec(),
+            BackendMessage::DataRow(vec![Some("1".to_owned()), Some("2".to_owned()), Some("3".to_owned())]).as_vec(),
             vec![DATA_ROW, 0, 0, 0, 21, 0, 3, 0, 0, 0, 1, 49, 0, 0, 0, 1, 50, 0, 0, 0, 1, 51]
         )
     }
 
+    #[test]
+    fn data_row_with_null() {
+        assert_eq!(
+            BackendMessage::DataRow(vec![Some("1".to_owned()), None, Some("".to_owned())]).as_vec(),
+            vec![DATA_ROW, 0, 0, 0, 19, 0, 3, 0, 0, 0, 1, 49, 255, 255, 255, 255, 0, 0, 0, 0]
+        )
+    }
+
+    #[test]
+    fn encoded_rows() {
+        let fields = [[Some("1"), None, Some("")], [Some("2"), Some("ab"), None]];
+        let mut rows = EncodedRows::with_capacity(64);
+        for row in &fields {
+            rows.push_row(row.len(), |index, buffer| match row[index] {
+                Some(field) => {
+                    buffer.extend_from_slice(field.as_bytes());
+                    true
+                }
+                None => false,
+            });
+        }
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(
+            BackendMessage::DataRows(rows).as_vec(),
+            fields
+                .iter()
+                .flat_map(|row| {
+                    BackendMessage::DataRow(row.iter().map(|field| field.map(ToOwned::to_owned)).collect()).as_vec()
+                })
+                .collect::<Vec<u8>>()
+        );
+    }
+
     #[test]
     fn row_description() {
         assert_eq!(
@@ -827,6 +1082,44 @@ mod serializing_backend_messages {
         );
     }
 
+    #[test]
+    fn row_description_of_table_column() {
+        assert_eq!(
+            BackendMessage::RowDescription(vec![ColumnMetadata::new("c1".to_owned(), 23, 4).with_source(16385, 2)])
+                .as_vec(),
+            vec![
+                ROW_DESCRIPTION,
+                0,
+                0,
+                0,
+                27,
+                0,
+                1,
+                99,
+                49,
+                0,
+                0,
+                0,
+                64,
+                1,
+                0,
+                2,
+                0,
+                0,
+                0,
+                23,
+                0,
+                4,
+                255,
+                255,
+                255,
+                255,
+                0,
+                0
+            ]
+        );
+    }
+
     #[test]
     fn command_complete() {
         assert_eq!(
@@ -846,11 +1139,19 @@ mod serializing_backend_messages {
     #[test]
     fn error_response() {
         assert_eq!(
-            BackendMessage::ErrorResponse(None, None, None).as_vec(),
+            BackendMessage::ErrorResponse(None, None, None, None).as_vec(),
             vec![ERROR_RESPONSE, 0, 0, 0, 5, 0]
         )
     }
 
+    #[test]
+    fn error_response_with_detail() {
+        assert_eq!(
+            BackendMessage::ErrorResponse(None, None, Some("a".to_owned()), Some("b".to_owned())).as_vec(),
+            vec![ERROR_RESPONSE, 0, 0, 0, 11, MESSAGE, b'a', 0, DETAIL, b'b', 0, 0]
+        )
+    }
+
     #[test]
     fn parameter_description() {
         assert_eq!(
@@ -878,4 +1179,12 @@ mod serializing_backend_messages {
     fn close_complete() {
         assert_eq!(BackendMessage::CloseComplete.as_vec(), vec![CLOSE_COMPLETE, 0, 0, 0, 4])
     }
+
+    #[test]
+    fn portal_suspended() {
+        assert_eq!(
+            BackendMessage::PortalSuspended.as_vec(),
+            vec![PORTAL_SUSPENDED, 0, 0, 0, 4]
+        )
+    }
 }
@@ -13,7 +13,7 @@
 // limitations under the License.
 
 use crate::{
-    messages::{BackendMessage, ColumnMetadata},
+    messages::{BackendMessage, ColumnMetadata, EncodedRows},
     sql_types::PostgreSqlType,
 };
 use std::fmt::{self, Display, Formatter};
@@ -21,9 +21,64 @@ use std::fmt::{self, Display, Formatter};
 /// Represents result of SQL query execution
 pub type QueryResult = std::result::Result<QueryEvent, QueryError>;
 /// Represents selected columns from tables
-pub type Description = Vec<(String, PostgreSqlType)>;
-/// Represents selected data from tables
-pub type Projection = (Description, Vec<Vec<String>>);
+pub type Description = Vec<ColumnDescription>;
+/// Represents selected data from tables, `None` fields are SQL NULLs
+pub type Projection = (Description, Vec<Vec<Option<String>>>);
+
+/// Describes a selected column, computed columns don't come from a table
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColumnDescription {
+    /// name of the column or its alias
+    pub name: String,
+    /// type of values of the column
+    pub pg_type: PostgreSqlType,
+    /// `false` if the column can't have NULL values
+    pub nullable: bool,
+    /// OID of the table the column comes from, `0` for computed columns
+    pub table_oid: u32,
+    /// number of the column in its table starting from `1`, `0` for
+    /// computed columns
+    pub attnum: i16,
+}
+
+impl ColumnDescription {
+    /// Describes a computed column that can have NULL values
+    pub fn new(name: &str, pg_type: PostgreSqlType) -> ColumnDescription {
+        ColumnDescription {
+            name: name.to_owned(),
+            pg_type,
+            nullable: true,
+            table_oid: 0,
+            attnum: 0,
+        }
+    }
+
+    /// Sets the table of the column and its number in the table
+    pub fn with_source(mut self, table_oid: u32, attnum: i16) -> ColumnDescription {
+        self.table_oid = table_oid;
+        self.attnum = attnum;
+        self
+    }
+
+    /// Sets whether the column can have NULL values
+    pub fn with_nullable(mut self, nullable: bool) -> ColumnDescription {
+        self.nullable = nullable;
+        self
+    }
+}
+
+impl From<(String, PostgreSqlType)> for ColumnDescription {
+    fn from((name, pg_type): (String, PostgreSqlType)) -> ColumnDescription {
+        ColumnDescription::new(&name, pg_type)
+    }
+}
+
+impl From<ColumnDescription> for ColumnMetadata {
+    fn from(column: ColumnDescription) -> ColumnMetadata {
+        ColumnMetadata::new(column.name, column.pg_type.pg_oid(), column.pg_type.pg_len())
+            .with_source(column.table_oid, column.attnum)
+    }
+}
 
 /// Represents successful events that can happen in server backend
 #[derive(Clone, Debug, PartialEq)]
@@ -40,22 +95,88 @@ pub enum QueryEvent {
     VariableSet,
     /// Transaction is started
     TransactionStarted,
+    /// Transaction is committed
+    TransactionCommitted,
+    /// Transaction or its subtransactions since a savepoint are rolled back
+    TransactionRolledBack,
+    /// Savepoint is established
+    SavepointCreated,
+    /// Savepoint is released
+    SavepointReleased,
     /// Number of records inserted into a table
     RecordsInserted(usize),
     /// Records selected from database
     RecordsSelected(Projection),
+    /// Description of records that are streamed by the following events
+    RowDescription(Description),
+    /// Part of records selected from database
+    DataRows(Vec<Vec<Option<String>>>),
+    /// Part of records selected from database that are already encoded, it
+    /// is sent only to senders that accept encoded rows
+    EncodedDataRows(EncodedRows),
+    /// Number of records streamed to the client by a select query
+    SelectComplete(usize),
+    /// Portal reached the row limit of its execution, the rest of its
+    /// records are streamed by the following executions
+    PortalSuspended,
     /// Number of records updated into a table
     RecordsUpdated(usize),
     /// Number of records deleted into a table
     RecordsDeleted(usize),
+    /// Number of records copied from a file into a table or from a table
+    /// into a file
+    RecordsCopied(usize),
     /// Parameters described needed by a prepared statement
     PreparedStatementDescribed(Vec<PostgreSqlType>, Description),
+    /// Records that will be returned by a portal, statements that do not
+    /// return records have empty description
+    PortalDescribed(Description),
     /// Processing of the query is complete
     QueryComplete,
+    /// Query string contains no statements, only whitespaces or comments
+    EmptyQuery,
     /// Parsing the exteneded query is complete
     ParseComplete,
     /// Binding the exteneded query is complete
     BindComplete,
+    /// Privileges successfully granted
+    PrivilegesGranted,
+    /// Privileges successfully revoked
+    PrivilegesRevoked,
+    /// Role attributes successfully changed
+    RoleAltered,
+    /// Settings of sessions connected to a database successfully changed
+    DatabaseAltered,
+    /// Privileges of objects that will be created are successfully changed
+    DefaultPrivilegesAltered,
+    /// Publication successfully created
+    PublicationCreated,
+    /// Publication successfully dropped
+    PublicationDropped,
+    /// Replication slot successfully created
+    ReplicationSlotCreated,
+    /// Replication slot successfully dropped
+    ReplicationSlotDropped,
+    /// Foreign table successfully created
+    ForeignTableCreated,
+    /// Rows of a table successfully rewritten in the requested order
+    TableClustered,
+    /// Defaults or nullability of columns successfully changed
+    TableAltered,
+    /// Function successfully created or replaced
+    FunctionCreated,
+    /// Function successfully dropped
+    FunctionDropped,
+    /// Event trigger successfully created
+    EventTriggerCreated,
+    /// Event trigger successfully dropped
+    EventTriggerDropped,
+    /// Anonymous code block successfully executed
+    BlockExecuted,
+    /// Aggregate function successfully created or replaced
+    AggregateCreated,
+    /// Lines of a plan shown by `EXPLAIN`
+    PlanExplained(Vec<String>),
 }
 
 impl Into<Vec<BackendMessage>> for QueryEvent {
@@ -67,15 +188,16 @@ impl Into<Vec<BackendMessage>> for QueryEvent {
             QueryEvent::TableDropped => vec![BackendMessage::CommandComplete("DROP TABLE".to_owned())],
             QueryEvent::VariableSet => vec![BackendMessage::CommandComplete("SET".to_owned())],
             QueryEvent::TransactionStarted => vec![BackendMessage::CommandComplete("BEGIN".to_owned())],
+            QueryEvent::TransactionCommitted => vec![BackendMessage::CommandComplete("COMMIT".to_owned())],
+            QueryEvent::TransactionRolledBack => vec![BackendMessage::CommandComplete("ROLLBACK".to_owned())],
+            QueryEvent::SavepointCreated => vec![BackendMessage::CommandComplete("SAVEPOINT".to_owned())],
+            QueryEvent::SavepointReleased => vec![BackendMessage::CommandComplete("RELEASE".to_owned())],
             QueryEvent::RecordsInserted(records) => {
                 vec![BackendMessage::CommandComplete(format!("INSERT 0 {}", records))]
             }
             QueryEvent::RecordsSelected(projection) => {
                 let definition = projection.0;
-                let description: Vec<ColumnMetadata> = definition
-                    .into_iter()
-                    .map(|(name, sql_type)| ColumnMetadata::new(name, sql_type.pg_oid(), sql_type.pg_len()))
-                    .collect();
+                let description: Vec<ColumnMetadata> = definition.into_iter().map(ColumnMetadata::from).collect();
                 let records = projection.1;
                 let len = records.len();
                 let mut messages = vec![BackendMessage::RowDescription(description)];
@@ -85,25 +207,80 @@ impl Into<Vec<BackendMessage>> for QueryEvent {
                 messages.push(BackendMessage::CommandComplete(format!("SELECT {}", len)));
                 messages
             }
+            QueryEvent::RowDescription(description) => vec![BackendMessage::RowDescription(
+                description.into_iter().map(ColumnMetadata::from).collect(),
+            )],
+            QueryEvent::DataRows(records) => records.into_iter().map(BackendMessage::DataRow).collect(),
+            QueryEvent::EncodedDataRows(records) => vec![BackendMessage::DataRows(records)],
+            QueryEvent::SelectComplete(records) => vec![BackendMessage::CommandComplete(format!("SELECT {}", records))],
             QueryEvent::RecordsUpdated(records) => vec![BackendMessage::CommandComplete(format!("UPDATE {}", records))],
             QueryEvent::RecordsDeleted(records) => vec![BackendMessage::CommandComplete(format!("DELETE {}", records))],
+            QueryEvent::RecordsCopied(records) => vec![BackendMessage::CommandComplete(format!("COPY {}", records))],
             QueryEvent::PreparedStatementDescribed(param_types, description) => {
                 let desc_message = if description.is_empty() {
                     BackendMessage::NoData
                 } else {
-                    let columns: Vec<ColumnMetadata> = description
-                        .into_iter()
-                        .map(|(name, sql_type)| ColumnMetadata::new(name, sql_type.pg_oid(), sql_type.pg_len()))
-                        .collect();
+                    let columns: Vec<ColumnMetadata> = description.into_iter().map(ColumnMetadata::from).collect();
                     BackendMessage::RowDescription(columns)
                 };
 
                 let type_ids = param_types.iter().map(PostgreSqlType::pg_oid).collect();
                 vec![BackendMessage::ParameterDescription(type_ids), desc_message]
             }
+            QueryEvent::PortalDescribed(description) => {
+                if description.is_empty() {
+                    vec![BackendMessage::NoData]
+                } else {
+                    vec![BackendMessage::RowDescription(
+                        description.into_iter().map(ColumnMetadata::from).collect(),
+                    )]
+                }
+            }
             QueryEvent::QueryComplete => vec![BackendMessage::ReadyForQuery],
+            QueryEvent::EmptyQuery => vec![BackendMessage::EmptyQueryResponse],
             QueryEvent::ParseComplete => vec![BackendMessage::ParseComplete],
             QueryEvent::BindComplete => vec![BackendMessage::BindComplete],
+            QueryEvent::PortalSuspended => vec![BackendMessage::PortalSuspended],
+            QueryEvent::PrivilegesGranted => vec![BackendMessage::CommandComplete("GRANT".to_owned())],
+            QueryEvent::PrivilegesRevoked => vec![BackendMessage::CommandComplete("REVOKE".to_owned())],
+            QueryEvent::RoleAltered => vec![BackendMessage::CommandComplete("ALTER ROLE".to_owned())],
+            QueryEvent::DatabaseAltered => vec![BackendMessage::CommandComplete("ALTER DATABASE".to_owned())],
+            QueryEvent::DefaultPrivilegesAltered => {
+                vec![BackendMessage::CommandComplete("ALTER DEFAULT PRIVILEGES".to_owned())]
+            }
+            QueryEvent::PublicationCreated => vec![BackendMessage::CommandComplete("CREATE PUBLICATION".to_owned())],
+            QueryEvent::PublicationDropped => vec![BackendMessage::CommandComplete("DROP PUBLICATION".to_owned())],
+            QueryEvent::ReplicationSlotCreated => {
+                vec![BackendMessage::CommandComplete("CREATE REPLICATION SLOT".to_owned())]
+            }
+            QueryEvent::ReplicationSlotDropped => {
+                vec![BackendMessage::CommandComplete("DROP REPLICATION SLOT".to_owned())]
+            }
+            QueryEvent::ForeignTableCreated => {
+                vec![BackendMessage::CommandComplete("CREATE FOREIGN TABLE".to_owned())]
+            }
+            QueryEvent::TableClustered => vec![BackendMessage::CommandComplete("CLUSTER".to_owned())],
+            QueryEvent::TableAltered => vec![BackendMessage::CommandComplete("ALTER TABLE".to_owned())],
+            QueryEvent::FunctionCreated => vec![BackendMessage::CommandComplete("CREATE FUNCTION".to_owned())],
+            QueryEvent::FunctionDropped => vec![BackendMessage::CommandComplete("DROP FUNCTION".to_owned())],
+            QueryEvent::EventTriggerCreated => {
+                vec![BackendMessage::CommandComplete("CREATE EVENT TRIGGER".to_owned())]
+            }
+            QueryEvent::EventTriggerDropped => vec![BackendMessage::CommandComplete("DROP EVENT TRIGGER".to_owned())],
+            QueryEvent::BlockExecuted => vec![BackendMessage::CommandComplete("DO".to_owned())],
+            QueryEvent::AggregateCreated => vec![BackendMessage::CommandComplete("CREATE AGGREGATE".to_owned())],
+            QueryEvent::PlanExplained(lines) => {
+                let mut messages = vec![BackendMessage::RowDescription(vec![ColumnMetadata::new(
+                    "QUERY PLAN".to_owned(),
+                    PostgreSqlType::VarChar.pg_oid(),
+                    PostgreSqlType::VarChar.pg_len(),
+                )])];
+                for line in lines {
+                    messages.push(BackendMessage::DataRow(vec![Some(line)]));
+                }
+                messages.push(BackendMessage::CommandComplete("EXPLAIN".to_owned()));
+                messages
+            }
         }
     }
 }
@@ -146,9 +323,18 @@ pub(crate) enum QueryErrorKind {
     SchemaHasDependentObjects(String),
     TableDoesNotExist(String),
     ColumnDoesNotExist(Vec<String>),
+    AmbiguousColumn(String),
+    InvalidColumnReference(String),
+    MissingFromClauseEntry(String),
+    InvalidTablesampleArgument(String),
+    InvalidRowCountInLimitClause,
+    InvalidRowCountInResultOffsetClause,
+    TablesampleMethodDoesNotExist(String),
     InvalidParameterValue(String),
     PreparedStatementDoesNotExist(String),
     PortalDoesNotExist(String),
+    PortalCannotBeRun(String),
+    CachedPlanChangedResultType,
     ProtocolViolation(String),
     FeatureNotSupported(String),
     TooManyInsertExpressions,
@@ -175,6 +361,86 @@ pub(crate) enum QueryErrorKind {
         right_type: String,
     },
     SyntaxError(String),
+    SubqueryTooManyColumns,
+    SetOperationColumnsMismatch(String),
+    PermissionDenied {
+        object_type: String,
+        object_name: String,
+    },
+    MustBeOwner {
+        object_type: String,
+        object_name: String,
+    },
+    ReadOnlySqlTransaction(String),
+    MustBeSuperuser(String),
+    BadCopyFileFormat(String),
+    IoError(String),
+    PublicationAlreadyExists(String),
+    PublicationDoesNotExist(String),
+    ReplicationSlotAlreadyExists(String),
+    ReplicationSlotDoesNotExist(String),
+    ServerDoesNotExist(String),
+    IndexDoesNotExist(String),
+    FunctionAlreadyExists(String),
+    FunctionDoesNotExist(String),
+    DivisionByZero,
+    SubstringError,
+    QueryCanceled(String),
+    AdminShutdown,
+    NoActiveSqlTransaction(String),
+    ActiveSqlTransaction(String),
+    InFailedSqlTransaction,
+    SavepointDoesNotExist(String),
+    DeadlockDetected,
+    ValueOutOfRange(PostgreSqlType),
+    FunctionWithoutReturn,
+    DatatypeMismatch(String),
+    EventTriggerAlreadyExists(String),
+    EventTriggerDoesNotExist(String),
+    NotEventTriggerFunction(String),
+    TriggerFunctionCall,
+    RaiseException(String),
+    InvalidFunctionDefinition(String),
+    ExternalRoutineException(String),
+    FdwError(String),
+    GroupingError(String),
+    CannotChangeForeignTable {
+        operation: String,
+        table_name: String,
+    },
+    InvalidPassword(String),
+    InvalidCertificate(String),
+    ConnectionRejected {
+        host: String,
+        user: String,
+        database: String,
+    },
+    NoHostBasedAccessEntry {
+        host: String,
+        user: String,
+        database: String,
+        ssl: bool,
+    },
+    TooManyConnections,
+    TooManyConnectionsForRole(String),
+    OutOfMemory {
+        operator: String,
+        work_mem: usize,
+    },
+    RecursionLimitExceeded {
+        name: String,
+        limit: usize,
+    },
+    StackDepthLimitExceeded(usize),
+    NotNullViolation(String),
+    ColumnContainsNulls {
+        column: String,
+        table_name: String,
+    },
+    LargeObjectAlreadyExists(u32),
+    LargeObjectDoesNotExist(u32),
+    InvalidLargeObjectDescriptor(i32),
+    LargeObjectNotOpenedForWriting(i32),
 }
 
 impl QueryErrorKind {
@@ -186,9 +452,18 @@ impl QueryErrorKind {
             Self::SchemaHasDependentObjects(_) => "2BP01",
             Self::TableDoesNotExist(_) => "42P01",
             Self::ColumnDoesNotExist(_) => "42703",
+            Self::AmbiguousColumn(_) => "42702",
+            Self::InvalidColumnReference(_) => "42P10",
+            Self::MissingFromClauseEntry(_) => "42P01",
+            Self::InvalidTablesampleArgument(_) => "2202H",
+            Self::InvalidRowCountInLimitClause => "2201W",
+            Self::InvalidRowCountInResultOffsetClause => "2201X",
+            Self::TablesampleMethodDoesNotExist(_) => "42704",
             Self::InvalidParameterValue(_) => "22023",
             Self::PreparedStatementDoesNotExist(_) => "26000",
             Self::PortalDoesNotExist(_) => "26000",
+            Self::PortalCannotBeRun(_) => "55000",
+            Self::CachedPlanChangedResultType => "0A000",
             Self::ProtocolViolation(_) => "08P01",
             Self::FeatureNotSupported(_) => "0A000",
             Self::TooManyInsertExpressions => "42601",
@@ -197,6 +472,59 @@ impl QueryErrorKind {
             Self::StringTypeLengthMismatch { .. } => "22026",
             Self::UndefinedFunction { .. } => "42883",
             Self::SyntaxError(_) => "42601",
+            Self::SubqueryTooManyColumns => "42601",
+            Self::SetOperationColumnsMismatch(_) => "42601",
+            Self::PermissionDenied { .. } => "42501",
+            Self::MustBeOwner { .. } => "42501",
+            Self::ReadOnlySqlTransaction(_) => "25006",
+            Self::MustBeSuperuser(_) => "42501",
+            Self::BadCopyFileFormat(_) => "22P04",
+            Self::IoError(_) => "58030",
+            Self::PublicationAlreadyExists(_) => "42710",
+            Self::PublicationDoesNotExist(_) => "42704",
+            Self::ReplicationSlotAlreadyExists(_) => "42710",
+            Self::ReplicationSlotDoesNotExist(_) => "42704",
+            Self::ServerDoesNotExist(_) => "42704",
+            Self::IndexDoesNotExist(_) => "42704",
+            Self::FunctionAlreadyExists(_) => "42723",
+            Self::FunctionDoesNotExist(_) => "42883",
+            Self::DivisionByZero => "22012",
+            Self::SubstringError => "22011",
+            Self::QueryCanceled(_) => "57014",
+            Self::AdminShutdown => "57P01",
+            Self::NoActiveSqlTransaction(_) => "25P01",
+            Self::ActiveSqlTransaction(_) => "25001",
+            Self::InFailedSqlTransaction => "25P02",
+            Self::SavepointDoesNotExist(_) => "3B001",
+            Self::DeadlockDetected => "40P01",
+            Self::ValueOutOfRange(_) => "22003",
+            Self::FunctionWithoutReturn => "2F005",
+            Self::DatatypeMismatch(_) => "42804",
+            Self::EventTriggerAlreadyExists(_) => "42710",
+            Self::EventTriggerDoesNotExist(_) => "42704",
+            Self::NotEventTriggerFunction(_) => "42P17",
+            Self::TriggerFunctionCall => "0A000",
+            Self::RaiseException(_) => "P0001",
+            Self::InvalidFunctionDefinition(_) => "42P13",
+            Self::ExternalRoutineException(_) => "38000",
+            Self::FdwError(_) => "HV000",
+            Self::GroupingError(_) => "42803",
+            Self::CannotChangeForeignTable { .. } => "42809",
+            Self::InvalidPassword(_) => "28P01",
+            Self::InvalidCertificate(_) => "28000",
+            Self::ConnectionRejected { .. } => "28000",
+            Self::NoHostBasedAccessEntry { .. } => "28000",
+            Self::TooManyConnections => "53300",
+            Self::TooManyConnectionsForRole(_) => "53300",
+            Self::OutOfMemory { .. } => "53200",
+            Self::RecursionLimitExceeded { .. } => "54001",
+            Self::StackDepthLimitExceeded(_) => "54001",
+            Self::NotNullViolation(_) => "23502",
+            Self::ColumnContainsNulls { .. } => "23502",
+            Self::LargeObjectAlreadyExists(_) => "42710",
+            Self::LargeObjectDoesNotExist(_) => "42704",
+            Self::InvalidLargeObjectDescriptor(_) => "42704",
+            Self::LargeObjectNotOpenedForWriting(_) => "55000",
         }
     }
 }
@@ -218,11 +546,22 @@ impl Display for QueryErrorKind {
                     write!(f, "column {} does not exist", columns[0])
                 }
             }
+            Self::AmbiguousColumn(column) => write!(f, "column reference \"{}\" is ambiguous", column),
+            Self::InvalidColumnReference(message) => write!(f, "{}", message),
+            Self::MissingFromClauseEntry(table) => write!(f, "missing FROM-clause entry for table \"{}\"", table),
+            Self::InvalidTablesampleArgument(message) => write!(f, "{}", message),
+            Self::InvalidRowCountInLimitClause => write!(f, "LIMIT must not be negative"),
+            Self::InvalidRowCountInResultOffsetClause => write!(f, "OFFSET must not be negative"),
+            Self::TablesampleMethodDoesNotExist(method) => {
+                write!(f, "tablesample method \"{}\" does not exist", method)
+            }
             Self::InvalidParameterValue(message) => write!(f, "{}", message),
             Self::PreparedStatementDoesNotExist(statement_name) => {
                 write!(f, "prepared statement {} does not exist", statement_name)
             }
             Self::PortalDoesNotExist(portal_name) => write!(f, "portal {} does not exist", portal_name),
+            Self::PortalCannotBeRun(portal_name) => write!(f, "portal {} cannot be run", portal_name),
+            Self::CachedPlanChangedResultType => write!(f, "cached plan must not change result type"),
             Self::ProtocolViolation(message) => write!(f, "{}", message),
             Self::FeatureNotSupported(raw_sql_query) => {
                 write!(f, "Currently, Query '{}' can't be executed", raw_sql_query)
@@ -267,6 +606,116 @@ impl Display for QueryErrorKind {
                 left_type, operator, right_type
             ),
             Self::SyntaxError(expression) => write!(f, "syntax error in {}", expression),
+            Self::SubqueryTooManyColumns => write!(f, "subquery has too many columns"),
+            Self::SetOperationColumnsMismatch(operator) => {
+                write!(f, "each {} query must have the same number of columns", operator)
+            }
+            Self::PermissionDenied {
+                object_type,
+                object_name,
+            } => write!(f, "permission denied for {} {}", object_type, object_name),
+            Self::MustBeOwner {
+                object_type,
+                object_name,
+            } => write!(f, "must be owner of {} {}", object_type, object_name),
+            Self::ReadOnlySqlTransaction(command) => {
+                write!(f, "cannot execute {} in a read-only transaction", command)
+            }
+            Self::MustBeSuperuser(action) => write!(f, "must be superuser to {}", action),
+            Self::BadCopyFileFormat(message) => write!(f, "{}", message),
+            Self::IoError(message) => write!(f, "{}", message),
+            Self::PublicationAlreadyExists(name) => write!(f, "publication \"{}\" already exists", name),
+            Self::PublicationDoesNotExist(name) => write!(f, "publication \"{}\" does not exist", name),
+            Self::ReplicationSlotAlreadyExists(name) => write!(f, "replication slot \"{}\" already exists", name),
+            Self::ReplicationSlotDoesNotExist(name) => write!(f, "replication slot \"{}\" does not exist", name),
+            Self::ServerDoesNotExist(name) => write!(f, "server \"{}\" does not exist", name),
+            Self::IndexDoesNotExist(name) => write!(f, "index \"{}\" does not exist", name),
+            Self::FunctionAlreadyExists(name) => write!(f, "function \"{}\" already exists", name),
+            Self::FunctionDoesNotExist(signature) => write!(f, "function {} does not exist", signature),
+            Self::DivisionByZero => write!(f, "division by zero"),
+            Self::SubstringError => write!(f, "negative substring length not allowed"),
+            Self::QueryCanceled(reason) => write!(f, "canceling statement due to {}", reason),
+            Self::AdminShutdown => write!(f, "terminating connection due to administrator command"),
+            Self::NoActiveSqlTransaction(command) => write!(f, "{} can only be used in transaction blocks", command),
+            Self::ActiveSqlTransaction(command) => write!(f, "{} cannot run inside a transaction block", command),
+            Self::InFailedSqlTransaction => write!(
+                f,
+                "current transaction is aborted, commands ignored until end of transaction block"
+            ),
+            Self::SavepointDoesNotExist(name) => write!(f, "savepoint \"{}\" does not exist", name),
+            Self::DeadlockDetected => write!(f, "deadlock detected"),
+            Self::ValueOutOfRange(pg_type) => write!(f, "{} out of range", pg_type),
+            Self::FunctionWithoutReturn => write!(f, "control reached end of function without RETURN"),
+            Self::DatatypeMismatch(message) => write!(f, "{}", message),
+            Self::EventTriggerAlreadyExists(name) => write!(f, "event trigger \"{}\" already exists", name),
+            Self::EventTriggerDoesNotExist(name) => write!(f, "event trigger \"{}\" does not exist", name),
+            Self::NotEventTriggerFunction(name) => write!(f, "function {} must return type event_trigger", name),
+            Self::TriggerFunctionCall => write!(f, "trigger functions can only be called as triggers"),
+            Self::RaiseException(message) => write!(f, "{}", message),
+            Self::InvalidFunctionDefinition(message) => write!(f, "{}", message),
+            Self::ExternalRoutineException(message) => write!(f, "{}", message),
+            Self::FdwError(message) => write!(f, "{}", message),
+            Self::GroupingError(column) => write!(
+                f,
+                "column \"{}\" must appear in the GROUP BY clause or be used in an aggregate function",
+                column
+            ),
+            Self::CannotChangeForeignTable { operation, table_name } => {
+                write!(f, "cannot {} foreign table \"{}\"", operation, table_name)
+            }
+            Self::InvalidPassword(user) => write!(f, "password authentication failed for user \"{}\"", user),
+            Self::InvalidCertificate(user) => write!(f, "certificate authentication failed for user \"{}\"", user),
+            Self::ConnectionRejected { host, user, database } => write!(
+                f,
+                "pg_hba.conf rejects connection for host \"{}\", user \"{}\", database \"{}\"",
+                host, user, database
+            ),
+            Self::NoHostBasedAccessEntry {
+                host,
+                user,
+                database,
+                ssl,
+            } => write!(
+                f,
+                "no pg_hba.conf entry for host \"{}\", user \"{}\", database \"{}\", SSL {}",
+                host,
+                user,
+                database,
+                if *ssl { "on" } else { "off" }
+            ),
+            Self::TooManyConnections => write!(f, "sorry, too many clients already"),
+            Self::TooManyConnectionsForRole(role) => write!(f, "too many connections for role \"{}\"", role),
+            Self::OutOfMemory { operator, work_mem } => {
+                write!(f, "out of memory: {} exceeds work_mem of {}kB", operator, work_mem)
+            }
+            Self::RecursionLimitExceeded { name, limit } => write!(
+                f,
+                "recursive query \"{}\" exceeded max_recursive_iterations of {}",
+                name, limit
+            ),
+            Self::StackDepthLimitExceeded(depth) => {
+                write!(
+                    f,
+                    "stack depth limit exceeded, functions are nested {} levels deep",
+                    depth
+                )
+            }
+            Self::NotNullViolation(column) => {
+                write!(f, "null value in column \"{}\" violates not-null constraint", column)
+            }
+            Self::ColumnContainsNulls { column, table_name } => {
+                write!(
+                    f,
+                    "column \"{}\" of relation \"{}\" contains null values",
+                    column, table_name
+                )
+            }
+            Self::LargeObjectAlreadyExists(oid) => write!(f, "large object {} already exists", oid),
+            Self::LargeObjectDoesNotExist(oid) => write!(f, "large object {} does not exist", oid),
+            Self::InvalidLargeObjectDescriptor(fd) => write!(f, "invalid large-object descriptor: {}", fd),
+            Self::LargeObjectNotOpenedForWriting(fd) => {
+                write!(f, "large object descriptor {} was not opened for writing", fd)
+            }
         }
     }
 }
@@ -276,6 +725,7 @@ impl Display for QueryErrorKind {
 pub struct QueryError {
     severity: Severity,
     kind: QueryErrorKind,
+    detail: Option<String>,
 }
 
 impl QueryError {
@@ -291,11 +741,28 @@ impl QueryError {
     fn message(&self) -> Option<String> {
         Some(format!("{}", self.kind))
     }
+
+    /// attaches a secondary message carrying more detail about the problem
+    pub fn with_detail(mut self, detail: String) -> QueryError {
+        self.detail = Some(detail);
+        self
+    }
+
+    /// SQLSTATE code of the error, e.g. `42P01`
+    pub fn sql_state(&self) -> &'static str {
+        self.kind.code()
+    }
+}
+
+impl Display for QueryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)
+    }
 }
 
 impl Into<BackendMessage> for QueryError {
     fn into(self) -> BackendMessage {
-        BackendMessage::ErrorResponse(self.severity(), self.code(), self.message())
+        BackendMessage::ErrorResponse(self.severity(), self.code(), self.message(), self.detail)
     }
 }
 
@@ -305,6 +772,7 @@ impl QueryError {
         QueryError {
             severity: Severity::Error,
             kind: QueryErrorKind::SchemaAlreadyExists(schema_name),
+            detail: None,
         }
     }
 
@@ -313,6 +781,7 @@ impl QueryError {
         QueryError {
             severity: Severity::Error,
             kind: QueryErrorKind::SchemaDoesNotExist(schema_name),
+            detail: None,
         }
     }
 
@@ -321,6 +790,7 @@ impl QueryError {
         QueryError {
             severity: Severity::Error,
             kind: QueryErrorKind::SchemaHasDependentObjects(schema_name),
+            detail: None,
         }
     }
 
@@ -329,6 +799,7 @@ impl QueryError {
         QueryError {
             severity: Severity::Error,
             kind: QueryErrorKind::TableAlreadyExists(table_name),
+            detail: None,
         }
     }
 
@@ -337,6 +808,7 @@ impl QueryError {
         QueryError {
             severity: Severity::Error,
             kind: QueryErrorKind::TableDoesNotExist(table_name),
+            detail: None,
         }
     }
 
@@ -345,6 +817,71 @@ impl QueryError {
         QueryError {
             severity: Severity::Error,
             kind: QueryErrorKind::ColumnDoesNotExist(non_existing_columns),
+            detail: None,
+        }
+    }
+
+    /// ambiguous column reference error constructor
+    pub fn ambiguous_column(column: String) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::AmbiguousColumn(column),
+            detail: None,
+        }
+    }
+
+    /// reference to a column of a query by its position is not valid, e.g.
+    /// `ORDER BY 3` of a query with two columns
+    pub fn invalid_column_reference(message: String) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::InvalidColumnReference(message),
+            detail: None,
+        }
+    }
+
+    /// qualifier of a column or of a wildcard does not name a relation of the FROM clause
+    pub fn missing_from_clause_entry(table: String) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::MissingFromClauseEntry(table),
+            detail: None,
+        }
+    }
+
+    /// sample percentage or seed of a TABLESAMPLE clause is not valid
+    pub fn invalid_tablesample_argument(message: String) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::InvalidTablesampleArgument(message),
+            detail: None,
+        }
+    }
+
+    /// row count of a LIMIT or FETCH FIRST clause is negative
+    pub fn invalid_row_count_in_limit_clause() -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::InvalidRowCountInLimitClause,
+            detail: None,
+        }
+    }
+
+    /// row count of an OFFSET clause is negative
+    pub fn invalid_row_count_in_result_offset_clause() -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::InvalidRowCountInResultOffsetClause,
+            detail: None,
+        }
+    }
+
+    /// TABLESAMPLE clause uses an unknown sampling method
+    pub fn tablesample_method_does_not_exist(method: String) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::TablesampleMethodDoesNotExist(method),
+            detail: None,
         }
     }
 
@@ -353,6 +890,7 @@ impl QueryError {
         QueryError {
             severity: Severity::Error,
             kind: QueryErrorKind::InvalidParameterValue(message),
+            detail: None,
         }
     }
 
@@ -361,6 +899,7 @@ impl QueryError {
         QueryError {
             severity: Severity::Error,
             kind: QueryErrorKind::PreparedStatementDoesNotExist(statement_name),
+            detail: None,
         }
     }
 
@@ -369,6 +908,26 @@ impl QueryError {
         QueryError {
             severity: Severity::Error,
             kind: QueryErrorKind::PortalDoesNotExist(portal_name),
+            detail: None,
+        }
+    }
+
+    /// portal that has already run to completion can't be executed again
+    pub fn portal_cannot_be_run(portal_name: String) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::PortalCannotBeRun(portal_name),
+            detail: None,
+        }
+    }
+
+    /// prepared statement returns rows of another type after the catalog
+    /// has been changed
+    pub fn cached_plan_changed_result_type() -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::CachedPlanChangedResultType,
+            detail: None,
         }
     }
 
@@ -377,6 +936,7 @@ impl QueryError {
         QueryError {
             severity: Severity::Error,
             kind: QueryErrorKind::ProtocolViolation(message),
+            detail: None,
         }
     }
 
@@ -385,6 +945,7 @@ impl QueryError {
         QueryError {
             severity: Severity::Error,
             kind: QueryErrorKind::FeatureNotSupported(feature_description),
+            detail: None,
         }
     }
 
@@ -393,6 +954,7 @@ impl QueryError {
         QueryError {
             severity: Severity::Error,
             kind: QueryErrorKind::TooManyInsertExpressions,
+            detail: None,
         }
     }
 
@@ -401,6 +963,26 @@ impl QueryError {
         QueryError {
             severity: Severity::Error,
             kind: QueryErrorKind::SyntaxError(expression),
+            detail: None,
+        }
+    }
+
+    /// subquery of `IN` selects more than one column
+    pub fn subquery_too_many_columns() -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::SubqueryTooManyColumns,
+            detail: None,
+        }
+    }
+
+    /// queries combined by `UNION`, `INTERSECT` or `EXCEPT` select different
+    /// number of columns
+    pub fn set_operation_columns_mismatch(operator: &str) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::SetOperationColumnsMismatch(operator.to_owned()),
+            detail: None,
         }
     }
 
@@ -413,419 +995,2073 @@ impl QueryError {
                 left_type,
                 right_type,
             },
+            detail: None,
         }
     }
 
-    /// numeric out of range constructor
-    pub fn out_of_range(pg_type: PostgreSqlType, column_name: String, row_index: usize) -> QueryError {
+    /// role does not have required privilege on an object
+    pub fn permission_denied(object_type: &str, object_name: String) -> QueryError {
         QueryError {
             severity: Severity::Error,
-            kind: QueryErrorKind::NumericTypeOutOfRange {
-                pg_type,
-                column_name,
-                row_index,
+            kind: QueryErrorKind::PermissionDenied {
+                object_type: object_type.to_owned(),
+                object_name,
             },
+            detail: None,
         }
     }
 
-    /// type mismatch constructor
-    pub fn type_mismatch(value: &str, pg_type: PostgreSqlType, column_name: String, row_index: usize) -> QueryError {
+    /// operation on an object is allowed only to its owner
+    pub fn must_be_owner(object_type: &str, object_name: String) -> QueryError {
         QueryError {
             severity: Severity::Error,
-            kind: QueryErrorKind::DataTypeMismatch {
-                pg_type,
-                value: value.to_owned(),
-                column_name,
-                row_index,
+            kind: QueryErrorKind::MustBeOwner {
+                object_type: object_type.to_owned(),
+                object_name,
             },
+            detail: None,
         }
     }
 
-    /// length of string types do not match constructor
-    pub fn string_length_mismatch(
-        pg_type: PostgreSqlType,
-        len: u64,
-        column_name: String,
-        row_index: usize,
-    ) -> QueryError {
+    /// read-only role tried to execute a command that modifies data or schema
+    pub fn read_only_sql_transaction(command: &str) -> QueryError {
         QueryError {
             severity: Severity::Error,
-            kind: QueryErrorKind::StringTypeLengthMismatch {
-                pg_type,
-                len,
-                column_name,
-                row_index,
-            },
+            kind: QueryErrorKind::ReadOnlySqlTransaction(command.to_owned()),
+            detail: None,
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// only superuser is allowed to execute the action
+    pub fn must_be_superuser(action: &str) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::MustBeSuperuser(action.to_owned()),
+            detail: None,
+        }
+    }
 
-    #[cfg(test)]
-    mod query_event {
-        use super::*;
+    /// data of a file that is copied into a table is malformed
+    pub fn bad_copy_file_format(message: String) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::BadCopyFileFormat(message),
+            detail: None,
+        }
+    }
 
-        #[test]
-        fn create_schema() {
-            let messages: Vec<BackendMessage> = QueryEvent::SchemaCreated.into();
-            assert_eq!(
-                messages,
-                vec![BackendMessage::CommandComplete("CREATE SCHEMA".to_owned())]
-            )
+    /// file on the server can't be read or written
+    pub fn io_error(message: String) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::IoError(message),
+            detail: None,
         }
+    }
 
-        #[test]
-        fn drop_schema() {
-            let messages: Vec<BackendMessage> = QueryEvent::SchemaDropped.into();
-            assert_eq!(
-                messages,
-                vec![BackendMessage::CommandComplete("DROP SCHEMA".to_owned())]
-            )
+    /// publication with the same name is already created
+    pub fn publication_already_exists(name: String) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::PublicationAlreadyExists(name),
+            detail: None,
         }
+    }
 
-        #[test]
-        fn create_table() {
-            let messages: Vec<BackendMessage> = QueryEvent::TableCreated.into();
-            assert_eq!(
-                messages,
-                vec![BackendMessage::CommandComplete("CREATE TABLE".to_owned())]
-            );
+    /// publication with the name is not created
+    pub fn publication_does_not_exist(name: String) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::PublicationDoesNotExist(name),
+            detail: None,
         }
+    }
 
-        #[test]
-        fn drop_table() {
-            let messages: Vec<BackendMessage> = QueryEvent::TableDropped.into();
-            assert_eq!(messages, vec![BackendMessage::CommandComplete("DROP TABLE".to_owned())]);
+    /// replication slot with the same name is already created
+    pub fn replication_slot_already_exists(name: String) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::ReplicationSlotAlreadyExists(name),
+            detail: None,
         }
+    }
 
-        #[test]
-        fn insert_record() {
-            let records_number = 3;
-            let messages: Vec<BackendMessage> = QueryEvent::RecordsInserted(records_number).into();
-            assert_eq!(
-                messages,
-                vec![BackendMessage::CommandComplete(format!("INSERT 0 {}", records_number))]
-            )
+    /// replication slot with the name is not created
+    pub fn replication_slot_does_not_exist(name: String) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::ReplicationSlotDoesNotExist(name),
+            detail: None,
         }
+    }
 
-        #[test]
-        fn select_records() {
-            let projection = (
-                vec![
-                    ("column_name_1".to_owned(), PostgreSqlType::SmallInt),
-                    ("column_name_2".to_owned(), PostgreSqlType::SmallInt),
-                ],
-                vec![
-                    vec!["1".to_owned(), "2".to_owned()],
-                    vec!["3".to_owned(), "4".to_owned()],
-                ],
-            );
-            let messages: Vec<BackendMessage> = QueryEvent::RecordsSelected(projection).into();
-            assert_eq!(
-                messages,
-                vec![
-                    BackendMessage::RowDescription(vec![
-                        ColumnMetadata::new("column_name_1".to_owned(), 21, 2),
-                        ColumnMetadata::new("column_name_2".to_owned(), 21, 2)
-                    ]),
-                    BackendMessage::DataRow(vec!["1".to_owned(), "2".to_owned()]),
-                    BackendMessage::DataRow(vec!["3".to_owned(), "4".to_owned()]),
-                    BackendMessage::CommandComplete("SELECT 2".to_owned())
-                ]
-            );
+    /// foreign data wrapper with the name is not registered
+    pub fn server_does_not_exist(name: String) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::ServerDoesNotExist(name),
+            detail: None,
         }
+    }
 
-        #[test]
-        fn update_records() {
-            let records_number = 3;
-            let messages: Vec<BackendMessage> = QueryEvent::RecordsUpdated(records_number).into();
-            assert_eq!(
-                messages,
-                vec![BackendMessage::CommandComplete(format!("UPDATE {}", records_number))]
-            );
+    /// index with the name does not exist
+    pub fn index_does_not_exist(name: String) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::IndexDoesNotExist(name),
+            detail: None,
         }
+    }
 
-        #[test]
-        fn delete_records() {
-            let records_number = 3;
-            let messages: Vec<BackendMessage> = QueryEvent::RecordsDeleted(records_number).into();
-            assert_eq!(
-                messages,
-                vec![BackendMessage::CommandComplete(format!("DELETE {}", records_number))]
-            )
+    /// function with the same name is already created
+    pub fn function_already_exists(name: String) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::FunctionAlreadyExists(name),
+            detail: None,
         }
+    }
 
-        #[test]
+    /// function with the name and argument types, e.g. `f(integer)`, is not
+    /// created
+    pub fn function_does_not_exist(signature: String) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::FunctionDoesNotExist(signature),
+            detail: None,
+        }
+    }
+
+    /// division or modulo by zero
+    pub fn division_by_zero() -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::DivisionByZero,
+            detail: None,
+        }
+    }
+
+    /// substring of a negative length
+    pub fn substring_error() -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::SubstringError,
+            detail: None,
+        }
+    }
+
+    /// statement was interrupted before it completed, e.g. by
+    /// `user request` or `statement timeout`
+    pub fn query_canceled(reason: &str) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::QueryCanceled(reason.to_owned()),
+            detail: None,
+        }
+    }
+
+    /// statement was interrupted because the server shuts down
+    pub fn admin_shutdown() -> QueryError {
+        QueryError {
+            severity: Severity::Fatal,
+            kind: QueryErrorKind::AdminShutdown,
+            detail: None,
+        }
+    }
+
+    /// `command` was executed outside of a transaction block
+    pub fn no_active_sql_transaction(command: &str) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::NoActiveSqlTransaction(command.to_owned()),
+            detail: None,
+        }
+    }
+
+    /// `command` can't be rolled back, so it can't be executed in a
+    /// transaction block
+    pub fn active_sql_transaction(command: &str) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::ActiveSqlTransaction(command.to_owned()),
+            detail: None,
+        }
+    }
+
+    /// statement was executed in a transaction block after one of its
+    /// statements failed
+    pub fn in_failed_sql_transaction() -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::InFailedSqlTransaction,
+            detail: None,
+        }
+    }
+
+    /// transaction block does not have a savepoint with the name
+    pub fn savepoint_does_not_exist(name: String) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::SavepointDoesNotExist(name),
+            detail: None,
+        }
+    }
+
+    /// statement waited for a lock held by a session that waits for a lock
+    /// of the statement
+    pub fn deadlock_detected() -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::DeadlockDetected,
+            detail: None,
+        }
+    }
+
+    /// computed value does not fit into the type
+    pub fn value_out_of_range(pg_type: PostgreSqlType) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::ValueOutOfRange(pg_type),
+            detail: None,
+        }
+    }
+
+    /// function finished without returning a value
+    pub fn function_without_return() -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::FunctionWithoutReturn,
+            detail: None,
+        }
+    }
+
+    /// value of an expression has a type that can't be used in its place
+    pub fn datatype_mismatch(message: String) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::DatatypeMismatch(message),
+            detail: None,
+        }
+    }
+
+    /// event trigger with the same name is already created
+    pub fn event_trigger_already_exists(name: String) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::EventTriggerAlreadyExists(name),
+            detail: None,
+        }
+    }
+
+    /// event trigger with the name is not created
+    pub fn event_trigger_does_not_exist(name: String) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::EventTriggerDoesNotExist(name),
+            detail: None,
+        }
+    }
+
+    /// function of an event trigger has to return `event_trigger`
+    pub fn not_event_trigger_function(name: String) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::NotEventTriggerFunction(name),
+            detail: None,
+        }
+    }
+
+    /// function that returns `event_trigger` is called directly
+    pub fn trigger_function_call() -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::TriggerFunctionCall,
+            detail: None,
+        }
+    }
+
+    /// `RAISE EXCEPTION` of a function
+    pub fn raise_exception(message: String) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::RaiseException(message),
+            detail: None,
+        }
+    }
+
+    /// function can't be created from its definition, e.g. a WebAssembly
+    /// module does not export it
+    pub fn invalid_function_definition(message: String) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::InvalidFunctionDefinition(message),
+            detail: None,
+        }
+    }
+
+    /// function that is executed outside of the engine failed, e.g. a
+    /// WebAssembly function trapped
+    pub fn external_routine_exception(message: String) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::ExternalRoutineException(message),
+            detail: None,
+        }
+    }
+
+    /// foreign data wrapper failed to supply rows of a foreign table
+    pub fn fdw_error(message: String) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::FdwError(message),
+            detail: None,
+        }
+    }
+
+    /// column is selected without being grouped or aggregated
+    pub fn grouping_error(column: String) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::GroupingError(column),
+            detail: None,
+        }
+    }
+
+    /// rows of foreign tables are supplied by foreign data wrappers and
+    /// can't be changed, `operation` is e.g. `insert into`
+    pub fn cannot_change_foreign_table(operation: &str, table_name: String) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::CannotChangeForeignTable {
+                operation: operation.to_owned(),
+                table_name,
+            },
+            detail: None,
+        }
+    }
+
+    /// server already has maximum number of client connections
+    pub fn too_many_connections() -> QueryError {
+        QueryError {
+            severity: Severity::Fatal,
+            kind: QueryErrorKind::TooManyConnections,
+            detail: None,
+        }
+    }
+
+    /// role already has maximum number of connections it is allowed to open
+    pub fn too_many_connections_for_role(role: String) -> QueryError {
+        QueryError {
+            severity: Severity::Fatal,
+            kind: QueryErrorKind::TooManyConnectionsForRole(role),
+            detail: None,
+        }
+    }
+
+    /// `operator` needs more memory for its intermediate results than
+    /// `work_mem` kilobytes it is allowed to use
+    pub fn out_of_memory(operator: &str, work_mem: usize) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::OutOfMemory {
+                operator: operator.to_owned(),
+                work_mem,
+            },
+            detail: None,
+        }
+    }
+
+    /// recursive common table expression `name` did not reach a fixpoint in
+    /// `limit` iterations
+    pub fn recursion_limit_exceeded(name: &str, limit: usize) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::RecursionLimitExceeded {
+                name: name.to_owned(),
+                limit,
+            },
+            detail: None,
+        }
+    }
+
+    /// function calls are nested `depth` levels deep, e.g. by unbounded
+    /// recursion
+    pub fn stack_depth_limit_exceeded(depth: usize) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::StackDepthLimitExceeded(depth),
+            detail: None,
+        }
+    }
+
+    /// NULL is inserted or updated into a column that is declared `NOT NULL`
+    pub fn not_null_violation(column: String) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::NotNullViolation(column),
+            detail: None,
+        }
+    }
+
+    /// `SET NOT NULL` of a column that already has NULLs in some rows
+    pub fn column_contains_nulls(column: String, table_name: String) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::ColumnContainsNulls { column, table_name },
+            detail: None,
+        }
+    }
+
+    /// `lo_create` of a large object with an OID that is already used
+    pub fn large_object_already_exists(oid: u32) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::LargeObjectAlreadyExists(oid),
+            detail: None,
+        }
+    }
+
+    /// large object with the OID does not exist
+    pub fn large_object_does_not_exist(oid: u32) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::LargeObjectDoesNotExist(oid),
+            detail: None,
+        }
+    }
+
+    /// descriptor of a large object is not open in the session
+    pub fn invalid_large_object_descriptor(fd: i32) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::InvalidLargeObjectDescriptor(fd),
+            detail: None,
+        }
+    }
+
+    /// `lowrite` to a large object that is opened only for reading
+    pub fn large_object_not_opened_for_writing(fd: i32) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::LargeObjectNotOpenedForWriting(fd),
+            detail: None,
+        }
+    }
+
+    /// client sent wrong password during authentication
+    pub fn password_authentication_failed(user: String) -> QueryError {
+        QueryError {
+            severity: Severity::Fatal,
+            kind: QueryErrorKind::InvalidPassword(user),
+            detail: None,
+        }
+    }
+
+    /// client did not present valid certificate issued for the user
+    pub fn certificate_authentication_failed(user: String) -> QueryError {
+        QueryError {
+            severity: Severity::Fatal,
+            kind: QueryErrorKind::InvalidCertificate(user),
+            detail: None,
+        }
+    }
+
+    /// host based access record rejects connection of a client
+    pub fn connection_rejected(host: String, user: String, database: String) -> QueryError {
+        QueryError {
+            severity: Severity::Fatal,
+            kind: QueryErrorKind::ConnectionRejected { host, user, database },
+            detail: None,
+        }
+    }
+
+    /// there is no host based access record for connection of a client
+    pub fn no_host_based_access_entry(host: String, user: String, database: String, ssl: bool) -> QueryError {
+        QueryError {
+            severity: Severity::Fatal,
+            kind: QueryErrorKind::NoHostBasedAccessEntry {
+                host,
+                user,
+                database,
+                ssl,
+            },
+            detail: None,
+        }
+    }
+
+    /// numeric out of range constructor
+    pub fn out_of_range(pg_type: PostgreSqlType, column_name: String, row_index: usize) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::NumericTypeOutOfRange {
+                pg_type,
+                column_name,
+                row_index,
+            },
+            detail: None,
+        }
+    }
+
+    /// type mismatch constructor
+    pub fn type_mismatch(value: &str, pg_type: PostgreSqlType, column_name: String, row_index: usize) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::DataTypeMismatch {
+                pg_type,
+                value: value.to_owned(),
+                column_name,
+                row_index,
+            },
+            detail: None,
+        }
+    }
+
+    /// length of string types do not match constructor
+    pub fn string_length_mismatch(
+        pg_type: PostgreSqlType,
+        len: u64,
+        column_name: String,
+        row_index: usize,
+    ) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::StringTypeLengthMismatch {
+                pg_type,
+                len,
+                column_name,
+                row_index,
+            },
+            detail: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(test)]
+    mod query_event {
+        use super::*;
+
+        #[test]
+        fn create_schema() {
+            let messages: Vec<BackendMessage> = QueryEvent::SchemaCreated.into();
+            assert_eq!(
+                messages,
+                vec![BackendMessage::CommandComplete("CREATE SCHEMA".to_owned())]
+            )
+        }
+
+        #[test]
+        fn drop_schema() {
+            let messages: Vec<BackendMessage> = QueryEvent::SchemaDropped.into();
+            assert_eq!(
+                messages,
+                vec![BackendMessage::CommandComplete("DROP SCHEMA".to_owned())]
+            )
+        }
+
+        #[test]
+        fn create_table() {
+            let messages: Vec<BackendMessage> = QueryEvent::TableCreated.into();
+            assert_eq!(
+                messages,
+                vec![BackendMessage::CommandComplete("CREATE TABLE".to_owned())]
+            );
+        }
+
+        #[test]
+        fn drop_table() {
+            let messages: Vec<BackendMessage> = QueryEvent::TableDropped.into();
+            assert_eq!(messages, vec![BackendMessage::CommandComplete("DROP TABLE".to_owned())]);
+        }
+
+        #[test]
+        fn transaction_control() {
+            let messages: Vec<BackendMessage> = QueryEvent::TransactionCommitted.into();
+            assert_eq!(messages, vec![BackendMessage::CommandComplete("COMMIT".to_owned())]);
+            let messages: Vec<BackendMessage> = QueryEvent::TransactionRolledBack.into();
+            assert_eq!(messages, vec![BackendMessage::CommandComplete("ROLLBACK".to_owned())]);
+            let messages: Vec<BackendMessage> = QueryEvent::SavepointCreated.into();
+            assert_eq!(messages, vec![BackendMessage::CommandComplete("SAVEPOINT".to_owned())]);
+            let messages: Vec<BackendMessage> = QueryEvent::SavepointReleased.into();
+            assert_eq!(messages, vec![BackendMessage::CommandComplete("RELEASE".to_owned())]);
+        }
+
+        #[test]
+        fn insert_record() {
+            let records_number = 3;
+            let messages: Vec<BackendMessage> = QueryEvent::RecordsInserted(records_number).into();
+            assert_eq!(
+                messages,
+                vec![BackendMessage::CommandComplete(format!("INSERT 0 {}", records_number))]
+            )
+        }
+
+        #[test]
+        fn select_records() {
+            let projection = (
+                vec![
+                    ColumnDescription::new("column_name_1", PostgreSqlType::SmallInt),
+                    ColumnDescription::new("column_name_2", PostgreSqlType::SmallInt),
+                ],
+                vec![
+                    vec![Some("1".to_owned()), Some("2".to_owned())],
+                    vec![Some("3".to_owned()), Some("4".to_owned())],
+                ],
+            );
+            let messages: Vec<BackendMessage> = QueryEvent::RecordsSelected(projection).into();
+            assert_eq!(
+                messages,
+                vec![
+                    BackendMessage::RowDescription(vec![
+                        ColumnMetadata::new("column_name_1".to_owned(), 21, 2),
+                        ColumnMetadata::new("column_name_2".to_owned(), 21, 2)
+                    ]),
+                    BackendMessage::DataRow(vec![Some("1".to_owned()), Some("2".to_owned())]),
+                    BackendMessage::DataRow(vec![Some("3".to_owned()), Some("4".to_owned())]),
+                    BackendMessage::CommandComplete("SELECT 2".to_owned())
+                ]
+            );
+        }
+
+        #[test]
+        fn streamed_records() {
+            let messages = vec![
+                QueryEvent::RowDescription(vec![ColumnDescription::new("column_name_1", PostgreSqlType::SmallInt)]),
+                QueryEvent::DataRows(vec![vec![Some("1".to_owned())], vec![Some("2".to_owned())]]),
+                QueryEvent::DataRows(vec![vec![Some("3".to_owned())]]),
+                QueryEvent::SelectComplete(3),
+            ]
+            .into_iter()
+            .flat_map(|event| -> Vec<BackendMessage> { event.into() })
+            .collect::<Vec<_>>();
+            assert_eq!(
+                messages,
+                vec![
+                    BackendMessage::RowDescription(vec![ColumnMetadata::new("column_name_1".to_owned(), 21, 2)]),
+                    BackendMessage::DataRow(vec![Some("1".to_owned())]),
+                    BackendMessage::DataRow(vec![Some("2".to_owned())]),
+                    BackendMessage::DataRow(vec![Some("3".to_owned())]),
+                    BackendMessage::CommandComplete("SELECT 3".to_owned())
+                ]
+            );
+        }
+
+        #[test]
+        fn update_records() {
+            let records_number = 3;
+            let messages: Vec<BackendMessage> = QueryEvent::RecordsUpdated(records_number).into();
+            assert_eq!(
+                messages,
+                vec![BackendMessage::CommandComplete(format!("UPDATE {}", records_number))]
+            );
+        }
+
+        #[test]
+        fn delete_records() {
+            let records_number = 3;
+            let messages: Vec<BackendMessage> = QueryEvent::RecordsDeleted(records_number).into();
+            assert_eq!(
+                messages,
+                vec![BackendMessage::CommandComplete(format!("DELETE {}", records_number))]
+            )
+        }
+
+        #[test]
+        fn copy_records() {
+            let records_number = 3;
+            let messages: Vec<BackendMessage> = QueryEvent::RecordsCopied(records_number).into();
+            assert_eq!(
+                messages,
+                vec![BackendMessage::CommandComplete(format!("COPY {}", records_number))]
+            )
+        }
+
+        #[test]
         fn describe_prepared_statement() {
             let messages: Vec<BackendMessage> = QueryEvent::PreparedStatementDescribed(
                 vec![PostgreSqlType::SmallInt],
-                vec![("si_column".to_owned(), PostgreSqlType::SmallInt)],
+                vec![ColumnDescription::new("si_column", PostgreSqlType::SmallInt)],
+            )
+            .into();
+            assert_eq!(
+                messages,
+                [
+                    BackendMessage::ParameterDescription(vec![21]),
+                    BackendMessage::RowDescription(vec![ColumnMetadata {
+                        name: "si_column".to_owned(),
+                        type_id: 21,
+                        type_size: 2,
+                        table_id: 0,
+                        column_id: 0,
+                    }])
+                ]
+            )
+        }
+
+        #[test]
+        fn describe_portal_without_records() {
+            let messages: Vec<BackendMessage> = QueryEvent::PortalDescribed(vec![]).into();
+            assert_eq!(messages, [BackendMessage::NoData])
+        }
+
+        #[test]
+        fn complete_query() {
+            let messages: Vec<BackendMessage> = QueryEvent::QueryComplete.into();
+            assert_eq!(messages, [BackendMessage::ReadyForQuery])
+        }
+
+        #[test]
+        fn plan_explained() {
+            let messages: Vec<BackendMessage> =
+                QueryEvent::PlanExplained(vec!["Seq Scan on schema_name.table_name".to_owned()]).into();
+            assert_eq!(
+                messages,
+                vec![
+                    BackendMessage::RowDescription(vec![ColumnMetadata::new(
+                        "QUERY PLAN".to_owned(),
+                        PostgreSqlType::VarChar.pg_oid(),
+                        PostgreSqlType::VarChar.pg_len(),
+                    )]),
+                    BackendMessage::DataRow(vec![Some("Seq Scan on schema_name.table_name".to_owned())]),
+                    BackendMessage::CommandComplete("EXPLAIN".to_owned())
+                ]
+            )
+        }
+
+        #[test]
+        fn empty_query() {
+            let messages: Vec<BackendMessage> = QueryEvent::EmptyQuery.into();
+            assert_eq!(messages, [BackendMessage::EmptyQueryResponse])
+        }
+
+        #[test]
+        fn complete_parse() {
+            let messages: Vec<BackendMessage> = QueryEvent::ParseComplete.into();
+            assert_eq!(messages, [BackendMessage::ParseComplete])
+        }
+
+        #[test]
+        fn complete_bind() {
+            let messages: Vec<BackendMessage> = QueryEvent::BindComplete.into();
+            assert_eq!(messages, [BackendMessage::BindComplete])
+        }
+
+        #[test]
+        fn suspend_portal() {
+            let messages: Vec<BackendMessage> = QueryEvent::PortalSuspended.into();
+            assert_eq!(messages, [BackendMessage::PortalSuspended])
+        }
+
+        #[test]
+        fn grant_privileges() {
+            let messages: Vec<BackendMessage> = QueryEvent::PrivilegesGranted.into();
+            assert_eq!(messages, vec![BackendMessage::CommandComplete("GRANT".to_owned())])
+        }
+
+        #[test]
+        fn revoke_privileges() {
+            let messages: Vec<BackendMessage> = QueryEvent::PrivilegesRevoked.into();
+            assert_eq!(messages, vec![BackendMessage::CommandComplete("REVOKE".to_owned())])
+        }
+
+        #[test]
+        fn alter_role() {
+            let messages: Vec<BackendMessage> = QueryEvent::RoleAltered.into();
+            assert_eq!(messages, vec![BackendMessage::CommandComplete("ALTER ROLE".to_owned())])
+        }
+
+        #[test]
+        fn alter_database() {
+            let messages: Vec<BackendMessage> = QueryEvent::DatabaseAltered.into();
+            assert_eq!(
+                messages,
+                vec![BackendMessage::CommandComplete("ALTER DATABASE".to_owned())]
+            )
+        }
+
+        #[test]
+        fn alter_default_privileges() {
+            let messages: Vec<BackendMessage> = QueryEvent::DefaultPrivilegesAltered.into();
+            assert_eq!(
+                messages,
+                vec![BackendMessage::CommandComplete("ALTER DEFAULT PRIVILEGES".to_owned())]
+            )
+        }
+
+        #[test]
+        fn create_publication() {
+            let messages: Vec<BackendMessage> = QueryEvent::PublicationCreated.into();
+            assert_eq!(
+                messages,
+                vec![BackendMessage::CommandComplete("CREATE PUBLICATION".to_owned())]
+            )
+        }
+
+        #[test]
+        fn drop_publication() {
+            let messages: Vec<BackendMessage> = QueryEvent::PublicationDropped.into();
+            assert_eq!(
+                messages,
+                vec![BackendMessage::CommandComplete("DROP PUBLICATION".to_owned())]
+            )
+        }
+
+        #[test]
+        fn create_replication_slot() {
+            let messages: Vec<BackendMessage> = QueryEvent::ReplicationSlotCreated.into();
+            assert_eq!(
+                messages,
+                vec![BackendMessage::CommandComplete("CREATE REPLICATION SLOT".to_owned())]
+            )
+        }
+
+        #[test]
+        fn drop_replication_slot() {
+            let messages: Vec<BackendMessage> = QueryEvent::ReplicationSlotDropped.into();
+            assert_eq!(
+                messages,
+                vec![BackendMessage::CommandComplete("DROP REPLICATION SLOT".to_owned())]
+            )
+        }
+
+        #[test]
+        fn create_foreign_table() {
+            let messages: Vec<BackendMessage> = QueryEvent::ForeignTableCreated.into();
+            assert_eq!(
+                messages,
+                vec![BackendMessage::CommandComplete("CREATE FOREIGN TABLE".to_owned())]
+            )
+        }
+
+        #[test]
+        fn cluster() {
+            let messages: Vec<BackendMessage> = QueryEvent::TableClustered.into();
+            assert_eq!(messages, vec![BackendMessage::CommandComplete("CLUSTER".to_owned())])
+        }
+
+        #[test]
+        fn alter_table() {
+            let messages: Vec<BackendMessage> = QueryEvent::TableAltered.into();
+            assert_eq!(
+                messages,
+                vec![BackendMessage::CommandComplete("ALTER TABLE".to_owned())]
+            )
+        }
+
+        #[test]
+        fn create_function() {
+            let messages: Vec<BackendMessage> = QueryEvent::FunctionCreated.into();
+            assert_eq!(
+                messages,
+                vec![BackendMessage::CommandComplete("CREATE FUNCTION".to_owned())]
+            )
+        }
+
+        #[test]
+        fn drop_function() {
+            let messages: Vec<BackendMessage> = QueryEvent::FunctionDropped.into();
+            assert_eq!(
+                messages,
+                vec![BackendMessage::CommandComplete("DROP FUNCTION".to_owned())]
+            )
+        }
+
+        #[test]
+        fn create_event_trigger() {
+            let messages: Vec<BackendMessage> = QueryEvent::EventTriggerCreated.into();
+            assert_eq!(
+                messages,
+                vec![BackendMessage::CommandComplete("CREATE EVENT TRIGGER".to_owned())]
+            )
+        }
+
+        #[test]
+        fn drop_event_trigger() {
+            let messages: Vec<BackendMessage> = QueryEvent::EventTriggerDropped.into();
+            assert_eq!(
+                messages,
+                vec![BackendMessage::CommandComplete("DROP EVENT TRIGGER".to_owned())]
+            )
+        }
+
+        #[test]
+        fn anonymous_block() {
+            let messages: Vec<BackendMessage> = QueryEvent::BlockExecuted.into();
+            assert_eq!(messages, vec![BackendMessage::CommandComplete("DO".to_owned())])
+        }
+
+        #[test]
+        fn create_aggregate() {
+            let messages: Vec<BackendMessage> = QueryEvent::AggregateCreated.into();
+            assert_eq!(
+                messages,
+                vec![BackendMessage::CommandComplete("CREATE AGGREGATE".to_owned())]
+            )
+        }
+    }
+
+    #[cfg(test)]
+    mod query_error {
+        use super::*;
+
+        #[test]
+        fn sql_state_and_message() {
+            let error = QueryError::table_does_not_exist("schema_name.table_name".to_owned());
+            assert_eq!(error.sql_state(), "42P01");
+            assert_eq!(
+                error.to_string(),
+                "table \"schema_name.table_name\" does not exist".to_owned()
+            );
+        }
+
+        #[test]
+        fn schema_already_exists() {
+            let schema_name = "some_table_name".to_owned();
+            let message: BackendMessage = QueryError::schema_already_exists(schema_name.clone()).into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("42P06"),
+                    Some(format!("schema \"{}\" already exists", schema_name)),
+                    None,
+                )
+            )
+        }
+
+        #[test]
+        fn schema_does_not_exists() {
+            let schema_name = "some_table_name".to_owned();
+            let message: BackendMessage = QueryError::schema_does_not_exist(schema_name.clone()).into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("3F000"),
+                    Some(format!("schema \"{}\" does not exist", schema_name)),
+                    None,
+                )
+            )
+        }
+
+        #[test]
+        fn table_already_exists() {
+            let table_name = "some_table_name".to_owned();
+            let message: BackendMessage = QueryError::table_already_exists(table_name.clone()).into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("42P07"),
+                    Some(format!("table \"{}\" already exists", table_name)),
+                    None,
+                )
+            )
+        }
+
+        #[test]
+        fn table_does_not_exists() {
+            let table_name = "some_table_name".to_owned();
+            let message: BackendMessage = QueryError::table_does_not_exist(table_name.clone()).into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("42P01"),
+                    Some(format!("table \"{}\" does not exist", table_name)),
+                    None,
+                )
+            )
+        }
+
+        #[test]
+        fn one_column_does_not_exists() {
+            let message: BackendMessage =
+                QueryError::column_does_not_exist(vec!["column_not_in_table".to_owned()]).into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("42703"),
+                    Some("column column_not_in_table does not exist".to_owned()),
+                    None,
+                )
+            )
+        }
+
+        #[test]
+        fn ambiguous_column() {
+            let message: BackendMessage = QueryError::ambiguous_column("id".to_owned()).into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("42702"),
+                    Some("column reference \"id\" is ambiguous".to_owned()),
+                    None,
+                )
+            )
+        }
+
+        #[test]
+        fn invalid_column_reference() {
+            let message: BackendMessage =
+                QueryError::invalid_column_reference("ORDER BY position 3 is not in select list".to_owned()).into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("42P10"),
+                    Some("ORDER BY position 3 is not in select list".to_owned()),
+                    None,
+                )
+            )
+        }
+
+        #[test]
+        fn missing_from_clause_entry() {
+            let message: BackendMessage = QueryError::missing_from_clause_entry("other".to_owned()).into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("42P01"),
+                    Some("missing FROM-clause entry for table \"other\"".to_owned()),
+                    None,
+                )
+            )
+        }
+
+        #[test]
+        fn invalid_tablesample_argument() {
+            let message: BackendMessage =
+                QueryError::invalid_tablesample_argument("sample percentage must be between 0 and 100".to_owned())
+                    .into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("2202H"),
+                    Some("sample percentage must be between 0 and 100".to_owned()),
+                    None,
+                )
+            )
+        }
+
+        #[test]
+        fn invalid_row_count_in_limit_clause() {
+            let message: BackendMessage = QueryError::invalid_row_count_in_limit_clause().into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("2201W"),
+                    Some("LIMIT must not be negative".to_owned()),
+                    None,
+                )
+            )
+        }
+
+        #[test]
+        fn invalid_row_count_in_result_offset_clause() {
+            let message: BackendMessage = QueryError::invalid_row_count_in_result_offset_clause().into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("2201X"),
+                    Some("OFFSET must not be negative".to_owned()),
+                    None,
+                )
+            )
+        }
+
+        #[test]
+        fn tablesample_method_does_not_exist() {
+            let message: BackendMessage = QueryError::tablesample_method_does_not_exist("random".to_owned()).into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("42704"),
+                    Some("tablesample method \"random\" does not exist".to_owned()),
+                    None,
+                )
+            )
+        }
+
+        #[test]
+        fn multiple_columns_does_not_exists() {
+            let message: BackendMessage = QueryError::column_does_not_exist(vec![
+                "column_not_in_table1".to_owned(),
+                "column_not_in_table2".to_owned(),
+            ])
+            .into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("42703"),
+                    Some("columns column_not_in_table1, column_not_in_table2 do not exist".to_owned()),
+                    None,
+                )
+            )
+        }
+
+        #[test]
+        fn invalid_parameter_value() {
+            let messages: BackendMessage =
+                QueryError::invalid_parameter_value("Wrong parameter value".to_owned()).into();
+            assert_eq!(
+                messages,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("22023"),
+                    Some("Wrong parameter value".to_owned()),
+                    None,
+                )
+            )
+        }
+
+        #[test]
+        fn error_with_detail() {
+            let messages: BackendMessage = QueryError::invalid_parameter_value("Wrong parameter value".to_owned())
+                .with_detail("parameter $1".to_owned())
+                .into();
+            assert_eq!(
+                messages,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("22023"),
+                    Some("Wrong parameter value".to_owned()),
+                    Some("parameter $1".to_owned()),
+                )
+            )
+        }
+
+        #[test]
+        fn prepared_statement_does_not_exists() {
+            let messages: BackendMessage =
+                QueryError::prepared_statement_does_not_exist("statement_name".to_owned()).into();
+            assert_eq!(
+                messages,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("26000"),
+                    Some("prepared statement statement_name does not exist".to_owned()),
+                    None,
+                )
+            )
+        }
+
+        #[test]
+        fn portal_cannot_be_run() {
+            let messages: BackendMessage = QueryError::portal_cannot_be_run("portal_name".to_owned()).into();
+            assert_eq!(
+                messages,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("55000"),
+                    Some("portal portal_name cannot be run".to_owned()),
+                    None,
+                )
+            )
+        }
+
+        #[test]
+        fn cached_plan_changed_result_type() {
+            let messages: BackendMessage = QueryError::cached_plan_changed_result_type().into();
+            assert_eq!(
+                messages,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("0A000"),
+                    Some("cached plan must not change result type".to_owned()),
+                    None,
+                )
+            )
+        }
+
+        #[test]
+        fn portal_does_not_exists() {
+            let messages: BackendMessage = QueryError::portal_does_not_exist("portal_name".to_owned()).into();
+            assert_eq!(
+                messages,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("26000"),
+                    Some("portal portal_name does not exist".to_owned()),
+                    None,
+                )
+            )
+        }
+
+        #[test]
+        fn protocol_violation() {
+            let messages: BackendMessage = QueryError::protocol_violation("Wrong protocol data".to_owned()).into();
+            assert_eq!(
+                messages,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("08P01"),
+                    Some("Wrong protocol data".to_owned()),
+                    None,
+                )
+            )
+        }
+
+        #[test]
+        fn feature_not_supported() {
+            let raw_sql_query = "some SQL query".to_owned();
+            let message: BackendMessage = QueryError::feature_not_supported(raw_sql_query.clone()).into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("0A000"),
+                    Some(format!("Currently, Query '{}' can't be executed", raw_sql_query)),
+                    None,
+                )
+            )
+        }
+
+        #[test]
+        fn too_many_insert_expressions() {
+            let message: BackendMessage = QueryError::too_many_insert_expressions().into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("42601"),
+                    Some("INSERT has more expressions than target columns".to_owned()),
+                    None,
+                )
+            )
+        }
+
+        #[test]
+        fn out_of_range_constraint_violation() {
+            let message: BackendMessage =
+                QueryError::out_of_range(PostgreSqlType::SmallInt, "col1".to_string(), 1).into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("22003"),
+                    Some("smallint is out of range for column 'col1' at row 1".to_owned()),
+                    None
+                )
+            )
+        }
+
+        #[test]
+        fn type_mismatch_constraint_violation() {
+            let message: BackendMessage =
+                QueryError::type_mismatch("abc", PostgreSqlType::SmallInt, "col1".to_string(), 1).into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("2200G"),
+                    Some("invalid input syntax for type smallint for column 'col1' at row 1: \"abc\"".to_owned()),
+                    None
+                )
+            )
+        }
+
+        #[test]
+        fn string_length_mismatch_constraint_violation() {
+            let message: BackendMessage =
+                QueryError::string_length_mismatch(PostgreSqlType::Char, 5, "col1".to_string(), 1).into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("22026"),
+                    Some("value too long for type character(5) for column 'col1' at row 1".to_owned()),
+                    None
+                )
+            )
+        }
+
+        #[test]
+        fn undefined_function() {
+            let message: BackendMessage =
+                QueryError::undefined_function("||".to_owned(), "NUMBER".to_owned(), "NUMBER".to_owned()).into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("42883"),
+                    Some("operator does not exist: (NUMBER || NUMBER)".to_owned()),
+                    None
+                )
+            )
+        }
+
+        #[test]
+        fn syntax_error() {
+            let messages: BackendMessage = QueryError::syntax_error("expression".to_owned()).into();
+            assert_eq!(
+                messages,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("42601"),
+                    Some("syntax error in expression".to_owned()),
+                    None
+                )
+            )
+        }
+
+        #[test]
+        fn subquery_too_many_columns() {
+            let message: BackendMessage = QueryError::subquery_too_many_columns().into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("42601"),
+                    Some("subquery has too many columns".to_owned()),
+                    None
+                )
+            )
+        }
+
+        #[test]
+        fn set_operation_columns_mismatch() {
+            let message: BackendMessage = QueryError::set_operation_columns_mismatch("UNION").into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("42601"),
+                    Some("each UNION query must have the same number of columns".to_owned()),
+                    None
+                )
+            )
+        }
+
+        #[test]
+        fn permission_denied() {
+            let message: BackendMessage =
+                QueryError::permission_denied("table", "schema_name.table_name".to_owned()).into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("42501"),
+                    Some("permission denied for table schema_name.table_name".to_owned()),
+                    None
+                )
+            )
+        }
+
+        #[test]
+        fn must_be_owner() {
+            let message: BackendMessage = QueryError::must_be_owner("schema", "schema_name".to_owned()).into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("42501"),
+                    Some("must be owner of schema schema_name".to_owned()),
+                    None
+                )
+            )
+        }
+
+        #[test]
+        fn password_authentication_failed() {
+            let message: BackendMessage = QueryError::password_authentication_failed("role_name".to_owned()).into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("FATAL"),
+                    Some("28P01"),
+                    Some("password authentication failed for user \"role_name\"".to_owned()),
+                    None
+                )
+            )
+        }
+
+        #[test]
+        fn read_only_sql_transaction() {
+            let message: BackendMessage = QueryError::read_only_sql_transaction("INSERT").into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("25006"),
+                    Some("cannot execute INSERT in a read-only transaction".to_owned()),
+                    None
+                )
+            )
+        }
+
+        #[test]
+        fn must_be_superuser() {
+            let message: BackendMessage = QueryError::must_be_superuser("COPY to or from a file").into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("42501"),
+                    Some("must be superuser to COPY to or from a file".to_owned()),
+                    None
+                )
+            )
+        }
+
+        #[test]
+        fn bad_copy_file_format() {
+            let message: BackendMessage =
+                QueryError::bad_copy_file_format("missing data for column \"column_2\"".to_owned()).into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("22P04"),
+                    Some("missing data for column \"column_2\"".to_owned()),
+                    None
+                )
+            )
+        }
+
+        #[test]
+        fn io_error() {
+            let message: BackendMessage = QueryError::io_error("could not open file \"data.csv\"".to_owned()).into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("58030"),
+                    Some("could not open file \"data.csv\"".to_owned()),
+                    None
+                )
+            )
+        }
+
+        #[test]
+        fn publication_already_exists() {
+            let message: BackendMessage = QueryError::publication_already_exists("changes".to_owned()).into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("42710"),
+                    Some("publication \"changes\" already exists".to_owned()),
+                    None
+                )
+            )
+        }
+
+        #[test]
+        fn publication_does_not_exist() {
+            let message: BackendMessage = QueryError::publication_does_not_exist("changes".to_owned()).into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("42704"),
+                    Some("publication \"changes\" does not exist".to_owned()),
+                    None
+                )
+            )
+        }
+
+        #[test]
+        fn replication_slot_already_exists() {
+            let message: BackendMessage = QueryError::replication_slot_already_exists("slot".to_owned()).into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("42710"),
+                    Some("replication slot \"slot\" already exists".to_owned()),
+                    None
+                )
+            )
+        }
+
+        #[test]
+        fn replication_slot_does_not_exist() {
+            let message: BackendMessage = QueryError::replication_slot_does_not_exist("slot".to_owned()).into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("42704"),
+                    Some("replication slot \"slot\" does not exist".to_owned()),
+                    None
+                )
+            )
+        }
+
+        #[test]
+        fn server_does_not_exist() {
+            let message: BackendMessage = QueryError::server_does_not_exist("remote".to_owned()).into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("42704"),
+                    Some("server \"remote\" does not exist".to_owned()),
+                    None
+                )
+            )
+        }
+
+        #[test]
+        fn index_does_not_exist() {
+            let message: BackendMessage = QueryError::index_does_not_exist("table_idx".to_owned()).into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("42704"),
+                    Some("index \"table_idx\" does not exist".to_owned()),
+                    None
+                )
+            )
+        }
+
+        #[test]
+        fn function_already_exists() {
+            let message: BackendMessage = QueryError::function_already_exists("add_one".to_owned()).into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("42723"),
+                    Some("function \"add_one\" already exists".to_owned()),
+                    None
+                )
+            )
+        }
+
+        #[test]
+        fn function_does_not_exist() {
+            let message: BackendMessage = QueryError::function_does_not_exist("add_one(integer)".to_owned()).into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("42883"),
+                    Some("function add_one(integer) does not exist".to_owned()),
+                    None
+                )
+            )
+        }
+
+        #[test]
+        fn division_by_zero() {
+            let message: BackendMessage = QueryError::division_by_zero().into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(Some("ERROR"), Some("22012"), Some("division by zero".to_owned()), None)
             )
-            .into();
+        }
+
+        #[test]
+        fn substring_error() {
+            let message: BackendMessage = QueryError::substring_error().into();
             assert_eq!(
-                messages,
-                [
-                    BackendMessage::ParameterDescription(vec![21]),
-                    BackendMessage::RowDescription(vec![ColumnMetadata {
-                        name: "si_column".to_owned(),
-                        type_id: 21,
-                        type_size: 2
-                    }])
-                ]
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("22011"),
+                    Some("negative substring length not allowed".to_owned()),
+                    None
+                )
             )
         }
 
         #[test]
-        fn complete_query() {
-            let messages: Vec<BackendMessage> = QueryEvent::QueryComplete.into();
-            assert_eq!(messages, [BackendMessage::ReadyForQuery])
+        fn query_canceled() {
+            let message: BackendMessage = QueryError::query_canceled("statement timeout").into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("57014"),
+                    Some("canceling statement due to statement timeout".to_owned()),
+                    None
+                )
+            )
         }
 
         #[test]
-        fn complete_parse() {
-            let messages: Vec<BackendMessage> = QueryEvent::ParseComplete.into();
-            assert_eq!(messages, [BackendMessage::ParseComplete])
+        fn admin_shutdown() {
+            let message: BackendMessage = QueryError::admin_shutdown().into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("FATAL"),
+                    Some("57P01"),
+                    Some("terminating connection due to administrator command".to_owned()),
+                    None
+                )
+            )
         }
 
         #[test]
-        fn complete_bind() {
-            let messages: Vec<BackendMessage> = QueryEvent::BindComplete.into();
-            assert_eq!(messages, [BackendMessage::BindComplete])
+        fn no_active_sql_transaction() {
+            let message: BackendMessage = QueryError::no_active_sql_transaction("SAVEPOINT").into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("25P01"),
+                    Some("SAVEPOINT can only be used in transaction blocks".to_owned()),
+                    None
+                )
+            )
         }
-    }
 
-    #[cfg(test)]
-    mod query_error {
-        use super::*;
+        #[test]
+        fn active_sql_transaction() {
+            let message: BackendMessage = QueryError::active_sql_transaction("CREATE TABLE").into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("25001"),
+                    Some("CREATE TABLE cannot run inside a transaction block".to_owned()),
+                    None
+                )
+            )
+        }
 
         #[test]
-        fn schema_already_exists() {
-            let schema_name = "some_table_name".to_owned();
-            let message: BackendMessage = QueryError::schema_already_exists(schema_name.clone()).into();
+        fn in_failed_sql_transaction() {
+            let message: BackendMessage = QueryError::in_failed_sql_transaction().into();
             assert_eq!(
                 message,
                 BackendMessage::ErrorResponse(
                     Some("ERROR"),
-                    Some("42P06"),
-                    Some(format!("schema \"{}\" already exists", schema_name)),
+                    Some("25P02"),
+                    Some("current transaction is aborted, commands ignored until end of transaction block".to_owned()),
+                    None
                 )
             )
         }
 
         #[test]
-        fn schema_does_not_exists() {
-            let schema_name = "some_table_name".to_owned();
-            let message: BackendMessage = QueryError::schema_does_not_exist(schema_name.clone()).into();
+        fn savepoint_does_not_exist() {
+            let message: BackendMessage = QueryError::savepoint_does_not_exist("name".to_owned()).into();
             assert_eq!(
                 message,
                 BackendMessage::ErrorResponse(
                     Some("ERROR"),
-                    Some("3F000"),
-                    Some(format!("schema \"{}\" does not exist", schema_name)),
+                    Some("3B001"),
+                    Some("savepoint \"name\" does not exist".to_owned()),
+                    None
                 )
             )
         }
 
         #[test]
-        fn table_already_exists() {
-            let table_name = "some_table_name".to_owned();
-            let message: BackendMessage = QueryError::table_already_exists(table_name.clone()).into();
+        fn deadlock_detected() {
+            let message: BackendMessage = QueryError::deadlock_detected().into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(Some("ERROR"), Some("40P01"), Some("deadlock detected".to_owned()), None)
+            )
+        }
+
+        #[test]
+        fn value_out_of_range() {
+            let message: BackendMessage = QueryError::value_out_of_range(PostgreSqlType::SmallInt).into();
             assert_eq!(
                 message,
                 BackendMessage::ErrorResponse(
                     Some("ERROR"),
-                    Some("42P07"),
-                    Some(format!("table \"{}\" already exists", table_name)),
+                    Some("22003"),
+                    Some("smallint out of range".to_owned()),
+                    None
                 )
             )
         }
 
         #[test]
-        fn table_does_not_exists() {
-            let table_name = "some_table_name".to_owned();
-            let message: BackendMessage = QueryError::table_does_not_exist(table_name.clone()).into();
+        fn function_without_return() {
+            let message: BackendMessage = QueryError::function_without_return().into();
             assert_eq!(
                 message,
                 BackendMessage::ErrorResponse(
                     Some("ERROR"),
-                    Some("42P01"),
-                    Some(format!("table \"{}\" does not exist", table_name)),
+                    Some("2F005"),
+                    Some("control reached end of function without RETURN".to_owned()),
+                    None
                 )
             )
         }
 
         #[test]
-        fn one_column_does_not_exists() {
+        fn datatype_mismatch() {
             let message: BackendMessage =
-                QueryError::column_does_not_exist(vec!["column_not_in_table".to_owned()]).into();
+                QueryError::datatype_mismatch("argument of IF must be type bool, not type integer".to_owned()).into();
             assert_eq!(
                 message,
                 BackendMessage::ErrorResponse(
                     Some("ERROR"),
-                    Some("42703"),
-                    Some("column column_not_in_table does not exist".to_owned()),
+                    Some("42804"),
+                    Some("argument of IF must be type bool, not type integer".to_owned()),
+                    None
                 )
             )
         }
 
         #[test]
-        fn multiple_columns_does_not_exists() {
-            let message: BackendMessage = QueryError::column_does_not_exist(vec![
-                "column_not_in_table1".to_owned(),
-                "column_not_in_table2".to_owned(),
-            ])
-            .into();
+        fn event_trigger_already_exists() {
+            let message: BackendMessage = QueryError::event_trigger_already_exists("no_drops".to_owned()).into();
             assert_eq!(
                 message,
                 BackendMessage::ErrorResponse(
                     Some("ERROR"),
-                    Some("42703"),
-                    Some("columns column_not_in_table1, column_not_in_table2 do not exist".to_owned()),
+                    Some("42710"),
+                    Some("event trigger \"no_drops\" already exists".to_owned()),
+                    None
                 )
             )
         }
 
         #[test]
-        fn invalid_parameter_value() {
-            let messages: BackendMessage =
-                QueryError::invalid_parameter_value("Wrong parameter value".to_owned()).into();
+        fn event_trigger_does_not_exist() {
+            let message: BackendMessage = QueryError::event_trigger_does_not_exist("no_drops".to_owned()).into();
             assert_eq!(
-                messages,
-                BackendMessage::ErrorResponse(Some("ERROR"), Some("22023"), Some("Wrong parameter value".to_owned()),)
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("42704"),
+                    Some("event trigger \"no_drops\" does not exist".to_owned()),
+                    None
+                )
             )
         }
 
         #[test]
-        fn prepared_statement_does_not_exists() {
-            let messages: BackendMessage =
-                QueryError::prepared_statement_does_not_exist("statement_name".to_owned()).into();
+        fn not_event_trigger_function() {
+            let message: BackendMessage = QueryError::not_event_trigger_function("add_one".to_owned()).into();
             assert_eq!(
-                messages,
+                message,
                 BackendMessage::ErrorResponse(
                     Some("ERROR"),
-                    Some("26000"),
-                    Some("prepared statement statement_name does not exist".to_owned()),
+                    Some("42P17"),
+                    Some("function add_one must return type event_trigger".to_owned()),
+                    None
                 )
             )
         }
 
         #[test]
-        fn portal_does_not_exists() {
-            let messages: BackendMessage = QueryError::portal_does_not_exist("portal_name".to_owned()).into();
+        fn trigger_function_call() {
+            let message: BackendMessage = QueryError::trigger_function_call().into();
             assert_eq!(
-                messages,
+                message,
                 BackendMessage::ErrorResponse(
                     Some("ERROR"),
-                    Some("26000"),
-                    Some("portal portal_name does not exist".to_owned()),
+                    Some("0A000"),
+                    Some("trigger functions can only be called as triggers".to_owned()),
+                    None
                 )
             )
         }
 
         #[test]
-        fn protocol_violation() {
-            let messages: BackendMessage = QueryError::protocol_violation("Wrong protocol data".to_owned()).into();
+        fn stack_depth_limit_exceeded() {
+            let message: BackendMessage = QueryError::stack_depth_limit_exceeded(100).into();
             assert_eq!(
-                messages,
-                BackendMessage::ErrorResponse(Some("ERROR"), Some("08P01"), Some("Wrong protocol data".to_owned()),)
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("54001"),
+                    Some("stack depth limit exceeded, functions are nested 100 levels deep".to_owned()),
+                    None
+                )
             )
         }
 
         #[test]
-        fn feature_not_supported() {
-            let raw_sql_query = "some SQL query".to_owned();
-            let message: BackendMessage = QueryError::feature_not_supported(raw_sql_query.clone()).into();
+        fn raise_exception() {
+            let message: BackendMessage = QueryError::raise_exception("tables can not be dropped".to_owned()).into();
             assert_eq!(
                 message,
                 BackendMessage::ErrorResponse(
                     Some("ERROR"),
-                    Some("0A000"),
-                    Some(format!("Currently, Query '{}' can't be executed", raw_sql_query)),
+                    Some("P0001"),
+                    Some("tables can not be dropped".to_owned()),
+                    None
                 )
             )
         }
 
         #[test]
-        fn too_many_insert_expressions() {
-            let message: BackendMessage = QueryError::too_many_insert_expressions().into();
+        fn invalid_function_definition() {
+            let message: BackendMessage =
+                QueryError::invalid_function_definition("module does not export \"add\"".to_owned()).into();
             assert_eq!(
                 message,
                 BackendMessage::ErrorResponse(
                     Some("ERROR"),
-                    Some("42601"),
-                    Some("INSERT has more expressions than target columns".to_owned()),
+                    Some("42P13"),
+                    Some("module does not export \"add\"".to_owned()),
+                    None
                 )
             )
         }
 
         #[test]
-        fn out_of_range_constraint_violation() {
-            let message: BackendMessage =
-                QueryError::out_of_range(PostgreSqlType::SmallInt, "col1".to_string(), 1).into();
+        fn external_routine_exception() {
+            let message: BackendMessage = QueryError::external_routine_exception("all fuel consumed".to_owned()).into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(Some("ERROR"), Some("38000"), Some("all fuel consumed".to_owned()), None)
+            )
+        }
+
+        #[test]
+        fn grouping_error() {
+            let message: BackendMessage = QueryError::grouping_error("name".to_owned()).into();
             assert_eq!(
                 message,
                 BackendMessage::ErrorResponse(
                     Some("ERROR"),
-                    Some("22003"),
-                    Some("smallint is out of range for column 'col1' at row 1".to_owned())
+                    Some("42803"),
+                    Some(
+                        "column \"name\" must appear in the GROUP BY clause or be used in an aggregate function"
+                            .to_owned()
+                    ),
+                    None
                 )
             )
         }
 
         #[test]
-        fn type_mismatch_constraint_violation() {
-            let message: BackendMessage =
-                QueryError::type_mismatch("abc", PostgreSqlType::SmallInt, "col1".to_string(), 1).into();
+        fn fdw_error() {
+            let message: BackendMessage = QueryError::fdw_error("connection refused".to_owned()).into();
             assert_eq!(
                 message,
                 BackendMessage::ErrorResponse(
                     Some("ERROR"),
-                    Some("2200G"),
-                    Some("invalid input syntax for type smallint for column 'col1' at row 1: \"abc\"".to_owned())
+                    Some("HV000"),
+                    Some("connection refused".to_owned()),
+                    None
                 )
             )
         }
 
         #[test]
-        fn string_length_mismatch_constraint_violation() {
+        fn cannot_change_foreign_table() {
             let message: BackendMessage =
-                QueryError::string_length_mismatch(PostgreSqlType::Char, 5, "col1".to_string(), 1).into();
+                QueryError::cannot_change_foreign_table("insert into", "schema_name.table_name".to_owned()).into();
             assert_eq!(
                 message,
                 BackendMessage::ErrorResponse(
                     Some("ERROR"),
-                    Some("22026"),
-                    Some("value too long for type character(5) for column 'col1' at row 1".to_owned())
+                    Some("42809"),
+                    Some("cannot insert into foreign table \"schema_name.table_name\"".to_owned()),
+                    None
                 )
             )
         }
 
         #[test]
-        fn undefined_function() {
+        fn too_many_connections() {
+            let message: BackendMessage = QueryError::too_many_connections().into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("FATAL"),
+                    Some("53300"),
+                    Some("sorry, too many clients already".to_owned()),
+                    None
+                )
+            )
+        }
+
+        #[test]
+        fn too_many_connections_for_role() {
+            let message: BackendMessage = QueryError::too_many_connections_for_role("role_name".to_owned()).into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("FATAL"),
+                    Some("53300"),
+                    Some("too many connections for role \"role_name\"".to_owned()),
+                    None
+                )
+            )
+        }
+
+        #[test]
+        fn out_of_memory() {
+            let message: BackendMessage = QueryError::out_of_memory("result of query", 64).into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("53200"),
+                    Some("out of memory: result of query exceeds work_mem of 64kB".to_owned()),
+                    None
+                )
+            )
+        }
+
+        #[test]
+        fn recursion_limit_exceeded() {
+            let message: BackendMessage = QueryError::recursion_limit_exceeded("tree", 100).into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("54001"),
+                    Some("recursive query \"tree\" exceeded max_recursive_iterations of 100".to_owned()),
+                    None
+                )
+            )
+        }
+
+        #[test]
+        fn not_null_violation() {
+            let message: BackendMessage = QueryError::not_null_violation("column_1".to_owned()).into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("23502"),
+                    Some("null value in column \"column_1\" violates not-null constraint".to_owned()),
+                    None
+                )
+            )
+        }
+
+        #[test]
+        fn column_contains_nulls() {
             let message: BackendMessage =
-                QueryError::undefined_function("||".to_owned(), "NUMBER".to_owned(), "NUMBER".to_owned()).into();
+                QueryError::column_contains_nulls("column_1".to_owned(), "schema_name.table_name".to_owned()).into();
             assert_eq!(
                 message,
                 BackendMessage::ErrorResponse(
                     Some("ERROR"),
-                    Some("42883"),
-                    Some("operator does not exist: (NUMBER || NUMBER)".to_owned())
+                    Some("23502"),
+                    Some("column \"column_1\" of relation \"schema_name.table_name\" contains null values".to_owned()),
+                    None
                 )
             )
         }
 
         #[test]
-        fn syntax_error() {
-            let messages: BackendMessage = QueryError::syntax_error("expression".to_owned()).into();
+        fn large_object_already_exists() {
+            let message: BackendMessage = QueryError::large_object_already_exists(16384).into();
             assert_eq!(
-                messages,
+                message,
                 BackendMessage::ErrorResponse(
                     Some("ERROR"),
-                    Some("42601"),
-                    Some("syntax error in expression".to_owned())
+                    Some("42710"),
+                    Some("large object 16384 already exists".to_owned()),
+                    None
+                )
+            )
+        }
+
+        #[test]
+        fn large_object_does_not_exist() {
+            let message: BackendMessage = QueryError::large_object_does_not_exist(16384).into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("42704"),
+                    Some("large object 16384 does not exist".to_owned()),
+                    None
+                )
+            )
+        }
+
+        #[test]
+        fn invalid_large_object_descriptor() {
+            let message: BackendMessage = QueryError::invalid_large_object_descriptor(3).into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("42704"),
+                    Some("invalid large-object descriptor: 3".to_owned()),
+                    None
+                )
+            )
+        }
+
+        #[test]
+        fn large_object_not_opened_for_writing() {
+            let message: BackendMessage = QueryError::large_object_not_opened_for_writing(0).into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("55000"),
+                    Some("large object descriptor 0 was not opened for writing".to_owned()),
+                    None
+                )
+            )
+        }
+
+        #[test]
+        fn certificate_authentication_failed() {
+            let message: BackendMessage = QueryError::certificate_authentication_failed("role_name".to_owned()).into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("FATAL"),
+                    Some("28000"),
+                    Some("certificate authentication failed for user \"role_name\"".to_owned()),
+                    None
+                )
+            )
+        }
+
+        #[test]
+        fn connection_rejected() {
+            let message: BackendMessage =
+                QueryError::connection_rejected("127.0.0.1".to_owned(), "user".to_owned(), "database".to_owned())
+                    .into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("FATAL"),
+                    Some("28000"),
+                    Some(
+                        "pg_hba.conf rejects connection for host \"127.0.0.1\", user \"user\", database \"database\""
+                            .to_owned()
+                    ),
+                    None
+                )
+            )
+        }
+
+        #[test]
+        fn no_host_based_access_entry() {
+            let message: BackendMessage = QueryError::no_host_based_access_entry(
+                "127.0.0.1".to_owned(),
+                "user".to_owned(),
+                "database".to_owned(),
+                true,
+            )
+            .into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("FATAL"),
+                    Some("28000"),
+                    Some(
+                        "no pg_hba.conf entry for host \"127.0.0.1\", user \"user\", database \"database\", SSL on"
+                            .to_owned()
+                    ),
+                    None
                 )
             )
         }
@@ -0,0 +1,329 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use hmac::{Hmac, Mac, NewMac};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use std::fmt::{self, Display, Formatter};
+
+/// Name of SASL mechanism
+pub(crate) const MECHANISM: &str = "SCRAM-SHA-256";
+const ITERATIONS: u32 = 4096;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 18;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Salted `SCRAM-SHA-256` password verifier as described in RFC 5802 and
+/// stored by PostgreSQL in `pg_authid.rolpassword`.
+///
+/// Textual form is `SCRAM-SHA-256$<iterations>:<salt>$<StoredKey>:<ServerKey>`
+/// where salt and keys are base64 encoded. Plain text password can't be
+/// restored from a verifier.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScramVerifier {
+    iterations: u32,
+    salt: Vec<u8>,
+    stored_key: Vec<u8>,
+    server_key: Vec<u8>,
+}
+
+impl ScramVerifier {
+    /// Creates verifier of `password` with a random salt
+    pub fn new(password: &str) -> ScramVerifier {
+        Self::with_salt(password, random_bytes(SALT_LEN), ITERATIONS)
+    }
+
+    /// Verifier that does not match any password. It is used to go through
+    /// the whole exchange with users that don't have a password so a client
+    /// can't find out which users exist
+    pub(crate) fn mock() -> ScramVerifier {
+        Self::new(base64::encode(random_bytes(SALT_LEN)).as_str())
+    }
+
+    fn with_salt(password: &str, salt: Vec<u8>, iterations: u32) -> ScramVerifier {
+        let salted_password = hi(password.as_bytes(), &salt, iterations);
+        let client_key = hmac(&salted_password, b"Client Key");
+        ScramVerifier {
+            iterations,
+            salt,
+            stored_key: Sha256::digest(&client_key).to_vec(),
+            server_key: hmac(&salted_password, b"Server Key"),
+        }
+    }
+
+    /// Parses verifier from its textual form. Returns `None` if `verifier` is
+    /// malformed
+    pub fn parse(verifier: &str) -> Option<ScramVerifier> {
+        let mut parts = verifier.split('$');
+        if parts.next()? != MECHANISM {
+            return None;
+        }
+        let mut iterations_and_salt = parts.next()?.split(':');
+        let iterations = iterations_and_salt.next()?.parse().ok().filter(|i| *i > 0)?;
+        let salt = base64::decode(iterations_and_salt.next()?).ok()?;
+        let mut keys = parts.next()?.split(':');
+        let stored_key = base64::decode(keys.next()?).ok()?;
+        let server_key = base64::decode(keys.next()?).ok()?;
+        if iterations_and_salt.next().is_some() || keys.next().is_some() || parts.next().is_some() {
+            return None;
+        }
+        Some(ScramVerifier {
+            iterations,
+            salt,
+            stored_key,
+            server_key,
+        })
+    }
+
+    /// returns `true` if `password` was used to create the verifier
+    pub fn matches(&self, password: &str) -> bool {
+        let other = Self::with_salt(password, self.salt.clone(), self.iterations);
+        constant_time_eq(&self.stored_key, &other.stored_key) && constant_time_eq(&self.server_key, &other.server_key)
+    }
+}
+
+impl Display for ScramVerifier {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}${}:{}${}:{}",
+            MECHANISM,
+            self.iterations,
+            base64::encode(&self.salt),
+            base64::encode(&self.stored_key),
+            base64::encode(&self.server_key)
+        )
+    }
+}
+
+/// Server side of `SCRAM-SHA-256` SASL exchange as described in RFC 5802 and
+/// RFC 7677. Channel binding is not supported.
+pub(crate) struct ServerExchange {
+    verifier: ScramVerifier,
+    gs2_header: String,
+    client_first_bare: String,
+    server_first: String,
+    nonce: String,
+}
+
+impl ServerExchange {
+    /// Starts exchange from `client-first-message`. Returns `None` if message
+    /// is malformed
+    pub(crate) fn start(verifier: ScramVerifier, client_first: &str) -> Option<ServerExchange> {
+        Self::with_server_nonce(verifier, client_first, base64::encode(random_bytes(NONCE_LEN)).as_str())
+    }
+
+    fn with_server_nonce(verifier: ScramVerifier, client_first: &str, server_nonce: &str) -> Option<ServerExchange> {
+        let mut gs2 = client_first.splitn(3, ',');
+        let channel_binding = gs2.next()?;
+        if channel_binding != "n" && channel_binding != "y" {
+            return None;
+        }
+        let authzid = gs2.next()?;
+        let client_first_bare = gs2.next()?;
+        let gs2_header = format!("{},{},", channel_binding, authzid);
+        // user name is ignored as PostgreSQL does, it is taken from startup message
+        let client_nonce = client_first_bare
+            .split(',')
+            .find(|attribute| attribute.starts_with("r="))
+            .map(|attribute| &attribute[2..])
+            .filter(|nonce| !nonce.is_empty())?;
+        let nonce = format!("{}{}", client_nonce, server_nonce);
+        let server_first = format!(
+            "r={},s={},i={}",
+            nonce,
+            base64::encode(&verifier.salt),
+            verifier.iterations
+        );
+        Some(ServerExchange {
+            verifier,
+            gs2_header,
+            client_first_bare: client_first_bare.to_owned(),
+            server_first,
+            nonce,
+        })
+    }
+
+    /// `server-first-message` that has to be sent to the client
+    pub(crate) fn server_first(&self) -> &str {
+        self.server_first.as_str()
+    }
+
+    /// Checks client proof from `client-final-message`. Returns
+    /// `server-final-message` if the client knows the password
+    pub(crate) fn finish(&self, client_final: &str) -> Option<String> {
+        let proof_position = client_final.rfind(",p=")?;
+        let without_proof = &client_final[..proof_position];
+        let proof = base64::decode(&client_final[proof_position + 3..]).ok()?;
+        let mut attributes = without_proof.split(',');
+        if attributes.next()? != format!("c={}", base64::encode(&self.gs2_header)) {
+            return None;
+        }
+        if attributes.next()? != format!("r={}", self.nonce) {
+            return None;
+        }
+        let auth_message = format!("{},{},{}", self.client_first_bare, self.server_first, without_proof);
+        let client_signature = hmac(&self.verifier.stored_key, auth_message.as_bytes());
+        if proof.len() != client_signature.len() {
+            return None;
+        }
+        let client_key = proof
+            .iter()
+            .zip(client_signature.iter())
+            .map(|(p, s)| p ^ s)
+            .collect::<Vec<u8>>();
+        if !constant_time_eq(&Sha256::digest(&client_key), &self.verifier.stored_key) {
+            return None;
+        }
+        let server_signature = hmac(&self.verifier.server_key, auth_message.as_bytes());
+        Some(format!("v={}", base64::encode(&server_signature)))
+    }
+}
+
+fn random_bytes(len: usize) -> Vec<u8> {
+    let mut bytes = vec![0; len];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = HmacSha256::new_varkey(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// `Hi()` function from RFC 5802 which is PBKDF2 with HMAC-SHA-256 producing
+/// exactly one block of output
+fn hi(password: &[u8], salt: &[u8], iterations: u32) -> Vec<u8> {
+    let mut first_block_salt = salt.to_vec();
+    first_block_salt.extend_from_slice(&1u32.to_be_bytes());
+    let mut u = hmac(password, &first_block_salt);
+    let mut result = u.clone();
+    for _ in 1..iterations {
+        u = hmac(password, &u);
+        for (r, u) in result.iter_mut().zip(u.iter()) {
+            *r ^= u;
+        }
+    }
+    result
+}
+
+fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
+    left.len() == right.len() && left.iter().zip(right.iter()).fold(0, |acc, (l, r)| acc | (l ^ r)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verifier_matches_only_its_password() {
+        let verifier = ScramVerifier::new("secret");
+
+        assert!(verifier.matches("secret"));
+        assert!(!verifier.matches("Secret"));
+        assert!(!verifier.matches(""));
+    }
+
+    #[test]
+    fn verifiers_of_same_password_are_salted_differently() {
+        assert_ne!(ScramVerifier::new("secret"), ScramVerifier::new("secret"));
+    }
+
+    #[test]
+    fn verifier_does_not_contain_password() {
+        assert!(!ScramVerifier::new("secret").to_string().contains("secret"));
+    }
+
+    #[test]
+    fn parse_textual_form() {
+        let verifier = ScramVerifier::new("secret");
+
+        let parsed = ScramVerifier::parse(verifier.to_string().as_str());
+
+        assert_eq!(parsed.as_ref(), Some(&verifier));
+        assert!(parsed.map(|verifier| verifier.matches("secret")).unwrap_or(false));
+    }
+
+    #[test]
+    fn salted_keys_from_rfc_7677_example() {
+        // salt and iteration count of SCRAM-SHA-256 example exchange in RFC 7677
+        let verifier = ScramVerifier::with_salt("pencil", base64::decode("W22ZaJ0SNY7soEsUEjb6gQ==").unwrap(), 4096);
+
+        assert_eq!(
+            verifier.to_string(),
+            "SCRAM-SHA-256$4096:W22ZaJ0SNY7soEsUEjb6gQ==$\
+             WG5d8oPm3OtcPnkdi4Uo7BkeZkBFzpcXkuLmtbsT4qY=:wfPLwcE6nTWhTAmQ7tl2KeoiWGPlZqQxSrmfPwDl2dU="
+        );
+    }
+
+    #[test]
+    fn rfc_7677_example_exchange() {
+        let verifier = ScramVerifier::with_salt("pencil", base64::decode("W22ZaJ0SNY7soEsUEjb6gQ==").unwrap(), 4096);
+        let exchange = ServerExchange::with_server_nonce(
+            verifier,
+            "n,,n=user,r=rOprNGfwEbeRWgbNEkqO",
+            "%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0",
+        )
+        .expect("valid client-first-message");
+
+        assert_eq!(
+            exchange.server_first(),
+            "r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,s=W22ZaJ0SNY7soEsUEjb6gQ==,i=4096"
+        );
+        assert_eq!(
+            exchange.finish(
+                "c=biws,r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,\
+                 p=dHzbZapWIk4jUhN+Ute9ytag9zjfMHgsqmmiz7AndVQ="
+            ),
+            Some("v=6rriTRBi23WpRR/wtup+mMhUZUn/dB5nLTJRsjl95G4=".to_owned())
+        );
+    }
+
+    #[test]
+    fn exchange_with_wrong_proof() {
+        let verifier = ScramVerifier::with_salt("pencil", base64::decode("W22ZaJ0SNY7soEsUEjb6gQ==").unwrap(), 4096);
+        let exchange = ServerExchange::with_server_nonce(verifier, "n,,n=user,r=client", "server")
+            .expect("valid client-first-message");
+
+        assert_eq!(
+            exchange.finish("c=biws,r=clientserver,p=dHzbZapWIk4jUhN+Ute9ytag9zjfMHgsqmmiz7AndVQ="),
+            None
+        );
+        assert_eq!(
+            exchange.finish("c=biws,r=otherserver,p=dHzbZapWIk4jUhN+Ute9ytag9zjfMHgsqmmiz7AndVQ="),
+            None
+        );
+    }
+
+    #[test]
+    fn mock_verifier_does_not_match_empty_password() {
+        assert!(!ScramVerifier::mock().matches(""));
+    }
+
+    #[test]
+    fn malformed_client_first_message() {
+        assert!(ServerExchange::start(ScramVerifier::mock(), "p=tls-server-end-point,,n=user,r=nonce").is_none());
+        assert!(ServerExchange::start(ScramVerifier::mock(), "n,,n=user").is_none());
+    }
+
+    #[test]
+    fn malformed_verifiers() {
+        assert_eq!(ScramVerifier::parse("md5abcdef"), None);
+        assert_eq!(ScramVerifier::parse("SCRAM-SHA-256$4096:c2FsdA==$a2V5"), None);
+        assert_eq!(ScramVerifier::parse("SCRAM-SHA-256$0:c2FsdA==$a2V5:a2V5"), None);
+        assert_eq!(ScramVerifier::parse("SCRAM-SHA-256$4096:c2FsdA==$a2V5:a2V5$"), None);
+    }
+}
@@ -42,6 +42,10 @@ pub enum PostgreSqlType {
     TimestampWithTimeZone,
     Date,
     Interval,
+    TsVector,
+    TsQuery,
+    /// result of functions that return no value
+    Void,
 }
 
 impl TryFrom<Oid> for PostgreSqlType {
@@ -65,6 +69,9 @@ impl TryFrom<Oid> for PostgreSqlType {
             1186 => Ok(PostgreSqlType::Interval),
             1266 => Ok(PostgreSqlType::TimeWithTimeZone),
             1700 => Ok(PostgreSqlType::Decimal),
+            3614 => Ok(PostgreSqlType::TsVector),
+            3615 => Ok(PostgreSqlType::TsQuery),
+            2278 => Ok(PostgreSqlType::Void),
             _ => Err(()),
         }
     }
@@ -89,6 +96,9 @@ impl PostgreSqlType {
             Self::Interval => 1186,
             Self::TimeWithTimeZone => 1266, // PG Timetz
             Self::Decimal => 1700,          // PG Numeric & Decimal
+            Self::TsVector => 3614,
+            Self::TsQuery => 3615,
+            Self::Void => 2278,
         }
     }
 
@@ -110,6 +120,9 @@ impl PostgreSqlType {
             Self::Interval => 16,
             Self::TimeWithTimeZone => 12,
             Self::Decimal => -1,
+            Self::TsVector => -1,
+            Self::TsQuery => -1,
+            Self::Void => 4,
         }
     }
 
@@ -142,7 +155,7 @@ impl PostgreSqlType {
         match self {
             Self::Bool => parse_bool_from_text(s),
             Self::Char => parse_char_from_text(s),
-            Self::VarChar => parse_varchar_from_text(s),
+            Self::VarChar | Self::TsVector | Self::TsQuery => parse_varchar_from_text(s),
             Self::SmallInt => parse_smallint_from_text(s),
             Self::Integer => parse_integer_from_text(s),
             Self::BigInt => parse_bigint_from_text(s),
@@ -169,6 +182,9 @@ impl Display for PostgreSqlType {
             Self::TimestampWithTimeZone => write!(f, "timestamp with timezone"),
             Self::Interval => write!(f, "interval"),
             Self::Decimal => write!(f, "decimal"),
+            Self::TsVector => write!(f, "tsvector"),
+            Self::TsQuery => write!(f, "tsquery"),
+            Self::Void => write!(f, "void"),
         }
     }
 }
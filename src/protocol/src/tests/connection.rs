@@ -12,7 +12,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::{tests::async_io::TestCase, Channel, Command, Receiver, RequestReceiver, VERSION_3};
+use crate::{
+    results::QueryEvent, tests::async_io::TestCase, Channel, Command, Receiver, RequestReceiver, ResponseSender,
+    Sender, VERSION_3,
+};
 use async_mutex::Mutex as AsyncMutex;
 use futures_lite::future::block_on;
 use std::sync::Arc;
@@ -26,7 +29,7 @@ mod read_query {
         block_on(async {
             let test_case = TestCase::with_content(vec![&[88], &[0, 0, 0, 4]]);
             let channel = Arc::new(AsyncMutex::new(Channel::Plain(test_case)));
-            let mut receiver = RequestReceiver::new((VERSION_3, vec![]), channel);
+            let mut receiver = RequestReceiver::new((VERSION_3, vec![]), false, channel);
 
             let query = receiver.receive().await.expect("no io errors");
             assert_eq!(query, Ok(Command::Terminate));
@@ -38,7 +41,7 @@ mod read_query {
         block_on(async {
             let test_case = TestCase::with_content(vec![&[81], &[0, 0, 0, 14], b"select 1;\0"]);
             let channel = Arc::new(AsyncMutex::new(Channel::Plain(test_case.clone())));
-            let mut receiver = RequestReceiver::new((VERSION_3, vec![]), channel);
+            let mut receiver = RequestReceiver::new((VERSION_3, vec![]), false, channel);
 
             let query = receiver.receive().await.expect("no io errors");
             assert_eq!(
@@ -55,7 +58,7 @@ mod read_query {
         block_on(async {
             let test_case = TestCase::with_content(vec![]);
             let channel = Arc::new(AsyncMutex::new(Channel::Plain(test_case)));
-            let mut connection = RequestReceiver::new((VERSION_3, vec![]), channel);
+            let mut connection = RequestReceiver::new((VERSION_3, vec![]), false, channel);
 
             let query = connection.receive().await;
             assert!(query.is_err());
@@ -67,7 +70,7 @@ mod read_query {
         block_on(async {
             let test_case = TestCase::with_content(vec![&[81]]);
             let channel = Arc::new(AsyncMutex::new(Channel::Plain(test_case)));
-            let mut connection = RequestReceiver::new((VERSION_3, vec![]), channel);
+            let mut connection = RequestReceiver::new((VERSION_3, vec![]), false, channel);
 
             let query = connection.receive().await;
             assert!(query.is_err());
@@ -79,10 +82,45 @@ mod read_query {
         block_on(async {
             let test_case = TestCase::with_content(vec![&[81], &[0, 0, 0, 14], b"sel;\0"]);
             let channel = Arc::new(AsyncMutex::new(Channel::Plain(test_case)));
-            let mut connection = RequestReceiver::new((VERSION_3, vec![]), channel);
+            let mut connection = RequestReceiver::new((VERSION_3, vec![]), false, channel);
 
             let query = connection.receive().await;
             assert!(query.is_err());
         });
     }
 }
+
+#[cfg(test)]
+mod send_response {
+    use super::*;
+    use async_io::Async;
+    use std::{io, os::unix::net::UnixStream};
+
+    #[test]
+    fn client_disconnected() {
+        let (server, client) = UnixStream::pair().expect("socket pair");
+        drop(client);
+        let channel = Arc::new(AsyncMutex::new(Channel::Plain(
+            Async::new(server).expect("non blocking socket"),
+        )));
+        let sender = ResponseSender::new((VERSION_3, vec![]), channel);
+
+        let sent = sender.send(Ok(QueryEvent::QueryComplete));
+
+        assert_eq!(sent.map_err(|error| error.kind()), Err(io::ErrorKind::BrokenPipe));
+    }
+
+    #[test]
+    fn response_is_written() {
+        block_on(async {
+            let test_case = TestCase::with_content(vec![]);
+            let channel = Arc::new(AsyncMutex::new(Channel::Plain(test_case.clone())));
+            let sender = ResponseSender::new((VERSION_3, vec![]), channel);
+
+            sender.send(Ok(QueryEvent::QueryComplete)).expect("no io errors");
+            sender.flush().expect("no io errors");
+
+            assert_eq!(test_case.read_result().await, vec![90, 0, 0, 0, 5, 73]);
+        });
+    }
+}
@@ -14,20 +14,36 @@
 
 use crate::{
     hand_shake,
+    hba::HostBasedAccess,
     messages::{BackendMessage, Encryption},
+    results::QueryError,
+    scram::ScramVerifier,
     tests::{
         async_io::{empty_file_named, TestCase},
         certificate_content, pg_frontend,
     },
-    ProtocolConfiguration,
+    Error, PasswordStore, ProtocolConfiguration, Receiver,
 };
 use futures_lite::future::block_on;
 use std::{
     io::Write,
     net::{Ipv4Addr, SocketAddr, SocketAddrV4},
     path::PathBuf,
+    sync::Arc,
 };
 
+struct SinglePassword(ScramVerifier);
+
+impl PasswordStore for SinglePassword {
+    fn password_verifier(&self, role: &str) -> Option<ScramVerifier> {
+        if role == "username" {
+            Some(self.0.clone())
+        } else {
+            None
+        }
+    }
+}
+
 fn path_to_temp_certificate() -> PathBuf {
     let named_temp_file = empty_file_named();
     let mut file = named_temp_file.reopen().expect("file with content");
@@ -217,3 +233,225 @@ fn successful_connection_handshake_for_ssl_only_secure() {
         assert_eq!(actual_content, expected_content);
     });
 }
+
+#[test]
+fn rejecting_connection_with_wrong_password() {
+    block_on(async {
+        let test_case = TestCase::with_content(vec![
+            pg_frontend::Message::Setup(vec![("user", "username"), ("database", "database_name")])
+                .as_vec()
+                .as_slice(),
+            pg_frontend::Message::Password("wrong").as_vec().as_slice(),
+            &[],
+        ]);
+
+        let config =
+            ProtocolConfiguration::none().with_password_store(Arc::new(SinglePassword(ScramVerifier::new("123"))));
+
+        let result = hand_shake(
+            test_case.clone(),
+            SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 8080)),
+            &config,
+        )
+        .await;
+
+        assert!(matches!(result, Ok(Err(Error::AuthenticationFailed(user))) if user == "username"));
+
+        let actual_content = test_case.read_result().await;
+        let mut expected_content = Vec::new();
+        expected_content.extend_from_slice(BackendMessage::AuthenticationCleartextPassword.as_vec().as_slice());
+        let error: BackendMessage = QueryError::password_authentication_failed("username".to_owned()).into();
+        expected_content.extend_from_slice(error.as_vec().as_slice());
+        assert_eq!(actual_content, expected_content);
+    });
+}
+
+#[test]
+fn accepting_connection_with_stored_password() {
+    block_on(async {
+        let test_case = TestCase::with_content(vec![
+            pg_frontend::Message::Setup(vec![("user", "username"), ("database", "database_name")])
+                .as_vec()
+                .as_slice(),
+            pg_frontend::Message::Password("123").as_vec().as_slice(),
+            &[],
+        ]);
+
+        let config =
+            ProtocolConfiguration::none().with_password_store(Arc::new(SinglePassword(ScramVerifier::new("123"))));
+
+        let result = hand_shake(
+            test_case.clone(),
+            SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 8080)),
+            &config,
+        )
+        .await;
+
+        assert!(matches!(result, Ok(Ok(_))));
+    });
+}
+
+fn with_rules(rules: &str) -> ProtocolConfiguration {
+    ProtocolConfiguration::none()
+        .with_password_store(Arc::new(SinglePassword(ScramVerifier::new("123"))))
+        .with_host_based_access(HostBasedAccess::parse(rules).expect("valid rules"))
+}
+
+fn startup_message() -> Vec<u8> {
+    pg_frontend::Message::Setup(vec![("user", "username"), ("database", "database_name")]).as_vec()
+}
+
+#[test]
+fn trusted_connection_does_not_ask_password() {
+    block_on(async {
+        let test_case = TestCase::with_content(vec![startup_message().as_slice(), &[]]);
+
+        let config = with_rules("host all username 127.0.0.1/32 trust");
+
+        let result = hand_shake(
+            test_case.clone(),
+            SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 8080)),
+            &config,
+        )
+        .await;
+
+        assert!(matches!(result, Ok(Ok((receiver, _sender))) if !receiver.is_secure()));
+
+        let actual_content = test_case.read_result().await;
+        let mut expected_content = Vec::new();
+        expected_content.extend_from_slice(BackendMessage::AuthenticationOk.as_vec().as_slice());
+        assert_eq!(actual_content[..expected_content.len()], expected_content[..]);
+    });
+}
+
+#[test]
+fn rejected_connection() {
+    block_on(async {
+        let test_case = TestCase::with_content(vec![startup_message().as_slice(), &[]]);
+
+        let config = with_rules("host all all 127.0.0.1/32 reject");
+
+        let result = hand_shake(
+            test_case.clone(),
+            SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 8080)),
+            &config,
+        )
+        .await;
+
+        assert!(matches!(result, Ok(Err(Error::ConnectionRejected(user))) if user == "username"));
+
+        let actual_content = test_case.read_result().await;
+        let error: BackendMessage = QueryError::connection_rejected(
+            "127.0.0.1".to_owned(),
+            "username".to_owned(),
+            "database_name".to_owned(),
+        )
+        .into();
+        assert_eq!(actual_content, error.as_vec());
+    });
+}
+
+#[test]
+fn reloaded_rules_apply_to_new_connections() {
+    block_on(async {
+        let config = with_rules("host all all 127.0.0.1/32 reject");
+        config.reload_host_based_access(Some(
+            HostBasedAccess::parse("host all username 127.0.0.1/32 trust").expect("valid rules"),
+        ));
+
+        let test_case = TestCase::with_content(vec![startup_message().as_slice(), &[]]);
+        let result = hand_shake(
+            test_case.clone(),
+            SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 8080)),
+            &config,
+        )
+        .await;
+
+        assert!(matches!(result, Ok(Ok(_))));
+    });
+}
+
+#[test]
+fn connection_without_matching_rule() {
+    block_on(async {
+        let test_case = TestCase::with_content(vec![startup_message().as_slice(), &[]]);
+
+        let config = with_rules("host all all 10.0.0.0/8 trust");
+
+        let result = hand_shake(
+            test_case.clone(),
+            SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 8080)),
+            &config,
+        )
+        .await;
+
+        assert!(matches!(result, Ok(Err(Error::ConnectionRejected(_)))));
+
+        let actual_content = test_case.read_result().await;
+        let error: BackendMessage = QueryError::no_host_based_access_entry(
+            "127.0.0.1".to_owned(),
+            "username".to_owned(),
+            "database_name".to_owned(),
+            false,
+        )
+        .into();
+        assert_eq!(actual_content, error.as_vec());
+    });
+}
+
+#[test]
+fn password_method_requires_stored_password() {
+    block_on(async {
+        let test_case = TestCase::with_content(vec![
+            pg_frontend::Message::Setup(vec![("user", "other_user")])
+                .as_vec()
+                .as_slice(),
+            pg_frontend::Message::Password("123").as_vec().as_slice(),
+            &[],
+        ]);
+
+        let config = with_rules("host all all all password");
+
+        let result = hand_shake(
+            test_case.clone(),
+            SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 8080)),
+            &config,
+        )
+        .await;
+
+        assert!(matches!(result, Ok(Err(Error::AuthenticationFailed(user))) if user == "other_user"));
+    });
+}
+
+#[test]
+fn scram_exchange_with_wrong_proof() {
+    block_on(async {
+        let test_case = TestCase::with_content(vec![
+            startup_message().as_slice(),
+            pg_frontend::Message::SaslInitialResponse("SCRAM-SHA-256", "n,,n=,r=client_nonce")
+                .as_vec()
+                .as_slice(),
+            pg_frontend::Message::SaslResponse("c=biws,r=client_nonce,p=dHzbZapWIk4jUhN+Ute9ytag9zjfMHgsqmmiz7AndVQ=")
+                .as_vec()
+                .as_slice(),
+            &[],
+        ]);
+
+        let config = with_rules("host all all all scram-sha-256");
+
+        let result = hand_shake(
+            test_case.clone(),
+            SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 8080)),
+            &config,
+        )
+        .await;
+
+        assert!(matches!(result, Ok(Err(Error::AuthenticationFailed(user))) if user == "username"));
+
+        let actual_content = test_case.read_result().await;
+        let sasl = BackendMessage::AuthenticationSASL(vec!["SCRAM-SHA-256".to_owned()]).as_vec();
+        assert_eq!(actual_content[..sasl.len()], sasl[..]);
+        let error: BackendMessage = QueryError::password_authentication_failed("username".to_owned()).into();
+        assert!(actual_content.ends_with(error.as_vec().as_slice()));
+    });
+}
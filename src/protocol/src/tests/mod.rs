@@ -19,6 +19,8 @@ mod connection;
 mod hand_shake;
 #[cfg(test)]
 mod pg_frontend;
+#[cfg(test)]
+mod tls;
 
 // tests/fixtures/identity.pfx dumped and stored here for tests
 fn certificate_content() -> Vec<u8> {
@@ -22,6 +22,8 @@ pub enum Message {
     SslDisabled,
     SslRequired,
     Password(&'static str),
+    SaslInitialResponse(&'static str, &'static str),
+    SaslResponse(&'static str),
 }
 
 impl Message {
@@ -71,6 +73,25 @@ impl Message {
                 with_len.extend_from_slice(&buff);
                 with_len
             }
+            Message::SaslInitialResponse(mechanism, data) => {
+                let mut buff = Vec::new();
+                buff.extend_from_slice(mechanism.as_bytes());
+                buff.extend_from_slice(&[0]);
+                buff.extend_from_slice(&(data.len() as i32).to_be_bytes());
+                buff.extend_from_slice(data.as_bytes());
+                let mut with_len = Vec::new();
+                with_len.extend_from_slice(b"p");
+                with_len.extend_from_slice(&(buff.len() as u32 + 4).to_be_bytes());
+                with_len.extend_from_slice(&buff);
+                with_len
+            }
+            Message::SaslResponse(data) => {
+                let mut with_len = Vec::new();
+                with_len.extend_from_slice(b"p");
+                with_len.extend_from_slice(&(data.len() as u32 + 4).to_be_bytes());
+                with_len.extend_from_slice(data.as_bytes());
+                with_len
+            }
         }
     }
 }
@@ -117,4 +138,17 @@ mod tests {
     fn password() {
         assert_eq!(Message::Password("123").as_vec(), vec![112, 0, 0, 0, 8, 49, 50, 51, 0])
     }
+
+    #[test]
+    fn sasl_initial_response() {
+        assert_eq!(
+            Message::SaslInitialResponse("M", "12").as_vec(),
+            vec![112, 0, 0, 0, 12, 77, 0, 0, 0, 0, 2, 49, 50]
+        )
+    }
+
+    #[test]
+    fn sasl_response() {
+        assert_eq!(Message::SaslResponse("12").as_vec(), vec![112, 0, 0, 0, 6, 49, 50])
+    }
 }
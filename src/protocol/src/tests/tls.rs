@@ -0,0 +1,270 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    hand_shake,
+    hba::HostBasedAccess,
+    messages::BackendMessage,
+    tests::{async_io::empty_file_named, pg_frontend},
+    Error, ProtocolConfiguration, Receiver,
+};
+use blocking::Unblock;
+use futures_lite::{
+    future::block_on,
+    io::{self, AsyncRead, AsyncWrite},
+};
+use openssl::{
+    asn1::Asn1Time,
+    bn::BigNum,
+    hash::MessageDigest,
+    pkcs12::Pkcs12,
+    pkey::{PKey, Private},
+    rsa::Rsa,
+    ssl::{SslConnector, SslMethod, SslVerifyMode},
+    x509::{extension::BasicConstraints, X509Name, X509},
+};
+use std::{
+    io::{Read, Write},
+    net::{Shutdown, TcpListener, TcpStream},
+    path::PathBuf,
+    pin::Pin,
+    task::{Context, Poll},
+    thread,
+};
+use tempfile::NamedTempFile;
+
+type KeyPair = (X509, PKey<Private>);
+
+fn certificate(common_name: &str, issuer: Option<&KeyPair>) -> KeyPair {
+    let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+    let mut name = X509Name::builder().unwrap();
+    name.append_entry_by_text("CN", common_name).unwrap();
+    let name = name.build();
+
+    let mut builder = X509::builder().unwrap();
+    builder.set_version(2).unwrap();
+    builder
+        .set_serial_number(&BigNum::from_u32(1).unwrap().to_asn1_integer().unwrap())
+        .unwrap();
+    builder.set_subject_name(&name).unwrap();
+    builder.set_pubkey(&key).unwrap();
+    builder.set_not_before(&Asn1Time::days_from_now(0).unwrap()).unwrap();
+    builder.set_not_after(&Asn1Time::days_from_now(1).unwrap()).unwrap();
+    match issuer {
+        Some((issuer_cert, issuer_key)) => {
+            builder.set_issuer_name(issuer_cert.subject_name()).unwrap();
+            builder.sign(issuer_key, MessageDigest::sha256()).unwrap();
+        }
+        None => {
+            builder
+                .append_extension(BasicConstraints::new().critical().ca().build().unwrap())
+                .unwrap();
+            builder.set_issuer_name(&name).unwrap();
+            builder.sign(&key, MessageDigest::sha256()).unwrap();
+        }
+    }
+    (builder.build(), key)
+}
+
+fn write_temp(content: &[u8]) -> NamedTempFile {
+    let file = empty_file_named();
+    file.reopen().unwrap().write_all(content).unwrap();
+    file
+}
+
+/// Reads and writes of a tcp stream are done in background threads
+/// independently of each other
+struct Duplex {
+    reader: Unblock<TcpStream>,
+    writer: Unblock<TcpStream>,
+}
+
+impl AsyncRead for Duplex {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().reader).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for Duplex {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context, buf: &[u8]) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().writer).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().writer).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().writer).poll_close(cx)
+    }
+}
+
+/// Connects to the server over `ssl` with `client` certificate as user
+/// `username` and returns the first message of the server after setup
+fn connect(listener: &TcpListener, client: Option<KeyPair>) -> thread::JoinHandle<Option<Vec<u8>>> {
+    let address = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.write_all(&pg_frontend::Message::SslRequired.as_vec()).unwrap();
+        let mut accepted = [0u8; 1];
+        stream.read_exact(&mut accepted).unwrap();
+        assert_eq!(&accepted, b"S");
+
+        let mut connector = SslConnector::builder(SslMethod::tls()).unwrap();
+        connector.set_verify(SslVerifyMode::NONE);
+        if let Some((cert, key)) = client {
+            connector.set_certificate(&cert).unwrap();
+            connector.set_private_key(&key).unwrap();
+        }
+        let mut tls = connector
+            .build()
+            .configure()
+            .unwrap()
+            .verify_hostname(false)
+            .connect("localhost", stream)
+            .ok()?;
+        tls.write_all(&pg_frontend::Message::Setup(vec![("user", "username")]).as_vec())
+            .ok()?;
+        let mut response = [0u8; 9];
+        tls.read_exact(&mut response).ok()?;
+        if response[0] != b'E' {
+            read_until_ready_for_query(&mut tls);
+        }
+        Some(response.to_vec())
+    })
+}
+
+fn read_until_ready_for_query(stream: &mut impl Read) {
+    loop {
+        let mut header = [0u8; 5];
+        stream.read_exact(&mut header).unwrap();
+        let mut body = vec![0u8; u32::from_be_bytes([header[1], header[2], header[3], header[4]]) as usize - 4];
+        stream.read_exact(&mut body).unwrap();
+        if header[0] == b'Z' {
+            return;
+        }
+    }
+}
+
+struct Server {
+    listener: TcpListener,
+    identity: NamedTempFile,
+    client_ca: NamedTempFile,
+    ca: KeyPair,
+}
+
+impl Server {
+    fn new() -> Server {
+        let ca = certificate("test ca", None);
+        let server = certificate("localhost", Some(&ca));
+        let identity = Pkcs12::builder()
+            .name("server")
+            .pkey(&server.1)
+            .cert(&server.0)
+            .build2("password")
+            .unwrap();
+        Server {
+            listener: TcpListener::bind("127.0.0.1:0").unwrap(),
+            identity: write_temp(&identity.to_der().unwrap()),
+            client_ca: write_temp(&ca.0.to_pem().unwrap()),
+            ca,
+        }
+    }
+
+    fn client_certificate(&self, common_name: &str) -> KeyPair {
+        certificate(common_name, Some(&self.ca))
+    }
+
+    fn config(&self) -> ProtocolConfiguration {
+        ProtocolConfiguration::with_ssl(PathBuf::from(self.identity.path()), "password".to_owned())
+            .with_client_certificates(PathBuf::from(self.client_ca.path()), false)
+            .with_host_based_access(HostBasedAccess::parse("hostssl all all all cert").unwrap())
+    }
+
+    /// Returns whether accepted connection is secure
+    fn accept(&self, config: &ProtocolConfiguration) -> io::Result<crate::Result<bool>> {
+        let (stream, address) = self.listener.accept().unwrap();
+        let duplex = Duplex {
+            reader: Unblock::new(stream.try_clone().unwrap()),
+            writer: Unblock::new(stream.try_clone().unwrap()),
+        };
+        let result = block_on(async {
+            hand_shake(duplex, address, config)
+                .await
+                .map(|result| result.map(|(receiver, _sender)| receiver.is_secure()))
+        });
+        // reading background thread of `Unblock` keeps the socket open otherwise
+        stream.shutdown(Shutdown::Read).unwrap();
+        result
+    }
+}
+
+#[test]
+fn certificate_issued_for_user() {
+    let server = Server::new();
+    let client = connect(&server.listener, Some(server.client_certificate("username")));
+
+    let result = server.accept(&server.config());
+
+    assert!(matches!(result, Ok(Ok(true))));
+    assert_eq!(client.join().unwrap(), Some(BackendMessage::AuthenticationOk.as_vec()));
+}
+
+#[test]
+fn certificate_issued_for_other_user() {
+    let server = Server::new();
+    let client = connect(&server.listener, Some(server.client_certificate("other_user")));
+
+    let result = server.accept(&server.config());
+
+    assert!(matches!(result, Ok(Err(Error::AuthenticationFailed(user))) if user == "username"));
+    assert!(matches!(client.join().unwrap(), Some(response) if response[0] == b'E'));
+}
+
+#[test]
+fn connection_without_certificate() {
+    let server = Server::new();
+    let client = connect(&server.listener, None);
+
+    let result = server.accept(&server.config());
+
+    assert!(matches!(result, Ok(Err(Error::AuthenticationFailed(_)))));
+    assert!(matches!(client.join().unwrap(), Some(response) if response[0] == b'E'));
+}
+
+#[test]
+fn certificate_is_required() {
+    let server = Server::new();
+    let client = connect(&server.listener, None);
+
+    let result = server.accept(
+        &server
+            .config()
+            .with_client_certificates(PathBuf::from(server.client_ca.path()), true),
+    );
+
+    assert!(result.is_err());
+    assert_eq!(client.join().unwrap(), None);
+}
+
+#[test]
+fn certificate_of_unknown_authority() {
+    let server = Server::new();
+    let client = connect(&server.listener, Some(certificate("username", None)));
+
+    let result = server.accept(&server.config());
+
+    assert!(result.is_err());
+    assert_eq!(client.join().unwrap(), None);
+}
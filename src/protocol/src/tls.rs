@@ -0,0 +1,152 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `openssl` based TLS for async streams. Unlike `native-tls` it lets the
+//! server request and verify client certificates.
+use async_openssl::SslStream;
+use futures_lite::{
+    future::poll_fn,
+    io::{self, AsyncRead, AsyncWrite, ErrorKind},
+};
+use openssl::{
+    nid::Nid,
+    pkcs12::Pkcs12,
+    ssl::{self, Ssl, SslAcceptor, SslMethod, SslVerifyMode},
+    x509::{X509Name, X509VerifyResult},
+};
+use std::{
+    path::Path,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Builds TLS acceptor from `pkcs12` encoded server `identity`. If `client_ca`
+/// file is specified clients are asked for certificates signed by one of
+/// authorities from the file
+pub(crate) fn acceptor(identity: &[u8], password: &str, client_ca: Option<(&Path, bool)>) -> io::Result<SslAcceptor> {
+    let identity = Pkcs12::from_der(identity)
+        .and_then(|pkcs12| pkcs12.parse2(password))
+        .map_err(|error| io::Error::new(ErrorKind::InvalidData, error))?;
+    let mut builder = SslAcceptor::mozilla_intermediate(SslMethod::tls()).map_err(io::Error::from)?;
+    if let Some(key) = identity.pkey {
+        builder.set_private_key(&key).map_err(io::Error::from)?;
+    }
+    if let Some(cert) = identity.cert {
+        builder.set_certificate(&cert).map_err(io::Error::from)?;
+    }
+    for cert in identity.ca.into_iter().flatten() {
+        builder.add_extra_chain_cert(cert).map_err(io::Error::from)?;
+    }
+    if let Some((ca_file, required)) = client_ca {
+        builder.set_ca_file(ca_file).map_err(io::Error::from)?;
+        builder.set_client_ca_list(X509Name::load_client_ca_file(ca_file).map_err(io::Error::from)?);
+        let mut mode = SslVerifyMode::PEER;
+        if required {
+            mode |= SslVerifyMode::FAIL_IF_NO_PEER_CERT;
+        }
+        builder.set_verify(mode);
+    }
+    Ok(builder.build())
+}
+
+/// Performs server side of TLS handshake over `stream`
+pub(crate) async fn accept<S>(acceptor: &SslAcceptor, stream: S) -> io::Result<TlsStream<S>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let ssl = Ssl::new(acceptor.context()).map_err(io::Error::from)?;
+    let mut stream = SslStream::new(ssl, Unflushed(stream)).map_err(io::Error::from)?;
+    poll_fn(|cx| {
+        let handshake = Pin::new(&mut stream).poll_accept(cx).map_err(handshake_error);
+        if let Poll::Ready(Err(error)) = Pin::new(&mut stream.get_mut().0).poll_flush(cx) {
+            return Poll::Ready(Err(error));
+        }
+        handshake
+    })
+    .await?;
+    Ok(TlsStream(stream))
+}
+
+fn handshake_error(error: ssl::Error) -> io::Error {
+    error.into_io_error().unwrap_or_else(|error| {
+        log::debug!("TLS handshake failed {:?}", error);
+        io::Error::from(ErrorKind::ConnectionAborted)
+    })
+}
+
+/// Encrypted stream established with `accept`
+pub(crate) struct TlsStream<S: Unpin>(SslStream<Unflushed<S>>);
+
+impl<S: Unpin> TlsStream<S> {
+    /// Common name from subject of client certificate. Certificates that
+    /// failed verification are ignored
+    pub(crate) fn peer_common_name(&self) -> Option<String> {
+        let ssl = self.0.ssl();
+        if ssl.verify_result() != X509VerifyResult::OK {
+            return None;
+        }
+        let certificate = ssl.peer_certificate()?;
+        let common_name = certificate.subject_name().entries_by_nid(Nid::COMMONNAME).next()?;
+        common_name.data().to_string().ok()
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> AsyncRead for TlsStream<S> {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().0).poll_read(cx, buf)
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> AsyncWrite for TlsStream<S> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().0).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        match Pin::new(&mut this.0).poll_flush(cx) {
+            Poll::Ready(Ok(())) => Pin::new(&mut this.0.get_mut().0).poll_flush(cx),
+            other => other,
+        }
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().0).poll_close(cx)
+    }
+}
+
+/// Stream that `openssl` writes to. `openssl` fails instead of retrying a
+/// flush that would block, so flushing it does nothing and the underlying
+/// stream is flushed by `accept` and `TlsStream` instead.
+pub(crate) struct Unflushed<S>(S);
+
+impl<S: AsyncRead + Unpin> AsyncRead for Unflushed<S> {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().0).poll_read(cx, buf)
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for Unflushed<S> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().0).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().0).poll_close(cx)
+    }
+}
@@ -14,10 +14,11 @@
 
 ///! Runtime cell and row representation.
 use bigdecimal::ToPrimitive;
+use bytes::Bytes;
 use ordered_float::OrderedFloat;
 use sql_types::SqlType;
 use sqlparser::ast::Value;
-use std::convert::TryFrom;
+use std::{convert::TryFrom, io::Write};
 
 /// value shared by the row.
 #[derive(Debug, Clone, PartialEq, Eq, Ord, PartialOrd, Hash)]
@@ -106,6 +107,35 @@ impl<'a> Datum<'a> {
         Datum::SqlType(val)
     }
 
+    /// Text of the datum as a field of a result row, `None` for NULL
+    pub fn to_field(&self) -> Option<String> {
+        match self {
+            Datum::Null => None,
+            other => Some(other.to_string()),
+        }
+    }
+
+    /// Writes text of the datum as a field of a result row into `buffer`
+    /// without allocating it, returns `false` for NULL
+    pub fn write_field(&self, buffer: &mut Vec<u8>) -> bool {
+        let written = match self {
+            Datum::Null => return false,
+            Datum::True => write!(buffer, "t"),
+            Datum::False => write!(buffer, "f"),
+            Datum::Int16(val) => write!(buffer, "{}", val),
+            Datum::Int32(val) => write!(buffer, "{}", val),
+            Datum::Int64(val) => write!(buffer, "{}", val),
+            Datum::UInt64(val) => write!(buffer, "{}", val),
+            Datum::Float32(val) => write!(buffer, "{}", val.into_inner()),
+            Datum::Float64(val) => write!(buffer, "{}", val.into_inner()),
+            Datum::String(val) => buffer.write_all(val.as_bytes()),
+            Datum::OwnedString(val) => buffer.write_all(val.as_bytes()),
+            Datum::SqlType(val) => buffer.write_all(val.to_string().as_bytes()),
+        };
+        written.expect("writing into a vector does not fail");
+        true
+    }
+
     pub fn as_i16(&self) -> i16 {
         match self {
             Self::Int16(val) => *val,
@@ -288,9 +318,10 @@ fn read_tag(data: &[u8], idx: &mut usize) -> TypeTag {
 
 /// in-memory representation of a table row. It is unable to deserialize
 /// the row without knowing the types of each column, which makes this unsafe
-/// however it is more memory efficient.
-#[derive(Debug, Clone, PartialEq, Eq, Default, PartialOrd, Ord)]
-pub struct Binary(Vec<u8>);
+/// however it is more memory efficient. Clones share the same bytes, so rows
+/// are not copied when they are read from storage.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, PartialOrd, Ord)]
+pub struct Binary(Bytes);
 
 impl Binary {
     pub fn new() -> Self {
@@ -299,11 +330,11 @@ impl Binary {
 
     #[allow(clippy::wrong_self_convention)]
     pub fn to_bytes(&self) -> &[u8] {
-        self.0.as_slice()
+        self.0.as_ref()
     }
 
     pub fn with_data(data: Vec<u8>) -> Self {
-        Self(data)
+        Self(Bytes::from(data))
     }
 
     pub fn pack<'a>(other: &[Datum<'a>]) -> Self {
@@ -361,11 +392,11 @@ impl Binary {
             }
         }
 
-        Self(data)
+        Self(Bytes::from(data))
     }
 
     pub fn unpack(&self) -> Vec<Datum> {
-        unpack_raw(self.0.as_slice())
+        unpack_raw(self.0.as_ref())
     }
 }
 
@@ -473,4 +504,61 @@ mod tests {
             assert_eq!(data, row.unpack());
         }
     }
+
+    #[test]
+    fn written_fields_match_text_of_fields() {
+        for datum in &[
+            Datum::from_null(),
+            Datum::from_bool(true),
+            Datum::from_bool(false),
+            Datum::from_i16(-1),
+            Datum::from_i32(2),
+            Datum::from_i64(i64::MIN),
+            Datum::from_u64(4),
+            Datum::from_f32(1.5),
+            Datum::from_f64(-0.25),
+            Datum::from_str("hello"),
+            Datum::from_string("world".to_owned()),
+            Datum::from_sql_type(SqlType::Bool),
+        ] {
+            let mut buffer = vec![0];
+            let written = datum.write_field(&mut buffer);
+
+            assert_eq!(
+                written.then(|| String::from_utf8(buffer[1..].to_vec()).expect("utf-8")),
+                datum.to_field()
+            );
+        }
+    }
+
+    #[test]
+    fn clones_share_bytes() {
+        let row = Binary::pack(&[Datum::from_str("hello")]);
+        let clone = row.clone();
+
+        assert_eq!(row.to_bytes().as_ptr(), clone.to_bytes().as_ptr());
+    }
+
+    #[test]
+    fn unpacked_strings_borrow_bytes() {
+        let row = Binary::pack(&[Datum::from_str("hello")]);
+
+        match row.unpack().as_slice() {
+            [Datum::String(value)] => assert_eq!(value.as_ptr(), row.to_bytes()[row.to_bytes().len() - 5..].as_ptr()),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn null_is_distinct_from_empty_string() {
+        let row = Binary::pack(&[Datum::from_null(), Datum::from_str("")]);
+
+        assert_eq!(
+            row.unpack()
+                .iter()
+                .map(Datum::to_field)
+                .collect::<Vec<Option<String>>>(),
+            vec![None, Some("".to_owned())]
+        );
+    }
 }
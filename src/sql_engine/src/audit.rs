@@ -0,0 +1,428 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Audit of executed statements. Every statement that passed privilege
+//! checks is classified and, if the policy of the session role includes its
+//! class, recorded into an audit sink.
+use crate::query::{
+    authorize::collect_query_tables,
+    extended::{CopyDirection, ExtendedStatement, PrivilegeObjects, SettingsOwner},
+    plan::Plan,
+};
+use sqlparser::ast::Statement;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{self, Display, Formatter},
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    path::Path,
+    str::FromStr,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Classes of statements that can be audited
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StatementClass {
+    /// `SELECT`
+    Read,
+    /// `INSERT`, `UPDATE` and `DELETE`
+    Write,
+    /// creation and removal of schemas and tables
+    Ddl,
+    /// privileges and roles management
+    Role,
+    /// everything else, e.g. `SET` or `BEGIN`
+    Misc,
+}
+
+impl StatementClass {
+    fn all() -> HashSet<StatementClass> {
+        vec![
+            StatementClass::Read,
+            StatementClass::Write,
+            StatementClass::Ddl,
+            StatementClass::Role,
+            StatementClass::Misc,
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    /// Parses comma separated list of classes, `all` and `none` are accepted
+    /// as well
+    fn parse_list(classes: &str) -> Result<HashSet<StatementClass>, String> {
+        let mut result = HashSet::new();
+        for class in classes.split(',').map(str::trim).filter(|class| !class.is_empty()) {
+            match class.to_lowercase().as_str() {
+                "all" => result.extend(StatementClass::all()),
+                "none" => {}
+                _ => {
+                    result.insert(class.parse()?);
+                }
+            }
+        }
+        Ok(result)
+    }
+}
+
+impl FromStr for StatementClass {
+    type Err = String;
+
+    fn from_str(class: &str) -> Result<Self, Self::Err> {
+        match class.to_lowercase().as_str() {
+            "read" => Ok(StatementClass::Read),
+            "write" => Ok(StatementClass::Write),
+            "ddl" => Ok(StatementClass::Ddl),
+            "role" => Ok(StatementClass::Role),
+            "misc" => Ok(StatementClass::Misc),
+            _ => Err(format!("unknown statement class {:?}", class)),
+        }
+    }
+}
+
+impl Display for StatementClass {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            StatementClass::Read => write!(f, "READ"),
+            StatementClass::Write => write!(f, "WRITE"),
+            StatementClass::Ddl => write!(f, "DDL"),
+            StatementClass::Role => write!(f, "ROLE"),
+            StatementClass::Misc => write!(f, "MISC"),
+        }
+    }
+}
+
+/// Which classes of statements are audited. Roles that have no classes of
+/// their own are audited with the default ones.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditPolicy {
+    classes: HashSet<StatementClass>,
+    roles: HashMap<String, HashSet<StatementClass>>,
+}
+
+impl AuditPolicy {
+    /// Creates policy with comma separated list of default `classes`, e.g.
+    /// `write, ddl`
+    pub fn parse(classes: &str) -> Result<AuditPolicy, String> {
+        Ok(AuditPolicy {
+            classes: StatementClass::parse_list(classes)?,
+            roles: HashMap::new(),
+        })
+    }
+
+    /// Overrides default classes for `role`, `none` disables audit of the role
+    pub fn with_role(mut self, role: &str, classes: &str) -> Result<AuditPolicy, String> {
+        self.roles.insert(role.to_owned(), StatementClass::parse_list(classes)?);
+        Ok(self)
+    }
+
+    fn audits(&self, role: &str, class: StatementClass) -> bool {
+        self.roles.get(role).unwrap_or(&self.classes).contains(&class)
+    }
+}
+
+/// Entry of audit log
+#[derive(Debug, PartialEq)]
+pub struct AuditRecord<'r> {
+    /// when the statement was executed
+    pub timestamp: SystemTime,
+    /// identifier of the client session
+    pub session_id: u64,
+    /// role on behalf of which the statement was executed
+    pub role: &'r str,
+    /// class of the statement
+    pub class: StatementClass,
+    /// names of schemas, tables or roles affected by the statement
+    pub objects: &'r [String],
+    /// text of the statement
+    pub statement: &'r str,
+}
+
+/// Formats record as a CSV line: milliseconds since unix epoch, session id,
+/// role, class, space separated objects and statement
+impl Display for AuditRecord<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let millis = self
+            .timestamp
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_millis())
+            .unwrap_or_default();
+        write!(
+            f,
+            "{},{},{},{},{},{}",
+            millis,
+            self.session_id,
+            csv_field(self.role),
+            self.class,
+            csv_field(&self.objects.join(" ")),
+            csv_field(self.statement)
+        )
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+/// Destination of audit records
+pub trait AuditSink: Send + Sync {
+    /// Persists `record`
+    fn write(&self, record: &AuditRecord) -> io::Result<()>;
+}
+
+/// Writes audit records line by line
+pub struct AuditLog<W: Write + Send> {
+    out: Mutex<W>,
+}
+
+impl AuditLog<File> {
+    /// Opens file at `path` to append records, the file is created if it
+    /// does not exist
+    pub fn open(path: &Path) -> io::Result<AuditLog<File>> {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map(AuditLog::new)
+    }
+}
+
+impl<W: Write + Send> AuditLog<W> {
+    /// Creates log that writes records into `out`
+    pub fn new(out: W) -> AuditLog<W> {
+        AuditLog { out: Mutex::new(out) }
+    }
+}
+
+impl<W: Write + Send> AuditSink for AuditLog<W> {
+    fn write(&self, record: &AuditRecord) -> io::Result<()> {
+        let mut out = self.out.lock().expect("to acquire audit log lock");
+        writeln!(out, "{}", record)?;
+        out.flush()
+    }
+}
+
+/// Records statements according to the policy. It is shared by all sessions.
+pub struct Auditor {
+    policy: AuditPolicy,
+    sink: Box<dyn AuditSink>,
+}
+
+impl Auditor {
+    /// Creates auditor that writes records matching `policy` into `sink`
+    pub fn new(policy: AuditPolicy, sink: Box<dyn AuditSink>) -> Auditor {
+        Auditor { policy, sink }
+    }
+
+    /// Failure to write a record does not stop the statement execution, it
+    /// is only logged
+    pub(crate) fn record(
+        &self,
+        session_id: u64,
+        role: &str,
+        (class, objects): (StatementClass, Vec<String>),
+        statement: &str,
+    ) {
+        if !self.policy.audits(role, class) {
+            return;
+        }
+        let record = AuditRecord {
+            timestamp: SystemTime::now(),
+            session_id,
+            role,
+            class,
+            objects: &objects,
+            statement,
+        };
+        if let Err(error) = self.sink.write(&record) {
+            log::error!("failed to write audit record {:?}: {:?}", record, error);
+        }
+    }
+}
+
+/// Class of `plan` and names of objects it affects
+pub(crate) fn classify_plan(plan: &Plan) -> (StatementClass, Vec<String>) {
+    match plan {
+        Plan::CreateSchema(info) => (StatementClass::Ddl, vec![info.schema_name.clone()]),
+        Plan::CreateTable(info) => (
+            StatementClass::Ddl,
+            vec![format!("{}.{}", info.schema_name, info.table_name)],
+        ),
+        Plan::DropSchemas(schemas) => (
+            StatementClass::Ddl,
+            schemas
+                .iter()
+                .map(|(schema_id, _cascade)| schema_id.name().to_owned())
+                .collect(),
+        ),
+        Plan::DropTables(tables) => (
+            StatementClass::Ddl,
+            tables
+                .iter()
+                .map(|table_id| format!("{}.{}", table_id.schema_name(), table_id.name()))
+                .collect(),
+        ),
+        Plan::Insert(table_inserts) => (
+            StatementClass::Write,
+            vec![format!(
+                "{}.{}",
+                table_inserts.table_id.schema_name(),
+                table_inserts.table_id.name()
+            )],
+        ),
+        Plan::NotProcessed(statement) => match statement.as_ref() {
+            Statement::Query(query) => {
+                let mut tables = vec![];
+                collect_query_tables(query, &mut tables);
+                (
+                    StatementClass::Read,
+                    tables.into_iter().map(ToString::to_string).collect(),
+                )
+            }
+            Statement::Update { table_name, .. } | Statement::Delete { table_name, .. } => {
+                (StatementClass::Write, vec![table_name.to_string()])
+            }
+            _ => (StatementClass::Misc, vec![]),
+        },
+    }
+}
+
+/// Class of `statement` and names of objects it affects
+pub(crate) fn classify_extended(statement: &ExtendedStatement) -> (StatementClass, Vec<String>) {
+    let objects = match statement {
+        ExtendedStatement::Copy(copy) => {
+            let class = match copy.direction {
+                CopyDirection::From => StatementClass::Write,
+                CopyDirection::To => StatementClass::Read,
+            };
+            return (class, vec![copy.table_name.to_string()]);
+        }
+        ExtendedStatement::CreatePublication { name, .. }
+        | ExtendedStatement::DropPublication { name, .. }
+        | ExtendedStatement::CreateReplicationSlot { name, .. }
+        | ExtendedStatement::DropReplicationSlot { name, .. } => {
+            return (StatementClass::Ddl, vec![name.clone()]);
+        }
+        ExtendedStatement::CreateForeignTable(foreign_table) => {
+            return (StatementClass::Ddl, vec![foreign_table.name.to_string()]);
+        }
+        ExtendedStatement::Cluster(cluster) => {
+            return (StatementClass::Ddl, vec![cluster.table_name.to_string()]);
+        }
+        ExtendedStatement::AlterTable(alter) => {
+            return (StatementClass::Ddl, vec![alter.table_name.to_string()]);
+        }
+        ExtendedStatement::CreateFunction { function, .. } => {
+            return (StatementClass::Ddl, vec![function.name.clone()]);
+        }
+        ExtendedStatement::DropFunction { name, .. } | ExtendedStatement::DropEventTrigger { name, .. } => {
+            return (StatementClass::Ddl, vec![name.clone()]);
+        }
+        ExtendedStatement::CreateEventTrigger(trigger) => {
+            return (StatementClass::Ddl, vec![trigger.name.clone()]);
+        }
+        ExtendedStatement::Do(_)
+        | ExtendedStatement::Explain(_)
+        | ExtendedStatement::Savepoint(_)
+        | ExtendedStatement::ReleaseSavepoint(_)
+        | ExtendedStatement::RollbackToSavepoint(_) => return (StatementClass::Misc, vec![]),
+        ExtendedStatement::CreateWasmFunction { function, .. } => {
+            return (StatementClass::Ddl, vec![function.name.clone()]);
+        }
+        ExtendedStatement::CreateAggregate { aggregate, .. } => {
+            return (StatementClass::Ddl, vec![aggregate.name.clone()]);
+        }
+        ExtendedStatement::Grant(change) | ExtendedStatement::Revoke(change) => match &change.objects {
+            PrivilegeObjects::Tables(names) | PrivilegeObjects::Schemas(names) => {
+                names.iter().map(ToString::to_string).collect()
+            }
+        },
+        ExtendedStatement::AlterRole { role, .. } => vec![role.clone()],
+        ExtendedStatement::AlterSettings { owner, .. } => match owner {
+            SettingsOwner::Role(name) | SettingsOwner::Database(name) => vec![name.clone()],
+        },
+        ExtendedStatement::AlterDefaultPrivileges(change) => change.schemas.iter().map(ToString::to_string).collect(),
+    };
+    (StatementClass::Role, objects)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn role_overrides_default_classes() {
+        let policy = AuditPolicy::parse("write, ddl")
+            .and_then(|policy| policy.with_role("reporting", "all"))
+            .and_then(|policy| policy.with_role("service", "none"))
+            .expect("valid policy");
+
+        assert!(policy.audits("role_1", StatementClass::Write));
+        assert!(!policy.audits("role_1", StatementClass::Read));
+        assert!(policy.audits("reporting", StatementClass::Read));
+        assert!(!policy.audits("service", StatementClass::Ddl));
+    }
+
+    #[test]
+    fn unknown_class() {
+        assert_eq!(
+            AuditPolicy::parse("read, select"),
+            Err("unknown statement class \"select\"".to_owned())
+        );
+    }
+
+    #[test]
+    fn record_as_csv_line() {
+        let record = AuditRecord {
+            timestamp: UNIX_EPOCH + Duration::from_millis(1_600_000_000_123),
+            session_id: 7,
+            role: "role_1",
+            class: StatementClass::Write,
+            objects: &["schema_name.table_name".to_owned()],
+            statement: "insert into schema_name.table_name values ('a', \"b\")",
+        };
+
+        assert_eq!(
+            record.to_string(),
+            "1600000000123,7,role_1,WRITE,schema_name.table_name,\
+             \"insert into schema_name.table_name values ('a', \"\"b\"\")\""
+        );
+    }
+
+    #[test]
+    fn log_writes_record_per_line() {
+        let log = AuditLog::new(vec![]);
+        let record = AuditRecord {
+            timestamp: UNIX_EPOCH,
+            session_id: 1,
+            role: "role_1",
+            class: StatementClass::Misc,
+            objects: &[],
+            statement: "begin",
+        };
+        log.write(&record).expect("no io errors");
+        log.write(&record).expect("no io errors");
+
+        assert_eq!(
+            String::from_utf8(log.out.into_inner().expect("not poisoned")).expect("utf8"),
+            "0,1,role_1,MISC,,begin\n0,1,role_1,MISC,,begin\n"
+        );
+    }
+}
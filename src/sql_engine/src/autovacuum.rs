@@ -0,0 +1,151 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Automatic maintenance of tables. Each round looks at access statistics of
+//! all tables and vacuums a table once its dead rows exceed
+//! `vacuum_threshold + vacuum_scale_factor * n_live_tup`, it analyzes a table
+//! once its modified rows exceed the same analyze threshold. Tables tune or
+//! disable it with storage parameters, e.g.
+//! `CREATE TABLE s.t (...) WITH (autovacuum_vacuum_threshold = 10)`.
+use crate::catalog_manager::{AutovacuumOptions, CatalogManager, TableStatistics};
+use kernel::SystemResult;
+
+/// Thresholds of tables that do not set their own
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AutovacuumSettings {
+    pub vacuum_threshold: u64,
+    pub vacuum_scale_factor: f64,
+    pub analyze_threshold: u64,
+    pub analyze_scale_factor: f64,
+}
+
+impl Default for AutovacuumSettings {
+    fn default() -> AutovacuumSettings {
+        AutovacuumSettings {
+            vacuum_threshold: 50,
+            vacuum_scale_factor: 0.2,
+            analyze_threshold: 50,
+            analyze_scale_factor: 0.1,
+        }
+    }
+}
+
+/// Maintenance that a round of autovacuum did on a table
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Maintenance {
+    Vacuum,
+    Analyze,
+}
+
+/// Maintenance that the table is due for
+fn due(statistics: &TableStatistics, options: &AutovacuumOptions, settings: &AutovacuumSettings) -> Vec<Maintenance> {
+    if !options.enabled {
+        return vec![];
+    }
+    let exceeds = |counter: u64, threshold: u64, scale_factor: f64| {
+        counter as f64 > threshold as f64 + scale_factor * statistics.n_live_tup as f64
+    };
+    let mut due = vec![];
+    if exceeds(
+        statistics.n_dead_tup,
+        options.vacuum_threshold.unwrap_or(settings.vacuum_threshold),
+        options.vacuum_scale_factor.unwrap_or(settings.vacuum_scale_factor),
+    ) {
+        due.push(Maintenance::Vacuum);
+    }
+    if exceeds(
+        statistics.n_mod_since_analyze,
+        options.analyze_threshold.unwrap_or(settings.analyze_threshold),
+        options.analyze_scale_factor.unwrap_or(settings.analyze_scale_factor),
+    ) {
+        due.push(Maintenance::Analyze);
+    }
+    due
+}
+
+/// Vacuums and analyzes tables that exceed their thresholds, returns what
+/// was done to which table
+pub fn run(
+    storage: &CatalogManager,
+    settings: &AutovacuumSettings,
+) -> SystemResult<Vec<(String, String, Maintenance)>> {
+    let mut done = vec![];
+    for ((schema_name, table_name), statistics) in storage.statistics().all() {
+        let options = storage.storage_options().autovacuum(&schema_name, &table_name);
+        for maintenance in due(&statistics, &options, settings) {
+            match maintenance {
+                Maintenance::Vacuum => storage.autovacuum_table(&schema_name, &table_name)?,
+                Maintenance::Analyze => storage.autoanalyze_table(&schema_name, &table_name)?,
+            }
+            done.push((schema_name.clone(), table_name.clone(), maintenance));
+        }
+    }
+    Ok(done)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn statistics(n_live_tup: u64, n_dead_tup: u64, n_mod_since_analyze: u64) -> TableStatistics {
+        TableStatistics {
+            n_live_tup,
+            n_dead_tup,
+            n_mod_since_analyze,
+            ..TableStatistics::default()
+        }
+    }
+
+    #[test]
+    fn thresholds_grow_with_live_rows() {
+        let settings = AutovacuumSettings::default();
+        let options = AutovacuumOptions::default();
+
+        assert_eq!(due(&statistics(0, 50, 50), &options, &settings), vec![]);
+        assert_eq!(
+            due(&statistics(0, 51, 51), &options, &settings),
+            vec![Maintenance::Vacuum, Maintenance::Analyze]
+        );
+        assert_eq!(
+            due(&statistics(1000, 200, 200), &options, &settings),
+            vec![Maintenance::Analyze]
+        );
+    }
+
+    #[test]
+    fn table_options_override_settings() {
+        let settings = AutovacuumSettings::default();
+        let options = AutovacuumOptions {
+            vacuum_threshold: Some(0),
+            vacuum_scale_factor: Some(0.0),
+            ..AutovacuumOptions::default()
+        };
+
+        assert_eq!(
+            due(&statistics(1000, 1, 1), &options, &settings),
+            vec![Maintenance::Vacuum]
+        );
+        assert_eq!(
+            due(
+                &statistics(0, 100, 100),
+                &AutovacuumOptions {
+                    enabled: false,
+                    ..options
+                },
+                &settings
+            ),
+            vec![]
+        );
+    }
+}
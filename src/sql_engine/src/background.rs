@@ -0,0 +1,362 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tasks that run in threads of their own next to client sessions, e.g. the
+//! checkpointer, the statistics collector and workers that extensions
+//! register. `Workers` starts them and on shutdown asks them to stop and
+//! waits until they return:
+//!
+//! ```ignore
+//! let workers = Workers::new("database", storage.clone());
+//! workers.every("checkpointer", Duration::from_secs(300), |context: &WorkerContext| {
+//!     context.storage().checkpoint().map(|_flushed| ()).map_err(|error| error.to_string())
+//! })?;
+//! workers.shutdown(Some(Duration::from_secs(5)));
+//! ```
+use crate::{catalog_manager::CatalogManager, embedded::Database};
+use std::{
+    sync::{Arc, Condvar, Mutex},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Task that runs in its own thread until it is asked to stop
+pub trait BackgroundWorker: Send {
+    /// Runs the task, it should return soon after the context is stopped
+    fn run(self: Box<Self>, context: WorkerContext);
+}
+
+impl<F> BackgroundWorker for F
+where
+    F: FnOnce(WorkerContext) + Send,
+{
+    fn run(self: Box<Self>, context: WorkerContext) {
+        (*self)(context)
+    }
+}
+
+/// Task that `Workers::every` runs periodically, an error is logged and the
+/// task is run again after the next interval
+pub trait PeriodicTask: Send {
+    fn run(&mut self, context: &WorkerContext) -> Result<(), String>;
+}
+
+impl<F> PeriodicTask for F
+where
+    F: FnMut(&WorkerContext) -> Result<(), String> + Send,
+{
+    fn run(&mut self, context: &WorkerContext) -> Result<(), String> {
+        self(context)
+    }
+}
+
+impl PeriodicTask for Box<dyn PeriodicTask> {
+    fn run(&mut self, context: &WorkerContext) -> Result<(), String> {
+        (**self).run(context)
+    }
+}
+
+/// Lifecycle of a background worker
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WorkerState {
+    Running,
+    Finished,
+    Panicked,
+}
+
+/// Request to stop that wakes up workers waiting for their next round
+#[derive(Default)]
+struct StopSignal {
+    stopped: Mutex<bool>,
+    changed: Condvar,
+}
+
+impl StopSignal {
+    fn stop(&self) {
+        *self.stopped.lock().expect("to acquire stop lock") = true;
+        self.changed.notify_all();
+    }
+
+    fn is_stopped(&self) -> bool {
+        *self.stopped.lock().expect("to acquire stop lock")
+    }
+}
+
+/// Access of a background worker to the database it runs for
+pub struct WorkerContext {
+    storage: Arc<CatalogManager>,
+    signal: Arc<StopSignal>,
+}
+
+impl WorkerContext {
+    /// Database to connect to, e.g. to run maintenance statements
+    pub fn database(&self) -> Database {
+        Database::with_storage(self.storage.clone())
+    }
+
+    /// Storage of the database, e.g. to make written data durable
+    pub fn storage(&self) -> &CatalogManager {
+        &self.storage
+    }
+
+    /// Whether the worker is asked to stop and has to return
+    pub fn is_stopped(&self) -> bool {
+        self.signal.is_stopped()
+    }
+
+    /// Sleeps for `timeout` unless the worker is asked to stop earlier,
+    /// returns whether the worker can keep running
+    pub fn wait(&self, timeout: Duration) -> bool {
+        let stopped = self.signal.stopped.lock().expect("to acquire stop lock");
+        let (stopped, _timeout) = self
+            .signal
+            .changed
+            .wait_timeout_while(stopped, timeout, |stopped| !*stopped)
+            .expect("to acquire stop lock");
+        !*stopped
+    }
+}
+
+struct Worker {
+    name: String,
+    state: WorkerState,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Worker {
+    /// Collects the outcome of the worker once its thread returned
+    fn poll(&mut self) -> WorkerState {
+        if let Some(handle) = self.handle.take() {
+            if !handle.is_finished() {
+                self.handle = Some(handle);
+            } else if handle.join().is_ok() {
+                self.state = WorkerState::Finished;
+            } else {
+                log::error!("background worker {} panicked", self.name);
+                self.state = WorkerState::Panicked;
+            }
+        }
+        self.state
+    }
+}
+
+/// Background workers of a server or of an extension. Workers are asked to
+/// stop when it is dropped without waiting for them.
+pub struct Workers {
+    prefix: String,
+    storage: Arc<CatalogManager>,
+    signal: Arc<StopSignal>,
+    workers: Mutex<Vec<Worker>>,
+}
+
+impl Workers {
+    /// Workers whose threads are named with `prefix` followed by their name
+    pub fn new(prefix: &str, storage: Arc<CatalogManager>) -> Workers {
+        Workers {
+            prefix: prefix.to_owned(),
+            storage,
+            signal: Arc::default(),
+            workers: Mutex::default(),
+        }
+    }
+
+    /// Starts `worker` unless another worker with the same name is running
+    /// or the workers are shut down
+    pub fn start(&self, name: &str, worker: impl BackgroundWorker + 'static) -> Result<(), String> {
+        self.spawn(name, Box::new(worker))
+    }
+
+    /// Starts a worker that runs `task` each `interval` until it is asked to
+    /// stop
+    pub fn every(&self, name: &str, interval: Duration, mut task: impl PeriodicTask + 'static) -> Result<(), String> {
+        let thread_name = self.thread_name(name);
+        self.start(name, move |context: WorkerContext| {
+            while context.wait(interval) {
+                if let Err(error) = task.run(&context) {
+                    log::error!("background worker {} failed: {}", thread_name, error);
+                }
+            }
+        })
+    }
+
+    pub(crate) fn spawn(&self, name: &str, worker: Box<dyn BackgroundWorker>) -> Result<(), String> {
+        if self.signal.is_stopped() {
+            return Err(format!("background worker {} can't start after shutdown", name));
+        }
+        let mut workers = self.workers.lock().expect("to acquire workers lock");
+        if workers
+            .iter_mut()
+            .any(|running| running.name == name && running.poll() == WorkerState::Running)
+        {
+            return Err(format!("background worker {} is already running", name));
+        }
+        let context = WorkerContext {
+            storage: self.storage.clone(),
+            signal: self.signal.clone(),
+        };
+        let handle = thread::Builder::new()
+            .name(self.thread_name(name))
+            .spawn(move || worker.run(context))
+            .map_err(|error| format!("failed to start background worker {}: {}", name, error))?;
+        workers.retain(|worker| worker.name != name);
+        workers.push(Worker {
+            name: name.to_owned(),
+            state: WorkerState::Running,
+            handle: Some(handle),
+        });
+        Ok(())
+    }
+
+    /// Names of started workers with their current state
+    pub fn states(&self) -> Vec<(String, WorkerState)> {
+        self.workers
+            .lock()
+            .expect("to acquire workers lock")
+            .iter_mut()
+            .map(|worker| (worker.name.clone(), worker.poll()))
+            .collect()
+    }
+
+    /// Asks workers to stop and waits until they return, at most `timeout`
+    /// if it is set. Returns names of workers that are still running.
+    pub fn shutdown(&self, timeout: Option<Duration>) -> Vec<String> {
+        self.signal.stop();
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            let running = self
+                .states()
+                .into_iter()
+                .filter(|(_name, state)| *state == WorkerState::Running)
+                .map(|(name, _state)| name)
+                .collect::<Vec<String>>();
+            if running.is_empty() || deadline.map(|deadline| Instant::now() >= deadline).unwrap_or(false) {
+                return running;
+            }
+            thread::sleep(SHUTDOWN_POLL_INTERVAL);
+        }
+    }
+
+    fn thread_name(&self, name: &str) -> String {
+        format!("{}-{}", self.prefix, name)
+    }
+}
+
+impl Drop for Workers {
+    fn drop(&mut self) {
+        self.signal.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    fn workers() -> Workers {
+        Workers::new(
+            "test",
+            Arc::new(CatalogManager::in_memory().expect("to create catalog")),
+        )
+    }
+
+    #[test]
+    fn waiting_worker_is_woken_up_on_shutdown() {
+        let workers = workers();
+        let (started, worker_started) = mpsc::channel();
+        workers
+            .start("sleeper", move |context: WorkerContext| {
+                started.send(()).expect("test to wait");
+                while context.wait(Duration::from_secs(3600)) {}
+            })
+            .expect("worker to start");
+        worker_started.recv().expect("worker to start");
+
+        assert_eq!(workers.states(), vec![("sleeper".to_owned(), WorkerState::Running)]);
+        assert_eq!(workers.shutdown(Some(Duration::from_secs(10))), Vec::<String>::new());
+        assert_eq!(workers.states(), vec![("sleeper".to_owned(), WorkerState::Finished)]);
+    }
+
+    #[test]
+    fn periodic_task_runs_until_shutdown() {
+        let workers = workers();
+        let (ran, task_ran) = mpsc::channel();
+        workers
+            .every("ticker", Duration::from_millis(1), move |_context: &WorkerContext| {
+                ran.send(()).map_err(|error| error.to_string())
+            })
+            .expect("worker to start");
+        task_ran.recv().expect("task to run");
+        task_ran.recv().expect("task to run again");
+
+        assert_eq!(workers.shutdown(None), Vec::<String>::new());
+    }
+
+    #[test]
+    fn worker_names_are_unique_while_running() {
+        let workers = workers();
+        workers
+            .start(
+                "worker",
+                |context: WorkerContext| while context.wait(Duration::from_secs(3600)) {},
+            )
+            .expect("worker to start");
+
+        assert_eq!(
+            workers.start("worker", |_context: WorkerContext| {}),
+            Err("background worker worker is already running".to_owned())
+        );
+    }
+
+    #[test]
+    fn workers_do_not_start_after_shutdown() {
+        let workers = workers();
+        workers.shutdown(None);
+
+        assert_eq!(
+            workers.start("late", |_context: WorkerContext| {}),
+            Err("background worker late can't start after shutdown".to_owned())
+        );
+    }
+
+    #[test]
+    fn panicked_worker() {
+        let workers = workers();
+        workers
+            .start("failing", |_context: WorkerContext| panic!("worker failed"))
+            .expect("worker to start");
+
+        assert_eq!(workers.shutdown(None), Vec::<String>::new());
+        assert_eq!(workers.states(), vec![("failing".to_owned(), WorkerState::Panicked)]);
+    }
+
+    #[test]
+    fn shutdown_gives_up_on_workers_that_do_not_stop() {
+        let workers = workers();
+        let (release, released) = mpsc::channel::<()>();
+        workers
+            .start("stubborn", move |_context: WorkerContext| {
+                released.recv().ok();
+            })
+            .expect("worker to start");
+
+        assert_eq!(
+            workers.shutdown(Some(Duration::from_millis(20))),
+            vec!["stubborn".to_owned()]
+        );
+        drop(release);
+        assert_eq!(workers.shutdown(None), Vec::<String>::new());
+    }
+}
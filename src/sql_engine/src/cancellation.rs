@@ -0,0 +1,261 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! Interruption of running statements. Every session owns a token that
+//! operators check while they process rows, the statement stops with an
+//! error once the token is cancelled by a query cancel request, by
+//! `statement_timeout` or by shutdown of the server.
+use protocol::results::QueryError;
+use std::{
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+use storage::ReadCursor;
+
+/// Number of rows a scan reads between checks of its token
+const CHECK_INTERVAL: usize = 256;
+/// The longest time a sleeping statement does not notice cancellation
+const SLEEP_INTERVAL: Duration = Duration::from_millis(10);
+
+const RUNNING: u8 = 0;
+const USER_REQUEST: u8 = 1;
+const STATEMENT_TIMEOUT: u8 = 2;
+const SHUTDOWN: u8 = 3;
+
+/// Why a running statement has to stop
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Interruption {
+    /// client asked to cancel the statement
+    UserRequest,
+    /// statement ran longer than `statement_timeout`
+    StatementTimeout,
+    /// server shuts down
+    Shutdown,
+}
+
+impl Interruption {
+    fn code(self) -> u8 {
+        match self {
+            Interruption::UserRequest => USER_REQUEST,
+            Interruption::StatementTimeout => STATEMENT_TIMEOUT,
+            Interruption::Shutdown => SHUTDOWN,
+        }
+    }
+
+    fn from_code(code: u8) -> Option<Interruption> {
+        match code {
+            USER_REQUEST => Some(Interruption::UserRequest),
+            STATEMENT_TIMEOUT => Some(Interruption::StatementTimeout),
+            SHUTDOWN => Some(Interruption::Shutdown),
+            _ => None,
+        }
+    }
+
+    /// Error that is sent to the client of the interrupted statement
+    pub fn error(self) -> QueryError {
+        match self {
+            Interruption::UserRequest => QueryError::query_canceled("user request"),
+            Interruption::StatementTimeout => QueryError::query_canceled("statement timeout"),
+            Interruption::Shutdown => QueryError::admin_shutdown(),
+        }
+    }
+}
+
+/// Cancellation state of a session that is shared between the session and
+/// whoever can interrupt its statements. Clones refer to the same state.
+#[derive(Clone, Default)]
+pub struct CancellationToken {
+    state: Arc<State>,
+    /// token of the session that runs the statement of this one, e.g. the
+    /// `DO` block of a nested session
+    parent: Option<Box<CancellationToken>>,
+}
+
+#[derive(Default)]
+struct State {
+    interruption: AtomicU8,
+    deadline: Mutex<Option<Instant>>,
+}
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Token that is cancelled together with this one, statements started
+    /// with the child token don't reset cancellation of this one
+    pub(crate) fn child(&self) -> CancellationToken {
+        CancellationToken {
+            state: Arc::default(),
+            parent: Some(Box::new(self.clone())),
+        }
+    }
+
+    /// Interrupts the running statement. Shutdown interrupts all the
+    /// following statements too and can't be overridden by other reasons.
+    pub fn cancel(&self, interruption: Interruption) {
+        let code = interruption.code();
+        let _ = self
+            .state
+            .interruption
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |current| {
+                if current == SHUTDOWN {
+                    None
+                } else {
+                    Some(code)
+                }
+            });
+    }
+
+    /// Reason why the running statement has to stop, if it has to
+    pub fn interruption(&self) -> Option<Interruption> {
+        if let Some(interruption) = Interruption::from_code(self.state.interruption.load(Ordering::SeqCst)) {
+            return Some(interruption);
+        }
+        let deadline = *self.state.deadline.lock().expect("to acquire lock");
+        match deadline {
+            Some(deadline) if Instant::now() >= deadline => {
+                self.cancel(Interruption::StatementTimeout);
+                Interruption::from_code(self.state.interruption.load(Ordering::SeqCst))
+            }
+            _ => self.parent.as_ref().and_then(|parent| parent.interruption()),
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.interruption().is_some()
+    }
+
+    /// Error of the interruption if the statement has to stop
+    pub(crate) fn check(&self) -> Result<(), QueryError> {
+        match self.interruption() {
+            Some(interruption) => Err(interruption.error()),
+            None => Ok(()),
+        }
+    }
+
+    /// Starts the next statement that is cancelled after `timeout`, zero
+    /// timeout never cancels it. Cancellation of the previous statement is
+    /// forgotten unless the server shuts down.
+    pub(crate) fn start(&self, timeout: Option<Duration>) {
+        let _ = self
+            .state
+            .interruption
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |current| {
+                if current == SHUTDOWN {
+                    None
+                } else {
+                    Some(RUNNING)
+                }
+            });
+        *self.state.deadline.lock().expect("to acquire lock") = timeout
+            .filter(|timeout| *timeout > Duration::from_millis(0))
+            .map(|timeout| Instant::now() + timeout);
+    }
+
+    /// Ends rows of `cursor` once the token is cancelled, the caller has to
+    /// `check` the token after the cursor is exhausted
+    pub(crate) fn guard(&self, cursor: ReadCursor) -> ReadCursor {
+        let token = self.clone();
+        let mut read = 0;
+        Box::new(cursor.take_while(move |_row| {
+            read += 1;
+            read % CHECK_INTERVAL != 0 || !token.is_cancelled()
+        }))
+    }
+
+    /// Sleeps for `duration` unless the statement is interrupted earlier
+    pub(crate) fn sleep(&self, duration: Duration) -> Result<(), QueryError> {
+        let wake_up = Instant::now() + duration;
+        loop {
+            self.check()?;
+            let now = Instant::now();
+            if now >= wake_up {
+                return Ok(());
+            }
+            thread::sleep(SLEEP_INTERVAL.min(wake_up - now));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use representation::Binary;
+
+    #[test]
+    fn statement_is_cancelled_until_the_next_one_starts() {
+        let token = CancellationToken::new();
+        assert_eq!(token.check(), Ok(()));
+
+        token.cancel(Interruption::UserRequest);
+        assert_eq!(token.check(), Err(QueryError::query_canceled("user request")));
+
+        token.start(None);
+        assert_eq!(token.check(), Ok(()));
+    }
+
+    #[test]
+    fn shutdown_cancels_all_statements() {
+        let token = CancellationToken::new();
+        token.cancel(Interruption::Shutdown);
+        token.cancel(Interruption::UserRequest);
+        token.start(None);
+
+        assert_eq!(token.interruption(), Some(Interruption::Shutdown));
+    }
+
+    #[test]
+    fn statement_timeout() {
+        let token = CancellationToken::new();
+        token.start(Some(Duration::from_millis(1)));
+
+        assert_eq!(
+            token.sleep(Duration::from_secs(10)),
+            Err(QueryError::query_canceled("statement timeout"))
+        );
+
+        token.start(Some(Duration::from_millis(0)));
+        assert_eq!(token.sleep(Duration::from_millis(1)), Ok(()));
+    }
+
+    #[test]
+    fn child_is_cancelled_with_its_parent() {
+        let parent = CancellationToken::new();
+        let child = parent.child();
+        child.start(None);
+        parent.cancel(Interruption::UserRequest);
+
+        assert_eq!(child.interruption(), Some(Interruption::UserRequest));
+
+        child.start(None);
+        assert_eq!(parent.interruption(), Some(Interruption::UserRequest));
+    }
+
+    #[test]
+    fn guarded_cursor_stops_once_cancelled() {
+        let token = CancellationToken::new();
+        let rows = (0..CHECK_INTERVAL * 4).map(|_index| Ok(Ok((Binary::new(), Binary::new()))));
+        let mut cursor = token.guard(Box::new(rows));
+        for _ in 0..CHECK_INTERVAL {
+            assert!(cursor.next().is_some());
+        }
+        token.cancel(Interruption::UserRequest);
+
+        assert_eq!(cursor.count(), CHECK_INTERVAL - 1);
+    }
+}
@@ -0,0 +1,197 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    catalog_manager::{
+        functions::{function_values, stored_function},
+        system_tables::{SystemRecord, SystemTable},
+    },
+    procedural::{Aggregate, Function},
+};
+use kernel::{SystemError, SystemResult};
+use protocol::sql_types::PostgreSqlType;
+use representation::Datum;
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    sync::{Arc, RwLock},
+};
+
+/// Table of the system catalog with aggregate functions and their owners
+pub(crate) const AGGREGATES_TABLE: &str = "AGGREGATES";
+
+struct StoredAggregate {
+    owner: String,
+    aggregate: Arc<Aggregate>,
+}
+
+/// Aggregate functions with their owners.
+///
+/// Every change is saved in the `AGGREGATES` table of the system catalog, a
+/// record per aggregate with its owner, types, initial condition and its
+/// transition and final functions. Functions are saved with the aggregate,
+/// as it keeps working with them after they are dropped or replaced.
+/// Aggregates of native extensions are not saved, they are registered again
+/// when extensions are loaded.
+#[derive(Default)]
+pub(crate) struct Aggregates {
+    aggregates: RwLock<HashMap<String, StoredAggregate>>,
+    store: SystemTable,
+}
+
+impl Aggregates {
+    /// Aggregates saved in `store`
+    pub(crate) fn load(store: SystemTable) -> SystemResult<Aggregates> {
+        let mut aggregates = HashMap::new();
+        let mut damaged = None;
+        store.load(|key, values| {
+            let name = key[0].as_str().to_owned();
+            match stored_aggregate(&name, values) {
+                Some(aggregate) => {
+                    aggregates.insert(
+                        name,
+                        StoredAggregate {
+                            owner: values[0].as_str().to_owned(),
+                            aggregate: Arc::new(aggregate),
+                        },
+                    );
+                }
+                None => damaged = Some(name),
+            }
+        })?;
+        if let Some(name) = damaged {
+            return Err(SystemError::corruption(format!(
+                "aggregate {} can't be read, restore the data directory from a backup",
+                name
+            )));
+        }
+        Ok(Aggregates {
+            aggregates: RwLock::new(aggregates),
+            store,
+        })
+    }
+
+    fn save(&self) -> SystemResult<()> {
+        self.store.save(|| {
+            self.aggregates
+                .read()
+                .expect("to acquire read lock")
+                .iter()
+                .filter_map(|(name, stored)| aggregate_record(name, stored))
+                .collect()
+        })
+    }
+
+    /// Returns `false` if an aggregate with the same name exists and is not
+    /// replaced, a replaced aggregate keeps its owner
+    pub(crate) fn create(&self, aggregate: Aggregate, owner: &str, replace: bool) -> SystemResult<bool> {
+        {
+            let mut aggregates = self.aggregates.write().expect("to acquire write lock");
+            let owner = match aggregates.get(&aggregate.name) {
+                Some(_) if !replace => return Ok(false),
+                Some(existing) => existing.owner.clone(),
+                None => owner.to_owned(),
+            };
+            aggregates.insert(
+                aggregate.name.clone(),
+                StoredAggregate {
+                    owner,
+                    aggregate: Arc::new(aggregate),
+                },
+            );
+        }
+        self.save().map(|()| true)
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<Arc<Aggregate>> {
+        self.aggregates
+            .read()
+            .expect("to acquire read lock")
+            .get(name)
+            .map(|stored| stored.aggregate.clone())
+    }
+
+    pub(crate) fn owner(&self, name: &str) -> Option<String> {
+        self.aggregates
+            .read()
+            .expect("to acquire read lock")
+            .get(name)
+            .map(|stored| stored.owner.clone())
+    }
+}
+
+/// Record of `AGGREGATES_TABLE` keyed by the name of the aggregate, values
+/// are the owner, type OIDs of the input and the state, the initial
+/// condition or `NULL`, then the transition and the final function, each as
+/// its name, the number of its values and values of the function
+fn aggregate_record(name: &str, stored: &StoredAggregate) -> Option<SystemRecord> {
+    let aggregate = &stored.aggregate;
+    let mut values = vec![
+        Datum::from_string(stored.owner.clone()),
+        Datum::from_u64(aggregate.input.pg_oid() as u64),
+        Datum::from_u64(aggregate.state.pg_oid() as u64),
+        match &aggregate.initial {
+            Some(initial) => Datum::from_string(initial.clone()),
+            None => Datum::from_null(),
+        },
+    ];
+    for function in std::iter::once(&aggregate.transition).chain(&aggregate.finish) {
+        let function_values = function_values(function)?;
+        values.push(Datum::from_string(function.name.clone()));
+        values.push(Datum::from_u64(function_values.len() as u64));
+        values.extend(function_values);
+    }
+    Some((vec![Datum::from_string(name.to_owned())], values))
+}
+
+/// Aggregate of a record of `AGGREGATES_TABLE`, `None` if the record can't
+/// be read
+fn stored_aggregate(name: &str, values: &[Datum]) -> Option<Aggregate> {
+    if values.len() < 4 {
+        return None;
+    }
+    let initial = match &values[3] {
+        Datum::Null => None,
+        initial => Some(initial.as_str().to_owned()),
+    };
+    let (transition, rest) = embedded_function(&values[4..])?;
+    let finish = match rest {
+        [] => None,
+        rest => match embedded_function(rest)? {
+            (finish, []) => Some(Arc::new(finish)),
+            _ => return None,
+        },
+    };
+    Some(Aggregate {
+        name: name.to_owned(),
+        input: PostgreSqlType::try_from(values[1].as_u64() as u32).ok()?,
+        state: PostgreSqlType::try_from(values[2].as_u64() as u32).ok()?,
+        initial,
+        transition: Arc::new(transition),
+        finish,
+    })
+}
+
+/// Function at the start of `values` with values after it
+fn embedded_function<'v, 'd>(values: &'v [Datum<'d>]) -> Option<(Function, &'v [Datum<'d>])> {
+    if values.len() < 2 {
+        return None;
+    }
+    let size = values[1].as_u64() as usize;
+    if values.len() < 2 + size {
+        return None;
+    }
+    let (function_values, rest) = values[2..].split_at(size);
+    Some((stored_function(values[0].as_str(), function_values)?, rest))
+}
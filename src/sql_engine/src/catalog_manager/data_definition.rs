@@ -13,10 +13,23 @@
 // limitations under the License.
 
 use crate::{
-    catalog_manager::{DropSchemaError, DropStrategy},
+    catalog_manager::{
+        aggregates::AGGREGATES_TABLE,
+        default_settings::SETTINGS_TABLE,
+        event_triggers::EVENT_TRIGGERS_TABLE,
+        format,
+        functions::FUNCTIONS_TABLE,
+        large_objects::{LARGE_OBJECTS_TABLE, LARGE_OBJECT_METADATA_TABLE},
+        privileges::PRIVILEGES_TABLE,
+        publications::{CHANGE_LOG_TABLE, PUBLICATIONS_TABLE, REPLICATION_SLOTS_TABLE},
+        roles::ROLES_TABLE,
+        stored,
+        system_tables::SystemTable,
+        DropSchemaError, DropStrategy,
+    },
     ColumnDefinition,
 };
-use kernel::{SystemError, SystemResult};
+use kernel::{Object, Operation, SystemError, SystemResult};
 use representation::{Binary, Datum};
 use sql_types::SqlType;
 use std::{
@@ -27,7 +40,7 @@ use std::{
         Arc, RwLock,
     },
 };
-use storage::{Database, InitStatus, PersistentDatabase};
+use storage::{Database, DefinitionError, InitStatus, PersistentDatabase};
 
 const SYSTEM_CATALOG: &'_ str = "system";
 // CREATE SCHEMA DEFINITION_SCHEMA
@@ -266,6 +279,21 @@ const TABLES_TABLE: &'_ str = "TABLES";
 //         )
 // )
 const COLUMNS_TABLE: &'_ str = "COLUMNS";
+const DEFINITION_TABLES: [&str; 4] = [CATALOG_NAMES_TABLE, SCHEMATA_TABLE, TABLES_TABLE, COLUMNS_TABLE];
+/// Tables of objects that are kept in memory and saved with `SystemTable`
+const OBJECT_TABLES: [&str; 11] = [
+    PRIVILEGES_TABLE,
+    ROLES_TABLE,
+    SETTINGS_TABLE,
+    FUNCTIONS_TABLE,
+    PUBLICATIONS_TABLE,
+    REPLICATION_SLOTS_TABLE,
+    CHANGE_LOG_TABLE,
+    LARGE_OBJECT_METADATA_TABLE,
+    LARGE_OBJECTS_TABLE,
+    EVENT_TRIGGERS_TABLE,
+    AGGREGATES_TABLE,
+];
 
 #[allow(dead_code)]
 fn catalog_names_types() -> [ColumnDefinition; 1] {
@@ -513,6 +541,18 @@ impl Table {
             .insert(column_id, column_definition);
     }
 
+    /// Replaces definitions of all columns keeping their ids and order
+    fn replace_columns(&self, column_definitions: &[ColumnDefinition]) -> Vec<(InnerId, ColumnDefinition)> {
+        let mut columns = self.columns.write().expect("to acquire write lock");
+        for (definition, new_definition) in columns.values_mut().zip(column_definitions) {
+            *definition = new_definition.clone();
+        }
+        columns
+            .iter()
+            .map(|(id, definition)| (*id, definition.clone()))
+            .collect()
+    }
+
     fn columns(&self) -> Vec<(InnerId, ColumnDefinition)> {
         self.columns
             .read()
@@ -523,6 +563,113 @@ impl Table {
     }
 }
 
+/// Record of `COLUMNS_TABLE` keyed by ids of the catalog, the schema, the
+/// table and the column
+fn column_record(
+    (catalog_id, schema_id, table_id, column_id): (InnerId, InnerId, InnerId, InnerId),
+    (catalog_name, schema_name, table_name): (&str, &str, &str),
+    column: &ColumnDefinition,
+) -> (Binary, Binary) {
+    (
+        Binary::pack(&[
+            Datum::from_u64(catalog_id),
+            Datum::from_u64(schema_id),
+            Datum::from_u64(table_id),
+            Datum::from_u64(column_id),
+        ]),
+        format::seal(&[
+            Datum::from_str(catalog_name),
+            Datum::from_str(schema_name),
+            Datum::from_str(table_name),
+            Datum::from_str(column.name().as_str()),
+            Datum::from_sql_type(column.sql_type()),
+            Datum::UInt64(column_id),
+            match column.default() {
+                Some(default) => Datum::from_str(default),
+                None => Datum::Null,
+            },
+            Datum::from_bool(column.is_not_null()),
+        ]),
+    )
+}
+
+/// Columns saved before defaults and nullability were recorded have neither
+/// until the system catalog is upgraded
+fn column_definition(data: &[Datum]) -> ColumnDefinition {
+    let default = match data.get(6) {
+        Some(Datum::Null) | None => None,
+        Some(default) => Some(default.as_str().to_owned()),
+    };
+    let not_null = matches!(data.get(7), Some(Datum::True));
+    ColumnDefinition::new(data[3].as_str(), data[4].as_sql_type())
+        .with_default(default)
+        .with_not_null(not_null)
+}
+
+/// Rewrites records of the system catalog of an older format version in the
+/// current one and checks that none of its records are damaged
+fn upgrade(system_catalog: &dyn Database) -> SystemResult<()> {
+    let version = format::version(system_catalog, DEFINITION_SCHEMA)?;
+    if version < format::FORMAT_VERSION {
+        if version < format::SEALED_VERSION {
+            seal_records(system_catalog)?;
+        }
+        create_object_tables(system_catalog)?;
+        format::set_version(system_catalog, DEFINITION_SCHEMA)?;
+        log::info!(
+            "system catalog is upgraded from format version {} to {}",
+            version,
+            format::FORMAT_VERSION
+        );
+    }
+    format::verify(system_catalog, DEFINITION_SCHEMA, &DEFINITION_TABLES)?;
+    format::verify(system_catalog, DEFINITION_SCHEMA, &OBJECT_TABLES)
+}
+
+/// Creates tables of objects that data directories of older versions do not
+/// have, tables that exist are left as they are
+fn create_object_tables(system_catalog: &dyn Database) -> SystemResult<()> {
+    for table in &OBJECT_TABLES {
+        match system_catalog.create_object(DEFINITION_SCHEMA, table) {
+            Ok(Ok(Err(DefinitionError::ObjectAlreadyExists))) => {}
+            result => stored(result, Operation::Create, Object::Table(DEFINITION_SCHEMA, table))?,
+        }
+    }
+    Ok(())
+}
+
+/// Adds checksums to records without them, columns get their defaults and
+/// nullability. Records that already have checksums are left as they are,
+/// so an interrupted upgrade can be run again
+fn seal_records(system_catalog: &dyn Database) -> SystemResult<()> {
+    for table in &[CATALOG_NAMES_TABLE, SCHEMATA_TABLE, TABLES_TABLE, COLUMNS_TABLE] {
+        let sealed = format::records(system_catalog, DEFINITION_SCHEMA, table)?
+            .into_iter()
+            .filter(|(_key, values)| !format::is_intact(values))
+            .map(|(key, values)| {
+                let data = values.unpack();
+                if *table != COLUMNS_TABLE {
+                    return (key, format::seal(&data));
+                }
+                let ids = key.unpack();
+                column_record(
+                    (ids[0].as_u64(), ids[1].as_u64(), ids[2].as_u64(), ids[3].as_u64()),
+                    (data[0].as_str(), data[1].as_str(), data[2].as_str()),
+                    &column_definition(&data),
+                )
+            })
+            .collect::<Vec<_>>();
+        if !sealed.is_empty() {
+            stored(
+                system_catalog.write(DEFINITION_SCHEMA, table, sealed),
+                Operation::Access,
+                Object::Table(DEFINITION_SCHEMA, table),
+            )?;
+        }
+    }
+    Ok(())
+}
+
 #[derive(Debug, PartialEq)]
 pub(crate) enum DropCatalogError {
     DoesNotExist,
@@ -532,7 +679,7 @@ pub(crate) enum DropCatalogError {
 pub(crate) struct DataDefinition {
     catalog_ids: AtomicU64,
     catalogs: RwLock<HashMap<Name, Arc<Catalog>>>,
-    system_catalog: Option<Box<dyn Database>>,
+    system_catalog: Option<Arc<dyn Database + Send + Sync>>,
 }
 
 impl DataDefinition {
@@ -548,6 +695,7 @@ impl DataDefinition {
         let system_catalog = PersistentDatabase::new(path.join(SYSTEM_CATALOG));
         let (catalogs, catalog_ids) = match system_catalog.init(DEFINITION_SCHEMA) {
             Ok(Ok(InitStatus::Loaded)) => {
+                upgrade(&system_catalog)?;
                 let mut max_id = 0;
                 let catalogs = system_catalog
                     .read(DEFINITION_SCHEMA, CATALOG_NAMES_TABLE)
@@ -586,21 +734,30 @@ impl DataDefinition {
                     .expect("no io error")
                     .expect("no platform error")
                     .expect("table COLUMNS is created");
+                create_object_tables(&system_catalog)?;
+                format::set_version(&system_catalog, DEFINITION_SCHEMA)?;
                 (HashMap::new(), 0)
             }
-            _ => {
-                return Err(SystemError::runtime_check_failure(
-                    "No Path in SledDatabaseCatalog".to_owned(),
-                ))
-            }
+            _ => return Err(SystemError::internal("No Path in SledDatabaseCatalog".to_owned())),
         };
         Ok(DataDefinition {
             catalog_ids: AtomicU64::new(catalog_ids),
             catalogs: RwLock::new(catalogs),
-            system_catalog: Some(Box::new(system_catalog)),
+            system_catalog: Some(Arc::new(system_catalog)),
         })
     }
 
+    /// Table of the system catalog for objects that are kept in memory, one
+    /// of `OBJECT_TABLES` that are created when the data directory is created
+    /// or upgraded
+    pub(crate) fn system_table(&self, table_name: &'static str) -> SystemTable {
+        debug_assert!(OBJECT_TABLES.contains(&table_name));
+        match self.system_catalog.as_ref() {
+            Some(system_catalog) => SystemTable::new(system_catalog.clone(), DEFINITION_SCHEMA, table_name),
+            None => SystemTable::default(),
+        }
+    }
+
     pub(crate) fn create_catalog(&self, catalog_name: &str) {
         let catalog_id = self.catalog_ids.fetch_add(1, Ordering::SeqCst);
         self.catalogs
@@ -614,7 +771,7 @@ impl DataDefinition {
                     CATALOG_NAMES_TABLE,
                     vec![(
                         Binary::pack(&[Datum::from_u64(catalog_id)]),
-                        Binary::pack(&[Datum::from_str(catalog_name)]),
+                        format::seal(&[Datum::from_str(catalog_name)]),
                     )],
                 )
                 .expect("no io error")
@@ -759,7 +916,7 @@ impl DataDefinition {
                     SCHEMATA_TABLE,
                     vec![(
                         Binary::pack(&[Datum::from_u64(catalog.id()), Datum::from_u64(schema_id)]),
-                        Binary::pack(&[Datum::from_str(catalog_name), Datum::from_str(schema_name)]),
+                        format::seal(&[Datum::from_str(catalog_name), Datum::from_str(schema_name)]),
                     )],
                 )
                 .expect("no io error")
@@ -1025,17 +1182,12 @@ impl DataDefinition {
                                     let data = data.unpack();
                                     let schema = data[1].as_str().to_owned();
                                     let table = data[2].as_str().to_owned();
-                                    let column = data[3].as_str().to_owned();
-                                    let sql_type = data[4].as_sql_type();
+                                    let column = column_definition(&data);
                                     max_id = max_id.max(id);
-                                    (id, schema, table, column, sql_type)
-                                })
-                                .filter(|(_id, schema, table, _column, _sql_type)| {
-                                    schema == schema_name && table == table_name
-                                })
-                                .map(|(id, _schema, _table, column, sql_type)| {
-                                    (id, ColumnDefinition::new(column.as_str(), sql_type))
+                                    (id, schema, table, column)
                                 })
+                                .filter(|(_id, schema, table, _column)| schema == schema_name && table == table_name)
+                                .map(|(id, _schema, _table, column)| (id, column))
                                 .collect::<BTreeMap<_, _>>();
                             schema.add_table(table_id, table_name, table_columns, max_id);
                             Some(table_id)
@@ -1080,7 +1232,7 @@ impl DataDefinition {
                             Datum::from_u64(schema.id()),
                             Datum::from_u64(created_table.id()),
                         ]),
-                        Binary::pack(&[
+                        format::seal(&[
                             Datum::from_str(catalog_name),
                             Datum::from_str(schema_name),
                             Datum::from_str(table_name),
@@ -1095,21 +1247,10 @@ impl DataDefinition {
                     .write(
                         DEFINITION_SCHEMA,
                         COLUMNS_TABLE,
-                        vec![(
-                            Binary::pack(&[
-                                Datum::from_u64(catalog.id()),
-                                Datum::from_u64(schema.id()),
-                                Datum::from_u64(created_table.id()),
-                                Datum::from_u64(id),
-                            ]),
-                            Binary::pack(&[
-                                Datum::from_str(catalog_name),
-                                Datum::from_str(schema_name),
-                                Datum::from_str(table_name),
-                                Datum::from_str(column.name().as_str()),
-                                Datum::from_sql_type(column.sql_type()),
-                                Datum::UInt64(id),
-                            ]),
+                        vec![column_record(
+                            (catalog.id(), schema.id(), created_table.id(), id),
+                            (catalog_name, schema_name, table_name),
+                            &column,
                         )],
                     )
                     .expect("no io error")
@@ -1119,6 +1260,50 @@ impl DataDefinition {
         }
     }
 
+    /// Replaces definitions of all columns of a table, e.g. to change their
+    /// defaults or nullability
+    pub(crate) fn alter_columns(
+        &self,
+        catalog_name: &str,
+        schema_name: &str,
+        table_name: &str,
+        column_definitions: &[ColumnDefinition],
+    ) {
+        let catalog = match self.catalog(catalog_name) {
+            Some(catalog) => catalog,
+            None => return,
+        };
+        let schema = match catalog.schema(schema_name) {
+            Some(schema) => schema,
+            None => return,
+        };
+        let table = match schema.table(table_name) {
+            Some(table) => table,
+            None => return,
+        };
+        let columns = table.replace_columns(column_definitions);
+        if let Some(system_catalog) = self.system_catalog.as_ref() {
+            system_catalog
+                .write(
+                    DEFINITION_SCHEMA,
+                    COLUMNS_TABLE,
+                    columns
+                        .iter()
+                        .map(|(id, column)| {
+                            column_record(
+                                (catalog.id(), schema.id(), table.id(), *id),
+                                (catalog_name, schema_name, table_name),
+                                column,
+                            )
+                        })
+                        .collect(),
+                )
+                .expect("no io error")
+                .expect("no platform error")
+                .expect("to save columns");
+        }
+    }
+
     pub(crate) fn drop_table(&self, catalog_name: &str, schema_name: &str, table_name: &str) {
         let catalog = match self.catalog(catalog_name) {
             Some(catalog) => catalog,
@@ -1187,16 +1372,15 @@ impl DataDefinition {
                         let id = record_id.unpack()[3].as_u64();
                         let data = data.unpack();
                         let schema = data[1].as_str().to_owned();
-                        let table = data[2].as_str().to_owned();
-                        let column = data[3].as_str().to_owned();
-                        let sql_type = data[4].as_sql_type();
+                        let table_of_column = data[2].as_str().to_owned();
+                        let column = column_definition(&data);
                         max_id = max_id.max(id);
-                        (id, schema, table, column, sql_type)
+                        (id, schema, table_of_column, column)
                     })
-                    .filter(|(_id, schema, _table, _column, _sql_type)| schema == schema_name)
-                    .map(|(id, _schema, _table, column, sql_type)| {
-                        (id, ColumnDefinition::new(column.as_str(), sql_type))
+                    .filter(|(_id, schema, table_of_column, _column)| {
+                        schema == schema_name && *table_of_column == table
                     })
+                    .map(|(id, _schema, _table, column)| (id, column))
                     .collect::<BTreeMap<_, _>>();
                 schema.add_table(table_id, table.as_str(), table_columns, max_id);
             }
@@ -1489,6 +1673,7 @@ mod tests {
     #[cfg(test)]
     mod persistent {
         use super::*;
+        use std::path::Path;
 
         #[rstest::fixture]
         fn storage_path() -> (DataDefinition, PathBuf) {
@@ -1801,6 +1986,38 @@ mod tests {
             ));
         }
 
+        #[rstest::rstest]
+        fn altered_columns_preserved_after_restart(storage_path: (DataDefinition, PathBuf)) {
+            let (data_definition, path) = storage_path;
+            data_definition.create_catalog("catalog_name");
+            data_definition.create_schema("catalog_name", "schema_name");
+            data_definition.create_table(
+                "catalog_name",
+                "schema_name",
+                "table_name",
+                &[
+                    ColumnDefinition::new("col_1", SqlType::SmallInt(0)),
+                    ColumnDefinition::new("col_2", SqlType::Integer(0)),
+                ],
+            );
+            let altered = vec![
+                ColumnDefinition::new("col_1", SqlType::SmallInt(0)).with_not_null(true),
+                ColumnDefinition::new("col_2", SqlType::Integer(0)).with_default(Some("10".to_owned())),
+            ];
+            data_definition.alter_columns("catalog_name", "schema_name", "table_name", &altered);
+            assert_eq!(
+                data_definition.table_columns("catalog_name", "schema_name", "table_name"),
+                altered
+            );
+            drop(data_definition);
+
+            let data_definition = DataDefinition::persistent(&path).expect("create persistent data definition");
+            assert_eq!(
+                data_definition.table_columns("catalog_name", "schema_name", "table_name"),
+                altered
+            );
+        }
+
         #[rstest::rstest]
         fn table_columns_data_preserved_after_restart(storage_path: (DataDefinition, PathBuf)) {
             let (data_definition, path) = storage_path;
@@ -2177,5 +2394,238 @@ mod tests {
                 vec![]
             );
         }
+
+        fn legacy_system_catalog(path: &Path) -> PersistentDatabase {
+            let system_catalog = PersistentDatabase::new(path.join(SYSTEM_CATALOG));
+            assert!(matches!(
+                system_catalog.init(DEFINITION_SCHEMA),
+                Ok(Ok(InitStatus::Created))
+            ));
+            for table in &DEFINITION_TABLES {
+                system_catalog
+                    .create_object(DEFINITION_SCHEMA, table)
+                    .expect("no io error")
+                    .expect("no platform error")
+                    .expect("table is created");
+            }
+            system_catalog
+        }
+
+        fn write(system_catalog: &PersistentDatabase, table: &str, key: &[Datum], values: &[Datum]) {
+            system_catalog
+                .write(
+                    DEFINITION_SCHEMA,
+                    table,
+                    vec![(Binary::pack(key), Binary::pack(values))],
+                )
+                .expect("no io error")
+                .expect("no platform error")
+                .expect("to write record");
+        }
+
+        #[rstest::rstest]
+        fn records_without_checksums_are_upgraded() {
+            let path = tempfile::tempdir().expect("to create temporary folder").into_path();
+            let system_catalog = legacy_system_catalog(&path);
+            write(
+                &system_catalog,
+                CATALOG_NAMES_TABLE,
+                &[Datum::from_u64(0)],
+                &[Datum::from_str("catalog_name")],
+            );
+            write(
+                &system_catalog,
+                SCHEMATA_TABLE,
+                &[Datum::from_u64(0), Datum::from_u64(0)],
+                &[Datum::from_str("catalog_name"), Datum::from_str("schema_name")],
+            );
+            write(
+                &system_catalog,
+                TABLES_TABLE,
+                &[Datum::from_u64(0), Datum::from_u64(0), Datum::from_u64(0)],
+                &[
+                    Datum::from_str("catalog_name"),
+                    Datum::from_str("schema_name"),
+                    Datum::from_str("table_name"),
+                ],
+            );
+            write(
+                &system_catalog,
+                COLUMNS_TABLE,
+                &[
+                    Datum::from_u64(0),
+                    Datum::from_u64(0),
+                    Datum::from_u64(0),
+                    Datum::from_u64(0),
+                ],
+                &[
+                    Datum::from_str("catalog_name"),
+                    Datum::from_str("schema_name"),
+                    Datum::from_str("table_name"),
+                    Datum::from_str("col_1"),
+                    Datum::from_sql_type(SqlType::Integer(0)),
+                    Datum::UInt64(0),
+                ],
+            );
+            drop(system_catalog);
+
+            let data_definition = DataDefinition::persistent(&path).expect("create persistent data definition");
+            assert_eq!(
+                data_definition.table_columns("catalog_name", "schema_name", "table_name"),
+                vec![ColumnDefinition::new("col_1", SqlType::Integer(0))]
+            );
+            drop(data_definition);
+
+            let system_catalog = PersistentDatabase::new(path.join(SYSTEM_CATALOG));
+            assert!(matches!(
+                system_catalog.init(DEFINITION_SCHEMA),
+                Ok(Ok(InitStatus::Loaded))
+            ));
+            assert_eq!(
+                format::version(&system_catalog, DEFINITION_SCHEMA),
+                Ok(format::FORMAT_VERSION)
+            );
+            assert_eq!(
+                format::verify(&system_catalog, DEFINITION_SCHEMA, &DEFINITION_TABLES),
+                Ok(())
+            );
+            assert_eq!(
+                format::verify(&system_catalog, DEFINITION_SCHEMA, &OBJECT_TABLES),
+                Ok(())
+            );
+        }
+
+        #[rstest::rstest]
+        fn object_tables_are_created_on_upgrade() {
+            let path = tempfile::tempdir().expect("to create temporary folder").into_path();
+            let system_catalog = legacy_system_catalog(&path);
+            system_catalog
+                .create_object(DEFINITION_SCHEMA, format::FORMAT_TABLE)
+                .expect("no io error")
+                .expect("no platform error")
+                .expect("table is created");
+            system_catalog
+                .write(
+                    DEFINITION_SCHEMA,
+                    format::FORMAT_TABLE,
+                    vec![(
+                        Binary::pack(&[Datum::from_u64(0)]),
+                        format::seal(&[Datum::from_u64(format::SEALED_VERSION)]),
+                    )],
+                )
+                .expect("no io error")
+                .expect("no platform error")
+                .expect("to write format version");
+            drop(system_catalog);
+
+            let data_definition = DataDefinition::persistent(&path).expect("create persistent data definition");
+            data_definition
+                .system_table(ROLES_TABLE)
+                .save(|| vec![(vec![Datum::from_str("role_1")], vec![Datum::from_null()])])
+                .expect("no system errors");
+            drop(data_definition);
+
+            let system_catalog = PersistentDatabase::new(path.join(SYSTEM_CATALOG));
+            assert!(matches!(
+                system_catalog.init(DEFINITION_SCHEMA),
+                Ok(Ok(InitStatus::Loaded))
+            ));
+            assert_eq!(
+                format::version(&system_catalog, DEFINITION_SCHEMA),
+                Ok(format::FORMAT_VERSION)
+            );
+            assert_eq!(
+                format::verify(&system_catalog, DEFINITION_SCHEMA, &OBJECT_TABLES),
+                Ok(())
+            );
+        }
+
+        #[rstest::rstest]
+        fn newer_format_version_is_not_read(storage_path: (DataDefinition, PathBuf)) {
+            let (data_definition, path) = storage_path;
+            drop(data_definition);
+            let system_catalog = PersistentDatabase::new(path.join(SYSTEM_CATALOG));
+            system_catalog
+                .init(DEFINITION_SCHEMA)
+                .expect("no io error")
+                .expect("no platform error");
+            system_catalog
+                .write(
+                    DEFINITION_SCHEMA,
+                    format::FORMAT_TABLE,
+                    vec![(
+                        Binary::pack(&[Datum::from_u64(0)]),
+                        format::seal(&[Datum::from_u64(99)]),
+                    )],
+                )
+                .expect("no io error")
+                .expect("no platform error")
+                .expect("to write format version");
+            drop(system_catalog);
+
+            assert_eq!(
+                DataDefinition::persistent(&path).err(),
+                Some(SystemError::unrecoverable(
+                    "system catalog has format version 99 but this server reads versions up to 5, \
+                     start a newer server with the data directory"
+                        .to_owned()
+                ))
+            );
+        }
+
+        #[rstest::rstest]
+        fn damaged_records_are_reported(storage_path: (DataDefinition, PathBuf)) {
+            let (data_definition, path) = storage_path;
+            data_definition.create_catalog("catalog_name");
+            drop(data_definition);
+            let system_catalog = PersistentDatabase::new(path.join(SYSTEM_CATALOG));
+            system_catalog
+                .init(DEFINITION_SCHEMA)
+                .expect("no io error")
+                .expect("no platform error");
+            write(
+                &system_catalog,
+                CATALOG_NAMES_TABLE,
+                &[Datum::from_u64(0)],
+                &[Datum::from_str("catalog_nam")],
+            );
+            drop(system_catalog);
+
+            assert_eq!(
+                DataDefinition::persistent(&path).err(),
+                Some(SystemError::corruption(
+                    "1 record(s) of system catalog table DEFINITION_SCHEMA.CATALOG_NAMES fail checksum verification, \
+                     restore the data directory from a backup"
+                        .to_owned()
+                ))
+            );
+        }
+
+        #[rstest::rstest]
+        fn damaged_object_records_are_reported(storage_path: (DataDefinition, PathBuf)) {
+            let (data_definition, path) = storage_path;
+            drop(data_definition);
+            let system_catalog = PersistentDatabase::new(path.join(SYSTEM_CATALOG));
+            system_catalog
+                .init(DEFINITION_SCHEMA)
+                .expect("no io error")
+                .expect("no platform error");
+            write(
+                &system_catalog,
+                ROLES_TABLE,
+                &[Datum::from_str("role_1")],
+                &[Datum::from_null()],
+            );
+            drop(system_catalog);
+
+            assert_eq!(
+                DataDefinition::persistent(&path).err(),
+                Some(SystemError::corruption(
+                    "1 record(s) of system catalog table DEFINITION_SCHEMA.ROLES fail checksum verification, \
+                     restore the data directory from a backup"
+                        .to_owned()
+                ))
+            );
+        }
     }
 }
@@ -0,0 +1,226 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    catalog_manager::system_tables::SystemTable,
+    query::extended::{SettingChange, SettingsOwner},
+};
+use kernel::SystemResult;
+use representation::Datum;
+use sqlparser::{
+    ast::{SetVariableValue, Statement},
+    dialect::PostgreSqlDialect,
+    parser::Parser,
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::RwLock,
+};
+
+/// Table of the system catalog with settings of roles and databases
+pub(crate) const SETTINGS_TABLE: &str = "SETTINGS";
+const ROLE: &str = "role";
+const DATABASE: &str = "database";
+
+/// Settings that sessions start with, set by `ALTER ROLE role SET` and
+/// `ALTER DATABASE database SET`
+///
+/// Every change is saved in the `SETTINGS` table of the system catalog, a
+/// record per setting with the SQL text of its value.
+#[derive(Default)]
+pub(crate) struct DefaultSettings {
+    settings: RwLock<HashMap<SettingsOwner, BTreeMap<String, SetVariableValue>>>,
+    store: SystemTable,
+}
+
+impl DefaultSettings {
+    /// Settings saved in `store`
+    pub(crate) fn load(store: SystemTable) -> SystemResult<DefaultSettings> {
+        let mut settings = HashMap::<SettingsOwner, BTreeMap<String, SetVariableValue>>::new();
+        store.load(|key, values| {
+            let owner = match key[0].as_str() {
+                ROLE => SettingsOwner::Role(key[1].as_str().to_owned()),
+                _ => SettingsOwner::Database(key[1].as_str().to_owned()),
+            };
+            let name = key[2].as_str();
+            match setting_value(name, values[0].as_str()) {
+                Some(value) => {
+                    settings.entry(owner).or_default().insert(name.to_owned(), value);
+                }
+                None => log::warn!(
+                    "value of setting {:?} of {:?} can't be read and is skipped",
+                    name,
+                    owner
+                ),
+            }
+        })?;
+        Ok(DefaultSettings {
+            settings: RwLock::new(settings),
+            store,
+        })
+    }
+
+    fn save(&self) -> SystemResult<()> {
+        self.store.save(|| {
+            let settings = self.settings.read().expect("to acquire read lock");
+            settings
+                .iter()
+                .flat_map(|(owner, owned)| {
+                    let (kind, owner) = match owner {
+                        SettingsOwner::Role(role) => (ROLE, role),
+                        SettingsOwner::Database(database) => (DATABASE, database),
+                    };
+                    owned.iter().map(move |(name, value)| {
+                        (
+                            vec![
+                                Datum::from_str(kind),
+                                Datum::from_string(owner.clone()),
+                                Datum::from_string(name.clone()),
+                            ],
+                            vec![Datum::from_string(value.to_string())],
+                        )
+                    })
+                })
+                .collect()
+        })
+    }
+
+    pub(crate) fn change(&self, owner: SettingsOwner, change: SettingChange) -> SystemResult<()> {
+        {
+            let mut settings = self.settings.write().expect("to acquire write lock");
+            match change {
+                SettingChange::Set(name, value) => {
+                    settings.entry(owner).or_default().insert(name.to_lowercase(), value);
+                }
+                SettingChange::Reset(name) => {
+                    if let Some(owned) = settings.get_mut(&owner) {
+                        owned.remove(&name.to_lowercase());
+                        if owned.is_empty() {
+                            settings.remove(&owner);
+                        }
+                    }
+                }
+                SettingChange::ResetAll => {
+                    settings.remove(&owner);
+                }
+            }
+        }
+        self.save()
+    }
+
+    /// Settings of a session of `role` connected to `database` in the order
+    /// they are applied, settings of the role override the ones of the
+    /// database
+    pub(crate) fn of_session(&self, database: &str, role: &str) -> Vec<(String, SetVariableValue)> {
+        let settings = self.settings.read().expect("to acquire read lock");
+        [
+            SettingsOwner::Database(database.to_owned()),
+            SettingsOwner::Role(role.to_owned()),
+        ]
+        .iter()
+        .filter_map(|owner| settings.get(owner))
+        .flat_map(|owned| owned.iter().map(|(name, value)| (name.clone(), value.clone())))
+        .collect()
+    }
+}
+
+/// Value of a setting from its SQL text
+fn setting_value(name: &str, text: &str) -> Option<SetVariableValue> {
+    match Parser::parse_sql(&PostgreSqlDialect {}, &format!("SET {} = {}", name, text))
+        .ok()?
+        .pop()?
+    {
+        Statement::SetVariable { value, .. } => Some(value),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlparser::ast::{Ident, Value};
+
+    fn number(value: &str) -> SetVariableValue {
+        SetVariableValue::Literal(Value::Number(value.parse().expect("number")))
+    }
+
+    #[test]
+    fn role_settings_follow_database_settings() {
+        let settings = DefaultSettings::default();
+        settings
+            .change(
+                SettingsOwner::Role("role_1".to_owned()),
+                SettingChange::Set("work_mem".to_owned(), number("128")),
+            )
+            .expect("no system errors");
+        settings
+            .change(
+                SettingsOwner::Database("db_1".to_owned()),
+                SettingChange::Set("Work_Mem".to_owned(), number("64")),
+            )
+            .expect("no system errors");
+        settings
+            .change(
+                SettingsOwner::Database("db_1".to_owned()),
+                SettingChange::Set(
+                    "search_path".to_owned(),
+                    SetVariableValue::Ident(Ident::new("schema_1")),
+                ),
+            )
+            .expect("no system errors");
+
+        assert_eq!(
+            settings.of_session("db_1", "role_1"),
+            vec![
+                (
+                    "search_path".to_owned(),
+                    SetVariableValue::Ident(Ident::new("schema_1"))
+                ),
+                ("work_mem".to_owned(), number("64")),
+                ("work_mem".to_owned(), number("128")),
+            ]
+        );
+        assert_eq!(settings.of_session("db_2", "role_2"), vec![]);
+    }
+
+    #[test]
+    fn settings_are_reset() {
+        let settings = DefaultSettings::default();
+        let role = SettingsOwner::Role("role_1".to_owned());
+        settings
+            .change(role.clone(), SettingChange::Set("work_mem".to_owned(), number("128")))
+            .expect("no system errors");
+        settings
+            .change(
+                role.clone(),
+                SettingChange::Set("statement_timeout".to_owned(), number("10")),
+            )
+            .expect("no system errors");
+
+        settings
+            .change(role.clone(), SettingChange::Reset("WORK_MEM".to_owned()))
+            .expect("no system errors");
+
+        assert_eq!(
+            settings.of_session("db_1", "role_1"),
+            vec![("statement_timeout".to_owned(), number("10"))]
+        );
+
+        settings
+            .change(role, SettingChange::ResetAll)
+            .expect("no system errors");
+
+        assert_eq!(settings.of_session("db_1", "role_1"), vec![]);
+    }
+}
@@ -0,0 +1,144 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Dependencies between objects of the catalog. An object can't exist
+//! without the objects it depends on, so `DROP ... CASCADE` drops its
+//! dependents first and `DROP ... RESTRICT` refuses to drop an object while
+//! anything depends on it.
+use std::{collections::HashSet, fmt};
+
+/// Object of the catalog that others can depend on
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum CatalogObject {
+    Schema(String),
+    Table(String, String),
+    ForeignTable(String, String),
+    /// table that is explicitly listed in a publication
+    PublicationTable {
+        publication: String,
+        schema_name: String,
+        table_name: String,
+    },
+}
+
+impl fmt::Display for CatalogObject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CatalogObject::Schema(schema_name) => write!(f, "schema {}", schema_name),
+            CatalogObject::Table(schema_name, table_name) => write!(f, "table {}.{}", schema_name, table_name),
+            CatalogObject::ForeignTable(schema_name, table_name) => {
+                write!(f, "foreign table {}.{}", schema_name, table_name)
+            }
+            CatalogObject::PublicationTable {
+                publication,
+                schema_name,
+                table_name,
+            } => write!(
+                f,
+                "publication of table {}.{} in publication {}",
+                schema_name, table_name, publication
+            ),
+        }
+    }
+}
+
+/// Edges from objects to the objects they depend on
+#[derive(Default)]
+pub(crate) struct DependencyGraph {
+    edges: Vec<(CatalogObject, CatalogObject)>,
+}
+
+impl DependencyGraph {
+    /// `dependent` can't exist without `referenced`
+    pub(crate) fn add(&mut self, dependent: CatalogObject, referenced: CatalogObject) {
+        self.edges.push((dependent, referenced));
+    }
+
+    /// Objects that depend on `object` directly
+    pub(crate) fn dependents(&self, object: &CatalogObject) -> Vec<&CatalogObject> {
+        self.edges
+            .iter()
+            .filter(|(_dependent, referenced)| referenced == object)
+            .map(|(dependent, _referenced)| dependent)
+            .collect()
+    }
+
+    /// Objects that depend on `object` directly or through other objects in
+    /// the order they have to be dropped, i.e. each object goes after all of
+    /// its own dependents
+    pub(crate) fn drop_order(&self, object: &CatalogObject) -> Vec<CatalogObject> {
+        let mut visited = HashSet::new();
+        let mut order = vec![];
+        for dependent in self.dependents(object) {
+            self.visit(dependent, &mut visited, &mut order);
+        }
+        order
+    }
+
+    fn visit(&self, object: &CatalogObject, visited: &mut HashSet<CatalogObject>, order: &mut Vec<CatalogObject>) {
+        if !visited.insert(object.clone()) {
+            return;
+        }
+        for dependent in self.dependents(object) {
+            self.visit(dependent, visited, order);
+        }
+        order.push(object.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(name: &str) -> CatalogObject {
+        CatalogObject::Table("schema_name".to_owned(), name.to_owned())
+    }
+
+    fn publication_table(publication: &str, name: &str) -> CatalogObject {
+        CatalogObject::PublicationTable {
+            publication: publication.to_owned(),
+            schema_name: "schema_name".to_owned(),
+            table_name: name.to_owned(),
+        }
+    }
+
+    #[test]
+    fn dependents_are_dropped_first() {
+        let schema = CatalogObject::Schema("schema_name".to_owned());
+        let mut graph = DependencyGraph::default();
+        graph.add(table("table_1"), schema.clone());
+        graph.add(table("table_2"), schema.clone());
+        graph.add(publication_table("changes", "table_1"), table("table_1"));
+        graph.add(table("other"), CatalogObject::Schema("other_schema".to_owned()));
+
+        assert_eq!(graph.dependents(&schema), vec![&table("table_1"), &table("table_2")]);
+        assert_eq!(
+            graph.drop_order(&schema),
+            vec![
+                publication_table("changes", "table_1"),
+                table("table_1"),
+                table("table_2")
+            ]
+        );
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(table("table_1").to_string(), "table schema_name.table_1");
+        assert_eq!(
+            publication_table("changes", "table_1").to_string(),
+            "publication of table schema_name.table_1 in publication changes"
+        );
+    }
+}
@@ -0,0 +1,246 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::catalog_manager::system_tables::{SystemRecord, SystemTable};
+use kernel::{SystemError, SystemResult};
+use representation::Datum;
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter},
+    sync::RwLock,
+};
+
+/// Table of the system catalog with event triggers
+pub(crate) const EVENT_TRIGGERS_TABLE: &str = "EVENT_TRIGGERS";
+
+/// Tags of commands that fire event triggers
+pub(crate) const DDL_TAGS: &[&str] = &[
+    "CREATE SCHEMA",
+    "DROP SCHEMA",
+    "CREATE TABLE",
+    "DROP TABLE",
+    "ALTER TABLE",
+    "CREATE FOREIGN TABLE",
+    "CREATE PUBLICATION",
+    "DROP PUBLICATION",
+    "CREATE FUNCTION",
+    "DROP FUNCTION",
+    "CREATE AGGREGATE",
+];
+
+/// `ddl_command_start` fires before a command is executed and can prevent
+/// it, `ddl_command_end` fires after a command succeeded
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum DdlEvent {
+    Start,
+    End,
+}
+
+impl DdlEvent {
+    pub(crate) fn parse(name: &str) -> Option<DdlEvent> {
+        match name.to_lowercase().as_str() {
+            "ddl_command_start" => Some(DdlEvent::Start),
+            "ddl_command_end" => Some(DdlEvent::End),
+            _ => None,
+        }
+    }
+}
+
+impl Display for DdlEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            DdlEvent::Start => write!(f, "ddl_command_start"),
+            DdlEvent::End => write!(f, "ddl_command_end"),
+        }
+    }
+}
+
+/// Function that is called on `event` of commands with one of `tags`, e.g.
+/// `DROP TABLE`, `None` stands for all commands
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct EventTrigger {
+    pub(crate) name: String,
+    pub(crate) event: DdlEvent,
+    pub(crate) tags: Option<Vec<String>>,
+    pub(crate) function: String,
+}
+
+impl EventTrigger {
+    fn fires_on(&self, event: DdlEvent, tag: &str) -> bool {
+        self.event == event
+            && self
+                .tags
+                .as_ref()
+                .map(|tags| tags.iter().any(|filter| filter == tag))
+                .unwrap_or(true)
+    }
+}
+
+/// Event triggers fire in alphabetical order of their names.
+///
+/// Every change is saved in the `EVENT_TRIGGERS` table of the system
+/// catalog, a record per trigger with its event, function and tags.
+#[derive(Default)]
+pub(crate) struct EventTriggers {
+    triggers: RwLock<BTreeMap<String, EventTrigger>>,
+    store: SystemTable,
+}
+
+impl EventTriggers {
+    /// Event triggers saved in `store`
+    pub(crate) fn load(store: SystemTable) -> SystemResult<EventTriggers> {
+        let mut triggers = BTreeMap::new();
+        let mut damaged = None;
+        store.load(|key, values| {
+            let name = key[0].as_str().to_owned();
+            match stored_trigger(&name, values) {
+                Some(trigger) => {
+                    triggers.insert(name, trigger);
+                }
+                None => damaged = Some(name),
+            }
+        })?;
+        if let Some(name) = damaged {
+            return Err(SystemError::corruption(format!(
+                "event trigger {} can't be read, restore the data directory from a backup",
+                name
+            )));
+        }
+        Ok(EventTriggers {
+            triggers: RwLock::new(triggers),
+            store,
+        })
+    }
+
+    fn save(&self) -> SystemResult<()> {
+        self.store.save(|| {
+            self.triggers
+                .read()
+                .expect("to acquire read lock")
+                .values()
+                .map(trigger_record)
+                .collect()
+        })
+    }
+
+    /// Returns `false` if a trigger with the same name exists
+    pub(crate) fn create(&self, trigger: EventTrigger) -> SystemResult<bool> {
+        {
+            let mut triggers = self.triggers.write().expect("to acquire write lock");
+            if triggers.contains_key(&trigger.name) {
+                return Ok(false);
+            }
+            triggers.insert(trigger.name.clone(), trigger);
+        }
+        self.save().map(|()| true)
+    }
+
+    pub(crate) fn drop(&self, name: &str) -> SystemResult<bool> {
+        let dropped = self
+            .triggers
+            .write()
+            .expect("to acquire write lock")
+            .remove(name)
+            .is_some();
+        if dropped {
+            self.save()?;
+        }
+        Ok(dropped)
+    }
+
+    /// Triggers that fire on `event` of a command with `tag`
+    pub(crate) fn matching(&self, event: DdlEvent, tag: &str) -> Vec<EventTrigger> {
+        self.triggers
+            .read()
+            .expect("to acquire read lock")
+            .values()
+            .filter(|trigger| trigger.fires_on(event, tag))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Record of `EVENT_TRIGGERS_TABLE` keyed by the name of the trigger, values
+/// are the event, the function and tags of commands or `NULL` for all
+/// commands
+fn trigger_record(trigger: &EventTrigger) -> SystemRecord {
+    let mut values = vec![
+        Datum::from_string(trigger.event.to_string()),
+        Datum::from_string(trigger.function.clone()),
+    ];
+    match &trigger.tags {
+        Some(tags) => values.extend(tags.iter().cloned().map(Datum::from_string)),
+        None => values.push(Datum::from_null()),
+    }
+    (vec![Datum::from_string(trigger.name.clone())], values)
+}
+
+/// Trigger of a record of `EVENT_TRIGGERS_TABLE`, `None` if the record can't
+/// be read
+fn stored_trigger(name: &str, values: &[Datum]) -> Option<EventTrigger> {
+    if values.len() < 2 {
+        return None;
+    }
+    let tags = match &values[2..] {
+        [Datum::Null] => None,
+        tags => Some(tags.iter().map(|tag| tag.as_str().to_owned()).collect()),
+    };
+    Some(EventTrigger {
+        name: name.to_owned(),
+        event: DdlEvent::parse(values[0].as_str())?,
+        tags,
+        function: values[1].as_str().to_owned(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trigger(name: &str, event: DdlEvent, tags: Option<Vec<&str>>) -> EventTrigger {
+        EventTrigger {
+            name: name.to_owned(),
+            event,
+            tags: tags.map(|tags| tags.into_iter().map(str::to_owned).collect()),
+            function: "audit".to_owned(),
+        }
+    }
+
+    #[test]
+    fn triggers_are_matched_by_event_and_tag_in_name_order() {
+        let triggers = EventTriggers::default();
+        assert_eq!(triggers.create(trigger("second", DdlEvent::Start, None)), Ok(true));
+        assert_eq!(
+            triggers.create(trigger("first", DdlEvent::Start, Some(vec!["DROP TABLE"]))),
+            Ok(true)
+        );
+        assert_eq!(triggers.create(trigger("end", DdlEvent::End, None)), Ok(true));
+        assert_eq!(triggers.create(trigger("first", DdlEvent::End, None)), Ok(false));
+
+        let names = |event, tag| {
+            triggers
+                .matching(event, tag)
+                .into_iter()
+                .map(|trigger| trigger.name)
+                .collect::<Vec<String>>()
+        };
+        assert_eq!(names(DdlEvent::Start, "DROP TABLE"), vec!["first", "second"]);
+        assert_eq!(names(DdlEvent::Start, "CREATE TABLE"), vec!["second"]);
+        assert_eq!(names(DdlEvent::End, "CREATE TABLE"), vec!["end"]);
+
+        assert_eq!(triggers.drop("second"), Ok(true));
+        assert_eq!(triggers.drop("second"), Ok(false));
+        assert_eq!(names(DdlEvent::Start, "CREATE TABLE"), Vec::<String>::new());
+    }
+}
@@ -0,0 +1,55 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use protocol::sql_types::PostgreSqlType;
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::RwLock,
+};
+
+/// Names of loaded extensions and types they registered. Extensions are
+/// loaded at startup and are not persisted.
+#[derive(Default)]
+pub(crate) struct Extensions {
+    names: RwLock<BTreeSet<String>>,
+    types: RwLock<HashMap<String, PostgreSqlType>>,
+}
+
+impl Extensions {
+    pub(crate) fn is_loaded(&self, name: &str) -> bool {
+        self.names.read().expect("to acquire read lock").contains(name)
+    }
+
+    pub(crate) fn load(&self, name: &str, types: Vec<(String, PostgreSqlType)>) {
+        self.names
+            .write()
+            .expect("to acquire write lock")
+            .insert(name.to_owned());
+        self.types.write().expect("to acquire write lock").extend(types);
+    }
+
+    /// Built-in type that values of the registered type `name` are stored as
+    pub(crate) fn base_type(&self, name: &str) -> Option<PostgreSqlType> {
+        self.types.read().expect("to acquire read lock").get(name).copied()
+    }
+
+    pub(crate) fn names(&self) -> Vec<String> {
+        self.names
+            .read()
+            .expect("to acquire read lock")
+            .iter()
+            .cloned()
+            .collect()
+    }
+}
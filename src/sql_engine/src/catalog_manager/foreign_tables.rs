@@ -0,0 +1,315 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::{
+    dml::copy::{datum, parse_records},
+    query::extended::CopyOptions,
+    ColumnDefinition,
+};
+use representation::{Binary, Datum};
+use std::{
+    collections::HashMap,
+    fs,
+    sync::{Arc, RwLock},
+};
+use storage::{ReadCursor, Row};
+
+/// Source of rows of foreign tables, e.g. a file, an HTTP endpoint or
+/// another database. Wrappers are registered under a name that foreign
+/// tables refer to as `SERVER`.
+pub trait ForeignDataWrapper: Send + Sync {
+    /// Rows of a foreign table with `columns` and `options` it was created
+    /// with. Values are in text representation, the same that `COPY` reads,
+    /// and `None` stands for `NULL`. Errors are reported to the client as is.
+    fn scan(
+        &self,
+        columns: &[ColumnDefinition],
+        options: &HashMap<String, String>,
+    ) -> Result<Vec<Vec<Option<String>>>, String>;
+}
+
+/// Built-in wrapper, registered as `file`, that reads CSV files on the
+/// server. Supported options are `filename`, `header`, `delimiter`, `quote`
+/// and `null` with the same meaning as `COPY` ones.
+struct FileDataWrapper;
+
+impl ForeignDataWrapper for FileDataWrapper {
+    fn scan(
+        &self,
+        _columns: &[ColumnDefinition],
+        options: &HashMap<String, String>,
+    ) -> Result<Vec<Vec<Option<String>>>, String> {
+        let file_name = match options.get("filename") {
+            Some(file_name) => file_name,
+            None => return Err("filename is required for file foreign table".to_owned()),
+        };
+        let mut copy_options = CopyOptions::default();
+        for (option, value) in options {
+            match option.as_str() {
+                "filename" => {}
+                "header" => copy_options.header = matches!(value.to_lowercase().as_str(), "true" | "on" | "1"),
+                "delimiter" => copy_options.delimiter = single_character(option, value)?,
+                "quote" => copy_options.quote = single_character(option, value)?,
+                "null" => copy_options.null = value.clone(),
+                _ => return Err(format!("invalid option \"{}\"", option)),
+            }
+        }
+        let text = fs::read_to_string(file_name)
+            .map_err(|error| format!("could not open file \"{}\" for reading: {}", file_name, error))?;
+        let mut records = parse_records(&text, &copy_options)?;
+        if copy_options.header && !records.is_empty() {
+            records.remove(0);
+        }
+        Ok(records)
+    }
+}
+
+fn single_character(option: &str, value: &str) -> Result<char, String> {
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => Err(format!("{} must be a single one-byte character", option)),
+    }
+}
+
+struct ForeignTable {
+    server: String,
+    options: HashMap<String, String>,
+}
+
+/// Registered foreign data wrappers and tables that are backed by them.
+/// Foreign tables are kept in memory only and have to be created again
+/// after restart.
+pub(crate) struct ForeignTables {
+    wrappers: RwLock<HashMap<String, Arc<dyn ForeignDataWrapper>>>,
+    tables: RwLock<HashMap<(String, String), ForeignTable>>,
+}
+
+impl Default for ForeignTables {
+    fn default() -> ForeignTables {
+        let foreign_tables = ForeignTables {
+            wrappers: RwLock::default(),
+            tables: RwLock::default(),
+        };
+        foreign_tables.register("file", Arc::new(FileDataWrapper));
+        foreign_tables
+    }
+}
+
+impl ForeignTables {
+    pub(crate) fn register(&self, name: &str, wrapper: Arc<dyn ForeignDataWrapper>) {
+        self.wrappers
+            .write()
+            .expect("to acquire write lock")
+            .insert(name.to_owned(), wrapper);
+    }
+
+    pub(crate) fn wrapper_exists(&self, name: &str) -> bool {
+        self.wrappers.read().expect("to acquire read lock").contains_key(name)
+    }
+
+    pub(crate) fn attach(&self, schema_name: &str, table_name: &str, server: &str, options: Vec<(String, String)>) {
+        self.tables.write().expect("to acquire write lock").insert(
+            (schema_name.to_owned(), table_name.to_owned()),
+            ForeignTable {
+                server: server.to_owned(),
+                options: options.into_iter().collect(),
+            },
+        );
+    }
+
+    pub(crate) fn is_foreign(&self, schema_name: &str, table_name: &str) -> bool {
+        self.tables
+            .read()
+            .expect("to acquire read lock")
+            .contains_key(&(schema_name.to_owned(), table_name.to_owned()))
+    }
+
+    /// Rows of the foreign table converted to column types, `None` if the
+    /// table is not foreign. Keys of rows are their positions.
+    pub(crate) fn scan(
+        &self,
+        schema_name: &str,
+        table_name: &str,
+        columns: &[ColumnDefinition],
+    ) -> Option<Result<ReadCursor, String>> {
+        let tables = self.tables.read().expect("to acquire read lock");
+        let table = tables.get(&(schema_name.to_owned(), table_name.to_owned()))?;
+        let wrapper = match self.wrappers.read().expect("to acquire read lock").get(&table.server) {
+            Some(wrapper) => wrapper.clone(),
+            None => return Some(Err(format!("server \"{}\" does not exist", table.server))),
+        };
+        Some(
+            wrapper
+                .scan(columns, &table.options)
+                .and_then(|records| rows(columns, records))
+                .map(|rows| Box::new(rows.into_iter().map(|row| Ok(Ok(row)))) as ReadCursor),
+        )
+    }
+
+    pub(crate) fn forget_table(&self, schema_name: &str, table_name: &str) {
+        self.tables
+            .write()
+            .expect("to acquire write lock")
+            .remove(&(schema_name.to_owned(), table_name.to_owned()));
+    }
+
+    pub(crate) fn forget_schema(&self, schema_name: &str) {
+        self.tables
+            .write()
+            .expect("to acquire write lock")
+            .retain(|(schema, _), _| schema != schema_name);
+    }
+}
+
+fn rows(columns: &[ColumnDefinition], records: Vec<Vec<Option<String>>>) -> Result<Vec<Row>, String> {
+    let mut rows = Vec::with_capacity(records.len());
+    for (index, record) in records.into_iter().enumerate() {
+        if record.len() != columns.len() {
+            return Err(format!(
+                "row {} has {} values, but the foreign table has {} columns",
+                index + 1,
+                record.len(),
+                columns.len()
+            ));
+        }
+        let mut values = Vec::with_capacity(columns.len());
+        for (field, column) in record.into_iter().zip(columns.iter()) {
+            match field {
+                None => values.push(Datum::from_null()),
+                Some(field) => match datum(&column.sql_type(), &field) {
+                    Ok(datum) => values.push(datum),
+                    Err(_) => {
+                        return Err(format!(
+                            "invalid value \"{}\" for column \"{}\" in row {}",
+                            field,
+                            column.name(),
+                            index + 1
+                        ))
+                    }
+                },
+            }
+        }
+        let key = (index as u64).to_be_bytes().to_vec();
+        rows.push((Binary::with_data(key), Binary::pack(&values)));
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sql_types::SqlType;
+
+    struct Constant(Vec<Vec<Option<String>>>);
+
+    impl ForeignDataWrapper for Constant {
+        fn scan(
+            &self,
+            _columns: &[ColumnDefinition],
+            _options: &HashMap<String, String>,
+        ) -> Result<Vec<Vec<Option<String>>>, String> {
+            Ok(self.0.clone())
+        }
+    }
+
+    fn columns() -> Vec<ColumnDefinition> {
+        vec![
+            ColumnDefinition::new("id", SqlType::SmallInt(i16::MIN)),
+            ColumnDefinition::new("name", SqlType::VarChar(10)),
+        ]
+    }
+
+    fn values(cursor: ReadCursor) -> Vec<Vec<String>> {
+        cursor
+            .map(|row| {
+                let (_key, values) = row.expect("no io errors").expect("no storage errors");
+                values.unpack().iter().map(ToString::to_string).collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn table_that_is_not_foreign() {
+        let foreign_tables = ForeignTables::default();
+        assert!(foreign_tables.scan("schema_name", "table_name", &columns()).is_none());
+    }
+
+    #[test]
+    fn rows_are_converted_to_column_types() {
+        let foreign_tables = ForeignTables::default();
+        foreign_tables.register(
+            "constant",
+            Arc::new(Constant(vec![
+                vec![Some("1".to_owned()), Some("one".to_owned())],
+                vec![Some("2".to_owned()), None],
+            ])),
+        );
+        foreign_tables.attach("schema_name", "table_name", "constant", vec![]);
+
+        let cursor = foreign_tables
+            .scan("schema_name", "table_name", &columns())
+            .expect("foreign table")
+            .expect("rows");
+
+        assert_eq!(
+            values(cursor),
+            vec![
+                vec!["1".to_owned(), "one".to_owned()],
+                vec!["2".to_owned(), "NULL".to_owned()]
+            ]
+        );
+    }
+
+    #[test]
+    fn value_of_wrong_type() {
+        let foreign_tables = ForeignTables::default();
+        foreign_tables.register(
+            "constant",
+            Arc::new(Constant(vec![vec![Some("one".to_owned()), Some("one".to_owned())]])),
+        );
+        foreign_tables.attach("schema_name", "table_name", "constant", vec![]);
+
+        assert_eq!(
+            foreign_tables
+                .scan("schema_name", "table_name", &columns())
+                .expect("foreign table")
+                .err(),
+            Some("invalid value \"one\" for column \"id\" in row 1".to_owned())
+        );
+    }
+
+    #[test]
+    fn file_without_filename() {
+        let foreign_tables = ForeignTables::default();
+        foreign_tables.attach("schema_name", "table_name", "file", vec![]);
+
+        assert_eq!(
+            foreign_tables
+                .scan("schema_name", "table_name", &columns())
+                .expect("foreign table")
+                .err(),
+            Some("filename is required for file foreign table".to_owned())
+        );
+    }
+
+    #[test]
+    fn forgotten_schema() {
+        let foreign_tables = ForeignTables::default();
+        foreign_tables.attach("schema_name", "table_name", "file", vec![]);
+
+        foreign_tables.forget_schema("schema_name");
+
+        assert!(!foreign_tables.is_foreign("schema_name", "table_name"));
+    }
+}
@@ -0,0 +1,178 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! On-disk format of records of the system catalog. Values of a record end
+//! with the CRC-32 checksum of the values before it, so that damaged records
+//! are reported before they are decoded. The version of the format is kept
+//! in its own table, data directories written before it was recorded have
+//! the first version.
+//!
+//! Versions:
+//!
+//! 1. Records without checksums, columns may lack their defaults and
+//!    nullability.
+//! 2. Records end with checksums.
+//! 3. Privileges, roles, settings, functions, publications, replication
+//!    slots and large objects are saved in tables of their own.
+//! 4. Event triggers are saved in a table of their own.
+//! 5. Aggregates are saved in a table of their own.
+use crate::catalog_manager::stored;
+use crc32fast::Hasher;
+use kernel::{Object, Operation, SystemError, SystemResult};
+use representation::{Binary, Datum};
+use storage::{Database, DefinitionError, Key};
+
+/// Table of the system catalog that keeps the version of its records
+pub(crate) const FORMAT_TABLE: &str = "FORMAT";
+/// Version of records that are written
+pub(crate) const FORMAT_VERSION: u64 = 5;
+/// First version whose records end with checksums
+pub(crate) const SEALED_VERSION: u64 = 2;
+/// Version of data directories that do not record it
+pub(crate) const LEGACY_VERSION: u64 = 1;
+
+/// Packs `values` followed by the checksum of their bytes
+pub(crate) fn seal(values: &[Datum]) -> Binary {
+    let mut data = Binary::pack(values).to_bytes().to_vec();
+    data.extend_from_slice(checksum(&data).to_bytes());
+    Binary::with_data(data)
+}
+
+/// Whether the checksum at the end of `values` matches the values before it
+pub(crate) fn is_intact(values: &Binary) -> bool {
+    let data = values.to_bytes();
+    let size = Datum::from_u64(0).size();
+    data.len() >= size && checksum(&data[..data.len() - size]).to_bytes() == &data[data.len() - size..]
+}
+
+fn checksum(data: &[u8]) -> Binary {
+    let mut hasher = Hasher::new();
+    hasher.update(data);
+    Binary::pack(&[Datum::from_u64(hasher.finalize() as u64)])
+}
+
+/// Version of records of `schema`, data directories of newer versions can't
+/// be read
+pub(crate) fn version(database: &dyn Database, schema: &str) -> SystemResult<u64> {
+    let records = match database.read(schema, FORMAT_TABLE) {
+        Ok(Ok(Err(DefinitionError::ObjectDoesNotExist))) => return Ok(LEGACY_VERSION),
+        result => stored(result, Operation::Access, Object::Table(schema, FORMAT_TABLE))?,
+    };
+    let mut version = LEGACY_VERSION;
+    for record in records {
+        let (_key, values) = stored(
+            record.map(|record| record.map(Ok)),
+            Operation::Access,
+            Object::Table(schema, FORMAT_TABLE),
+        )?;
+        if !is_intact(&values) {
+            return Err(damaged(schema, FORMAT_TABLE, 1));
+        }
+        version = values.unpack()[0].as_u64();
+    }
+    if version > FORMAT_VERSION {
+        return Err(SystemError::unrecoverable(format!(
+            "system catalog has format version {} but this server reads versions up to {}, \
+             start a newer server with the data directory",
+            version, FORMAT_VERSION
+        )));
+    }
+    Ok(version)
+}
+
+/// Records that the records of `schema` have the current version
+pub(crate) fn set_version(database: &dyn Database, schema: &str) -> SystemResult<()> {
+    match database.create_object(schema, FORMAT_TABLE) {
+        Ok(Ok(Err(DefinitionError::ObjectAlreadyExists))) => {}
+        result => stored(result, Operation::Create, Object::Table(schema, FORMAT_TABLE))?,
+    }
+    stored(
+        database.write(
+            schema,
+            FORMAT_TABLE,
+            vec![(
+                Binary::pack(&[Datum::from_u64(0)]),
+                seal(&[Datum::from_u64(FORMAT_VERSION)]),
+            )],
+        ),
+        Operation::Access,
+        Object::Table(schema, FORMAT_TABLE),
+    )
+    .map(|_written| ())
+}
+
+/// Checks checksums of all records of `tables`
+pub(crate) fn verify(database: &dyn Database, schema: &str, tables: &[&str]) -> SystemResult<()> {
+    for table in tables {
+        let damaged_records = records(database, schema, table)?
+            .iter()
+            .filter(|(_key, values)| !is_intact(values))
+            .count();
+        if damaged_records > 0 {
+            return Err(damaged(schema, table, damaged_records));
+        }
+    }
+    Ok(())
+}
+
+/// All records of `table` of `schema`
+pub(crate) fn records(database: &dyn Database, schema: &str, table: &str) -> SystemResult<Vec<(Key, Binary)>> {
+    stored(
+        database.read(schema, table),
+        Operation::Access,
+        Object::Table(schema, table),
+    )?
+    .map(|record| {
+        stored(
+            record.map(|record| record.map(Ok)),
+            Operation::Access,
+            Object::Table(schema, table),
+        )
+    })
+    .collect()
+}
+
+fn damaged(schema: &str, table: &str, records: usize) -> SystemError {
+    SystemError::corruption(format!(
+        "{} record(s) of system catalog table {}.{} fail checksum verification, \
+         restore the data directory from a backup",
+        records, schema, table
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sealed_values_are_intact() {
+        let values = seal(&[Datum::from_str("catalog_name"), Datum::from_u64(1)]);
+
+        assert!(is_intact(&values));
+        assert_eq!(
+            values.unpack()[..2],
+            [Datum::from_str("catalog_name"), Datum::from_u64(1)]
+        );
+    }
+
+    #[test]
+    fn changed_values_are_not_intact() {
+        let mut data = seal(&[Datum::from_str("catalog_name")]).to_bytes().to_vec();
+        data[10] ^= 1;
+
+        assert!(!is_intact(&Binary::with_data(data)));
+        assert!(!is_intact(&Binary::pack(&[Datum::from_str("catalog_name")])));
+        assert!(!is_intact(&Binary::new()));
+    }
+}
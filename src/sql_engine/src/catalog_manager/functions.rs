@@ -0,0 +1,250 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    catalog_manager::system_tables::{SystemRecord, SystemTable},
+    dml::functions::{
+        large_objects::{bytea_input, bytea_output},
+        ScalarFunctions,
+    },
+    procedural::{self, parse_body, wasm, Body, Function, Returns, Variable, WasmFunction},
+};
+use kernel::{SystemError, SystemResult};
+use protocol::sql_types::PostgreSqlType;
+use representation::Datum;
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    sync::{Arc, RwLock},
+};
+
+/// Table of the system catalog with functions and their owners
+pub(crate) const FUNCTIONS_TABLE: &str = "FUNCTIONS";
+
+struct StoredFunction {
+    owner: String,
+    function: Arc<Function>,
+}
+
+/// Functions of the procedural language with their owners.
+///
+/// Every change is saved in the `FUNCTIONS` table of the system catalog, a
+/// record per function with its owner, result type, source or WebAssembly
+/// module and parameters. Functions of native extensions are not saved,
+/// they are registered again when extensions are loaded.
+///
+/// Functions are registered among `scalar_functions` while they exist, so
+/// that statements call them like built-in functions.
+pub(crate) struct Functions {
+    functions: RwLock<HashMap<String, StoredFunction>>,
+    store: SystemTable,
+    scalar_functions: ScalarFunctions,
+}
+
+impl Functions {
+    /// Functions of an in-memory catalog
+    pub(crate) fn new(scalar_functions: ScalarFunctions) -> Functions {
+        Functions {
+            functions: RwLock::default(),
+            store: SystemTable::default(),
+            scalar_functions,
+        }
+    }
+
+    /// Functions saved in `store`, blocks are parsed and modules are compiled
+    /// again
+    pub(crate) fn load(store: SystemTable, scalar_functions: ScalarFunctions) -> SystemResult<Functions> {
+        let mut functions = HashMap::new();
+        let mut damaged = None;
+        store.load(|key, values| {
+            let name = key[0].as_str().to_owned();
+            match stored_function(&name, &values[1..]) {
+                Some(function) => {
+                    functions.insert(
+                        name,
+                        StoredFunction {
+                            owner: values[0].as_str().to_owned(),
+                            function: Arc::new(function),
+                        },
+                    );
+                }
+                None => damaged = Some(name),
+            }
+        })?;
+        if let Some(name) = damaged {
+            return Err(SystemError::corruption(format!(
+                "function {} can't be read, restore the data directory from a backup",
+                name
+            )));
+        }
+        for stored in functions.values() {
+            procedural::register(stored.function.clone(), &scalar_functions);
+        }
+        Ok(Functions {
+            functions: RwLock::new(functions),
+            store,
+            scalar_functions,
+        })
+    }
+
+    fn save(&self) -> SystemResult<()> {
+        self.store.save(|| {
+            self.functions
+                .read()
+                .expect("to acquire read lock")
+                .iter()
+                .filter_map(|(name, stored)| function_record(name, stored))
+                .collect()
+        })
+    }
+
+    /// Returns `false` if a function with the same name exists and is not
+    /// replaced, a replaced function keeps its owner
+    pub(crate) fn create(&self, function: Function, owner: &str, replace: bool) -> SystemResult<bool> {
+        {
+            let mut functions = self.functions.write().expect("to acquire write lock");
+            let owner = match functions.get(&function.name) {
+                Some(_) if !replace => return Ok(false),
+                Some(existing) => {
+                    procedural::unregister(&existing.function, &self.scalar_functions);
+                    existing.owner.clone()
+                }
+                None => owner.to_owned(),
+            };
+            let function = Arc::new(function);
+            procedural::register(function.clone(), &self.scalar_functions);
+            functions.insert(function.name.clone(), StoredFunction { owner, function });
+        }
+        self.save().map(|()| true)
+    }
+
+    pub(crate) fn drop(&self, name: &str) -> SystemResult<bool> {
+        let dropped = self.functions.write().expect("to acquire write lock").remove(name);
+        if let Some(dropped) = &dropped {
+            procedural::unregister(&dropped.function, &self.scalar_functions);
+        }
+        let dropped = dropped.is_some();
+        if dropped {
+            self.save()?;
+        }
+        Ok(dropped)
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<Arc<Function>> {
+        self.functions
+            .read()
+            .expect("to acquire read lock")
+            .get(name)
+            .map(|stored| stored.function.clone())
+    }
+
+    pub(crate) fn owner(&self, name: &str) -> Option<String> {
+        self.functions
+            .read()
+            .expect("to acquire read lock")
+            .get(name)
+            .map(|stored| stored.owner.clone())
+    }
+}
+
+/// Record of `FUNCTIONS_TABLE` keyed by the name of the function, values are
+/// the owner followed by values of the function
+fn function_record(name: &str, stored: &StoredFunction) -> Option<SystemRecord> {
+    let mut values = vec![Datum::from_string(stored.owner.clone())];
+    values.extend(function_values(&stored.function)?);
+    Some((vec![Datum::from_string(name.to_owned())], values))
+}
+
+/// OID of the result type or `NULL` for `event_trigger`, the language with
+/// the source of a block or the file, the export and the code of a module,
+/// names and type OIDs of parameters. `None` for functions of native
+/// extensions.
+pub(super) fn function_values(function: &Function) -> Option<Vec<Datum<'static>>> {
+    let mut values = vec![match function.returns {
+        Returns::Type(returns) => Datum::from_u64(returns.pg_oid() as u64),
+        Returns::EventTrigger => Datum::from_null(),
+    }];
+    match &function.body {
+        Body::Block(_block, source) => {
+            values.push(Datum::from_str(procedural::LANGUAGE));
+            values.push(Datum::from_string(source.clone()));
+        }
+        Body::Wasm(module) => {
+            values.push(Datum::from_str(wasm::LANGUAGE));
+            values.push(Datum::from_string(module.file().to_owned()));
+            values.push(Datum::from_string(module.export().to_owned()));
+            values.push(Datum::from_string(bytea_output(module.code())));
+        }
+        Body::Native(_) => return None,
+    }
+    for param in &function.params {
+        values.push(Datum::from_string(param.name.clone()));
+        values.push(Datum::from_u64(param.pg_type.pg_oid() as u64));
+    }
+    Some(values)
+}
+
+/// Function `name` of its values, `None` if they can't be read
+pub(super) fn stored_function(name: &str, values: &[Datum]) -> Option<Function> {
+    if values.len() < 2 {
+        return None;
+    }
+    let returns = match &values[0] {
+        Datum::Null => Returns::EventTrigger,
+        oid => Returns::Type(PostgreSqlType::try_from(oid.as_u64() as u32).ok()?),
+    };
+    let body_size = match values[1].as_str() {
+        procedural::LANGUAGE => 1,
+        wasm::LANGUAGE => 3,
+        _ => return None,
+    };
+    if values.len() < 2 + body_size {
+        return None;
+    }
+    let (body_values, params) = values[2..].split_at(body_size);
+    let params = params.chunks_exact(2);
+    if !params.remainder().is_empty() {
+        return None;
+    }
+    let params = params
+        .map(|param| {
+            PostgreSqlType::try_from(param[1].as_u64() as u32)
+                .ok()
+                .map(|pg_type| Variable {
+                    name: param[0].as_str().to_owned(),
+                    pg_type,
+                })
+        })
+        .collect::<Option<Vec<_>>>()?;
+    let body = match (body_values, returns) {
+        ([source], _) => Body::Block(parse_body(source.as_str()).ok()?, source.as_str().to_owned()),
+        ([file, export, code], Returns::Type(returns)) => Body::Wasm(
+            WasmFunction::compile(
+                file.as_str().to_owned(),
+                export.as_str().to_owned(),
+                &bytea_input(code.as_str()).ok()?,
+                &params,
+                returns,
+            )
+            .ok()?,
+        ),
+        _ => return None,
+    };
+    Some(Function {
+        name: name.to_owned(),
+        params,
+        returns,
+        body,
+    })
+}
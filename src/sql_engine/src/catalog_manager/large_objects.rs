@@ -0,0 +1,295 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    catalog_manager::system_tables::SystemTable,
+    dml::functions::large_objects::{bytea_input, bytea_output},
+};
+use kernel::{SystemError, SystemResult};
+use representation::Datum;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        RwLock,
+    },
+};
+
+/// Table of the system catalog with OIDs of large objects
+pub(crate) const LARGE_OBJECT_METADATA_TABLE: &str = "LARGE_OBJECT_METADATA";
+/// Table of the system catalog with pages of large objects
+pub(crate) const LARGE_OBJECTS_TABLE: &str = "LARGE_OBJECTS";
+
+/// Number of bytes of a large object that are stored together
+pub(crate) const PAGE_SIZE: usize = 2048;
+/// Lower OIDs are reserved in PostgreSQL
+const FIRST_NORMAL_OID: u32 = 16384;
+
+/// Content of a large object split into pages, pages that were never
+/// written read as zeros
+#[derive(Default)]
+struct LargeObject {
+    pages: BTreeMap<usize, Vec<u8>>,
+    size: usize,
+}
+
+impl LargeObject {
+    fn read(&self, offset: usize, len: usize) -> Vec<u8> {
+        let end = self.size.min(offset.saturating_add(len));
+        let mut data = Vec::with_capacity(end.saturating_sub(offset));
+        let mut position = offset;
+        while position < end {
+            let (page, start) = (position / PAGE_SIZE, position % PAGE_SIZE);
+            let count = (PAGE_SIZE - start).min(end - position);
+            let written = match self.pages.get(&page) {
+                Some(bytes) => bytes.get(start..).unwrap_or_default(),
+                None => &[],
+            };
+            let copied = written.len().min(count);
+            data.extend_from_slice(&written[..copied]);
+            data.resize(data.len() + count - copied, 0);
+            position += count;
+        }
+        data
+    }
+
+    fn write(&mut self, offset: usize, data: &[u8]) {
+        let mut position = offset;
+        for chunk in Self::chunks(offset, data) {
+            let (page, start) = (position / PAGE_SIZE, position % PAGE_SIZE);
+            let bytes = self.pages.entry(page).or_default();
+            if bytes.len() < start + chunk.len() {
+                bytes.resize(start + chunk.len(), 0);
+            }
+            bytes[start..start + chunk.len()].copy_from_slice(chunk);
+            position += chunk.len();
+        }
+        self.size = self.size.max(position);
+    }
+
+    /// Parts of `data` written at `offset` that fall into separate pages
+    fn chunks(offset: usize, data: &[u8]) -> Vec<&[u8]> {
+        let first = (PAGE_SIZE - offset % PAGE_SIZE).min(data.len());
+        let (head, tail) = data.split_at(first);
+        let mut chunks = vec![head];
+        chunks.extend(tail.chunks(PAGE_SIZE));
+        chunks.retain(|chunk| !chunk.is_empty());
+        chunks
+    }
+}
+
+/// Large objects by their OIDs. Every created large object has a record in
+/// the `LARGE_OBJECT_METADATA` table of the system catalog and every written
+/// page is saved in the `LARGE_OBJECTS` table as a record of its own, so a
+/// write saves only the pages it changed.
+pub(crate) struct LargeObjects {
+    objects: RwLock<HashMap<u32, LargeObject>>,
+    next_oid: AtomicU32,
+    metadata: SystemTable,
+    pages: SystemTable,
+}
+
+impl Default for LargeObjects {
+    fn default() -> LargeObjects {
+        LargeObjects {
+            objects: RwLock::default(),
+            next_oid: AtomicU32::new(FIRST_NORMAL_OID),
+            metadata: SystemTable::default(),
+            pages: SystemTable::default(),
+        }
+    }
+}
+
+impl LargeObjects {
+    /// Large objects saved in `metadata` and `pages`
+    pub(crate) fn load(metadata: SystemTable, pages: SystemTable) -> SystemResult<LargeObjects> {
+        let mut objects = HashMap::<u32, LargeObject>::new();
+        metadata.read(|key, _values| {
+            objects.insert(key[0].as_u64() as u32, LargeObject::default());
+        })?;
+        let mut damaged = None;
+        pages.read(|key, values| {
+            let (oid, page) = (key[0].as_u64() as u32, key[1].as_u64() as usize);
+            match (objects.get_mut(&oid), bytea_input(values[0].as_str())) {
+                (Some(object), Ok(bytes)) => {
+                    object.size = object.size.max(page * PAGE_SIZE + bytes.len());
+                    object.pages.insert(page, bytes);
+                }
+                (Some(_), Err(_)) => damaged = Some(oid),
+                (None, _) => log::warn!("page {} of not existing large object {} is skipped", page, oid),
+            }
+        })?;
+        if let Some(oid) = damaged {
+            return Err(SystemError::corruption(format!(
+                "pages of large object {} can't be read, restore the data directory from a backup",
+                oid
+            )));
+        }
+        Ok(LargeObjects {
+            objects: RwLock::new(objects),
+            next_oid: AtomicU32::new(FIRST_NORMAL_OID),
+            metadata,
+            pages,
+        })
+    }
+
+    /// Creates an empty large object, `None` as `oid` assigns an unused one.
+    /// Returns `None` if a large object with the OID exists.
+    pub(crate) fn create(&self, oid: Option<u32>) -> SystemResult<Option<u32>> {
+        let mut objects = self.objects.write().expect("to acquire write lock");
+        let oid = match oid {
+            Some(oid) if objects.contains_key(&oid) => return Ok(None),
+            Some(oid) => oid,
+            None => loop {
+                let oid = self.next_oid.fetch_add(1, Ordering::SeqCst);
+                if oid >= FIRST_NORMAL_OID && !objects.contains_key(&oid) {
+                    break oid;
+                }
+            },
+        };
+        self.metadata.write(vec![(vec![Datum::from_u64(oid as u64)], vec![])])?;
+        objects.insert(oid, LargeObject::default());
+        Ok(Some(oid))
+    }
+
+    pub(crate) fn exists(&self, oid: u32) -> bool {
+        self.objects.read().expect("to acquire read lock").contains_key(&oid)
+    }
+
+    /// At most `len` bytes from `offset`, `None` if the large object does
+    /// not exist
+    pub(crate) fn read(&self, oid: u32, offset: usize, len: usize) -> Option<Vec<u8>> {
+        self.objects
+            .read()
+            .expect("to acquire read lock")
+            .get(&oid)
+            .map(|object| object.read(offset, len))
+    }
+
+    /// Returns `false` if the large object does not exist
+    pub(crate) fn write(&self, oid: u32, offset: usize, data: &[u8]) -> SystemResult<bool> {
+        match self.objects.write().expect("to acquire write lock").get_mut(&oid) {
+            Some(object) => {
+                object.write(offset, data);
+                if !data.is_empty() {
+                    let changed = offset / PAGE_SIZE..=(offset + data.len() - 1) / PAGE_SIZE;
+                    self.pages.write(
+                        changed
+                            .filter_map(|page| object.pages.get(&page).map(|bytes| (page, bytes)))
+                            .map(|(page, bytes)| {
+                                (
+                                    vec![Datum::from_u64(oid as u64), Datum::from_u64(page as u64)],
+                                    vec![Datum::from_string(bytea_output(bytes))],
+                                )
+                            })
+                            .collect(),
+                    )?;
+                }
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    pub(crate) fn unlink(&self, oid: u32) -> SystemResult<bool> {
+        match self.objects.write().expect("to acquire write lock").remove(&oid) {
+            Some(object) => {
+                self.pages.delete(
+                    object
+                        .pages
+                        .keys()
+                        .map(|page| vec![Datum::from_u64(oid as u64), Datum::from_u64(*page as u64)])
+                        .collect(),
+                )?;
+                self.metadata.delete(vec![vec![Datum::from_u64(oid as u64)]])?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn assigned_oids_are_not_reused() {
+        let large_objects = LargeObjects::default();
+        assert_eq!(
+            large_objects
+                .create(Some(FIRST_NORMAL_OID + 1))
+                .expect("no system errors"),
+            Some(FIRST_NORMAL_OID + 1)
+        );
+        assert_eq!(
+            large_objects.create(None).expect("no system errors"),
+            Some(FIRST_NORMAL_OID)
+        );
+        assert_eq!(
+            large_objects.create(None).expect("no system errors"),
+            Some(FIRST_NORMAL_OID + 2)
+        );
+        assert_eq!(
+            large_objects.create(Some(FIRST_NORMAL_OID)).expect("no system errors"),
+            None
+        );
+    }
+
+    #[test]
+    fn content_spans_pages() {
+        let large_objects = LargeObjects::default();
+        let oid = large_objects.create(None).expect("no system errors").expect("created");
+        let data = (0..PAGE_SIZE * 2 + 10).map(|index| index as u8).collect::<Vec<u8>>();
+        assert!(large_objects.write(oid, 5, &data).expect("no system errors"));
+
+        assert_eq!(large_objects.read(oid, 0, 5), Some(vec![0; 5]));
+        assert_eq!(large_objects.read(oid, 5, data.len()), Some(data.clone()));
+        assert_eq!(
+            large_objects.read(oid, PAGE_SIZE - 1, 3),
+            Some(data[PAGE_SIZE - 6..PAGE_SIZE - 3].to_vec())
+        );
+        assert_eq!(
+            large_objects.read(oid, data.len() + 3, 10),
+            Some(data[data.len() - 2..].to_vec())
+        );
+        assert_eq!(large_objects.read(oid, data.len() + 5, 10), Some(vec![]));
+    }
+
+    #[test]
+    fn holes_read_as_zeros() {
+        let large_objects = LargeObjects::default();
+        let oid = large_objects.create(None).expect("no system errors").expect("created");
+        assert!(large_objects
+            .write(oid, PAGE_SIZE * 3, b"end")
+            .expect("no system errors"));
+
+        let content = large_objects.read(oid, 0, PAGE_SIZE * 4).expect("exists");
+        assert_eq!(content.len(), PAGE_SIZE * 3 + 3);
+        assert!(content[..PAGE_SIZE * 3].iter().all(|byte| *byte == 0));
+        assert_eq!(&content[PAGE_SIZE * 3..], b"end");
+    }
+
+    #[test]
+    fn unlinked_objects_do_not_exist() {
+        let large_objects = LargeObjects::default();
+        let oid = large_objects.create(None).expect("no system errors").expect("created");
+        assert!(large_objects.unlink(oid).expect("no system errors"));
+
+        assert!(!large_objects.exists(oid));
+        assert_eq!(large_objects.read(oid, 0, 1), None);
+        assert!(!large_objects.write(oid, 0, b"data").expect("no system errors"));
+        assert!(!large_objects.unlink(oid).expect("no system errors"));
+    }
+}
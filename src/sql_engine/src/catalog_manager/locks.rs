@@ -0,0 +1,266 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Locks of schemas, tables and rows. A lock is held by the transaction of a
+//! session until the transaction ends, statements outside of a transaction
+//! block release their locks when they complete. Shared locks are held by
+//! many transactions at once, an exclusive lock by a single one. A
+//! transaction that can't acquire a lock waits until the holders release
+//! it. If the holders wait, directly or through other transactions, for a
+//! lock of the waiting transaction, the waiting one is aborted.
+use crate::cancellation::CancellationToken;
+use protocol::results::QueryError;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Condvar, Mutex, MutexGuard,
+    },
+    time::Duration,
+};
+use storage::Key;
+
+/// How often a waiting transaction checks whether its statement is
+/// interrupted
+const WAIT_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Transaction that holds or waits for locks
+pub(crate) type Owner = u64;
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub(crate) enum LockMode {
+    /// statements read or write rows of a table
+    Shared,
+    /// definition of an object is changed or a row is updated
+    Exclusive,
+}
+
+impl LockMode {
+    fn conflicts(self, other: LockMode) -> bool {
+        self == LockMode::Exclusive || other == LockMode::Exclusive
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum Resource {
+    /// schema by its name or table by its qualified name
+    Object(String),
+    /// row of a table with its key
+    Row(String, Key),
+}
+
+#[derive(Default)]
+struct LockTable {
+    held: HashMap<Resource, HashMap<Owner, LockMode>>,
+    waiting: HashMap<Owner, (Resource, LockMode)>,
+}
+
+impl LockTable {
+    /// Owners that hold `resource` in a mode that conflicts with `mode`
+    fn blockers<'t>(&'t self, owner: Owner, resource: &Resource, mode: LockMode) -> impl Iterator<Item = Owner> + 't {
+        self.held
+            .get(resource)
+            .into_iter()
+            .flatten()
+            .filter(move |(holder, held)| **holder != owner && mode.conflicts(**held))
+            .map(|(holder, _)| *holder)
+    }
+
+    /// Whether owners that block `owner` wait for it, directly or through
+    /// other owners
+    fn deadlocked(&self, owner: Owner) -> bool {
+        let mut visited = HashSet::new();
+        let mut waiters = vec![owner];
+        while let Some(waiter) = waiters.pop() {
+            let (resource, mode) = match self.waiting.get(&waiter) {
+                Some(waiting) => waiting,
+                None => continue,
+            };
+            for blocker in self.blockers(waiter, resource, *mode) {
+                if blocker == owner {
+                    return true;
+                }
+                if visited.insert(blocker) {
+                    waiters.push(blocker);
+                }
+            }
+        }
+        false
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct Locks {
+    table: Mutex<LockTable>,
+    released: Condvar,
+    owners: AtomicU64,
+}
+
+impl Locks {
+    /// Identifies the transaction of a new session
+    pub(crate) fn owner(&self) -> Owner {
+        self.owners.fetch_add(1, Ordering::SeqCst)
+    }
+
+    /// Waits until `owner` holds `resource` in `mode`. A held lock is
+    /// upgraded to the exclusive one, but never downgraded.
+    pub(crate) fn acquire(
+        &self,
+        owner: Owner,
+        resource: Resource,
+        mode: LockMode,
+        cancellation: &CancellationToken,
+    ) -> Result<(), QueryError> {
+        let mut table = self.table();
+        loop {
+            if table.blockers(owner, &resource, mode).next().is_none() {
+                table.waiting.remove(&owner);
+                let held = table.held.entry(resource).or_default().entry(owner).or_insert(mode);
+                if *held < mode {
+                    *held = mode;
+                }
+                return Ok(());
+            }
+            table.waiting.insert(owner, (resource.clone(), mode));
+            let interrupted = if table.deadlocked(owner) {
+                Err(QueryError::deadlock_detected())
+            } else {
+                cancellation.check()
+            };
+            if let Err(error) = interrupted {
+                table.waiting.remove(&owner);
+                return Err(error);
+            }
+            table = self
+                .released
+                .wait_timeout(table, WAIT_INTERVAL)
+                .expect("to acquire lock table lock")
+                .0;
+        }
+    }
+
+    /// Locks rows of the table with `keys` exclusively
+    pub(crate) fn lock_rows(
+        &self,
+        owner: Owner,
+        schema_name: &str,
+        table_name: &str,
+        keys: impl IntoIterator<Item = Key>,
+        cancellation: &CancellationToken,
+    ) -> Result<(), QueryError> {
+        let table = format!("{}.{}", schema_name, table_name);
+        for key in keys {
+            self.acquire(
+                owner,
+                Resource::Row(table.clone(), key),
+                LockMode::Exclusive,
+                cancellation,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Releases all locks of `owner` and wakes up transactions that wait
+    /// for them
+    pub(crate) fn release(&self, owner: Owner) {
+        let mut table = self.table();
+        let before = table.held.len();
+        table.held.retain(|_, holders| {
+            holders.remove(&owner);
+            !holders.is_empty()
+        });
+        table.waiting.remove(&owner);
+        if table.held.len() != before {
+            self.released.notify_all();
+        }
+    }
+
+    fn table(&self) -> MutexGuard<'_, LockTable> {
+        self.table.lock().expect("to acquire lock table lock")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cancellation::Interruption;
+    use representation::Binary;
+    use std::{sync::Arc, thread};
+
+    fn table(name: &str) -> Resource {
+        Resource::Object(name.to_owned())
+    }
+
+    #[test]
+    fn shared_locks_are_held_at_once() {
+        let locks = Locks::default();
+        let token = CancellationToken::new();
+
+        assert_eq!(locks.acquire(1, table("t"), LockMode::Shared, &token), Ok(()));
+        assert_eq!(locks.acquire(2, table("t"), LockMode::Shared, &token), Ok(()));
+
+        token.cancel(Interruption::UserRequest);
+        assert_eq!(
+            locks.acquire(1, table("t"), LockMode::Exclusive, &token),
+            Err(QueryError::query_canceled("user request"))
+        );
+        locks.release(2);
+        assert_eq!(locks.acquire(1, table("t"), LockMode::Exclusive, &token), Ok(()));
+    }
+
+    #[test]
+    fn exclusive_lock_waits_for_release() {
+        let locks = Arc::new(Locks::default());
+        let token = CancellationToken::new();
+        locks
+            .acquire(1, table("t"), LockMode::Shared, &token)
+            .expect("lock acquired");
+
+        let waiter = {
+            let locks = locks.clone();
+            thread::spawn(move || locks.acquire(2, table("t"), LockMode::Exclusive, &CancellationToken::new()))
+        };
+        thread::sleep(Duration::from_millis(50));
+        locks.release(1);
+
+        assert_eq!(waiter.join().expect("waiter to finish"), Ok(()));
+    }
+
+    #[test]
+    fn cycle_of_waiting_transactions_is_a_deadlock() {
+        let locks = Arc::new(Locks::default());
+        let token = CancellationToken::new();
+        locks
+            .lock_rows(1, "s", "t", vec![Binary::pack(&[])], &token)
+            .expect("row locked");
+        locks
+            .acquire(2, table("s.t"), LockMode::Exclusive, &token)
+            .expect("table locked");
+
+        let waiter = {
+            let locks = locks.clone();
+            thread::spawn(move || locks.acquire(1, table("s.t"), LockMode::Shared, &CancellationToken::new()))
+        };
+        while !locks.table().waiting.contains_key(&1) {
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        assert_eq!(
+            locks.lock_rows(2, "s", "t", vec![Binary::pack(&[])], &token),
+            Err(QueryError::deadlock_detected())
+        );
+        locks.release(2);
+        assert_eq!(waiter.join().expect("waiter to finish"), Ok(()));
+    }
+}
@@ -12,22 +12,63 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::{catalog_manager::data_definition::DataDefinition, ColumnDefinition};
+use crate::{
+    catalog_manager::{
+        aggregates::Aggregates, data_definition::DataDefinition, default_settings::DefaultSettings,
+        dependencies::DependencyGraph, event_triggers::EventTriggers, extensions::Extensions,
+        foreign_tables::ForeignTables, functions::Functions, privileges::Privileges, publications::Publications,
+        roles::Roles, storage_options::StorageOptions, table_statistics::AccessStatistics,
+    },
+    dml::functions::{large_objects as large_object_functions, system, ScalarFunctions},
+    ColumnDefinition,
+};
 use kernel::{Object, Operation, SystemError, SystemResult};
+use protocol::{scram::ScramVerifier, PasswordStore};
 use std::{
     collections::HashMap,
+    io,
     path::PathBuf,
     sync::{
         atomic::{AtomicU64, Ordering},
-        RwLock,
+        Arc, RwLock,
     },
+    time::SystemTime,
+};
+use storage::{
+    Database, DefinitionError, InMemoryDatabase, InitStatus, Key, PersistentDatabase, ReadCursor, Row, StorageError,
 };
-use storage::{Database, InMemoryDatabase, InitStatus, Key, PersistentDatabase, ReadCursor, Row};
 
 pub type FullSchemaId = Option<u64>;
 pub type FullTableId = Option<(u64, Option<u64>)>;
 
+mod aggregates;
 mod data_definition;
+mod default_settings;
+mod dependencies;
+mod event_triggers;
+mod extensions;
+mod foreign_tables;
+mod format;
+mod functions;
+mod large_objects;
+mod locks;
+mod privileges;
+mod publications;
+mod roles;
+mod storage_options;
+mod system_tables;
+mod table_statistics;
+
+pub use dependencies::CatalogObject;
+pub(crate) use event_triggers::{DdlEvent, EventTrigger, DDL_TAGS};
+pub use foreign_tables::ForeignDataWrapper;
+pub(crate) use large_objects::LargeObjects;
+pub(crate) use locks::{LockMode, Locks, Owner, Resource};
+pub use privileges::{Privilege, PUBLIC, SUPERUSER};
+pub(crate) use publications::SlotCreationError;
+pub use publications::{ChangeHook, ChangeKind, ChangeStream, ReplicationSlotInfo, RowChange};
+pub use storage_options::AutovacuumOptions;
+pub use table_statistics::TableStatistics;
 
 pub enum DropStrategy {
     Restrict,
@@ -43,9 +84,25 @@ pub enum DropSchemaError {
 
 pub struct CatalogManager {
     key_id_generator: AtomicU64,
+    catalog_version: AtomicU64,
+    transaction_id_generator: Arc<AtomicU64>,
+    started_at: SystemTime,
     data_storage: Box<dyn Database>,
     data_definition: DataDefinition,
     schemas: RwLock<HashMap<u64, String>>,
+    privileges: Privileges,
+    roles: Roles,
+    default_settings: DefaultSettings,
+    statistics: AccessStatistics,
+    storage_options: StorageOptions,
+    publications: Publications,
+    foreign_tables: ForeignTables,
+    functions: Functions,
+    aggregates: Aggregates,
+    scalar_functions: ScalarFunctions,
+    event_triggers: EventTriggers,
+    extensions: Extensions,
+    locks: Arc<Locks>,
 }
 
 impl Default for CatalogManager {
@@ -58,16 +115,39 @@ unsafe impl Send for CatalogManager {}
 unsafe impl Sync for CatalogManager {}
 
 const DEFAULT_CATALOG: &'_ str = "public";
+/// Lower ids are reserved in PostgreSQL
+const FIRST_TRANSACTION_ID: u64 = 3;
+/// OIDs below are reserved for objects of the system catalog
+const FIRST_NORMAL_OID: u32 = 16384;
 
 impl CatalogManager {
     pub fn in_memory() -> SystemResult<CatalogManager> {
         let data_definition = DataDefinition::in_memory();
         data_definition.create_catalog(DEFAULT_CATALOG);
+        let transaction_id_generator = Arc::new(AtomicU64::new(FIRST_TRANSACTION_ID));
+        let started_at = SystemTime::now();
+        let scalar_functions = builtin_functions(&transaction_id_generator, started_at, LargeObjects::default());
         Ok(Self {
             key_id_generator: AtomicU64::default(),
+            catalog_version: AtomicU64::default(),
+            transaction_id_generator,
+            started_at,
             data_storage: Box::new(InMemoryDatabase::default()),
             data_definition,
             schemas: RwLock::default(),
+            privileges: Privileges::default(),
+            roles: Roles::default(),
+            default_settings: DefaultSettings::default(),
+            statistics: AccessStatistics::default(),
+            storage_options: StorageOptions::default(),
+            publications: Publications::default(),
+            foreign_tables: ForeignTables::default(),
+            functions: Functions::new(scalar_functions.clone()),
+            aggregates: Aggregates::default(),
+            scalar_functions,
+            event_triggers: EventTriggers::default(),
+            extensions: Extensions::default(),
+            locks: Arc::default(),
         })
     }
 
@@ -92,12 +172,19 @@ impl CatalogManager {
                         }
                         Ok(Err(error)) => {
                             log::error!("Error during schema {:?} initialization {:?}", schema, error);
-                            return Err(SystemError::bug_in_sql_engine(
+                            return Err(storage_error(error).context(format!(
+                                "{} {}",
                                 Operation::Access,
-                                Object::Schema(schema.as_str()),
-                            ));
+                                Object::Schema(schema.as_str())
+                            )));
+                        }
+                        Err(io_error) => {
+                            return Err(SystemError::io(io_error).context(format!(
+                                "{} {}",
+                                Operation::Access,
+                                Object::Schema(schema.as_str())
+                            )))
                         }
-                        Err(io_error) => return Err(SystemError::io(io_error)),
                     }
                 }
             }
@@ -105,11 +192,48 @@ impl CatalogManager {
                 data_definition.create_catalog(DEFAULT_CATALOG);
             }
         }
+        let privileges = Privileges::load(data_definition.system_table(privileges::PRIVILEGES_TABLE))?;
+        let aggregates = Aggregates::load(data_definition.system_table(aggregates::AGGREGATES_TABLE))?;
+        let event_triggers = EventTriggers::load(data_definition.system_table(event_triggers::EVENT_TRIGGERS_TABLE))?;
+        let roles = Roles::load(data_definition.system_table(roles::ROLES_TABLE))?;
+        let default_settings = DefaultSettings::load(data_definition.system_table(default_settings::SETTINGS_TABLE))?;
+        let publications = Publications::load(
+            data_definition.system_table(publications::PUBLICATIONS_TABLE),
+            data_definition.system_table(publications::REPLICATION_SLOTS_TABLE),
+            data_definition.system_table(publications::CHANGE_LOG_TABLE),
+        )?;
+        let transaction_id_generator = Arc::new(AtomicU64::new(FIRST_TRANSACTION_ID));
+        let started_at = SystemTime::now();
+        let large_objects = LargeObjects::load(
+            data_definition.system_table(large_objects::LARGE_OBJECT_METADATA_TABLE),
+            data_definition.system_table(large_objects::LARGE_OBJECTS_TABLE),
+        )?;
+        let scalar_functions = builtin_functions(&transaction_id_generator, started_at, large_objects);
+        let functions = Functions::load(
+            data_definition.system_table(functions::FUNCTIONS_TABLE),
+            scalar_functions.clone(),
+        )?;
         Ok(Self {
             key_id_generator: AtomicU64::default(),
+            catalog_version: AtomicU64::default(),
+            transaction_id_generator,
+            started_at,
             data_storage: Box::new(catalog),
             data_definition,
             schemas: RwLock::default(),
+            privileges,
+            roles,
+            default_settings,
+            statistics: AccessStatistics::default(),
+            storage_options: StorageOptions::default(),
+            publications,
+            foreign_tables: ForeignTables::default(),
+            functions,
+            aggregates,
+            scalar_functions,
+            event_triggers,
+            extensions: Extensions::default(),
+            locks: Arc::default(),
         })
     }
 
@@ -117,6 +241,39 @@ impl CatalogManager {
         self.key_id_generator.fetch_add(1, Ordering::SeqCst)
     }
 
+    /// Every statement runs in its own transaction, an id is assigned only
+    /// when it is asked for, e.g. by `txid_current()`. Ids are not persisted
+    /// and start over after restart.
+    pub fn next_transaction_id(&self) -> u64 {
+        self.transaction_id_generator.fetch_add(1, Ordering::SeqCst)
+    }
+
+    /// When the database was opened, i.e. the start time of a server
+    pub fn started_at(&self) -> SystemTime {
+        self.started_at
+    }
+
+    /// Changes whenever a schema or a table is created or dropped, so that
+    /// prepared statements can detect that they were planned against
+    /// outdated metadata
+    pub fn catalog_version(&self) -> u64 {
+        self.catalog_version.load(Ordering::SeqCst)
+    }
+
+    /// Makes data of all tables durable, returns the number of flushed bytes
+    pub fn checkpoint(&self) -> SystemResult<usize> {
+        match self.data_storage.checkpoint() {
+            Ok(Ok(flushed)) => Ok(flushed),
+            Ok(Err(error)) => Err(storage_error(error).context("checkpoint")),
+            Err(io_error) => Err(SystemError::io(io_error).context("checkpoint")),
+        }
+    }
+
+    fn catalog_changed(&self) {
+        self.catalog_version.fetch_add(1, Ordering::SeqCst);
+    }
+
+    #[tracing::instrument(skip(self))]
     pub fn create_schema(&self, schema_name: &str) -> SystemResult<()> {
         match self.data_definition.create_schema(DEFAULT_CATALOG, schema_name) {
             Some((_, Some(schema_id))) => {
@@ -125,13 +282,13 @@ impl CatalogManager {
                     .write()
                     .expect("to acquire write lock")
                     .insert(schema_id, schema_name.to_owned());
-                match self.data_storage.create_schema(schema_name) {
-                    Ok(Ok(Ok(()))) => Ok(()),
-                    _ => Err(SystemError::bug_in_sql_engine(
-                        Operation::Create,
-                        Object::Schema(schema_name),
-                    )),
-                }
+                stored(
+                    self.data_storage.create_schema(schema_name),
+                    Operation::Create,
+                    Object::Schema(schema_name),
+                )?;
+                self.catalog_changed();
+                Ok(())
             }
             Some((_, None)) => Err(SystemError::bug_in_sql_engine(
                 Operation::Create,
@@ -144,7 +301,28 @@ impl CatalogManager {
         }
     }
 
+    /// `Restrict` strategy keeps the schema if any object depends on it,
+    /// `Cascade` drops the dependents of the schema before it
+    #[tracing::instrument(skip(self, strategy))]
     pub fn drop_schema(&self, schema_id: u64, strategy: DropStrategy) -> SystemResult<Result<(), DropSchemaError>> {
+        let schema_name = match self.schemas.read().expect("to acquire read lock").get(&schema_id) {
+            None => return Ok(Err(DropSchemaError::DoesNotExist)),
+            Some(schema_name) => schema_name.clone(),
+        };
+        let schema = CatalogObject::Schema(schema_name);
+        match strategy {
+            DropStrategy::Restrict => {
+                if !self.dependents(&schema).is_empty() {
+                    return Ok(Err(DropSchemaError::HasDependentObjects));
+                }
+            }
+            DropStrategy::Cascade => {
+                for object in self.dependency_graph().drop_order(&schema) {
+                    log::debug!("drop cascades to {}", object);
+                    self.drop_dependent(&object)?;
+                }
+            }
+        }
         match self.schemas.write().expect("to acquire write lock").remove(&schema_id) {
             None => Ok(Err(DropSchemaError::DoesNotExist)),
             Some(schema_name) => {
@@ -152,19 +330,81 @@ impl CatalogManager {
                     .data_definition
                     .drop_schema(DEFAULT_CATALOG, schema_name.as_str(), strategy)
                 {
-                    Ok(()) => match self.data_storage.drop_schema(schema_name.as_str()) {
-                        Ok(Ok(Ok(()))) => Ok(Ok(())),
-                        _ => Err(SystemError::bug_in_sql_engine(
+                    Ok(()) => {
+                        stored(
+                            self.data_storage.drop_schema(schema_name.as_str()),
                             Operation::Drop,
                             Object::Schema(schema_name.as_str()),
-                        )),
-                    },
+                        )?;
+                        self.privileges.forget_schema(schema_name.as_str())?;
+                        self.statistics.forget_schema(schema_name.as_str());
+                        self.storage_options.forget_schema(schema_name.as_str());
+                        self.publications.forget_schema(schema_name.as_str())?;
+                        self.foreign_tables.forget_schema(schema_name.as_str());
+                        self.catalog_changed();
+                        Ok(Ok(()))
+                    }
                     Err(error) => Ok(Err(error)),
                 }
             }
         }
     }
 
+    /// Objects that depend on `object` directly
+    pub fn dependents(&self, object: &CatalogObject) -> Vec<CatalogObject> {
+        self.dependency_graph()
+            .dependents(object)
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    fn dependency_graph(&self) -> DependencyGraph {
+        let mut graph = DependencyGraph::default();
+        let table = |schema_name: &str, table_name: &str| {
+            if self.is_foreign_table(schema_name, table_name) {
+                CatalogObject::ForeignTable(schema_name.to_owned(), table_name.to_owned())
+            } else {
+                CatalogObject::Table(schema_name.to_owned(), table_name.to_owned())
+            }
+        };
+        for schema_name in self.schemas() {
+            for table_name in self.tables(&schema_name) {
+                graph.add(
+                    table(&schema_name, &table_name),
+                    CatalogObject::Schema(schema_name.clone()),
+                );
+            }
+        }
+        for (publication, schema_name, table_name) in self.publications.members() {
+            let referenced = table(&schema_name, &table_name);
+            graph.add(
+                CatalogObject::PublicationTable {
+                    publication,
+                    schema_name,
+                    table_name,
+                },
+                referenced,
+            );
+        }
+        graph
+    }
+
+    fn drop_dependent(&self, object: &CatalogObject) -> SystemResult<()> {
+        match object {
+            CatalogObject::Schema(_) => Ok(()),
+            CatalogObject::Table(schema_name, table_name) | CatalogObject::ForeignTable(schema_name, table_name) => {
+                self.drop_table(schema_name, table_name)
+            }
+            CatalogObject::PublicationTable {
+                publication,
+                schema_name,
+                table_name,
+            } => self.publications.remove_table(publication, schema_name, table_name),
+        }
+    }
+
+    #[tracing::instrument(skip(self, column_definitions))]
     pub fn create_table(
         &self,
         schema_id: u64,
@@ -175,13 +415,14 @@ impl CatalogManager {
             Some(schema_name) => {
                 self.data_definition
                     .create_table(DEFAULT_CATALOG, schema_name, table_name, column_definitions);
-                match self.data_storage.create_object(schema_name, table_name) {
-                    Ok(Ok(Ok(()))) => Ok(()),
-                    _ => Err(SystemError::bug_in_sql_engine(
-                        Operation::Create,
-                        Object::Table(schema_name, table_name),
-                    )),
-                }
+                stored(
+                    self.data_storage.create_object(schema_name, table_name),
+                    Operation::Create,
+                    Object::Table(schema_name, table_name),
+                )?;
+                self.statistics.register_table(schema_name, table_name);
+                self.catalog_changed();
+                Ok(())
             }
             None => Err(SystemError::bug_in_sql_engine(
                 Operation::Create,
@@ -190,53 +431,223 @@ impl CatalogManager {
         }
     }
 
+    /// Replaces definitions of all columns of a table, e.g. to change their
+    /// defaults or nullability
+    pub(crate) fn alter_columns(
+        &self,
+        schema_name: &str,
+        table_name: &str,
+        column_definitions: &[ColumnDefinition],
+    ) -> SystemResult<()> {
+        self.data_definition
+            .alter_columns(DEFAULT_CATALOG, schema_name, table_name, column_definitions);
+        self.catalog_changed();
+        Ok(())
+    }
+
     pub fn table_columns(&self, schema_name: &str, table_name: &str) -> SystemResult<Vec<ColumnDefinition>> {
         Ok(self
             .data_definition
             .table_columns(DEFAULT_CATALOG, schema_name, table_name))
     }
 
+    /// Names of all schemas
+    pub fn schemas(&self) -> Vec<String> {
+        self.data_definition.schemas(DEFAULT_CATALOG)
+    }
+
+    /// Names of all tables of `schema_name`
+    pub fn tables(&self, schema_name: &str) -> Vec<String> {
+        self.data_definition.tables(DEFAULT_CATALOG, schema_name)
+    }
+
+    #[tracing::instrument(skip(self))]
     pub fn drop_table(&self, schema_name: &str, table_name: &str) -> SystemResult<()> {
         self.data_definition
             .drop_table(DEFAULT_CATALOG, schema_name, table_name);
-        match self.data_storage.drop_object(schema_name, table_name) {
-            Ok(Ok(Ok(()))) => Ok(()),
-            _ => Err(SystemError::bug_in_sql_engine(
-                Operation::Drop,
-                Object::Table(schema_name, table_name),
-            )),
-        }
+        stored(
+            self.data_storage.drop_object(schema_name, table_name),
+            Operation::Drop,
+            Object::Table(schema_name, table_name),
+        )?;
+        self.privileges.forget_table(schema_name, table_name)?;
+        self.statistics.forget_table(schema_name, table_name);
+        self.storage_options.forget_table(schema_name, table_name);
+        self.publications.forget_table(schema_name, table_name)?;
+        self.foreign_tables.forget_table(schema_name, table_name);
+        self.catalog_changed();
+        Ok(())
     }
 
+    #[tracing::instrument(skip(self, values))]
     pub fn write_into(&self, schema_name: &str, table_name: &str, values: Vec<Row>) -> SystemResult<usize> {
         log::debug!("{:#?}", values);
-        match self.data_storage.write(schema_name, table_name, values) {
-            Ok(Ok(Ok(size))) => Ok(size),
-            _ => Err(SystemError::bug_in_sql_engine(
-                Operation::Access,
-                Object::Table(schema_name, table_name),
-            )),
-        }
+        stored(
+            self.data_storage.write(schema_name, table_name, values),
+            Operation::Access,
+            Object::Table(schema_name, table_name),
+        )
     }
 
+    /// Rows of foreign tables are supplied by their foreign data wrappers
+    #[tracing::instrument(skip(self))]
     pub fn full_scan(&self, schema_name: &str, table_name: &str) -> SystemResult<ReadCursor> {
-        match self.data_storage.read(schema_name, table_name) {
-            Ok(Ok(Ok(read))) => Ok(read),
-            _ => Err(SystemError::bug_in_sql_engine(
-                Operation::Access,
-                Object::Table(schema_name, table_name),
-            )),
+        if let Some(scan) = self.foreign_scan(schema_name, table_name) {
+            return scan.map_err(|message| {
+                SystemError::io(io::Error::other(message)).context(format!(
+                    "{} {}",
+                    Operation::Access,
+                    Object::Table(schema_name, table_name)
+                ))
+            });
         }
+        stored(
+            self.data_storage.read(schema_name, table_name),
+            Operation::Access,
+            Object::Table(schema_name, table_name),
+        )
     }
 
+    #[tracing::instrument(skip(self, keys))]
     pub fn delete_from(&self, schema_name: &str, table_name: &str, keys: Vec<Key>) -> SystemResult<usize> {
-        match self.data_storage.delete(schema_name, table_name, keys) {
-            Ok(Ok(Ok(len))) => Ok(len),
-            _ => Err(SystemError::bug_in_sql_engine(
-                Operation::Access,
-                Object::Table(schema_name, table_name),
-            )),
+        stored(
+            self.data_storage.delete(schema_name, table_name, keys),
+            Operation::Access,
+            Object::Table(schema_name, table_name),
+        )
+    }
+
+    /// Rows supplied by the foreign data wrapper of the table, `None` if the
+    /// table is not foreign
+    pub fn foreign_scan(&self, schema_name: &str, table_name: &str) -> Option<Result<ReadCursor, String>> {
+        let columns = self
+            .data_definition
+            .table_columns(DEFAULT_CATALOG, schema_name, table_name);
+        self.foreign_tables.scan(schema_name, table_name, &columns)
+    }
+
+    pub(crate) fn privileges(&self) -> &Privileges {
+        &self.privileges
+    }
+
+    pub(crate) fn roles(&self) -> &Roles {
+        &self.roles
+    }
+
+    pub(crate) fn default_settings(&self) -> &DefaultSettings {
+        &self.default_settings
+    }
+
+    pub(crate) fn locks(&self) -> &Arc<Locks> {
+        &self.locks
+    }
+
+    pub(crate) fn statistics(&self) -> &AccessStatistics {
+        &self.statistics
+    }
+
+    pub(crate) fn storage_options(&self) -> &StorageOptions {
+        &self.storage_options
+    }
+
+    pub(crate) fn foreign_tables(&self) -> &ForeignTables {
+        &self.foreign_tables
+    }
+
+    pub(crate) fn functions(&self) -> &Functions {
+        &self.functions
+    }
+
+    pub(crate) fn aggregates(&self) -> &Aggregates {
+        &self.aggregates
+    }
+
+    pub(crate) fn scalar_functions(&self) -> &ScalarFunctions {
+        &self.scalar_functions
+    }
+
+    pub(crate) fn event_triggers(&self) -> &EventTriggers {
+        &self.event_triggers
+    }
+
+    pub(crate) fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    /// Names of loaded extensions in alphabetical order
+    pub fn loaded_extensions(&self) -> Vec<String> {
+        self.extensions.names()
+    }
+
+    /// Registers `wrapper` under `name` that `CREATE FOREIGN TABLE ... SERVER`
+    /// refers to, a wrapper that was registered under the same name is
+    /// replaced
+    pub fn register_foreign_data_wrapper(&self, name: &str, wrapper: Arc<dyn ForeignDataWrapper>) {
+        self.foreign_tables.register(name, wrapper);
+    }
+
+    pub fn is_foreign_table(&self, schema_name: &str, table_name: &str) -> bool {
+        self.foreign_tables.is_foreign(schema_name, table_name)
+    }
+
+    pub(crate) fn publications(&self) -> &Publications {
+        &self.publications
+    }
+
+    /// Stream of committed row changes of tables that are included into
+    /// the publication, `None` if it does not exist
+    pub fn subscribe(&self, publication: &str) -> Option<ChangeStream> {
+        self.publications.subscribe(publication)
+    }
+
+    /// Stream of row changes that the replication slot retained since its
+    /// subscriber received the last one, `None` if the slot does not exist
+    /// or is consumed by another stream
+    pub fn consume_replication_slot(&self, slot: &str) -> Option<ChangeStream> {
+        self.publications.consume_slot(slot)
+    }
+
+    /// Replication slots ordered by name
+    pub fn replication_slots(&self) -> Vec<ReplicationSlotInfo> {
+        self.publications.slots()
+    }
+
+    /// Registers `hook` that is called with committed row changes of all
+    /// tables
+    pub fn register_change_hook(&self, hook: Arc<dyn ChangeHook>) {
+        self.publications.register_hook(hook);
+    }
+
+    /// Access counters of the table, e.g. to decide whether it needs to be
+    /// vacuumed or analyzed
+    pub fn table_statistics(&self, schema_name: &str, table_name: &str) -> Option<TableStatistics> {
+        self.statistics.table(schema_name, table_name)
+    }
+
+    /// Rows are removed from storage as soon as they are deleted, so that
+    /// vacuuming a table makes their removal durable and forgets dead rows
+    pub fn autovacuum_table(&self, schema_name: &str, table_name: &str) -> SystemResult<()> {
+        self.checkpoint()?;
+        self.statistics.autovacuumed(schema_name, table_name);
+        Ok(())
+    }
+
+    /// Counts live rows of the table and forgets its modifications
+    pub fn autoanalyze_table(&self, schema_name: &str, table_name: &str) -> SystemResult<()> {
+        let mut live_rows = 0;
+        for row in self.full_scan(schema_name, table_name)? {
+            match row {
+                Ok(Ok(_row)) => live_rows += 1,
+                Ok(Err(error)) => {
+                    return Err(storage_error(error).context(format!("analyze {}.{}", schema_name, table_name)))
+                }
+                Err(io_error) => {
+                    return Err(SystemError::io(io_error).context(format!("analyze {}.{}", schema_name, table_name)))
+                }
+            }
         }
+        self.statistics.autoanalyzed(schema_name, table_name, live_rows);
+        Ok(())
     }
 
     pub fn schema_exists(&self, schema_name: &str) -> FullSchemaId {
@@ -250,6 +661,64 @@ impl CatalogManager {
             .table_exists(DEFAULT_CATALOG, schema_name, table_name)
             .and_then(|(_catalog, full_table)| full_table)
     }
+
+    /// OID that clients see for the table, e.g. in row descriptions. Table
+    /// ids are unique only within their schema, so the OID is made of the
+    /// schema id in the high and the table id in the low 16 bits.
+    pub fn table_oid(&self, schema_name: &str, table_name: &str) -> Option<u32> {
+        match self.table_exists(schema_name, table_name) {
+            Some((schema_id, Some(table_id))) => {
+                Some(FIRST_NORMAL_OID.wrapping_add(((schema_id as u32) << 16) | (table_id as u32 & 0xFFFF)))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl PasswordStore for CatalogManager {
+    fn password_verifier(&self, role: &str) -> Option<ScramVerifier> {
+        self.roles.password_verifier(role)
+    }
+}
+
+/// Built-in scalar functions, functions of the system and of large objects
+/// use the state of the catalog
+fn builtin_functions(
+    transaction_ids: &Arc<AtomicU64>,
+    started_at: SystemTime,
+    large_objects: LargeObjects,
+) -> ScalarFunctions {
+    let functions = ScalarFunctions::default();
+    system::register(&functions, transaction_ids.clone(), started_at);
+    large_object_functions::register(&functions, Arc::new(large_objects));
+    functions
+}
+
+/// Tells failures of the storage apart from objects that the engine expected
+/// to find in it
+fn stored<T>(
+    result: io::Result<Result<Result<T, DefinitionError>, StorageError>>,
+    operation: Operation,
+    object: Object,
+) -> SystemResult<T> {
+    let context = format!("{} {}", operation, object);
+    match result {
+        Ok(Ok(Ok(value))) => Ok(value),
+        Ok(Ok(Err(_definition_error))) => Err(SystemError::bug_in_sql_engine(operation, object)),
+        Ok(Err(error)) => Err(storage_error(error).context(context)),
+        Err(io_error) => Err(SystemError::io(io_error).context(context)),
+    }
+}
+
+pub(crate) fn storage_error(error: StorageError) -> SystemError {
+    match error {
+        StorageError::Io => SystemError::io(io::Error::other("storage failed to write")),
+        StorageError::CascadeIo(objects) => SystemError::io(io::Error::other(format!(
+            "storage failed to drop {}",
+            objects.join(", ")
+        ))),
+        StorageError::Storage => SystemError::corruption("storage can't serve its data".to_owned()),
+    }
 }
 
 #[cfg(test)]
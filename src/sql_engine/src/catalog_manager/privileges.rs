@@ -0,0 +1,773 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::catalog_manager::system_tables::{SystemRecord, SystemTable};
+use kernel::SystemResult;
+use representation::Datum;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{self, Display, Formatter},
+    sync::RwLock,
+};
+
+/// Role that bypasses all privilege checks
+pub const SUPERUSER: &str = "postgres";
+/// Pseudo role that every role is a member of
+pub const PUBLIC: &str = "public";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Privilege {
+    Select,
+    Insert,
+    Update,
+    Delete,
+    Create,
+    Usage,
+}
+
+impl Privilege {
+    pub fn table_privileges() -> Vec<Privilege> {
+        vec![
+            Privilege::Select,
+            Privilege::Insert,
+            Privilege::Update,
+            Privilege::Delete,
+        ]
+    }
+
+    pub fn schema_privileges() -> Vec<Privilege> {
+        vec![Privilege::Create, Privilege::Usage]
+    }
+
+    pub fn column_privileges() -> Vec<Privilege> {
+        vec![Privilege::Select, Privilege::Insert, Privilege::Update]
+    }
+
+    fn from_name(name: &str) -> Option<Privilege> {
+        [
+            Privilege::Select,
+            Privilege::Insert,
+            Privilege::Update,
+            Privilege::Delete,
+            Privilege::Create,
+            Privilege::Usage,
+        ]
+        .iter()
+        .copied()
+        .find(|privilege| privilege.to_string() == name)
+    }
+}
+
+impl Display for Privilege {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Privilege::Select => write!(f, "SELECT"),
+            Privilege::Insert => write!(f, "INSERT"),
+            Privilege::Update => write!(f, "UPDATE"),
+            Privilege::Delete => write!(f, "DELETE"),
+            Privilege::Create => write!(f, "CREATE"),
+            Privilege::Usage => write!(f, "USAGE"),
+        }
+    }
+}
+
+type Role = String;
+type Grants = HashMap<Role, HashSet<Privilege>>;
+
+struct ObjectPrivileges {
+    owner: Role,
+    grants: Grants,
+    /// privileges granted on individual columns of a table
+    column_grants: HashMap<String, Grants>,
+}
+
+impl ObjectPrivileges {
+    fn new(owner: &str) -> ObjectPrivileges {
+        ObjectPrivileges {
+            owner: owner.to_owned(),
+            grants: HashMap::new(),
+            column_grants: HashMap::new(),
+        }
+    }
+
+    fn has_on_column(&self, role: &str, column_name: &str, privilege: Privilege) -> bool {
+        self.has(role, privilege)
+            || self
+                .column_grants
+                .get(column_name)
+                .map(|grants| {
+                    [role, PUBLIC].iter().any(|grantee| {
+                        grants
+                            .get(*grantee)
+                            .map(|privileges| privileges.contains(&privilege))
+                            .unwrap_or(false)
+                    })
+                })
+                .unwrap_or(false)
+    }
+
+    fn has(&self, role: &str, privilege: Privilege) -> bool {
+        self.owner == role
+            || [role, PUBLIC].iter().any(|grantee| {
+                self.grants
+                    .get(*grantee)
+                    .map(|privileges| privileges.contains(&privilege))
+                    .unwrap_or(false)
+            })
+    }
+}
+
+/// Keeps owners of schemas and tables together with privileges granted on them.
+///
+/// Objects that were created before ownership tracking (e.g. loaded from disk)
+/// are treated as owned by `SUPERUSER`.
+///
+/// Default privileges are granted on tables when they are created. They are
+/// kept per creator role either for a single schema or for all schemas.
+///
+/// Every change is saved in the `PRIVILEGES` table of the system catalog, a
+/// record per owner or granted privilege.
+#[derive(Default)]
+pub(crate) struct Privileges {
+    schemas: RwLock<HashMap<String, ObjectPrivileges>>,
+    tables: RwLock<HashMap<(String, String), ObjectPrivileges>>,
+    table_defaults: RwLock<HashMap<(Role, Option<String>), Grants>>,
+    store: SystemTable,
+}
+
+/// Table of the system catalog with owners and privileges
+pub(crate) const PRIVILEGES_TABLE: &str = "PRIVILEGES";
+const SCHEMA_OWNER: &str = "schema owner";
+const TABLE_OWNER: &str = "table owner";
+const SCHEMA_GRANT: &str = "schema grant";
+const TABLE_GRANT: &str = "table grant";
+const COLUMN_GRANT: &str = "column grant";
+const TABLE_DEFAULT: &str = "table default";
+
+impl Privileges {
+    /// Owners and privileges saved in `store`
+    pub(crate) fn load(store: SystemTable) -> SystemResult<Privileges> {
+        let mut schemas = HashMap::<String, ObjectPrivileges>::new();
+        let mut tables = HashMap::<(String, String), ObjectPrivileges>::new();
+        let mut table_defaults = HashMap::<(Role, Option<String>), Grants>::new();
+        let mut grants = vec![];
+        store.load(|key, values| {
+            if key[0].as_str() == TABLE_DEFAULT {
+                let schema = match &key[2] {
+                    Datum::Null => None,
+                    schema => Some(schema.as_str().to_owned()),
+                };
+                if let Some(privilege) = Privilege::from_name(key[4].as_str()) {
+                    table_defaults
+                        .entry((key[1].as_str().to_owned(), schema))
+                        .or_default()
+                        .entry(key[3].as_str().to_owned())
+                        .or_default()
+                        .insert(privilege);
+                }
+                return;
+            }
+            let names = key.iter().map(Datum::as_str).collect::<Vec<_>>();
+            match names.as_slice() {
+                [SCHEMA_OWNER, schema] => {
+                    schemas.insert(schema.to_string(), ObjectPrivileges::new(values[0].as_str()));
+                }
+                [TABLE_OWNER, schema, table] => {
+                    tables.insert(
+                        (schema.to_string(), table.to_string()),
+                        ObjectPrivileges::new(values[0].as_str()),
+                    );
+                }
+                _ => grants.push(names.iter().map(ToString::to_string).collect::<Vec<_>>()),
+            }
+        })?;
+        // grants are applied when all owners are known, records are read in
+        // the order of their keys
+        for grant in grants {
+            match grant.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+                [SCHEMA_GRANT, schema, grantee, privilege] => {
+                    if let Some(privilege) = Privilege::from_name(privilege) {
+                        schemas
+                            .entry(schema.to_string())
+                            .or_insert_with(|| ObjectPrivileges::new(SUPERUSER))
+                            .grants
+                            .entry(grantee.to_string())
+                            .or_default()
+                            .insert(privilege);
+                    }
+                }
+                [TABLE_GRANT, schema, table, grantee, privilege] => {
+                    if let Some(privilege) = Privilege::from_name(privilege) {
+                        tables
+                            .entry((schema.to_string(), table.to_string()))
+                            .or_insert_with(|| ObjectPrivileges::new(SUPERUSER))
+                            .grants
+                            .entry(grantee.to_string())
+                            .or_default()
+                            .insert(privilege);
+                    }
+                }
+                [COLUMN_GRANT, schema, table, column, grantee, privilege] => {
+                    if let Some(privilege) = Privilege::from_name(privilege) {
+                        tables
+                            .entry((schema.to_string(), table.to_string()))
+                            .or_insert_with(|| ObjectPrivileges::new(SUPERUSER))
+                            .column_grants
+                            .entry(column.to_string())
+                            .or_default()
+                            .entry(grantee.to_string())
+                            .or_default()
+                            .insert(privilege);
+                    }
+                }
+                record => log::warn!("unknown record {:?} of privileges is skipped", record),
+            }
+        }
+        Ok(Privileges {
+            schemas: RwLock::new(schemas),
+            tables: RwLock::new(tables),
+            table_defaults: RwLock::new(table_defaults),
+            store,
+        })
+    }
+
+    fn save(&self) -> SystemResult<()> {
+        self.store.save(|| {
+            let mut records = vec![];
+            for (schema_name, schema) in self.schemas.read().expect("to acquire read lock").iter() {
+                records.push(owner(&[SCHEMA_OWNER, schema_name], &schema.owner));
+                records.extend(grant_records(&[SCHEMA_GRANT, schema_name], &schema.grants));
+            }
+            for ((schema_name, table_name), table) in self.tables.read().expect("to acquire read lock").iter() {
+                records.push(owner(&[TABLE_OWNER, schema_name, table_name], &table.owner));
+                records.extend(grant_records(&[TABLE_GRANT, schema_name, table_name], &table.grants));
+                for (column_name, grants) in &table.column_grants {
+                    records.extend(grant_records(
+                        &[COLUMN_GRANT, schema_name, table_name, column_name],
+                        grants,
+                    ));
+                }
+            }
+            for ((creator, schema_name), grants) in self.table_defaults.read().expect("to acquire read lock").iter() {
+                let schema_name = schema_name
+                    .as_ref()
+                    .map(|schema_name| Datum::from_string(schema_name.clone()))
+                    .unwrap_or_else(Datum::from_null);
+                for (grantee, privileges) in grants {
+                    for privilege in privileges {
+                        records.push((
+                            vec![
+                                Datum::from_str(TABLE_DEFAULT),
+                                Datum::from_string(creator.clone()),
+                                schema_name.clone(),
+                                Datum::from_string(grantee.clone()),
+                                Datum::from_string(privilege.to_string()),
+                            ],
+                            vec![],
+                        ));
+                    }
+                }
+            }
+            records
+        })
+    }
+
+    pub(crate) fn set_schema_owner(&self, schema_name: &str, role: &str) -> SystemResult<()> {
+        self.schemas
+            .write()
+            .expect("to acquire write lock")
+            .insert(schema_name.to_owned(), ObjectPrivileges::new(role));
+        self.save()
+    }
+
+    /// Table receives default privileges of `role` that created it
+    pub(crate) fn set_table_owner(&self, schema_name: &str, table_name: &str, role: &str) -> SystemResult<()> {
+        let mut table = ObjectPrivileges::new(role);
+        {
+            let defaults = self.table_defaults.read().expect("to acquire read lock");
+            for key in &[(role.to_owned(), None), (role.to_owned(), Some(schema_name.to_owned()))] {
+                for (grantee, privileges) in defaults.get(key).into_iter().flatten() {
+                    table
+                        .grants
+                        .entry(grantee.clone())
+                        .or_default()
+                        .extend(privileges.iter().copied());
+                }
+            }
+        }
+        self.tables
+            .write()
+            .expect("to acquire write lock")
+            .insert((schema_name.to_owned(), table_name.to_owned()), table);
+        self.save()
+    }
+
+    pub(crate) fn forget_schema(&self, schema_name: &str) -> SystemResult<()> {
+        self.schemas.write().expect("to acquire write lock").remove(schema_name);
+        self.tables
+            .write()
+            .expect("to acquire write lock")
+            .retain(|(schema, _table), _| schema != schema_name);
+        self.save()
+    }
+
+    pub(crate) fn forget_table(&self, schema_name: &str, table_name: &str) -> SystemResult<()> {
+        self.tables
+            .write()
+            .expect("to acquire write lock")
+            .remove(&(schema_name.to_owned(), table_name.to_owned()));
+        self.save()
+    }
+
+    pub(crate) fn is_schema_owner(&self, role: &str, schema_name: &str) -> bool {
+        role == SUPERUSER
+            || self
+                .schemas
+                .read()
+                .expect("to acquire read lock")
+                .get(schema_name)
+                .map(|schema| schema.owner == role)
+                .unwrap_or(false)
+    }
+
+    pub(crate) fn is_table_owner(&self, role: &str, schema_name: &str, table_name: &str) -> bool {
+        role == SUPERUSER
+            || self
+                .tables
+                .read()
+                .expect("to acquire read lock")
+                .get(&(schema_name.to_owned(), table_name.to_owned()))
+                .map(|table| table.owner == role)
+                .unwrap_or(false)
+    }
+
+    pub(crate) fn has_schema_privilege(&self, role: &str, schema_name: &str, privilege: Privilege) -> bool {
+        role == SUPERUSER
+            || self
+                .schemas
+                .read()
+                .expect("to acquire read lock")
+                .get(schema_name)
+                .map(|schema| schema.has(role, privilege))
+                .unwrap_or(false)
+    }
+
+    pub(crate) fn has_table_privilege(
+        &self,
+        role: &str,
+        schema_name: &str,
+        table_name: &str,
+        privilege: Privilege,
+    ) -> bool {
+        role == SUPERUSER
+            || self
+                .tables
+                .read()
+                .expect("to acquire read lock")
+                .get(&(schema_name.to_owned(), table_name.to_owned()))
+                .map(|table| table.has(role, privilege))
+                .unwrap_or(false)
+    }
+
+    /// Role has `privilege` on the column either through the table or the
+    /// column itself
+    pub(crate) fn has_column_privilege(
+        &self,
+        role: &str,
+        schema_name: &str,
+        table_name: &str,
+        column_name: &str,
+        privilege: Privilege,
+    ) -> bool {
+        role == SUPERUSER
+            || self
+                .tables
+                .read()
+                .expect("to acquire read lock")
+                .get(&(schema_name.to_owned(), table_name.to_owned()))
+                .map(|table| table.has_on_column(role, column_name, privilege))
+                .unwrap_or(false)
+    }
+
+    pub(crate) fn grant_on_schema(
+        &self,
+        schema_name: &str,
+        grantee: &str,
+        privileges: &[Privilege],
+    ) -> SystemResult<()> {
+        {
+            let mut schemas = self.schemas.write().expect("to acquire write lock");
+            let schema = schemas
+                .entry(schema_name.to_owned())
+                .or_insert_with(|| ObjectPrivileges::new(SUPERUSER));
+            schema
+                .grants
+                .entry(grantee.to_owned())
+                .or_default()
+                .extend(privileges.iter().copied());
+        }
+        self.save()
+    }
+
+    pub(crate) fn revoke_on_schema(
+        &self,
+        schema_name: &str,
+        grantee: &str,
+        privileges: &[Privilege],
+    ) -> SystemResult<()> {
+        if let Some(schema) = self
+            .schemas
+            .write()
+            .expect("to acquire write lock")
+            .get_mut(schema_name)
+        {
+            if let Some(granted) = schema.grants.get_mut(grantee) {
+                granted.retain(|privilege| !privileges.contains(privilege));
+            }
+        }
+        self.save()
+    }
+
+    pub(crate) fn grant_on_table(
+        &self,
+        schema_name: &str,
+        table_name: &str,
+        grantee: &str,
+        privileges: &[Privilege],
+    ) -> SystemResult<()> {
+        {
+            let mut tables = self.tables.write().expect("to acquire write lock");
+            let table = tables
+                .entry((schema_name.to_owned(), table_name.to_owned()))
+                .or_insert_with(|| ObjectPrivileges::new(SUPERUSER));
+            table
+                .grants
+                .entry(grantee.to_owned())
+                .or_default()
+                .extend(privileges.iter().copied());
+        }
+        self.save()
+    }
+
+    pub(crate) fn grant_table_defaults(
+        &self,
+        creator: &str,
+        schema_name: Option<&str>,
+        grantee: &str,
+        privileges: &[Privilege],
+    ) -> SystemResult<()> {
+        self.table_defaults
+            .write()
+            .expect("to acquire write lock")
+            .entry((creator.to_owned(), schema_name.map(ToOwned::to_owned)))
+            .or_default()
+            .entry(grantee.to_owned())
+            .or_default()
+            .extend(privileges.iter().copied());
+        self.save()
+    }
+
+    pub(crate) fn revoke_table_defaults(
+        &self,
+        creator: &str,
+        schema_name: Option<&str>,
+        grantee: &str,
+        privileges: &[Privilege],
+    ) -> SystemResult<()> {
+        if let Some(grants) = self
+            .table_defaults
+            .write()
+            .expect("to acquire write lock")
+            .get_mut(&(creator.to_owned(), schema_name.map(ToOwned::to_owned)))
+        {
+            if let Some(granted) = grants.get_mut(grantee) {
+                granted.retain(|privilege| !privileges.contains(privilege));
+            }
+        }
+        self.save()
+    }
+
+    /// Privileges revoked from a table are revoked from all its columns too
+    pub(crate) fn revoke_on_table(
+        &self,
+        schema_name: &str,
+        table_name: &str,
+        grantee: &str,
+        privileges: &[Privilege],
+    ) -> SystemResult<()> {
+        if let Some(table) = self
+            .tables
+            .write()
+            .expect("to acquire write lock")
+            .get_mut(&(schema_name.to_owned(), table_name.to_owned()))
+        {
+            if let Some(granted) = table.grants.get_mut(grantee) {
+                granted.retain(|privilege| !privileges.contains(privilege));
+            }
+            for grants in table.column_grants.values_mut() {
+                if let Some(granted) = grants.get_mut(grantee) {
+                    granted.retain(|privilege| !privileges.contains(privilege));
+                }
+            }
+        }
+        self.save()
+    }
+
+    pub(crate) fn grant_on_column(
+        &self,
+        schema_name: &str,
+        table_name: &str,
+        column_name: &str,
+        grantee: &str,
+        privilege: Privilege,
+    ) -> SystemResult<()> {
+        {
+            let mut tables = self.tables.write().expect("to acquire write lock");
+            let table = tables
+                .entry((schema_name.to_owned(), table_name.to_owned()))
+                .or_insert_with(|| ObjectPrivileges::new(SUPERUSER));
+            table
+                .column_grants
+                .entry(column_name.to_owned())
+                .or_default()
+                .entry(grantee.to_owned())
+                .or_default()
+                .insert(privilege);
+        }
+        self.save()
+    }
+
+    pub(crate) fn revoke_on_column(
+        &self,
+        schema_name: &str,
+        table_name: &str,
+        column_name: &str,
+        grantee: &str,
+        privilege: Privilege,
+    ) -> SystemResult<()> {
+        if let Some(granted) = self
+            .tables
+            .write()
+            .expect("to acquire write lock")
+            .get_mut(&(schema_name.to_owned(), table_name.to_owned()))
+            .and_then(|table| table.column_grants.get_mut(column_name))
+            .and_then(|grants| grants.get_mut(grantee))
+        {
+            granted.remove(&privilege);
+        }
+        self.save()
+    }
+}
+
+fn names(names: &[&str]) -> Vec<Datum<'static>> {
+    names.iter().map(|name| Datum::from_string(name.to_string())).collect()
+}
+
+fn owner(object: &[&str], owner: &str) -> SystemRecord {
+    (names(object), names(&[owner]))
+}
+
+fn grant_records(object: &[&str], grants: &Grants) -> Vec<SystemRecord> {
+    grants
+        .iter()
+        .flat_map(|(grantee, privileges)| {
+            privileges.iter().map(move |privilege| {
+                let mut key = object.to_vec();
+                let privilege = privilege.to_string();
+                key.extend_from_slice(&[grantee, &privilege]);
+                (names(&key), vec![])
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHEMA: &str = "schema_name";
+    const TABLE: &str = "table_name";
+
+    #[test]
+    fn owner_has_all_table_privileges() {
+        let privileges = Privileges::default();
+        privileges
+            .set_table_owner(SCHEMA, TABLE, "owner")
+            .expect("no system errors");
+
+        for privilege in Privilege::table_privileges() {
+            assert!(privileges.has_table_privilege("owner", SCHEMA, TABLE, privilege));
+            assert!(!privileges.has_table_privilege("other", SCHEMA, TABLE, privilege));
+        }
+    }
+
+    #[test]
+    fn superuser_has_privileges_on_not_owned_objects() {
+        let privileges = Privileges::default();
+        privileges.set_schema_owner(SCHEMA, "owner").expect("no system errors");
+        privileges
+            .set_table_owner(SCHEMA, TABLE, "owner")
+            .expect("no system errors");
+
+        assert!(privileges.is_schema_owner(SUPERUSER, SCHEMA));
+        assert!(privileges.is_table_owner(SUPERUSER, SCHEMA, TABLE));
+        assert!(privileges.has_schema_privilege(SUPERUSER, SCHEMA, Privilege::Create));
+        assert!(privileges.has_table_privilege(SUPERUSER, SCHEMA, TABLE, Privilege::Delete));
+    }
+
+    #[test]
+    fn granted_privileges_can_be_revoked() {
+        let privileges = Privileges::default();
+        privileges
+            .set_table_owner(SCHEMA, TABLE, "owner")
+            .expect("no system errors");
+        privileges
+            .grant_on_table(SCHEMA, TABLE, "other", &[Privilege::Select, Privilege::Insert])
+            .expect("no system errors");
+
+        assert!(privileges.has_table_privilege("other", SCHEMA, TABLE, Privilege::Select));
+        assert!(privileges.has_table_privilege("other", SCHEMA, TABLE, Privilege::Insert));
+
+        privileges
+            .revoke_on_table(SCHEMA, TABLE, "other", &[Privilege::Select])
+            .expect("no system errors");
+
+        assert!(!privileges.has_table_privilege("other", SCHEMA, TABLE, Privilege::Select));
+        assert!(privileges.has_table_privilege("other", SCHEMA, TABLE, Privilege::Insert));
+    }
+
+    #[test]
+    fn privileges_granted_to_public_apply_to_every_role() {
+        let privileges = Privileges::default();
+        privileges.set_schema_owner(SCHEMA, "owner").expect("no system errors");
+        privileges
+            .grant_on_schema(SCHEMA, PUBLIC, &[Privilege::Usage])
+            .expect("no system errors");
+
+        assert!(privileges.has_schema_privilege("any_role", SCHEMA, Privilege::Usage));
+        assert!(!privileges.has_schema_privilege("any_role", SCHEMA, Privilege::Create));
+    }
+
+    #[test]
+    fn column_privileges() {
+        let privileges = Privileges::default();
+        privileges
+            .set_table_owner(SCHEMA, TABLE, "owner")
+            .expect("no system errors");
+        privileges
+            .grant_on_column(SCHEMA, TABLE, "name", "other", Privilege::Select)
+            .expect("no system errors");
+
+        assert!(privileges.has_column_privilege("other", SCHEMA, TABLE, "name", Privilege::Select));
+        assert!(!privileges.has_column_privilege("other", SCHEMA, TABLE, "ssn", Privilege::Select));
+        assert!(!privileges.has_column_privilege("other", SCHEMA, TABLE, "name", Privilege::Update));
+        assert!(!privileges.has_table_privilege("other", SCHEMA, TABLE, Privilege::Select));
+        assert!(privileges.has_column_privilege("owner", SCHEMA, TABLE, "ssn", Privilege::Select));
+
+        privileges
+            .revoke_on_column(SCHEMA, TABLE, "name", "other", Privilege::Select)
+            .expect("no system errors");
+
+        assert!(!privileges.has_column_privilege("other", SCHEMA, TABLE, "name", Privilege::Select));
+    }
+
+    #[test]
+    fn revoke_on_table_revokes_column_privileges() {
+        let privileges = Privileges::default();
+        privileges
+            .set_table_owner(SCHEMA, TABLE, "owner")
+            .expect("no system errors");
+        privileges
+            .grant_on_column(SCHEMA, TABLE, "name", "other", Privilege::Select)
+            .expect("no system errors");
+        privileges
+            .grant_on_column(SCHEMA, TABLE, "name", "other", Privilege::Update)
+            .expect("no system errors");
+
+        privileges
+            .revoke_on_table(SCHEMA, TABLE, "other", &[Privilege::Select])
+            .expect("no system errors");
+
+        assert!(!privileges.has_column_privilege("other", SCHEMA, TABLE, "name", Privilege::Select));
+        assert!(privileges.has_column_privilege("other", SCHEMA, TABLE, "name", Privilege::Update));
+    }
+
+    #[test]
+    fn default_privileges_are_granted_on_created_tables() {
+        let privileges = Privileges::default();
+        privileges
+            .grant_table_defaults("owner", None, "reader", &[Privilege::Select])
+            .expect("no system errors");
+        privileges
+            .grant_table_defaults("owner", Some(SCHEMA), "writer", &[Privilege::Insert])
+            .expect("no system errors");
+        privileges
+            .grant_table_defaults("other", None, "other_reader", &[Privilege::Select])
+            .expect("no system errors");
+
+        privileges
+            .set_table_owner(SCHEMA, TABLE, "owner")
+            .expect("no system errors");
+        privileges
+            .set_table_owner("other_schema", TABLE, "owner")
+            .expect("no system errors");
+
+        assert!(privileges.has_table_privilege("reader", SCHEMA, TABLE, Privilege::Select));
+        assert!(privileges.has_table_privilege("writer", SCHEMA, TABLE, Privilege::Insert));
+        assert!(!privileges.has_table_privilege("other_reader", SCHEMA, TABLE, Privilege::Select));
+        assert!(privileges.has_table_privilege("reader", "other_schema", TABLE, Privilege::Select));
+        assert!(!privileges.has_table_privilege("writer", "other_schema", TABLE, Privilege::Insert));
+    }
+
+    #[test]
+    fn revoked_default_privileges_do_not_affect_existing_tables() {
+        let privileges = Privileges::default();
+        privileges
+            .grant_table_defaults("owner", None, "reader", &[Privilege::Select])
+            .expect("no system errors");
+        privileges
+            .set_table_owner(SCHEMA, TABLE, "owner")
+            .expect("no system errors");
+
+        privileges
+            .revoke_table_defaults("owner", None, "reader", &[Privilege::Select])
+            .expect("no system errors");
+        privileges
+            .set_table_owner(SCHEMA, "new_table", "owner")
+            .expect("no system errors");
+
+        assert!(privileges.has_table_privilege("reader", SCHEMA, TABLE, Privilege::Select));
+        assert!(!privileges.has_table_privilege("reader", SCHEMA, "new_table", Privilege::Select));
+    }
+
+    #[test]
+    fn dropped_schema_forgets_privileges_of_its_tables() {
+        let privileges = Privileges::default();
+        privileges.set_schema_owner(SCHEMA, "owner").expect("no system errors");
+        privileges
+            .set_table_owner(SCHEMA, TABLE, "owner")
+            .expect("no system errors");
+        privileges
+            .grant_on_table(SCHEMA, TABLE, "other", &[Privilege::Select])
+            .expect("no system errors");
+
+        privileges.forget_schema(SCHEMA).expect("no system errors");
+        privileges
+            .set_table_owner(SCHEMA, TABLE, "new_owner")
+            .expect("no system errors");
+
+        assert!(!privileges.has_table_privilege("other", SCHEMA, TABLE, Privilege::Select));
+        assert!(!privileges.is_table_owner("owner", SCHEMA, TABLE));
+    }
+}
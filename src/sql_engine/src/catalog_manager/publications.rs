@@ -0,0 +1,897 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::catalog_manager::system_tables::{SystemRecord, SystemTable};
+use kernel::SystemResult;
+use representation::Datum;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc, Arc, Condvar, Mutex, RwLock,
+    },
+    time::Duration,
+};
+
+/// Kind of a row change
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChangeKind {
+    Insert,
+    Update,
+    Delete,
+}
+
+/// Row level change of a published table. Values are in the text
+/// representation of query results, `None` stands for `NULL`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowChange {
+    pub schema_name: String,
+    pub table_name: String,
+    pub kind: ChangeKind,
+    /// values of the row before the change, `None` for inserts
+    pub old: Option<Vec<Option<String>>>,
+    /// values of the row after the change, `None` for deletes
+    pub new: Option<Vec<Option<String>>>,
+}
+
+impl RowChange {
+    pub(crate) fn insert(schema_name: &str, table_name: &str, new: &[Datum]) -> RowChange {
+        RowChange::new(schema_name, table_name, ChangeKind::Insert, None, Some(new))
+    }
+
+    pub(crate) fn update(schema_name: &str, table_name: &str, old: &[Datum], new: &[Datum]) -> RowChange {
+        RowChange::new(schema_name, table_name, ChangeKind::Update, Some(old), Some(new))
+    }
+
+    pub(crate) fn delete(schema_name: &str, table_name: &str, old: &[Datum]) -> RowChange {
+        RowChange::new(schema_name, table_name, ChangeKind::Delete, Some(old), None)
+    }
+
+    fn new(
+        schema_name: &str,
+        table_name: &str,
+        kind: ChangeKind,
+        old: Option<&[Datum]>,
+        new: Option<&[Datum]>,
+    ) -> RowChange {
+        RowChange {
+            schema_name: schema_name.to_owned(),
+            table_name: table_name.to_owned(),
+            kind,
+            old: old.map(values),
+            new: new.map(values),
+        }
+    }
+}
+
+fn values(row: &[Datum]) -> Vec<Option<String>> {
+    row.iter()
+        .map(|datum| match datum {
+            Datum::Null => None,
+            datum => Some(datum.to_string()),
+        })
+        .collect()
+}
+
+/// Callback that is invoked with committed changes of every table, e.g. to
+/// invalidate caches or to keep a search index in sync. It is called on the
+/// thread of the session that made the changes, so it has to be quick.
+pub trait ChangeHook: Send + Sync {
+    /// `changes` of a single table made by one statement
+    fn on_changes(&self, changes: &[RowChange]);
+}
+
+impl<F: Fn(&[RowChange]) + Send + Sync> ChangeHook for F {
+    fn on_changes(&self, changes: &[RowChange]) {
+        self(changes)
+    }
+}
+
+/// Changes of tables of a publication that are streamed to one subscriber.
+/// The stream ends when the publication is dropped. Streams of a
+/// replication slot also end when the slot is dropped.
+pub struct ChangeStream {
+    source: Source,
+}
+
+enum Source {
+    Channel(mpsc::Receiver<RowChange>),
+    Slot(Arc<ReplicationSlot>),
+}
+
+impl ChangeStream {
+    /// Waits for the next change, `None` when the stream has ended
+    pub fn recv(&self) -> Option<RowChange> {
+        match &self.source {
+            Source::Channel(receiver) => receiver.recv().ok(),
+            Source::Slot(slot) => slot.next(None),
+        }
+    }
+
+    /// Waits for the next change at most `timeout`
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<RowChange> {
+        match &self.source {
+            Source::Channel(receiver) => receiver.recv_timeout(timeout).ok(),
+            Source::Slot(slot) => slot.next(Some(timeout)),
+        }
+    }
+
+    /// Next change if there is one that was not received yet
+    pub fn try_recv(&self) -> Option<RowChange> {
+        match &self.source {
+            Source::Channel(receiver) => receiver.try_recv().ok(),
+            Source::Slot(slot) => slot.next(Some(Duration::from_secs(0))),
+        }
+    }
+}
+
+impl Drop for ChangeStream {
+    /// Slot keeps retaining changes for the next stream
+    fn drop(&mut self) {
+        if let Source::Slot(slot) = &self.source {
+            slot.state.lock().expect("to acquire slot lock").active = false;
+        }
+    }
+}
+
+impl Iterator for ChangeStream {
+    type Item = RowChange;
+
+    fn next(&mut self) -> Option<RowChange> {
+        self.recv()
+    }
+}
+
+/// Published tables, `None` stands for all tables including the ones that
+/// will be created
+struct Publication {
+    tables: Option<HashSet<(String, String)>>,
+    subscribers: Vec<mpsc::Sender<RowChange>>,
+}
+
+impl Publication {
+    fn includes(&self, schema_name: &str, table_name: &str) -> bool {
+        match &self.tables {
+            None => true,
+            Some(tables) => tables.contains(&(schema_name.to_owned(), table_name.to_owned())),
+        }
+    }
+}
+
+/// Replication slot of a publication retains changes of its tables until
+/// they are received, so subscribers can disconnect and resume without
+/// losing changes
+struct ReplicationSlot {
+    name: String,
+    publication: String,
+    state: Mutex<SlotState>,
+    retained_changed: Condvar,
+    store: Arc<SlotStore>,
+}
+
+#[derive(Default)]
+struct SlotState {
+    /// changes that were not received yet with their positions
+    retained: VecDeque<(u64, RowChange)>,
+    /// position of the last received change
+    confirmed: u64,
+    /// whether a stream consumes the slot
+    active: bool,
+    dropped: bool,
+}
+
+impl ReplicationSlot {
+    /// Waits at most `timeout` for the next retained change, `None` waits
+    /// until the slot is dropped
+    fn next(&self, timeout: Option<Duration>) -> Option<RowChange> {
+        let mut state = self.state.lock().expect("to acquire slot lock");
+        while state.retained.is_empty() && !state.dropped {
+            state = match timeout {
+                None => self.retained_changed.wait(state).expect("to acquire slot lock"),
+                Some(timeout) => {
+                    let (state, result) = self
+                        .retained_changed
+                        .wait_timeout(state, timeout)
+                        .expect("to acquire slot lock");
+                    if result.timed_out() && state.retained.is_empty() {
+                        return None;
+                    }
+                    state
+                }
+            };
+        }
+        let (position, change) = state.retained.pop_front()?;
+        state.confirmed = position;
+        // a stream can't report errors, the change is received again after
+        // restart if the confirmed position is not saved
+        if let Err(error) = self.store.confirm(&self.name, &self.publication, position) {
+            log::error!(
+                "position {} of slot {:?} is not saved: {:?}",
+                position,
+                self.name,
+                error
+            );
+        }
+        Some(change)
+    }
+
+    fn retain(&self, changes: &[(u64, RowChange)]) -> SystemResult<()> {
+        self.store.retain(&self.name, changes)?;
+        self.state
+            .lock()
+            .expect("to acquire slot lock")
+            .retained
+            .extend(changes.iter().cloned());
+        self.retained_changed.notify_all();
+        Ok(())
+    }
+
+    fn drop_slot(&self) -> SystemResult<()> {
+        let mut state = self.state.lock().expect("to acquire slot lock");
+        state.dropped = true;
+        let positions = state.retained.drain(..).map(|(position, _change)| position).collect();
+        self.retained_changed.notify_all();
+        self.store.forget(&self.name, positions)
+    }
+}
+
+/// Tables of the system catalog with replication slots and changes that
+/// they retain. A slot record keeps the publication and the confirmed
+/// position, every retained change has a record of its own that is deleted
+/// once the change is received.
+#[derive(Default)]
+struct SlotStore {
+    slots: SystemTable,
+    change_log: SystemTable,
+}
+
+impl SlotStore {
+    fn save(&self, name: &str, publication: &str, confirmed: u64) -> SystemResult<()> {
+        self.slots.write(vec![(
+            vec![Datum::from_string(name.to_owned())],
+            vec![Datum::from_string(publication.to_owned()), Datum::from_u64(confirmed)],
+        )])
+    }
+
+    fn retain(&self, name: &str, changes: &[(u64, RowChange)]) -> SystemResult<()> {
+        self.change_log.write(
+            changes
+                .iter()
+                .map(|(position, change)| (change_key(name, *position), change_values(change)))
+                .collect(),
+        )
+    }
+
+    fn confirm(&self, name: &str, publication: &str, position: u64) -> SystemResult<()> {
+        self.save(name, publication, position)?;
+        self.change_log.delete(vec![change_key(name, position)])
+    }
+
+    fn forget(&self, name: &str, positions: Vec<u64>) -> SystemResult<()> {
+        self.change_log.delete(
+            positions
+                .into_iter()
+                .map(|position| change_key(name, position))
+                .collect(),
+        )?;
+        self.slots.delete(vec![vec![Datum::from_string(name.to_owned())]])
+    }
+}
+
+fn change_key(name: &str, position: u64) -> Vec<Datum<'static>> {
+    vec![Datum::from_string(name.to_owned()), Datum::from_u64(position)]
+}
+
+/// Values of a retained change, rows are stored as the number of their
+/// values followed by them or as `NULL` if there is no row
+fn change_values(change: &RowChange) -> Vec<Datum<'static>> {
+    let kind = match change.kind {
+        ChangeKind::Insert => INSERT,
+        ChangeKind::Update => UPDATE,
+        ChangeKind::Delete => DELETE,
+    };
+    let mut values = vec![
+        Datum::from_string(change.schema_name.clone()),
+        Datum::from_string(change.table_name.clone()),
+        Datum::from_str(kind),
+    ];
+    for row in &[&change.old, &change.new] {
+        match row {
+            None => values.push(Datum::from_null()),
+            Some(row) => {
+                values.push(Datum::from_u64(row.len() as u64));
+                values.extend(row.iter().map(|value| match value {
+                    None => Datum::from_null(),
+                    Some(value) => Datum::from_string(value.clone()),
+                }));
+            }
+        }
+    }
+    values
+}
+
+fn retained_change(values: &[Datum]) -> Option<RowChange> {
+    let kind = match values.get(2)?.as_str() {
+        INSERT => ChangeKind::Insert,
+        UPDATE => ChangeKind::Update,
+        DELETE => ChangeKind::Delete,
+        _ => return None,
+    };
+    let mut rest = values.get(3..)?;
+    let mut rows = vec![];
+    for _ in 0..2 {
+        let (row, tail) = rest.split_first()?;
+        rest = tail;
+        rows.push(match row {
+            Datum::Null => None,
+            len => {
+                let (row, tail) = rest.split_at((len.as_u64() as usize).min(rest.len()));
+                rest = tail;
+                Some(
+                    row.iter()
+                        .map(|value| match value {
+                            Datum::Null => None,
+                            value => Some(value.as_str().to_owned()),
+                        })
+                        .collect(),
+                )
+            }
+        });
+    }
+    let new = rows.pop()?;
+    let old = rows.pop()?;
+    Some(RowChange {
+        schema_name: values[0].as_str().to_owned(),
+        table_name: values[1].as_str().to_owned(),
+        kind,
+        old,
+        new,
+    })
+}
+
+const INSERT: &str = "insert";
+const UPDATE: &str = "update";
+const DELETE: &str = "delete";
+
+/// State of a replication slot
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplicationSlotInfo {
+    pub name: String,
+    pub publication: String,
+    /// whether a subscriber consumes the slot
+    pub active: bool,
+    /// number of changes that were not received yet
+    pub retained_changes: usize,
+    /// position of the last received change, positions increase with every
+    /// published change
+    pub confirmed_position: u64,
+}
+
+/// Reason why a replication slot can't be created
+#[derive(Debug, PartialEq)]
+pub(crate) enum SlotCreationError {
+    SlotAlreadyExists,
+    PublicationDoesNotExist,
+}
+
+/// Table of the system catalog with publications and their tables
+pub(crate) const PUBLICATIONS_TABLE: &str = "PUBLICATIONS";
+/// Table of the system catalog with replication slots
+pub(crate) const REPLICATION_SLOTS_TABLE: &str = "REPLICATION_SLOTS";
+/// Table of the system catalog with changes retained by replication slots
+pub(crate) const CHANGE_LOG_TABLE: &str = "CHANGE_LOG";
+
+/// Keeps publications and streams changes of their tables to subscribers,
+/// replication slots and change hooks.
+///
+/// Publications and their tables are saved in the `PUBLICATIONS` table of
+/// the system catalog, replication slots keep their state and retained
+/// changes there too, so subscribers resume after restart where they
+/// stopped. Subscribers without slots and hooks are not saved.
+#[derive(Default)]
+pub(crate) struct Publications {
+    publications: RwLock<HashMap<String, Publication>>,
+    slots: RwLock<HashMap<String, Arc<ReplicationSlot>>>,
+    position: AtomicU64,
+    hooks: RwLock<Vec<Arc<dyn ChangeHook>>>,
+    store: SystemTable,
+    slot_store: Arc<SlotStore>,
+}
+
+impl Publications {
+    /// Publications saved in `store`, replication slots saved in `slots`
+    /// with their changes retained in `change_log`
+    pub(crate) fn load(store: SystemTable, slots: SystemTable, change_log: SystemTable) -> SystemResult<Publications> {
+        let mut publications = HashMap::<String, Publication>::new();
+        let mut members = vec![];
+        store.load(|key, values| match key {
+            [name] => {
+                publications
+                    .entry(name.as_str().to_owned())
+                    .or_insert_with(|| Publication {
+                        tables: if values[0].as_bool() {
+                            None
+                        } else {
+                            Some(HashSet::new())
+                        },
+                        subscribers: vec![],
+                    });
+            }
+            [name, schema_name, table_name] => members.push((
+                name.as_str().to_owned(),
+                schema_name.as_str().to_owned(),
+                table_name.as_str().to_owned(),
+            )),
+            _ => log::warn!("unknown record {:?} of publications is skipped", key),
+        })?;
+        for (name, schema_name, table_name) in members {
+            if let Some(tables) = publications
+                .get_mut(&name)
+                .and_then(|publication| publication.tables.as_mut())
+            {
+                tables.insert((schema_name, table_name));
+            }
+        }
+        let mut states = HashMap::<String, (String, SlotState)>::new();
+        slots.read(|key, values| {
+            states.insert(
+                key[0].as_str().to_owned(),
+                (
+                    values[0].as_str().to_owned(),
+                    SlotState {
+                        confirmed: values[1].as_u64(),
+                        ..SlotState::default()
+                    },
+                ),
+            );
+        })?;
+        change_log.read(
+            |key, values| match (states.get_mut(key[0].as_str()), retained_change(values)) {
+                (Some((_publication, state)), Some(change)) => state.retained.push_back((key[1].as_u64(), change)),
+                _ => log::warn!("retained change {:?} of slot {:?} is skipped", key[1], key[0]),
+            },
+        )?;
+        let slot_store = Arc::new(SlotStore { slots, change_log });
+        let mut position = 0;
+        let slots = states
+            .into_iter()
+            .map(|(name, (publication, mut state))| {
+                state
+                    .retained
+                    .make_contiguous()
+                    .sort_by_key(|(position, _change)| *position);
+                position = state
+                    .retained
+                    .back()
+                    .map(|(position, _change)| *position)
+                    .unwrap_or(0)
+                    .max(state.confirmed)
+                    .max(position);
+                let slot = ReplicationSlot {
+                    name: name.clone(),
+                    publication,
+                    state: Mutex::new(state),
+                    retained_changed: Condvar::new(),
+                    store: slot_store.clone(),
+                };
+                (name, Arc::new(slot))
+            })
+            .collect();
+        Ok(Publications {
+            publications: RwLock::new(publications),
+            slots: RwLock::new(slots),
+            position: AtomicU64::new(position),
+            hooks: RwLock::default(),
+            store,
+            slot_store,
+        })
+    }
+
+    fn save(&self) -> SystemResult<()> {
+        self.store.save(|| {
+            let mut records: Vec<SystemRecord> = vec![];
+            for (name, publication) in self.publications.read().expect("to acquire read lock").iter() {
+                records.push((
+                    vec![Datum::from_string(name.clone())],
+                    vec![Datum::from_bool(publication.tables.is_none())],
+                ));
+                for (schema_name, table_name) in publication.tables.iter().flatten() {
+                    records.push((
+                        vec![
+                            Datum::from_string(name.clone()),
+                            Datum::from_string(schema_name.clone()),
+                            Datum::from_string(table_name.clone()),
+                        ],
+                        vec![],
+                    ));
+                }
+            }
+            records
+        })
+    }
+
+    /// Returns `false` if the publication already exists
+    pub(crate) fn create(&self, name: &str, tables: Option<Vec<(String, String)>>) -> SystemResult<bool> {
+        {
+            let mut publications = self.publications.write().expect("to acquire write lock");
+            if publications.contains_key(name) {
+                return Ok(false);
+            }
+            publications.insert(
+                name.to_owned(),
+                Publication {
+                    tables: tables.map(|tables| tables.into_iter().collect()),
+                    subscribers: vec![],
+                },
+            );
+        }
+        self.save()?;
+        Ok(true)
+    }
+
+    /// Returns `false` if the publication does not exist, streams of its
+    /// subscribers end and its replication slots are dropped
+    pub(crate) fn drop(&self, name: &str) -> SystemResult<bool> {
+        let dropped = self
+            .publications
+            .write()
+            .expect("to acquire write lock")
+            .remove(name)
+            .is_some();
+        if dropped {
+            let mut slots = self.slots.write().expect("to acquire write lock");
+            let names = slots
+                .iter()
+                .filter(|(_name, slot)| slot.publication == name)
+                .map(|(name, _slot)| name.clone())
+                .collect::<Vec<_>>();
+            for name in names {
+                if let Some(slot) = slots.remove(&name) {
+                    slot.drop_slot()?;
+                }
+            }
+            drop(slots);
+            self.save()?;
+        }
+        Ok(dropped)
+    }
+
+    pub(crate) fn subscribe(&self, name: &str) -> Option<ChangeStream> {
+        let mut publications = self.publications.write().expect("to acquire write lock");
+        publications.get_mut(name).map(|publication| {
+            let (sender, receiver) = mpsc::channel();
+            publication.subscribers.push(sender);
+            ChangeStream {
+                source: Source::Channel(receiver),
+            }
+        })
+    }
+
+    pub(crate) fn create_slot(&self, name: &str, publication: &str) -> SystemResult<Result<(), SlotCreationError>> {
+        let publications = self.publications.read().expect("to acquire read lock");
+        if !publications.contains_key(publication) {
+            return Ok(Err(SlotCreationError::PublicationDoesNotExist));
+        }
+        let mut slots = self.slots.write().expect("to acquire write lock");
+        if slots.contains_key(name) {
+            return Ok(Err(SlotCreationError::SlotAlreadyExists));
+        }
+        let confirmed = self.position.load(Ordering::SeqCst);
+        self.slot_store.save(name, publication, confirmed)?;
+        slots.insert(
+            name.to_owned(),
+            Arc::new(ReplicationSlot {
+                name: name.to_owned(),
+                publication: publication.to_owned(),
+                state: Mutex::new(SlotState {
+                    confirmed,
+                    ..SlotState::default()
+                }),
+                retained_changed: Condvar::new(),
+                store: self.slot_store.clone(),
+            }),
+        );
+        Ok(Ok(()))
+    }
+
+    /// Returns `false` if the slot does not exist, its stream ends
+    pub(crate) fn drop_slot(&self, name: &str) -> SystemResult<bool> {
+        match self.slots.write().expect("to acquire write lock").remove(name) {
+            Some(slot) => {
+                slot.drop_slot()?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Stream of changes retained by the slot starting with the first one
+    /// that was not received, `None` if the slot does not exist or another
+    /// stream consumes it
+    pub(crate) fn consume_slot(&self, name: &str) -> Option<ChangeStream> {
+        let slot = self.slots.read().expect("to acquire read lock").get(name).cloned()?;
+        {
+            let mut state = slot.state.lock().expect("to acquire slot lock");
+            if state.active {
+                return None;
+            }
+            state.active = true;
+        }
+        Some(ChangeStream {
+            source: Source::Slot(slot),
+        })
+    }
+
+    pub(crate) fn slots(&self) -> Vec<ReplicationSlotInfo> {
+        let mut slots = self
+            .slots
+            .read()
+            .expect("to acquire read lock")
+            .iter()
+            .map(|(name, slot)| {
+                let state = slot.state.lock().expect("to acquire slot lock");
+                ReplicationSlotInfo {
+                    name: name.clone(),
+                    publication: slot.publication.clone(),
+                    active: state.active,
+                    retained_changes: state.retained.len(),
+                    confirmed_position: state.confirmed,
+                }
+            })
+            .collect::<Vec<ReplicationSlotInfo>>();
+        slots.sort_by(|left, right| left.name.cmp(&right.name));
+        slots
+    }
+
+    pub(crate) fn register_hook(&self, hook: Arc<dyn ChangeHook>) {
+        self.hooks.write().expect("to acquire write lock").push(hook);
+    }
+
+    /// Whether anyone receives changes of the table, rows are not decoded
+    /// for publishing otherwise
+    pub(crate) fn is_published(&self, schema_name: &str, table_name: &str) -> bool {
+        if !self.hooks.read().expect("to acquire read lock").is_empty() {
+            return true;
+        }
+        let slots = self.slots.read().expect("to acquire read lock");
+        self.publications
+            .read()
+            .expect("to acquire read lock")
+            .iter()
+            .any(|(name, publication)| {
+                (!publication.subscribers.is_empty() || slots.values().any(|slot| &slot.publication == name))
+                    && publication.includes(schema_name, table_name)
+            })
+    }
+
+    /// Sends `changes` of a table to subscribers of publications that
+    /// include it, subscribers that dropped their streams are forgotten
+    pub(crate) fn publish(&self, schema_name: &str, table_name: &str, changes: Vec<RowChange>) -> SystemResult<()> {
+        if changes.is_empty() {
+            return Ok(());
+        }
+        for hook in self.hooks.read().expect("to acquire read lock").iter() {
+            hook.on_changes(&changes);
+        }
+        let first = self.position.fetch_add(changes.len() as u64, Ordering::SeqCst) + 1;
+        let positioned = (first..)
+            .zip(changes.iter().cloned())
+            .collect::<Vec<(u64, RowChange)>>();
+        let slots = self.slots.read().expect("to acquire read lock");
+        let mut publications = self.publications.write().expect("to acquire write lock");
+        for (name, publication) in publications.iter_mut() {
+            if publication.includes(schema_name, table_name) {
+                publication
+                    .subscribers
+                    .retain(|subscriber| changes.iter().all(|change| subscriber.send(change.clone()).is_ok()));
+                for slot in slots.values().filter(|slot| &slot.publication == name) {
+                    slot.retain(&positioned)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Tables that are explicitly listed in publications as
+    /// `(publication, schema_name, table_name)`
+    pub(crate) fn members(&self) -> Vec<(String, String, String)> {
+        let mut members = vec![];
+        for (name, publication) in self.publications.read().expect("to acquire read lock").iter() {
+            for (schema_name, table_name) in publication.tables.iter().flatten() {
+                members.push((name.clone(), schema_name.clone(), table_name.clone()));
+            }
+        }
+        members
+    }
+
+    pub(crate) fn remove_table(&self, name: &str, schema_name: &str, table_name: &str) -> SystemResult<()> {
+        if let Some(tables) = self
+            .publications
+            .write()
+            .expect("to acquire write lock")
+            .get_mut(name)
+            .and_then(|publication| publication.tables.as_mut())
+        {
+            tables.remove(&(schema_name.to_owned(), table_name.to_owned()));
+        }
+        self.save()
+    }
+
+    pub(crate) fn forget_table(&self, schema_name: &str, table_name: &str) -> SystemResult<()> {
+        let table = (schema_name.to_owned(), table_name.to_owned());
+        for publication in self.publications.write().expect("to acquire write lock").values_mut() {
+            if let Some(tables) = publication.tables.as_mut() {
+                tables.remove(&table);
+            }
+        }
+        self.save()
+    }
+
+    pub(crate) fn forget_schema(&self, schema_name: &str) -> SystemResult<()> {
+        for publication in self.publications.write().expect("to acquire write lock").values_mut() {
+            if let Some(tables) = publication.tables.as_mut() {
+                tables.retain(|(schema, _table)| schema != schema_name);
+            }
+        }
+        self.save()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(table_name: &str) -> RowChange {
+        RowChange::insert("schema_name", table_name, &[Datum::from_i16(1), Datum::from_null()])
+    }
+
+    #[test]
+    fn changes_of_published_tables_are_streamed() {
+        let publications = Publications::default();
+        publications
+            .create("changes", Some(vec![("schema_name".to_owned(), "table_1".to_owned())]))
+            .expect("no system errors");
+        let stream = publications.subscribe("changes").expect("publication exists");
+
+        assert!(publications.is_published("schema_name", "table_1"));
+        assert!(!publications.is_published("schema_name", "table_2"));
+
+        publications
+            .publish("schema_name", "table_1", vec![change("table_1")])
+            .expect("no system errors");
+        publications
+            .publish("schema_name", "table_2", vec![change("table_2")])
+            .expect("no system errors");
+
+        assert_eq!(
+            stream.try_recv(),
+            Some(RowChange {
+                schema_name: "schema_name".to_owned(),
+                table_name: "table_1".to_owned(),
+                kind: ChangeKind::Insert,
+                old: None,
+                new: Some(vec![Some("1".to_owned()), None]),
+            })
+        );
+        assert_eq!(stream.try_recv(), None);
+    }
+
+    #[test]
+    fn stream_ends_when_publication_is_dropped() {
+        let publications = Publications::default();
+        publications.create("changes", None).expect("no system errors");
+        let stream = publications.subscribe("changes").expect("publication exists");
+
+        assert!(publications.drop("changes").expect("no system errors"));
+        assert_eq!(stream.recv(), None);
+    }
+
+    #[test]
+    fn hooks_receive_changes_of_all_tables() {
+        let publications = Publications::default();
+        let received = Arc::new(std::sync::Mutex::new(vec![]));
+        let sink = received.clone();
+        publications.register_hook(Arc::new(move |changes: &[RowChange]| {
+            sink.lock().expect("locked").extend_from_slice(changes)
+        }));
+
+        assert!(publications.is_published("schema_name", "table_1"));
+
+        publications
+            .publish("schema_name", "table_1", vec![change("table_1")])
+            .expect("no system errors");
+        publications
+            .publish("schema_name", "table_2", vec![change("table_2")])
+            .expect("no system errors");
+
+        assert_eq!(
+            received.lock().expect("locked").as_slice(),
+            &[change("table_1"), change("table_2")]
+        );
+    }
+
+    #[test]
+    fn dropped_streams_are_forgotten() {
+        let publications = Publications::default();
+        publications.create("changes", None).expect("no system errors");
+        drop(publications.subscribe("changes"));
+
+        publications
+            .publish("schema_name", "table_1", vec![change("table_1")])
+            .expect("no system errors");
+
+        assert!(!publications.is_published("schema_name", "table_1"));
+    }
+
+    #[test]
+    fn slot_retains_changes_until_they_are_received() {
+        let publications = Publications::default();
+        publications.create("changes", None).expect("no system errors");
+        assert_eq!(
+            publications.create_slot("slot", "changes").expect("no system errors"),
+            Ok(())
+        );
+        assert!(publications.is_published("schema_name", "table_1"));
+
+        publications
+            .publish("schema_name", "table_1", vec![change("table_1"), change("table_2")])
+            .expect("no system errors");
+        let stream = publications.consume_slot("slot").expect("slot exists");
+        assert!(publications.consume_slot("slot").is_none());
+        assert_eq!(stream.try_recv(), Some(change("table_1")));
+        drop(stream);
+
+        publications
+            .publish("schema_name", "table_3", vec![change("table_3")])
+            .expect("no system errors");
+        assert_eq!(
+            publications.slots(),
+            vec![ReplicationSlotInfo {
+                name: "slot".to_owned(),
+                publication: "changes".to_owned(),
+                active: false,
+                retained_changes: 2,
+                confirmed_position: 1,
+            }]
+        );
+
+        let stream = publications.consume_slot("slot").expect("slot exists");
+        assert_eq!(stream.try_recv(), Some(change("table_2")));
+        assert_eq!(stream.try_recv(), Some(change("table_3")));
+        assert_eq!(stream.try_recv(), None);
+    }
+
+    #[test]
+    fn slots_of_dropped_publication_are_dropped() {
+        let publications = Publications::default();
+        publications.create("changes", None).expect("no system errors");
+        assert_eq!(
+            publications.create_slot("slot", "other").expect("no system errors"),
+            Err(SlotCreationError::PublicationDoesNotExist)
+        );
+        assert_eq!(
+            publications.create_slot("slot", "changes").expect("no system errors"),
+            Ok(())
+        );
+        assert_eq!(
+            publications.create_slot("slot", "changes").expect("no system errors"),
+            Err(SlotCreationError::SlotAlreadyExists)
+        );
+        let stream = publications.consume_slot("slot").expect("slot exists");
+
+        assert!(publications.drop("changes").expect("no system errors"));
+        assert_eq!(stream.recv(), None);
+        assert!(publications.slots().is_empty());
+        assert!(!publications.drop_slot("slot").expect("no system errors"));
+    }
+}
@@ -0,0 +1,232 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::catalog_manager::{system_tables::SystemTable, SUPERUSER};
+use kernel::SystemResult;
+use protocol::scram::ScramVerifier;
+use representation::Datum;
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    sync::RwLock,
+};
+
+/// Table of the system catalog with credentials and attributes of roles
+pub(crate) const ROLES_TABLE: &str = "ROLES";
+
+/// Keeps credentials and attributes of roles. Only salted verifiers are
+/// stored, plain text passwords never reach the catalog.
+///
+/// Every change is saved in the `ROLES` table of the system catalog, a
+/// record per role with its verifier, read-only flag and connection limit.
+#[derive(Default)]
+pub(crate) struct Roles {
+    passwords: RwLock<HashMap<String, ScramVerifier>>,
+    read_only: RwLock<HashSet<String>>,
+    connection_limits: RwLock<HashMap<String, u32>>,
+    store: SystemTable,
+}
+
+impl Roles {
+    /// Roles saved in `store`
+    pub(crate) fn load(store: SystemTable) -> SystemResult<Roles> {
+        let mut passwords = HashMap::new();
+        let mut read_only = HashSet::new();
+        let mut connection_limits = HashMap::new();
+        store.load(|key, values| {
+            let role = key[0].as_str().to_owned();
+            match &values[0] {
+                Datum::Null => {}
+                verifier => match ScramVerifier::parse(verifier.as_str()) {
+                    Some(verifier) => {
+                        passwords.insert(role.clone(), verifier);
+                    }
+                    None => log::warn!("password verifier of role {:?} can't be read and is skipped", role),
+                },
+            }
+            if values[1].as_bool() {
+                read_only.insert(role.clone());
+            }
+            if values[2] != Datum::Null {
+                connection_limits.insert(role, values[2].as_u64() as u32);
+            }
+        })?;
+        Ok(Roles {
+            passwords: RwLock::new(passwords),
+            read_only: RwLock::new(read_only),
+            connection_limits: RwLock::new(connection_limits),
+            store,
+        })
+    }
+
+    fn save(&self) -> SystemResult<()> {
+        self.store.save(|| {
+            let passwords = self.passwords.read().expect("to acquire read lock");
+            let read_only = self.read_only.read().expect("to acquire read lock");
+            let connection_limits = self.connection_limits.read().expect("to acquire read lock");
+            passwords
+                .keys()
+                .chain(read_only.iter())
+                .chain(connection_limits.keys())
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .map(|role| {
+                    (
+                        vec![Datum::from_string(role.clone())],
+                        vec![
+                            passwords
+                                .get(role)
+                                .map(|verifier| Datum::from_string(verifier.to_string()))
+                                .unwrap_or_else(Datum::from_null),
+                            Datum::from_bool(read_only.contains(role)),
+                            connection_limits
+                                .get(role)
+                                .map(|limit| Datum::from_u64(*limit as u64))
+                                .unwrap_or_else(Datum::from_null),
+                        ],
+                    )
+                })
+                .collect()
+        })
+    }
+
+    pub(crate) fn set_password(&self, role: &str, verifier: Option<ScramVerifier>) -> SystemResult<()> {
+        {
+            let mut passwords = self.passwords.write().expect("to acquire write lock");
+            match verifier {
+                Some(verifier) => {
+                    passwords.insert(role.to_owned(), verifier);
+                }
+                None => {
+                    passwords.remove(role);
+                }
+            }
+        }
+        self.save()
+    }
+
+    pub(crate) fn password_verifier(&self, role: &str) -> Option<ScramVerifier> {
+        self.passwords.read().expect("to acquire read lock").get(role).cloned()
+    }
+
+    pub(crate) fn set_read_only(&self, role: &str, read_only: bool) -> SystemResult<()> {
+        {
+            let mut roles = self.read_only.write().expect("to acquire write lock");
+            if read_only {
+                roles.insert(role.to_owned());
+            } else {
+                roles.remove(role);
+            }
+        }
+        self.save()
+    }
+
+    /// `None` removes the limit
+    pub(crate) fn set_connection_limit(&self, role: &str, limit: Option<u32>) -> SystemResult<()> {
+        {
+            let mut limits = self.connection_limits.write().expect("to acquire write lock");
+            match limit {
+                Some(limit) => {
+                    limits.insert(role.to_owned(), limit);
+                }
+                None => {
+                    limits.remove(role);
+                }
+            }
+        }
+        self.save()
+    }
+
+    /// `SUPERUSER` is not subject to connection limit
+    pub(crate) fn connection_limit(&self, role: &str) -> Option<u32> {
+        if role == SUPERUSER {
+            None
+        } else {
+            self.connection_limits
+                .read()
+                .expect("to acquire read lock")
+                .get(role)
+                .copied()
+        }
+    }
+
+    /// `SUPERUSER` is never read-only, otherwise nobody could lift the
+    /// restriction
+    pub(crate) fn is_read_only(&self, role: &str) -> bool {
+        role != SUPERUSER && self.read_only.read().expect("to acquire read lock").contains(role)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn password_is_replaced() {
+        let roles = Roles::default();
+        roles
+            .set_password("role_1", Some(ScramVerifier::new("old")))
+            .expect("no system errors");
+        roles
+            .set_password("role_1", Some(ScramVerifier::new("new")))
+            .expect("no system errors");
+
+        let verifier = roles.password_verifier("role_1").expect("password is set");
+        assert!(verifier.matches("new"));
+        assert!(!verifier.matches("old"));
+    }
+
+    #[test]
+    fn password_is_removed() {
+        let roles = Roles::default();
+        roles
+            .set_password("role_1", Some(ScramVerifier::new("secret")))
+            .expect("no system errors");
+        roles.set_password("role_1", None).expect("no system errors");
+
+        assert_eq!(roles.password_verifier("role_1"), None);
+    }
+
+    #[test]
+    fn read_only_flag_is_lifted() {
+        let roles = Roles::default();
+        roles.set_read_only("role_1", true).expect("no system errors");
+
+        assert!(roles.is_read_only("role_1"));
+        assert!(!roles.is_read_only("role_2"));
+
+        roles.set_read_only("role_1", false).expect("no system errors");
+
+        assert!(!roles.is_read_only("role_1"));
+    }
+
+    #[test]
+    fn connection_limit_is_removed() {
+        let roles = Roles::default();
+        roles.set_connection_limit("role_1", Some(2)).expect("no system errors");
+
+        assert_eq!(roles.connection_limit("role_1"), Some(2));
+
+        roles.set_connection_limit("role_1", None).expect("no system errors");
+
+        assert_eq!(roles.connection_limit("role_1"), None);
+    }
+
+    #[test]
+    fn superuser_is_never_read_only() {
+        let roles = Roles::default();
+        roles.set_read_only(SUPERUSER, true).expect("no system errors");
+
+        assert!(!roles.is_read_only(SUPERUSER));
+    }
+}
@@ -0,0 +1,188 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::HashMap, sync::RwLock};
+
+/// Autovacuum parameters of a table given as storage parameters of
+/// `CREATE TABLE ... WITH (autovacuum_enabled = false)`. Thresholds that are
+/// not set are taken from settings of the server.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AutovacuumOptions {
+    pub enabled: bool,
+    pub vacuum_threshold: Option<u64>,
+    pub vacuum_scale_factor: Option<f64>,
+    pub analyze_threshold: Option<u64>,
+    pub analyze_scale_factor: Option<f64>,
+}
+
+impl Default for AutovacuumOptions {
+    fn default() -> AutovacuumOptions {
+        AutovacuumOptions {
+            enabled: true,
+            vacuum_threshold: None,
+            vacuum_scale_factor: None,
+            analyze_threshold: None,
+            analyze_scale_factor: None,
+        }
+    }
+}
+
+impl AutovacuumOptions {
+    /// Options from `name = value` storage parameters, an error describes an
+    /// unknown parameter or an invalid value
+    pub(crate) fn parse(parameters: &[(String, String)]) -> Result<AutovacuumOptions, String> {
+        let mut options = AutovacuumOptions::default();
+        for (name, value) in parameters {
+            match name.to_lowercase().as_str() {
+                "autovacuum_enabled" => {
+                    options.enabled = match value.to_lowercase().as_str() {
+                        "true" | "on" | "1" => true,
+                        "false" | "off" | "0" => false,
+                        _ => return Err(invalid_value(name, value)),
+                    }
+                }
+                "autovacuum_vacuum_threshold" => options.vacuum_threshold = Some(threshold(name, value)?),
+                "autovacuum_vacuum_scale_factor" => options.vacuum_scale_factor = Some(scale_factor(name, value)?),
+                "autovacuum_analyze_threshold" => options.analyze_threshold = Some(threshold(name, value)?),
+                "autovacuum_analyze_scale_factor" => options.analyze_scale_factor = Some(scale_factor(name, value)?),
+                _ => return Err(format!("unrecognized parameter \"{}\"", name)),
+            }
+        }
+        Ok(options)
+    }
+}
+
+fn threshold(name: &str, value: &str) -> Result<u64, String> {
+    value.parse().map_err(|_| invalid_value(name, value))
+}
+
+fn scale_factor(name: &str, value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(factor) if (0.0..=100.0).contains(&factor) => Ok(factor),
+        _ => Err(invalid_value(name, value)),
+    }
+}
+
+fn invalid_value(name: &str, value: &str) -> String {
+    format!("invalid value for option \"{}\": {}", name, value)
+}
+
+/// Storage parameters of tables, they are kept in memory only like access
+/// statistics they tune
+#[derive(Default)]
+pub(crate) struct StorageOptions {
+    tables: RwLock<HashMap<(String, String), AutovacuumOptions>>,
+}
+
+impl StorageOptions {
+    pub(crate) fn set(&self, schema_name: &str, table_name: &str, options: AutovacuumOptions) {
+        self.tables
+            .write()
+            .expect("to acquire write lock")
+            .insert((schema_name.to_owned(), table_name.to_owned()), options);
+    }
+
+    /// Options of the table, defaults if it was created without any
+    pub(crate) fn autovacuum(&self, schema_name: &str, table_name: &str) -> AutovacuumOptions {
+        self.tables
+            .read()
+            .expect("to acquire read lock")
+            .get(&(schema_name.to_owned(), table_name.to_owned()))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn forget_table(&self, schema_name: &str, table_name: &str) {
+        self.tables
+            .write()
+            .expect("to acquire write lock")
+            .remove(&(schema_name.to_owned(), table_name.to_owned()));
+    }
+
+    pub(crate) fn forget_schema(&self, schema_name: &str) {
+        self.tables
+            .write()
+            .expect("to acquire write lock")
+            .retain(|(schema, _table), _| schema != schema_name);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parameters(parameters: &[(&str, &str)]) -> Vec<(String, String)> {
+        parameters
+            .iter()
+            .map(|(name, value)| ((*name).to_owned(), (*value).to_owned()))
+            .collect()
+    }
+
+    #[test]
+    fn parse_autovacuum_options() {
+        assert_eq!(
+            AutovacuumOptions::parse(&parameters(&[
+                ("autovacuum_enabled", "off"),
+                ("autovacuum_vacuum_threshold", "10"),
+                ("autovacuum_analyze_scale_factor", "0.5"),
+            ])),
+            Ok(AutovacuumOptions {
+                enabled: false,
+                vacuum_threshold: Some(10),
+                vacuum_scale_factor: None,
+                analyze_threshold: None,
+                analyze_scale_factor: Some(0.5),
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_autovacuum_options() {
+        assert_eq!(
+            AutovacuumOptions::parse(&parameters(&[("fillfactor", "70")])),
+            Err("unrecognized parameter \"fillfactor\"".to_owned())
+        );
+        assert_eq!(
+            AutovacuumOptions::parse(&parameters(&[("autovacuum_vacuum_threshold", "-1")])),
+            Err("invalid value for option \"autovacuum_vacuum_threshold\": -1".to_owned())
+        );
+        assert_eq!(
+            AutovacuumOptions::parse(&parameters(&[("autovacuum_vacuum_scale_factor", "101")])),
+            Err("invalid value for option \"autovacuum_vacuum_scale_factor\": 101".to_owned())
+        );
+    }
+
+    #[test]
+    fn options_are_forgotten_with_their_table() {
+        let storage_options = StorageOptions::default();
+        let disabled = AutovacuumOptions {
+            enabled: false,
+            ..AutovacuumOptions::default()
+        };
+        storage_options.set("schema_name", "table_1", disabled);
+        storage_options.set("schema_name", "table_2", disabled);
+
+        storage_options.forget_table("schema_name", "table_1");
+        assert_eq!(
+            storage_options.autovacuum("schema_name", "table_1"),
+            AutovacuumOptions::default()
+        );
+        assert_eq!(storage_options.autovacuum("schema_name", "table_2"), disabled);
+        storage_options.forget_schema("schema_name");
+        assert_eq!(
+            storage_options.autovacuum("schema_name", "table_2"),
+            AutovacuumOptions::default()
+        );
+    }
+}
@@ -0,0 +1,160 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tables of the system catalog that keep objects which live in memory, e.g.
+//! privileges or roles. Every change of such objects saves records of all of
+//! them, only records that differ from the saved ones are written or deleted.
+use crate::catalog_manager::{format, stored};
+use kernel::{Object, Operation, SystemResult};
+use representation::{Binary, Datum};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+use storage::{Database, Key};
+
+/// Key and values of a record
+pub(crate) type SystemRecord = (Vec<Datum<'static>>, Vec<Datum<'static>>);
+
+/// Table of the system catalog, records are not kept anywhere if the catalog
+/// is in memory
+#[derive(Default)]
+pub(crate) struct SystemTable {
+    database: Option<(Arc<dyn Database + Send + Sync>, &'static str, &'static str)>,
+    /// records that are saved, the lock is held while records are saved
+    saved: Mutex<HashMap<Key, Binary>>,
+}
+
+impl SystemTable {
+    pub(crate) fn new(
+        database: Arc<dyn Database + Send + Sync>,
+        schema_name: &'static str,
+        table_name: &'static str,
+    ) -> SystemTable {
+        SystemTable {
+            database: Some((database, schema_name, table_name)),
+            saved: Mutex::default(),
+        }
+    }
+
+    /// Calls `record` with keys and values without checksums of saved
+    /// records, the records are remembered as saved. Checksums are verified
+    /// when the system catalog is opened
+    pub(crate) fn load<R: FnMut(&[Datum], &[Datum])>(&self, record: R) -> SystemResult<()> {
+        let records = self.records(record)?;
+        *self.saved.lock().expect("to acquire lock") = records.into_iter().collect();
+        Ok(())
+    }
+
+    /// Calls `record` with keys and values without checksums of records
+    /// written with `write`
+    pub(crate) fn read<R: FnMut(&[Datum], &[Datum])>(&self, record: R) -> SystemResult<()> {
+        self.records(record).map(|_records| ())
+    }
+
+    fn records<R: FnMut(&[Datum], &[Datum])>(&self, mut record: R) -> SystemResult<Vec<(Key, Binary)>> {
+        let (database, schema_name, table_name) = match self.database.as_ref() {
+            Some(database) => database,
+            None => return Ok(vec![]),
+        };
+        let records = format::records(database.as_ref(), schema_name, table_name)?;
+        for (key, values) in &records {
+            let values = values.unpack();
+            record(&key.unpack(), &values[..values.len() - 1]);
+        }
+        Ok(records)
+    }
+
+    /// Writes `records` one by one, for tables whose records are too large to
+    /// be saved all at once. They are not remembered as saved, so such
+    /// tables are never saved with `save`
+    pub(crate) fn write(&self, records: Vec<SystemRecord>) -> SystemResult<()> {
+        let (database, schema_name, table_name) = match self.database.as_ref() {
+            Some(database) => database,
+            None => return Ok(()),
+        };
+        stored(
+            database.write(
+                schema_name,
+                table_name,
+                records
+                    .into_iter()
+                    .map(|(key, values)| (Binary::pack(&key), format::seal(&values)))
+                    .collect(),
+            ),
+            Operation::Access,
+            Object::Table(schema_name, table_name),
+        )
+        .map(|_written| ())
+    }
+
+    /// Deletes records written with `write`
+    pub(crate) fn delete(&self, keys: Vec<Vec<Datum<'static>>>) -> SystemResult<()> {
+        let (database, schema_name, table_name) = match self.database.as_ref() {
+            Some(database) => database,
+            None => return Ok(()),
+        };
+        stored(
+            database.delete(
+                schema_name,
+                table_name,
+                keys.iter().map(|key| Binary::pack(key)).collect(),
+            ),
+            Operation::Access,
+            Object::Table(schema_name, table_name),
+        )
+        .map(|_deleted| ())
+    }
+
+    /// Saves `records` as the only records of the table, `records` is called
+    /// when no other records are being saved so that the last saved records
+    /// are the latest ones
+    pub(crate) fn save<R: FnOnce() -> Vec<SystemRecord>>(&self, records: R) -> SystemResult<()> {
+        let (database, schema_name, table_name) = match self.database.as_ref() {
+            Some(database) => database,
+            None => return Ok(()),
+        };
+        let mut saved = self.saved.lock().expect("to acquire lock");
+        let records = records()
+            .into_iter()
+            .map(|(key, values)| (Binary::pack(&key), format::seal(&values)))
+            .collect::<HashMap<_, _>>();
+        let changed = records
+            .iter()
+            .filter(|(key, values)| saved.get(*key) != Some(*values))
+            .map(|(key, values)| (key.clone(), values.clone()))
+            .collect::<Vec<_>>();
+        let removed = saved
+            .keys()
+            .filter(|key| !records.contains_key(*key))
+            .cloned()
+            .collect::<Vec<_>>();
+        if !changed.is_empty() {
+            stored(
+                database.write(schema_name, table_name, changed),
+                Operation::Access,
+                Object::Table(schema_name, table_name),
+            )?;
+        }
+        if !removed.is_empty() {
+            stored(
+                database.delete(schema_name, table_name, removed),
+                Operation::Access,
+                Object::Table(schema_name, table_name),
+            )?;
+        }
+        *saved = records;
+        Ok(())
+    }
+}
@@ -0,0 +1,159 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::HashMap, sync::RwLock, time::SystemTime};
+
+/// Access counters of a table since it was created
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct TableStatistics {
+    /// number of sequential scans
+    pub seq_scan: u64,
+    /// number of rows fetched by sequential scans
+    pub seq_tup_read: u64,
+    /// number of index scans
+    pub idx_scan: u64,
+    /// number of rows fetched by index scans
+    pub idx_tup_fetch: u64,
+    /// number of rows inserted
+    pub n_tup_ins: u64,
+    /// number of rows updated
+    pub n_tup_upd: u64,
+    /// number of rows deleted
+    pub n_tup_del: u64,
+    /// estimated number of rows in the table
+    pub n_live_tup: u64,
+    /// number of rows updated or deleted since the table was last vacuumed
+    pub n_dead_tup: u64,
+    /// number of rows modified since the table was last analyzed
+    pub n_mod_since_analyze: u64,
+    /// when the table was last vacuumed by autovacuum
+    pub last_autovacuum: Option<SystemTime>,
+    /// when the table was last analyzed by autovacuum
+    pub last_autoanalyze: Option<SystemTime>,
+    /// number of times the table was vacuumed by autovacuum
+    pub autovacuum_count: u64,
+    /// number of times the table was analyzed by autovacuum
+    pub autoanalyze_count: u64,
+}
+
+/// Keeps access counters of all user tables
+#[derive(Default)]
+pub(crate) struct AccessStatistics {
+    tables: RwLock<HashMap<(String, String), TableStatistics>>,
+}
+
+impl AccessStatistics {
+    pub(crate) fn register_table(&self, schema_name: &str, table_name: &str) {
+        self.tables.write().expect("to acquire write lock").insert(
+            (schema_name.to_owned(), table_name.to_owned()),
+            TableStatistics::default(),
+        );
+    }
+
+    pub(crate) fn forget_table(&self, schema_name: &str, table_name: &str) {
+        self.tables
+            .write()
+            .expect("to acquire write lock")
+            .remove(&(schema_name.to_owned(), table_name.to_owned()));
+    }
+
+    pub(crate) fn forget_schema(&self, schema_name: &str) {
+        self.tables
+            .write()
+            .expect("to acquire write lock")
+            .retain(|(schema, _table), _| schema != schema_name);
+    }
+
+    pub(crate) fn seq_scanned(&self, schema_name: &str, table_name: &str, rows: usize) {
+        self.update(schema_name, table_name, |statistics| {
+            statistics.seq_scan += 1;
+            statistics.seq_tup_read += rows as u64;
+        })
+    }
+
+    pub(crate) fn inserted(&self, schema_name: &str, table_name: &str, rows: usize) {
+        self.update(schema_name, table_name, |statistics| {
+            statistics.n_tup_ins += rows as u64;
+            statistics.n_live_tup += rows as u64;
+            statistics.n_mod_since_analyze += rows as u64;
+        })
+    }
+
+    pub(crate) fn updated(&self, schema_name: &str, table_name: &str, rows: usize) {
+        self.update(schema_name, table_name, |statistics| {
+            statistics.n_tup_upd += rows as u64;
+            statistics.n_dead_tup += rows as u64;
+            statistics.n_mod_since_analyze += rows as u64;
+        })
+    }
+
+    pub(crate) fn deleted(&self, schema_name: &str, table_name: &str, rows: usize) {
+        self.update(schema_name, table_name, |statistics| {
+            statistics.n_tup_del += rows as u64;
+            statistics.n_live_tup = statistics.n_live_tup.saturating_sub(rows as u64);
+            statistics.n_dead_tup += rows as u64;
+            statistics.n_mod_since_analyze += rows as u64;
+        })
+    }
+
+    pub(crate) fn autovacuumed(&self, schema_name: &str, table_name: &str) {
+        self.update(schema_name, table_name, |statistics| {
+            statistics.n_dead_tup = 0;
+            statistics.last_autovacuum = Some(SystemTime::now());
+            statistics.autovacuum_count += 1;
+        })
+    }
+
+    /// `live_rows` are counted while the table is analyzed
+    pub(crate) fn autoanalyzed(&self, schema_name: &str, table_name: &str, live_rows: usize) {
+        self.update(schema_name, table_name, |statistics| {
+            statistics.n_live_tup = live_rows as u64;
+            statistics.n_mod_since_analyze = 0;
+            statistics.last_autoanalyze = Some(SystemTime::now());
+            statistics.autoanalyze_count += 1;
+        })
+    }
+
+    pub(crate) fn table(&self, schema_name: &str, table_name: &str) -> Option<TableStatistics> {
+        self.tables
+            .read()
+            .expect("to acquire read lock")
+            .get(&(schema_name.to_owned(), table_name.to_owned()))
+            .copied()
+    }
+
+    /// Statistics of all tables ordered by schema and table names
+    pub(crate) fn all(&self) -> Vec<((String, String), TableStatistics)> {
+        let mut tables = self
+            .tables
+            .read()
+            .expect("to acquire read lock")
+            .iter()
+            .map(|(name, statistics)| (name.clone(), *statistics))
+            .collect::<Vec<_>>();
+        tables.sort_by(|(left, _), (right, _)| left.cmp(right));
+        tables
+    }
+
+    fn update<F: FnOnce(&mut TableStatistics)>(&self, schema_name: &str, table_name: &str, change: F) {
+        if let Some(statistics) = self
+            .tables
+            .write()
+            .expect("to acquire write lock")
+            .get_mut(&(schema_name.to_owned(), table_name.to_owned()))
+        {
+            change(statistics)
+        }
+    }
+}
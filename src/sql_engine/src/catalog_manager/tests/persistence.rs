@@ -13,10 +13,20 @@
 // limitations under the License.
 
 use super::*;
-use crate::{catalog_manager::CatalogManager, ColumnDefinition};
+use crate::{
+    cancellation::CancellationToken,
+    catalog_manager::{CatalogManager, DdlEvent, EventTrigger},
+    dml::{functions::large_objects::bytea_output, predicate::Scalar},
+    procedural::{parse_body, Aggregate, Body, Function, Returns, Variable},
+    query::extended::{SettingChange, SettingsOwner},
+    session::LargeObjectDescriptors,
+    ColumnDefinition,
+};
+use protocol::{results::QueryError, sql_types::PostgreSqlType};
 use representation::{Binary, Datum};
 use sql_types::SqlType;
-use std::path::PathBuf;
+use sqlparser::ast::{Ident, SetVariableValue, Value};
+use std::{path::PathBuf, sync::Arc};
 use storage::Row;
 use tempfile::TempDir;
 
@@ -125,3 +135,421 @@ fn stored_data_is_preserved_after_restart(persistent: (CatalogManager, TempDir))
         )],
     );
 }
+
+#[rstest::rstest]
+fn privileges_are_preserved_after_restart(persistent: (CatalogManager, TempDir)) {
+    let (catalog_manager, root_path) = persistent;
+    catalog_manager.create_schema(SCHEMA).expect("to create a schema");
+    let privileges = catalog_manager.privileges();
+    privileges.set_schema_owner(SCHEMA, "owner").expect("no system errors");
+    privileges
+        .grant_table_defaults("owner", Some(SCHEMA), "reader", &[Privilege::Select])
+        .expect("no system errors");
+    privileges
+        .set_table_owner(SCHEMA, "table_name", "owner")
+        .expect("no system errors");
+    privileges
+        .grant_on_table(SCHEMA, "table_name", "writer", &[Privilege::Insert, Privilege::Delete])
+        .expect("no system errors");
+    privileges
+        .revoke_on_table(SCHEMA, "table_name", "writer", &[Privilege::Delete])
+        .expect("no system errors");
+    privileges
+        .grant_on_column(SCHEMA, "table_name", "col_test", "auditor", Privilege::Select)
+        .expect("no system errors");
+    privileges
+        .grant_on_schema(SCHEMA, PUBLIC, &[Privilege::Usage])
+        .expect("no system errors");
+
+    drop(catalog_manager);
+
+    let catalog_manager = CatalogManager::persistent(root_path.into_path()).expect("to create catalog manager");
+    let privileges = catalog_manager.privileges();
+
+    assert!(privileges.is_schema_owner("owner", SCHEMA));
+    assert!(privileges.is_table_owner("owner", SCHEMA, "table_name"));
+    assert!(privileges.has_schema_privilege("any_role", SCHEMA, Privilege::Usage));
+    assert!(privileges.has_table_privilege("reader", SCHEMA, "table_name", Privilege::Select));
+    assert!(privileges.has_table_privilege("writer", SCHEMA, "table_name", Privilege::Insert));
+    assert!(!privileges.has_table_privilege("writer", SCHEMA, "table_name", Privilege::Delete));
+    assert!(privileges.has_column_privilege("auditor", SCHEMA, "table_name", "col_test", Privilege::Select));
+    assert!(!privileges.has_column_privilege("auditor", SCHEMA, "table_name", "other", Privilege::Select));
+
+    privileges
+        .set_table_owner(SCHEMA, "new_table", "owner")
+        .expect("no system errors");
+
+    assert!(privileges.has_table_privilege("reader", SCHEMA, "new_table", Privilege::Select));
+}
+
+#[rstest::rstest]
+fn roles_are_preserved_after_restart(persistent: (CatalogManager, TempDir)) {
+    let (catalog_manager, root_path) = persistent;
+    let roles = catalog_manager.roles();
+    roles
+        .set_password("role_1", Some(ScramVerifier::new("secret")))
+        .expect("no system errors");
+    roles.set_read_only("role_1", true).expect("no system errors");
+    roles.set_connection_limit("role_2", Some(3)).expect("no system errors");
+    roles
+        .set_password("role_3", Some(ScramVerifier::new("removed")))
+        .expect("no system errors");
+    roles.set_password("role_3", None).expect("no system errors");
+
+    drop(catalog_manager);
+
+    let catalog_manager = CatalogManager::persistent(root_path.into_path()).expect("to create catalog manager");
+    let roles = catalog_manager.roles();
+
+    assert!(roles
+        .password_verifier("role_1")
+        .expect("password is set")
+        .matches("secret"));
+    assert!(roles.is_read_only("role_1"));
+    assert_eq!(roles.connection_limit("role_1"), None);
+    assert_eq!(roles.password_verifier("role_2"), None);
+    assert!(!roles.is_read_only("role_2"));
+    assert_eq!(roles.connection_limit("role_2"), Some(3));
+    assert_eq!(roles.password_verifier("role_3"), None);
+}
+
+#[rstest::rstest]
+fn functions_are_preserved_after_restart(persistent: (CatalogManager, TempDir)) {
+    let (catalog_manager, root_path) = persistent;
+    let source = "begin return n + 1; end";
+    let increment = Function {
+        name: "increment".to_owned(),
+        params: vec![Variable {
+            name: "n".to_owned(),
+            pg_type: PostgreSqlType::Integer,
+        }],
+        returns: Returns::Type(PostgreSqlType::Integer),
+        body: Body::Block(parse_body(source).expect("parsed"), source.to_owned()),
+    };
+    let trigger_source = "begin raise notice 'fired'; end";
+    let notify = Function {
+        name: "notify".to_owned(),
+        params: vec![],
+        returns: Returns::EventTrigger,
+        body: Body::Block(parse_body(trigger_source).expect("parsed"), trigger_source.to_owned()),
+    };
+    let functions = catalog_manager.functions();
+    assert_eq!(functions.create(increment.clone(), "role_1", false), Ok(true));
+    assert_eq!(functions.create(notify.clone(), "role_2", false), Ok(true));
+    assert_eq!(
+        functions.create(
+            Function {
+                name: "dropped".to_owned(),
+                ..increment.clone()
+            },
+            "role_1",
+            false
+        ),
+        Ok(true)
+    );
+    assert_eq!(functions.drop("dropped"), Ok(true));
+
+    drop(catalog_manager);
+
+    let catalog_manager = CatalogManager::persistent(root_path.into_path()).expect("to create catalog manager");
+    let functions = catalog_manager.functions();
+
+    assert_eq!(functions.get("increment").as_deref(), Some(&increment));
+    assert_eq!(functions.owner("increment"), Some("role_1".to_owned()));
+    assert_eq!(functions.get("notify").as_deref(), Some(&notify));
+    assert_eq!(functions.owner("notify"), Some("role_2".to_owned()));
+    assert_eq!(functions.get("dropped"), None);
+}
+
+#[rstest::rstest]
+fn aggregates_are_preserved_after_restart(persistent: (CatalogManager, TempDir)) {
+    let (catalog_manager, root_path) = persistent;
+    let function = |name: &str, params: &[&str], source: &str| {
+        Arc::new(Function {
+            name: name.to_owned(),
+            params: params
+                .iter()
+                .map(|param| Variable {
+                    name: (*param).to_owned(),
+                    pg_type: PostgreSqlType::Integer,
+                })
+                .collect(),
+            returns: Returns::Type(PostgreSqlType::Integer),
+            body: Body::Block(parse_body(source).expect("parsed"), source.to_owned()),
+        })
+    };
+    let total = Aggregate {
+        name: "total".to_owned(),
+        input: PostgreSqlType::Integer,
+        state: PostgreSqlType::Integer,
+        initial: Some("0".to_owned()),
+        transition: function("add", &["a", "b"], "begin return a + b; end"),
+        finish: None,
+    };
+    let doubled = Aggregate {
+        name: "doubled".to_owned(),
+        initial: None,
+        finish: Some(function("double", &["n"], "begin return n * 2; end")),
+        ..total.clone()
+    };
+    let aggregates = catalog_manager.aggregates();
+    assert_eq!(aggregates.create(total.clone(), "role_1", false), Ok(true));
+    assert_eq!(aggregates.create(doubled.clone(), "role_2", false), Ok(true));
+    assert_eq!(aggregates.create(doubled.clone(), "role_1", false), Ok(false));
+
+    drop(catalog_manager);
+
+    let catalog_manager = CatalogManager::persistent(root_path.into_path()).expect("to create catalog manager");
+    let aggregates = catalog_manager.aggregates();
+
+    assert_eq!(aggregates.get("total").as_deref(), Some(&total));
+    assert_eq!(aggregates.owner("total"), Some("role_1".to_owned()));
+    assert_eq!(aggregates.get("doubled").as_deref(), Some(&doubled));
+    assert_eq!(aggregates.owner("doubled"), Some("role_2".to_owned()));
+}
+
+#[rstest::rstest]
+fn event_triggers_are_preserved_after_restart(persistent: (CatalogManager, TempDir)) {
+    let (catalog_manager, root_path) = persistent;
+    let on_drop = EventTrigger {
+        name: "on_drop".to_owned(),
+        event: DdlEvent::Start,
+        tags: Some(vec!["DROP TABLE".to_owned(), "DROP SCHEMA".to_owned()]),
+        function: "notify".to_owned(),
+    };
+    let on_any = EventTrigger {
+        name: "on_any".to_owned(),
+        event: DdlEvent::End,
+        tags: None,
+        function: "notify".to_owned(),
+    };
+    let event_triggers = catalog_manager.event_triggers();
+    assert_eq!(event_triggers.create(on_drop.clone()), Ok(true));
+    assert_eq!(event_triggers.create(on_any.clone()), Ok(true));
+    assert_eq!(
+        event_triggers.create(EventTrigger {
+            name: "dropped".to_owned(),
+            ..on_any.clone()
+        }),
+        Ok(true)
+    );
+    assert_eq!(event_triggers.drop("dropped"), Ok(true));
+
+    drop(catalog_manager);
+
+    let catalog_manager = CatalogManager::persistent(root_path.into_path()).expect("to create catalog manager");
+    let event_triggers = catalog_manager.event_triggers();
+
+    assert_eq!(event_triggers.matching(DdlEvent::Start, "DROP SCHEMA"), vec![on_drop]);
+    assert_eq!(event_triggers.matching(DdlEvent::Start, "CREATE TABLE"), vec![]);
+    assert_eq!(event_triggers.matching(DdlEvent::End, "CREATE TABLE"), vec![on_any]);
+}
+
+#[rstest::rstest]
+fn default_settings_are_preserved_after_restart(persistent: (CatalogManager, TempDir)) {
+    let (catalog_manager, root_path) = persistent;
+    let settings = catalog_manager.default_settings();
+    settings
+        .change(
+            SettingsOwner::Database("db_1".to_owned()),
+            SettingChange::Set(
+                "search_path".to_owned(),
+                SetVariableValue::Ident(Ident::new("schema_1")),
+            ),
+        )
+        .expect("no system errors");
+    settings
+        .change(
+            SettingsOwner::Role("role_1".to_owned()),
+            SettingChange::Set(
+                "work_mem".to_owned(),
+                SetVariableValue::Literal(Value::Number("128".parse().expect("number"))),
+            ),
+        )
+        .expect("no system errors");
+    settings
+        .change(
+            SettingsOwner::Role("role_1".to_owned()),
+            SettingChange::Set(
+                "application_name".to_owned(),
+                SetVariableValue::Literal(Value::SingleQuotedString("it's mine".to_owned())),
+            ),
+        )
+        .expect("no system errors");
+    settings
+        .change(
+            SettingsOwner::Role("role_2".to_owned()),
+            SettingChange::Set(
+                "work_mem".to_owned(),
+                SetVariableValue::Literal(Value::Number("64".parse().expect("number"))),
+            ),
+        )
+        .expect("no system errors");
+    settings
+        .change(SettingsOwner::Role("role_2".to_owned()), SettingChange::ResetAll)
+        .expect("no system errors");
+
+    drop(catalog_manager);
+
+    let catalog_manager = CatalogManager::persistent(root_path.into_path()).expect("to create catalog manager");
+
+    assert_eq!(
+        catalog_manager.default_settings().of_session("db_1", "role_1"),
+        vec![
+            (
+                "search_path".to_owned(),
+                SetVariableValue::Ident(Ident::new("schema_1"))
+            ),
+            (
+                "application_name".to_owned(),
+                SetVariableValue::Literal(Value::SingleQuotedString("it's mine".to_owned()))
+            ),
+            (
+                "work_mem".to_owned(),
+                SetVariableValue::Literal(Value::Number("128".parse().expect("number")))
+            ),
+        ]
+    );
+    assert_eq!(catalog_manager.default_settings().of_session("db_2", "role_2"), vec![]);
+}
+
+/// Result of built-in function `name` that a statement of a session with
+/// opened `descriptors` calls
+fn call_builtin(
+    catalog_manager: &CatalogManager,
+    descriptors: &LargeObjectDescriptors,
+    name: &str,
+    args: &[Scalar],
+) -> Result<Scalar, QueryError> {
+    let functions = catalog_manager
+        .scalar_functions()
+        .for_statement(&CancellationToken::default(), descriptors);
+    functions.overloads(name).pop().expect("built-in function").call(args)
+}
+
+#[rstest::rstest]
+fn large_objects_are_preserved_after_restart(persistent: (CatalogManager, TempDir)) {
+    const INV_READ_WRITE: i64 = 0x0006_0000;
+    let (catalog_manager, root_path) = persistent;
+    let descriptors = LargeObjectDescriptors::default();
+    let call =
+        |name: &str, args: &[Scalar]| call_builtin(&catalog_manager, &descriptors, name, args).expect("no errors");
+    let written = call("lo_create", &[Scalar::Integer(0)]);
+    let data = (0..large_objects::PAGE_SIZE + 10)
+        .map(|index| index as u8)
+        .collect::<Vec<u8>>();
+    let fd = call("lo_open", &[written.clone(), Scalar::Integer(INV_READ_WRITE)]);
+    call("lowrite", &[fd.clone(), Scalar::Text(bytea_output(b"head\0"))]);
+    call("lowrite", &[fd, Scalar::Text(bytea_output(&data))]);
+    let empty = call("lo_create", &[Scalar::Integer(0)]);
+    let unlinked = call("lo_create", &[Scalar::Integer(0)]);
+    let fd = call("lo_open", &[unlinked.clone(), Scalar::Integer(INV_READ_WRITE)]);
+    call("lowrite", &[fd, Scalar::Text(bytea_output(b"data"))]);
+    call("lo_unlink", std::slice::from_ref(&unlinked));
+
+    drop(catalog_manager);
+
+    let catalog_manager = CatalogManager::persistent(root_path.into_path()).expect("to create catalog manager");
+    let descriptors = LargeObjectDescriptors::default();
+    let call = |name: &str, args: &[Scalar]| call_builtin(&catalog_manager, &descriptors, name, args);
+
+    let mut content = b"head\0".to_vec();
+    content.extend_from_slice(&data);
+    let fd = call("lo_open", &[written, Scalar::Integer(INV_READ_WRITE)]).expect("no errors");
+    assert_eq!(
+        call("loread", &[fd, Scalar::Integer(content.len() as i64 + 1)]),
+        Ok(Scalar::Text(bytea_output(&content)))
+    );
+    let fd = call("lo_open", &[empty, Scalar::Integer(INV_READ_WRITE)]).expect("no errors");
+    assert_eq!(
+        call("loread", &[fd, Scalar::Integer(1)]),
+        Ok(Scalar::Text(bytea_output(&[])))
+    );
+    let unlinked = match unlinked {
+        Scalar::Integer(oid) => oid as u32,
+        other => panic!("OID of a large object, not {:?}", other),
+    };
+    assert_eq!(
+        call(
+            "lo_open",
+            &[Scalar::Integer(i64::from(unlinked)), Scalar::Integer(INV_READ_WRITE)]
+        ),
+        Err(QueryError::large_object_does_not_exist(unlinked))
+    );
+}
+
+#[rstest::rstest]
+fn publications_and_replication_slots_are_preserved_after_restart(persistent: (CatalogManager, TempDir)) {
+    let (catalog_manager, root_path) = persistent;
+    let publications = catalog_manager.publications();
+    let change = |table_name: &str| RowChange::insert(SCHEMA, table_name, &[Datum::from_i16(1), Datum::from_null()]);
+    assert!(publications
+        .create(
+            "changes",
+            Some(vec![
+                (SCHEMA.to_owned(), "table_1".to_owned()),
+                (SCHEMA.to_owned(), "table_2".to_owned())
+            ])
+        )
+        .expect("no system errors"));
+    assert!(publications.create("all_changes", None).expect("no system errors"));
+    publications
+        .remove_table("changes", SCHEMA, "table_2")
+        .expect("no system errors");
+    assert_eq!(
+        publications.create_slot("slot", "changes").expect("no system errors"),
+        Ok(())
+    );
+    assert_eq!(
+        publications
+            .create_slot("dropped", "changes")
+            .expect("no system errors"),
+        Ok(())
+    );
+    publications
+        .publish(SCHEMA, "table_1", vec![change("table_1"), change("table_1")])
+        .expect("no system errors");
+    publications
+        .publish(SCHEMA, "table_2", vec![change("table_2")])
+        .expect("no system errors");
+    assert!(publications.drop_slot("dropped").expect("no system errors"));
+    let stream = publications.consume_slot("slot").expect("slot exists");
+    assert_eq!(stream.try_recv(), Some(change("table_1")));
+    drop(stream);
+    publications
+        .publish(SCHEMA, "table_1", vec![change("table_3")])
+        .expect("no system errors");
+
+    drop(catalog_manager);
+
+    let catalog_manager = CatalogManager::persistent(root_path.into_path()).expect("to create catalog manager");
+    let publications = catalog_manager.publications();
+
+    assert_eq!(
+        publications.members(),
+        vec![("changes".to_owned(), SCHEMA.to_owned(), "table_1".to_owned())]
+    );
+    assert!(publications
+        .create("all_changes", None)
+        .map(|created| !created)
+        .expect("no system errors"));
+    assert_eq!(
+        publications.slots(),
+        vec![ReplicationSlotInfo {
+            name: "slot".to_owned(),
+            publication: "changes".to_owned(),
+            active: false,
+            retained_changes: 2,
+            confirmed_position: 1,
+        }]
+    );
+    let stream = publications.consume_slot("slot").expect("slot exists");
+    assert_eq!(stream.try_recv(), Some(change("table_1")));
+    assert_eq!(stream.try_recv(), Some(change("table_3")));
+    assert_eq!(stream.try_recv(), None);
+    publications
+        .publish(SCHEMA, "table_1", vec![change("table_4")])
+        .expect("no system errors");
+    assert_eq!(stream.try_recv(), Some(change("table_4")));
+    assert_eq!(publications.slots()[0].confirmed_position, 5);
+}
@@ -94,6 +94,10 @@ fn restrict_drop_schema_does_not_drop_schema_with_table(catalog_manager_with_sch
             .expect("no system errors"),
         Err(DropSchemaError::HasDependentObjects)
     );
+    assert_eq!(
+        catalog_manager_with_schema.create_table(schema_id, "other_table", &[]),
+        Ok(())
+    );
 }
 
 #[rstest::rstest]
@@ -122,3 +122,27 @@ fn table_columns_on_empty_table(catalog_manager_with_schema: CatalogManager) {
         vec![]
     );
 }
+
+#[rstest::rstest]
+fn statistics_of_dropped_table_are_forgotten(catalog_manager_with_schema: CatalogManager) {
+    let schema_id = catalog_manager_with_schema
+        .schema_exists(SCHEMA)
+        .expect("schema exists");
+    catalog_manager_with_schema
+        .create_table(
+            schema_id,
+            "table_name",
+            &[ColumnDefinition::new("column_test", SqlType::SmallInt(i16::MIN))],
+        )
+        .expect("table is created");
+    assert_eq!(
+        catalog_manager_with_schema.table_statistics(SCHEMA, "table_name"),
+        Some(TableStatistics::default())
+    );
+
+    catalog_manager_with_schema
+        .drop_table(SCHEMA, "table_name")
+        .expect("table is dropped");
+
+    assert_eq!(catalog_manager_with_schema.table_statistics(SCHEMA, "table_name"), None);
+}
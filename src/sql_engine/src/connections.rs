@@ -0,0 +1,167 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Accounting of open client connections to enforce global and per role
+//! limits.
+use crate::catalog_manager::CatalogManager;
+use protocol::results::QueryError;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+/// Counts open connections per role. A connection is accounted until its
+/// `ConnectionSlot` is dropped.
+pub struct Connections {
+    storage: Arc<CatalogManager>,
+    max_connections: AtomicUsize,
+    active: Mutex<HashMap<String, usize>>,
+}
+
+impl Connections {
+    /// Creates registry that accepts up to `max_connections` connections
+    pub fn new(storage: Arc<CatalogManager>, max_connections: usize) -> Arc<Connections> {
+        Arc::new(Connections {
+            storage,
+            max_connections: AtomicUsize::new(max_connections),
+            active: Mutex::default(),
+        })
+    }
+
+    /// Accounts new connection of `role` or returns `too_many_connections`
+    /// error that has to be sent to the client before the connection is
+    /// closed
+    pub fn open(self: &Arc<Self>, role: &str) -> Result<ConnectionSlot, QueryError> {
+        let mut active = self.active.lock().expect("to acquire lock");
+        if active.values().sum::<usize>() >= self.max_connections() {
+            return Err(QueryError::too_many_connections());
+        }
+        let role_connections = active.entry(role.to_owned()).or_default();
+        if let Some(limit) = self.storage.roles().connection_limit(role) {
+            if *role_connections >= limit as usize {
+                return Err(QueryError::too_many_connections_for_role(role.to_owned()));
+            }
+        }
+        *role_connections += 1;
+        Ok(ConnectionSlot {
+            connections: self.clone(),
+            role: role.to_owned(),
+        })
+    }
+
+    /// Maximum number of connections that can be open at the same time
+    pub fn max_connections(&self) -> usize {
+        self.max_connections.load(Ordering::SeqCst)
+    }
+
+    /// Changes the limit for new connections, connections that are already
+    /// open over the new limit are not closed
+    pub fn set_max_connections(&self, max_connections: usize) {
+        self.max_connections.store(max_connections, Ordering::SeqCst);
+    }
+
+    /// Number of open connections
+    pub fn count(&self) -> usize {
+        self.active.lock().expect("to acquire lock").values().sum()
+    }
+
+    fn close(&self, role: &str) {
+        let mut active = self.active.lock().expect("to acquire lock");
+        if let Some(role_connections) = active.get_mut(role) {
+            *role_connections -= 1;
+            if *role_connections == 0 {
+                active.remove(role);
+            }
+        }
+    }
+}
+
+/// Open connection of a role, it is closed on drop
+pub struct ConnectionSlot {
+    connections: Arc<Connections>,
+    role: String,
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.connections.close(&self.role);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog_manager::SUPERUSER;
+
+    #[test]
+    fn max_connections() {
+        let connections = Connections::new(Arc::new(CatalogManager::default()), 2);
+        let _first = connections.open("role_1").expect("connection is open");
+        let second = connections.open("role_2").expect("connection is open");
+
+        assert!(matches!(connections.open(SUPERUSER), Err(error) if error == QueryError::too_many_connections()));
+
+        drop(second);
+
+        assert!(connections.open(SUPERUSER).is_ok());
+    }
+
+    #[test]
+    fn change_max_connections() {
+        let connections = Connections::new(Arc::new(CatalogManager::default()), 2);
+        let first = connections.open("role_1").expect("connection is open");
+        let _second = connections.open("role_2").expect("connection is open");
+
+        connections.set_max_connections(1);
+
+        assert_eq!(connections.count(), 2);
+        assert!(matches!(connections.open(SUPERUSER), Err(error) if error == QueryError::too_many_connections()));
+
+        drop(first);
+        connections.set_max_connections(3);
+
+        assert!(connections.open(SUPERUSER).is_ok());
+        assert_eq!(connections.max_connections(), 3);
+    }
+
+    #[test]
+    fn role_connection_limit() {
+        let storage = Arc::new(CatalogManager::default());
+        storage
+            .roles()
+            .set_connection_limit("role_1", Some(1))
+            .expect("no system errors");
+        storage
+            .roles()
+            .set_connection_limit(SUPERUSER, Some(1))
+            .expect("no system errors");
+        let connections = Connections::new(storage, 10);
+        let first = connections.open("role_1").expect("connection is open");
+
+        assert!(matches!(
+            connections.open("role_1"),
+            Err(error) if error == QueryError::too_many_connections_for_role("role_1".to_owned())
+        ));
+        assert!(connections.open("role_2").is_ok());
+        assert!(connections.open(SUPERUSER).and(connections.open(SUPERUSER)).is_ok());
+
+        drop(first);
+
+        assert!(connections.open("role_1").is_ok());
+        assert_eq!(connections.count(), 0);
+    }
+}
@@ -0,0 +1,116 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    catalog_manager::{CatalogManager, SUPERUSER},
+    dcl::send,
+    query::{extended::DefaultPrivilegesChange, SchemaId, SchemaNamingError},
+};
+use kernel::{SystemError, SystemResult};
+use protocol::{
+    results::{QueryError, QueryEvent},
+    Sender,
+};
+use std::{convert::TryFrom, sync::Arc};
+
+pub(crate) struct AlterDefaultPrivilegesCommand {
+    change: DefaultPrivilegesChange,
+    role: String,
+    storage: Arc<CatalogManager>,
+    session: Arc<dyn Sender>,
+}
+
+impl AlterDefaultPrivilegesCommand {
+    pub(crate) fn new(
+        change: DefaultPrivilegesChange,
+        role: String,
+        storage: Arc<CatalogManager>,
+        session: Arc<dyn Sender>,
+    ) -> AlterDefaultPrivilegesCommand {
+        AlterDefaultPrivilegesCommand {
+            change,
+            role,
+            storage,
+            session,
+        }
+    }
+
+    /// Only superuser can change default privileges of other roles
+    pub(crate) fn execute(&mut self) -> SystemResult<()> {
+        let target_roles = if self.change.target_roles.is_empty() {
+            vec![self.role.clone()]
+        } else {
+            self.change.target_roles.clone()
+        };
+        if let Some(target) = target_roles
+            .iter()
+            .find(|target| self.role != SUPERUSER && **target != self.role)
+        {
+            self.session
+                .send(Err(QueryError::permission_denied("role", target.clone())))
+                .map_err(SystemError::connection_lost)?;
+            return Ok(());
+        }
+        let schemas = match self.resolve_schemas() {
+            Ok(schemas) => schemas,
+            Err(()) => return Ok(()),
+        };
+        let privileges = self.storage.privileges();
+        for target in &target_roles {
+            for schema_name in &schemas {
+                for grantee in &self.change.grantees {
+                    if self.change.grant {
+                        privileges.grant_table_defaults(
+                            target,
+                            schema_name.as_deref(),
+                            grantee,
+                            &self.change.privileges,
+                        )?
+                    } else {
+                        privileges.revoke_table_defaults(
+                            target,
+                            schema_name.as_deref(),
+                            grantee,
+                            &self.change.privileges,
+                        )?
+                    }
+                }
+            }
+        }
+        self.session
+            .send(Ok(QueryEvent::DefaultPrivilegesAltered))
+            .map_err(SystemError::connection_lost)?;
+        Ok(())
+    }
+
+    /// `None` stands for all schemas
+    fn resolve_schemas(&self) -> Result<Vec<Option<String>>, ()> {
+        if self.change.schemas.is_empty() {
+            return Ok(vec![None]);
+        }
+        let mut schemas = vec![];
+        for name in &self.change.schemas {
+            let schema_id = match SchemaId::try_from(name.clone()) {
+                Ok(schema_id) => schema_id,
+                Err(SchemaNamingError(message)) => return send(&self.session, QueryError::syntax_error(message)),
+            };
+            let schema_name = schema_id.name();
+            if self.storage.schema_exists(schema_name).is_none() {
+                return send(&self.session, QueryError::schema_does_not_exist(schema_name.to_owned()));
+            }
+            schemas.push(Some(schema_name.to_owned()));
+        }
+        Ok(schemas)
+    }
+}
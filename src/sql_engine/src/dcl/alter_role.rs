@@ -0,0 +1,78 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    catalog_manager::{CatalogManager, SUPERUSER},
+    query::extended::RoleOption,
+};
+use kernel::{SystemError, SystemResult};
+use protocol::{
+    results::{QueryError, QueryEvent},
+    Sender,
+};
+use std::sync::Arc;
+
+pub(crate) struct AlterRoleCommand {
+    target: String,
+    options: Vec<RoleOption>,
+    role: String,
+    storage: Arc<CatalogManager>,
+    session: Arc<dyn Sender>,
+}
+
+impl AlterRoleCommand {
+    pub(crate) fn new(
+        target: String,
+        options: Vec<RoleOption>,
+        role: String,
+        storage: Arc<CatalogManager>,
+        session: Arc<dyn Sender>,
+    ) -> AlterRoleCommand {
+        AlterRoleCommand {
+            target,
+            options,
+            role,
+            storage,
+            session,
+        }
+    }
+
+    /// Only superuser can change password of other roles, make roles
+    /// read-only and limit their connections
+    pub(crate) fn execute(&mut self) -> SystemResult<()> {
+        let allowed = self.role == SUPERUSER
+            || self.options.iter().all(|option| match option {
+                RoleOption::Password(_) => self.role == self.target,
+                RoleOption::ReadOnly(_) | RoleOption::ConnectionLimit(_) => false,
+            });
+        if !allowed {
+            self.session
+                .send(Err(QueryError::permission_denied("role", self.target.clone())))
+                .map_err(SystemError::connection_lost)?;
+            return Ok(());
+        }
+        let roles = self.storage.roles();
+        for option in self.options.drain(..) {
+            match option {
+                RoleOption::Password(password) => roles.set_password(&self.target, password)?,
+                RoleOption::ReadOnly(read_only) => roles.set_read_only(&self.target, read_only)?,
+                RoleOption::ConnectionLimit(limit) => roles.set_connection_limit(&self.target, limit)?,
+            }
+        }
+        self.session
+            .send(Ok(QueryEvent::RoleAltered))
+            .map_err(SystemError::connection_lost)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,89 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    catalog_manager::{CatalogManager, SUPERUSER},
+    query::extended::{SettingChange, SettingsOwner},
+    session::settings,
+};
+use kernel::{SystemError, SystemResult};
+use protocol::{
+    results::{QueryError, QueryEvent},
+    Sender,
+};
+use std::sync::Arc;
+
+pub(crate) struct AlterSettingsCommand {
+    owner: SettingsOwner,
+    change: SettingChange,
+    role: String,
+    storage: Arc<CatalogManager>,
+    session: Arc<dyn Sender>,
+}
+
+impl AlterSettingsCommand {
+    pub(crate) fn new(
+        owner: SettingsOwner,
+        change: SettingChange,
+        role: String,
+        storage: Arc<CatalogManager>,
+        session: Arc<dyn Sender>,
+    ) -> AlterSettingsCommand {
+        AlterSettingsCommand {
+            owner,
+            change,
+            role,
+            storage,
+            session,
+        }
+    }
+
+    /// Roles can change their own settings, settings of other roles and of
+    /// databases are changed only by superuser. Values are checked before
+    /// they are stored, so that sessions don't fail to start.
+    pub(crate) fn execute(self) -> SystemResult<()> {
+        let (allowed, denied, altered) = match &self.owner {
+            SettingsOwner::Role(target) => (
+                self.role == SUPERUSER || self.role == *target,
+                QueryError::permission_denied("role", target.clone()),
+                QueryEvent::RoleAltered,
+            ),
+            SettingsOwner::Database(database) => (
+                self.role == SUPERUSER,
+                QueryError::permission_denied("database", database.clone()),
+                QueryEvent::DatabaseAltered,
+            ),
+        };
+        let checked = match &self.change {
+            _ if !allowed => Err(denied),
+            SettingChange::Set(name, value) => settings::check(&name.to_lowercase(), value),
+            SettingChange::Reset(_) | SettingChange::ResetAll => Ok(()),
+        };
+        let AlterSettingsCommand {
+            owner,
+            change,
+            storage,
+            session,
+            ..
+        } = self;
+        let result = match checked {
+            Ok(()) => {
+                storage.default_settings().change(owner, change)?;
+                Ok(altered)
+            }
+            Err(error) => Err(error),
+        };
+        session.send(result).map_err(SystemError::connection_lost)
+    }
+}
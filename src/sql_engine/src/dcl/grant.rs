@@ -0,0 +1,77 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    catalog_manager::CatalogManager,
+    dcl::{check_columns, resolve_targets, PrivilegeTarget},
+    query::extended::PrivilegeChange,
+};
+use kernel::{SystemError, SystemResult};
+use protocol::{results::QueryEvent, Sender};
+use std::sync::Arc;
+
+pub(crate) struct GrantCommand {
+    change: PrivilegeChange,
+    role: String,
+    storage: Arc<CatalogManager>,
+    session: Arc<dyn Sender>,
+}
+
+impl GrantCommand {
+    pub(crate) fn new(
+        change: PrivilegeChange,
+        role: String,
+        storage: Arc<CatalogManager>,
+        session: Arc<dyn Sender>,
+    ) -> GrantCommand {
+        GrantCommand {
+            change,
+            role,
+            storage,
+            session,
+        }
+    }
+
+    pub(crate) fn execute(&mut self) -> SystemResult<()> {
+        let targets = match resolve_targets(&self.change.objects, &self.role, &self.storage, &self.session) {
+            Ok(targets) => targets,
+            Err(()) => return Ok(()),
+        };
+        if check_columns(&self.change, &targets, &self.storage, &self.session).is_err() {
+            return Ok(());
+        }
+        let privileges = self.storage.privileges();
+        for target in targets {
+            for grantee in &self.change.grantees {
+                match &target {
+                    PrivilegeTarget::Schema(schema_name) => {
+                        privileges.grant_on_schema(schema_name, grantee, &self.change.privileges)?
+                    }
+                    PrivilegeTarget::Table(schema_name, table_name) => {
+                        privileges.grant_on_table(schema_name, table_name, grantee, &self.change.privileges)?;
+                        for (privilege, columns) in &self.change.column_privileges {
+                            for column in columns {
+                                privileges.grant_on_column(schema_name, table_name, column, grantee, *privilege)?
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        self.session
+            .send(Ok(QueryEvent::PrivilegesGranted))
+            .map_err(SystemError::connection_lost)?;
+        Ok(())
+    }
+}
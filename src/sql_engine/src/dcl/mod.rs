@@ -0,0 +1,128 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub(crate) mod alter_default_privileges;
+pub(crate) mod alter_role;
+pub(crate) mod alter_settings;
+pub(crate) mod grant;
+pub(crate) mod revoke;
+
+use crate::{
+    catalog_manager::CatalogManager,
+    query::{
+        extended::{PrivilegeChange, PrivilegeObjects},
+        resolve::Scope,
+        SchemaId, SchemaNamingError, TableId, TableNamingError,
+    },
+};
+use protocol::{results::QueryError, Sender};
+use std::{convert::TryFrom, sync::Arc};
+
+pub(crate) enum PrivilegeTarget {
+    Schema(String),
+    Table(String, String),
+}
+
+/// Checks that all objects exist and `role` owns them. Only owners can grant
+/// or revoke privileges on an object.
+pub(crate) fn resolve_targets(
+    objects: &PrivilegeObjects,
+    role: &str,
+    storage: &CatalogManager,
+    session: &Arc<dyn Sender>,
+) -> Result<Vec<PrivilegeTarget>, ()> {
+    let mut targets = vec![];
+    match objects {
+        PrivilegeObjects::Schemas(names) => {
+            for name in names {
+                let schema_id = match SchemaId::try_from(name.clone()) {
+                    Ok(schema_id) => schema_id,
+                    Err(SchemaNamingError(message)) => return send(session, QueryError::syntax_error(message)),
+                };
+                let schema_name = schema_id.name();
+                if storage.schema_exists(schema_name).is_none() {
+                    return send(session, QueryError::schema_does_not_exist(schema_name.to_owned()));
+                }
+                if !storage.privileges().is_schema_owner(role, schema_name) {
+                    return send(session, QueryError::must_be_owner("schema", schema_name.to_owned()));
+                }
+                targets.push(PrivilegeTarget::Schema(schema_name.to_owned()));
+            }
+        }
+        PrivilegeObjects::Tables(names) => {
+            for name in names {
+                let table_id = match TableId::try_from(name.clone()) {
+                    Ok(table_id) => table_id,
+                    Err(TableNamingError(message)) => return send(session, QueryError::syntax_error(message)),
+                };
+                let schema_name = table_id.schema_name();
+                let table_name = table_id.name();
+                match storage.table_exists(schema_name, table_name) {
+                    None => return send(session, QueryError::schema_does_not_exist(schema_name.to_owned())),
+                    Some((_, None)) => {
+                        return send(
+                            session,
+                            QueryError::table_does_not_exist(format!("{}.{}", schema_name, table_name)),
+                        )
+                    }
+                    Some((_, Some(_))) => {}
+                }
+                if !storage.privileges().is_table_owner(role, schema_name, table_name) {
+                    return send(
+                        session,
+                        QueryError::must_be_owner("table", format!("{}.{}", schema_name, table_name)),
+                    );
+                }
+                targets.push(PrivilegeTarget::Table(schema_name.to_owned(), table_name.to_owned()));
+            }
+        }
+    }
+    Ok(targets)
+}
+
+/// Checks that every column of `change` exists in all table `targets`
+pub(crate) fn check_columns(
+    change: &PrivilegeChange,
+    targets: &[PrivilegeTarget],
+    storage: &CatalogManager,
+    session: &Arc<dyn Sender>,
+) -> Result<(), ()> {
+    for target in targets {
+        if let PrivilegeTarget::Table(schema_name, table_name) = target {
+            let table_columns = match storage.table_columns(schema_name, table_name) {
+                Ok(table_columns) => table_columns,
+                Err(_) => {
+                    return send(
+                        session,
+                        QueryError::table_does_not_exist(format!("{}.{}", schema_name, table_name)),
+                    )
+                }
+            };
+            let columns = change
+                .column_privileges
+                .iter()
+                .flat_map(|(_privilege, columns)| columns)
+                .map(String::as_str);
+            if let Err(error) = Scope::new(&table_columns).resolve_all(columns) {
+                return send(session, error);
+            }
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn send<T>(session: &Arc<dyn Sender>, error: QueryError) -> Result<T, ()> {
+    session.send(Err(error)).ok();
+    Err(())
+}
@@ -0,0 +1,159 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::{
+    catalog_manager::CatalogManager,
+    dml::{batch::Batches, copy},
+    query::{
+        extended::{AlterTableStatement, ColumnChange},
+        resolve::Scope,
+        TableId, TableNamingError,
+    },
+    ColumnDefinition,
+};
+use kernel::{SystemError, SystemResult};
+use protocol::{
+    results::{QueryError, QueryEvent},
+    Sender,
+};
+use representation::Datum;
+use sqlparser::ast::{Expr, UnaryOperator, Value};
+use std::{convert::TryFrom, sync::Arc};
+
+/// Changes defaults and nullability of columns. `SET NOT NULL` scans the
+/// table and rejects all changes if a changed column already has NULLs.
+pub(crate) struct AlterTableCommand {
+    alter: AlterTableStatement,
+    role: String,
+    storage: Arc<CatalogManager>,
+    session: Arc<dyn Sender>,
+}
+
+impl AlterTableCommand {
+    pub(crate) fn new(
+        alter: AlterTableStatement,
+        role: String,
+        storage: Arc<CatalogManager>,
+        session: Arc<dyn Sender>,
+    ) -> AlterTableCommand {
+        AlterTableCommand {
+            alter,
+            role,
+            storage,
+            session,
+        }
+    }
+
+    pub(crate) fn execute(&mut self) -> SystemResult<()> {
+        let table_id = match TableId::try_from(self.alter.table_name.clone()) {
+            Ok(table_id) => table_id,
+            Err(TableNamingError(message)) => return self.send(Err(QueryError::syntax_error(message))),
+        };
+        let schema_name = table_id.schema_name();
+        let table_name = table_id.name();
+        let full_name = format!("{}.{}", schema_name, table_name);
+        match self.storage.table_exists(schema_name, table_name) {
+            None => return self.send(Err(QueryError::schema_does_not_exist(schema_name.to_owned()))),
+            Some((_, None)) => return self.send(Err(QueryError::table_does_not_exist(full_name))),
+            Some((_, Some(_))) => {}
+        }
+        let privileges = self.storage.privileges();
+        if !privileges.is_table_owner(&self.role, schema_name, table_name)
+            && !privileges.is_schema_owner(&self.role, schema_name)
+        {
+            return self.send(Err(QueryError::must_be_owner("table", full_name)));
+        }
+        if self.storage.is_foreign_table(schema_name, table_name) {
+            return self.send(Err(QueryError::cannot_change_foreign_table("alter", full_name)));
+        }
+
+        let mut columns = self.storage.table_columns(schema_name, table_name)?;
+        let indexes =
+            match Scope::new(&columns).resolve_all(self.alter.changes.iter().map(|(column, _)| column.as_str())) {
+                Ok(indexes) => indexes,
+                Err(error) => return self.send(Err(error)),
+            };
+        let mut checked = vec![];
+        for (index, (_, change)) in indexes.into_iter().zip(&self.alter.changes) {
+            let column = columns[index].clone();
+            columns[index] = match change {
+                ColumnChange::SetDefault(expr) => match default_value(&column, expr) {
+                    Ok(default) => column.with_default(default),
+                    Err(error) => return self.send(Err(error)),
+                },
+                ColumnChange::DropDefault => column.with_default(None),
+                ColumnChange::SetNotNull => {
+                    if !column.is_not_null() {
+                        checked.push(index);
+                    }
+                    column.with_not_null(true)
+                }
+                ColumnChange::DropNotNull => {
+                    checked.retain(|checked_index| *checked_index != index);
+                    column.with_not_null(false)
+                }
+            };
+        }
+
+        if !checked.is_empty() {
+            let mut scanned = 0;
+            for batch in Batches::new(self.storage.full_scan(schema_name, table_name)?) {
+                scanned += batch.len();
+                let nulls = batch
+                    .rows()
+                    .iter()
+                    .flat_map(|row| checked.iter().filter(move |index| matches!(row[**index], Datum::Null)))
+                    .next();
+                if let Some(index) = nulls {
+                    self.storage.statistics().seq_scanned(schema_name, table_name, scanned);
+                    return self.send(Err(QueryError::column_contains_nulls(
+                        columns[*index].name(),
+                        full_name,
+                    )));
+                }
+            }
+            self.storage.statistics().seq_scanned(schema_name, table_name, scanned);
+        }
+
+        self.storage.alter_columns(schema_name, table_name, &columns)?;
+        self.send(Ok(QueryEvent::TableAltered))
+    }
+
+    fn send(&self, result: Result<QueryEvent, QueryError>) -> SystemResult<()> {
+        self.session.send(result).map_err(SystemError::connection_lost)?;
+        Ok(())
+    }
+}
+
+/// Text of a literal `DEFAULT` of `column` that is converted the same way as
+/// fields of `COPY`, `None` for `DEFAULT NULL`
+pub(crate) fn default_value(column: &ColumnDefinition, expr: &Expr) -> Result<Option<String>, QueryError> {
+    let text = match expr {
+        Expr::Value(Value::Null) => return Ok(None),
+        Expr::Value(Value::Number(number)) => number.to_string(),
+        Expr::Value(Value::SingleQuotedString(text)) => text.clone(),
+        Expr::Value(Value::Boolean(boolean)) => boolean.to_string(),
+        Expr::UnaryOp {
+            op: UnaryOperator::Minus,
+            expr,
+        } => match &**expr {
+            Expr::Value(Value::Number(number)) => (-number).to_string(),
+            _ => return Err(QueryError::feature_not_supported(format!("DEFAULT {}", expr))),
+        },
+        expr => return Err(QueryError::feature_not_supported(format!("DEFAULT {}", expr))),
+    };
+    match copy::datum(&column.sql_type(), &text) {
+        Ok(_) => Ok(Some(text)),
+        Err(error) => Err(copy::constraint_violation(error, column, 1)),
+    }
+}
@@ -0,0 +1,152 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    catalog_manager::CatalogManager,
+    query::{
+        extended::{ClusterOrder, ClusterStatement},
+        resolve::Scope,
+        TableId, TableNamingError,
+    },
+};
+use kernel::{SystemError, SystemResult};
+use protocol::{
+    results::{QueryError, QueryEvent},
+    Sender,
+};
+use representation::{unpack_raw, Binary, Datum};
+use std::{cmp::Ordering, convert::TryFrom, sync::Arc};
+use storage::Row;
+
+/// Rewrites all rows of a table in the order of the given columns, so that
+/// scans read rows with close values one after another. The table is
+/// expected not to be modified while it is rewritten.
+pub(crate) struct ClusterTableCommand {
+    cluster: ClusterStatement,
+    role: String,
+    storage: Arc<CatalogManager>,
+    session: Arc<dyn Sender>,
+}
+
+impl ClusterTableCommand {
+    pub(crate) fn new(
+        cluster: ClusterStatement,
+        role: String,
+        storage: Arc<CatalogManager>,
+        session: Arc<dyn Sender>,
+    ) -> ClusterTableCommand {
+        ClusterTableCommand {
+            cluster,
+            role,
+            storage,
+            session,
+        }
+    }
+
+    pub(crate) fn execute(&mut self) -> SystemResult<()> {
+        let table_id = match TableId::try_from(self.cluster.table_name.clone()) {
+            Ok(table_id) => table_id,
+            Err(TableNamingError(message)) => return self.send(Err(QueryError::syntax_error(message))),
+        };
+        let schema_name = table_id.schema_name();
+        let table_name = table_id.name();
+        let full_name = format!("{}.{}", schema_name, table_name);
+        match self.storage.table_exists(schema_name, table_name) {
+            None => return self.send(Err(QueryError::schema_does_not_exist(schema_name.to_owned()))),
+            Some((_, None)) => return self.send(Err(QueryError::table_does_not_exist(full_name))),
+            Some((_, Some(_))) => {}
+        }
+        let privileges = self.storage.privileges();
+        if !privileges.is_table_owner(&self.role, schema_name, table_name)
+            && !privileges.is_schema_owner(&self.role, schema_name)
+        {
+            return self.send(Err(QueryError::must_be_owner("table", full_name)));
+        }
+        if self.storage.is_foreign_table(schema_name, table_name) {
+            return self.send(Err(QueryError::cannot_change_foreign_table("cluster", full_name)));
+        }
+        let order = match &self.cluster.order {
+            ClusterOrder::Index(index) => return self.send(Err(QueryError::index_does_not_exist(index.clone()))),
+            ClusterOrder::Columns(columns) => columns,
+        };
+        let all_columns = self.storage.table_columns(schema_name, table_name)?;
+        let sort_keys = match Scope::new(&all_columns).resolve_all(order.iter().map(|(column, _)| column.as_str())) {
+            Ok(indexes) => indexes
+                .into_iter()
+                .zip(order.iter().map(|(_, descending)| *descending))
+                .collect::<Vec<(usize, bool)>>(),
+            Err(error) => return self.send(Err(error)),
+        };
+
+        let rows = self
+            .storage
+            .full_scan(schema_name, table_name)?
+            .map(Result::unwrap)
+            .map(Result::unwrap)
+            .collect::<Vec<Row>>();
+        self.storage
+            .statistics()
+            .seq_scanned(schema_name, table_name, rows.len());
+        let mut decoded = rows
+            .iter()
+            .map(|(_key, values)| unpack_raw(values.to_bytes()))
+            .collect::<Vec<Vec<Datum>>>();
+        decoded.sort_by(|left, right| {
+            sort_keys
+                .iter()
+                .map(|(index, descending)| {
+                    let ordering = compare(&left[*index], &right[*index]);
+                    if *descending {
+                        ordering.reverse()
+                    } else {
+                        ordering
+                    }
+                })
+                .find(|ordering| *ordering != Ordering::Equal)
+                .unwrap_or(Ordering::Equal)
+        });
+        let rewritten = decoded
+            .iter()
+            .map(|row| {
+                (
+                    Binary::with_data(self.storage.next_key_id().to_be_bytes().to_vec()),
+                    Binary::pack(row),
+                )
+            })
+            .collect::<Vec<Row>>();
+
+        self.storage.delete_from(
+            schema_name,
+            table_name,
+            rows.iter().map(|(key, _values)| key.clone()).collect(),
+        )?;
+        self.storage.write_into(schema_name, table_name, rewritten)?;
+        self.send(Ok(QueryEvent::TableClustered))
+    }
+
+    fn send(&self, result: Result<QueryEvent, QueryError>) -> SystemResult<()> {
+        self.session.send(result).map_err(SystemError::connection_lost)?;
+        Ok(())
+    }
+}
+
+/// NULLs go after all other values like in PostgreSQL ascending order
+fn compare(left: &Datum, right: &Datum) -> Ordering {
+    match (left, right) {
+        (Datum::Null, Datum::Null) => Ordering::Equal,
+        (Datum::Null, _) => Ordering::Greater,
+        (_, Datum::Null) => Ordering::Less,
+        (left, right) => left.cmp(right),
+    }
+}
@@ -0,0 +1,137 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    catalog_manager::{CatalogManager, SUPERUSER},
+    procedural::{Aggregate, Function, Returns},
+    query::extended::AggregateStatement,
+};
+use kernel::{SystemError, SystemResult};
+use protocol::{
+    results::{QueryError, QueryEvent},
+    sql_types::PostgreSqlType,
+    Sender,
+};
+use std::sync::Arc;
+
+pub(crate) struct CreateAggregateCommand {
+    statement: AggregateStatement,
+    or_replace: bool,
+    role: String,
+    storage: Arc<CatalogManager>,
+    session: Arc<dyn Sender>,
+}
+
+impl CreateAggregateCommand {
+    pub(crate) fn new(
+        statement: AggregateStatement,
+        or_replace: bool,
+        role: String,
+        storage: Arc<CatalogManager>,
+        session: Arc<dyn Sender>,
+    ) -> CreateAggregateCommand {
+        CreateAggregateCommand {
+            statement,
+            or_replace,
+            role,
+            storage,
+            session,
+        }
+    }
+
+    /// Transition and final functions are resolved once, an aggregate keeps
+    /// working with them after they are dropped or replaced. Only owner of
+    /// an aggregate can replace it.
+    pub(crate) fn execute(&mut self) -> SystemResult<()> {
+        let result = match self.aggregate() {
+            Ok(aggregate) => {
+                let name = aggregate.name.clone();
+                let aggregates = self.storage.aggregates();
+                match aggregates.owner(&name) {
+                    Some(owner) if self.or_replace && owner != self.role && self.role != SUPERUSER => {
+                        Err(QueryError::must_be_owner("aggregate", name))
+                    }
+                    _ if self.storage.functions().get(&name).is_some() => {
+                        Err(QueryError::function_already_exists(name))
+                    }
+                    _ if aggregates.create(aggregate, &self.role, self.or_replace)? => Ok(QueryEvent::AggregateCreated),
+                    _ => Err(QueryError::function_already_exists(name)),
+                }
+            }
+            Err(error) => Err(error),
+        };
+        self.session.send(result).map_err(SystemError::connection_lost)?;
+        Ok(())
+    }
+
+    fn aggregate(&self) -> Result<Aggregate, QueryError> {
+        let AggregateStatement {
+            name,
+            input,
+            state,
+            transition,
+            finish,
+            initial,
+        } = self.statement.clone();
+        let transition = self.function(&transition, &[state, input], Some(state))?;
+        let finish = match finish {
+            Some(finish) => Some(self.function(&finish, &[state], None)?),
+            None => None,
+        };
+        let aggregate = Aggregate {
+            name,
+            input,
+            state,
+            initial,
+            transition,
+            finish,
+        };
+        aggregate.start().map(|_state| aggregate)
+    }
+
+    /// Function `name` that accepts `params` and returns `returns` or a value
+    /// of any type if it is `None`
+    fn function(
+        &self,
+        name: &str,
+        params: &[PostgreSqlType],
+        returns: Option<PostgreSqlType>,
+    ) -> Result<Arc<Function>, QueryError> {
+        match self.storage.functions().get(name) {
+            Some(function)
+                if function
+                    .params
+                    .iter()
+                    .map(|param| param.pg_type)
+                    .eq(params.iter().copied())
+                    && match (function.returns, returns) {
+                        (Returns::Type(actual), Some(expected)) => actual == expected,
+                        (Returns::Type(_), None) => true,
+                        (Returns::EventTrigger, _) => false,
+                    } =>
+            {
+                Ok(function)
+            }
+            _ => Err(QueryError::function_does_not_exist(format!(
+                "{}({})",
+                name,
+                params
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ))),
+        }
+    }
+}
@@ -0,0 +1,61 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    catalog_manager::{CatalogManager, EventTrigger},
+    procedural::Returns,
+};
+use kernel::{SystemError, SystemResult};
+use protocol::{
+    results::{QueryError, QueryEvent},
+    Sender,
+};
+use std::sync::Arc;
+
+pub(crate) struct CreateEventTriggerCommand {
+    trigger: EventTrigger,
+    storage: Arc<CatalogManager>,
+    session: Arc<dyn Sender>,
+}
+
+impl CreateEventTriggerCommand {
+    pub(crate) fn new(
+        trigger: EventTrigger,
+        storage: Arc<CatalogManager>,
+        session: Arc<dyn Sender>,
+    ) -> CreateEventTriggerCommand {
+        CreateEventTriggerCommand {
+            trigger,
+            storage,
+            session,
+        }
+    }
+
+    /// Function of the trigger has to exist and return `event_trigger`
+    pub(crate) fn execute(&mut self) -> SystemResult<()> {
+        let function = &self.trigger.function;
+        let result = match self.storage.functions().get(function) {
+            None => Err(QueryError::function_does_not_exist(format!("{}()", function))),
+            Some(found) if found.returns != Returns::EventTrigger => {
+                Err(QueryError::not_event_trigger_function(function.clone()))
+            }
+            Some(_) if self.storage.event_triggers().create(self.trigger.clone())? => {
+                Ok(QueryEvent::EventTriggerCreated)
+            }
+            Some(_) => Err(QueryError::event_trigger_already_exists(self.trigger.name.clone())),
+        };
+        self.session.send(result).map_err(SystemError::connection_lost)?;
+        Ok(())
+    }
+}
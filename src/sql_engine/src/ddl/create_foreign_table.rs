@@ -0,0 +1,85 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::{catalog_manager::CatalogManager, query::plan::TableCreationInfo};
+use kernel::{SystemError, SystemResult};
+use protocol::{
+    results::{QueryError, QueryEvent},
+    Sender,
+};
+use std::sync::Arc;
+
+pub(crate) struct CreateForeignTableCommand {
+    table_info: TableCreationInfo,
+    server: String,
+    options: Vec<(String, String)>,
+    owner: String,
+    storage: Arc<CatalogManager>,
+    session: Arc<dyn Sender>,
+}
+
+impl CreateForeignTableCommand {
+    pub(crate) fn new(
+        table_info: TableCreationInfo,
+        server: String,
+        options: Vec<(String, String)>,
+        owner: String,
+        storage: Arc<CatalogManager>,
+        session: Arc<dyn Sender>,
+    ) -> CreateForeignTableCommand {
+        CreateForeignTableCommand {
+            table_info,
+            server,
+            options,
+            owner,
+            storage,
+            session,
+        }
+    }
+
+    pub(crate) fn execute(&mut self) -> SystemResult<()> {
+        let table_name = self.table_info.table_name.as_str();
+        let schema_name = self.table_info.schema_name.as_str();
+
+        if !self.storage.foreign_tables().wrapper_exists(&self.server) {
+            self.session
+                .send(Err(QueryError::server_does_not_exist(self.server.clone())))
+                .map_err(SystemError::connection_lost)?;
+            return Ok(());
+        }
+        match self.storage.table_exists(schema_name, table_name) {
+            None => self
+                .session
+                .send(Err(QueryError::schema_does_not_exist(schema_name.to_owned())))
+                .map_err(SystemError::connection_lost)?,
+            Some((_, Some(_))) => self
+                .session
+                .send(Err(QueryError::table_already_exists(table_name.to_owned())))
+                .map_err(SystemError::connection_lost)?,
+            Some((schema_id, None)) => {
+                self.storage
+                    .create_table(schema_id, table_name, self.table_info.columns.as_slice())?;
+                self.storage
+                    .privileges()
+                    .set_table_owner(schema_name, table_name, &self.owner)?;
+                self.storage
+                    .foreign_tables()
+                    .attach(schema_name, table_name, &self.server, self.options.clone());
+                self.session
+                    .send(Ok(QueryEvent::ForeignTableCreated))
+                    .map_err(SystemError::connection_lost)?
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,67 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    catalog_manager::{CatalogManager, SUPERUSER},
+    procedural::Function,
+};
+use kernel::{SystemError, SystemResult};
+use protocol::{
+    results::{QueryError, QueryEvent},
+    Sender,
+};
+use std::sync::Arc;
+
+pub(crate) struct CreateFunctionCommand {
+    function: Function,
+    or_replace: bool,
+    role: String,
+    storage: Arc<CatalogManager>,
+    session: Arc<dyn Sender>,
+}
+
+impl CreateFunctionCommand {
+    pub(crate) fn new(
+        function: Function,
+        or_replace: bool,
+        role: String,
+        storage: Arc<CatalogManager>,
+        session: Arc<dyn Sender>,
+    ) -> CreateFunctionCommand {
+        CreateFunctionCommand {
+            function,
+            or_replace,
+            role,
+            storage,
+            session,
+        }
+    }
+
+    /// Only owner of a function can replace it
+    pub(crate) fn execute(&mut self) -> SystemResult<()> {
+        let name = self.function.name.clone();
+        let functions = self.storage.functions();
+        let result = match functions.owner(&name) {
+            Some(owner) if self.or_replace && owner != self.role && self.role != SUPERUSER => {
+                Err(QueryError::must_be_owner("function", name))
+            }
+            _ if functions.create(self.function.clone(), &self.role, self.or_replace)? => {
+                Ok(QueryEvent::FunctionCreated)
+            }
+            _ => Err(QueryError::function_already_exists(name)),
+        };
+        self.session.send(result).map_err(SystemError::connection_lost)?;
+        Ok(())
+    }
+}
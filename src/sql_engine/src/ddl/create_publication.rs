@@ -0,0 +1,83 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::{
+    catalog_manager::CatalogManager,
+    query::{extended::PublicationTables, TableId, TableNamingError},
+};
+use kernel::{SystemError, SystemResult};
+use protocol::{
+    results::{QueryError, QueryEvent},
+    Sender,
+};
+use std::{convert::TryFrom, sync::Arc};
+
+pub(crate) struct CreatePublicationCommand {
+    name: String,
+    tables: PublicationTables,
+    storage: Arc<CatalogManager>,
+    session: Arc<dyn Sender>,
+}
+
+impl CreatePublicationCommand {
+    pub(crate) fn new(
+        name: String,
+        tables: PublicationTables,
+        storage: Arc<CatalogManager>,
+        session: Arc<dyn Sender>,
+    ) -> CreatePublicationCommand {
+        CreatePublicationCommand {
+            name,
+            tables,
+            storage,
+            session,
+        }
+    }
+
+    pub(crate) fn execute(&mut self) -> SystemResult<()> {
+        let tables = match &self.tables {
+            PublicationTables::All => None,
+            PublicationTables::Tables(names) => {
+                let mut tables = vec![];
+                for name in names {
+                    let table_id = match TableId::try_from(name.clone()) {
+                        Ok(table_id) => table_id,
+                        Err(TableNamingError(message)) => return self.send(Err(QueryError::syntax_error(message))),
+                    };
+                    let schema_name = table_id.schema_name();
+                    let table_name = table_id.name();
+                    match self.storage.table_exists(schema_name, table_name) {
+                        None => return self.send(Err(QueryError::schema_does_not_exist(schema_name.to_owned()))),
+                        Some((_, None)) => {
+                            return self.send(Err(QueryError::table_does_not_exist(
+                                schema_name.to_owned() + "." + table_name,
+                            )))
+                        }
+                        Some((_, Some(_))) => tables.push((schema_name.to_owned(), table_name.to_owned())),
+                    }
+                }
+                Some(tables)
+            }
+        };
+        if self.storage.publications().create(&self.name, tables)? {
+            self.send(Ok(QueryEvent::PublicationCreated))
+        } else {
+            self.send(Err(QueryError::publication_already_exists(self.name.clone())))
+        }
+    }
+
+    fn send(&self, result: Result<QueryEvent, QueryError>) -> SystemResult<()> {
+        self.session.send(result).map_err(SystemError::connection_lost)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,60 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::catalog_manager::{CatalogManager, SlotCreationError};
+use kernel::{SystemError, SystemResult};
+use protocol::{
+    results::{QueryError, QueryEvent},
+    Sender,
+};
+use std::sync::Arc;
+
+pub(crate) struct CreateReplicationSlotCommand {
+    name: String,
+    publication: String,
+    storage: Arc<CatalogManager>,
+    session: Arc<dyn Sender>,
+}
+
+impl CreateReplicationSlotCommand {
+    pub(crate) fn new(
+        name: String,
+        publication: String,
+        storage: Arc<CatalogManager>,
+        session: Arc<dyn Sender>,
+    ) -> CreateReplicationSlotCommand {
+        CreateReplicationSlotCommand {
+            name,
+            publication,
+            storage,
+            session,
+        }
+    }
+
+    /// Slot retains changes of tables of the publication that are made
+    /// after it is created
+    pub(crate) fn execute(&mut self) -> SystemResult<()> {
+        let result = match self.storage.publications().create_slot(&self.name, &self.publication)? {
+            Ok(()) => Ok(QueryEvent::ReplicationSlotCreated),
+            Err(SlotCreationError::SlotAlreadyExists) => {
+                Err(QueryError::replication_slot_already_exists(self.name.clone()))
+            }
+            Err(SlotCreationError::PublicationDoesNotExist) => {
+                Err(QueryError::publication_does_not_exist(self.publication.clone()))
+            }
+        };
+        self.session.send(result).map_err(SystemError::connection_lost)?;
+        Ok(())
+    }
+}
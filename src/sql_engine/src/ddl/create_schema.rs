@@ -13,12 +13,13 @@
 // limitations under the License.
 
 use crate::{catalog_manager::CatalogManager, query::plan::SchemaCreationInfo};
-use kernel::SystemResult;
+use kernel::{SystemError, SystemResult};
 use protocol::{results::QueryEvent, Sender};
 use std::sync::Arc;
 
 pub(crate) struct CreateSchemaCommand {
     schema_info: SchemaCreationInfo,
+    owner: String,
     storage: Arc<CatalogManager>,
     session: Arc<dyn Sender>,
 }
@@ -26,11 +27,13 @@ pub(crate) struct CreateSchemaCommand {
 impl CreateSchemaCommand {
     pub(crate) fn new(
         schema_info: SchemaCreationInfo,
+        owner: String,
         storage: Arc<CatalogManager>,
         session: Arc<dyn Sender>,
     ) -> CreateSchemaCommand {
         CreateSchemaCommand {
             schema_info,
+            owner,
             storage,
             session,
         }
@@ -41,9 +44,10 @@ impl CreateSchemaCommand {
         match self.storage.create_schema(schema_name) {
             Err(error) => Err(error),
             Ok(()) => {
+                self.storage.privileges().set_schema_owner(schema_name, &self.owner)?;
                 self.session
                     .send(Ok(QueryEvent::SchemaCreated))
-                    .expect("To Send Query Result to Client");
+                    .map_err(SystemError::connection_lost)?;
                 Ok(())
             }
         }
@@ -13,7 +13,7 @@
 // limitations under the License.
 
 use crate::{catalog_manager::CatalogManager, query::plan::TableCreationInfo};
-use kernel::SystemResult;
+use kernel::{SystemError, SystemResult};
 use protocol::{
     results::{QueryError, QueryEvent},
     Sender,
@@ -22,6 +22,7 @@ use std::sync::Arc;
 
 pub(crate) struct CreateTableCommand {
     table_info: TableCreationInfo,
+    owner: String,
     storage: Arc<CatalogManager>,
     session: Arc<dyn Sender>,
 }
@@ -29,11 +30,13 @@ pub(crate) struct CreateTableCommand {
 impl CreateTableCommand {
     pub(crate) fn new(
         table_info: TableCreationInfo,
+        owner: String,
         storage: Arc<CatalogManager>,
         session: Arc<dyn Sender>,
     ) -> CreateTableCommand {
         CreateTableCommand {
             table_info,
+            owner,
             storage,
             session,
         }
@@ -47,21 +50,28 @@ impl CreateTableCommand {
             None => self
                 .session
                 .send(Err(QueryError::schema_does_not_exist(schema_name.to_owned())))
-                .expect("To Send Query Result to Client"),
+                .map_err(SystemError::connection_lost)?,
             Some((_, Some(_))) => self
                 .session
                 .send(Err(QueryError::table_already_exists(table_name.to_owned())))
-                .expect("To Send Query Result to Client"),
+                .map_err(SystemError::connection_lost)?,
             Some((schema_id, None)) => {
                 match self
                     .storage
                     .create_table(schema_id, table_name, self.table_info.columns.as_slice())
                 {
                     Err(error) => return Err(error),
-                    Ok(()) => self
-                        .session
-                        .send(Ok(QueryEvent::TableCreated))
-                        .expect("To Send Query Result to Client"),
+                    Ok(()) => {
+                        self.storage
+                            .privileges()
+                            .set_table_owner(schema_name, table_name, &self.owner)?;
+                        self.storage
+                            .storage_options()
+                            .set(schema_name, table_name, self.table_info.autovacuum);
+                        self.session
+                            .send(Ok(QueryEvent::TableCreated))
+                            .map_err(SystemError::connection_lost)?
+                    }
                 }
             }
         }
@@ -0,0 +1,94 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    catalog_manager::CatalogManager,
+    ddl::create_function::CreateFunctionCommand,
+    procedural::{Body, Function, Returns, WasmFunction},
+    query::extended::WasmFunctionStatement,
+};
+use kernel::{SystemError, SystemResult};
+use protocol::{results::QueryError, Sender};
+use std::{fs, sync::Arc};
+
+pub(crate) struct CreateWasmFunctionCommand {
+    statement: WasmFunctionStatement,
+    or_replace: bool,
+    role: String,
+    storage: Arc<CatalogManager>,
+    session: Arc<dyn Sender>,
+}
+
+impl CreateWasmFunctionCommand {
+    pub(crate) fn new(
+        statement: WasmFunctionStatement,
+        or_replace: bool,
+        role: String,
+        storage: Arc<CatalogManager>,
+        session: Arc<dyn Sender>,
+    ) -> CreateWasmFunctionCommand {
+        CreateWasmFunctionCommand {
+            statement,
+            or_replace,
+            role,
+            storage,
+            session,
+        }
+    }
+
+    /// Module is compiled once, when the function is created. Later changes
+    /// of the file are not picked up until the function is replaced.
+    pub(crate) fn execute(&mut self) -> SystemResult<()> {
+        let WasmFunctionStatement {
+            name,
+            params,
+            returns,
+            file,
+            export,
+        } = self.statement.clone();
+        let code = match fs::read(&file) {
+            Ok(code) => code,
+            Err(error) => {
+                self.session
+                    .send(Err(QueryError::io_error(format!(
+                        "could not open file \"{}\" for reading: {}",
+                        file, error
+                    ))))
+                    .map_err(SystemError::connection_lost)?;
+                return Ok(());
+            }
+        };
+        let wasm = match WasmFunction::compile(file, export, &code, &params, returns) {
+            Ok(wasm) => wasm,
+            Err(error) => {
+                self.session.send(Err(error)).map_err(SystemError::connection_lost)?;
+                return Ok(());
+            }
+        };
+        let function = Function {
+            name,
+            params,
+            returns: Returns::Type(returns),
+            body: Body::Wasm(wasm),
+        };
+        CreateFunctionCommand::new(
+            function,
+            self.or_replace,
+            self.role.clone(),
+            self.storage.clone(),
+            self.session.clone(),
+        )
+        .execute()
+    }
+}
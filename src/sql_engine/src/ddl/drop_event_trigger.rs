@@ -0,0 +1,54 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::catalog_manager::CatalogManager;
+use kernel::{SystemError, SystemResult};
+use protocol::{
+    results::{QueryError, QueryEvent},
+    Sender,
+};
+use std::sync::Arc;
+
+pub(crate) struct DropEventTriggerCommand {
+    name: String,
+    if_exists: bool,
+    storage: Arc<CatalogManager>,
+    session: Arc<dyn Sender>,
+}
+
+impl DropEventTriggerCommand {
+    pub(crate) fn new(
+        name: String,
+        if_exists: bool,
+        storage: Arc<CatalogManager>,
+        session: Arc<dyn Sender>,
+    ) -> DropEventTriggerCommand {
+        DropEventTriggerCommand {
+            name,
+            if_exists,
+            storage,
+            session,
+        }
+    }
+
+    pub(crate) fn execute(&mut self) -> SystemResult<()> {
+        let result = if self.storage.event_triggers().drop(&self.name)? || self.if_exists {
+            Ok(QueryEvent::EventTriggerDropped)
+        } else {
+            Err(QueryError::event_trigger_does_not_exist(self.name.clone()))
+        };
+        self.session.send(result).map_err(SystemError::connection_lost)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,62 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::catalog_manager::{CatalogManager, SUPERUSER};
+use kernel::{SystemError, SystemResult};
+use protocol::{
+    results::{QueryError, QueryEvent},
+    Sender,
+};
+use std::sync::Arc;
+
+pub(crate) struct DropFunctionCommand {
+    name: String,
+    if_exists: bool,
+    role: String,
+    storage: Arc<CatalogManager>,
+    session: Arc<dyn Sender>,
+}
+
+impl DropFunctionCommand {
+    pub(crate) fn new(
+        name: String,
+        if_exists: bool,
+        role: String,
+        storage: Arc<CatalogManager>,
+        session: Arc<dyn Sender>,
+    ) -> DropFunctionCommand {
+        DropFunctionCommand {
+            name,
+            if_exists,
+            role,
+            storage,
+            session,
+        }
+    }
+
+    /// Only owner of a function can drop it
+    pub(crate) fn execute(&mut self) -> SystemResult<()> {
+        let functions = self.storage.functions();
+        let result = match functions.owner(&self.name) {
+            Some(owner) if owner != self.role && self.role != SUPERUSER => {
+                Err(QueryError::must_be_owner("function", self.name.clone()))
+            }
+            Some(_) if functions.drop(&self.name)? => Ok(QueryEvent::FunctionDropped),
+            _ if self.if_exists => Ok(QueryEvent::FunctionDropped),
+            _ => Err(QueryError::function_does_not_exist(self.name.clone())),
+        };
+        self.session.send(result).map_err(SystemError::connection_lost)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,54 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::catalog_manager::CatalogManager;
+use kernel::{SystemError, SystemResult};
+use protocol::{
+    results::{QueryError, QueryEvent},
+    Sender,
+};
+use std::sync::Arc;
+
+pub(crate) struct DropPublicationCommand {
+    name: String,
+    if_exists: bool,
+    storage: Arc<CatalogManager>,
+    session: Arc<dyn Sender>,
+}
+
+impl DropPublicationCommand {
+    pub(crate) fn new(
+        name: String,
+        if_exists: bool,
+        storage: Arc<CatalogManager>,
+        session: Arc<dyn Sender>,
+    ) -> DropPublicationCommand {
+        DropPublicationCommand {
+            name,
+            if_exists,
+            storage,
+            session,
+        }
+    }
+
+    /// Streams of subscribers of the publication end
+    pub(crate) fn execute(&mut self) -> SystemResult<()> {
+        let result = if self.storage.publications().drop(&self.name)? || self.if_exists {
+            Ok(QueryEvent::PublicationDropped)
+        } else {
+            Err(QueryError::publication_does_not_exist(self.name.clone()))
+        };
+        self.session.send(result).map_err(SystemError::connection_lost)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,55 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::catalog_manager::CatalogManager;
+use kernel::{SystemError, SystemResult};
+use protocol::{
+    results::{QueryError, QueryEvent},
+    Sender,
+};
+use std::sync::Arc;
+
+pub(crate) struct DropReplicationSlotCommand {
+    name: String,
+    if_exists: bool,
+    storage: Arc<CatalogManager>,
+    session: Arc<dyn Sender>,
+}
+
+impl DropReplicationSlotCommand {
+    pub(crate) fn new(
+        name: String,
+        if_exists: bool,
+        storage: Arc<CatalogManager>,
+        session: Arc<dyn Sender>,
+    ) -> DropReplicationSlotCommand {
+        DropReplicationSlotCommand {
+            name,
+            if_exists,
+            storage,
+            session,
+        }
+    }
+
+    /// Retained changes are discarded and the stream of the slot ends
+    pub(crate) fn execute(&mut self) -> SystemResult<()> {
+        let result = if self.storage.publications().drop_slot(&self.name)? || self.if_exists {
+            Ok(QueryEvent::ReplicationSlotDropped)
+        } else {
+            Err(QueryError::replication_slot_does_not_exist(self.name.clone()))
+        };
+        self.session.send(result).map_err(SystemError::connection_lost)?;
+        Ok(())
+    }
+}
@@ -13,10 +13,10 @@
 // limitations under the License.
 
 use crate::{
-    catalog_manager::{CatalogManager, DropSchemaError, DropStrategy},
+    catalog_manager::{CatalogManager, CatalogObject, DropSchemaError, DropStrategy},
     query::SchemaId,
 };
-use kernel::SystemResult;
+use kernel::{SystemError, SystemResult};
 use protocol::{
     results::{QueryError, QueryEvent},
     Sender,
@@ -56,7 +56,7 @@ impl DropSchemaCommand {
             None => {
                 self.session
                     .send(Err(QueryError::schema_does_not_exist(schema_name)))
-                    .expect("To Send Query Result to Client");
+                    .map_err(SystemError::connection_lost)?;
                 Ok(())
             }
             Some(schema_id) => {
@@ -67,21 +67,30 @@ impl DropSchemaCommand {
                         Ok(())
                     }
                     Ok(Err(DropSchemaError::HasDependentObjects)) => {
+                        let detail = self
+                            .storage
+                            .dependents(&CatalogObject::Schema(schema_name.clone()))
+                            .iter()
+                            .map(|dependent| format!("{} depends on schema {}", dependent, schema_name))
+                            .collect::<Vec<String>>()
+                            .join("\n");
                         self.session
-                            .send(Err(QueryError::schema_has_dependent_objects(schema_name)))
-                            .expect("To Send Query Result to Client");
+                            .send(Err(
+                                QueryError::schema_has_dependent_objects(schema_name).with_detail(detail)
+                            ))
+                            .map_err(SystemError::connection_lost)?;
                         Ok(())
                     }
                     Ok(Err(DropSchemaError::DoesNotExist)) => {
                         self.session
                             .send(Err(QueryError::schema_does_not_exist(schema_name)))
-                            .expect("To Send Query Result to Client");
+                            .map_err(SystemError::connection_lost)?;
                         Ok(())
                     }
                     Ok(Ok(())) => {
                         self.session
                             .send(Ok(QueryEvent::SchemaDropped))
-                            .expect("To Send Query Result to Client");
+                            .map_err(SystemError::connection_lost)?;
                         Ok(())
                     }
                 }
@@ -13,7 +13,7 @@
 // limitations under the License.
 
 use crate::{catalog_manager::CatalogManager, query::TableId};
-use kernel::SystemResult;
+use kernel::{SystemError, SystemResult};
 use protocol::{results::QueryEvent, Sender};
 use std::sync::Arc;
 
@@ -36,7 +36,7 @@ impl DropTableCommand {
             Ok(()) => {
                 self.session
                     .send(Ok(QueryEvent::TableDropped))
-                    .expect("To Send Query Result to Client");
+                    .map_err(SystemError::connection_lost)?;
                 Ok(())
             }
         }
@@ -12,7 +12,20 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub(crate) mod alter_table;
+pub(crate) mod cluster_table;
+pub(crate) mod create_aggregate;
+pub(crate) mod create_event_trigger;
+pub(crate) mod create_foreign_table;
+pub(crate) mod create_function;
+pub(crate) mod create_publication;
+pub(crate) mod create_replication_slot;
 pub(crate) mod create_schema;
 pub(crate) mod create_table;
+pub(crate) mod create_wasm_function;
+pub(crate) mod drop_event_trigger;
+pub(crate) mod drop_function;
+pub(crate) mod drop_publication;
+pub(crate) mod drop_replication_slot;
 pub(crate) mod drop_schema;
 pub(crate) mod drop_table;
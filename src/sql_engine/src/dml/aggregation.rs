@@ -0,0 +1,377 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Aggregates of the select list. `count`, `sum`, `avg`, `min` and `max`
+//! are built in, other aggregates are created with `CREATE AGGREGATE` and
+//! fold values with their transition function.
+use crate::{
+    dml::functions::ScalarFunctions,
+    procedural::{Aggregate, Value},
+};
+use protocol::{results::QueryError, sql_types::PostgreSqlType};
+use representation::Datum;
+use sql_types::SqlType;
+use std::sync::Arc;
+
+/// Number of fractional digits of averages of integers
+const AVG_SCALE: u32 = 16;
+
+/// Aggregate called in the select list
+#[derive(Debug, Clone)]
+pub(crate) enum Aggregation {
+    /// number of rows with a value, of all rows without an argument
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+    Defined(Arc<Aggregate>),
+}
+
+/// State of an aggregate for a group of rows
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum State {
+    Count(i64),
+    Sum(Sum),
+    Avg(Sum, i64),
+    /// the smallest or the largest value so far
+    Extreme(Option<Datum<'static>>),
+    Defined(Value),
+}
+
+/// Sum of values of the type of the aggregated column
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Sum {
+    Empty,
+    Integer(i128),
+    Real(f64),
+    Double(f64),
+}
+
+impl Aggregation {
+    /// Built in aggregate with the name
+    pub(crate) fn builtin(name: &str) -> Option<Aggregation> {
+        match name {
+            "count" => Some(Aggregation::Count),
+            "sum" => Some(Aggregation::Sum),
+            "avg" => Some(Aggregation::Avg),
+            "min" => Some(Aggregation::Min),
+            "max" => Some(Aggregation::Max),
+            _ => None,
+        }
+    }
+
+    /// Name of the result column
+    pub(crate) fn name(&self) -> String {
+        match self {
+            Aggregation::Count => "count".to_owned(),
+            Aggregation::Sum => "sum".to_owned(),
+            Aggregation::Avg => "avg".to_owned(),
+            Aggregation::Min => "min".to_owned(),
+            Aggregation::Max => "max".to_owned(),
+            Aggregation::Defined(aggregate) => aggregate.name.clone(),
+        }
+    }
+
+    /// Type of the result for values of `input` type, `None` for `*`.
+    /// Errors if the aggregate can't take them.
+    pub(crate) fn returns(&self, input: Option<&SqlType>) -> Result<SqlType, QueryError> {
+        let input = match (self, input) {
+            (Aggregation::Count, _) => return Ok(SqlType::BigInt(i64::MIN)),
+            (_, None) => return Err(QueryError::function_does_not_exist(format!("{}(*)", self.name()))),
+            (_, Some(input)) => input,
+        };
+        match (self, input) {
+            (Aggregation::Sum, SqlType::SmallInt(_)) | (Aggregation::Sum, SqlType::Integer(_)) => {
+                Ok(SqlType::BigInt(i64::MIN))
+            }
+            (Aggregation::Sum, SqlType::BigInt(_)) | (Aggregation::Sum, SqlType::Decimal) => Ok(SqlType::Decimal),
+            (Aggregation::Sum, SqlType::Real) => Ok(SqlType::Real),
+            (Aggregation::Sum, SqlType::DoublePrecision) => Ok(SqlType::DoublePrecision),
+            (Aggregation::Avg, SqlType::SmallInt(_))
+            | (Aggregation::Avg, SqlType::Integer(_))
+            | (Aggregation::Avg, SqlType::BigInt(_))
+            | (Aggregation::Avg, SqlType::Decimal) => Ok(SqlType::Decimal),
+            (Aggregation::Avg, SqlType::Real) | (Aggregation::Avg, SqlType::DoublePrecision) => {
+                Ok(SqlType::DoublePrecision)
+            }
+            (Aggregation::Min, input) | (Aggregation::Max, input) => Ok(*input),
+            (Aggregation::Defined(aggregate), _) => Ok(column_type(aggregate.returns())),
+            (aggregation, input) => {
+                let input: &'static str = input.into();
+                Err(QueryError::function_does_not_exist(format!(
+                    "{}({})",
+                    aggregation.name(),
+                    input
+                )))
+            }
+        }
+    }
+
+    /// State of a group before any value is accumulated
+    pub(crate) fn start(&self) -> Result<State, QueryError> {
+        match self {
+            Aggregation::Count => Ok(State::Count(0)),
+            Aggregation::Sum => Ok(State::Sum(Sum::Empty)),
+            Aggregation::Avg => Ok(State::Avg(Sum::Empty, 0)),
+            Aggregation::Min | Aggregation::Max => Ok(State::Extreme(None)),
+            Aggregation::Defined(aggregate) => aggregate.start().map(State::Defined),
+        }
+    }
+
+    /// Accumulates a value into the state of a group, `NULL` values are
+    /// skipped. Defined aggregates call their functions with `functions` of
+    /// the statement.
+    pub(crate) fn accumulate(
+        &self,
+        state: State,
+        datum: &Datum,
+        functions: &ScalarFunctions,
+    ) -> Result<State, QueryError> {
+        match (self, state) {
+            (Aggregation::Defined(aggregate), State::Defined(state)) => {
+                aggregate.accumulate(state, value(datum), functions).map(State::Defined)
+            }
+            (_, state) if *datum == Datum::Null => Ok(state),
+            (Aggregation::Count, State::Count(count)) => Ok(State::Count(count + 1)),
+            (Aggregation::Sum, State::Sum(sum)) => Ok(State::Sum(sum.add(datum))),
+            (Aggregation::Avg, State::Avg(sum, count)) => Ok(State::Avg(sum.add(datum), count + 1)),
+            (Aggregation::Min, State::Extreme(Some(current))) if current <= owned(datum) => {
+                Ok(State::Extreme(Some(current)))
+            }
+            (Aggregation::Max, State::Extreme(Some(current))) if current >= owned(datum) => {
+                Ok(State::Extreme(Some(current)))
+            }
+            (Aggregation::Min, State::Extreme(_)) | (Aggregation::Max, State::Extreme(_)) => {
+                Ok(State::Extreme(Some(owned(datum))))
+            }
+            (_, state) => Ok(state),
+        }
+    }
+
+    /// Text representation of the result of a group
+    pub(crate) fn finish(&self, state: State, functions: &ScalarFunctions) -> Result<Option<String>, QueryError> {
+        match (self, state) {
+            (Aggregation::Defined(aggregate), State::Defined(state)) => {
+                aggregate.finish(state, functions).map(|result| result.to_field())
+            }
+            (_, State::Count(count)) => Ok(Some(count.to_string())),
+            (_, State::Sum(sum)) => Ok(sum.to_field()),
+            (_, State::Avg(Sum::Integer(sum), count)) => Ok(Some(divide(sum, count, AVG_SCALE))),
+            (_, State::Avg(Sum::Real(sum), count)) | (_, State::Avg(Sum::Double(sum), count)) => {
+                Ok(Datum::from_f64(sum / count as f64).to_field())
+            }
+            (_, State::Avg(Sum::Empty, _)) => Ok(None),
+            (_, State::Extreme(extreme)) => Ok(extreme.and_then(|datum| datum.to_field())),
+            (_, State::Defined(state)) => Ok(state.to_field()),
+        }
+    }
+}
+
+impl Sum {
+    fn add(self, datum: &Datum) -> Sum {
+        match (self, datum) {
+            (Sum::Empty, Datum::Float32(value)) => Sum::Real(f64::from(value.into_inner())),
+            (Sum::Empty, Datum::Float64(value)) => Sum::Double(value.into_inner()),
+            (Sum::Empty, datum) => Sum::Integer(0).add(datum),
+            (Sum::Integer(sum), Datum::Int16(value)) => Sum::Integer(sum + i128::from(*value)),
+            (Sum::Integer(sum), Datum::Int32(value)) => Sum::Integer(sum + i128::from(*value)),
+            (Sum::Integer(sum), Datum::Int64(value)) => Sum::Integer(sum + i128::from(*value)),
+            (Sum::Integer(sum), Datum::UInt64(value)) => Sum::Integer(sum + i128::from(*value)),
+            (Sum::Real(sum), Datum::Float32(value)) => Sum::Real(sum + f64::from(value.into_inner())),
+            (Sum::Double(sum), Datum::Float64(value)) => Sum::Double(sum + value.into_inner()),
+            (sum, _) => sum,
+        }
+    }
+
+    fn to_field(self) -> Option<String> {
+        match self {
+            Sum::Empty => None,
+            Sum::Integer(sum) => Some(sum.to_string()),
+            Sum::Real(sum) => Datum::from_f32(sum as f32).to_field(),
+            Sum::Double(sum) => Datum::from_f64(sum).to_field(),
+        }
+    }
+}
+
+/// `sum / count` rounded half away from zero to `scale` fractional digits
+fn divide(sum: i128, count: i64, scale: u32) -> String {
+    let count = count as u128;
+    let mut integer = sum.unsigned_abs() / count;
+    let mut remainder = sum.unsigned_abs() % count;
+    let mut fraction = 0;
+    for _ in 0..scale {
+        remainder *= 10;
+        fraction = fraction * 10 + remainder / count;
+        remainder %= count;
+    }
+    if remainder * 2 >= count {
+        fraction += 1;
+        if fraction == 10u128.pow(scale) {
+            fraction = 0;
+            integer += 1;
+        }
+    }
+    let sign = if sum < 0 && (integer > 0 || fraction > 0) {
+        "-"
+    } else {
+        ""
+    };
+    format!("{}{}.{:0scale$}", sign, integer, fraction, scale = scale as usize)
+}
+
+/// Type of results of created aggregates and of parameters and results of
+/// created functions, text has no length limit
+pub(crate) fn column_type(pg_type: PostgreSqlType) -> SqlType {
+    match pg_type {
+        PostgreSqlType::Bool => SqlType::Bool,
+        PostgreSqlType::Char => SqlType::Char(u64::MAX),
+        PostgreSqlType::VarChar => SqlType::VarChar(u64::MAX),
+        PostgreSqlType::Decimal => SqlType::Decimal,
+        PostgreSqlType::SmallInt => SqlType::SmallInt(i16::MIN),
+        PostgreSqlType::Integer => SqlType::Integer(i32::MIN),
+        PostgreSqlType::BigInt => SqlType::BigInt(i64::MIN),
+        PostgreSqlType::Real => SqlType::Real,
+        PostgreSqlType::DoublePrecision => SqlType::DoublePrecision,
+        PostgreSqlType::Time => SqlType::Time,
+        PostgreSqlType::TimeWithTimeZone => SqlType::TimeWithTimeZone,
+        PostgreSqlType::Timestamp => SqlType::Timestamp,
+        PostgreSqlType::TimestampWithTimeZone => SqlType::TimestampWithTimeZone,
+        PostgreSqlType::Date => SqlType::Date,
+        PostgreSqlType::Interval => SqlType::Interval,
+        PostgreSqlType::TsVector => SqlType::TsVector,
+        PostgreSqlType::TsQuery => SqlType::TsQuery,
+        PostgreSqlType::Void => SqlType::Void,
+    }
+}
+
+/// Copy of a value that outlives the row it is read from
+fn owned(datum: &Datum) -> Datum<'static> {
+    match datum {
+        Datum::Null => Datum::Null,
+        Datum::True => Datum::True,
+        Datum::False => Datum::False,
+        Datum::Int16(value) => Datum::Int16(*value),
+        Datum::Int32(value) => Datum::Int32(*value),
+        Datum::Int64(value) => Datum::Int64(*value),
+        Datum::UInt64(value) => Datum::UInt64(*value),
+        Datum::Float32(value) => Datum::Float32(*value),
+        Datum::Float64(value) => Datum::Float64(*value),
+        Datum::String(value) => Datum::OwnedString((*value).to_owned()),
+        Datum::OwnedString(value) => Datum::OwnedString(value.clone()),
+        Datum::SqlType(sql_type) => Datum::SqlType(*sql_type),
+    }
+}
+
+/// Argument of transition functions of created aggregates
+fn value(datum: &Datum) -> Value {
+    match datum {
+        Datum::Null => Value::Null,
+        Datum::True => Value::Bool(true),
+        Datum::False => Value::Bool(false),
+        Datum::Int16(value) => Value::Integer(i64::from(*value)),
+        Datum::Int32(value) => Value::Integer(i64::from(*value)),
+        Datum::Int64(value) => Value::Integer(*value),
+        other => Value::Text(other.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aggregate(aggregation: Aggregation, values: &[Datum]) -> Option<String> {
+        let state = values
+            .iter()
+            .fold(aggregation.start().expect("initial state"), |state, datum| {
+                aggregation
+                    .accumulate(state, datum, &ScalarFunctions::default())
+                    .expect("accumulated value")
+            });
+        aggregation.finish(state, &ScalarFunctions::default()).expect("result")
+    }
+
+    #[test]
+    fn count_skips_nulls() {
+        assert_eq!(
+            aggregate(
+                Aggregation::Count,
+                &[Datum::from_i32(1), Datum::from_null(), Datum::from_i32(2)]
+            ),
+            Some("2".to_owned())
+        );
+        assert_eq!(aggregate(Aggregation::Count, &[]), Some("0".to_owned()));
+    }
+
+    #[test]
+    fn sum_of_integers_does_not_overflow() {
+        assert_eq!(
+            aggregate(
+                Aggregation::Sum,
+                &[Datum::from_i64(i64::MAX), Datum::from_i64(i64::MAX)]
+            ),
+            Some("18446744073709551614".to_owned())
+        );
+        assert_eq!(aggregate(Aggregation::Sum, &[Datum::from_null()]), None);
+    }
+
+    #[test]
+    fn avg_of_integers_is_rounded() {
+        assert_eq!(
+            aggregate(
+                Aggregation::Avg,
+                &[Datum::from_i16(1), Datum::from_i16(1), Datum::from_i16(0)]
+            ),
+            Some("0.6666666666666667".to_owned())
+        );
+        assert_eq!(
+            aggregate(Aggregation::Avg, &[Datum::from_i16(-1), Datum::from_i16(-2)]),
+            Some("-1.5000000000000000".to_owned())
+        );
+        assert_eq!(aggregate(Aggregation::Avg, &[]), None);
+    }
+
+    #[test]
+    fn min_and_max() {
+        let values = [
+            Datum::from_str("b"),
+            Datum::from_null(),
+            Datum::from_str("c"),
+            Datum::from_str("a"),
+        ];
+        assert_eq!(aggregate(Aggregation::Min, &values), Some("a".to_owned()));
+        assert_eq!(aggregate(Aggregation::Max, &values), Some("c".to_owned()));
+    }
+
+    #[test]
+    fn result_types() {
+        assert_eq!(
+            Aggregation::Sum.returns(Some(&SqlType::Integer(i32::MIN))),
+            Ok(SqlType::BigInt(i64::MIN))
+        );
+        assert_eq!(
+            Aggregation::Avg.returns(Some(&SqlType::Real)),
+            Ok(SqlType::DoublePrecision)
+        );
+        assert_eq!(Aggregation::Count.returns(None), Ok(SqlType::BigInt(i64::MIN)));
+        assert_eq!(
+            Aggregation::Max.returns(None),
+            Err(QueryError::function_does_not_exist("max(*)".to_owned()))
+        );
+        assert_eq!(
+            Aggregation::Avg.returns(Some(&SqlType::VarChar(10))),
+            Err(QueryError::function_does_not_exist("avg(varchar)".to_owned()))
+        );
+    }
+}
@@ -0,0 +1,799 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Batch-at-a-time processing of table rows. Rows are read from storage in
+//! batches of [`BATCH_SIZE`] and each row is decoded once into columns that
+//! operators process as a whole.
+//!
+//! Projection and grouping aggregation use up to `max_parallel_workers`
+//! threads besides the session one. Joins are nested loops over the
+//! materialized inner relation and run on the session thread only.
+//! Groups that don't fit into `work_mem` are spilled to temporary files.
+use crate::dml::memory::{text_rows_size, WorkMem};
+use protocol::{messages::EncodedRows, results::QueryError};
+use representation::{unpack_raw, Datum};
+use std::{
+    collections::hash_map::DefaultHasher,
+    fs::File,
+    hash::{Hash, Hasher},
+    io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    thread,
+};
+use storage::{Key, ReadCursor, Row, Values};
+
+/// Maximum number of rows in a batch
+pub(crate) const BATCH_SIZE: usize = 1024;
+
+/// Rows read from storage at once
+pub(crate) struct Batch {
+    keys: Vec<Key>,
+    values: Vec<Values>,
+}
+
+impl Batch {
+    pub(crate) fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub(crate) fn keys(self) -> Vec<Key> {
+        self.keys
+    }
+
+    /// Rows of the batch as they are stored
+    pub(crate) fn stored(&self) -> impl Iterator<Item = Row> + '_ {
+        self.keys.iter().cloned().zip(self.values.iter().cloned())
+    }
+
+    /// Decodes rows of the batch and returns values of columns at `indexes`,
+    /// a column can be requested more than once
+    pub(crate) fn columns(&self, indexes: &[usize]) -> Vec<Vec<Datum<'_>>> {
+        let mut columns = vec![Vec::with_capacity(self.len()); indexes.len()];
+        for values in &self.values {
+            let row = unpack_raw(values.to_bytes());
+            for (column, index) in columns.iter_mut().zip(indexes) {
+                column.push(row[*index].clone());
+            }
+        }
+        columns
+    }
+
+    /// Decodes all values of rows of the batch
+    pub(crate) fn rows(&self) -> Vec<Vec<Datum<'_>>> {
+        self.values.iter().map(|values| unpack_raw(values.to_bytes())).collect()
+    }
+
+    /// Decodes rows of the batch and replaces values of columns at indexes
+    /// from `assignments`
+    pub(crate) fn assign(self, assignments: &[(usize, Datum)]) -> Vec<(Key, Values)> {
+        self.keys
+            .into_iter()
+            .zip(self.values)
+            .map(|(key, values)| {
+                let mut row = unpack_raw(values.to_bytes());
+                for (index, datum) in assignments {
+                    row[*index] = datum.clone();
+                }
+                (key, Values::pack(&row))
+            })
+            .collect()
+    }
+}
+
+/// Splits rows of a table scan into batches
+pub(crate) struct Batches {
+    cursor: ReadCursor,
+}
+
+impl Batches {
+    pub(crate) fn new(cursor: ReadCursor) -> Batches {
+        Batches { cursor }
+    }
+}
+
+impl Iterator for Batches {
+    type Item = Batch;
+
+    fn next(&mut self) -> Option<Batch> {
+        let mut keys = Vec::with_capacity(BATCH_SIZE);
+        let mut values = Vec::with_capacity(BATCH_SIZE);
+        for (key, value) in self
+            .cursor
+            .by_ref()
+            .take(BATCH_SIZE)
+            .map(Result::unwrap)
+            .map(Result::unwrap)
+        {
+            keys.push(key);
+            values.push(value);
+        }
+        if keys.is_empty() {
+            None
+        } else {
+            Some(Batch { keys, values })
+        }
+    }
+}
+
+/// Reasons why projection stops before all batches are emitted
+#[derive(Debug, PartialEq)]
+pub(crate) enum Interrupted<E> {
+    OutOfMemory,
+    Emit(E),
+    /// a value of a row can't be computed
+    Failed(QueryError),
+}
+
+/// Projects columns at `indexes` of all batches into rows of their text
+/// representation and passes them to `emit` batch by batch. Besides the
+/// current thread up to `workers` threads decode batches at once, their
+/// results are emitted in the order of batches. Rows of batches that are
+/// decoded at once can't exceed `work_mem`. Returns number of emitted rows.
+pub(crate) fn project<E, F: FnMut(Vec<Vec<Option<String>>>) -> Result<(), E>>(
+    batches: Batches,
+    indexes: &[usize],
+    workers: usize,
+    work_mem: &mut WorkMem,
+    emit: F,
+) -> Result<usize, Interrupted<E>> {
+    project_rows(
+        batches,
+        |batch| Ok(to_text_rows(&batch.columns(indexes))),
+        workers,
+        work_mem,
+        emit,
+    )
+}
+
+/// Projects columns at `indexes` of all batches like [`project`] does, but
+/// values are encoded into data row messages as they are decoded, so that
+/// no text of a value is allocated on its own
+pub(crate) fn project_encoded<E, F: FnMut(EncodedRows) -> Result<(), E>>(
+    batches: Batches,
+    indexes: &[usize],
+    workers: usize,
+    work_mem: &mut WorkMem,
+    emit: F,
+) -> Result<usize, Interrupted<E>> {
+    project_rows(
+        batches,
+        |batch| Ok(encode(&batch.columns(indexes))),
+        workers,
+        work_mem,
+        emit,
+    )
+}
+
+/// Rows of a projected batch
+pub(crate) trait Projected: Send {
+    fn rows(&self) -> usize;
+
+    /// Bytes that rows take in memory
+    fn size(&self) -> usize;
+}
+
+impl Projected for Vec<Vec<Option<String>>> {
+    fn rows(&self) -> usize {
+        self.len()
+    }
+
+    fn size(&self) -> usize {
+        text_rows_size(self)
+    }
+}
+
+impl Projected for EncodedRows {
+    fn rows(&self) -> usize {
+        self.len()
+    }
+
+    fn size(&self) -> usize {
+        self.as_bytes().len()
+    }
+}
+
+/// Projects rows of all batches with `rows` like [`project`] does, e.g.
+/// to compute values of rows
+pub(crate) fn project_rows<E, P, R, F>(
+    mut batches: Batches,
+    rows: R,
+    workers: usize,
+    work_mem: &mut WorkMem,
+    mut emit: F,
+) -> Result<usize, Interrupted<E>>
+where
+    P: Projected,
+    R: Fn(&Batch) -> Result<P, QueryError> + Sync,
+    F: FnMut(P) -> Result<(), E>,
+{
+    let mut emitted = 0;
+    while let Some(first) = batches.next() {
+        let rest = batches.by_ref().take(workers).collect::<Vec<Batch>>();
+        let rows = &rows;
+        let parts = thread::scope(|scope| {
+            let handles = rest
+                .iter()
+                .map(|batch| scope.spawn(move || rows(batch)))
+                .collect::<Vec<_>>();
+            let mut parts = vec![rows(&first)];
+            parts.extend(
+                handles
+                    .into_iter()
+                    .map(|handle| handle.join().expect("projection worker not to panic")),
+            );
+            parts
+        })
+        .into_iter()
+        .collect::<Result<Vec<_>, QueryError>>()
+        .map_err(Interrupted::Failed)?;
+        let size = parts.iter().map(Projected::size).sum();
+        work_mem.allocate(size).map_err(|()| Interrupted::OutOfMemory)?;
+        for part in parts {
+            emitted += part.rows();
+            emit(part).map_err(Interrupted::Emit)?;
+        }
+        work_mem.free(size);
+    }
+    Ok(emitted)
+}
+
+/// Encodes columns into data row messages, NULLs are encoded as such
+pub(crate) fn encode(columns: &[Vec<Datum>]) -> EncodedRows {
+    let len = columns.first().map(Vec::len).unwrap_or_default();
+    let values = columns.iter().flatten().map(Datum::size).sum::<usize>();
+    let mut rows = EncodedRows::with_capacity(len * (7 + 4 * columns.len()) + values);
+    for row in 0..len {
+        rows.push_row(columns.len(), |field, buffer| columns[field][row].write_field(buffer));
+    }
+    rows
+}
+
+/// Row of a partition with its number in the scan and its grouping key
+type PartitionedRow<'b, G> = (usize, G, Vec<Datum<'b>>);
+
+/// Hash table of groups that [`aggregate`] folds rows of a partition into
+pub(crate) trait GroupTable<G>: Default + Send {
+    /// Whether rows with `key` are already folded into the table
+    fn contains(&self, key: &G) -> bool;
+
+    /// Approximate number of bytes that the table takes in memory
+    fn size(&self) -> usize;
+}
+
+/// Folds rows of all batches into `workers + 1` partitions by the hash of
+/// their `key`, so that rows with the same key end up in the same partition
+/// and partitions don't have to be merged. Besides the current thread up to
+/// `workers` threads decode batches at once, then as many threads `fold`
+/// rows of a partition each. Rows of a partition are folded in the order of
+/// the scan, `fold` gets the number of a row in the scan.
+///
+/// Group tables of all partitions share `budget` bytes. Once the table of a
+/// partition exceeds its share, rows of groups that it doesn't have yet are
+/// written to a temporary file and are folded into a new table after the
+/// scan. Each table is turned into its result by `finish` as soon as it is
+/// complete. Returns results of all tables and number of folded rows.
+pub(crate) fn aggregate<G, T, R, K, F, E>(
+    mut batches: Batches,
+    workers: usize,
+    budget: usize,
+    key: K,
+    fold: F,
+    finish: E,
+) -> Result<(Vec<R>, usize), QueryError>
+where
+    G: Hash + Send,
+    T: GroupTable<G>,
+    R: Send,
+    K: Fn(&[Datum]) -> G + Sync,
+    F: Fn(&mut T, G, &[Datum], usize) -> Result<(), QueryError> + Sync,
+    E: Fn(T) -> Result<R, QueryError> + Sync,
+{
+    let mut partitions = (0..=workers)
+        .map(|_| (T::default(), None))
+        .collect::<Vec<(T, Option<Spill>)>>();
+    let budget = (budget / partitions.len()).max(1);
+    let mut folded = 0;
+    while let Some(first) = batches.next() {
+        let mut chunk = vec![first];
+        chunk.extend(batches.by_ref().take(workers));
+        let (key, fold, count) = (&key, &fold, partitions.len());
+        let mut offsets = Vec::with_capacity(chunk.len());
+        for batch in &chunk {
+            offsets.push(folded);
+            folded += batch.len();
+        }
+        let split = thread::scope(|scope| {
+            let handles = chunk
+                .iter()
+                .zip(&offsets)
+                .skip(1)
+                .map(|(batch, offset)| scope.spawn(move || partition(batch, *offset, count, key)))
+                .collect::<Vec<_>>();
+            let mut split = vec![partition(&chunk[0], offsets[0], count, key)];
+            split.extend(
+                handles
+                    .into_iter()
+                    .map(|handle| handle.join().expect("aggregation worker not to panic")),
+            );
+            split
+        });
+        let mut rows = (0..count).map(|_| vec![]).collect::<Vec<Vec<PartitionedRow<G>>>>();
+        for batch in split {
+            for (partition, batch_rows) in rows.iter_mut().zip(batch) {
+                partition.extend(batch_rows);
+            }
+        }
+        thread::scope(|scope| {
+            let mut tables = partitions.iter_mut().zip(rows);
+            let ((first_table, first_spill), first_rows) = tables.next().expect("at least one partition");
+            let handles = tables
+                .map(|((table, spill), rows)| scope.spawn(move || fold_rows(table, spill, rows, budget, fold)))
+                .collect::<Vec<_>>();
+            let mut folded = vec![fold_rows(first_table, first_spill, first_rows, budget, fold)];
+            folded.extend(
+                handles
+                    .into_iter()
+                    .map(|handle| handle.join().expect("aggregation worker not to panic")),
+            );
+            folded
+        })
+        .into_iter()
+        .collect::<Result<(), QueryError>>()?;
+    }
+    let (key, fold, finish) = (&key, &fold, &finish);
+    let results = thread::scope(|scope| {
+        let mut partitions = partitions.into_iter();
+        let (first_table, first_spill) = partitions.next().expect("at least one partition");
+        let handles = partitions
+            .map(|(table, spill)| scope.spawn(move || fold_spilled(table, spill, budget, key, fold, finish)))
+            .collect::<Vec<_>>();
+        let mut results = vec![fold_spilled(first_table, first_spill, budget, key, fold, finish)];
+        results.extend(
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("aggregation worker not to panic")),
+        );
+        results
+    })
+    .into_iter()
+    .collect::<Result<Vec<Vec<R>>, QueryError>>()?;
+    Ok((results.into_iter().flatten().collect(), folded))
+}
+
+/// Decodes rows of `batch` and splits them into `count` partitions by the
+/// hash of their key
+fn partition<'b, G: Hash, K: Fn(&[Datum]) -> G>(
+    batch: &'b Batch,
+    offset: usize,
+    count: usize,
+    key: &K,
+) -> Vec<Vec<PartitionedRow<'b, G>>> {
+    let mut partitions = (0..count).map(|_| vec![]).collect::<Vec<Vec<_>>>();
+    for (number, row) in batch.rows().into_iter().enumerate() {
+        let key = key(&row);
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        partitions[hasher.finish() as usize % count].push((offset + number, key, row));
+    }
+    partitions
+}
+
+fn fold_rows<G, T: GroupTable<G>, F: Fn(&mut T, G, &[Datum], usize) -> Result<(), QueryError>>(
+    table: &mut T,
+    spill: &mut Option<Spill>,
+    rows: Vec<PartitionedRow<G>>,
+    budget: usize,
+    fold: &F,
+) -> Result<(), QueryError> {
+    for (number, key, row) in rows {
+        fold_or_spill(table, spill, (number, key, &row), budget, fold)?;
+    }
+    Ok(())
+}
+
+/// Folds a row into `table` unless the table exceeds `budget` and doesn't
+/// have the group of the row, the row is written to `spill` then
+fn fold_or_spill<G, T: GroupTable<G>, F: Fn(&mut T, G, &[Datum], usize) -> Result<(), QueryError>>(
+    table: &mut T,
+    spill: &mut Option<Spill>,
+    (number, key, row): (usize, G, &[Datum]),
+    budget: usize,
+    fold: &F,
+) -> Result<(), QueryError> {
+    if table.size() < budget || table.contains(&key) {
+        return fold(table, key, row, number);
+    }
+    let spill = match spill {
+        Some(spill) => spill,
+        None => spill.insert(Spill::new().map_err(spill_error)?),
+    };
+    spill.write(number, row).map_err(spill_error)
+}
+
+/// Finishes `table` and folds rows that were spilled next to it into new
+/// tables until no rows are left
+fn fold_spilled<G, T, R, K, F, E>(
+    table: T,
+    mut spill: Option<Spill>,
+    budget: usize,
+    key: &K,
+    fold: &F,
+    finish: &E,
+) -> Result<Vec<R>, QueryError>
+where
+    T: GroupTable<G>,
+    K: Fn(&[Datum]) -> G,
+    F: Fn(&mut T, G, &[Datum], usize) -> Result<(), QueryError>,
+    E: Fn(T) -> Result<R, QueryError>,
+{
+    let mut results = vec![finish(table)?];
+    while let Some(spilled) = spill.take() {
+        let mut rows = spilled.rows().map_err(spill_error)?;
+        let mut table = T::default();
+        let mut buffer = vec![];
+        while let Some(number) = rows.next(&mut buffer).map_err(spill_error)? {
+            let row = unpack_raw(&buffer);
+            fold_or_spill(&mut table, &mut spill, (number, key(&row), &row), budget, fold)?;
+        }
+        results.push(finish(table)?);
+    }
+    Ok(results)
+}
+
+fn spill_error(error: io::Error) -> QueryError {
+    QueryError::io_error(format!("could not spill groups to a temporary file: {}", error))
+}
+
+/// Rows written to a temporary file with their numbers in the scan, the
+/// file is removed when it is closed
+struct Spill {
+    file: BufWriter<File>,
+}
+
+impl Spill {
+    fn new() -> io::Result<Spill> {
+        Ok(Spill {
+            file: BufWriter::new(tempfile::tempfile()?),
+        })
+    }
+
+    fn write(&mut self, number: usize, row: &[Datum]) -> io::Result<()> {
+        let values = Values::pack(row);
+        self.file.write_all(&(number as u64).to_le_bytes())?;
+        self.file.write_all(&(values.to_bytes().len() as u32).to_le_bytes())?;
+        self.file.write_all(values.to_bytes())
+    }
+
+    /// Reads rows back in the order they were written
+    fn rows(self) -> io::Result<SpilledRows> {
+        let mut file = self.file.into_inner().map_err(|error| error.into_error())?;
+        file.seek(SeekFrom::Start(0))?;
+        Ok(SpilledRows {
+            file: BufReader::new(file),
+        })
+    }
+}
+
+struct SpilledRows {
+    file: BufReader<File>,
+}
+
+impl SpilledRows {
+    /// Reads values of the next row into `buffer` and returns its number in
+    /// the scan, `None` if all rows were read
+    fn next(&mut self, buffer: &mut Vec<u8>) -> io::Result<Option<usize>> {
+        let mut number = [0; 8];
+        match self.file.read_exact(&mut number) {
+            Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            read => read?,
+        }
+        let mut len = [0; 4];
+        self.file.read_exact(&mut len)?;
+        buffer.resize(u32::from_le_bytes(len) as usize, 0);
+        self.file.read_exact(buffer)?;
+        Ok(Some(u64::from_le_bytes(number) as usize))
+    }
+}
+
+/// Converts columns into rows of their text representation, NULLs are `None`
+pub(crate) fn to_text_rows(columns: &[Vec<Datum>]) -> Vec<Vec<Option<String>>> {
+    let len = columns.first().map(Vec::len).unwrap_or_default();
+    (0..len)
+        .map(|row| columns.iter().map(|column| column[row].to_field()).collect())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use protocol::messages::BackendMessage;
+    use representation::Binary;
+    use std::io;
+
+    fn cursor(rows: usize) -> ReadCursor {
+        Box::new(
+            (0..rows)
+                .map(|index| {
+                    let key = Binary::pack(&[Datum::from_u64(index as u64)]);
+                    let values = Binary::pack(&[Datum::from_i16(index as i16), Datum::from_string(index.to_string())]);
+                    Ok(Ok((key, values)))
+                })
+                .collect::<Vec<io::Result<_>>>()
+                .into_iter(),
+        )
+    }
+
+    #[test]
+    fn table_scan_is_split_into_batches() {
+        assert_eq!(
+            Batches::new(cursor(BATCH_SIZE * 2 + 1))
+                .map(|batch| batch.len())
+                .collect::<Vec<_>>(),
+            vec![BATCH_SIZE, BATCH_SIZE, 1]
+        );
+    }
+
+    #[test]
+    fn empty_table_has_no_batches() {
+        assert_eq!(Batches::new(cursor(0)).count(), 0);
+    }
+
+    #[test]
+    fn columns_of_batch() {
+        let batch = Batches::new(cursor(2)).next().expect("batch");
+
+        assert_eq!(
+            to_text_rows(&batch.columns(&[1, 0, 1])),
+            vec![
+                vec![Some("0".to_owned()), Some("0".to_owned()), Some("0".to_owned())],
+                vec![Some("1".to_owned()), Some("1".to_owned()), Some("1".to_owned())]
+            ]
+        );
+    }
+
+    #[test]
+    fn encoded_columns_of_batch() {
+        let batch = Batches::new(cursor(2)).next().expect("batch");
+        let columns = batch.columns(&[1, 0]);
+
+        assert_eq!(
+            encode(&columns).as_bytes(),
+            to_text_rows(&columns)
+                .into_iter()
+                .flat_map(|row| BackendMessage::DataRow(row).as_vec())
+                .collect::<Vec<u8>>()
+                .as_slice()
+        );
+    }
+
+    #[test]
+    fn encoded_projection_keeps_order_of_rows() {
+        let rows = BATCH_SIZE * 3 + 5;
+        let mut encoded = vec![];
+
+        assert_eq!(
+            project_encoded(Batches::new(cursor(rows)), &[0], 2, &mut WorkMem::new(1024), |part| {
+                encoded.extend_from_slice(part.as_bytes());
+                Ok::<(), ()>(())
+            }),
+            Ok(rows)
+        );
+        assert_eq!(
+            encoded,
+            (0..rows)
+                .flat_map(|index| BackendMessage::DataRow(vec![Some(index.to_string())]).as_vec())
+                .collect::<Vec<u8>>()
+        );
+    }
+
+    #[test]
+    fn parallel_projection_keeps_order_of_rows() {
+        let rows = BATCH_SIZE * 3 + 5;
+
+        let project = |workers| {
+            let mut projected = vec![];
+            let emitted = project(
+                Batches::new(cursor(rows)),
+                &[0],
+                workers,
+                &mut WorkMem::new(1024),
+                |part| {
+                    projected.extend(part);
+                    Ok::<(), ()>(())
+                },
+            );
+            (emitted, projected)
+        };
+
+        assert_eq!(project(2), project(0));
+        let (emitted, projected) = project(2);
+        assert_eq!(emitted, Ok(rows));
+        assert_eq!(
+            projected.into_iter().map(|row| row[0].clone()).collect::<Vec<_>>(),
+            (0..rows).map(|index| Some(index.to_string())).collect::<Vec<_>>()
+        );
+    }
+
+    /// Groups with the number of their first row, number of their rows and
+    /// length of text values of their rows
+    #[derive(Default)]
+    struct Counts(Vec<(i16, usize, usize, usize)>);
+
+    impl GroupTable<i16> for Counts {
+        fn contains(&self, key: &i16) -> bool {
+            self.0.iter().any(|(group, ..)| group == key)
+        }
+
+        fn size(&self) -> usize {
+            self.0.len() * std::mem::size_of::<(i16, usize, usize, usize)>()
+        }
+    }
+
+    fn count(rows: usize, workers: usize, budget: usize) -> (Vec<(i16, usize, usize, usize)>, usize) {
+        let (tables, folded) = aggregate(
+            Batches::new(cursor(rows)),
+            workers,
+            budget,
+            |row| row[0].as_i16() % 7,
+            |groups: &mut Counts, key, row, number| {
+                let len = row[1].as_str().len();
+                match groups.0.iter_mut().find(|(group, ..)| *group == key) {
+                    Some((_group, _first, count, total)) => {
+                        *count += 1;
+                        *total += len;
+                    }
+                    None => groups.0.push((key, number, 1, len)),
+                }
+                Ok(())
+            },
+            |groups| Ok(groups.0),
+        )
+        .expect("no errors");
+        let mut groups = tables.into_iter().flatten().collect::<Vec<_>>();
+        groups.sort_by_key(|(_group, first, ..)| *first);
+        (groups, folded)
+    }
+
+    #[test]
+    fn parallel_aggregation_keeps_groups_in_one_partition() {
+        let rows = BATCH_SIZE * 3 + 5;
+
+        let (groups, folded) = count(rows, 0, usize::MAX);
+        assert_eq!(folded, rows);
+        assert_eq!(groups.len(), 7);
+        assert_eq!(count(rows, 2, usize::MAX), (groups, folded));
+    }
+
+    #[test]
+    fn groups_over_budget_are_spilled() {
+        let rows = BATCH_SIZE * 3 + 5;
+        let (groups, folded) = count(rows, 0, usize::MAX);
+
+        assert_eq!(count(rows, 0, 1), (groups.clone(), folded));
+        assert_eq!(count(rows, 2, 1), (groups, folded));
+    }
+
+    #[test]
+    fn failed_fold_stops_aggregation() {
+        assert_eq!(
+            aggregate(
+                Batches::new(cursor(BATCH_SIZE * 3)),
+                2,
+                usize::MAX,
+                |row| row[0].as_i16(),
+                |_groups: &mut Counts, key, _row, _number| match key {
+                    100 => Err(QueryError::division_by_zero()),
+                    _ => Ok(()),
+                },
+                |_groups| Ok(())
+            )
+            .map(|(_tables, folded)| folded),
+            Err(QueryError::division_by_zero())
+        );
+    }
+
+    #[test]
+    fn batches_are_emitted_one_by_one() {
+        let mut sizes = vec![];
+
+        assert_eq!(
+            project(
+                Batches::new(cursor(BATCH_SIZE + 1)),
+                &[0],
+                0,
+                &mut WorkMem::new(1024),
+                |part| {
+                    sizes.push(part.len());
+                    Ok::<(), ()>(())
+                }
+            ),
+            Ok(BATCH_SIZE + 1)
+        );
+        assert_eq!(sizes, vec![BATCH_SIZE, 1]);
+    }
+
+    #[test]
+    fn batches_decoded_at_once_exceed_work_mem() {
+        let mut work_mem = WorkMem::new(64);
+
+        assert_eq!(
+            project(
+                Batches::new(cursor(BATCH_SIZE * 4)),
+                &[0, 1],
+                1,
+                &mut work_mem,
+                |_| Ok::<(), ()>(())
+            ),
+            Err(Interrupted::OutOfMemory)
+        );
+    }
+
+    #[test]
+    fn failed_emit_stops_projection() {
+        let mut emitted = 0;
+
+        assert_eq!(
+            project(
+                Batches::new(cursor(BATCH_SIZE * 3)),
+                &[0],
+                0,
+                &mut WorkMem::new(1024),
+                |_| {
+                    emitted += 1;
+                    Err("disconnected")
+                }
+            ),
+            Err(Interrupted::Emit("disconnected"))
+        );
+        assert_eq!(emitted, 1);
+    }
+
+    #[test]
+    fn failed_row_stops_projection() {
+        let mut emitted = 0;
+
+        assert_eq!(
+            project_rows(
+                Batches::new(cursor(BATCH_SIZE * 3)),
+                |batch| match batch.rows()[0][0] {
+                    Datum::Int16(0) => Ok(to_text_rows(&batch.columns(&[0]))),
+                    _ => Err(QueryError::division_by_zero()),
+                },
+                0,
+                &mut WorkMem::new(1024),
+                |_| {
+                    emitted += 1;
+                    Ok::<(), ()>(())
+                }
+            ),
+            Err(Interrupted::Failed(QueryError::division_by_zero()))
+        );
+        assert_eq!(emitted, 1);
+    }
+
+    #[test]
+    fn assign_values_to_columns() {
+        let batch = Batches::new(cursor(2)).next().expect("batch");
+        let rows = batch.assign(&[(1, Datum::from_str("a"))]);
+
+        assert_eq!(
+            rows.iter()
+                .map(|(_key, values)| values.unpack().iter().map(ToString::to_string).collect::<Vec<_>>())
+                .collect::<Vec<_>>(),
+            vec![
+                vec!["0".to_owned(), "a".to_owned()],
+                vec!["1".to_owned(), "a".to_owned()]
+            ]
+        );
+    }
+}
@@ -0,0 +1,425 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! `COPY` of table rows from and to CSV files on the server side.
+use crate::{
+    catalog_manager::{CatalogManager, RowChange},
+    dml::{self, batch::Batches, write_buffer::WriteBuffer},
+    query::{
+        extended::{CopyDirection, CopyOptions, CopyStatement},
+        resolve::Scope,
+        TableId, TableNamingError,
+    },
+    transaction::Transaction,
+    ColumnDefinition,
+};
+use bigdecimal::BigDecimal;
+use kernel::{SystemError, SystemResult};
+use protocol::{
+    results::{QueryError, QueryEvent},
+    Sender,
+};
+use representation::{Binary, Datum};
+use sql_types::{ConstraintError, SqlType};
+use sqlparser::ast::Value;
+use std::{
+    convert::TryFrom,
+    fs::{self, File},
+    io::{BufWriter, Write},
+    str::FromStr,
+    sync::Arc,
+};
+use storage::Row;
+
+pub(crate) struct CopyCommand {
+    statement: CopyStatement,
+    storage: Arc<CatalogManager>,
+    session: Arc<dyn Sender>,
+    transaction: Arc<Transaction>,
+}
+
+impl CopyCommand {
+    pub(crate) fn new(
+        statement: CopyStatement,
+        storage: Arc<CatalogManager>,
+        session: Arc<dyn Sender>,
+        transaction: Arc<Transaction>,
+    ) -> CopyCommand {
+        CopyCommand {
+            statement,
+            storage,
+            session,
+            transaction,
+        }
+    }
+
+    pub(crate) fn execute(&mut self) -> SystemResult<()> {
+        let table_id = match TableId::try_from(self.statement.table_name.clone()) {
+            Ok(table_id) => table_id,
+            Err(TableNamingError(message)) => return self.send(Err(QueryError::syntax_error(message))),
+        };
+        let schema_name = table_id.schema_name();
+        let table_name = table_id.name();
+        match self.storage.table_exists(schema_name, table_name) {
+            None => return self.send(Err(QueryError::schema_does_not_exist(schema_name.to_owned()))),
+            Some((_, None)) => {
+                return self.send(Err(QueryError::table_does_not_exist(
+                    schema_name.to_owned() + "." + table_name,
+                )))
+            }
+            Some((_, Some(_))) => {}
+        }
+        if self.statement.direction == CopyDirection::From && self.storage.is_foreign_table(schema_name, table_name) {
+            return self.send(Err(QueryError::cannot_change_foreign_table(
+                "copy to",
+                schema_name.to_owned() + "." + table_name,
+            )));
+        }
+        let all_columns = self.storage.table_columns(schema_name, table_name)?;
+        let indexes = if self.statement.columns.is_empty() {
+            (0..all_columns.len()).collect::<Vec<usize>>()
+        } else {
+            match Scope::new(&all_columns).resolve_all(self.statement.columns.iter().map(String::as_str)) {
+                Ok(indexes) => indexes,
+                Err(error) => return self.send(Err(error)),
+            }
+        };
+        match self.statement.direction {
+            CopyDirection::From => self.copy_from(schema_name, table_name, &all_columns, &indexes),
+            CopyDirection::To => self.copy_to(schema_name, table_name, &all_columns, &indexes),
+        }
+    }
+
+    /// All records of the file are validated before any of them is written
+    /// so a malformed file does not leave the table partially loaded
+    fn copy_from(
+        &self,
+        schema_name: &str,
+        table_name: &str,
+        all_columns: &[ColumnDefinition],
+        indexes: &[usize],
+    ) -> SystemResult<()> {
+        let file_name = &self.statement.file_name;
+        let options = &self.statement.options;
+        let text = match fs::read_to_string(file_name) {
+            Ok(text) => text,
+            Err(error) => {
+                return self.send(Err(QueryError::io_error(format!(
+                    "could not open file \"{}\" for reading: {}",
+                    file_name, error
+                ))))
+            }
+        };
+        let records = match parse_records(&text, options) {
+            Ok(records) => records,
+            Err(message) => return self.send(Err(QueryError::bad_copy_file_format(message))),
+        };
+        let skipped = if options.header { 1 } else { 0 };
+        let mut rows: Vec<Row> = Vec::with_capacity(records.len());
+        let published = self.storage.publications().is_published(schema_name, table_name);
+        let mut changes = vec![];
+        for (line, record) in records.into_iter().enumerate().skip(skipped) {
+            if record.len() > indexes.len() {
+                return self.send(Err(QueryError::bad_copy_file_format(
+                    "extra data after last expected column".to_owned(),
+                )));
+            }
+            if record.len() < indexes.len() {
+                return self.send(Err(QueryError::bad_copy_file_format(format!(
+                    "missing data for column \"{}\"",
+                    all_columns[indexes[record.len()]].name()
+                ))));
+            }
+            let mut values = all_columns.iter().map(dml::default_datum).collect::<Vec<Datum>>();
+            for (field, index) in record.into_iter().zip(indexes.iter()) {
+                let column = &all_columns[*index];
+                match field {
+                    Some(field) => match datum(&column.sql_type(), &field) {
+                        Ok(datum) => values[*index] = datum,
+                        Err(error) => return self.send(Err(constraint_violation(error, column, line + 1))),
+                    },
+                    None => values[*index] = Datum::from_null(),
+                }
+            }
+            if let Some(error) = dml::null_violation(all_columns, &values) {
+                return self.send(Err(error));
+            }
+            if published {
+                changes.push(RowChange::insert(schema_name, table_name, &values));
+            }
+            let key = self.storage.next_key_id().to_be_bytes().to_vec();
+            rows.push((Binary::with_data(key), Binary::pack(&values)));
+        }
+        let mut buffer = WriteBuffer::rows(&self.transaction, &self.storage, schema_name, table_name);
+        buffer.push(rows)?;
+        let copied = buffer.flush()?;
+        self.storage.statistics().inserted(schema_name, table_name, copied);
+        self.transaction
+            .publish(&self.storage, schema_name, table_name, changes)?;
+        self.send(Ok(QueryEvent::RecordsCopied(copied)))
+    }
+
+    fn copy_to(
+        &self,
+        schema_name: &str,
+        table_name: &str,
+        all_columns: &[ColumnDefinition],
+        indexes: &[usize],
+    ) -> SystemResult<()> {
+        let file_name = &self.statement.file_name;
+        let options = &self.statement.options;
+        let mut writer = match File::create(file_name) {
+            Ok(file) => BufWriter::new(file),
+            Err(error) => {
+                return self.send(Err(QueryError::io_error(format!(
+                    "could not open file \"{}\" for writing: {}",
+                    file_name, error
+                ))))
+            }
+        };
+        let mut text = String::new();
+        if options.header {
+            let names = indexes
+                .iter()
+                .map(|index| Some(all_columns[*index].name()))
+                .collect::<Vec<Option<String>>>();
+            write_record(&mut text, &names, options);
+        }
+        let mut copied = 0;
+        for batch in Batches::new(self.transaction.full_scan(&self.storage, schema_name, table_name)?) {
+            let columns = batch.columns(indexes);
+            for row in 0..batch.len() {
+                let fields = columns
+                    .iter()
+                    .map(|column| match &column[row] {
+                        Datum::Null => None,
+                        datum => Some(datum.to_string()),
+                    })
+                    .collect::<Vec<Option<String>>>();
+                write_record(&mut text, &fields, options);
+            }
+            copied += batch.len();
+            if let Err(error) = writer.write_all(text.as_bytes()) {
+                return self.send(Err(QueryError::io_error(format!(
+                    "could not write to file \"{}\": {}",
+                    file_name, error
+                ))));
+            }
+            text.clear();
+        }
+        if let Err(error) = writer.write_all(text.as_bytes()).and_then(|()| writer.flush()) {
+            return self.send(Err(QueryError::io_error(format!(
+                "could not write to file \"{}\": {}",
+                file_name, error
+            ))));
+        }
+        self.storage.statistics().seq_scanned(schema_name, table_name, copied);
+        self.send(Ok(QueryEvent::RecordsCopied(copied)))
+    }
+
+    fn send(&self, result: Result<QueryEvent, QueryError>) -> SystemResult<()> {
+        self.session.send(result).map_err(SystemError::connection_lost)?;
+        Ok(())
+    }
+}
+
+/// Converts a field of CSV record into a value of `sql_type` the same way
+/// `INSERT` converts literals
+pub(crate) fn datum(sql_type: &SqlType, field: &str) -> Result<Datum<'static>, ConstraintError> {
+    sql_type.constraint().validate(field)?;
+    let value = match sql_type {
+        SqlType::Bool => Value::Boolean(matches!(
+            field.to_lowercase().as_str(),
+            "true" | "t" | "yes" | "y" | "on" | "1"
+        )),
+        SqlType::Char(_) | SqlType::VarChar(_) => Value::SingleQuotedString(field.to_owned()),
+        SqlType::TsVector | SqlType::TsQuery => Value::SingleQuotedString(
+            String::from_utf8(sql_type.serializer().ser(field)).expect("normalized value to be UTF-8"),
+        ),
+        _ => match BigDecimal::from_str(field) {
+            Ok(number) => Value::Number(number),
+            Err(_) => return Err(ConstraintError::TypeMismatch(field.to_owned())),
+        },
+    };
+    Datum::try_from(&value).map_err(|_| ConstraintError::TypeMismatch(field.to_owned()))
+}
+
+pub(crate) fn constraint_violation(error: ConstraintError, column: &ColumnDefinition, line: usize) -> QueryError {
+    match error {
+        ConstraintError::OutOfRange => QueryError::out_of_range((&column.sql_type()).into(), column.name(), line),
+        ConstraintError::TypeMismatch(value) => {
+            QueryError::type_mismatch(&value, (&column.sql_type()).into(), column.name(), line)
+        }
+        ConstraintError::ValueTooLong(len) => {
+            QueryError::string_length_mismatch((&column.sql_type()).into(), len, column.name(), line)
+        }
+    }
+}
+
+/// Splits CSV `text` into records of fields. Quoted fields can contain
+/// delimiters, line breaks and doubled quotes. Unquoted fields that are
+/// equal to NULL string of `options` are `None`.
+pub(crate) fn parse_records(text: &str, options: &CopyOptions) -> Result<Vec<Vec<Option<String>>>, String> {
+    let mut records = vec![];
+    let mut chars = text.chars().peekable();
+    while chars.peek().is_some() {
+        let mut record = vec![];
+        let mut end_of_record = false;
+        while !end_of_record {
+            let mut field = String::new();
+            let mut quoted = false;
+            loop {
+                match chars.next() {
+                    None | Some('\n') => {
+                        end_of_record = true;
+                        break;
+                    }
+                    Some('\r') => {
+                        chars.next_if_eq(&'\n');
+                        end_of_record = true;
+                        break;
+                    }
+                    Some(c) if c == options.delimiter => break,
+                    Some(c) if c == options.quote => {
+                        quoted = true;
+                        loop {
+                            match chars.next() {
+                                None => return Err("unterminated CSV quoted field".to_owned()),
+                                Some(c) if c == options.quote => {
+                                    if chars.next_if_eq(&options.quote).is_none() {
+                                        break;
+                                    }
+                                    field.push(c);
+                                }
+                                Some(c) => field.push(c),
+                            }
+                        }
+                    }
+                    Some(c) => field.push(c),
+                }
+            }
+            if !quoted && field == options.null {
+                record.push(None);
+            } else {
+                record.push(Some(field));
+            }
+        }
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// Appends line of `fields` to `text`, `None` is written as NULL string of
+/// `options`. Fields that could be mistaken for a delimiter, a line break
+/// or NULL are quoted.
+fn write_record(text: &mut String, fields: &[Option<String>], options: &CopyOptions) {
+    for (index, field) in fields.iter().enumerate() {
+        if index > 0 {
+            text.push(options.delimiter);
+        }
+        match field {
+            None => text.push_str(&options.null),
+            Some(field)
+                if *field == options.null || field.contains(&[options.delimiter, options.quote, '\n', '\r'][..]) =>
+            {
+                text.push(options.quote);
+                for c in field.chars() {
+                    if c == options.quote {
+                        text.push(c);
+                    }
+                    text.push(c);
+                }
+                text.push(options.quote);
+            }
+            Some(field) => text.push_str(field),
+        }
+    }
+    text.push('\n');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records(text: &str, options: &CopyOptions) -> Vec<Vec<Option<String>>> {
+        parse_records(text, options).expect("valid CSV")
+    }
+
+    #[test]
+    fn unquoted_fields() {
+        assert_eq!(
+            records("1,a\r\n2,\n", &CopyOptions::default()),
+            vec![
+                vec![Some("1".to_owned()), Some("a".to_owned())],
+                vec![Some("2".to_owned()), None]
+            ]
+        );
+    }
+
+    #[test]
+    fn quoted_fields() {
+        assert_eq!(
+            records(
+                "\"a,b\",\"say \"\"hi\"\"\",\"\"\n\"line\nbreak\"",
+                &CopyOptions::default()
+            ),
+            vec![
+                vec![
+                    Some("a,b".to_owned()),
+                    Some("say \"hi\"".to_owned()),
+                    Some("".to_owned())
+                ],
+                vec![Some("line\nbreak".to_owned())]
+            ]
+        );
+    }
+
+    #[test]
+    fn custom_delimiter_quote_and_null() {
+        let options = CopyOptions {
+            header: false,
+            delimiter: ';',
+            quote: '\'',
+            null: "\\N".to_owned(),
+        };
+
+        assert_eq!(
+            records("'a;b';\\N;'\\N'", &options),
+            vec![vec![Some("a;b".to_owned()), None, Some("\\N".to_owned())]]
+        );
+    }
+
+    #[test]
+    fn unterminated_quoted_field() {
+        assert_eq!(
+            parse_records("1,\"a\n", &CopyOptions::default()),
+            Err("unterminated CSV quoted field".to_owned())
+        );
+    }
+
+    #[test]
+    fn written_record_is_parsed_back() {
+        let options = CopyOptions::default();
+        let fields = vec![
+            Some("1".to_owned()),
+            None,
+            Some("".to_owned()),
+            Some("a,\"b\"\nc".to_owned()),
+        ];
+        let mut text = String::new();
+        write_record(&mut text, &fields, &options);
+
+        assert_eq!(text, "1,,\"\",\"a,\"\"b\"\"\nc\"\n");
+        assert_eq!(records(&text, &options), vec![fields]);
+    }
+}
@@ -0,0 +1,315 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Common table expressions of `WITH` clauses. Each expression is executed
+//! once before the query and its rows are kept in memory for relations of
+//! the query that refer to it by name. An expression that refers to itself
+//! in the second query of `UNION [ALL]` is recursive: the first query gives
+//! the initial rows and the second one is executed with rows of the previous
+//! iteration in place of the expression until it does not produce new rows.
+use crate::{
+    dml::{copy, select::operand_query, subquery},
+    query::authorize::collect_query_tables,
+    ColumnDefinition,
+};
+use protocol::results::{Description, QueryError};
+use representation::{Binary, Datum};
+use sql_types::SqlType;
+use sqlparser::ast::{Cte, Ident, Query, SetExpr, SetOperator};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+use storage::{ReadCursor, Row};
+
+/// Iterations of a recursive expression a query can run unless a session
+/// changes it with `SET max_recursive_iterations`
+pub(crate) const DEFAULT_MAX_RECURSIVE_ITERATIONS: usize = 1000;
+
+type TextRow = Vec<Option<String>>;
+
+/// Expressions that relations of a query can refer to by their names
+pub(crate) type WorkTables = HashMap<String, Arc<WorkTable>>;
+
+/// Materialized rows of an expression
+pub(crate) struct WorkTable {
+    columns: Vec<ColumnDefinition>,
+    rows: Vec<Row>,
+}
+
+impl WorkTable {
+    /// Table without rows with columns of `description`, `aliases` rename
+    /// the first columns
+    pub(crate) fn new(description: &Description, aliases: &[Ident]) -> WorkTable {
+        let columns = description
+            .iter()
+            .enumerate()
+            .map(|(index, column)| {
+                let name = aliases
+                    .get(index)
+                    .map(|alias| alias.value.as_str())
+                    .unwrap_or(&column.name);
+                let sql_type = match subquery::sql_type(column.pg_type) {
+                    SqlType::Char(_) => SqlType::Char(u64::MAX),
+                    sql_type => sql_type,
+                };
+                ColumnDefinition::new(name, sql_type)
+            })
+            .collect();
+        WorkTable { columns, rows: vec![] }
+    }
+
+    /// Single row without columns that a query without `FROM` clause selects
+    /// values from
+    pub(crate) fn single_row() -> WorkTable {
+        WorkTable {
+            columns: vec![],
+            rows: vec![(Binary::pack(&[Datum::from_u64(0)]), Binary::pack(&[]))],
+        }
+    }
+
+    pub(crate) fn columns(&self) -> Vec<ColumnDefinition> {
+        self.columns.clone()
+    }
+
+    pub(crate) fn scan(&self) -> ReadCursor {
+        Box::new(self.rows.clone().into_iter().map(|row| Ok(Ok(row))))
+    }
+
+    /// Converts text representation of values of `rows` into values of the
+    /// columns and appends them
+    pub(crate) fn extend(&mut self, rows: Vec<TextRow>) -> Result<(), QueryError> {
+        for row in rows {
+            let mut values = vec![];
+            for (field, column) in row.iter().zip(&self.columns) {
+                values.push(match field {
+                    None => Datum::from_null(),
+                    Some(text) => copy::datum(&column.sql_type(), text).map_err(|_| {
+                        QueryError::datatype_mismatch(format!(
+                            "value \"{}\" of column \"{}\" can't be kept in a work table",
+                            text,
+                            column.name()
+                        ))
+                    })?,
+                });
+            }
+            let key = Binary::pack(&[Datum::from_u64(self.rows.len() as u64)]);
+            self.rows.push((key, Binary::pack(&values)));
+        }
+        Ok(())
+    }
+}
+
+/// Runs queries of expressions with the work tables they refer to
+pub(crate) trait Terms {
+    /// Description of `query`
+    fn describe(&self, query: &Query) -> Result<Description, QueryError>;
+
+    /// Description and rows of `query` with `bound` table in place of the
+    /// expression with `name`
+    fn execute(
+        &self,
+        query: &Query,
+        bound: Option<(&str, WorkTable)>,
+    ) -> Result<(Description, Vec<TextRow>), QueryError>;
+}
+
+/// Table of `cte` with columns of its query but without rows
+pub(crate) fn describe(cte: &Cte, terms: &dyn Terms) -> Result<WorkTable, QueryError> {
+    let query = match recursive_terms(cte) {
+        Some((initial, _recursive, _all)) => operand_query(initial),
+        None => cte.query.clone(),
+    };
+    Ok(WorkTable::new(&terms.describe(&query)?, &cte.alias.columns))
+}
+
+/// Rows of `cte`, a recursive expression fails if its recursive query still
+/// produces new rows after `limit` iterations
+pub(crate) fn evaluate(cte: &Cte, limit: usize, terms: &dyn Terms) -> Result<WorkTable, QueryError> {
+    let (initial, recursive, all) = match recursive_terms(cte) {
+        Some(terms) => terms,
+        None => {
+            let (description, rows) = terms.execute(&cte.query, None)?;
+            let mut table = WorkTable::new(&description, &cte.alias.columns);
+            table.extend(rows)?;
+            return Ok(table);
+        }
+    };
+    let name = cte.alias.name.value.as_str();
+    let (description, rows) = terms.execute(&operand_query(initial), None)?;
+    let mut result = WorkTable::new(&description, &cte.alias.columns);
+    let mut seen = HashSet::new();
+    let mut new_rows = distinct(rows, all, &mut seen);
+    let recursive = operand_query(recursive);
+    let mut iterations = 0;
+    while !new_rows.is_empty() {
+        if iterations == limit {
+            return Err(QueryError::recursion_limit_exceeded(name, limit));
+        }
+        iterations += 1;
+        let mut working = WorkTable {
+            columns: result.columns.clone(),
+            rows: vec![],
+        };
+        working.extend(new_rows.clone())?;
+        result.extend(new_rows)?;
+        let (produced, rows) = terms.execute(&recursive, Some((name, working)))?;
+        if produced.len() != description.len() {
+            return Err(QueryError::set_operation_columns_mismatch(
+                &SetOperator::Union.to_string(),
+            ));
+        }
+        new_rows = distinct(rows, all, &mut seen);
+    }
+    Ok(result)
+}
+
+/// Initial and recursive queries of a recursive expression and whether rows
+/// of iterations are kept as is
+fn recursive_terms(cte: &Cte) -> Option<(&SetExpr, &SetExpr, bool)> {
+    match &cte.query.body {
+        SetExpr::SetOperation {
+            op: SetOperator::Union,
+            all,
+            left,
+            right,
+        } if refers_to(&operand_query(right), &cte.alias.name) => Some((left, right, *all)),
+        _ => None,
+    }
+}
+
+/// Whether a relation of `query` is named as the expression
+fn refers_to(query: &Query, name: &Ident) -> bool {
+    let mut tables = vec![];
+    collect_query_tables(query, &mut tables);
+    tables
+        .iter()
+        .any(|table| matches!(table.0.as_slice(), [table] if table.value == name.value))
+}
+
+/// Rows that were not seen before, all rows if duplicates are kept
+fn distinct(rows: Vec<TextRow>, all: bool, seen: &mut HashSet<TextRow>) -> Vec<TextRow> {
+    if all {
+        return rows;
+    }
+    rows.into_iter().filter(|row| seen.insert(row.clone())).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::table_sample::parse_sql;
+    use protocol::{results::ColumnDescription, sql_types::PostgreSqlType};
+    use sqlparser::{ast::Statement, dialect::PostgreSqlDialect};
+    use std::cell::RefCell;
+
+    /// Gives the rows of each execution in order, the last ones are repeated
+    struct Scripted(RefCell<Vec<Vec<TextRow>>>);
+
+    impl Scripted {
+        fn new(outputs: Vec<Vec<i32>>) -> Scripted {
+            Scripted(RefCell::new(
+                outputs
+                    .into_iter()
+                    .rev()
+                    .map(|rows| rows.iter().map(|value| vec![Some(value.to_string())]).collect())
+                    .collect(),
+            ))
+        }
+    }
+
+    impl Terms for Scripted {
+        fn describe(&self, _query: &Query) -> Result<Description, QueryError> {
+            Ok(vec![ColumnDescription::new("n", PostgreSqlType::Integer)])
+        }
+
+        fn execute(
+            &self,
+            query: &Query,
+            _bound: Option<(&str, WorkTable)>,
+        ) -> Result<(Description, Vec<TextRow>), QueryError> {
+            let mut outputs = self.0.borrow_mut();
+            let rows = match outputs.len() {
+                1 => outputs[0].clone(),
+                _ => outputs.pop().expect("output of execution"),
+            };
+            Ok((self.describe(query)?, rows))
+        }
+    }
+
+    fn cte(sql: &str) -> Cte {
+        match parse_sql(&PostgreSqlDialect {}, sql).expect("parsed").pop() {
+            Some(Statement::Query(query)) => query.ctes[0].clone(),
+            _ => unreachable!("query"),
+        }
+    }
+
+    const RECURSIVE_ALL: &str =
+        "with recursive t (m) as (select 1 union all select m + 1 from t where m < 3) select m from t";
+    const RECURSIVE: &str = "with recursive t (m) as (select 1 union select m from t) select m from t";
+
+    #[test]
+    fn non_recursive() {
+        let table = evaluate(
+            &cte("with t (m) as (select n from s.t) select m from t"),
+            10,
+            &Scripted::new(vec![vec![1, 2]]),
+        )
+        .expect("rows");
+
+        assert_eq!(
+            table.columns,
+            vec![ColumnDefinition::new("m", SqlType::Integer(i32::MIN))]
+        );
+        assert_eq!(table.rows.len(), 2);
+    }
+
+    #[test]
+    fn recursion_stops_without_new_rows() {
+        let table = evaluate(
+            &cte(RECURSIVE_ALL),
+            10,
+            &Scripted::new(vec![vec![1], vec![2], vec![3], vec![]]),
+        )
+        .expect("rows");
+
+        assert_eq!(table.rows.len(), 3);
+    }
+
+    #[test]
+    fn union_discards_rows_of_previous_iterations() {
+        let table = evaluate(&cte(RECURSIVE), 10, &Scripted::new(vec![vec![1, 1], vec![1]])).expect("rows");
+
+        assert_eq!(table.rows.len(), 1);
+    }
+
+    #[test]
+    fn recursion_limit() {
+        assert_eq!(
+            evaluate(&cte(RECURSIVE_ALL), 5, &Scripted::new(vec![vec![1]])).map(|table| table.rows.len()),
+            Err(QueryError::recursion_limit_exceeded("t", 5))
+        );
+    }
+
+    #[test]
+    fn description_of_initial_query() {
+        let table = describe(&cte(RECURSIVE), &Scripted::new(vec![vec![]])).expect("described");
+
+        assert_eq!(
+            table.columns,
+            vec![ColumnDefinition::new("m", SqlType::Integer(i32::MIN))]
+        );
+        assert!(table.rows.is_empty());
+    }
+}
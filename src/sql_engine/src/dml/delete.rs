@@ -12,26 +12,53 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::catalog_manager::CatalogManager;
-use kernel::SystemResult;
+use crate::{
+    cancellation::CancellationToken,
+    catalog_manager::{CatalogManager, RowChange},
+    dml::{self, batch::Batches, functions::ScalarFunctions, write_buffer::WriteBuffer},
+    transaction::Transaction,
+};
+use kernel::{SystemError, SystemResult};
 use protocol::{
     results::{QueryError, QueryEvent},
     Sender,
 };
-use sqlparser::ast::ObjectName;
+use sqlparser::ast::{Expr, ObjectName};
 use std::sync::Arc;
 
 pub(crate) struct DeleteCommand {
     name: ObjectName,
+    selection: Option<Expr>,
     storage: Arc<CatalogManager>,
     session: Arc<dyn Sender>,
+    cancellation: CancellationToken,
+    /// functions that the statement calls
+    functions: ScalarFunctions,
+    transaction: Arc<Transaction>,
 }
 
 impl DeleteCommand {
-    pub(crate) fn new(name: ObjectName, storage: Arc<CatalogManager>, session: Arc<dyn Sender>) -> DeleteCommand {
-        DeleteCommand { name, storage, session }
+    pub(crate) fn new(
+        name: ObjectName,
+        selection: Option<Expr>,
+        storage: Arc<CatalogManager>,
+        session: Arc<dyn Sender>,
+        functions: ScalarFunctions,
+        transaction: Arc<Transaction>,
+    ) -> DeleteCommand {
+        DeleteCommand {
+            name,
+            selection,
+            storage,
+            session,
+            cancellation: functions.cancellation().clone(),
+            functions,
+            transaction,
+        }
     }
 
+    /// Rows that were deleted before the statement was cancelled or aborted
+    /// by a deadlock stay deleted and their changes are published
     pub(crate) fn execute(&mut self) -> SystemResult<()> {
         let schema_name = self.name.0[0].to_string();
         let table_name = self.name.0[1].to_string();
@@ -40,32 +67,82 @@ impl DeleteCommand {
             None => self
                 .session
                 .send(Err(QueryError::schema_does_not_exist(schema_name)))
-                .expect("To Send Result to Client"),
+                .map_err(SystemError::connection_lost)?,
             Some((_, None)) => self
                 .session
                 .send(Err(QueryError::table_does_not_exist(
                     schema_name + "." + table_name.as_str(),
                 )))
-                .expect("To Send Result to Client"),
+                .map_err(SystemError::connection_lost)?,
+            Some((_, Some(_))) if self.storage.is_foreign_table(&schema_name, &table_name) => self
+                .session
+                .send(Err(QueryError::cannot_change_foreign_table(
+                    "delete from",
+                    schema_name + "." + table_name.as_str(),
+                )))
+                .map_err(SystemError::connection_lost)?,
             Some((_, Some(_))) => {
-                match self.storage.full_scan(&schema_name, &table_name) {
-                    Err(e) => return Err(e),
-                    Ok(reads) => {
-                        let keys = reads
-                            .map(Result::unwrap)
-                            .map(Result::unwrap)
-                            .map(|(key, _)| key)
-                            .collect();
-
-                        match self.storage.delete_from(&schema_name, &table_name, keys) {
-                            Err(e) => return Err(e),
-                            Ok(records_number) => self
-                                .session
-                                .send(Ok(QueryEvent::RecordsDeleted(records_number)))
-                                .expect("To Send Query Result to Client"),
+                let condition = match &self.selection {
+                    Some(selection) => match dml::table_condition(
+                        selection,
+                        (&schema_name, &table_name),
+                        &self.storage.table_columns(&schema_name, &table_name)?,
+                        &self.functions,
+                    ) {
+                        Ok(condition) => Some(condition),
+                        Err(error) => {
+                            self.session.send(Err(error)).map_err(SystemError::connection_lost)?;
+                            return Ok(());
                         }
-                    }
+                    },
+                    None => None,
+                };
+                let reads = self.transaction.full_scan(&self.storage, &schema_name, &table_name)?;
+                let reads = match condition {
+                    Some(condition) => condition.filter(reads),
+                    None => reads,
                 };
+                let mut scanned = 0;
+                let mut buffer = WriteBuffer::keys(&self.transaction, &self.storage, &schema_name, &table_name);
+                let published = self.storage.publications().is_published(&schema_name, &table_name);
+                let mut changes = vec![];
+                let mut interrupted = Ok(());
+                for batch in Batches::new(reads) {
+                    let locked = self.cancellation.check().and_then(|()| {
+                        self.transaction.lock_rows(
+                            &schema_name,
+                            &table_name,
+                            batch.stored().map(|(key, _)| key),
+                            &self.cancellation,
+                        )
+                    });
+                    if let Err(error) = locked {
+                        interrupted = Err(error);
+                        break;
+                    }
+                    scanned += batch.len();
+                    if published {
+                        changes.extend(
+                            batch
+                                .rows()
+                                .iter()
+                                .map(|row| RowChange::delete(&schema_name, &table_name, row)),
+                        );
+                    }
+                    buffer.push(batch.keys())?;
+                }
+                let records_number = buffer.flush()?;
+                self.transaction
+                    .publish(&self.storage, &schema_name, &table_name, changes)?;
+                self.storage
+                    .statistics()
+                    .seq_scanned(&schema_name, &table_name, scanned);
+                self.storage
+                    .statistics()
+                    .deleted(&schema_name, &table_name, records_number);
+                self.session
+                    .send(interrupted.map(|()| QueryEvent::RecordsDeleted(records_number)))
+                    .map_err(SystemError::connection_lost)?
             }
         }
         Ok(())
@@ -0,0 +1,273 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Functions of the large object facility. Large objects are stored in pages
+//! by the catalog and opened through descriptors of a session. `bytea`
+//! arguments and results use the hex format, e.g. `\x48656c6c6f`.
+use crate::{
+    catalog_manager::LargeObjects,
+    dml::{functions::ScalarFunctions, predicate::Scalar},
+    session::LargeObjectDescriptors,
+};
+use kernel::SystemError;
+use protocol::{results::QueryError, sql_types::PostgreSqlType};
+use sql_types::SqlType;
+use std::{convert::TryFrom, sync::Arc};
+
+const TEXT: SqlType = SqlType::VarChar(u64::MAX);
+const INTEGER: SqlType = SqlType::Integer(i32::MIN);
+/// OIDs are unsigned 32-bit numbers
+const OID: SqlType = SqlType::BigInt(i64::MIN);
+
+/// `lo_open` mode flags of libpq
+const INV_WRITE: i64 = 0x0002_0000;
+const INV_READ: i64 = 0x0004_0000;
+
+pub(crate) fn register(functions: &ScalarFunctions, large_objects: Arc<LargeObjects>) {
+    let objects = large_objects.clone();
+    functions.register_volatile("lo_create", &[OID], INTEGER, move |_functions, args| {
+        create(&objects, oid(&args[0])?)
+    });
+    let objects = large_objects.clone();
+    functions.register_volatile("lo_open", &[OID, INTEGER], INTEGER, move |functions, args| {
+        open(&objects, functions.large_objects(), oid(&args[0])?, integer(&args[1]))
+    });
+    let objects = large_objects.clone();
+    functions.register_volatile("loread", &[INTEGER, INTEGER], TEXT, move |functions, args| {
+        read(
+            &objects,
+            functions.large_objects(),
+            descriptor(&args[0])?,
+            integer(&args[1]),
+        )
+    });
+    let objects = large_objects.clone();
+    functions.register_volatile("lowrite", &[INTEGER, TEXT], INTEGER, move |functions, args| {
+        write(
+            &objects,
+            functions.large_objects(),
+            descriptor(&args[0])?,
+            text(&args[1]),
+        )
+    });
+    functions.register_volatile("lo_close", &[INTEGER], INTEGER, |functions, args| {
+        close(functions.large_objects(), descriptor(&args[0])?)
+    });
+    functions.register_volatile("lo_unlink", &[OID], INTEGER, move |_functions, args| {
+        unlink(&large_objects, oid(&args[0])?)
+    });
+}
+
+/// Creates an empty large object, `0` as `oid` assigns an unused OID
+fn create(large_objects: &LargeObjects, oid: u32) -> Result<Scalar, QueryError> {
+    match large_objects
+        .create(if oid == 0 { None } else { Some(oid) })
+        .map_err(io_error)?
+    {
+        Some(oid) => Ok(Scalar::Integer(i64::from(oid))),
+        None => Err(QueryError::large_object_already_exists(oid)),
+    }
+}
+
+/// Opens the large object for reading or for reading and writing, returns
+/// its descriptor
+fn open(
+    large_objects: &LargeObjects,
+    descriptors: &LargeObjectDescriptors,
+    oid: u32,
+    mode: i64,
+) -> Result<Scalar, QueryError> {
+    if mode & (INV_READ | INV_WRITE) == 0 {
+        return Err(QueryError::invalid_parameter_value(format!(
+            "invalid flags for opening a large object: {}",
+            mode
+        )));
+    }
+    if !large_objects.exists(oid) {
+        return Err(QueryError::large_object_does_not_exist(oid));
+    }
+    Ok(Scalar::Integer(i64::from(descriptors.open(oid, mode & INV_WRITE != 0))))
+}
+
+/// Reads at most `len` bytes from the current position of the descriptor
+fn read(
+    large_objects: &LargeObjects,
+    descriptors: &LargeObjectDescriptors,
+    fd: i32,
+    len: i64,
+) -> Result<Scalar, QueryError> {
+    descriptors
+        .with_descriptor(fd, |descriptor| {
+            let data = large_objects
+                .read(descriptor.oid, descriptor.offset, usize::try_from(len).unwrap_or(0))
+                .ok_or_else(|| QueryError::large_object_does_not_exist(descriptor.oid))?;
+            descriptor.offset += data.len();
+            Ok(Scalar::Text(bytea_output(&data)))
+        })
+        .unwrap_or_else(|| Err(QueryError::invalid_large_object_descriptor(fd)))
+}
+
+/// Writes `data` at the current position of the descriptor, returns the
+/// number of written bytes
+fn write(
+    large_objects: &LargeObjects,
+    descriptors: &LargeObjectDescriptors,
+    fd: i32,
+    data: &str,
+) -> Result<Scalar, QueryError> {
+    let data = bytea_input(data)?;
+    descriptors
+        .with_descriptor(fd, |descriptor| {
+            if !descriptor.writable {
+                return Err(QueryError::large_object_not_opened_for_writing(fd));
+            }
+            if !large_objects
+                .write(descriptor.oid, descriptor.offset, &data)
+                .map_err(io_error)?
+            {
+                return Err(QueryError::large_object_does_not_exist(descriptor.oid));
+            }
+            descriptor.offset += data.len();
+            Ok(Scalar::Integer(data.len() as i64))
+        })
+        .unwrap_or_else(|| Err(QueryError::invalid_large_object_descriptor(fd)))
+}
+
+fn close(descriptors: &LargeObjectDescriptors, fd: i32) -> Result<Scalar, QueryError> {
+    if descriptors.close(fd) {
+        Ok(Scalar::Integer(0))
+    } else {
+        Err(QueryError::invalid_large_object_descriptor(fd))
+    }
+}
+
+fn unlink(large_objects: &LargeObjects, oid: u32) -> Result<Scalar, QueryError> {
+    if large_objects.unlink(oid).map_err(io_error)? {
+        Ok(Scalar::Integer(1))
+    } else {
+        Err(QueryError::large_object_does_not_exist(oid))
+    }
+}
+
+/// Pages of large objects are stored with the catalog, a failed write fails
+/// the statement
+fn io_error(error: SystemError) -> QueryError {
+    QueryError::io_error(error.to_string())
+}
+
+fn text(arg: &Scalar) -> &str {
+    match arg {
+        Scalar::Text(text) => text.as_str(),
+        _ => unreachable!("text argument to be checked when function is resolved"),
+    }
+}
+
+fn integer(arg: &Scalar) -> i64 {
+    match arg {
+        Scalar::Integer(value) => *value,
+        Scalar::Float(value) => value.round() as i64,
+        _ => unreachable!("numeric argument to be checked when function is resolved"),
+    }
+}
+
+fn oid(arg: &Scalar) -> Result<u32, QueryError> {
+    let value = integer(arg);
+    u32::try_from(value)
+        .map_err(|_| QueryError::invalid_parameter_value(format!("value \"{}\" is out of range for type oid", value)))
+}
+
+fn descriptor(arg: &Scalar) -> Result<i32, QueryError> {
+    i32::try_from(integer(arg)).map_err(|_| QueryError::value_out_of_range(PostgreSqlType::Integer))
+}
+
+/// Bytes of `bytea` text in hex format, e.g. `\x0aff`, or in escape format
+/// where `\\` is a backslash and `\nnn` is an octal byte value
+pub(crate) fn bytea_input(text: &str) -> Result<Vec<u8>, QueryError> {
+    let invalid = || QueryError::datatype_mismatch("invalid input syntax for type bytea".to_owned());
+    if let Some(hex) = text.strip_prefix("\\x") {
+        if hex.len() % 2 != 0 {
+            return Err(QueryError::datatype_mismatch(
+                "invalid hexadecimal data: odd number of digits".to_owned(),
+            ));
+        }
+        return (0..hex.len())
+            .step_by(2)
+            .map(|index| {
+                hex.get(index..index + 2)
+                    .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+                    .ok_or_else(invalid)
+            })
+            .collect();
+    }
+    let mut bytes = vec![];
+    let mut rest = text.as_bytes();
+    while let Some((byte, tail)) = rest.split_first() {
+        match (byte, tail) {
+            (b'\\', [b'\\', tail @ ..]) => {
+                bytes.push(b'\\');
+                rest = tail;
+            }
+            (b'\\', [high @ b'0'..=b'3', middle @ b'0'..=b'7', low @ b'0'..=b'7', tail @ ..]) => {
+                bytes.push((high - b'0') * 64 + (middle - b'0') * 8 + (low - b'0'));
+                rest = tail;
+            }
+            (b'\\', _) => return Err(invalid()),
+            (byte, tail) => {
+                bytes.push(*byte);
+                rest = tail;
+            }
+        }
+    }
+    Ok(bytes)
+}
+
+/// Hex format of `bytea`
+pub(crate) fn bytea_output(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(2 + bytes.len() * 2);
+    text.push_str("\\x");
+    for byte in bytes {
+        text.push_str(&format!("{:02x}", byte));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bytea_formats() {
+        assert_eq!(bytea_input("\\x48656C6c6f"), Ok(b"Hello".to_vec()));
+        assert_eq!(bytea_input("a\\\\b\\000\\377"), Ok(vec![b'a', b'\\', b'b', 0, 255]));
+        assert_eq!(bytea_output(&[0, 10, 255]), "\\x000aff");
+        assert_eq!(
+            bytea_input("\\x123"),
+            Err(QueryError::datatype_mismatch(
+                "invalid hexadecimal data: odd number of digits".to_owned()
+            ))
+        );
+        assert_eq!(
+            bytea_input("\\xzz"),
+            Err(QueryError::datatype_mismatch(
+                "invalid input syntax for type bytea".to_owned()
+            ))
+        );
+        assert_eq!(
+            bytea_input("\\9"),
+            Err(QueryError::datatype_mismatch(
+                "invalid input syntax for type bytea".to_owned()
+            ))
+        );
+    }
+}
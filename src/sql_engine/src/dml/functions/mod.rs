@@ -0,0 +1,354 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Scalar functions that are called in select lists and conditions. A
+//! function is registered with types of its parameters and of its result,
+//! functions with the same name and different parameters overload each
+//! other. Built-in functions are registered when the registry is created,
+//! functions of the procedural language when they are created or loaded.
+//! Built-in functions are strict, they return NULL if any argument is NULL
+//! without being called. Functions of the procedural language are called
+//! with NULL arguments too. Volatile functions, e.g. `txid_current()` or
+//! functions of the procedural language, can return a different result or
+//! change data each time they are called, so results of statements that
+//! call them are not cached.
+use crate::{cancellation::CancellationToken, dml::predicate::Scalar, session::LargeObjectDescriptors};
+use protocol::results::QueryError;
+use sql_types::SqlType;
+use sqlparser::ast::ObjectName;
+use std::{
+    collections::HashMap,
+    fmt::{self, Debug, Formatter},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+};
+
+pub(crate) mod large_objects;
+mod string;
+pub(crate) mod system;
+pub(crate) mod text_search;
+
+/// Schema of built-in functions, their names can be qualified with it
+const CATALOG_SCHEMA: &str = "pg_catalog";
+
+/// How deep calls of functions can be nested, e.g. by recursion. Sessions
+/// run on threads with the default stack size which has to fit the calls.
+const MAX_DEPTH: usize = 32;
+
+/// Computes the result from values of arguments that are not NULL, other
+/// functions are called through the functions of the calling statement
+pub(crate) type Call = dyn Fn(&ScalarFunctions, &[Scalar]) -> Result<Scalar, QueryError> + Send + Sync;
+
+/// Registered overload of a function with types of its parameters
+struct Overload {
+    name: String,
+    params: Vec<SqlType>,
+    returns: SqlType,
+    strict: bool,
+    volatile: bool,
+    call: Box<Call>,
+}
+
+/// Overload of a function that a statement calls
+pub(crate) struct ScalarFunction {
+    overload: Arc<Overload>,
+    functions: ScalarFunctions,
+}
+
+impl ScalarFunction {
+    pub(crate) fn params(&self) -> &[SqlType] {
+        &self.overload.params
+    }
+
+    pub(crate) fn returns(&self) -> SqlType {
+        self.overload.returns
+    }
+
+    pub(crate) fn is_volatile(&self) -> bool {
+        self.overload.volatile
+    }
+
+    pub(crate) fn call(&self, args: &[Scalar]) -> Result<Scalar, QueryError> {
+        if self.overload.volatile {
+            self.functions.volatile_called.store(true, Ordering::SeqCst);
+        }
+        if self.overload.strict && args.contains(&Scalar::Null) {
+            Ok(Scalar::Null)
+        } else {
+            (self.overload.call)(&self.functions, args)
+        }
+    }
+}
+
+impl Debug for ScalarFunction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScalarFunction")
+            .field("name", &self.overload.name)
+            .field("params", &self.overload.params)
+            .finish()
+    }
+}
+
+impl PartialEq for ScalarFunction {
+    fn eq(&self, other: &Self) -> bool {
+        self.overload.name == other.overload.name && self.overload.params == other.overload.params
+    }
+}
+
+/// Scalar functions by their names. Clones share registered functions,
+/// `for_statement` gives a clone whose functions are interrupted with the
+/// statement that calls them and use large objects of its session.
+#[derive(Clone)]
+pub(crate) struct ScalarFunctions {
+    functions: Arc<RwLock<HashMap<String, Vec<Arc<Overload>>>>>,
+    cancellation: CancellationToken,
+    large_objects: LargeObjectDescriptors,
+    /// whether the statement called a volatile function
+    volatile_called: Arc<AtomicBool>,
+    /// number of calls that the calls of these functions are nested in
+    depth: usize,
+}
+
+impl Default for ScalarFunctions {
+    fn default() -> ScalarFunctions {
+        let functions = ScalarFunctions {
+            functions: Arc::default(),
+            cancellation: CancellationToken::default(),
+            large_objects: LargeObjectDescriptors::default(),
+            volatile_called: Arc::default(),
+            depth: 0,
+        };
+        string::register(&functions);
+        text_search::register(&functions);
+        functions
+    }
+}
+
+impl ScalarFunctions {
+    /// Functions that are called by a statement which is interrupted with
+    /// `cancellation` and whose session opened `large_objects`
+    pub(crate) fn for_statement(
+        &self,
+        cancellation: &CancellationToken,
+        large_objects: &LargeObjectDescriptors,
+    ) -> ScalarFunctions {
+        ScalarFunctions {
+            functions: self.functions.clone(),
+            cancellation: cancellation.clone(),
+            large_objects: large_objects.clone(),
+            volatile_called: Arc::default(),
+            depth: 0,
+        }
+    }
+
+    /// Token of the statement that calls the functions
+    pub(crate) fn cancellation(&self) -> &CancellationToken {
+        &self.cancellation
+    }
+
+    /// Large objects opened by the session of the statement
+    pub(crate) fn large_objects(&self) -> &LargeObjectDescriptors {
+        &self.large_objects
+    }
+
+    /// Whether the statement called a volatile function, its result can't
+    /// be reused then
+    pub(crate) fn volatile_called(&self) -> bool {
+        self.volatile_called.load(Ordering::SeqCst)
+    }
+
+    /// Functions that a function calls, nesting is limited to stop unbounded
+    /// recursion before it overflows the stack
+    pub(crate) fn nested(&self) -> Result<ScalarFunctions, QueryError> {
+        if self.depth >= MAX_DEPTH {
+            return Err(QueryError::stack_depth_limit_exceeded(self.depth));
+        }
+        Ok(ScalarFunctions {
+            depth: self.depth + 1,
+            ..self.clone()
+        })
+    }
+
+    /// Registers strict overload of function `name` that accepts arguments
+    /// of `params` types and returns a value of `returns` type, it replaces
+    /// an overload with the same parameters
+    pub(crate) fn register(
+        &self,
+        name: &str,
+        params: &[SqlType],
+        returns: SqlType,
+        call: impl Fn(&ScalarFunctions, &[Scalar]) -> Result<Scalar, QueryError> + Send + Sync + 'static,
+    ) {
+        self.add(name, params, returns, true, false, Box::new(call))
+    }
+
+    /// Registers strict overload of volatile function `name` like `register`
+    pub(crate) fn register_volatile(
+        &self,
+        name: &str,
+        params: &[SqlType],
+        returns: SqlType,
+        call: impl Fn(&ScalarFunctions, &[Scalar]) -> Result<Scalar, QueryError> + Send + Sync + 'static,
+    ) {
+        self.add(name, params, returns, true, true, Box::new(call))
+    }
+
+    /// Registers overload of volatile function `name` like `register`, the
+    /// overload is called with NULL arguments too
+    pub(crate) fn register_called_on_null(
+        &self,
+        name: &str,
+        params: &[SqlType],
+        returns: SqlType,
+        call: impl Fn(&ScalarFunctions, &[Scalar]) -> Result<Scalar, QueryError> + Send + Sync + 'static,
+    ) {
+        self.add(name, params, returns, false, true, Box::new(call))
+    }
+
+    fn add(&self, name: &str, params: &[SqlType], returns: SqlType, strict: bool, volatile: bool, call: Box<Call>) {
+        let overload = Arc::new(Overload {
+            name: name.to_lowercase(),
+            params: params.to_vec(),
+            returns,
+            strict,
+            volatile,
+            call,
+        });
+        let mut functions = self.functions.write().expect("to acquire write lock");
+        let overloads = functions.entry(overload.name.clone()).or_default();
+        overloads.retain(|registered| registered.params != overload.params);
+        overloads.push(overload);
+    }
+
+    /// Removes overload of function `name` with `params` types
+    pub(crate) fn unregister(&self, name: &str, params: &[SqlType]) {
+        let name = name.to_lowercase();
+        let mut functions = self.functions.write().expect("to acquire write lock");
+        if let Some(overloads) = functions.get_mut(&name) {
+            overloads.retain(|registered| registered.params != params);
+            if overloads.is_empty() {
+                functions.remove(&name);
+            }
+        }
+    }
+
+    /// Overloads of function `name` called by the statement of these
+    /// functions, empty if there is no such function
+    pub(crate) fn overloads(&self, name: &str) -> Vec<ScalarFunction> {
+        self.functions
+            .read()
+            .expect("to acquire read lock")
+            .get(&name.to_lowercase())
+            .map(|overloads| {
+                overloads
+                    .iter()
+                    .map(|overload| ScalarFunction {
+                        overload: overload.clone(),
+                        functions: self.clone(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Name of the function that a call refers to, names of built-in functions
+/// can be qualified with the `pg_catalog` schema
+pub(crate) fn function_name(name: &ObjectName) -> Option<String> {
+    match name.0.as_slice() {
+        [name] => Some(name.value.to_lowercase()),
+        [schema, name] if schema.value.to_lowercase() == CATALOG_SCHEMA => Some(name.value.to_lowercase()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strict_functions_are_not_called_with_null() {
+        let functions = ScalarFunctions::default();
+        functions.register(
+            "fail",
+            &[SqlType::Integer(i32::MIN)],
+            SqlType::Bool,
+            |_functions, _args| Err(QueryError::division_by_zero()),
+        );
+        let fail = functions.overloads("FAIL").pop().expect("registered function");
+
+        assert_eq!(fail.call(&[Scalar::Null]), Ok(Scalar::Null));
+        assert_eq!(fail.call(&[Scalar::Integer(1)]), Err(QueryError::division_by_zero()));
+    }
+
+    #[test]
+    fn functions_called_on_null() {
+        let functions = ScalarFunctions::default();
+        functions.register_called_on_null("is_missing", &[SqlType::Bool], SqlType::Bool, |_functions, args| {
+            Ok(Scalar::Bool(args[0] == Scalar::Null))
+        });
+        let is_missing = functions.overloads("is_missing").pop().expect("registered function");
+
+        assert_eq!(is_missing.call(&[Scalar::Null]), Ok(Scalar::Bool(true)));
+        assert_eq!(is_missing.call(&[Scalar::Bool(false)]), Ok(Scalar::Bool(false)));
+    }
+
+    #[test]
+    fn overloads_with_same_parameters_are_replaced() {
+        let functions = ScalarFunctions::default();
+        functions.register("f", &[SqlType::Bool], SqlType::Bool, |_functions, _args| {
+            Ok(Scalar::Bool(true))
+        });
+        functions.register("f", &[SqlType::Bool], SqlType::Bool, |_functions, _args| {
+            Ok(Scalar::Bool(false))
+        });
+        functions.register("f", &[], SqlType::Bool, |_functions, _args| Ok(Scalar::Null));
+
+        let overloads = functions.overloads("f");
+        assert_eq!(overloads.len(), 2);
+        assert_eq!(overloads[0].call(&[Scalar::Bool(true)]), Ok(Scalar::Bool(false)));
+        assert!(functions.overloads("g").is_empty());
+    }
+
+    #[test]
+    fn unregistered_overloads_are_not_called() {
+        let functions = ScalarFunctions::default();
+        functions.register("f", &[SqlType::Bool], SqlType::Bool, |_functions, _args| {
+            Ok(Scalar::Bool(true))
+        });
+        functions.register("f", &[], SqlType::Bool, |_functions, _args| Ok(Scalar::Null));
+        functions.unregister("F", &[SqlType::Bool]);
+
+        let overloads = functions.overloads("f");
+        assert_eq!(overloads.len(), 1);
+        assert!(overloads[0].params().is_empty());
+        functions.unregister("f", &[]);
+        assert!(functions.overloads("f").is_empty());
+    }
+
+    #[test]
+    fn nesting_of_calls_is_limited() {
+        let mut functions = ScalarFunctions::default();
+        for _ in 0..MAX_DEPTH {
+            functions = functions.nested().expect("nested functions");
+        }
+
+        assert_eq!(
+            functions.nested().map(|_nested| ()),
+            Err(QueryError::stack_depth_limit_exceeded(MAX_DEPTH))
+        );
+    }
+}
@@ -0,0 +1,141 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `upper`, `lower`, `length`, `substring` and `trim` of text values
+use crate::dml::{functions::ScalarFunctions, predicate::Scalar};
+use protocol::results::QueryError;
+use sql_types::SqlType;
+
+const TEXT: SqlType = SqlType::VarChar(u64::MAX);
+const INTEGER: SqlType = SqlType::Integer(i32::MIN);
+
+pub(crate) fn register(functions: &ScalarFunctions) {
+    functions.register("upper", &[TEXT], TEXT, |_functions, args| {
+        Ok(Scalar::Text(text(&args[0]).to_uppercase()))
+    });
+    functions.register("lower", &[TEXT], TEXT, |_functions, args| {
+        Ok(Scalar::Text(text(&args[0]).to_lowercase()))
+    });
+    functions.register("length", &[TEXT], INTEGER, |_functions, args| {
+        Ok(Scalar::Integer(text(&args[0]).chars().count() as i64))
+    });
+    functions.register("substring", &[TEXT, INTEGER], TEXT, |_functions, args| {
+        substring(text(&args[0]), integer(&args[1]), None)
+    });
+    functions.register("substring", &[TEXT, INTEGER, INTEGER], TEXT, |_functions, args| {
+        substring(text(&args[0]), integer(&args[1]), Some(integer(&args[2])))
+    });
+    functions.register("trim", &[TEXT], TEXT, |_functions, args| Ok(trim(text(&args[0]), " ")));
+    functions.register("trim", &[TEXT, TEXT], TEXT, |_functions, args| {
+        Ok(trim(text(&args[0]), text(&args[1])))
+    });
+}
+
+fn text(arg: &Scalar) -> &str {
+    match arg {
+        Scalar::Text(text) => text.as_str(),
+        _ => unreachable!("text argument to be checked when function is resolved"),
+    }
+}
+
+fn integer(arg: &Scalar) -> i64 {
+    match arg {
+        Scalar::Integer(value) => *value,
+        Scalar::Float(value) => value.round() as i64,
+        _ => unreachable!("numeric argument to be checked when function is resolved"),
+    }
+}
+
+/// Characters of `text` from position `start` counting from 1, `count` of
+/// them or all till the end. Positions before the first character count
+/// towards `count` too.
+fn substring(text: &str, start: i64, count: Option<i64>) -> Result<Scalar, QueryError> {
+    let end = match count {
+        Some(count) if count < 0 => return Err(QueryError::substring_error()),
+        Some(count) => start.saturating_add(count),
+        None => i64::MAX,
+    };
+    let start = start.max(1);
+    let taken = end.saturating_sub(start).max(0) as usize;
+    Ok(Scalar::Text(
+        text.chars().skip(start as usize - 1).take(taken).collect(),
+    ))
+}
+
+/// Removes the longest prefix and suffix of `text` that consist of
+/// `characters` only
+fn trim(text: &str, characters: &str) -> Scalar {
+    Scalar::Text(text.trim_matches(|c| characters.contains(c)).to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(name: &str, args: &[Scalar]) -> Result<Scalar, QueryError> {
+        let functions = ScalarFunctions::default();
+        let function = functions
+            .overloads(name)
+            .into_iter()
+            .find(|function| function.params().len() == args.len())
+            .expect("registered function");
+        function.call(args)
+    }
+
+    fn text(text: &str) -> Scalar {
+        Scalar::Text(text.to_owned())
+    }
+
+    #[test]
+    fn change_case() {
+        assert_eq!(call("upper", &[text("Straße")]), Ok(text("STRASSE")));
+        assert_eq!(call("lower", &[text("ÀBC")]), Ok(text("àbc")));
+    }
+
+    #[test]
+    fn length_in_characters() {
+        assert_eq!(call("length", &[text("añb")]), Ok(Scalar::Integer(3)));
+        assert_eq!(call("length", &[text("")]), Ok(Scalar::Integer(0)));
+    }
+
+    #[test]
+    fn substrings() {
+        assert_eq!(
+            call("substring", &[text("abcdef"), Scalar::Integer(3)]),
+            Ok(text("cdef"))
+        );
+        assert_eq!(
+            call("substring", &[text("abcdef"), Scalar::Integer(2), Scalar::Integer(3)]),
+            Ok(text("bcd"))
+        );
+        assert_eq!(
+            call("substring", &[text("abcdef"), Scalar::Integer(-1), Scalar::Integer(4)]),
+            Ok(text("ab"))
+        );
+        assert_eq!(
+            call("substring", &[text("abcdef"), Scalar::Integer(10), Scalar::Integer(2)]),
+            Ok(text(""))
+        );
+        assert_eq!(
+            call("substring", &[text("abcdef"), Scalar::Integer(1), Scalar::Integer(-1)]),
+            Err(QueryError::substring_error())
+        );
+    }
+
+    #[test]
+    fn trimmed_characters() {
+        assert_eq!(call("trim", &[text("  a b  ")]), Ok(text("a b")));
+        assert_eq!(call("trim", &[text("xyaxy"), text("yx")]), Ok(text("a")));
+    }
+}
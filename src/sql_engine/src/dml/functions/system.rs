@@ -0,0 +1,147 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Functions that health checks and test suites call, e.g.
+//! `SELECT pg_is_in_recovery()` or `SELECT pg_sleep(0.5)`
+use crate::dml::{functions::ScalarFunctions, predicate::Scalar};
+use protocol::results::QueryError;
+use sql_types::SqlType;
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+pub(crate) fn register(functions: &ScalarFunctions, transaction_ids: Arc<AtomicU64>, started_at: SystemTime) {
+    functions.register_volatile(
+        "pg_sleep",
+        &[SqlType::DoublePrecision],
+        SqlType::Void,
+        |functions, args| {
+            let seconds = match args[0] {
+                Scalar::Integer(seconds) => seconds as f64,
+                Scalar::Float(seconds) => seconds,
+                _ => unreachable!("numeric argument to be checked when function is resolved"),
+            };
+            sleep(seconds, functions).map(|()| Scalar::Text("".to_owned()))
+        },
+    );
+    functions.register_volatile(
+        "txid_current",
+        &[],
+        SqlType::BigInt(i64::MIN),
+        move |_functions, _args| Ok(Scalar::Integer(transaction_ids.fetch_add(1, Ordering::SeqCst) as i64)),
+    );
+    functions.register(
+        "pg_postmaster_start_time",
+        &[],
+        SqlType::TimestampWithTimeZone,
+        move |_functions, _args| Ok(Scalar::Text(timestamp_with_time_zone(started_at))),
+    );
+    functions.register("pg_is_in_recovery", &[], SqlType::Bool, |_functions, _args| {
+        Ok(Scalar::Bool(false))
+    });
+}
+
+/// Sleeps for fractional `seconds`, cancellation of the statement wakes it
+/// up
+fn sleep(seconds: f64, functions: &ScalarFunctions) -> Result<(), QueryError> {
+    if seconds < 0.0 {
+        return Err(QueryError::invalid_parameter_value(format!(
+            "pg_sleep duration must not be negative: {}",
+            seconds
+        )));
+    }
+    match Duration::try_from_secs_f64(seconds) {
+        Ok(duration) => functions.cancellation().sleep(duration),
+        Err(_) => Err(QueryError::invalid_parameter_value(format!(
+            "pg_sleep duration is out of range: {}",
+            seconds
+        ))),
+    }
+}
+
+/// Text representation of `time` as `timestamp with time zone` in UTC,
+/// e.g. `2020-09-13 12:26:40.123456+00`
+pub(crate) fn timestamp_with_time_zone(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = since_epoch.as_secs();
+    let (year, month, day) = civil_from_days((seconds / 86_400) as i64);
+    let seconds_of_day = seconds % 86_400;
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}.{:06}+00",
+        year,
+        month,
+        day,
+        seconds_of_day / 3_600,
+        seconds_of_day % 3_600 / 60,
+        seconds_of_day % 60,
+        since_epoch.subsec_micros()
+    )
+}
+
+/// Gregorian date of the day number `days` since 1970-01-01
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timestamps() {
+        assert_eq!(timestamp_with_time_zone(UNIX_EPOCH), "1970-01-01 00:00:00.000000+00");
+        assert_eq!(
+            timestamp_with_time_zone(UNIX_EPOCH + Duration::from_micros(1_600_000_000_123_456)),
+            "2020-09-13 12:26:40.123456+00"
+        );
+        assert_eq!(
+            timestamp_with_time_zone(UNIX_EPOCH + Duration::from_secs(951_782_400)),
+            "2000-02-29 00:00:00.000000+00"
+        );
+    }
+
+    #[test]
+    fn sleep_durations() {
+        let functions = ScalarFunctions::default();
+        register(&functions, Arc::default(), UNIX_EPOCH);
+        let pg_sleep = functions.overloads("pg_sleep").pop().expect("registered function");
+
+        assert_eq!(pg_sleep.call(&[Scalar::Float(0.001)]), Ok(Scalar::Text("".to_owned())));
+        assert_eq!(pg_sleep.call(&[Scalar::Null]), Ok(Scalar::Null));
+        assert_eq!(
+            pg_sleep.call(&[Scalar::Integer(-1)]),
+            Err(QueryError::invalid_parameter_value(
+                "pg_sleep duration must not be negative: -1".to_owned()
+            ))
+        );
+        assert!(functions.volatile_called());
+    }
+}
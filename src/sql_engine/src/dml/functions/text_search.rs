@@ -0,0 +1,92 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `to_tsvector` and `to_tsquery` of full-text search and `ts_match_vq`
+//! that the `@@` operator is turned into
+use crate::{
+    dml::{functions::ScalarFunctions, predicate::Scalar},
+    query::match_operator::MATCH_FUNCTION,
+};
+use protocol::results::QueryError;
+use sql_types::{
+    text_search::{TsQuery, TsVector},
+    SqlType,
+};
+
+const TEXT: SqlType = SqlType::VarChar(u64::MAX);
+
+pub(crate) const TO_TSVECTOR_FUNCTION: &str = "to_tsvector";
+const TO_TSQUERY_FUNCTION: &str = "to_tsquery";
+
+pub(crate) fn register(functions: &ScalarFunctions) {
+    functions.register(TO_TSVECTOR_FUNCTION, &[TEXT], SqlType::TsVector, |_functions, args| {
+        Ok(Scalar::Text(TsVector::from_document(text(&args[0])).to_string()))
+    });
+    functions.register(TO_TSQUERY_FUNCTION, &[TEXT], SqlType::TsQuery, |_functions, args| {
+        TsQuery::from_text(text(&args[0]))
+            .map(|query| Scalar::Text(query.to_string()))
+            .map_err(QueryError::datatype_mismatch)
+    });
+    functions.register(
+        MATCH_FUNCTION,
+        &[SqlType::TsVector, SqlType::TsQuery],
+        SqlType::Bool,
+        |_functions, args| match (TsVector::parse(text(&args[0])), TsQuery::parse(text(&args[1]))) {
+            (Ok(vector), Ok(query)) => Ok(Scalar::Bool(query.matches(&vector))),
+            (Err(message), _) | (_, Err(message)) => Err(QueryError::datatype_mismatch(message)),
+        },
+    );
+}
+
+fn text(arg: &Scalar) -> &str {
+    match arg {
+        Scalar::Text(text) => text.as_str(),
+        _ => unreachable!("text argument to be checked when function is resolved"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(name: &str, args: &[Scalar]) -> Result<Scalar, QueryError> {
+        let functions = ScalarFunctions::default();
+        let function = functions.overloads(name).pop().expect("registered function");
+        function.call(args)
+    }
+
+    #[test]
+    fn text_search_functions() {
+        let text = |text: &str| Scalar::Text(text.to_owned());
+        assert_eq!(
+            call(TO_TSVECTOR_FUNCTION, &[text("Fat cats, fat rats")]),
+            Ok(text("'cats':2 'fat':1,3 'rats':4"))
+        );
+        assert_eq!(
+            call(TO_TSQUERY_FUNCTION, &[text("Fat & !Cats")]),
+            Ok(text("'fat' & !'cats'"))
+        );
+        assert_eq!(call(TO_TSVECTOR_FUNCTION, &[Scalar::Null]), Ok(Scalar::Null));
+        assert_eq!(
+            call(TO_TSQUERY_FUNCTION, &[text("fat & | rats")]),
+            Err(QueryError::datatype_mismatch(
+                "syntax error in tsquery: \"fat & | rats\"".to_owned()
+            ))
+        );
+        assert_eq!(
+            call(MATCH_FUNCTION, &[text("'cats':2 'fat':1"), text("'fat' & !'rats'")]),
+            Ok(Scalar::Bool(true))
+        );
+    }
+}
@@ -12,8 +12,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::{catalog_manager::CatalogManager, dml::ExpressionEvaluation, query::plan::TableInserts};
-use kernel::SystemResult;
+use crate::{
+    catalog_manager::{CatalogManager, RowChange},
+    dml::{self, ExpressionEvaluation},
+    query::{plan::TableInserts, resolve::Scope},
+    transaction::Transaction,
+};
+use kernel::{SystemError, SystemResult};
 use protocol::{
     results::{QueryError, QueryEvent},
     Sender,
@@ -21,7 +26,7 @@ use protocol::{
 use representation::{Binary, Datum};
 use sql_types::ConstraintError;
 use sqlparser::ast::{DataType, Expr, Query, SetExpr, UnaryOperator, Value};
-use std::{convert::TryFrom, str::FromStr, sync::Arc};
+use std::{str::FromStr, sync::Arc};
 use storage::Row;
 
 pub(crate) struct InsertCommand<'ic> {
@@ -29,6 +34,7 @@ pub(crate) struct InsertCommand<'ic> {
     table_inserts: TableInserts,
     storage: Arc<CatalogManager>,
     session: Arc<dyn Sender>,
+    transaction: Arc<Transaction>,
 }
 
 impl<'ic> InsertCommand<'ic> {
@@ -37,12 +43,14 @@ impl<'ic> InsertCommand<'ic> {
         table_inserts: TableInserts,
         storage: Arc<CatalogManager>,
         session: Arc<dyn Sender>,
+        transaction: Arc<Transaction>,
     ) -> InsertCommand<'ic> {
         InsertCommand {
             raw_sql_query,
             table_inserts,
             storage,
             session,
+            transaction,
         }
     }
 
@@ -77,6 +85,7 @@ impl<'ic> InsertCommand<'ic> {
                             Expr::Value(value) => value.clone(),
                             Expr::Cast { expr, data_type } => match (&**expr, data_type) {
                                 (Expr::Value(Value::Boolean(v)), DataType::Boolean) => Value::Boolean(*v),
+                                (Expr::Value(Value::Null), _) => Value::Null,
                                 (Expr::Value(Value::SingleQuotedString(v)), DataType::Boolean) => {
                                     Value::Boolean(bool::from_str(v).unwrap())
                                 }
@@ -86,7 +95,7 @@ impl<'ic> InsertCommand<'ic> {
                                             "Cast from {:?} to {:?} is not currently supported",
                                             expr, data_type
                                         ))))
-                                        .expect("To Send Query Result to Client");
+                                        .map_err(SystemError::connection_lost)?;
                                     return Ok(());
                                 }
                             },
@@ -97,7 +106,7 @@ impl<'ic> InsertCommand<'ic> {
                                         .send(Err(QueryError::syntax_error(
                                             op.to_string() + expr.to_string().as_str(),
                                         )))
-                                        .expect("To Send Query Result to Client");
+                                        .map_err(SystemError::connection_lost)?;
                                     return Ok(());
                                 }
                             },
@@ -108,7 +117,7 @@ impl<'ic> InsertCommand<'ic> {
                             expr => {
                                 self.session
                                     .send(Err(QueryError::syntax_error(expr.to_string())))
-                                    .expect("To Send Query Result to Client");
+                                    .map_err(SystemError::connection_lost)?;
                                 return Ok(());
                             }
                         };
@@ -121,13 +130,20 @@ impl<'ic> InsertCommand<'ic> {
                     None => self
                         .session
                         .send(Err(QueryError::schema_does_not_exist(schema_name.to_owned())))
-                        .expect("To Send Result to Client"),
+                        .map_err(SystemError::connection_lost)?,
                     Some((_, None)) => self
                         .session
                         .send(Err(QueryError::table_does_not_exist(
                             schema_name.to_owned() + "." + table_name,
                         )))
-                        .expect("To Send Result to Client"),
+                        .map_err(SystemError::connection_lost)?,
+                    Some((_, Some(_))) if self.storage.is_foreign_table(schema_name, table_name) => self
+                        .session
+                        .send(Err(QueryError::cannot_change_foreign_table(
+                            "insert into",
+                            schema_name.to_owned() + "." + table_name,
+                        )))
+                        .map_err(SystemError::connection_lost)?,
                     Some((_, Some(_))) => {
                         let column_names = columns;
                         let all_columns = self.storage.table_columns(&schema_name, &table_name)?;
@@ -139,58 +155,46 @@ impl<'ic> InsertCommand<'ic> {
 
                             index_cols
                         } else {
-                            let mut index_cols = vec![];
-                            let mut non_existing_cols = vec![];
-                            for column_name in column_names {
-                                let mut found = None;
-                                for (index, column_definition) in all_columns.iter().enumerate() {
-                                    if column_definition.has_name(&column_name) {
-                                        found = Some((index, column_definition.clone()));
-                                        break;
-                                    }
-                                }
-
-                                match found {
-                                    Some(index_col) => index_cols.push(index_col),
-                                    None => non_existing_cols.push(column_name.clone()),
+                            match Scope::new(&all_columns).resolve_all(column_names.iter().map(String::as_str)) {
+                                Ok(indexes) => indexes
+                                    .into_iter()
+                                    .map(|index| (index, all_columns[index].clone()))
+                                    .collect(),
+                                Err(error) => {
+                                    self.session.send(Err(error)).map_err(SystemError::connection_lost)?;
+                                    return Ok(());
                                 }
                             }
-
-                            if !non_existing_cols.is_empty() {
-                                self.session
-                                    .send(Err(QueryError::column_does_not_exist(non_existing_cols)))
-                                    .expect("To Send Result to Client");
-                                return Ok(());
-                            }
-
-                            index_cols
                         };
 
                         let mut to_write: Vec<Row> = vec![];
                         let mut errors = Vec::new();
+                        let published = self.storage.publications().is_published(schema_name, table_name);
+                        let mut changes = vec![];
 
                         for (row_index, row) in rows.iter().enumerate() {
                             if row.len() > all_columns.len() {
                                 self.session
                                     .send(Err(QueryError::too_many_insert_expressions()))
-                                    .expect("To Send Result to Client");
+                                    .map_err(SystemError::connection_lost)?;
                                 return Ok(());
                             }
 
                             let key = self.storage.next_key_id().to_be_bytes().to_vec();
 
-                            // TODO: The default value or NULL should be initialized for SQL types of all columns.
-                            let mut record = vec![Datum::from_null(); all_columns.len()];
+                            let mut record = all_columns.iter().map(dml::default_datum).collect::<Vec<Datum>>();
                             for (item, (index, column_definition)) in row.iter().zip(index_columns.iter()) {
-                                let v = match item.clone() {
-                                    Value::Number(v) => v.to_string(),
-                                    Value::SingleQuotedString(v) => v.to_string(),
-                                    Value::Boolean(v) => v.to_string(),
+                                let constraint = column_definition.sql_type().constraint();
+                                let validation = match item.clone() {
+                                    Value::Number(v) => constraint.validate(v.to_string().as_str()),
+                                    Value::SingleQuotedString(v) => constraint.validate(v.as_str()),
+                                    Value::Boolean(v) => constraint.validate(v.to_string().as_str()),
+                                    Value::Null => Ok(()),
                                     _ => unimplemented!("other types not implemented"),
                                 };
-                                match column_definition.sql_type().constraint().validate(v.as_str()) {
+                                match validation {
                                     Ok(()) => {
-                                        record[*index] = Datum::try_from(item).unwrap();
+                                        record[*index] = dml::literal_datum(&column_definition.sql_type(), item);
                                     }
                                     Err(e) => {
                                         errors.push((e, column_definition.clone()));
@@ -222,19 +226,33 @@ impl<'ic> InsertCommand<'ic> {
                                     };
                                     self.session
                                         .send(Err(error_to_send))
-                                        .expect("To Send Query Result to Client");
+                                        .map_err(SystemError::connection_lost)?;
                                 }
                                 return Ok(());
                             }
+                            if let Some(error) = dml::null_violation(&all_columns, &record) {
+                                self.session.send(Err(error)).map_err(SystemError::connection_lost)?;
+                                return Ok(());
+                            }
+                            if published {
+                                changes.push(RowChange::insert(schema_name, table_name, &record));
+                            }
                             to_write.push((Binary::with_data(key), Binary::pack(&record)));
                         }
 
-                        match self.storage.write_into(&schema_name, &table_name, to_write) {
+                        match self
+                            .transaction
+                            .write_into(&self.storage, &schema_name, &table_name, to_write)
+                        {
                             Err(error) => return Err(error),
-                            Ok(size) => self
-                                .session
-                                .send(Ok(QueryEvent::RecordsInserted(size)))
-                                .expect("To Send Result to Client"),
+                            Ok(size) => {
+                                self.storage.statistics().inserted(schema_name, table_name, size);
+                                self.transaction
+                                    .publish(&self.storage, schema_name, table_name, changes)?;
+                                self.session
+                                    .send(Ok(QueryEvent::RecordsInserted(size)))
+                                    .map_err(SystemError::connection_lost)?
+                            }
                         }
                     }
                 }
@@ -243,7 +261,7 @@ impl<'ic> InsertCommand<'ic> {
             _ => {
                 self.session
                     .send(Err(QueryError::feature_not_supported(self.raw_sql_query.to_owned())))
-                    .expect("To Send Query Result to Client");
+                    .map_err(SystemError::connection_lost)?;
                 Ok(())
             }
         }
@@ -0,0 +1,316 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Nested loop join of table scans. Rows of the inner relation are read
+//! once and kept in memory, each row of the outer relation is combined with
+//! all of them. Values of a combined row are values of the outer row
+//! followed by values of the inner row. Outer joins extend rows without a
+//! match on the other side with NULLs.
+use crate::{
+    catalog_manager::storage_error,
+    dml::{memory::WorkMem, predicate::Predicate},
+};
+use kernel::{SystemError, SystemResult};
+use representation::{unpack_raw, Binary, Datum};
+use std::collections::VecDeque;
+use storage::{ReadCursor, Row, RowResult};
+
+/// Rows of the joined relations a join keeps even without a match
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum JoinKind {
+    Inner,
+    Left,
+    Right,
+    Full,
+}
+
+impl JoinKind {
+    /// Whether rows of the preceding relations are kept without a match,
+    /// columns of the joined relation can be NULL then
+    pub(crate) fn keeps_outer(self) -> bool {
+        matches!(self, JoinKind::Left | JoinKind::Full)
+    }
+
+    /// Whether rows of the joined relation are kept without a match,
+    /// columns of the preceding relations can be NULL then
+    pub(crate) fn keeps_inner(self) -> bool {
+        matches!(self, JoinKind::Right | JoinKind::Full)
+    }
+}
+
+/// Reads all rows of the inner relation, fails with `Ok(Err(()))` if they
+/// exceed `work_mem`
+pub(crate) fn materialize(cursor: ReadCursor, work_mem: &mut WorkMem) -> SystemResult<Result<Vec<Row>, ()>> {
+    let mut rows = vec![];
+    for row in cursor {
+        match row {
+            Ok(Ok((key, values))) => {
+                if work_mem
+                    .allocate(key.to_bytes().len() + values.to_bytes().len())
+                    .is_err()
+                {
+                    return Ok(Err(()));
+                }
+                rows.push((key, values));
+            }
+            Ok(Err(error)) => return Err(storage_error(error)),
+            Err(error) => return Err(SystemError::io(error)),
+        }
+    }
+    Ok(Ok(rows))
+}
+
+/// Combines rows of `outer` with `inner` rows for which `condition` holds,
+/// all of them without a condition. Rows of `outer` have `outer_width`
+/// columns and `inner` rows have `inner_width` columns. Errors of `outer` are
+/// passed through.
+pub(crate) fn nested_loop(
+    outer: ReadCursor,
+    inner: Vec<Row>,
+    kind: JoinKind,
+    condition: Option<Predicate>,
+    outer_width: usize,
+    inner_width: usize,
+) -> ReadCursor {
+    Box::new(NestedLoop {
+        outer,
+        matched: vec![false; inner.len()],
+        inner,
+        kind,
+        condition,
+        outer_nulls: Binary::pack(&vec![Datum::from_null(); outer_width]),
+        inner_nulls: Binary::pack(&vec![Datum::from_null(); inner_width]),
+        pending: VecDeque::new(),
+        unmatched: None,
+    })
+}
+
+struct NestedLoop {
+    outer: ReadCursor,
+    inner: Vec<Row>,
+    kind: JoinKind,
+    condition: Option<Predicate>,
+    /// inner rows that matched at least one outer row
+    matched: Vec<bool>,
+    outer_nulls: Binary,
+    inner_nulls: Binary,
+    /// combined rows of the current outer row
+    pending: VecDeque<Row>,
+    /// position of the next inner row to check for a match once all outer
+    /// rows are joined
+    unmatched: Option<usize>,
+}
+
+impl NestedLoop {
+    fn join(&mut self, key: Binary, values: Binary) {
+        let mut found = false;
+        for (index, (inner_key, inner_values)) in self.inner.iter().enumerate() {
+            let combined = concat(&values, inner_values);
+            let holds = match &self.condition {
+                Some(condition) => condition.holds(&unpack_raw(combined.to_bytes())),
+                None => true,
+            };
+            if holds {
+                found = true;
+                self.matched[index] = true;
+                self.pending.push_back((concat(&key, inner_key), combined));
+            }
+        }
+        if !found && self.kind.keeps_outer() {
+            let combined = concat(&values, &self.inner_nulls);
+            self.pending.push_back((key, combined));
+        }
+    }
+}
+
+impl Iterator for NestedLoop {
+    type Item = RowResult;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(row) = self.pending.pop_front() {
+                return Some(Ok(Ok(row)));
+            }
+            if self.unmatched.is_some() {
+                break;
+            }
+            match self.outer.next() {
+                Some(Ok(Ok((key, values)))) => self.join(key, values),
+                Some(error) => return Some(error),
+                None => self.unmatched = Some(0),
+            }
+        }
+        if !self.kind.keeps_inner() {
+            return None;
+        }
+        let start = self.unmatched.unwrap_or_default();
+        let position = (start..self.inner.len()).find(|index| !self.matched[*index])?;
+        self.unmatched = Some(position + 1);
+        let (key, values) = &self.inner[position];
+        Some(Ok(Ok((key.clone(), concat(&self.outer_nulls, values)))))
+    }
+}
+
+fn concat(left: &Binary, right: &Binary) -> Binary {
+    Binary::with_data([left.to_bytes(), right.to_bytes()].concat())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dml::functions::ScalarFunctions;
+    use sql_types::SqlType;
+    use sqlparser::ast::{BinaryOperator, Expr, Ident};
+    use std::io;
+
+    fn cursor(values: &[i16]) -> ReadCursor {
+        Box::new(
+            rows(values)
+                .into_iter()
+                .map(|row| Ok(Ok(row)))
+                .collect::<Vec<RowResult>>()
+                .into_iter(),
+        )
+    }
+
+    fn rows(values: &[i16]) -> Vec<Row> {
+        values
+            .iter()
+            .map(|value| {
+                let row = Binary::pack(&[Datum::from_i16(*value)]);
+                (row.clone(), row)
+            })
+            .collect()
+    }
+
+    fn values(cursor: ReadCursor) -> Vec<Vec<Option<i16>>> {
+        cursor
+            .map(|row| {
+                let (_key, values) = row.expect("no io error").expect("no storage error");
+                unpack_raw(values.to_bytes())
+                    .into_iter()
+                    .map(|datum| match datum {
+                        Datum::Null => None,
+                        datum => Some(datum.as_i16()),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// `outer = inner` condition of rows with a column of each relation
+    fn equal() -> Option<Predicate> {
+        let types = vec![SqlType::SmallInt(i16::MIN), SqlType::SmallInt(i16::MIN)];
+        let expr = Expr::BinaryOp {
+            left: Box::new(Expr::Identifier(Ident::new("outer"))),
+            op: BinaryOperator::Eq,
+            right: Box::new(Expr::Identifier(Ident::new("inner"))),
+        };
+        let resolve = |expr: &Expr| {
+            Ok(Some(if *expr == Expr::Identifier(Ident::new("outer")) {
+                0
+            } else {
+                1
+            }))
+        };
+        Some(
+            Predicate::compile(&expr, "JOIN/ON", &types, &ScalarFunctions::default(), None, &resolve)
+                .expect("compiled condition"),
+        )
+    }
+
+    fn join(kind: JoinKind, condition: Option<Predicate>) -> Vec<Vec<Option<i16>>> {
+        values(nested_loop(cursor(&[1, 2]), rows(&[2, 3]), kind, condition, 1, 1))
+    }
+
+    #[test]
+    fn combines_each_outer_row_with_each_inner_row() {
+        assert_eq!(
+            join(JoinKind::Inner, None),
+            vec![
+                vec![Some(1), Some(2)],
+                vec![Some(1), Some(3)],
+                vec![Some(2), Some(2)],
+                vec![Some(2), Some(3)],
+            ]
+        );
+    }
+
+    #[test]
+    fn inner_join() {
+        assert_eq!(join(JoinKind::Inner, equal()), vec![vec![Some(2), Some(2)]]);
+    }
+
+    #[test]
+    fn left_join() {
+        assert_eq!(
+            join(JoinKind::Left, equal()),
+            vec![vec![Some(1), None], vec![Some(2), Some(2)]]
+        );
+    }
+
+    #[test]
+    fn right_join() {
+        assert_eq!(
+            join(JoinKind::Right, equal()),
+            vec![vec![Some(2), Some(2)], vec![None, Some(3)]]
+        );
+    }
+
+    #[test]
+    fn full_join() {
+        assert_eq!(
+            join(JoinKind::Full, equal()),
+            vec![vec![Some(1), None], vec![Some(2), Some(2)], vec![None, Some(3)]]
+        );
+    }
+
+    #[test]
+    fn empty_inner_relation() {
+        assert_eq!(
+            values(nested_loop(cursor(&[1, 2]), vec![], JoinKind::Inner, None, 1, 1)),
+            Vec::<Vec<Option<i16>>>::new()
+        );
+        assert_eq!(
+            values(nested_loop(cursor(&[1]), vec![], JoinKind::Left, equal(), 1, 1)),
+            vec![vec![Some(1), None]]
+        );
+    }
+
+    #[test]
+    fn errors_of_outer_relation_are_passed_through() {
+        let outer: ReadCursor = Box::new(vec![Err(io::Error::other("failed"))].into_iter());
+
+        assert_eq!(
+            nested_loop(outer, rows(&[1]), JoinKind::Inner, None, 1, 1)
+                .filter(Result::is_err)
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn inner_relation_exceeds_work_mem() {
+        let mut work_mem = WorkMem::new(0);
+
+        assert!(matches!(materialize(cursor(&[1]), &mut work_mem), Ok(Err(()))));
+    }
+
+    #[test]
+    fn materialized_inner_relation() {
+        let mut work_mem = WorkMem::new(1);
+
+        assert!(matches!(materialize(cursor(&[1, 2]), &mut work_mem), Ok(Ok(rows)) if rows.len() == 2));
+    }
+}
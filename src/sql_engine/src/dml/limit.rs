@@ -0,0 +1,361 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `LIMIT`, `FETCH FIRST` and `OFFSET` clauses of queries. Their values are
+//! evaluated once before the query is executed and can't refer to columns.
+//! Rows are counted when they are sent, so the clauses apply alike to rows
+//! of scans, aggregations and set operations. Executions of portals with a
+//! row limit split rows the same way.
+use crate::dml::{functions::ScalarFunctions, predicate::Predicate};
+use protocol::{
+    results::{QueryError, QueryEvent, QueryResult},
+    Sender,
+};
+use sqlparser::ast::{Expr, Query};
+use std::{
+    io, mem,
+    sync::{Arc, Mutex},
+};
+
+/// Rows that are skipped and the number of rows that are selected after them
+#[derive(Debug, PartialEq)]
+pub(crate) struct RowLimit {
+    offset: usize,
+    count: Option<usize>,
+}
+
+impl RowLimit {
+    /// Limit of `query`, `None` if the query selects all of its rows
+    pub(crate) fn of(query: &Query, functions: &ScalarFunctions) -> Result<Option<RowLimit>, QueryError> {
+        let count = match (&query.limit, &query.fetch) {
+            (Some(_), Some(fetch)) => return Err(QueryError::syntax_error(fetch.to_string())),
+            (Some(limit), None) => row_count(limit, functions)?,
+            (None, Some(fetch)) if fetch.percent || fetch.with_ties => {
+                return Err(QueryError::feature_not_supported(fetch.to_string()))
+            }
+            (None, Some(fetch)) => match &fetch.quantity {
+                Some(quantity) => row_count(quantity, functions)?,
+                None => Some(1),
+            },
+            (None, None) => None,
+        };
+        if matches!(count, Some(count) if count < 0) {
+            return Err(QueryError::invalid_row_count_in_limit_clause());
+        }
+        let offset = match &query.offset {
+            Some(offset) => row_count(&offset.value, functions)?.unwrap_or_default(),
+            None => 0,
+        };
+        if offset < 0 {
+            return Err(QueryError::invalid_row_count_in_result_offset_clause());
+        }
+        if count.is_none() && offset == 0 {
+            return Ok(None);
+        }
+        Ok(Some(RowLimit {
+            offset: offset as usize,
+            count: count.map(|count| count as usize),
+        }))
+    }
+}
+
+/// Value of a row count expression, `None` for NULL. Numbers with a
+/// fractional part are rounded.
+fn row_count(expr: &Expr, functions: &ScalarFunctions) -> Result<Option<i64>, QueryError> {
+    let (value, _sql_type) = Predicate::expression(expr, &[], functions, &|_column| Ok(None))?;
+    let text = match value.value(&[])? {
+        Some(text) => text,
+        None => return Ok(None),
+    };
+    match text.parse::<i64>() {
+        Ok(count) => Ok(Some(count)),
+        Err(_) => match text.parse::<f64>() {
+            Ok(count) if count.is_finite() => Ok(Some(count.round() as i64)),
+            _ => Err(QueryError::datatype_mismatch(format!(
+                "invalid input syntax for type bigint: \"{}\"",
+                text
+            ))),
+        },
+    }
+}
+
+/// Sends rows of a query to `sender` within a limit, the number of selected
+/// rows is counted accordingly
+pub(crate) struct Limited {
+    sender: Arc<dyn Sender>,
+    remaining: Mutex<(RowLimit, usize)>,
+}
+
+impl Limited {
+    pub(crate) fn new(sender: Arc<dyn Sender>, limit: RowLimit) -> Limited {
+        Limited {
+            sender,
+            remaining: Mutex::new((limit, 0)),
+        }
+    }
+}
+
+impl Sender for Limited {
+    fn flush(&self) -> io::Result<()> {
+        self.sender.flush()
+    }
+
+    fn send(&self, query_result: QueryResult) -> io::Result<()> {
+        let mut remaining = self.remaining.lock().expect("to acquire lock");
+        let (limit, sent) = &mut *remaining;
+        match query_result {
+            Ok(QueryEvent::DataRows(rows)) => {
+                let skipped = limit.offset.min(rows.len());
+                limit.offset -= skipped;
+                let taken = limit.count.unwrap_or(usize::MAX).min(rows.len() - skipped);
+                if let Some(count) = &mut limit.count {
+                    *count -= taken;
+                }
+                *sent += taken;
+                if taken == 0 {
+                    return Ok(());
+                }
+                let rows = rows.into_iter().skip(skipped).take(taken).collect();
+                self.sender.send(Ok(QueryEvent::DataRows(rows)))
+            }
+            Ok(QueryEvent::SelectComplete(_)) => self.sender.send(Ok(QueryEvent::SelectComplete(*sent))),
+            query_result => self.sender.send(query_result),
+        }
+    }
+}
+
+/// Sends at most `max_rows` rows of a portal to `sender`, the rest of them
+/// are kept until the portal is executed again
+pub(crate) struct Portion {
+    sender: Arc<dyn Sender>,
+    state: Mutex<(usize, Vec<Vec<Option<String>>>)>,
+}
+
+impl Portion {
+    pub(crate) fn new(sender: Arc<dyn Sender>, max_rows: usize) -> Portion {
+        Portion {
+            sender,
+            state: Mutex::new((max_rows, vec![])),
+        }
+    }
+
+    /// Rows that were selected after the portal reached its row limit
+    pub(crate) fn take_rest(&self) -> Vec<Vec<Option<String>>> {
+        let mut state = self.state.lock().expect("to acquire lock");
+        mem::take(&mut state.1)
+    }
+}
+
+impl Sender for Portion {
+    fn flush(&self) -> io::Result<()> {
+        self.sender.flush()
+    }
+
+    fn send(&self, query_result: QueryResult) -> io::Result<()> {
+        let mut state = self.state.lock().expect("to acquire lock");
+        let (left, rest) = &mut *state;
+        match query_result {
+            Ok(QueryEvent::DataRows(mut rows)) => {
+                let taken = (*left).min(rows.len());
+                *left -= taken;
+                rest.extend(rows.drain(taken..));
+                if taken == 0 {
+                    return Ok(());
+                }
+                self.sender.send(Ok(QueryEvent::DataRows(rows)))
+            }
+            Ok(QueryEvent::SelectComplete(_)) if !rest.is_empty() => self.sender.send(Ok(QueryEvent::PortalSuspended)),
+            Err(error) => {
+                rest.clear();
+                self.sender.send(Err(error))
+            }
+            query_result => self.sender.send(query_result),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::table_sample::parse_sql;
+    use sqlparser::{ast::Statement, dialect::PostgreSqlDialect};
+
+    fn limit(sql: &str) -> Result<Option<RowLimit>, QueryError> {
+        match parse_sql(&PostgreSqlDialect {}, sql).expect("parsed").pop() {
+            Some(Statement::Query(query)) => RowLimit::of(&query, &ScalarFunctions::default()),
+            _ => unreachable!("query"),
+        }
+    }
+
+    #[test]
+    fn literals() {
+        assert_eq!(limit("select a from s.t"), Ok(None));
+        assert_eq!(limit("select a from s.t limit all offset 0"), Ok(None));
+        assert_eq!(
+            limit("select a from s.t limit 2 offset 3"),
+            Ok(Some(RowLimit {
+                offset: 3,
+                count: Some(2)
+            }))
+        );
+        assert_eq!(
+            limit("select a from s.t offset 3 rows"),
+            Ok(Some(RowLimit { offset: 3, count: None }))
+        );
+    }
+
+    #[test]
+    fn fetch_first() {
+        assert_eq!(
+            limit("select a from s.t offset 1 row fetch first 2 rows only"),
+            Ok(Some(RowLimit {
+                offset: 1,
+                count: Some(2)
+            }))
+        );
+        assert_eq!(
+            limit("select a from s.t fetch first row only"),
+            Ok(Some(RowLimit {
+                offset: 0,
+                count: Some(1)
+            }))
+        );
+        assert_eq!(
+            limit("select a from s.t fetch first 10 percent rows only"),
+            Err(QueryError::feature_not_supported(
+                "FETCH FIRST 10 PERCENT ROWS ONLY".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn expressions() {
+        assert_eq!(
+            limit("select a from s.t limit 2 * 3 offset '4'"),
+            Ok(Some(RowLimit {
+                offset: 4,
+                count: Some(6)
+            }))
+        );
+        assert_eq!(
+            limit("select a from s.t limit 2.5"),
+            Ok(Some(RowLimit {
+                offset: 0,
+                count: Some(3)
+            }))
+        );
+        assert_eq!(limit("select a from s.t limit null offset null"), Ok(None));
+        assert_eq!(
+            limit("select a from s.t limit a"),
+            Err(QueryError::feature_not_supported("a".to_owned()))
+        );
+        assert_eq!(
+            limit("select a from s.t limit 'all'"),
+            Err(QueryError::datatype_mismatch(
+                "invalid input syntax for type bigint: \"all\"".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn negative_values() {
+        assert_eq!(
+            limit("select a from s.t limit -1"),
+            Err(QueryError::invalid_row_count_in_limit_clause())
+        );
+        assert_eq!(
+            limit("select a from s.t offset 1 - 2"),
+            Err(QueryError::invalid_row_count_in_result_offset_clause())
+        );
+    }
+
+    struct Collector(Mutex<Vec<QueryResult>>);
+
+    impl Sender for Collector {
+        fn flush(&self) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn send(&self, query_result: QueryResult) -> io::Result<()> {
+            self.0.lock().expect("to acquire lock").push(query_result);
+            Ok(())
+        }
+    }
+
+    fn rows(values: &[i32]) -> Vec<Vec<Option<String>>> {
+        values.iter().map(|value| vec![Some(value.to_string())]).collect()
+    }
+
+    #[test]
+    fn rows_of_batches() {
+        let collector = Arc::new(Collector(Mutex::new(vec![])));
+        let limited = Limited::new(
+            collector.clone(),
+            RowLimit {
+                offset: 3,
+                count: Some(3),
+            },
+        );
+        limited.send(Ok(QueryEvent::DataRows(rows(&[1, 2])))).expect("sent");
+        limited.send(Ok(QueryEvent::DataRows(rows(&[3, 4, 5])))).expect("sent");
+        limited.send(Ok(QueryEvent::DataRows(rows(&[6, 7])))).expect("sent");
+        limited.send(Ok(QueryEvent::DataRows(rows(&[8])))).expect("sent");
+        limited.send(Ok(QueryEvent::SelectComplete(8))).expect("sent");
+
+        assert_eq!(
+            *collector.0.lock().expect("to acquire lock"),
+            vec![
+                Ok(QueryEvent::DataRows(rows(&[4, 5]))),
+                Ok(QueryEvent::DataRows(rows(&[6]))),
+                Ok(QueryEvent::SelectComplete(3)),
+            ]
+        );
+    }
+
+    #[test]
+    fn rows_of_portion() {
+        let collector = Arc::new(Collector(Mutex::new(vec![])));
+        let portion = Portion::new(collector.clone(), 3);
+        portion.send(Ok(QueryEvent::DataRows(rows(&[1, 2])))).expect("sent");
+        portion.send(Ok(QueryEvent::DataRows(rows(&[3, 4, 5])))).expect("sent");
+        portion.send(Ok(QueryEvent::SelectComplete(5))).expect("sent");
+
+        assert_eq!(
+            *collector.0.lock().expect("to acquire lock"),
+            vec![
+                Ok(QueryEvent::DataRows(rows(&[1, 2]))),
+                Ok(QueryEvent::DataRows(rows(&[3]))),
+                Ok(QueryEvent::PortalSuspended),
+            ]
+        );
+        assert_eq!(portion.take_rest(), rows(&[4, 5]));
+    }
+
+    #[test]
+    fn all_rows_of_portion() {
+        let collector = Arc::new(Collector(Mutex::new(vec![])));
+        let portion = Portion::new(collector.clone(), 3);
+        portion.send(Ok(QueryEvent::DataRows(rows(&[1, 2])))).expect("sent");
+        portion.send(Ok(QueryEvent::SelectComplete(2))).expect("sent");
+
+        assert_eq!(
+            *collector.0.lock().expect("to acquire lock"),
+            vec![
+                Ok(QueryEvent::DataRows(rows(&[1, 2]))),
+                Ok(QueryEvent::SelectComplete(2)),
+            ]
+        );
+        assert_eq!(portion.take_rest(), Vec::<Vec<Option<String>>>::new());
+    }
+}
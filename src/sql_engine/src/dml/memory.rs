@@ -0,0 +1,97 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Accounting of memory that query operators hold for their intermediate
+//! results. Each operator gets its own budget of `work_mem` kilobytes.
+
+/// Default budget of an operator in kilobytes
+pub(crate) const DEFAULT_WORK_MEM: usize = 4096;
+
+/// Budget of an operator
+#[derive(Debug)]
+pub(crate) struct WorkMem {
+    limit: usize,
+    used: usize,
+}
+
+impl WorkMem {
+    /// Creates budget of `limit` kilobytes
+    pub(crate) fn new(limit: usize) -> WorkMem {
+        WorkMem { limit, used: 0 }
+    }
+
+    /// Budget in kilobytes
+    pub(crate) fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Accounts `bytes` held by the operator, fails if the operator exceeds
+    /// its budget
+    pub(crate) fn allocate(&mut self, bytes: usize) -> Result<(), ()> {
+        self.used += bytes;
+        if self.used > self.limit * 1024 {
+            Err(())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Releases `bytes` the operator no longer holds
+    pub(crate) fn free(&mut self, bytes: usize) {
+        self.used -= bytes;
+    }
+}
+
+/// Approximate number of bytes that rows of text values occupy
+pub(crate) fn text_rows_size(rows: &[Vec<Option<String>>]) -> usize {
+    rows.iter()
+        .map(|row| {
+            std::mem::size_of::<Vec<Option<String>>>()
+                + row
+                    .iter()
+                    .map(|value| std::mem::size_of::<Option<String>>() + value.as_ref().map_or(0, String::len))
+                    .sum::<usize>()
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allocations_within_budget() {
+        let mut work_mem = WorkMem::new(1);
+
+        assert_eq!(work_mem.allocate(512), Ok(()));
+        assert_eq!(work_mem.allocate(512), Ok(()));
+    }
+
+    #[test]
+    fn allocation_exceeds_budget() {
+        let mut work_mem = WorkMem::new(1);
+
+        assert_eq!(work_mem.allocate(1000), Ok(()));
+        assert_eq!(work_mem.allocate(25), Err(()));
+    }
+
+    #[test]
+    fn freed_memory_can_be_allocated_again() {
+        let mut work_mem = WorkMem::new(1);
+
+        assert_eq!(work_mem.allocate(1000), Ok(()));
+        work_mem.free(1000);
+        assert_eq!(work_mem.allocate(1000), Ok(()));
+    }
+}
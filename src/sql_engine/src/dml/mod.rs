@@ -12,15 +12,103 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::{
+    dml::{functions::ScalarFunctions, predicate::Predicate},
+    query::resolve::Scope,
+    ColumnDefinition,
+};
 use bigdecimal::BigDecimal;
 use protocol::{results::QueryError, Sender};
+use representation::Datum;
+use sql_types::SqlType;
 use sqlparser::ast::{BinaryOperator, Expr, Value};
-use std::{ops::Deref, sync::Arc};
+use std::{convert::TryFrom, ops::Deref, sync::Arc};
 
+pub(crate) mod aggregation;
+pub(crate) mod batch;
+pub(crate) mod copy;
+pub(crate) mod cte;
 pub(crate) mod delete;
+pub(crate) mod functions;
 pub(crate) mod insert;
+pub(crate) mod join;
+pub(crate) mod limit;
+pub(crate) mod memory;
+pub(crate) mod predicate;
+pub(crate) mod sample;
 pub(crate) mod select;
+pub(crate) mod set_operation;
+pub(crate) mod sort;
+pub(crate) mod subquery;
 pub(crate) mod update;
+pub(crate) mod values;
+pub(crate) mod write_buffer;
+
+/// Value of a column that a row does not specify, NULL if the column has no
+/// default
+pub(crate) fn default_datum(column: &ColumnDefinition) -> Datum<'static> {
+    match column.default() {
+        Some(default) => copy::datum(&column.sql_type(), default).expect("default to be validated when it was set"),
+        None => Datum::from_null(),
+    }
+}
+
+/// Value of a validated literal, values of full-text search types are
+/// stored normalized
+pub(crate) fn literal_datum(sql_type: &SqlType, value: &Value) -> Datum<'static> {
+    match (sql_type, value) {
+        (SqlType::TsVector, Value::SingleQuotedString(text)) | (SqlType::TsQuery, Value::SingleQuotedString(text)) => {
+            copy::datum(sql_type, text).expect("value to be validated")
+        }
+        _ => Datum::try_from(value).unwrap(),
+    }
+}
+
+/// Error for the first column that is declared `NOT NULL` but is NULL in `row`
+pub(crate) fn null_violation(columns: &[ColumnDefinition], row: &[Datum]) -> Option<QueryError> {
+    columns
+        .iter()
+        .zip(row)
+        .find(|(column, datum)| column.is_not_null() && matches!(datum, Datum::Null))
+        .map(|(column, _)| QueryError::not_null_violation(column.name()))
+}
+
+/// Condition of `WHERE` clause of `update` and `delete`, it refers to
+/// columns of the modified table by their names that can be qualified by
+/// the name of the table
+pub(crate) fn table_condition(
+    selection: &Expr,
+    (schema_name, table_name): (&str, &str),
+    columns: &[ColumnDefinition],
+    functions: &ScalarFunctions,
+) -> Result<Predicate, QueryError> {
+    let scope = Scope::new(columns);
+    let types = columns.iter().map(ColumnDefinition::sql_type).collect::<Vec<_>>();
+    Predicate::compile(selection, "WHERE", &types, functions, None, &|expr| {
+        let (qualifier, column) = match expr {
+            Expr::Identifier(column) => (&[][..], column),
+            Expr::CompoundIdentifier(idents) => match idents.split_last() {
+                Some((column, qualifier)) => (qualifier, column),
+                None => return Ok(None),
+            },
+            _ => return Ok(None),
+        };
+        let qualified = match qualifier {
+            [] => true,
+            [table] => table.value == table_name,
+            [schema, table] => schema.value == schema_name && table.value == table_name,
+            _ => false,
+        };
+        if !qualified {
+            let qualifier = qualifier.iter().map(|ident| ident.value.as_str()).collect::<Vec<_>>();
+            return Err(QueryError::missing_from_clause_entry(qualifier.join(".")));
+        }
+        match scope.lookup(&column.value)? {
+            Some(index) => Ok(Some(index)),
+            None => Err(scope.unknown(vec![column.value.clone()])),
+        }
+    })
+}
 
 pub(crate) struct ExpressionEvaluation {
     session: Arc<dyn Sender>,
@@ -35,6 +123,7 @@ impl ExpressionEvaluation {
         match self.inner_eval(expr)? {
             ExprResult::Number(v) => Ok(Value::Number(v)),
             ExprResult::String(v) => Ok(Value::SingleQuotedString(v)),
+            ExprResult::Null => Ok(Value::Null),
         }
     }
 
@@ -43,6 +132,7 @@ impl ExpressionEvaluation {
             let left = self.inner_eval(left.deref())?;
             let right = self.inner_eval(right.deref())?;
             match (left, right) {
+                (ExprResult::Null, _) | (_, ExprResult::Null) => Ok(ExprResult::Null),
                 (ExprResult::Number(left), ExprResult::Number(right)) => match op {
                     BinaryOperator::Plus => Ok(ExprResult::Number(left + right)),
                     BinaryOperator::Minus => Ok(ExprResult::Number(left - right)),
@@ -66,7 +156,7 @@ impl ExpressionEvaluation {
                                 "NUMBER".to_owned(),
                                 "NUMBER".to_owned(),
                             )))
-                            .expect("To Send Query Result to Client");
+                            .ok();
                         Err(())
                     }
                 },
@@ -79,7 +169,7 @@ impl ExpressionEvaluation {
                                 "STRING".to_owned(),
                                 "STRING".to_owned(),
                             )))
-                            .expect("To Send Query Result to Client");
+                            .ok();
                         Err(())
                     }
                 },
@@ -92,7 +182,7 @@ impl ExpressionEvaluation {
                                 "NUMBER".to_owned(),
                                 "STRING".to_owned(),
                             )))
-                            .expect("To Send Query Result to Client");
+                            .ok();
                         Err(())
                     }
                 },
@@ -105,7 +195,7 @@ impl ExpressionEvaluation {
                                 "STRING".to_owned(),
                                 "NUMBER".to_owned(),
                             )))
-                            .expect("To Send Query Result to Client");
+                            .ok();
                         Err(())
                     }
                 },
@@ -114,10 +204,9 @@ impl ExpressionEvaluation {
             match expr {
                 Expr::Value(Value::Number(v)) => Ok(ExprResult::Number(v.clone())),
                 Expr::Value(Value::SingleQuotedString(v)) => Ok(ExprResult::String(v.clone())),
+                Expr::Value(Value::Null) => Ok(ExprResult::Null),
                 e => {
-                    self.session
-                        .send(Err(QueryError::syntax_error(e.to_string())))
-                        .expect("To Send Query Result to Client");
+                    self.session.send(Err(QueryError::syntax_error(e.to_string()))).ok();
                     Err(())
                 }
             }
@@ -129,4 +218,6 @@ impl ExpressionEvaluation {
 pub(crate) enum ExprResult {
     Number(BigDecimal),
     String(String),
+    /// operators yield NULL if any of their operands is NULL
+    Null,
 }
@@ -0,0 +1,1331 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `WHERE` and `HAVING` clauses and join conditions. A condition is
+//! compiled against columns of rows it filters once, string literals take
+//! the type of the column they are compared with. The compiled condition is
+//! evaluated for each row with three-valued logic and rows for which it is
+//! NULL are skipped. Items of a select list are compiled the same way into
+//! expressions that compute a value of each row.
+use crate::{
+    dml::{
+        functions::{function_name, text_search::TO_TSVECTOR_FUNCTION, ScalarFunction, ScalarFunctions},
+        subquery::{self, Subqueries, Subquery},
+    },
+    query::match_operator::MATCH_FUNCTION,
+};
+use protocol::results::QueryError;
+use representation::{unpack_raw, Datum};
+use sql_types::{
+    text_search::{TsQuery, TsVector},
+    SqlType,
+};
+use sqlparser::ast::{BinaryOperator, Expr, Function, Query, UnaryOperator, Value};
+use std::{cmp::Ordering, sync::Arc};
+use storage::ReadCursor;
+
+/// Index of the column that an identifier or a function call refers to,
+/// `None` if a function call is not a column of filtered rows
+pub(crate) type Resolve<'r> = dyn Fn(&Expr) -> Result<Option<usize>, QueryError> + 'r;
+
+/// Value of a column or of a literal that predicates compare
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Scalar {
+    Null,
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    Text(String),
+}
+
+impl Scalar {
+    fn from_datum(datum: &Datum) -> Scalar {
+        match datum {
+            Datum::Null => Scalar::Null,
+            Datum::True => Scalar::Bool(true),
+            Datum::False => Scalar::Bool(false),
+            Datum::Int16(value) => Scalar::Integer(i64::from(*value)),
+            Datum::Int32(value) => Scalar::Integer(i64::from(*value)),
+            Datum::Int64(value) => Scalar::Integer(*value),
+            Datum::UInt64(value) => Scalar::Float(*value as f64),
+            Datum::Float32(value) => Scalar::Float(f64::from(value.into_inner())),
+            Datum::Float64(value) => Scalar::Float(value.into_inner()),
+            Datum::String(value) => Scalar::Text((*value).to_owned()),
+            Datum::OwnedString(value) => Scalar::Text(value.clone()),
+            Datum::SqlType(sql_type) => Scalar::Text(sql_type.to_string()),
+        }
+    }
+
+    /// Value of the text representation of a value of the kind
+    pub(crate) fn parse(text: Option<&str>, kind: Kind) -> Scalar {
+        match (text, kind) {
+            (None, _) => Scalar::Null,
+            (Some(text), Kind::Number) => match text.parse::<i64>() {
+                Ok(value) => Scalar::Integer(value),
+                Err(_) => match text.parse::<f64>() {
+                    Ok(value) => Scalar::Float(value),
+                    Err(_) => Scalar::Text(text.to_owned()),
+                },
+            },
+            (Some("t"), Kind::Bool) => Scalar::Bool(true),
+            (Some("f"), Kind::Bool) => Scalar::Bool(false),
+            (Some(text), _) => Scalar::Text(text.to_owned()),
+        }
+    }
+
+    pub(crate) fn partial_cmp(&self, other: &Scalar) -> Option<Ordering> {
+        match (self, other) {
+            (Scalar::Bool(left), Scalar::Bool(right)) => Some(left.cmp(right)),
+            (Scalar::Integer(left), Scalar::Integer(right)) => Some(left.cmp(right)),
+            (Scalar::Integer(left), Scalar::Float(right)) => (*left as f64).partial_cmp(right),
+            (Scalar::Float(left), Scalar::Integer(right)) => left.partial_cmp(&(*right as f64)),
+            (Scalar::Float(left), Scalar::Float(right)) => left.partial_cmp(right),
+            (Scalar::Text(left), Scalar::Text(right)) => Some(left.cmp(right)),
+            _ => None,
+        }
+    }
+}
+
+/// Kinds of values that can be compared with each other
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Kind {
+    Bool,
+    Number,
+    Text,
+    TsVector,
+    TsQuery,
+    /// string literal or NULL that takes the kind of the other operand
+    Unknown,
+}
+
+impl Kind {
+    pub(crate) fn of(sql_type: &SqlType) -> Kind {
+        match sql_type {
+            SqlType::Bool => Kind::Bool,
+            SqlType::SmallInt(_)
+            | SqlType::Integer(_)
+            | SqlType::BigInt(_)
+            | SqlType::Decimal
+            | SqlType::Real
+            | SqlType::DoublePrecision => Kind::Number,
+            SqlType::TsVector => Kind::TsVector,
+            SqlType::TsQuery => Kind::TsQuery,
+            _ => Kind::Text,
+        }
+    }
+}
+
+/// Compiled condition
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Predicate {
+    Column {
+        index: usize,
+        kind: Kind,
+        /// values of `char` columns are compared without trailing spaces
+        blank_padded: bool,
+    },
+    Constant(Scalar),
+    Compare(BinaryOperator, Box<Predicate>, Box<Predicate>),
+    And(Box<Predicate>, Box<Predicate>),
+    Or(Box<Predicate>, Box<Predicate>),
+    Not(Box<Predicate>),
+    IsNull(Box<Predicate>),
+    /// `+`, `-`, `*`, `/` or `%` of numbers with the type of the result
+    Arithmetic(BinaryOperator, Box<Predicate>, Box<Predicate>, SqlType),
+    /// call of a scalar function
+    Call(Arc<ScalarFunction>, Vec<Predicate>),
+    /// whether the value is among values of a subquery, `NOT IN` if negated
+    In(Box<Predicate>, Arc<Subquery>, bool),
+    /// whether a subquery returns any row
+    Exists(Arc<Subquery>),
+    /// result of the first branch whose condition holds, the last predicate
+    /// otherwise
+    Case(Vec<(Predicate, Predicate)>, Box<Predicate>),
+}
+
+/// Predicate with the kind of its result and the name of the type for errors
+#[derive(Clone)]
+struct Typed {
+    predicate: Predicate,
+    kind: Kind,
+    type_name: &'static str,
+    sql_type: Option<SqlType>,
+}
+
+impl Typed {
+    fn constant(scalar: Scalar, kind: Kind, type_name: &'static str) -> Typed {
+        Typed {
+            predicate: Predicate::Constant(scalar),
+            kind,
+            type_name,
+            sql_type: None,
+        }
+    }
+
+    fn boolean(predicate: Predicate) -> Typed {
+        Typed {
+            predicate,
+            kind: Kind::Bool,
+            type_name: "bool",
+            sql_type: None,
+        }
+    }
+
+    /// Value of `sql_type` type
+    fn of_type(predicate: Predicate, sql_type: SqlType) -> Typed {
+        Typed {
+            predicate,
+            kind: Kind::of(&sql_type),
+            type_name: (&sql_type).into(),
+            sql_type: Some(sql_type),
+        }
+    }
+
+    /// Gives string literals and NULL the kind of `other`
+    fn coerce(self, other: &Typed) -> Result<Typed, QueryError> {
+        if self.kind != Kind::Unknown || other.kind == Kind::Unknown {
+            return Ok(self);
+        }
+        let scalar = match self.predicate {
+            Predicate::Constant(Scalar::Text(text)) => match other.kind {
+                Kind::Number => match text.trim().parse::<i64>() {
+                    Ok(value) => Scalar::Integer(value),
+                    Err(_) => match text.trim().parse::<f64>() {
+                        Ok(value) if value.is_finite() => Scalar::Float(value),
+                        _ => return Err(invalid_input(other.type_name, &text)),
+                    },
+                },
+                Kind::Bool => match text.trim().to_lowercase().as_str() {
+                    "t" | "true" | "y" | "yes" | "on" | "1" => Scalar::Bool(true),
+                    "f" | "false" | "n" | "no" | "off" | "0" => Scalar::Bool(false),
+                    _ => return Err(invalid_input(other.type_name, &text)),
+                },
+                Kind::TsVector => match TsVector::parse(&text) {
+                    Ok(vector) => Scalar::Text(vector.to_string()),
+                    Err(message) => return Err(QueryError::datatype_mismatch(message)),
+                },
+                Kind::TsQuery => match TsQuery::parse(&text) {
+                    Ok(query) => Scalar::Text(query.to_string()),
+                    Err(message) => return Err(QueryError::datatype_mismatch(message)),
+                },
+                _ if matches!(other.sql_type, Some(SqlType::Char(_))) => Scalar::Text(text.trim_end().to_owned()),
+                _ => Scalar::Text(text),
+            },
+            _ => Scalar::Null,
+        };
+        Ok(Typed {
+            sql_type: other.sql_type,
+            ..Typed::constant(scalar, other.kind, other.type_name)
+        })
+    }
+}
+
+impl Predicate {
+    /// Compiles `expr` of the `clause` against columns of `types`, `resolve`
+    /// gives the index of a column. Functions that `expr` calls are looked
+    /// up in `functions`, subqueries are executed by `subqueries` and are not
+    /// supported without them.
+    pub(crate) fn compile(
+        expr: &Expr,
+        clause: &str,
+        types: &[SqlType],
+        functions: &ScalarFunctions,
+        subqueries: Option<&Arc<dyn Subqueries>>,
+        resolve: &Resolve,
+    ) -> Result<Predicate, QueryError> {
+        let compiler = Compiler {
+            types,
+            functions,
+            subqueries,
+            resolve,
+        };
+        compiler.condition(expr, clause)
+    }
+
+    /// Compiles a value expression `expr` against columns of `types` and
+    /// returns it with the type of its result
+    pub(crate) fn expression(
+        expr: &Expr,
+        types: &[SqlType],
+        functions: &ScalarFunctions,
+        resolve: &Resolve,
+    ) -> Result<(Predicate, SqlType), QueryError> {
+        let compiler = Compiler {
+            types,
+            functions,
+            subqueries: None,
+            resolve,
+        };
+        let typed = compiler.compile(expr)?;
+        let sql_type = match (typed.sql_type, typed.kind) {
+            (Some(sql_type), _) => sql_type,
+            (None, Kind::Bool) => SqlType::Bool,
+            (None, Kind::TsVector) => SqlType::TsVector,
+            (None, Kind::TsQuery) => SqlType::TsQuery,
+            (None, _) => SqlType::VarChar(u64::MAX),
+        };
+        Ok((typed.predicate, sql_type))
+    }
+
+    /// Whether the predicate is true for decoded values of a row
+    pub(crate) fn holds(&self, row: &[Datum]) -> bool {
+        self.eval(&|index, _kind| Scalar::from_datum(&row[index])) == Ok(Scalar::Bool(true))
+    }
+
+    /// Whether the predicate is true for text representation of values of
+    /// a row, e.g. results of aggregates
+    pub(crate) fn holds_for_text(&self, row: &[Option<String>]) -> bool {
+        self.eval(&|index, kind| Scalar::parse(row[index].as_deref(), kind)) == Ok(Scalar::Bool(true))
+    }
+
+    /// Text representation of the value of the expression for decoded values
+    /// of a row, `None` for NULL
+    pub(crate) fn value(&self, row: &[Datum]) -> Result<Option<String>, QueryError> {
+        Ok(match self.eval(&|index, _kind| Scalar::from_datum(&row[index]))? {
+            Scalar::Null => None,
+            Scalar::Bool(true) => Some("t".to_owned()),
+            Scalar::Bool(false) => Some("f".to_owned()),
+            Scalar::Integer(value) => Some(value.to_string()),
+            Scalar::Float(value) => Some(value.to_string()),
+            Scalar::Text(text) => Some(text),
+        })
+    }
+
+    /// Returns rows of `cursor` for which the predicate holds. Errors of the
+    /// underlying scan are always returned.
+    pub(crate) fn filter(self, cursor: ReadCursor) -> ReadCursor {
+        Box::new(cursor.filter(move |row| match row {
+            Ok(Ok((_key, values))) => self.holds(&unpack_raw(values.to_bytes())),
+            _ => true,
+        }))
+    }
+
+    /// Errors if arithmetic of the expression fails
+    fn eval(&self, row: &dyn Fn(usize, Kind) -> Scalar) -> Result<Scalar, QueryError> {
+        Ok(match self {
+            Predicate::Column {
+                index,
+                kind,
+                blank_padded,
+            } => match row(*index, *kind) {
+                Scalar::Text(text) if *blank_padded => Scalar::Text(text.trim_end().to_owned()),
+                scalar => scalar,
+            },
+            Predicate::Constant(scalar) => scalar.clone(),
+            Predicate::Compare(op, left, right) => {
+                let ordering = left.eval(row)?.partial_cmp(&right.eval(row)?);
+                match ordering {
+                    None => Scalar::Null,
+                    Some(ordering) => Scalar::Bool(match op {
+                        BinaryOperator::Eq => ordering == Ordering::Equal,
+                        BinaryOperator::NotEq => ordering != Ordering::Equal,
+                        BinaryOperator::Lt => ordering == Ordering::Less,
+                        BinaryOperator::LtEq => ordering != Ordering::Greater,
+                        BinaryOperator::Gt => ordering == Ordering::Greater,
+                        _ => ordering != Ordering::Less,
+                    }),
+                }
+            }
+            Predicate::And(left, right) => match (left.eval(row)?, right.eval(row)?) {
+                (Scalar::Bool(false), _) | (_, Scalar::Bool(false)) => Scalar::Bool(false),
+                (Scalar::Bool(true), Scalar::Bool(true)) => Scalar::Bool(true),
+                _ => Scalar::Null,
+            },
+            Predicate::Or(left, right) => match (left.eval(row)?, right.eval(row)?) {
+                (Scalar::Bool(true), _) | (_, Scalar::Bool(true)) => Scalar::Bool(true),
+                (Scalar::Bool(false), Scalar::Bool(false)) => Scalar::Bool(false),
+                _ => Scalar::Null,
+            },
+            Predicate::Not(predicate) => match predicate.eval(row)? {
+                Scalar::Bool(value) => Scalar::Bool(!value),
+                _ => Scalar::Null,
+            },
+            Predicate::IsNull(predicate) => Scalar::Bool(predicate.eval(row)? == Scalar::Null),
+            Predicate::Arithmetic(op, left, right, result) => {
+                arithmetic(op, left.eval(row)?, right.eval(row)?, result)?
+            }
+            Predicate::Call(function, args) => function.call(
+                &args
+                    .iter()
+                    .map(|arg| arg.eval(row))
+                    .collect::<Result<Vec<Scalar>, QueryError>>()?,
+            )?,
+            Predicate::In(operand, subquery, negated) => {
+                let values = subquery.values(Predicate::outer_values(subquery, row)?)?;
+                let operand = operand.eval(row)?;
+                let found = if values.is_empty() {
+                    Scalar::Bool(false)
+                } else if operand == Scalar::Null {
+                    Scalar::Null
+                } else if values
+                    .iter()
+                    .any(|value| operand.partial_cmp(value) == Some(Ordering::Equal))
+                {
+                    Scalar::Bool(true)
+                } else if values.contains(&Scalar::Null) {
+                    Scalar::Null
+                } else {
+                    Scalar::Bool(false)
+                };
+                match found {
+                    Scalar::Bool(found) => Scalar::Bool(found != *negated),
+                    unknown => unknown,
+                }
+            }
+            Predicate::Exists(subquery) => {
+                Scalar::Bool(!subquery.values(Predicate::outer_values(subquery, row)?)?.is_empty())
+            }
+            Predicate::Case(branches, otherwise) => {
+                for (condition, result) in branches {
+                    if condition.eval(row)? == Scalar::Bool(true) {
+                        return result.eval(row);
+                    }
+                }
+                otherwise.eval(row)?
+            }
+        })
+    }
+
+    /// Values of columns of the outer row that a subquery refers to
+    fn outer_values(subquery: &Subquery, row: &dyn Fn(usize, Kind) -> Scalar) -> Result<Vec<Scalar>, QueryError> {
+        subquery.outer().map(|column| column.eval(row)).collect()
+    }
+}
+
+struct Compiler<'c> {
+    types: &'c [SqlType],
+    functions: &'c ScalarFunctions,
+    subqueries: Option<&'c Arc<dyn Subqueries>>,
+    resolve: &'c Resolve<'c>,
+}
+
+impl Compiler<'_> {
+    fn compile(&self, expr: &Expr) -> Result<Typed, QueryError> {
+        match expr {
+            Expr::Identifier(_) | Expr::CompoundIdentifier(_) => match (self.resolve)(expr)? {
+                Some(index) => Ok(self.column(index)),
+                None => Err(QueryError::feature_not_supported(expr.to_string())),
+            },
+            Expr::Function(function) => match (self.resolve)(expr)? {
+                Some(index) => Ok(self.column(index)),
+                None => self.function(expr, function),
+            },
+            Expr::Value(Value::Number(number)) => number_literal(&number.to_string(), false),
+            Expr::UnaryOp {
+                op: UnaryOperator::Minus,
+                expr: operand,
+            } => match &**operand {
+                Expr::Value(Value::Number(number)) => number_literal(&number.to_string(), true),
+                operand => self.arithmetic(
+                    &BinaryOperator::Minus,
+                    number_literal("0", false)?,
+                    self.compile(operand)?,
+                ),
+            },
+            Expr::UnaryOp {
+                op: UnaryOperator::Plus,
+                expr: operand,
+            } => self.arithmetic(
+                &BinaryOperator::Plus,
+                number_literal("0", false)?,
+                self.compile(operand)?,
+            ),
+            Expr::Value(Value::SingleQuotedString(text)) => {
+                Ok(Typed::constant(Scalar::Text(text.clone()), Kind::Unknown, "unknown"))
+            }
+            Expr::Value(Value::Boolean(value)) => Ok(Typed::constant(Scalar::Bool(*value), Kind::Bool, "bool")),
+            Expr::Value(Value::Null) => Ok(Typed::constant(Scalar::Null, Kind::Unknown, "unknown")),
+            Expr::Nested(expr) => self.compile(expr),
+            Expr::IsNull(operand) => Ok(Typed::boolean(Predicate::IsNull(Box::new(
+                self.compile(operand)?.predicate,
+            )))),
+            Expr::IsNotNull(operand) => Ok(Typed::boolean(Predicate::Not(Box::new(Predicate::IsNull(Box::new(
+                self.compile(operand)?.predicate,
+            )))))),
+            Expr::UnaryOp {
+                op: UnaryOperator::Not,
+                expr: operand,
+            } => Ok(Typed::boolean(Predicate::Not(Box::new(
+                self.condition(operand, "NOT")?,
+            )))),
+            Expr::BinaryOp { left, op, right } => match op {
+                BinaryOperator::And => Ok(Typed::boolean(Predicate::And(
+                    Box::new(self.condition(left, "AND")?),
+                    Box::new(self.condition(right, "AND")?),
+                ))),
+                BinaryOperator::Or => Ok(Typed::boolean(Predicate::Or(
+                    Box::new(self.condition(left, "OR")?),
+                    Box::new(self.condition(right, "OR")?),
+                ))),
+                BinaryOperator::Eq
+                | BinaryOperator::NotEq
+                | BinaryOperator::Lt
+                | BinaryOperator::LtEq
+                | BinaryOperator::Gt
+                | BinaryOperator::GtEq => Ok(Typed::boolean(self.comparison(op, left, right)?)),
+                BinaryOperator::Plus
+                | BinaryOperator::Minus
+                | BinaryOperator::Multiply
+                | BinaryOperator::Divide
+                | BinaryOperator::Modulus => self.arithmetic(op, self.compile(left)?, self.compile(right)?),
+                _ => Err(QueryError::feature_not_supported(expr.to_string())),
+            },
+            Expr::InSubquery {
+                expr: operand,
+                subquery,
+                negated,
+            } => {
+                let subquery = self.subquery(subquery)?;
+                if subquery.columns() != 1 {
+                    return Err(QueryError::subquery_too_many_columns());
+                }
+                let column = Typed::of_type(Predicate::Constant(Scalar::Null), subquery.sql_type());
+                let operand = self.compile(operand)?.coerce(&column)?;
+                if operand.kind != column.kind && operand.kind != Kind::Unknown {
+                    return Err(QueryError::undefined_function(
+                        BinaryOperator::Eq.to_string(),
+                        operand.type_name.to_owned(),
+                        column.type_name.to_owned(),
+                    ));
+                }
+                Ok(Typed::boolean(Predicate::In(
+                    Box::new(operand.predicate),
+                    subquery,
+                    *negated,
+                )))
+            }
+            Expr::Exists(query) => Ok(Typed::boolean(Predicate::Exists(self.subquery(query)?))),
+            Expr::Between {
+                expr: operand,
+                negated,
+                low,
+                high,
+            } => {
+                let operand = self.compile(operand)?;
+                let between = Predicate::And(
+                    Box::new(self.compare(&BinaryOperator::GtEq, operand.clone(), self.compile(low)?)?),
+                    Box::new(self.compare(&BinaryOperator::LtEq, operand, self.compile(high)?)?),
+                );
+                Ok(Typed::boolean(negate(between, *negated)))
+            }
+            Expr::InList {
+                expr: operand,
+                list,
+                negated,
+            } => {
+                let mut found = None;
+                for value in list {
+                    let equal = self.comparison(&BinaryOperator::Eq, operand, value)?;
+                    found = Some(match found {
+                        None => equal,
+                        Some(found) => Predicate::Or(Box::new(found), Box::new(equal)),
+                    });
+                }
+                match found {
+                    Some(found) => Ok(Typed::boolean(negate(found, *negated))),
+                    None => Err(QueryError::feature_not_supported(expr.to_string())),
+                }
+            }
+            Expr::Case {
+                operand,
+                conditions,
+                results,
+                else_result,
+            } => self.case(operand.as_deref(), conditions, results, else_result.as_deref()),
+            _ => Err(QueryError::feature_not_supported(expr.to_string())),
+        }
+    }
+
+    /// Comparison of two rows or of two values, a row can't be compared with
+    /// a value
+    fn comparison(&self, op: &BinaryOperator, left: &Expr, right: &Expr) -> Result<Predicate, QueryError> {
+        match (row_fields(left), row_fields(right)) {
+            (Some(left), Some(right)) => self.compare_rows(op, left, right),
+            (None, None) => self.compare(op, self.compile(left)?, self.compile(right)?),
+            (Some(_), None) => Err(QueryError::undefined_function(
+                op.to_string(),
+                "record".to_owned(),
+                self.compile(right)?.type_name.to_owned(),
+            )),
+            (None, Some(_)) => Err(QueryError::undefined_function(
+                op.to_string(),
+                self.compile(left)?.type_name.to_owned(),
+                "record".to_owned(),
+            )),
+        }
+    }
+
+    /// Rows are equal if all their fields are equal and are ordered by the
+    /// first pair of fields that are not equal. A NULL field makes the result
+    /// NULL unless it is decided by fields before it, or by any unequal field
+    /// for `=` and `<>`.
+    fn compare_rows(&self, op: &BinaryOperator, left: &[Expr], right: &[Expr]) -> Result<Predicate, QueryError> {
+        if left.len() != right.len() {
+            return Err(QueryError::datatype_mismatch(
+                "unequal number of entries in row expressions".to_owned(),
+            ));
+        }
+        let mut fields = left.iter().zip(right).rev();
+        match op {
+            BinaryOperator::Eq => fields.try_fold(Predicate::Constant(Scalar::Bool(true)), |rest, (left, right)| {
+                Ok(Predicate::And(
+                    Box::new(self.comparison(op, left, right)?),
+                    Box::new(rest),
+                ))
+            }),
+            BinaryOperator::NotEq => {
+                fields.try_fold(Predicate::Constant(Scalar::Bool(false)), |rest, (left, right)| {
+                    Ok(Predicate::Or(
+                        Box::new(self.comparison(op, left, right)?),
+                        Box::new(rest),
+                    ))
+                })
+            }
+            BinaryOperator::Lt | BinaryOperator::LtEq | BinaryOperator::Gt | BinaryOperator::GtEq => {
+                let strict = match op {
+                    BinaryOperator::Lt | BinaryOperator::LtEq => BinaryOperator::Lt,
+                    _ => BinaryOperator::Gt,
+                };
+                let equal_rows = matches!(op, BinaryOperator::LtEq | BinaryOperator::GtEq);
+                fields.try_fold(Predicate::Constant(Scalar::Bool(equal_rows)), |rest, (left, right)| {
+                    Ok(Predicate::Or(
+                        Box::new(self.comparison(&strict, left, right)?),
+                        Box::new(Predicate::And(
+                            Box::new(self.comparison(&BinaryOperator::Eq, left, right)?),
+                            Box::new(rest),
+                        )),
+                    ))
+                })
+            }
+            _ => Err(QueryError::undefined_function(
+                op.to_string(),
+                "record".to_owned(),
+                "record".to_owned(),
+            )),
+        }
+    }
+
+    /// Comparison of operands of the same kind, string literals and NULL take
+    /// the kind of the other operand
+    fn compare(&self, op: &BinaryOperator, left: Typed, right: Typed) -> Result<Predicate, QueryError> {
+        let left = left.coerce(&right)?;
+        let right = right.coerce(&left)?;
+        if left.kind != right.kind && left.kind != Kind::Unknown && right.kind != Kind::Unknown {
+            return Err(QueryError::undefined_function(
+                op.to_string(),
+                left.type_name.to_owned(),
+                right.type_name.to_owned(),
+            ));
+        }
+        Ok(Predicate::Compare(
+            op.clone(),
+            Box::new(left.predicate),
+            Box::new(right.predicate),
+        ))
+    }
+
+    /// Searched `CASE` if there is no `operand`, otherwise `operand` is
+    /// compared with values of `conditions`. Results have to be of the same
+    /// kind and numbers take the widest type of them, results that are string
+    /// literals or NULL take the type of the others or are text.
+    fn case(
+        &self,
+        operand: Option<&Expr>,
+        conditions: &[Expr],
+        results: &[Expr],
+        else_result: Option<&Expr>,
+    ) -> Result<Typed, QueryError> {
+        let operand = operand.map(|operand| self.compile(operand)).transpose()?;
+        let conditions = conditions
+            .iter()
+            .map(|condition| match &operand {
+                Some(operand) => self.compare(&BinaryOperator::Eq, operand.clone(), self.compile(condition)?),
+                None => self.condition(condition, "CASE/WHEN"),
+            })
+            .collect::<Result<Vec<Predicate>, QueryError>>()?;
+        let results = results
+            .iter()
+            .chain(else_result)
+            .map(|result| self.compile(result))
+            .collect::<Result<Vec<Typed>, QueryError>>()?;
+        let mut unified: Option<Typed> = None;
+        for result in results.iter().filter(|result| result.kind != Kind::Unknown) {
+            unified = Some(match unified {
+                None => result.clone(),
+                Some(unified) if unified.kind != result.kind => {
+                    return Err(QueryError::datatype_mismatch(format!(
+                        "CASE types {} and {} cannot be matched",
+                        unified.type_name, result.type_name
+                    )))
+                }
+                Some(unified) => match (unified.kind, unified.sql_type, result.sql_type) {
+                    (Kind::Number, Some(left), Some(right)) => {
+                        Typed::of_type(Predicate::Constant(Scalar::Null), promote(left, right))
+                    }
+                    (Kind::Text, left, right) if left != right => {
+                        Typed::of_type(Predicate::Constant(Scalar::Null), SqlType::VarChar(u64::MAX))
+                    }
+                    _ => unified,
+                },
+            });
+        }
+        let unified =
+            unified.unwrap_or_else(|| Typed::of_type(Predicate::Constant(Scalar::Null), SqlType::VarChar(u64::MAX)));
+        let mut results = results
+            .into_iter()
+            .map(|result| result.coerce(&unified).map(|result| result.predicate))
+            .collect::<Result<Vec<Predicate>, QueryError>>()?;
+        let otherwise = match else_result {
+            Some(_) => results.pop().expect("result of ELSE"),
+            None => Predicate::Constant(Scalar::Null),
+        };
+        Ok(Typed {
+            predicate: Predicate::Case(conditions.into_iter().zip(results).collect(), Box::new(otherwise)),
+            ..unified
+        })
+    }
+
+    /// Subquery of a condition, columns that it refers to and that are not
+    /// columns of its own relations are columns of filtered rows
+    fn subquery(&self, query: &Query) -> Result<Arc<Subquery>, QueryError> {
+        let subqueries = match self.subqueries {
+            Some(subqueries) => subqueries,
+            None => return Err(QueryError::feature_not_supported(query.to_string())),
+        };
+        let mut outer = vec![];
+        for reference in subquery::references(query) {
+            if subqueries.is_inner(query, &reference) || outer.iter().any(|(known, _)| *known == reference) {
+                continue;
+            }
+            if let Ok(Some(index)) = (self.resolve)(&reference) {
+                outer.push((reference, self.column(index).predicate));
+            }
+        }
+        Ok(Arc::new(Subquery::new(query.clone(), outer, subqueries.clone())?))
+    }
+
+    /// Calls of scalar functions and of the `@@` operator
+    fn function(&self, expr: &Expr, function: &Function) -> Result<Typed, QueryError> {
+        let name = match function_name(&function.name) {
+            Some(name) => name,
+            None => return Err(QueryError::feature_not_supported(expr.to_string())),
+        };
+        let args = function
+            .args
+            .iter()
+            .map(|arg| self.compile(arg))
+            .collect::<Result<Vec<Typed>, QueryError>>()?;
+        match (name.as_str(), args.as_slice()) {
+            (MATCH_FUNCTION, [vector, query]) => {
+                let vector = match vector.kind {
+                    Kind::Text | Kind::Unknown => self.call(TO_TSVECTOR_FUNCTION, vec![vector.clone()])?,
+                    _ => vector.clone(),
+                };
+                if vector.kind != Kind::TsVector || (query.kind != Kind::TsQuery && query.kind != Kind::Unknown) {
+                    return Err(QueryError::undefined_function(
+                        "@@".to_owned(),
+                        vector.type_name.to_owned(),
+                        query.type_name.to_owned(),
+                    ));
+                }
+                self.call(MATCH_FUNCTION, vec![vector, query.clone()])
+            }
+            _ => self.call(&name, args),
+        }
+    }
+
+    /// Call of the overload of a scalar function whose parameters accept
+    /// arguments, string literals and NULL are accepted as any type. Calls
+    /// of functions that are not volatile with constant arguments are
+    /// evaluated once when they are compiled.
+    fn call(&self, name: &str, args: Vec<Typed>) -> Result<Typed, QueryError> {
+        let overloads = self.functions.overloads(name);
+        let accepts = |function: &ScalarFunction| {
+            function.params().len() == args.len()
+                && function
+                    .params()
+                    .iter()
+                    .zip(&args)
+                    .all(|(param, arg)| arg.kind == Kind::of(param) || arg.kind == Kind::Unknown)
+        };
+        let function = match overloads.into_iter().find(|function| accepts(function)) {
+            Some(function) => function,
+            None => {
+                return Err(QueryError::function_does_not_exist(format!(
+                    "{}({})",
+                    name,
+                    args.iter().map(|arg| arg.type_name).collect::<Vec<&str>>().join(", ")
+                )))
+            }
+        };
+        let mut predicates = vec![];
+        for (arg, param) in args.into_iter().zip(function.params()) {
+            predicates.push(
+                arg.coerce(&Typed::of_type(Predicate::Constant(Scalar::Null), *param))?
+                    .predicate,
+            );
+        }
+        let returns = function.returns();
+        let constants = predicates
+            .iter()
+            .map(|predicate| match predicate {
+                Predicate::Constant(scalar) => Some(scalar.clone()),
+                _ => None,
+            })
+            .collect::<Option<Vec<Scalar>>>();
+        match constants {
+            Some(args) if !function.is_volatile() => {
+                Ok(Typed::of_type(Predicate::Constant(function.call(&args)?), returns))
+            }
+            _ => Ok(Typed::of_type(Predicate::Call(Arc::new(function), predicates), returns)),
+        }
+    }
+
+    /// Operand of a logical operator, it has to be boolean or NULL
+    fn condition(&self, expr: &Expr, operator: &str) -> Result<Predicate, QueryError> {
+        let typed = self.compile(expr)?;
+        match typed.kind {
+            Kind::Bool => Ok(typed.predicate),
+            Kind::Unknown if typed.predicate == Predicate::Constant(Scalar::Null) => Ok(typed.predicate),
+            _ => Err(QueryError::datatype_mismatch(format!(
+                "argument of {} must be type boolean, not type {}",
+                operator, typed.type_name
+            ))),
+        }
+    }
+
+    /// Operands of arithmetic have to be numbers, string literals and NULL
+    /// take the type of the other operand
+    fn arithmetic(&self, op: &BinaryOperator, left: Typed, right: Typed) -> Result<Typed, QueryError> {
+        let left = left.coerce(&right)?;
+        let right = right.coerce(&left)?;
+        let sql_type = match (left.kind, right.kind, left.sql_type, right.sql_type) {
+            (Kind::Number, Kind::Number, Some(left), Some(right)) => promote(left, right),
+            _ => {
+                return Err(QueryError::undefined_function(
+                    op.to_string(),
+                    left.type_name.to_owned(),
+                    right.type_name.to_owned(),
+                ))
+            }
+        };
+        Ok(Typed {
+            predicate: Predicate::Arithmetic(
+                op.clone(),
+                Box::new(left.predicate),
+                Box::new(right.predicate),
+                sql_type,
+            ),
+            kind: Kind::Number,
+            type_name: (&sql_type).into(),
+            sql_type: Some(sql_type),
+        })
+    }
+
+    fn column(&self, index: usize) -> Typed {
+        let sql_type = self.types[index];
+        Typed {
+            predicate: Predicate::Column {
+                index,
+                kind: Kind::of(&sql_type),
+                blank_padded: matches!(sql_type, SqlType::Char(_)),
+            },
+            kind: Kind::of(&sql_type),
+            type_name: (&sql_type).into(),
+            sql_type: Some(sql_type),
+        }
+    }
+}
+
+/// Fields of a row constructor `ROW(a, b)`, parenthesized lists are turned
+/// into them by the parser
+fn row_fields(expr: &Expr) -> Option<&[Expr]> {
+    match expr {
+        Expr::Nested(expr) => row_fields(expr),
+        Expr::Function(Function {
+            name,
+            args,
+            over: None,
+            distinct: false,
+        }) => match name.0.as_slice() {
+            [name] if name.quote_style.is_none() && name.value.eq_ignore_ascii_case("row") => Some(args),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// `NOT predicate` if the predicate is `negated`
+fn negate(predicate: Predicate, negated: bool) -> Predicate {
+    if negated {
+        Predicate::Not(Box::new(predicate))
+    } else {
+        predicate
+    }
+}
+
+fn number_literal(number: &str, negative: bool) -> Result<Typed, QueryError> {
+    let number = if negative {
+        format!("-{}", number)
+    } else {
+        number.to_owned()
+    };
+    let (scalar, type_name, sql_type) = match number.parse::<i32>() {
+        Ok(value) => (Scalar::Integer(i64::from(value)), "integer", SqlType::Integer(i32::MIN)),
+        Err(_) => match number.parse::<i64>() {
+            Ok(value) => (Scalar::Integer(value), "bigint", SqlType::BigInt(i64::MIN)),
+            Err(_) => match number.parse::<f64>() {
+                Ok(value) => (Scalar::Float(value), "numeric", SqlType::Decimal),
+                Err(_) => return Err(invalid_input("numeric", &number)),
+            },
+        },
+    };
+    Ok(Typed {
+        predicate: Predicate::Constant(scalar),
+        kind: Kind::Number,
+        type_name,
+        sql_type: Some(sql_type),
+    })
+}
+
+/// Type of the result of arithmetic of numbers of `left` and `right` types,
+/// the wider type of both
+fn promote(left: SqlType, right: SqlType) -> SqlType {
+    match (left, right) {
+        (SqlType::DoublePrecision, _) | (_, SqlType::DoublePrecision) => SqlType::DoublePrecision,
+        (SqlType::Real, SqlType::Real) => SqlType::Real,
+        (SqlType::Real, _) | (_, SqlType::Real) => SqlType::DoublePrecision,
+        (SqlType::Decimal, _) | (_, SqlType::Decimal) => SqlType::Decimal,
+        (SqlType::BigInt(_), _) | (_, SqlType::BigInt(_)) => SqlType::BigInt(i64::MIN),
+        (SqlType::Integer(_), _) | (_, SqlType::Integer(_)) => SqlType::Integer(i32::MIN),
+        _ => SqlType::SmallInt(i16::MIN),
+    }
+}
+
+/// Integers are computed exactly and have to fit into the type of the
+/// result, other numbers are computed as floats
+fn arithmetic(op: &BinaryOperator, left: Scalar, right: Scalar, result: &SqlType) -> Result<Scalar, QueryError> {
+    let range = match result {
+        SqlType::SmallInt(_) => Some((i64::from(i16::MIN), i64::from(i16::MAX))),
+        SqlType::Integer(_) => Some((i64::from(i32::MIN), i64::from(i32::MAX))),
+        SqlType::BigInt(_) => Some((i64::MIN, i64::MAX)),
+        _ => None,
+    };
+    let out_of_range = || QueryError::value_out_of_range(result.into());
+    match (left, right, range) {
+        (Scalar::Integer(left), Scalar::Integer(right), Some((min, max))) => {
+            let value = match op {
+                BinaryOperator::Divide | BinaryOperator::Modulus if right == 0 => {
+                    return Err(QueryError::division_by_zero())
+                }
+                BinaryOperator::Plus => left.checked_add(right),
+                BinaryOperator::Minus => left.checked_sub(right),
+                BinaryOperator::Multiply => left.checked_mul(right),
+                BinaryOperator::Divide => left.checked_div(right),
+                _ => left.checked_rem(right),
+            };
+            match value {
+                Some(value) if min <= value && value <= max => Ok(Scalar::Integer(value)),
+                _ => Err(out_of_range()),
+            }
+        }
+        (Scalar::Integer(left), Scalar::Integer(right), None) => float_arithmetic(op, left as f64, right as f64),
+        (Scalar::Integer(left), Scalar::Float(right), _) => float_arithmetic(op, left as f64, right),
+        (Scalar::Float(left), Scalar::Integer(right), _) => float_arithmetic(op, left, right as f64),
+        (Scalar::Float(left), Scalar::Float(right), _) => float_arithmetic(op, left, right),
+        _ => Ok(Scalar::Null),
+    }
+    .and_then(|value| match value {
+        Scalar::Float(value) if !value.is_finite() => Err(out_of_range()),
+        value => Ok(value),
+    })
+}
+
+fn float_arithmetic(op: &BinaryOperator, left: f64, right: f64) -> Result<Scalar, QueryError> {
+    Ok(Scalar::Float(match op {
+        BinaryOperator::Divide | BinaryOperator::Modulus if right == 0.0 => return Err(QueryError::division_by_zero()),
+        BinaryOperator::Plus => left + right,
+        BinaryOperator::Minus => left - right,
+        BinaryOperator::Multiply => left * right,
+        BinaryOperator::Divide => left / right,
+        _ => left % right,
+    }))
+}
+
+fn invalid_input(type_name: &str, text: &str) -> QueryError {
+    QueryError::datatype_mismatch(format!("invalid input syntax for type {}: \"{}\"", type_name, text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{query::resolve::Scope, ColumnDefinition};
+    use protocol::sql_types::PostgreSqlType;
+    use sqlparser::{ast::Ident, dialect::PostgreSqlDialect, parser::Parser, tokenizer::Tokenizer};
+
+    fn columns() -> Vec<ColumnDefinition> {
+        vec![
+            ColumnDefinition::new("id", SqlType::Integer(i32::MIN)),
+            ColumnDefinition::new("name", SqlType::Char(5)),
+            ColumnDefinition::new("price", SqlType::Real),
+        ]
+    }
+
+    fn compile(sql: &str) -> Result<Predicate, QueryError> {
+        let tokens = Tokenizer::new(&PostgreSqlDialect {}, sql).tokenize().expect("tokens");
+        let expr = Parser::new(tokens).parse_expr().expect("expression");
+        let columns = columns();
+        let types = columns.iter().map(ColumnDefinition::sql_type).collect::<Vec<SqlType>>();
+        let scope = Scope::new(&columns);
+        Predicate::compile(
+            &expr,
+            "WHERE",
+            &types,
+            &ScalarFunctions::default(),
+            None,
+            &|expr| match expr {
+                Expr::Identifier(Ident { value, .. }) => scope.lookup(value),
+                Expr::CompoundIdentifier(idents) => match idents.as_slice() {
+                    [table, column] if table.value == "t" => scope.lookup(&column.value),
+                    _ => Err(QueryError::missing_from_clause_entry(idents[0].value.clone())),
+                },
+                _ => Ok(None),
+            },
+        )
+    }
+
+    fn holds(sql: &str, row: &[Datum]) -> bool {
+        compile(sql).expect("compiled predicate").holds(row)
+    }
+
+    fn row(id: Option<i32>, name: &str, price: f32) -> Vec<Datum<'static>> {
+        vec![
+            id.map(Datum::from_i32).unwrap_or_else(Datum::from_null),
+            Datum::from_string(name.to_owned()),
+            Datum::from_f32(price),
+        ]
+    }
+
+    #[test]
+    fn compares_numbers() {
+        let row = row(Some(5), "a", 2.5);
+        assert!(holds("id = 5", &row));
+        assert!(holds("t.id >= -1", &row));
+        assert!(holds("price < 3", &row));
+        assert!(holds("2.5 = price", &row));
+        assert!(!holds("id <> 5", &row));
+    }
+
+    #[test]
+    fn coerces_string_literals_to_type_of_column() {
+        let row = row(Some(5), "a    ", 2.5);
+        assert!(holds("id = '5'", &row));
+        assert!(holds("name = 'a'", &row));
+        assert_eq!(
+            compile("id = 'five'"),
+            Err(QueryError::datatype_mismatch(
+                "invalid input syntax for type integer: \"five\"".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn logical_operators() {
+        let row = row(Some(5), "a", 2.5);
+        assert!(holds("id = 5 and (price > 3 or name = 'a')", &row));
+        assert!(!holds("id = 5 and not (price > 3 or name = 'a')", &row));
+    }
+
+    #[test]
+    fn comparison_with_null_is_unknown() {
+        let row = row(None, "a", 2.5);
+        assert!(!holds("id = 5", &row));
+        assert!(!holds("not id = 5", &row));
+        assert!(holds("id = 5 or price > 1", &row));
+        assert!(holds("id is null", &row));
+        assert!(!holds("id is not null", &row));
+    }
+
+    #[test]
+    fn comparison_of_different_types() {
+        assert_eq!(
+            compile("name = 5"),
+            Err(QueryError::undefined_function(
+                "=".to_owned(),
+                "char".to_owned(),
+                "integer".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn non_boolean_condition() {
+        assert_eq!(
+            compile("id"),
+            Err(QueryError::datatype_mismatch(
+                "argument of WHERE must be type boolean, not type integer".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn conditions_of_text_values() {
+        let predicate = compile("id > 4 and name = 'a' and price < 3").expect("compiled predicate");
+
+        assert!(predicate.holds_for_text(&[Some("5".to_owned()), Some("a   ".to_owned()), Some("2.5".to_owned())]));
+        assert!(!predicate.holds_for_text(&[None, Some("a".to_owned()), Some("2.5".to_owned())]));
+    }
+
+    fn value(sql: &str, row: &[Datum]) -> Result<(Option<String>, SqlType), QueryError> {
+        let tokens = Tokenizer::new(&PostgreSqlDialect {}, sql).tokenize().expect("tokens");
+        let expr = Parser::new(tokens).parse_expr().expect("expression");
+        let columns = columns();
+        let types = columns.iter().map(ColumnDefinition::sql_type).collect::<Vec<SqlType>>();
+        let scope = Scope::new(&columns);
+        let (expression, sql_type) =
+            Predicate::expression(&expr, &types, &ScalarFunctions::default(), &|expr| match expr {
+                Expr::Identifier(Ident { value, .. }) => scope.lookup(value),
+                _ => Ok(None),
+            })?;
+        Ok((expression.value(row)?, sql_type))
+    }
+
+    #[test]
+    fn arithmetic_of_numbers() {
+        let row = row(Some(5), "a", 2.5);
+        assert_eq!(
+            value("id + 2 * 3", &row),
+            Ok((Some("11".to_owned()), SqlType::Integer(i32::MIN)))
+        );
+        assert_eq!(
+            value("(id - 1) / 3 % 2", &row),
+            Ok((Some("1".to_owned()), SqlType::Integer(i32::MIN)))
+        );
+        assert_eq!(
+            value("price * 2", &row),
+            Ok((Some("5".to_owned()), SqlType::DoublePrecision))
+        );
+        assert_eq!(
+            value("-id", &row),
+            Ok((Some("-5".to_owned()), SqlType::Integer(i32::MIN)))
+        );
+        assert_eq!(value("id + 0.5", &row), Ok((Some("5.5".to_owned()), SqlType::Decimal)));
+        assert_eq!(
+            value("id + '1'", &row),
+            Ok((Some("6".to_owned()), SqlType::Integer(i32::MIN)))
+        );
+        assert!(holds("id * 2 > price + 7", &row));
+    }
+
+    #[test]
+    fn arithmetic_with_null_is_null() {
+        assert_eq!(
+            value("id + 1", &row(None, "a", 2.5)),
+            Ok((None, SqlType::Integer(i32::MIN)))
+        );
+        assert_eq!(
+            value("price - null", &row(Some(5), "a", 2.5)),
+            Ok((None, SqlType::Real))
+        );
+    }
+
+    #[test]
+    fn failed_arithmetic() {
+        let row = row(Some(5), "a", 2.5);
+        assert_eq!(value("id / 0", &row), Err(QueryError::division_by_zero()));
+        assert_eq!(value("price % 0", &row), Err(QueryError::division_by_zero()));
+        assert_eq!(
+            value("id * 2147483647", &row),
+            Err(QueryError::value_out_of_range(PostgreSqlType::Integer))
+        );
+        assert_eq!(
+            value("name + 1", &row),
+            Err(QueryError::undefined_function(
+                "+".to_owned(),
+                "char".to_owned(),
+                "integer".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn calls_of_scalar_functions() {
+        let row = row(Some(5), "ab   ", 2.5);
+        assert!(holds("upper(name) = 'AB'", &row));
+        assert!(holds("length(name) = 2", &row));
+        assert!(holds("substring('abc', id - 3) = 'bc'", &row));
+        assert_eq!(
+            value("trim(upper(name), 'B')", &row),
+            Ok((Some("A".to_owned()), SqlType::VarChar(u64::MAX)))
+        );
+        assert_eq!(
+            compile("length(id) = 1"),
+            Err(QueryError::function_does_not_exist("length(integer)".to_owned()))
+        );
+        assert_eq!(
+            compile("substring(name) = 'a'"),
+            Err(QueryError::function_does_not_exist("substring(char)".to_owned()))
+        );
+    }
+
+    #[test]
+    fn unknown_functions() {
+        assert_eq!(
+            compile("f(id) = 1"),
+            Err(QueryError::function_does_not_exist("f(integer)".to_owned()))
+        );
+    }
+
+    #[test]
+    fn unknown_qualifier() {
+        assert_eq!(
+            compile("other.id = 1"),
+            Err(QueryError::missing_from_clause_entry("other".to_owned()))
+        );
+    }
+
+    #[test]
+    fn between() {
+        let row = row(Some(5), "b", 2.5);
+        assert!(holds("id between 1 and 5", &row));
+        assert!(!holds("id not between 1 and 5", &row));
+        assert!(holds("price not between 3 and 4", &row));
+        assert!(holds("name between 'a' and 'c'", &row));
+        assert!(holds("id between '1' and '10'", &row));
+        assert!(!holds("id between null and 10", &row));
+        assert!(!holds("id not between null and 10", &row));
+        assert!(holds("id not between null and 4", &row));
+        assert_eq!(
+            compile("id between 'a' and 10"),
+            Err(QueryError::datatype_mismatch(
+                "invalid input syntax for type integer: \"a\"".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn in_list() {
+        let row = row(Some(5), "b    ", 2.5);
+        assert!(holds("id in (1, 5, 7)", &row));
+        assert!(!holds("id not in (1, 5, 7)", &row));
+        assert!(holds("id in ('3', '5')", &row));
+        assert!(holds("name in ('a', 'b')", &row));
+        assert!(holds("price in (2.5)", &row));
+        assert!(holds("id in (1, null, 5)", &row));
+        assert!(!holds("id in (1, null)", &row));
+        assert!(!holds("id not in (1, null)", &row));
+        assert!(!holds("null in (1, 5)", &row));
+        assert_eq!(
+            compile("id in (1, 'five')"),
+            Err(QueryError::datatype_mismatch(
+                "invalid input syntax for type integer: \"five\"".to_owned()
+            ))
+        );
+        assert_eq!(
+            compile("name in (1, 2)"),
+            Err(QueryError::undefined_function(
+                "=".to_owned(),
+                "char".to_owned(),
+                "integer".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn row_comparisons() {
+        let row = row(Some(5), "b    ", 2.5);
+        assert!(holds("row(id, name) = row(5, 'b')", &row));
+        assert!(holds("row(id, price) <> row(5, 3)", &row));
+        assert!(holds("row(id, price) < row(5, 3)", &row));
+        assert!(holds("row(id, price) <= row(5, 2.5)", &row));
+        assert!(!holds("row(id, price) > row(5, 2.5)", &row));
+        assert!(holds("row(id, null) < row(6, 1)", &row));
+        assert!(!holds("row(id, null) < row(5, 1)", &row));
+        assert!(!holds("not row(id, null) < row(5, 1)", &row));
+        assert!(!holds("row(id, null) = row(5, 1)", &row));
+        assert!(holds("not row(id, null) = row(4, 1)", &row));
+        assert!(holds(
+            "row(id, row(name, price)) in (row(1, row('a', 1)), row(5, row('b', 2.5)))",
+            &row
+        ));
+    }
+
+    #[test]
+    fn searched_case() {
+        let row = row(Some(5), "a", 2.5);
+        assert_eq!(
+            value(
+                "case when id > 10 then 'big' when id > 1 then 'small' else 'tiny' end",
+                &row
+            ),
+            Ok((Some("small".to_owned()), SqlType::VarChar(u64::MAX)))
+        );
+        assert_eq!(
+            value("case when id > 10 then 1 end", &row),
+            Ok((None, SqlType::Integer(i32::MIN)))
+        );
+        assert!(holds("case when name = 'a' then price > 2 else false end", &row));
+        assert!(!holds("case when id is null then true end", &row));
+    }
+
+    #[test]
+    fn simple_case() {
+        assert_eq!(
+            value(
+                "case id when 1 then 'one' when 5 then 'five' end",
+                &row(Some(5), "a", 2.5)
+            ),
+            Ok((Some("five".to_owned()), SqlType::VarChar(u64::MAX)))
+        );
+        assert_eq!(
+            value("case id when 1 then 'one' else 'other' end", &row(None, "a", 2.5)),
+            Ok((Some("other".to_owned()), SqlType::VarChar(u64::MAX)))
+        );
+    }
+
+    #[test]
+    fn case_results_take_common_type() {
+        let row = row(Some(5), "a", 2.5);
+        assert_eq!(
+            value("case when id = 5 then id else price end", &row),
+            Ok((Some("5".to_owned()), SqlType::DoublePrecision))
+        );
+        assert_eq!(
+            value("case when id = 1 then id else '7' end", &row),
+            Ok((Some("7".to_owned()), SqlType::Integer(i32::MIN)))
+        );
+        assert_eq!(
+            value("case when id = 1 then id else 'seven' end", &row),
+            Err(QueryError::datatype_mismatch(
+                "invalid input syntax for type integer: \"seven\"".to_owned()
+            ))
+        );
+        assert_eq!(
+            value("case when id = 1 then id else name end", &row),
+            Err(QueryError::datatype_mismatch(
+                "CASE types integer and char cannot be matched".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn non_boolean_case_condition() {
+        assert_eq!(
+            value("case when id then 1 end", &row(Some(5), "a", 2.5)),
+            Err(QueryError::datatype_mismatch(
+                "argument of CASE/WHEN must be type boolean, not type integer".to_owned()
+            ))
+        );
+    }
+}
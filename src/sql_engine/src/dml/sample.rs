@@ -0,0 +1,338 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Sampling of table scans for `TABLESAMPLE` clauses. The SQL parser does not
+//! know the clause, so statements are rewritten before parsing to use table
+//! hints `WITH (TABLESAMPLE(method, percentage), REPEATABLE(seed))` that are
+//! read back here.
+use protocol::results::QueryError;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use sqlparser::ast::{Expr, Function, Query, SetExpr, TableFactor, UnaryOperator, Value};
+use std::iter;
+use storage::{ReadCursor, RowResult};
+
+/// Number of consecutive rows that `SYSTEM` sampling selects or skips as a
+/// whole
+pub(crate) const SAMPLE_BLOCK_SIZE: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum SampleMethod {
+    /// selects blocks of rows
+    System,
+    /// selects each row on its own
+    Bernoulli,
+}
+
+/// Fraction of rows of a table that a scan returns
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TableSample {
+    method: SampleMethod,
+    fraction: f64,
+    seed: Option<u64>,
+}
+
+impl TableSample {
+    /// Reads the sample from hints of a table, `None` if the table is not
+    /// sampled
+    pub(crate) fn from_hints(hints: &[Expr]) -> Result<Option<TableSample>, QueryError> {
+        let (method, arguments) = match hints.iter().find_map(|hint| hint_call(hint, "tablesample")) {
+            Some((method, arguments)) => (method, arguments),
+            None => return Ok(None),
+        };
+        let name = match method {
+            Expr::Identifier(ident) => ident.value.to_lowercase(),
+            other => return Err(QueryError::tablesample_method_does_not_exist(other.to_string())),
+        };
+        let method = match name.as_str() {
+            "system" => SampleMethod::System,
+            "bernoulli" => SampleMethod::Bernoulli,
+            _ => return Err(QueryError::tablesample_method_does_not_exist(name)),
+        };
+        let percentage = match arguments {
+            [percentage] => number(percentage, "TABLESAMPLE parameter cannot be null")?,
+            _ => {
+                return Err(QueryError::invalid_tablesample_argument(format!(
+                    "tablesample method {} requires 1 argument, not {}",
+                    name,
+                    arguments.len()
+                )))
+            }
+        };
+        if !(0.0..=100.0).contains(&percentage) {
+            return Err(QueryError::invalid_tablesample_argument(
+                "sample percentage must be between 0 and 100".to_owned(),
+            ));
+        }
+        let seed = match hints.iter().find_map(|hint| hint_call(hint, "repeatable")) {
+            Some((seed, [])) => Some(number(seed, "TABLESAMPLE REPEATABLE parameter cannot be null")?.to_bits()),
+            Some(_) => {
+                return Err(QueryError::feature_not_supported(
+                    "REPEATABLE with many seeds".to_owned(),
+                ))
+            }
+            None => None,
+        };
+        Ok(Some(TableSample {
+            method,
+            fraction: percentage / 100.0,
+            seed,
+        }))
+    }
+
+    /// Whether the sample returns different rows each time the table is
+    /// scanned
+    pub(crate) fn is_random(&self) -> bool {
+        self.seed.is_none()
+    }
+
+    /// Returns rows of `cursor` that belong to the sample. Errors of the
+    /// underlying scan are always returned.
+    pub(crate) fn scan(&self, cursor: ReadCursor) -> ReadCursor {
+        let rng = match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        Box::new(Sampled {
+            cursor,
+            method: self.method,
+            fraction: self.fraction,
+            rng,
+            scanned: 0,
+            block_selected: false,
+        })
+    }
+}
+
+/// Whether `query` reads a table sample that is not `REPEATABLE`, so its
+/// result differs from one execution to another
+pub(crate) fn samples_randomly(query: &Query) -> bool {
+    body_samples_randomly(&query.body)
+}
+
+fn body_samples_randomly(body: &SetExpr) -> bool {
+    match body {
+        SetExpr::Query(query) => samples_randomly(query),
+        SetExpr::SetOperation { left, right, .. } => body_samples_randomly(left) || body_samples_randomly(right),
+        SetExpr::Select(select) => select
+            .from
+            .iter()
+            .flat_map(|table| iter::once(&table.relation).chain(table.joins.iter().map(|join| &join.relation)))
+            .any(|relation| match relation {
+                TableFactor::Table { with_hints, .. } => matches!(
+                    TableSample::from_hints(with_hints),
+                    Ok(Some(sample)) if sample.is_random()
+                ),
+                _ => false,
+            }),
+        _ => false,
+    }
+}
+
+struct Sampled {
+    cursor: ReadCursor,
+    method: SampleMethod,
+    fraction: f64,
+    rng: StdRng,
+    scanned: usize,
+    block_selected: bool,
+}
+
+impl Iterator for Sampled {
+    type Item = RowResult;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let row = self.cursor.next()?;
+            let selected = match self.method {
+                SampleMethod::System => {
+                    if self.scanned.is_multiple_of(SAMPLE_BLOCK_SIZE) {
+                        self.block_selected = self.rng.gen::<f64>() < self.fraction;
+                    }
+                    self.block_selected
+                }
+                SampleMethod::Bernoulli => self.rng.gen::<f64>() < self.fraction,
+            };
+            self.scanned += 1;
+            if selected || !matches!(row, Ok(Ok(_))) {
+                return Some(row);
+            }
+        }
+    }
+}
+
+/// First argument and the rest of arguments of the hint if it is a call of
+/// `name`
+fn hint_call<'h>(hint: &'h Expr, name: &str) -> Option<(&'h Expr, &'h [Expr])> {
+    match hint {
+        Expr::Function(Function {
+            name: function, args, ..
+        }) if function.0.len() == 1 && function.0[0].value.eq_ignore_ascii_case(name) => args.split_first(),
+        _ => None,
+    }
+}
+
+fn number(expr: &Expr, null_message: &str) -> Result<f64, QueryError> {
+    let parsed = match expr {
+        Expr::Value(Value::Null) => return Err(QueryError::invalid_tablesample_argument(null_message.to_owned())),
+        Expr::Value(Value::Number(number)) => number.to_string().parse::<f64>().ok(),
+        Expr::UnaryOp {
+            op: UnaryOperator::Minus,
+            expr,
+        } => match &**expr {
+            Expr::Value(Value::Number(number)) => number.to_string().parse::<f64>().ok().map(|number| -number),
+            _ => None,
+        },
+        _ => None,
+    };
+    parsed.ok_or_else(|| QueryError::feature_not_supported(format!("TABLESAMPLE argument {}", expr)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use representation::{Binary, Datum};
+    use sqlparser::ast::{Ident, ObjectName};
+    use std::io;
+
+    fn cursor(rows: usize) -> ReadCursor {
+        Box::new(
+            (0..rows)
+                .map(|index| {
+                    let key = Binary::pack(&[Datum::from_u64(index as u64)]);
+                    Ok(Ok((key.clone(), key)))
+                })
+                .collect::<Vec<io::Result<_>>>()
+                .into_iter(),
+        )
+    }
+
+    fn call(name: &str, args: Vec<Expr>) -> Expr {
+        Expr::Function(Function {
+            name: ObjectName(vec![Ident::new(name)]),
+            args,
+            over: None,
+            distinct: false,
+        })
+    }
+
+    fn number(value: &str) -> Expr {
+        Expr::Value(Value::Number(value.parse().expect("number")))
+    }
+
+    fn sample(method: SampleMethod, percentage: f64, seed: u64) -> TableSample {
+        TableSample {
+            method,
+            fraction: percentage / 100.0,
+            seed: Some(seed),
+        }
+    }
+
+    fn keys(cursor: ReadCursor) -> Vec<Binary> {
+        cursor.map(|row| row.unwrap().unwrap().0).collect()
+    }
+
+    #[test]
+    fn not_sampled_table() {
+        assert_eq!(TableSample::from_hints(&[]), Ok(None));
+    }
+
+    #[test]
+    fn sample_from_hints() {
+        assert_eq!(
+            TableSample::from_hints(&[
+                call(
+                    "TABLESAMPLE",
+                    vec![Expr::Identifier(Ident::new("Bernoulli")), number("12.5")]
+                ),
+                call("REPEATABLE", vec![number("42")])
+            ]),
+            Ok(Some(TableSample {
+                method: SampleMethod::Bernoulli,
+                fraction: 0.125,
+                seed: Some(42f64.to_bits())
+            }))
+        );
+    }
+
+    #[test]
+    fn invalid_samples() {
+        assert_eq!(
+            TableSample::from_hints(&[call(
+                "TABLESAMPLE",
+                vec![Expr::Identifier(Ident::new("random")), number("10")]
+            )]),
+            Err(QueryError::tablesample_method_does_not_exist("random".to_owned()))
+        );
+        assert_eq!(
+            TableSample::from_hints(&[call(
+                "TABLESAMPLE",
+                vec![Expr::Identifier(Ident::new("system")), number("101")]
+            )]),
+            Err(QueryError::invalid_tablesample_argument(
+                "sample percentage must be between 0 and 100".to_owned()
+            ))
+        );
+        assert_eq!(
+            TableSample::from_hints(&[call(
+                "TABLESAMPLE",
+                vec![Expr::Identifier(Ident::new("system")), Expr::Value(Value::Null)]
+            )]),
+            Err(QueryError::invalid_tablesample_argument(
+                "TABLESAMPLE parameter cannot be null".to_owned()
+            ))
+        );
+        assert_eq!(
+            TableSample::from_hints(&[call(
+                "TABLESAMPLE",
+                vec![Expr::Identifier(Ident::new("system")), number("1"), number("2")]
+            )]),
+            Err(QueryError::invalid_tablesample_argument(
+                "tablesample method system requires 1 argument, not 2".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn whole_and_empty_samples() {
+        for method in &[SampleMethod::System, SampleMethod::Bernoulli] {
+            assert_eq!(keys(sample(*method, 100.0, 1).scan(cursor(100))).len(), 100);
+            assert_eq!(keys(sample(*method, 0.0, 1).scan(cursor(100))).len(), 0);
+        }
+    }
+
+    #[test]
+    fn system_sample_selects_whole_blocks() {
+        let rows = keys(sample(SampleMethod::System, 50.0, 7).scan(cursor(SAMPLE_BLOCK_SIZE * 100)));
+        assert_eq!(rows.len() % SAMPLE_BLOCK_SIZE, 0);
+        assert!(rows.len() > SAMPLE_BLOCK_SIZE * 25 && rows.len() < SAMPLE_BLOCK_SIZE * 75);
+    }
+
+    #[test]
+    fn bernoulli_sample_selects_rows() {
+        let rows = keys(sample(SampleMethod::Bernoulli, 10.0, 7).scan(cursor(10_000)));
+        assert!(rows.len() > 500 && rows.len() < 1_500);
+    }
+
+    #[test]
+    fn repeatable_samples_are_the_same() {
+        let sample = sample(SampleMethod::Bernoulli, 30.0, 3);
+        assert_eq!(keys(sample.scan(cursor(1_000))), keys(sample.scan(cursor(1_000))));
+    }
+
+    #[test]
+    fn scan_errors_are_not_sampled_out() {
+        let failing: ReadCursor = Box::new(vec![Err(io::Error::other("disk failure"))].into_iter());
+        assert_eq!(sample(SampleMethod::System, 0.0, 1).scan(failing).count(), 1);
+    }
+}
@@ -12,20 +12,59 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::catalog_manager::CatalogManager;
+use crate::{
+    cancellation::CancellationToken,
+    catalog_manager::CatalogManager,
+    dml::{
+        aggregation::{Aggregation, State},
+        batch::{self, Batch, Batches, GroupTable, Interrupted},
+        cte::{self, Terms, WorkTable, WorkTables, DEFAULT_MAX_RECURSIVE_ITERATIONS},
+        functions::ScalarFunctions,
+        join::{self, JoinKind},
+        limit::{Limited, RowLimit},
+        memory::{text_rows_size, WorkMem},
+        predicate::Predicate,
+        sample::TableSample,
+        set_operation,
+        sort::{Order, Sorted},
+        subquery::{Output, Rows, Subqueries},
+        values,
+    },
+    procedural,
+    query::{match_operator::MATCH_FUNCTION, resolve::Scope},
+    transaction::Transaction,
+    ColumnDefinition,
+};
 use kernel::{SystemError, SystemResult};
 use protocol::{
-    results::{Description, QueryError, QueryEvent},
+    results::{ColumnDescription, Description, QueryError, QueryEvent},
     Sender,
 };
-use sqlparser::ast::{Expr, Ident, Query, Select, SelectItem, SetExpr, TableFactor, TableWithJoins};
-use std::{ops::Deref, sync::Arc};
+use representation::Datum;
+use sql_types::SqlType;
+use sqlparser::ast::{
+    Expr, Function, Ident, Join, JoinConstraint, JoinOperator, ObjectName, Query, Select, SelectItem, SetExpr,
+    SetOperator, TableAlias, TableFactor, TableWithJoins, Values,
+};
+use std::iter;
+use std::{collections::HashMap, mem, ops::Deref, ops::Range, slice, sync::Arc};
+use storage::ReadCursor;
 
 pub(crate) struct SelectCommand<'sc> {
     raw_sql_query: &'sc str,
     query: Box<Query>,
     storage: Arc<CatalogManager>,
     session: Arc<dyn Sender>,
+    parallel_workers: usize,
+    work_mem: usize,
+    cancellation: CancellationToken,
+    /// functions that the statement calls
+    functions: ScalarFunctions,
+    max_recursive_iterations: usize,
+    /// common table expressions of the query and of queries it is nested in
+    work_tables: WorkTables,
+    /// transaction block whose written rows the query reads
+    transaction: Option<Arc<Transaction>>,
 }
 
 impl<'sc> SelectCommand<'sc> {
@@ -34,205 +73,1541 @@ impl<'sc> SelectCommand<'sc> {
         query: Box<Query>,
         storage: Arc<CatalogManager>,
         session: Arc<dyn Sender>,
+        parallel_workers: usize,
+        work_mem: usize,
+        functions: ScalarFunctions,
     ) -> SelectCommand<'sc> {
         SelectCommand {
             raw_sql_query,
             query,
             storage,
             session,
+            parallel_workers,
+            work_mem,
+            cancellation: functions.cancellation().clone(),
+            functions,
+            max_recursive_iterations: DEFAULT_MAX_RECURSIVE_ITERATIONS,
+            work_tables: WorkTables::new(),
+            transaction: None,
         }
     }
 
-    pub(crate) fn describe(&mut self) -> SystemResult<Description> {
-        let input = self.parse_select_input()?;
+    /// Recursive common table expressions that don't reach a fixpoint in
+    /// `iterations` fail the query
+    pub(crate) fn with_max_recursive_iterations(mut self, iterations: usize) -> SelectCommand<'sc> {
+        self.max_recursive_iterations = iterations;
+        self
+    }
 
-        let all_columns = self.storage.table_columns(&input.schema_name, &input.table_name)?;
-        let mut column_definitions = vec![];
-        let mut non_existing_columns = vec![];
-        for column_name in &input.selected_columns {
-            let mut found = None;
-            for column_definition in &all_columns {
-                if column_definition.has_name(&column_name) {
-                    found = Some(column_definition);
-                    break;
-                }
-            }
+    fn with_work_tables(mut self, work_tables: WorkTables) -> SelectCommand<'sc> {
+        self.work_tables = work_tables;
+        self
+    }
 
-            if let Some(column_definition) = found {
-                column_definitions.push(column_definition);
-            } else {
-                non_existing_columns.push(column_name.clone());
+    /// Tables are read as `transaction` sees them, only rows of storage are
+    /// read without it
+    pub(crate) fn with_transaction(mut self, transaction: Option<Arc<Transaction>>) -> SelectCommand<'sc> {
+        self.transaction = transaction;
+        self
+    }
+
+    pub(crate) fn describe(&mut self) -> SystemResult<Description> {
+        let order = match Order::of(&mut self.query) {
+            Ok(order) => order,
+            Err(error) => {
+                self.session.send(Err(error)).map_err(SystemError::connection_lost)?;
+                return Err(SystemError::runtime_check_failure("Invalid Order".to_owned()));
             }
-        }
+        };
+        let description = self.describe_columns()?;
+        Ok(match order {
+            Some(order) => order.visible(description),
+            None => description,
+        })
+    }
 
-        if !non_existing_columns.is_empty() {
-            self.session
-                .send(Err(QueryError::column_does_not_exist(non_existing_columns)))
-                .expect("To Send Result to Client");
-            return Err(SystemError::runtime_check_failure("Column Does Not Exist".to_owned()));
+    /// Description of all selected columns including hidden sort keys
+    fn describe_columns(&mut self) -> SystemResult<Description> {
+        if !self.bind_ctes(true)? {
+            return Err(SystemError::runtime_check_failure(
+                "Common Table Expression Failed".to_owned(),
+            ));
         }
+        if let SetExpr::Values(values) = &self.query.body {
+            return match values::evaluate(values, &self.functions) {
+                Ok((description, _rows)) => Ok(description),
+                Err(error) => {
+                    self.session.send(Err(error)).map_err(SystemError::connection_lost)?;
+                    Err(SystemError::runtime_check_failure("Invalid Values".to_owned()))
+                }
+            };
+        }
+        if let SetExpr::SetOperation { op, left, right, .. } = &self.query.body {
+            let left = self.nested_command(left).describe()?;
+            let right = self.nested_command(right).describe()?;
+            return match set_operation::describe(op, left, &right) {
+                Ok(description) => Ok(description),
+                Err(error) => {
+                    self.session.send(Err(error)).map_err(SystemError::connection_lost)?;
+                    Err(SystemError::runtime_check_failure(
+                        "Set Operation Types Mismatch".to_owned(),
+                    ))
+                }
+            };
+        }
+        let input = self.parse_select_input()?;
 
-        let description = column_definitions
-            .into_iter()
-            .map(|column_definition| (column_definition.name(), (&column_definition.sql_type()).into()))
-            .collect();
-
-        Ok(description)
+        let columns = self.columns(&input)?;
+        match resolve(&input, &columns, &self.functions) {
+            Ok(resolved) => Ok(resolved.description),
+            Err(error) => {
+                self.session.send(Err(error)).map_err(SystemError::connection_lost)?;
+                Err(SystemError::runtime_check_failure("Column Does Not Exist".to_owned()))
+            }
+        }
     }
 
     pub(crate) fn execute(&mut self) -> SystemResult<()> {
+        if !self.bind_ctes(false)? {
+            return Ok(());
+        }
+        match RowLimit::of(&self.query, &self.functions) {
+            Ok(Some(limit)) => self.session = Arc::new(Limited::new(self.session.clone(), limit)),
+            Ok(None) => {}
+            Err(error) => {
+                self.session.send(Err(error)).map_err(SystemError::connection_lost)?;
+                return Ok(());
+            }
+        }
+        match Order::of(&mut self.query) {
+            Ok(Some(order)) => self.session = Arc::new(Sorted::new(self.session.clone(), order, self.work_mem)),
+            Ok(None) => {}
+            Err(error) => {
+                self.session.send(Err(error)).map_err(SystemError::connection_lost)?;
+                return Ok(());
+            }
+        }
+        if let SetExpr::SetOperation { op, all, left, right } = &self.query.body {
+            return self.execute_set_operation(op, *all, left, right);
+        }
+        if let SetExpr::Values(values) = &self.query.body {
+            let output = values::evaluate(values, &self.functions);
+            return self.send_output(output);
+        }
         let input = match self.parse_select_input() {
             Ok(input) => input,
             Err(_) => return Ok(()),
         };
 
-        match self.storage.full_scan(&input.schema_name, &input.table_name) {
-            Err(error) => return Err(error),
-            Ok(records) => {
-                let all_columns = self.storage.table_columns(&input.schema_name, &input.table_name)?;
-                let mut description = vec![];
-                let mut column_indexes = vec![];
-                let mut non_existing_columns = vec![];
-                for column_name in input.selected_columns.iter() {
-                    let mut found = None;
-                    for (index, column_definition) in all_columns.iter().enumerate() {
-                        if column_definition.has_name(column_name) {
-                            found = Some((index, column_definition.clone()));
-                            break;
+        let columns = self.columns(&input)?;
+        let functions = &self.functions;
+        let mut resolved = match resolve(&input, &columns, functions) {
+            Ok(resolved) => resolved,
+            Err(error) => {
+                self.session.send(Err(error)).map_err(SystemError::connection_lost)?;
+                return Ok(());
+            }
+        };
+        let subqueries: Arc<dyn Subqueries> = Arc::new(self.nested_select());
+        let conditions = match Conditions::compile(&input, &columns, &resolved, functions, &subqueries) {
+            Ok(conditions) => conditions,
+            Err(error) => {
+                self.session.send(Err(error)).map_err(SystemError::connection_lost)?;
+                return Ok(());
+            }
+        };
+
+        let mut joined: Option<ReadCursor> = None;
+        for (position, (relation, condition)) in input.relations.iter().zip(conditions.joins).enumerate() {
+            let scan = match self.scan(relation)? {
+                Some(scan) => scan,
+                None => return Ok(()),
+            };
+            joined = Some(match joined {
+                None => scan,
+                Some(outer) => {
+                    let mut work_mem = WorkMem::new(self.work_mem);
+                    let inner = match join::materialize(scan, &mut work_mem)? {
+                        Ok(inner) => inner,
+                        Err(()) => {
+                            self.session
+                                .send(Err(QueryError::out_of_memory("join", work_mem.limit())))
+                                .map_err(SystemError::connection_lost)?;
+                            return Ok(());
                         }
-                    }
+                    };
+                    self.storage
+                        .statistics()
+                        .seq_scanned(&relation.schema_name, &relation.table_name, inner.len());
+                    let widths = &columns.ranges[position];
+                    self.cancellation.guard(join::nested_loop(
+                        outer,
+                        inner,
+                        relation.join,
+                        condition,
+                        widths.start,
+                        widths.len(),
+                    ))
+                }
+            });
+        }
+        let records = joined.expect("at least one relation in FROM clause");
+        let records = match conditions.selection {
+            Some(selection) => selection.filter(records),
+            None => records,
+        };
+        if input.is_grouped() {
+            return self.execute_grouped(&input, resolved, conditions.having, records);
+        }
 
-                    if let Some((index, column_definition)) = found {
-                        column_indexes.push(index);
-                        description.push(column_definition);
-                    } else {
-                        non_existing_columns.push(column_name.clone());
-                    }
+        self.session
+            .send(Ok(QueryEvent::RowDescription(mem::take(&mut resolved.description))))
+            .map_err(SystemError::connection_lost)?;
+        let mut work_mem = WorkMem::new(self.work_mem);
+        let session = self.session.clone();
+        let emit = |rows| {
+            session.send(Ok(QueryEvent::DataRows(rows)))?;
+            session.flush()
+        };
+        let selected = if resolved.expressions.iter().all(Option::is_none) && session.accepts_encoded_rows() {
+            let indexes = resolved.indexes.iter().flatten().copied().collect::<Vec<usize>>();
+            batch::project_encoded(
+                Batches::new(records),
+                &indexes,
+                self.parallel_workers,
+                &mut work_mem,
+                |rows| {
+                    session.send(Ok(QueryEvent::EncodedDataRows(rows)))?;
+                    session.flush()
+                },
+            )
+        } else if resolved.expressions.iter().all(Option::is_none) {
+            let indexes = resolved.indexes.iter().flatten().copied().collect::<Vec<usize>>();
+            batch::project(
+                Batches::new(records),
+                &indexes,
+                self.parallel_workers,
+                &mut work_mem,
+                emit,
+            )
+        } else {
+            batch::project_rows(
+                Batches::new(records),
+                |batch| resolved.values(batch),
+                self.parallel_workers,
+                &mut work_mem,
+                emit,
+            )
+        };
+        match selected.and_then(|selected| {
+            self.cancellation.check().map_err(Interrupted::Failed)?;
+            Ok(selected)
+        }) {
+            Ok(selected) => {
+                self.storage.statistics().seq_scanned(
+                    &input.relations[0].schema_name,
+                    &input.relations[0].table_name,
+                    selected,
+                );
+                self.session
+                    .send(Ok(QueryEvent::SelectComplete(selected)))
+                    .map_err(SystemError::connection_lost)?;
+            }
+            Err(Interrupted::OutOfMemory) => self
+                .session
+                .send(Err(QueryError::out_of_memory("projection", work_mem.limit())))
+                .map_err(SystemError::connection_lost)?,
+            Err(Interrupted::Failed(error)) => self.session.send(Err(error)).map_err(SystemError::connection_lost)?,
+            Err(Interrupted::Emit(error)) => return Err(SystemError::connection_lost(error)),
+        }
+
+        Ok(())
+    }
+
+    /// Executes both queries of the set operation and combines their rows,
+    /// rows of both queries have to fit into `work_mem`
+    fn execute_set_operation(
+        &self,
+        operator: &SetOperator,
+        all: bool,
+        left: &SetExpr,
+        right: &SetExpr,
+    ) -> SystemResult<()> {
+        let nested = self.nested_select();
+        let mut work_mem = WorkMem::new(self.work_mem);
+        let mut operand = |body: &SetExpr| {
+            let (description, rows) = Subqueries::execute(&nested, &operand_query(body))?;
+            work_mem
+                .allocate(text_rows_size(&rows))
+                .map_err(|()| QueryError::out_of_memory("set operation", work_mem.limit()))?;
+            Ok((description, rows))
+        };
+        let combined = operand(left)
+            .and_then(|left| operand(right).map(|right| (left, right)))
+            .and_then(|(left, right)| set_operation::combine(operator, all, left, right));
+        self.send_output(combined)
+    }
+
+    /// Sends rows of a materialized result or the error that produced it
+    fn send_output(&self, output: Result<Output, QueryError>) -> SystemResult<()> {
+        let (description, rows) = match output {
+            Ok(output) => output,
+            Err(error) => {
+                self.session.send(Err(error)).map_err(SystemError::connection_lost)?;
+                return Ok(());
+            }
+        };
+        let selected = rows.len();
+        self.session
+            .send(Ok(QueryEvent::RowDescription(description)))
+            .map_err(SystemError::connection_lost)?;
+        if !rows.is_empty() {
+            self.session
+                .send(Ok(QueryEvent::DataRows(rows)))
+                .map_err(SystemError::connection_lost)?;
+        }
+        self.session
+            .send(Ok(QueryEvent::SelectComplete(selected)))
+            .map_err(SystemError::connection_lost)
+    }
+
+    /// Evaluates common table expressions of the query, each of them can
+    /// refer to the preceding ones. Only columns of the expressions are
+    /// known if the query is `described`. Returns `false` if an expression
+    /// failed.
+    fn bind_ctes(&mut self, described: bool) -> SystemResult<bool> {
+        for cte in mem::take(&mut self.query.ctes) {
+            let terms = self.nested_select();
+            let work_table = if described {
+                cte::describe(&cte, &terms)
+            } else {
+                cte::evaluate(&cte, self.max_recursive_iterations, &terms)
+            };
+            match work_table {
+                Ok(work_table) => {
+                    self.work_tables.insert(cte.alias.name.value, Arc::new(work_table));
+                }
+                Err(error) => {
+                    self.session.send(Err(error)).map_err(SystemError::connection_lost)?;
+                    return Ok(false);
                 }
+            }
+        }
+        Ok(true)
+    }
+
+    fn nested_select(&self) -> NestedSelect {
+        NestedSelect {
+            storage: self.storage.clone(),
+            parallel_workers: self.parallel_workers,
+            work_mem: self.work_mem,
+            functions: self.functions.clone(),
+            max_recursive_iterations: self.max_recursive_iterations,
+            work_tables: self.work_tables.clone(),
+            transaction: self.transaction.clone(),
+        }
+    }
+
+    /// Command for a query of a set operation that reports to the same
+    /// session
+    fn nested_command(&self, body: &SetExpr) -> SelectCommand<'sc> {
+        SelectCommand::new(
+            self.raw_sql_query,
+            Box::new(operand_query(body)),
+            self.storage.clone(),
+            self.session.clone(),
+            self.parallel_workers,
+            self.work_mem,
+            self.functions.clone(),
+        )
+        .with_max_recursive_iterations(self.max_recursive_iterations)
+        .with_work_tables(self.work_tables.clone())
+        .with_transaction(self.transaction.clone())
+    }
 
-                if !non_existing_columns.is_empty() {
-                    self.session
-                        .send(Err(QueryError::column_does_not_exist(non_existing_columns)))
-                        .expect("To Send Result to Client");
-                    return Ok(());
+    /// Rows of the relation, `None` if a foreign table can't be scanned.
+    /// The rows end early if the statement is cancelled.
+    fn scan(&self, relation: &Relation) -> SystemResult<Option<ReadCursor>> {
+        if let Some(work_table) = &relation.work_table {
+            return Ok(Some(self.cancellation.guard(work_table.scan())));
+        }
+        let records = match self.storage.foreign_scan(&relation.schema_name, &relation.table_name) {
+            Some(Err(message)) => {
+                self.session
+                    .send(Err(QueryError::fdw_error(message)))
+                    .map_err(SystemError::connection_lost)?;
+                return Ok(None);
+            }
+            Some(Ok(records)) => records,
+            None => match &self.transaction {
+                Some(transaction) => {
+                    transaction.full_scan(&self.storage, &relation.schema_name, &relation.table_name)?
                 }
+                None => self.storage.full_scan(&relation.schema_name, &relation.table_name)?,
+            },
+        };
+        Ok(Some(self.cancellation.guard(match &relation.sample {
+            Some(sample) => sample.scan(records),
+            None => records,
+        })))
+    }
 
-                let values: Vec<Vec<String>> = records
-                    .map(Result::unwrap)
-                    .map(Result::unwrap)
-                    .map(|(_key, values)| {
-                        let row: Vec<String> = values.unpack().into_iter().map(|datum| datum.to_string()).collect();
+    /// Columns of all relations of the `FROM` clause
+    fn columns(&self, input: &SelectInput) -> SystemResult<Columns> {
+        let mut columns = Columns::default();
+        for (position, relation) in input.relations.iter().enumerate() {
+            let outer_joined = relation.join.keeps_outer()
+                || input.relations[position + 1..]
+                    .iter()
+                    .any(|following| following.join.keeps_inner());
+            columns.push(
+                self.relation_columns(relation)?,
+                self.storage
+                    .table_oid(&relation.schema_name, &relation.table_name)
+                    .unwrap_or(0),
+                outer_joined,
+            );
+        }
+        Ok(columns)
+    }
 
-                        let mut values = vec![];
-                        for origin in column_indexes.iter() {
-                            for (index, value) in row.iter().enumerate() {
-                                if index == *origin {
-                                    values.push(value.clone())
-                                }
+    /// Groups rows by values of grouped columns and aggregates values of
+    /// each group. A query without `GROUP BY` has a single group even if
+    /// the table is empty. Groups for which `having` does not hold are
+    /// skipped. Groups are split between parallel workers and are sent in
+    /// the order they appeared in. Tables of groups that exceed `work_mem`
+    /// are spilled to temporary files, aggregates of a complete table are
+    /// finished at once so that only results of its groups are kept.
+    fn execute_grouped(
+        &self,
+        input: &SelectInput,
+        resolved: Resolved,
+        having: Option<Predicate>,
+        records: ReadCursor,
+    ) -> SystemResult<()> {
+        let hidden = input
+            .having
+            .iter()
+            .flat_map(|having| &having.aggregates)
+            .map(|(_call, item)| item);
+        let aggregates = input
+            .selected
+            .iter()
+            .zip(&resolved.indexes)
+            .chain(hidden.zip(&resolved.having))
+            .filter_map(|(item, index)| match item {
+                Selected::Aggregate(aggregation, _) => Some((*index, aggregation.clone())),
+                Selected::Column(_) | Selected::Expression(_) => None,
+            })
+            .collect::<Vec<(Option<usize>, Aggregation)>>();
+        let selected_aggregates = aggregates.len() - resolved.having.len();
+        let functions = &self.functions;
+        let mut scanned = 0;
+        let grouped = batch::aggregate(
+            Batches::new(records),
+            self.parallel_workers,
+            self.work_mem * 1024,
+            |row| {
+                resolved
+                    .group_by
+                    .iter()
+                    .map(|index| row[*index].to_field())
+                    .collect::<Vec<Option<String>>>()
+            },
+            |groups: &mut Groups, key, row, number| {
+                let states = groups.states(key, number, &aggregates)?;
+                for (state, (index, aggregation)) in states.iter_mut().zip(&aggregates) {
+                    let current = mem::replace(state, State::Count(0));
+                    let datum = match index {
+                        Some(index) => &row[*index],
+                        None => &Datum::True,
+                    };
+                    *state = aggregation.accumulate(current, datum, functions)?;
+                }
+                Ok(())
+            },
+            |groups: Groups| {
+                groups
+                    .groups
+                    .into_iter()
+                    .map(|(first_row, key, states)| Ok((first_row, key, finish(&aggregates, states, functions)?)))
+                    .collect::<Result<Vec<_>, QueryError>>()
+            },
+        );
+        let rows = grouped.and_then(|(tables, folded)| {
+            scanned = folded;
+            self.cancellation.check()?;
+            let mut groups = tables.into_iter().flatten().collect::<Vec<_>>();
+            groups.sort_by_key(|(first_row, _key, _results)| *first_row);
+            if groups.is_empty() && resolved.group_by.is_empty() {
+                groups.push((0, vec![], finish(&aggregates, start(&aggregates)?, functions)?));
+            }
+            let mut rows = vec![];
+            for (_first_row, key, mut results) in groups {
+                let hidden = results.split_off(selected_aggregates);
+                if let Some(having) = &having {
+                    let mut row = key.clone();
+                    row.extend(hidden);
+                    if !having.holds_for_text(&row) {
+                        continue;
+                    }
+                }
+                let mut results = results.into_iter();
+                rows.push(
+                    input
+                        .selected
+                        .iter()
+                        .zip(&resolved.indexes)
+                        .map(|(item, index)| match item {
+                            Selected::Column(_) => {
+                                let position = resolved.group_by.iter().position(|grouped| Some(*grouped) == *index);
+                                key[position.expect("selected column to be grouped")].clone()
                             }
-                        }
-                        log::debug!("{:#?}", values);
-                        values
-                    })
-                    .collect();
-
-                let projection = (
-                    description
-                        .into_iter()
-                        .map(|column| (column.name(), (&column.sql_type()).into()))
+                            Selected::Aggregate(..) => results.next().expect("result of each aggregate"),
+                            Selected::Expression(_) => unreachable!("grouped query does not select expressions"),
+                        })
                         .collect(),
-                    values,
                 );
-                self.session
-                    .send(Ok(QueryEvent::RecordsSelected(projection)))
-                    .expect("To Send Query Result to Client");
             }
+            Ok(rows)
+        });
+        let rows = match rows {
+            Ok(rows) => rows,
+            Err(error) => {
+                self.session.send(Err(error)).map_err(SystemError::connection_lost)?;
+                return Ok(());
+            }
+        };
+        self.storage
+            .statistics()
+            .seq_scanned(&input.relations[0].schema_name, &input.relations[0].table_name, scanned);
+        let selected = rows.len();
+        self.session
+            .send(Ok(QueryEvent::RowDescription(resolved.description)))
+            .map_err(SystemError::connection_lost)?;
+        self.session
+            .send(Ok(QueryEvent::DataRows(rows)))
+            .map_err(SystemError::connection_lost)?;
+        self.session
+            .send(Ok(QueryEvent::SelectComplete(selected)))
+            .map_err(SystemError::connection_lost)?;
+        Ok(())
+    }
+
+    /// Call of an aggregate with a column or `*` as its argument. Built in
+    /// aggregates hide created aggregates with the same name.
+    fn aggregate_call(&self, relations: &[Relation], function: &Function) -> SystemResult<Option<Selected>> {
+        match (function.name.0.as_slice(), function.args.as_slice()) {
+            ([name], [argument]) if function.over.is_none() && !function.distinct => {
+                let name = procedural::identifier(name);
+                let aggregation = match Aggregation::builtin(&name) {
+                    Some(aggregation) => aggregation,
+                    None => match self.storage.aggregates().get(&name) {
+                        Some(aggregate) => Aggregation::Defined(aggregate),
+                        None => return Ok(None),
+                    },
+                };
+                match argument {
+                    Expr::Wildcard => Ok(Some(Selected::Aggregate(aggregation, None))),
+                    argument => Ok(self
+                        .column_name(relations, argument)?
+                        .map(|column| Selected::Aggregate(aggregation, Some(column)))),
+                }
+            }
+            _ => Ok(None),
         }
+    }
+
+    /// Column `expr` refers to either by its name or qualified with the name
+    /// of a relation
+    fn column_name(&self, relations: &[Relation], expr: &Expr) -> SystemResult<Option<ColumnName>> {
+        match expr {
+            Expr::Identifier(column) => Ok(Some(ColumnName::new(None, column))),
+            Expr::CompoundIdentifier(idents) => match idents.split_last() {
+                Some((column, qualifier)) => match relation_of(relations, qualifier) {
+                    Some(relation) => Ok(Some(ColumnName::new(Some(relation), column))),
+                    None => Err(self.missing_from_clause_entry(qualifier)),
+                },
+                None => Ok(None),
+            },
+            _ => Ok(None),
+        }
+    }
 
+    /// Calls of aggregates in `expr` with the aggregates they call
+    fn collect_aggregates(
+        &self,
+        relations: &[Relation],
+        expr: &Expr,
+        aggregates: &mut Vec<(Expr, Selected)>,
+    ) -> SystemResult<()> {
+        match expr {
+            Expr::Function(function) => match self.aggregate_call(relations, function)? {
+                Some(aggregate) => aggregates.push((expr.clone(), aggregate)),
+                None => return Err(self.not_supported()),
+            },
+            Expr::BinaryOp { left, right, .. } => {
+                self.collect_aggregates(relations, left, aggregates)?;
+                self.collect_aggregates(relations, right, aggregates)?;
+            }
+            Expr::UnaryOp { expr, .. } | Expr::Nested(expr) | Expr::IsNull(expr) | Expr::IsNotNull(expr) => {
+                self.collect_aggregates(relations, expr, aggregates)?
+            }
+            Expr::Between { expr, low, high, .. } => {
+                for expr in &[expr, low, high] {
+                    self.collect_aggregates(relations, expr, aggregates)?;
+                }
+            }
+            Expr::InList { expr, list, .. } => {
+                self.collect_aggregates(relations, expr, aggregates)?;
+                for expr in list {
+                    self.collect_aggregates(relations, expr, aggregates)?;
+                }
+            }
+            Expr::Case {
+                operand,
+                conditions,
+                results,
+                else_result,
+            } => {
+                for expr in operand
+                    .as_deref()
+                    .into_iter()
+                    .chain(conditions)
+                    .chain(results)
+                    .chain(else_result.as_deref())
+                {
+                    self.collect_aggregates(relations, expr, aggregates)?;
+                }
+            }
+            _ => {}
+        }
         Ok(())
     }
 
+    fn missing_from_clause_entry(&self, qualifier: &[Ident]) -> SystemError {
+        match self
+            .session
+            .send(Err(QueryError::missing_from_clause_entry(qualifier_name(qualifier))))
+        {
+            Ok(()) => SystemError::runtime_check_failure("Missing FROM-clause Entry".to_owned()),
+            Err(error) => SystemError::connection_lost(error),
+        }
+    }
+
+    fn not_supported(&self) -> SystemError {
+        match self
+            .session
+            .send(Err(QueryError::feature_not_supported(self.raw_sql_query.to_owned())))
+        {
+            Ok(()) => SystemError::unsupported(self.raw_sql_query.to_owned()),
+            Err(error) => SystemError::connection_lost(error),
+        }
+    }
+
+    /// Table of the `FROM` clause, common table expression or `VALUES` list,
+    /// tables have to exist
+    fn relation(&self, table_factor: &TableFactor, join: JoinKind, condition: Option<Expr>) -> SystemResult<Relation> {
+        let relation = match table_factor {
+            TableFactor::Derived {
+                lateral: false,
+                subquery,
+                alias,
+            } => {
+                return match values::values_of(subquery) {
+                    Some(values) => self.values_relation(values, alias.as_ref(), join, condition),
+                    None => Err(self.not_supported()),
+                }
+            }
+            TableFactor::Table { name, alias, .. } if self.work_table(name).is_some() => {
+                return Ok(Relation {
+                    schema_name: String::new(),
+                    table_name: name.to_string(),
+                    alias: alias.as_ref().map(|alias| alias.name.value.clone()),
+                    sample: None,
+                    join,
+                    condition,
+                    work_table: self.work_table(name),
+                })
+            }
+            TableFactor::Table { name, .. } if name.0.len() != 2 => {
+                self.session
+                    .send(Err(QueryError::table_does_not_exist(name.to_string())))
+                    .map_err(SystemError::connection_lost)?;
+                return Err(SystemError::runtime_check_failure("Table Does Not Exist".to_owned()));
+            }
+            TableFactor::Table {
+                name,
+                alias,
+                with_hints,
+                ..
+            } => match TableSample::from_hints(with_hints) {
+                Ok(sample) => Relation {
+                    schema_name: name.0[0].to_string(),
+                    table_name: name.0[1].to_string(),
+                    alias: alias.as_ref().map(|alias| alias.name.value.clone()),
+                    sample,
+                    join,
+                    condition,
+                    work_table: None,
+                },
+                Err(error) => {
+                    self.session.send(Err(error)).map_err(SystemError::connection_lost)?;
+                    return Err(SystemError::runtime_check_failure("Invalid Table Sample".to_owned()));
+                }
+            },
+            _ => return Err(self.not_supported()),
+        };
+
+        match self.storage.table_exists(&relation.schema_name, &relation.table_name) {
+            None => {
+                self.session
+                    .send(Err(QueryError::schema_does_not_exist(relation.schema_name.clone())))
+                    .map_err(SystemError::connection_lost)?;
+                Err(SystemError::runtime_check_failure("Schema Does Not Exist".to_owned()))
+            }
+            Some((_, None)) => {
+                self.session
+                    .send(Err(QueryError::table_does_not_exist(
+                        relation.schema_name.clone() + "." + relation.table_name.as_str(),
+                    )))
+                    .map_err(SystemError::connection_lost)?;
+                Err(SystemError::runtime_check_failure("Table Does Not Exist".to_owned()))
+            }
+            Some((_, Some(_))) => Ok(relation),
+        }
+    }
+
+    /// `VALUES` list of the `FROM` clause, it is named `*VALUES*` unless it
+    /// has an alias
+    fn values_relation(
+        &self,
+        values: &Values,
+        alias: Option<&TableAlias>,
+        join: JoinKind,
+        condition: Option<Expr>,
+    ) -> SystemResult<Relation> {
+        let name = alias.map_or_else(|| "*VALUES*".to_owned(), |alias| alias.name.value.clone());
+        let aliases = alias.map_or(&[][..], |alias| &alias.columns);
+        match values::work_table(values, &name, aliases, &self.functions) {
+            Ok(work_table) => Ok(Relation {
+                schema_name: String::new(),
+                table_name: name,
+                alias: None,
+                sample: None,
+                join,
+                condition,
+                work_table: Some(Arc::new(work_table)),
+            }),
+            Err(error) => {
+                self.session.send(Err(error)).map_err(SystemError::connection_lost)?;
+                Err(SystemError::runtime_check_failure("Invalid Values".to_owned()))
+            }
+        }
+    }
+
+    /// Common table expression that an unqualified `name` refers to
+    fn work_table(&self, name: &ObjectName) -> Option<Arc<WorkTable>> {
+        match name.0.as_slice() {
+            [name] => self.work_tables.get(&name.value).cloned(),
+            _ => None,
+        }
+    }
+
+    /// All columns of the relation at `position` qualified with it
+    fn all_columns(&self, relations: &[Relation], position: usize) -> SystemResult<Vec<Selected>> {
+        Ok(self
+            .relation_columns(&relations[position])?
+            .into_iter()
+            .map(|column_definition| {
+                Selected::Column(ColumnName {
+                    relation: Some(position),
+                    name: column_definition.name(),
+                })
+            })
+            .collect())
+    }
+
+    fn relation_columns(&self, relation: &Relation) -> SystemResult<Vec<ColumnDefinition>> {
+        match &relation.work_table {
+            Some(work_table) => Ok(work_table.columns()),
+            None => self.storage.table_columns(&relation.schema_name, &relation.table_name),
+        }
+    }
+
+    /// Column or aggregate that an expression of the select list refers to,
+    /// any other expression is computed for each row
+    fn selected_expr(&self, relations: &[Relation], expr: &Expr) -> SystemResult<Selected> {
+        let selected = match expr {
+            Expr::Function(function) => self.aggregate_call(relations, function)?,
+            expr => self.column_name(relations, expr)?.map(Selected::Column),
+        };
+        Ok(selected.unwrap_or_else(|| Selected::Expression(expr.clone())))
+    }
+
     fn parse_select_input(&self) -> SystemResult<SelectInput> {
         let Query { body, .. } = &*self.query;
         if let SetExpr::Select(select) = body {
-            let Select { projection, from, .. } = select.deref();
-            let TableWithJoins { relation, .. } = &from[0];
-            let (schema_name, table_name) = match relation {
-                TableFactor::Table { name, .. } => {
-                    let table_name = name.0[1].to_string();
-                    let schema_name = name.0[0].to_string();
-                    (schema_name, table_name)
-                }
-                _ => {
-                    self.session
-                        .send(Err(QueryError::feature_not_supported(self.raw_sql_query.to_owned())))
-                        .expect("To Send Query Result to Client");
-                    return Err(SystemError::runtime_check_failure("Feature Not Supported".to_owned()));
+            let Select {
+                projection,
+                from,
+                selection,
+                group_by,
+                having,
+                ..
+            } = select.deref();
+            let mut relations = vec![];
+            if from.is_empty() {
+                relations.push(Relation {
+                    schema_name: String::new(),
+                    table_name: String::new(),
+                    alias: None,
+                    sample: None,
+                    join: JoinKind::Inner,
+                    condition: None,
+                    work_table: Some(Arc::new(WorkTable::single_row())),
+                });
+            }
+            for TableWithJoins { relation, joins } in from {
+                relations.push(self.relation(relation, JoinKind::Inner, None)?);
+                for Join {
+                    relation,
+                    join_operator,
+                } in joins
+                {
+                    let (join, condition) = match join_operator {
+                        JoinOperator::Inner(JoinConstraint::On(condition)) => (JoinKind::Inner, condition),
+                        JoinOperator::LeftOuter(JoinConstraint::On(condition)) => (JoinKind::Left, condition),
+                        JoinOperator::RightOuter(JoinConstraint::On(condition)) => (JoinKind::Right, condition),
+                        JoinOperator::FullOuter(JoinConstraint::On(condition)) => (JoinKind::Full, condition),
+                        JoinOperator::CrossJoin => {
+                            relations.push(self.relation(relation, JoinKind::Inner, None)?);
+                            continue;
+                        }
+                        _ => return Err(self.not_supported()),
+                    };
+                    relations.push(self.relation(relation, join, Some(condition.clone()))?);
                 }
-            };
+            }
 
-            match self.storage.table_exists(&schema_name, &table_name) {
-                None => {
-                    self.session
-                        .send(Err(QueryError::schema_does_not_exist(schema_name)))
-                        .expect("To Send Result to Client");
-                    Err(SystemError::runtime_check_failure("Schema Does Not Exist".to_owned()))
-                }
-                Some((_, None)) => {
-                    self.session
-                        .send(Err(QueryError::table_does_not_exist(
-                            schema_name + "." + table_name.as_str(),
-                        )))
-                        .expect("To Send Result to Client");
-                    Err(SystemError::runtime_check_failure("Table Does Not Exist".to_owned()))
-                }
-                Some((_, Some(_))) => {
-                    let selected_columns = {
-                        let projection = projection.clone();
-                        let mut columns: Vec<String> = vec![];
-                        for item in projection {
-                            match item {
-                                SelectItem::Wildcard => {
-                                    let all_columns = self.storage.table_columns(&schema_name, &table_name)?;
-                                    columns.extend(
-                                        all_columns
-                                            .into_iter()
-                                            .map(|column_definition| column_definition.name())
-                                            .collect::<Vec<String>>(),
-                                    )
-                                }
-                                SelectItem::UnnamedExpr(Expr::Identifier(Ident { value, .. })) => {
-                                    columns.push(value.clone())
-                                }
-                                _ => {
-                                    self.session
-                                        .send(Err(QueryError::feature_not_supported(self.raw_sql_query.to_owned())))
-                                        .expect("To Send Query Result to Client");
-                                    return Err(SystemError::runtime_check_failure("Feature Not Supported".to_owned()));
-                                }
-                            }
+            let mut selected = vec![];
+            let mut aliases = vec![];
+            for item in projection {
+                match item {
+                    SelectItem::Wildcard => {
+                        for position in 0..relations.len() {
+                            selected.extend(self.all_columns(&relations, position)?);
                         }
-                        columns
-                    };
+                    }
+                    SelectItem::QualifiedWildcard(ObjectName(qualifier)) => match relation_of(&relations, qualifier) {
+                        Some(position) => selected.extend(self.all_columns(&relations, position)?),
+                        None => return Err(self.missing_from_clause_entry(qualifier)),
+                    },
+                    SelectItem::UnnamedExpr(expr) => selected.push(self.selected_expr(&relations, expr)?),
+                    SelectItem::ExprWithAlias { expr, alias } => {
+                        selected.push(self.selected_expr(&relations, expr)?);
+                        aliases.push(Some(alias.value.clone()));
+                    }
+                }
+                aliases.resize(selected.len(), None);
+            }
+            let mut grouped_columns = vec![];
+            for expr in group_by {
+                match self.column_name(&relations, expr)? {
+                    Some(column) => grouped_columns.push(column),
+                    None => return Err(self.not_supported()),
+                }
+            }
 
-                    Ok(SelectInput {
-                        schema_name,
-                        table_name,
-                        selected_columns,
+            let having = match having {
+                Some(condition) => {
+                    let mut aggregates = vec![];
+                    self.collect_aggregates(&relations, condition, &mut aggregates)?;
+                    Some(Having {
+                        condition: condition.clone(),
+                        aggregates,
                     })
                 }
-            }
+                None => None,
+            };
+
+            Ok(SelectInput {
+                relations,
+                selected,
+                aliases,
+                group_by: grouped_columns,
+                selection: selection.clone(),
+                having,
+            })
         } else {
             self.session
                 .send(Err(QueryError::feature_not_supported(self.raw_sql_query.to_owned())))
-                .expect("To Send Query Result to Client");
-            Err(SystemError::runtime_check_failure("Feature Not Supported".to_owned()))
+                .map_err(SystemError::connection_lost)?;
+            Err(SystemError::unsupported(self.raw_sql_query.to_owned()))
         }
     }
 }
 
-struct SelectInput {
+/// Query of an operand of a set operation
+pub(crate) fn operand_query(body: &SetExpr) -> Query {
+    match body {
+        SetExpr::Query(query) => (**query).clone(),
+        body => Query {
+            ctes: vec![],
+            body: body.clone(),
+            order_by: vec![],
+            limit: None,
+            offset: None,
+            fetch: None,
+        },
+    }
+}
+
+/// Executes subqueries of conditions and queries of set operations as
+/// nested `SELECT` commands of the same session
+struct NestedSelect {
+    storage: Arc<CatalogManager>,
+    parallel_workers: usize,
+    work_mem: usize,
+    functions: ScalarFunctions,
+    max_recursive_iterations: usize,
+    work_tables: WorkTables,
+    transaction: Option<Arc<Transaction>>,
+}
+
+impl NestedSelect {
+    fn command<'q>(&self, raw_sql_query: &'q str, query: &Query, rows: Arc<Rows>) -> SelectCommand<'q> {
+        SelectCommand::new(
+            raw_sql_query,
+            Box::new(query.clone()),
+            self.storage.clone(),
+            rows,
+            self.parallel_workers,
+            self.work_mem,
+            self.functions.clone(),
+        )
+        .with_max_recursive_iterations(self.max_recursive_iterations)
+        .with_work_tables(self.work_tables.clone())
+        .with_transaction(self.transaction.clone())
+    }
+}
+
+impl Subqueries for NestedSelect {
+    fn execute(&self, query: &Query) -> Result<Output, QueryError> {
+        let rows = Arc::new(Rows::default());
+        let raw_sql_query = query.to_string();
+        let executed = self.command(&raw_sql_query, query, rows.clone()).execute();
+        match (executed, rows.take()) {
+            (_, Err(error)) => Err(error),
+            (Ok(()), Ok(result)) => Ok(result),
+            (Err(error), Ok(_)) => Err(QueryError::io_error(error.to_string())),
+        }
+    }
+
+    fn is_inner(&self, query: &Query, expr: &Expr) -> bool {
+        let (qualifier, column) = match column_reference(expr) {
+            Some(reference) => reference,
+            None => return true,
+        };
+        let select = match &query.body {
+            SetExpr::Select(select) => select,
+            _ => return true,
+        };
+        select
+            .from
+            .iter()
+            .flat_map(|TableWithJoins { relation, joins }| {
+                iter::once(relation).chain(joins.iter().map(|join| &join.relation))
+            })
+            .any(|table_factor| match table_factor {
+                TableFactor::Table { name, alias, .. } => {
+                    let (schema_name, table_name, columns) = match name.0.as_slice() {
+                        [name] => match self.work_tables.get(&name.value) {
+                            Some(work_table) => (String::new(), name.to_string(), work_table.columns()),
+                            None => return false,
+                        },
+                        [schema_name, table_name] => (
+                            schema_name.to_string(),
+                            table_name.to_string(),
+                            self.storage
+                                .table_columns(&schema_name.to_string(), &table_name.to_string())
+                                .unwrap_or_default(),
+                        ),
+                        _ => return false,
+                    };
+                    let relation = Relation {
+                        schema_name,
+                        table_name,
+                        alias: alias.as_ref().map(|alias| alias.name.value.clone()),
+                        sample: None,
+                        join: JoinKind::Inner,
+                        condition: None,
+                        work_table: None,
+                    };
+                    match qualifier {
+                        [] => columns.iter().any(|definition| definition.name() == column.value),
+                        qualifier => relation.is_named(qualifier),
+                    }
+                }
+                _ => false,
+            })
+    }
+}
+
+impl Terms for NestedSelect {
+    fn describe(&self, query: &Query) -> Result<Description, QueryError> {
+        let rows = Arc::new(Rows::default());
+        let raw_sql_query = query.to_string();
+        let described = self.command(&raw_sql_query, query, rows.clone()).describe();
+        match (described, rows.take()) {
+            (_, Err(error)) => Err(error),
+            (Ok(description), Ok(_)) => Ok(description),
+            (Err(error), Ok(_)) => Err(QueryError::io_error(error.to_string())),
+        }
+    }
+
+    fn execute(&self, query: &Query, bound: Option<(&str, WorkTable)>) -> Result<Output, QueryError> {
+        match bound {
+            Some((name, work_table)) => {
+                let mut work_tables = self.work_tables.clone();
+                work_tables.insert(name.to_owned(), Arc::new(work_table));
+                Subqueries::execute(
+                    &NestedSelect {
+                        storage: self.storage.clone(),
+                        parallel_workers: self.parallel_workers,
+                        work_mem: self.work_mem,
+                        functions: self.functions.clone(),
+                        max_recursive_iterations: self.max_recursive_iterations,
+                        work_tables,
+                        transaction: self.transaction.clone(),
+                    },
+                    query,
+                )
+            }
+            None => Subqueries::execute(self, query),
+        }
+    }
+}
+
+/// Table of the `FROM` clause and the names it can be referred to by
+struct Relation {
     schema_name: String,
     table_name: String,
-    selected_columns: Vec<String>,
+    alias: Option<String>,
+    sample: Option<TableSample>,
+    /// rows that joining the relation to the preceding ones keeps
+    join: JoinKind,
+    /// `ON` condition that joins the relation to the preceding ones
+    condition: Option<Expr>,
+    /// rows of a common table expression or of a query without `FROM`
+    work_table: Option<Arc<WorkTable>>,
+}
+
+impl Relation {
+    /// Whether a qualifier of a column or of a wildcard names the relation.
+    /// An alias hides the name of the table.
+    fn is_named(&self, qualifier: &[Ident]) -> bool {
+        match (qualifier, &self.alias) {
+            ([name], Some(alias)) => name.value == *alias,
+            (_, Some(_)) => false,
+            ([table], None) => table.to_string() == self.table_name,
+            ([schema, table], None) => schema.to_string() == self.schema_name && table.to_string() == self.table_name,
+            _ => false,
+        }
+    }
+}
+
+/// Position of the relation that `qualifier` names
+fn relation_of(relations: &[Relation], qualifier: &[Ident]) -> Option<usize> {
+    relations.iter().position(|relation| relation.is_named(qualifier))
+}
+
+fn qualifier_name(qualifier: &[Ident]) -> String {
+    qualifier
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<String>>()
+        .join(".")
+}
+
+/// Qualifier and name of the column `expr` refers to
+fn column_reference(expr: &Expr) -> Option<(&[Ident], &Ident)> {
+    match expr {
+        Expr::Identifier(column) => Some((&[], column)),
+        Expr::CompoundIdentifier(idents) => idents.split_last().map(|(column, qualifier)| (qualifier, column)),
+        _ => None,
+    }
+}
+
+/// Index of the column among `columns` of the first relations of the
+/// `FROM` clause
+fn column_index(
+    relations: &[Relation],
+    columns: &Columns,
+    qualifier: &[Ident],
+    column: &Ident,
+) -> Result<usize, QueryError> {
+    let column = match qualifier {
+        [] => ColumnName::new(None, column),
+        _ => match relation_of(&relations[..columns.ranges.len()], qualifier) {
+            Some(relation) => ColumnName::new(Some(relation), column),
+            None => return Err(QueryError::missing_from_clause_entry(qualifier_name(qualifier))),
+        },
+    };
+    match columns.lookup(&column)? {
+        Some(index) => Ok(index),
+        None => Err(columns.unknown(vec![column.name])),
+    }
+}
+
+/// Compiled join conditions, `WHERE` and `HAVING` clauses
+struct Conditions {
+    /// condition of each relation of the `FROM` clause
+    joins: Vec<Option<Predicate>>,
+    selection: Option<Predicate>,
+    having: Option<Predicate>,
+}
+
+impl Conditions {
+    fn compile(
+        input: &SelectInput,
+        columns: &Columns,
+        resolved: &Resolved,
+        functions: &ScalarFunctions,
+        subqueries: &Arc<dyn Subqueries>,
+    ) -> Result<Conditions, QueryError> {
+        let mut joins = vec![];
+        for (position, relation) in input.relations.iter().enumerate() {
+            joins.push(match &relation.condition {
+                Some(condition) => Some(Conditions::row(
+                    input,
+                    columns,
+                    functions,
+                    subqueries,
+                    position + 1,
+                    condition,
+                    "JOIN/ON",
+                )?),
+                None => None,
+            });
+        }
+        let selection = match &input.selection {
+            Some(selection) => Some(Conditions::row(
+                input,
+                columns,
+                functions,
+                subqueries,
+                input.relations.len(),
+                selection,
+                "WHERE",
+            )?),
+            None => None,
+        };
+        let having = match &input.having {
+            Some(having) => Some(Conditions::group(input, columns, resolved, functions, having)?),
+            None => None,
+        };
+        Ok(Conditions {
+            joins,
+            selection,
+            having,
+        })
+    }
+
+    /// Condition of rows of the first `relations` relations
+    fn row(
+        input: &SelectInput,
+        columns: &Columns,
+        functions: &ScalarFunctions,
+        subqueries: &Arc<dyn Subqueries>,
+        relations: usize,
+        expr: &Expr,
+        clause: &str,
+    ) -> Result<Predicate, QueryError> {
+        let visible = columns.first(relations);
+        Predicate::compile(
+            expr,
+            clause,
+            &visible.types(),
+            functions,
+            Some(subqueries),
+            &|expr| match column_reference(expr) {
+                Some((qualifier, column)) => column_index(&input.relations, &visible, qualifier, column).map(Some),
+                None => Ok(None),
+            },
+        )
+    }
+
+    /// Condition of groups, it refers to values of grouped columns followed
+    /// by results of aggregates it calls
+    fn group(
+        input: &SelectInput,
+        columns: &Columns,
+        resolved: &Resolved,
+        functions: &ScalarFunctions,
+        having: &Having,
+    ) -> Result<Predicate, QueryError> {
+        let mut types = resolved
+            .group_by
+            .iter()
+            .map(|index| columns.all[*index].sql_type())
+            .collect::<Vec<SqlType>>();
+        types.extend(resolved.having_types.iter().copied());
+        Predicate::compile(&having.condition, "HAVING", &types, functions, None, &|expr| {
+            if let Some(position) = having.aggregates.iter().position(|(call, _)| call == expr) {
+                return Ok(Some(resolved.group_by.len() + position));
+            }
+            match column_reference(expr) {
+                Some((qualifier, column)) => {
+                    let index = column_index(&input.relations, columns, qualifier, column)?;
+                    match resolved.group_by.iter().position(|grouped| *grouped == index) {
+                        Some(position) => Ok(Some(position)),
+                        None => Err(QueryError::grouping_error(column.value.clone())),
+                    }
+                }
+                None => Ok(None),
+            }
+        })
+    }
+}
+
+/// `HAVING` clause with calls of aggregates in it
+struct Having {
+    condition: Expr,
+    aggregates: Vec<(Expr, Selected)>,
+}
+
+struct SelectInput {
+    relations: Vec<Relation>,
+    selected: Vec<Selected>,
+    /// names that `AS` gives to items of the select list
+    aliases: Vec<Option<String>>,
+    group_by: Vec<ColumnName>,
+    selection: Option<Expr>,
+    having: Option<Having>,
+}
+
+impl SelectInput {
+    fn is_grouped(&self) -> bool {
+        !self.group_by.is_empty()
+            || self.having.is_some()
+            || self.selected.iter().any(|item| matches!(item, Selected::Aggregate(..)))
+    }
+}
+
+/// Column the query refers to, a qualified column belongs to the relation
+/// at `relation`
+struct ColumnName {
+    relation: Option<usize>,
+    name: String,
+}
+
+impl ColumnName {
+    fn new(relation: Option<usize>, column: &Ident) -> ColumnName {
+        ColumnName {
+            relation,
+            name: column.value.clone(),
+        }
+    }
+}
+
+/// Columns of rows that relations of the `FROM` clause are joined into,
+/// columns of a relation follow columns of the preceding one
+#[derive(Default)]
+struct Columns {
+    all: Vec<ColumnDefinition>,
+    ranges: Vec<Range<usize>>,
+    /// OIDs of relations and whether an outer join pads their rows with NULLs
+    sources: Vec<(u32, bool)>,
+}
+
+impl Columns {
+    fn push(&mut self, columns: Vec<ColumnDefinition>, table_oid: u32, outer_joined: bool) {
+        let start = self.all.len();
+        self.all.extend(columns);
+        self.ranges.push(start..self.all.len());
+        self.sources.push((table_oid, outer_joined));
+    }
+
+    /// Description of the column at `index` that is selected as `name`
+    fn describe(&self, index: usize, name: &str) -> ColumnDescription {
+        let column = &self.all[index];
+        let relation = self
+            .ranges
+            .iter()
+            .position(|range| range.contains(&index))
+            .expect("column belongs to a relation");
+        let (table_oid, outer_joined) = self.sources[relation];
+        // columns of work tables don't belong to a table
+        let attnum = match table_oid {
+            0 => 0,
+            _ => (index - self.ranges[relation].start + 1) as i16,
+        };
+        ColumnDescription::new(name, (&column.sql_type()).into())
+            .with_source(table_oid, attnum)
+            .with_nullable(outer_joined || !column.is_not_null())
+    }
+
+    /// Columns of the first `relations` relations
+    fn first(&self, relations: usize) -> Columns {
+        let end = self.ranges[relations - 1].end;
+        Columns {
+            all: self.all[..end].to_vec(),
+            ranges: self.ranges[..relations].to_vec(),
+            sources: self.sources[..relations].to_vec(),
+        }
+    }
+
+    fn types(&self) -> Vec<SqlType> {
+        self.all.iter().map(ColumnDefinition::sql_type).collect()
+    }
+
+    /// Index of the column, unqualified names are looked up in all relations
+    fn lookup(&self, column: &ColumnName) -> Result<Option<usize>, QueryError> {
+        match column.relation {
+            Some(relation) => {
+                let range = self.ranges[relation].clone();
+                Ok(Scope::new(&self.all[range.clone()])
+                    .lookup(&column.name)?
+                    .map(|index| range.start + index))
+            }
+            None => Scope::new(&self.all).lookup(&column.name),
+        }
+    }
+
+    fn unknown(&self, names: Vec<String>) -> QueryError {
+        Scope::new(&self.all).unknown(names)
+    }
+}
+
+/// Item of the select list
+enum Selected {
+    Column(ColumnName),
+    /// aggregate of values of the column, of all rows for `*`
+    Aggregate(Aggregation, Option<ColumnName>),
+    /// value computed from values of a row
+    Expression(Expr),
+}
+
+impl Selected {
+    fn column(&self) -> Option<&ColumnName> {
+        match self {
+            Selected::Column(column) => Some(column),
+            Selected::Aggregate(_, column) => column.as_ref(),
+            Selected::Expression(_) => None,
+        }
+    }
+}
+
+/// Indexes of columns that items of the select list, `GROUP BY` and
+/// aggregates of `HAVING` refer to
+struct Resolved {
+    /// `None` for aggregates of all rows and for expressions
+    indexes: Vec<Option<usize>>,
+    /// compiled expressions of the select list, `None` for other items
+    expressions: Vec<Option<Predicate>>,
+    group_by: Vec<usize>,
+    description: Description,
+    having: Vec<Option<usize>>,
+    /// result types of aggregates of `HAVING`
+    having_types: Vec<SqlType>,
+}
+
+/// Name of the column of an expression without an alias, calls are named
+/// after their functions like in PostgreSQL
+fn expression_name(expr: &Expr) -> String {
+    match expr {
+        Expr::Function(function) => match function.name.0.last() {
+            Some(name) if name.value.to_lowercase() != MATCH_FUNCTION => procedural::identifier(name),
+            _ => "?column?".to_owned(),
+        },
+        _ => "?column?".to_owned(),
+    }
+}
+
+/// Errors if a column does not exist or if a query is grouped and a selected
+/// column is neither grouped nor aggregated
+fn resolve(input: &SelectInput, columns: &Columns, functions: &ScalarFunctions) -> Result<Resolved, QueryError> {
+    let mut non_existing_columns = vec![];
+    let mut indexes = vec![];
+    let mut expressions = vec![];
+    let mut description = vec![];
+    for (item, alias) in input.selected.iter().zip(&input.aliases) {
+        if let Selected::Expression(expr) = item {
+            if input.is_grouped() {
+                return Err(QueryError::feature_not_supported(expr.to_string()));
+            }
+            let (expression, sql_type) =
+                Predicate::expression(
+                    expr,
+                    &columns.types(),
+                    functions,
+                    &|expr| match column_reference(expr) {
+                        Some((qualifier, column)) => {
+                            column_index(&input.relations, columns, qualifier, column).map(Some)
+                        }
+                        None => Ok(None),
+                    },
+                )?;
+            indexes.push(None);
+            expressions.push(Some(expression));
+            description.push(ColumnDescription::new(
+                alias.as_deref().unwrap_or(&expression_name(expr)),
+                (&sql_type).into(),
+            ));
+            continue;
+        }
+        let index = match item.column() {
+            Some(column) => match columns.lookup(column)? {
+                Some(index) => Some(index),
+                None => {
+                    non_existing_columns.push(column.name.clone());
+                    continue;
+                }
+            },
+            None => None,
+        };
+        indexes.push(index);
+        expressions.push(None);
+        let column = index.map(|index| &columns.all[index]);
+        description.push(match (item, index) {
+            (Selected::Aggregate(aggregation, _), _) => ColumnDescription::new(
+                alias.as_deref().unwrap_or(&aggregation.name()),
+                (&aggregation.returns(column.map(ColumnDefinition::sql_type).as_ref())?).into(),
+            ),
+            (Selected::Column(_), Some(index)) => {
+                columns.describe(index, alias.as_deref().unwrap_or(&columns.all[index].name()))
+            }
+            (Selected::Column(_), None) => unreachable!("selected column is resolved"),
+            (Selected::Expression(_), _) => unreachable!("expression is resolved"),
+        });
+    }
+    let mut group_by = vec![];
+    for column in &input.group_by {
+        match columns.lookup(column)? {
+            Some(index) => group_by.push(index),
+            None => non_existing_columns.push(column.name.clone()),
+        }
+    }
+    let mut having = vec![];
+    let mut having_types = vec![];
+    for (_, item) in input.having.iter().flat_map(|having| &having.aggregates) {
+        let index = match item.column() {
+            Some(column) => match columns.lookup(column)? {
+                Some(index) => Some(index),
+                None => {
+                    non_existing_columns.push(column.name.clone());
+                    continue;
+                }
+            },
+            None => None,
+        };
+        if let Selected::Aggregate(aggregation, _) = item {
+            having_types.push(aggregation.returns(index.map(|index| columns.all[index].sql_type()).as_ref())?);
+        }
+        having.push(index);
+    }
+    if !non_existing_columns.is_empty() {
+        return Err(columns.unknown(non_existing_columns));
+    }
+    if input.is_grouped() {
+        for (item, index) in input.selected.iter().zip(&indexes) {
+            if let (Selected::Column(column), Some(index)) = (item, index) {
+                if !group_by.contains(index) {
+                    return Err(QueryError::grouping_error(column.name.clone()));
+                }
+            }
+        }
+    }
+    Ok(Resolved {
+        indexes,
+        expressions,
+        group_by,
+        description,
+        having,
+        having_types,
+    })
+}
+
+impl Resolved {
+    /// Text representation of selected values of rows of the batch
+    fn values(&self, batch: &Batch) -> Result<Vec<Vec<Option<String>>>, QueryError> {
+        batch
+            .rows()
+            .iter()
+            .map(|row| {
+                self.indexes
+                    .iter()
+                    .zip(&self.expressions)
+                    .map(|(index, expression)| match (index, expression) {
+                        (_, Some(expression)) => expression.value(row),
+                        (Some(index), None) => Ok(row[*index].to_field()),
+                        (None, None) => unreachable!("aggregates are selected by grouped queries"),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// Groups of rows with the same values of grouped columns and states of
+/// aggregates of each group with the number of the first row of the group
+#[derive(Default)]
+struct Groups {
+    groups: Vec<(usize, Vec<Option<String>>, Vec<State>)>,
+    positions: HashMap<Vec<Option<String>>, usize>,
+    size: usize,
+}
+
+impl GroupTable<Vec<Option<String>>> for Groups {
+    fn contains(&self, key: &Vec<Option<String>>) -> bool {
+        self.positions.contains_key(key)
+    }
+
+    fn size(&self) -> usize {
+        self.size
+    }
+}
+
+impl Groups {
+    fn states(
+        &mut self,
+        key: Vec<Option<String>>,
+        row: usize,
+        aggregates: &[(Option<usize>, Aggregation)],
+    ) -> Result<&mut Vec<State>, QueryError> {
+        let position = match self.positions.get(&key) {
+            Some(position) => *position,
+            None => {
+                // the key is held by the group and by its position
+                self.size += 2 * text_rows_size(slice::from_ref(&key))
+                    + mem::size_of::<(usize, Vec<Option<String>>, Vec<State>)>()
+                    + mem::size_of::<usize>()
+                    + aggregates.len() * mem::size_of::<State>();
+                self.groups.push((row, key.clone(), start(aggregates)?));
+                self.positions.insert(key, self.groups.len() - 1);
+                self.groups.len() - 1
+            }
+        };
+        Ok(&mut self.groups[position].2)
+    }
+}
+
+fn start(aggregates: &[(Option<usize>, Aggregation)]) -> Result<Vec<State>, QueryError> {
+    aggregates
+        .iter()
+        .map(|(_index, aggregation)| aggregation.start())
+        .collect()
+}
+
+fn finish(
+    aggregates: &[(Option<usize>, Aggregation)],
+    states: Vec<State>,
+    functions: &ScalarFunctions,
+) -> Result<Vec<Option<String>>, QueryError> {
+    states
+        .into_iter()
+        .zip(aggregates)
+        .map(|(state, (_index, aggregation))| aggregation.finish(state, functions))
+        .collect()
 }
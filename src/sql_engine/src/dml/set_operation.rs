@@ -0,0 +1,197 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `UNION`, `INTERSECT` and `EXCEPT` of rows of two queries. Rows of both
+//! queries are materialized and compared by the text representation of their
+//! values, NULLs are equal to each other. Without `ALL` the result has no
+//! duplicate rows, with `ALL` a row occurs as many times as the operator
+//! keeps its occurrences in the left and right queries.
+use crate::dml::subquery::Output;
+use protocol::{
+    results::{ColumnDescription, Description, QueryError},
+    sql_types::PostgreSqlType,
+};
+use sqlparser::ast::SetOperator;
+use std::collections::{HashMap, HashSet};
+
+type Row = Vec<Option<String>>;
+
+/// Combines rows of `left` and `right` queries with `operator`. Columns of
+/// the result are named after columns of the left query.
+pub(crate) fn combine(operator: &SetOperator, all: bool, left: Output, right: Output) -> Result<Output, QueryError> {
+    let description = describe(operator, left.0, &right.0)?;
+    Ok((description, rows(operator, all, left.1, right.1)))
+}
+
+/// Description of the result of `operator`, types of the columns of both
+/// queries must have a common type
+pub(crate) fn describe(
+    operator: &SetOperator,
+    left: Description,
+    right: &Description,
+) -> Result<Description, QueryError> {
+    if left.len() != right.len() {
+        return Err(QueryError::set_operation_columns_mismatch(&operator.to_string()));
+    }
+    left.into_iter()
+        .zip(right)
+        .map(|(left, right)| match common_type(left.pg_type, right.pg_type) {
+            Some(pg_type) => {
+                let mut column = ColumnDescription::new(&left.name, pg_type);
+                column.nullable = left.nullable || right.nullable;
+                Ok(column)
+            }
+            None => Err(QueryError::datatype_mismatch(format!(
+                "{} types {} and {} cannot be matched",
+                operator, left.pg_type, right.pg_type
+            ))),
+        })
+        .collect()
+}
+
+/// Type that values of both types can be converted to. Numbers are widened
+/// to the type that can represent both of them, strings are of variable
+/// length.
+pub(crate) fn common_type(left: PostgreSqlType, right: PostgreSqlType) -> Option<PostgreSqlType> {
+    const NUMBERS: [PostgreSqlType; 6] = [
+        PostgreSqlType::SmallInt,
+        PostgreSqlType::Integer,
+        PostgreSqlType::BigInt,
+        PostgreSqlType::Decimal,
+        PostgreSqlType::Real,
+        PostgreSqlType::DoublePrecision,
+    ];
+    const STRINGS: [PostgreSqlType; 2] = [PostgreSqlType::Char, PostgreSqlType::VarChar];
+    if left == right {
+        return Some(left);
+    }
+    for category in [&NUMBERS[..], &STRINGS[..]].iter() {
+        match (
+            category.iter().position(|pg_type| *pg_type == left),
+            category.iter().position(|pg_type| *pg_type == right),
+        ) {
+            (Some(left), Some(right)) => return Some(category[left.max(right)]),
+            (None, None) => {}
+            _ => return None,
+        }
+    }
+    None
+}
+
+fn rows(operator: &SetOperator, all: bool, left: Vec<Row>, right: Vec<Row>) -> Vec<Row> {
+    match (operator, all) {
+        (SetOperator::Union, true) => left.into_iter().chain(right).collect(),
+        (SetOperator::Union, false) => distinct(left.into_iter().chain(right)),
+        (SetOperator::Intersect, true) => {
+            let mut counts = counts(right);
+            left.into_iter().filter(|row| take(&mut counts, row)).collect()
+        }
+        (SetOperator::Intersect, false) => {
+            let right = right.into_iter().collect::<HashSet<Row>>();
+            distinct(left.into_iter().filter(|row| right.contains(row)))
+        }
+        (SetOperator::Except, true) => {
+            let mut counts = counts(right);
+            left.into_iter().filter(|row| !take(&mut counts, row)).collect()
+        }
+        (SetOperator::Except, false) => {
+            let right = right.into_iter().collect::<HashSet<Row>>();
+            distinct(left.into_iter().filter(|row| !right.contains(row)))
+        }
+    }
+}
+
+/// First occurrences of rows in their order
+fn distinct(rows: impl Iterator<Item = Row>) -> Vec<Row> {
+    let mut seen = HashSet::new();
+    rows.filter(|row| seen.insert(row.clone())).collect()
+}
+
+fn counts(rows: Vec<Row>) -> HashMap<Row, usize> {
+    let mut counts = HashMap::new();
+    for row in rows {
+        *counts.entry(row).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Takes an occurrence of `row`, `false` if none is left
+fn take(counts: &mut HashMap<Row, usize>, row: &Row) -> bool {
+    match counts.get_mut(row) {
+        Some(count) if *count > 0 => {
+            *count -= 1;
+            true
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows_of(values: &[Option<&str>]) -> Vec<Row> {
+        values.iter().map(|value| vec![value.map(ToOwned::to_owned)]).collect()
+    }
+
+    #[test]
+    fn duplicates_are_removed_without_all() {
+        let left = rows_of(&[Some("1"), Some("2"), Some("2"), None]);
+        let right = rows_of(&[Some("2"), Some("3"), None]);
+
+        assert_eq!(
+            rows(&SetOperator::Union, false, left.clone(), right.clone()),
+            rows_of(&[Some("1"), Some("2"), None, Some("3")])
+        );
+        assert_eq!(
+            rows(&SetOperator::Intersect, false, left.clone(), right.clone()),
+            rows_of(&[Some("2"), None])
+        );
+        assert_eq!(rows(&SetOperator::Except, false, left, right), rows_of(&[Some("1")]));
+    }
+
+    #[test]
+    fn occurrences_are_counted_with_all() {
+        let left = rows_of(&[Some("1"), Some("2"), Some("2"), Some("2")]);
+        let right = rows_of(&[Some("2"), Some("2"), Some("3")]);
+
+        assert_eq!(rows(&SetOperator::Union, true, left.clone(), right.clone()).len(), 7);
+        assert_eq!(
+            rows(&SetOperator::Intersect, true, left.clone(), right.clone()),
+            rows_of(&[Some("2"), Some("2")])
+        );
+        assert_eq!(
+            rows(&SetOperator::Except, true, left, right),
+            rows_of(&[Some("1"), Some("2")])
+        );
+    }
+
+    #[test]
+    fn common_types() {
+        assert_eq!(
+            common_type(PostgreSqlType::SmallInt, PostgreSqlType::BigInt),
+            Some(PostgreSqlType::BigInt)
+        );
+        assert_eq!(
+            common_type(PostgreSqlType::Char, PostgreSqlType::VarChar),
+            Some(PostgreSqlType::VarChar)
+        );
+        assert_eq!(
+            common_type(PostgreSqlType::Bool, PostgreSqlType::Bool),
+            Some(PostgreSqlType::Bool)
+        );
+        assert_eq!(common_type(PostgreSqlType::Integer, PostgreSqlType::VarChar), None);
+        assert_eq!(common_type(PostgreSqlType::Date, PostgreSqlType::Timestamp), None);
+    }
+}
@@ -0,0 +1,346 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `ORDER BY` clauses of queries. Selected rows are sorted by values of
+//! their columns before they are sent. Keys are either positions of
+//! columns, e.g. `ORDER BY 2`, or expressions that are selected as hidden
+//! columns, which are dropped once rows are sorted. Keys of set operations
+//! can only be positions or names of their columns.
+use crate::dml::{
+    memory::{text_rows_size, WorkMem},
+    predicate::{Kind, Scalar},
+    subquery,
+};
+use protocol::{
+    results::{ColumnDescription, Description, QueryError, QueryEvent, QueryResult},
+    Sender,
+};
+use sqlparser::ast::{Expr, OrderByExpr, Query, SelectItem, SetExpr, Value};
+use std::{
+    cmp::Ordering,
+    io, mem,
+    sync::{Arc, Mutex},
+};
+
+type TextRow = Vec<Option<String>>;
+
+/// Column a query is sorted by
+#[derive(Debug, Clone, PartialEq)]
+enum Column {
+    /// position of a column starting from 1
+    Position(usize),
+    /// name of a column of a set operation
+    Name(String),
+    /// index of a hidden column
+    Hidden(usize),
+}
+
+#[derive(Debug, PartialEq)]
+struct SortKey {
+    column: Column,
+    descending: bool,
+    nulls_first: bool,
+}
+
+/// Keys of `ORDER BY` clause of a query and the number of hidden columns it
+/// selects for them
+#[derive(Debug, PartialEq)]
+pub(crate) struct Order {
+    keys: Vec<SortKey>,
+    hidden: usize,
+}
+
+impl Order {
+    /// Takes `ORDER BY` clause of `query` and adds its keys to the select
+    /// list as hidden columns, `None` if the query is not sorted
+    pub(crate) fn of(query: &mut Query) -> Result<Option<Order>, QueryError> {
+        let order_by = mem::take(&mut query.order_by);
+        if order_by.is_empty() {
+            return Ok(None);
+        }
+        let mut keys = vec![];
+        let mut hidden = vec![];
+        for OrderByExpr { expr, asc, nulls_first } in order_by {
+            let descending = asc == Some(false);
+            let column = match (position(&expr), &query.body) {
+                (Some(position), _) => Column::Position(position),
+                (None, SetExpr::Select(select)) => {
+                    hidden.push(SelectItem::UnnamedExpr(
+                        aliased(&select.projection, &expr).unwrap_or(expr),
+                    ));
+                    Column::Hidden(hidden.len() - 1)
+                }
+                (None, _) => match expr {
+                    Expr::Identifier(name) => Column::Name(name.value),
+                    expr => return Err(QueryError::feature_not_supported(format!("ORDER BY {}", expr))),
+                },
+            };
+            keys.push(SortKey {
+                column,
+                descending,
+                nulls_first: nulls_first.unwrap_or(descending),
+            });
+        }
+        let hidden_columns = hidden.len();
+        if let SetExpr::Select(select) = &mut query.body {
+            select.projection.extend(hidden);
+        }
+        Ok(Some(Order {
+            keys,
+            hidden: hidden_columns,
+        }))
+    }
+
+    /// Description of selected columns without hidden ones
+    pub(crate) fn visible(&self, mut description: Description) -> Description {
+        description.truncate(description.len().saturating_sub(self.hidden));
+        description
+    }
+
+    /// Keys of columns of `description` that includes hidden columns
+    fn resolve(&self, description: &[ColumnDescription]) -> Result<Vec<Resolved>, QueryError> {
+        let visible = description.len().saturating_sub(self.hidden);
+        self.keys
+            .iter()
+            .map(|key| {
+                let index = match &key.column {
+                    Column::Position(position) if (1..=visible).contains(position) => position - 1,
+                    Column::Position(position) => {
+                        return Err(QueryError::invalid_column_reference(format!(
+                            "ORDER BY position {} is not in select list",
+                            position
+                        )))
+                    }
+                    Column::Name(name) => match description[..visible].iter().position(|column| &column.name == name) {
+                        Some(index) => index,
+                        None => return Err(QueryError::column_does_not_exist(vec![name.clone()])),
+                    },
+                    Column::Hidden(hidden) => visible + hidden,
+                };
+                Ok(Resolved {
+                    index,
+                    kind: Kind::of(&subquery::sql_type(description[index].pg_type)),
+                    descending: key.descending,
+                    nulls_first: key.nulls_first,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Key with the index and kind of its column
+struct Resolved {
+    index: usize,
+    kind: Kind,
+    descending: bool,
+    nulls_first: bool,
+}
+
+/// Position of a column that `expr` refers to, e.g. `2` of `ORDER BY 2`
+fn position(expr: &Expr) -> Option<usize> {
+    match expr {
+        Expr::Value(Value::Number(number)) => number.to_string().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Expression of the select list item that has `expr` as its alias
+fn aliased(projection: &[SelectItem], expr: &Expr) -> Option<Expr> {
+    let name = match expr {
+        Expr::Identifier(name) => name,
+        _ => return None,
+    };
+    projection.iter().find_map(|item| match item {
+        SelectItem::ExprWithAlias { expr, alias } if alias.value == name.value => Some(expr.clone()),
+        _ => None,
+    })
+}
+
+/// Order of values of a key, NULLs are greater than other values unless
+/// they go first
+fn compare(left: &Option<String>, right: &Option<String>, key: &Resolved) -> Ordering {
+    match (left, right) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) if key.nulls_first => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) if key.nulls_first => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(left), Some(right)) => {
+            let ordering = Scalar::parse(Some(left), key.kind)
+                .partial_cmp(&Scalar::parse(Some(right), key.kind))
+                .unwrap_or(Ordering::Equal);
+            if key.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        }
+    }
+}
+
+/// Rows that are kept until all of them are selected and the keys to sort
+/// them by, `None` once sorting failed
+struct State {
+    keys: Option<Vec<Resolved>>,
+    rows: Vec<TextRow>,
+    work_mem: WorkMem,
+}
+
+/// Sorts rows of a query before they are sent to `sender`, the rows have to
+/// fit into `work_mem`
+pub(crate) struct Sorted {
+    sender: Arc<dyn Sender>,
+    order: Order,
+    state: Mutex<State>,
+}
+
+impl Sorted {
+    pub(crate) fn new(sender: Arc<dyn Sender>, order: Order, work_mem: usize) -> Sorted {
+        Sorted {
+            sender,
+            order,
+            state: Mutex::new(State {
+                keys: Some(vec![]),
+                rows: vec![],
+                work_mem: WorkMem::new(work_mem),
+            }),
+        }
+    }
+}
+
+impl Sender for Sorted {
+    fn flush(&self) -> io::Result<()> {
+        self.sender.flush()
+    }
+
+    fn send(&self, query_result: QueryResult) -> io::Result<()> {
+        let mut state = self.state.lock().expect("to acquire lock");
+        match query_result {
+            Ok(QueryEvent::RowDescription(description)) => match self.order.resolve(&description) {
+                Ok(keys) => {
+                    state.keys = Some(keys);
+                    self.sender
+                        .send(Ok(QueryEvent::RowDescription(self.order.visible(description))))
+                }
+                Err(error) => {
+                    state.keys = None;
+                    self.sender.send(Err(error))
+                }
+            },
+            Ok(QueryEvent::DataRows(rows)) => {
+                if state.keys.is_none() {
+                    return Ok(());
+                }
+                if state.work_mem.allocate(text_rows_size(&rows)).is_err() {
+                    state.keys = None;
+                    state.rows.clear();
+                    let limit = state.work_mem.limit();
+                    return self.sender.send(Err(QueryError::out_of_memory("sort", limit)));
+                }
+                state.rows.extend(rows);
+                Ok(())
+            }
+            Ok(QueryEvent::SelectComplete(selected)) => {
+                let keys = match state.keys.take() {
+                    Some(keys) => keys,
+                    None => return Ok(()),
+                };
+                let mut rows = mem::take(&mut state.rows);
+                rows.sort_by(|left, right| {
+                    keys.iter()
+                        .map(|key| compare(&left[key.index], &right[key.index], key))
+                        .find(|ordering| *ordering != Ordering::Equal)
+                        .unwrap_or(Ordering::Equal)
+                });
+                for row in rows.iter_mut() {
+                    row.truncate(row.len() - self.order.hidden);
+                }
+                if !rows.is_empty() {
+                    self.sender.send(Ok(QueryEvent::DataRows(rows)))?;
+                }
+                self.sender.send(Ok(QueryEvent::SelectComplete(selected)))
+            }
+            Err(error) => {
+                state.rows.clear();
+                self.sender.send(Err(error))
+            }
+            query_result => self.sender.send(query_result),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::table_sample::parse_sql;
+    use sqlparser::{ast::Statement, dialect::PostgreSqlDialect};
+
+    fn query(sql: &str) -> Query {
+        match parse_sql(&PostgreSqlDialect {}, sql).expect("parsed").pop() {
+            Some(Statement::Query(query)) => *query,
+            _ => unreachable!("query"),
+        }
+    }
+
+    fn key(column: Column, descending: bool, nulls_first: bool) -> SortKey {
+        SortKey {
+            column,
+            descending,
+            nulls_first,
+        }
+    }
+
+    #[test]
+    fn not_sorted() {
+        assert_eq!(Order::of(&mut query("select a from s.t")), Ok(None));
+    }
+
+    #[test]
+    fn keys_are_hidden_columns() {
+        let mut sorted = query("select a as b, c from s.t order by 2, b desc, a + c nulls first");
+
+        assert_eq!(
+            Order::of(&mut sorted),
+            Ok(Some(Order {
+                keys: vec![
+                    key(Column::Position(2), false, false),
+                    key(Column::Hidden(0), true, true),
+                    key(Column::Hidden(1), false, true),
+                ],
+                hidden: 2
+            }))
+        );
+        assert_eq!(sorted.to_string(), "SELECT a AS b, c, a, a + c FROM s.t");
+    }
+
+    #[test]
+    fn keys_of_set_operation() {
+        let mut sorted = query("select a from s.t union select b from s.u order by a desc nulls last, 1");
+
+        assert_eq!(
+            Order::of(&mut sorted),
+            Ok(Some(Order {
+                keys: vec![
+                    key(Column::Name("a".to_owned()), true, false),
+                    key(Column::Position(1), false, false)
+                ],
+                hidden: 0
+            }))
+        );
+        assert_eq!(
+            Order::of(&mut query("select a from s.t union select b from s.u order by a + 1")),
+            Err(QueryError::feature_not_supported("ORDER BY a + 1".to_owned()))
+        );
+    }
+}
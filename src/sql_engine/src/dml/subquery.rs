@@ -0,0 +1,312 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Subqueries of `IN` and `EXISTS` conditions. A subquery that does not
+//! refer to columns of filtered rows is executed once, when the condition is
+//! compiled, and its rows are kept for all filtered rows. A correlated
+//! subquery is executed for each filtered row with values of the columns it
+//! refers to in place of the references.
+use crate::dml::predicate::{Kind, Predicate, Scalar};
+use bigdecimal::BigDecimal;
+use protocol::{
+    results::{Description, QueryError, QueryEvent, QueryResult},
+    sql_types::PostgreSqlType,
+    Sender,
+};
+use sql_types::SqlType;
+use sqlparser::ast::{Expr, Function, Join, JoinConstraint, JoinOperator, Query, SelectItem, SetExpr, Value};
+use std::{
+    borrow::Cow,
+    fmt::{self, Debug, Formatter},
+    io,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+/// Description and rows of an executed subquery
+pub(crate) type Output = (Description, Vec<Vec<Option<String>>>);
+
+/// Executes subqueries of conditions of a query
+pub(crate) trait Subqueries: Send + Sync {
+    /// Description and rows of `query`
+    fn execute(&self, query: &Query) -> Result<Output, QueryError>;
+
+    /// Whether `expr` refers to a column of a relation of `query` rather
+    /// than to a column of the outer query
+    fn is_inner(&self, query: &Query, expr: &Expr) -> bool;
+}
+
+/// Compiled subquery with references to columns of the outer query
+pub(crate) struct Subquery {
+    query: Query,
+    /// references to columns of filtered rows and the columns they refer to
+    outer: Vec<(Expr, Predicate)>,
+    columns: usize,
+    sql_type: SqlType,
+    subqueries: Arc<dyn Subqueries>,
+    /// values of an uncorrelated subquery
+    values: Option<Vec<Scalar>>,
+}
+
+impl Subquery {
+    /// Checks the subquery by executing it once, with NULLs in place of the
+    /// references if it is correlated
+    pub(crate) fn new(
+        query: Query,
+        outer: Vec<(Expr, Predicate)>,
+        subqueries: Arc<dyn Subqueries>,
+    ) -> Result<Subquery, QueryError> {
+        let nulls = vec![Scalar::Null; outer.len()];
+        let (description, rows) = subqueries.execute(&bind(&query, &outer, &nulls))?;
+        let sql_type = description
+            .first()
+            .map(|column| sql_type(column.pg_type))
+            .unwrap_or(SqlType::Bool);
+        let values = if outer.is_empty() {
+            Some(first_values(rows, sql_type))
+        } else {
+            None
+        };
+        Ok(Subquery {
+            query,
+            outer,
+            columns: description.len(),
+            sql_type,
+            subqueries,
+            values,
+        })
+    }
+
+    /// Number of columns of rows of the subquery
+    pub(crate) fn columns(&self) -> usize {
+        self.columns
+    }
+
+    /// Type of the first column of rows of the subquery
+    pub(crate) fn sql_type(&self) -> SqlType {
+        self.sql_type
+    }
+
+    /// Columns of filtered rows that the subquery refers to
+    pub(crate) fn outer(&self) -> impl Iterator<Item = &Predicate> {
+        self.outer.iter().map(|(_reference, column)| column)
+    }
+
+    /// Values of the first column of rows of the subquery for `outer`
+    /// values of the columns it refers to
+    pub(crate) fn values(&self, outer: Vec<Scalar>) -> Result<Cow<'_, [Scalar]>, QueryError> {
+        match &self.values {
+            Some(values) => Ok(Cow::Borrowed(values)),
+            None => {
+                let (_description, rows) = self.subqueries.execute(&bind(&self.query, &self.outer, &outer))?;
+                Ok(Cow::Owned(first_values(rows, self.sql_type)))
+            }
+        }
+    }
+}
+
+impl Debug for Subquery {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Subquery")
+            .field("query", &self.query.to_string())
+            .field("outer", &self.outer)
+            .finish()
+    }
+}
+
+impl PartialEq for Subquery {
+    fn eq(&self, other: &Self) -> bool {
+        self.query == other.query && self.outer == other.outer
+    }
+}
+
+/// Columns that the select list, `WHERE` clause and join conditions of
+/// `query` refer to
+pub(crate) fn references(query: &Query) -> Vec<Expr> {
+    let mut references = vec![];
+    visit_query(&mut query.clone(), &mut |reference| references.push(reference.clone()));
+    references
+}
+
+/// Copy of `query` with literals of `values` in place of `outer` references
+fn bind(query: &Query, outer: &[(Expr, Predicate)], values: &[Scalar]) -> Query {
+    let mut bound = query.clone();
+    visit_query(&mut bound, &mut |reference| {
+        if let Some(position) = outer.iter().position(|(outer, _column)| outer == reference) {
+            *reference = literal(&values[position]);
+        }
+    });
+    bound
+}
+
+fn literal(scalar: &Scalar) -> Expr {
+    Expr::Value(match scalar {
+        Scalar::Null => Value::Null,
+        Scalar::Bool(value) => Value::Boolean(*value),
+        Scalar::Integer(value) => Value::Number(BigDecimal::from(*value)),
+        Scalar::Float(value) => match BigDecimal::from_str(&value.to_string()) {
+            Ok(number) => Value::Number(number),
+            Err(_) => Value::Null,
+        },
+        Scalar::Text(text) => Value::SingleQuotedString(text.clone()),
+    })
+}
+
+fn visit_query(query: &mut Query, visit: &mut dyn FnMut(&mut Expr)) {
+    if let SetExpr::Select(select) = &mut query.body {
+        for item in &mut select.projection {
+            match item {
+                SelectItem::UnnamedExpr(expr) | SelectItem::ExprWithAlias { expr, .. } => visit_expr(expr, visit),
+                SelectItem::Wildcard | SelectItem::QualifiedWildcard(_) => {}
+            }
+        }
+        for table_with_joins in &mut select.from {
+            for Join { join_operator, .. } in &mut table_with_joins.joins {
+                match join_operator {
+                    JoinOperator::Inner(JoinConstraint::On(condition))
+                    | JoinOperator::LeftOuter(JoinConstraint::On(condition))
+                    | JoinOperator::RightOuter(JoinConstraint::On(condition))
+                    | JoinOperator::FullOuter(JoinConstraint::On(condition)) => visit_expr(condition, visit),
+                    _ => {}
+                }
+            }
+        }
+        if let Some(selection) = &mut select.selection {
+            visit_expr(selection, visit);
+        }
+    }
+}
+
+/// Visits column references of `expr`, columns of nested subqueries are not
+/// visited
+fn visit_expr(expr: &mut Expr, visit: &mut dyn FnMut(&mut Expr)) {
+    match expr {
+        Expr::Identifier(_) | Expr::CompoundIdentifier(_) => visit(expr),
+        Expr::IsNull(expr)
+        | Expr::IsNotNull(expr)
+        | Expr::UnaryOp { expr, .. }
+        | Expr::Cast { expr, .. }
+        | Expr::Nested(expr)
+        | Expr::InSubquery { expr, .. } => visit_expr(expr, visit),
+        Expr::BinaryOp { left, right, .. } => {
+            visit_expr(left, visit);
+            visit_expr(right, visit);
+        }
+        Expr::Between { expr, low, high, .. } => {
+            visit_expr(expr, visit);
+            visit_expr(low, visit);
+            visit_expr(high, visit);
+        }
+        Expr::InList { expr, list, .. } => {
+            visit_expr(expr, visit);
+            for expr in list {
+                visit_expr(expr, visit);
+            }
+        }
+        Expr::Function(Function { args, .. }) => {
+            for expr in args {
+                visit_expr(expr, visit);
+            }
+        }
+        Expr::Case {
+            operand,
+            conditions,
+            results,
+            else_result,
+        } => {
+            for expr in operand
+                .as_deref_mut()
+                .into_iter()
+                .chain(conditions)
+                .chain(results)
+                .chain(else_result.as_deref_mut())
+            {
+                visit_expr(expr, visit);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Values of the first column of `rows` of `sql_type` type, values of `char`
+/// columns are compared without trailing spaces
+fn first_values(rows: Vec<Vec<Option<String>>>, sql_type: SqlType) -> Vec<Scalar> {
+    let kind = Kind::of(&sql_type);
+    rows.into_iter()
+        .map(
+            |row| match Scalar::parse(row.into_iter().next().flatten().as_deref(), kind) {
+                Scalar::Text(text) if matches!(sql_type, SqlType::Char(_)) => Scalar::Text(text.trim_end().to_owned()),
+                scalar => scalar,
+            },
+        )
+        .collect()
+}
+
+pub(crate) fn sql_type(pg_type: PostgreSqlType) -> SqlType {
+    match pg_type {
+        PostgreSqlType::Bool => SqlType::Bool,
+        PostgreSqlType::Char => SqlType::Char(1),
+        PostgreSqlType::VarChar => SqlType::VarChar(u64::MAX),
+        PostgreSqlType::Decimal => SqlType::Decimal,
+        PostgreSqlType::SmallInt => SqlType::SmallInt(i16::MIN),
+        PostgreSqlType::Integer => SqlType::Integer(i32::MIN),
+        PostgreSqlType::BigInt => SqlType::BigInt(i64::MIN),
+        PostgreSqlType::Real => SqlType::Real,
+        PostgreSqlType::DoublePrecision => SqlType::DoublePrecision,
+        PostgreSqlType::Time => SqlType::Time,
+        PostgreSqlType::TimeWithTimeZone => SqlType::TimeWithTimeZone,
+        PostgreSqlType::Timestamp => SqlType::Timestamp,
+        PostgreSqlType::TimestampWithTimeZone => SqlType::TimestampWithTimeZone,
+        PostgreSqlType::Date => SqlType::Date,
+        PostgreSqlType::Interval => SqlType::Interval,
+        PostgreSqlType::TsVector => SqlType::TsVector,
+        PostgreSqlType::TsQuery => SqlType::TsQuery,
+        PostgreSqlType::Void => SqlType::Void,
+    }
+}
+
+/// Keeps the description and rows of a subquery and the first error it
+/// failed with
+#[derive(Default)]
+pub(crate) struct Rows(Mutex<(Output, Option<QueryError>)>);
+
+impl Rows {
+    /// Description and rows of the subquery unless it failed
+    pub(crate) fn take(&self) -> Result<Output, QueryError> {
+        let (output, error) = std::mem::take(&mut *self.0.lock().expect("to acquire lock"));
+        match error {
+            Some(error) => Err(error),
+            None => Ok(output),
+        }
+    }
+}
+
+impl Sender for Rows {
+    fn flush(&self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn send(&self, query_result: QueryResult) -> io::Result<()> {
+        let mut result = self.0.lock().expect("to acquire lock");
+        match query_result {
+            Ok(QueryEvent::RowDescription(description)) => (result.0).0 = description,
+            Ok(QueryEvent::DataRows(rows)) => (result.0).1.extend(rows),
+            Ok(_) => {}
+            Err(error) => {
+                result.1.get_or_insert(error);
+            }
+        }
+        Ok(())
+    }
+}
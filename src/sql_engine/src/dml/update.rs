@@ -12,40 +12,59 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::{catalog_manager::CatalogManager, dml::ExpressionEvaluation};
-use kernel::SystemResult;
+use crate::{
+    cancellation::CancellationToken,
+    catalog_manager::{CatalogManager, RowChange},
+    dml::{self, batch::Batches, functions::ScalarFunctions, write_buffer::WriteBuffer, ExpressionEvaluation},
+    query::resolve::Scope,
+    transaction::Transaction,
+};
+use kernel::{SystemError, SystemResult};
 use protocol::{
     results::{QueryError, QueryEvent},
     Sender,
 };
-use representation::{unpack_raw, Binary, Datum};
+use representation::Datum;
 use sql_types::ConstraintError;
 use sqlparser::ast::{Assignment, Expr, Ident, ObjectName, UnaryOperator, Value};
-use std::{collections::BTreeSet, convert::TryFrom, sync::Arc};
-use storage::Row;
+use std::sync::Arc;
 
 pub(crate) struct UpdateCommand {
     name: ObjectName,
     assignments: Vec<Assignment>,
+    selection: Option<Expr>,
     storage: Arc<CatalogManager>,
     session: Arc<dyn Sender>,
+    cancellation: CancellationToken,
+    /// functions that the statement calls
+    functions: ScalarFunctions,
+    transaction: Arc<Transaction>,
 }
 
 impl UpdateCommand {
     pub(crate) fn new(
         name: ObjectName,
         assignments: Vec<Assignment>,
+        selection: Option<Expr>,
         storage: Arc<CatalogManager>,
         session: Arc<dyn Sender>,
+        functions: ScalarFunctions,
+        transaction: Arc<Transaction>,
     ) -> UpdateCommand {
         UpdateCommand {
             name,
             assignments,
+            selection,
             storage,
             session,
+            cancellation: functions.cancellation().clone(),
+            functions,
+            transaction,
         }
     }
 
+    /// Rows that were updated before the statement was cancelled or aborted
+    /// by a deadlock stay updated and their changes are published
     pub(crate) fn execute(&mut self) -> SystemResult<()> {
         let schema_name = self.name.0[0].to_string();
         let table_name = self.name.0[1].to_string();
@@ -65,7 +84,7 @@ impl UpdateCommand {
                             .send(Err(QueryError::syntax_error(
                                 op.to_string() + expr.to_string().as_str(),
                             )))
-                            .expect("To Send Query Result to Client");
+                            .map_err(SystemError::connection_lost)?;
                         return Ok(());
                     }
                 },
@@ -76,7 +95,7 @@ impl UpdateCommand {
                 expr => {
                     self.session
                         .send(Err(QueryError::syntax_error(expr.to_string())))
-                        .expect("To Send Query Result to Client");
+                        .map_err(SystemError::connection_lost)?;
                     return Ok(());
                 }
             };
@@ -88,55 +107,65 @@ impl UpdateCommand {
             None => self
                 .session
                 .send(Err(QueryError::schema_does_not_exist(schema_name)))
-                .expect("To Send Result to Client"),
+                .map_err(SystemError::connection_lost)?,
             Some((_, None)) => self
                 .session
                 .send(Err(QueryError::table_does_not_exist(
                     schema_name + "." + table_name.as_str(),
                 )))
-                .expect("To Send Result to Client"),
+                .map_err(SystemError::connection_lost)?,
+            Some((_, Some(_))) if self.storage.is_foreign_table(&schema_name, &table_name) => self
+                .session
+                .send(Err(QueryError::cannot_change_foreign_table(
+                    "update",
+                    schema_name + "." + table_name.as_str(),
+                )))
+                .map_err(SystemError::connection_lost)?,
             Some((_, Some(_))) => {
                 let all_columns = self.storage.table_columns(&schema_name, &table_name)?;
                 let mut errors = Vec::new();
                 let mut index_value_pairs = Vec::new();
-                let mut non_existing_columns = BTreeSet::new();
-                let mut column_exists = false;
+                let mut non_existing_columns = vec![];
+                let scope = Scope::new(&all_columns);
 
                 for (column_name, value) in to_update {
-                    for (index, column_definition) in all_columns.iter().enumerate() {
-                        if column_definition.has_name(&column_name) {
-                            let v = match value.clone() {
-                                Value::Number(v) => v.to_string(),
-                                Value::SingleQuotedString(v) => v.to_string(),
-                                Value::Boolean(v) => v.to_string(),
+                    match scope.lookup(&column_name) {
+                        Ok(Some(index)) => {
+                            let column_definition = &all_columns[index];
+                            let constraint = column_definition.sql_type().constraint();
+                            let validation = match value.clone() {
+                                Value::Number(v) => constraint.validate(v.to_string().as_str()),
+                                Value::SingleQuotedString(v) => constraint.validate(v.as_str()),
+                                Value::Boolean(v) => constraint.validate(v.to_string().as_str()),
+                                Value::Null => Ok(()),
                                 _ => unimplemented!("other types not implemented"),
                             };
-                            match column_definition.sql_type().constraint().validate(v.as_str()) {
+                            match validation {
                                 Ok(()) => {
-                                    index_value_pairs.push((index, Datum::try_from(&value).unwrap()));
+                                    index_value_pairs
+                                        .push((index, dml::literal_datum(&column_definition.sql_type(), &value)));
                                 }
                                 Err(e) => {
                                     errors.push((e, column_definition.clone()));
                                 }
                             }
-
-                            column_exists = true;
-
-                            break;
                         }
-                    }
-
-                    if !column_exists {
-                        non_existing_columns.insert(column_name.clone());
+                        Ok(None) => {
+                            if !non_existing_columns.contains(&column_name) {
+                                non_existing_columns.push(column_name.clone());
+                            }
+                        }
+                        Err(error) => {
+                            self.session.send(Err(error)).map_err(SystemError::connection_lost)?;
+                            return Ok(());
+                        }
                     }
                 }
 
                 if !non_existing_columns.is_empty() {
                     self.session
-                        .send(Err(QueryError::column_does_not_exist(
-                            non_existing_columns.into_iter().collect(),
-                        )))
-                        .expect("To Send Result to Client");
+                        .send(Err(scope.unknown(non_existing_columns)))
+                        .map_err(SystemError::connection_lost)?;
                     return Ok(());
                 }
                 if !errors.is_empty() {
@@ -162,34 +191,82 @@ impl UpdateCommand {
                         };
                         self.session
                             .send(Err(error_to_send))
-                            .expect("To Send Query Result to Client");
+                            .map_err(SystemError::connection_lost)?;
                     }
                     return Ok(());
                 }
+                let assigned_null = index_value_pairs
+                    .iter()
+                    .find(|(index, datum)| all_columns[*index].is_not_null() && matches!(datum, Datum::Null));
+                if let Some((index, _)) = assigned_null {
+                    self.session
+                        .send(Err(QueryError::not_null_violation(all_columns[*index].name())))
+                        .map_err(SystemError::connection_lost)?;
+                    return Ok(());
+                }
 
-                let to_update: Vec<Row> = match self.storage.full_scan(&schema_name, &table_name) {
-                    Err(error) => return Err(error),
-                    Ok(reads) => reads
-                        .map(Result::unwrap)
-                        .map(Result::unwrap)
-                        .map(|(key, values)| {
-                            let mut values = unpack_raw(values.to_bytes());
-                            for (idx, data) in index_value_pairs.as_slice() {
-                                values[*idx] = data.clone();
-                            }
-                            (key, Binary::pack(&values))
-                        })
-                        .collect(),
+                let condition = match &self.selection {
+                    Some(selection) => match dml::table_condition(
+                        selection,
+                        (&schema_name, &table_name),
+                        &all_columns,
+                        &self.functions,
+                    ) {
+                        Ok(condition) => Some(condition),
+                        Err(error) => {
+                            self.session.send(Err(error)).map_err(SystemError::connection_lost)?;
+                            return Ok(());
+                        }
+                    },
+                    None => None,
                 };
-
-                match self.storage.write_into(&schema_name, &table_name, to_update) {
-                    Err(error) => return Err(error),
-                    Ok(records_number) => {
-                        self.session
-                            .send(Ok(QueryEvent::RecordsUpdated(records_number)))
-                            .expect("To Send Query Result to Client");
+                let reads = self.transaction.full_scan(&self.storage, &schema_name, &table_name)?;
+                let reads = match condition {
+                    Some(condition) => condition.filter(reads),
+                    None => reads,
+                };
+                let mut scanned = 0;
+                let mut buffer = WriteBuffer::rows(&self.transaction, &self.storage, &schema_name, &table_name);
+                let published = self.storage.publications().is_published(&schema_name, &table_name);
+                let mut changes = vec![];
+                let mut interrupted = Ok(());
+                for batch in Batches::new(reads) {
+                    let locked = self.cancellation.check().and_then(|()| {
+                        self.transaction.lock_rows(
+                            &schema_name,
+                            &table_name,
+                            batch.stored().map(|(key, _)| key),
+                            &self.cancellation,
+                        )
+                    });
+                    if let Err(error) = locked {
+                        interrupted = Err(error);
+                        break;
+                    }
+                    scanned += batch.len();
+                    if published {
+                        for old in batch.rows() {
+                            let mut new = old.clone();
+                            for (index, datum) in index_value_pairs.iter() {
+                                new[*index] = datum.clone();
+                            }
+                            changes.push(RowChange::update(&schema_name, &table_name, &old, &new));
+                        }
                     }
+                    buffer.push(batch.assign(&index_value_pairs))?;
                 }
+                let records_number = buffer.flush()?;
+                self.transaction
+                    .publish(&self.storage, &schema_name, &table_name, changes)?;
+                self.storage
+                    .statistics()
+                    .seq_scanned(&schema_name, &table_name, scanned);
+                self.storage
+                    .statistics()
+                    .updated(&schema_name, &table_name, records_number);
+                self.session
+                    .send(interrupted.map(|()| QueryEvent::RecordsUpdated(records_number)))
+                    .map_err(SystemError::connection_lost)?;
             }
         }
         Ok(())
@@ -0,0 +1,184 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `VALUES` lists that are queried as relations. Their expressions are
+//! evaluated once and can't refer to columns. Columns of a list are named
+//! `column1`, `column2` and so on and have the common type of their values,
+//! NULLs take the type of other values of the column.
+use crate::dml::{
+    cte::WorkTable, functions::ScalarFunctions, predicate::Predicate, set_operation::common_type, subquery::Output,
+};
+use protocol::{
+    results::{ColumnDescription, QueryError},
+    sql_types::PostgreSqlType,
+};
+use sqlparser::ast::{Expr, Ident, Query, SetExpr, Value, Values};
+
+/// `VALUES` list of `query` if it has no other clauses
+pub(crate) fn values_of(query: &Query) -> Option<&Values> {
+    match &query.body {
+        SetExpr::Values(values)
+            if query.ctes.is_empty()
+                && query.order_by.is_empty()
+                && query.limit.is_none()
+                && query.offset.is_none()
+                && query.fetch.is_none() =>
+        {
+            Some(values)
+        }
+        _ => None,
+    }
+}
+
+/// Description and rows of `values`, all of its rows must have the same
+/// number of values
+pub(crate) fn evaluate(values: &Values, functions: &ScalarFunctions) -> Result<Output, QueryError> {
+    let width = values.0.first().map_or(0, Vec::len);
+    let mut types: Vec<Option<PostgreSqlType>> = vec![None; width];
+    let mut rows = vec![];
+    for exprs in &values.0 {
+        if exprs.len() != width {
+            return Err(QueryError::syntax_error(values.to_string()));
+        }
+        let mut row = vec![];
+        for (expr, common) in exprs.iter().zip(types.iter_mut()) {
+            let (value, sql_type) = Predicate::expression(expr, &[], functions, &|_column| Ok(None))?;
+            row.push(value.value(&[])?);
+            if *expr == Expr::Value(Value::Null) {
+                continue;
+            }
+            let pg_type = (&sql_type).into();
+            *common = match *common {
+                None => Some(pg_type),
+                Some(common) => match common_type(common, pg_type) {
+                    Some(pg_type) => Some(pg_type),
+                    None => {
+                        return Err(QueryError::datatype_mismatch(format!(
+                            "VALUES types {} and {} cannot be matched",
+                            common, pg_type
+                        )))
+                    }
+                },
+            };
+        }
+        rows.push(row);
+    }
+    let description = types
+        .into_iter()
+        .enumerate()
+        .map(|(index, pg_type)| {
+            ColumnDescription::new(
+                &format!("column{}", index + 1),
+                pg_type.unwrap_or(PostgreSqlType::VarChar),
+            )
+        })
+        .collect();
+    Ok((description, rows))
+}
+
+/// Rows of `values` in the `FROM` clause as the relation `name`, `aliases`
+/// rename its first columns
+pub(crate) fn work_table(
+    values: &Values,
+    name: &str,
+    aliases: &[Ident],
+    functions: &ScalarFunctions,
+) -> Result<WorkTable, QueryError> {
+    let (description, rows) = evaluate(values, functions)?;
+    if aliases.len() > description.len() {
+        return Err(QueryError::invalid_column_reference(format!(
+            "table \"{}\" has {} columns available but {} columns specified",
+            name,
+            description.len(),
+            aliases.len()
+        )));
+    }
+    let mut table = WorkTable::new(&description, aliases);
+    table.extend(rows)?;
+    Ok(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::table_sample::parse_sql;
+    use sqlparser::{ast::Statement, dialect::PostgreSqlDialect};
+
+    fn values(sql: &str) -> Result<Output, QueryError> {
+        match parse_sql(&PostgreSqlDialect {}, sql).expect("parsed").pop() {
+            Some(Statement::Query(query)) => match &query.body {
+                SetExpr::Values(values) => evaluate(values, &ScalarFunctions::default()),
+                _ => unreachable!("values"),
+            },
+            _ => unreachable!("query"),
+        }
+    }
+
+    fn column(name: &str, pg_type: PostgreSqlType) -> ColumnDescription {
+        ColumnDescription::new(name, pg_type)
+    }
+
+    #[test]
+    fn columns_are_numbered() {
+        assert_eq!(
+            values("values (1, 'a'), (2 + 3, 'b')"),
+            Ok((
+                vec![
+                    column("column1", PostgreSqlType::Integer),
+                    column("column2", PostgreSqlType::VarChar)
+                ],
+                vec![
+                    vec![Some("1".to_owned()), Some("a".to_owned())],
+                    vec![Some("5".to_owned()), Some("b".to_owned())],
+                ]
+            ))
+        );
+    }
+
+    #[test]
+    fn nulls_take_type_of_other_values() {
+        assert_eq!(
+            values("values (null, null), (true, null)"),
+            Ok((
+                vec![
+                    column("column1", PostgreSqlType::Bool),
+                    column("column2", PostgreSqlType::VarChar)
+                ],
+                vec![vec![None, None], vec![Some("t".to_owned()), None]]
+            ))
+        );
+    }
+
+    #[test]
+    fn common_type_of_values() {
+        assert_eq!(
+            values("values (1), (12345678901)").map(|(description, _rows)| description),
+            Ok(vec![column("column1", PostgreSqlType::BigInt)])
+        );
+        assert_eq!(
+            values("values (1), ('a')"),
+            Err(QueryError::datatype_mismatch(
+                "VALUES types integer and variable character cannot be matched".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn rows_of_different_length() {
+        assert_eq!(
+            values("values (1, 2), (3)"),
+            Err(QueryError::syntax_error("VALUES (1, 2), (3)".to_owned()))
+        );
+    }
+}
@@ -0,0 +1,178 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{catalog_manager::CatalogManager, dml::batch::BATCH_SIZE, transaction::Transaction};
+use kernel::SystemResult;
+use storage::{Key, Row};
+
+/// Number of buffered rows that are written into storage at once
+pub(crate) const WRITE_BUFFER_SIZE: usize = 16 * BATCH_SIZE;
+
+type Write<T> = fn(&Transaction, &CatalogManager, &str, &str, Vec<T>) -> SystemResult<usize>;
+
+/// Buffers changes that a statement makes to a table and applies them to
+/// storage or to the write set of the transaction block in large chunks
+/// instead of an operation per row. Changes left in the buffer are applied
+/// when the statement flushes it.
+pub(crate) struct WriteBuffer<'b, T> {
+    transaction: &'b Transaction,
+    storage: &'b CatalogManager,
+    schema_name: &'b str,
+    table_name: &'b str,
+    write: Write<T>,
+    pending: Vec<T>,
+    written: usize,
+}
+
+impl<'b> WriteBuffer<'b, Row> {
+    /// Buffer of rows that are inserted or replaced
+    pub(crate) fn rows(
+        transaction: &'b Transaction,
+        storage: &'b CatalogManager,
+        schema_name: &'b str,
+        table_name: &'b str,
+    ) -> Self {
+        WriteBuffer::new(transaction, storage, schema_name, table_name, Transaction::write_into)
+    }
+}
+
+impl<'b> WriteBuffer<'b, Key> {
+    /// Buffer of keys of rows that are deleted
+    pub(crate) fn keys(
+        transaction: &'b Transaction,
+        storage: &'b CatalogManager,
+        schema_name: &'b str,
+        table_name: &'b str,
+    ) -> Self {
+        WriteBuffer::new(transaction, storage, schema_name, table_name, Transaction::delete_from)
+    }
+}
+
+impl<'b, T> WriteBuffer<'b, T> {
+    fn new(
+        transaction: &'b Transaction,
+        storage: &'b CatalogManager,
+        schema_name: &'b str,
+        table_name: &'b str,
+        write: Write<T>,
+    ) -> Self {
+        WriteBuffer {
+            transaction,
+            storage,
+            schema_name,
+            table_name,
+            write,
+            pending: Vec::with_capacity(WRITE_BUFFER_SIZE),
+            written: 0,
+        }
+    }
+
+    pub(crate) fn push(&mut self, changes: Vec<T>) -> SystemResult<()> {
+        self.pending.extend(changes);
+        if self.pending.len() >= WRITE_BUFFER_SIZE {
+            self.write_pending()?;
+        }
+        Ok(())
+    }
+
+    /// Applies the rest of changes and returns number of all applied ones
+    pub(crate) fn flush(mut self) -> SystemResult<usize> {
+        self.write_pending()?;
+        Ok(self.written)
+    }
+
+    fn write_pending(&mut self) -> SystemResult<()> {
+        if !self.pending.is_empty() {
+            let pending = std::mem::replace(&mut self.pending, Vec::with_capacity(WRITE_BUFFER_SIZE));
+            self.written += (self.write)(
+                self.transaction,
+                self.storage,
+                self.schema_name,
+                self.table_name,
+                pending,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ColumnDefinition;
+    use representation::{Binary, Datum};
+    use sql_types::SqlType;
+
+    fn storage() -> CatalogManager {
+        let storage = CatalogManager::default();
+        storage.create_schema("schema_name").expect("schema created");
+        let schema_id = storage.schema_exists("schema_name").expect("schema exists");
+        storage
+            .create_table(
+                schema_id,
+                "table_name",
+                &[ColumnDefinition::new("column_1", SqlType::SmallInt(i16::MIN))],
+            )
+            .expect("table created");
+        storage
+    }
+
+    fn rows(range: std::ops::Range<u64>) -> Vec<Row> {
+        range
+            .map(|index| {
+                (
+                    Binary::pack(&[Datum::from_u64(index)]),
+                    Binary::pack(&[Datum::from_i16(1)]),
+                )
+            })
+            .collect()
+    }
+
+    fn stored(storage: &CatalogManager) -> usize {
+        storage.full_scan("schema_name", "table_name").expect("scan").count()
+    }
+
+    #[test]
+    fn changes_are_written_when_buffer_is_full() {
+        let storage = storage();
+        let transaction = Transaction::new(storage.locks().clone());
+        let mut buffer = WriteBuffer::rows(&transaction, &storage, "schema_name", "table_name");
+
+        buffer.push(rows(0..10)).expect("no system errors");
+        assert_eq!(stored(&storage), 0);
+        buffer
+            .push(rows(10..WRITE_BUFFER_SIZE as u64))
+            .expect("no system errors");
+        assert_eq!(stored(&storage), WRITE_BUFFER_SIZE);
+    }
+
+    #[test]
+    fn flush_writes_the_rest_of_changes() {
+        let storage = storage();
+        let transaction = Transaction::new(storage.locks().clone());
+        let mut buffer = WriteBuffer::rows(&transaction, &storage, "schema_name", "table_name");
+        buffer.push(rows(0..10)).expect("no system errors");
+
+        assert_eq!(buffer.flush(), Ok(10));
+        assert_eq!(stored(&storage), 10);
+
+        let mut buffer = WriteBuffer::keys(&transaction, &storage, "schema_name", "table_name");
+        buffer
+            .push(rows(0..5).into_iter().map(|(key, _values)| key).collect())
+            .expect("no system errors");
+
+        assert_eq!(buffer.flush(), Ok(5));
+        assert_eq!(stored(&storage), 5);
+    }
+}
@@ -0,0 +1,149 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! Dump of schemas, tables and their rows as SQL statements. The dump can
+//! be loaded back with `psql` either into the database or into PostgreSQL,
+//! so it is a way to migrate data between them.
+use crate::{catalog_manager::CatalogManager, dml::batch::Batches, ColumnDefinition};
+use kernel::{SystemError, SystemResult};
+use representation::Datum;
+use sql_types::SqlType;
+use std::io::Write;
+
+/// Writes `CREATE SCHEMA`, `CREATE TABLE` and `INSERT` statements that
+/// recreate all schemas and tables of `storage` with their rows into `out`
+pub fn dump(storage: &CatalogManager, out: &mut dyn Write) -> SystemResult<()> {
+    write_dump(storage, out).map_err(SystemError::io)
+}
+
+fn write_dump(storage: &CatalogManager, out: &mut dyn Write) -> std::io::Result<()> {
+    writeln!(out, "--\n-- Database dump\n--\n")?;
+    let mut schemas = storage.schemas();
+    schemas.sort();
+    for schema_name in schemas.iter() {
+        writeln!(out, "CREATE SCHEMA {};\n", schema_name)?;
+    }
+    for schema_name in schemas.iter() {
+        let mut tables = storage.tables(schema_name);
+        tables.sort();
+        for table_name in tables.iter() {
+            // rows of foreign tables belong to their sources
+            if storage.is_foreign_table(schema_name, table_name) {
+                continue;
+            }
+            let columns = storage
+                .table_columns(schema_name, table_name)
+                .map_err(|error| to_io_error(error, schema_name, table_name))?;
+            writeln!(out, "CREATE TABLE {}.{} (", schema_name, table_name)?;
+            for (index, column) in columns.iter().enumerate() {
+                let separator = if index + 1 < columns.len() { "," } else { "" };
+                writeln!(
+                    out,
+                    "    {} {}{}",
+                    column.name(),
+                    type_name(&column.sql_type()),
+                    separator
+                )?;
+            }
+            writeln!(out, ");\n")?;
+            write_rows(storage, schema_name, table_name, &columns, out)?;
+        }
+    }
+    writeln!(out, "--\n-- Database dump complete\n--")?;
+    out.flush()
+}
+
+fn write_rows(
+    storage: &CatalogManager,
+    schema_name: &str,
+    table_name: &str,
+    columns: &[ColumnDefinition],
+    out: &mut dyn Write,
+) -> std::io::Result<()> {
+    let cursor = storage
+        .full_scan(schema_name, table_name)
+        .map_err(|error| to_io_error(error, schema_name, table_name))?;
+    let names = columns.iter().map(ColumnDefinition::name).collect::<Vec<String>>();
+    let indexes = (0..columns.len()).collect::<Vec<usize>>();
+    let mut dumped = 0;
+    for batch in Batches::new(cursor) {
+        let values = batch.columns(&indexes);
+        for row in 0..batch.len() {
+            let literals = values
+                .iter()
+                .map(|column| literal(&column[row]))
+                .collect::<Vec<String>>();
+            writeln!(
+                out,
+                "INSERT INTO {}.{} ({}) VALUES ({});",
+                schema_name,
+                table_name,
+                names.join(", "),
+                literals.join(", ")
+            )?;
+        }
+        dumped += batch.len();
+    }
+    if dumped > 0 {
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+fn to_io_error(error: SystemError, schema_name: &str, table_name: &str) -> std::io::Error {
+    std::io::Error::other(format!(
+        "could not read table {}.{}: {:?}",
+        schema_name, table_name, error
+    ))
+}
+
+/// Name of the type that both the database and PostgreSQL accept in
+/// `CREATE TABLE`
+fn type_name(sql_type: &SqlType) -> String {
+    match sql_type {
+        SqlType::Bool => "boolean".to_owned(),
+        SqlType::Char(length) => format!("char({})", length),
+        SqlType::VarChar(length) => format!("varchar({})", length),
+        other => other.to_string(),
+    }
+}
+
+fn literal(datum: &Datum) -> String {
+    match datum {
+        Datum::Null => "NULL".to_owned(),
+        Datum::True => "true".to_owned(),
+        Datum::False => "false".to_owned(),
+        Datum::String(_) | Datum::OwnedString(_) => format!("'{}'", datum.to_string().replace('\'', "''")),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn literals() {
+        assert_eq!(literal(&Datum::from_null()), "NULL");
+        assert_eq!(literal(&Datum::from_bool(true)), "true");
+        assert_eq!(literal(&Datum::from_i16(-1)), "-1");
+        assert_eq!(literal(&Datum::from_str("it's")), "'it''s'");
+    }
+
+    #[test]
+    fn type_names() {
+        assert_eq!(type_name(&SqlType::Bool), "boolean");
+        assert_eq!(type_name(&SqlType::VarChar(10)), "varchar(10)");
+        assert_eq!(type_name(&SqlType::SmallInt(i16::MIN)), "smallint");
+    }
+}
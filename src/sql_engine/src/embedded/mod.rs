@@ -0,0 +1,394 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! In-process access to the database. Applications embed the engine and run
+//! SQL through a `Connection`, results are returned directly instead of
+//! being encoded into messages of the wire protocol.
+use crate::{catalog_manager::CatalogManager, extension, is_ddl, script, QueryExecutor};
+use kernel::{SystemError, SystemResult};
+use protocol::{
+    results::{QueryEvent, QueryResult},
+    sql_formats::PostgreSqlFormat,
+    Sender,
+};
+use std::{
+    io,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+pub use crate::catalog_manager::{ChangeHook, ChangeKind, ForeignDataWrapper, RowChange};
+pub use crate::extension::{
+    BackgroundWorker, Extension, LoadedExtension, NativeAggregate, NativeFunction, PeriodicTask, Registry,
+    WorkerContext,
+};
+pub use crate::statistics::{fingerprint, normalize};
+pub use protocol::{
+    results::{ColumnDescription, QueryError},
+    sql_types::PostgreSqlType,
+};
+pub use record_batch::{Array, DataType, Field, RecordBatch};
+
+mod record_batch;
+
+/// Database that lives in the process of the application
+pub struct Database {
+    storage: Arc<CatalogManager>,
+}
+
+impl Database {
+    /// Database that is lost when it is dropped
+    pub fn in_memory() -> SystemResult<Database> {
+        CatalogManager::in_memory().map(|storage| Database {
+            storage: Arc::new(storage),
+        })
+    }
+
+    /// Database that is stored in `path` and loaded from it if it exists
+    pub fn persistent(path: PathBuf) -> SystemResult<Database> {
+        CatalogManager::persistent(path).map(|storage| Database {
+            storage: Arc::new(storage),
+        })
+    }
+
+    /// Database that shares `storage` with a server, e.g. to serve it over
+    /// another protocol
+    pub fn with_storage(storage: Arc<CatalogManager>) -> Database {
+        Database { storage }
+    }
+
+    /// Registers `hook` that is called with row changes that statements of
+    /// any connection make
+    pub fn on_changes(&self, hook: impl ChangeHook + 'static) {
+        self.storage.register_change_hook(Arc::new(hook));
+    }
+
+    /// Registers `wrapper` that supplies rows of foreign tables created with
+    /// `SERVER name`
+    pub fn register_foreign_data_wrapper(&self, name: &str, wrapper: impl ForeignDataWrapper + 'static) {
+        self.storage.register_foreign_data_wrapper(name, Arc::new(wrapper));
+    }
+
+    /// Loads `extension` with its functions, types and background workers,
+    /// workers run until the returned extension is stopped or dropped
+    pub fn load_extension(&self, extension: &dyn Extension) -> Result<LoadedExtension, String> {
+        extension::load(&self.storage, extension)
+    }
+
+    /// Opens connection on behalf of `user`, connections of the same
+    /// database see changes of each other
+    pub fn connect(&self, user: &str) -> Connection {
+        self.connect_with(user, |executor| executor)
+    }
+
+    /// Opens connection on behalf of `user` whose executor is set up by
+    /// `configure`, e.g. with settings of a server. Like clients that don't
+    /// name a database, the connection gets settings of the database named
+    /// after `user`.
+    pub fn connect_with(&self, user: &str, configure: impl FnOnce(QueryExecutor) -> QueryExecutor) -> Connection {
+        let results = Arc::new(Results(Mutex::new(vec![])));
+        Connection {
+            executor: configure(QueryExecutor::new(self.storage.clone(), results.clone(), user)).with_database(user),
+            results,
+        }
+    }
+}
+
+/// Session of the database, statements of a connection are executed one at
+/// a time with its own settings
+pub struct Connection {
+    executor: QueryExecutor,
+    results: Arc<Results>,
+}
+
+impl Connection {
+    /// Executes a single SQL statement. The outer error is a failure of the
+    /// database itself, the inner one is an error of the statement.
+    pub fn execute(&mut self, sql: &str) -> SystemResult<Result<Outcome, QueryError>> {
+        self.discard_on_failure(|executor| executor.execute(sql))?;
+        Ok(self.take_outcome())
+    }
+
+    /// Executes a single SQL statement with `$1`, `$2`, ... placeholders that
+    /// are substituted with `params` in text representation, `None` stands
+    /// for `NULL`. Values are never interpreted as SQL.
+    pub fn execute_with_params(
+        &mut self,
+        sql: &str,
+        params: &[Option<String>],
+    ) -> SystemResult<Result<Outcome, QueryError>> {
+        self.discard_on_failure(|executor| executor.parse_prepared_statement("", sql, &[]))?;
+        if let Err(error) = self.take_outcome() {
+            return Ok(Err(error));
+        }
+        let raw_params = params
+            .iter()
+            .map(|param| param.as_ref().map(|param| param.as_bytes().to_vec()))
+            .collect::<Vec<Option<Vec<u8>>>>();
+        self.discard_on_failure(|executor| {
+            executor.bind_prepared_statement_to_portal("", "", &[PostgreSqlFormat::Text], &raw_params, &[])
+        })?;
+        if let Err(error) = self.take_outcome() {
+            return Ok(Err(error));
+        }
+        self.discard_on_failure(|executor| executor.execute_portal("", 0))?;
+        Ok(self.take_outcome())
+    }
+
+    /// Executes semicolon separated statements of `script`, e.g. a
+    /// migration, one after another and reports `progress` after each of
+    /// them. Unless the connection is already in a transaction block, the
+    /// script runs in its own one that is committed at the end. Execution
+    /// stops at the first failing statement and the block is rolled back,
+    /// rows changed by statements before the failing one are reverted.
+    /// DDL can't run in a transaction block, so rows changed before it are
+    /// committed and it runs on its own before the block is started again.
+    pub fn execute_script(
+        &mut self,
+        script: &str,
+        mut progress: impl FnMut(&ScriptProgress),
+    ) -> SystemResult<Result<Vec<Outcome>, ScriptError>> {
+        let statements = script::split_statements(script);
+        let own_block = !self.executor.in_transaction_block();
+        if own_block {
+            self.script_block("begin;")?;
+        }
+        let mut outcomes = vec![];
+        for (index, statement) in statements.iter().enumerate() {
+            let started = Instant::now();
+            let ddl = own_block && is_ddl(statement);
+            if ddl {
+                self.script_block("commit;")?;
+            }
+            let executed = self.execute(statement)?;
+            if ddl {
+                self.script_block("begin;")?;
+            }
+            match executed {
+                Ok(outcome) => {
+                    progress(&ScriptProgress {
+                        index,
+                        total: statements.len(),
+                        statement,
+                        elapsed: started.elapsed(),
+                    });
+                    outcomes.push(outcome);
+                }
+                Err(error) => {
+                    if own_block {
+                        self.script_block("rollback;")?;
+                    }
+                    return Ok(Err(ScriptError {
+                        index,
+                        statement: (*statement).to_owned(),
+                        error,
+                    }));
+                }
+            }
+        }
+        if own_block {
+            self.script_block("commit;")?;
+        }
+        Ok(Ok(outcomes))
+    }
+
+    /// Starts or ends the transaction block of a script, `BEGIN`, `COMMIT`
+    /// and `ROLLBACK` don't fail with an error of the statement
+    fn script_block(&mut self, statement: &str) -> SystemResult<()> {
+        self.execute(statement)?
+            .map(|_| ())
+            .map_err(|error| SystemError::runtime_check_failure(error.to_string()))
+    }
+
+    /// Runs `step` of the executor and drops results it managed to send
+    /// before failing, so that they are not taken for the outcome of the
+    /// next statement
+    fn discard_on_failure(&mut self, step: impl FnOnce(&mut QueryExecutor) -> SystemResult<()>) -> SystemResult<()> {
+        let result = step(&mut self.executor);
+        if result.is_err() {
+            self.results.0.lock().expect("to acquire results lock").clear();
+        }
+        result
+    }
+
+    fn take_outcome(&self) -> Result<Outcome, QueryError> {
+        let results = std::mem::take(&mut *self.results.0.lock().expect("to acquire results lock"));
+        Outcome::collect(results)
+    }
+
+    /// Executes a query, statements that do not return rows produce an
+    /// empty result set
+    pub fn query(&mut self, sql: &str) -> SystemResult<Result<ResultSet, QueryError>> {
+        self.execute(sql).map(|outcome| {
+            outcome.map(|outcome| match outcome {
+                Outcome::Rows(result_set) => result_set,
+                Outcome::Affected(_) | Outcome::Done => ResultSet::default(),
+            })
+        })
+    }
+}
+
+/// Statement of a script that was executed successfully
+#[derive(Debug)]
+pub struct ScriptProgress<'s> {
+    /// position of the statement in the script starting from zero
+    pub index: usize,
+    /// number of statements in the script
+    pub total: usize,
+    /// text of the statement without the terminating semicolon
+    pub statement: &'s str,
+    /// time the statement took
+    pub elapsed: Duration,
+}
+
+/// Statement of a script that failed, statements after it are not executed
+#[derive(Debug, PartialEq)]
+pub struct ScriptError {
+    /// position of the statement in the script starting from zero
+    pub index: usize,
+    /// text of the statement without the terminating semicolon
+    pub statement: String,
+    /// error of the statement
+    pub error: QueryError,
+}
+
+struct Results(Mutex<Vec<QueryResult>>);
+
+impl Sender for Results {
+    fn flush(&self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn send(&self, query_result: QueryResult) -> io::Result<()> {
+        self.0.lock().expect("to acquire results lock").push(query_result);
+        Ok(())
+    }
+}
+
+/// Result of a successfully executed statement
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    /// rows returned by a query
+    Rows(ResultSet),
+    /// number of rows inserted, updated, deleted or copied
+    Affected(usize),
+    /// statement that neither returns nor changes rows, e.g. `CREATE TABLE`
+    Done,
+}
+
+impl Outcome {
+    fn collect(results: Vec<QueryResult>) -> Result<Outcome, QueryError> {
+        let mut outcome = Outcome::Done;
+        for result in results {
+            match result? {
+                QueryEvent::RowDescription(description) => {
+                    outcome = Outcome::Rows(ResultSet::new(description, vec![]));
+                }
+                QueryEvent::DataRows(rows) => {
+                    if let Outcome::Rows(result_set) = &mut outcome {
+                        result_set.push(rows);
+                    }
+                }
+                QueryEvent::RecordsSelected((description, rows)) => {
+                    outcome = Outcome::Rows(ResultSet::new(description, rows));
+                }
+                QueryEvent::RecordsInserted(affected)
+                | QueryEvent::RecordsUpdated(affected)
+                | QueryEvent::RecordsDeleted(affected)
+                | QueryEvent::RecordsCopied(affected) => outcome = Outcome::Affected(affected),
+                _ => {}
+            }
+        }
+        Ok(outcome)
+    }
+}
+
+/// Rows returned by a query
+#[derive(Debug, Default, PartialEq)]
+pub struct ResultSet {
+    columns: Vec<ColumnDescription>,
+    rows: Vec<Vec<Value>>,
+}
+
+impl ResultSet {
+    fn new(columns: Vec<ColumnDescription>, rows: Vec<Vec<Option<String>>>) -> ResultSet {
+        let mut result_set = ResultSet { columns, rows: vec![] };
+        result_set.push(rows);
+        result_set
+    }
+
+    fn push(&mut self, rows: Vec<Vec<Option<String>>>) {
+        for row in rows {
+            let values = row
+                .into_iter()
+                .zip(self.columns.iter())
+                .map(|(text, column)| Value::parse(text, &column.pg_type))
+                .collect();
+            self.rows.push(values);
+        }
+    }
+
+    /// Names, types and origins of columns
+    pub fn columns(&self) -> &[ColumnDescription] {
+        &self.columns
+    }
+
+    /// Rows with values in the order of columns
+    pub fn rows(&self) -> &[Vec<Value>] {
+        &self.rows
+    }
+
+    /// Columns of the result set in the memory layout of Apache Arrow
+    pub fn to_record_batch(&self) -> RecordBatch {
+        RecordBatch::from(self)
+    }
+}
+
+/// Value of a column
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    SmallInt(i16),
+    Integer(i32),
+    BigInt(i64),
+    Real(f32),
+    DoublePrecision(f64),
+    /// character types and types that do not have a native representation
+    Text(String),
+}
+
+impl Value {
+    /// Rows are produced as text, `None` stands for an absent value of any type
+    fn parse(text: Option<String>, sql_type: &PostgreSqlType) -> Value {
+        let text = match text {
+            Some(text) => text,
+            None => return Value::Null,
+        };
+        let parsed = match sql_type {
+            PostgreSqlType::Bool => match text.as_str() {
+                "t" => Some(Value::Bool(true)),
+                "f" => Some(Value::Bool(false)),
+                _ => None,
+            },
+            PostgreSqlType::SmallInt => text.parse().ok().map(Value::SmallInt),
+            PostgreSqlType::Integer => text.parse().ok().map(Value::Integer),
+            PostgreSqlType::BigInt => text.parse().ok().map(Value::BigInt),
+            PostgreSqlType::Real => text.parse().ok().map(Value::Real),
+            PostgreSqlType::DoublePrecision => text.parse().ok().map(Value::DoublePrecision),
+            _ => None,
+        };
+        parsed.unwrap_or(Value::Text(text))
+    }
+}
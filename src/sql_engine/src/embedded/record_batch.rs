@@ -0,0 +1,307 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! Result sets in the columnar memory layout of Apache Arrow. Buffers of an
+//! array are laid out as the Arrow specification requires, so consumers can
+//! hand them over to Arrow arrays without converting values one by one.
+use crate::embedded::{PostgreSqlType, ResultSet, Value};
+
+/// Logical type of an array, names follow Arrow data types
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DataType {
+    Boolean,
+    Int16,
+    Int32,
+    Int64,
+    Float32,
+    Float64,
+    /// character types and types that do not have a native representation
+    Utf8,
+}
+
+impl From<&PostgreSqlType> for DataType {
+    fn from(sql_type: &PostgreSqlType) -> DataType {
+        match sql_type {
+            PostgreSqlType::Bool => DataType::Boolean,
+            PostgreSqlType::SmallInt => DataType::Int16,
+            PostgreSqlType::Integer => DataType::Int32,
+            PostgreSqlType::BigInt => DataType::Int64,
+            PostgreSqlType::Real => DataType::Float32,
+            PostgreSqlType::DoublePrecision => DataType::Float64,
+            _ => DataType::Utf8,
+        }
+    }
+}
+
+/// Name, type and nullability of a column
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    name: String,
+    data_type: DataType,
+    nullable: bool,
+}
+
+impl Field {
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    pub fn data_type(&self) -> DataType {
+        self.data_type
+    }
+
+    pub fn is_nullable(&self) -> bool {
+        self.nullable
+    }
+}
+
+/// Values of a column. The validity bitmap has a bit per value, the least
+/// significant bit first, unset bits mark nulls. Booleans are a bitmap as
+/// well, numbers are little endian, `Utf8` has a buffer of `i32` offsets
+/// followed by a buffer of concatenated strings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Array {
+    data_type: DataType,
+    len: usize,
+    null_count: usize,
+    validity: Vec<u8>,
+    buffers: Vec<Vec<u8>>,
+}
+
+impl Array {
+    fn new(data_type: DataType, values: &[&Value]) -> Array {
+        let len = values.len();
+        let mut validity = vec![0; bitmap_len(len)];
+        let mut null_count = 0;
+        let mut data = vec![];
+        let mut offsets = vec![];
+        if data_type == DataType::Boolean {
+            data = vec![0; bitmap_len(len)];
+        }
+        if data_type == DataType::Utf8 {
+            offsets.extend_from_slice(&0i32.to_le_bytes());
+        }
+        for (index, value) in values.iter().enumerate() {
+            let valid = match (data_type, value) {
+                (DataType::Boolean, Value::Bool(value)) => {
+                    if *value {
+                        set_bit(&mut data, index);
+                    }
+                    true
+                }
+                (DataType::Int16, Value::SmallInt(value)) => {
+                    data.extend_from_slice(&value.to_le_bytes());
+                    true
+                }
+                (DataType::Int32, Value::Integer(value)) => {
+                    data.extend_from_slice(&value.to_le_bytes());
+                    true
+                }
+                (DataType::Int64, Value::BigInt(value)) => {
+                    data.extend_from_slice(&value.to_le_bytes());
+                    true
+                }
+                (DataType::Float32, Value::Real(value)) => {
+                    data.extend_from_slice(&value.to_le_bytes());
+                    true
+                }
+                (DataType::Float64, Value::DoublePrecision(value)) => {
+                    data.extend_from_slice(&value.to_le_bytes());
+                    true
+                }
+                (DataType::Utf8, Value::Text(value)) => {
+                    data.extend_from_slice(value.as_bytes());
+                    true
+                }
+                _ => false,
+            };
+            if valid {
+                set_bit(&mut validity, index);
+            } else {
+                null_count += 1;
+                // slots of nulls still take space in fixed width buffers
+                data.extend(std::iter::repeat_n(0, data_type.width()));
+            }
+            if data_type == DataType::Utf8 {
+                offsets.extend_from_slice(&(data.len() as i32).to_le_bytes());
+            }
+        }
+        let buffers = if data_type == DataType::Utf8 {
+            vec![offsets, data]
+        } else {
+            vec![data]
+        };
+        Array {
+            data_type,
+            len,
+            null_count,
+            validity,
+            buffers,
+        }
+    }
+
+    pub fn data_type(&self) -> DataType {
+        self.data_type
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn null_count(&self) -> usize {
+        self.null_count
+    }
+
+    pub fn is_null(&self, index: usize) -> bool {
+        self.validity[index / 8] & (1 << (index % 8)) == 0
+    }
+
+    pub fn validity(&self) -> &[u8] {
+        &self.validity
+    }
+
+    pub fn buffers(&self) -> &[Vec<u8>] {
+        &self.buffers
+    }
+}
+
+impl DataType {
+    /// Bytes that a value takes in a fixed width buffer, bitmaps and
+    /// variable width values take none
+    fn width(self) -> usize {
+        match self {
+            DataType::Boolean | DataType::Utf8 => 0,
+            DataType::Int16 => 2,
+            DataType::Int32 | DataType::Float32 => 4,
+            DataType::Int64 | DataType::Float64 => 8,
+        }
+    }
+}
+
+fn bitmap_len(len: usize) -> usize {
+    len.div_ceil(8)
+}
+
+fn set_bit(bitmap: &mut [u8], index: usize) {
+    bitmap[index / 8] |= 1 << (index % 8);
+}
+
+/// Equal length columns of a result set
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordBatch {
+    schema: Vec<Field>,
+    columns: Vec<Array>,
+    num_rows: usize,
+}
+
+impl RecordBatch {
+    pub fn schema(&self) -> &[Field] {
+        &self.schema
+    }
+
+    pub fn columns(&self) -> &[Array] {
+        &self.columns
+    }
+
+    pub fn column(&self, index: usize) -> &Array {
+        &self.columns[index]
+    }
+
+    pub fn num_rows(&self) -> usize {
+        self.num_rows
+    }
+}
+
+impl From<&ResultSet> for RecordBatch {
+    fn from(result_set: &ResultSet) -> RecordBatch {
+        let schema = result_set
+            .columns()
+            .iter()
+            .map(|column| Field {
+                name: column.name.clone(),
+                data_type: (&column.pg_type).into(),
+                nullable: column.nullable,
+            })
+            .collect::<Vec<Field>>();
+        let columns = schema
+            .iter()
+            .enumerate()
+            .map(|(index, field)| {
+                let values = result_set.rows().iter().map(|row| &row[index]).collect::<Vec<&Value>>();
+                Array::new(field.data_type, &values)
+            })
+            .collect();
+        RecordBatch {
+            schema,
+            columns,
+            num_rows: result_set.rows().len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn booleans_are_bitmap() {
+        let array = Array::new(
+            DataType::Boolean,
+            &[
+                &Value::Bool(true),
+                &Value::Null,
+                &Value::Bool(false),
+                &Value::Bool(true),
+            ],
+        );
+
+        assert_eq!(array.len(), 4);
+        assert_eq!(array.null_count(), 1);
+        assert_eq!(array.validity(), &[0b1101]);
+        assert_eq!(array.buffers(), &[vec![0b1001]]);
+        assert!(array.is_null(1));
+    }
+
+    #[test]
+    fn nulls_take_slots_of_fixed_width_values() {
+        let array = Array::new(
+            DataType::Int16,
+            &[&Value::SmallInt(1), &Value::Null, &Value::SmallInt(-1)],
+        );
+
+        assert_eq!(array.validity(), &[0b101]);
+        assert_eq!(array.buffers(), &[vec![1, 0, 0, 0, 0xff, 0xff]]);
+    }
+
+    #[test]
+    fn strings_have_offsets() {
+        let array = Array::new(
+            DataType::Utf8,
+            &[
+                &Value::Text("ab".to_owned()),
+                &Value::Null,
+                &Value::Text("c".to_owned()),
+            ],
+        );
+
+        assert_eq!(array.validity(), &[0b101]);
+        assert_eq!(
+            array.buffers(),
+            &[vec![0, 0, 0, 0, 2, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0], b"abc".to_vec()]
+        );
+    }
+}
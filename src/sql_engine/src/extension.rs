@@ -0,0 +1,393 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Native extensions that embedders load into the engine at startup, before
+//! sessions are served. An extension registers functions that are called
+//! from SQL, aggregates that queries group rows with, names of types that
+//! columns are declared with and background workers that run next to
+//! sessions, once or periodically:
+//!
+//! ```ignore
+//! struct Greetings;
+//!
+//! impl Extension for Greetings {
+//!     fn name(&self) -> &str {
+//!         "greetings"
+//!     }
+//!
+//!     fn register(&self, registry: &mut Registry) {
+//!         registry.function("greet", &[PostgreSqlType::VarChar], PostgreSqlType::VarChar, |args: &[Option<String>]| {
+//!             Ok(args[0].as_ref().map(|name| format!("Hello, {}!", name)))
+//!         });
+//!         registry.register_type("email", PostgreSqlType::VarChar);
+//!     }
+//! }
+//!
+//! let loaded = database.load_extension(&Greetings)?;
+//! ```
+use crate::{
+    background::Workers,
+    catalog_manager::{CatalogManager, SUPERUSER},
+    procedural::{Aggregate, Body, Function, Returns, Value, Variable},
+};
+use protocol::{results::QueryError, sql_types::PostgreSqlType};
+use std::{
+    fmt::{self, Debug, Formatter},
+    sync::Arc,
+    time::Duration,
+};
+
+pub use crate::background::{BackgroundWorker, PeriodicTask, WorkerContext};
+
+/// Set of functions, types and background workers that is loaded as a whole
+pub trait Extension {
+    /// Unique name of the extension
+    fn name(&self) -> &str;
+
+    /// Adds everything the extension provides to `registry`
+    fn register(&self, registry: &mut Registry);
+}
+
+/// Function that is implemented in Rust and called from SQL like functions
+/// created with `CREATE FUNCTION`
+pub trait NativeFunction: Send + Sync {
+    /// Arguments and the result are in text representation, `None` stands
+    /// for `NULL`. An error is reported to the client that called the
+    /// function.
+    fn call(&self, args: &[Option<String>]) -> Result<Option<String>, String>;
+}
+
+impl<F> NativeFunction for F
+where
+    F: Fn(&[Option<String>]) -> Result<Option<String>, String> + Send + Sync,
+{
+    fn call(&self, args: &[Option<String>]) -> Result<Option<String>, String> {
+        self(args)
+    }
+}
+
+/// Aggregate of an extension, non `NULL` values of a group are folded into
+/// a state of `state` type with a transition function that is called with
+/// the state and a value
+pub struct NativeAggregate {
+    input: PostgreSqlType,
+    state: PostgreSqlType,
+    initial: Option<String>,
+    transition: Arc<dyn NativeFunction>,
+    finish: Option<(PostgreSqlType, Arc<dyn NativeFunction>)>,
+}
+
+impl NativeAggregate {
+    /// Aggregate of `input` values whose result is the final state. Without
+    /// an initial state the first value of a group becomes the state.
+    pub fn new(
+        input: PostgreSqlType,
+        state: PostgreSqlType,
+        transition: impl NativeFunction + 'static,
+    ) -> NativeAggregate {
+        NativeAggregate {
+            input,
+            state,
+            initial: None,
+            transition: Arc::new(transition),
+            finish: None,
+        }
+    }
+
+    /// State of a group before any value is accumulated, in text
+    /// representation
+    pub fn initial(mut self, initial: &str) -> NativeAggregate {
+        self.initial = Some(initial.to_owned());
+        self
+    }
+
+    /// Computes the result of `returns` type from the final state
+    pub fn finish(mut self, returns: PostgreSqlType, finish: impl NativeFunction + 'static) -> NativeAggregate {
+        self.finish = Some((returns, Arc::new(finish)));
+        self
+    }
+}
+
+struct FunctionDefinition {
+    name: String,
+    params: Vec<PostgreSqlType>,
+    returns: PostgreSqlType,
+    function: Arc<dyn NativeFunction>,
+}
+
+/// What an extension provides, it is filled by `Extension::register`
+#[derive(Default)]
+pub struct Registry {
+    functions: Vec<FunctionDefinition>,
+    aggregates: Vec<(String, NativeAggregate)>,
+    types: Vec<(String, PostgreSqlType)>,
+    workers: Vec<(String, Box<dyn BackgroundWorker>)>,
+    tasks: Vec<(String, Duration, Box<dyn PeriodicTask>)>,
+}
+
+impl Registry {
+    /// Registers function `name` that accepts arguments of `params` types
+    /// and returns a value of `returns` type
+    pub fn function(
+        &mut self,
+        name: &str,
+        params: &[PostgreSqlType],
+        returns: PostgreSqlType,
+        function: impl NativeFunction + 'static,
+    ) -> &mut Registry {
+        self.functions.push(FunctionDefinition {
+            name: name.to_lowercase(),
+            params: params.to_vec(),
+            returns,
+            function: Arc::new(function),
+        });
+        self
+    }
+
+    /// Registers aggregate `name` that is called with a column in the select
+    /// list of a query
+    pub fn aggregate(&mut self, name: &str, aggregate: NativeAggregate) -> &mut Registry {
+        self.aggregates.push((name.to_lowercase(), aggregate));
+        self
+    }
+
+    /// Registers type `name` whose values are stored as values of the
+    /// built-in `base` type, e.g. in columns of `CREATE TABLE`
+    pub fn register_type(&mut self, name: &str, base: PostgreSqlType) -> &mut Registry {
+        self.types.push((name.to_lowercase(), base));
+        self
+    }
+
+    /// Registers `worker` that is started in a thread named after the
+    /// extension and `name` once the extension is loaded
+    pub fn background_worker(&mut self, name: &str, worker: impl BackgroundWorker + 'static) -> &mut Registry {
+        self.workers.push((name.to_owned(), Box::new(worker)));
+        self
+    }
+
+    /// Registers `task` that runs each `interval` in a thread named after
+    /// the extension and `name` while the extension is loaded
+    pub fn periodic_task(
+        &mut self,
+        name: &str,
+        interval: Duration,
+        task: impl PeriodicTask + 'static,
+    ) -> &mut Registry {
+        self.tasks.push((name.to_owned(), interval, Box::new(task)));
+        self
+    }
+}
+
+/// Extension whose background workers are running. Workers are asked to
+/// stop when it is dropped without waiting for them.
+pub struct LoadedExtension {
+    name: String,
+    workers: Workers,
+}
+
+impl LoadedExtension {
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    /// Background workers of the extension with their current state
+    pub fn workers(&self) -> &Workers {
+        &self.workers
+    }
+
+    /// Asks background workers to stop and waits until they return
+    pub fn stop(self) {
+        self.workers.shutdown(None);
+    }
+}
+
+/// Loads `extension` into `storage`, nothing is registered if any of its
+/// functions or types conflicts with existing ones
+pub fn load(storage: &Arc<CatalogManager>, extension: &dyn Extension) -> Result<LoadedExtension, String> {
+    let name = extension.name().to_owned();
+    if storage.extensions().is_loaded(&name) {
+        return Err(format!("extension \"{}\" is already loaded", name));
+    }
+    let mut registry = Registry::default();
+    extension.register(&mut registry);
+    let names = registry
+        .functions
+        .iter()
+        .map(|definition| &definition.name)
+        .chain(registry.aggregates.iter().map(|(name, _aggregate)| name));
+    for function_name in names {
+        if storage.functions().get(function_name).is_some() || storage.aggregates().get(function_name).is_some() {
+            return Err(format!("function \"{}\" already exists", function_name));
+        }
+    }
+    for (type_name, base) in &registry.types {
+        if is_built_in(type_name) || storage.extensions().base_type(type_name).is_some() {
+            return Err(format!("type \"{}\" already exists", type_name));
+        }
+        if !is_column_type(*base) {
+            return Err(format!("type \"{}\" can't be based on type {}", type_name, base));
+        }
+    }
+
+    for definition in registry.functions {
+        let function = Function {
+            name: definition.name,
+            params: definition
+                .params
+                .into_iter()
+                .enumerate()
+                .map(|(index, pg_type)| Variable {
+                    name: format!("${}", index + 1),
+                    pg_type,
+                })
+                .collect(),
+            returns: Returns::Type(definition.returns),
+            body: Body::Native(NativeBody {
+                extension: name.clone(),
+                function: definition.function,
+            }),
+        };
+        storage
+            .functions()
+            .create(function, SUPERUSER, false)
+            .map_err(|error| error.to_string())?;
+    }
+    for (aggregate_name, aggregate) in registry.aggregates {
+        let native = |function_name: String, params: Vec<PostgreSqlType>, returns, function| {
+            Arc::new(Function {
+                name: function_name,
+                params: params
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, pg_type)| Variable {
+                        name: format!("${}", index + 1),
+                        pg_type,
+                    })
+                    .collect(),
+                returns: Returns::Type(returns),
+                body: Body::Native(NativeBody {
+                    extension: name.clone(),
+                    function,
+                }),
+            })
+        };
+        let NativeAggregate {
+            input,
+            state,
+            initial,
+            transition,
+            finish,
+        } = aggregate;
+        let transition = native(
+            format!("{}_transition", aggregate_name),
+            vec![state, input],
+            state,
+            transition,
+        );
+        let finish =
+            finish.map(|(returns, finish)| native(format!("{}_finish", aggregate_name), vec![state], returns, finish));
+        let aggregate = Aggregate {
+            name: aggregate_name,
+            input,
+            state,
+            initial,
+            transition,
+            finish,
+        };
+        storage
+            .aggregates()
+            .create(aggregate, SUPERUSER, false)
+            .map_err(|error| error.to_string())?;
+    }
+    storage.extensions().load(&name, registry.types);
+
+    let workers = Workers::new(&name, storage.clone());
+    for (worker_name, worker) in registry.workers {
+        if let Err(error) = workers.spawn(&worker_name, worker) {
+            log::error!("extension {}: {}", name, error);
+        }
+    }
+    for (task_name, interval, task) in registry.tasks {
+        if let Err(error) = workers.every(&task_name, interval, task) {
+            log::error!("extension {}: {}", name, error);
+        }
+    }
+    Ok(LoadedExtension { name, workers })
+}
+
+fn is_built_in(type_name: &str) -> bool {
+    matches!(
+        type_name,
+        "smallint"
+            | "int"
+            | "integer"
+            | "bigint"
+            | "char"
+            | "varchar"
+            | "boolean"
+            | "serial"
+            | "smallserial"
+            | "bigserial"
+    )
+}
+
+fn is_column_type(pg_type: PostgreSqlType) -> bool {
+    matches!(
+        pg_type,
+        PostgreSqlType::Bool
+            | PostgreSqlType::Char
+            | PostgreSqlType::VarChar
+            | PostgreSqlType::SmallInt
+            | PostgreSqlType::Integer
+            | PostgreSqlType::BigInt
+    )
+}
+
+/// Body of a function registered by an extension
+#[derive(Clone)]
+pub(crate) struct NativeBody {
+    extension: String,
+    function: Arc<dyn NativeFunction>,
+}
+
+impl NativeBody {
+    pub(crate) fn call(&self, args: Vec<Value>) -> Result<Value, QueryError> {
+        let args = args
+            .into_iter()
+            .map(|arg| match arg {
+                Value::Null => None,
+                other => Some(other.to_text()),
+            })
+            .collect::<Vec<Option<String>>>();
+        match self.function.call(&args) {
+            Ok(Some(result)) => Ok(Value::Text(result)),
+            Ok(None) => Ok(Value::Null),
+            Err(message) => Err(QueryError::external_routine_exception(message)),
+        }
+    }
+}
+
+impl Debug for NativeBody {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NativeBody")
+            .field("extension", &self.extension)
+            .finish()
+    }
+}
+
+impl PartialEq for NativeBody {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.function, &other.function)
+    }
+}
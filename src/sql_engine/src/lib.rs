@@ -16,19 +16,58 @@ extern crate bigdecimal;
 extern crate log;
 
 use crate::{
-    catalog_manager::CatalogManager,
+    audit::{classify_extended, classify_plan, Auditor, StatementClass},
+    cancellation::CancellationToken,
+    catalog_manager::{CatalogManager, DdlEvent, LockMode, SUPERUSER},
+    dcl::{
+        alter_default_privileges::AlterDefaultPrivilegesCommand, alter_role::AlterRoleCommand,
+        alter_settings::AlterSettingsCommand, grant::GrantCommand, revoke::RevokeCommand,
+    },
     ddl::{
-        create_schema::CreateSchemaCommand, create_table::CreateTableCommand, drop_schema::DropSchemaCommand,
-        drop_table::DropTableCommand,
+        alter_table::AlterTableCommand, cluster_table::ClusterTableCommand, create_aggregate::CreateAggregateCommand,
+        create_event_trigger::CreateEventTriggerCommand, create_foreign_table::CreateForeignTableCommand,
+        create_function::CreateFunctionCommand, create_publication::CreatePublicationCommand,
+        create_replication_slot::CreateReplicationSlotCommand, create_schema::CreateSchemaCommand,
+        create_table::CreateTableCommand, create_wasm_function::CreateWasmFunctionCommand,
+        drop_event_trigger::DropEventTriggerCommand, drop_function::DropFunctionCommand,
+        drop_publication::DropPublicationCommand, drop_replication_slot::DropReplicationSlotCommand,
+        drop_schema::DropSchemaCommand, drop_table::DropTableCommand,
+    },
+    dml::{
+        copy::CopyCommand, cte::DEFAULT_MAX_RECURSIVE_ITERATIONS, delete::DeleteCommand, functions::ScalarFunctions,
+        insert::InsertCommand, limit::Portion, memory::DEFAULT_WORK_MEM, sample::samples_randomly,
+        select::SelectCommand, update::UpdateCommand,
+    },
+    procedural::{Block, SessionExecutor},
+    query::{
+        authorize::{collect_query_tables, Authorizer},
+        bind::{with_sample_params, ParamBinder},
+        explain::{ExplainAnalyze, Instrumentation},
+        extended::{self, CopyDirection, ExplainStatement, ExtendedStatement, RoleOption},
+        param_types::infer_param_types,
+        plan::Plan,
+        prepare::parse_prepared,
+        process::QueryProcessor,
+        resolve::Scope,
+        table_sample, TableId,
     },
-    dml::{delete::DeleteCommand, insert::InsertCommand, select::SelectCommand, update::UpdateCommand},
-    query::{bind::ParamBinder, plan::Plan, process::QueryProcessor},
-    session::{statement::PreparedStatement, Session},
+    result_cache::{ResultCache, ResultRecorder},
+    server_settings::{is_reload_call, ServerSettings, RELOAD_FUNCTION},
+    session::{
+        settings::{
+            self, SettingValue, AUTO_EXPLAIN_LOG_MIN_DURATION, LOG_MIN_DURATION_STATEMENT, MAX_PARALLEL_WORKERS,
+            MAX_RECURSIVE_ITERATIONS, STATEMENT_TIMEOUT, WORK_MEM,
+        },
+        statement::{Portal, PreparedStatement},
+        Session,
+    },
+    statistics::{tables_view, RowCounter, StatementStatistics, StatisticsQuery, RESET_FUNCTION},
+    transaction::{Status, Transaction},
 };
 use itertools::izip;
-use kernel::SystemResult;
+use kernel::{SystemError, SystemErrorKind, SystemResult};
 use protocol::{
-    results::{QueryError, QueryEvent},
+    results::{ColumnDescription, Description, QueryError, QueryEvent},
     sql_formats::PostgreSqlFormat,
     sql_types::PostgreSqlType,
     sql_values::PostgreSqlValue,
@@ -37,24 +76,46 @@ use protocol::{
 use serde::{Deserialize, Serialize};
 use sql_types::SqlType;
 use sqlparser::{
-    ast::Statement,
-    dialect::{Dialect, PostgreSqlDialect},
-    parser::Parser,
+    ast::{Query, SetVariableValue, Statement},
+    dialect::PostgreSqlDialect,
+};
+use std::{
+    convert::TryFrom,
+    iter, mem,
+    sync::Arc,
+    time::{Duration, Instant},
 };
-use std::{iter, sync::Arc};
 
+pub mod audit;
+pub mod autovacuum;
+pub mod background;
+pub mod cancellation;
 pub mod catalog_manager;
+pub mod connections;
+mod dcl;
 mod ddl;
 mod dml;
+pub mod dump;
+pub mod embedded;
+pub mod extension;
+mod procedural;
 mod query;
+pub mod result_cache;
+pub mod script;
+pub mod server_settings;
 mod session;
+pub mod statistics;
+mod transaction;
 
-pub type Projection = (Vec<ColumnDefinition>, Vec<Vec<String>>);
+pub type Projection = (Vec<ColumnDefinition>, Vec<Vec<Option<String>>>);
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct ColumnDefinition {
     name: String,
     sql_type: SqlType,
+    /// text of the value that is inserted when a row does not specify one
+    default: Option<String>,
+    not_null: bool,
 }
 
 impl ColumnDefinition {
@@ -62,9 +123,29 @@ impl ColumnDefinition {
         Self {
             name: name.to_string(),
             sql_type,
+            default: None,
+            not_null: false,
         }
     }
 
+    pub fn with_default(mut self, default: Option<String>) -> Self {
+        self.default = default;
+        self
+    }
+
+    pub fn with_not_null(mut self, not_null: bool) -> Self {
+        self.not_null = not_null;
+        self
+    }
+
+    pub fn default(&self) -> Option<&str> {
+        self.default.as_deref()
+    }
+
+    pub fn is_not_null(&self) -> bool {
+        self.not_null
+    }
+
     pub fn sql_type(&self) -> SqlType {
         self.sql_type
     }
@@ -84,25 +165,209 @@ pub struct QueryExecutor {
     session: Session,
     processor: QueryProcessor,
     param_binder: ParamBinder,
+    authorizer: Authorizer,
+    auditor: Option<Arc<Auditor>>,
+    session_id: u64,
+    statement_id: u64,
+    default_log_min_duration_statement: Option<Duration>,
+    default_auto_explain_log_min_duration: Option<Duration>,
+    default_statement_timeout: Option<Duration>,
+    default_max_parallel_workers: usize,
+    default_work_mem: usize,
+    row_counter: Arc<RowCounter>,
+    statistics: Option<Arc<StatementStatistics>>,
+    result_cache: Option<Arc<ResultCache>>,
+    server_settings: Option<Arc<ServerSettings>>,
+    cancellation: CancellationToken,
+    transaction: Arc<Transaction>,
 }
 
 impl QueryExecutor {
-    pub fn new(storage: Arc<CatalogManager>, sender: Arc<dyn Sender>) -> Self {
+    pub fn new(storage: Arc<CatalogManager>, sender: Arc<dyn Sender>, session_user: &str) -> Self {
+        let row_counter = Arc::new(RowCounter::new(sender));
+        let sender: Arc<dyn Sender> = row_counter.clone();
+        let transaction = Arc::new(Transaction::new(storage.locks().clone()));
         Self {
             storage: storage.clone(),
             sender: sender.clone(),
-            session: Session::new(),
-            processor: QueryProcessor::new(storage, sender.clone()),
-            param_binder: ParamBinder::new(sender),
+            session: Session::new(session_user),
+            processor: QueryProcessor::new(storage.clone(), sender.clone()),
+            param_binder: ParamBinder::new(sender.clone()),
+            authorizer: Authorizer::new(storage, sender),
+            auditor: None,
+            session_id: 0,
+            statement_id: 0,
+            default_log_min_duration_statement: None,
+            default_auto_explain_log_min_duration: None,
+            default_statement_timeout: None,
+            default_max_parallel_workers: 0,
+            default_work_mem: DEFAULT_WORK_MEM,
+            row_counter,
+            statistics: None,
+            result_cache: None,
+            server_settings: None,
+            cancellation: CancellationToken::new(),
+            transaction,
+        }
+    }
+
+    /// Token that interrupts the running statement of the session when it
+    /// is cancelled, e.g. by a query cancel request or server shutdown
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.cancellation.clone()
+    }
+
+    /// Statements that run longer than `timeout` are cancelled. Sessions
+    /// can change it with `SET statement_timeout`.
+    pub fn with_statement_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.default_statement_timeout = timeout;
+        self.session.set_statement_timeout(timeout);
+        self
+    }
+
+    /// Successfully executed statements are accumulated into `statistics`
+    /// that can be queried from `pg_stat_statements` view
+    pub fn with_statement_statistics(mut self, statistics: Arc<StatementStatistics>) -> Self {
+        self.statistics = Some(statistics);
+        self
+    }
+
+    /// Results of queries are served from `cache` until tables they read
+    /// from are modified
+    pub fn with_result_cache(mut self, cache: Arc<ResultCache>) -> Self {
+        self.result_cache = Some(cache);
+        self
+    }
+
+    /// Plans of statements that run longer than `duration` are logged in
+    /// `EXPLAIN ANALYZE` format. Sessions can change it with
+    /// `SET "auto_explain.log_min_duration"`.
+    pub fn with_auto_explain_log_min_duration(mut self, duration: Option<Duration>) -> Self {
+        self.default_auto_explain_log_min_duration = duration;
+        self.session.set_auto_explain_log_min_duration(duration);
+        self
+    }
+
+    /// Statements that run longer than `duration` are logged together with
+    /// summary of their plan. Sessions can change it with
+    /// `SET log_min_duration_statement`.
+    pub fn with_log_min_duration_statement(mut self, duration: Option<Duration>) -> Self {
+        self.default_log_min_duration_statement = duration;
+        self.session.set_log_min_duration_statement(duration);
+        self
+    }
+
+    /// Queries can use up to `workers` threads besides the session one to
+    /// process rows. Sessions can change it with `SET max_parallel_workers`.
+    pub fn with_max_parallel_workers(mut self, workers: usize) -> Self {
+        self.default_max_parallel_workers = workers;
+        self.session.set_max_parallel_workers(workers);
+        self
+    }
+
+    /// Each query operator can hold up to `kilobytes` of intermediate
+    /// results. Sessions can change it with `SET work_mem`.
+    pub fn with_work_mem(mut self, kilobytes: usize) -> Self {
+        self.default_work_mem = kilobytes;
+        self.session.set_work_mem(kilobytes);
+        self
+    }
+
+    /// Slow statement thresholds follow `settings` of the server when they
+    /// are reloaded, unless the session changed them with `SET`. Superusers
+    /// can reload the settings with `SELECT pg_reload_conf()`.
+    pub fn with_server_settings(mut self, settings: Arc<ServerSettings>) -> Self {
+        self.server_settings = Some(settings);
+        self.adopt_server_settings();
+        self
+    }
+
+    /// Identifies the session in audit records and tracing spans
+    pub fn with_session_id(mut self, session_id: u64) -> Self {
+        self.session_id = session_id;
+        self
+    }
+
+    /// Statements executed in the session are recorded by `auditor`
+    pub fn with_audit(mut self, auditor: Arc<Auditor>) -> Self {
+        self.auditor = Some(auditor);
+        self
+    }
+
+    /// The session starts with settings of `ALTER DATABASE database SET`
+    /// and then of `ALTER ROLE role SET` of its user as if it changed them
+    /// with `SET`, so it has to be configured with defaults of the server
+    /// before
+    pub fn with_database(mut self, database: &str) -> Self {
+        let settings = self
+            .storage
+            .default_settings()
+            .of_session(database, self.session.session_user());
+        for (name, value) in settings {
+            if let Err(error) = self.apply_setting(&name, &value) {
+                log::warn!(
+                    "setting {:?} of session {} is ignored: {}",
+                    name,
+                    self.session_id,
+                    error
+                );
+            }
         }
+        self
     }
 
     #[allow(clippy::match_wild_err_arm)]
     pub fn execute(&mut self, raw_sql_query: &str) -> SystemResult<()> {
-        let statement = match Parser::parse_sql(&PostgreSqlDialect {}, raw_sql_query) {
+        let span = self.statement_span();
+        let _entered = span.enter();
+        let started = self.start_statement();
+        let parsed = tracing::info_span!("parse").in_scope(|| extended::parse(&PostgreSqlDialect {}, raw_sql_query));
+        match parsed {
+            Some(Ok(statement)) => {
+                log::info!("extended stmt: {:#?}", statement);
+                if matches!(statement, ExtendedStatement::RollbackToSavepoint(_)) || !self.rejected_by_failed_block()? {
+                    tracing::info_span!("execute")
+                        .in_scope(|| self.process_extended_statement(raw_sql_query, statement))?;
+                    self.finish_statement();
+                }
+                self.log_slow_statement(started, raw_sql_query, "Utility");
+                self.record_statistics(started, raw_sql_query);
+                self.sender
+                    .send(Ok(QueryEvent::QueryComplete))
+                    .map_err(SystemError::connection_lost)?;
+                return Ok(());
+            }
+            Some(Err(e)) => {
+                log::error!("{:?} can't be parsed. Error: {:?}", raw_sql_query, e);
+                self.sender
+                    .send(Err(QueryError::syntax_error(format!(
+                        "{:?} can't be parsed",
+                        raw_sql_query
+                    ))))
+                    .map_err(SystemError::connection_lost)?;
+                self.finish_statement();
+                return Ok(());
+            }
+            None => {}
+        }
+
+        let parsed =
+            tracing::info_span!("parse").in_scope(|| table_sample::parse_sql(&PostgreSqlDialect {}, raw_sql_query));
+        let statement = match parsed {
             Ok(mut statements) => {
                 log::info!("stmts: {:#?}", statements);
-                statements.pop().unwrap()
+                match statements.pop() {
+                    Some(statement) => statement,
+                    None => {
+                        self.sender
+                            .send(Ok(QueryEvent::EmptyQuery))
+                            .map_err(SystemError::connection_lost)?;
+                        self.sender
+                            .send(Ok(QueryEvent::QueryComplete))
+                            .map_err(SystemError::connection_lost)?;
+                        return Ok(());
+                    }
+                }
             }
             Err(e) => {
                 log::error!("{:?} can't be parsed. Error: {:?}", raw_sql_query, e);
@@ -111,16 +376,21 @@ impl QueryExecutor {
                         "{:?} can't be parsed",
                         raw_sql_query
                     ))))
-                    .expect("To Send Query Result to Client");
+                    .map_err(SystemError::connection_lost)?;
+                self.finish_statement();
                 return Ok(());
             }
         };
 
-        self.process_statement(raw_sql_query, statement)?;
+        if ends_block(&statement) || !self.rejected_by_failed_block()? {
+            self.process_statement(started, raw_sql_query, statement)?;
+            self.finish_statement();
+        }
+        self.record_statistics(started, raw_sql_query);
 
         self.sender
             .send(Ok(QueryEvent::QueryComplete))
-            .expect("To Send Query Complete Event to Client");
+            .map_err(SystemError::connection_lost)?;
 
         Ok(())
     }
@@ -129,12 +399,12 @@ impl QueryExecutor {
         &mut self,
         statement_name: &str,
         raw_sql_query: &str,
-        param_types: &[PostgreSqlType],
+        param_types: &[Option<PostgreSqlType>],
     ) -> SystemResult<()> {
-        let statement = match Parser::parse_sql(&PreparedStatementDialect {}, raw_sql_query) {
-            Ok(mut statements) => {
-                log::info!("stmts: {:#?}", statements);
-                statements.pop().unwrap()
+        let statement = match parse_prepared(raw_sql_query) {
+            Ok(statement) => {
+                log::info!("stmt: {:#?}", statement);
+                statement
             }
             Err(e) => {
                 log::error!("{:?} can't be parsed. Error: {:?}", raw_sql_query, e);
@@ -143,26 +413,32 @@ impl QueryExecutor {
                         "{:?} can't be parsed",
                         raw_sql_query
                     ))))
-                    .expect("To Send Query Result to Client");
+                    .map_err(SystemError::connection_lost)?;
                 return Ok(());
             }
         };
 
-        let description = match &statement {
-            Statement::Query(query) => {
-                SelectCommand::new(raw_sql_query, query.clone(), self.storage.clone(), self.sender.clone())
-                    .describe()?
-            }
-            _ => vec![],
+        let catalog_version = self.storage.catalog_version();
+        let param_types = infer_param_types(&self.storage, &statement, param_types);
+        let description = match self.describe_statement(raw_sql_query, &with_sample_params(&statement, &param_types)) {
+            Ok(description) => description,
+            // the client already got the reason why the statement can't be described
+            Err(error) if matches!(error.kind(), SystemErrorKind::RuntimeCheckFailure) => return Ok(()),
+            Err(error) => return Err(error),
         };
-
-        let prepared_statement = PreparedStatement::new(statement, param_types.to_vec(), description);
+        let prepared_statement = PreparedStatement::new(
+            statement,
+            raw_sql_query.to_owned(),
+            param_types,
+            description,
+            catalog_version,
+        );
         self.session
             .set_prepared_statement(statement_name.to_owned(), prepared_statement);
 
         self.sender
             .send(Ok(QueryEvent::ParseComplete))
-            .expect("To Send ParseComplete Event");
+            .map_err(SystemError::connection_lost)?;
 
         Ok(())
     }
@@ -175,12 +451,29 @@ impl QueryExecutor {
                         stmt.param_types().to_vec(),
                         stmt.description().to_vec(),
                     )))
-                    .expect("To Send ParametersDescribed Event");
+                    .map_err(SystemError::connection_lost)?;
             }
             None => {
                 self.sender
                     .send(Err(QueryError::prepared_statement_does_not_exist(name.to_owned())))
-                    .expect("To Send Error to Client");
+                    .map_err(SystemError::connection_lost)?;
+            }
+        };
+
+        Ok(())
+    }
+
+    pub fn describe_portal(&mut self, name: &str) -> SystemResult<()> {
+        match self.session.get_portal(name) {
+            Some(portal) => {
+                self.sender
+                    .send(Ok(QueryEvent::PortalDescribed(portal.description().to_vec())))
+                    .map_err(SystemError::connection_lost)?;
+            }
+            None => {
+                self.sender
+                    .send(Err(QueryError::portal_does_not_exist(name.to_owned())))
+                    .map_err(SystemError::connection_lost)?;
             }
         };
 
@@ -195,122 +488,790 @@ impl QueryExecutor {
         raw_params: &[Option<Vec<u8>>],
         result_formats: &[PostgreSqlFormat],
     ) -> SystemResult<()> {
-        let prepared_statement = match self.session.get_prepared_statement(statement_name) {
+        if !self.revalidate_prepared_statement(statement_name)? {
+            return Ok(());
+        }
+        let prepared_statement = match self.session.get_prepared_statement_mut(statement_name) {
             Some(prepared_statement) => prepared_statement,
             None => {
                 self.sender
                     .send(Err(QueryError::prepared_statement_does_not_exist(
                         statement_name.to_owned(),
                     )))
-                    .expect("To Send Error to Client");
+                    .map_err(SystemError::connection_lost)?;
                 return Ok(());
             }
         };
 
-        let param_types = prepared_statement.param_types();
-        if param_types.len() != raw_params.len() {
+        let param_types_len = prepared_statement.param_types().len();
+        if param_types_len != raw_params.len() {
             let message = format!(
                 "Bind message supplies {actual} parameters, \
                  but prepared statement \"{name}\" requires {expected}",
                 name = statement_name,
                 actual = raw_params.len(),
-                expected = param_types.len()
+                expected = param_types_len
             );
             self.sender
                 .send(Err(QueryError::protocol_violation(message)))
-                .expect("To Send Error to Client");
+                .map_err(SystemError::connection_lost)?;
             return Ok(());
         }
 
-        let param_formats = match pad_formats(param_formats, raw_params.len()) {
-            Ok(param_formats) => param_formats,
-            Err(msg) => {
-                self.sender
-                    .send(Err(QueryError::protocol_violation(msg)))
-                    .expect("To Send Error to Client");
-                return Ok(());
+        // drivers bind the same statement with the same formats over and
+        // over, so formats are validated only when they change
+        let (param_formats, result_formats) = match prepared_statement.bound_formats(param_formats, result_formats) {
+            Some((params, results)) => (params.to_vec(), results.to_vec()),
+            None => {
+                let padded = pad_formats(param_formats, raw_params.len()).and_then(|params| {
+                    pad_formats(result_formats, prepared_statement.description().len()).map(|results| (params, results))
+                });
+                match padded {
+                    Ok((params, results)) => {
+                        prepared_statement.set_bound_formats(
+                            param_formats,
+                            result_formats,
+                            params.clone(),
+                            results.clone(),
+                        );
+                        (params, results)
+                    }
+                    Err(msg) => {
+                        self.sender
+                            .send(Err(QueryError::protocol_violation(msg)))
+                            .map_err(SystemError::connection_lost)?;
+                        return Ok(());
+                    }
+                }
             }
         };
 
-        let mut params: Vec<PostgreSqlValue> = vec![];
-        for (raw_param, typ, format) in izip!(raw_params, param_types, param_formats) {
+        let mut params: Vec<PostgreSqlValue> = Vec::with_capacity(raw_params.len());
+        let param_types = prepared_statement.param_types();
+        for (index, (raw_param, typ, format)) in izip!(raw_params, param_types, param_formats).enumerate() {
             match raw_param {
                 None => params.push(PostgreSqlValue::Null),
                 Some(bytes) => match typ.decode(&format, &bytes) {
                     Ok(param) => params.push(param),
                     Err(msg) => {
+                        let detail = format!(
+                            "parameter ${} of type {} was sent in {} format with {} bytes",
+                            index + 1,
+                            typ,
+                            match format {
+                                PostgreSqlFormat::Binary => "binary",
+                                PostgreSqlFormat::Text => "text",
+                            },
+                            bytes.len()
+                        );
                         self.sender
-                            .send(Err(QueryError::invalid_parameter_value(msg)))
-                            .expect("To Send Error to Client");
+                            .send(Err(QueryError::invalid_parameter_value(msg).with_detail(detail)))
+                            .map_err(SystemError::connection_lost)?;
                         return Ok(());
                     }
                 },
             }
         }
 
+        let catalog_version = prepared_statement.catalog_version();
         let mut new_stmt = prepared_statement.stmt().clone();
         if self.param_binder.bind(&mut new_stmt, &params).is_err() {
             return Ok(());
         }
 
-        let result_formats = match pad_formats(result_formats, prepared_statement.description().len()) {
-            Ok(result_formats) => result_formats,
-            Err(msg) => {
-                self.sender
-                    .send(Err(QueryError::protocol_violation(msg)))
-                    .expect("To Send Error to Client");
-                return Ok(());
-            }
+        let raw_sql_query = prepared_statement.raw_sql_query().to_owned();
+        let description = prepared_statement.description().to_vec();
+        let plan = match tracing::info_span!("plan").in_scope(|| self.processor.process(new_stmt.clone())) {
+            Ok(plan) => plan,
+            Err(()) => return Ok(()),
         };
 
         self.session.set_portal(
             portal_name.to_owned(),
-            statement_name.to_owned(),
-            new_stmt,
-            result_formats,
+            Portal::new(
+                statement_name.to_owned(),
+                raw_sql_query,
+                new_stmt,
+                plan,
+                catalog_version,
+                result_formats,
+                description,
+            ),
         );
 
         self.sender
             .send(Ok(QueryEvent::BindComplete))
-            .expect("To Send BindComplete Event");
+            .map_err(SystemError::connection_lost)?;
 
         Ok(())
     }
 
     // TODO: Parameter `max_rows` should be handled.
-    pub fn execute_portal(&mut self, portal_name: &str, _max_rows: i32) -> SystemResult<()> {
-        let portal = match self.session.get_portal(portal_name) {
+    pub fn execute_portal(&mut self, portal_name: &str, max_rows: i32) -> SystemResult<()> {
+        let span = self.statement_span();
+        let _entered = span.enter();
+        let started = self.start_statement();
+        let portal = match self.session.get_portal_mut(portal_name) {
             Some(portal) => portal,
             None => {
                 self.sender
                     .send(Err(QueryError::portal_does_not_exist(portal_name.to_owned())))
-                    .expect("To Send Error to Client");
+                    .map_err(SystemError::connection_lost)?;
                 return Ok(());
             }
         };
 
-        let statement = portal.stmt();
-        let raw_sql_query = format!("{}", statement);
-        self.process_statement(&raw_sql_query, statement.clone())
+        let max_rows = max_rows.max(0) as usize;
+        if portal.is_suspended() {
+            // rows that are left from a previous execution are sent without
+            // running the query again
+            let rows = portal.fetch(max_rows);
+            let sent = rows.len();
+            let suspended = portal.is_suspended();
+            if !rows.is_empty() {
+                self.sender
+                    .send(Ok(QueryEvent::DataRows(rows)))
+                    .map_err(SystemError::connection_lost)?;
+            }
+            let result = if suspended {
+                QueryEvent::PortalSuspended
+            } else {
+                QueryEvent::SelectComplete(sent)
+            };
+            self.sender.send(Ok(result)).map_err(SystemError::connection_lost)?;
+            return Ok(());
+        }
+        if portal.is_completed() {
+            // rows of a completed query are exhausted, other statements
+            // must not be applied twice
+            let result = match portal.plan() {
+                Plan::NotProcessed(statement) if matches!(**statement, Statement::Query(_)) => {
+                    Ok(QueryEvent::SelectComplete(0))
+                }
+                _ => Err(QueryError::portal_cannot_be_run(portal_name.to_owned())),
+            };
+            self.sender.send(result).map_err(SystemError::connection_lost)?;
+            return Ok(());
+        }
+        portal.complete();
+
+        let raw_sql_query = portal.raw_sql_query().to_owned();
+        if portal.catalog_version() != self.storage.catalog_version() && !self.replan_portal(portal_name)? {
+            return Ok(());
+        }
+        let plan = match self.session.get_portal(portal_name) {
+            Some(portal) => portal.plan().clone(),
+            None => return Ok(()),
+        };
+        let ends = matches!(&plan, Plan::NotProcessed(statement) if ends_block(statement));
+        if !ends && self.rejected_by_failed_block()? {
+            return Ok(());
+        }
+        let portion = match &plan {
+            Plan::NotProcessed(statement) if max_rows > 0 && matches!(**statement, Statement::Query(_)) => {
+                Some(Arc::new(Portion::new(self.sender.clone(), max_rows)))
+            }
+            _ => None,
+        };
+        match portion {
+            None => self.execute_plan(started, &raw_sql_query, Ok(plan))?,
+            Some(portion) => {
+                // rows past the limit are kept by the portal for its next
+                // executions, other portals keep their own rows
+                let sender = mem::replace(&mut self.sender, portion.clone());
+                let executed = self.execute_plan(started, &raw_sql_query, Ok(plan));
+                self.sender = sender;
+                executed?;
+                let rest = portion.take_rest();
+                if let (false, Some(portal)) = (rest.is_empty(), self.session.get_portal_mut(portal_name)) {
+                    portal.suspend(rest);
+                }
+            }
+        }
+        self.finish_statement();
+        self.record_statistics(started, &raw_sql_query);
+        Ok(())
     }
 
-    pub fn flush(&self) {
-        match self.sender.flush() {
-            Ok(_) => {}
-            Err(e) => {
-                log::error!("Flush error: {:?}", e);
+    /// Whether statements of the session run in a transaction block that
+    /// was started by `BEGIN`
+    pub fn in_transaction_block(&self) -> bool {
+        self.transaction.status() != Status::Idle
+    }
+
+    pub fn flush(&self) -> SystemResult<()> {
+        self.sender.flush().map_err(SystemError::connection_lost)
+    }
+
+    fn process_extended_statement(&self, raw_sql_query: &str, statement: ExtendedStatement) -> SystemResult<()> {
+        let role = self.session.session_user().to_owned();
+        if self.authorizer.authorize_extended(&role, &statement).is_err() {
+            return Ok(());
+        }
+        if let Some(auditor) = &self.auditor {
+            let raw_sql_query = match &statement {
+                ExtendedStatement::AlterRole { options, .. }
+                    if options.iter().any(|option| matches!(option, RoleOption::Password(_))) =>
+                {
+                    "<statement with password is not recorded>"
+                }
+                _ => raw_sql_query,
+            };
+            auditor.record(self.session_id, &role, classify_extended(&statement), raw_sql_query);
+        }
+        let ddl_tag = statement.ddl_tag();
+        if !self.outside_of_block(classify_extended(&statement).0, ddl_tag)?
+            || !self.locked_objects(classify_extended(&statement))?
+            || !self.ddl_command_start(ddl_tag)?
+        {
+            return Ok(());
+        }
+        let executed = match statement {
+            ExtendedStatement::Grant(change) => {
+                GrantCommand::new(change, role, self.storage.clone(), self.sender.clone()).execute()
+            }
+            ExtendedStatement::Revoke(change) => {
+                RevokeCommand::new(change, role, self.storage.clone(), self.sender.clone()).execute()
+            }
+            ExtendedStatement::AlterRole { role: target, options } => {
+                AlterRoleCommand::new(target, options, role, self.storage.clone(), self.sender.clone()).execute()
+            }
+            ExtendedStatement::AlterDefaultPrivileges(change) => {
+                AlterDefaultPrivilegesCommand::new(change, role, self.storage.clone(), self.sender.clone()).execute()
+            }
+            ExtendedStatement::AlterSettings { owner, change } => {
+                AlterSettingsCommand::new(owner, change, role, self.storage.clone(), self.sender.clone()).execute()
+            }
+            ExtendedStatement::Copy(copy) => {
+                let modified = match copy.direction {
+                    CopyDirection::From => Some(vec![copy.table_name.to_string()]),
+                    CopyDirection::To => None,
+                };
+                CopyCommand::new(
+                    copy,
+                    self.storage.clone(),
+                    self.sender.clone(),
+                    self.transaction.clone(),
+                )
+                .execute()?;
+                if let (Some(cache), Some(objects)) = (&self.result_cache, modified) {
+                    cache.invalidate(&objects);
+                }
+                Ok(())
+            }
+            ExtendedStatement::CreatePublication { name, tables } => {
+                CreatePublicationCommand::new(name, tables, self.storage.clone(), self.sender.clone()).execute()
+            }
+            ExtendedStatement::DropPublication { name, if_exists } => {
+                DropPublicationCommand::new(name, if_exists, self.storage.clone(), self.sender.clone()).execute()
+            }
+            ExtendedStatement::CreateReplicationSlot { name, publication } => {
+                CreateReplicationSlotCommand::new(name, publication, self.storage.clone(), self.sender.clone())
+                    .execute()
             }
+            ExtendedStatement::DropReplicationSlot { name, if_exists } => {
+                DropReplicationSlotCommand::new(name, if_exists, self.storage.clone(), self.sender.clone()).execute()
+            }
+            ExtendedStatement::CreateForeignTable(foreign_table) => {
+                match self
+                    .processor
+                    .handle_create_table(foreign_table.name, &foreign_table.columns, &[])
+                {
+                    Ok(Plan::CreateTable(table_info)) => CreateForeignTableCommand::new(
+                        table_info,
+                        foreign_table.server,
+                        foreign_table.options,
+                        role,
+                        self.storage.clone(),
+                        self.sender.clone(),
+                    )
+                    .execute(),
+                    _ => Ok(()),
+                }
+            }
+            ExtendedStatement::Cluster(cluster) => {
+                let table_name = cluster.table_name.to_string();
+                ClusterTableCommand::new(cluster, role, self.storage.clone(), self.sender.clone()).execute()?;
+                if let Some(cache) = &self.result_cache {
+                    cache.invalidate(&[table_name]);
+                }
+                Ok(())
+            }
+            ExtendedStatement::AlterTable(alter) => {
+                AlterTableCommand::new(alter, role, self.storage.clone(), self.sender.clone()).execute()
+            }
+            ExtendedStatement::CreateFunction { function, or_replace } => {
+                CreateFunctionCommand::new(function, or_replace, role, self.storage.clone(), self.sender.clone())
+                    .execute()
+            }
+            ExtendedStatement::DropFunction { name, if_exists } => {
+                DropFunctionCommand::new(name, if_exists, role, self.storage.clone(), self.sender.clone()).execute()
+            }
+            ExtendedStatement::CreateEventTrigger(trigger) => {
+                CreateEventTriggerCommand::new(trigger, self.storage.clone(), self.sender.clone()).execute()
+            }
+            ExtendedStatement::DropEventTrigger { name, if_exists } => {
+                DropEventTriggerCommand::new(name, if_exists, self.storage.clone(), self.sender.clone()).execute()
+            }
+            ExtendedStatement::Do(block) => self.execute_block(&block),
+            ExtendedStatement::Explain(explain) => self.explain(raw_sql_query, &role, explain),
+            ExtendedStatement::CreateAggregate { aggregate, or_replace } => {
+                CreateAggregateCommand::new(aggregate, or_replace, role, self.storage.clone(), self.sender.clone())
+                    .execute()
+            }
+            ExtendedStatement::CreateWasmFunction { function, or_replace } => {
+                CreateWasmFunctionCommand::new(function, or_replace, role, self.storage.clone(), self.sender.clone())
+                    .execute()
+            }
+            ExtendedStatement::Savepoint(name) => self
+                .sender
+                .send(self.transaction.savepoint(name).map(|()| QueryEvent::SavepointCreated))
+                .map_err(SystemError::connection_lost),
+            ExtendedStatement::ReleaseSavepoint(name) => self
+                .sender
+                .send(self.transaction.release(&name).map(|()| QueryEvent::SavepointReleased))
+                .map_err(SystemError::connection_lost),
+            ExtendedStatement::RollbackToSavepoint(name) => self
+                .sender
+                .send(
+                    self.transaction
+                        .rollback_to(&name)
+                        .map(|()| QueryEvent::TransactionRolledBack),
+                )
+                .map_err(SystemError::connection_lost),
         };
+        executed?;
+        self.ddl_command_end(ddl_tag);
+        Ok(())
     }
 
-    fn process_statement(&self, raw_sql_query: &str, statement: Statement) -> SystemResult<()> {
+    /// Sends the plan of the explained statement without executing it
+    fn explain(&self, raw_sql_query: &str, role: &str, explain: ExplainStatement) -> SystemResult<()> {
+        let plan = match self.processor.process(*explain.statement) {
+            Ok(plan) => plan,
+            Err(()) => return Ok(()),
+        };
+        if self.authorizer.authorize(role, &plan).is_err() {
+            return Ok(());
+        }
+        let node = match plan.node() {
+            Some(node) => node,
+            None => {
+                return self
+                    .sender
+                    .send(Err(QueryError::feature_not_supported(raw_sql_query.to_owned())))
+                    .map_err(SystemError::connection_lost)
+            }
+        };
+        for (schema_name, table_name) in node.relations() {
+            let missing = match schema_name {
+                Some(schema_name) => match self.storage.table_exists(schema_name, table_name) {
+                    None => Some(QueryError::schema_does_not_exist(schema_name.to_owned())),
+                    Some((_, None)) => Some(QueryError::table_does_not_exist(format!(
+                        "{}.{}",
+                        schema_name, table_name
+                    ))),
+                    Some((_, Some(_))) => None,
+                },
+                None => Some(QueryError::table_does_not_exist(table_name.to_owned())),
+            };
+            if let Some(error) = missing {
+                return self.sender.send(Err(error)).map_err(SystemError::connection_lost);
+            }
+        }
+        self.sender
+            .send(Ok(QueryEvent::PlanExplained(node.explain(explain.format))))
+            .map_err(SystemError::connection_lost)
+    }
+
+    /// Statements of `block` are executed one by one, the ones that
+    /// succeeded before an error are not rolled back
+    fn execute_block(&self, block: &Block) -> SystemResult<()> {
+        let mut executor = SessionExecutor::new(|sender| self.nested_session(sender));
+        let result = procedural::execute_block(block, &mut executor);
+        if let Some(error) = executor.system_error() {
+            return Err(error);
+        }
+        self.sender
+            .send(result.map(|()| QueryEvent::BlockExecuted))
+            .map_err(SystemError::connection_lost)?;
+        Ok(())
+    }
+
+    /// Session of the same role that sends results to `sender`, statements
+    /// executed in it are audited and invalidate cached results
+    fn nested_session(&self, sender: Arc<dyn Sender>) -> QueryExecutor {
+        let mut session = QueryExecutor::new(self.storage.clone(), sender, self.session.session_user())
+            .with_session_id(self.session_id)
+            .with_max_parallel_workers(self.session.max_parallel_workers())
+            .with_work_mem(self.session.work_mem());
+        session.auditor = self.auditor.clone();
+        session.cancellation = self.cancellation.child();
+        session.result_cache = self.result_cache.clone();
+        session.transaction = self.transaction.clone();
+        session
+            .session
+            .set_max_recursive_iterations(self.session.max_recursive_iterations());
+        session.session.set_large_objects(self.session.large_objects().clone());
+        session
+    }
+
+    /// Statements of a failed transaction block are rejected until the block
+    /// or the failed subtransaction is rolled back, returns `true` then
+    fn rejected_by_failed_block(&self) -> SystemResult<bool> {
+        if self.transaction.status() != Status::Failed {
+            return Ok(false);
+        }
+        self.sender
+            .send(Err(QueryError::in_failed_sql_transaction()))
+            .map_err(SystemError::connection_lost)?;
+        Ok(true)
+    }
+
+    /// An error of a statement fails the transaction block that it was
+    /// executed in, locks are released unless the block goes on
+    fn finish_statement(&self) {
+        if self.row_counter.failed() {
+            self.transaction.fail();
+        }
+        self.transaction.release_locks();
+    }
+
+    /// Returns `false` if DDL is executed in a transaction block, the error
+    /// is sent then. Writes of a block are kept until it is committed, but
+    /// DDL changes the catalog at once and could not be rolled back.
+    fn outside_of_block(&self, class: StatementClass, tag: Option<&str>) -> SystemResult<bool> {
+        if class != StatementClass::Ddl || !self.in_transaction_block() {
+            return Ok(true);
+        }
+        self.sender
+            .send(Err(QueryError::active_sql_transaction(tag.unwrap_or("DDL"))))
+            .map_err(SystemError::connection_lost)?;
+        Ok(false)
+    }
+
+    /// Waits for locks of objects that a statement reads or changes, the
+    /// error is sent when a lock can't be acquired. DDL waits until
+    /// statements that use its objects complete, so that they run against
+    /// the definition they were planned with.
+    fn locked_objects(&self, (class, objects): (StatementClass, Vec<String>)) -> SystemResult<bool> {
+        let mode = match class {
+            StatementClass::Read | StatementClass::Write => LockMode::Shared,
+            StatementClass::Ddl => LockMode::Exclusive,
+            StatementClass::Role | StatementClass::Misc => return Ok(true),
+        };
+        for object in objects {
+            if let Err(error) = self.transaction.lock(object, mode, &self.cancellation) {
+                self.sender.send(Err(error)).map_err(SystemError::connection_lost)?;
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Discards cached results that read rows of `tables` which a commit
+    /// wrote
+    fn invalidate_results(&self, tables: &[String]) {
+        if let Some(cache) = &self.result_cache {
+            if !tables.is_empty() {
+                cache.invalidate(tables);
+            }
+        }
+    }
+
+    /// Returns `false` if a `ddl_command_start` trigger failed, the command
+    /// with `tag` must not be executed then
+    fn ddl_command_start(&self, tag: Option<&str>) -> SystemResult<bool> {
+        match tag.map(|tag| self.fire_event_triggers(DdlEvent::Start, tag)) {
+            Some(Err(error)) => {
+                self.sender.send(Err(error)).map_err(SystemError::connection_lost)?;
+                Ok(false)
+            }
+            _ => Ok(true),
+        }
+    }
+
+    /// Fires `ddl_command_end` triggers if the command with `tag` succeeded.
+    /// Commands can't be rolled back, so errors of the triggers are only
+    /// logged.
+    fn ddl_command_end(&self, tag: Option<&str>) {
+        if let Some(tag) = tag {
+            if self.row_counter.failed() {
+                return;
+            }
+            if let Err(error) = self.fire_event_triggers(DdlEvent::End, tag) {
+                log::warn!("ddl_command_end trigger of {} failed: {}", tag, error);
+            }
+        }
+    }
+
+    /// Fires triggers of `event` in order of their names, the first error
+    /// stops the rest of them
+    fn fire_event_triggers(&self, event: DdlEvent, tag: &str) -> Result<(), QueryError> {
+        for trigger in self.storage.event_triggers().matching(event, tag) {
+            match self.storage.functions().get(&trigger.function) {
+                Some(function) => procedural::fire(&function, &event.to_string(), tag, &self.functions())?,
+                None => return Err(QueryError::function_does_not_exist(format!("{}()", trigger.function))),
+            }
+        }
+        Ok(())
+    }
+
+    fn log_slow_statement(&self, started: Instant, raw_sql_query: &str, plan: &str) {
+        if let Some(threshold) = self.session.log_min_duration_statement() {
+            let duration = started.elapsed();
+            if duration >= threshold {
+                log::info!(
+                    "duration: {:.3} ms  plan: {}  statement: {}",
+                    duration.as_secs_f64() * 1000.0,
+                    plan,
+                    raw_sql_query
+                );
+            }
+        }
+    }
+
+    /// Span of a single statement execution, it is a parent of parse, plan
+    /// and execute spans
+    fn statement_span(&mut self) -> tracing::Span {
+        self.statement_id += 1;
+        tracing::info_span!(
+            "statement",
+            session_id = self.session_id,
+            statement_id = self.statement_id
+        )
+    }
+
+    /// Discards rows and errors that were counted outside of statement
+    /// execution and starts timeout of the statement
+    fn start_statement(&mut self) -> Instant {
+        self.row_counter.take();
+        self.adopt_server_settings();
+        self.cancellation.start(self.session.statement_timeout());
+        Instant::now()
+    }
+
+    /// Replaces defaults that were reloaded by the server, session values
+    /// follow them unless they were changed with `SET`
+    fn adopt_server_settings(&mut self) {
+        let settings = match &self.server_settings {
+            Some(settings) => settings.clone(),
+            None => return,
+        };
+        let log_min_duration_statement = settings.log_min_duration_statement();
+        if self.session.log_min_duration_statement() == self.default_log_min_duration_statement {
+            self.session.set_log_min_duration_statement(log_min_duration_statement);
+        }
+        self.default_log_min_duration_statement = log_min_duration_statement;
+        let auto_explain_log_min_duration = settings.auto_explain_log_min_duration();
+        if self.session.auto_explain_log_min_duration() == self.default_auto_explain_log_min_duration {
+            self.session
+                .set_auto_explain_log_min_duration(auto_explain_log_min_duration);
+        }
+        self.default_auto_explain_log_min_duration = auto_explain_log_min_duration;
+    }
+
+    /// Returns `false` if the server can't reload its configuration and the
+    /// query has to be executed as a regular one
+    fn reload_configuration(&self) -> SystemResult<bool> {
+        let settings = match &self.server_settings {
+            Some(settings) => settings,
+            None => return Ok(false),
+        };
+        let result = if self.session.session_user() != SUPERUSER {
+            Err(QueryError::permission_denied("function", RELOAD_FUNCTION.to_owned()))
+        } else if settings.reload() {
+            Ok(QueryEvent::RecordsSelected((
+                vec![ColumnDescription::new(RELOAD_FUNCTION, PostgreSqlType::Bool)],
+                vec![vec![Some("t".to_owned())]],
+            )))
+        } else {
+            return Ok(false);
+        };
+        self.sender.send(result).map_err(SystemError::connection_lost)?;
+        Ok(true)
+    }
+
+    fn record_statistics(&self, started: Instant, raw_sql_query: &str) {
+        if let (Some(statistics), Some(rows)) = (&self.statistics, self.row_counter.take()) {
+            statistics.record(self.session.session_user(), raw_sql_query, started.elapsed(), rows);
+        }
+    }
+
+    /// Returns `false` if the query has to be executed as a regular one
+    fn query_statistics(&self, query: StatisticsQuery) -> SystemResult<bool> {
+        let role = self.session.session_user();
+        let result = match (query, &self.statistics) {
+            (StatisticsQuery::Tables, _) => Ok(QueryEvent::RecordsSelected(tables_view(&self.storage))),
+            (_, None) => return Ok(false),
+            (StatisticsQuery::Statements, Some(statistics)) => Ok(QueryEvent::RecordsSelected(statistics.view(role))),
+            (StatisticsQuery::Reset, Some(statistics)) if role == SUPERUSER => {
+                statistics.reset();
+                Ok(QueryEvent::RecordsSelected((
+                    vec![ColumnDescription::new(RESET_FUNCTION, PostgreSqlType::VarChar)],
+                    vec![vec![Some("".to_owned())]],
+                )))
+            }
+            (StatisticsQuery::Reset, Some(_)) => {
+                Err(QueryError::permission_denied("function", RESET_FUNCTION.to_owned()))
+            }
+        };
+        self.sender.send(result).map_err(SystemError::connection_lost)?;
+        Ok(true)
+    }
+
+    fn auto_explain(&self, raw_sql_query: &str, plan: &str, started: Instant, planned: Instant) {
+        if let Some(threshold) = self.session.auto_explain_log_min_duration() {
+            let duration = started.elapsed();
+            if duration >= threshold {
+                let explain = ExplainAnalyze {
+                    query_text: raw_sql_query,
+                    plan,
+                    instrumentation: Instrumentation {
+                        planning: planned.duration_since(started),
+                        execution: planned.elapsed(),
+                        rows: self.row_counter.rows(),
+                    },
+                };
+                log::info!(
+                    "duration: {:.3} ms  plan:\n{}",
+                    duration.as_secs_f64() * 1000.0,
+                    explain
+                );
+            }
+        }
+    }
+
+    fn set_variable(&mut self, variable: &str, value: &SetVariableValue) -> SystemResult<()> {
+        let set = self.apply_setting(variable, value);
+        self.sender
+            .send(set.map(|()| QueryEvent::VariableSet))
+            .map_err(SystemError::connection_lost)
+    }
+
+    /// Changes the setting of the session, unknown settings are ignored
+    fn apply_setting(&mut self, variable: &str, value: &SetVariableValue) -> Result<(), QueryError> {
+        let variable = variable.to_lowercase();
+        match variable.as_str() {
+            LOG_MIN_DURATION_STATEMENT => self.set_duration(
+                &variable,
+                value,
+                self.default_log_min_duration_statement,
+                Session::set_log_min_duration_statement,
+            ),
+            AUTO_EXPLAIN_LOG_MIN_DURATION => self.set_duration(
+                &variable,
+                value,
+                self.default_auto_explain_log_min_duration,
+                Session::set_auto_explain_log_min_duration,
+            ),
+            STATEMENT_TIMEOUT => self.set_duration(
+                &variable,
+                value,
+                self.default_statement_timeout,
+                Session::set_statement_timeout,
+            ),
+            MAX_PARALLEL_WORKERS => settings::parse_count(&variable, value).map(|workers| match workers {
+                SettingValue::Default => self.session.set_max_parallel_workers(self.default_max_parallel_workers),
+                SettingValue::Value(workers) => self.session.set_max_parallel_workers(workers),
+            }),
+            WORK_MEM => settings::parse_memory(&variable, value).map(|work_mem| match work_mem {
+                SettingValue::Default => self.session.set_work_mem(self.default_work_mem),
+                SettingValue::Value(work_mem) => self.session.set_work_mem(work_mem),
+            }),
+            MAX_RECURSIVE_ITERATIONS => settings::parse_count(&variable, value).map(|iterations| match iterations {
+                SettingValue::Default => self
+                    .session
+                    .set_max_recursive_iterations(DEFAULT_MAX_RECURSIVE_ITERATIONS),
+                SettingValue::Value(iterations) => self.session.set_max_recursive_iterations(iterations),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    fn set_duration(
+        &mut self,
+        variable: &str,
+        value: &SetVariableValue,
+        default: Option<Duration>,
+        set: fn(&mut Session, Option<Duration>),
+    ) -> Result<(), QueryError> {
+        match settings::parse_duration(variable, value)? {
+            SettingValue::Default => set(&mut self.session, default),
+            SettingValue::Value(duration) => set(&mut self.session, duration),
+        }
+        Ok(())
+    }
+
+    fn process_statement(&mut self, started: Instant, raw_sql_query: &str, statement: Statement) -> SystemResult<()> {
         log::debug!("STATEMENT = {:?}", statement);
-        match self.processor.process(statement) {
+        let plan = tracing::info_span!("plan").in_scope(|| self.processor.process(statement));
+        self.execute_plan(started, raw_sql_query, plan)
+    }
+
+    /// Privileges are checked right before the execution, so that plans of
+    /// portals can't be executed by a role that lost its privileges
+    fn execute_plan(&mut self, started: Instant, raw_sql_query: &str, plan: Result<Plan, ()>) -> SystemResult<()> {
+        let plan = plan.and_then(|plan| {
+            self.authorizer
+                .authorize(self.session.session_user(), &plan)
+                .map(|()| plan)
+        });
+        if let (Some(auditor), Ok(plan)) = (&self.auditor, &plan) {
+            auditor.record(
+                self.session_id,
+                self.session.session_user(),
+                classify_plan(plan),
+                raw_sql_query,
+            );
+        }
+        let ddl_tag = plan.as_ref().ok().and_then(Plan::ddl_tag);
+        let plan = match plan {
+            Ok(plan)
+                if self.outside_of_block(classify_plan(&plan).0, ddl_tag)?
+                    && self.locked_objects(classify_plan(&plan))?
+                    && self.ddl_command_start(ddl_tag)? =>
+            {
+                Ok(plan)
+            }
+            _ => Err(()),
+        };
+        let planned = Instant::now();
+        let summary = match (
+            &plan,
+            self.session.log_min_duration_statement(),
+            self.session.auto_explain_log_min_duration(),
+        ) {
+            (Ok(plan), Some(_), _) | (Ok(plan), _, Some(_)) => Some(plan.to_string()),
+            _ => None,
+        };
+        let modified = match (&self.result_cache, &plan) {
+            (Some(_), Ok(plan)) => match classify_plan(plan) {
+                (StatementClass::Write, objects) | (StatementClass::Ddl, objects) => Some(objects),
+                _ => None,
+            },
+            _ => None,
+        };
+        let execution = tracing::info_span!("execute");
+        let entered = execution.enter();
+        match plan {
             Ok(Plan::CreateSchema(creation_info)) => {
-                CreateSchemaCommand::new(creation_info, self.storage.clone(), self.sender.clone()).execute()?;
+                CreateSchemaCommand::new(
+                    creation_info,
+                    self.session.session_user().to_owned(),
+                    self.storage.clone(),
+                    self.sender.clone(),
+                )
+                .execute()?;
             }
             Ok(Plan::CreateTable(creation_info)) => {
-                CreateTableCommand::new(creation_info, self.storage.clone(), self.sender.clone()).execute()?;
+                CreateTableCommand::new(
+                    creation_info,
+                    self.session.session_user().to_owned(),
+                    self.storage.clone(),
+                    self.sender.clone(),
+                )
+                .execute()?;
             }
             Ok(Plan::DropSchemas(schemas)) => {
                 for (schema, cascade) in schemas {
@@ -323,60 +1284,319 @@ impl QueryExecutor {
                 }
             }
             Ok(Plan::Insert(table_insert)) => {
-                InsertCommand::new(raw_sql_query, table_insert, self.storage.clone(), self.sender.clone()).execute()?;
+                InsertCommand::new(
+                    raw_sql_query,
+                    table_insert,
+                    self.storage.clone(),
+                    self.sender.clone(),
+                    self.transaction.clone(),
+                )
+                .execute()?;
             }
             Ok(Plan::NotProcessed(statement)) => match *statement {
                 Statement::StartTransaction { .. } => {
+                    self.transaction.begin();
                     self.sender
                         .send(Ok(QueryEvent::TransactionStarted))
-                        .expect("To Send Query Result to Client");
+                        .map_err(SystemError::connection_lost)?;
                 }
-                Statement::SetVariable { .. } => {
+                Statement::Commit { chain } => {
+                    let event = if self.transaction.status() == Status::Failed {
+                        self.transaction.rollback();
+                        QueryEvent::TransactionRolledBack
+                    } else {
+                        let written = self.transaction.commit(&self.storage)?;
+                        self.invalidate_results(&written);
+                        QueryEvent::TransactionCommitted
+                    };
+                    if chain {
+                        self.transaction.begin();
+                    }
+                    self.sender.send(Ok(event)).map_err(SystemError::connection_lost)?;
+                }
+                Statement::Rollback { chain } => {
+                    self.transaction.rollback();
+                    if chain {
+                        self.transaction.begin();
+                    }
                     self.sender
-                        .send(Ok(QueryEvent::VariableSet))
-                        .expect("To Send Query Result to Client");
+                        .send(Ok(QueryEvent::TransactionRolledBack))
+                        .map_err(SystemError::connection_lost)?;
                 }
+                Statement::SetVariable { variable, value, .. } => self.set_variable(&variable.value, &value)?,
                 Statement::Drop { .. } => {
                     self.sender
                         .send(Err(QueryError::feature_not_supported(raw_sql_query.to_owned())))
-                        .expect("To Send Query Result to Client");
+                        .map_err(SystemError::connection_lost)?;
                 }
                 Statement::Query(query) => {
-                    SelectCommand::new(raw_sql_query, query, self.storage.clone(), self.sender.clone()).execute()?;
+                    let served = match StatisticsQuery::recognize(&query) {
+                        Some(statistics_query) => self.query_statistics(statistics_query)?,
+                        None if is_reload_call(&query) => self.reload_configuration()?,
+                        None => false,
+                    };
+                    if !served {
+                        self.select(raw_sql_query, query)?;
+                    }
                 }
                 Statement::Update {
                     table_name,
                     assignments,
-                    ..
+                    selection,
                 } => {
-                    UpdateCommand::new(table_name, assignments, self.storage.clone(), self.sender.clone()).execute()?;
+                    UpdateCommand::new(
+                        table_name,
+                        assignments,
+                        selection,
+                        self.storage.clone(),
+                        self.sender.clone(),
+                        self.functions(),
+                        self.transaction.clone(),
+                    )
+                    .execute()?;
                 }
-                Statement::Delete { table_name, .. } => {
-                    DeleteCommand::new(table_name, self.storage.clone(), self.sender.clone()).execute()?;
+                Statement::Delete { table_name, selection } => {
+                    DeleteCommand::new(
+                        table_name,
+                        selection,
+                        self.storage.clone(),
+                        self.sender.clone(),
+                        self.functions(),
+                        self.transaction.clone(),
+                    )
+                    .execute()?;
                 }
                 _ => {
                     self.sender
                         .send(Err(QueryError::feature_not_supported(raw_sql_query.to_owned())))
-                        .expect("To Send Query Result to Client");
+                        .map_err(SystemError::connection_lost)?;
                 }
             },
             Err(()) => {}
         };
+        self.ddl_command_end(ddl_tag);
+        if let (Some(cache), Some(objects)) = (&self.result_cache, modified) {
+            cache.invalidate(&objects);
+        }
+        drop(entered);
+        if let Some(summary) = summary {
+            self.log_slow_statement(started, raw_sql_query, &summary);
+            self.auto_explain(raw_sql_query, &summary, started, planned);
+        }
 
         Ok(())
     }
-}
 
-#[derive(Debug)]
-struct PreparedStatementDialect {}
+    /// Describes the rows that `statement` returns and checks that tables it
+    /// modifies exist
+    fn describe_statement(&self, raw_sql_query: &str, statement: &Statement) -> SystemResult<Description> {
+        match statement {
+            Statement::Query(query) => SelectCommand::new(
+                raw_sql_query,
+                query.clone(),
+                self.storage.clone(),
+                self.sender.clone(),
+                self.session.max_parallel_workers(),
+                self.session.work_mem(),
+                self.functions(),
+            )
+            .with_max_recursive_iterations(self.session.max_recursive_iterations())
+            .describe(),
+            Statement::Insert { .. } | Statement::Update { .. } | Statement::Delete { .. } => {
+                self.check_modified_table(statement)?;
+                Ok(vec![])
+            }
+            _ => Ok(vec![]),
+        }
+    }
 
-impl Dialect for PreparedStatementDialect {
-    fn is_identifier_start(&self, ch: char) -> bool {
-        (ch >= 'a' && ch <= 'z') || (ch >= 'A' && ch <= 'Z') || ch == '$' || ch == '_'
+    /// Describes the prepared statement again if the catalog was changed
+    /// since it was described. Returns `false` and sends an error to the
+    /// client if the statement can't be used anymore
+    fn revalidate_prepared_statement(&mut self, statement_name: &str) -> SystemResult<bool> {
+        let catalog_version = self.storage.catalog_version();
+        let (raw_sql_query, stmt, description) = match self.session.get_prepared_statement(statement_name) {
+            Some(prepared_statement) if prepared_statement.catalog_version() != catalog_version => (
+                prepared_statement.raw_sql_query().to_owned(),
+                with_sample_params(prepared_statement.stmt(), prepared_statement.param_types()),
+                prepared_statement.description().to_vec(),
+            ),
+            _ => return Ok(true),
+        };
+        let description = match self.revalidate_description(&raw_sql_query, &stmt, &description)? {
+            Some(description) => description,
+            None => return Ok(false),
+        };
+        if let Some(prepared_statement) = self.session.get_prepared_statement_mut(statement_name) {
+            prepared_statement.revalidated(catalog_version, description);
+        }
+        Ok(true)
     }
 
-    fn is_identifier_part(&self, ch: char) -> bool {
-        (ch >= 'a' && ch <= 'z') || (ch >= 'A' && ch <= 'Z') || (ch >= '0' && ch <= '9') || ch == '$' || ch == '_'
+    /// Plans the bound statement of the portal again against the current
+    /// catalog. Returns `false` and sends an error to the client if the
+    /// portal can't be executed anymore
+    fn replan_portal(&mut self, portal_name: &str) -> SystemResult<bool> {
+        let catalog_version = self.storage.catalog_version();
+        let (raw_sql_query, stmt, description) = match self.session.get_portal(portal_name) {
+            Some(portal) => (
+                portal.raw_sql_query().to_owned(),
+                portal.stmt().clone(),
+                portal.description().to_vec(),
+            ),
+            None => return Ok(false),
+        };
+        let description = match self.revalidate_description(&raw_sql_query, &stmt, &description)? {
+            Some(description) => description,
+            None => return Ok(false),
+        };
+        let plan = match tracing::info_span!("plan").in_scope(|| self.processor.process(stmt)) {
+            Ok(plan) => plan,
+            Err(()) => return Ok(false),
+        };
+        if let Some(portal) = self.session.get_portal_mut(portal_name) {
+            portal.replan(plan, catalog_version, description);
+        }
+        Ok(true)
+    }
+
+    /// Describes `statement` again, the result can't change names or types
+    /// of columns, but e.g. a recreated table changes their source. Returns
+    /// `None` and sends an error to the client if the result changed.
+    fn revalidate_description(
+        &self,
+        raw_sql_query: &str,
+        statement: &Statement,
+        description: &[ColumnDescription],
+    ) -> SystemResult<Option<Description>> {
+        let actual = match self.describe_statement(raw_sql_query, statement) {
+            Ok(actual) => actual,
+            Err(_) => return Ok(None),
+        };
+        let same_columns = actual.len() == description.len()
+            && actual
+                .iter()
+                .zip(description)
+                .all(|(actual, described)| actual.name == described.name && actual.pg_type == described.pg_type);
+        if !same_columns {
+            self.sender
+                .send(Err(QueryError::cached_plan_changed_result_type()))
+                .map_err(SystemError::connection_lost)?;
+            return Ok(None);
+        }
+        Ok(Some(actual))
+    }
+
+    /// Sends an error to the client if the table or any of columns that
+    /// `statement` modifies does not exist
+    fn check_modified_table(&self, statement: &Statement) -> SystemResult<()> {
+        let (table_name, columns) = match statement {
+            Statement::Insert {
+                table_name, columns, ..
+            } => (table_name, columns.clone()),
+            Statement::Update {
+                table_name,
+                assignments,
+                ..
+            } => (
+                table_name,
+                assignments.iter().map(|assignment| assignment.id.clone()).collect(),
+            ),
+            Statement::Delete { table_name, .. } => (table_name, vec![]),
+            _ => return Ok(()),
+        };
+        let table_id = match TableId::try_from(table_name.clone()) {
+            Ok(table_id) => table_id,
+            Err(_) => return Ok(()),
+        };
+        let (error, message) = match self.storage.table_exists(table_id.schema_name(), table_id.name()) {
+            None => (
+                QueryError::schema_does_not_exist(table_id.schema_name().to_owned()),
+                "Schema Does Not Exist",
+            ),
+            Some((_, None)) => (
+                QueryError::table_does_not_exist(format!("{}.{}", table_id.schema_name(), table_id.name())),
+                "Table Does Not Exist",
+            ),
+            Some((_, Some(_))) => {
+                let all_columns = self.storage.table_columns(table_id.schema_name(), table_id.name())?;
+                match Scope::new(&all_columns).resolve_all(columns.iter().map(|column| column.value.as_str())) {
+                    Ok(_) => return Ok(()),
+                    Err(error) => (error, "Column Does Not Exist"),
+                }
+            }
+        };
+        self.sender.send(Err(error)).map_err(SystemError::connection_lost)?;
+        Err(SystemError::runtime_check_failure(message.to_owned()))
+    }
+
+    /// Serves `query` from the result cache if it is enabled, otherwise the
+    /// query is executed and its result is cached
+    fn select(&self, raw_sql_query: &str, query: Box<Query>) -> SystemResult<()> {
+        let cache = match &self.result_cache {
+            // other sessions don't see rows written by the transaction block
+            Some(cache) if !samples_randomly(&query) && !self.transaction.has_writes() => cache.clone(),
+            _ => {
+                return SelectCommand::new(
+                    raw_sql_query,
+                    query,
+                    self.storage.clone(),
+                    self.sender.clone(),
+                    self.session.max_parallel_workers(),
+                    self.session.work_mem(),
+                    self.functions(),
+                )
+                .with_max_recursive_iterations(self.session.max_recursive_iterations())
+                .with_transaction(Some(self.transaction.clone()))
+                .execute()
+            }
+        };
+        let key = query.to_string();
+        if let Some((description, rows)) = cache.get(&key) {
+            let selected = rows.len();
+            self.sender
+                .send(Ok(QueryEvent::RowDescription(description)))
+                .map_err(SystemError::connection_lost)?;
+            if !rows.is_empty() {
+                self.sender
+                    .send(Ok(QueryEvent::DataRows(rows)))
+                    .map_err(SystemError::connection_lost)?;
+            }
+            self.sender
+                .send(Ok(QueryEvent::SelectComplete(selected)))
+                .map_err(SystemError::connection_lost)?;
+            return Ok(());
+        }
+        let mut tables = vec![];
+        collect_query_tables(&query, &mut tables);
+        let tables = tables.into_iter().map(ToString::to_string).collect();
+        let generation = cache.generation();
+        let recorder = Arc::new(ResultRecorder::new(self.sender.clone()));
+        let functions = self.functions();
+        SelectCommand::new(
+            raw_sql_query,
+            query,
+            self.storage.clone(),
+            recorder.clone(),
+            self.session.max_parallel_workers(),
+            self.session.work_mem(),
+            functions.clone(),
+        )
+        .with_max_recursive_iterations(self.session.max_recursive_iterations())
+        .with_transaction(Some(self.transaction.clone()))
+        .execute()?;
+        // results of volatile functions differ from one execution to another
+        if let Some(result) = recorder.take_result().filter(|_| !functions.volatile_called()) {
+            cache.put(key, tables, generation, result);
+        }
+        Ok(())
+    }
+
+    /// Scalar functions that the current statement calls
+    fn functions(&self) -> ScalarFunctions {
+        self.storage
+            .scalar_functions()
+            .for_statement(&self.cancellation, self.session.large_objects())
     }
 }
 
@@ -389,5 +1609,25 @@ fn pad_formats(formats: &[PostgreSqlFormat], param_len: usize) -> Result<Vec<Pos
     }
 }
 
+/// Whether `raw_sql_query` is DDL, which can't run in a transaction block
+pub(crate) fn is_ddl(raw_sql_query: &str) -> bool {
+    match extended::parse(&PostgreSqlDialect {}, raw_sql_query) {
+        Some(Ok(statement)) => classify_extended(&statement).0 == StatementClass::Ddl,
+        Some(Err(_)) => false,
+        None => matches!(
+            table_sample::parse_sql(&PostgreSqlDialect {}, raw_sql_query).as_deref(),
+            Ok([
+                ..,
+                Statement::CreateTable { .. } | Statement::CreateSchema { .. } | Statement::Drop { .. }
+            ])
+        ),
+    }
+}
+
+/// `COMMIT` and `ROLLBACK` end a failed transaction block
+fn ends_block(statement: &Statement) -> bool {
+    matches!(statement, Statement::Commit { .. } | Statement::Rollback { .. })
+}
+
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,82 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    dml::functions::ScalarFunctions,
+    procedural::{call, Function, Returns, Value},
+};
+use protocol::{results::QueryError, sql_types::PostgreSqlType};
+use std::sync::Arc;
+
+/// Aggregate that folds values of a group into a state with `transition`
+/// and computes its result from the state with `finish`:
+///
+/// ```sql
+/// CREATE AGGREGATE total (integer) (
+///     SFUNC = add,
+///     STYPE = bigint,
+///     INITCOND = '0'
+/// );
+/// ```
+///
+/// `NULL` values are skipped. Without an initial condition the first
+/// value of a group becomes the state and `NULL` is the result of an empty
+/// group, like with aggregates whose transition function is strict in
+/// PostgreSQL.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Aggregate {
+    pub(crate) name: String,
+    pub(crate) input: PostgreSqlType,
+    pub(crate) state: PostgreSqlType,
+    pub(crate) initial: Option<String>,
+    pub(crate) transition: Arc<Function>,
+    pub(crate) finish: Option<Arc<Function>>,
+}
+
+impl Aggregate {
+    pub(crate) fn returns(&self) -> PostgreSqlType {
+        match self.finish.as_ref().map(|finish| finish.returns) {
+            Some(Returns::Type(returns)) => returns,
+            _ => self.state,
+        }
+    }
+
+    /// State of a group before any value is accumulated
+    pub(crate) fn start(&self) -> Result<Value, QueryError> {
+        match &self.initial {
+            Some(initial) => Value::Text(initial.clone()).cast(self.state),
+            None => Ok(Value::Null),
+        }
+    }
+
+    pub(crate) fn accumulate(
+        &self,
+        state: Value,
+        value: Value,
+        functions: &ScalarFunctions,
+    ) -> Result<Value, QueryError> {
+        match (state, value) {
+            (state, Value::Null) => Ok(state),
+            (Value::Null, value) if self.initial.is_none() => value.cast(self.state),
+            (state, value) => call(&self.transition, vec![state, value], functions),
+        }
+    }
+
+    pub(crate) fn finish(&self, state: Value, functions: &ScalarFunctions) -> Result<Value, QueryError> {
+        match &self.finish {
+            Some(finish) => call(finish, vec![state], functions),
+            None => Ok(state),
+        }
+    }
+}
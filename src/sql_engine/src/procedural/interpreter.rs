@@ -0,0 +1,693 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{identifier, Block, Body, Condition, Function, RaiseLevel, Returns, SqlExecutor, Statement};
+use crate::dml::{
+    functions::{function_name, ScalarFunctions},
+    predicate::{Kind, Scalar},
+};
+use protocol::{results::QueryError, sql_types::PostgreSqlType};
+use sql_types::SqlType;
+use sqlparser::ast::{BinaryOperator, Expr, Function as Call, UnaryOperator, Value as Literal};
+use std::{cmp::Ordering, collections::HashMap};
+
+/// Value of an argument, a variable or a result of a function
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    Null,
+    Bool(bool),
+    Integer(i64),
+    Text(String),
+    /// `ROW(value, ...)` or `(value, value, ...)`
+    Row(Vec<Value>),
+}
+
+impl Value {
+    /// Text representation of query results
+    pub(crate) fn to_text(&self) -> String {
+        match self {
+            Value::Null => "NULL".to_owned(),
+            Value::Bool(true) => "t".to_owned(),
+            Value::Bool(false) => "f".to_owned(),
+            Value::Integer(value) => value.to_string(),
+            Value::Text(value) => value.clone(),
+            Value::Row(values) => {
+                let fields = values.iter().map(row_field).collect::<Vec<String>>();
+                format!("({})", fields.join(","))
+            }
+        }
+    }
+
+    /// Text of the value as a field of a result row, `None` for NULL
+    pub(crate) fn to_field(&self) -> Option<String> {
+        match self {
+            Value::Null => None,
+            other => Some(other.to_text()),
+        }
+    }
+
+    pub(crate) fn type_name(&self) -> &'static str {
+        match self {
+            Value::Null => "unknown",
+            Value::Bool(_) => "boolean",
+            Value::Integer(_) => "integer",
+            Value::Text(_) => "text",
+            Value::Row(_) => "record",
+        }
+    }
+
+    /// Value of an argument or a result of a scalar function, numbers with a
+    /// fractional part are kept as text
+    pub(crate) fn from_scalar(scalar: Scalar) -> Value {
+        match scalar {
+            Scalar::Null => Value::Null,
+            Scalar::Bool(value) => Value::Bool(value),
+            Scalar::Integer(value) => Value::Integer(value),
+            Scalar::Float(value) => Value::Text(value.to_string()),
+            Scalar::Text(text) => Value::Text(text),
+        }
+    }
+
+    /// Value of `sql_type` type that scalar functions take and return
+    pub(crate) fn into_scalar(self, sql_type: &SqlType) -> Result<Scalar, QueryError> {
+        let pg_type: PostgreSqlType = sql_type.into();
+        Ok(match self.cast(pg_type)? {
+            Value::Null => Scalar::Null,
+            Value::Bool(value) => Scalar::Bool(value),
+            Value::Integer(value) => Scalar::Integer(value),
+            Value::Text(text) if Kind::of(sql_type) == Kind::Number => match text.trim().parse::<f64>() {
+                Ok(value) if value.is_finite() => Scalar::Float(value),
+                _ => return Err(invalid_input(pg_type, &text)),
+            },
+            Value::Text(text) => Scalar::Text(text),
+            Value::Row(_) => unreachable!("values are cast to scalar types"),
+        })
+    }
+
+    /// Whether the value can be an argument of a parameter of `sql_type`
+    fn accepted_as(&self, sql_type: &SqlType) -> bool {
+        matches!(
+            (self, Kind::of(sql_type)),
+            (Value::Null, _) | (Value::Text(_), _) | (Value::Bool(_), Kind::Bool) | (Value::Integer(_), Kind::Number)
+        )
+    }
+
+    /// Converts the value to `pg_type` of a variable or a result
+    pub(super) fn cast(self, pg_type: PostgreSqlType) -> Result<Value, QueryError> {
+        let (min, max) = match pg_type {
+            PostgreSqlType::SmallInt => (i64::from(i16::MIN), i64::from(i16::MAX)),
+            PostgreSqlType::Integer => (i64::from(i32::MIN), i64::from(i32::MAX)),
+            PostgreSqlType::BigInt => (i64::MIN, i64::MAX),
+            PostgreSqlType::Bool => {
+                return match self {
+                    Value::Null | Value::Bool(_) => Ok(self),
+                    Value::Text(text) => match text.trim().to_lowercase().as_str() {
+                        "t" | "true" => Ok(Value::Bool(true)),
+                        "f" | "false" => Ok(Value::Bool(false)),
+                        _ => Err(invalid_input(pg_type, &text)),
+                    },
+                    other => Err(assignment_mismatch(&other, pg_type)),
+                }
+            }
+            _ => {
+                return match self {
+                    Value::Null => Ok(Value::Null),
+                    other => Ok(Value::Text(other.to_text())),
+                }
+            }
+        };
+        let value = match self {
+            Value::Null => return Ok(Value::Null),
+            Value::Integer(value) => value,
+            Value::Text(text) => text.trim().parse().map_err(|_| invalid_input(pg_type, &text))?,
+            other => return Err(assignment_mismatch(&other, pg_type)),
+        };
+        if value < min || value > max {
+            Err(QueryError::value_out_of_range(pg_type))
+        } else {
+            Ok(Value::Integer(value))
+        }
+    }
+}
+
+/// Field of the text representation of a row, NULL is empty and a field that
+/// is empty or contains delimiters is quoted
+fn row_field(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        value => {
+            let text = value.to_text();
+            if text.is_empty() || text.contains(|c: char| "(),\"\\".contains(c) || c.is_whitespace()) {
+                format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\"\""))
+            } else {
+                text
+            }
+        }
+    }
+}
+
+fn invalid_input(pg_type: PostgreSqlType, text: &str) -> QueryError {
+    QueryError::datatype_mismatch(format!("invalid input syntax for type {}: \"{}\"", pg_type, text))
+}
+
+fn assignment_mismatch(value: &Value, pg_type: PostgreSqlType) -> QueryError {
+    QueryError::datatype_mismatch(format!(
+        "value of type {} can't be assigned to type {}",
+        value.type_name(),
+        pg_type
+    ))
+}
+
+/// Executes `function` with `args` that match its parameters. The function
+/// calls other functions of `functions` and its loops stop once their
+/// statement is interrupted.
+pub(crate) fn call(function: &Function, args: Vec<Value>, functions: &ScalarFunctions) -> Result<Value, QueryError> {
+    let returns = match function.returns {
+        Returns::Type(returns) => returns,
+        Returns::EventTrigger => return Err(QueryError::trigger_function_call()),
+    };
+    let block = match &function.body {
+        Body::Block(block, _source) => block,
+        Body::Wasm(wasm) => return wasm.call(&function.params, returns, args)?.cast(returns),
+        Body::Native(native) => {
+            let args = function
+                .params
+                .iter()
+                .zip(args)
+                .map(|(param, arg)| arg.cast(param.pg_type))
+                .collect::<Result<Vec<Value>, QueryError>>()?;
+            return native.call(args)?.cast(returns);
+        }
+    };
+    let mut no_sql = NoSql(Some(functions));
+    let mut frame = Frame::new(&mut no_sql);
+    for (param, arg) in function.params.iter().zip(args) {
+        frame.declare(&param.name, param.pg_type, arg)?;
+    }
+    match frame.run_block(block)? {
+        Flow::Return(value) => value.cast(returns),
+        Flow::Next | Flow::Exit | Flow::Continue => Err(QueryError::function_without_return()),
+    }
+}
+
+/// Executes event trigger `function` for `event` of a command with `tag`,
+/// e.g. `ddl_command_start` of `DROP TABLE`
+pub(crate) fn fire(function: &Function, event: &str, tag: &str, functions: &ScalarFunctions) -> Result<(), QueryError> {
+    let block = match &function.body {
+        Body::Block(block, _source) => block,
+        Body::Wasm(_) | Body::Native(_) => return Err(QueryError::trigger_function_call()),
+    };
+    let mut no_sql = NoSql(Some(functions));
+    let mut frame = Frame::new(&mut no_sql);
+    frame.declare("tg_event", PostgreSqlType::VarChar, Value::Text(event.to_owned()))?;
+    frame.declare("tg_tag", PostgreSqlType::VarChar, Value::Text(tag.to_owned()))?;
+    frame.run_block(block).map(|_flow| ())
+}
+
+/// Executes block of a `DO` statement, its SQL statements are executed by
+/// `executor`
+pub(crate) fn execute_block(block: &Block, executor: &mut dyn SqlExecutor) -> Result<(), QueryError> {
+    Frame::new(executor).run_block(block).map(|_flow| ())
+}
+
+/// Functions are executed outside of a session, they can't execute SQL but
+/// call other functions and are interrupted with the statement that calls
+/// them
+struct NoSql<'f>(Option<&'f ScalarFunctions>);
+
+impl SqlExecutor for NoSql<'_> {
+    fn execute(&mut self, _sql: &str) -> Result<(), QueryError> {
+        Err(QueryError::feature_not_supported(
+            "SQL statements in functions".to_owned(),
+        ))
+    }
+
+    fn interrupted(&self) -> Result<(), QueryError> {
+        self.0.map_or(Ok(()), |functions| functions.cancellation().check())
+    }
+
+    fn functions(&self) -> Option<&ScalarFunctions> {
+        self.0
+    }
+}
+
+/// What happens after a statement is executed
+enum Flow {
+    Next,
+    Exit,
+    Continue,
+    Return(Value),
+}
+
+/// Variables of a function call with their types
+struct Frame<'e> {
+    variables: HashMap<String, (PostgreSqlType, Value)>,
+    executor: &'e mut dyn SqlExecutor,
+}
+
+impl<'e> Frame<'e> {
+    fn new(executor: &'e mut dyn SqlExecutor) -> Frame<'e> {
+        Frame {
+            variables: HashMap::new(),
+            executor,
+        }
+    }
+
+    fn declare(&mut self, name: &str, pg_type: PostgreSqlType, value: Value) -> Result<(), QueryError> {
+        let value = value.cast(pg_type)?;
+        self.variables.insert(name.to_owned(), (pg_type, value));
+        Ok(())
+    }
+
+    /// Variables that `block` declares are visible only inside of it and
+    /// its exception handlers
+    fn run_block(&mut self, block: &Block) -> Result<Flow, QueryError> {
+        let mut names = block
+            .declarations
+            .iter()
+            .map(|(variable, _default)| variable.name.as_str())
+            .collect::<Vec<&str>>();
+        if !block.handlers.is_empty() {
+            names.extend(&["sqlstate", "sqlerrm"]);
+        }
+        let outer = names
+            .into_iter()
+            .map(|name| (name.to_owned(), self.variables.get(name).cloned()))
+            .collect::<Vec<(String, Option<(PostgreSqlType, Value)>)>>();
+        let result = match self.run_statements(block) {
+            Err(error) => self.handle(block, error),
+            flow => flow,
+        };
+        for (name, variable) in outer {
+            match variable {
+                Some(variable) => self.variables.insert(name, variable),
+                None => self.variables.remove(&name),
+            };
+        }
+        result
+    }
+
+    fn run_statements(&mut self, block: &Block) -> Result<Flow, QueryError> {
+        for (variable, default) in &block.declarations {
+            let value = match default {
+                Some(default) => self.eval(default)?,
+                None => Value::Null,
+            };
+            self.declare(&variable.name, variable.pg_type, value)?;
+        }
+        self.execute(&block.statements)
+    }
+
+    /// Executes the first handler of `block` that catches `error`. Changes
+    /// that SQL statements made before the error are not rolled back.
+    fn handle(&mut self, block: &Block, error: QueryError) -> Result<Flow, QueryError> {
+        let handler = block.handlers.iter().find(|handler| {
+            handler.conditions.iter().any(|condition| match condition {
+                Condition::Others => true,
+                Condition::SqlState(code) => code == error.sql_state(),
+            })
+        });
+        match handler {
+            Some(handler) => {
+                self.declare(
+                    "sqlstate",
+                    PostgreSqlType::VarChar,
+                    Value::Text(error.sql_state().to_owned()),
+                )?;
+                self.declare("sqlerrm", PostgreSqlType::VarChar, Value::Text(error.to_string()))?;
+                self.execute(&handler.statements)
+            }
+            None => Err(error),
+        }
+    }
+
+    fn execute(&mut self, statements: &[Statement]) -> Result<Flow, QueryError> {
+        for statement in statements {
+            let flow = match statement {
+                Statement::Assign { name, value } => {
+                    let value = self.eval(value)?;
+                    match self.variables.get_mut(name) {
+                        Some((pg_type, variable)) => *variable = value.cast(*pg_type)?,
+                        None => return Err(QueryError::column_does_not_exist(vec![name.clone()])),
+                    }
+                    Flow::Next
+                }
+                Statement::If { branches, otherwise } => {
+                    let mut taken = otherwise;
+                    for (condition, statements) in branches {
+                        if self.condition(condition, "IF")? {
+                            taken = statements;
+                            break;
+                        }
+                    }
+                    self.execute(taken)?
+                }
+                Statement::While { condition, body } => {
+                    let mut flow = Flow::Next;
+                    while self.condition(condition, "WHILE")? {
+                        self.executor.interrupted()?;
+                        match self.execute(body)? {
+                            Flow::Next | Flow::Continue => {}
+                            Flow::Exit => break,
+                            returned => {
+                                flow = returned;
+                                break;
+                            }
+                        }
+                    }
+                    flow
+                }
+                Statement::Loop(body) => loop {
+                    self.executor.interrupted()?;
+                    match self.execute(body)? {
+                        Flow::Next | Flow::Continue => {}
+                        Flow::Exit => break Flow::Next,
+                        returned => break returned,
+                    }
+                },
+                Statement::Exit(condition) => match condition {
+                    Some(condition) if !self.condition(condition, "EXIT WHEN")? => Flow::Next,
+                    _ => Flow::Exit,
+                },
+                Statement::Continue(condition) => match condition {
+                    Some(condition) if !self.condition(condition, "CONTINUE WHEN")? => Flow::Next,
+                    _ => Flow::Continue,
+                },
+                Statement::Null => Flow::Next,
+                Statement::Block(block) => self.run_block(block)?,
+                Statement::Sql(sql) => {
+                    self.executor.execute(sql)?;
+                    Flow::Next
+                }
+                Statement::Execute(command) => {
+                    match self.eval(command)? {
+                        Value::Text(sql) => self.executor.execute(&sql)?,
+                        other => {
+                            return Err(QueryError::datatype_mismatch(format!(
+                                "query string argument of EXECUTE must be type text, not type {}",
+                                other.type_name()
+                            )))
+                        }
+                    }
+                    Flow::Next
+                }
+                Statement::Return(Some(value)) => Flow::Return(self.eval(value)?),
+                Statement::Return(None) => Flow::Return(Value::Null),
+                Statement::Raise { level, format, args } => {
+                    self.raise(*level, format, args)?;
+                    Flow::Next
+                }
+            };
+            if let Flow::Next = flow {
+                continue;
+            }
+            return Ok(flow);
+        }
+        Ok(Flow::Next)
+    }
+
+    /// Messages below `EXCEPTION` level are written to the server log
+    fn raise(&self, level: RaiseLevel, format: &str, args: &[Expr]) -> Result<(), QueryError> {
+        let mut args = args.iter();
+        let mut message = String::new();
+        for (index, part) in format.split('%').enumerate() {
+            if index > 0 {
+                match args.next() {
+                    Some(arg) => message.push_str(&self.eval(arg)?.to_text()),
+                    None => message.push('%'),
+                }
+            }
+            message.push_str(part);
+        }
+        match level {
+            RaiseLevel::Debug => log::debug!("{}", message),
+            RaiseLevel::Log | RaiseLevel::Info | RaiseLevel::Notice => log::info!("{}", message),
+            RaiseLevel::Warning => log::warn!("{}", message),
+            RaiseLevel::Exception => return Err(QueryError::raise_exception(message)),
+        }
+        Ok(())
+    }
+
+    /// `NULL` conditions do not hold
+    fn condition(&self, expr: &Expr, construct: &str) -> Result<bool, QueryError> {
+        match self.eval(expr)? {
+            Value::Bool(value) => Ok(value),
+            Value::Null => Ok(false),
+            other => Err(QueryError::datatype_mismatch(format!(
+                "argument of {} must be type boolean, not type {}",
+                construct,
+                other.type_name()
+            ))),
+        }
+    }
+
+    fn eval(&self, expr: &Expr) -> Result<Value, QueryError> {
+        match expr {
+            Expr::Identifier(ident) => {
+                let name = identifier(ident);
+                match self.variables.get(&name) {
+                    Some((_pg_type, value)) => Ok(value.clone()),
+                    None => Err(QueryError::column_does_not_exist(vec![name])),
+                }
+            }
+            Expr::Value(Literal::Number(number)) => {
+                let number = number.to_string();
+                if number.contains(|c: char| !c.is_ascii_digit()) {
+                    return Err(QueryError::datatype_mismatch(format!(
+                        "numeric value {} is not supported in functions",
+                        number
+                    )));
+                }
+                number
+                    .parse()
+                    .map(Value::Integer)
+                    .map_err(|_| QueryError::value_out_of_range(PostgreSqlType::BigInt))
+            }
+            Expr::Value(Literal::SingleQuotedString(text)) => Ok(Value::Text(text.clone())),
+            Expr::Value(Literal::Boolean(value)) => Ok(Value::Bool(*value)),
+            Expr::Value(Literal::Null) => Ok(Value::Null),
+            Expr::Nested(expr) => self.eval(expr),
+            Expr::IsNull(expr) => Ok(Value::Bool(self.eval(expr)? == Value::Null)),
+            Expr::IsNotNull(expr) => Ok(Value::Bool(self.eval(expr)? != Value::Null)),
+            Expr::UnaryOp { op, expr } => match (op, self.eval(expr)?) {
+                (_, Value::Null) => Ok(Value::Null),
+                (UnaryOperator::Plus, Value::Integer(value)) => Ok(Value::Integer(value)),
+                (UnaryOperator::Minus, Value::Integer(value)) => value
+                    .checked_neg()
+                    .map(Value::Integer)
+                    .ok_or_else(|| QueryError::value_out_of_range(PostgreSqlType::BigInt)),
+                (UnaryOperator::Not, Value::Bool(value)) => Ok(Value::Bool(!value)),
+                (op, value) => Err(QueryError::undefined_function(
+                    op.to_string(),
+                    String::new(),
+                    value.type_name().to_owned(),
+                )),
+            },
+            Expr::BinaryOp { left, op, right } => self.eval_binary(left, op, right),
+            Expr::Function(function)
+                if function.name.0.len() == 1
+                    && identifier(&function.name.0[0]) == "row"
+                    && function.over.is_none()
+                    && !function.distinct =>
+            {
+                function
+                    .args
+                    .iter()
+                    .map(|arg| self.eval(arg))
+                    .collect::<Result<Vec<Value>, QueryError>>()
+                    .map(Value::Row)
+            }
+            Expr::Function(function) if function.over.is_none() && !function.distinct => self.call(function),
+            Expr::InList { expr, list, negated } => {
+                let value = self.eval(expr)?;
+                let mut result = Value::Bool(false);
+                for item in list {
+                    match apply(&BinaryOperator::Eq, value.clone(), self.eval(item)?)? {
+                        Value::Bool(true) => {
+                            result = Value::Bool(true);
+                            break;
+                        }
+                        Value::Null => result = Value::Null,
+                        _ => {}
+                    }
+                }
+                match result {
+                    Value::Bool(found) => Ok(Value::Bool(found != *negated)),
+                    other => Ok(other),
+                }
+            }
+            other => Err(QueryError::syntax_error(other.to_string())),
+        }
+    }
+
+    /// Call of the overload of a scalar function whose parameters accept
+    /// the arguments, text is accepted as any type like string literals
+    fn call(&self, call: &Call) -> Result<Value, QueryError> {
+        let name = match function_name(&call.name) {
+            Some(name) => name,
+            None => return Err(QueryError::syntax_error(call.to_string())),
+        };
+        let functions = match self.executor.functions() {
+            Some(functions) => functions,
+            None => return Err(QueryError::feature_not_supported(call.to_string())),
+        };
+        let args = call
+            .args
+            .iter()
+            .map(|arg| self.eval(arg))
+            .collect::<Result<Vec<Value>, QueryError>>()?;
+        let function = functions.overloads(&name).into_iter().find(|function| {
+            function.params().len() == args.len()
+                && function
+                    .params()
+                    .iter()
+                    .zip(&args)
+                    .all(|(param, arg)| arg.accepted_as(param))
+        });
+        match function {
+            Some(function) => {
+                let args = args
+                    .into_iter()
+                    .zip(function.params())
+                    .map(|(arg, param)| arg.into_scalar(param))
+                    .collect::<Result<Vec<Scalar>, QueryError>>()?;
+                function.call(&args).map(Value::from_scalar)
+            }
+            None => Err(QueryError::function_does_not_exist(format!(
+                "{}({})",
+                name,
+                args.iter().map(Value::type_name).collect::<Vec<&str>>().join(", ")
+            ))),
+        }
+    }
+
+    fn eval_binary(&self, left: &Expr, op: &BinaryOperator, right: &Expr) -> Result<Value, QueryError> {
+        let left = self.eval(left)?;
+        match (op, &left) {
+            (BinaryOperator::And, Value::Bool(false)) => return Ok(Value::Bool(false)),
+            (BinaryOperator::Or, Value::Bool(true)) => return Ok(Value::Bool(true)),
+            _ => {}
+        }
+        let right = self.eval(right)?;
+        apply(op, left, right)
+    }
+}
+
+/// Applies `op` to evaluated operands
+fn apply(op: &BinaryOperator, left: Value, right: Value) -> Result<Value, QueryError> {
+    let result = match (op, left, right) {
+        (BinaryOperator::And, Value::Null, Value::Bool(false)) => Value::Bool(false),
+        (BinaryOperator::Or, Value::Null, Value::Bool(true)) => Value::Bool(true),
+        (BinaryOperator::And, Value::Bool(_), right) | (BinaryOperator::Or, Value::Bool(_), right)
+            if right == Value::Null || matches!(right, Value::Bool(_)) =>
+        {
+            right
+        }
+        (op, Value::Row(left), Value::Row(right)) => compare_rows(op, left, right)?,
+        (_, Value::Null, _) | (_, _, Value::Null) => Value::Null,
+        (BinaryOperator::StringConcat, left, right) => Value::Text(left.to_text() + &right.to_text()),
+        (op, Value::Integer(left), Value::Integer(right)) => arithmetic(op, left, right)?,
+        (op, Value::Text(left), Value::Text(right)) => compare(op, left.cmp(&right), "text")?,
+        (op, Value::Bool(left), Value::Bool(right)) => compare(op, left.cmp(&right), "boolean")?,
+        (op, left, right) => {
+            return Err(QueryError::undefined_function(
+                op.to_string(),
+                left.type_name().to_owned(),
+                right.type_name().to_owned(),
+            ))
+        }
+    };
+    Ok(result)
+}
+
+fn arithmetic(op: &BinaryOperator, left: i64, right: i64) -> Result<Value, QueryError> {
+    let result = match op {
+        BinaryOperator::Plus => left.checked_add(right),
+        BinaryOperator::Minus => left.checked_sub(right),
+        BinaryOperator::Multiply => left.checked_mul(right),
+        BinaryOperator::Divide | BinaryOperator::Modulus if right == 0 => return Err(QueryError::division_by_zero()),
+        BinaryOperator::Divide => left.checked_div(right),
+        BinaryOperator::Modulus => left.checked_rem(right),
+        op => return compare(op, left.cmp(&right), "integer"),
+    };
+    result
+        .map(Value::Integer)
+        .ok_or_else(|| QueryError::value_out_of_range(PostgreSqlType::BigInt))
+}
+
+/// Rows are equal if all their fields are equal and are ordered by the
+/// first pair of fields that are not equal. A NULL field makes the result
+/// NULL unless it is decided by fields before it, or by any unequal field for
+/// `=` and `<>`.
+fn compare_rows(op: &BinaryOperator, left: Vec<Value>, right: Vec<Value>) -> Result<Value, QueryError> {
+    if left.len() != right.len() {
+        return Err(QueryError::datatype_mismatch(
+            "unequal number of entries in row expressions".to_owned(),
+        ));
+    }
+    match op {
+        BinaryOperator::Eq | BinaryOperator::NotEq => {
+            let mut result = Value::Bool(true);
+            for (left, right) in left.into_iter().zip(right) {
+                match apply(&BinaryOperator::Eq, left, right)? {
+                    Value::Bool(false) => {
+                        result = Value::Bool(false);
+                        break;
+                    }
+                    Value::Null => result = Value::Null,
+                    _ => {}
+                }
+            }
+            match (op, result) {
+                (BinaryOperator::NotEq, Value::Bool(equal)) => Ok(Value::Bool(!equal)),
+                (_, result) => Ok(result),
+            }
+        }
+        BinaryOperator::Lt | BinaryOperator::LtEq | BinaryOperator::Gt | BinaryOperator::GtEq => {
+            for (left, right) in left.into_iter().zip(right) {
+                match apply(&BinaryOperator::Eq, left.clone(), right.clone())? {
+                    Value::Bool(true) => {}
+                    Value::Bool(false) => return apply(op, left, right),
+                    _ => return Ok(Value::Null),
+                }
+            }
+            Ok(Value::Bool(matches!(op, BinaryOperator::LtEq | BinaryOperator::GtEq)))
+        }
+        op => Err(QueryError::undefined_function(
+            op.to_string(),
+            "record".to_owned(),
+            "record".to_owned(),
+        )),
+    }
+}
+
+/// Comparison of operands of the same type
+fn compare(op: &BinaryOperator, ordering: Ordering, type_name: &str) -> Result<Value, QueryError> {
+    let result = match op {
+        BinaryOperator::Eq => ordering == Ordering::Equal,
+        BinaryOperator::NotEq => ordering != Ordering::Equal,
+        BinaryOperator::Lt => ordering == Ordering::Less,
+        BinaryOperator::LtEq => ordering != Ordering::Greater,
+        BinaryOperator::Gt => ordering == Ordering::Greater,
+        BinaryOperator::GtEq => ordering != Ordering::Less,
+        op => {
+            return Err(QueryError::undefined_function(
+                op.to_string(),
+                type_name.to_owned(),
+                type_name.to_owned(),
+            ))
+        }
+    };
+    Ok(Value::Bool(result))
+}
@@ -0,0 +1,223 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Minimal procedural language of functions. Bodies are parsed once when a
+//! function is created and interpreted on every call:
+//!
+//! ```sql
+//! CREATE FUNCTION factorial(n integer) RETURNS bigint AS $$
+//! DECLARE
+//!     result bigint := 1;
+//! BEGIN
+//!     WHILE n > 1 LOOP
+//!         result := result * n;
+//!         n := n - 1;
+//!     END LOOP;
+//!     RETURN result;
+//! END
+//! $$ LANGUAGE plpgsql;
+//! ```
+//!
+//! Functions that return `event_trigger` can't be called directly, they are
+//! fired by event triggers with `tg_event` and `tg_tag` variables set.
+//!
+//! `DO` executes a block once without creating a function. Besides the
+//! statements of the language, its blocks can contain SQL statements and
+//! `EXECUTE 'sql'` of dynamically built ones:
+//!
+//! ```sql
+//! DO $$
+//! BEGIN
+//!     CREATE SCHEMA app;
+//! EXCEPTION
+//!     WHEN duplicate_schema THEN
+//!         RAISE NOTICE 'schema app already exists';
+//! END
+//! $$;
+//! ```
+use crate::{
+    dml::{aggregation::column_type, functions::ScalarFunctions},
+    extension::NativeBody,
+};
+use protocol::{results::QueryError, sql_types::PostgreSqlType};
+use sql_types::SqlType;
+use sqlparser::ast::{Expr, Ident};
+use std::sync::Arc;
+
+mod aggregate;
+mod interpreter;
+mod parser;
+mod session;
+pub(crate) mod wasm;
+
+pub(crate) use aggregate::Aggregate;
+pub(crate) use interpreter::{call, execute_block, fire, Value};
+pub(crate) use parser::{parse_body, parse_type};
+pub(crate) use session::SessionExecutor;
+pub(crate) use wasm::WasmFunction;
+
+/// Name of the language in `CREATE FUNCTION ... LANGUAGE`
+pub const LANGUAGE: &str = "plpgsql";
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Function {
+    pub(crate) name: String,
+    pub(crate) params: Vec<Variable>,
+    pub(crate) returns: Returns,
+    pub(crate) body: Body,
+}
+
+/// Block of the procedural language with its source, an exported function
+/// of a WebAssembly module or a function of a native extension
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Body {
+    Block(Block, String),
+    Wasm(WasmFunction),
+    Native(NativeBody),
+}
+
+/// `RETURNS type` or `RETURNS event_trigger`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Returns {
+    Type(PostgreSqlType),
+    EventTrigger,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Variable {
+    pub(crate) name: String,
+    pub(crate) pg_type: PostgreSqlType,
+}
+
+/// `[DECLARE declarations] BEGIN statements [EXCEPTION handlers] END`
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Block {
+    pub(crate) declarations: Vec<(Variable, Option<Expr>)>,
+    pub(crate) statements: Vec<Statement>,
+    pub(crate) handlers: Vec<Handler>,
+}
+
+/// `WHEN condition [OR condition ...] THEN statements`, `sqlstate` and
+/// `sqlerrm` variables hold code and message of the caught error
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Handler {
+    pub(crate) conditions: Vec<Condition>,
+    pub(crate) statements: Vec<Statement>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Condition {
+    /// `OTHERS` catches any error
+    Others,
+    /// `SQLSTATE 'code'` or name of a condition, e.g. `duplicate_table`
+    SqlState(String),
+}
+
+/// Executes SQL statements of blocks on behalf of the interpreter
+pub(crate) trait SqlExecutor {
+    fn execute(&mut self, sql: &str) -> Result<(), QueryError>;
+
+    /// Error if the statement that runs the block was cancelled, loops of
+    /// the block check it on each iteration
+    fn interrupted(&self) -> Result<(), QueryError> {
+        Ok(())
+    }
+
+    /// Functions that the block calls, `None` if it can't call any
+    fn functions(&self) -> Option<&ScalarFunctions> {
+        None
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Statement {
+    /// `name := value`
+    Assign { name: String, value: Expr },
+    /// `IF condition THEN ... [ELSIF condition THEN ...] [ELSE ...] END IF`
+    If {
+        branches: Vec<(Expr, Vec<Statement>)>,
+        otherwise: Vec<Statement>,
+    },
+    /// `WHILE condition LOOP ... END LOOP`
+    While { condition: Expr, body: Vec<Statement> },
+    /// `LOOP ... END LOOP`
+    Loop(Vec<Statement>),
+    /// `EXIT [WHEN condition]`
+    Exit(Option<Expr>),
+    /// `CONTINUE [WHEN condition]`
+    Continue(Option<Expr>),
+    /// `NULL` that does nothing
+    Null,
+    /// nested `[DECLARE ...] BEGIN ... END` block
+    Block(Block),
+    /// SQL statement, e.g. `CREATE TABLE`, executed as it is
+    Sql(String),
+    /// `EXECUTE command` where command is a text of an SQL statement
+    Execute(Expr),
+    /// `RETURN [value]`
+    Return(Option<Expr>),
+    /// `RAISE [level] 'format' [, argument ...]`, `%` in the format is
+    /// replaced with the next argument
+    Raise {
+        level: RaiseLevel,
+        format: String,
+        args: Vec<Expr>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum RaiseLevel {
+    Debug,
+    Log,
+    Info,
+    Notice,
+    Warning,
+    Exception,
+}
+
+/// Registers `function` among scalar functions of statements, functions
+/// that return `event_trigger` fail when they are called
+pub(crate) fn register(function: Arc<Function>, functions: &ScalarFunctions) {
+    let name = function.name.clone();
+    let params = parameter_types(&function);
+    match function.returns {
+        Returns::Type(returns) => {
+            let returns = column_type(returns);
+            functions.register_called_on_null(&name, &params, returns, move |functions, args| {
+                let args = args.iter().cloned().map(Value::from_scalar).collect();
+                call(&function, args, &functions.nested()?)?.into_scalar(&returns)
+            })
+        }
+        Returns::EventTrigger => functions.register(&name, &params, SqlType::VarChar(u64::MAX), |_functions, _args| {
+            Err(QueryError::trigger_function_call())
+        }),
+    }
+}
+
+/// Removes `function` from scalar functions of statements
+pub(crate) fn unregister(function: &Function, functions: &ScalarFunctions) {
+    functions.unregister(&function.name, &parameter_types(function));
+}
+
+fn parameter_types(function: &Function) -> Vec<SqlType> {
+    function.params.iter().map(|param| column_type(param.pg_type)).collect()
+}
+
+/// Unquoted identifiers are case insensitive
+pub(crate) fn identifier(ident: &Ident) -> String {
+    match ident.quote_style {
+        None => ident.value.to_lowercase(),
+        Some(_) => ident.value.clone(),
+    }
+}
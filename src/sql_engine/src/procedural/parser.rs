@@ -0,0 +1,617 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{identifier, Block, Condition, Handler, RaiseLevel, Statement, Variable};
+use protocol::sql_types::PostgreSqlType;
+use sqlparser::{
+    ast::{DataType, Expr},
+    dialect::{keywords::Keyword, PostgreSqlDialect},
+    parser::{Parser, ParserError},
+    tokenizer::{Token, Tokenizer, Word},
+};
+
+/// Names of conditions that exception handlers can catch with their codes
+const CONDITIONS: &[(&str, &str)] = &[
+    ("feature_not_supported", "0A000"),
+    ("numeric_value_out_of_range", "22003"),
+    ("division_by_zero", "22012"),
+    ("invalid_schema_name", "3F000"),
+    ("insufficient_privilege", "42501"),
+    ("syntax_error", "42601"),
+    ("undefined_column", "42703"),
+    ("datatype_mismatch", "42804"),
+    ("undefined_function", "42883"),
+    ("undefined_table", "42P01"),
+    ("undefined_object", "42704"),
+    ("duplicate_schema", "42P06"),
+    ("duplicate_table", "42P07"),
+    ("duplicate_object", "42710"),
+    ("duplicate_function", "42723"),
+    ("raise_exception", "P0001"),
+];
+
+/// Parses body of a function or a `DO` statement
+/// `[DECLARE name type [:= value]; ...] BEGIN ... END`
+pub(crate) fn parse_body(body: &str) -> Result<Block, ParserError> {
+    let tokens = Tokenizer::new(&PostgreSqlDialect {}, body)
+        .tokenize()
+        .map_err(|error| ParserError::TokenizerError(error.message))?;
+    let mut parser = Parser::new(row_constructors(tokens));
+    let block = parse_block(&mut parser)?;
+    let _ = parser.consume_token(&Token::SemiColon);
+    match parser.next_token() {
+        Token::EOF => Ok(block),
+        unexpected => Err(ParserError::ParserError(format!(
+            "Expected end of function body, found: {}",
+            unexpected
+        ))),
+    }
+}
+
+/// Words after which a parenthesized list starts an expression, e.g.
+/// `IF (a, b) = (1, 2)`, rather than being arguments of a function or a list
+/// of `IN` or `VALUES`
+const EXPRESSION_STARTS: &[&str] = &[
+    "AND", "CASE", "ELSE", "ELSIF", "HAVING", "IF", "NOT", "ON", "OR", "RETURN", "SELECT", "THEN", "WHEN", "WHERE",
+    "WHILE",
+];
+
+/// `sqlparser` does not parse row constructors without the `ROW` keyword, so
+/// it is inserted before parenthesized lists of two or more expressions that
+/// are in a position of an expression
+fn row_constructors(tokens: Vec<Token>) -> Vec<Token> {
+    let mut rewritten = Vec::with_capacity(tokens.len());
+    for (index, token) in tokens.iter().enumerate() {
+        if *token == Token::LParen && is_list(&tokens[index + 1..]) {
+            let previous = rewritten
+                .iter()
+                .rev()
+                .find(|token| !matches!(token, Token::Whitespace(_)));
+            let starts_expression = match previous {
+                None => true,
+                Some(Token::Word(Word { value, quote_style, .. })) => {
+                    quote_style.is_none() && EXPRESSION_STARTS.contains(&value.to_uppercase().as_str())
+                }
+                Some(Token::RParen) | Some(Token::Number(_)) | Some(Token::SingleQuotedString(_)) => false,
+                Some(_) => true,
+            };
+            if starts_expression {
+                rewritten.push(Token::make_word("ROW", None));
+            }
+        }
+        rewritten.push(token.clone());
+    }
+    rewritten
+}
+
+/// Whether tokens after `(` up to the matching `)` have a comma that is not
+/// nested in other parentheses
+fn is_list(tokens: &[Token]) -> bool {
+    let mut depth = 0;
+    for token in tokens {
+        match token {
+            Token::LParen => depth += 1,
+            Token::RParen if depth == 0 => return false,
+            Token::RParen => depth -= 1,
+            Token::Comma if depth == 0 => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+fn parse_block(parser: &mut Parser) -> Result<Block, ParserError> {
+    let mut declarations = vec![];
+    if parse_word(parser, "DECLARE") {
+        while !peek_word(parser, "BEGIN") {
+            let name = identifier(&parser.parse_identifier()?);
+            let pg_type = parse_type(&parser.parse_data_type()?)?;
+            let default = if parse_assignment(parser) || parser.parse_keyword(Keyword::DEFAULT) {
+                Some(parser.parse_expr()?)
+            } else {
+                None
+            };
+            parser.expect_token(&Token::SemiColon)?;
+            declarations.push((Variable { name, pg_type }, default));
+        }
+    }
+    expect_word(parser, "BEGIN")?;
+    let statements = parse_statements(parser, &["EXCEPTION", "END"])?;
+    let mut handlers = vec![];
+    if parse_word(parser, "EXCEPTION") {
+        while parse_word(parser, "WHEN") {
+            let mut conditions = vec![parse_condition(parser)?];
+            while parser.parse_keyword(Keyword::OR) {
+                conditions.push(parse_condition(parser)?);
+            }
+            expect_word(parser, "THEN")?;
+            let statements = parse_statements(parser, &["WHEN", "END"])?;
+            handlers.push(Handler { conditions, statements });
+        }
+        if handlers.is_empty() {
+            return Err(ParserError::ParserError(format!(
+                "Expected WHEN, found: {}",
+                parser.peek_token()
+            )));
+        }
+    }
+    expect_word(parser, "END")?;
+    Ok(Block {
+        declarations,
+        statements,
+        handlers,
+    })
+}
+
+/// `OTHERS`, `SQLSTATE 'code'` or name of a condition
+fn parse_condition(parser: &mut Parser) -> Result<Condition, ParserError> {
+    if parse_word(parser, "OTHERS") {
+        return Ok(Condition::Others);
+    }
+    if parse_word(parser, "SQLSTATE") {
+        return parser.parse_literal_string().map(Condition::SqlState);
+    }
+    let name = identifier(&parser.parse_identifier()?);
+    CONDITIONS
+        .iter()
+        .find(|(condition, _code)| *condition == name)
+        .map(|(_condition, code)| Condition::SqlState((*code).to_owned()))
+        .ok_or_else(|| ParserError::ParserError(format!("unrecognized exception condition \"{}\"", name)))
+}
+
+/// Types of arguments, results and variables of functions
+pub(crate) fn parse_type(data_type: &DataType) -> Result<PostgreSqlType, ParserError> {
+    match data_type {
+        DataType::Boolean => Ok(PostgreSqlType::Bool),
+        DataType::SmallInt => Ok(PostgreSqlType::SmallInt),
+        DataType::Int => Ok(PostgreSqlType::Integer),
+        DataType::BigInt => Ok(PostgreSqlType::BigInt),
+        DataType::Char(_) => Ok(PostgreSqlType::Char),
+        DataType::Varchar(_) | DataType::Text => Ok(PostgreSqlType::VarChar),
+        other => Err(ParserError::ParserError(format!(
+            "type {} is not supported in functions",
+            other
+        ))),
+    }
+}
+
+/// Statements up to one of `terminators` that is not consumed
+fn parse_statements(parser: &mut Parser, terminators: &[&str]) -> Result<Vec<Statement>, ParserError> {
+    let mut statements = vec![];
+    while !terminators.iter().any(|terminator| peek_word(parser, terminator)) {
+        if parser.peek_token() == Token::EOF {
+            return Err(ParserError::ParserError(format!(
+                "Expected {}, found: EOF",
+                terminators.join(" or ")
+            )));
+        }
+        statements.push(parse_statement(parser)?);
+    }
+    Ok(statements)
+}
+
+fn parse_statement(parser: &mut Parser) -> Result<Statement, ParserError> {
+    let statement = if parse_word(parser, "IF") {
+        let mut branches = vec![parse_branch(parser)?];
+        while parse_word(parser, "ELSIF") || parse_word(parser, "ELSEIF") {
+            branches.push(parse_branch(parser)?);
+        }
+        let otherwise = if parse_word(parser, "ELSE") {
+            parse_statements(parser, &["END"])?
+        } else {
+            vec![]
+        };
+        expect_words(parser, &["END", "IF"])?;
+        Statement::If { branches, otherwise }
+    } else if parse_word(parser, "WHILE") {
+        let condition = parser.parse_expr()?;
+        expect_word(parser, "LOOP")?;
+        Statement::While {
+            condition,
+            body: parse_loop_body(parser)?,
+        }
+    } else if parse_word(parser, "LOOP") {
+        Statement::Loop(parse_loop_body(parser)?)
+    } else if parse_word(parser, "EXIT") {
+        Statement::Exit(parse_when(parser)?)
+    } else if parse_word(parser, "CONTINUE") {
+        Statement::Continue(parse_when(parser)?)
+    } else if parse_word(parser, "RETURN") {
+        if parser.peek_token() == Token::SemiColon {
+            Statement::Return(None)
+        } else {
+            Statement::Return(Some(parser.parse_expr()?))
+        }
+    } else if parse_word(parser, "RAISE") {
+        parse_raise(parser)?
+    } else if peek_word(parser, "DECLARE") || peek_word(parser, "BEGIN") {
+        Statement::Block(parse_block(parser)?)
+    } else if parser.parse_keyword(Keyword::NULL) {
+        Statement::Null
+    } else if parse_word(parser, "EXECUTE") {
+        Statement::Execute(parser.parse_expr()?)
+    } else if is_assignment(parser) {
+        let name = identifier(&parser.parse_identifier()?);
+        if !parse_assignment(parser) {
+            parser.expect_token(&Token::Eq)?;
+        }
+        Statement::Assign {
+            name,
+            value: parser.parse_expr()?,
+        }
+    } else {
+        return parse_sql(parser);
+    };
+    parser.expect_token(&Token::SemiColon)?;
+    Ok(statement)
+}
+
+/// `name :=` or `name =`, anything else that starts with a word is an SQL
+/// statement
+fn is_assignment(parser: &Parser) -> bool {
+    match (parser.peek_token(), parser.peek_nth_token(1)) {
+        (Token::Word(_), Token::Colon) | (Token::Word(_), Token::Eq) => true,
+        (Token::Word(_), _) => false,
+        _ => true,
+    }
+}
+
+/// Text of an SQL statement up to `;` that is consumed
+fn parse_sql(parser: &mut Parser) -> Result<Statement, ParserError> {
+    let mut sql = parser.next_token().to_string();
+    loop {
+        match parser.next_token_no_skip() {
+            Some(Token::SemiColon) => return Ok(Statement::Sql(sql)),
+            Some(Token::SingleQuotedString(text)) => {
+                sql.push('\'');
+                sql.push_str(&text.replace('\'', "''"));
+                sql.push('\'');
+            }
+            Some(token) => sql.push_str(&token.to_string()),
+            None => return Err(ParserError::ParserError("Expected ;, found: EOF".to_owned())),
+        }
+    }
+}
+
+/// `[level] 'format' [, argument ...]`, the level is `EXCEPTION` by default
+fn parse_raise(parser: &mut Parser) -> Result<Statement, ParserError> {
+    let levels = [
+        ("DEBUG", RaiseLevel::Debug),
+        ("LOG", RaiseLevel::Log),
+        ("INFO", RaiseLevel::Info),
+        ("NOTICE", RaiseLevel::Notice),
+        ("WARNING", RaiseLevel::Warning),
+        ("EXCEPTION", RaiseLevel::Exception),
+    ];
+    let level = levels
+        .iter()
+        .find(|(word, _level)| parse_word(parser, word))
+        .map(|(_word, level)| *level)
+        .unwrap_or(RaiseLevel::Exception);
+    let format = match parser.next_token() {
+        Token::SingleQuotedString(format) => format,
+        unexpected => {
+            return Err(ParserError::ParserError(format!(
+                "Expected message format, found: {}",
+                unexpected
+            )))
+        }
+    };
+    let mut args = vec![];
+    while parser.consume_token(&Token::Comma) {
+        args.push(parser.parse_expr()?);
+    }
+    Ok(Statement::Raise { level, format, args })
+}
+
+/// `condition THEN statements`
+fn parse_branch(parser: &mut Parser) -> Result<(Expr, Vec<Statement>), ParserError> {
+    let condition = parser.parse_expr()?;
+    expect_word(parser, "THEN")?;
+    let statements = parse_statements(parser, &["ELSIF", "ELSEIF", "ELSE", "END"])?;
+    Ok((condition, statements))
+}
+
+/// `statements END LOOP`
+fn parse_loop_body(parser: &mut Parser) -> Result<Vec<Statement>, ParserError> {
+    let body = parse_statements(parser, &["END"])?;
+    expect_words(parser, &["END", "LOOP"])?;
+    Ok(body)
+}
+
+fn parse_when(parser: &mut Parser) -> Result<Option<Expr>, ParserError> {
+    if parse_word(parser, "WHEN") {
+        parser.parse_expr().map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+/// `:=`
+fn parse_assignment(parser: &mut Parser) -> bool {
+    if parser.peek_token() == Token::Colon && parser.peek_nth_token(1) == Token::Eq {
+        parser.next_token();
+        parser.next_token();
+        true
+    } else {
+        false
+    }
+}
+
+fn peek_word(parser: &Parser, expected: &str) -> bool {
+    match parser.peek_token() {
+        Token::Word(Word {
+            value,
+            quote_style: None,
+            ..
+        }) => value.to_uppercase() == expected,
+        _ => false,
+    }
+}
+
+fn parse_word(parser: &mut Parser, expected: &str) -> bool {
+    if peek_word(parser, expected) {
+        parser.next_token();
+        true
+    } else {
+        false
+    }
+}
+
+fn expect_word(parser: &mut Parser, expected: &str) -> Result<(), ParserError> {
+    if parse_word(parser, expected) {
+        Ok(())
+    } else {
+        Err(ParserError::ParserError(format!(
+            "Expected {}, found: {}",
+            expected,
+            parser.peek_token()
+        )))
+    }
+}
+
+fn expect_words(parser: &mut Parser, expected: &[&str]) -> Result<(), ParserError> {
+    expected.iter().try_for_each(|word| expect_word(parser, word))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlparser::ast::{BinaryOperator, Ident, Value};
+
+    fn identifier(name: &str) -> Expr {
+        Expr::Identifier(Ident::new(name))
+    }
+
+    fn number(value: i64) -> Expr {
+        Expr::Value(Value::Number(value.into()))
+    }
+
+    fn binary(left: Expr, op: BinaryOperator, right: Expr) -> Expr {
+        Expr::BinaryOp {
+            left: Box::new(left),
+            op,
+            right: Box::new(right),
+        }
+    }
+
+    #[test]
+    fn declarations_and_statements() {
+        assert_eq!(
+            parse_body(
+                "declare total integer := 0; Step smallint; \
+                 begin \
+                   while total < 10 loop total := total + step; end loop; \
+                   if total > 10 then return 1; elsif total = 10 then return 0; else return -1; end if; \
+                 end;"
+            ),
+            Ok(Block {
+                handlers: vec![],
+                declarations: vec![
+                    (
+                        Variable {
+                            name: "total".to_owned(),
+                            pg_type: PostgreSqlType::Integer
+                        },
+                        Some(number(0))
+                    ),
+                    (
+                        Variable {
+                            name: "step".to_owned(),
+                            pg_type: PostgreSqlType::SmallInt
+                        },
+                        None
+                    ),
+                ],
+                statements: vec![
+                    Statement::While {
+                        condition: binary(identifier("total"), BinaryOperator::Lt, number(10)),
+                        body: vec![Statement::Assign {
+                            name: "total".to_owned(),
+                            value: binary(identifier("total"), BinaryOperator::Plus, identifier("step"))
+                        }]
+                    },
+                    Statement::If {
+                        branches: vec![
+                            (
+                                binary(identifier("total"), BinaryOperator::Gt, number(10)),
+                                vec![Statement::Return(Some(number(1)))]
+                            ),
+                            (
+                                binary(identifier("total"), BinaryOperator::Eq, number(10)),
+                                vec![Statement::Return(Some(number(0)))]
+                            ),
+                        ],
+                        otherwise: vec![Statement::Return(Some(Expr::UnaryOp {
+                            op: sqlparser::ast::UnaryOperator::Minus,
+                            expr: Box::new(number(1))
+                        }))]
+                    }
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn loop_with_exit() {
+        assert_eq!(
+            parse_body("begin loop exit when done; continue; end loop; end"),
+            Ok(Block {
+                handlers: vec![],
+                declarations: vec![],
+                statements: vec![Statement::Loop(vec![
+                    Statement::Exit(Some(identifier("done"))),
+                    Statement::Continue(None)
+                ])]
+            })
+        );
+    }
+
+    #[test]
+    fn raise_and_bare_return() {
+        assert_eq!(
+            parse_body("begin raise notice 'fired % on %', tg_event, tg_tag; raise 'stop'; return; end"),
+            Ok(Block {
+                handlers: vec![],
+                declarations: vec![],
+                statements: vec![
+                    Statement::Raise {
+                        level: RaiseLevel::Notice,
+                        format: "fired % on %".to_owned(),
+                        args: vec![identifier("tg_event"), identifier("tg_tag")]
+                    },
+                    Statement::Raise {
+                        level: RaiseLevel::Exception,
+                        format: "stop".to_owned(),
+                        args: vec![]
+                    },
+                    Statement::Return(None)
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn sql_statements_and_handlers() {
+        assert_eq!(
+            parse_body(
+                "begin \
+                   create table s.t (name varchar(10) default 'it''s'); \
+                   execute 'drop table s.' || name; \
+                   begin drop schema s; end; \
+                 exception \
+                   when duplicate_table or sqlstate '42P06' then return; \
+                   when others then null; \
+                 end"
+            ),
+            Ok(Block {
+                declarations: vec![],
+                statements: vec![
+                    Statement::Sql("create table s.t (name varchar(10) default 'it''s')".to_owned()),
+                    Statement::Execute(binary(
+                        Expr::Value(Value::SingleQuotedString("drop table s.".to_owned())),
+                        BinaryOperator::StringConcat,
+                        identifier("name")
+                    )),
+                    Statement::Block(Block {
+                        declarations: vec![],
+                        statements: vec![Statement::Sql("drop schema s".to_owned())],
+                        handlers: vec![]
+                    })
+                ],
+                handlers: vec![
+                    Handler {
+                        conditions: vec![
+                            Condition::SqlState("42P07".to_owned()),
+                            Condition::SqlState("42P06".to_owned())
+                        ],
+                        statements: vec![Statement::Return(None)]
+                    },
+                    Handler {
+                        conditions: vec![Condition::Others],
+                        statements: vec![Statement::Null]
+                    }
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn row_constructors_in_expressions() {
+        fn function(name: &str, args: Vec<Expr>) -> Expr {
+            Expr::Function(sqlparser::ast::Function {
+                name: sqlparser::ast::ObjectName(vec![Ident::new(name)]),
+                args,
+                over: None,
+                distinct: false,
+            })
+        }
+
+        assert_eq!(
+            parse_body("begin if (a, 1) = (2, f(a, 3)) then return 1; end if; return a in (1, 2); end"),
+            Ok(Block {
+                declarations: vec![],
+                statements: vec![
+                    Statement::If {
+                        branches: vec![(
+                            binary(
+                                function("ROW", vec![identifier("a"), number(1)]),
+                                BinaryOperator::Eq,
+                                function("ROW", vec![number(2), function("f", vec![identifier("a"), number(3)])])
+                            ),
+                            vec![Statement::Return(Some(number(1)))]
+                        )],
+                        otherwise: vec![]
+                    },
+                    Statement::Return(Some(Expr::InList {
+                        expr: Box::new(identifier("a")),
+                        list: vec![number(1), number(2)],
+                        negated: false
+                    }))
+                ],
+                handlers: vec![]
+            })
+        );
+    }
+
+    #[test]
+    fn unknown_condition() {
+        assert_eq!(
+            parse_body("begin return 1; exception when no_such_thing then return 0; end"),
+            Err(ParserError::ParserError(
+                "unrecognized exception condition \"no_such_thing\"".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn unterminated_block() {
+        assert_eq!(
+            parse_body("begin return 1;"),
+            Err(ParserError::ParserError(
+                "Expected EXCEPTION or END, found: EOF".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn unsupported_type() {
+        assert_eq!(
+            parse_body("declare x real; begin return x; end"),
+            Err(ParserError::ParserError(
+                "type REAL is not supported in functions".to_owned()
+            ))
+        );
+    }
+}
@@ -0,0 +1,118 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{dml::functions::ScalarFunctions, procedural::SqlExecutor, transaction::Status, QueryExecutor};
+use kernel::SystemError;
+use protocol::{
+    results::{QueryError, QueryResult},
+    Sender,
+};
+use std::{
+    io,
+    sync::{Arc, Mutex},
+};
+
+/// Executes SQL statements of a `DO` block in a nested session of the same
+/// role. Results of the statements are discarded, the first error aborts
+/// the block unless it is caught by an exception handler. Inside of a
+/// transaction block each statement runs in its own subtransaction, so that
+/// a caught error reverts only changes of the failed statement.
+pub(crate) struct SessionExecutor {
+    session: QueryExecutor,
+    errors: Arc<FirstError>,
+    system_error: Option<SystemError>,
+    functions: ScalarFunctions,
+}
+
+impl SessionExecutor {
+    pub(crate) fn new(session: impl FnOnce(Arc<dyn Sender>) -> QueryExecutor) -> SessionExecutor {
+        let errors = Arc::new(FirstError::default());
+        let session = session(errors.clone());
+        let functions = session.functions();
+        SessionExecutor {
+            session,
+            errors,
+            system_error: None,
+            functions,
+        }
+    }
+
+    /// Error that has to stop the session, exception handlers of the block
+    /// can't catch it
+    pub(crate) fn system_error(self) -> Option<SystemError> {
+        self.system_error
+    }
+}
+
+impl SqlExecutor for SessionExecutor {
+    fn execute(&mut self, sql: &str) -> Result<(), QueryError> {
+        if self.system_error.is_none() {
+            let transaction = self.session.transaction.clone();
+            let subtransaction = transaction.status() == Status::InProgress
+                && transaction.savepoint(STATEMENT_SAVEPOINT.to_owned()).is_ok();
+            let executed = self.session.execute(sql).map(|()| {
+                let error = self.errors.take();
+                if subtransaction {
+                    if error.is_some() {
+                        let _ = transaction.rollback_to(STATEMENT_SAVEPOINT);
+                    }
+                    // the savepoint is gone if the statement ended the block
+                    let _ = transaction.release(STATEMENT_SAVEPOINT);
+                }
+                error
+            });
+            match executed {
+                Ok(Some(error)) => return Err(error),
+                Ok(None) => return Ok(()),
+                Err(error) => self.system_error = Some(error),
+            }
+        }
+        // never reaches the client, the system error is reported instead
+        Err(QueryError::io_error("statement of the block failed".to_owned()))
+    }
+
+    fn interrupted(&self) -> Result<(), QueryError> {
+        self.session.cancellation.check()
+    }
+
+    fn functions(&self) -> Option<&ScalarFunctions> {
+        Some(&self.functions)
+    }
+}
+
+/// Name of savepoints that start subtransactions of statements, it can't be
+/// used in SQL as identifiers are never empty
+const STATEMENT_SAVEPOINT: &str = "";
+
+#[derive(Default)]
+struct FirstError(Mutex<Option<QueryError>>);
+
+impl FirstError {
+    fn take(&self) -> Option<QueryError> {
+        self.0.lock().expect("to acquire lock").take()
+    }
+}
+
+impl Sender for FirstError {
+    fn flush(&self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn send(&self, query_result: QueryResult) -> io::Result<()> {
+        if let Err(error) = query_result {
+            self.0.lock().expect("to acquire lock").get_or_insert(error);
+        }
+        Ok(())
+    }
+}
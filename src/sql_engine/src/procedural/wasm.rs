@@ -0,0 +1,300 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Functions that are compiled to WebAssembly. Each call instantiates the
+//! module in a new sandbox that has no imports, limited memory and limited
+//! fuel, so a function can't reach the server or run forever.
+//!
+//! Arguments and results are marshalled by their SQL types:
+//!
+//! | SQL type                      | WebAssembly type                  |
+//! |-------------------------------|-----------------------------------|
+//! | `boolean`, `smallint`, `int`  | `i32`                             |
+//! | `bigint`                      | `i64`                             |
+//! | `char`, `varchar`, `text`     | `i32` pointer and `i32` length    |
+//!
+//! Text arguments are copied into memory that the module allocates with its
+//! exported `alloc(length i32) -> i32` function. Text results are `i64`
+//! with pointer in the high and length in the low 32 bits. Modules that
+//! work with text export their memory as `memory`.
+use super::{Value, Variable};
+use protocol::{results::QueryError, sql_types::PostgreSqlType};
+use std::{
+    fmt::{self, Debug, Formatter},
+    sync::Arc,
+};
+use wasmi::{
+    core::ValueType, AsContext, Config, Engine, Error, ExternType, Instance, Linker, Memory, Module, Store,
+    StoreLimits, StoreLimitsBuilder, Value as WasmValue,
+};
+
+/// Name of the language in `CREATE FUNCTION ... LANGUAGE`
+pub const LANGUAGE: &str = "wasm";
+/// Fuel of a single call, roughly the number of executed instructions
+const FUEL: u64 = 10_000_000;
+/// Linear memory of a single call in bytes
+const MEMORY_LIMIT: usize = 16 * 1024 * 1024;
+const MEMORY: &str = "memory";
+const ALLOC: &str = "alloc";
+
+/// Function that `export`s a compiled module of `file`, the code of the
+/// module is kept to be saved in the system catalog
+#[derive(Clone)]
+pub(crate) struct WasmFunction {
+    file: String,
+    export: String,
+    code: Arc<[u8]>,
+    engine: Engine,
+    module: Arc<Module>,
+}
+
+impl Debug for WasmFunction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WasmFunction")
+            .field("file", &self.file)
+            .field("export", &self.export)
+            .finish()
+    }
+}
+
+impl PartialEq for WasmFunction {
+    fn eq(&self, other: &Self) -> bool {
+        self.file == other.file && self.export == other.export
+    }
+}
+
+impl WasmFunction {
+    /// Compiles `code` of `file` and checks that `export` matches `params`
+    /// and `returns`
+    pub(crate) fn compile(
+        file: String,
+        export: String,
+        code: &[u8],
+        params: &[Variable],
+        returns: PostgreSqlType,
+    ) -> Result<WasmFunction, QueryError> {
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module = Module::new(&engine, code).map_err(|error| {
+            QueryError::invalid_function_definition(format!("module \"{}\" can't be compiled: {}", file, error))
+        })?;
+        if let Some(import) = module.imports().next() {
+            return Err(QueryError::invalid_function_definition(format!(
+                "module \"{}\" imports \"{}\", functions can't import anything",
+                file,
+                import.name()
+            )));
+        }
+        let expected_params = params
+            .iter()
+            .flat_map(|param| lower(param.pg_type))
+            .copied()
+            .collect::<Vec<ValueType>>();
+        let expected_results = [lower_result(returns)];
+        let exported = exported_function(&module, &export).ok_or_else(|| missing_export(&file, &export))?;
+        if exported.0 != expected_params || exported.1 != expected_results {
+            return Err(QueryError::invalid_function_definition(format!(
+                "function \"{}\" of module \"{}\" has to be of type ({}) -> {}",
+                export,
+                file,
+                type_names(&expected_params),
+                type_names(&expected_results)
+            )));
+        }
+        let uses_text = params.iter().any(|param| is_text(param.pg_type)) || is_text(returns);
+        if uses_text {
+            let has_memory = module
+                .exports()
+                .any(|item| item.name() == MEMORY && matches!(item.ty(), ExternType::Memory(_)));
+            if !has_memory {
+                return Err(missing_export(&file, MEMORY));
+            }
+            if params.iter().any(|param| is_text(param.pg_type))
+                && exported_function(&module, ALLOC) != Some((vec![ValueType::I32], vec![ValueType::I32]))
+            {
+                return Err(QueryError::invalid_function_definition(format!(
+                    "module \"{}\" has to export function \"{}\" of type (i32) -> i32 to accept text arguments",
+                    file, ALLOC
+                )));
+            }
+        }
+        Ok(WasmFunction {
+            file,
+            export,
+            code: code.into(),
+            engine,
+            module: Arc::new(module),
+        })
+    }
+
+    pub(crate) fn file(&self) -> &str {
+        &self.file
+    }
+
+    pub(crate) fn export(&self) -> &str {
+        &self.export
+    }
+
+    pub(crate) fn code(&self) -> &[u8] {
+        &self.code
+    }
+
+    /// Calls the function in a new instance of the module. Functions are
+    /// strict, any `NULL` argument gives `NULL` without a call.
+    pub(crate) fn call(
+        &self,
+        params: &[Variable],
+        returns: PostgreSqlType,
+        args: Vec<Value>,
+    ) -> Result<Value, QueryError> {
+        if args.contains(&Value::Null) {
+            return Ok(Value::Null);
+        }
+        let limits = StoreLimitsBuilder::new().memory_size(MEMORY_LIMIT).build();
+        let mut store = Store::new(&self.engine, limits);
+        store.limiter(|limits: &mut StoreLimits| limits);
+        store.add_fuel(FUEL).map_err(|error| trap(error.into()))?;
+        let instance = Linker::new(&self.engine)
+            .instantiate(&mut store, &self.module)
+            .and_then(|instance| instance.start(&mut store))
+            .map_err(trap)?;
+        let mut inputs = vec![];
+        for (param, arg) in params.iter().zip(args) {
+            match arg.cast(param.pg_type)? {
+                Value::Bool(value) => inputs.push(WasmValue::I32(value as i32)),
+                Value::Integer(value) if param.pg_type == PostgreSqlType::BigInt => inputs.push(WasmValue::I64(value)),
+                Value::Integer(value) => inputs.push(WasmValue::I32(value as i32)),
+                Value::Text(text) => {
+                    let pointer = write_text(&mut store, &instance, &text)?;
+                    inputs.push(WasmValue::I32(pointer));
+                    inputs.push(WasmValue::I32(text.len() as i32));
+                }
+                Value::Null => return Ok(Value::Null),
+                Value::Row(_) => unreachable!("values are cast to scalar types of parameters"),
+            }
+        }
+        let function = instance
+            .get_func(&store, &self.export)
+            .ok_or_else(|| missing_export(&self.file, &self.export))?;
+        let mut outputs = [WasmValue::default(lower_result(returns))];
+        function.call(&mut store, &inputs, &mut outputs).map_err(trap)?;
+        match (&outputs[0], returns) {
+            (WasmValue::I32(value), PostgreSqlType::Bool) => Ok(Value::Bool(*value != 0)),
+            (WasmValue::I32(value), _) => Ok(Value::Integer(i64::from(*value))),
+            (WasmValue::I64(value), PostgreSqlType::BigInt) => Ok(Value::Integer(*value)),
+            (WasmValue::I64(packed), _) => read_text(&store, &instance, *packed).map(Value::Text),
+            (other, _) => Err(QueryError::external_routine_exception(format!(
+                "unexpected result {:?}",
+                other
+            ))),
+        }
+    }
+}
+
+/// Types of arguments and results of functions are limited by `parse_type`
+fn lower(pg_type: PostgreSqlType) -> &'static [ValueType] {
+    match pg_type {
+        PostgreSqlType::Bool | PostgreSqlType::SmallInt | PostgreSqlType::Integer => &[ValueType::I32],
+        PostgreSqlType::BigInt => &[ValueType::I64],
+        _ => &[ValueType::I32, ValueType::I32],
+    }
+}
+
+fn lower_result(pg_type: PostgreSqlType) -> ValueType {
+    match pg_type {
+        PostgreSqlType::Bool | PostgreSqlType::SmallInt | PostgreSqlType::Integer => ValueType::I32,
+        _ => ValueType::I64,
+    }
+}
+
+fn is_text(pg_type: PostgreSqlType) -> bool {
+    matches!(pg_type, PostgreSqlType::Char | PostgreSqlType::VarChar)
+}
+
+fn exported_function(module: &Module, name: &str) -> Option<(Vec<ValueType>, Vec<ValueType>)> {
+    module
+        .exports()
+        .find(|item| item.name() == name)
+        .and_then(|item| match item.ty() {
+            ExternType::Func(func_type) => Some((func_type.params().to_vec(), func_type.results().to_vec())),
+            _ => None,
+        })
+}
+
+fn missing_export(file: &str, name: &str) -> QueryError {
+    QueryError::invalid_function_definition(format!("module \"{}\" does not export \"{}\"", file, name))
+}
+
+fn type_names(types: &[ValueType]) -> String {
+    types
+        .iter()
+        .map(|value_type| match value_type {
+            ValueType::I32 => "i32",
+            ValueType::I64 => "i64",
+            ValueType::F32 => "f32",
+            ValueType::F64 => "f64",
+            ValueType::FuncRef => "funcref",
+            ValueType::ExternRef => "externref",
+        })
+        .collect::<Vec<&str>>()
+        .join(", ")
+}
+
+fn trap(error: Error) -> QueryError {
+    QueryError::external_routine_exception(error.to_string())
+}
+
+fn memory(store: impl AsContext, instance: &Instance) -> Result<Memory, QueryError> {
+    instance
+        .get_memory(store, MEMORY)
+        .ok_or_else(|| QueryError::external_routine_exception(format!("module does not export \"{}\"", MEMORY)))
+}
+
+/// Copies `text` into memory allocated by the module
+fn write_text(store: &mut Store<StoreLimits>, instance: &Instance, text: &str) -> Result<i32, QueryError> {
+    let alloc = instance
+        .get_func(&*store, ALLOC)
+        .ok_or_else(|| QueryError::external_routine_exception(format!("module does not export \"{}\"", ALLOC)))?;
+    let mut pointer = [WasmValue::I32(0)];
+    alloc
+        .call(&mut *store, &[WasmValue::I32(text.len() as i32)], &mut pointer)
+        .map_err(trap)?;
+    let pointer = match pointer[0] {
+        WasmValue::I32(pointer) => pointer,
+        _ => unreachable!("type of alloc is checked when function is created"),
+    };
+    memory(&*store, instance)?
+        .write(store, pointer as u32 as usize, text.as_bytes())
+        .map_err(|error| QueryError::external_routine_exception(error.to_string()))?;
+    Ok(pointer)
+}
+
+/// Reads UTF-8 text that `packed` pointer and length refer to
+fn read_text(store: &Store<StoreLimits>, instance: &Instance, packed: i64) -> Result<String, QueryError> {
+    let pointer = (packed as u64 >> 32) as usize;
+    let length = (packed as u64 & 0xFFFF_FFFF) as usize;
+    if length > MEMORY_LIMIT {
+        return Err(QueryError::external_routine_exception(format!(
+            "text result of {} bytes exceeds memory limit",
+            length
+        )));
+    }
+    let mut bytes = vec![0; length];
+    memory(store, instance)?
+        .read(store, pointer, &mut bytes)
+        .map_err(|error| QueryError::external_routine_exception(error.to_string()))?;
+    String::from_utf8(bytes)
+        .map_err(|_| QueryError::external_routine_exception("text result is not valid UTF-8".to_owned()))
+}
@@ -0,0 +1,487 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Module for checking that a role has enough privileges to execute a plan.
+use crate::{
+    catalog_manager::{CatalogManager, Privilege, SUPERUSER},
+    query::{
+        extended::{ExtendedStatement, SettingsOwner},
+        plan::Plan,
+        TableId,
+    },
+    ColumnDefinition,
+};
+use protocol::{results::QueryError, Sender};
+use sqlparser::ast::{
+    Expr, Function, ObjectName, OrderByExpr, Query, Select, SelectItem, SetExpr, Statement, TableFactor,
+};
+use std::{convert::TryFrom, sync::Arc};
+
+/// `Err(())` means that the error is already sent to the client. If the client
+/// has disconnected meanwhile the statement is aborted all the same and the
+/// lost connection surfaces on the next message of the session.
+type Result = std::result::Result<(), ()>;
+
+pub(crate) struct Authorizer {
+    storage: Arc<CatalogManager>,
+    sender: Arc<dyn Sender>,
+}
+
+impl Authorizer {
+    pub fn new(storage: Arc<CatalogManager>, sender: Arc<dyn Sender>) -> Self {
+        Self { storage, sender }
+    }
+
+    /// Sends `insufficient_privilege` error to the client if `role` is not
+    /// allowed to execute `plan`.
+    ///
+    /// Objects that do not exist are skipped, commands report them on their own.
+    pub fn authorize(&self, role: &str, plan: &Plan) -> Result {
+        if let Some(command) = modifying_command(plan) {
+            self.check_writable(role, command)?;
+        }
+        match plan {
+            Plan::CreateSchema(_) => Ok(()),
+            Plan::CreateTable(table_info) => self.check_schema(role, &table_info.schema_name, Privilege::Create),
+            Plan::DropSchemas(schemas) => {
+                for (schema_id, _cascade) in schemas {
+                    if !self.storage.privileges().is_schema_owner(role, schema_id.name()) {
+                        return self.send(QueryError::must_be_owner("schema", schema_id.name().to_owned()));
+                    }
+                }
+                Ok(())
+            }
+            Plan::DropTables(tables) => {
+                for table_id in tables {
+                    let privileges = self.storage.privileges();
+                    if !privileges.is_table_owner(role, table_id.schema_name(), table_id.name())
+                        && !privileges.is_schema_owner(role, table_id.schema_name())
+                    {
+                        return self.send(QueryError::must_be_owner(
+                            "table",
+                            format!("{}.{}", table_id.schema_name(), table_id.name()),
+                        ));
+                    }
+                }
+                Ok(())
+            }
+            Plan::Insert(table_inserts) => {
+                let columns = if table_inserts.column_indices.is_empty() {
+                    Columns::All
+                } else {
+                    Columns::Only(
+                        table_inserts
+                            .column_indices
+                            .iter()
+                            .map(|column| column.value.clone())
+                            .collect(),
+                    )
+                };
+                self.check_table(role, &table_inserts.table_id, Privilege::Insert, columns)?;
+                self.check_query(role, &table_inserts.input)
+            }
+            Plan::NotProcessed(statement) => match statement.as_ref() {
+                Statement::Query(query) => self.check_query(role, query),
+                Statement::Update {
+                    table_name,
+                    assignments,
+                    selection,
+                } => {
+                    self.check_table_name(
+                        role,
+                        table_name,
+                        Privilege::Update,
+                        Columns::Only(
+                            assignments
+                                .iter()
+                                .map(|assignment| assignment.id.value.clone())
+                                .collect(),
+                        ),
+                    )?;
+                    self.check_condition(role, table_name, selection)
+                }
+                Statement::Delete { table_name, selection } => {
+                    self.check_table_name(role, table_name, Privilege::Delete, Columns::Unknown)?;
+                    self.check_condition(role, table_name, selection)
+                }
+                _ => Ok(()),
+            },
+        }
+    }
+
+    /// Rows that `update` and `delete` modify reveal values of columns of
+    /// their `WHERE` clause, so these columns have to be readable
+    fn check_condition(&self, role: &str, table_name: &ObjectName, selection: &Option<Expr>) -> Result {
+        let selection = match selection {
+            Some(selection) => selection,
+            None => return Ok(()),
+        };
+        let mut columns = vec![];
+        let columns = match collect_expr_columns(selection, &mut columns) {
+            Some(()) if columns.is_empty() => return Ok(()),
+            Some(()) => Columns::Only(columns),
+            None => Columns::Unknown,
+        };
+        self.check_table_name(role, table_name, Privilege::Select, columns)
+    }
+
+    /// Read-only roles can't execute any of extended statements, object
+    /// privileges are checked by commands themselves. Files on the server
+    /// can be copied, foreign tables and WebAssembly functions created only
+    /// by superuser. Statements of `DO` blocks are authorized one by one, the
+    /// explained statement of `EXPLAIN` once it is planned.
+    pub fn authorize_extended(&self, role: &str, statement: &ExtendedStatement) -> Result {
+        self.check_writable(
+            role,
+            match statement {
+                ExtendedStatement::Grant(_) => "GRANT",
+                ExtendedStatement::Revoke(_) => "REVOKE",
+                ExtendedStatement::AlterRole { .. } => "ALTER ROLE",
+                ExtendedStatement::AlterDefaultPrivileges(_) => "ALTER DEFAULT PRIVILEGES",
+                ExtendedStatement::AlterSettings {
+                    owner: SettingsOwner::Role(_),
+                    ..
+                } => "ALTER ROLE",
+                ExtendedStatement::AlterSettings {
+                    owner: SettingsOwner::Database(_),
+                    ..
+                } => "ALTER DATABASE",
+                ExtendedStatement::Copy(_) if role == SUPERUSER => return Ok(()),
+                ExtendedStatement::Copy(_) => {
+                    return self.send(QueryError::must_be_superuser("COPY to or from a file"))
+                }
+                ExtendedStatement::CreatePublication { .. } | ExtendedStatement::DropPublication { .. }
+                    if role != SUPERUSER =>
+                {
+                    return self.send(QueryError::must_be_superuser("create or drop publications"))
+                }
+                ExtendedStatement::CreatePublication { .. } => "CREATE PUBLICATION",
+                ExtendedStatement::DropPublication { .. } => "DROP PUBLICATION",
+                ExtendedStatement::CreateReplicationSlot { .. } | ExtendedStatement::DropReplicationSlot { .. }
+                    if role != SUPERUSER =>
+                {
+                    return self.send(QueryError::must_be_superuser("create or drop replication slots"))
+                }
+                ExtendedStatement::CreateReplicationSlot { .. } => "CREATE REPLICATION SLOT",
+                ExtendedStatement::DropReplicationSlot { .. } => "DROP REPLICATION SLOT",
+                ExtendedStatement::CreateForeignTable(_) if role != SUPERUSER => {
+                    return self.send(QueryError::must_be_superuser("create foreign tables"))
+                }
+                ExtendedStatement::CreateForeignTable(_) => "CREATE FOREIGN TABLE",
+                ExtendedStatement::Cluster(_) => "CLUSTER",
+                ExtendedStatement::AlterTable(_) => "ALTER TABLE",
+                ExtendedStatement::CreateFunction { .. } => "CREATE FUNCTION",
+                ExtendedStatement::DropFunction { .. } => "DROP FUNCTION",
+                ExtendedStatement::CreateAggregate { .. } => "CREATE AGGREGATE",
+                ExtendedStatement::CreateEventTrigger(_) | ExtendedStatement::DropEventTrigger { .. }
+                    if role != SUPERUSER =>
+                {
+                    return self.send(QueryError::must_be_superuser("create or drop event triggers"))
+                }
+                ExtendedStatement::CreateEventTrigger(_) => "CREATE EVENT TRIGGER",
+                ExtendedStatement::DropEventTrigger { .. } => "DROP EVENT TRIGGER",
+                ExtendedStatement::Do(_)
+                | ExtendedStatement::Explain(_)
+                | ExtendedStatement::Savepoint(_)
+                | ExtendedStatement::ReleaseSavepoint(_)
+                | ExtendedStatement::RollbackToSavepoint(_) => return Ok(()),
+                ExtendedStatement::CreateWasmFunction { .. } if role != SUPERUSER => {
+                    return self.send(QueryError::must_be_superuser("create functions in language wasm"))
+                }
+                ExtendedStatement::CreateWasmFunction { .. } => "CREATE FUNCTION",
+            },
+        )
+    }
+
+    fn check_writable(&self, role: &str, command: &str) -> Result {
+        if self.storage.roles().is_read_only(role) {
+            self.send(QueryError::read_only_sql_transaction(command))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn check_query(&self, role: &str, query: &Query) -> Result {
+        let mut tables = vec![];
+        collect_query_tables(query, &mut tables);
+        let columns = if tables.len() == 1 {
+            query_columns(query)
+        } else {
+            Columns::Unknown
+        };
+        for table_name in tables {
+            self.check_table_name(role, table_name, Privilege::Select, columns.clone())?;
+        }
+        Ok(())
+    }
+
+    fn check_table_name(&self, role: &str, table_name: &ObjectName, privilege: Privilege, columns: Columns) -> Result {
+        match TableId::try_from(table_name.clone()) {
+            Ok(table_id) => self.check_table(role, &table_id, privilege, columns),
+            Err(_) => Ok(()),
+        }
+    }
+
+    /// Role that does not have `privilege` on the table needs it on each of
+    /// `columns` the statement refers to
+    fn check_table(&self, role: &str, table_id: &TableId, privilege: Privilege, columns: Columns) -> Result {
+        let schema_name = table_id.schema_name();
+        let table_name = table_id.name();
+        match self.storage.table_exists(schema_name, table_name) {
+            Some((_, Some(_))) => {
+                self.check_schema(role, schema_name, Privilege::Usage)?;
+                let privileges = self.storage.privileges();
+                let columns = match columns {
+                    Columns::All => self
+                        .storage
+                        .table_columns(schema_name, table_name)
+                        .map(|definitions| definitions.iter().map(ColumnDefinition::name).collect())
+                        .unwrap_or_default(),
+                    Columns::Only(columns) => columns,
+                    Columns::Unknown => vec![],
+                };
+                if privileges.has_table_privilege(role, schema_name, table_name, privilege)
+                    || (!columns.is_empty()
+                        && columns.iter().all(|column| {
+                            privileges.has_column_privilege(role, schema_name, table_name, column, privilege)
+                        }))
+                {
+                    Ok(())
+                } else {
+                    self.send(QueryError::permission_denied(
+                        "table",
+                        format!("{}.{}", schema_name, table_name),
+                    ))
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn check_schema(&self, role: &str, schema_name: &str, privilege: Privilege) -> Result {
+        if self
+            .storage
+            .privileges()
+            .has_schema_privilege(role, schema_name, privilege)
+        {
+            Ok(())
+        } else {
+            self.send(QueryError::permission_denied("schema", schema_name.to_owned()))
+        }
+    }
+
+    fn send(&self, error: QueryError) -> Result {
+        self.sender.send(Err(error)).ok();
+        Err(())
+    }
+}
+
+/// Columns of a table a statement refers to
+#[derive(Clone)]
+enum Columns {
+    All,
+    Only(Vec<String>),
+    /// columns can't be attributed to a table, e.g. in joins
+    Unknown,
+}
+
+/// Columns referred by a query that selects from a single table
+fn query_columns(query: &Query) -> Columns {
+    match &query.body {
+        SetExpr::Select(select) if query.ctes.is_empty() => {
+            select_columns(select, &query.order_by).unwrap_or(Columns::Unknown)
+        }
+        _ => Columns::Unknown,
+    }
+}
+
+/// Keys of `ORDER BY` that name aliases of the select list refer to the
+/// columns of the aliased expressions, which are already collected
+fn select_columns(select: &Select, order_by: &[OrderByExpr]) -> Option<Columns> {
+    let mut columns = vec![];
+    let mut all = false;
+    for item in &select.projection {
+        match item {
+            SelectItem::Wildcard | SelectItem::QualifiedWildcard(_) => all = true,
+            SelectItem::UnnamedExpr(expr) | SelectItem::ExprWithAlias { expr, .. } => {
+                collect_expr_columns(expr, &mut columns)?
+            }
+        }
+    }
+    for expr in select.selection.iter().chain(&select.group_by).chain(&select.having) {
+        collect_expr_columns(expr, &mut columns)?;
+    }
+    for OrderByExpr { expr, .. } in order_by {
+        match expr {
+            Expr::Identifier(name)
+                if select.projection.iter().any(
+                    |item| matches!(item, SelectItem::ExprWithAlias { alias, .. } if alias.value == name.value),
+                ) => {}
+            expr => collect_expr_columns(expr, &mut columns)?,
+        }
+    }
+    if all {
+        Some(Columns::All)
+    } else {
+        Some(Columns::Only(columns))
+    }
+}
+
+/// Returns `None` if columns of `expr` can't be determined
+fn collect_expr_columns(expr: &Expr, columns: &mut Vec<String>) -> Option<()> {
+    match expr {
+        Expr::Identifier(ident) => columns.push(ident.value.clone()),
+        Expr::CompoundIdentifier(idents) => columns.push(idents.last()?.value.clone()),
+        Expr::Value(_) | Expr::TypedString { .. } => {}
+        Expr::IsNull(expr)
+        | Expr::IsNotNull(expr)
+        | Expr::UnaryOp { expr, .. }
+        | Expr::Cast { expr, .. }
+        | Expr::Collate { expr, .. }
+        | Expr::Nested(expr) => collect_expr_columns(expr, columns)?,
+        Expr::BinaryOp { left, right, .. } => {
+            collect_expr_columns(left, columns)?;
+            collect_expr_columns(right, columns)?;
+        }
+        Expr::Between { expr, low, high, .. } => {
+            for expr in &[expr, low, high] {
+                collect_expr_columns(expr, columns)?;
+            }
+        }
+        Expr::InList { expr, list, .. } => {
+            collect_expr_columns(expr, columns)?;
+            for expr in list {
+                collect_expr_columns(expr, columns)?;
+            }
+        }
+        Expr::Function(Function { args, .. }) => {
+            for expr in args {
+                collect_expr_columns(expr, columns)?;
+            }
+        }
+        Expr::Case {
+            operand,
+            conditions,
+            results,
+            else_result,
+        } => {
+            for expr in operand
+                .as_deref()
+                .into_iter()
+                .chain(conditions)
+                .chain(results)
+                .chain(else_result.as_deref())
+            {
+                collect_expr_columns(expr, columns)?;
+            }
+        }
+        _ => return None,
+    }
+    Some(())
+}
+
+fn modifying_command(plan: &Plan) -> Option<&'static str> {
+    match plan {
+        Plan::CreateSchema(_) => Some("CREATE SCHEMA"),
+        Plan::CreateTable(_) => Some("CREATE TABLE"),
+        Plan::DropSchemas(_) => Some("DROP SCHEMA"),
+        Plan::DropTables(_) => Some("DROP TABLE"),
+        Plan::Insert(_) => Some("INSERT"),
+        Plan::NotProcessed(statement) => match statement.as_ref() {
+            Statement::Update { .. } => Some("UPDATE"),
+            Statement::Delete { .. } => Some("DELETE"),
+            _ => None,
+        },
+    }
+}
+
+pub(crate) fn collect_query_tables<'q>(query: &'q Query, tables: &mut Vec<&'q ObjectName>) {
+    for cte in &query.ctes {
+        collect_query_tables(&cte.query, tables);
+    }
+    collect_set_expr_tables(&query.body, tables);
+    for OrderByExpr { expr, .. } in &query.order_by {
+        collect_expr_tables(expr, tables);
+    }
+}
+
+fn collect_set_expr_tables<'q>(set_expr: &'q SetExpr, tables: &mut Vec<&'q ObjectName>) {
+    match set_expr {
+        SetExpr::Select(select) => {
+            for table_with_joins in &select.from {
+                collect_table_factor_tables(&table_with_joins.relation, tables);
+                for join in &table_with_joins.joins {
+                    collect_table_factor_tables(&join.relation, tables);
+                }
+            }
+            if let Some(selection) = &select.selection {
+                collect_expr_tables(selection, tables);
+            }
+        }
+        SetExpr::Query(query) => collect_query_tables(query, tables),
+        SetExpr::SetOperation { left, right, .. } => {
+            collect_set_expr_tables(left, tables);
+            collect_set_expr_tables(right, tables);
+        }
+        SetExpr::Values(_) => {}
+    }
+}
+
+/// Tables of subqueries of a condition
+fn collect_expr_tables<'q>(expr: &'q Expr, tables: &mut Vec<&'q ObjectName>) {
+    match expr {
+        Expr::InSubquery { expr, subquery, .. } => {
+            collect_expr_tables(expr, tables);
+            collect_query_tables(subquery, tables);
+        }
+        Expr::Exists(query) | Expr::Subquery(query) => collect_query_tables(query, tables),
+        Expr::IsNull(expr) | Expr::IsNotNull(expr) | Expr::UnaryOp { expr, .. } | Expr::Nested(expr) => {
+            collect_expr_tables(expr, tables)
+        }
+        Expr::BinaryOp { left, right, .. } => {
+            collect_expr_tables(left, tables);
+            collect_expr_tables(right, tables);
+        }
+        Expr::Case {
+            operand,
+            conditions,
+            results,
+            else_result,
+        } => {
+            for expr in operand
+                .as_deref()
+                .into_iter()
+                .chain(conditions)
+                .chain(results)
+                .chain(else_result.as_deref())
+            {
+                collect_expr_tables(expr, tables);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn collect_table_factor_tables<'q>(table_factor: &'q TableFactor, tables: &mut Vec<&'q ObjectName>) {
+    match table_factor {
+        TableFactor::Table { name, .. } => tables.push(name),
+        TableFactor::Derived { subquery, .. } => collect_query_tables(subquery, tables),
+        TableFactor::NestedJoin(table_with_joins) => {
+            collect_table_factor_tables(&table_with_joins.relation, tables);
+            for join in &table_with_joins.joins {
+                collect_table_factor_tables(&join.relation, tables);
+            }
+        }
+    }
+}
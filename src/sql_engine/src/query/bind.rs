@@ -13,10 +13,16 @@
 // limitations under the License.
 
 use bigdecimal::BigDecimal;
-use protocol::{results::QueryError, sql_values::PostgreSqlValue, Sender};
-use sqlparser::ast::{Assignment, Expr, Ident, Query, SetExpr, Statement, Value};
+use protocol::{results::QueryError, sql_types::PostgreSqlType, sql_values::PostgreSqlValue, Sender};
+use sqlparser::ast::{
+    Expr, Ident, JoinConstraint, JoinOperator, Query, Select, SelectItem, SetExpr, Statement, TableFactor,
+    TableWithJoins, Value,
+};
 use std::sync::Arc;
 
+/// `Err(())` means that the error is already sent to the client. If the client
+/// has disconnected meanwhile the statement is aborted all the same and the
+/// lost connection surfaces on the next message of the session.
 type Result = std::result::Result<(), ()>;
 
 pub(crate) struct ParamBinder {
@@ -28,70 +34,198 @@ impl ParamBinder {
         Self { sender }
     }
 
-    /// Replaces the paramsters of prepared statement with values.
-    ///
-    /// TODO:
-    /// Only two SQL formats has been supported to bind parameters as below.
-    ///     `insert into schema_name.table_name values ($1, 1), ($2, 2)`
-    ///     `update schema_name.table_name set col1 = $1, col2 = $2`
-    /// Needs to support other statements (as `select` and `delete`) and other
-    /// expressions in SQL (as `BinaryOp` and `UnaryOp` in `where` statement).
+    /// Replaces the parameters of prepared statement with values wherever
+    /// an expression can occur, e.g. in `VALUES` of `insert`, `SET` of
+    /// `update` and `WHERE` clauses of `select`, `update` and `delete`.
     pub fn bind(&self, stmt: &mut Statement, params: &[PostgreSqlValue]) -> Result {
         match stmt {
-            Statement::Insert { .. } => bind_insert(stmt, params),
-            Statement::Update { .. } => bind_update(stmt, params),
+            Statement::Query(_) | Statement::Insert { .. } | Statement::Update { .. } | Statement::Delete { .. } => {
+                visit_exprs(stmt, &mut |expr| replace_expr_with_params(expr, params))
+            }
             _ => {
                 self.sender
                     .send(Err(QueryError::feature_not_supported(format!(
                         "Bind parameters is not supported on SQL `{}`",
                         stmt
                     ))))
-                    .expect("To Send Bind Error");
-                Err(())
+                    .ok();
+                return Err(());
             }
         }
+
+        log::debug!("Bound SQL: {}", stmt);
+        Ok(())
     }
 }
 
-fn bind_insert(stmt: &mut Statement, params: &[PostgreSqlValue]) -> Result {
-    let mut body = match stmt {
-        Statement::Insert { source, .. } => {
-            let source: &mut Query = source;
-            let Query { body, .. } = source;
-            body
+/// Calls `visit` on every expression of `stmt` and then on its
+/// subexpressions
+pub(crate) fn visit_exprs(stmt: &mut Statement, visit: &mut dyn FnMut(&mut Expr)) {
+    match stmt {
+        Statement::Query(query) => visit_query(query, visit),
+        Statement::Insert { source, .. } => visit_query(source, visit),
+        Statement::Update {
+            assignments, selection, ..
+        } => {
+            for assignment in assignments {
+                visit_expr(&mut assignment.value, visit);
+            }
+            visit_optional_expr(selection, visit);
         }
-        _ => return Err(()),
-    };
+        Statement::Delete { selection, .. } => visit_optional_expr(selection, visit),
+        _ => {}
+    }
+}
+
+fn visit_query(query: &mut Query, visit: &mut dyn FnMut(&mut Expr)) {
+    for cte in &mut query.ctes {
+        visit_query(&mut cte.query, visit);
+    }
+    visit_set_expr(&mut query.body, visit);
+    for order_by in &mut query.order_by {
+        visit_expr(&mut order_by.expr, visit);
+    }
+    visit_optional_expr(&mut query.limit, visit);
+    if let Some(offset) = &mut query.offset {
+        visit_expr(&mut offset.value, visit);
+    }
+    if let Some(fetch) = &mut query.fetch {
+        visit_optional_expr(&mut fetch.quantity, visit);
+    }
+}
 
-    if let SetExpr::Values(values) = &mut body {
-        let values = &mut values.0;
-        for line in values {
-            for col in line {
-                replace_expr_with_params(col, params);
+fn visit_set_expr(set_expr: &mut SetExpr, visit: &mut dyn FnMut(&mut Expr)) {
+    match set_expr {
+        SetExpr::Select(select) => visit_select(select, visit),
+        SetExpr::Query(query) => visit_query(query, visit),
+        SetExpr::SetOperation { left, right, .. } => {
+            visit_set_expr(left, visit);
+            visit_set_expr(right, visit);
+        }
+        SetExpr::Values(values) => {
+            for row in &mut values.0 {
+                for expr in row {
+                    visit_expr(expr, visit);
+                }
             }
         }
     }
+}
 
-    log::debug!("Bound Insert SQL: {}", stmt);
-    Ok(())
+fn visit_select(select: &mut Select, visit: &mut dyn FnMut(&mut Expr)) {
+    if let Some(top) = &mut select.top {
+        visit_optional_expr(&mut top.quantity, visit);
+    }
+    for item in &mut select.projection {
+        match item {
+            SelectItem::UnnamedExpr(expr) | SelectItem::ExprWithAlias { expr, .. } => visit_expr(expr, visit),
+            SelectItem::Wildcard | SelectItem::QualifiedWildcard(_) => {}
+        }
+    }
+    for table_with_joins in &mut select.from {
+        visit_table_with_joins(table_with_joins, visit);
+    }
+    visit_optional_expr(&mut select.selection, visit);
+    for expr in &mut select.group_by {
+        visit_expr(expr, visit);
+    }
+    visit_optional_expr(&mut select.having, visit);
 }
 
-fn bind_update(stmt: &mut Statement, params: &[PostgreSqlValue]) -> Result {
-    let assignments = match stmt {
-        Statement::Update { assignments, .. } => assignments,
-        _ => return Err(()),
-    };
+fn visit_table_with_joins(table_with_joins: &mut TableWithJoins, visit: &mut dyn FnMut(&mut Expr)) {
+    visit_table_factor(&mut table_with_joins.relation, visit);
+    for join in &mut table_with_joins.joins {
+        visit_table_factor(&mut join.relation, visit);
+        match &mut join.join_operator {
+            JoinOperator::Inner(JoinConstraint::On(expr))
+            | JoinOperator::LeftOuter(JoinConstraint::On(expr))
+            | JoinOperator::RightOuter(JoinConstraint::On(expr))
+            | JoinOperator::FullOuter(JoinConstraint::On(expr)) => visit_expr(expr, visit),
+            _ => {}
+        }
+    }
+}
 
-    for assignment in assignments {
-        let Assignment { value, .. } = assignment;
-        replace_expr_with_params(value, params);
+fn visit_table_factor(table_factor: &mut TableFactor, visit: &mut dyn FnMut(&mut Expr)) {
+    match table_factor {
+        TableFactor::Table { args, .. } => {
+            for expr in args {
+                visit_expr(expr, visit);
+            }
+        }
+        TableFactor::Derived { subquery, .. } => visit_query(subquery, visit),
+        TableFactor::NestedJoin(table_with_joins) => visit_table_with_joins(table_with_joins, visit),
     }
+}
 
-    log::debug!("Bound Insert SQL: {}", stmt);
-    Ok(())
+fn visit_optional_expr(expr: &mut Option<Expr>, visit: &mut dyn FnMut(&mut Expr)) {
+    if let Some(expr) = expr {
+        visit_expr(expr, visit);
+    }
 }
 
-fn parse_param_index(value: &str) -> Option<usize> {
+fn visit_expr(expr: &mut Expr, visit: &mut dyn FnMut(&mut Expr)) {
+    visit(expr);
+    match expr {
+        Expr::IsNull(expr)
+        | Expr::IsNotNull(expr)
+        | Expr::UnaryOp { expr, .. }
+        | Expr::Cast { expr, .. }
+        | Expr::Extract { expr, .. }
+        | Expr::Collate { expr, .. }
+        | Expr::Nested(expr) => visit_expr(expr, visit),
+        Expr::InList { expr, list, .. } => {
+            visit_expr(expr, visit);
+            for expr in list {
+                visit_expr(expr, visit);
+            }
+        }
+        Expr::InSubquery { expr, subquery, .. } => {
+            visit_expr(expr, visit);
+            visit_query(subquery, visit);
+        }
+        Expr::Between { expr, low, high, .. } => {
+            visit_expr(expr, visit);
+            visit_expr(low, visit);
+            visit_expr(high, visit);
+        }
+        Expr::BinaryOp { left, right, .. } => {
+            visit_expr(left, visit);
+            visit_expr(right, visit);
+        }
+        Expr::Function(function) => {
+            for expr in &mut function.args {
+                visit_expr(expr, visit);
+            }
+        }
+        Expr::Case {
+            operand,
+            conditions,
+            results,
+            else_result,
+        } => {
+            if let Some(operand) = operand {
+                visit_expr(operand, visit);
+            }
+            for expr in conditions.iter_mut().chain(results.iter_mut()) {
+                visit_expr(expr, visit);
+            }
+            if let Some(else_result) = else_result {
+                visit_expr(else_result, visit);
+            }
+        }
+        Expr::Exists(query) | Expr::Subquery(query) => visit_query(query, visit),
+        Expr::ListAgg(list_agg) => visit_expr(&mut list_agg.expr, visit),
+        Expr::Identifier(_)
+        | Expr::Wildcard
+        | Expr::QualifiedWildcard(_)
+        | Expr::CompoundIdentifier(_)
+        | Expr::Value(_)
+        | Expr::TypedString { .. } => {}
+    }
+}
+
+pub(crate) fn parse_param_index(value: &str) -> Option<usize> {
     let mut chars = value.chars();
     if chars.next() != Some('$') || !chars.all(|c| c.is_digit(10)) {
         return None;
@@ -117,9 +251,33 @@ fn pg_value_to_expr(value: &PostgreSqlValue) -> Expr {
     }
 }
 
+/// Statement that is described as if its parameters were bound to values
+/// of `param_types`, parameters are bound to literals of the same kinds
+pub(crate) fn with_sample_params(stmt: &Statement, param_types: &[PostgreSqlType]) -> Statement {
+    let params = param_types
+        .iter()
+        .map(|param_type| match param_type {
+            PostgreSqlType::Bool => PostgreSqlValue::True,
+            PostgreSqlType::SmallInt => PostgreSqlValue::Int16(0),
+            PostgreSqlType::Integer => PostgreSqlValue::Int32(0),
+            PostgreSqlType::BigInt => PostgreSqlValue::Int64(0),
+            _ => PostgreSqlValue::String(String::new()),
+        })
+        .collect::<Vec<_>>();
+    let mut stmt = stmt.clone();
+    visit_exprs(&mut stmt, &mut |expr| replace_expr_with_params(expr, &params));
+    stmt
+}
+
+/// Parameters are decoded according to their types, so explicit casts of
+/// parameters, e.g. `$1::smallint`, are replaced with values as well
 fn replace_expr_with_params(expr: &mut Expr, params: &[PostgreSqlValue]) {
     let value = match expr {
         Expr::Identifier(Ident { value, .. }) => value,
+        Expr::Cast { expr: param, .. } => match &**param {
+            Expr::Identifier(Ident { value, .. }) => value,
+            _ => return,
+        },
         _ => return,
     };
 
@@ -0,0 +1,298 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Output of `EXPLAIN` built from plan trees and of `EXPLAIN ANALYZE` built
+//! from measurements of executed plans.
+use std::{
+    fmt::{self, Display, Formatter, Write},
+    time::Duration,
+};
+
+/// Format of `EXPLAIN` output, `EXPLAIN (FORMAT JSON)` is meant for tools
+/// that render plan trees
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ExplainFormat {
+    Text,
+    Json,
+}
+
+/// Node of a plan tree as `EXPLAIN` shows it
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PlanNode {
+    pub node_type: &'static str,
+    /// kind of modification of `ModifyTable` nodes, e.g. `Update`
+    pub operation: Option<&'static str>,
+    pub schema: Option<String>,
+    pub relation: Option<String>,
+    pub plans: Vec<PlanNode>,
+}
+
+impl PlanNode {
+    pub(crate) fn new(node_type: &'static str) -> PlanNode {
+        PlanNode {
+            node_type,
+            operation: None,
+            schema: None,
+            relation: None,
+            plans: vec![],
+        }
+    }
+
+    pub(crate) fn scan(schema: Option<String>, relation: String) -> PlanNode {
+        PlanNode {
+            schema,
+            relation: Some(relation),
+            ..PlanNode::new("Seq Scan")
+        }
+    }
+
+    pub(crate) fn modify(
+        operation: &'static str,
+        schema: Option<String>,
+        relation: String,
+        plans: Vec<PlanNode>,
+    ) -> PlanNode {
+        PlanNode {
+            operation: Some(operation),
+            schema,
+            relation: Some(relation),
+            plans,
+            ..PlanNode::new("ModifyTable")
+        }
+    }
+
+    /// Tables that the node and its children read or modify
+    pub(crate) fn relations(&self) -> Vec<(Option<&str>, &str)> {
+        let mut relations = vec![];
+        if let Some(relation) = &self.relation {
+            relations.push((self.schema.as_deref(), relation.as_str()));
+        }
+        for plan in &self.plans {
+            relations.extend(plan.relations());
+        }
+        relations
+    }
+
+    /// Rows of `EXPLAIN` output, JSON is returned as a single row
+    pub(crate) fn explain(&self, format: ExplainFormat) -> Vec<String> {
+        match format {
+            ExplainFormat::Text => {
+                let mut lines = vec![];
+                self.text_lines(0, &mut lines);
+                lines
+            }
+            ExplainFormat::Json => {
+                let mut json = String::new();
+                json.push_str("[\n  {\n    \"Plan\": ");
+                self.write_json(&mut json, 4).expect("writing to string");
+                json.push_str("\n  }\n]");
+                vec![json]
+            }
+        }
+    }
+
+    fn text_lines(&self, depth: usize, lines: &mut Vec<String>) {
+        let label = match (self.operation, &self.relation) {
+            (Some(operation), Some(relation)) => format!("{} on {}", operation, self.qualified(relation)),
+            (None, Some(relation)) => format!("{} on {}", self.node_type, self.qualified(relation)),
+            (_, None) => self.node_type.to_owned(),
+        };
+        if depth == 0 {
+            lines.push(label);
+        } else {
+            lines.push(format!("{}->  {}", " ".repeat(6 * (depth - 1) + 2), label));
+        }
+        for plan in &self.plans {
+            plan.text_lines(depth + 1, lines);
+        }
+    }
+
+    fn qualified(&self, relation: &str) -> String {
+        match &self.schema {
+            Some(schema) => format!("{}.{}", schema, relation),
+            None => relation.to_owned(),
+        }
+    }
+
+    /// Writes the node as a JSON object whose braces are indented by `indent`
+    /// spaces, the opening brace is not indented
+    fn write_json(&self, json: &mut String, indent: usize) -> fmt::Result {
+        let mut members = vec![("Node Type", json_string(self.node_type))];
+        if let Some(operation) = self.operation {
+            members.push(("Operation", json_string(operation)));
+        }
+        if let Some(relation) = &self.relation {
+            members.push(("Relation Name", json_string(relation)));
+        }
+        if let Some(schema) = &self.schema {
+            members.push(("Schema", json_string(schema)));
+        }
+        let padding = " ".repeat(indent + 2);
+        json.push('{');
+        for (index, (name, value)) in members.iter().enumerate() {
+            if index > 0 {
+                json.push(',');
+            }
+            write!(json, "\n{}\"{}\": {}", padding, name, value)?;
+        }
+        if !self.plans.is_empty() {
+            write!(json, ",\n{}\"Plans\": [", padding)?;
+            for (index, plan) in self.plans.iter().enumerate() {
+                if index > 0 {
+                    json.push(',');
+                }
+                write!(json, "\n{}  ", padding)?;
+                plan.write_json(json, indent + 4)?;
+            }
+            write!(json, "\n{}]", padding)?;
+        }
+        write!(json, "\n{}}}", " ".repeat(indent))
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+/// Measurements of a single plan execution
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Instrumentation {
+    /// time spent to build and authorize the plan
+    pub planning: Duration,
+    /// time spent to execute the plan
+    pub execution: Duration,
+    /// number of rows that the plan produced or modified
+    pub rows: usize,
+}
+
+/// Formats plan summary with its measurements in a way `EXPLAIN ANALYZE` does
+pub(crate) struct ExplainAnalyze<'e> {
+    pub query_text: &'e str,
+    pub plan: &'e str,
+    pub instrumentation: Instrumentation,
+}
+
+impl Display for ExplainAnalyze<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Query Text: {}", self.query_text)?;
+        writeln!(f, "{}  (actual rows={} loops=1)", self.plan, self.instrumentation.rows)?;
+        writeln!(f, "Planning Time: {:.3} ms", millis(self.instrumentation.planning))?;
+        write!(f, "Execution Time: {:.3} ms", millis(self.instrumentation.execution))
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update() -> PlanNode {
+        PlanNode::modify(
+            "Update",
+            Some("schema_name".to_owned()),
+            "table_name".to_owned(),
+            vec![PlanNode::scan(Some("schema_name".to_owned()), "table_name".to_owned())],
+        )
+    }
+
+    #[test]
+    fn explain_text_output() {
+        assert_eq!(
+            update().explain(ExplainFormat::Text),
+            vec![
+                "Update on schema_name.table_name".to_owned(),
+                "  ->  Seq Scan on schema_name.table_name".to_owned()
+            ]
+        );
+    }
+
+    #[test]
+    fn explain_json_output() {
+        assert_eq!(
+            update().explain(ExplainFormat::Json),
+            vec![r#"[
+  {
+    "Plan": {
+      "Node Type": "ModifyTable",
+      "Operation": "Update",
+      "Relation Name": "table_name",
+      "Schema": "schema_name",
+      "Plans": [
+        {
+          "Node Type": "Seq Scan",
+          "Relation Name": "table_name",
+          "Schema": "schema_name"
+        }
+      ]
+    }
+  }
+]"#
+            .to_owned()]
+        );
+    }
+
+    #[test]
+    fn json_strings_are_escaped() {
+        assert_eq!(
+            PlanNode::scan(None, "quoted \"name\"\\".to_owned()).explain(ExplainFormat::Json),
+            vec![r#"[
+  {
+    "Plan": {
+      "Node Type": "Seq Scan",
+      "Relation Name": "quoted \"name\"\\"
+    }
+  }
+]"#
+            .to_owned()]
+        );
+    }
+
+    #[test]
+    fn explain_analyze_output() {
+        let explain = ExplainAnalyze {
+            query_text: "select * from schema_name.table_name",
+            plan: "Seq Scan on schema_name.table_name",
+            instrumentation: Instrumentation {
+                planning: Duration::from_micros(120),
+                execution: Duration::from_micros(2_500),
+                rows: 3,
+            },
+        };
+
+        assert_eq!(
+            explain.to_string(),
+            "Query Text: select * from schema_name.table_name\n\
+             Seq Scan on schema_name.table_name  (actual rows=3 loops=1)\n\
+             Planning Time: 0.120 ms\n\
+             Execution Time: 2.500 ms"
+        );
+    }
+}
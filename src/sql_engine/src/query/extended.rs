@@ -0,0 +1,2051 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Module for statements that `sqlparser` does not support. They are parsed
+//! on top of its tokenizer and parser primitives before falling back to
+//! `Parser::parse_sql`.
+use crate::{
+    catalog_manager::{DdlEvent, EventTrigger, Privilege, DDL_TAGS, PUBLIC},
+    procedural::{self, wasm, Block, Body, Function, Returns, Variable},
+    query::explain::ExplainFormat,
+    script,
+};
+use protocol::{scram::ScramVerifier, sql_types::PostgreSqlType};
+use sqlparser::{
+    ast::{ColumnDef, Expr, ObjectName, SetVariableValue, Statement},
+    dialect::{keywords::Keyword, Dialect},
+    parser::{IsOptional, Parser, ParserError},
+    tokenizer::{Token, Tokenizer, Word},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum ExtendedStatement {
+    Grant(PrivilegeChange),
+    Revoke(PrivilegeChange),
+    AlterRole {
+        role: String,
+        options: Vec<RoleOption>,
+    },
+    AlterDefaultPrivileges(DefaultPrivilegesChange),
+    /// `ALTER ROLE role SET ...` and `ALTER DATABASE database SET ...`
+    AlterSettings {
+        owner: SettingsOwner,
+        change: SettingChange,
+    },
+    Copy(CopyStatement),
+    CreatePublication {
+        name: String,
+        tables: PublicationTables,
+    },
+    DropPublication {
+        name: String,
+        if_exists: bool,
+    },
+    CreateReplicationSlot {
+        name: String,
+        publication: String,
+    },
+    DropReplicationSlot {
+        name: String,
+        if_exists: bool,
+    },
+    CreateForeignTable(ForeignTableStatement),
+    Cluster(ClusterStatement),
+    AlterTable(AlterTableStatement),
+    CreateFunction {
+        function: Function,
+        or_replace: bool,
+    },
+    DropFunction {
+        name: String,
+        if_exists: bool,
+    },
+    CreateEventTrigger(EventTrigger),
+    DropEventTrigger {
+        name: String,
+        if_exists: bool,
+    },
+    Do(Block),
+    CreateWasmFunction {
+        function: WasmFunctionStatement,
+        or_replace: bool,
+    },
+    CreateAggregate {
+        aggregate: AggregateStatement,
+        or_replace: bool,
+    },
+    Explain(ExplainStatement),
+    Savepoint(String),
+    ReleaseSavepoint(String),
+    RollbackToSavepoint(String),
+}
+
+impl ExtendedStatement {
+    /// Tag of a DDL command that event triggers can filter on, `None` if
+    /// the statement does not fire event triggers
+    pub(crate) fn ddl_tag(&self) -> Option<&'static str> {
+        match self {
+            ExtendedStatement::CreatePublication { .. } => Some("CREATE PUBLICATION"),
+            ExtendedStatement::DropPublication { .. } => Some("DROP PUBLICATION"),
+            ExtendedStatement::CreateForeignTable(_) => Some("CREATE FOREIGN TABLE"),
+            ExtendedStatement::CreateFunction { .. } | ExtendedStatement::CreateWasmFunction { .. } => {
+                Some("CREATE FUNCTION")
+            }
+            ExtendedStatement::DropFunction { .. } => Some("DROP FUNCTION"),
+            ExtendedStatement::CreateAggregate { .. } => Some("CREATE AGGREGATE"),
+            ExtendedStatement::AlterTable(_) => Some("ALTER TABLE"),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum RoleOption {
+    /// password is hashed right after parsing so the plain text is not kept
+    /// around (e.g. in logs of executed statements)
+    Password(Option<ScramVerifier>),
+    ReadOnly(bool),
+    /// `None` stands for no limit, i.e. `CONNECTION LIMIT -1`
+    ConnectionLimit(Option<u32>),
+}
+
+/// Sessions of the role or connected to the database start with settings
+/// that are set for it
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum SettingsOwner {
+    Role(String),
+    Database(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum SettingChange {
+    /// `SET name { TO | = } value`
+    Set(String, SetVariableValue),
+    /// `RESET name` or `SET name TO DEFAULT`
+    Reset(String),
+    /// `RESET ALL`
+    ResetAll,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum PrivilegeObjects {
+    Tables(Vec<ObjectName>),
+    Schemas(Vec<ObjectName>),
+}
+
+/// `column_privileges` are applicable only to tables, e.g.
+/// `GRANT SELECT (column_1, column_2) ON table_name TO role`
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PrivilegeChange {
+    pub privileges: Vec<Privilege>,
+    pub column_privileges: Vec<(Privilege, Vec<String>)>,
+    pub objects: PrivilegeObjects,
+    pub grantees: Vec<String>,
+}
+
+type PrivilegeItem = (Privilege, Option<Vec<String>>);
+
+/// Change of privileges that tables get when they are created by
+/// `target_roles`. Empty `target_roles` stands for the current role and empty
+/// `schemas` for all schemas.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct DefaultPrivilegesChange {
+    pub target_roles: Vec<String>,
+    pub schemas: Vec<ObjectName>,
+    pub grant: bool,
+    pub privileges: Vec<Privilege>,
+    pub grantees: Vec<String>,
+}
+
+/// Tables whose changes a publication streams, empty `Tables` stands for a
+/// publication without tables
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum PublicationTables {
+    All,
+    Tables(Vec<ObjectName>),
+}
+
+/// `CREATE FOREIGN TABLE name (column type, ...) SERVER server [OPTIONS (option 'value', ...)]`
+/// where `server` is the name of a registered foreign data wrapper
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ForeignTableStatement {
+    pub name: ObjectName,
+    pub columns: Vec<ColumnDef>,
+    pub server: String,
+    pub options: Vec<(String, String)>,
+}
+
+/// `CLUSTER table_name ORDER BY column [ASC | DESC], ...` that rewrites rows
+/// of a table in the given order, or `CLUSTER table_name USING index_name`
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ClusterStatement {
+    pub table_name: ObjectName,
+    pub order: ClusterOrder,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum ClusterOrder {
+    Index(String),
+    /// names of columns with `true` for descending order
+    Columns(Vec<(String, bool)>),
+}
+
+/// `ALTER TABLE name ALTER [COLUMN] column action, ...` that changes
+/// defaults and nullability of columns
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct AlterTableStatement {
+    pub table_name: ObjectName,
+    pub changes: Vec<(String, ColumnChange)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum ColumnChange {
+    SetDefault(Expr),
+    DropDefault,
+    SetNotNull,
+    DropNotNull,
+}
+
+/// `EXPLAIN [ ( FORMAT { TEXT | JSON } ) ] statement`
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ExplainStatement {
+    pub format: ExplainFormat,
+    pub statement: Box<Statement>,
+}
+
+/// Function in `LANGUAGE wasm` that `export`s a module of `file` on the
+/// server, the file is read when the statement is executed
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct WasmFunctionStatement {
+    pub name: String,
+    pub params: Vec<Variable>,
+    pub returns: PostgreSqlType,
+    pub file: String,
+    pub export: String,
+}
+
+/// Aggregate whose `transition` and `finish` functions are looked up when
+/// the statement is executed
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct AggregateStatement {
+    pub name: String,
+    pub input: PostgreSqlType,
+    pub state: PostgreSqlType,
+    pub transition: String,
+    pub finish: Option<String>,
+    pub initial: Option<String>,
+}
+
+/// `COPY table_name [(column, ...)] FROM | TO 'file_name' [WITH] (option, ...)`
+/// that moves data between a table and a file on the server. `COPY ... FROM
+/// STDIN` is left to `sqlparser`
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CopyStatement {
+    pub table_name: ObjectName,
+    pub columns: Vec<String>,
+    pub direction: CopyDirection,
+    pub file_name: String,
+    pub options: CopyOptions,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum CopyDirection {
+    From,
+    To,
+}
+
+/// Options of CSV format, the only format that is supported by `COPY`
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CopyOptions {
+    pub header: bool,
+    pub delimiter: char,
+    pub quote: char,
+    /// unquoted value that stands for NULL
+    pub null: String,
+}
+
+impl Default for CopyOptions {
+    fn default() -> CopyOptions {
+        CopyOptions {
+            header: false,
+            delimiter: ',',
+            quote: '"',
+            null: String::new(),
+        }
+    }
+}
+
+/// Returns `None` if `sql` is not an extended statement and has to be parsed
+/// by `sqlparser`
+pub(crate) fn parse(dialect: &dyn Dialect, sql: &str) -> Option<Result<ExtendedStatement, ParserError>> {
+    let sql = script::dollar_quotes_to_literals(sql);
+    let tokens = match Tokenizer::new(dialect, &sql).tokenize() {
+        Ok(tokens) => tokens,
+        Err(_) => return None,
+    };
+    let mut parser = Parser::new(tokens);
+    let statement = match parser.peek_token() {
+        Token::Word(Word {
+            keyword: Keyword::GRANT,
+            ..
+        }) => {
+            parser.next_token();
+            parse_privilege_change(&mut parser, Keyword::TO).map(ExtendedStatement::Grant)
+        }
+        Token::Word(Word {
+            keyword: Keyword::REVOKE,
+            ..
+        }) => {
+            parser.next_token();
+            parse_privilege_change(&mut parser, Keyword::FROM).map(ExtendedStatement::Revoke)
+        }
+        Token::Word(Word {
+            keyword: Keyword::ALTER,
+            ..
+        }) => {
+            parser.next_token();
+            if parse_role_keyword(&mut parser) {
+                parse_alter_role(&mut parser)
+            } else if parse_word(&mut parser, "DATABASE") {
+                parse_alter_database(&mut parser)
+            } else if parser.parse_keyword(Keyword::DEFAULT) {
+                parse_alter_default_privileges(&mut parser)
+            } else if parser.parse_keyword(Keyword::TABLE) {
+                parse_alter_table(&mut parser)?
+            } else {
+                return None;
+            }
+        }
+        Token::Word(Word {
+            keyword: Keyword::COPY, ..
+        }) => {
+            parser.next_token();
+            parse_copy(&mut parser)?
+        }
+        Token::Word(Word {
+            keyword: Keyword::CREATE,
+            ..
+        }) => {
+            parser.next_token();
+            if parse_word(&mut parser, "PUBLICATION") {
+                parse_create_publication(&mut parser)
+            } else if parse_replication_slot(&mut parser) {
+                parse_create_replication_slot(&mut parser)
+            } else if parse_word(&mut parser, "FUNCTION") {
+                parse_create_function(&mut parser, false)
+            } else if parse_word(&mut parser, "AGGREGATE") {
+                parse_create_aggregate(&mut parser, false)
+            } else if parse_word(&mut parser, "EVENT") {
+                expect_word(&mut parser, "TRIGGER").and_then(|()| parse_create_event_trigger(&mut parser))
+            } else if parser.parse_keyword(Keyword::OR) {
+                if !parse_word(&mut parser, "REPLACE") {
+                    return None;
+                } else if parse_word(&mut parser, "FUNCTION") {
+                    parse_create_function(&mut parser, true)
+                } else if parse_word(&mut parser, "AGGREGATE") {
+                    parse_create_aggregate(&mut parser, true)
+                } else {
+                    return None;
+                }
+            } else if parser.parse_keyword(Keyword::FOREIGN) {
+                parser
+                    .expect_keyword(Keyword::TABLE)
+                    .and_then(|()| parse_create_foreign_table(&mut parser))
+            } else {
+                return None;
+            }
+        }
+        Token::Word(Word {
+            value,
+            quote_style: None,
+            ..
+        }) if value.to_uppercase() == "CLUSTER" => {
+            parser.next_token();
+            parse_cluster(&mut parser)
+        }
+        Token::Word(Word {
+            value,
+            quote_style: None,
+            ..
+        }) if value.to_uppercase() == "DO" => {
+            parser.next_token();
+            parse_do(&mut parser)
+        }
+        Token::Word(Word {
+            value,
+            quote_style: None,
+            ..
+        }) if value.to_uppercase() == "EXPLAIN" => {
+            parser.next_token();
+            parse_explain(&mut parser)
+        }
+        Token::Word(Word {
+            keyword: Keyword::DROP, ..
+        }) => {
+            parser.next_token();
+            if parse_word(&mut parser, "PUBLICATION") {
+                parse_drop_publication(&mut parser)
+            } else if parse_replication_slot(&mut parser) {
+                parse_drop_replication_slot(&mut parser)
+            } else if parse_word(&mut parser, "FUNCTION") {
+                parse_drop_function(&mut parser)
+            } else if parse_word(&mut parser, "EVENT") {
+                expect_word(&mut parser, "TRIGGER").and_then(|()| parse_drop_event_trigger(&mut parser))
+            } else {
+                return None;
+            }
+        }
+        Token::Word(Word {
+            keyword: Keyword::SAVEPOINT,
+            ..
+        }) => {
+            parser.next_token();
+            parse_savepoint_name(&mut parser).map(ExtendedStatement::Savepoint)
+        }
+        Token::Word(Word {
+            keyword: Keyword::RELEASE,
+            ..
+        }) => {
+            parser.next_token();
+            let _ = parser.parse_keyword(Keyword::SAVEPOINT);
+            parse_savepoint_name(&mut parser).map(ExtendedStatement::ReleaseSavepoint)
+        }
+        Token::Word(Word {
+            keyword: Keyword::ROLLBACK,
+            ..
+        }) => {
+            parser.next_token();
+            let _ = parser.parse_one_of_keywords(&[Keyword::TRANSACTION, Keyword::WORK]);
+            if !parser.parse_keyword(Keyword::TO) {
+                return None;
+            }
+            let _ = parser.parse_keyword(Keyword::SAVEPOINT);
+            parse_savepoint_name(&mut parser).map(ExtendedStatement::RollbackToSavepoint)
+        }
+        _ => return None,
+    };
+    Some(statement.and_then(|statement| expect_end_of_statement(&mut parser).map(|()| statement)))
+}
+
+fn parse_privilege_change(parser: &mut Parser, grantee_keyword: Keyword) -> Result<PrivilegeChange, ParserError> {
+    let privileges = parse_privileges(parser)?;
+    parser.expect_keyword(Keyword::ON)?;
+    let (objects, applicable) = if parser.parse_keyword(Keyword::SCHEMA) {
+        let names = parser.parse_comma_separated(Parser::parse_object_name)?;
+        (PrivilegeObjects::Schemas(names), Privilege::schema_privileges())
+    } else {
+        let _ = parser.parse_keyword(Keyword::TABLE);
+        let names = parser.parse_comma_separated(Parser::parse_object_name)?;
+        (PrivilegeObjects::Tables(names), Privilege::table_privileges())
+    };
+    let object_type = match objects {
+        PrivilegeObjects::Tables(_) => "table",
+        PrivilegeObjects::Schemas(_) => "schema",
+    };
+    let (privileges, column_privileges) = match privileges {
+        None => (applicable, vec![]),
+        Some(items) => {
+            let mut privileges = vec![];
+            let mut column_privileges = vec![];
+            for (privilege, columns) in items {
+                match columns {
+                    None => {
+                        check_applicable(privilege, &applicable, object_type)?;
+                        privileges.push(privilege);
+                    }
+                    Some(_) if object_type != "table" => {
+                        return Err(ParserError::ParserError(
+                            "column privileges are only valid for tables".to_owned(),
+                        ))
+                    }
+                    Some(columns) => {
+                        check_applicable(privilege, &Privilege::column_privileges(), "column")?;
+                        column_privileges.push((privilege, columns));
+                    }
+                }
+            }
+            (privileges, column_privileges)
+        }
+    };
+    parser.expect_keyword(grantee_keyword)?;
+    let grantees = parser.parse_comma_separated(parse_grantee)?;
+    Ok(PrivilegeChange {
+        privileges,
+        column_privileges,
+        objects,
+        grantees,
+    })
+}
+
+/// Returns `None` for `ALL [PRIVILEGES]`
+fn parse_privileges(parser: &mut Parser) -> Result<Option<Vec<PrivilegeItem>>, ParserError> {
+    if parser.parse_keyword(Keyword::ALL) {
+        parse_word(parser, "PRIVILEGES");
+        Ok(None)
+    } else {
+        parser.parse_comma_separated(parse_privilege_item).map(Some)
+    }
+}
+
+/// Privilege optionally followed by a list of columns
+fn parse_privilege_item(parser: &mut Parser) -> Result<PrivilegeItem, ParserError> {
+    let privilege = parse_privilege(parser)?;
+    if parser.consume_token(&Token::LParen) {
+        let columns = parser.parse_comma_separated(|parser| parser.parse_identifier().map(|ident| ident.value))?;
+        parser.expect_token(&Token::RParen)?;
+        Ok((privilege, Some(columns)))
+    } else {
+        Ok((privilege, None))
+    }
+}
+
+fn check_applicable(privilege: Privilege, applicable: &[Privilege], object_type: &str) -> Result<(), ParserError> {
+    if applicable.contains(&privilege) {
+        Ok(())
+    } else {
+        Err(ParserError::ParserError(format!(
+            "invalid privilege type {} for {}",
+            privilege, object_type
+        )))
+    }
+}
+
+fn parse_role_keyword(parser: &mut Parser) -> bool {
+    parser.parse_keyword(Keyword::USER) || parse_word(parser, "ROLE")
+}
+
+fn parse_alter_role(parser: &mut Parser) -> Result<ExtendedStatement, ParserError> {
+    let role = parser.parse_identifier()?.value;
+    if let Some(change) = parse_setting_change(parser)? {
+        return Ok(ExtendedStatement::AlterSettings {
+            owner: SettingsOwner::Role(role),
+            change,
+        });
+    }
+    let _ = parser.parse_keyword(Keyword::WITH);
+    let mut options = vec![];
+    loop {
+        if parse_word(parser, "PASSWORD") {
+            let password = match parser.next_token() {
+                Token::SingleQuotedString(password) => Some(ScramVerifier::new(password.as_str())),
+                Token::Word(Word {
+                    keyword: Keyword::NULL, ..
+                }) => None,
+                _ => return Err(ParserError::ParserError("Expected password string or NULL".to_owned())),
+            };
+            options.push(RoleOption::Password(password));
+        } else if parse_word(parser, "READONLY") {
+            options.push(RoleOption::ReadOnly(true));
+        } else if parse_word(parser, "NOREADONLY") {
+            options.push(RoleOption::ReadOnly(false));
+        } else if parse_word(parser, "CONNECTION") {
+            parser.expect_keyword(Keyword::LIMIT)?;
+            options.push(RoleOption::ConnectionLimit(parse_connection_limit(parser)?));
+        } else {
+            break;
+        }
+    }
+    if options.is_empty() {
+        return Err(ParserError::ParserError(format!(
+            "Expected PASSWORD, READONLY, NOREADONLY or CONNECTION LIMIT, found: {}",
+            parser.peek_token()
+        )));
+    }
+    Ok(ExtendedStatement::AlterRole { role, options })
+}
+
+fn parse_alter_database(parser: &mut Parser) -> Result<ExtendedStatement, ParserError> {
+    let database = parser.parse_identifier()?.value;
+    match parse_setting_change(parser)? {
+        Some(change) => Ok(ExtendedStatement::AlterSettings {
+            owner: SettingsOwner::Database(database),
+            change,
+        }),
+        None => Err(ParserError::ParserError(format!(
+            "Expected SET or RESET, found: {}",
+            parser.peek_token()
+        ))),
+    }
+}
+
+/// `None` if the statement does not change a setting
+fn parse_setting_change(parser: &mut Parser) -> Result<Option<SettingChange>, ParserError> {
+    if parser.parse_keyword(Keyword::SET) {
+        match parser.parse_set()? {
+            Statement::SetVariable {
+                variable,
+                value: SetVariableValue::Ident(value),
+                ..
+            } if value.value.to_lowercase() == "default" => Ok(Some(SettingChange::Reset(variable.value))),
+            Statement::SetVariable { variable, value, .. } => Ok(Some(SettingChange::Set(variable.value, value))),
+            _ => Err(ParserError::ParserError("Expected setting name".to_owned())),
+        }
+    } else if parse_word(parser, "RESET") {
+        if parser.parse_keyword(Keyword::ALL) {
+            Ok(Some(SettingChange::ResetAll))
+        } else {
+            Ok(Some(SettingChange::Reset(parser.parse_identifier()?.value)))
+        }
+    } else {
+        Ok(None)
+    }
+}
+
+fn parse_connection_limit(parser: &mut Parser) -> Result<Option<u32>, ParserError> {
+    let negative = parser.consume_token(&Token::Minus);
+    match (negative, parser.next_token()) {
+        (true, Token::Number(number)) if number == "1" => Ok(None),
+        (false, Token::Number(number)) => number
+            .parse()
+            .map(Some)
+            .map_err(|_| ParserError::ParserError(format!("invalid connection limit: {}", number))),
+        (_, other) => Err(ParserError::ParserError(format!(
+            "invalid connection limit: {}{}",
+            if negative { "-" } else { "" },
+            other
+        ))),
+    }
+}
+
+/// `ALTER DEFAULT PRIVILEGES [FOR ROLE role [, ...]] [IN SCHEMA schema [, ...]]
+/// {GRANT | REVOKE} privileges ON TABLES {TO | FROM} grantee [, ...]`
+fn parse_alter_default_privileges(parser: &mut Parser) -> Result<ExtendedStatement, ParserError> {
+    if !parse_word(parser, "PRIVILEGES") {
+        return Err(ParserError::ParserError(format!(
+            "Expected PRIVILEGES, found: {}",
+            parser.peek_token()
+        )));
+    }
+    let target_roles = if parser.parse_keyword(Keyword::FOR) {
+        if !parse_role_keyword(parser) {
+            return Err(ParserError::ParserError(format!(
+                "Expected ROLE, found: {}",
+                parser.peek_token()
+            )));
+        }
+        parser.parse_comma_separated(|parser| parser.parse_identifier().map(|ident| ident.value))?
+    } else {
+        vec![]
+    };
+    let schemas = if parser.parse_keyword(Keyword::IN) {
+        parser.expect_keyword(Keyword::SCHEMA)?;
+        parser.parse_comma_separated(Parser::parse_object_name)?
+    } else {
+        vec![]
+    };
+    let grant = if parser.parse_keyword(Keyword::GRANT) {
+        true
+    } else {
+        parser.expect_keyword(Keyword::REVOKE)?;
+        false
+    };
+    let privileges = parse_privileges(parser)?;
+    parser.expect_keyword(Keyword::ON)?;
+    if !parse_word(parser, "TABLES") {
+        return Err(ParserError::ParserError(format!(
+            "Expected TABLES, found: {}",
+            parser.peek_token()
+        )));
+    }
+    let privileges = match privileges {
+        None => Privilege::table_privileges(),
+        Some(items) => {
+            let mut privileges = vec![];
+            for (privilege, columns) in items {
+                if columns.is_some() {
+                    return Err(ParserError::ParserError(
+                        "default privileges can not be granted on columns".to_owned(),
+                    ));
+                }
+                check_applicable(privilege, &Privilege::table_privileges(), "table")?;
+                privileges.push(privilege);
+            }
+            privileges
+        }
+    };
+    parser.expect_keyword(if grant { Keyword::TO } else { Keyword::FROM })?;
+    let grantees = parser.parse_comma_separated(parse_grantee)?;
+    Ok(ExtendedStatement::AlterDefaultPrivileges(DefaultPrivilegesChange {
+        target_roles,
+        schemas,
+        grant,
+        privileges,
+        grantees,
+    }))
+}
+
+fn parse_privilege(parser: &mut Parser) -> Result<Privilege, ParserError> {
+    match parser.next_token() {
+        Token::Word(word) => match word.keyword {
+            Keyword::SELECT => Ok(Privilege::Select),
+            Keyword::INSERT => Ok(Privilege::Insert),
+            Keyword::UPDATE => Ok(Privilege::Update),
+            Keyword::DELETE => Ok(Privilege::Delete),
+            Keyword::CREATE => Ok(Privilege::Create),
+            _ if word.value.to_uppercase() == "USAGE" => Ok(Privilege::Usage),
+            _ => Err(ParserError::ParserError(format!(
+                "unrecognized privilege type {}",
+                word.value
+            ))),
+        },
+        other => Err(ParserError::ParserError(format!(
+            "Expected privilege type, found: {}",
+            other
+        ))),
+    }
+}
+
+/// Returns `None` if the statement does not copy to or from a file
+fn parse_copy(parser: &mut Parser) -> Option<Result<ExtendedStatement, ParserError>> {
+    let table_name = parser.parse_object_name().ok()?;
+    let columns = parser.parse_parenthesized_column_list(IsOptional::Optional).ok()?;
+    let direction = match parser.parse_one_of_keywords(&[Keyword::FROM, Keyword::TO])? {
+        Keyword::FROM => CopyDirection::From,
+        _ => CopyDirection::To,
+    };
+    let file_name = match parser.next_token() {
+        Token::SingleQuotedString(file_name) => file_name,
+        _ => return None,
+    };
+    Some(parse_copy_options(parser).map(|options| {
+        ExtendedStatement::Copy(CopyStatement {
+            table_name,
+            columns: columns.into_iter().map(|column| column.value).collect(),
+            direction,
+            file_name,
+            options,
+        })
+    }))
+}
+
+fn parse_copy_options(parser: &mut Parser) -> Result<CopyOptions, ParserError> {
+    let mut options = CopyOptions::default();
+    let mut format = None;
+    let _ = parser.parse_keyword(Keyword::WITH);
+    if parser.consume_token(&Token::LParen) {
+        loop {
+            if parse_word(parser, "FORMAT") {
+                format = Some(parser.parse_identifier()?.value.to_lowercase());
+            } else if parser.parse_keyword(Keyword::HEADER) {
+                options.header = parse_boolean_option(parser)?;
+            } else if parse_word(parser, "DELIMITER") {
+                options.delimiter = parse_character_option(parser, "delimiter")?;
+            } else if parse_word(parser, "QUOTE") {
+                options.quote = parse_character_option(parser, "quote")?;
+            } else if parser.parse_keyword(Keyword::NULL) {
+                options.null = parser.parse_literal_string()?;
+            } else {
+                return Err(ParserError::ParserError(format!(
+                    "Expected COPY option, found: {}",
+                    parser.peek_token()
+                )));
+            }
+            if !parser.consume_token(&Token::Comma) {
+                break;
+            }
+        }
+        parser.expect_token(&Token::RParen)?;
+    }
+    match format.as_deref() {
+        Some("csv") => {}
+        Some(format) => {
+            return Err(ParserError::ParserError(format!(
+                "COPY format \"{}\" is not supported",
+                format
+            )))
+        }
+        None => {
+            return Err(ParserError::ParserError(
+                "COPY supports only CSV format, it has to be specified with FORMAT csv".to_owned(),
+            ))
+        }
+    }
+    if options.delimiter == options.quote {
+        return Err(ParserError::ParserError(
+            "COPY delimiter and quote must be different".to_owned(),
+        ));
+    }
+    if options.null.contains(options.delimiter) {
+        return Err(ParserError::ParserError(
+            "COPY delimiter must not appear in the NULL specification".to_owned(),
+        ));
+    }
+    Ok(options)
+}
+
+/// Boolean option that is specified without a value is turned on
+fn parse_boolean_option(parser: &mut Parser) -> Result<bool, ParserError> {
+    match parser.peek_token() {
+        Token::Comma | Token::RParen => Ok(true),
+        _ => match parser.parse_identifier()?.value.to_lowercase().as_str() {
+            "true" | "on" => Ok(true),
+            "false" | "off" => Ok(false),
+            other => Err(ParserError::ParserError(format!(
+                "Expected boolean value, found: {}",
+                other
+            ))),
+        },
+    }
+}
+
+fn parse_character_option(parser: &mut Parser, option: &str) -> Result<char, ParserError> {
+    let value = parser.parse_literal_string()?;
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(character), None) if character.is_ascii() && character != '\n' && character != '\r' => Ok(character),
+        _ => Err(ParserError::ParserError(format!(
+            "COPY {} must be a single one-byte character",
+            option
+        ))),
+    }
+}
+
+/// `CREATE PUBLICATION name [FOR TABLE table_name, ... | FOR ALL TABLES]`
+fn parse_create_publication(parser: &mut Parser) -> Result<ExtendedStatement, ParserError> {
+    let name = parser.parse_identifier()?.value;
+    let tables = if parser.parse_keyword(Keyword::FOR) {
+        if parser.parse_keyword(Keyword::ALL) {
+            if !parse_word(parser, "TABLES") {
+                return Err(ParserError::ParserError(format!(
+                    "Expected TABLES, found: {}",
+                    parser.peek_token()
+                )));
+            }
+            PublicationTables::All
+        } else {
+            parser.expect_keyword(Keyword::TABLE)?;
+            PublicationTables::Tables(parser.parse_comma_separated(Parser::parse_object_name)?)
+        }
+    } else {
+        PublicationTables::Tables(vec![])
+    };
+    Ok(ExtendedStatement::CreatePublication { name, tables })
+}
+
+fn parse_create_foreign_table(parser: &mut Parser) -> Result<ExtendedStatement, ParserError> {
+    let name = parser.parse_object_name()?;
+    parser.expect_token(&Token::LParen)?;
+    let columns = parser.parse_comma_separated(|parser| {
+        let name = parser.parse_identifier()?;
+        let data_type = parser.parse_data_type()?;
+        Ok(ColumnDef {
+            name,
+            data_type,
+            collation: None,
+            options: vec![],
+        })
+    })?;
+    parser.expect_token(&Token::RParen)?;
+    if !parse_word(parser, "SERVER") {
+        return Err(ParserError::ParserError(format!(
+            "Expected SERVER, found: {}",
+            parser.peek_token()
+        )));
+    }
+    let server = parser.parse_identifier()?.value;
+    let options = if parse_word(parser, "OPTIONS") {
+        parser.expect_token(&Token::LParen)?;
+        let options = parser.parse_comma_separated(|parser| {
+            let option = parser.parse_identifier()?.value;
+            match parser.next_token() {
+                Token::SingleQuotedString(value) => Ok((option, value)),
+                other => Err(ParserError::ParserError(format!(
+                    "Expected option value string, found: {}",
+                    other
+                ))),
+            }
+        })?;
+        parser.expect_token(&Token::RParen)?;
+        options
+    } else {
+        vec![]
+    };
+    Ok(ExtendedStatement::CreateForeignTable(ForeignTableStatement {
+        name,
+        columns,
+        server,
+        options,
+    }))
+}
+
+/// `CLUSTER table_name USING index_name | ORDER BY column [ASC | DESC], ...`
+fn parse_cluster(parser: &mut Parser) -> Result<ExtendedStatement, ParserError> {
+    let table_name = parser.parse_object_name()?;
+    let order = if parser.parse_keyword(Keyword::USING) {
+        ClusterOrder::Index(parser.parse_identifier()?.value)
+    } else {
+        parser.expect_keywords(&[Keyword::ORDER, Keyword::BY])?;
+        ClusterOrder::Columns(parser.parse_comma_separated(|parser| {
+            let column = parser.parse_identifier()?.value;
+            let descending = parser.parse_one_of_keywords(&[Keyword::ASC, Keyword::DESC]) == Some(Keyword::DESC);
+            Ok((column, descending))
+        })?)
+    };
+    Ok(ExtendedStatement::Cluster(ClusterStatement { table_name, order }))
+}
+
+/// `ALTER TABLE name ALTER [COLUMN] column {SET DEFAULT expr | DROP DEFAULT |
+/// SET NOT NULL | DROP NOT NULL}, ...`, other forms of `ALTER TABLE` are left
+/// to `sqlparser`
+fn parse_alter_table(parser: &mut Parser) -> Option<Result<ExtendedStatement, ParserError>> {
+    let table_name = parser.parse_object_name().ok()?;
+    if !parser.parse_keyword(Keyword::ALTER) {
+        return None;
+    }
+    let mut changes = vec![];
+    let parsed = loop {
+        match parse_column_change(parser) {
+            Ok(change) => changes.push(change),
+            Err(error) => break Err(error),
+        }
+        if !parser.consume_token(&Token::Comma) {
+            break Ok(());
+        }
+        if let Err(error) = parser.expect_keyword(Keyword::ALTER) {
+            break Err(error);
+        }
+    };
+    Some(parsed.map(|()| ExtendedStatement::AlterTable(AlterTableStatement { table_name, changes })))
+}
+
+fn parse_column_change(parser: &mut Parser) -> Result<(String, ColumnChange), ParserError> {
+    let _ = parser.parse_keyword(Keyword::COLUMN);
+    let column = parser.parse_identifier()?.value;
+    let change = if parser.parse_keyword(Keyword::SET) {
+        if parser.parse_keyword(Keyword::DEFAULT) {
+            ColumnChange::SetDefault(parser.parse_expr()?)
+        } else {
+            parser.expect_keywords(&[Keyword::NOT, Keyword::NULL])?;
+            ColumnChange::SetNotNull
+        }
+    } else {
+        parser.expect_keyword(Keyword::DROP)?;
+        if parser.parse_keyword(Keyword::DEFAULT) {
+            ColumnChange::DropDefault
+        } else {
+            parser.expect_keywords(&[Keyword::NOT, Keyword::NULL])?;
+            ColumnChange::DropNotNull
+        }
+    };
+    Ok((column, change))
+}
+
+/// `DROP PUBLICATION [IF EXISTS] name`
+fn parse_explain(parser: &mut Parser) -> Result<ExtendedStatement, ParserError> {
+    let mut format = ExplainFormat::Text;
+    if parser.consume_token(&Token::LParen) {
+        loop {
+            let option = parser.parse_identifier()?.value.to_lowercase();
+            match option.as_str() {
+                "format" => {
+                    format = match parser.parse_identifier()?.value.to_lowercase().as_str() {
+                        "text" => ExplainFormat::Text,
+                        "json" => ExplainFormat::Json,
+                        other => {
+                            return Err(ParserError::ParserError(format!(
+                                "unrecognized value for EXPLAIN option \"format\": \"{}\"",
+                                other
+                            )))
+                        }
+                    }
+                }
+                _ => {
+                    return Err(ParserError::ParserError(format!(
+                        "unrecognized EXPLAIN option \"{}\"",
+                        option
+                    )))
+                }
+            }
+            if !parser.consume_token(&Token::Comma) {
+                break;
+            }
+        }
+        parser.expect_token(&Token::RParen)?;
+    }
+    let statement = parser.parse_statement()?;
+    Ok(ExtendedStatement::Explain(ExplainStatement {
+        format,
+        statement: Box::new(statement),
+    }))
+}
+
+fn parse_drop_publication(parser: &mut Parser) -> Result<ExtendedStatement, ParserError> {
+    let if_exists = parser.parse_keywords(&[Keyword::IF, Keyword::EXISTS]);
+    let name = parser.parse_identifier()?.value;
+    Ok(ExtendedStatement::DropPublication { name, if_exists })
+}
+
+/// `CREATE [OR REPLACE] FUNCTION name ([parameter type, ...]) RETURNS
+/// {type | event_trigger} AS 'body' [LANGUAGE plpgsql]`, the body is usually
+/// dollar quoted. Functions in `LANGUAGE wasm` are defined as
+/// `AS 'file' [, 'function']` where the function is exported by the module
+/// of the file, it has the same name as the created one by default.
+fn parse_create_function(parser: &mut Parser, or_replace: bool) -> Result<ExtendedStatement, ParserError> {
+    let name = procedural::identifier(&parser.parse_identifier()?);
+    parser.expect_token(&Token::LParen)?;
+    let params = if parser.consume_token(&Token::RParen) {
+        vec![]
+    } else {
+        let params = parser.parse_comma_separated(|parser| {
+            let name = procedural::identifier(&parser.parse_identifier()?);
+            let pg_type = procedural::parse_type(&parser.parse_data_type()?)?;
+            Ok(Variable { name, pg_type })
+        })?;
+        parser.expect_token(&Token::RParen)?;
+        params
+    };
+    if !parse_word(parser, "RETURNS") {
+        return Err(ParserError::ParserError(format!(
+            "Expected RETURNS, found: {}",
+            parser.peek_token()
+        )));
+    }
+    let returns = if parse_word(parser, "EVENT_TRIGGER") {
+        Returns::EventTrigger
+    } else {
+        Returns::Type(procedural::parse_type(&parser.parse_data_type()?)?)
+    };
+    let mut definition = None;
+    let mut language = None;
+    loop {
+        if parser.parse_keyword(Keyword::AS) {
+            let mut strings = vec![];
+            loop {
+                match parser.next_token() {
+                    Token::SingleQuotedString(text) => strings.push(text),
+                    other => {
+                        return Err(ParserError::ParserError(format!(
+                            "Expected function body string, found: {}",
+                            other
+                        )))
+                    }
+                }
+                if !parser.consume_token(&Token::Comma) {
+                    break;
+                }
+            }
+            definition = Some(strings);
+        } else if let Some(name) = parse_language(parser)? {
+            language = Some(name);
+        } else {
+            break;
+        }
+    }
+    let definition = match definition {
+        Some(definition) => definition,
+        None => {
+            return Err(ParserError::ParserError(format!(
+                "Expected AS, found: {}",
+                parser.peek_token()
+            )))
+        }
+    };
+    let language = language.unwrap_or_else(|| procedural::LANGUAGE.to_owned());
+    match (language.as_str(), definition.as_slice(), returns) {
+        (procedural::LANGUAGE, [body], _) => Ok(ExtendedStatement::CreateFunction {
+            function: Function {
+                name,
+                params,
+                returns,
+                body: Body::Block(procedural::parse_body(body)?, body.clone()),
+            },
+            or_replace,
+        }),
+        (procedural::LANGUAGE, _, _) => Err(ParserError::ParserError(
+            "only one function body can be specified".to_owned(),
+        )),
+        (wasm::LANGUAGE, _, Returns::EventTrigger) => Err(ParserError::ParserError(format!(
+            "functions in language {} can't return event_trigger",
+            wasm::LANGUAGE
+        ))),
+        (wasm::LANGUAGE, [file], Returns::Type(returns)) | (wasm::LANGUAGE, [file, _], Returns::Type(returns)) => {
+            let export = definition.get(1).cloned().unwrap_or_else(|| name.clone());
+            Ok(ExtendedStatement::CreateWasmFunction {
+                function: WasmFunctionStatement {
+                    name,
+                    params,
+                    returns,
+                    file: file.clone(),
+                    export,
+                },
+                or_replace,
+            })
+        }
+        (wasm::LANGUAGE, _, _) => Err(ParserError::ParserError(
+            "Expected 'file' [, 'function'] of a WebAssembly module".to_owned(),
+        )),
+        (other, _, _) => Err(unsupported_language(other)),
+    }
+}
+
+/// `DO [LANGUAGE plpgsql] 'code' [LANGUAGE plpgsql]`, the code is usually
+/// dollar quoted
+fn parse_do(parser: &mut Parser) -> Result<ExtendedStatement, ParserError> {
+    let mut language = parse_language(parser)?;
+    let block = match parser.next_token() {
+        Token::SingleQuotedString(code) => procedural::parse_body(&code)?,
+        other => {
+            return Err(ParserError::ParserError(format!(
+                "Expected code string, found: {}",
+                other
+            )))
+        }
+    };
+    if language.is_none() {
+        language = parse_language(parser)?;
+    }
+    match language {
+        Some(language) if language != procedural::LANGUAGE => Err(unsupported_language(&language)),
+        _ => Ok(ExtendedStatement::Do(block)),
+    }
+}
+
+/// `LANGUAGE name`, returns `None` if there is no language clause
+fn parse_language(parser: &mut Parser) -> Result<Option<String>, ParserError> {
+    if parse_word(parser, "LANGUAGE") {
+        Ok(Some(parser.parse_identifier()?.value.to_lowercase()))
+    } else {
+        Ok(None)
+    }
+}
+
+fn unsupported_language(language: &str) -> ParserError {
+    ParserError::ParserError(format!("language \"{}\" is not supported", language))
+}
+
+/// `DROP FUNCTION [IF EXISTS] name [([type, ...])]`, functions can't be
+/// overloaded, so argument types are not needed to find one
+fn parse_drop_function(parser: &mut Parser) -> Result<ExtendedStatement, ParserError> {
+    let if_exists = parser.parse_keywords(&[Keyword::IF, Keyword::EXISTS]);
+    let name = procedural::identifier(&parser.parse_identifier()?);
+    if parser.consume_token(&Token::LParen) && !parser.consume_token(&Token::RParen) {
+        parser.parse_comma_separated(Parser::parse_data_type)?;
+        parser.expect_token(&Token::RParen)?;
+    }
+    Ok(ExtendedStatement::DropFunction { name, if_exists })
+}
+
+/// `CREATE AGGREGATE name (type) (SFUNC = function, STYPE = type
+/// [, FINALFUNC = function] [, INITCOND = 'value'])`
+fn parse_create_aggregate(parser: &mut Parser, or_replace: bool) -> Result<ExtendedStatement, ParserError> {
+    let name = procedural::identifier(&parser.parse_identifier()?);
+    parser.expect_token(&Token::LParen)?;
+    let input = procedural::parse_type(&parser.parse_data_type()?)?;
+    parser.expect_token(&Token::RParen)?;
+    parser.expect_token(&Token::LParen)?;
+    let mut transition = None;
+    let mut state = None;
+    let mut finish = None;
+    let mut initial = None;
+    loop {
+        let option = parser.parse_identifier()?.value.to_uppercase();
+        parser.expect_token(&Token::Eq)?;
+        match option.as_str() {
+            "SFUNC" => transition = Some(procedural::identifier(&parser.parse_identifier()?)),
+            "STYPE" => state = Some(procedural::parse_type(&parser.parse_data_type()?)?),
+            "FINALFUNC" => finish = Some(procedural::identifier(&parser.parse_identifier()?)),
+            "INITCOND" => initial = Some(parser.parse_literal_string()?),
+            _ => {
+                return Err(ParserError::ParserError(format!(
+                    "aggregate attribute \"{}\" not recognized",
+                    option.to_lowercase()
+                )))
+            }
+        }
+        if !parser.consume_token(&Token::Comma) {
+            break;
+        }
+    }
+    parser.expect_token(&Token::RParen)?;
+    match (transition, state) {
+        (Some(transition), Some(state)) => Ok(ExtendedStatement::CreateAggregate {
+            aggregate: AggregateStatement {
+                name,
+                input,
+                state,
+                transition,
+                finish,
+                initial,
+            },
+            or_replace,
+        }),
+        (None, _) => Err(ParserError::ParserError("aggregate sfunc must be specified".to_owned())),
+        (_, None) => Err(ParserError::ParserError("aggregate stype must be specified".to_owned())),
+    }
+}
+
+/// `CREATE EVENT TRIGGER name ON {ddl_command_start | ddl_command_end}
+/// [WHEN TAG IN ('tag', ...)] EXECUTE {FUNCTION | PROCEDURE} function()`
+fn parse_create_event_trigger(parser: &mut Parser) -> Result<ExtendedStatement, ParserError> {
+    let name = procedural::identifier(&parser.parse_identifier()?);
+    parser.expect_keyword(Keyword::ON)?;
+    let event = parser.parse_identifier()?.value;
+    let event = DdlEvent::parse(&event)
+        .ok_or_else(|| ParserError::ParserError(format!("unrecognized event name \"{}\"", event)))?;
+    let tags = if parse_word(parser, "WHEN") {
+        expect_word(parser, "TAG")?;
+        parser.expect_keyword(Keyword::IN)?;
+        parser.expect_token(&Token::LParen)?;
+        let tags = parser.parse_comma_separated(|parser| {
+            let tag = parser.parse_literal_string()?;
+            let normalized = tag.split_whitespace().collect::<Vec<&str>>().join(" ").to_uppercase();
+            if DDL_TAGS.contains(&normalized.as_str()) {
+                Ok(normalized)
+            } else {
+                Err(ParserError::ParserError(format!(
+                    "filter value \"{}\" not recognized for filter variable \"tag\"",
+                    tag
+                )))
+            }
+        })?;
+        parser.expect_token(&Token::RParen)?;
+        Some(tags)
+    } else {
+        None
+    };
+    expect_word(parser, "EXECUTE")?;
+    if !parse_word(parser, "FUNCTION") && !parse_word(parser, "PROCEDURE") {
+        return Err(ParserError::ParserError(format!(
+            "Expected FUNCTION, found: {}",
+            parser.peek_token()
+        )));
+    }
+    let function = procedural::identifier(&parser.parse_identifier()?);
+    parser.expect_token(&Token::LParen)?;
+    parser.expect_token(&Token::RParen)?;
+    Ok(ExtendedStatement::CreateEventTrigger(EventTrigger {
+        name,
+        event,
+        tags,
+        function,
+    }))
+}
+
+/// `DROP EVENT TRIGGER [IF EXISTS] name`
+fn parse_drop_event_trigger(parser: &mut Parser) -> Result<ExtendedStatement, ParserError> {
+    let if_exists = parser.parse_keywords(&[Keyword::IF, Keyword::EXISTS]);
+    let name = procedural::identifier(&parser.parse_identifier()?);
+    Ok(ExtendedStatement::DropEventTrigger { name, if_exists })
+}
+
+fn parse_savepoint_name(parser: &mut Parser) -> Result<String, ParserError> {
+    match procedural::identifier(&parser.parse_identifier()?) {
+        name if name.is_empty() => Err(ParserError::ParserError("zero-length delimited identifier".to_owned())),
+        name => Ok(name),
+    }
+}
+
+/// `REPLICATION SLOT` after `CREATE` or `DROP`, nothing is consumed
+/// otherwise
+fn parse_replication_slot(parser: &mut Parser) -> bool {
+    match (parser.peek_token(), parser.peek_nth_token(1)) {
+        (Token::Word(replication), Token::Word(slot))
+            if replication.quote_style.is_none()
+                && slot.quote_style.is_none()
+                && replication.value.to_uppercase() == "REPLICATION"
+                && slot.value.to_uppercase() == "SLOT" =>
+        {
+            parser.next_token();
+            parser.next_token();
+            true
+        }
+        _ => false,
+    }
+}
+
+/// `CREATE REPLICATION SLOT name FOR PUBLICATION publication`
+fn parse_create_replication_slot(parser: &mut Parser) -> Result<ExtendedStatement, ParserError> {
+    let name = parser.parse_identifier()?.value;
+    parser.expect_keyword(Keyword::FOR)?;
+    if !parse_word(parser, "PUBLICATION") {
+        return Err(ParserError::ParserError(format!(
+            "Expected PUBLICATION, found: {}",
+            parser.peek_token()
+        )));
+    }
+    let publication = parser.parse_identifier()?.value;
+    Ok(ExtendedStatement::CreateReplicationSlot { name, publication })
+}
+
+/// `DROP REPLICATION SLOT [IF EXISTS] name`
+fn parse_drop_replication_slot(parser: &mut Parser) -> Result<ExtendedStatement, ParserError> {
+    let if_exists = parser.parse_keywords(&[Keyword::IF, Keyword::EXISTS]);
+    let name = parser.parse_identifier()?.value;
+    Ok(ExtendedStatement::DropReplicationSlot { name, if_exists })
+}
+
+fn parse_grantee(parser: &mut Parser) -> Result<String, ParserError> {
+    if parse_word(parser, "PUBLIC") {
+        Ok(PUBLIC.to_owned())
+    } else {
+        parser.parse_identifier().map(|ident| ident.value)
+    }
+}
+
+/// Consumes the next token if it is an unquoted word that is not a
+/// `sqlparser` keyword
+fn parse_word(parser: &mut Parser, expected: &str) -> bool {
+    match parser.peek_token() {
+        Token::Word(Word {
+            value,
+            quote_style: None,
+            ..
+        }) if value.to_uppercase() == expected => {
+            parser.next_token();
+            true
+        }
+        _ => false,
+    }
+}
+
+fn expect_word(parser: &mut Parser, expected: &str) -> Result<(), ParserError> {
+    if parse_word(parser, expected) {
+        Ok(())
+    } else {
+        Err(ParserError::ParserError(format!(
+            "Expected {}, found: {}",
+            expected,
+            parser.peek_token()
+        )))
+    }
+}
+
+fn expect_end_of_statement(parser: &mut Parser) -> Result<(), ParserError> {
+    let _ = parser.consume_token(&Token::SemiColon);
+    match parser.peek_token() {
+        Token::EOF => Ok(()),
+        other => Err(ParserError::ParserError(format!(
+            "Expected end of statement, found: {}",
+            other
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::procedural::{Condition, Handler, Statement};
+    use sqlparser::{
+        ast::{DataType, Ident, Value},
+        dialect::PostgreSqlDialect,
+    };
+
+    fn name(parts: &[&str]) -> ObjectName {
+        ObjectName(parts.iter().map(|part| Ident::new(*part)).collect())
+    }
+
+    #[test]
+    fn not_extended_statement() {
+        assert_eq!(
+            parse(&PostgreSqlDialect {}, "select * from schema_name.table_name;"),
+            None
+        );
+    }
+
+    #[test]
+    fn grant_table_privileges() {
+        assert_eq!(
+            parse(
+                &PostgreSqlDialect {},
+                "grant select, insert on table schema_name.table_name to role_1, public;"
+            ),
+            Some(Ok(ExtendedStatement::Grant(PrivilegeChange {
+                privileges: vec![Privilege::Select, Privilege::Insert],
+                column_privileges: vec![],
+                objects: PrivilegeObjects::Tables(vec![name(&["schema_name", "table_name"])]),
+                grantees: vec!["role_1".to_owned(), PUBLIC.to_owned()],
+            })))
+        );
+    }
+
+    #[test]
+    fn revoke_all_schema_privileges() {
+        assert_eq!(
+            parse(
+                &PostgreSqlDialect {},
+                "revoke all privileges on schema schema_name from role_1"
+            ),
+            Some(Ok(ExtendedStatement::Revoke(PrivilegeChange {
+                privileges: Privilege::schema_privileges(),
+                column_privileges: vec![],
+                objects: PrivilegeObjects::Schemas(vec![name(&["schema_name"])]),
+                grantees: vec!["role_1".to_owned()],
+            })))
+        );
+    }
+
+    #[test]
+    fn privilege_is_not_applicable_to_object() {
+        assert_eq!(
+            parse(&PostgreSqlDialect {}, "grant usage on schema_name.table_name to role_1"),
+            Some(Err(ParserError::ParserError(
+                "invalid privilege type USAGE for table".to_owned()
+            )))
+        );
+    }
+
+    #[test]
+    fn grant_column_privileges() {
+        assert_eq!(
+            parse(
+                &PostgreSqlDialect {},
+                "grant select (column_1, column_2), update (column_2), delete on schema_name.table_name to role_1"
+            ),
+            Some(Ok(ExtendedStatement::Grant(PrivilegeChange {
+                privileges: vec![Privilege::Delete],
+                column_privileges: vec![
+                    (Privilege::Select, vec!["column_1".to_owned(), "column_2".to_owned()]),
+                    (Privilege::Update, vec!["column_2".to_owned()]),
+                ],
+                objects: PrivilegeObjects::Tables(vec![name(&["schema_name", "table_name"])]),
+                grantees: vec!["role_1".to_owned()],
+            })))
+        );
+    }
+
+    #[test]
+    fn column_privileges_are_not_applicable() {
+        assert_eq!(
+            parse(
+                &PostgreSqlDialect {},
+                "grant delete (column_1) on schema_name.table_name to role_1"
+            ),
+            Some(Err(ParserError::ParserError(
+                "invalid privilege type DELETE for column".to_owned()
+            )))
+        );
+        assert_eq!(
+            parse(
+                &PostgreSqlDialect {},
+                "grant create (column_1) on schema schema_name to role_1"
+            ),
+            Some(Err(ParserError::ParserError(
+                "column privileges are only valid for tables".to_owned()
+            )))
+        );
+    }
+
+    #[test]
+    fn alter_user_password() {
+        match parse(&PostgreSqlDialect {}, "alter user role_1 with password 'secret';") {
+            Some(Ok(ExtendedStatement::AlterRole { role, options })) => {
+                assert_eq!(role, "role_1");
+                match options.as_slice() {
+                    [RoleOption::Password(Some(verifier))] => assert!(verifier.matches("secret")),
+                    other => panic!("unexpected {:?}", other),
+                }
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn alter_role_remove_password() {
+        assert_eq!(
+            parse(&PostgreSqlDialect {}, "alter role role_1 password null"),
+            Some(Ok(ExtendedStatement::AlterRole {
+                role: "role_1".to_owned(),
+                options: vec![RoleOption::Password(None)],
+            }))
+        );
+    }
+
+    #[test]
+    fn alter_role_read_only() {
+        assert_eq!(
+            parse(&PostgreSqlDialect {}, "alter role role_1 with readonly"),
+            Some(Ok(ExtendedStatement::AlterRole {
+                role: "role_1".to_owned(),
+                options: vec![RoleOption::ReadOnly(true)],
+            }))
+        );
+        assert_eq!(
+            parse(&PostgreSqlDialect {}, "alter user role_1 noreadonly password null"),
+            Some(Ok(ExtendedStatement::AlterRole {
+                role: "role_1".to_owned(),
+                options: vec![RoleOption::ReadOnly(false), RoleOption::Password(None)],
+            }))
+        );
+    }
+
+    #[test]
+    fn alter_role_connection_limit() {
+        assert_eq!(
+            parse(&PostgreSqlDialect {}, "alter role role_1 with connection limit 10"),
+            Some(Ok(ExtendedStatement::AlterRole {
+                role: "role_1".to_owned(),
+                options: vec![RoleOption::ConnectionLimit(Some(10))],
+            }))
+        );
+        assert_eq!(
+            parse(&PostgreSqlDialect {}, "alter role role_1 connection limit -1"),
+            Some(Ok(ExtendedStatement::AlterRole {
+                role: "role_1".to_owned(),
+                options: vec![RoleOption::ConnectionLimit(None)],
+            }))
+        );
+        assert_eq!(
+            parse(&PostgreSqlDialect {}, "alter role role_1 connection limit -2"),
+            Some(Err(ParserError::ParserError("invalid connection limit: -2".to_owned())))
+        );
+    }
+
+    #[test]
+    fn alter_role_settings() {
+        assert_eq!(
+            parse(&PostgreSqlDialect {}, "alter role role_1 set work_mem = '1MB'"),
+            Some(Ok(ExtendedStatement::AlterSettings {
+                owner: SettingsOwner::Role("role_1".to_owned()),
+                change: SettingChange::Set(
+                    "work_mem".to_owned(),
+                    SetVariableValue::Literal(Value::SingleQuotedString("1MB".to_owned()))
+                ),
+            }))
+        );
+        assert_eq!(
+            parse(&PostgreSqlDialect {}, "alter user role_1 set search_path to default"),
+            Some(Ok(ExtendedStatement::AlterSettings {
+                owner: SettingsOwner::Role("role_1".to_owned()),
+                change: SettingChange::Reset("search_path".to_owned()),
+            }))
+        );
+        assert_eq!(
+            parse(&PostgreSqlDialect {}, "alter role role_1 reset all"),
+            Some(Ok(ExtendedStatement::AlterSettings {
+                owner: SettingsOwner::Role("role_1".to_owned()),
+                change: SettingChange::ResetAll,
+            }))
+        );
+    }
+
+    #[test]
+    fn alter_database_settings() {
+        assert_eq!(
+            parse(&PostgreSqlDialect {}, "alter database db_1 set search_path to schema_1"),
+            Some(Ok(ExtendedStatement::AlterSettings {
+                owner: SettingsOwner::Database("db_1".to_owned()),
+                change: SettingChange::Set(
+                    "search_path".to_owned(),
+                    SetVariableValue::Ident(Ident::new("schema_1"))
+                ),
+            }))
+        );
+        assert_eq!(
+            parse(&PostgreSqlDialect {}, "alter database db_1 reset statement_timeout"),
+            Some(Ok(ExtendedStatement::AlterSettings {
+                owner: SettingsOwner::Database("db_1".to_owned()),
+                change: SettingChange::Reset("statement_timeout".to_owned()),
+            }))
+        );
+        assert!(matches!(
+            parse(&PostgreSqlDialect {}, "alter database db_1 rename to db_2"),
+            Some(Err(_))
+        ));
+    }
+
+    #[test]
+    fn alter_role_without_options() {
+        assert!(matches!(
+            parse(&PostgreSqlDialect {}, "alter role role_1"),
+            Some(Err(_))
+        ));
+    }
+
+    #[test]
+    fn alter_default_privileges_grant() {
+        assert_eq!(
+            parse(
+                &PostgreSqlDialect {},
+                "alter default privileges for role role_1 in schema schema_name grant select on tables to public"
+            ),
+            Some(Ok(ExtendedStatement::AlterDefaultPrivileges(DefaultPrivilegesChange {
+                target_roles: vec!["role_1".to_owned()],
+                schemas: vec![name(&["schema_name"])],
+                grant: true,
+                privileges: vec![Privilege::Select],
+                grantees: vec![PUBLIC.to_owned()],
+            })))
+        );
+    }
+
+    #[test]
+    fn alter_default_privileges_revoke_all() {
+        assert_eq!(
+            parse(
+                &PostgreSqlDialect {},
+                "alter default privileges revoke all on tables from role_2;"
+            ),
+            Some(Ok(ExtendedStatement::AlterDefaultPrivileges(DefaultPrivilegesChange {
+                target_roles: vec![],
+                schemas: vec![],
+                grant: false,
+                privileges: Privilege::table_privileges(),
+                grantees: vec!["role_2".to_owned()],
+            })))
+        );
+    }
+
+    #[test]
+    fn default_privileges_only_on_tables() {
+        assert!(matches!(
+            parse(
+                &PostgreSqlDialect {},
+                "alter default privileges grant usage on schemas to role_1"
+            ),
+            Some(Err(_))
+        ));
+        assert_eq!(
+            parse(
+                &PostgreSqlDialect {},
+                "alter default privileges grant usage on tables to role_1"
+            ),
+            Some(Err(ParserError::ParserError(
+                "invalid privilege type USAGE for table".to_owned()
+            )))
+        );
+    }
+
+    #[test]
+    fn alter_table_is_not_extended_statement() {
+        assert_eq!(
+            parse(
+                &PostgreSqlDialect {},
+                "alter table schema_name.table_name add column col smallint"
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn alter_table_column_defaults_and_nullability() {
+        assert_eq!(
+            parse(
+                &PostgreSqlDialect {},
+                "alter table schema_name.table_name alter column col_1 set default 10, alter col_2 drop default, \
+                alter col_1 set not null, alter column col_2 drop not null"
+            ),
+            Some(Ok(ExtendedStatement::AlterTable(AlterTableStatement {
+                table_name: name(&["schema_name", "table_name"]),
+                changes: vec![
+                    (
+                        "col_1".to_owned(),
+                        ColumnChange::SetDefault(Expr::Value(Value::Number(10.into())))
+                    ),
+                    ("col_2".to_owned(), ColumnChange::DropDefault),
+                    ("col_1".to_owned(), ColumnChange::SetNotNull),
+                    ("col_2".to_owned(), ColumnChange::DropNotNull),
+                ],
+            })))
+        );
+    }
+
+    #[test]
+    fn alter_table_column_without_action() {
+        assert!(matches!(
+            parse(
+                &PostgreSqlDialect {},
+                "alter table schema_name.table_name alter column col_1 set null"
+            ),
+            Some(Err(_))
+        ));
+    }
+
+    #[test]
+    fn explain_in_json_format() {
+        let statement = match parse(
+            &PostgreSqlDialect {},
+            "explain (format json) select * from schema_name.table_name",
+        ) {
+            Some(Ok(ExtendedStatement::Explain(explain))) => explain,
+            other => panic!("unexpected {:?}", other),
+        };
+        assert_eq!(statement.format, ExplainFormat::Json);
+        assert_eq!(statement.statement.to_string(), "SELECT * FROM schema_name.table_name");
+    }
+
+    #[test]
+    fn explain_in_default_format() {
+        assert!(matches!(
+            parse(&PostgreSqlDialect {}, "explain delete from schema_name.table_name;"),
+            Some(Ok(ExtendedStatement::Explain(ExplainStatement {
+                format: ExplainFormat::Text,
+                ..
+            })))
+        ));
+    }
+
+    #[test]
+    fn explain_with_unknown_option() {
+        assert_eq!(
+            parse(&PostgreSqlDialect {}, "explain (format yaml) select 1"),
+            Some(Err(ParserError::ParserError(
+                "unrecognized value for EXPLAIN option \"format\": \"yaml\"".to_owned()
+            )))
+        );
+        assert_eq!(
+            parse(&PostgreSqlDialect {}, "explain (costs) select 1"),
+            Some(Err(ParserError::ParserError(
+                "unrecognized EXPLAIN option \"costs\"".to_owned()
+            )))
+        );
+    }
+
+    #[test]
+    fn trailing_tokens() {
+        assert!(matches!(
+            parse(
+                &PostgreSqlDialect {},
+                "grant create on schema schema_name to role_1 cascade"
+            ),
+            Some(Err(_))
+        ));
+    }
+
+    #[test]
+    fn copy_from_file() {
+        assert_eq!(
+            parse(
+                &PostgreSqlDialect {},
+                "copy schema_name.table_name (column_1, column_2) from '/tmp/data.csv' \
+                 with (format csv, header, delimiter ';', quote '|', null 'NULL');"
+            ),
+            Some(Ok(ExtendedStatement::Copy(CopyStatement {
+                table_name: name(&["schema_name", "table_name"]),
+                columns: vec!["column_1".to_owned(), "column_2".to_owned()],
+                direction: CopyDirection::From,
+                file_name: "/tmp/data.csv".to_owned(),
+                options: CopyOptions {
+                    header: true,
+                    delimiter: ';',
+                    quote: '|',
+                    null: "NULL".to_owned(),
+                },
+            })))
+        );
+    }
+
+    #[test]
+    fn copy_to_file() {
+        assert_eq!(
+            parse(
+                &PostgreSqlDialect {},
+                "copy schema_name.table_name to '/tmp/data.csv' (format csv, header false)"
+            ),
+            Some(Ok(ExtendedStatement::Copy(CopyStatement {
+                table_name: name(&["schema_name", "table_name"]),
+                columns: vec![],
+                direction: CopyDirection::To,
+                file_name: "/tmp/data.csv".to_owned(),
+                options: CopyOptions::default(),
+            })))
+        );
+    }
+
+    #[test]
+    fn copy_from_stdin_is_not_extended_statement() {
+        assert_eq!(
+            parse(&PostgreSqlDialect {}, "copy schema_name.table_name from stdin;"),
+            None
+        );
+    }
+
+    #[test]
+    fn copy_in_text_format() {
+        assert_eq!(
+            parse(
+                &PostgreSqlDialect {},
+                "copy schema_name.table_name from '/tmp/data.txt' with (format text)"
+            ),
+            Some(Err(ParserError::ParserError(
+                "COPY format \"text\" is not supported".to_owned()
+            )))
+        );
+        assert!(matches!(
+            parse(
+                &PostgreSqlDialect {},
+                "copy schema_name.table_name from '/tmp/data.txt'"
+            ),
+            Some(Err(_))
+        ));
+    }
+
+    #[test]
+    fn copy_delimiter_is_single_character() {
+        assert_eq!(
+            parse(
+                &PostgreSqlDialect {},
+                "copy schema_name.table_name from '/tmp/data.csv' with (format csv, delimiter ';;')"
+            ),
+            Some(Err(ParserError::ParserError(
+                "COPY delimiter must be a single one-byte character".to_owned()
+            )))
+        );
+    }
+
+    #[test]
+    fn create_publication_for_tables() {
+        assert_eq!(
+            parse(
+                &PostgreSqlDialect {},
+                "create publication changes for table schema_name.table_1, schema_name.table_2;"
+            ),
+            Some(Ok(ExtendedStatement::CreatePublication {
+                name: "changes".to_owned(),
+                tables: PublicationTables::Tables(vec![
+                    name(&["schema_name", "table_1"]),
+                    name(&["schema_name", "table_2"])
+                ]),
+            }))
+        );
+    }
+
+    #[test]
+    fn create_publication_for_all_tables() {
+        assert_eq!(
+            parse(&PostgreSqlDialect {}, "create publication changes for all tables"),
+            Some(Ok(ExtendedStatement::CreatePublication {
+                name: "changes".to_owned(),
+                tables: PublicationTables::All,
+            }))
+        );
+    }
+
+    #[test]
+    fn drop_publication() {
+        assert_eq!(
+            parse(&PostgreSqlDialect {}, "drop publication if exists changes;"),
+            Some(Ok(ExtendedStatement::DropPublication {
+                name: "changes".to_owned(),
+                if_exists: true,
+            }))
+        );
+    }
+
+    #[test]
+    fn create_replication_slot() {
+        assert_eq!(
+            parse(
+                &PostgreSqlDialect {},
+                "create replication slot subscriber for publication changes;"
+            ),
+            Some(Ok(ExtendedStatement::CreateReplicationSlot {
+                name: "subscriber".to_owned(),
+                publication: "changes".to_owned(),
+            }))
+        );
+    }
+
+    #[test]
+    fn drop_replication_slot() {
+        assert_eq!(
+            parse(&PostgreSqlDialect {}, "drop replication slot subscriber;"),
+            Some(Ok(ExtendedStatement::DropReplicationSlot {
+                name: "subscriber".to_owned(),
+                if_exists: false,
+            }))
+        );
+    }
+
+    #[test]
+    fn create_event_trigger() {
+        assert_eq!(
+            parse(
+                &PostgreSqlDialect {},
+                "create event trigger no_drops on ddl_command_start \
+                 when tag in ('drop table', 'DROP  SCHEMA') execute function forbid_drops();"
+            ),
+            Some(Ok(ExtendedStatement::CreateEventTrigger(EventTrigger {
+                name: "no_drops".to_owned(),
+                event: DdlEvent::Start,
+                tags: Some(vec!["DROP TABLE".to_owned(), "DROP SCHEMA".to_owned()]),
+                function: "forbid_drops".to_owned(),
+            })))
+        );
+    }
+
+    #[test]
+    fn event_trigger_with_unknown_tag() {
+        assert_eq!(
+            parse(
+                &PostgreSqlDialect {},
+                "create event trigger audit on ddl_command_end when tag in ('insert') execute procedure audit();"
+            ),
+            Some(Err(ParserError::ParserError(
+                "filter value \"insert\" not recognized for filter variable \"tag\"".to_owned()
+            )))
+        );
+    }
+
+    #[test]
+    fn drop_event_trigger() {
+        assert_eq!(
+            parse(&PostgreSqlDialect {}, "drop event trigger if exists no_drops;"),
+            Some(Ok(ExtendedStatement::DropEventTrigger {
+                name: "no_drops".to_owned(),
+                if_exists: true,
+            }))
+        );
+    }
+
+    #[test]
+    fn savepoints() {
+        assert_eq!(
+            parse(&PostgreSqlDialect {}, "savepoint Before_Update;"),
+            Some(Ok(ExtendedStatement::Savepoint("before_update".to_owned())))
+        );
+        assert_eq!(
+            parse(&PostgreSqlDialect {}, "release savepoint before_update;"),
+            Some(Ok(ExtendedStatement::ReleaseSavepoint("before_update".to_owned())))
+        );
+        assert_eq!(
+            parse(&PostgreSqlDialect {}, "release before_update"),
+            Some(Ok(ExtendedStatement::ReleaseSavepoint("before_update".to_owned())))
+        );
+        assert_eq!(
+            parse(&PostgreSqlDialect {}, "rollback work to savepoint before_update;"),
+            Some(Ok(ExtendedStatement::RollbackToSavepoint("before_update".to_owned())))
+        );
+        assert_eq!(parse(&PostgreSqlDialect {}, "rollback;"), None);
+        assert!(matches!(parse(&PostgreSqlDialect {}, "savepoint \"\";"), Some(Err(_))));
+    }
+
+    #[test]
+    fn anonymous_block() {
+        assert_eq!(
+            parse(
+                &PostgreSqlDialect {},
+                "do $$ begin create schema app; exception when duplicate_schema then null; end $$ language plpgsql;"
+            ),
+            Some(Ok(ExtendedStatement::Do(Block {
+                declarations: vec![],
+                statements: vec![Statement::Sql("create schema app".to_owned())],
+                handlers: vec![Handler {
+                    conditions: vec![Condition::SqlState("42P06".to_owned())],
+                    statements: vec![Statement::Null],
+                }],
+            })))
+        );
+    }
+
+    #[test]
+    fn create_foreign_table() {
+        assert_eq!(
+            parse(
+                &PostgreSqlDialect {},
+                "create foreign table schema_name.table_name (id smallint, name varchar(10)) \
+                 server file options (filename '/tmp/data.csv', header 'true');"
+            ),
+            Some(Ok(ExtendedStatement::CreateForeignTable(ForeignTableStatement {
+                name: name(&["schema_name", "table_name"]),
+                columns: vec![
+                    ColumnDef {
+                        name: Ident::new("id"),
+                        data_type: DataType::SmallInt,
+                        collation: None,
+                        options: vec![],
+                    },
+                    ColumnDef {
+                        name: Ident::new("name"),
+                        data_type: DataType::Varchar(Some(10)),
+                        collation: None,
+                        options: vec![],
+                    }
+                ],
+                server: "file".to_owned(),
+                options: vec![
+                    ("filename".to_owned(), "/tmp/data.csv".to_owned()),
+                    ("header".to_owned(), "true".to_owned())
+                ],
+            })))
+        );
+    }
+
+    #[test]
+    fn create_foreign_table_without_server() {
+        assert_eq!(
+            parse(
+                &PostgreSqlDialect {},
+                "create foreign table schema_name.table_name (id smallint)"
+            ),
+            Some(Err(ParserError::ParserError("Expected SERVER, found: EOF".to_owned())))
+        );
+    }
+
+    #[test]
+    fn cluster_by_columns() {
+        assert_eq!(
+            parse(
+                &PostgreSqlDialect {},
+                "cluster schema_name.table_name order by column_1, column_2 desc;"
+            ),
+            Some(Ok(ExtendedStatement::Cluster(ClusterStatement {
+                table_name: name(&["schema_name", "table_name"]),
+                order: ClusterOrder::Columns(vec![("column_1".to_owned(), false), ("column_2".to_owned(), true)]),
+            })))
+        );
+    }
+
+    #[test]
+    fn cluster_using_index() {
+        assert_eq!(
+            parse(&PostgreSqlDialect {}, "CLUSTER schema_name.table_name USING table_idx"),
+            Some(Ok(ExtendedStatement::Cluster(ClusterStatement {
+                table_name: name(&["schema_name", "table_name"]),
+                order: ClusterOrder::Index("table_idx".to_owned()),
+            })))
+        );
+    }
+
+    #[test]
+    fn create_table_is_not_extended_statement() {
+        assert_eq!(
+            parse(
+                &PostgreSqlDialect {},
+                "create table schema_name.table_name (column_1 smallint);"
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn create_aggregate() {
+        assert_eq!(
+            parse(
+                &PostgreSqlDialect {},
+                "create or replace aggregate Total(integer) (sfunc = add, stype = bigint, finalfunc = round, initcond = '0')"
+            ),
+            Some(Ok(ExtendedStatement::CreateAggregate {
+                aggregate: AggregateStatement {
+                    name: "total".to_owned(),
+                    input: PostgreSqlType::Integer,
+                    state: PostgreSqlType::BigInt,
+                    transition: "add".to_owned(),
+                    finish: Some("round".to_owned()),
+                    initial: Some("0".to_owned()),
+                },
+                or_replace: true,
+            }))
+        );
+    }
+
+    #[test]
+    fn create_aggregate_without_state_type() {
+        assert_eq!(
+            parse(&PostgreSqlDialect {}, "create aggregate total(integer) (sfunc = add)"),
+            Some(Err(ParserError::ParserError(
+                "aggregate stype must be specified".to_owned()
+            )))
+        );
+    }
+}
@@ -0,0 +1,168 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The `@@` operator of full-text search, e.g. `document @@ query`, is not
+//! supported by the SQL parser, so it is turned into a function call
+//! `ts_match_vq(document, query)` after tokenization. Operands bind like
+//! operands of other operators of PostgreSQL: weaker than arithmetic and
+//! stronger than comparisons.
+use sqlparser::{
+    dialect::keywords::Keyword,
+    parser::Parser,
+    tokenizer::{Token, Word},
+};
+
+/// Name of the function that the operator is turned into
+pub(crate) const MATCH_FUNCTION: &str = "ts_match_vq";
+/// Operands of the operator are parsed as operands of `&`
+const MATCH_PRECEDENCE: u8 = 23;
+
+/// Replaces `left @@ right` with `ts_match_vq(left, right)`. Operators
+/// whose operands can't be parsed are left as is for the parser to report.
+pub(crate) fn match_operators(mut tokens: Vec<Token>) -> Vec<Token> {
+    loop {
+        let rewritten = (0..tokens.len())
+            .filter(|index| is_operator(&tokens, *index))
+            .find_map(|index| rewrite(&tokens, index));
+        match rewritten {
+            Some(rewritten) => tokens = rewritten,
+            None => return tokens,
+        }
+    }
+}
+
+fn is_operator(tokens: &[Token], index: usize) -> bool {
+    tokens[index] == Token::Char('@') && tokens.get(index + 1) == Some(&Token::Char('@'))
+}
+
+/// Tokens with the operator at `index` replaced by the function call
+fn rewrite(tokens: &[Token], index: usize) -> Option<Vec<Token>> {
+    let start = left_operand(tokens, index)?;
+    let end = index + 2 + right_operand(&tokens[index + 2..])?;
+    let mut rewritten = Vec::with_capacity(tokens.len() + 2);
+    rewritten.extend_from_slice(&tokens[..start]);
+    rewritten.push(Token::make_word(MATCH_FUNCTION, None));
+    rewritten.push(Token::LParen);
+    rewritten.extend_from_slice(&tokens[start..index]);
+    rewritten.push(Token::Comma);
+    rewritten.extend_from_slice(&tokens[index + 2..end]);
+    rewritten.push(Token::RParen);
+    rewritten.extend_from_slice(&tokens[end..]);
+    Some(rewritten)
+}
+
+/// Index of the first token of the longest operand that ends before the
+/// operator at `index`. The search stops at an unclosed parenthesis, a
+/// comma or a semicolon.
+fn left_operand(tokens: &[Token], index: usize) -> Option<usize> {
+    let mut operand = None;
+    let mut depth = 0;
+    for start in (0..index).rev() {
+        match &tokens[start] {
+            Token::RParen => depth += 1,
+            Token::LParen if depth == 0 => break,
+            Token::LParen => depth -= 1,
+            Token::Comma | Token::SemiColon if depth == 0 => break,
+            _ => {}
+        }
+        if depth > 0 || matches!(tokens[start], Token::Whitespace(_)) || is_not(&tokens[start]) {
+            continue;
+        }
+        let mut parser = Parser::new(tokens[start..index].to_vec());
+        if parser.parse_subexpr(MATCH_PRECEDENCE).is_ok() && parser.peek_token() == Token::EOF {
+            operand = Some(start);
+        }
+    }
+    operand
+}
+
+/// Number of tokens of the operand that starts `tokens`
+fn right_operand(tokens: &[Token]) -> Option<usize> {
+    let mut parser = Parser::new(tokens.to_vec());
+    parser.parse_subexpr(MATCH_PRECEDENCE).ok()?;
+    let mut remaining = 0;
+    while parser.next_token() != Token::EOF {
+        remaining += 1;
+    }
+    let mut significant = 0;
+    for (end, token) in tokens.iter().enumerate().rev() {
+        if significant == remaining {
+            return Some(end + 1);
+        }
+        if !matches!(token, Token::Whitespace(_)) {
+            significant += 1;
+        }
+    }
+    None
+}
+
+/// `NOT` binds weaker than the operator, `NOT a @@ b` is `NOT (a @@ b)`
+fn is_not(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::Word(Word {
+            keyword: Keyword::NOT,
+            quote_style: None,
+            ..
+        })
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlparser::{dialect::PostgreSqlDialect, tokenizer::Tokenizer};
+
+    fn rewrite(sql: &str) -> String {
+        let tokens = Tokenizer::new(&PostgreSqlDialect {}, sql)
+            .tokenize()
+            .expect("tokenized");
+        Parser::new(match_operators(tokens))
+            .parse_statement()
+            .expect("parsed")
+            .to_string()
+    }
+
+    #[test]
+    fn operands_of_match() {
+        assert_eq!(
+            rewrite("select * from t where t.body @@ to_tsquery('fat & rat')"),
+            "SELECT * FROM t WHERE ts_match_vq(t.body, to_tsquery('fat & rat'))"
+        );
+        assert_eq!(
+            rewrite("select * from t where a = 1 and to_tsvector(body) @@ 'rat' or b"),
+            "SELECT * FROM t WHERE a = 1 AND ts_match_vq(to_tsvector(body), 'rat') OR b"
+        );
+    }
+
+    #[test]
+    fn nested_operators() {
+        assert_eq!(
+            rewrite("select * from t where not (body @@ 'a') and (title @@ 'b' = true)"),
+            "SELECT * FROM t WHERE NOT (ts_match_vq(body, 'a')) AND (ts_match_vq(title, 'b') = true)"
+        );
+        assert_eq!(
+            rewrite("select * from t where not body @@ 'a'"),
+            "SELECT * FROM t WHERE NOT ts_match_vq(body, 'a')"
+        );
+    }
+
+    #[test]
+    fn operators_in_literals_are_kept() {
+        assert_eq!(
+            rewrite("select * from t where body = 'a @@ b'"),
+            "SELECT * FROM t WHERE body = 'a @@ b'"
+        );
+    }
+}
@@ -14,9 +14,20 @@
 
 ///! Module for representing how a query will be parameters bound, executed and
 ///! values represented during runtime.
+pub mod authorize;
 pub mod bind;
+pub(crate) mod explain;
+pub mod extended;
+pub(crate) mod match_operator;
+pub(crate) mod param_types;
 pub mod plan;
+pub(crate) mod prepare;
 pub mod process;
+pub(crate) mod recursive;
+pub(crate) mod resolve;
+pub(crate) mod row_constructor;
+pub(crate) mod row_count;
+pub(crate) mod table_sample;
 
 use sql_types::SqlType;
 use sqlparser::ast::ObjectName;
@@ -69,7 +80,7 @@ impl TryFrom<ObjectName> for TableId {
     }
 }
 
-pub struct TableNamingError(String);
+pub struct TableNamingError(pub(crate) String);
 
 /// represents a schema uniquely
 ///
@@ -98,4 +109,4 @@ impl TryFrom<ObjectName> for SchemaId {
     }
 }
 
-pub struct SchemaNamingError(String);
+pub struct SchemaNamingError(pub(crate) String);
@@ -0,0 +1,225 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Inference of types of prepared statement parameters that were not
+//! specified by the client in `Parse` message.
+use crate::{
+    catalog_manager::CatalogManager,
+    query::{
+        authorize::collect_query_tables,
+        bind::{parse_param_index, visit_exprs},
+    },
+    ColumnDefinition,
+};
+use protocol::sql_types::PostgreSqlType;
+use sqlparser::ast::{BinaryOperator, DataType, Expr, Ident, ObjectName, Query, SetExpr, Statement};
+use std::collections::HashMap;
+
+/// Types of all parameters of `stmt`. Type of a parameter that is not
+/// specified is taken from its explicit cast, e.g. `$1::smallint`, or from
+/// the column it is compared with, assigned or inserted to. Parameters that
+/// are used in any other way are `varchar`.
+pub(crate) fn infer_param_types(
+    storage: &CatalogManager,
+    stmt: &Statement,
+    specified: &[Option<PostgreSqlType>],
+) -> Vec<PostgreSqlType> {
+    let columns = referred_columns(storage, stmt);
+    let mut inferred = HashMap::new();
+    match stmt {
+        Statement::Insert {
+            table_name,
+            columns: names,
+            source,
+        } => {
+            let table_columns = table_columns(storage, table_name);
+            let targets = if names.is_empty() {
+                table_columns.into_iter().map(Some).collect()
+            } else {
+                names
+                    .iter()
+                    .map(|name| find_column(&table_columns, name))
+                    .collect::<Vec<_>>()
+            };
+            if let SetExpr::Values(values) = &source.body {
+                for row in &values.0 {
+                    for (expr, column) in row.iter().zip(&targets) {
+                        if let Some(column) = column {
+                            infer_from_column(&mut inferred, expr, column);
+                        }
+                    }
+                }
+            }
+        }
+        Statement::Update { assignments, .. } => {
+            for assignment in assignments {
+                if let Some(column) = find_column(&columns, &assignment.id) {
+                    infer_from_column(&mut inferred, &assignment.value, &column);
+                }
+            }
+        }
+        Statement::Query(query) => infer_row_counts(&mut inferred, query),
+        _ => {}
+    }
+    let mut casts: HashMap<usize, PostgreSqlType> = HashMap::new();
+    let mut params = specified.len();
+    visit_exprs(&mut stmt.clone(), &mut |expr| match expr {
+        Expr::Identifier(Ident { value, .. }) => {
+            if let Some(index) = parse_param_index(value) {
+                params = params.max(index + 1);
+            }
+        }
+        Expr::Cast { expr, data_type } => {
+            if let (Expr::Identifier(Ident { value, .. }), Some(sql_type)) = (&**expr, cast_type(data_type)) {
+                if let Some(index) = parse_param_index(value) {
+                    casts.insert(index, sql_type);
+                }
+            }
+        }
+        Expr::BinaryOp { left, op, right } if is_typed_by_operands(op) => {
+            infer_from_expr(&mut inferred, &columns, right, left);
+            infer_from_expr(&mut inferred, &columns, left, right);
+        }
+        Expr::Between { expr, low, high, .. } => {
+            infer_from_expr(&mut inferred, &columns, low, expr);
+            infer_from_expr(&mut inferred, &columns, high, expr);
+        }
+        Expr::InList { expr, list, .. } => {
+            for item in list.iter() {
+                infer_from_expr(&mut inferred, &columns, item, expr);
+            }
+        }
+        _ => {}
+    });
+    (0..params)
+        .map(|index| {
+            specified
+                .get(index)
+                .copied()
+                .flatten()
+                .or_else(|| casts.get(&index).copied())
+                .or_else(|| inferred.get(&index).copied())
+                .unwrap_or(PostgreSqlType::VarChar)
+        })
+        .collect()
+}
+
+/// Columns of tables `stmt` reads from or writes into
+fn referred_columns(storage: &CatalogManager, stmt: &Statement) -> Vec<ColumnDefinition> {
+    let mut tables: Vec<&ObjectName> = vec![];
+    match stmt {
+        Statement::Query(query) => collect_query_tables(query, &mut tables),
+        Statement::Insert { table_name, source, .. } => {
+            tables.push(table_name);
+            collect_query_tables(source as &Query, &mut tables);
+        }
+        Statement::Update { table_name, .. } | Statement::Delete { table_name, .. } => tables.push(table_name),
+        _ => {}
+    }
+    tables
+        .into_iter()
+        .flat_map(|table_name| table_columns(storage, table_name))
+        .collect()
+}
+
+fn table_columns(storage: &CatalogManager, table_name: &ObjectName) -> Vec<ColumnDefinition> {
+    match table_name.0.as_slice() {
+        [schema_name, table_name] => storage
+            .table_columns(&schema_name.value, &table_name.value)
+            .unwrap_or_default(),
+        _ => vec![],
+    }
+}
+
+fn cast_type(data_type: &DataType) -> Option<PostgreSqlType> {
+    match data_type {
+        DataType::Boolean => Some(PostgreSqlType::Bool),
+        DataType::Char(_) => Some(PostgreSqlType::Char),
+        DataType::Varchar(_) | DataType::Text => Some(PostgreSqlType::VarChar),
+        DataType::SmallInt => Some(PostgreSqlType::SmallInt),
+        DataType::Int => Some(PostgreSqlType::Integer),
+        DataType::BigInt => Some(PostgreSqlType::BigInt),
+        DataType::Real => Some(PostgreSqlType::Real),
+        DataType::Double | DataType::Float(_) => Some(PostgreSqlType::DoublePrecision),
+        DataType::Decimal(_, _) => Some(PostgreSqlType::Decimal),
+        DataType::Date => Some(PostgreSqlType::Date),
+        DataType::Time => Some(PostgreSqlType::Time),
+        DataType::Timestamp => Some(PostgreSqlType::Timestamp),
+        DataType::Interval => Some(PostgreSqlType::Interval),
+        _ => None,
+    }
+}
+
+fn is_typed_by_operands(op: &BinaryOperator) -> bool {
+    matches!(
+        op,
+        BinaryOperator::Eq
+            | BinaryOperator::NotEq
+            | BinaryOperator::Lt
+            | BinaryOperator::LtEq
+            | BinaryOperator::Gt
+            | BinaryOperator::GtEq
+            | BinaryOperator::Plus
+            | BinaryOperator::Minus
+            | BinaryOperator::Multiply
+            | BinaryOperator::Divide
+            | BinaryOperator::Modulus
+    )
+}
+
+fn find_column(columns: &[ColumnDefinition], name: &Ident) -> Option<ColumnDefinition> {
+    columns.iter().find(|column| column.has_name(&name.value)).cloned()
+}
+
+/// Infers type of `param` if it is a parameter and `other` is a column
+fn infer_from_expr(
+    inferred: &mut HashMap<usize, PostgreSqlType>,
+    columns: &[ColumnDefinition],
+    param: &Expr,
+    other: &Expr,
+) {
+    let column = match other {
+        Expr::Identifier(name) => find_column(columns, name),
+        Expr::CompoundIdentifier(names) => names.last().and_then(|name| find_column(columns, name)),
+        _ => None,
+    };
+    if let Some(column) = column {
+        infer_from_column(inferred, param, &column);
+    }
+}
+
+/// Parameters that are values of `LIMIT`, `OFFSET` and `FETCH FIRST` of
+/// `query` are `bigint` row counts
+fn infer_row_counts(inferred: &mut HashMap<usize, PostgreSqlType>, query: &Query) {
+    let values = query
+        .limit
+        .iter()
+        .chain(query.offset.iter().map(|offset| &offset.value))
+        .chain(query.fetch.iter().filter_map(|fetch| fetch.quantity.as_ref()));
+    for value in values {
+        if let Expr::Identifier(Ident { value, .. }) = value {
+            if let Some(index) = parse_param_index(value) {
+                inferred.entry(index).or_insert(PostgreSqlType::BigInt);
+            }
+        }
+    }
+}
+
+fn infer_from_column(inferred: &mut HashMap<usize, PostgreSqlType>, param: &Expr, column: &ColumnDefinition) {
+    if let Expr::Identifier(Ident { value, .. }) = param {
+        if let Some(index) = parse_param_index(value) {
+            inferred.entry(index).or_insert_with(|| (&column.sql_type()).into());
+        }
+    }
+}
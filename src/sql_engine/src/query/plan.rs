@@ -13,15 +13,18 @@
 // limitations under the License.
 
 ///! represents a plan to be executed by the engine.
-use crate::query::{SchemaId, TableId};
-use crate::ColumnDefinition;
-use sqlparser::ast::{Ident, Query, Statement};
+use crate::query::{authorize::collect_query_tables, explain::PlanNode, SchemaId, TableId};
+use crate::{catalog_manager::AutovacuumOptions, ColumnDefinition};
+use sqlparser::ast::{Ident, ObjectName, Query, SetExpr, Statement};
+use std::fmt::{self, Display, Formatter};
 
 #[derive(Debug, Clone)]
 pub struct TableCreationInfo {
     pub schema_name: String,
     pub table_name: String,
     pub columns: Vec<ColumnDefinition>, // pub table_constraints: Vec<TableConstraints> ??
+    /// storage parameters of `WITH (...)`
+    pub autovacuum: AutovacuumOptions,
 }
 
 #[derive(Debug, Clone)]
@@ -45,3 +48,126 @@ pub enum Plan {
     Insert(TableInserts),
     NotProcessed(Box<Statement>),
 }
+
+impl Plan {
+    /// Tag of a DDL command that event triggers can filter on, `None` if
+    /// the plan does not fire event triggers
+    pub(crate) fn ddl_tag(&self) -> Option<&'static str> {
+        match self {
+            Plan::CreateTable(_) => Some("CREATE TABLE"),
+            Plan::CreateSchema(_) => Some("CREATE SCHEMA"),
+            Plan::DropTables(_) => Some("DROP TABLE"),
+            Plan::DropSchemas(_) => Some("DROP SCHEMA"),
+            Plan::Insert(_) | Plan::NotProcessed(_) => None,
+        }
+    }
+
+    /// Plan tree that `EXPLAIN` shows, `None` for utility statements
+    pub(crate) fn node(&self) -> Option<PlanNode> {
+        match self {
+            Plan::Insert(table_inserts) => Some(PlanNode::modify(
+                "Insert",
+                Some(table_inserts.table_id.schema_name().to_owned()),
+                table_inserts.table_id.name().to_owned(),
+                vec![query_node(&table_inserts.input)],
+            )),
+            Plan::NotProcessed(statement) => match statement.as_ref() {
+                Statement::Query(query) => Some(query_node(query)),
+                Statement::Update { table_name, .. } => Some(modify_node("Update", table_name)),
+                Statement::Delete { table_name, .. } => Some(modify_node("Delete", table_name)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+fn query_node(query: &Query) -> PlanNode {
+    if let SetExpr::Values(_) = query.body {
+        return PlanNode::new("Values Scan");
+    }
+    let mut tables = vec![];
+    collect_query_tables(query, &mut tables);
+    let mut scans = tables.into_iter().map(scan_node).collect::<Vec<PlanNode>>();
+    match scans.len() {
+        0 => PlanNode::new("Result"),
+        1 => scans.remove(0),
+        _ => PlanNode {
+            plans: scans,
+            ..PlanNode::new("Nested Loop")
+        },
+    }
+}
+
+fn modify_node(operation: &'static str, table_name: &ObjectName) -> PlanNode {
+    let scan = scan_node(table_name);
+    PlanNode::modify(
+        operation,
+        scan.schema.clone(),
+        scan.relation.clone().expect("scanned relation"),
+        vec![scan],
+    )
+}
+
+fn scan_node(table_name: &ObjectName) -> PlanNode {
+    match table_name.0.as_slice() {
+        [schema, table] => PlanNode::scan(Some(schema.value.clone()), table.value.clone()),
+        _ => PlanNode::scan(None, table_name.to_string()),
+    }
+}
+
+/// Short summary of a plan, e.g. for logs of slow statements
+impl Display for Plan {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Plan::CreateTable(info) => write!(f, "Create Table {}.{}", info.schema_name, info.table_name),
+            Plan::CreateSchema(info) => write!(f, "Create Schema {}", info.schema_name),
+            Plan::DropTables(tables) => write!(
+                f,
+                "Drop Table {}",
+                tables
+                    .iter()
+                    .map(|table_id| format!("{}.{}", table_id.schema_name(), table_id.name()))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            Plan::DropSchemas(schemas) => write!(
+                f,
+                "Drop Schema {}",
+                schemas
+                    .iter()
+                    .map(|(schema_id, _cascade)| schema_id.name())
+                    .collect::<Vec<&str>>()
+                    .join(", ")
+            ),
+            Plan::Insert(table_inserts) => write!(
+                f,
+                "Insert on {}.{}",
+                table_inserts.table_id.schema_name(),
+                table_inserts.table_id.name()
+            ),
+            Plan::NotProcessed(statement) => match statement.as_ref() {
+                Statement::Query(query) => {
+                    let mut tables = vec![];
+                    collect_query_tables(query, &mut tables);
+                    if tables.is_empty() {
+                        write!(f, "Result")
+                    } else {
+                        write!(
+                            f,
+                            "Seq Scan on {}",
+                            tables
+                                .iter()
+                                .map(ToString::to_string)
+                                .collect::<Vec<String>>()
+                                .join(", ")
+                        )
+                    }
+                }
+                Statement::Update { table_name, .. } => write!(f, "Update on {}", table_name),
+                Statement::Delete { table_name, .. } => write!(f, "Delete on {}", table_name),
+                _ => write!(f, "Utility"),
+            },
+        }
+    }
+}
@@ -0,0 +1,198 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Parsing of prepared statements. Parameters `$1`, `$2`, ... are not
+//! supported by the SQL parser, so they are turned into identifiers with
+//! the same name after tokenization. Dollar-quoted strings, e.g.
+//! `$$it's$$` or `$tag$it's$tag$`, are turned into regular string literals
+//! beforehand.
+use crate::query::{
+    match_operator::match_operators,
+    recursive::recursive_ctes,
+    row_constructor::row_constructors,
+    row_count::{restore_row_counts, row_counts},
+    table_sample::table_samples,
+};
+use sqlparser::{
+    ast::Statement,
+    dialect::{keywords::Keyword, PostgreSqlDialect},
+    parser::{Parser, ParserError},
+    tokenizer::{Token, Tokenizer, Word},
+};
+
+/// Parses single statement with parameters
+pub(crate) fn parse_prepared(sql: &str) -> Result<Statement, ParserError> {
+    let sql = replace_dollar_quoted_strings(sql).map_err(ParserError::TokenizerError)?;
+    let tokens = Tokenizer::new(&PostgreSqlDialect {}, &sql).tokenize()?;
+    let (tokens, row_counts) = row_counts(match_operators(table_samples(recursive_ctes(row_constructors(
+        replace_placeholders(tokens),
+    )))));
+    let mut parser = Parser::new(tokens);
+    let mut statement = parser.parse_statement()?;
+    restore_row_counts(&mut statement, &row_counts);
+    while parser.consume_token(&Token::SemiColon) {}
+    match parser.peek_token() {
+        Token::EOF => Ok(statement),
+        token => Err(ParserError::ParserError(format!(
+            "Expected end of statement, found: {}",
+            token
+        ))),
+    }
+}
+
+/// Merges `$` and following number into identifier of a parameter
+fn replace_placeholders(tokens: Vec<Token>) -> Vec<Token> {
+    let mut result = Vec::with_capacity(tokens.len());
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match (&token, tokens.peek()) {
+            (Token::Char('$'), Some(Token::Number(number))) if number.chars().all(|c| c.is_ascii_digit()) => {
+                result.push(Token::Word(Word {
+                    value: format!("${}", number),
+                    quote_style: None,
+                    keyword: Keyword::NoKeyword,
+                }));
+                tokens.next();
+            }
+            _ => result.push(token),
+        }
+    }
+    result
+}
+
+/// Rewrites dollar-quoted strings into single quoted ones. Content of
+/// quoted strings, quoted identifiers and comments is left as is.
+fn replace_dollar_quoted_strings(sql: &str) -> Result<String, String> {
+    let chars = sql.chars().collect::<Vec<char>>();
+    let mut result = String::with_capacity(sql.len());
+    let mut index = 0;
+    while index < chars.len() {
+        let current = chars[index];
+        let skip_until = match current {
+            '\'' => chars[index + 1..]
+                .iter()
+                .position(|c| *c == '\'')
+                .map(|end| index + end + 2),
+            '"' => chars[index + 1..]
+                .iter()
+                .position(|c| *c == '"')
+                .map(|end| index + end + 2),
+            '-' if chars.get(index + 1) == Some(&'-') => Some(
+                chars[index..]
+                    .iter()
+                    .position(|c| *c == '\n')
+                    .map_or(chars.len(), |end| index + end + 1),
+            ),
+            '/' if chars.get(index + 1) == Some(&'*') => Some(
+                chars[index + 2..]
+                    .windows(2)
+                    .position(|pair| pair == ['*', '/'])
+                    .map_or(chars.len(), |end| index + end + 4),
+            ),
+            '$' if index == 0 || !is_identifier_part(chars[index - 1]) => match dollar_quote_tag(&chars[index..]) {
+                Some(tag) => {
+                    let content_start = index + tag.len();
+                    let content_end = chars[content_start..]
+                        .windows(tag.len())
+                        .position(|window| window == tag.as_slice())
+                        .map(|end| content_start + end)
+                        .ok_or_else(|| "unterminated dollar-quoted string".to_owned())?;
+                    result.push('\'');
+                    for c in &chars[content_start..content_end] {
+                        if *c == '\'' {
+                            result.push('\'');
+                        }
+                        result.push(*c);
+                    }
+                    result.push('\'');
+                    index = content_end + tag.len();
+                    continue;
+                }
+                None => None,
+            },
+            _ => None,
+        };
+        let next = skip_until.unwrap_or(index + 1).min(chars.len());
+        result.extend(&chars[index..next]);
+        index = next;
+    }
+    Ok(result)
+}
+
+/// Opening `$tag$` of dollar-quoted string, tag is empty or an identifier
+/// that does not contain `$`
+fn dollar_quote_tag(chars: &[char]) -> Option<Vec<char>> {
+    let end = chars[1..].iter().position(|c| *c == '$')? + 2;
+    let tag = &chars[1..end - 1];
+    match tag.first() {
+        Some(first) if !(first.is_alphabetic() || *first == '_') => None,
+        _ if tag.iter().all(|c| c.is_alphanumeric() || *c == '_') => Some(chars[..end].to_vec()),
+        _ => None,
+    }
+}
+
+fn is_identifier_part(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parameters_with_many_digits() {
+        assert_eq!(
+            parse_prepared("select * from schema_name.table_name where column_1 = $10 and column_2=$2;")
+                .map(|statement| statement.to_string()),
+            Ok("SELECT * FROM schema_name.table_name WHERE column_1 = $10 AND column_2 = $2".to_owned())
+        );
+    }
+
+    #[test]
+    fn dollar_in_identifiers_is_not_a_parameter() {
+        assert_eq!(
+            parse_prepared("select column$1 from schema_name.table_name").map(|statement| statement.to_string()),
+            Ok("SELECT column$1 FROM schema_name.table_name".to_owned())
+        );
+    }
+
+    #[test]
+    fn dollar_quoted_strings() {
+        assert_eq!(
+            replace_dollar_quoted_strings("select $$it's$$, $tag$a $$ b$tag$, '$1', $1"),
+            Ok("select 'it''s', 'a $$ b', '$1', $1".to_owned())
+        );
+    }
+
+    #[test]
+    fn dollar_quotes_in_literals_and_comments_are_ignored() {
+        assert_eq!(
+            replace_dollar_quoted_strings("select '$$' -- $$\n, \"$a$\" /* $$ */"),
+            Ok("select '$$' -- $$\n, \"$a$\" /* $$ */".to_owned())
+        );
+    }
+
+    #[test]
+    fn unterminated_dollar_quoted_string() {
+        assert_eq!(
+            replace_dollar_quoted_strings("select $$abc"),
+            Err("unterminated dollar-quoted string".to_owned())
+        );
+    }
+
+    #[test]
+    fn unexpected_tokens_after_statement() {
+        assert!(parse_prepared("select 1; select 2").is_err());
+    }
+}
@@ -15,15 +15,21 @@
 ///! Module for transforming the input Query AST into representation the engine can process.
 use crate::query::plan::{Plan, SchemaCreationInfo, TableCreationInfo, TableInserts};
 use crate::{
-    catalog_manager::CatalogManager,
+    catalog_manager::{AutovacuumOptions, CatalogManager},
+    ddl::alter_table,
     query::{SchemaId, SchemaNamingError, TableId, TableNamingError},
     ColumnDefinition,
 };
-use protocol::{results::QueryError, Sender};
+use protocol::{results::QueryError, sql_types::PostgreSqlType, Sender};
 use sql_types::SqlType;
-use sqlparser::ast::{ColumnDef, DataType, ObjectName, ObjectType, Statement};
+use sqlparser::ast::{
+    ColumnDef, ColumnOption, ColumnOptionDef, DataType, ObjectName, ObjectType, SqlOption, Statement, Value,
+};
 use std::{convert::TryFrom, sync::Arc};
 
+/// `Err(())` means that the error is already sent to the client. If the client
+/// has disconnected meanwhile the statement is aborted all the same and the
+/// lost connection surfaces on the next message of the session.
 type Result<T> = std::result::Result<T, ()>;
 
 pub(crate) struct QueryProcessor {
@@ -38,14 +44,17 @@ impl<'qp> QueryProcessor {
 
     pub fn process(&self, stmt: Statement) -> Result<Plan> {
         match stmt {
-            Statement::CreateTable { name, columns, .. } => self.handle_create_table(name, &columns),
+            Statement::CreateTable {
+                name,
+                columns,
+                with_options,
+                ..
+            } => self.handle_create_table(name, &columns, &with_options),
             Statement::CreateSchema { schema_name, .. } => {
                 let schema_id = match SchemaId::try_from(schema_name) {
                     Ok(schema_id) => schema_id,
                     Err(SchemaNamingError(message)) => {
-                        self.sender
-                            .send(Err(QueryError::syntax_error(message)))
-                            .expect("To Send Query Result to Client");
+                        self.sender.send(Err(QueryError::syntax_error(message))).ok();
                         return Err(());
                     }
                 };
@@ -53,7 +62,7 @@ impl<'qp> QueryProcessor {
                     Some(_) => {
                         self.sender
                             .send(Err(QueryError::schema_already_exists(schema_id.name().to_string())))
-                            .expect("To Send Query Result to Client");
+                            .ok();
                         Err(())
                     }
                     None => Ok(Plan::CreateSchema(SchemaCreationInfo {
@@ -78,9 +87,7 @@ impl<'qp> QueryProcessor {
                     input: source,
                 })),
                 Err(TableNamingError(message)) => {
-                    self.sender
-                        .send(Err(QueryError::syntax_error(message)))
-                        .expect("To Send Query Result to Client");
+                    self.sender.send(Err(QueryError::syntax_error(message))).ok();
                     Err(())
                 }
             },
@@ -102,15 +109,25 @@ impl<'qp> QueryProcessor {
                     "serial" => Ok(SqlType::Integer(1)),
                     "smallserial" => Ok(SqlType::SmallInt(1)),
                     "bigserial" => Ok(SqlType::BigInt(1)),
-                    other_type => {
-                        self.sender
-                            .send(Err(QueryError::feature_not_supported(format!(
-                                "{} type is not supported",
-                                other_type
-                            ))))
-                            .expect("To Send Query Result to Client");
-                        Err(())
-                    }
+                    "tsvector" => Ok(SqlType::TsVector),
+                    "tsquery" => Ok(SqlType::TsQuery),
+                    other_type => match self.storage.extensions().base_type(other_type) {
+                        Some(PostgreSqlType::Bool) => Ok(SqlType::Bool),
+                        Some(PostgreSqlType::Char) => Ok(SqlType::Char(255)),
+                        Some(PostgreSqlType::VarChar) => Ok(SqlType::VarChar(255)),
+                        Some(PostgreSqlType::SmallInt) => Ok(SqlType::SmallInt(i16::MIN)),
+                        Some(PostgreSqlType::Integer) => Ok(SqlType::Integer(i32::MIN)),
+                        Some(PostgreSqlType::BigInt) => Ok(SqlType::BigInt(i64::MIN)),
+                        _ => {
+                            self.sender
+                                .send(Err(QueryError::feature_not_supported(format!(
+                                    "{} type is not supported",
+                                    other_type
+                                ))))
+                                .ok();
+                            Err(())
+                        }
+                    },
                 }
             }
             other_type => {
@@ -119,7 +136,7 @@ impl<'qp> QueryProcessor {
                         "{} type is not supported",
                         other_type
                     ))))
-                    .expect("To Send Query Result to Client");
+                    .ok();
                 Err(())
             }
         }
@@ -130,19 +147,36 @@ impl<'qp> QueryProcessor {
         for column in columns {
             let sql_type = self.sql_type_from_datatype(&column.data_type)?;
             // maybe a different type should be used to represent this instead of the storage's representation.
-            let column_definition = ColumnDefinition::new(column.name.value.as_str(), sql_type);
+            let mut column_definition = ColumnDefinition::new(column.name.value.as_str(), sql_type);
+            for ColumnOptionDef { option, .. } in &column.options {
+                column_definition = match option {
+                    ColumnOption::NotNull => column_definition.with_not_null(true),
+                    ColumnOption::Null => column_definition.with_not_null(false),
+                    ColumnOption::Default(expr) => match alter_table::default_value(&column_definition, expr) {
+                        Ok(default) => column_definition.with_default(default),
+                        Err(error) => {
+                            self.sender.send(Err(error)).ok();
+                            return Err(());
+                        }
+                    },
+                    _ => column_definition,
+                };
+            }
             column_defs.push(column_definition);
         }
         Ok(column_defs)
     }
 
-    fn handle_create_table(&self, name: ObjectName, columns: &[ColumnDef]) -> Result<Plan> {
+    pub(crate) fn handle_create_table(
+        &self,
+        name: ObjectName,
+        columns: &[ColumnDef],
+        with_options: &[SqlOption],
+    ) -> Result<Plan> {
         let table_id = match TableId::try_from(name) {
             Ok(table_id) => table_id,
             Err(TableNamingError(message)) => {
-                self.sender
-                    .send(Err(QueryError::syntax_error(message)))
-                    .expect("To Send Query Result to Client");
+                self.sender.send(Err(QueryError::syntax_error(message))).ok();
                 return Err(());
             }
         };
@@ -152,7 +186,7 @@ impl<'qp> QueryProcessor {
             None => {
                 self.sender
                     .send(Err(QueryError::schema_does_not_exist(schema_name.to_owned())))
-                    .expect("To Send Query Result to Client");
+                    .ok();
                 Err(())
             }
             Some((_, Some(_))) => {
@@ -161,15 +195,33 @@ impl<'qp> QueryProcessor {
                         "{}.{}",
                         schema_name, table_name
                     ))))
-                    .expect("To Send Query Result to Client");
+                    .ok();
                 Err(())
             }
             Some((_, None)) => {
                 let columns = self.resolve_column_definitions(columns)?;
+                let parameters = with_options
+                    .iter()
+                    .map(|SqlOption { name, value }| {
+                        let value = match value {
+                            Value::SingleQuotedString(value) => value.clone(),
+                            value => value.to_string(),
+                        };
+                        (name.value.clone(), value)
+                    })
+                    .collect::<Vec<(String, String)>>();
+                let autovacuum = match AutovacuumOptions::parse(&parameters) {
+                    Ok(autovacuum) => autovacuum,
+                    Err(message) => {
+                        self.sender.send(Err(QueryError::invalid_parameter_value(message))).ok();
+                        return Err(());
+                    }
+                };
                 let table_info = TableCreationInfo {
                     schema_name: schema_name.to_owned(),
                     table_name: table_name.to_owned(),
                     columns,
+                    autovacuum,
                 };
                 Ok(Plan::CreateTable(table_info))
             }
@@ -186,9 +238,7 @@ impl<'qp> QueryProcessor {
                     let table_id = match TableId::try_from(name.clone()) {
                         Ok(table_id) => table_id,
                         Err(TableNamingError(message)) => {
-                            self.sender
-                                .send(Err(QueryError::syntax_error(message)))
-                                .expect("To Send Query Result to Client");
+                            self.sender.send(Err(QueryError::syntax_error(message))).ok();
                             return Err(());
                         }
                     };
@@ -198,7 +248,7 @@ impl<'qp> QueryProcessor {
                         None => {
                             self.sender
                                 .send(Err(QueryError::schema_does_not_exist(schema_name.to_owned())))
-                                .expect("To Send Query Result to Client");
+                                .ok();
                             return Err(());
                         }
                         Some((_, None)) => {
@@ -207,7 +257,7 @@ impl<'qp> QueryProcessor {
                                     "{}.{}",
                                     schema_name, table_name
                                 ))))
-                                .expect("To Send Query Result to Client");
+                                .ok();
                             return Err(());
                         }
                         Some((_, Some(_))) => table_names.push(table_id),
@@ -221,9 +271,7 @@ impl<'qp> QueryProcessor {
                     let schema_id = match SchemaId::try_from(name.clone()) {
                         Ok(schema_id) => schema_id,
                         Err(SchemaNamingError(message)) => {
-                            self.sender
-                                .send(Err(QueryError::syntax_error(message)))
-                                .expect("To Send Query Result to Client");
+                            self.sender.send(Err(QueryError::syntax_error(message))).ok();
                             return Err(());
                         }
                     };
@@ -231,7 +279,7 @@ impl<'qp> QueryProcessor {
                         None => {
                             self.sender
                                 .send(Err(QueryError::schema_does_not_exist(schema_id.name().to_owned())))
-                                .expect("To Send Query Result to Client");
+                                .ok();
                             return Err(());
                         }
                         Some(_) => schema_names.push((schema_id, cascade)),
@@ -0,0 +1,75 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `WITH RECURSIVE` is not supported by the SQL parser, so `RECURSIVE` is
+//! removed after tokenization. A common table expression that refers to
+//! itself is evaluated as a recursive one.
+use sqlparser::{
+    dialect::keywords::Keyword,
+    tokenizer::{Token, Word},
+};
+
+/// Removes `RECURSIVE` that follows `WITH`
+pub(crate) fn recursive_ctes(tokens: Vec<Token>) -> Vec<Token> {
+    let mut result: Vec<Token> = Vec::with_capacity(tokens.len());
+    for token in tokens {
+        if is_keyword(&token, Keyword::RECURSIVE) {
+            let previous = result.iter().rev().find(|token| !matches!(token, Token::Whitespace(_)));
+            if previous
+                .map(|previous| is_keyword(previous, Keyword::WITH))
+                .unwrap_or_default()
+            {
+                continue;
+            }
+        }
+        result.push(token);
+    }
+    result
+}
+
+fn is_keyword(token: &Token, expected: Keyword) -> bool {
+    matches!(token, Token::Word(Word { keyword, quote_style: None, .. }) if *keyword == expected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlparser::{dialect::PostgreSqlDialect, parser::Parser, tokenizer::Tokenizer};
+
+    fn parse(sql: &str) -> String {
+        let tokens = Tokenizer::new(&PostgreSqlDialect {}, sql)
+            .tokenize()
+            .expect("tokenized");
+        Parser::new(recursive_ctes(tokens))
+            .parse_statement()
+            .expect("parsed")
+            .to_string()
+    }
+
+    #[test]
+    fn recursive_cte() {
+        assert_eq!(
+            parse("with recursive t (n) as (select n from s.t) select n from t"),
+            "WITH t (n) AS (SELECT n FROM s.t) SELECT n FROM t"
+        );
+    }
+
+    #[test]
+    fn recursive_as_name() {
+        assert_eq!(
+            parse("select recursive from s.recursive"),
+            "SELECT recursive FROM s.recursive"
+        );
+    }
+}
@@ -0,0 +1,202 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Resolution of column names against the columns of a table, shared by every
+//! command that refers to columns by name.
+
+use crate::ColumnDefinition;
+use protocol::results::QueryError;
+
+/// The largest edit distance at which an unknown column name is still
+/// considered a misspelling of an existing one
+const MAX_DISTANCE: usize = 3;
+
+/// Columns that names of a statement are resolved against
+pub(crate) struct Scope<'c> {
+    columns: &'c [ColumnDefinition],
+}
+
+impl<'c> Scope<'c> {
+    pub(crate) fn new(columns: &'c [ColumnDefinition]) -> Scope<'c> {
+        Scope { columns }
+    }
+
+    /// Index of the column named `name`, `None` if there is no such column
+    /// and an error if more than one column has that name
+    pub(crate) fn lookup(&self, name: &str) -> Result<Option<usize>, QueryError> {
+        let mut found = None;
+        for (index, column) in self.columns.iter().enumerate() {
+            if column.has_name(name) {
+                if found.is_some() {
+                    return Err(QueryError::ambiguous_column(name.to_owned()));
+                }
+                found = Some(index);
+            }
+        }
+        Ok(found)
+    }
+
+    /// Indexes of the columns named `names`; all names that do not exist are
+    /// reported together
+    pub(crate) fn resolve_all<'n, I: IntoIterator<Item = &'n str>>(&self, names: I) -> Result<Vec<usize>, QueryError> {
+        let mut indexes = vec![];
+        let mut unknown = vec![];
+        for name in names {
+            match self.lookup(name)? {
+                Some(index) => indexes.push(index),
+                None => unknown.push(name.to_owned()),
+            }
+        }
+        if unknown.is_empty() {
+            Ok(indexes)
+        } else {
+            Err(self.unknown(unknown))
+        }
+    }
+
+    /// Error for names that do not exist. A single misspelled name gets the
+    /// closest existing column suggested in the detail of the error
+    pub(crate) fn unknown(&self, names: Vec<String>) -> QueryError {
+        let suggestion = match names.as_slice() {
+            [name] => self.closest(name),
+            _ => None,
+        };
+        let error = QueryError::column_does_not_exist(names);
+        match suggestion {
+            Some(column) => error.with_detail(format!("Perhaps you meant to reference the column \"{}\".", column)),
+            None => error,
+        }
+    }
+
+    /// The column with the smallest edit distance to `name`, if it is close
+    /// enough and there is no other column equally close
+    fn closest(&self, name: &str) -> Option<String> {
+        let max_distance = MAX_DISTANCE.min(name.chars().count() / 2);
+        let mut best: Option<(usize, String)> = None;
+        let mut tied = false;
+        for column in self.columns {
+            let column = column.name();
+            let distance = distance(name, &column);
+            if distance > max_distance {
+                continue;
+            }
+            match &best {
+                Some((best_distance, best_name)) if *best_distance == distance => {
+                    tied = tied || *best_name != column;
+                }
+                Some((best_distance, _)) if *best_distance < distance => {}
+                _ => {
+                    best = Some((distance, column));
+                    tied = false;
+                }
+            }
+        }
+        if tied {
+            None
+        } else {
+            best.map(|(_, column)| column)
+        }
+    }
+}
+
+/// Levenshtein distance between `left` and `right`
+fn distance(left: &str, right: &str) -> usize {
+    let right = right.chars().collect::<Vec<char>>();
+    let mut previous = (0..=right.len()).collect::<Vec<usize>>();
+    for (i, l) in left.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, r) in right.iter().enumerate() {
+            let substitution = previous[j] + if l == *r { 0 } else { 1 };
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[right.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sql_types::SqlType;
+
+    fn columns(names: &[&str]) -> Vec<ColumnDefinition> {
+        names
+            .iter()
+            .map(|name| ColumnDefinition::new(name, SqlType::Integer(i32::MIN)))
+            .collect()
+    }
+
+    #[test]
+    fn edit_distance() {
+        assert_eq!(distance("name", "name"), 0);
+        assert_eq!(distance("nmae", "name"), 2);
+        assert_eq!(distance("nam", "name"), 1);
+        assert_eq!(distance("", "name"), 4);
+    }
+
+    #[test]
+    fn resolves_columns_in_order_of_names() {
+        let columns = columns(&["id", "name", "age"]);
+        assert_eq!(Scope::new(&columns).resolve_all(vec!["age", "id"]), Ok(vec![2, 0]));
+    }
+
+    #[test]
+    fn duplicate_column_names_are_ambiguous() {
+        let columns = columns(&["id", "name", "id"]);
+        assert_eq!(
+            Scope::new(&columns).resolve_all(vec!["name", "id"]),
+            Err(QueryError::ambiguous_column("id".to_owned()))
+        );
+    }
+
+    #[test]
+    fn misspelled_column_gets_a_suggestion() {
+        let columns = columns(&["id", "name", "age"]);
+        assert_eq!(
+            Scope::new(&columns).resolve_all(vec!["nmae"]),
+            Err(QueryError::column_does_not_exist(vec!["nmae".to_owned()])
+                .with_detail("Perhaps you meant to reference the column \"name\".".to_owned()))
+        );
+    }
+
+    #[test]
+    fn no_suggestion_for_distant_names() {
+        let columns = columns(&["id", "name", "age"]);
+        assert_eq!(
+            Scope::new(&columns).resolve_all(vec!["address"]),
+            Err(QueryError::column_does_not_exist(vec!["address".to_owned()]))
+        );
+    }
+
+    #[test]
+    fn no_suggestion_when_columns_are_equally_close() {
+        let columns = columns(&["column_1", "column_2"]);
+        assert_eq!(
+            Scope::new(&columns).resolve_all(vec!["column_3"]),
+            Err(QueryError::column_does_not_exist(vec!["column_3".to_owned()]))
+        );
+    }
+
+    #[test]
+    fn no_suggestion_for_several_unknown_columns() {
+        let columns = columns(&["id", "name"]);
+        assert_eq!(
+            Scope::new(&columns).resolve_all(vec!["nme", "di"]),
+            Err(QueryError::column_does_not_exist(vec![
+                "nme".to_owned(),
+                "di".to_owned()
+            ]))
+        );
+    }
+}
@@ -0,0 +1,184 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Row constructors without the `ROW` keyword, e.g. `(a, b) = (1, 'x')`, are
+//! not supported by the SQL parser, so `ROW` is inserted before parenthesized
+//! lists that are operands of comparisons or `IN` after tokenization. Lists
+//! of `VALUES`, columns and arguments of functions are left as is.
+use sqlparser::{
+    dialect::keywords::Keyword,
+    tokenizer::{Token, Word},
+};
+
+/// Keywords after which a parenthesized list starts an expression
+const EXPRESSION_STARTS: &[Keyword] = &[
+    Keyword::AND,
+    Keyword::CASE,
+    Keyword::ELSE,
+    Keyword::HAVING,
+    Keyword::NOT,
+    Keyword::ON,
+    Keyword::OR,
+    Keyword::SELECT,
+    Keyword::THEN,
+    Keyword::WHEN,
+    Keyword::WHERE,
+];
+
+/// Inserts `ROW` before parenthesized lists of two or more expressions that
+/// are compared with `=`, `<>`, `<`, `<=`, `>` or `>=`, that are tested with
+/// `IN` or that are items of an `IN` list
+pub(crate) fn row_constructors(tokens: Vec<Token>) -> Vec<Token> {
+    let mut rewritten = Vec::with_capacity(tokens.len());
+    for (index, token) in tokens.iter().enumerate() {
+        if *token == Token::LParen && is_row(&tokens, index) {
+            rewritten.push(Token::make_keyword("ROW"));
+        }
+        rewritten.push(token.clone());
+    }
+    rewritten
+}
+
+/// Whether the list that starts at `index` is an operand of a comparison or
+/// of `IN`
+fn is_row(tokens: &[Token], index: usize) -> bool {
+    let end = match list_end(tokens, index) {
+        Some(end) => end,
+        None => return false,
+    };
+    let previous = previous_token(tokens, index);
+    let starts_expression = match previous {
+        None => true,
+        Some(Token::Word(word)) => word.quote_style.is_none() && EXPRESSION_STARTS.contains(&word.keyword),
+        Some(Token::RParen) | Some(Token::Number(_)) | Some(Token::SingleQuotedString(_)) => false,
+        Some(_) => true,
+    };
+    if !starts_expression {
+        return false;
+    }
+    let next = next_token(tokens, end + 1);
+    is_comparison(previous)
+        || is_comparison(next.map(|(_, token)| token))
+        || match next {
+            Some((_, token)) if is_keyword(token, Keyword::IN) => true,
+            Some((after, token)) if is_keyword(token, Keyword::NOT) => {
+                matches!(next_token(tokens, after + 1), Some((_, token)) if is_keyword(token, Keyword::IN))
+            }
+            _ => false,
+        }
+        || (matches!(previous, Some(Token::LParen) | Some(Token::Comma)) && is_in_list_item(tokens, index))
+}
+
+/// Index of `)` that closes the list that starts at `index`, `None` if there
+/// is no comma that is not nested in other parentheses
+fn list_end(tokens: &[Token], index: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut list = false;
+    for (end, token) in tokens.iter().enumerate().skip(index + 1) {
+        match token {
+            Token::LParen => depth += 1,
+            Token::RParen if depth == 0 => return if list { Some(end) } else { None },
+            Token::RParen => depth -= 1,
+            Token::Comma if depth == 0 => list = true,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Whether the parenthesis that encloses the list at `index` follows `IN`
+fn is_in_list_item(tokens: &[Token], index: usize) -> bool {
+    let mut depth = 0;
+    for start in (0..index).rev() {
+        match &tokens[start] {
+            Token::RParen => depth += 1,
+            Token::LParen if depth == 0 => {
+                return matches!(previous_token(tokens, start), Some(token) if is_keyword(token, Keyword::IN))
+            }
+            Token::LParen => depth -= 1,
+            _ => {}
+        }
+    }
+    false
+}
+
+fn previous_token(tokens: &[Token], index: usize) -> Option<&Token> {
+    tokens[..index]
+        .iter()
+        .rev()
+        .find(|token| !matches!(token, Token::Whitespace(_)))
+}
+
+fn next_token(tokens: &[Token], index: usize) -> Option<(usize, &Token)> {
+    tokens
+        .iter()
+        .enumerate()
+        .skip(index)
+        .find(|(_, token)| !matches!(token, Token::Whitespace(_)))
+}
+
+fn is_comparison(token: Option<&Token>) -> bool {
+    matches!(
+        token,
+        Some(Token::Eq) | Some(Token::Neq) | Some(Token::Lt) | Some(Token::LtEq) | Some(Token::Gt) | Some(Token::GtEq)
+    )
+}
+
+fn is_keyword(token: &Token, keyword: Keyword) -> bool {
+    matches!(token, Token::Word(Word { keyword: found, quote_style: None, .. }) if *found == keyword)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlparser::{dialect::PostgreSqlDialect, parser::Parser, tokenizer::Tokenizer};
+
+    fn rewrite(sql: &str) -> String {
+        let tokens = Tokenizer::new(&PostgreSqlDialect {}, sql)
+            .tokenize()
+            .expect("tokenized");
+        Parser::new(row_constructors(tokens))
+            .parse_statement()
+            .expect("parsed")
+            .to_string()
+    }
+
+    #[test]
+    fn compared_rows() {
+        assert_eq!(
+            rewrite("select * from t where (a, b) = (1, 'x') and (id, score) < (2, 0)"),
+            "SELECT * FROM t WHERE ROW(a, b) = ROW(1, 'x') AND ROW(id, score) < ROW(2, 0)"
+        );
+    }
+
+    #[test]
+    fn rows_in_list() {
+        assert_eq!(
+            rewrite("select * from t where (a, b) in ((1, 2), (3, 4)) or (a, b) not in ((5, 6))"),
+            "SELECT * FROM t WHERE ROW(a, b) IN (ROW(1, 2), ROW(3, 4)) OR ROW(a, b) NOT IN (ROW(5, 6))"
+        );
+    }
+
+    #[test]
+    fn other_lists_are_kept() {
+        assert_eq!(
+            rewrite("insert into t (a, b) values (1, 2), (3, 4)"),
+            "INSERT INTO t (a, b) VALUES (1, 2), (3, 4)"
+        );
+        assert_eq!(
+            rewrite("select f(a, b) from t where a in (1, 2) and f(a, b) = 1"),
+            "SELECT f(a, b) FROM t WHERE a IN (1, 2) AND f(a, b) = 1"
+        );
+    }
+}
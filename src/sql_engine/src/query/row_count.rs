@@ -0,0 +1,277 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Values of `LIMIT`, `OFFSET` and `FETCH FIRST` clauses can only be number
+//! literals for the SQL parser, so other values, e.g. parameters `$1` or
+//! expressions `2 * 5`, are parsed on their own and replaced with negative
+//! numbers, that the tokenizer never produces, after tokenization. The
+//! expressions take their places back once the statement is parsed.
+use crate::query::bind::visit_exprs;
+use sqlparser::{
+    ast::{Expr, Statement, Value},
+    dialect::keywords::Keyword,
+    parser::Parser,
+    tokenizer::{Token, Whitespace, Word},
+};
+
+/// Keywords that end the value of a clause that follows `keyword`
+fn terminators(keyword: Keyword) -> &'static [Keyword] {
+    match keyword {
+        Keyword::LIMIT => &[
+            Keyword::OFFSET,
+            Keyword::FETCH,
+            Keyword::FOR,
+            Keyword::UNION,
+            Keyword::EXCEPT,
+            Keyword::INTERSECT,
+        ],
+        Keyword::OFFSET => &[
+            Keyword::ROW,
+            Keyword::ROWS,
+            Keyword::LIMIT,
+            Keyword::FETCH,
+            Keyword::FOR,
+        ],
+        _ => &[Keyword::ROW, Keyword::ROWS, Keyword::PERCENT],
+    }
+}
+
+/// Replaces values of `LIMIT`, `OFFSET` and `FETCH FIRST` clauses that are
+/// not number literals with placeholders and returns the values in order of
+/// the placeholders. Values that can't be parsed are left as is for the
+/// parser to report.
+pub(crate) fn row_counts(tokens: Vec<Token>) -> (Vec<Token>, Vec<Expr>) {
+    let mut result = Vec::with_capacity(tokens.len());
+    let mut expressions = vec![];
+    let mut index = 0;
+    while index < tokens.len() {
+        result.push(tokens[index].clone());
+        let keyword = match clause(&tokens, index) {
+            Some(keyword) => keyword,
+            None => {
+                index += 1;
+                continue;
+            }
+        };
+        let mut start = index + 1;
+        if keyword == Keyword::FETCH {
+            match next_word(&tokens, start) {
+                Some((position, Keyword::FIRST)) | Some((position, Keyword::NEXT)) => {
+                    result.extend_from_slice(&tokens[start..=position]);
+                    start = position + 1;
+                }
+                _ => {
+                    index += 1;
+                    continue;
+                }
+            }
+        }
+        let end = value_end(&tokens, start, terminators(keyword));
+        match value(&tokens[start..end]) {
+            Some(expr) => {
+                expressions.push(expr);
+                result.push(Token::Whitespace(Whitespace::Space));
+                result.push(Token::Number(format!("-{}", expressions.len())));
+                result.push(Token::Whitespace(Whitespace::Space));
+            }
+            None => result.extend_from_slice(&tokens[start..end]),
+        }
+        index = end;
+    }
+    (result, expressions)
+}
+
+/// Puts `expressions` of [`row_counts`] back in place of their placeholders
+pub(crate) fn restore_row_counts(statement: &mut Statement, expressions: &[Expr]) {
+    if expressions.is_empty() {
+        return;
+    }
+    visit_exprs(statement, &mut |expr| {
+        if let Expr::Value(Value::Number(number)) = expr {
+            let position = number
+                .to_string()
+                .strip_prefix('-')
+                .and_then(|position| position.parse::<usize>().ok());
+            if let Some(restored) = position.and_then(|position| expressions.get(position.wrapping_sub(1))) {
+                *expr = restored.clone();
+            }
+        }
+    });
+}
+
+/// Keyword of a clause with a row count that starts at `index`. `LIMIT` of
+/// `CONNECTION LIMIT` of roles is not a clause of a query.
+fn clause(tokens: &[Token], index: usize) -> Option<Keyword> {
+    match &tokens[index] {
+        Token::Word(Word {
+            keyword: keyword @ Keyword::LIMIT,
+            quote_style: None,
+            ..
+        }) => {
+            let previous = tokens[..index]
+                .iter()
+                .rev()
+                .find(|token| !matches!(token, Token::Whitespace(_)));
+            match previous {
+                Some(Token::Word(Word { value, .. })) if value.eq_ignore_ascii_case("connection") => None,
+                _ => Some(*keyword),
+            }
+        }
+        Token::Word(Word {
+            keyword: keyword @ Keyword::OFFSET,
+            quote_style: None,
+            ..
+        })
+        | Token::Word(Word {
+            keyword: keyword @ Keyword::FETCH,
+            quote_style: None,
+            ..
+        }) => Some(*keyword),
+        _ => None,
+    }
+}
+
+/// Position and keyword of the first word at or after `start`
+fn next_word(tokens: &[Token], start: usize) -> Option<(usize, Keyword)> {
+    tokens[start..]
+        .iter()
+        .position(|token| !matches!(token, Token::Whitespace(_)))
+        .and_then(|position| match &tokens[start + position] {
+            Token::Word(Word {
+                keyword,
+                quote_style: None,
+                ..
+            }) => Some((start + position, *keyword)),
+            _ => None,
+        })
+}
+
+/// End of the value that starts at `start`, i.e. the first of `terminators`,
+/// `;` or `)` that is not nested in parentheses
+fn value_end(tokens: &[Token], start: usize, terminators: &[Keyword]) -> usize {
+    let mut depth = 0;
+    for (position, token) in tokens.iter().enumerate().skip(start) {
+        match token {
+            Token::LParen => depth += 1,
+            Token::RParen if depth == 0 => return position,
+            Token::RParen => depth -= 1,
+            Token::SemiColon if depth == 0 => return position,
+            Token::Word(Word {
+                keyword,
+                quote_style: None,
+                ..
+            }) if depth == 0 && terminators.contains(keyword) => return position,
+            _ => {}
+        }
+    }
+    tokens.len()
+}
+
+/// Expression of a value that is not a number literal or `ALL`
+fn value(tokens: &[Token]) -> Option<Expr> {
+    let mut significant = tokens.iter().filter(|token| !matches!(token, Token::Whitespace(_)));
+    match (significant.next(), significant.next()) {
+        (None, _) | (Some(Token::Number(_)), None) => return None,
+        (
+            Some(Token::Word(Word {
+                keyword: Keyword::ALL,
+                quote_style: None,
+                ..
+            })),
+            None,
+        ) => return None,
+        _ => {}
+    }
+    let mut parser = Parser::new(tokens.to_vec());
+    let expr = parser.parse_expr().ok()?;
+    match parser.peek_token() {
+        Token::EOF => Some(expr),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::{prepare::parse_prepared, table_sample::parse_sql};
+    use sqlparser::{dialect::PostgreSqlDialect, tokenizer::Tokenizer};
+
+    fn parse(sql: &str) -> String {
+        let tokens = Tokenizer::new(&PostgreSqlDialect {}, sql)
+            .tokenize()
+            .expect("tokenized");
+        let (tokens, expressions) = row_counts(tokens);
+        let mut statement = Parser::new(tokens).parse_statement().expect("parsed");
+        restore_row_counts(&mut statement, &expressions);
+        statement.to_string()
+    }
+
+    #[test]
+    fn number_literals() {
+        assert_eq!(
+            parse("select a from s.t limit 10 offset 5"),
+            "SELECT a FROM s.t LIMIT 10 OFFSET 5"
+        );
+        assert_eq!(parse("select a from s.t limit all"), "SELECT a FROM s.t");
+    }
+
+    #[test]
+    fn expressions() {
+        assert_eq!(
+            parse("select a from s.t limit 2 * 5 offset (1 + 1) rows"),
+            "SELECT a FROM s.t LIMIT 2 * 5 OFFSET (1 + 1) ROWS"
+        );
+    }
+
+    #[test]
+    fn fetch_first() {
+        assert_eq!(
+            parse("select a from s.t offset 1 row fetch first 2 + 1 rows only"),
+            "SELECT a FROM s.t OFFSET 1 ROW FETCH FIRST 2 + 1 ROWS ONLY"
+        );
+        assert_eq!(
+            parse("select a from s.t fetch next row only"),
+            "SELECT a FROM s.t FETCH FIRST ROWS ONLY"
+        );
+    }
+
+    #[test]
+    fn parameters() {
+        assert_eq!(
+            parse_prepared("select a from s.t where a = $1 limit $2 offset $3")
+                .expect("parsed")
+                .to_string(),
+            "SELECT a FROM s.t WHERE a = $1 LIMIT $2 OFFSET $3"
+        );
+    }
+
+    #[test]
+    fn nested_queries() {
+        assert_eq!(
+            parse("select a from (select a from s.t limit 1 + 1) as t where a in (select b from s.u offset -1)"),
+            "SELECT a FROM (SELECT a FROM s.t LIMIT 1 + 1) AS t WHERE a IN (SELECT b FROM s.u OFFSET - 1)"
+        );
+    }
+
+    #[test]
+    fn invalid_value_is_left_for_parser() {
+        let tokens = Tokenizer::new(&PostgreSqlDialect {}, "select a from s.t limit 1 +")
+            .tokenize()
+            .expect("tokenized");
+        let (_tokens, expressions) = row_counts(tokens);
+
+        assert!(expressions.is_empty());
+        assert!(parse_sql(&PostgreSqlDialect {}, "select a from s.t limit 1 +").is_err());
+    }
+}
@@ -0,0 +1,193 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `TABLESAMPLE` clauses, e.g. `t TABLESAMPLE SYSTEM (10) REPEATABLE (1)`, are
+//! not supported by the SQL parser, so they are turned into table hints
+//! `t WITH (TABLESAMPLE(SYSTEM, 10), REPEATABLE(1))` after tokenization.
+use crate::query::{
+    match_operator::match_operators,
+    recursive::recursive_ctes,
+    row_constructor::row_constructors,
+    row_count::{restore_row_counts, row_counts},
+};
+use sqlparser::{
+    ast::Statement,
+    dialect::{keywords::Keyword, Dialect},
+    parser::{Parser, ParserError},
+    tokenizer::{Token, Tokenizer, Word},
+};
+
+/// Parses semicolon separated statements that can sample tables and match
+/// documents with `@@`, limit rows by expressions and compare rows
+pub(crate) fn parse_sql(dialect: &dyn Dialect, sql: &str) -> Result<Vec<Statement>, ParserError> {
+    let tokens = Tokenizer::new(dialect, sql).tokenize()?;
+    let (tokens, row_counts) = row_counts(match_operators(table_samples(recursive_ctes(row_constructors(tokens)))));
+    let mut parser = Parser::new(tokens);
+    let mut statements = vec![];
+    let mut expecting_delimiter = false;
+    loop {
+        while parser.consume_token(&Token::SemiColon) {
+            expecting_delimiter = false;
+        }
+        match parser.peek_token() {
+            Token::EOF => return Ok(statements),
+            token if expecting_delimiter => {
+                return Err(ParserError::ParserError(format!(
+                    "Expected end of statement, found: {}",
+                    token
+                )))
+            }
+            _ => {
+                let mut statement = parser.parse_statement()?;
+                restore_row_counts(&mut statement, &row_counts);
+                statements.push(statement);
+                expecting_delimiter = true;
+            }
+        }
+    }
+}
+
+/// Replaces `TABLESAMPLE method (arguments) [REPEATABLE (seed)]` with table
+/// hints. Tokens that do not have the shape of the clause are left as is.
+pub(crate) fn table_samples(tokens: Vec<Token>) -> Vec<Token> {
+    let mut result = Vec::with_capacity(tokens.len());
+    let mut index = 0;
+    while index < tokens.len() {
+        match sample_clause(&tokens, index) {
+            Some((method, arguments, seed, end)) => {
+                result.push(Token::make_keyword("WITH"));
+                result.push(Token::LParen);
+                result.push(Token::make_word("TABLESAMPLE", None));
+                result.push(Token::LParen);
+                result.push(method.clone());
+                result.push(Token::Comma);
+                result.extend_from_slice(arguments);
+                result.push(Token::RParen);
+                if let Some(seed) = seed {
+                    result.push(Token::Comma);
+                    result.push(Token::make_word("REPEATABLE", None));
+                    result.push(Token::LParen);
+                    result.extend_from_slice(seed);
+                    result.push(Token::RParen);
+                }
+                result.push(Token::RParen);
+                index = end;
+            }
+            None => {
+                result.push(tokens[index].clone());
+                index += 1;
+            }
+        }
+    }
+    result
+}
+
+/// Method, arguments and seed of the clause that starts at `start` and the
+/// index of the token after the clause
+#[allow(clippy::type_complexity)]
+fn sample_clause(tokens: &[Token], start: usize) -> Option<(&Token, &[Token], Option<&[Token]>, usize)> {
+    if !is_keyword(&tokens[start], Keyword::TABLESAMPLE) {
+        return None;
+    }
+    let method = next_token(tokens, start + 1)?;
+    match &tokens[method] {
+        Token::Word(_) => {}
+        _ => return None,
+    }
+    let (arguments, end) = parenthesized(tokens, method + 1)?;
+    match next_token(tokens, end) {
+        Some(repeatable) if is_keyword(&tokens[repeatable], Keyword::REPEATABLE) => {
+            let (seed, end) = parenthesized(tokens, repeatable + 1)?;
+            Some((&tokens[method], arguments, Some(seed), end))
+        }
+        _ => Some((&tokens[method], arguments, None, end)),
+    }
+}
+
+/// Tokens between parentheses that open at the first token after `start`
+/// and the index of the token after the closing parenthesis
+fn parenthesized(tokens: &[Token], start: usize) -> Option<(&[Token], usize)> {
+    let open = next_token(tokens, start)?;
+    if tokens[open] != Token::LParen {
+        return None;
+    }
+    let mut depth = 0;
+    for (index, token) in tokens.iter().enumerate().skip(open) {
+        match token {
+            Token::LParen => depth += 1,
+            Token::RParen if depth == 1 => return Some((&tokens[open + 1..index], index + 1)),
+            Token::RParen => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Index of the first token at or after `start` that is not a whitespace
+fn next_token(tokens: &[Token], start: usize) -> Option<usize> {
+    (start..tokens.len()).find(|index| !matches!(tokens[*index], Token::Whitespace(_)))
+}
+
+fn is_keyword(token: &Token, expected: Keyword) -> bool {
+    matches!(token, Token::Word(Word { keyword, quote_style: None, .. }) if *keyword == expected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlparser::dialect::PostgreSqlDialect;
+
+    fn parse(sql: &str) -> String {
+        parse_sql(&PostgreSqlDialect {}, sql)
+            .expect("parsed")
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<String>>()
+            .join("; ")
+    }
+
+    #[test]
+    fn sampled_table() {
+        assert_eq!(
+            parse("select * from s.t tablesample system (10)"),
+            "SELECT * FROM s.t WITH (TABLESAMPLE(system, 10))"
+        );
+    }
+
+    #[test]
+    fn sampled_table_with_alias_and_seed() {
+        assert_eq!(
+            parse("select x.a from s.t as x TABLESAMPLE bernoulli (2.5 * 2) REPEATABLE (42);"),
+            "SELECT x.a FROM s.t AS x WITH (TABLESAMPLE(bernoulli, 2.5 * 2), REPEATABLE(42))"
+        );
+    }
+
+    #[test]
+    fn statements_without_samples() {
+        assert_eq!(
+            parse("select system from s.t; select 'tablesample system (1)'"),
+            "SELECT system FROM s.t; SELECT 'tablesample system (1)'"
+        );
+    }
+
+    #[test]
+    fn statements_are_separated() {
+        assert_eq!(
+            parse_sql(&PostgreSqlDialect {}, "select 1 select 2"),
+            Err(ParserError::ParserError(
+                "Expected end of statement, found: select".to_owned()
+            ))
+        );
+    }
+}
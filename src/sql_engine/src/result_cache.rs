@@ -0,0 +1,268 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Opt-in cache of `SELECT` results shared by all sessions. Results are
+//! keyed by the text of parsed query, so statements that differ only in
+//! whitespaces or letter case of keywords share an entry. An entry is
+//! discarded as soon as any of tables it reads from is modified or dropped.
+use protocol::{
+    results::{Description, QueryEvent, QueryResult},
+    Sender,
+};
+use std::{
+    collections::HashMap,
+    io, mem,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+
+struct Entry {
+    description: Description,
+    rows: Vec<Vec<Option<String>>>,
+    tables: Vec<String>,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct Entries {
+    results: HashMap<String, Entry>,
+    clock: u64,
+    /// incremented on each invalidation so that results computed
+    /// concurrently with a write are not cached
+    generation: u64,
+}
+
+/// Results of read only queries that are served from memory until tables
+/// they refer to change
+pub struct ResultCache {
+    capacity: usize,
+    entries: Mutex<Entries>,
+}
+
+impl ResultCache {
+    /// Creates cache that holds up to `capacity` results, the least
+    /// recently used one is evicted to make room for a new one
+    pub fn new(capacity: usize) -> ResultCache {
+        ResultCache {
+            capacity,
+            entries: Mutex::default(),
+        }
+    }
+
+    /// Cached description and rows of `query`
+    pub(crate) fn get(&self, query: &str) -> Option<(Description, Vec<Vec<Option<String>>>)> {
+        let mut entries = self.entries.lock().expect("to acquire result cache lock");
+        entries.clock += 1;
+        let clock = entries.clock;
+        entries.results.get_mut(query).map(|entry| {
+            entry.last_used = clock;
+            (entry.description.clone(), entry.rows.clone())
+        })
+    }
+
+    /// Current generation, it has to be taken before `query` is executed
+    pub(crate) fn generation(&self) -> u64 {
+        self.entries.lock().expect("to acquire result cache lock").generation
+    }
+
+    /// Caches result of `query` that reads from `tables` unless any table
+    /// was modified since `generation` was taken
+    pub(crate) fn put(
+        &self,
+        query: String,
+        tables: Vec<String>,
+        generation: u64,
+        (description, rows): (Description, Vec<Vec<Option<String>>>),
+    ) {
+        let mut entries = self.entries.lock().expect("to acquire result cache lock");
+        if self.capacity == 0 || entries.generation != generation {
+            return;
+        }
+        if entries.results.len() >= self.capacity && !entries.results.contains_key(&query) {
+            let evicted = entries
+                .results
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(query, _)| query.clone());
+            if let Some(evicted) = evicted {
+                entries.results.remove(&evicted);
+            }
+        }
+        entries.clock += 1;
+        let last_used = entries.clock;
+        entries.results.insert(
+            query,
+            Entry {
+                description,
+                rows,
+                tables,
+                last_used,
+            },
+        );
+    }
+
+    /// Discards results that read from any of `objects`. Object is either
+    /// a table qualified with its schema name or a schema name.
+    pub(crate) fn invalidate(&self, objects: &[String]) {
+        let mut entries = self.entries.lock().expect("to acquire result cache lock");
+        entries.generation += 1;
+        entries.results.retain(|_, entry| {
+            !entry.tables.iter().any(|table| {
+                objects
+                    .iter()
+                    .any(|object| table == object || table.split('.').next() == Some(object.as_str()))
+            })
+        });
+    }
+
+    /// Number of cached results
+    pub fn len(&self) -> usize {
+        self.entries.lock().expect("to acquire result cache lock").results.len()
+    }
+
+    /// Returns `true` if there is no cached results
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Forwards results of a query to the client and keeps copy of them to be
+/// cached if the query completes without errors
+pub(crate) struct ResultRecorder {
+    sender: Arc<dyn Sender>,
+    result: Mutex<(Description, Vec<Vec<Option<String>>>)>,
+    completed: AtomicBool,
+    failed: AtomicBool,
+}
+
+impl ResultRecorder {
+    pub(crate) fn new(sender: Arc<dyn Sender>) -> ResultRecorder {
+        ResultRecorder {
+            sender,
+            result: Mutex::default(),
+            completed: AtomicBool::new(false),
+            failed: AtomicBool::new(false),
+        }
+    }
+
+    /// Recorded description and rows if all of them were sent successfully
+    pub(crate) fn take_result(&self) -> Option<(Description, Vec<Vec<Option<String>>>)> {
+        if self.completed.load(Ordering::SeqCst) && !self.failed.load(Ordering::SeqCst) {
+            Some(mem::take(&mut *self.result.lock().expect("to acquire recorder lock")))
+        } else {
+            None
+        }
+    }
+}
+
+impl Sender for ResultRecorder {
+    fn flush(&self) -> io::Result<()> {
+        self.sender.flush()
+    }
+
+    fn send(&self, query_result: QueryResult) -> io::Result<()> {
+        match &query_result {
+            Ok(QueryEvent::RowDescription(description)) => {
+                self.result.lock().expect("to acquire recorder lock").0 = description.clone()
+            }
+            Ok(QueryEvent::DataRows(rows)) => self
+                .result
+                .lock()
+                .expect("to acquire recorder lock")
+                .1
+                .extend(rows.iter().cloned()),
+            Ok(QueryEvent::SelectComplete(_)) => self.completed.store(true, Ordering::SeqCst),
+            Ok(_) => {}
+            Err(_) => self.failed.store(true, Ordering::SeqCst),
+        }
+        self.sender.send(query_result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use protocol::{results::ColumnDescription, sql_types::PostgreSqlType};
+
+    fn result(value: &str) -> (Description, Vec<Vec<Option<String>>>) {
+        (
+            vec![ColumnDescription::new("column_1", PostgreSqlType::SmallInt)],
+            vec![vec![Some(value.to_owned())]],
+        )
+    }
+
+    #[test]
+    fn least_recently_used_is_evicted() {
+        let cache = ResultCache::new(2);
+        let generation = cache.generation();
+        cache.put("query_1".to_owned(), vec![], generation, result("1"));
+        cache.put("query_2".to_owned(), vec![], generation, result("2"));
+        assert_eq!(cache.get("query_1"), Some(result("1")));
+
+        cache.put("query_3".to_owned(), vec![], generation, result("3"));
+
+        assert_eq!(cache.get("query_1"), Some(result("1")));
+        assert_eq!(cache.get("query_2"), None);
+        assert_eq!(cache.get("query_3"), Some(result("3")));
+    }
+
+    #[test]
+    fn tables_and_schemas_invalidate_entries() {
+        let cache = ResultCache::new(3);
+        let generation = cache.generation();
+        cache.put(
+            "query_1".to_owned(),
+            vec!["schema_1.table_1".to_owned()],
+            generation,
+            result("1"),
+        );
+        cache.put(
+            "query_2".to_owned(),
+            vec!["schema_1.table_2".to_owned()],
+            generation,
+            result("2"),
+        );
+        cache.put(
+            "query_3".to_owned(),
+            vec!["schema_2.table_1".to_owned()],
+            generation,
+            result("3"),
+        );
+
+        cache.invalidate(&["schema_1.table_1".to_owned()]);
+        assert_eq!(cache.len(), 2);
+
+        cache.invalidate(&["schema_2".to_owned()]);
+        assert_eq!(cache.get("query_2"), Some(result("2")));
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn result_computed_concurrently_with_write_is_not_cached() {
+        let cache = ResultCache::new(1);
+        let generation = cache.generation();
+        cache.invalidate(&["schema_1.table_1".to_owned()]);
+
+        cache.put(
+            "query_1".to_owned(),
+            vec!["schema_1.table_1".to_owned()],
+            generation,
+            result("1"),
+        );
+
+        assert!(cache.is_empty());
+    }
+}
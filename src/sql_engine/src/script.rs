@@ -0,0 +1,209 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Scripts of semicolon separated statements, e.g. migrations, that are
+//! executed one statement after another.
+use std::borrow::Cow;
+
+/// Splits `script` into statements without terminating semicolons.
+/// Semicolons inside of string literals, quoted identifiers, dollar quoted
+/// strings and comments do not end statements. Statements that consist only
+/// of whitespaces and comments are skipped.
+pub fn split_statements(script: &str) -> Vec<&str> {
+    let bytes = script.as_bytes();
+    let mut statements = vec![];
+    let mut start = 0;
+    let mut has_code = false;
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b';' => {
+                if has_code {
+                    statements.push(script[start..index].trim());
+                }
+                start = index + 1;
+                has_code = false;
+                index += 1;
+            }
+            b'-' if bytes.get(index + 1) == Some(&b'-') => {
+                index = find(script, index + 2, "\n")
+                    .map(|end| end + 1)
+                    .unwrap_or_else(|| bytes.len());
+            }
+            b'/' if bytes.get(index + 1) == Some(&b'*') => {
+                index = find(script, index + 2, "*/")
+                    .map(|end| end + 2)
+                    .unwrap_or_else(|| bytes.len());
+            }
+            quote @ b'\'' | quote @ b'"' => {
+                has_code = true;
+                index = end_of_quoted(bytes, index + 1, quote);
+            }
+            b'$' => {
+                has_code = true;
+                index = match dollar_quote(script, index) {
+                    Some(tag) => find(script, index + tag.len(), tag)
+                        .map(|end| end + tag.len())
+                        .unwrap_or_else(|| bytes.len()),
+                    None => index + 1,
+                };
+            }
+            byte => {
+                has_code |= !byte.is_ascii_whitespace();
+                index += 1;
+            }
+        }
+    }
+    if has_code {
+        statements.push(script[start..].trim());
+    }
+    statements
+}
+
+/// Replaces dollar quoted strings of `sql`, e.g. function bodies, with
+/// string literals that the tokenizer understands
+pub(crate) fn dollar_quotes_to_literals(sql: &str) -> Cow<'_, str> {
+    if !sql.contains('$') {
+        return Cow::Borrowed(sql);
+    }
+    let bytes = sql.as_bytes();
+    let mut replaced = String::with_capacity(sql.len());
+    let mut copied = 0;
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'-' if bytes.get(index + 1) == Some(&b'-') => {
+                index = find(sql, index + 2, "\n")
+                    .map(|end| end + 1)
+                    .unwrap_or_else(|| bytes.len());
+            }
+            b'/' if bytes.get(index + 1) == Some(&b'*') => {
+                index = find(sql, index + 2, "*/")
+                    .map(|end| end + 2)
+                    .unwrap_or_else(|| bytes.len());
+            }
+            quote @ b'\'' | quote @ b'"' => index = end_of_quoted(bytes, index + 1, quote),
+            b'$' => match dollar_quote(sql, index) {
+                Some(tag) => {
+                    let start = index + tag.len();
+                    let end = match find(sql, start, tag) {
+                        Some(end) => end,
+                        None => break,
+                    };
+                    replaced.push_str(&sql[copied..index]);
+                    replaced.push('\'');
+                    replaced.push_str(&sql[start..end].replace('\'', "''"));
+                    replaced.push('\'');
+                    index = end + tag.len();
+                    copied = index;
+                }
+                None => index += 1,
+            },
+            _ => index += 1,
+        }
+    }
+    if copied == 0 {
+        return Cow::Borrowed(sql);
+    }
+    replaced.push_str(&sql[copied..]);
+    Cow::Owned(replaced)
+}
+
+fn find(script: &str, from: usize, pattern: &str) -> Option<usize> {
+    script[from..].find(pattern).map(|position| from + position)
+}
+
+/// Index right after the closing `quote`, doubled quotes are escaped ones
+fn end_of_quoted(bytes: &[u8], mut index: usize, quote: u8) -> usize {
+    while index < bytes.len() {
+        if bytes[index] == quote {
+            if bytes.get(index + 1) == Some(&quote) {
+                index += 2;
+                continue;
+            }
+            return index + 1;
+        }
+        index += 1;
+    }
+    bytes.len()
+}
+
+/// `$tag$` that starts a dollar quoted string at `start`
+fn dollar_quote(script: &str, start: usize) -> Option<&str> {
+    let end = find(script, start + 1, "$")?;
+    let tag = &script[start + 1..end];
+    if tag.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') && !tag.starts_with(|c: char| c.is_ascii_digit()) {
+        Some(&script[start..=end])
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn statements() {
+        assert_eq!(
+            split_statements("create schema s;\n  create table s.t (c smallint) ;insert into s.t values (1)"),
+            vec![
+                "create schema s",
+                "create table s.t (c smallint)",
+                "insert into s.t values (1)"
+            ]
+        );
+    }
+
+    #[test]
+    fn semicolons_in_literals_and_identifiers() {
+        assert_eq!(
+            split_statements("insert into s.t values ('a;''b'); select \"c;\" from s.t;"),
+            vec!["insert into s.t values ('a;''b')", "select \"c;\" from s.t"]
+        );
+    }
+
+    #[test]
+    fn semicolons_in_dollar_quoted_strings() {
+        assert_eq!(
+            split_statements("select $body$ a; b $body$; select $$;$$; select $1;"),
+            vec!["select $body$ a; b $body$", "select $$;$$", "select $1"]
+        );
+    }
+
+    #[test]
+    fn comments() {
+        assert_eq!(
+            split_statements("-- first; migration\ncreate schema s; /* drop; */\n-- the end;\n"),
+            vec!["-- first; migration\ncreate schema s"]
+        );
+    }
+
+    #[test]
+    fn dollar_quoted_strings_as_literals() {
+        assert_eq!(
+            dollar_quotes_to_literals("create function f() returns text as $body$ begin return 'a;$$'; end $body$"),
+            "create function f() returns text as ' begin return ''a;$$''; end '"
+        );
+        assert_eq!(
+            dollar_quotes_to_literals("select '$$', $1 -- $$"),
+            Cow::Borrowed("select '$$', $1 -- $$")
+        );
+    }
+
+    #[test]
+    fn empty_statements() {
+        assert_eq!(split_statements(" ; ;\n"), Vec::<&str>::new());
+    }
+}
@@ -0,0 +1,152 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Settings of a running server that can be changed without a restart.
+//! Sessions pick new defaults up at the start of the next statement unless
+//! they changed the setting with `SET`. `SELECT pg_reload_conf()` asks the
+//! server to read its configuration again.
+use sqlparser::ast::{Expr, Query, Select, SelectItem, SetExpr};
+use std::{sync::RwLock, time::Duration};
+
+/// Name of the function that reloads configuration of the server
+pub const RELOAD_FUNCTION: &str = "pg_reload_conf";
+const CATALOG_SCHEMA: &str = "pg_catalog";
+
+type Reload = Box<dyn Fn() + Send + Sync>;
+
+/// Defaults shared by all sessions of a server
+#[derive(Default)]
+pub struct ServerSettings {
+    log_min_duration_statement: RwLock<Option<Duration>>,
+    auto_explain_log_min_duration: RwLock<Option<Duration>>,
+    reload: RwLock<Option<Reload>>,
+}
+
+impl ServerSettings {
+    pub fn log_min_duration_statement(&self) -> Option<Duration> {
+        *self
+            .log_min_duration_statement
+            .read()
+            .expect("to acquire server settings lock")
+    }
+
+    pub fn set_log_min_duration_statement(&self, duration: Option<Duration>) {
+        *self
+            .log_min_duration_statement
+            .write()
+            .expect("to acquire server settings lock") = duration;
+    }
+
+    pub fn auto_explain_log_min_duration(&self) -> Option<Duration> {
+        *self
+            .auto_explain_log_min_duration
+            .read()
+            .expect("to acquire server settings lock")
+    }
+
+    pub fn set_auto_explain_log_min_duration(&self, duration: Option<Duration>) {
+        *self
+            .auto_explain_log_min_duration
+            .write()
+            .expect("to acquire server settings lock") = duration;
+    }
+
+    /// `reload` is run when a superuser calls `pg_reload_conf()`
+    pub fn set_reload(&self, reload: impl Fn() + Send + Sync + 'static) {
+        *self.reload.write().expect("to acquire server settings lock") = Some(Box::new(reload));
+    }
+
+    /// Returns `false` if the server can't reload its configuration
+    pub fn reload(&self) -> bool {
+        match self.reload.read().expect("to acquire server settings lock").as_ref() {
+            Some(reload) => {
+                reload();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// returns `true` if `query` is `SELECT pg_reload_conf()`
+pub(crate) fn is_reload_call(query: &Query) -> bool {
+    let select = match &query.body {
+        SetExpr::Select(select) if query.ctes.is_empty() => select,
+        _ => return false,
+    };
+    let Select {
+        projection,
+        from,
+        selection,
+        group_by,
+        having,
+        ..
+    } = select.as_ref();
+    if !from.is_empty() || selection.is_some() || !group_by.is_empty() || having.is_some() {
+        return false;
+    }
+    match projection.as_slice() {
+        [SelectItem::UnnamedExpr(Expr::Function(function))] if function.args.is_empty() => {
+            match function.name.0.as_slice() {
+                [name] => name.value.to_lowercase() == RELOAD_FUNCTION,
+                [schema, name] => {
+                    schema.value.to_lowercase() == CATALOG_SCHEMA && name.value.to_lowercase() == RELOAD_FUNCTION
+                }
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlparser::{ast::Statement, dialect::PostgreSqlDialect, parser::Parser};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    fn recognize(sql: &str) -> bool {
+        match Parser::parse_sql(&PostgreSqlDialect {}, sql).expect("valid sql").pop() {
+            Some(Statement::Query(query)) => is_reload_call(&query),
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn reload_call() {
+        assert!(recognize("select pg_reload_conf()"));
+        assert!(recognize("SELECT pg_catalog.PG_RELOAD_CONF()"));
+        assert!(!recognize("select pg_reload_conf(1)"));
+        assert!(!recognize("select pg_reload_conf() from t"));
+        assert!(!recognize("select other.pg_reload_conf()"));
+    }
+
+    #[test]
+    fn reload_runs_handler() {
+        let settings = ServerSettings::default();
+        assert!(!settings.reload());
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        settings.set_reload(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        assert!(settings.reload());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
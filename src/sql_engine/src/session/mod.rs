@@ -12,12 +12,16 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub(crate) mod settings;
 pub(crate) mod statement;
 
-use protocol::sql_formats::PostgreSqlFormat;
-use sqlparser::ast::Statement;
+use crate::dml::{cte::DEFAULT_MAX_RECURSIVE_ITERATIONS, memory::DEFAULT_WORK_MEM};
 use statement::{Portal, PreparedStatement};
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 /// A `Session` holds SQL state that is attached to a session.
 #[derive(Clone, Debug)]
@@ -26,20 +30,142 @@ pub struct Session {
     prepared_statements: HashMap<String, PreparedStatement>,
     /// A map from statement names to bound statements
     portals: HashMap<String, Portal>,
+    /// A role on behalf of which queries are executed
+    session_user: String,
+    /// Statements that run longer are logged, `None` disables the log
+    log_min_duration_statement: Option<Duration>,
+    /// Plans of statements that run longer are logged with their
+    /// measurements, `None` disables the log
+    auto_explain_log_min_duration: Option<Duration>,
+    /// Statements that run longer are cancelled, `None` disables the timeout
+    statement_timeout: Option<Duration>,
+    /// Number of worker threads a query operator can use besides the
+    /// session one
+    max_parallel_workers: usize,
+    /// Kilobytes of intermediate results a query operator can hold
+    work_mem: usize,
+    /// Iterations a recursive common table expression can run
+    max_recursive_iterations: usize,
+    /// Large objects opened with `lo_open` by their descriptors
+    large_objects: LargeObjectDescriptors,
+}
+
+/// A large object opened in a session
+#[derive(Clone, Debug, PartialEq)]
+pub struct LargeObjectDescriptor {
+    pub oid: u32,
+    pub writable: bool,
+    /// Position of the next read or write
+    pub offset: usize,
+}
+
+/// Large objects opened in a session by their descriptors, clones share
+/// them with functions that statements of the session call
+#[derive(Clone, Debug, Default)]
+pub struct LargeObjectDescriptors(Arc<Mutex<HashMap<i32, LargeObjectDescriptor>>>);
+
+impl LargeObjectDescriptors {
+    /// Returns the lowest descriptor that is not in use
+    pub fn open(&self, oid: u32, writable: bool) -> i32 {
+        let mut descriptors = self.0.lock().expect("to acquire lock");
+        let fd = (0..).find(|fd| !descriptors.contains_key(fd)).expect("free descriptor");
+        descriptors.insert(
+            fd,
+            LargeObjectDescriptor {
+                oid,
+                writable,
+                offset: 0,
+            },
+        );
+        fd
+    }
+
+    /// Result of `f` applied to the descriptor, `None` if it is not open
+    pub fn with_descriptor<T>(&self, fd: i32, f: impl FnOnce(&mut LargeObjectDescriptor) -> T) -> Option<T> {
+        self.0.lock().expect("to acquire lock").get_mut(&fd).map(f)
+    }
+
+    pub fn close(&self, fd: i32) -> bool {
+        self.0.lock().expect("to acquire lock").remove(&fd).is_some()
+    }
 }
 
 impl Session {
-    pub fn new() -> Self {
+    pub fn new(session_user: &str) -> Self {
         Self {
             prepared_statements: HashMap::new(),
             portals: HashMap::new(),
+            session_user: session_user.to_owned(),
+            log_min_duration_statement: None,
+            auto_explain_log_min_duration: None,
+            statement_timeout: None,
+            max_parallel_workers: 0,
+            work_mem: DEFAULT_WORK_MEM,
+            max_recursive_iterations: DEFAULT_MAX_RECURSIVE_ITERATIONS,
+            large_objects: LargeObjectDescriptors::default(),
         }
     }
 
+    pub fn session_user(&self) -> &str {
+        self.session_user.as_str()
+    }
+
+    pub fn log_min_duration_statement(&self) -> Option<Duration> {
+        self.log_min_duration_statement
+    }
+
+    pub fn set_log_min_duration_statement(&mut self, duration: Option<Duration>) {
+        self.log_min_duration_statement = duration;
+    }
+
+    pub fn auto_explain_log_min_duration(&self) -> Option<Duration> {
+        self.auto_explain_log_min_duration
+    }
+
+    pub fn set_auto_explain_log_min_duration(&mut self, duration: Option<Duration>) {
+        self.auto_explain_log_min_duration = duration;
+    }
+
+    pub fn statement_timeout(&self) -> Option<Duration> {
+        self.statement_timeout
+    }
+
+    pub fn set_statement_timeout(&mut self, timeout: Option<Duration>) {
+        self.statement_timeout = timeout;
+    }
+
+    pub fn max_parallel_workers(&self) -> usize {
+        self.max_parallel_workers
+    }
+
+    pub fn set_max_parallel_workers(&mut self, workers: usize) {
+        self.max_parallel_workers = workers;
+    }
+
+    pub fn work_mem(&self) -> usize {
+        self.work_mem
+    }
+
+    pub fn set_work_mem(&mut self, kilobytes: usize) {
+        self.work_mem = kilobytes;
+    }
+
+    pub fn max_recursive_iterations(&self) -> usize {
+        self.max_recursive_iterations
+    }
+
+    pub fn set_max_recursive_iterations(&mut self, iterations: usize) {
+        self.max_recursive_iterations = iterations;
+    }
+
     pub fn get_prepared_statement(&self, name: &str) -> Option<&PreparedStatement> {
         self.prepared_statements.get(name)
     }
 
+    pub fn get_prepared_statement_mut(&mut self, name: &str) -> Option<&mut PreparedStatement> {
+        self.prepared_statements.get_mut(name)
+    }
+
     pub fn set_prepared_statement(&mut self, name: String, statement: PreparedStatement) {
         self.prepared_statements.insert(name, statement);
     }
@@ -48,14 +174,21 @@ impl Session {
         self.portals.get(name)
     }
 
-    pub fn set_portal(
-        &mut self,
-        portal_name: String,
-        statement_name: String,
-        stmt: Statement,
-        result_formats: Vec<PostgreSqlFormat>,
-    ) {
-        let new_portal = Portal::new(statement_name, stmt, result_formats);
-        self.portals.insert(portal_name, new_portal);
+    pub fn get_portal_mut(&mut self, name: &str) -> Option<&mut Portal> {
+        self.portals.get_mut(name)
+    }
+
+    pub fn set_portal(&mut self, portal_name: String, portal: Portal) {
+        self.portals.insert(portal_name, portal);
+    }
+
+    pub fn large_objects(&self) -> &LargeObjectDescriptors {
+        &self.large_objects
+    }
+
+    /// Shares large objects opened by another session, e.g. by the session
+    /// that executes a `DO` block
+    pub fn set_large_objects(&mut self, large_objects: LargeObjectDescriptors) {
+        self.large_objects = large_objects;
     }
 }
@@ -0,0 +1,263 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Run-time parameters that can be changed by `SET` statement
+use protocol::results::QueryError;
+use sqlparser::ast::{SetVariableValue, Value};
+use std::time::Duration;
+
+pub(crate) const LOG_MIN_DURATION_STATEMENT: &str = "log_min_duration_statement";
+/// has to be quoted in `SET` statement, e.g.
+/// `SET "auto_explain.log_min_duration" = '1s'`
+pub(crate) const AUTO_EXPLAIN_LOG_MIN_DURATION: &str = "auto_explain.log_min_duration";
+/// `0` turns off the timeout as well as `-1`
+pub(crate) const STATEMENT_TIMEOUT: &str = "statement_timeout";
+pub(crate) const MAX_PARALLEL_WORKERS: &str = "max_parallel_workers";
+pub(crate) const WORK_MEM: &str = "work_mem";
+pub(crate) const MAX_RECURSIVE_ITERATIONS: &str = "max_recursive_iterations";
+/// Minimal value of `work_mem` in kilobytes
+const MIN_WORK_MEM: usize = 64;
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum SettingValue<T> {
+    Default,
+    Value(T),
+}
+
+/// Parses duration in milliseconds or with one of `ms`, `s` and `min` units,
+/// `-1` turns off the feature that the parameter controls
+pub(crate) fn parse_duration(
+    name: &str,
+    value: &SetVariableValue,
+) -> Result<SettingValue<Option<Duration>>, QueryError> {
+    let text = match value {
+        SetVariableValue::Ident(ident) if ident.value.to_lowercase() == "default" => return Ok(SettingValue::Default),
+        SetVariableValue::Literal(Value::Number(number)) => number.to_string(),
+        SetVariableValue::Literal(Value::SingleQuotedString(text)) => text.trim().to_owned(),
+        other => return Err(invalid_value(name, &other.to_string())),
+    };
+    if text == "-1" {
+        return Ok(SettingValue::Value(None));
+    }
+    let unit_start = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+    let amount = text[..unit_start]
+        .parse::<u64>()
+        .map_err(|_| invalid_value(name, &text))?;
+    let duration = match text[unit_start..].trim() {
+        "" | "ms" => Duration::from_millis(amount),
+        "s" => Duration::from_secs(amount),
+        "min" => Duration::from_secs(amount * 60),
+        _ => return Err(invalid_value(name, &text)),
+    };
+    Ok(SettingValue::Value(Some(duration)))
+}
+
+/// Parses non negative number
+pub(crate) fn parse_count(name: &str, value: &SetVariableValue) -> Result<SettingValue<usize>, QueryError> {
+    match value {
+        SetVariableValue::Ident(ident) if ident.value.to_lowercase() == "default" => Ok(SettingValue::Default),
+        SetVariableValue::Literal(Value::Number(number)) => number
+            .to_string()
+            .parse()
+            .map(SettingValue::Value)
+            .map_err(|_| invalid_value(name, &number.to_string())),
+        SetVariableValue::Literal(Value::SingleQuotedString(text)) => text
+            .trim()
+            .parse()
+            .map(SettingValue::Value)
+            .map_err(|_| invalid_value(name, text)),
+        other => Err(invalid_value(name, &other.to_string())),
+    }
+}
+
+/// Parses amount of memory in kilobytes or with one of `kB`, `MB` and `GB`
+/// units, the amount can't be less than 64 kilobytes
+pub(crate) fn parse_memory(name: &str, value: &SetVariableValue) -> Result<SettingValue<usize>, QueryError> {
+    let text = match value {
+        SetVariableValue::Ident(ident) if ident.value.to_lowercase() == "default" => return Ok(SettingValue::Default),
+        SetVariableValue::Literal(Value::Number(number)) => number.to_string(),
+        SetVariableValue::Literal(Value::SingleQuotedString(text)) => text.trim().to_owned(),
+        other => return Err(invalid_value(name, &other.to_string())),
+    };
+    let unit_start = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+    let amount = text[..unit_start]
+        .parse::<usize>()
+        .map_err(|_| invalid_value(name, &text))?;
+    let kilobytes = match text[unit_start..].trim() {
+        "" | "kB" => amount,
+        "MB" => amount * 1024,
+        "GB" => amount * 1024 * 1024,
+        _ => return Err(invalid_value(name, &text)),
+    };
+    if kilobytes < MIN_WORK_MEM {
+        return Err(invalid_value(name, &text));
+    }
+    Ok(SettingValue::Value(kilobytes))
+}
+
+/// Checks that the parameter `name` accepts `value`, values of parameters
+/// that are not known are accepted as `SET` ignores them
+pub(crate) fn check(name: &str, value: &SetVariableValue) -> Result<(), QueryError> {
+    match name {
+        LOG_MIN_DURATION_STATEMENT | AUTO_EXPLAIN_LOG_MIN_DURATION | STATEMENT_TIMEOUT => {
+            parse_duration(name, value).map(drop)
+        }
+        MAX_PARALLEL_WORKERS | MAX_RECURSIVE_ITERATIONS => parse_count(name, value).map(drop),
+        WORK_MEM => parse_memory(name, value).map(drop),
+        _ => Ok(()),
+    }
+}
+
+fn invalid_value(name: &str, value: &str) -> QueryError {
+    QueryError::invalid_parameter_value(format!("invalid value for parameter \"{}\": \"{}\"", name, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlparser::ast::Ident;
+
+    fn parse(value: SetVariableValue) -> Result<SettingValue<Option<Duration>>, QueryError> {
+        parse_duration(LOG_MIN_DURATION_STATEMENT, &value)
+    }
+
+    #[test]
+    fn milliseconds_by_default() {
+        assert_eq!(
+            parse(SetVariableValue::Literal(Value::Number(250.into()))),
+            Ok(SettingValue::Value(Some(Duration::from_millis(250))))
+        );
+    }
+
+    #[test]
+    fn duration_with_unit() {
+        assert_eq!(
+            parse(SetVariableValue::Literal(Value::SingleQuotedString("2 s".to_owned()))),
+            Ok(SettingValue::Value(Some(Duration::from_secs(2))))
+        );
+        assert_eq!(
+            parse(SetVariableValue::Literal(Value::SingleQuotedString("1min".to_owned()))),
+            Ok(SettingValue::Value(Some(Duration::from_secs(60))))
+        );
+    }
+
+    #[test]
+    fn disabled() {
+        assert_eq!(
+            parse(SetVariableValue::Literal(Value::SingleQuotedString("-1".to_owned()))),
+            Ok(SettingValue::Value(None))
+        );
+    }
+
+    #[test]
+    fn default() {
+        assert_eq!(
+            parse(SetVariableValue::Ident(Ident::new("DEFAULT"))),
+            Ok(SettingValue::Default)
+        );
+    }
+
+    #[test]
+    fn invalid_unit() {
+        assert_eq!(
+            parse(SetVariableValue::Literal(Value::SingleQuotedString(
+                "10 hours".to_owned()
+            ))),
+            Err(QueryError::invalid_parameter_value(
+                "invalid value for parameter \"log_min_duration_statement\": \"10 hours\"".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn count() {
+        assert_eq!(
+            parse_count(
+                MAX_PARALLEL_WORKERS,
+                &SetVariableValue::Literal(Value::SingleQuotedString("4".to_owned()))
+            ),
+            Ok(SettingValue::Value(4))
+        );
+        assert_eq!(
+            parse_count(
+                MAX_PARALLEL_WORKERS,
+                &SetVariableValue::Literal(Value::Number(0.into()))
+            ),
+            Ok(SettingValue::Value(0))
+        );
+    }
+
+    #[test]
+    fn negative_count() {
+        assert_eq!(
+            parse_count(
+                MAX_PARALLEL_WORKERS,
+                &SetVariableValue::Literal(Value::SingleQuotedString("-2".to_owned()))
+            ),
+            Err(QueryError::invalid_parameter_value(
+                "invalid value for parameter \"max_parallel_workers\": \"-2\"".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn memory_with_unit() {
+        assert_eq!(
+            parse_memory(
+                WORK_MEM,
+                &SetVariableValue::Literal(Value::SingleQuotedString("16MB".to_owned()))
+            ),
+            Ok(SettingValue::Value(16 * 1024))
+        );
+        assert_eq!(
+            parse_memory(WORK_MEM, &SetVariableValue::Literal(Value::Number(128.into()))),
+            Ok(SettingValue::Value(128))
+        );
+    }
+
+    #[test]
+    fn too_little_memory() {
+        assert_eq!(
+            parse_memory(
+                WORK_MEM,
+                &SetVariableValue::Literal(Value::SingleQuotedString("8kB".to_owned()))
+            ),
+            Err(QueryError::invalid_parameter_value(
+                "invalid value for parameter \"work_mem\": \"8kB\"".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn check_values_of_known_parameters() {
+        assert_eq!(
+            check(
+                WORK_MEM,
+                &SetVariableValue::Literal(Value::SingleQuotedString("4MB".to_owned()))
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            check(
+                WORK_MEM,
+                &SetVariableValue::Literal(Value::SingleQuotedString("4TB".to_owned()))
+            ),
+            Err(invalid_value(WORK_MEM, "4TB"))
+        );
+        assert_eq!(
+            check("search_path", &SetVariableValue::Ident(Ident::new("schema_1"))),
+            Ok(())
+        );
+    }
+}
@@ -34,7 +34,12 @@
 //! 4. The client issues an `Execute` message with the name of a portal, causing
 //!    that portal to actually start scanning and returning results.
 
-use protocol::{results::Description, sql_formats::PostgreSqlFormat, sql_types::PostgreSqlType};
+use crate::query::plan::Plan;
+use protocol::{
+    results::{ColumnDescription, Description},
+    sql_formats::PostgreSqlFormat,
+    sql_types::PostgreSqlType,
+};
 use sqlparser::ast::Statement;
 
 /// A prepared statement.
@@ -42,19 +47,45 @@ use sqlparser::ast::Statement;
 pub struct PreparedStatement {
     /// The raw prepared SQL statement will be bound to a portal.
     stmt: Statement,
+    /// The text of the statement as it was sent by the client.
+    raw_sql_query: String,
     /// The types of any bound parameters.
     param_types: Vec<PostgreSqlType>,
     /// The type of the rows that will be returned.
     description: Description,
+    /// The version of the catalog the statement was described against.
+    catalog_version: u64,
+    /// The formats of the last `Bind` message that were successfully
+    /// validated.
+    bound_formats: Option<BoundFormats>,
+}
+
+/// Formats of parameters and result columns as a client sent them in a `Bind`
+/// message and padded to the number of parameters and result columns.
+#[derive(Clone, Debug)]
+struct BoundFormats {
+    sent_params: Vec<PostgreSqlFormat>,
+    sent_results: Vec<PostgreSqlFormat>,
+    params: Vec<PostgreSqlFormat>,
+    results: Vec<PostgreSqlFormat>,
 }
 
 impl PreparedStatement {
     /// Constructs a new `PreparedStatement`.
-    pub fn new(stmt: Statement, param_types: Vec<PostgreSqlType>, description: Description) -> PreparedStatement {
+    pub fn new(
+        stmt: Statement,
+        raw_sql_query: String,
+        param_types: Vec<PostgreSqlType>,
+        description: Description,
+        catalog_version: u64,
+    ) -> PreparedStatement {
         PreparedStatement {
             stmt,
+            raw_sql_query,
             param_types,
             description,
+            catalog_version,
+            bound_formats: None,
         }
     }
 
@@ -63,15 +94,63 @@ impl PreparedStatement {
         &self.stmt
     }
 
+    /// Returns the text of the statement with parameter placeholders.
+    pub fn raw_sql_query(&self) -> &str {
+        self.raw_sql_query.as_str()
+    }
+
     /// Returns the types of any bound parameters.
     pub fn param_types(&self) -> &[PostgreSqlType] {
         &self.param_types
     }
 
     /// Returns the type of the rows that will be returned.
-    pub fn description(&self) -> &[(String, PostgreSqlType)] {
+    pub fn description(&self) -> &[ColumnDescription] {
         self.description.as_ref()
     }
+
+    /// Returns the version of the catalog the statement was described against.
+    pub fn catalog_version(&self) -> u64 {
+        self.catalog_version
+    }
+
+    /// Records that the statement is still valid for the given version of
+    /// the catalog with the `description` made against it.
+    pub fn revalidated(&mut self, catalog_version: u64, description: Description) {
+        self.catalog_version = catalog_version;
+        self.description = description;
+    }
+
+    /// Returns padded formats of parameters and result columns if the last
+    /// `Bind` message sent the same formats.
+    pub fn bound_formats(
+        &self,
+        param_formats: &[PostgreSqlFormat],
+        result_formats: &[PostgreSqlFormat],
+    ) -> Option<(&[PostgreSqlFormat], &[PostgreSqlFormat])> {
+        match &self.bound_formats {
+            Some(bound) if bound.sent_params == param_formats && bound.sent_results == result_formats => {
+                Some((&bound.params, &bound.results))
+            }
+            _ => None,
+        }
+    }
+
+    /// Remembers validated formats of a `Bind` message.
+    pub fn set_bound_formats(
+        &mut self,
+        sent_params: &[PostgreSqlFormat],
+        sent_results: &[PostgreSqlFormat],
+        params: Vec<PostgreSqlFormat>,
+        results: Vec<PostgreSqlFormat>,
+    ) {
+        self.bound_formats = Some(BoundFormats {
+            sent_params: sent_params.to_vec(),
+            sent_results: sent_results.to_vec(),
+            params,
+            results,
+        });
+    }
 }
 
 /// A portal represents the execution state of a running or runnable query.
@@ -79,24 +158,112 @@ impl PreparedStatement {
 pub struct Portal {
     /// The name of the prepared statement that is bound to this portal.
     statement_name: String,
-    /// The bound SQL statement from the prepared statement.
+    /// The text of the prepared statement.
+    raw_sql_query: String,
+    /// The bound SQL statement, it is planned again if the catalog changes
+    /// before the portal is executed.
     stmt: Statement,
+    /// The plan of the bound SQL statement, it is executed without
+    /// re-planning while the catalog is unchanged.
+    plan: Plan,
+    /// The version of the catalog the statement was planned against.
+    catalog_version: u64,
     /// The desired output format for each column in the result set.
     result_formats: Vec<PostgreSqlFormat>,
+    /// The type of the rows that will be returned.
+    description: Description,
+    /// Whether the portal has been run to completion.
+    completed: bool,
+    /// Rows that are left after an execution reached its row limit, they
+    /// are sent by the following executions of the portal.
+    suspended: Option<Vec<Vec<Option<String>>>>,
 }
 
 impl Portal {
     /// Constructs a new `Portal`.
-    pub fn new(statement_name: String, stmt: Statement, result_formats: Vec<PostgreSqlFormat>) -> Self {
+    pub fn new(
+        statement_name: String,
+        raw_sql_query: String,
+        stmt: Statement,
+        plan: Plan,
+        catalog_version: u64,
+        result_formats: Vec<PostgreSqlFormat>,
+        description: Description,
+    ) -> Self {
         Self {
             statement_name,
+            raw_sql_query,
             stmt,
+            plan,
+            catalog_version,
             result_formats,
+            description,
+            completed: false,
+            suspended: None,
         }
     }
 
+    /// Returns the text of the prepared statement.
+    pub fn raw_sql_query(&self) -> &str {
+        self.raw_sql_query.as_str()
+    }
+
     /// Returns the bound SQL statement.
     pub fn stmt(&self) -> &Statement {
         &self.stmt
     }
+
+    /// Returns the plan of the bound SQL statement.
+    pub fn plan(&self) -> &Plan {
+        &self.plan
+    }
+
+    /// Returns the version of the catalog the statement was planned against.
+    pub fn catalog_version(&self) -> u64 {
+        self.catalog_version
+    }
+
+    /// Replaces the plan and the description with ones made against the
+    /// given version of the catalog.
+    pub fn replan(&mut self, plan: Plan, catalog_version: u64, description: Description) {
+        self.plan = plan;
+        self.catalog_version = catalog_version;
+        self.description = description;
+    }
+
+    /// Returns the type of the rows that will be returned.
+    pub fn description(&self) -> &[ColumnDescription] {
+        self.description.as_ref()
+    }
+
+    /// Returns whether the portal has been run to completion.
+    pub fn is_completed(&self) -> bool {
+        self.completed
+    }
+
+    /// Marks the portal as run to completion. It has to be bound again to
+    /// be executed with fresh parameters.
+    pub fn complete(&mut self) {
+        self.completed = true;
+    }
+
+    /// Returns whether rows are left from a previous execution of the portal.
+    pub fn is_suspended(&self) -> bool {
+        self.suspended.is_some()
+    }
+
+    /// Keeps rows that are left after an execution reached its row limit.
+    pub fn suspend(&mut self, rows: Vec<Vec<Option<String>>>) {
+        self.suspended = Some(rows);
+    }
+
+    /// Takes at most `max_rows` rows that are left, all of them if it is `0`.
+    /// The portal is no longer suspended once its rows are exhausted.
+    pub fn fetch(&mut self, max_rows: usize) -> Vec<Vec<Option<String>>> {
+        let rows = self.suspended.get_or_insert_with(Vec::new);
+        if max_rows == 0 || max_rows >= rows.len() {
+            return self.suspended.take().unwrap_or_default();
+        }
+        rows.drain(..max_rows).collect()
+    }
 }
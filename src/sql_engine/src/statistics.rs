@@ -0,0 +1,564 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Aggregated statistics of executed statements similar to PostgreSQL
+//! `pg_stat_statements` extension. Statements that differ only in literal
+//! values, whitespaces or case of keywords have the same fingerprint and are
+//! accumulated into the same entry. Access counters of tables
+//! kept by the catalog are exposed through `pg_stat_user_tables` view.
+use crate::{
+    catalog_manager::{CatalogManager, SUPERUSER},
+    dml::functions::system::timestamp_with_time_zone,
+};
+use protocol::{
+    results::{ColumnDescription, Projection, QueryEvent, QueryResult},
+    sql_types::PostgreSqlType,
+    Sender,
+};
+use sqlparser::{
+    ast::{Expr, Query, Select, SelectItem, SetExpr, TableFactor},
+    dialect::PostgreSqlDialect,
+    tokenizer::{Token, Tokenizer},
+};
+use std::{
+    collections::HashMap,
+    io,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+/// Name of the view with the statistics of statements
+pub const STATISTICS_VIEW: &str = "pg_stat_statements";
+/// Name of the view with the access statistics of tables
+pub const TABLES_VIEW: &str = "pg_stat_user_tables";
+/// Name of the function that discards all the statistics
+pub const RESET_FUNCTION: &str = "pg_stat_statements_reset";
+const CATALOG_SCHEMA: &str = "pg_catalog";
+const INSUFFICIENT_PRIVILEGE: &str = "<insufficient privilege>";
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct Counters {
+    calls: u64,
+    total_time: Duration,
+    rows: u64,
+}
+
+/// Statistics shared by all sessions
+#[derive(Default)]
+pub struct StatementStatistics {
+    /// normalized text of the first execution and counters of statements by
+    /// role and fingerprint
+    entries: Mutex<HashMap<(String, i64), (String, Counters)>>,
+}
+
+impl StatementStatistics {
+    /// Accumulates single execution of `raw_sql_query` by `role`
+    pub(crate) fn record(&self, role: &str, raw_sql_query: &str, duration: Duration, rows: usize) {
+        let mut entries = self.entries.lock().expect("to acquire statistics lock");
+        let (_query, counters) = entries
+            .entry((role.to_owned(), fingerprint(raw_sql_query)))
+            .or_insert_with(|| (normalize(raw_sql_query), Counters::default()));
+        counters.calls += 1;
+        counters.total_time += duration;
+        counters.rows += rows as u64;
+    }
+
+    /// Discards all accumulated statistics
+    pub(crate) fn reset(&self) {
+        self.entries.lock().expect("to acquire statistics lock").clear();
+    }
+
+    /// Keeps at most `max` statements, the least called ones are discarded
+    /// first. Returns the number of discarded statements.
+    pub fn trim(&self, max: usize) -> usize {
+        let mut entries = self.entries.lock().expect("to acquire statistics lock");
+        if entries.len() <= max {
+            return 0;
+        }
+        let mut least_called = entries
+            .iter()
+            .map(|(key, (_query, counters))| (counters.calls, key.clone()))
+            .collect::<Vec<_>>();
+        least_called.sort();
+        let discarded = entries.len() - max;
+        for (_calls, key) in least_called.into_iter().take(discarded) {
+            entries.remove(&key);
+        }
+        discarded
+    }
+
+    /// Content of `pg_stat_statements` view as seen by `role`. Only superuser
+    /// can see texts of statements executed by other roles.
+    pub(crate) fn view(&self, role: &str) -> Projection {
+        let description = vec![
+            ColumnDescription::new("role", PostgreSqlType::VarChar),
+            ColumnDescription::new("queryid", PostgreSqlType::BigInt),
+            ColumnDescription::new("query", PostgreSqlType::VarChar),
+            ColumnDescription::new("calls", PostgreSqlType::BigInt),
+            ColumnDescription::new("total_time", PostgreSqlType::DoublePrecision),
+            ColumnDescription::new("mean_time", PostgreSqlType::DoublePrecision),
+            ColumnDescription::new("rows", PostgreSqlType::BigInt),
+        ];
+        let entries = self.entries.lock().expect("to acquire statistics lock");
+        let mut statements = entries.iter().collect::<Vec<_>>();
+        statements.sort_by(|((left, _), (left_query, _)), ((right, _), (right_query, _))| {
+            (left, left_query).cmp(&(right, right_query))
+        });
+        let records = statements
+            .into_iter()
+            .map(|((owner, queryid), (query, counters))| {
+                let total_time = counters.total_time.as_secs_f64() * 1000.0;
+                vec![
+                    Some(owner.clone()),
+                    Some(queryid.to_string()),
+                    Some(if role == SUPERUSER || role == owner {
+                        query.clone()
+                    } else {
+                        INSUFFICIENT_PRIVILEGE.to_owned()
+                    }),
+                    Some(counters.calls.to_string()),
+                    Some(format!("{:.3}", total_time)),
+                    Some(format!("{:.3}", total_time / counters.calls as f64)),
+                    Some(counters.rows.to_string()),
+                ]
+            })
+            .collect();
+        (description, records)
+    }
+}
+
+/// Content of `pg_stat_user_tables` view
+pub(crate) fn tables_view(storage: &CatalogManager) -> Projection {
+    let mut description = vec![
+        ColumnDescription::new("schemaname", PostgreSqlType::VarChar),
+        ColumnDescription::new("relname", PostgreSqlType::VarChar),
+    ];
+    description.extend(
+        [
+            "seq_scan",
+            "seq_tup_read",
+            "idx_scan",
+            "idx_tup_fetch",
+            "n_tup_ins",
+            "n_tup_upd",
+            "n_tup_del",
+            "n_live_tup",
+            "n_dead_tup",
+            "n_mod_since_analyze",
+        ]
+        .iter()
+        .map(|column| ColumnDescription::new(column, PostgreSqlType::BigInt)),
+    );
+    description.extend(
+        ["last_autovacuum", "last_autoanalyze"]
+            .iter()
+            .map(|column| ColumnDescription::new(column, PostgreSqlType::TimestampWithTimeZone)),
+    );
+    description.extend(
+        ["autovacuum_count", "autoanalyze_count"]
+            .iter()
+            .map(|column| ColumnDescription::new(column, PostgreSqlType::BigInt)),
+    );
+    let records = storage
+        .statistics()
+        .all()
+        .into_iter()
+        .map(|((schema_name, table_name), statistics)| {
+            let mut record = vec![Some(schema_name), Some(table_name)];
+            record.extend(
+                [
+                    statistics.seq_scan,
+                    statistics.seq_tup_read,
+                    statistics.idx_scan,
+                    statistics.idx_tup_fetch,
+                    statistics.n_tup_ins,
+                    statistics.n_tup_upd,
+                    statistics.n_tup_del,
+                    statistics.n_live_tup,
+                    statistics.n_dead_tup,
+                    statistics.n_mod_since_analyze,
+                ]
+                .iter()
+                .map(|counter| Some(counter.to_string())),
+            );
+            record.push(statistics.last_autovacuum.map(timestamp_with_time_zone));
+            record.push(statistics.last_autoanalyze.map(timestamp_with_time_zone));
+            record.push(Some(statistics.autovacuum_count.to_string()));
+            record.push(Some(statistics.autoanalyze_count.to_string()));
+            record
+        })
+        .collect();
+    (description, records)
+}
+
+/// Replaces literals of the statement with `$1`, `$2`, ... placeholders and
+/// collapses whitespaces and comments into a single space. Placeholders are
+/// numbered after parameters of prepared statements.
+pub fn normalize(raw_sql_query: &str) -> String {
+    let tokens = match Tokenizer::new(&PostgreSqlDialect {}, raw_sql_query).tokenize() {
+        Ok(tokens) => tokens,
+        Err(_) => return raw_sql_query.trim().to_owned(),
+    };
+    let mut placeholders = tokens
+        .windows(2)
+        .filter_map(|pair| match pair {
+            [Token::Char('$'), Token::Number(number)] => number.parse::<usize>().ok(),
+            _ => None,
+        })
+        .max()
+        .unwrap_or(0);
+    let mut normalized = String::new();
+    let mut previous = None;
+    for token in &tokens {
+        match token {
+            Token::Whitespace(_) => {
+                if !normalized.is_empty() && !normalized.ends_with(' ') {
+                    normalized.push(' ');
+                }
+                continue;
+            }
+            Token::SemiColon => {}
+            Token::Number(_) if previous == Some(&Token::Char('$')) => normalized.push_str(&token.to_string()),
+            Token::Number(_)
+            | Token::SingleQuotedString(_)
+            | Token::NationalStringLiteral(_)
+            | Token::HexStringLiteral(_) => {
+                placeholders += 1;
+                normalized.push_str(&format!("${}", placeholders));
+            }
+            token => normalized.push_str(&token.to_string()),
+        }
+        previous = Some(token);
+    }
+    normalized.trim_end().to_owned()
+}
+
+/// Stable fingerprint of the statement that does not depend on its literals,
+/// whitespaces, comments and case of unquoted words. Statements with the same
+/// fingerprint have the same normalized text up to whitespaces and case.
+pub fn fingerprint(raw_sql_query: &str) -> i64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    let texts = match Tokenizer::new(&PostgreSqlDialect {}, raw_sql_query).tokenize() {
+        Ok(tokens) => {
+            let mut texts = vec![];
+            let mut previous = None;
+            for token in &tokens {
+                texts.push(match token {
+                    Token::Whitespace(_) | Token::SemiColon => continue,
+                    Token::Number(_) if previous == Some(&Token::Char('$')) => token.to_string(),
+                    Token::Number(_)
+                    | Token::SingleQuotedString(_)
+                    | Token::NationalStringLiteral(_)
+                    | Token::HexStringLiteral(_) => "?".to_owned(),
+                    Token::Word(word) if word.quote_style.is_none() => word.value.to_lowercase(),
+                    token => token.to_string(),
+                });
+                previous = Some(token);
+            }
+            texts
+        }
+        Err(_) => vec![raw_sql_query.trim().to_lowercase()],
+    };
+    let mut hash = OFFSET_BASIS;
+    // texts of tokens are separated by a byte that they can't contain
+    for byte in texts.iter().flat_map(|text| text.bytes().chain(Some(0))) {
+        hash = (hash ^ u64::from(byte)).wrapping_mul(PRIME);
+    }
+    hash as i64
+}
+
+/// Queries of the statistics that are served by the engine itself
+#[derive(Debug, PartialEq)]
+pub(crate) enum StatisticsQuery {
+    /// `SELECT * FROM pg_stat_statements`
+    Statements,
+    /// `SELECT * FROM pg_stat_user_tables`
+    Tables,
+    /// `SELECT pg_stat_statements_reset()`
+    Reset,
+}
+
+impl StatisticsQuery {
+    pub(crate) fn recognize(query: &Query) -> Option<StatisticsQuery> {
+        let select = match &query.body {
+            SetExpr::Select(select) if query.ctes.is_empty() => select,
+            _ => return None,
+        };
+        let Select {
+            projection,
+            from,
+            selection,
+            group_by,
+            having,
+            ..
+        } = select.as_ref();
+        if selection.is_some() || !group_by.is_empty() || having.is_some() {
+            return None;
+        }
+        match (from.as_slice(), projection.as_slice()) {
+            ([], [SelectItem::UnnamedExpr(Expr::Function(function))])
+                if function.args.is_empty() && is_catalog_object(&function.name.0, RESET_FUNCTION) =>
+            {
+                Some(StatisticsQuery::Reset)
+            }
+            ([table], [SelectItem::Wildcard]) if table.joins.is_empty() => match &table.relation {
+                TableFactor::Table { name, .. } if is_catalog_object(&name.0, STATISTICS_VIEW) => {
+                    Some(StatisticsQuery::Statements)
+                }
+                TableFactor::Table { name, .. } if is_catalog_object(&name.0, TABLES_VIEW) => {
+                    Some(StatisticsQuery::Tables)
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+fn is_catalog_object(name: &[sqlparser::ast::Ident], object: &str) -> bool {
+    match name {
+        [name] => name.value.to_lowercase() == object,
+        [schema, name] => schema.value.to_lowercase() == CATALOG_SCHEMA && name.value.to_lowercase() == object,
+        _ => false,
+    }
+}
+
+/// Counts rows that statements send to the client
+pub(crate) struct RowCounter {
+    sender: Arc<dyn Sender>,
+    rows: AtomicUsize,
+    failed: AtomicBool,
+}
+
+impl RowCounter {
+    pub(crate) fn new(sender: Arc<dyn Sender>) -> RowCounter {
+        RowCounter {
+            sender,
+            rows: AtomicUsize::new(0),
+            failed: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns number of rows counted since the previous call to `take`
+    pub(crate) fn rows(&self) -> usize {
+        self.rows.load(Ordering::SeqCst)
+    }
+
+    /// Returns `true` if an error was sent since the previous call to `take`
+    pub(crate) fn failed(&self) -> bool {
+        self.failed.load(Ordering::SeqCst)
+    }
+
+    /// Returns number of rows counted since the previous call or `None` if
+    /// an error was sent in the meantime
+    pub(crate) fn take(&self) -> Option<usize> {
+        let rows = self.rows.swap(0, Ordering::SeqCst);
+        if self.failed.swap(false, Ordering::SeqCst) {
+            None
+        } else {
+            Some(rows)
+        }
+    }
+}
+
+impl Sender for RowCounter {
+    fn flush(&self) -> io::Result<()> {
+        self.sender.flush()
+    }
+
+    fn send(&self, query_result: QueryResult) -> io::Result<()> {
+        match &query_result {
+            Ok(QueryEvent::RecordsSelected((_, records))) | Ok(QueryEvent::DataRows(records)) => {
+                self.rows.fetch_add(records.len(), Ordering::SeqCst);
+            }
+            Ok(QueryEvent::RecordsInserted(rows))
+            | Ok(QueryEvent::RecordsUpdated(rows))
+            | Ok(QueryEvent::RecordsDeleted(rows)) => {
+                self.rows.fetch_add(*rows, Ordering::SeqCst);
+            }
+            Ok(_) => {}
+            Err(_) => self.failed.store(true, Ordering::SeqCst),
+        }
+        self.sender.send(query_result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlparser::{ast::Statement, parser::Parser};
+
+    fn recognize(sql: &str) -> Option<StatisticsQuery> {
+        match Parser::parse_sql(&PostgreSqlDialect {}, sql).expect("valid sql").pop() {
+            Some(Statement::Query(query)) => StatisticsQuery::recognize(&query),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn literals_are_replaced_with_placeholders() {
+        assert_eq!(
+            normalize("insert into schema_name.table_name values (1, 'one', -2.5);"),
+            "insert into schema_name.table_name values ($1, $2, -$3)"
+        );
+    }
+
+    #[test]
+    fn whitespaces_are_collapsed() {
+        assert_eq!(
+            normalize("select *\n  from\tschema_name.table_name -- all of them\n"),
+            "select * from schema_name.table_name"
+        );
+    }
+
+    #[test]
+    fn parameters_are_kept() {
+        assert_eq!(
+            normalize("update schema_name.table_name set column_1 = $1, column_2 = 2"),
+            "update schema_name.table_name set column_1 = $1, column_2 = $2"
+        );
+    }
+
+    #[test]
+    fn fingerprint_ignores_literals_whitespaces_and_case() {
+        let query = fingerprint("select * from schema_name.table_name where column_1 = 1;");
+        assert_eq!(
+            fingerprint("SELECT *\n  FROM schema_name.table_name -- filtered\n  WHERE column_1 = 'one'"),
+            query
+        );
+        assert_ne!(
+            fingerprint("select * from schema_name.table_name where column_2 = 1"),
+            query
+        );
+        assert_ne!(
+            fingerprint("select * from schema_name.table_name where column_1 = $1"),
+            query
+        );
+        assert_ne!(
+            fingerprint("select * from schema_name.\"Table_Name\" where column_1 = 1"),
+            query
+        );
+    }
+
+    #[test]
+    fn fingerprint_is_stable() {
+        assert_eq!(fingerprint("select 1"), 6_311_055_018_836_179_048);
+        assert_eq!(fingerprint(""), 0xcbf2_9ce4_8422_2325_u64 as i64);
+    }
+
+    #[test]
+    fn statements_with_same_fingerprint_are_accumulated() {
+        let statistics = StatementStatistics::default();
+        statistics.record("role_1", "select 1", Duration::from_millis(1), 1);
+        statistics.record("role_1", "SELECT   2;", Duration::from_millis(1), 1);
+
+        let (_, records) = statistics.view(SUPERUSER);
+        assert_eq!(
+            records
+                .into_iter()
+                .map(|record| (record[1].clone(), record[2].clone(), record[3].clone()))
+                .collect::<Vec<_>>(),
+            vec![(
+                Some(fingerprint("select 1").to_string()),
+                Some("select $1".to_owned()),
+                Some("2".to_owned())
+            )]
+        );
+    }
+
+    #[test]
+    fn statistics_view() {
+        assert_eq!(
+            recognize("select * from pg_stat_statements"),
+            Some(StatisticsQuery::Statements)
+        );
+        assert_eq!(
+            recognize("select * from pg_catalog.pg_stat_statements"),
+            Some(StatisticsQuery::Statements)
+        );
+        assert_eq!(recognize("select * from schema_name.pg_stat_statements"), None);
+        assert_eq!(
+            recognize("select * from pg_stat_user_tables"),
+            Some(StatisticsQuery::Tables)
+        );
+    }
+
+    #[test]
+    fn reset_function() {
+        assert_eq!(
+            recognize("select pg_stat_statements_reset()"),
+            Some(StatisticsQuery::Reset)
+        );
+        assert_eq!(recognize("select pg_stat_statements_reset"), None);
+    }
+
+    #[test]
+    fn least_called_statements_are_trimmed() {
+        let statistics = StatementStatistics::default();
+        statistics.record("role_1", "select 1", Duration::from_millis(1), 1);
+        statistics.record("role_1", "select 1", Duration::from_millis(1), 1);
+        statistics.record("role_1", "select * from table_1", Duration::from_millis(1), 1);
+        statistics.record("role_2", "select * from table_2", Duration::from_millis(1), 1);
+        statistics.record("role_2", "select * from table_2", Duration::from_millis(1), 1);
+
+        assert_eq!(statistics.trim(2), 1);
+        assert_eq!(statistics.trim(2), 0);
+        let (_, records) = statistics.view(SUPERUSER);
+        assert_eq!(
+            records
+                .into_iter()
+                .map(|record| record[2].clone())
+                .collect::<Vec<Option<String>>>(),
+            vec![Some("select $1".to_owned()), Some("select * from table_2".to_owned())]
+        );
+    }
+
+    #[test]
+    fn texts_of_other_roles_are_hidden() {
+        let statistics = StatementStatistics::default();
+        statistics.record("role_1", "select 1", Duration::from_millis(2), 1);
+        statistics.record("role_1", "select 2", Duration::from_millis(4), 1);
+        statistics.record("role_2", "select 3", Duration::from_millis(1), 1);
+
+        let (_, records) = statistics.view("role_2");
+        assert_eq!(
+            records,
+            vec![
+                vec![
+                    Some("role_1".to_owned()),
+                    Some(fingerprint("select 1").to_string()),
+                    Some(INSUFFICIENT_PRIVILEGE.to_owned()),
+                    Some("2".to_owned()),
+                    Some("6.000".to_owned()),
+                    Some("3.000".to_owned()),
+                    Some("2".to_owned())
+                ],
+                vec![
+                    Some("role_2".to_owned()),
+                    Some(fingerprint("select 3").to_string()),
+                    Some("select $1".to_owned()),
+                    Some("1".to_owned()),
+                    Some("1.000".to_owned()),
+                    Some("1.000".to_owned()),
+                    Some("1".to_owned())
+                ],
+            ]
+        );
+    }
+}
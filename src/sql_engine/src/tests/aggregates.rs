@@ -0,0 +1,509 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use protocol::sql_types::PostgreSqlType;
+
+const TOTAL: &str = "create function add_to(total bigint, value integer) returns bigint as $$
+    begin
+        return total + value;
+    end
+    $$ language plpgsql;";
+
+const LARGEST: &str = "create function larger(current integer, value integer) returns integer as $$
+    begin
+        if value > current then
+            return value;
+        end if;
+        return current;
+    end
+    $$ language plpgsql;";
+
+fn with_rows(engine: &mut QueryExecutor) {
+    engine
+        .execute("create table schema_name.table_name (category smallint, amount integer);")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name values (2, 10), (1, 5), (2, null), (1, 7), (2, 3);")
+        .expect("no system errors");
+}
+
+fn setup_events() -> Vec<Result<QueryEvent, QueryError>> {
+    vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::FunctionCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::AggregateCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(5)),
+        Ok(QueryEvent::QueryComplete),
+    ]
+}
+
+#[rstest::rstest]
+fn aggregate_all_rows(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine.execute(TOTAL).expect("no system errors");
+    engine
+        .execute("create aggregate total(integer) (sfunc = add_to, stype = bigint, initcond = '0');")
+        .expect("no system errors");
+    with_rows(&mut engine);
+    engine
+        .execute("select total(amount) from schema_name.table_name;")
+        .expect("no system errors");
+
+    let mut expected = setup_events();
+    expected.extend(vec![
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "total",
+            PostgreSqlType::BigInt,
+        )])),
+        Ok(QueryEvent::DataRows(vec![vec![Some("25".to_owned())]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+    collector.assert_content(expected);
+}
+
+#[rstest::rstest]
+fn aggregate_groups(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine.execute(LARGEST).expect("no system errors");
+    engine
+        .execute("create aggregate largest(integer) (sfunc = larger, stype = integer);")
+        .expect("no system errors");
+    with_rows(&mut engine);
+    engine
+        .execute("select category, largest(amount) from schema_name.table_name group by category;")
+        .expect("no system errors");
+
+    let mut expected = setup_events();
+    expected.extend(vec![
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("category", PostgreSqlType::SmallInt).with_source(16384, 1),
+            ColumnDescription::new("largest", PostgreSqlType::Integer),
+        ])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("2".to_owned()), Some("10".to_owned())],
+            vec![Some("1".to_owned()), Some("7".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(2)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+    collector.assert_content(expected);
+}
+
+#[rstest::rstest]
+fn final_function(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine.execute(TOTAL).expect("no system errors");
+    engine
+        .execute(
+            "create function describe(total bigint) returns varchar(20) as $$
+            begin
+                return 'total is ' || total;
+            end
+            $$ language plpgsql;",
+        )
+        .expect("no system errors");
+    engine
+        .execute(
+            "create aggregate summary(integer) (sfunc = add_to, stype = bigint, finalfunc = describe, initcond = '0');",
+        )
+        .expect("no system errors");
+    engine
+        .execute("create table schema_name.table_name (amount integer);")
+        .expect("no system errors");
+    engine
+        .execute("select summary(amount) from schema_name.table_name;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::FunctionCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::FunctionCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::AggregateCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "summary",
+            PostgreSqlType::VarChar,
+        )])),
+        Ok(QueryEvent::DataRows(vec![vec![Some("total is 0".to_owned())]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn column_has_to_be_grouped(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine.execute(LARGEST).expect("no system errors");
+    engine
+        .execute("create aggregate largest(integer) (sfunc = larger, stype = integer);")
+        .expect("no system errors");
+    with_rows(&mut engine);
+    engine
+        .execute("select category, largest(amount) from schema_name.table_name;")
+        .expect("no system errors");
+
+    let mut expected = setup_events();
+    expected.extend(vec![
+        Err(QueryError::grouping_error("category".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+    collector.assert_content(expected);
+}
+
+#[rstest::rstest]
+fn transition_function_has_to_exist(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    engine
+        .execute("create aggregate total(integer) (sfunc = add_to, stype = bigint);")
+        .expect("no system errors");
+    engine.execute(LARGEST).expect("no system errors");
+    engine
+        .execute("create aggregate total(integer) (sfunc = larger, stype = bigint);")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Err(QueryError::function_does_not_exist(
+            "add_to(bigint, integer)".to_owned(),
+        )),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::FunctionCreated),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::function_does_not_exist(
+            "larger(bigint, integer)".to_owned(),
+        )),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn replace_aggregate(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    engine.execute(LARGEST).expect("no system errors");
+    engine
+        .execute("create aggregate largest(integer) (sfunc = larger, stype = integer);")
+        .expect("no system errors");
+    engine
+        .execute("create aggregate largest(integer) (sfunc = larger, stype = integer);")
+        .expect("no system errors");
+    engine
+        .execute("create or replace aggregate largest(integer) (sfunc = larger, stype = integer, initcond = '0');")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::FunctionCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::AggregateCreated),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::function_already_exists("largest".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::AggregateCreated),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn builtin_aggregates_of_groups(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    with_rows(&mut engine);
+    engine
+        .execute(
+            "select category, count(*), count(amount), sum(amount), avg(amount), min(amount), max(amount) \
+             from schema_name.table_name group by category;",
+        )
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(5)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("category", PostgreSqlType::SmallInt).with_source(16384, 1),
+            ColumnDescription::new("count", PostgreSqlType::BigInt),
+            ColumnDescription::new("count", PostgreSqlType::BigInt),
+            ColumnDescription::new("sum", PostgreSqlType::BigInt),
+            ColumnDescription::new("avg", PostgreSqlType::Decimal),
+            ColumnDescription::new("min", PostgreSqlType::Integer),
+            ColumnDescription::new("max", PostgreSqlType::Integer),
+        ])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![
+                Some("2".to_owned()),
+                Some("3".to_owned()),
+                Some("2".to_owned()),
+                Some("13".to_owned()),
+                Some("6.5000000000000000".to_owned()),
+                Some("3".to_owned()),
+                Some("10".to_owned()),
+            ],
+            vec![
+                Some("1".to_owned()),
+                Some("2".to_owned()),
+                Some("2".to_owned()),
+                Some("12".to_owned()),
+                Some("6.0000000000000000".to_owned()),
+                Some("5".to_owned()),
+                Some("7".to_owned()),
+            ],
+        ])),
+        Ok(QueryEvent::SelectComplete(2)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn builtin_aggregates_of_empty_table(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (name varchar(10), price bigint);")
+        .expect("no system errors");
+    engine
+        .execute("select count(*), sum(price), max(name) from schema_name.table_name;")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name values ('b', 15), ('a', 20);")
+        .expect("no system errors");
+    engine
+        .execute("select count(*), sum(price), avg(price), min(name) from schema_name.table_name;")
+        .expect("no system errors");
+    engine
+        .execute("select sum(name) from schema_name.table_name;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("count", PostgreSqlType::BigInt),
+            ColumnDescription::new("sum", PostgreSqlType::Decimal),
+            ColumnDescription::new("max", PostgreSqlType::VarChar),
+        ])),
+        Ok(QueryEvent::DataRows(vec![vec![Some("0".to_owned()), None, None]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(2)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("count", PostgreSqlType::BigInt),
+            ColumnDescription::new("sum", PostgreSqlType::Decimal),
+            ColumnDescription::new("avg", PostgreSqlType::Decimal),
+            ColumnDescription::new("min", PostgreSqlType::VarChar),
+        ])),
+        Ok(QueryEvent::DataRows(vec![vec![
+            Some("2".to_owned()),
+            Some("35".to_owned()),
+            Some("17.5000000000000000".to_owned()),
+            Some("a".to_owned()),
+        ]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::function_does_not_exist("sum(varchar)".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn groups_aggregated_by_parallel_workers(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (engine, collector) = sql_engine_with_schema;
+    let mut engine = engine.with_max_parallel_workers(3);
+    engine
+        .execute("create table schema_name.table_name (category smallint, amount integer);")
+        .expect("no system errors");
+    let values = (0..3000)
+        .map(|index| format!("({}, {})", (index + 1) % 3, index))
+        .collect::<Vec<_>>()
+        .join(", ");
+    engine
+        .execute(format!("insert into schema_name.table_name values {};", values).as_str())
+        .expect("no system errors");
+    engine
+        .execute("select category, count(*), sum(amount) from schema_name.table_name group by category;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(3000)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("category", PostgreSqlType::SmallInt).with_source(16384, 1),
+            ColumnDescription::new("count", PostgreSqlType::BigInt),
+            ColumnDescription::new("sum", PostgreSqlType::BigInt),
+        ])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![
+                Some("1".to_owned()),
+                Some("1000".to_owned()),
+                Some("1498500".to_owned()),
+            ],
+            vec![
+                Some("2".to_owned()),
+                Some("1000".to_owned()),
+                Some("1499500".to_owned()),
+            ],
+            vec![
+                Some("0".to_owned()),
+                Some("1000".to_owned()),
+                Some("1500500".to_owned()),
+            ],
+        ])),
+        Ok(QueryEvent::SelectComplete(3)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn having_filters_groups(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    with_rows(&mut engine);
+    engine
+        .execute(
+            "select category, sum(amount) from schema_name.table_name \
+             group by category having count(*) > 2 and max(amount) >= 10;",
+        )
+        .expect("no system errors");
+    engine
+        .execute("select category, count(*) from schema_name.table_name group by category having category = 1;")
+        .expect("no system errors");
+    engine
+        .execute("select count(*) from schema_name.table_name having sum(amount) > 100;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(5)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("category", PostgreSqlType::SmallInt).with_source(16384, 1),
+            ColumnDescription::new("sum", PostgreSqlType::BigInt),
+        ])),
+        Ok(QueryEvent::DataRows(vec![vec![
+            Some("2".to_owned()),
+            Some("13".to_owned()),
+        ]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("category", PostgreSqlType::SmallInt).with_source(16384, 1),
+            ColumnDescription::new("count", PostgreSqlType::BigInt),
+        ])),
+        Ok(QueryEvent::DataRows(vec![vec![
+            Some("1".to_owned()),
+            Some("2".to_owned()),
+        ]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "count",
+            PostgreSqlType::BigInt,
+        )])),
+        Ok(QueryEvent::DataRows(vec![])),
+        Ok(QueryEvent::SelectComplete(0)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn having_refers_to_not_grouped_column(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    with_rows(&mut engine);
+    engine
+        .execute("select category from schema_name.table_name group by category having amount > 3;")
+        .expect("no system errors");
+    engine
+        .execute("select category from schema_name.table_name group by category having sum(amount);")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(5)),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::grouping_error("amount".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::datatype_mismatch(
+            "argument of HAVING must be type boolean, not type bigint".to_owned(),
+        )),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn groups_over_work_mem_are_spilled(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (engine, collector) = sql_engine_with_schema;
+    let mut engine = engine.with_work_mem(64).with_max_parallel_workers(1);
+    engine
+        .execute("create table schema_name.table_name (category integer, amount integer);")
+        .expect("no system errors");
+    let values = (0..6000)
+        .map(|index| format!("({}, {})", index % 3000, index))
+        .collect::<Vec<_>>()
+        .join(", ");
+    engine
+        .execute(format!("insert into schema_name.table_name values {};", values).as_str())
+        .expect("no system errors");
+    engine
+        .execute("select category, count(*), sum(amount) from schema_name.table_name group by category;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(6000)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("category", PostgreSqlType::Integer).with_source(16384, 1),
+            ColumnDescription::new("count", PostgreSqlType::BigInt),
+            ColumnDescription::new("sum", PostgreSqlType::BigInt),
+        ])),
+        Ok(QueryEvent::DataRows(
+            (0..3000)
+                .map(|category| {
+                    vec![
+                        Some(category.to_string()),
+                        Some("2".to_owned()),
+                        Some((2 * category + 3000).to_string()),
+                    ]
+                })
+                .collect(),
+        )),
+        Ok(QueryEvent::SelectComplete(3000)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
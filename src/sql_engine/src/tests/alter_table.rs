@@ -0,0 +1,211 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use super::*;
+use protocol::sql_types::PostgreSqlType;
+
+#[rstest::fixture]
+fn with_table(sql_engine_with_schema: (QueryExecutor, ResultCollector)) -> (QueryExecutor, ResultCollector) {
+    let (mut engine, _collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint, column_2 varchar(10));")
+        .expect("no system errors");
+    let collector = sender();
+    let storage = engine.storage.clone();
+    (QueryExecutor::new(storage, collector.clone(), SUPERUSER), collector)
+}
+
+fn description() -> Vec<ColumnDescription> {
+    vec![
+        ColumnDescription::new("column_1", PostgreSqlType::SmallInt).with_source(16384, 1),
+        ColumnDescription::new("column_2", PostgreSqlType::VarChar).with_source(16384, 2),
+    ]
+}
+
+#[rstest::rstest]
+fn set_and_drop_default(with_table: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = with_table;
+    engine
+        .execute("alter table schema_name.table_name alter column column_2 set default 'none';")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name (column_1) values (1);")
+        .expect("no system errors");
+    engine
+        .execute("alter table schema_name.table_name alter column column_2 drop default;")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name (column_1) values (2);")
+        .expect("no system errors");
+    engine
+        .execute("select * from schema_name.table_name;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::TableAltered),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(1)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableAltered),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(1)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(description())),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("1".to_owned()), Some("none".to_owned())],
+            vec![Some("2".to_owned()), None],
+        ])),
+        Ok(QueryEvent::SelectComplete(2)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn default_of_wrong_type(with_table: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = with_table;
+    engine
+        .execute("alter table schema_name.table_name alter column column_1 set default 'abc';")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Err(QueryError::type_mismatch(
+            "abc",
+            PostgreSqlType::SmallInt,
+            "column_1".to_owned(),
+            1,
+        )),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn set_not_null_on_column_with_nulls(with_table: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = with_table;
+    engine
+        .execute("insert into schema_name.table_name values (1, null);")
+        .expect("no system errors");
+    engine
+        .execute("alter table schema_name.table_name alter column column_2 set not null;")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name values (2, null);")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::RecordsInserted(1)),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::column_contains_nulls(
+            "column_2".to_owned(),
+            "schema_name.table_name".to_owned(),
+        )),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(1)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn not_null_column_rejects_nulls(with_table: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = with_table;
+    engine
+        .execute("insert into schema_name.table_name values (1, 'a');")
+        .expect("no system errors");
+    engine
+        .execute("alter table schema_name.table_name alter column column_2 set not null;")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name values (2, null);")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name (column_1) values (2);")
+        .expect("no system errors");
+    engine
+        .execute("update schema_name.table_name set column_2 = null;")
+        .expect("no system errors");
+    engine
+        .execute("alter table schema_name.table_name alter column column_2 drop not null;")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name values (2, null);")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::RecordsInserted(1)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableAltered),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::not_null_violation("column_2".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::not_null_violation("column_2".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::not_null_violation("column_2".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableAltered),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(1)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn alter_non_existent_column(with_table: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = with_table;
+    engine
+        .execute("alter table schema_name.table_name alter column column_3 drop default;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Err(QueryError::column_does_not_exist(vec!["column_3".to_owned()])),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn create_table_with_defaults_and_not_null(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint not null, column_2 smallint default -1);")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name (column_1) values (1);")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name (column_2) values (2);")
+        .expect("no system errors");
+    engine
+        .execute("select * from schema_name.table_name;")
+        .expect("no system errors");
+
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(1)),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::not_null_violation("column_1".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("column_1", PostgreSqlType::SmallInt)
+                .with_source(16384, 1)
+                .with_nullable(false),
+            ColumnDescription::new("column_2", PostgreSqlType::SmallInt).with_source(16384, 2),
+        ])),
+        Ok(QueryEvent::DataRows(vec![vec![
+            Some("1".to_owned()),
+            Some("-1".to_owned()),
+        ]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
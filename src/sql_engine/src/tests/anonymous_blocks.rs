@@ -0,0 +1,170 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use protocol::sql_types::PostgreSqlType;
+
+const MIGRATION: &str = "do $$
+    begin
+        create schema app;
+    exception
+        when duplicate_schema then
+            raise notice 'schema app already exists';
+    end
+    $$;";
+
+#[rstest::rstest]
+fn conditional_migration_step(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    engine.execute(MIGRATION).expect("no system errors");
+    engine.execute(MIGRATION).expect("no system errors");
+    engine.execute("create schema app;").expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::BlockExecuted),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::BlockExecuted),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::schema_already_exists("app".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn dynamic_statements(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute(
+            "do language plpgsql $$
+            declare
+                step integer := 1;
+            begin
+                create table schema_name.steps (id integer, name varchar(10));
+                while step <= 3 loop
+                    execute 'insert into schema_name.steps values (' || step || ', ''step ' || step || ''')';
+                    step := step + 1;
+                end loop;
+            end $$;",
+        )
+        .expect("no system errors");
+    engine
+        .execute("select * from schema_name.steps;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::BlockExecuted),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("id", PostgreSqlType::Integer).with_source(16384, 1),
+            ColumnDescription::new("name", PostgreSqlType::VarChar).with_source(16384, 2),
+        ])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("1".to_owned()), Some("step 1".to_owned())],
+            vec![Some("2".to_owned()), Some("step 2".to_owned())],
+            vec![Some("3".to_owned()), Some("step 3".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(3)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn errors_abort_block_without_rollback(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute(
+            "do $$
+            begin
+                create table schema_name.table_name (id smallint);
+                insert into schema_name.table_name values (1);
+                create table schema_name.table_name (id smallint);
+                insert into schema_name.table_name values (2);
+            end $$;",
+        )
+        .expect("no system errors");
+    engine
+        .execute(
+            "do $$
+            begin
+                insert into schema_name.table_name values (100000);
+            exception
+                when others then
+                    raise exception 'migration failed: % %', sqlstate, sqlerrm;
+            end $$;",
+        )
+        .expect("no system errors");
+    engine
+        .execute("select * from schema_name.table_name;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::table_already_exists("schema_name.table_name".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::raise_exception(format!(
+            "migration failed: 22003 {}",
+            QueryError::out_of_range(PostgreSqlType::SmallInt, "id".to_owned(), 1)
+        ))),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "id",
+            PostgreSqlType::SmallInt,
+        )
+        .with_source(16384, 1)])),
+        Ok(QueryEvent::DataRows(vec![vec![Some("1".to_owned())]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn statements_are_executed_with_privileges_of_role(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (engine, collector) = sql_engine_with_schema;
+    let mut other = QueryExecutor::new(engine.storage.clone(), collector.clone(), "role_1");
+    other
+        .execute("create table schema_name.table_name (id smallint);")
+        .expect("no system errors");
+    other
+        .execute("do $$ begin create table schema_name.table_name (id smallint); end $$;")
+        .expect("no system errors");
+
+    let results = collector.0.lock().expect("locked").clone();
+    assert!(results[2].is_err());
+    assert_eq!(results[2..4], results[4..6]);
+}
+
+#[rstest::rstest]
+fn functions_can_not_execute_statements(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    engine
+        .execute("create function make_schema() returns boolean as $$ begin create schema app; return true; end $$;")
+        .expect("no system errors");
+    engine.execute("select make_schema();").expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::FunctionCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "make_schema",
+            PostgreSqlType::Bool,
+        )])),
+        Err(QueryError::feature_not_supported(
+            "SQL statements in functions".to_owned(),
+        )),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
@@ -0,0 +1,159 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use crate::audit::{AuditPolicy, AuditRecord, AuditSink, Auditor, StatementClass};
+
+type Entry = (u64, String, StatementClass, Vec<String>, String);
+
+#[derive(Default)]
+struct Records(Mutex<Vec<Entry>>);
+
+impl AuditSink for Arc<Records> {
+    fn write(&self, record: &AuditRecord) -> io::Result<()> {
+        self.0.lock().expect("locked").push((
+            record.session_id,
+            record.role.to_owned(),
+            record.class,
+            record.objects.to_vec(),
+            record.statement.to_owned(),
+        ));
+        Ok(())
+    }
+}
+
+impl Records {
+    fn assert_content(&self, expected: Vec<(&str, StatementClass, Vec<&str>, &str)>) {
+        let actual = self.0.lock().expect("locked");
+        let expected = expected
+            .into_iter()
+            .map(|(role, class, objects, statement)| {
+                (
+                    1,
+                    role.to_owned(),
+                    class,
+                    objects.into_iter().map(ToOwned::to_owned).collect(),
+                    statement.to_owned(),
+                )
+            })
+            .collect::<Vec<Entry>>();
+        assert_eq!(actual.deref(), &expected);
+    }
+}
+
+fn audited_engine(policy: AuditPolicy, role: &str) -> (QueryExecutor, Arc<Records>) {
+    let records = Arc::new(Records::default());
+    let auditor = Arc::new(Auditor::new(policy, Box::new(records.clone())));
+    (
+        QueryExecutor::new(in_memory_catalog_manager(), sender(), role)
+            .with_session_id(1)
+            .with_audit(auditor),
+        records,
+    )
+}
+
+#[rstest::rstest]
+fn statements_are_recorded_with_affected_objects() {
+    let (mut engine, records) = audited_engine(AuditPolicy::parse("all").expect("valid policy"), SUPERUSER);
+    for sql in &[
+        "create schema schema_name;",
+        "create table schema_name.table_name (column_1 smallint);",
+        "insert into schema_name.table_name values (1);",
+        "select * from schema_name.table_name;",
+        "grant select on table schema_name.table_name to role_1;",
+    ] {
+        engine.execute(sql).expect("no system errors");
+    }
+
+    records.assert_content(vec![
+        (
+            SUPERUSER,
+            StatementClass::Ddl,
+            vec!["schema_name"],
+            "create schema schema_name;",
+        ),
+        (
+            SUPERUSER,
+            StatementClass::Ddl,
+            vec!["schema_name.table_name"],
+            "create table schema_name.table_name (column_1 smallint);",
+        ),
+        (
+            SUPERUSER,
+            StatementClass::Write,
+            vec!["schema_name.table_name"],
+            "insert into schema_name.table_name values (1);",
+        ),
+        (
+            SUPERUSER,
+            StatementClass::Read,
+            vec!["schema_name.table_name"],
+            "select * from schema_name.table_name;",
+        ),
+        (
+            SUPERUSER,
+            StatementClass::Role,
+            vec!["schema_name.table_name"],
+            "grant select on table schema_name.table_name to role_1;",
+        ),
+    ]);
+}
+
+#[rstest::rstest]
+fn only_configured_classes_are_recorded() {
+    let policy = AuditPolicy::parse("ddl")
+        .and_then(|policy| policy.with_role("role_1", "none"))
+        .expect("valid policy");
+    let (mut superuser, records) = audited_engine(policy.clone(), SUPERUSER);
+    superuser
+        .execute("create schema schema_name;")
+        .expect("no system errors");
+    superuser
+        .execute("select * from schema_name.table_name;")
+        .expect("no system errors");
+
+    let (mut role, role_records) = audited_engine(policy, "role_1");
+    role.execute("create schema schema_name;").expect("no system errors");
+
+    records.assert_content(vec![(
+        SUPERUSER,
+        StatementClass::Ddl,
+        vec!["schema_name"],
+        "create schema schema_name;",
+    )]);
+    role_records.assert_content(vec![]);
+}
+
+#[rstest::rstest]
+fn denied_statements_are_not_recorded() {
+    let (mut engine, records) = audited_engine(AuditPolicy::parse("all").expect("valid policy"), "role_1");
+    engine.execute("drop schema schema_name;").expect("no system errors");
+
+    records.assert_content(vec![]);
+}
+
+#[rstest::rstest]
+fn password_is_not_recorded() {
+    let (mut engine, records) = audited_engine(AuditPolicy::parse("role").expect("valid policy"), SUPERUSER);
+    engine
+        .execute("alter role role_1 password 'secret';")
+        .expect("no system errors");
+
+    records.assert_content(vec![(
+        SUPERUSER,
+        StatementClass::Role,
+        vec!["role_1"],
+        "<statement with password is not recorded>",
+    )]);
+}
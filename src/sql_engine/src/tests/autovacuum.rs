@@ -0,0 +1,127 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use crate::autovacuum::{self, AutovacuumSettings, Maintenance};
+
+fn settings() -> AutovacuumSettings {
+    AutovacuumSettings {
+        vacuum_threshold: 1,
+        vacuum_scale_factor: 0.0,
+        analyze_threshold: 2,
+        analyze_scale_factor: 0.0,
+    }
+}
+
+#[test]
+fn tables_over_thresholds_are_maintained() {
+    let storage = in_memory_catalog_manager();
+    let collector = sender();
+    let mut engine = QueryExecutor::new(storage.clone(), collector.clone(), SUPERUSER);
+    engine.execute("create schema schema_name;").expect("no system errors");
+    engine
+        .execute("create table schema_name.busy (column_1 smallint);")
+        .expect("no system errors");
+    engine
+        .execute("create table schema_name.quiet (column_1 smallint);")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.busy values (1), (2), (3);")
+        .expect("no system errors");
+    engine
+        .execute("update schema_name.busy set column_1 = 4;")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.quiet values (1);")
+        .expect("no system errors");
+
+    assert_eq!(
+        autovacuum::run(&storage, &settings()),
+        Ok(vec![
+            ("schema_name".to_owned(), "busy".to_owned(), Maintenance::Vacuum),
+            ("schema_name".to_owned(), "busy".to_owned(), Maintenance::Analyze),
+        ])
+    );
+    let busy = storage
+        .table_statistics("schema_name", "busy")
+        .expect("table statistics");
+    assert_eq!(
+        (
+            busy.n_live_tup,
+            busy.n_dead_tup,
+            busy.n_mod_since_analyze,
+            busy.autovacuum_count,
+            busy.autoanalyze_count
+        ),
+        (3, 0, 0, 1, 1)
+    );
+    assert!(busy.last_autovacuum.is_some() && busy.last_autoanalyze.is_some());
+    assert_eq!(autovacuum::run(&storage, &settings()), Ok(vec![]));
+}
+
+#[test]
+fn storage_parameters_tune_autovacuum() {
+    let storage = in_memory_catalog_manager();
+    let collector = sender();
+    let mut engine = QueryExecutor::new(storage.clone(), collector.clone(), SUPERUSER);
+    engine.execute("create schema schema_name;").expect("no system errors");
+    engine
+        .execute("create table schema_name.disabled (column_1 smallint) with (autovacuum_enabled = false);")
+        .expect("no system errors");
+    engine
+        .execute(
+            "create table schema_name.eager (column_1 smallint) \
+             with (autovacuum_analyze_threshold = 0, autovacuum_vacuum_threshold = 100);",
+        )
+        .expect("no system errors");
+    engine
+        .execute("create table schema_name.invalid (column_1 smallint) with (fillfactor = 70);")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.disabled values (1), (2), (3);")
+        .expect("no system errors");
+    engine
+        .execute("delete from schema_name.disabled;")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.eager values (1);")
+        .expect("no system errors");
+
+    assert_eq!(
+        autovacuum::run(&storage, &settings()),
+        Ok(vec![(
+            "schema_name".to_owned(),
+            "eager".to_owned(),
+            Maintenance::Analyze
+        )])
+    );
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::invalid_parameter_value(
+            "unrecognized parameter \"fillfactor\"".to_owned(),
+        )),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(3)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsDeleted(3)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(1)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
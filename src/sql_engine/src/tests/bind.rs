@@ -16,13 +16,7 @@ use super::*;
 
 #[rstest::rstest]
 fn bind_insert_raw_statement(sender: ResultCollector) {
-    let mut statement = Parser::parse_sql(
-        &PreparedStatementDialect {},
-        "insert into schema_name.table_name values ($1, $2)",
-    )
-    .unwrap()
-    .pop()
-    .unwrap();
+    let mut statement = parse_prepared("insert into schema_name.table_name values ($1, $2)").unwrap();
 
     ParamBinder::new(sender)
         .bind(
@@ -39,13 +33,7 @@ fn bind_insert_raw_statement(sender: ResultCollector) {
 
 #[rstest::rstest]
 fn bind_update_raw_statement(sender: ResultCollector) {
-    let mut statement = Parser::parse_sql(
-        &PreparedStatementDialect {},
-        "update schema_name.table_name set column_1 = $1, column_2 = $2",
-    )
-    .unwrap()
-    .pop()
-    .unwrap();
+    let mut statement = parse_prepared("update schema_name.table_name set column_1 = $1, column_2 = $2").unwrap();
 
     ParamBinder::new(sender)
         .bind(
@@ -59,3 +47,122 @@ fn bind_update_raw_statement(sender: ResultCollector) {
         "UPDATE schema_name.table_name SET column_1 = 1, column_2 = 'abc'"
     );
 }
+
+#[rstest::rstest]
+fn bind_update_with_where_clause(sender: ResultCollector) {
+    let mut statement = parse_prepared(
+        "update schema_name.table_name set column_1 = $1 where column_2 = $2 and column_1 between $3 and 10",
+    )
+    .unwrap();
+
+    ParamBinder::new(sender)
+        .bind(
+            &mut statement,
+            &[
+                PostgreSqlValue::Int16(1),
+                PostgreSqlValue::String("abc".into()),
+                PostgreSqlValue::Int32(5),
+            ],
+        )
+        .unwrap();
+
+    assert_eq!(
+        statement.to_string(),
+        "UPDATE schema_name.table_name SET column_1 = 1 WHERE column_2 = 'abc' AND column_1 BETWEEN 5 AND 10"
+    );
+}
+
+#[rstest::rstest]
+fn bind_delete_with_where_clause(sender: ResultCollector) {
+    let mut statement =
+        parse_prepared("delete from schema_name.table_name where column_1 in ($1, $2) or column_2 is null").unwrap();
+
+    ParamBinder::new(sender)
+        .bind(&mut statement, &[PostgreSqlValue::Int16(1), PostgreSqlValue::Int16(2)])
+        .unwrap();
+
+    assert_eq!(
+        statement.to_string(),
+        "DELETE FROM schema_name.table_name WHERE column_1 IN (1, 2) OR column_2 IS NULL"
+    );
+}
+
+#[rstest::rstest]
+fn bind_select_with_nested_expressions(sender: ResultCollector) {
+    let mut statement = parse_prepared(
+        "select column_1, $1 from schema_name.table_name \
+         where (column_1 > $2 + 1) and column_2 in (select column_2 from schema_name.other where column_3 = $3)",
+    )
+    .unwrap();
+
+    ParamBinder::new(sender)
+        .bind(
+            &mut statement,
+            &[
+                PostgreSqlValue::String("x".into()),
+                PostgreSqlValue::Int64(2),
+                PostgreSqlValue::True,
+            ],
+        )
+        .unwrap();
+
+    assert_eq!(
+        statement.to_string(),
+        "SELECT column_1, 'x' FROM schema_name.table_name \
+         WHERE (column_1 > 2 + 1) AND column_2 IN (SELECT column_2 FROM schema_name.other WHERE column_3 = true)"
+    );
+}
+
+#[rstest::rstest]
+fn bind_not_supported_statement(sender: ResultCollector) {
+    let mut statement = parse_prepared("create schema schema_name").unwrap();
+
+    assert_eq!(ParamBinder::new(sender.clone()).bind(&mut statement, &[]), Err(()));
+    sender.assert_content(vec![Err(QueryError::feature_not_supported(
+        "Bind parameters is not supported on SQL `CREATE SCHEMA schema_name`".to_owned(),
+    ))]);
+}
+
+#[rstest::rstest]
+fn bind_params_with_casts(sender: ResultCollector) {
+    let mut statement =
+        parse_prepared("insert into schema_name.table_name values ($1::smallint, $2::varchar(10))").unwrap();
+
+    ParamBinder::new(sender)
+        .bind(&mut statement, &[PostgreSqlValue::Int16(1), PostgreSqlValue::Null])
+        .unwrap();
+
+    assert_eq!(
+        statement.to_string(),
+        "INSERT INTO schema_name.table_name VALUES (1, NULL)"
+    );
+}
+
+#[rstest::rstest]
+fn bind_repeated_params_out_of_order(sender: ResultCollector) {
+    let mut statement = parse_prepared("insert into schema_name.table_name values ($2, $1, $2)").unwrap();
+
+    ParamBinder::new(sender)
+        .bind(&mut statement, &[PostgreSqlValue::Int16(1), PostgreSqlValue::Int16(2)])
+        .unwrap();
+
+    assert_eq!(
+        statement.to_string(),
+        "INSERT INTO schema_name.table_name VALUES (2, 1, 2)"
+    );
+}
+
+#[rstest::rstest]
+fn bind_params_with_two_digit_numbers(sender: ResultCollector) {
+    let mut statement =
+        parse_prepared("select * from schema_name.table_name where column_1=$11 and column_2 = $1").unwrap();
+    let mut params = (1..=11).map(PostgreSqlValue::Int32).collect::<Vec<_>>();
+    params[0] = PostgreSqlValue::String("one".into());
+
+    ParamBinder::new(sender).bind(&mut statement, &params).unwrap();
+
+    assert_eq!(
+        statement.to_string(),
+        "SELECT * FROM schema_name.table_name WHERE column_1 = 11 AND column_2 = 'one'"
+    );
+}
@@ -25,7 +25,7 @@ fn bind_insert_statement_to_portal(sql_engine_with_schema: (QueryExecutor, Resul
         .parse_prepared_statement(
             "statement_name",
             "insert into schema_name.table_name values ($1, $2);",
-            &[PostgreSqlType::SmallInt, PostgreSqlType::SmallInt],
+            &[Some(PostgreSqlType::SmallInt), Some(PostgreSqlType::SmallInt)],
         )
         .expect("no system errors");
     engine
@@ -61,7 +61,7 @@ fn bind_update_statement_to_portal(sql_engine_with_schema: (QueryExecutor, Resul
         .parse_prepared_statement(
             "statement_name",
             "update schema_name.table_name set column_1 = $1, column_2 = $2;",
-            &[PostgreSqlType::SmallInt, PostgreSqlType::SmallInt],
+            &[Some(PostgreSqlType::SmallInt), Some(PostgreSqlType::SmallInt)],
         )
         .expect("no system errors");
     engine
@@ -85,3 +85,102 @@ fn bind_update_statement_to_portal(sql_engine_with_schema: (QueryExecutor, Resul
         Ok(QueryEvent::BindComplete),
     ]);
 }
+
+#[rstest::rstest]
+fn bind_parameter_that_can_not_be_decoded(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint, column_2 smallint);")
+        .expect("no system errors");
+    engine
+        .parse_prepared_statement(
+            "statement_name",
+            "insert into schema_name.table_name values ($1, $2);",
+            &[Some(PostgreSqlType::SmallInt), Some(PostgreSqlType::SmallInt)],
+        )
+        .expect("no system errors");
+    engine
+        .bind_prepared_statement_to_portal(
+            "portal_name",
+            "statement_name",
+            &[PostgreSqlFormat::Text, PostgreSqlFormat::Binary],
+            &[Some(b"1".to_vec()), Some(vec![0, 0, 0, 1])],
+            &[],
+        )
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::ParseComplete),
+        Err(QueryError::invalid_parameter_value("invalid buffer size".to_owned())
+            .with_detail("parameter $2 of type smallint was sent in binary format with 4 bytes".to_owned())),
+    ]);
+}
+
+#[rstest::rstest]
+fn bind_statement_with_changed_formats(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint, column_2 smallint);")
+        .expect("no system errors");
+    engine
+        .parse_prepared_statement(
+            "statement_name",
+            "insert into schema_name.table_name values ($1, $2);",
+            &[Some(PostgreSqlType::SmallInt), Some(PostgreSqlType::SmallInt)],
+        )
+        .expect("no system errors");
+    engine
+        .bind_prepared_statement_to_portal(
+            "portal_name",
+            "statement_name",
+            &[PostgreSqlFormat::Text],
+            &[Some(b"1".to_vec()), Some(b"2".to_vec())],
+            &[],
+        )
+        .expect("no system errors");
+    engine
+        .bind_prepared_statement_to_portal(
+            "portal_name",
+            "statement_name",
+            &[PostgreSqlFormat::Text],
+            &[Some(b"3".to_vec()), Some(b"4".to_vec())],
+            &[],
+        )
+        .expect("no system errors");
+    engine
+        .bind_prepared_statement_to_portal(
+            "portal_name",
+            "statement_name",
+            &[PostgreSqlFormat::Text, PostgreSqlFormat::Text, PostgreSqlFormat::Text],
+            &[Some(b"5".to_vec()), Some(b"6".to_vec())],
+            &[],
+        )
+        .expect("no system errors");
+    engine
+        .bind_prepared_statement_to_portal(
+            "portal_name",
+            "statement_name",
+            &[PostgreSqlFormat::Binary],
+            &[Some(vec![0, 7]), Some(vec![0, 8])],
+            &[],
+        )
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::ParseComplete),
+        Ok(QueryEvent::BindComplete),
+        Ok(QueryEvent::BindComplete),
+        Err(QueryError::protocol_violation(
+            "expected 3 field format specifiers, but got 2".to_owned(),
+        )),
+        Ok(QueryEvent::BindComplete),
+    ]);
+}
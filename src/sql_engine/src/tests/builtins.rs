@@ -0,0 +1,144 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use protocol::sql_types::PostgreSqlType;
+
+fn selected(name: &str, pg_type: PostgreSqlType, value: Option<&str>) -> Vec<QueryResult> {
+    vec![
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(name, pg_type)])),
+        Ok(QueryEvent::DataRows(vec![vec![value.map(str::to_owned)]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+    ]
+}
+
+#[rstest::rstest]
+fn sleep(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    engine.execute("select pg_sleep(0.01);").expect("no system errors");
+    engine
+        .execute("select pg_catalog.pg_sleep(null);")
+        .expect("no system errors");
+    engine.execute("select pg_sleep('never');").expect("no system errors");
+    engine.execute("select pg_sleep(-1);").expect("no system errors");
+
+    collector.assert_content(
+        [
+            selected("pg_sleep", PostgreSqlType::Void, Some("")),
+            selected("pg_sleep", PostgreSqlType::Void, None),
+            vec![
+                Err(QueryError::datatype_mismatch(
+                    "invalid input syntax for type double precision: \"never\"".to_owned(),
+                )),
+                Ok(QueryEvent::QueryComplete),
+                Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+                    "pg_sleep",
+                    PostgreSqlType::Void,
+                )])),
+                Err(QueryError::invalid_parameter_value(
+                    "pg_sleep duration must not be negative: -1".to_owned(),
+                )),
+                Ok(QueryEvent::QueryComplete),
+            ],
+        ]
+        .concat(),
+    );
+}
+
+#[rstest::rstest]
+fn sleep_for_each_row(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.delays (seconds smallint);")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.delays values (0), (1), (0);")
+        .expect("no system errors");
+    engine
+        .execute("select seconds, pg_sleep(seconds / 100) from schema_name.delays where seconds = 0;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(3)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("seconds", PostgreSqlType::SmallInt).with_source(16384, 1),
+            ColumnDescription::new("pg_sleep", PostgreSqlType::Void),
+        ])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("0".to_owned()), Some("".to_owned())],
+            vec![Some("0".to_owned()), Some("".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(2)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn transaction_ids_increase(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    engine.execute("select txid_current();").expect("no system errors");
+    engine
+        .execute("select txid_current(), txid_current() + 10;")
+        .expect("no system errors");
+
+    collector.assert_content(
+        [
+            selected("txid_current", PostgreSqlType::BigInt, Some("3")),
+            vec![
+                Ok(QueryEvent::RowDescription(vec![
+                    ColumnDescription::new("txid_current", PostgreSqlType::BigInt),
+                    ColumnDescription::new("?column?", PostgreSqlType::BigInt),
+                ])),
+                Ok(QueryEvent::DataRows(vec![vec![
+                    Some("4".to_owned()),
+                    Some("15".to_owned()),
+                ]])),
+                Ok(QueryEvent::SelectComplete(1)),
+                Ok(QueryEvent::QueryComplete),
+            ],
+        ]
+        .concat(),
+    );
+}
+
+#[rstest::rstest]
+fn server_state(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    engine
+        .execute("select pg_postmaster_start_time();")
+        .expect("no system errors");
+    engine
+        .execute("select pg_is_in_recovery() or pg_is_in_recovery() is null;")
+        .expect("no system errors");
+
+    collector.assert_content(
+        [
+            selected(
+                "pg_postmaster_start_time",
+                PostgreSqlType::TimestampWithTimeZone,
+                Some(&crate::dml::functions::system::timestamp_with_time_zone(
+                    engine.storage.started_at(),
+                )),
+            ),
+            selected("?column?", PostgreSqlType::Bool, Some("f")),
+        ]
+        .concat(),
+    );
+}
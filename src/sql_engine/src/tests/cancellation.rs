@@ -0,0 +1,195 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use crate::cancellation::Interruption;
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::Duration,
+};
+
+#[rstest::rstest]
+fn statement_timeout_interrupts_sleep(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    engine.execute("set statement_timeout = 10;").expect("no system errors");
+    engine.execute("select pg_sleep(10);").expect("no system errors");
+    engine.execute("set statement_timeout = 0;").expect("no system errors");
+    engine.execute("select pg_sleep(0.02);").expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::VariableSet),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "pg_sleep",
+            PostgreSqlType::Void,
+        )])),
+        Err(QueryError::query_canceled("statement timeout")),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::VariableSet),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "pg_sleep",
+            PostgreSqlType::Void,
+        )])),
+        Ok(QueryEvent::DataRows(vec![vec![Some("".to_owned())]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn statement_timeout_interrupts_join(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (engine, collector) = sql_engine_with_schema;
+    let mut engine = engine.with_statement_timeout(Some(Duration::from_millis(10)));
+    engine
+        .execute("create table schema_name.numbers (n integer);")
+        .expect("no system errors");
+    let values = (0..100).map(|n| format!("({})", n)).collect::<Vec<String>>().join(", ");
+    engine
+        .execute(&format!("insert into schema_name.numbers values {};", values))
+        .expect("no system errors");
+    engine
+        .execute(
+            "select count(*) from schema_name.numbers as a \
+             cross join schema_name.numbers as b \
+             cross join schema_name.numbers as c;",
+        )
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(100)),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::query_canceled("statement timeout")),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn statement_timeout_interrupts_loop_of_block(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (engine, collector) = sql_engine;
+    let mut engine = engine.with_statement_timeout(Some(Duration::from_millis(10)));
+    engine
+        .execute("do $$ begin loop null; end loop; end $$;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Err(QueryError::query_canceled("statement timeout")),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn statement_timeout_interrupts_loop_of_function(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (engine, collector) = sql_engine;
+    let mut engine = engine.with_statement_timeout(Some(Duration::from_millis(10)));
+    engine
+        .execute("create function spin() returns integer as $$ begin loop null; end loop; end $$ language plpgsql;")
+        .expect("no system errors");
+    engine.execute("select spin();").expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::FunctionCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "spin",
+            PostgreSqlType::Integer,
+        )])),
+        Err(QueryError::query_canceled("statement timeout")),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn statement_timeout_interrupts_loop_of_event_trigger(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (engine, collector) = sql_engine_with_schema;
+    let mut engine = engine.with_statement_timeout(Some(Duration::from_millis(10)));
+    engine
+        .execute(
+            "create function spin() returns event_trigger as $$ begin loop null; end loop; end $$ language plpgsql;",
+        )
+        .expect("no system errors");
+    engine
+        .execute("create event trigger spinning on ddl_command_start execute function spin();")
+        .expect("no system errors");
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint);")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::FunctionCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::EventTriggerCreated),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::query_canceled("statement timeout")),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn cancel_request_interrupts_running_statement(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    let token = engine.cancellation_token();
+    let done = Arc::new(AtomicBool::default());
+    let canceller = {
+        let done = done.clone();
+        thread::spawn(move || {
+            while !done.load(Ordering::SeqCst) {
+                thread::sleep(Duration::from_millis(5));
+                token.cancel(Interruption::UserRequest);
+            }
+        })
+    };
+    engine.execute("select pg_sleep(10);").expect("no system errors");
+    done.store(true, Ordering::SeqCst);
+    canceller.join().expect("canceller to finish");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "pg_sleep",
+            PostgreSqlType::Void,
+        )])),
+        Err(QueryError::query_canceled("user request")),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn shutdown_interrupts_all_statements(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    engine.cancellation_token().cancel(Interruption::Shutdown);
+    engine.execute("select pg_sleep(10);").expect("no system errors");
+    engine.execute("select pg_sleep(10);").expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "pg_sleep",
+            PostgreSqlType::Void,
+        )])),
+        Err(QueryError::admin_shutdown()),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "pg_sleep",
+            PostgreSqlType::Void,
+        )])),
+        Err(QueryError::admin_shutdown()),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
@@ -0,0 +1,114 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use protocol::sql_types::PostgreSqlType;
+
+#[rstest::fixture]
+fn with_table(sql_engine_with_schema: (QueryExecutor, ResultCollector)) -> (QueryExecutor, ResultCollector) {
+    let (mut engine, _collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint, column_2 varchar(10));")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name values (3, 'c'), (1, 'a'), (2, 'b'), (1, 'z');")
+        .expect("no system errors");
+    let collector = sender();
+    let storage = engine.storage.clone();
+    (QueryExecutor::new(storage, collector.clone(), SUPERUSER), collector)
+}
+
+#[rstest::rstest]
+fn cluster_by_columns(with_table: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = with_table;
+    engine
+        .execute("cluster schema_name.table_name order by column_1, column_2 desc;")
+        .expect("no system errors");
+    engine
+        .execute("select * from schema_name.table_name;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::TableClustered),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("column_1", PostgreSqlType::SmallInt).with_source(16384, 1),
+            ColumnDescription::new("column_2", PostgreSqlType::VarChar).with_source(16384, 2),
+        ])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("1".to_owned()), Some("z".to_owned())],
+            vec![Some("1".to_owned()), Some("a".to_owned())],
+            vec![Some("2".to_owned()), Some("b".to_owned())],
+            vec![Some("3".to_owned()), Some("c".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(4)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn cluster_using_index(with_table: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = with_table;
+    engine
+        .execute("cluster schema_name.table_name using table_name_idx;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Err(QueryError::index_does_not_exist("table_name_idx".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn cluster_by_non_existent_column(with_table: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = with_table;
+    engine
+        .execute("cluster schema_name.table_name order by column_1, column_3;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Err(QueryError::column_does_not_exist(vec!["column_3".to_owned()])),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn cluster_non_existent_table(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("cluster schema_name.table_name order by column_1;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::table_does_not_exist("schema_name.table_name".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn only_owner_clusters_table(with_table: (QueryExecutor, ResultCollector)) {
+    let (engine, _collector) = with_table;
+    let collector = sender();
+    let mut other = QueryExecutor::new(engine.storage.clone(), collector.clone(), "role_1");
+    other
+        .execute("cluster schema_name.table_name order by column_1;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Err(QueryError::must_be_owner("table", "schema_name.table_name".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
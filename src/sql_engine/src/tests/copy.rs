@@ -0,0 +1,229 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use super::*;
+use protocol::sql_types::PostgreSqlType;
+use std::fs;
+use tempfile::TempDir;
+
+#[rstest::fixture]
+fn engine_with_table(sql_engine_with_schema: (QueryExecutor, ResultCollector)) -> (QueryExecutor, ResultCollector) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint, column_2 varchar(10), column_3 boolean);")
+        .expect("no system errors");
+
+    (engine, collector)
+}
+
+fn temp_dir() -> TempDir {
+    tempfile::tempdir().expect("to create temporary folder")
+}
+
+fn file_path(dir: &TempDir, name: &str) -> String {
+    dir.path().join(name).to_str().expect("utf8 path").to_owned()
+}
+
+#[rstest::rstest]
+fn copy_from_file(engine_with_table: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = engine_with_table;
+    let dir = temp_dir();
+    let path = file_path(&dir, "table.csv");
+    fs::write(&path, "column_1,column_2,column_3\n1,\"a,b\",t\n2,,false\n").expect("to write file");
+
+    engine
+        .execute(&format!(
+            "copy schema_name.table_name from '{}' with (format csv, header);",
+            path
+        ))
+        .expect("no system errors");
+    engine
+        .execute("select * from schema_name.table_name;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsCopied(2)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("column_1", PostgreSqlType::SmallInt).with_source(16384, 1),
+            ColumnDescription::new("column_2", PostgreSqlType::VarChar).with_source(16384, 2),
+            ColumnDescription::new("column_3", PostgreSqlType::Bool).with_source(16384, 3),
+        ])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("1".to_owned()), Some("a,b".to_owned()), Some("t".to_owned())],
+            vec![Some("2".to_owned()), None, Some("f".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(2)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn copy_to_file(engine_with_table: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = engine_with_table;
+    let dir = temp_dir();
+    let path = file_path(&dir, "table.csv");
+
+    engine
+        .execute("insert into schema_name.table_name values (1, 'a;b', true), (2, 'c', false);")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name (column_1) values (3);")
+        .expect("no system errors");
+    engine
+        .execute(&format!(
+            "copy schema_name.table_name (column_2, column_1) to '{}' with (format csv, header, delimiter ';', null 'N/A');",
+            path
+        ))
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(2)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(1)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsCopied(3)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+    assert_eq!(
+        fs::read_to_string(&path).expect("to read file"),
+        "column_2;column_1\n\"a;b\";1\nc;2\nN/A;3\n"
+    );
+}
+
+#[rstest::rstest]
+fn copy_from_not_existing_file(engine_with_table: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = engine_with_table;
+    let dir = temp_dir();
+    let path = file_path(&dir, "not_existing.csv");
+
+    engine
+        .execute(&format!(
+            "copy schema_name.table_name from '{}' with (format csv);",
+            path
+        ))
+        .expect("no system errors");
+
+    let results = collector.0.lock().expect("locked");
+    match &results[4] {
+        Err(error) => assert_eq!(
+            error,
+            &QueryError::io_error(format!(
+                "could not open file \"{}\" for reading: {}",
+                path,
+                fs::read(&path).expect_err("file not to exist")
+            ))
+        ),
+        other => panic!("expected io error, got {:?}", other),
+    }
+}
+
+#[rstest::rstest]
+fn copy_from_file_with_missing_column(engine_with_table: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = engine_with_table;
+    let dir = temp_dir();
+    let path = file_path(&dir, "table.csv");
+    fs::write(&path, "1,a,t\n2,b\n").expect("to write file");
+
+    engine
+        .execute(&format!(
+            "copy schema_name.table_name from '{}' with (format csv);",
+            path
+        ))
+        .expect("no system errors");
+    engine
+        .execute("select * from schema_name.table_name;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::bad_copy_file_format(
+            "missing data for column \"column_3\"".to_owned(),
+        )),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("column_1", PostgreSqlType::SmallInt).with_source(16384, 1),
+            ColumnDescription::new("column_2", PostgreSqlType::VarChar).with_source(16384, 2),
+            ColumnDescription::new("column_3", PostgreSqlType::Bool).with_source(16384, 3),
+        ])),
+        Ok(QueryEvent::SelectComplete(0)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn copy_from_file_with_invalid_value(engine_with_table: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = engine_with_table;
+    let dir = temp_dir();
+    let path = file_path(&dir, "table.csv");
+    fs::write(&path, "1,a,t\n100000,b,f\n").expect("to write file");
+
+    engine
+        .execute(&format!(
+            "copy schema_name.table_name from '{}' with (format csv);",
+            path
+        ))
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::out_of_range(
+            PostgreSqlType::SmallInt,
+            "column_1".to_owned(),
+            2,
+        )),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn only_superuser_can_copy_files() {
+    let storage = in_memory_catalog_manager();
+    let collector = sender();
+    let mut engine = QueryExecutor::new(storage, collector.clone(), "other");
+    let dir = temp_dir();
+
+    engine.execute("create schema schema_name;").expect("no system errors");
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint);")
+        .expect("no system errors");
+    engine
+        .execute(&format!(
+            "copy schema_name.table_name to '{}' with (format csv);",
+            file_path(&dir, "table.csv")
+        ))
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::must_be_superuser("COPY to or from a file")),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
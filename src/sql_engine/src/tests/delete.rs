@@ -62,17 +62,73 @@ fn delete_all_records(sql_engine_with_schema: (QueryExecutor, ResultCollector))
         Ok(QueryEvent::QueryComplete),
         Ok(QueryEvent::RecordsInserted(1)),
         Ok(QueryEvent::QueryComplete),
-        Ok(QueryEvent::RecordsSelected((
-            vec![("column_test".to_owned(), PostgreSqlType::SmallInt)],
-            vec![vec!["123".to_owned()], vec!["456".to_owned()]],
-        ))),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "column_test",
+            PostgreSqlType::SmallInt,
+        )
+        .with_source(16384, 1)])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("123".to_owned())],
+            vec![Some("456".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(2)),
         Ok(QueryEvent::QueryComplete),
         Ok(QueryEvent::RecordsDeleted(2)),
         Ok(QueryEvent::QueryComplete),
-        Ok(QueryEvent::RecordsSelected((
-            vec![("column_test".to_owned(), PostgreSqlType::SmallInt)],
-            vec![],
-        ))),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "column_test",
+            PostgreSqlType::SmallInt,
+        )
+        .with_source(16384, 1)])),
+        Ok(QueryEvent::SelectComplete(0)),
         Ok(QueryEvent::QueryComplete),
     ])
 }
+
+#[rstest::rstest]
+fn delete_records_that_match_condition(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (id smallint, name varchar(10));")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name values (1, 'a'), (2, 'b'), (3, null);")
+        .expect("no system errors");
+    engine
+        .execute("delete from schema_name.table_name where id = 1 or table_name.name is null;")
+        .expect("no system errors");
+    engine
+        .execute("delete from schema_name.table_name where id > 10;")
+        .expect("no system errors");
+    engine
+        .execute("select * from schema_name.table_name;")
+        .expect("no system errors");
+    engine
+        .execute("delete from schema_name.table_name where non_existent = 1;")
+        .expect("no system errors");
+
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(3)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsDeleted(2)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsDeleted(0)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("id", PostgreSqlType::SmallInt).with_source(16384, 1),
+            ColumnDescription::new("name", PostgreSqlType::VarChar).with_source(16384, 2),
+        ])),
+        Ok(QueryEvent::DataRows(vec![vec![
+            Some("2".to_owned()),
+            Some("b".to_owned()),
+        ]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::column_does_not_exist(vec!["non_existent".to_owned()])),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
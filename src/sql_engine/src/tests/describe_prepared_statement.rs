@@ -25,7 +25,7 @@ fn describe_select_statement(sql_engine_with_schema: (QueryExecutor, ResultColle
         .parse_prepared_statement(
             "statement_name",
             "select * from schema_name.table_name where column = $1 and column_2 = $2;",
-            &[PostgreSqlType::SmallInt, PostgreSqlType::SmallInt],
+            &[Some(PostgreSqlType::SmallInt), Some(PostgreSqlType::SmallInt)],
         )
         .expect("no system errors");
     engine
@@ -40,8 +40,8 @@ fn describe_select_statement(sql_engine_with_schema: (QueryExecutor, ResultColle
         Ok(QueryEvent::PreparedStatementDescribed(
             vec![PostgreSqlType::SmallInt, PostgreSqlType::SmallInt],
             vec![
-                ("column_1".to_owned(), PostgreSqlType::SmallInt),
-                ("column_2".to_owned(), PostgreSqlType::SmallInt),
+                ColumnDescription::new("column_1", PostgreSqlType::SmallInt).with_source(16384, 1),
+                ColumnDescription::new("column_2", PostgreSqlType::SmallInt).with_source(16384, 2),
             ],
         )),
     ]);
@@ -57,7 +57,7 @@ fn describe_update_statement(sql_engine_with_schema: (QueryExecutor, ResultColle
         .parse_prepared_statement(
             "statement_name",
             "update schema_name.table_name set column_1 = $1 where column_2 = $2;",
-            &[PostgreSqlType::SmallInt, PostgreSqlType::SmallInt],
+            &[Some(PostgreSqlType::SmallInt), Some(PostgreSqlType::SmallInt)],
         )
         .expect("no system errors");
     engine
@@ -90,3 +90,198 @@ fn describe_not_existed_statement(sql_engine_with_schema: (QueryExecutor, Result
         Err(QueryError::prepared_statement_does_not_exist("non_existent".to_owned())),
     ]);
 }
+
+#[rstest::rstest]
+fn describe_statement_with_inferred_param_types(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint, column_2 bigint);")
+        .expect("no system errors");
+    engine
+        .parse_prepared_statement(
+            "statement_name",
+            "select * from schema_name.table_name where column_1 = $1 and $2 > column_2 and column_1 in ($3, $4);",
+            &[None, None, Some(PostgreSqlType::Integer)],
+        )
+        .expect("no system errors");
+    engine
+        .describe_prepared_statement("statement_name")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::ParseComplete),
+        Ok(QueryEvent::PreparedStatementDescribed(
+            vec![
+                PostgreSqlType::SmallInt,
+                PostgreSqlType::BigInt,
+                PostgreSqlType::Integer,
+                PostgreSqlType::SmallInt,
+            ],
+            vec![
+                ColumnDescription::new("column_1", PostgreSqlType::SmallInt).with_source(16384, 1),
+                ColumnDescription::new("column_2", PostgreSqlType::BigInt).with_source(16384, 2),
+            ],
+        )),
+    ]);
+}
+
+#[rstest::rstest]
+fn describe_insert_with_inferred_param_types(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint, column_2 bigint, column_3 varchar(10));")
+        .expect("no system errors");
+    engine
+        .parse_prepared_statement(
+            "statement_name",
+            "insert into schema_name.table_name (column_2, column_1) values ($1, $2), ($3, 1);",
+            &[],
+        )
+        .expect("no system errors");
+    engine
+        .describe_prepared_statement("statement_name")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::ParseComplete),
+        Ok(QueryEvent::PreparedStatementDescribed(
+            vec![PostgreSqlType::BigInt, PostgreSqlType::SmallInt, PostgreSqlType::BigInt],
+            vec![],
+        )),
+    ]);
+}
+
+#[rstest::rstest]
+fn describe_statement_with_params_of_unknown_type(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint);")
+        .expect("no system errors");
+    engine
+        .parse_prepared_statement(
+            "statement_name",
+            "update schema_name.table_name set column_1 = $2 where $1 = $3;",
+            &[],
+        )
+        .expect("no system errors");
+    engine
+        .describe_prepared_statement("statement_name")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::ParseComplete),
+        Ok(QueryEvent::PreparedStatementDescribed(
+            vec![
+                PostgreSqlType::VarChar,
+                PostgreSqlType::SmallInt,
+                PostgreSqlType::VarChar,
+            ],
+            vec![],
+        )),
+    ]);
+}
+
+#[rstest::rstest]
+fn describe_statement_with_param_casts(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint, column_2 varchar(10));")
+        .expect("no system errors");
+    engine
+        .parse_prepared_statement(
+            "statement_name",
+            "insert into schema_name.table_name values ($1::bigint, $2::text);",
+            &[None, Some(PostgreSqlType::Char)],
+        )
+        .expect("no system errors");
+    engine
+        .describe_prepared_statement("statement_name")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::ParseComplete),
+        Ok(QueryEvent::PreparedStatementDescribed(
+            vec![PostgreSqlType::BigInt, PostgreSqlType::Char],
+            vec![],
+        )),
+    ]);
+}
+
+#[rstest::rstest]
+fn describe_params_in_select_list(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint);")
+        .expect("no system errors");
+    for (name, sql) in &[
+        ("only_param", "select $1;"),
+        ("column_and_param", "select column_1, $1 from schema_name.table_name;"),
+        ("casted_param", "select $1::int + 1;"),
+    ] {
+        engine
+            .parse_prepared_statement(name, sql, &[])
+            .expect("no system errors");
+        engine.describe_prepared_statement(name).expect("no system errors");
+    }
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::ParseComplete),
+        Ok(QueryEvent::PreparedStatementDescribed(
+            vec![PostgreSqlType::VarChar],
+            vec![ColumnDescription::new("?column?", PostgreSqlType::VarChar)],
+        )),
+        Ok(QueryEvent::ParseComplete),
+        Ok(QueryEvent::PreparedStatementDescribed(
+            vec![PostgreSqlType::VarChar],
+            vec![
+                ColumnDescription::new("column_1", PostgreSqlType::SmallInt).with_source(16384, 1),
+                ColumnDescription::new("?column?", PostgreSqlType::VarChar),
+            ],
+        )),
+        Ok(QueryEvent::ParseComplete),
+        Ok(QueryEvent::PreparedStatementDescribed(
+            vec![PostgreSqlType::Integer],
+            vec![ColumnDescription::new("?column?", PostgreSqlType::Integer)],
+        )),
+    ]);
+}
+
+#[rstest::rstest]
+fn statement_that_can_not_be_described_is_not_parsed(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .parse_prepared_statement("statement_name", "select $1 from schema_name.non_existent;", &[])
+        .expect("no system errors");
+    engine
+        .describe_prepared_statement("statement_name")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::table_does_not_exist("schema_name.non_existent".to_owned())),
+        Err(QueryError::prepared_statement_does_not_exist(
+            "statement_name".to_owned(),
+        )),
+    ]);
+}
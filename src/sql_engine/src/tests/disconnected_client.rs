@@ -0,0 +1,88 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use kernel::SystemError;
+
+/// Client that has gone away, every message to it fails
+struct Disconnected;
+
+impl Sender for Disconnected {
+    fn flush(&self) -> io::Result<()> {
+        Err(io::Error::from(io::ErrorKind::BrokenPipe))
+    }
+
+    fn send(&self, _query_result: QueryResult) -> io::Result<()> {
+        Err(io::Error::from(io::ErrorKind::BrokenPipe))
+    }
+}
+
+fn connection_lost() -> SystemError {
+    SystemError::connection_lost(io::Error::from(io::ErrorKind::BrokenPipe))
+}
+
+#[rstest::fixture]
+fn disconnected(sql_engine_with_schema: (QueryExecutor, ResultCollector)) -> QueryExecutor {
+    let (mut engine, _collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint);")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name values (1), (2);")
+        .expect("no system errors");
+    QueryExecutor::new(engine.storage.clone(), Arc::new(Disconnected), SUPERUSER)
+}
+
+#[rstest::rstest]
+fn select_aborts_when_client_is_gone(disconnected: QueryExecutor) {
+    let mut engine = disconnected;
+
+    assert_eq!(
+        engine.execute("select column_1 from schema_name.table_name;"),
+        Err(connection_lost())
+    );
+}
+
+#[rstest::rstest]
+fn error_response_to_gone_client(disconnected: QueryExecutor) {
+    let mut engine = disconnected;
+
+    assert_eq!(
+        engine.execute("select column_1 from schema_name.non_existent;"),
+        Err(connection_lost())
+    );
+}
+
+#[rstest::rstest]
+fn flush_to_gone_client(disconnected: QueryExecutor) {
+    let engine = disconnected;
+
+    let error = engine.flush().unwrap_err();
+
+    assert!(error.is_connection_lost());
+}
+
+#[rstest::rstest]
+fn executor_keeps_working_after_client_is_gone(disconnected: QueryExecutor) {
+    let mut engine = disconnected;
+
+    assert_eq!(
+        engine.execute("update schema_name.table_name set column_1 = 3;"),
+        Err(connection_lost())
+    );
+    assert_eq!(
+        engine.execute("delete from schema_name.table_name;"),
+        Err(connection_lost())
+    );
+}
@@ -0,0 +1,101 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use super::*;
+use crate::dump::dump;
+
+fn dump_of(storage: &CatalogManager) -> String {
+    let mut out = vec![];
+    dump(storage, &mut out).expect("no system errors");
+    String::from_utf8(out).expect("utf8")
+}
+
+#[rstest::rstest]
+fn dump_schemas_tables_and_rows() {
+    let storage = in_memory_catalog_manager();
+    let mut engine = QueryExecutor::new(storage.clone(), sender(), SUPERUSER);
+    engine.execute("create schema schema_1;").expect("no system errors");
+    engine.execute("create schema schema_2;").expect("no system errors");
+    engine
+        .execute("create table schema_1.table_1 (column_1 smallint, column_2 varchar(10), column_3 boolean);")
+        .expect("no system errors");
+    engine
+        .execute("create table schema_2.table_2 (column_1 integer);")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_1.table_1 values (1, 'it''s', true);")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_1.table_1 (column_1) values (2);")
+        .expect("no system errors");
+
+    assert_eq!(
+        dump_of(&storage),
+        "--\n\
+         -- Database dump\n\
+         --\n\
+         \n\
+         CREATE SCHEMA schema_1;\n\
+         \n\
+         CREATE SCHEMA schema_2;\n\
+         \n\
+         CREATE TABLE schema_1.table_1 (\n    \
+             column_1 smallint,\n    \
+             column_2 varchar(10),\n    \
+             column_3 boolean\n\
+         );\n\
+         \n\
+         INSERT INTO schema_1.table_1 (column_1, column_2, column_3) VALUES (1, 'it''s', true);\n\
+         INSERT INTO schema_1.table_1 (column_1, column_2, column_3) VALUES (2, NULL, NULL);\n\
+         \n\
+         CREATE TABLE schema_2.table_2 (\n    \
+             column_1 integer\n\
+         );\n\
+         \n\
+         --\n\
+         -- Database dump complete\n\
+         --\n"
+    );
+}
+
+#[rstest::rstest]
+fn dump_is_loaded_back() {
+    let storage = in_memory_catalog_manager();
+    let mut engine = QueryExecutor::new(storage.clone(), sender(), SUPERUSER);
+    engine.execute("create schema schema_name;").expect("no system errors");
+    engine
+        .execute("create table schema_name.table_name (column_1 bigint, column_2 char(5));")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name values (1, 'a'), (2, 'b');")
+        .expect("no system errors");
+    let dumped = dump_of(&storage);
+
+    let restored = in_memory_catalog_manager();
+    let collector = sender();
+    let mut engine = QueryExecutor::new(restored.clone(), collector.clone(), SUPERUSER);
+    for statement in dumped
+        .lines()
+        .filter(|line| !line.starts_with("--"))
+        .collect::<Vec<&str>>()
+        .join("\n")
+        .split(';')
+        .map(str::trim)
+        .filter(|statement| !statement.is_empty())
+    {
+        engine.execute(statement).expect("no system errors");
+    }
+
+    assert!(collector.0.lock().expect("locked").iter().all(Result::is_ok));
+    assert_eq!(dump_of(&restored), dumped);
+}
@@ -0,0 +1,507 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::embedded::{
+    ChangeKind, ColumnDescription, DataType, Database, Outcome, PostgreSqlType, QueryError, RowChange, ScriptError,
+    Value,
+};
+use std::sync::{Arc, Mutex};
+
+fn database() -> Database {
+    Database::in_memory().expect("no system errors")
+}
+
+#[test]
+fn statements_without_rows() {
+    let database = database();
+    let mut connection = database.connect("postgres");
+
+    assert_eq!(
+        connection
+            .execute("create schema schema_name;")
+            .expect("no system errors"),
+        Ok(Outcome::Done)
+    );
+    assert_eq!(
+        connection
+            .execute("create table schema_name.table_name (column_1 smallint);")
+            .expect("no system errors"),
+        Ok(Outcome::Done)
+    );
+    assert_eq!(
+        connection
+            .execute("insert into schema_name.table_name values (1), (2);")
+            .expect("no system errors"),
+        Ok(Outcome::Affected(2))
+    );
+    assert_eq!(
+        connection
+            .execute("delete from schema_name.table_name;")
+            .expect("no system errors"),
+        Ok(Outcome::Affected(2))
+    );
+}
+
+#[test]
+fn typed_result_set() {
+    let database = database();
+    let mut connection = database.connect("postgres");
+    connection
+        .execute("create schema schema_name;")
+        .expect("no system errors")
+        .expect("no query errors");
+    connection
+        .execute("create table schema_name.table_name (column_1 integer, column_2 varchar(10), column_3 boolean);")
+        .expect("no system errors")
+        .expect("no query errors");
+    connection
+        .execute("insert into schema_name.table_name values (1, 'a', true);")
+        .expect("no system errors")
+        .expect("no query errors");
+    connection
+        .execute("insert into schema_name.table_name (column_1) values (2);")
+        .expect("no system errors")
+        .expect("no query errors");
+
+    let result_set = connection
+        .query("select * from schema_name.table_name;")
+        .expect("no system errors")
+        .expect("no query errors");
+
+    assert_eq!(
+        result_set.columns(),
+        &[
+            ColumnDescription::new("column_1", PostgreSqlType::Integer).with_source(16384, 1),
+            ColumnDescription::new("column_2", PostgreSqlType::VarChar).with_source(16384, 2),
+            ColumnDescription::new("column_3", PostgreSqlType::Bool).with_source(16384, 3),
+        ][..]
+    );
+    assert_eq!(
+        result_set.rows(),
+        &[
+            vec![Value::Integer(1), Value::Text("a".to_owned()), Value::Bool(true)],
+            vec![Value::Integer(2), Value::Null, Value::Null],
+        ][..]
+    );
+}
+
+#[test]
+fn statement_error() {
+    let database = database();
+    let mut connection = database.connect("postgres");
+
+    assert_eq!(
+        connection
+            .query("select * from schema_name.table_name;")
+            .expect("no system errors"),
+        Err(QueryError::schema_does_not_exist("schema_name".to_owned()))
+    );
+}
+
+#[test]
+fn connections_share_database() {
+    let database = database();
+    let mut first = database.connect("postgres");
+    let mut second = database.connect("postgres");
+    first
+        .execute("create schema schema_name;")
+        .expect("no system errors")
+        .expect("no query errors");
+
+    assert_eq!(
+        second.execute("create schema schema_name;").expect("no system errors"),
+        Err(QueryError::schema_already_exists("schema_name".to_owned()))
+    );
+}
+
+#[test]
+fn result_set_as_record_batch() {
+    let database = database();
+    let mut connection = database.connect("postgres");
+    for statement in &[
+        "create schema schema_name;",
+        "create table schema_name.table_name (column_1 bigint, column_2 varchar(10));",
+        "insert into schema_name.table_name values (1, 'a'), (2, 'bc');",
+        "insert into schema_name.table_name (column_1) values (3);",
+    ] {
+        connection
+            .execute(statement)
+            .expect("no system errors")
+            .expect("no query errors");
+    }
+
+    let batch = connection
+        .query("select * from schema_name.table_name;")
+        .expect("no system errors")
+        .expect("no query errors")
+        .to_record_batch();
+
+    assert_eq!(batch.num_rows(), 3);
+    assert_eq!(
+        batch
+            .schema()
+            .iter()
+            .map(|field| (field.name(), field.data_type()))
+            .collect::<Vec<_>>(),
+        vec![("column_1", DataType::Int64), ("column_2", DataType::Utf8)]
+    );
+    assert_eq!(batch.column(0).null_count(), 0);
+    assert_eq!(
+        batch.column(0).buffers(),
+        &[[1i64.to_le_bytes(), 2i64.to_le_bytes(), 3i64.to_le_bytes()].concat()]
+    );
+    assert_eq!(batch.column(1).null_count(), 1);
+    assert!(batch.column(1).is_null(2));
+    assert_eq!(batch.column(1).buffers()[1], b"abc".to_vec());
+}
+
+#[test]
+fn hook_receives_committed_changes() {
+    let database = database();
+    let changes = Arc::new(Mutex::new(vec![]));
+    let sink = changes.clone();
+    database.on_changes(move |committed: &[RowChange]| {
+        sink.lock()
+            .expect("locked")
+            .extend(committed.iter().map(|change| (change.kind, change.new.clone())))
+    });
+    let mut connection = database.connect("postgres");
+    for statement in &[
+        "create schema schema_name;",
+        "create table schema_name.table_name (column_1 smallint);",
+        "insert into schema_name.table_name values (1), (2);",
+        "update schema_name.table_name set column_1 = 3;",
+    ] {
+        connection
+            .execute(statement)
+            .expect("no system errors")
+            .expect("no query errors");
+    }
+    connection
+        .execute("insert into schema_name.table_name values (100000);")
+        .expect("no system errors")
+        .expect_err("value out of range");
+
+    let three = Some(vec![Some("3".to_owned())]);
+    assert_eq!(
+        changes.lock().expect("locked").as_slice(),
+        &[
+            (ChangeKind::Insert, Some(vec![Some("1".to_owned())])),
+            (ChangeKind::Insert, Some(vec![Some("2".to_owned())])),
+            (ChangeKind::Update, three.clone()),
+            (ChangeKind::Update, three),
+        ]
+    );
+}
+
+#[test]
+fn hook_receives_changes_of_block_when_it_is_committed() {
+    let database = database();
+    let changes = Arc::new(Mutex::new(vec![]));
+    let sink = changes.clone();
+    database.on_changes(move |committed: &[RowChange]| {
+        sink.lock()
+            .expect("locked")
+            .extend(committed.iter().map(|change| (change.kind, change.new.clone())))
+    });
+    let mut connection = database.connect("postgres");
+    for statement in &[
+        "create schema schema_name;",
+        "create table schema_name.table_name (column_1 smallint);",
+        "begin;",
+        "insert into schema_name.table_name values (1);",
+        "rollback;",
+        "begin;",
+        "insert into schema_name.table_name values (2);",
+    ] {
+        connection
+            .execute(statement)
+            .expect("no system errors")
+            .expect("no query errors");
+    }
+    assert!(changes.lock().expect("locked").is_empty());
+
+    connection
+        .execute("commit;")
+        .expect("no system errors")
+        .expect("no query errors");
+
+    assert_eq!(
+        changes.lock().expect("locked").as_slice(),
+        &[(ChangeKind::Insert, Some(vec![Some("2".to_owned())]))]
+    );
+}
+
+#[test]
+fn statement_with_params() {
+    let database = database();
+    let mut connection = database.connect("postgres");
+    connection
+        .execute("create schema schema_name;")
+        .expect("no system errors")
+        .expect("no query errors");
+    connection
+        .execute("create table schema_name.table_name (column_1 smallint, column_2 varchar(50));")
+        .expect("no system errors")
+        .expect("no query errors");
+
+    assert_eq!(
+        connection
+            .execute_with_params(
+                "insert into schema_name.table_name values ($1, $2);",
+                &[
+                    Some("1".to_owned()),
+                    Some("a'); drop schema schema_name; --".to_owned())
+                ]
+            )
+            .expect("no system errors"),
+        Ok(Outcome::Affected(1))
+    );
+    assert_eq!(
+        connection
+            .execute_with_params("insert into schema_name.table_name values ($1, $2);", &[None, None])
+            .expect("no system errors"),
+        Ok(Outcome::Affected(1))
+    );
+
+    let result_set = connection
+        .query("select * from schema_name.table_name;")
+        .expect("no system errors")
+        .expect("no query errors");
+    assert_eq!(
+        result_set.rows(),
+        &[
+            vec![
+                Value::SmallInt(1),
+                Value::Text("a'); drop schema schema_name; --".to_owned())
+            ],
+            vec![Value::Null, Value::Null]
+        ]
+    );
+}
+
+#[test]
+fn statement_with_params_that_can_not_be_parsed() {
+    let database = database();
+    let mut connection = database.connect("postgres");
+    connection
+        .execute("create schema schema_name;")
+        .expect("no system errors")
+        .expect("no query errors");
+    connection
+        .execute("create table schema_name.table_name (column_1 smallint);")
+        .expect("no system errors")
+        .expect("no query errors");
+    connection
+        .execute_with_params(
+            "insert into schema_name.table_name values ($1);",
+            &[Some("1".to_owned())],
+        )
+        .expect("no system errors")
+        .expect("no query errors");
+
+    assert!(connection
+        .execute_with_params("insert into", &[Some("2".to_owned())])
+        .expect("no system errors")
+        .is_err());
+    assert_eq!(
+        connection
+            .query("select * from schema_name.table_name;")
+            .expect("no system errors")
+            .expect("no query errors")
+            .rows(),
+        &[vec![Value::SmallInt(1)]]
+    );
+}
+
+#[test]
+fn script_with_progress() {
+    let database = database();
+    let mut connection = database.connect("postgres");
+    let mut reported = vec![];
+
+    assert_eq!(
+        connection
+            .execute_script(
+                "create schema schema_name;\n\
+                 create table schema_name.table_name (name varchar(10));\n\
+                 insert into schema_name.table_name values ('a;b'), ('c');",
+                |progress| reported.push((progress.index, progress.total, progress.statement.to_owned())),
+            )
+            .expect("no system errors"),
+        Ok(vec![Outcome::Done, Outcome::Done, Outcome::Affected(2)])
+    );
+    assert_eq!(
+        reported,
+        vec![
+            (0, 3, "create schema schema_name".to_owned()),
+            (
+                1,
+                3,
+                "create table schema_name.table_name (name varchar(10))".to_owned()
+            ),
+            (
+                2,
+                3,
+                "insert into schema_name.table_name values ('a;b'), ('c')".to_owned()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn script_stops_at_failing_statement() {
+    let database = database();
+    let mut connection = database.connect("postgres");
+    let mut reported = 0;
+
+    assert_eq!(
+        connection
+            .execute_script(
+                "create schema schema_name; create schema schema_name; create schema other_schema;",
+                |_progress| reported += 1,
+            )
+            .expect("no system errors"),
+        Err(ScriptError {
+            index: 1,
+            statement: "create schema schema_name".to_owned(),
+            error: QueryError::schema_already_exists("schema_name".to_owned()),
+        })
+    );
+    assert_eq!(reported, 1);
+    assert_eq!(
+        connection
+            .execute("create schema other_schema;")
+            .expect("no system errors"),
+        Ok(Outcome::Done)
+    );
+}
+
+#[test]
+fn failing_script_leaves_no_changes_of_earlier_statements() {
+    let database = database();
+    let mut connection = database.connect("postgres");
+    connection.execute("create schema schema_name;").unwrap().unwrap();
+    connection
+        .execute("create table schema_name.table_name (column_1 smallint);")
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(
+        connection
+            .execute_script(
+                "insert into schema_name.table_name values (1); \
+                 update schema_name.table_name set column_1 = 2; \
+                 insert into schema_name.non_existent values (3);",
+                |_progress| {},
+            )
+            .expect("no system errors")
+            .map_err(|error| error.index),
+        Err(2)
+    );
+    assert_eq!(
+        connection
+            .query("select column_1 from schema_name.table_name;")
+            .expect("no system errors")
+            .map(|result_set| result_set.rows().len()),
+        Ok(0)
+    );
+}
+
+#[test]
+fn script_in_transaction_block_is_part_of_it() {
+    let database = database();
+    let mut connection = database.connect("postgres");
+    connection.execute("create schema schema_name;").unwrap().unwrap();
+    connection
+        .execute("create table schema_name.table_name (column_1 smallint);")
+        .unwrap()
+        .unwrap();
+    connection.execute("begin;").unwrap().unwrap();
+
+    assert!(connection
+        .execute_script("insert into schema_name.table_name values (1);", |_progress| {})
+        .expect("no system errors")
+        .is_ok());
+    connection.execute("rollback;").unwrap().unwrap();
+
+    assert_eq!(
+        connection
+            .query("select column_1 from schema_name.table_name;")
+            .expect("no system errors")
+            .map(|result_set| result_set.rows().len()),
+        Ok(0)
+    );
+}
+
+#[test]
+fn statement_after_system_error_gets_its_own_outcome() {
+    let database = database();
+    let mut connection = database.connect("postgres");
+    connection.execute("create schema schema_name;").unwrap().unwrap();
+    connection
+        .execute("create table schema_name.table_name (column_1 smallint);")
+        .unwrap()
+        .unwrap();
+
+    assert!(connection
+        .execute_with_params("(select column_1 from schema_name.table_name);", &[])
+        .is_err());
+
+    assert_eq!(
+        connection
+            .execute("insert into schema_name.table_name values (1);")
+            .unwrap(),
+        Ok(Outcome::Affected(1))
+    );
+}
+
+#[test]
+fn ddl_of_script_commits_rows_changed_before_it() {
+    let database = database();
+    let mut connection = database.connect("postgres");
+    connection.execute("create schema schema_name;").unwrap().unwrap();
+    connection
+        .execute("create table schema_name.table_name (column_1 smallint);")
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(
+        connection
+            .execute_script(
+                "insert into schema_name.table_name values (1); \
+                 create table schema_name.other (column_1 smallint); \
+                 insert into schema_name.table_name values (2); \
+                 insert into schema_name.non_existent values (3);",
+                |_progress| {},
+            )
+            .expect("no system errors")
+            .map_err(|error| error.index),
+        Err(3)
+    );
+    assert_eq!(
+        connection
+            .query("select column_1 from schema_name.table_name;")
+            .expect("no system errors")
+            .expect("no query errors")
+            .rows(),
+        &[vec![Value::SmallInt(1)]]
+    );
+    assert_eq!(
+        connection
+            .query("select column_1 from schema_name.other;")
+            .expect("no system errors")
+            .map(|result_set| result_set.rows().len()),
+        Ok(0)
+    );
+}
@@ -0,0 +1,77 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use protocol::sql_types::PostgreSqlType;
+
+#[rstest::rstest]
+fn empty_query_string(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    engine.execute("").expect("no system errors");
+
+    collector.assert_content(vec![Ok(QueryEvent::EmptyQuery), Ok(QueryEvent::QueryComplete)]);
+}
+
+#[rstest::rstest]
+fn whitespaces_and_comments_only(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    engine.execute(" \n\t ").expect("no system errors");
+    engine.execute("-- nothing to do").expect("no system errors");
+    engine.execute("/* nothing */ ; ;").expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::EmptyQuery),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::EmptyQuery),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::EmptyQuery),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn comments_around_statements(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("/* table */ create table schema_name.table_name (column_1 smallint); -- created")
+        .expect("no system errors");
+    engine
+        .execute("-- defaults\nalter table schema_name.table_name alter column column_1 set default 1 /* done */")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name values (2); -- inserted")
+        .expect("no system errors");
+    engine
+        .execute("-- leading\nselect * from schema_name.table_name /* trailing */;")
+        .expect("no system errors");
+
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableAltered),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(1)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "column_1",
+            PostgreSqlType::SmallInt,
+        )
+        .with_source(16384, 1)])),
+        Ok(QueryEvent::DataRows(vec![vec![Some("2".to_owned())]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
@@ -0,0 +1,171 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+const FORBID_DROPS: &str = "create function forbid_drops() returns event_trigger as $$
+    begin
+        raise exception '% of % is not allowed', tg_tag, tg_event;
+    end
+    $$ language plpgsql;";
+
+#[rstest::rstest]
+fn start_trigger_prevents_command(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine.execute(FORBID_DROPS).expect("no system errors");
+    engine
+        .execute(
+            "create event trigger no_drops on ddl_command_start \
+             when tag in ('drop table', 'drop schema') execute function forbid_drops();",
+        )
+        .expect("no system errors");
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint);")
+        .expect("no system errors");
+    engine
+        .execute("drop table schema_name.table_name;")
+        .expect("no system errors");
+    engine
+        .execute("drop event trigger no_drops;")
+        .expect("no system errors");
+    engine
+        .execute("drop table schema_name.table_name;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::FunctionCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::EventTriggerCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::raise_exception(
+            "DROP TABLE of ddl_command_start is not allowed".to_owned(),
+        )),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::EventTriggerDropped),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableDropped),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn triggers_fire_on_extended_statements(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    engine.execute(FORBID_DROPS).expect("no system errors");
+    engine
+        .execute("create event trigger no_drops on ddl_command_start execute procedure forbid_drops();")
+        .expect("no system errors");
+    engine.execute("drop function forbid_drops;").expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::FunctionCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::EventTriggerCreated),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::raise_exception(
+            "DROP FUNCTION of ddl_command_start is not allowed".to_owned(),
+        )),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn end_trigger_errors_do_not_fail_command(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    engine.execute(FORBID_DROPS).expect("no system errors");
+    engine
+        .execute("create event trigger after_ddl on ddl_command_end execute function forbid_drops();")
+        .expect("no system errors");
+    engine.execute("create schema schema_name;").expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::FunctionCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::EventTriggerCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn event_trigger_errors(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    engine.execute(FORBID_DROPS).expect("no system errors");
+    engine
+        .execute("create function answer() returns integer as 'begin return 42; end' language plpgsql;")
+        .expect("no system errors");
+    engine
+        .execute("create event trigger audit on ddl_command_end execute function missing();")
+        .expect("no system errors");
+    engine
+        .execute("create event trigger audit on ddl_command_end execute function answer();")
+        .expect("no system errors");
+    engine
+        .execute("create event trigger audit on ddl_command_end execute function forbid_drops();")
+        .expect("no system errors");
+    engine
+        .execute("create event trigger audit on ddl_command_end execute function forbid_drops();")
+        .expect("no system errors");
+    engine.execute("drop event trigger missing;").expect("no system errors");
+    engine
+        .execute("drop event trigger if exists missing;")
+        .expect("no system errors");
+    engine.execute("select forbid_drops();").expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::FunctionCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::FunctionCreated),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::function_does_not_exist("missing()".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::not_event_trigger_function("answer".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::EventTriggerCreated),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::event_trigger_already_exists("audit".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::event_trigger_does_not_exist("missing".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::EventTriggerDropped),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::trigger_function_call()),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn only_superuser_manages_event_triggers(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    engine.execute(FORBID_DROPS).expect("no system errors");
+    let mut other = QueryExecutor::new(engine.storage.clone(), collector.clone(), "role_1");
+    other
+        .execute("create event trigger no_drops on ddl_command_start execute function forbid_drops();")
+        .expect("no system errors");
+    other.execute("drop event trigger no_drops;").expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::FunctionCreated),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::must_be_superuser("create or drop event triggers")),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::must_be_superuser("create or drop event triggers")),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
@@ -25,7 +25,7 @@ fn execute_insert_portal(sql_engine_with_schema: (QueryExecutor, ResultCollector
         .parse_prepared_statement(
             "statement_name",
             "insert into schema_name.table_name values ($1, $2);",
-            &[PostgreSqlType::SmallInt, PostgreSqlType::SmallInt],
+            &[Some(PostgreSqlType::SmallInt), Some(PostgreSqlType::SmallInt)],
         )
         .expect("no system errors");
     engine
@@ -63,7 +63,7 @@ fn execute_update_portal(sql_engine_with_schema: (QueryExecutor, ResultCollector
         .parse_prepared_statement(
             "statement_name",
             "update schema_name.table_name set column_1 = $1, column_2 = $2;",
-            &[PostgreSqlType::SmallInt, PostgreSqlType::SmallInt],
+            &[Some(PostgreSqlType::SmallInt), Some(PostgreSqlType::SmallInt)],
         )
         .expect("no system errors");
     engine
@@ -89,3 +89,562 @@ fn execute_update_portal(sql_engine_with_schema: (QueryExecutor, ResultCollector
         Ok(QueryEvent::RecordsUpdated(1)),
     ]);
 }
+
+#[rstest::rstest]
+fn execute_completed_portal(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint, column_2 smallint);")
+        .expect("no system errors");
+    engine
+        .parse_prepared_statement(
+            "statement_name",
+            "insert into schema_name.table_name values ($1, $2);",
+            &[Some(PostgreSqlType::SmallInt), Some(PostgreSqlType::SmallInt)],
+        )
+        .expect("no system errors");
+    engine
+        .bind_prepared_statement_to_portal(
+            "portal_name",
+            "statement_name",
+            &[PostgreSqlFormat::Text],
+            &[Some(b"1".to_vec()), Some(b"2".to_vec())],
+            &[],
+        )
+        .expect("no system errors");
+    engine.execute_portal("portal_name", 0).expect("no system errors");
+    engine.execute_portal("portal_name", 0).expect("no system errors");
+    engine
+        .execute("select * from schema_name.table_name;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::ParseComplete),
+        Ok(QueryEvent::BindComplete),
+        Ok(QueryEvent::RecordsInserted(1)),
+        Err(QueryError::portal_cannot_be_run("portal_name".to_owned())),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("column_1", PostgreSqlType::SmallInt).with_source(16384, 1),
+            ColumnDescription::new("column_2", PostgreSqlType::SmallInt).with_source(16384, 2),
+        ])),
+        Ok(QueryEvent::DataRows(vec![vec![
+            Some("1".to_owned()),
+            Some("2".to_owned()),
+        ]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn execute_completed_select_portal(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint, column_2 smallint);")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name values (1, 2);")
+        .expect("no system errors");
+    engine
+        .parse_prepared_statement(
+            "statement_name",
+            "select * from schema_name.table_name where column_1 = $1;",
+            &[Some(PostgreSqlType::SmallInt)],
+        )
+        .expect("no system errors");
+    engine
+        .bind_prepared_statement_to_portal(
+            "portal_name",
+            "statement_name",
+            &[PostgreSqlFormat::Text],
+            &[Some(b"1".to_vec())],
+            &[],
+        )
+        .expect("no system errors");
+    engine.execute_portal("portal_name", 0).expect("no system errors");
+    engine.execute_portal("portal_name", 0).expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(1)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::ParseComplete),
+        Ok(QueryEvent::BindComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("column_1", PostgreSqlType::SmallInt).with_source(16384, 1),
+            ColumnDescription::new("column_2", PostgreSqlType::SmallInt).with_source(16384, 2),
+        ])),
+        Ok(QueryEvent::DataRows(vec![vec![
+            Some("1".to_owned()),
+            Some("2".to_owned()),
+        ]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::SelectComplete(0)),
+    ]);
+}
+
+#[rstest::rstest]
+fn execute_select_portal_with_row_count_params(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint);")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name values (1), (2), (3), (4);")
+        .expect("no system errors");
+    engine
+        .parse_prepared_statement(
+            "statement_name",
+            "select column_1 from schema_name.table_name offset $1 rows fetch first $2 + 1 rows only;",
+            &[],
+        )
+        .expect("no system errors");
+    engine
+        .bind_prepared_statement_to_portal(
+            "portal_name",
+            "statement_name",
+            &[PostgreSqlFormat::Text, PostgreSqlFormat::Text],
+            &[Some(b"1".to_vec()), Some(b"1".to_vec())],
+            &[],
+        )
+        .expect("no system errors");
+    engine.execute_portal("portal_name", 0).expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(4)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::ParseComplete),
+        Ok(QueryEvent::BindComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "column_1",
+            PostgreSqlType::SmallInt,
+        )
+        .with_source(16384, 1)])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("2".to_owned())],
+            vec![Some("3".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(2)),
+    ]);
+}
+
+#[rstest::rstest]
+fn interleave_suspended_portals(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_1 (column_1 smallint);")
+        .expect("no system errors");
+    engine
+        .execute("create table schema_name.table_2 (column_2 smallint);")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_1 values (1), (2), (3);")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_2 values (4), (5);")
+        .expect("no system errors");
+    engine
+        .parse_prepared_statement("statement_1", "select column_1 from schema_name.table_1;", &[])
+        .expect("no system errors");
+    engine
+        .parse_prepared_statement("statement_2", "select column_2 from schema_name.table_2;", &[])
+        .expect("no system errors");
+    engine
+        .bind_prepared_statement_to_portal("portal_1", "statement_1", &[], &[], &[])
+        .expect("no system errors");
+    engine
+        .bind_prepared_statement_to_portal("portal_2", "statement_2", &[], &[], &[])
+        .expect("no system errors");
+    engine.execute_portal("portal_1", 2).expect("no system errors");
+    engine.execute_portal("portal_2", 1).expect("no system errors");
+    engine.execute_portal("portal_1", 2).expect("no system errors");
+    engine.execute_portal("portal_2", 0).expect("no system errors");
+    engine.execute_portal("portal_1", 2).expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(3)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(2)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::ParseComplete),
+        Ok(QueryEvent::ParseComplete),
+        Ok(QueryEvent::BindComplete),
+        Ok(QueryEvent::BindComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "column_1",
+            PostgreSqlType::SmallInt,
+        )
+        .with_source(16384, 1)])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("1".to_owned())],
+            vec![Some("2".to_owned())],
+        ])),
+        Ok(QueryEvent::PortalSuspended),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "column_2",
+            PostgreSqlType::SmallInt,
+        )
+        .with_source(16385, 1)])),
+        Ok(QueryEvent::DataRows(vec![vec![Some("4".to_owned())]])),
+        Ok(QueryEvent::PortalSuspended),
+        Ok(QueryEvent::DataRows(vec![vec![Some("3".to_owned())]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::DataRows(vec![vec![Some("5".to_owned())]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::SelectComplete(0)),
+    ]);
+}
+
+#[rstest::rstest]
+fn execute_rebound_portal(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint, column_2 smallint);")
+        .expect("no system errors");
+    engine
+        .parse_prepared_statement(
+            "statement_name",
+            "insert into schema_name.table_name values ($1, $2);",
+            &[Some(PostgreSqlType::SmallInt), Some(PostgreSqlType::SmallInt)],
+        )
+        .expect("no system errors");
+    engine
+        .bind_prepared_statement_to_portal(
+            "portal_name",
+            "statement_name",
+            &[PostgreSqlFormat::Text],
+            &[Some(b"1".to_vec()), Some(b"2".to_vec())],
+            &[],
+        )
+        .expect("no system errors");
+    engine.execute_portal("portal_name", 0).expect("no system errors");
+    engine
+        .bind_prepared_statement_to_portal(
+            "portal_name",
+            "statement_name",
+            &[PostgreSqlFormat::Text],
+            &[Some(b"3".to_vec()), Some(b"4".to_vec())],
+            &[],
+        )
+        .expect("no system errors");
+    engine.execute_portal("portal_name", 0).expect("no system errors");
+    engine
+        .execute("select * from schema_name.table_name;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::ParseComplete),
+        Ok(QueryEvent::BindComplete),
+        Ok(QueryEvent::RecordsInserted(1)),
+        Ok(QueryEvent::BindComplete),
+        Ok(QueryEvent::RecordsInserted(1)),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("column_1", PostgreSqlType::SmallInt).with_source(16384, 1),
+            ColumnDescription::new("column_2", PostgreSqlType::SmallInt).with_source(16384, 2),
+        ])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("1".to_owned()), Some("2".to_owned())],
+            vec![Some("3".to_owned()), Some("4".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(2)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn execute_portals_bound_to_the_same_statement(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint, column_2 smallint);")
+        .expect("no system errors");
+    engine
+        .parse_prepared_statement(
+            "statement_name",
+            "insert into schema_name.table_name values ($1, $2);",
+            &[Some(PostgreSqlType::SmallInt), Some(PostgreSqlType::SmallInt)],
+        )
+        .expect("no system errors");
+    engine
+        .bind_prepared_statement_to_portal(
+            "portal_1",
+            "statement_name",
+            &[PostgreSqlFormat::Text],
+            &[Some(b"1".to_vec()), Some(b"2".to_vec())],
+            &[],
+        )
+        .expect("no system errors");
+    engine
+        .bind_prepared_statement_to_portal(
+            "portal_2",
+            "statement_name",
+            &[PostgreSqlFormat::Text],
+            &[Some(b"3".to_vec()), Some(b"4".to_vec())],
+            &[],
+        )
+        .expect("no system errors");
+    engine.execute_portal("portal_2", 0).expect("no system errors");
+    engine.execute_portal("portal_1", 0).expect("no system errors");
+    engine
+        .execute("select * from schema_name.table_name;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::ParseComplete),
+        Ok(QueryEvent::BindComplete),
+        Ok(QueryEvent::BindComplete),
+        Ok(QueryEvent::RecordsInserted(1)),
+        Ok(QueryEvent::RecordsInserted(1)),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("column_1", PostgreSqlType::SmallInt).with_source(16384, 1),
+            ColumnDescription::new("column_2", PostgreSqlType::SmallInt).with_source(16384, 2),
+        ])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("3".to_owned()), Some("4".to_owned())],
+            vec![Some("1".to_owned()), Some("2".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(2)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn privileges_are_checked_on_portal_execution() {
+    let storage = in_memory_catalog_manager();
+    let collector = sender();
+    let mut owner = QueryExecutor::new(storage.clone(), collector.clone(), SUPERUSER);
+    owner.execute("create schema schema_name;").expect("no system errors");
+    owner
+        .execute("create table schema_name.table_name (column_1 smallint);")
+        .expect("no system errors");
+    owner
+        .execute("grant usage on schema schema_name to role_1;")
+        .expect("no system errors");
+    owner
+        .execute("grant insert on table schema_name.table_name to role_1;")
+        .expect("no system errors");
+
+    let role_collector = sender();
+    let mut engine = QueryExecutor::new(storage, role_collector.clone(), "role_1");
+    engine
+        .parse_prepared_statement(
+            "statement_name",
+            "insert into schema_name.table_name values ($1);",
+            &[Some(PostgreSqlType::SmallInt)],
+        )
+        .expect("no system errors");
+    engine
+        .bind_prepared_statement_to_portal(
+            "portal_name",
+            "statement_name",
+            &[PostgreSqlFormat::Text],
+            &[Some(b"1".to_vec())],
+            &[],
+        )
+        .expect("no system errors");
+    owner
+        .execute("revoke insert on table schema_name.table_name from role_1;")
+        .expect("no system errors");
+    engine.execute_portal("portal_name", 0).expect("no system errors");
+
+    role_collector.assert_content(vec![
+        Ok(QueryEvent::ParseComplete),
+        Ok(QueryEvent::BindComplete),
+        Err(QueryError::permission_denied(
+            "table",
+            "schema_name.table_name".to_owned(),
+        )),
+    ]);
+}
+
+#[rstest::rstest]
+fn execute_insert_portal_with_null_params(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint, column_2 varchar(10));")
+        .expect("no system errors");
+    engine
+        .parse_prepared_statement(
+            "statement_name",
+            "insert into schema_name.table_name values ($1::smallint, $2);",
+            &[],
+        )
+        .expect("no system errors");
+    engine
+        .bind_prepared_statement_to_portal(
+            "portal_name",
+            "statement_name",
+            &[],
+            &[None, Some(b"abc".to_vec())],
+            &[],
+        )
+        .expect("no system errors");
+    engine.execute_portal("portal_name", 0).expect("no system errors");
+    engine
+        .bind_prepared_statement_to_portal(
+            "portal_name",
+            "statement_name",
+            &[PostgreSqlFormat::Binary],
+            &[Some(vec![0, 1]), None],
+            &[],
+        )
+        .expect("no system errors");
+    engine.execute_portal("portal_name", 0).expect("no system errors");
+    engine
+        .execute("select * from schema_name.table_name;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::ParseComplete),
+        Ok(QueryEvent::BindComplete),
+        Ok(QueryEvent::RecordsInserted(1)),
+        Ok(QueryEvent::BindComplete),
+        Ok(QueryEvent::RecordsInserted(1)),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("column_1", PostgreSqlType::SmallInt).with_source(16384, 1),
+            ColumnDescription::new("column_2", PostgreSqlType::VarChar).with_source(16384, 2),
+        ])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![None, Some("abc".to_owned())],
+            vec![Some("1".to_owned()), None],
+        ])),
+        Ok(QueryEvent::SelectComplete(2)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn execute_update_portal_with_null_param(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint);")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name values (1);")
+        .expect("no system errors");
+    engine
+        .parse_prepared_statement(
+            "statement_name",
+            "update schema_name.table_name set column_1 = $1;",
+            &[],
+        )
+        .expect("no system errors");
+    engine
+        .bind_prepared_statement_to_portal("portal_name", "statement_name", &[], &[None], &[])
+        .expect("no system errors");
+    engine.execute_portal("portal_name", 0).expect("no system errors");
+    engine
+        .execute("select * from schema_name.table_name;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(1)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::ParseComplete),
+        Ok(QueryEvent::BindComplete),
+        Ok(QueryEvent::RecordsUpdated(1)),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "column_1",
+            PostgreSqlType::SmallInt,
+        )
+        .with_source(16384, 1)])),
+        Ok(QueryEvent::DataRows(vec![vec![None]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn execute_update_and_delete_portals_with_conditions(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (id smallint, name varchar(10));")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name values (1, 'a'), (2, 'b'), (3, 'c');")
+        .expect("no system errors");
+    engine
+        .parse_prepared_statement(
+            "update",
+            "update schema_name.table_name set name = $1 where id = $2;",
+            &[],
+        )
+        .expect("no system errors");
+    engine
+        .bind_prepared_statement_to_portal(
+            "update",
+            "update",
+            &[],
+            &[Some(b"z".to_vec()), Some(b"2".to_vec())],
+            &[],
+        )
+        .expect("no system errors");
+    engine.execute_portal("update", 0).expect("no system errors");
+    engine
+        .parse_prepared_statement("delete", "delete from schema_name.table_name where id = $1;", &[])
+        .expect("no system errors");
+    engine
+        .bind_prepared_statement_to_portal("delete", "delete", &[], &[Some(b"1".to_vec())], &[])
+        .expect("no system errors");
+    engine.execute_portal("delete", 0).expect("no system errors");
+    engine
+        .execute("select * from schema_name.table_name;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(3)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::ParseComplete),
+        Ok(QueryEvent::BindComplete),
+        Ok(QueryEvent::RecordsUpdated(1)),
+        Ok(QueryEvent::ParseComplete),
+        Ok(QueryEvent::BindComplete),
+        Ok(QueryEvent::RecordsDeleted(1)),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("id", PostgreSqlType::SmallInt).with_source(16384, 1),
+            ColumnDescription::new("name", PostgreSqlType::VarChar).with_source(16384, 2),
+        ])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("2".to_owned()), Some("z".to_owned())],
+            vec![Some("3".to_owned()), Some("c".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(2)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
@@ -0,0 +1,146 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use protocol::sql_types::PostgreSqlType;
+
+#[rstest::rstest]
+fn explain_select(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint);")
+        .expect("no system errors");
+    engine
+        .execute("explain select * from schema_name.table_name;")
+        .expect("no system errors");
+    engine.execute("explain select 1;").expect("no system errors");
+
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::PlanExplained(vec![
+            "Seq Scan on schema_name.table_name".to_owned()
+        ])),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::PlanExplained(vec!["Result".to_owned()])),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn explain_modifications_without_executing_them(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint);")
+        .expect("no system errors");
+    engine
+        .execute("explain insert into schema_name.table_name values (1);")
+        .expect("no system errors");
+    engine
+        .execute("explain delete from schema_name.table_name;")
+        .expect("no system errors");
+    engine
+        .execute("select * from schema_name.table_name;")
+        .expect("no system errors");
+
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::PlanExplained(vec![
+            "Insert on schema_name.table_name".to_owned(),
+            "  ->  Values Scan".to_owned(),
+        ])),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::PlanExplained(vec![
+            "Delete on schema_name.table_name".to_owned(),
+            "  ->  Seq Scan on schema_name.table_name".to_owned(),
+        ])),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "column_1",
+            PostgreSqlType::SmallInt,
+        )
+        .with_source(16384, 1)])),
+        Ok(QueryEvent::SelectComplete(0)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn explain_in_json_format(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint);")
+        .expect("no system errors");
+    engine
+        .execute("explain (format json) update schema_name.table_name set column_1 = 2;")
+        .expect("no system errors");
+
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::PlanExplained(vec![r#"[
+  {
+    "Plan": {
+      "Node Type": "ModifyTable",
+      "Operation": "Update",
+      "Relation Name": "table_name",
+      "Schema": "schema_name",
+      "Plans": [
+        {
+          "Node Type": "Seq Scan",
+          "Relation Name": "table_name",
+          "Schema": "schema_name"
+        }
+      ]
+    }
+  }
+]"#
+        .to_owned()])),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn explain_errors(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("explain select * from schema_name.non_existent;")
+        .expect("no system errors");
+    engine
+        .execute("explain select * from non_existent_schema.table_name;")
+        .expect("no system errors");
+    engine
+        .execute("explain create table schema_name.table_name (column_1 smallint);")
+        .expect("no system errors");
+
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::table_does_not_exist("schema_name.non_existent".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::schema_does_not_exist("non_existent_schema".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::feature_not_supported(
+            "explain create table schema_name.table_name (column_1 smallint);".to_owned(),
+        )),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
@@ -0,0 +1,308 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::background::WorkerState;
+use crate::embedded::{
+    ColumnDescription, Database, Extension, NativeAggregate, PostgreSqlType, QueryError, Registry, Value, WorkerContext,
+};
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+struct Text;
+
+impl Extension for Text {
+    fn name(&self) -> &str {
+        "text"
+    }
+
+    fn register(&self, registry: &mut Registry) {
+        registry
+            .function(
+                "repeat",
+                &[PostgreSqlType::VarChar, PostgreSqlType::Integer],
+                PostgreSqlType::VarChar,
+                |args: &[Option<String>]| match (&args[0], &args[1]) {
+                    (Some(text), Some(count)) => {
+                        let count = count.parse::<usize>().map_err(|error| error.to_string())?;
+                        Ok(Some(text.repeat(count)))
+                    }
+                    _ => Ok(None),
+                },
+            )
+            .function("fail", &[], PostgreSqlType::Integer, |_args: &[Option<String>]| {
+                Err("failed on purpose".to_owned())
+            })
+            .aggregate(
+                "joined",
+                NativeAggregate::new(
+                    PostgreSqlType::VarChar,
+                    PostgreSqlType::VarChar,
+                    |args: &[Option<String>]| {
+                        Ok(Some(format!(
+                            "{},{}",
+                            args[0].as_deref().unwrap_or(""),
+                            args[1].as_deref().unwrap_or("")
+                        )))
+                    },
+                )
+                .finish(PostgreSqlType::VarChar, |args: &[Option<String>]| {
+                    Ok(args[0].as_ref().map(|joined| format!("[{}]", joined)))
+                }),
+            )
+            .register_type("email", PostgreSqlType::VarChar);
+    }
+}
+
+fn database() -> Database {
+    Database::in_memory().expect("no system errors")
+}
+
+fn selected(database: &Database, sql: &str) -> Result<Vec<Vec<Value>>, QueryError> {
+    database
+        .connect("postgres")
+        .query(sql)
+        .expect("no system errors")
+        .map(|result_set| result_set.rows().to_vec())
+}
+
+#[test]
+fn call_native_function() {
+    let database = database();
+    database.load_extension(&Text).expect("extension is loaded");
+
+    assert_eq!(
+        selected(&database, "select repeat('ab', 3);"),
+        Ok(vec![vec![Value::Text("ababab".to_owned())]])
+    );
+    assert_eq!(
+        selected(&database, "select repeat(null, 3);"),
+        Ok(vec![vec![Value::Null]])
+    );
+    assert_eq!(
+        selected(&database, "select fail();"),
+        Err(QueryError::external_routine_exception("failed on purpose".to_owned()))
+    );
+}
+
+#[test]
+fn arguments_are_cast_to_parameter_types() {
+    let database = database();
+    database.load_extension(&Text).expect("extension is loaded");
+
+    assert_eq!(
+        selected(&database, "select repeat('ab', 'many');"),
+        Err(QueryError::datatype_mismatch(
+            "invalid input syntax for type integer: \"many\"".to_owned()
+        ))
+    );
+}
+
+#[test]
+fn columns_of_registered_type() {
+    let database = database();
+    database.load_extension(&Text).expect("extension is loaded");
+    let mut connection = database.connect("postgres");
+    connection
+        .execute("create schema schema_name;")
+        .expect("no system errors")
+        .expect("no query errors");
+    connection
+        .execute("create table schema_name.users (address email);")
+        .expect("no system errors")
+        .expect("no query errors");
+    connection
+        .execute("insert into schema_name.users values ('user@example.com');")
+        .expect("no system errors")
+        .expect("no query errors");
+
+    let result_set = connection
+        .query("select * from schema_name.users;")
+        .expect("no system errors")
+        .expect("no query errors");
+    assert_eq!(
+        result_set.columns(),
+        &[ColumnDescription::new("address", PostgreSqlType::VarChar).with_source(16384, 1)][..]
+    );
+    assert_eq!(
+        result_set.rows(),
+        &[vec![Value::Text("user@example.com".to_owned())]][..]
+    );
+}
+
+#[test]
+fn native_aggregate() {
+    let database = database();
+    database.load_extension(&Text).expect("extension is loaded");
+    let mut connection = database.connect("postgres");
+    connection
+        .execute("create schema schema_name;")
+        .expect("no system errors")
+        .expect("no query errors");
+    connection
+        .execute("create table schema_name.words (length smallint, word varchar(10));")
+        .expect("no system errors")
+        .expect("no query errors");
+    connection
+        .execute("insert into schema_name.words values (1, 'a'), (2, 'bb'), (1, 'c'), (2, null);")
+        .expect("no system errors")
+        .expect("no query errors");
+
+    assert_eq!(
+        selected(
+            &database,
+            "select length, joined(word) from schema_name.words group by length;"
+        ),
+        Ok(vec![
+            vec![Value::SmallInt(1), Value::Text("[a,c]".to_owned())],
+            vec![Value::SmallInt(2), Value::Text("[bb]".to_owned())],
+        ])
+    );
+}
+
+#[test]
+fn extension_is_loaded_once() {
+    let database = database();
+    database.load_extension(&Text).expect("extension is loaded");
+
+    assert_eq!(
+        database.load_extension(&Text).map(|loaded| loaded.name().to_owned()),
+        Err("extension \"text\" is already loaded".to_owned())
+    );
+}
+
+struct Conflicting;
+
+impl Extension for Conflicting {
+    fn name(&self) -> &str {
+        "conflicting"
+    }
+
+    fn register(&self, registry: &mut Registry) {
+        registry
+            .function("answer", &[], PostgreSqlType::Integer, |_args: &[Option<String>]| {
+                Ok(Some("42".to_owned()))
+            })
+            .register_type("integer", PostgreSqlType::BigInt);
+    }
+}
+
+#[test]
+fn nothing_is_registered_on_conflict() {
+    let database = database();
+
+    assert_eq!(
+        database
+            .load_extension(&Conflicting)
+            .map(|loaded| loaded.name().to_owned()),
+        Err("type \"integer\" already exists".to_owned())
+    );
+    assert_eq!(
+        selected(&database, "select answer();"),
+        Err(QueryError::function_does_not_exist("answer()".to_owned()))
+    );
+}
+
+struct Counter {
+    runs: Arc<AtomicUsize>,
+    started: mpsc::Sender<()>,
+}
+
+impl Extension for Counter {
+    fn name(&self) -> &str {
+        "counter"
+    }
+
+    fn register(&self, registry: &mut Registry) {
+        let runs = self.runs.clone();
+        let started = self.started.clone();
+        registry.background_worker("maintenance", move |context: WorkerContext| {
+            let mut connection = context.database().connect("postgres");
+            connection
+                .execute("create schema maintenance;")
+                .expect("no system errors")
+                .expect("no query errors");
+            started.send(()).expect("to notify test");
+            while !context.is_stopped() {
+                runs.fetch_add(1, Ordering::SeqCst);
+                thread::sleep(Duration::from_millis(1));
+            }
+        });
+    }
+}
+
+#[test]
+fn background_worker_runs_until_stopped() {
+    let database = database();
+    let runs = Arc::new(AtomicUsize::default());
+    let (started, worker_started) = mpsc::channel();
+    let loaded = database
+        .load_extension(&Counter {
+            runs: runs.clone(),
+            started,
+        })
+        .expect("extension is loaded");
+    worker_started.recv().expect("worker to start");
+    loaded.stop();
+    let stopped_at = runs.load(Ordering::SeqCst);
+    thread::sleep(Duration::from_millis(10));
+
+    assert_eq!(runs.load(Ordering::SeqCst), stopped_at);
+    assert_eq!(
+        database
+            .connect("postgres")
+            .execute("create schema maintenance;")
+            .expect("no system errors"),
+        Err(QueryError::schema_already_exists("maintenance".to_owned()))
+    );
+}
+
+struct Ticker {
+    ticks: mpsc::Sender<()>,
+}
+
+impl Extension for Ticker {
+    fn name(&self) -> &str {
+        "ticker"
+    }
+
+    fn register(&self, registry: &mut Registry) {
+        let ticks = self.ticks.clone();
+        registry.periodic_task("tick", Duration::from_millis(1), move |_context: &WorkerContext| {
+            ticks.send(()).map_err(|error| error.to_string())
+        });
+    }
+}
+
+#[test]
+fn periodic_task_runs_until_stopped() {
+    let database = database();
+    let (ticks, ticked) = mpsc::channel();
+    let loaded = database.load_extension(&Ticker { ticks }).expect("extension is loaded");
+    ticked.recv().expect("task to run");
+    ticked.recv().expect("task to run again");
+
+    assert_eq!(
+        loaded.workers().states(),
+        vec![("tick".to_owned(), WorkerState::Running)]
+    );
+    loaded.stop();
+    while ticked.try_recv().is_ok() {}
+    thread::sleep(Duration::from_millis(10));
+    assert!(ticked.try_recv().is_err());
+}
@@ -0,0 +1,194 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use super::*;
+use crate::catalog_manager::ForeignDataWrapper;
+use protocol::sql_types::PostgreSqlType;
+use std::{collections::HashMap, fs};
+
+struct Numbers;
+
+impl ForeignDataWrapper for Numbers {
+    fn scan(
+        &self,
+        _columns: &[ColumnDefinition],
+        options: &HashMap<String, String>,
+    ) -> Result<Vec<Vec<Option<String>>>, String> {
+        let count = options
+            .get("count")
+            .and_then(|count| count.parse::<i16>().ok())
+            .ok_or_else(|| "count option is required".to_owned())?;
+        Ok((1..=count).map(|number| vec![Some(number.to_string())]).collect())
+    }
+}
+
+#[rstest::rstest]
+fn select_from_csv_file(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    let dir = tempfile::tempdir().expect("to create temporary folder");
+    let path = dir.path().join("table.csv");
+    fs::write(&path, "id;name\n1;one\n2;\n").expect("to write file");
+
+    engine
+        .execute(&format!(
+            "create foreign table schema_name.table_name (id smallint, name varchar(10)) \
+             server file options (filename '{}', header 'true', delimiter ';');",
+            path.display()
+        ))
+        .expect("no system errors");
+    engine
+        .execute("select * from schema_name.table_name;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::ForeignTableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("id", PostgreSqlType::SmallInt).with_source(16384, 1),
+            ColumnDescription::new("name", PostgreSqlType::VarChar).with_source(16384, 2),
+        ])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("1".to_owned()), Some("one".to_owned())],
+            vec![Some("2".to_owned()), None],
+        ])),
+        Ok(QueryEvent::SelectComplete(2)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn select_from_custom_wrapper() {
+    let storage = in_memory_catalog_manager();
+    storage.register_foreign_data_wrapper("numbers", Arc::new(Numbers));
+    let collector = sender();
+    let mut engine = QueryExecutor::new(storage, collector.clone(), SUPERUSER);
+    engine.execute("create schema schema_name;").expect("no system errors");
+
+    engine
+        .execute("create foreign table schema_name.numbers (number smallint) server numbers options (count '3');")
+        .expect("no system errors");
+    engine
+        .execute("select number from schema_name.numbers;")
+        .expect("no system errors");
+    engine
+        .execute("create foreign table schema_name.broken (number smallint) server numbers;")
+        .expect("no system errors");
+    engine
+        .execute("select number from schema_name.broken;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::ForeignTableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "number",
+            PostgreSqlType::SmallInt,
+        )
+        .with_source(16384, 1)])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("1".to_owned())],
+            vec![Some("2".to_owned())],
+            vec![Some("3".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(3)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::ForeignTableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::fdw_error("count option is required".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn unknown_server(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+
+    engine
+        .execute("create foreign table schema_name.table_name (id smallint) server remote;")
+        .expect("no system errors");
+    engine
+        .execute("select * from schema_name.table_name;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::server_does_not_exist("remote".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::table_does_not_exist("schema_name.table_name".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn foreign_table_can_not_be_changed() {
+    let storage = in_memory_catalog_manager();
+    storage.register_foreign_data_wrapper("numbers", Arc::new(Numbers));
+    let collector = sender();
+    let mut engine = QueryExecutor::new(storage, collector.clone(), SUPERUSER);
+    engine.execute("create schema schema_name;").expect("no system errors");
+    engine
+        .execute("create foreign table schema_name.numbers (number smallint) server numbers options (count '3');")
+        .expect("no system errors");
+
+    engine
+        .execute("insert into schema_name.numbers values (4);")
+        .expect("no system errors");
+    engine
+        .execute("update schema_name.numbers set number = 4;")
+        .expect("no system errors");
+    engine
+        .execute("delete from schema_name.numbers;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::ForeignTableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::cannot_change_foreign_table(
+            "insert into",
+            "schema_name.numbers".to_owned(),
+        )),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::cannot_change_foreign_table(
+            "update",
+            "schema_name.numbers".to_owned(),
+        )),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::cannot_change_foreign_table(
+            "delete from",
+            "schema_name.numbers".to_owned(),
+        )),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn only_superuser_can_create_foreign_tables() {
+    let collector = sender();
+    let mut engine = QueryExecutor::new(in_memory_catalog_manager(), collector.clone(), "other");
+
+    engine
+        .execute("create foreign table schema_name.table_name (id smallint) server file;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Err(QueryError::must_be_superuser("create foreign tables")),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
@@ -0,0 +1,214 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use protocol::sql_types::PostgreSqlType;
+
+#[rstest::fixture]
+fn with_documents(sql_engine_with_schema: (QueryExecutor, ResultCollector)) -> (QueryExecutor, ResultCollector) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.docs (id smallint, body varchar(100), doc tsvector);")
+        .expect("no system errors");
+    engine
+        .execute(
+            "insert into schema_name.docs values \
+             (1, 'The fat cat sat on a mat', 'fat cat sat mat'), \
+             (2, 'Fat rats ate the cheese', 'rats:2 fat:1 cheese');",
+        )
+        .expect("no system errors");
+
+    (engine, collector)
+}
+
+#[rstest::rstest]
+fn stored_tsvector_is_normalized(with_documents: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = with_documents;
+    engine
+        .execute("select id, doc from schema_name.docs;")
+        .expect("no system errors");
+
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(2)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("id", PostgreSqlType::SmallInt).with_source(16384, 1),
+            ColumnDescription::new("doc", PostgreSqlType::TsVector).with_source(16384, 3),
+        ])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("1".to_owned()), Some("'cat' 'fat' 'mat' 'sat'".to_owned())],
+            vec![Some("2".to_owned()), Some("'cheese' 'fat':1 'rats':2".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(2)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn match_documents(with_documents: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = with_documents;
+    engine
+        .execute("select id from schema_name.docs where to_tsvector(body) @@ to_tsquery('fat & rats');")
+        .expect("no system errors");
+    engine
+        .execute("select id from schema_name.docs where doc @@ 'cat | cheese' and id > 1;")
+        .expect("no system errors");
+    engine
+        .execute("select id from schema_name.docs where not doc @@ 'cat';")
+        .expect("no system errors");
+
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(2)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "id",
+            PostgreSqlType::SmallInt,
+        )
+        .with_source(16384, 1)])),
+        Ok(QueryEvent::DataRows(vec![vec![Some("2".to_owned())]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "id",
+            PostgreSqlType::SmallInt,
+        )
+        .with_source(16384, 1)])),
+        Ok(QueryEvent::DataRows(vec![vec![Some("2".to_owned())]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "id",
+            PostgreSqlType::SmallInt,
+        )
+        .with_source(16384, 1)])),
+        Ok(QueryEvent::DataRows(vec![vec![Some("2".to_owned())]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn text_search_functions(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    engine
+        .execute("select to_tsvector('Fat cats, fat rats');")
+        .expect("no system errors");
+    engine
+        .execute("select pg_catalog.to_tsquery('Fat & (Cats | Rats)');")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "to_tsvector",
+            PostgreSqlType::TsVector,
+        )])),
+        Ok(QueryEvent::DataRows(vec![vec![Some(
+            "'cats':2 'fat':1,3 'rats':4".to_owned(),
+        )]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "to_tsquery",
+            PostgreSqlType::TsQuery,
+        )])),
+        Ok(QueryEvent::DataRows(vec![vec![Some(
+            "'fat' & ( 'cats' | 'rats' )".to_owned(),
+        )]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn text_search_in_expressions(with_documents: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = with_documents;
+    engine
+        .execute(
+            "select id, to_tsvector(body) @@ to_tsquery(lower(substring(body, 1, 3))), to_tsquery(substring(body, 5, 4)) \
+             from schema_name.docs where id = 2;",
+        )
+        .expect("no system errors");
+    engine
+        .execute("select id, doc @@ 'cheese' and not doc @@ 'cat' from schema_name.docs;")
+        .expect("no system errors");
+
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(2)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("id", PostgreSqlType::SmallInt).with_source(16384, 1),
+            ColumnDescription::new("?column?", PostgreSqlType::Bool),
+            ColumnDescription::new("to_tsquery", PostgreSqlType::TsQuery),
+        ])),
+        Ok(QueryEvent::DataRows(vec![vec![
+            Some("2".to_owned()),
+            Some("t".to_owned()),
+            Some("'rats'".to_owned()),
+        ]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("id", PostgreSqlType::SmallInt).with_source(16384, 1),
+            ColumnDescription::new("?column?", PostgreSqlType::Bool),
+        ])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("1".to_owned()), Some("f".to_owned())],
+            vec![Some("2".to_owned()), Some("t".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(2)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn malformed_text_search_values(with_documents: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = with_documents;
+    engine
+        .execute("select id from schema_name.docs where doc @@ to_tsquery('fat & | cat');")
+        .expect("no system errors");
+    engine
+        .execute("select id from schema_name.docs where id @@ to_tsquery('cat');")
+        .expect("no system errors");
+
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(2)),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::datatype_mismatch(
+            "syntax error in tsquery: \"fat & | cat\"".to_owned(),
+        )),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::undefined_function(
+            "@@".to_owned(),
+            "smallint".to_owned(),
+            "tsquery".to_owned(),
+        )),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
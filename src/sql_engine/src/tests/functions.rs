@@ -0,0 +1,392 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use protocol::sql_types::PostgreSqlType;
+
+const FACTORIAL: &str = "create function factorial(n integer) returns bigint as $$
+    declare
+        result bigint := 1;
+    begin
+        while n > 1 loop
+            result := result * n;
+            n := n - 1;
+        end loop;
+        return result;
+    end
+    $$ language plpgsql;";
+
+/// Events of a query that selects a single value
+fn selected(name: &str, pg_type: PostgreSqlType, value: Option<&str>) -> Vec<QueryResult> {
+    vec![
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(name, pg_type)])),
+        Ok(QueryEvent::DataRows(vec![vec![value.map(str::to_owned)]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+    ]
+}
+
+#[rstest::rstest]
+fn call_function(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    engine.execute(FACTORIAL).expect("no system errors");
+    engine.execute("select factorial(5);").expect("no system errors");
+    engine.execute("select Factorial(2 + 1);").expect("no system errors");
+
+    collector.assert_content(
+        [
+            vec![Ok(QueryEvent::FunctionCreated), Ok(QueryEvent::QueryComplete)],
+            selected("factorial", PostgreSqlType::BigInt, Some("120")),
+            selected("factorial", PostgreSqlType::BigInt, Some("6")),
+        ]
+        .concat(),
+    );
+}
+
+#[rstest::rstest]
+fn branches_loops_and_nulls(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    engine
+        .execute(
+            "create function describe(value integer) returns varchar(20) as '
+            declare
+                steps integer := 0;
+            begin
+                if value is null then
+                    return ''nothing'';
+                elsif value < 0 then
+                    return ''negative'';
+                end if;
+                loop
+                    exit when value < 10;
+                    value := value / 10;
+                    steps := steps + 1;
+                end loop;
+                return steps || '' steps'';
+            end' language plpgsql;",
+        )
+        .expect("no system errors");
+    engine.execute("select describe(null);").expect("no system errors");
+    engine.execute("select describe(-1);").expect("no system errors");
+    engine.execute("select describe(12345);").expect("no system errors");
+
+    collector.assert_content(
+        [
+            vec![Ok(QueryEvent::FunctionCreated), Ok(QueryEvent::QueryComplete)],
+            selected("describe", PostgreSqlType::VarChar, Some("nothing")),
+            selected("describe", PostgreSqlType::VarChar, Some("negative")),
+            selected("describe", PostgreSqlType::VarChar, Some("4 steps")),
+        ]
+        .concat(),
+    );
+}
+
+#[rstest::rstest]
+fn runtime_errors(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    engine
+        .execute(
+            "create function divide(dividend smallint, divisor smallint) returns smallint as $$
+            begin
+                if divisor = 0 then
+                    return dividend / divisor;
+                elsif divisor > 0 then
+                    return dividend * divisor;
+                end if;
+            end $$ language plpgsql;",
+        )
+        .expect("no system errors");
+    engine.execute("select divide(1, 0);").expect("no system errors");
+    engine.execute("select divide(1000, 1000);").expect("no system errors");
+    engine.execute("select divide(1, -1);").expect("no system errors");
+    engine.execute("select divide(1);").expect("no system errors");
+    engine.execute("select missing();").expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::FunctionCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "divide",
+            PostgreSqlType::SmallInt,
+        )])),
+        Err(QueryError::division_by_zero()),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "divide",
+            PostgreSqlType::SmallInt,
+        )])),
+        Err(QueryError::value_out_of_range(PostgreSqlType::SmallInt)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "divide",
+            PostgreSqlType::SmallInt,
+        )])),
+        Err(QueryError::function_without_return()),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::function_does_not_exist("divide(integer)".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::function_does_not_exist("missing()".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn create_replace_and_drop(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    engine.execute(FACTORIAL).expect("no system errors");
+    engine.execute(FACTORIAL).expect("no system errors");
+    engine
+        .execute(
+            "create or replace function factorial(n integer) returns bigint as 'begin return 0; end' language plpgsql;",
+        )
+        .expect("no system errors");
+    engine.execute("select factorial(5);").expect("no system errors");
+    engine
+        .execute("drop function factorial(integer);")
+        .expect("no system errors");
+    engine.execute("drop function factorial;").expect("no system errors");
+    engine
+        .execute("drop function if exists factorial;")
+        .expect("no system errors");
+
+    collector.assert_content(
+        [
+            vec![
+                Ok(QueryEvent::FunctionCreated),
+                Ok(QueryEvent::QueryComplete),
+                Err(QueryError::function_already_exists("factorial".to_owned())),
+                Ok(QueryEvent::QueryComplete),
+                Ok(QueryEvent::FunctionCreated),
+                Ok(QueryEvent::QueryComplete),
+            ],
+            selected("factorial", PostgreSqlType::BigInt, Some("0")),
+            vec![
+                Ok(QueryEvent::FunctionDropped),
+                Ok(QueryEvent::QueryComplete),
+                Err(QueryError::function_does_not_exist("factorial".to_owned())),
+                Ok(QueryEvent::QueryComplete),
+                Ok(QueryEvent::FunctionDropped),
+                Ok(QueryEvent::QueryComplete),
+            ],
+        ]
+        .concat(),
+    );
+}
+
+#[rstest::rstest]
+fn only_owner_can_replace_or_drop_function(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, _collector) = sql_engine;
+    engine.execute(FACTORIAL).expect("no system errors");
+    let collector = sender();
+    let mut other = QueryExecutor::new(engine.storage.clone(), collector.clone(), "other");
+    other.execute("select factorial(3);").expect("no system errors");
+    other
+        .execute("create or replace function factorial(n integer) returns bigint as 'begin return 0; end';")
+        .expect("no system errors");
+    other.execute("drop function factorial;").expect("no system errors");
+
+    collector.assert_content(
+        [
+            selected("factorial", PostgreSqlType::BigInt, Some("6")),
+            vec![
+                Err(QueryError::must_be_owner("function", "factorial".to_owned())),
+                Ok(QueryEvent::QueryComplete),
+                Err(QueryError::must_be_owner("function", "factorial".to_owned())),
+                Ok(QueryEvent::QueryComplete),
+            ],
+        ]
+        .concat(),
+    );
+}
+
+#[rstest::rstest]
+fn function_body_with_syntax_error(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    let sql = "create function broken() returns integer as $$ begin return 1; $$ language plpgsql;";
+    engine.execute(sql).expect("no system errors");
+
+    collector.assert_content(vec![Err(QueryError::syntax_error(format!(
+        "{:?} can't be parsed",
+        sql
+    )))]);
+}
+
+#[rstest::rstest]
+fn row_comparisons(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    engine
+        .execute(
+            "create function compare(a integer, b integer) returns varchar(20) as $$
+            begin
+                if (a, b) = (1, 2) then
+                    return 'equal';
+                elsif (a, b) in ((3, 4), (5, 6)) then
+                    return 'listed';
+                elsif (a, b) < (a, 10) then
+                    return 'less';
+                end if;
+                return (a, b) <> (a, null);
+            end $$ language plpgsql;",
+        )
+        .expect("no system errors");
+    engine.execute("select compare(1, 2);").expect("no system errors");
+    engine.execute("select compare(5, 6);").expect("no system errors");
+    engine.execute("select compare(7, 8);").expect("no system errors");
+    engine.execute("select compare(7, 12);").expect("no system errors");
+    engine
+        .execute("do $$ begin if (1, 2) = (1, 2, 3) then return; end if; end $$;")
+        .expect("no system errors");
+
+    collector.assert_content(
+        [
+            vec![Ok(QueryEvent::FunctionCreated), Ok(QueryEvent::QueryComplete)],
+            selected("compare", PostgreSqlType::VarChar, Some("equal")),
+            selected("compare", PostgreSqlType::VarChar, Some("listed")),
+            selected("compare", PostgreSqlType::VarChar, Some("less")),
+            selected("compare", PostgreSqlType::VarChar, None),
+            vec![
+                Err(QueryError::datatype_mismatch(
+                    "unequal number of entries in row expressions".to_owned(),
+                )),
+                Ok(QueryEvent::QueryComplete),
+            ],
+        ]
+        .concat(),
+    );
+}
+
+const INC: &str = "create function inc(n integer) returns integer as $$ begin return n + 1; end $$ language plpgsql;";
+
+#[rstest::rstest]
+fn functions_are_called_for_each_row(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine.execute(INC).expect("no system errors");
+    engine
+        .execute("create table schema_name.numbers (a integer);")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.numbers values (0), (1), (2);")
+        .expect("no system errors");
+    engine
+        .execute("select a, inc(a) from schema_name.numbers where inc(a) > 1;")
+        .expect("no system errors");
+    engine
+        .execute("update schema_name.numbers set a = 10 where inc(a) = 3;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::FunctionCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(3)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("a", PostgreSqlType::Integer).with_source(16384, 1),
+            ColumnDescription::new("inc", PostgreSqlType::Integer),
+        ])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("1".to_owned()), Some("2".to_owned())],
+            vec![Some("2".to_owned()), Some("3".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(2)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsUpdated(1)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn calls_in_expressions(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    engine.execute(INC).expect("no system errors");
+    engine
+        .execute("select inc(1), inc(2) as two, inc(inc(1)) * 10, upper('x');")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::FunctionCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("inc", PostgreSqlType::Integer),
+            ColumnDescription::new("two", PostgreSqlType::Integer),
+            ColumnDescription::new("?column?", PostgreSqlType::Integer),
+            ColumnDescription::new("upper", PostgreSqlType::VarChar),
+        ])),
+        Ok(QueryEvent::DataRows(vec![vec![
+            Some("2".to_owned()),
+            Some("3".to_owned()),
+            Some("30".to_owned()),
+            Some("X".to_owned()),
+        ]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn functions_call_functions(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    engine
+        .execute(
+            "create function fib(n integer) returns bigint as $$
+            begin
+                if n < 2 then
+                    return n;
+                end if;
+                return fib(n - 1) + fib(n - 2);
+            end $$ language plpgsql;",
+        )
+        .expect("no system errors");
+    engine
+        .execute("create function shout(value varchar(20)) returns varchar(20) as $$ begin return upper(value) || '!'; end $$;")
+        .expect("no system errors");
+    engine
+        .execute("create function endless(n integer) returns integer as $$ begin return endless(n + 1); end $$;")
+        .expect("no system errors");
+    engine.execute("select fib(10);").expect("no system errors");
+    engine.execute("select shout('hi');").expect("no system errors");
+    engine.execute("select endless(0);").expect("no system errors");
+    engine
+        .execute("do $$ begin if fib(5) <> 5 then raise exception 'wrong'; end if; end $$;")
+        .expect("no system errors");
+
+    collector.assert_content(
+        [
+            vec![
+                Ok(QueryEvent::FunctionCreated),
+                Ok(QueryEvent::QueryComplete),
+                Ok(QueryEvent::FunctionCreated),
+                Ok(QueryEvent::QueryComplete),
+                Ok(QueryEvent::FunctionCreated),
+                Ok(QueryEvent::QueryComplete),
+            ],
+            selected("fib", PostgreSqlType::BigInt, Some("55")),
+            selected("shout", PostgreSqlType::VarChar, Some("HI!")),
+            vec![
+                Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+                    "endless",
+                    PostgreSqlType::Integer,
+                )])),
+                Err(QueryError::stack_depth_limit_exceeded(32)),
+                Ok(QueryEvent::QueryComplete),
+                Ok(QueryEvent::BlockExecuted),
+                Ok(QueryEvent::QueryComplete),
+            ],
+        ]
+        .concat(),
+    );
+}
@@ -71,10 +71,13 @@ fn insert_and_select_single_row(sql_engine_with_schema: (QueryExecutor, ResultCo
         Ok(QueryEvent::QueryComplete),
         Ok(QueryEvent::RecordsInserted(1)),
         Ok(QueryEvent::QueryComplete),
-        Ok(QueryEvent::RecordsSelected((
-            vec![("column_test".to_owned(), PostgreSqlType::SmallInt)],
-            vec![vec!["123".to_owned()]],
-        ))),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "column_test",
+            PostgreSqlType::SmallInt,
+        )
+        .with_source(16384, 1)])),
+        Ok(QueryEvent::DataRows(vec![vec![Some("123".to_owned())]])),
+        Ok(QueryEvent::SelectComplete(1)),
         Ok(QueryEvent::QueryComplete),
     ]);
 }
@@ -106,17 +109,26 @@ fn insert_and_select_multiple_rows(sql_engine_with_schema: (QueryExecutor, Resul
         Ok(QueryEvent::QueryComplete),
         Ok(QueryEvent::RecordsInserted(1)),
         Ok(QueryEvent::QueryComplete),
-        Ok(QueryEvent::RecordsSelected((
-            vec![("column_test".to_owned(), PostgreSqlType::SmallInt)],
-            vec![vec!["123".to_owned()]],
-        ))),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "column_test",
+            PostgreSqlType::SmallInt,
+        )
+        .with_source(16384, 1)])),
+        Ok(QueryEvent::DataRows(vec![vec![Some("123".to_owned())]])),
+        Ok(QueryEvent::SelectComplete(1)),
         Ok(QueryEvent::QueryComplete),
         Ok(QueryEvent::RecordsInserted(1)),
         Ok(QueryEvent::QueryComplete),
-        Ok(QueryEvent::RecordsSelected((
-            vec![("column_test".to_owned(), PostgreSqlType::SmallInt)],
-            vec![vec!["123".to_owned()], vec!["456".to_owned()]],
-        ))),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "column_test",
+            PostgreSqlType::SmallInt,
+        )
+        .with_source(16384, 1)])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("123".to_owned())],
+            vec![Some("456".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(2)),
         Ok(QueryEvent::QueryComplete),
     ]);
 }
@@ -141,17 +153,16 @@ fn insert_and_select_named_columns(sql_engine_with_schema: (QueryExecutor, Resul
         Ok(QueryEvent::QueryComplete),
         Ok(QueryEvent::RecordsInserted(2)),
         Ok(QueryEvent::QueryComplete),
-        Ok(QueryEvent::RecordsSelected((
-            vec![
-                ("col1".to_owned(), PostgreSqlType::SmallInt),
-                ("col2".to_owned(), PostgreSqlType::SmallInt),
-                ("col3".to_owned(), PostgreSqlType::SmallInt),
-            ],
-            vec![
-                vec!["3".to_owned(), "1".to_owned(), "2".to_owned()],
-                vec!["6".to_owned(), "4".to_owned(), "5".to_owned()],
-            ],
-        ))),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("col1", PostgreSqlType::SmallInt).with_source(16384, 1),
+            ColumnDescription::new("col2", PostgreSqlType::SmallInt).with_source(16384, 2),
+            ColumnDescription::new("col3", PostgreSqlType::SmallInt).with_source(16384, 3),
+        ])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("3".to_owned()), Some("1".to_owned()), Some("2".to_owned())],
+            vec![Some("6".to_owned()), Some("4".to_owned()), Some("5".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(2)),
         Ok(QueryEvent::QueryComplete),
     ]);
 }
@@ -176,18 +187,17 @@ fn insert_multiple_rows(sql_engine_with_schema: (QueryExecutor, ResultCollector)
         Ok(QueryEvent::QueryComplete),
         Ok(QueryEvent::RecordsInserted(3)),
         Ok(QueryEvent::QueryComplete),
-        Ok(QueryEvent::RecordsSelected((
-            vec![
-                ("column_1".to_owned(), PostgreSqlType::SmallInt),
-                ("column_2".to_owned(), PostgreSqlType::SmallInt),
-                ("column_3".to_owned(), PostgreSqlType::SmallInt),
-            ],
-            vec![
-                vec!["1".to_owned(), "4".to_owned(), "7".to_owned()],
-                vec!["2".to_owned(), "5".to_owned(), "8".to_owned()],
-                vec!["3".to_owned(), "6".to_owned(), "9".to_owned()],
-            ],
-        ))),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("column_1", PostgreSqlType::SmallInt).with_source(16384, 1),
+            ColumnDescription::new("column_2", PostgreSqlType::SmallInt).with_source(16384, 2),
+            ColumnDescription::new("column_3", PostgreSqlType::SmallInt).with_source(16384, 3),
+        ])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("1".to_owned()), Some("4".to_owned()), Some("7".to_owned())],
+            vec![Some("2".to_owned()), Some("5".to_owned()), Some("8".to_owned())],
+            vec![Some("3".to_owned()), Some("6".to_owned()), Some("9".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(3)),
         Ok(QueryEvent::QueryComplete),
     ]);
 }
@@ -217,28 +227,27 @@ fn insert_and_select_different_integer_types(sql_engine_with_schema: (QueryExecu
         Ok(QueryEvent::QueryComplete),
         Ok(QueryEvent::RecordsInserted(1)),
         Ok(QueryEvent::QueryComplete),
-        Ok(QueryEvent::RecordsSelected((
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("column_si", PostgreSqlType::SmallInt).with_source(16384, 1),
+            ColumnDescription::new("column_i", PostgreSqlType::Integer).with_source(16384, 2),
+            ColumnDescription::new("column_bi", PostgreSqlType::BigInt).with_source(16384, 3),
+            ColumnDescription::new("column_serial", PostgreSqlType::Integer).with_source(16384, 4),
+        ])),
+        Ok(QueryEvent::DataRows(vec![
             vec![
-                ("column_si".to_owned(), PostgreSqlType::SmallInt),
-                ("column_i".to_owned(), PostgreSqlType::Integer),
-                ("column_bi".to_owned(), PostgreSqlType::BigInt),
-                ("column_serial".to_owned(), PostgreSqlType::Integer),
+                Some("-32768".to_owned()),
+                Some("-2147483648".to_owned()),
+                Some("-9223372036854775808".to_owned()),
+                Some("1".to_owned()),
             ],
             vec![
-                vec![
-                    "-32768".to_owned(),
-                    "-2147483648".to_owned(),
-                    "-9223372036854775808".to_owned(),
-                    "1".to_owned(),
-                ],
-                vec![
-                    "32767".to_owned(),
-                    "2147483647".to_owned(),
-                    "9223372036854775807".to_owned(),
-                    "1".to_owned(),
-                ],
+                Some("32767".to_owned()),
+                Some("2147483647".to_owned()),
+                Some("9223372036854775807".to_owned()),
+                Some("1".to_owned()),
             ],
-        ))),
+        ])),
+        Ok(QueryEvent::SelectComplete(2)),
         Ok(QueryEvent::QueryComplete),
     ]);
 }
@@ -268,16 +277,15 @@ fn insert_and_select_different_character_types(sql_engine_with_schema: (QueryExe
         Ok(QueryEvent::QueryComplete),
         Ok(QueryEvent::RecordsInserted(1)),
         Ok(QueryEvent::QueryComplete),
-        Ok(QueryEvent::RecordsSelected((
-            vec![
-                ("column_c".to_owned(), PostgreSqlType::Char),
-                ("column_vc".to_owned(), PostgreSqlType::VarChar),
-            ],
-            vec![
-                vec!["12345abcde".to_owned(), "12345abcde".to_owned()],
-                vec!["12345abcde".to_owned(), "abcde".to_owned()],
-            ],
-        ))),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("column_c", PostgreSqlType::Char).with_source(16384, 1),
+            ColumnDescription::new("column_vc", PostgreSqlType::VarChar).with_source(16384, 2),
+        ])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("12345abcde".to_owned()), Some("12345abcde".to_owned())],
+            vec![Some("12345abcde".to_owned()), Some("abcde".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(2)),
         Ok(QueryEvent::QueryComplete),
     ]);
 }
@@ -353,10 +361,13 @@ mod operators {
                     Ok(QueryEvent::QueryComplete),
                     Ok(QueryEvent::RecordsInserted(1)),
                     Ok(QueryEvent::QueryComplete),
-                    Ok(QueryEvent::RecordsSelected((
-                        vec![("column_si".to_owned(), PostgreSqlType::SmallInt)],
-                        vec![vec!["3".to_owned()]],
-                    ))),
+                    Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+                        "column_si",
+                        PostgreSqlType::SmallInt,
+                    )
+                    .with_source(16384, 1)])),
+                    Ok(QueryEvent::DataRows(vec![vec![Some("3".to_owned())]])),
+                    Ok(QueryEvent::SelectComplete(1)),
                     Ok(QueryEvent::QueryComplete),
                 ]);
             }
@@ -378,10 +389,13 @@ mod operators {
                     Ok(QueryEvent::QueryComplete),
                     Ok(QueryEvent::RecordsInserted(1)),
                     Ok(QueryEvent::QueryComplete),
-                    Ok(QueryEvent::RecordsSelected((
-                        vec![("column_si".to_owned(), PostgreSqlType::SmallInt)],
-                        vec![vec!["-1".to_owned()]],
-                    ))),
+                    Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+                        "column_si",
+                        PostgreSqlType::SmallInt,
+                    )
+                    .with_source(16384, 1)])),
+                    Ok(QueryEvent::DataRows(vec![vec![Some("-1".to_owned())]])),
+                    Ok(QueryEvent::SelectComplete(1)),
                     Ok(QueryEvent::QueryComplete),
                 ]);
             }
@@ -403,10 +417,13 @@ mod operators {
                     Ok(QueryEvent::QueryComplete),
                     Ok(QueryEvent::RecordsInserted(1)),
                     Ok(QueryEvent::QueryComplete),
-                    Ok(QueryEvent::RecordsSelected((
-                        vec![("column_si".to_owned(), PostgreSqlType::SmallInt)],
-                        vec![vec!["6".to_owned()]],
-                    ))),
+                    Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+                        "column_si",
+                        PostgreSqlType::SmallInt,
+                    )
+                    .with_source(16384, 1)])),
+                    Ok(QueryEvent::DataRows(vec![vec![Some("6".to_owned())]])),
+                    Ok(QueryEvent::SelectComplete(1)),
                     Ok(QueryEvent::QueryComplete),
                 ]);
             }
@@ -428,10 +445,13 @@ mod operators {
                     Ok(QueryEvent::QueryComplete),
                     Ok(QueryEvent::RecordsInserted(1)),
                     Ok(QueryEvent::QueryComplete),
-                    Ok(QueryEvent::RecordsSelected((
-                        vec![("column_si".to_owned(), PostgreSqlType::SmallInt)],
-                        vec![vec!["4".to_owned()]],
-                    ))),
+                    Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+                        "column_si",
+                        PostgreSqlType::SmallInt,
+                    )
+                    .with_source(16384, 1)])),
+                    Ok(QueryEvent::DataRows(vec![vec![Some("4".to_owned())]])),
+                    Ok(QueryEvent::SelectComplete(1)),
                     Ok(QueryEvent::QueryComplete),
                 ]);
             }
@@ -453,10 +473,13 @@ mod operators {
                     Ok(QueryEvent::QueryComplete),
                     Ok(QueryEvent::RecordsInserted(1)),
                     Ok(QueryEvent::QueryComplete),
-                    Ok(QueryEvent::RecordsSelected((
-                        vec![("column_si".to_owned(), PostgreSqlType::SmallInt)],
-                        vec![vec!["0".to_owned()]],
-                    ))),
+                    Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+                        "column_si",
+                        PostgreSqlType::SmallInt,
+                    )
+                    .with_source(16384, 1)])),
+                    Ok(QueryEvent::DataRows(vec![vec![Some("0".to_owned())]])),
+                    Ok(QueryEvent::SelectComplete(1)),
                     Ok(QueryEvent::QueryComplete),
                 ]);
             }
@@ -481,10 +504,12 @@ mod operators {
                     Ok(QueryEvent::QueryComplete),
                     Ok(QueryEvent::RecordsInserted(1)),
                     Ok(QueryEvent::QueryComplete),
-                    Ok(QueryEvent::RecordsSelected((
-                        vec![("column_si".to_owned(), PostgreSqlType::SmallInt)],
-                        vec![vec!["64".to_owned()]],
-                    ))),
+                    Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+                        "column_si",
+                        PostgreSqlType::SmallInt,
+                    )])),
+                    Ok(QueryEvent::DataRows(vec![vec![Some("64".to_owned())]])),
+                    Ok(QueryEvent::SelectComplete(1)),
                     Ok(QueryEvent::QueryComplete),
                 ]);
             }
@@ -508,10 +533,12 @@ mod operators {
                     Ok(QueryEvent::QueryComplete),
                     Ok(QueryEvent::RecordsInserted(1)),
                     Ok(QueryEvent::QueryComplete),
-                    Ok(QueryEvent::RecordsSelected((
-                        vec![("column_si".to_owned(), PostgreSqlType::SmallInt)],
-                        vec![vec!["4".to_owned()]],
-                    ))),
+                    Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+                        "column_si",
+                        PostgreSqlType::SmallInt,
+                    )])),
+                    Ok(QueryEvent::DataRows(vec![vec![Some("4".to_owned())]])),
+                    Ok(QueryEvent::SelectComplete(1)),
                     Ok(QueryEvent::QueryComplete),
                 ]);
             }
@@ -535,10 +562,12 @@ mod operators {
                     Ok(QueryEvent::QueryComplete),
                     Ok(QueryEvent::RecordsInserted(1)),
                     Ok(QueryEvent::QueryComplete),
-                    Ok(QueryEvent::RecordsSelected((
-                        vec![("column_si".to_owned(), PostgreSqlType::SmallInt)],
-                        vec![vec!["2".to_owned()]],
-                    ))),
+                    Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+                        "column_si",
+                        PostgreSqlType::SmallInt,
+                    )])),
+                    Ok(QueryEvent::DataRows(vec![vec![Some("2".to_owned())]])),
+                    Ok(QueryEvent::SelectComplete(1)),
                     Ok(QueryEvent::QueryComplete),
                 ]);
             }
@@ -562,10 +591,12 @@ mod operators {
                     Ok(QueryEvent::QueryComplete),
                     Ok(QueryEvent::RecordsInserted(1)),
                     Ok(QueryEvent::QueryComplete),
-                    Ok(QueryEvent::RecordsSelected((
-                        vec![("column_si".to_owned(), PostgreSqlType::SmallInt)],
-                        vec![vec!["120".to_owned()]],
-                    ))),
+                    Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+                        "column_si",
+                        PostgreSqlType::SmallInt,
+                    )])),
+                    Ok(QueryEvent::DataRows(vec![vec![Some("120".to_owned())]])),
+                    Ok(QueryEvent::SelectComplete(1)),
                     Ok(QueryEvent::QueryComplete),
                 ]);
             }
@@ -589,10 +620,12 @@ mod operators {
                     Ok(QueryEvent::QueryComplete),
                     Ok(QueryEvent::RecordsInserted(1)),
                     Ok(QueryEvent::QueryComplete),
-                    Ok(QueryEvent::RecordsSelected((
-                        vec![("column_si".to_owned(), PostgreSqlType::SmallInt)],
-                        vec![vec!["120".to_owned()]],
-                    ))),
+                    Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+                        "column_si",
+                        PostgreSqlType::SmallInt,
+                    )])),
+                    Ok(QueryEvent::DataRows(vec![vec![Some("120".to_owned())]])),
+                    Ok(QueryEvent::SelectComplete(1)),
                     Ok(QueryEvent::QueryComplete),
                 ]);
             }
@@ -616,10 +649,12 @@ mod operators {
                     Ok(QueryEvent::QueryComplete),
                     Ok(QueryEvent::RecordsInserted(1)),
                     Ok(QueryEvent::QueryComplete),
-                    Ok(QueryEvent::RecordsSelected((
-                        vec![("column_si".to_owned(), PostgreSqlType::SmallInt)],
-                        vec![vec!["5".to_owned()]],
-                    ))),
+                    Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+                        "column_si",
+                        PostgreSqlType::SmallInt,
+                    )])),
+                    Ok(QueryEvent::DataRows(vec![vec![Some("5".to_owned())]])),
+                    Ok(QueryEvent::SelectComplete(1)),
                     Ok(QueryEvent::QueryComplete),
                 ]);
             }
@@ -641,10 +676,13 @@ mod operators {
                     Ok(QueryEvent::QueryComplete),
                     Ok(QueryEvent::RecordsInserted(1)),
                     Ok(QueryEvent::QueryComplete),
-                    Ok(QueryEvent::RecordsSelected((
-                        vec![("column_si".to_owned(), PostgreSqlType::SmallInt)],
-                        vec![vec!["1".to_owned()]],
-                    ))),
+                    Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+                        "column_si",
+                        PostgreSqlType::SmallInt,
+                    )
+                    .with_source(16384, 1)])),
+                    Ok(QueryEvent::DataRows(vec![vec![Some("1".to_owned())]])),
+                    Ok(QueryEvent::SelectComplete(1)),
                     Ok(QueryEvent::QueryComplete),
                 ]);
             }
@@ -666,10 +704,13 @@ mod operators {
                     Ok(QueryEvent::QueryComplete),
                     Ok(QueryEvent::RecordsInserted(1)),
                     Ok(QueryEvent::QueryComplete),
-                    Ok(QueryEvent::RecordsSelected((
-                        vec![("column_si".to_owned(), PostgreSqlType::SmallInt)],
-                        vec![vec!["7".to_owned()]],
-                    ))),
+                    Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+                        "column_si",
+                        PostgreSqlType::SmallInt,
+                    )
+                    .with_source(16384, 1)])),
+                    Ok(QueryEvent::DataRows(vec![vec![Some("7".to_owned())]])),
+                    Ok(QueryEvent::SelectComplete(1)),
                     Ok(QueryEvent::QueryComplete),
                 ]);
             }
@@ -693,10 +734,12 @@ mod operators {
                     Ok(QueryEvent::QueryComplete),
                     Ok(QueryEvent::RecordsInserted(1)),
                     Ok(QueryEvent::QueryComplete),
-                    Ok(QueryEvent::RecordsSelected((
-                        vec![("column_si".to_owned(), PostgreSqlType::SmallInt)],
-                        vec![vec!["-2".to_owned()]],
-                    ))),
+                    Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+                        "column_si",
+                        PostgreSqlType::SmallInt,
+                    )])),
+                    Ok(QueryEvent::DataRows(vec![vec![Some("-2".to_owned())]])),
+                    Ok(QueryEvent::SelectComplete(1)),
                     Ok(QueryEvent::QueryComplete),
                 ]);
             }
@@ -720,10 +763,12 @@ mod operators {
                     Ok(QueryEvent::QueryComplete),
                     Ok(QueryEvent::RecordsInserted(1)),
                     Ok(QueryEvent::QueryComplete),
-                    Ok(QueryEvent::RecordsSelected((
-                        vec![("column_si".to_owned(), PostgreSqlType::SmallInt)],
-                        vec![vec!["16".to_owned()]],
-                    ))),
+                    Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+                        "column_si",
+                        PostgreSqlType::SmallInt,
+                    )])),
+                    Ok(QueryEvent::DataRows(vec![vec![Some("16".to_owned())]])),
+                    Ok(QueryEvent::SelectComplete(1)),
                     Ok(QueryEvent::QueryComplete),
                 ]);
             }
@@ -747,10 +792,12 @@ mod operators {
                     Ok(QueryEvent::QueryComplete),
                     Ok(QueryEvent::RecordsInserted(1)),
                     Ok(QueryEvent::QueryComplete),
-                    Ok(QueryEvent::RecordsSelected((
-                        vec![("column_si".to_owned(), PostgreSqlType::SmallInt)],
-                        vec![vec!["2".to_owned()]],
-                    ))),
+                    Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+                        "column_si",
+                        PostgreSqlType::SmallInt,
+                    )])),
+                    Ok(QueryEvent::DataRows(vec![vec![Some("2".to_owned())]])),
+                    Ok(QueryEvent::SelectComplete(1)),
                     Ok(QueryEvent::QueryComplete),
                 ]);
             }
@@ -772,10 +819,13 @@ mod operators {
                     Ok(QueryEvent::QueryComplete),
                     Ok(QueryEvent::RecordsInserted(1)),
                     Ok(QueryEvent::QueryComplete),
-                    Ok(QueryEvent::RecordsSelected((
-                        vec![("column_si".to_owned(), PostgreSqlType::SmallInt)],
-                        vec![vec!["5".to_owned()]],
-                    ))),
+                    Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+                        "column_si",
+                        PostgreSqlType::SmallInt,
+                    )
+                    .with_source(16384, 1)])),
+                    Ok(QueryEvent::DataRows(vec![vec![Some("5".to_owned())]])),
+                    Ok(QueryEvent::SelectComplete(1)),
                     Ok(QueryEvent::QueryComplete),
                 ]);
             }
@@ -813,10 +863,13 @@ mod operators {
                 Ok(QueryEvent::QueryComplete),
                 Ok(QueryEvent::RecordsInserted(1)),
                 Ok(QueryEvent::QueryComplete),
-                Ok(QueryEvent::RecordsSelected((
-                    vec![("strings".to_owned(), PostgreSqlType::Char)],
-                    vec![vec!["12345".to_owned()]],
-                ))),
+                Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+                    "strings",
+                    PostgreSqlType::Char,
+                )
+                .with_source(16384, 1)])),
+                Ok(QueryEvent::DataRows(vec![vec![Some("12345".to_owned())]])),
+                Ok(QueryEvent::SelectComplete(1)),
                 Ok(QueryEvent::QueryComplete),
             ]);
         }
@@ -843,10 +896,16 @@ mod operators {
                 Ok(QueryEvent::QueryComplete),
                 Ok(QueryEvent::RecordsInserted(1)),
                 Ok(QueryEvent::QueryComplete),
-                Ok(QueryEvent::RecordsSelected((
-                    vec![("strings".to_owned(), PostgreSqlType::Char)],
-                    vec![vec!["145".to_owned()], vec!["451".to_owned()]],
-                ))),
+                Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+                    "strings",
+                    PostgreSqlType::Char,
+                )
+                .with_source(16384, 1)])),
+                Ok(QueryEvent::DataRows(vec![
+                    vec![Some("145".to_owned())],
+                    vec![Some("451".to_owned())],
+                ])),
+                Ok(QueryEvent::SelectComplete(2)),
                 Ok(QueryEvent::QueryComplete),
             ]);
         }
@@ -0,0 +1,135 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use protocol::sql_types::PostgreSqlType;
+
+fn selected(name: &str, pg_type: PostgreSqlType, value: &str) -> Vec<QueryResult> {
+    vec![
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(name, pg_type)])),
+        Ok(QueryEvent::DataRows(vec![vec![Some(value.to_owned())]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+    ]
+}
+
+fn failed(name: &str, pg_type: PostgreSqlType, error: QueryError) -> Vec<QueryResult> {
+    vec![
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(name, pg_type)])),
+        Err(error),
+        Ok(QueryEvent::QueryComplete),
+    ]
+}
+
+#[rstest::rstest]
+fn write_and_read_large_object(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    engine.execute("select lo_create(0);").expect("no system errors");
+    engine
+        .execute("select lo_open(16384, 393216);")
+        .expect("no system errors");
+    engine
+        .execute("select lowrite(0, '\\x48656c6c6f');")
+        .expect("no system errors");
+    engine
+        .execute("select lowrite(0, ', world');")
+        .expect("no system errors");
+    engine.execute("select lo_close(0);").expect("no system errors");
+    engine
+        .execute("select lo_open(16384, 262144);")
+        .expect("no system errors");
+    engine.execute("select loread(0, 5);").expect("no system errors");
+    engine.execute("select loread(0, 100);").expect("no system errors");
+    engine.execute("select loread(0, 100);").expect("no system errors");
+    engine.execute("select lowrite(0, 'more');").expect("no system errors");
+    engine.execute("select lo_unlink(16384);").expect("no system errors");
+    engine.execute("select loread(0, 1);").expect("no system errors");
+
+    collector.assert_content(
+        [
+            selected("lo_create", PostgreSqlType::Integer, "16384"),
+            selected("lo_open", PostgreSqlType::Integer, "0"),
+            selected("lowrite", PostgreSqlType::Integer, "5"),
+            selected("lowrite", PostgreSqlType::Integer, "7"),
+            selected("lo_close", PostgreSqlType::Integer, "0"),
+            selected("lo_open", PostgreSqlType::Integer, "0"),
+            selected("loread", PostgreSqlType::VarChar, "\\x48656c6c6f"),
+            selected("loread", PostgreSqlType::VarChar, "\\x2c20776f726c64"),
+            selected("loread", PostgreSqlType::VarChar, "\\x"),
+            failed(
+                "lowrite",
+                PostgreSqlType::Integer,
+                QueryError::large_object_not_opened_for_writing(0),
+            ),
+            selected("lo_unlink", PostgreSqlType::Integer, "1"),
+            failed(
+                "loread",
+                PostgreSqlType::VarChar,
+                QueryError::large_object_does_not_exist(16384),
+            ),
+        ]
+        .concat(),
+    );
+}
+
+#[rstest::rstest]
+fn large_object_errors(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    engine.execute("select lo_create(20000);").expect("no system errors");
+    engine.execute("select lo_create(20000);").expect("no system errors");
+    engine
+        .execute("select lo_open(30000, 262144);")
+        .expect("no system errors");
+    engine.execute("select lo_open(20000, 1);").expect("no system errors");
+    engine.execute("select loread(3, 10);").expect("no system errors");
+    engine.execute("select lo_close(3);").expect("no system errors");
+    engine.execute("select lo_unlink(30000);").expect("no system errors");
+
+    collector.assert_content(
+        [
+            selected("lo_create", PostgreSqlType::Integer, "20000"),
+            failed(
+                "lo_create",
+                PostgreSqlType::Integer,
+                QueryError::large_object_already_exists(20000),
+            ),
+            failed(
+                "lo_open",
+                PostgreSqlType::Integer,
+                QueryError::large_object_does_not_exist(30000),
+            ),
+            failed(
+                "lo_open",
+                PostgreSqlType::Integer,
+                QueryError::invalid_parameter_value("invalid flags for opening a large object: 1".to_owned()),
+            ),
+            failed(
+                "loread",
+                PostgreSqlType::VarChar,
+                QueryError::invalid_large_object_descriptor(3),
+            ),
+            failed(
+                "lo_close",
+                PostgreSqlType::Integer,
+                QueryError::invalid_large_object_descriptor(3),
+            ),
+            failed(
+                "lo_unlink",
+                PostgreSqlType::Integer,
+                QueryError::large_object_does_not_exist(30000),
+            ),
+        ]
+        .concat(),
+    );
+}
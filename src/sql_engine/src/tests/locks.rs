@@ -0,0 +1,131 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use std::{thread, time::Duration};
+
+fn session(storage: &Arc<CatalogManager>) -> (QueryExecutor, ResultCollector) {
+    let collector = sender();
+    (
+        QueryExecutor::new(storage.clone(), collector.clone(), SUPERUSER),
+        collector,
+    )
+}
+
+fn storage_with_tables() -> Arc<CatalogManager> {
+    let storage = in_memory_catalog_manager();
+    let (mut engine, _) = session(&storage);
+    for query in &[
+        "create schema schema_name;",
+        "create table schema_name.first (id smallint);",
+        "create table schema_name.second (id smallint);",
+        "insert into schema_name.first values (1);",
+        "insert into schema_name.second values (2);",
+    ] {
+        engine.execute(query).expect("no system errors");
+    }
+    storage
+}
+
+fn completed(event: QueryResult) -> Vec<QueryResult> {
+    vec![event, Ok(QueryEvent::QueryComplete)]
+}
+
+#[test]
+fn drop_table_waits_for_transaction_that_modified_it() {
+    let storage = storage_with_tables();
+    let (mut writer, _) = session(&storage);
+    writer.execute("begin;").expect("no system errors");
+    writer
+        .execute("delete from schema_name.first;")
+        .expect("no system errors");
+
+    let (mut dropper, dropped) = session(&storage);
+    let waiter = thread::spawn(move || dropper.execute("drop table schema_name.first;"));
+    thread::sleep(Duration::from_millis(50));
+    dropped.assert_content(vec![]);
+
+    writer.execute("commit;").expect("no system errors");
+    waiter.join().expect("dropper to finish").expect("no system errors");
+
+    dropped.assert_content(completed(Ok(QueryEvent::TableDropped)));
+}
+
+#[test]
+fn one_of_deadlocked_transactions_is_aborted() {
+    let storage = storage_with_tables();
+    let (mut first, first_results) = session(&storage);
+    let (mut second, second_results) = session(&storage);
+    first.execute("begin;").expect("no system errors");
+    first
+        .execute("update schema_name.first set id = 3;")
+        .expect("no system errors");
+    second.execute("begin;").expect("no system errors");
+    second
+        .execute("update schema_name.second set id = 4;")
+        .expect("no system errors");
+
+    let waiter = thread::spawn(move || {
+        second
+            .execute("delete from schema_name.first;")
+            .expect("no system errors");
+        second.execute("commit;").expect("no system errors");
+    });
+    thread::sleep(Duration::from_millis(50));
+    first
+        .execute("delete from schema_name.second;")
+        .expect("no system errors");
+    first.execute("rollback;").expect("no system errors");
+    waiter.join().expect("second transaction to finish");
+
+    let mut expected = completed(Ok(QueryEvent::TransactionStarted));
+    expected.extend(completed(Ok(QueryEvent::RecordsUpdated(1))));
+    expected.extend(completed(Err(QueryError::deadlock_detected())));
+    expected.extend(completed(Ok(QueryEvent::TransactionRolledBack)));
+    first_results.assert_content(expected);
+
+    let mut expected = completed(Ok(QueryEvent::TransactionStarted));
+    expected.extend(completed(Ok(QueryEvent::RecordsUpdated(1))));
+    expected.extend(completed(Ok(QueryEvent::RecordsDeleted(1))));
+    expected.extend(completed(Ok(QueryEvent::TransactionCommitted)));
+    second_results.assert_content(expected);
+}
+
+#[test]
+fn drop_schema_waits_for_transaction_that_reads_its_table() {
+    let storage = storage_with_tables();
+    let (mut reader, read) = session(&storage);
+    reader.execute("begin;").expect("no system errors");
+    reader
+        .execute("select id from schema_name.first;")
+        .expect("no system errors");
+
+    let (mut dropper, dropped) = session(&storage);
+    let waiter = thread::spawn(move || dropper.execute("drop schema schema_name cascade;"));
+    thread::sleep(Duration::from_millis(50));
+    dropped.assert_content(vec![]);
+
+    reader.execute("commit;").expect("no system errors");
+    waiter.join().expect("dropper to finish").expect("no system errors");
+    dropped.assert_content(completed(Ok(QueryEvent::SchemaDropped)));
+
+    reader
+        .execute("select id from schema_name.first;")
+        .expect("no system errors");
+    let results = read.0.lock().expect("locked");
+    assert_eq!(
+        results[results.len() - 2..],
+        completed(Err(QueryError::schema_does_not_exist("schema_name".to_owned())))[..]
+    );
+}
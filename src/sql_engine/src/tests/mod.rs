@@ -12,33 +12,114 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+#[cfg(test)]
+mod aggregates;
+#[cfg(test)]
+mod alter_table;
+#[cfg(test)]
+mod anonymous_blocks;
+#[cfg(test)]
+mod audit;
+#[cfg(test)]
+mod autovacuum;
 #[cfg(test)]
 mod bind;
 #[cfg(test)]
 mod bind_prepared_statement_to_portal;
 #[cfg(test)]
+mod builtins;
+#[cfg(test)]
+mod cancellation;
+#[cfg(test)]
+mod cluster;
+#[cfg(test)]
+mod copy;
+#[cfg(test)]
 mod delete;
 #[cfg(test)]
+mod describe_portal;
+#[cfg(test)]
 mod describe_prepared_statement;
 #[cfg(test)]
+mod disconnected_client;
+#[cfg(test)]
+mod dump;
+#[cfg(test)]
+mod embedded;
+#[cfg(test)]
+mod empty_query;
+#[cfg(test)]
+mod event_triggers;
+#[cfg(test)]
 mod execute_portal;
 #[cfg(test)]
+mod explain;
+#[cfg(test)]
+mod extensions;
+#[cfg(test)]
+mod foreign_tables;
+#[cfg(test)]
+mod full_text_search;
+#[cfg(test)]
+mod functions;
+#[cfg(test)]
 mod insert;
 #[cfg(test)]
+mod large_objects;
+#[cfg(test)]
+mod locks;
+#[cfg(test)]
+mod null_values;
+#[cfg(test)]
+mod order_by;
+#[cfg(test)]
 mod parse_prepared_statement;
 #[cfg(test)]
+mod plan_invalidation;
+#[cfg(test)]
+mod privileges;
+#[cfg(test)]
+mod publications;
+#[cfg(test)]
+mod recursive_queries;
+#[cfg(test)]
+mod result_cache;
+#[cfg(test)]
+mod roles;
+#[cfg(test)]
 mod schema;
 #[cfg(test)]
 mod select;
 #[cfg(test)]
+mod set_operations;
+#[cfg(test)]
+mod settings;
+#[cfg(test)]
+mod statistics;
+#[cfg(test)]
+mod string_functions;
+#[cfg(test)]
+mod subqueries;
+#[cfg(test)]
 mod table;
 #[cfg(test)]
+mod tracing;
+#[cfg(test)]
+mod transactions;
+#[cfg(test)]
 mod type_constraints;
 #[cfg(test)]
 mod update;
+#[cfg(test)]
+mod values_lists;
+#[cfg(test)]
+mod wasm_functions;
 
 use super::*;
-use crate::{catalog_manager::CatalogManager, QueryExecutor};
+use crate::{
+    catalog_manager::{CatalogManager, SUPERUSER},
+    QueryExecutor,
+};
 use protocol::results::{QueryError, QueryResult};
 use std::{
     io,
@@ -86,7 +167,7 @@ fn sender() -> ResultCollector {
 fn sql_engine() -> (QueryExecutor, ResultCollector) {
     let collector = Arc::new(Collector(Mutex::new(vec![])));
     (
-        QueryExecutor::new(in_memory_catalog_manager(), collector.clone()),
+        QueryExecutor::new(in_memory_catalog_manager(), collector.clone(), SUPERUSER),
         collector,
     )
 }
@@ -0,0 +1,185 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use super::*;
+use protocol::sql_types::PostgreSqlType;
+
+#[rstest::rstest]
+fn null_is_distinct_from_empty_string(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 varchar(5), column_2 varchar(5));")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name values (null, ''), ('NULL', null);")
+        .expect("no system errors");
+    engine
+        .execute("select * from schema_name.table_name;")
+        .expect("no system errors");
+
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(2)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("column_1", PostgreSqlType::VarChar).with_source(16384, 1),
+            ColumnDescription::new("column_2", PostgreSqlType::VarChar).with_source(16384, 2),
+        ])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![None, Some("".to_owned())],
+            vec![Some("NULL".to_owned()), None],
+        ])),
+        Ok(QueryEvent::SelectComplete(2)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn arithmetic_with_null_gives_null(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint, column_2 smallint);")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name values (1 + null, 2);")
+        .expect("no system errors");
+    engine
+        .execute("select * from schema_name.table_name;")
+        .expect("no system errors");
+
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(1)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("column_1", PostgreSqlType::SmallInt).with_source(16384, 1),
+            ColumnDescription::new("column_2", PostgreSqlType::SmallInt).with_source(16384, 2),
+        ])),
+        Ok(QueryEvent::DataRows(vec![vec![None, Some("2".to_owned())]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn update_column_to_null(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint, column_2 smallint);")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name values (1, 2);")
+        .expect("no system errors");
+    engine
+        .execute("update schema_name.table_name set column_2 = null;")
+        .expect("no system errors");
+    engine
+        .execute("select * from schema_name.table_name;")
+        .expect("no system errors");
+
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(1)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsUpdated(1)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("column_1", PostgreSqlType::SmallInt).with_source(16384, 1),
+            ColumnDescription::new("column_2", PostgreSqlType::SmallInt).with_source(16384, 2),
+        ])),
+        Ok(QueryEvent::DataRows(vec![vec![Some("1".to_owned()), None]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn conditions_with_nulls(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 varchar(5), column_2 smallint);")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name values (null, 1), ('', 2), (null, 3), ('x', null);")
+        .expect("no system errors");
+    engine
+        .execute("select column_2 from schema_name.table_name where column_1 is null or column_1 = '';")
+        .expect("no system errors");
+    engine
+        .execute("select column_2 from schema_name.table_name where not column_1 = 'x';")
+        .expect("no system errors");
+    engine
+        .execute("select column_2 from schema_name.table_name where column_1 = 'x' or column_2 > 1;")
+        .expect("no system errors");
+    engine
+        .execute("select column_1 is null, column_2 is not null, column_1 = '' from schema_name.table_name;")
+        .expect("no system errors");
+
+    let column_2 = || {
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "column_2",
+            PostgreSqlType::SmallInt,
+        )
+        .with_source(16384, 2)]))
+    };
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(4)),
+        Ok(QueryEvent::QueryComplete),
+        column_2(),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("1".to_owned())],
+            vec![Some("2".to_owned())],
+            vec![Some("3".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(3)),
+        Ok(QueryEvent::QueryComplete),
+        column_2(),
+        Ok(QueryEvent::DataRows(vec![vec![Some("2".to_owned())]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+        column_2(),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("2".to_owned())],
+            vec![Some("3".to_owned())],
+            vec![None],
+        ])),
+        Ok(QueryEvent::SelectComplete(3)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("?column?", PostgreSqlType::Bool),
+            ColumnDescription::new("?column?", PostgreSqlType::Bool),
+            ColumnDescription::new("?column?", PostgreSqlType::Bool),
+        ])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("t".to_owned()), Some("t".to_owned()), None],
+            vec![Some("f".to_owned()), Some("t".to_owned()), Some("t".to_owned())],
+            vec![Some("t".to_owned()), Some("t".to_owned()), None],
+            vec![Some("f".to_owned()), Some("f".to_owned()), Some("f".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(4)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
@@ -0,0 +1,204 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use protocol::sql_types::PostgreSqlType;
+
+fn column(name: &str, attnum: i16) -> ColumnDescription {
+    ColumnDescription::new(name, PostgreSqlType::SmallInt).with_source(16384, attnum)
+}
+
+fn rows(values: &[[Option<i16>; 2]]) -> Vec<Vec<Option<String>>> {
+    values
+        .iter()
+        .map(|row| row.iter().map(|value| value.map(|value| value.to_string())).collect())
+        .collect()
+}
+
+#[rstest::fixture]
+fn with_table(sql_engine_with_schema: (QueryExecutor, ResultCollector)) -> (QueryExecutor, ResultCollector) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint, column_2 smallint);")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name values (1, 3), (2, null), (1, 1), (null, 2), (2, 5);")
+        .expect("no system errors");
+    (engine, collector)
+}
+
+const TABLE_CREATED: [QueryResult; 6] = [
+    Ok(QueryEvent::SchemaCreated),
+    Ok(QueryEvent::QueryComplete),
+    Ok(QueryEvent::TableCreated),
+    Ok(QueryEvent::QueryComplete),
+    Ok(QueryEvent::RecordsInserted(5)),
+    Ok(QueryEvent::QueryComplete),
+];
+
+#[rstest::rstest]
+fn keys_with_mixed_directions(with_table: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = with_table;
+    engine
+        .execute("select * from schema_name.table_name order by column_1 desc, column_2;")
+        .expect("no system errors");
+    engine
+        .execute("select * from schema_name.table_name order by column_1 nulls first, column_2 desc nulls last;")
+        .expect("no system errors");
+
+    let mut expected = TABLE_CREATED.to_vec();
+    expected.extend(vec![
+        Ok(QueryEvent::RowDescription(vec![
+            column("column_1", 1),
+            column("column_2", 2),
+        ])),
+        Ok(QueryEvent::DataRows(rows(&[
+            [None, Some(2)],
+            [Some(2), Some(5)],
+            [Some(2), None],
+            [Some(1), Some(1)],
+            [Some(1), Some(3)],
+        ]))),
+        Ok(QueryEvent::SelectComplete(5)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            column("column_1", 1),
+            column("column_2", 2),
+        ])),
+        Ok(QueryEvent::DataRows(rows(&[
+            [None, Some(2)],
+            [Some(1), Some(3)],
+            [Some(1), Some(1)],
+            [Some(2), Some(5)],
+            [Some(2), None],
+        ]))),
+        Ok(QueryEvent::SelectComplete(5)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+    collector.assert_content_for_single_queries(expected);
+}
+
+#[rstest::rstest]
+fn expressions_aliases_and_positions(with_table: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = with_table;
+    engine
+        .execute("select column_1 from schema_name.table_name order by column_2 * -1 nulls last limit 3;")
+        .expect("no system errors");
+    engine
+        .execute(
+            "select column_1 as first, column_2 from schema_name.table_name \
+             where column_1 is not null order by first, 2 desc;",
+        )
+        .expect("no system errors");
+    engine
+        .execute("select column_1 from schema_name.table_name order by 2;")
+        .expect("no system errors");
+
+    let mut expected = TABLE_CREATED.to_vec();
+    expected.extend(vec![
+        Ok(QueryEvent::RowDescription(vec![column("column_1", 1)])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("2".to_owned())],
+            vec![Some("1".to_owned())],
+            vec![None],
+        ])),
+        Ok(QueryEvent::SelectComplete(3)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            column("first", 1),
+            column("column_2", 2),
+        ])),
+        Ok(QueryEvent::DataRows(rows(&[
+            [Some(1), Some(3)],
+            [Some(1), Some(1)],
+            [Some(2), None],
+            [Some(2), Some(5)],
+        ]))),
+        Ok(QueryEvent::SelectComplete(4)),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::invalid_column_reference(
+            "ORDER BY position 2 is not in select list".to_owned(),
+        )),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+    collector.assert_content_for_single_queries(expected);
+}
+
+#[rstest::rstest]
+fn groups_and_set_operations(with_table: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = with_table;
+    engine
+        .execute("select column_1, count(*) from schema_name.table_name group by column_1 order by count(*) desc, 1;")
+        .expect("no system errors");
+    engine
+        .execute(
+            "select column_1 from schema_name.table_name union select column_2 from schema_name.table_name \
+             order by column_1 desc;",
+        )
+        .expect("no system errors");
+
+    let mut expected = TABLE_CREATED.to_vec();
+    expected.extend(vec![
+        Ok(QueryEvent::RowDescription(vec![
+            column("column_1", 1),
+            ColumnDescription::new("count", PostgreSqlType::BigInt),
+        ])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("1".to_owned()), Some("2".to_owned())],
+            vec![Some("2".to_owned()), Some("2".to_owned())],
+            vec![None, Some("1".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(3)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "column_1",
+            PostgreSqlType::SmallInt,
+        )])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![None],
+            vec![Some("5".to_owned())],
+            vec![Some("3".to_owned())],
+            vec![Some("2".to_owned())],
+            vec![Some("1".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(5)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+    collector.assert_content_for_single_queries(expected);
+}
+
+#[rstest::rstest]
+fn describe_sorted_statement(with_table: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = with_table;
+    engine
+        .parse_prepared_statement(
+            "statement_name",
+            "select column_1 from schema_name.table_name order by column_2;",
+            &[],
+        )
+        .expect("no system errors");
+    engine
+        .describe_prepared_statement("statement_name")
+        .expect("no system errors");
+
+    let mut expected = TABLE_CREATED.to_vec();
+    expected.extend(vec![
+        Ok(QueryEvent::ParseComplete),
+        Ok(QueryEvent::PreparedStatementDescribed(
+            vec![],
+            vec![column("column_1", 1)],
+        )),
+    ]);
+    collector.assert_content(expected);
+}
@@ -13,7 +13,6 @@
 // limitations under the License.
 
 use super::*;
-use kernel::SystemError;
 use protocol::sql_types::PostgreSqlType;
 
 #[rstest::rstest]
@@ -26,7 +25,7 @@ fn parse_select_statement(sql_engine_with_schema: (QueryExecutor, ResultCollecto
         .parse_prepared_statement(
             "statement_name",
             "select * from schema_name.table_name where column = $1 and column_2 = $2;",
-            &[PostgreSqlType::SmallInt, PostgreSqlType::SmallInt],
+            &[Some(PostgreSqlType::SmallInt), Some(PostgreSqlType::SmallInt)],
         )
         .expect("no system errors");
 
@@ -42,17 +41,13 @@ fn parse_select_statement(sql_engine_with_schema: (QueryExecutor, ResultCollecto
 #[rstest::rstest]
 fn parse_select_statement_with_not_existed_table(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
     let (mut engine, collector) = sql_engine_with_schema;
-    let error = engine
+    engine
         .parse_prepared_statement(
             "statement_name",
             "select * from schema_name.non_existent where column_1 = $1;",
             &[],
         )
-        .unwrap_err();
-    assert_eq!(
-        error,
-        SystemError::runtime_check_failure("Table Does Not Exist".to_owned())
-    );
+        .expect("no system errors");
 
     collector.assert_content(vec![
         Ok(QueryEvent::SchemaCreated),
@@ -67,17 +62,13 @@ fn parse_select_statement_with_not_existed_column(sql_engine_with_schema: (Query
     engine
         .execute("create table schema_name.table_name (column_1 smallint, column_2 smallint);")
         .expect("no system errors");
-    let error = engine
+    engine
         .parse_prepared_statement(
             "statement_name",
             "select column_not_in_table from schema_name.table_name where column_1 = $1;",
             &[],
         )
-        .unwrap_err();
-    assert_eq!(
-        error,
-        SystemError::runtime_check_failure("Column Does Not Exist".to_owned())
-    );
+        .expect("no system errors");
 
     collector.assert_content(vec![
         Ok(QueryEvent::SchemaCreated),
@@ -100,15 +91,112 @@ fn parse_update_statement(sql_engine_with_schema: (QueryExecutor, ResultCollecto
         .parse_prepared_statement(
             "statement_name",
             "update schema_name.table_name set column_1 = $1 where column_2 = $2;",
-            &[PostgreSqlType::SmallInt, PostgreSqlType::SmallInt],
+            &[Some(PostgreSqlType::SmallInt), Some(PostgreSqlType::SmallInt)],
+        )
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::ParseComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn parse_insert_statement_with_not_existed_table(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .parse_prepared_statement(
+            "statement_name",
+            "insert into schema_name.non_existent values ($1);",
+            &[],
+        )
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::table_does_not_exist("schema_name.non_existent".to_owned())),
+    ]);
+}
+
+#[rstest::rstest]
+fn parse_update_statement_with_not_existed_column(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint);")
+        .expect("no system errors");
+    engine
+        .parse_prepared_statement(
+            "statement_name",
+            "update schema_name.table_name set column_1 = $1, column_not_in_table = $2;",
+            &[],
         )
         .expect("no system errors");
 
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::column_does_not_exist(
+            vec!["column_not_in_table".to_owned()],
+        )),
+    ]);
+}
+
+#[rstest::rstest]
+fn parse_delete_statement_with_not_existed_schema(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    engine
+        .parse_prepared_statement(
+            "statement_name",
+            "delete from non_existent.table_name where column_1 = $1;",
+            &[],
+        )
+        .expect("no system errors");
+
+    collector.assert_content(vec![Err(QueryError::schema_does_not_exist("non_existent".to_owned()))]);
+}
+
+#[rstest::rstest]
+fn parse_statement_with_dollar_quoted_string(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint, column_2 varchar(10));")
+        .expect("no system errors");
+    engine
+        .parse_prepared_statement(
+            "statement_name",
+            "insert into schema_name.table_name values ($1, $$it's$$);",
+            &[],
+        )
+        .expect("no system errors");
+    engine
+        .bind_prepared_statement_to_portal("portal_name", "statement_name", &[], &[Some(b"1".to_vec())], &[])
+        .expect("no system errors");
+    engine.execute_portal("portal_name", 0).expect("no system errors");
+    engine
+        .execute("select column_2 from schema_name.table_name;")
+        .expect("no system errors");
+
     collector.assert_content(vec![
         Ok(QueryEvent::SchemaCreated),
         Ok(QueryEvent::QueryComplete),
         Ok(QueryEvent::TableCreated),
         Ok(QueryEvent::QueryComplete),
         Ok(QueryEvent::ParseComplete),
+        Ok(QueryEvent::BindComplete),
+        Ok(QueryEvent::RecordsInserted(1)),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "column_2",
+            PostgreSqlType::VarChar,
+        )
+        .with_source(16384, 2)])),
+        Ok(QueryEvent::DataRows(vec![vec![Some("it's".to_owned())]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
     ]);
 }
@@ -0,0 +1,258 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use protocol::sql_types::PostgreSqlType;
+
+#[rstest::rstest]
+fn bind_statement_after_its_table_was_recreated(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint, column_2 smallint);")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name values (1, 2);")
+        .expect("no system errors");
+    engine
+        .parse_prepared_statement(
+            "statement_name",
+            "select * from schema_name.table_name where column_1 = $1;",
+            &[Some(PostgreSqlType::SmallInt)],
+        )
+        .expect("no system errors");
+    engine
+        .execute("drop table schema_name.table_name;")
+        .expect("no system errors");
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint, column_2 smallint);")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name values (1, 3);")
+        .expect("no system errors");
+    engine
+        .bind_prepared_statement_to_portal(
+            "portal_name",
+            "statement_name",
+            &[PostgreSqlFormat::Text],
+            &[Some(b"1".to_vec())],
+            &[],
+        )
+        .expect("no system errors");
+    engine.execute_portal("portal_name", 0).expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(1)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::ParseComplete),
+        Ok(QueryEvent::TableDropped),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(1)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::BindComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("column_1", PostgreSqlType::SmallInt).with_source(16385, 1),
+            ColumnDescription::new("column_2", PostgreSqlType::SmallInt).with_source(16385, 2),
+        ])),
+        Ok(QueryEvent::DataRows(vec![vec![
+            Some("1".to_owned()),
+            Some("3".to_owned()),
+        ]])),
+        Ok(QueryEvent::SelectComplete(1)),
+    ]);
+}
+
+#[rstest::rstest]
+fn bind_statement_after_its_table_was_dropped(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint, column_2 smallint);")
+        .expect("no system errors");
+    engine
+        .parse_prepared_statement(
+            "statement_name",
+            "insert into schema_name.table_name values ($1, $2);",
+            &[Some(PostgreSqlType::SmallInt), Some(PostgreSqlType::SmallInt)],
+        )
+        .expect("no system errors");
+    engine
+        .execute("drop table schema_name.table_name;")
+        .expect("no system errors");
+    engine
+        .bind_prepared_statement_to_portal(
+            "portal_name",
+            "statement_name",
+            &[PostgreSqlFormat::Text],
+            &[Some(b"1".to_vec()), Some(b"2".to_vec())],
+            &[],
+        )
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::ParseComplete),
+        Ok(QueryEvent::TableDropped),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::table_does_not_exist("schema_name.table_name".to_owned())),
+    ]);
+}
+
+#[rstest::rstest]
+fn bind_statement_after_result_type_was_changed(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint, column_2 smallint);")
+        .expect("no system errors");
+    engine
+        .parse_prepared_statement(
+            "statement_name",
+            "select * from schema_name.table_name where column_1 = $1;",
+            &[Some(PostgreSqlType::SmallInt)],
+        )
+        .expect("no system errors");
+    engine
+        .execute("drop table schema_name.table_name;")
+        .expect("no system errors");
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint, column_2 integer);")
+        .expect("no system errors");
+    engine
+        .bind_prepared_statement_to_portal(
+            "portal_name",
+            "statement_name",
+            &[PostgreSqlFormat::Text],
+            &[Some(b"1".to_vec())],
+            &[],
+        )
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::ParseComplete),
+        Ok(QueryEvent::TableDropped),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::cached_plan_changed_result_type()),
+    ]);
+}
+
+#[rstest::rstest]
+fn execute_portal_after_its_table_was_dropped(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint, column_2 smallint);")
+        .expect("no system errors");
+    engine
+        .parse_prepared_statement(
+            "statement_name",
+            "insert into schema_name.table_name values ($1, $2);",
+            &[Some(PostgreSqlType::SmallInt), Some(PostgreSqlType::SmallInt)],
+        )
+        .expect("no system errors");
+    engine
+        .bind_prepared_statement_to_portal(
+            "portal_name",
+            "statement_name",
+            &[PostgreSqlFormat::Text],
+            &[Some(b"1".to_vec()), Some(b"2".to_vec())],
+            &[],
+        )
+        .expect("no system errors");
+    engine
+        .execute("drop table schema_name.table_name;")
+        .expect("no system errors");
+    engine.execute_portal("portal_name", 0).expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::ParseComplete),
+        Ok(QueryEvent::BindComplete),
+        Ok(QueryEvent::TableDropped),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::table_does_not_exist("schema_name.table_name".to_owned())),
+    ]);
+}
+
+#[rstest::rstest]
+fn execute_portal_after_its_table_was_recreated(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint, column_2 smallint);")
+        .expect("no system errors");
+    engine
+        .parse_prepared_statement(
+            "statement_name",
+            "insert into schema_name.table_name values ($1, $2);",
+            &[Some(PostgreSqlType::SmallInt), Some(PostgreSqlType::SmallInt)],
+        )
+        .expect("no system errors");
+    engine
+        .bind_prepared_statement_to_portal(
+            "portal_name",
+            "statement_name",
+            &[PostgreSqlFormat::Text],
+            &[Some(b"1".to_vec()), Some(b"2".to_vec())],
+            &[],
+        )
+        .expect("no system errors");
+    engine
+        .execute("drop table schema_name.table_name;")
+        .expect("no system errors");
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint, column_2 smallint);")
+        .expect("no system errors");
+    engine.execute_portal("portal_name", 0).expect("no system errors");
+    engine
+        .execute("select * from schema_name.table_name;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::ParseComplete),
+        Ok(QueryEvent::BindComplete),
+        Ok(QueryEvent::TableDropped),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(1)),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("column_1", PostgreSqlType::SmallInt).with_source(16385, 1),
+            ColumnDescription::new("column_2", PostgreSqlType::SmallInt).with_source(16385, 2),
+        ])),
+        Ok(QueryEvent::DataRows(vec![vec![
+            Some("1".to_owned()),
+            Some("2".to_owned()),
+        ]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
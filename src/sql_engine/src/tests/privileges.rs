@@ -0,0 +1,505 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use protocol::sql_types::PostgreSqlType;
+
+type Engine = (QueryExecutor, ResultCollector);
+
+fn engine_for(storage: Arc<CatalogManager>, role: &str) -> Engine {
+    let collector = sender();
+    (QueryExecutor::new(storage, collector.clone(), role), collector)
+}
+
+#[rstest::fixture]
+fn owner_and_other() -> (Engine, Engine) {
+    let storage = in_memory_catalog_manager();
+    let (mut owner, owner_collector) = engine_for(storage.clone(), "owner");
+    owner.execute("create schema schema_name;").expect("no system errors");
+    owner
+        .execute("create table schema_name.table_name (column_1 smallint);")
+        .expect("no system errors");
+    owner
+        .execute("insert into schema_name.table_name values (1);")
+        .expect("no system errors");
+    ((owner, owner_collector), engine_for(storage, "other"))
+}
+
+fn selected_one() -> Vec<QueryResult> {
+    vec![
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "column_1",
+            PostgreSqlType::SmallInt,
+        )
+        .with_source(16384, 1)])),
+        Ok(QueryEvent::DataRows(vec![vec![Some("1".to_owned())]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+    ]
+}
+
+#[rstest::rstest]
+fn owner_can_access_own_table(owner_and_other: (Engine, Engine)) {
+    let ((mut owner, collector), _other) = owner_and_other;
+    owner
+        .execute("select * from schema_name.table_name;")
+        .expect("no system errors");
+
+    collector.assert_content(
+        vec![
+            Ok(QueryEvent::SchemaCreated),
+            Ok(QueryEvent::QueryComplete),
+            Ok(QueryEvent::TableCreated),
+            Ok(QueryEvent::QueryComplete),
+            Ok(QueryEvent::RecordsInserted(1)),
+            Ok(QueryEvent::QueryComplete),
+        ]
+        .into_iter()
+        .chain(selected_one())
+        .collect(),
+    );
+}
+
+#[rstest::rstest]
+fn other_role_can_not_use_schema_without_grant(owner_and_other: (Engine, Engine)) {
+    let (_owner, (mut other, collector)) = owner_and_other;
+    other
+        .execute("select * from schema_name.table_name;")
+        .expect("no system errors");
+    other
+        .execute("create table schema_name.other_table (column_1 smallint);")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Err(QueryError::permission_denied("schema", "schema_name".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::permission_denied("schema", "schema_name".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn other_role_needs_table_privileges(owner_and_other: (Engine, Engine)) {
+    let ((mut owner, _owner_collector), (mut other, collector)) = owner_and_other;
+    owner
+        .execute("grant usage on schema schema_name to other;")
+        .expect("no system errors");
+    other
+        .execute("select * from schema_name.table_name;")
+        .expect("no system errors");
+    other
+        .execute("insert into schema_name.table_name values (2);")
+        .expect("no system errors");
+    other
+        .execute("update schema_name.table_name set column_1 = 2;")
+        .expect("no system errors");
+    other
+        .execute("delete from schema_name.table_name;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Err(QueryError::permission_denied(
+            "table",
+            "schema_name.table_name".to_owned(),
+        )),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::permission_denied(
+            "table",
+            "schema_name.table_name".to_owned(),
+        )),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::permission_denied(
+            "table",
+            "schema_name.table_name".to_owned(),
+        )),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::permission_denied(
+            "table",
+            "schema_name.table_name".to_owned(),
+        )),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn granted_privileges_allow_access(owner_and_other: (Engine, Engine)) {
+    let ((mut owner, owner_collector), (mut other, collector)) = owner_and_other;
+    owner
+        .execute("grant usage on schema schema_name to other;")
+        .expect("no system errors");
+    owner
+        .execute("grant select, update on schema_name.table_name to other;")
+        .expect("no system errors");
+    other
+        .execute("select * from schema_name.table_name;")
+        .expect("no system errors");
+    other
+        .execute("update schema_name.table_name set column_1 = 1;")
+        .expect("no system errors");
+    other
+        .execute("delete from schema_name.table_name;")
+        .expect("no system errors");
+
+    owner_collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(1)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::PrivilegesGranted),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::PrivilegesGranted),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+    collector.assert_content(
+        selected_one()
+            .into_iter()
+            .chain(vec![
+                Ok(QueryEvent::RecordsUpdated(1)),
+                Ok(QueryEvent::QueryComplete),
+                Err(QueryError::permission_denied(
+                    "table",
+                    "schema_name.table_name".to_owned(),
+                )),
+                Ok(QueryEvent::QueryComplete),
+            ])
+            .collect(),
+    );
+}
+
+#[rstest::rstest]
+fn revoked_privileges_deny_access(owner_and_other: (Engine, Engine)) {
+    let ((mut owner, _owner_collector), (mut other, collector)) = owner_and_other;
+    owner
+        .execute("grant all on schema schema_name to public;")
+        .expect("no system errors");
+    owner
+        .execute("grant all privileges on table schema_name.table_name to public;")
+        .expect("no system errors");
+    other
+        .execute("select * from schema_name.table_name;")
+        .expect("no system errors");
+    owner
+        .execute("revoke select on schema_name.table_name from public;")
+        .expect("no system errors");
+    other
+        .execute("select * from schema_name.table_name;")
+        .expect("no system errors");
+
+    collector.assert_content(
+        selected_one()
+            .into_iter()
+            .chain(vec![
+                Err(QueryError::permission_denied(
+                    "table",
+                    "schema_name.table_name".to_owned(),
+                )),
+                Ok(QueryEvent::QueryComplete),
+            ])
+            .collect(),
+    );
+}
+
+#[rstest::rstest]
+fn schema_create_privilege_allows_to_create_tables(owner_and_other: (Engine, Engine)) {
+    let ((mut owner, _owner_collector), (mut other, collector)) = owner_and_other;
+    owner
+        .execute("grant create on schema schema_name to other;")
+        .expect("no system errors");
+    other
+        .execute("create table schema_name.other_table (column_1 smallint);")
+        .expect("no system errors");
+    other
+        .execute("drop table schema_name.other_table;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableDropped),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn only_owner_can_drop_objects(owner_and_other: (Engine, Engine)) {
+    let (_owner, (mut other, collector)) = owner_and_other;
+    other
+        .execute("drop table schema_name.table_name;")
+        .expect("no system errors");
+    other.execute("drop schema schema_name;").expect("no system errors");
+
+    collector.assert_content(vec![
+        Err(QueryError::must_be_owner("table", "schema_name.table_name".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::must_be_owner("schema", "schema_name".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn only_owner_can_grant_privileges(owner_and_other: (Engine, Engine)) {
+    let (_owner, (mut other, collector)) = owner_and_other;
+    other
+        .execute("grant usage on schema schema_name to other;")
+        .expect("no system errors");
+    other
+        .execute("grant select on schema_name.table_name to other;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Err(QueryError::must_be_owner("schema", "schema_name".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::must_be_owner("table", "schema_name.table_name".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn superuser_bypasses_privilege_checks(owner_and_other: (Engine, Engine)) {
+    let ((owner, _owner_collector), _other) = owner_and_other;
+    let (mut superuser, collector) = engine_for(owner.storage.clone(), SUPERUSER);
+    superuser
+        .execute("select * from schema_name.table_name;")
+        .expect("no system errors");
+
+    collector.assert_content(selected_one());
+}
+
+#[rstest::rstest]
+fn grant_on_non_existent_table(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("grant select on schema_name.non_existent to other;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::table_does_not_exist("schema_name.non_existent".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn grant_not_applicable_privilege(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("grant select on schema schema_name to other;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::syntax_error(
+            "\"grant select on schema schema_name to other;\" can\'t be parsed".to_owned(),
+        )),
+    ]);
+}
+
+#[rstest::rstest]
+fn default_privileges_are_granted_on_new_tables(owner_and_other: (Engine, Engine)) {
+    let ((mut owner, _owner_collector), (mut other, collector)) = owner_and_other;
+    owner
+        .execute("grant usage on schema schema_name to other;")
+        .expect("no system errors");
+    owner
+        .execute("alter default privileges in schema schema_name grant select on tables to other;")
+        .expect("no system errors");
+    owner
+        .execute("create table schema_name.new_table (column_1 smallint);")
+        .expect("no system errors");
+    other
+        .execute("select * from schema_name.new_table;")
+        .expect("no system errors");
+    other
+        .execute("select * from schema_name.table_name;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "column_1",
+            PostgreSqlType::SmallInt,
+        )
+        .with_source(16385, 1)])),
+        Ok(QueryEvent::SelectComplete(0)),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::permission_denied(
+            "table",
+            "schema_name.table_name".to_owned(),
+        )),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn only_superuser_alters_default_privileges_of_other_roles(owner_and_other: (Engine, Engine)) {
+    let (_owner, (mut other, collector)) = owner_and_other;
+    other
+        .execute("alter default privileges for role owner grant all on tables to other;")
+        .expect("no system errors");
+    other
+        .execute("alter default privileges for role other grant all on tables to public;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Err(QueryError::permission_denied("role", "owner".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::DefaultPrivilegesAltered),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn default_privileges_in_non_existent_schema(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    engine
+        .execute("alter default privileges in schema non_existent grant select on tables to public;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Err(QueryError::schema_does_not_exist("non_existent".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn column_privileges_restrict_access_to_other_columns(owner_and_other: (Engine, Engine)) {
+    let ((mut owner, _owner_collector), (mut other, collector)) = owner_and_other;
+    for sql in &[
+        "create table schema_name.people (name varchar(10), ssn varchar(11));",
+        "insert into schema_name.people values ('alex', '123-45-6789');",
+        "grant usage on schema schema_name to other;",
+        "grant select (name), update (name) on schema_name.people to other;",
+    ] {
+        owner.execute(sql).expect("no system errors");
+    }
+    for sql in &[
+        "select name from schema_name.people;",
+        "select ssn from schema_name.people;",
+        "select * from schema_name.people;",
+        "update schema_name.people set name = 'bob';",
+        "update schema_name.people set ssn = '000-00-0000';",
+        "insert into schema_name.people (name) values ('eve');",
+    ] {
+        other.execute(sql).expect("no system errors");
+    }
+
+    let denied = || Err(QueryError::permission_denied("table", "schema_name.people".to_owned()));
+    collector.assert_content(vec![
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "name",
+            PostgreSqlType::VarChar,
+        )
+        .with_source(16385, 1)])),
+        Ok(QueryEvent::DataRows(vec![vec![Some("alex".to_owned())]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+        denied(),
+        Ok(QueryEvent::QueryComplete),
+        denied(),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsUpdated(1)),
+        Ok(QueryEvent::QueryComplete),
+        denied(),
+        Ok(QueryEvent::QueryComplete),
+        denied(),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn column_privileges_cover_sort_keys(owner_and_other: (Engine, Engine)) {
+    let ((mut owner, _owner_collector), (mut other, collector)) = owner_and_other;
+    for sql in &[
+        "create table schema_name.people (name varchar(10), ssn varchar(11));",
+        "insert into schema_name.people values ('alex', '123-45-6789');",
+        "grant usage on schema schema_name to other;",
+        "grant select (name) on schema_name.people to other;",
+    ] {
+        owner.execute(sql).expect("no system errors");
+    }
+    for sql in &[
+        "select name from schema_name.people order by ssn;",
+        "select name from schema_name.people order by (select max(ssn) from schema_name.people);",
+        "select name as alias from schema_name.people order by alias;",
+    ] {
+        other.execute(sql).expect("no system errors");
+    }
+
+    let denied = || Err(QueryError::permission_denied("table", "schema_name.people".to_owned()));
+    collector.assert_content(vec![
+        denied(),
+        Ok(QueryEvent::QueryComplete),
+        denied(),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "alias",
+            PostgreSqlType::VarChar,
+        )
+        .with_source(16385, 1)])),
+        Ok(QueryEvent::DataRows(vec![vec![Some("alex".to_owned())]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn conditions_of_changes_need_select_on_their_columns(owner_and_other: (Engine, Engine)) {
+    let ((mut owner, _owner_collector), (mut other, collector)) = owner_and_other;
+    for sql in &[
+        "create table schema_name.people (name varchar(10), ssn varchar(11));",
+        "insert into schema_name.people values ('alex', '123-45-6789');",
+        "grant usage on schema schema_name to other;",
+        "grant select (name), update (name), delete on schema_name.people to other;",
+    ] {
+        owner.execute(sql).expect("no system errors");
+    }
+    for sql in &[
+        "update schema_name.people set name = 'bob' where ssn = '123-45-6789';",
+        "delete from schema_name.people where ssn = '123-45-6789';",
+        "update schema_name.people set name = 'bob' where name = 'alex';",
+    ] {
+        other.execute(sql).expect("no system errors");
+    }
+
+    let denied = || Err(QueryError::permission_denied("table", "schema_name.people".to_owned()));
+    collector.assert_content(vec![
+        denied(),
+        Ok(QueryEvent::QueryComplete),
+        denied(),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsUpdated(1)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn grant_on_non_existent_column(owner_and_other: (Engine, Engine)) {
+    let ((mut owner, collector), _other) = owner_and_other;
+    owner
+        .execute("grant select (column_2) on schema_name.table_name to other;")
+        .expect("no system errors");
+
+    let content = collector.0.lock().expect("locked");
+    assert_eq!(
+        content[content.len() - 2..].to_vec(),
+        vec![
+            Err(QueryError::column_does_not_exist(vec!["column_2".to_owned()])
+                .with_detail("Perhaps you meant to reference the column \"column_1\".".to_owned())),
+            Ok(QueryEvent::QueryComplete),
+        ]
+    );
+}
@@ -0,0 +1,374 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use super::*;
+use crate::catalog_manager::{ChangeKind, ChangeStream, ReplicationSlotInfo, RowChange};
+
+fn engine_with_table() -> (QueryExecutor, ResultCollector, Arc<CatalogManager>) {
+    let storage = in_memory_catalog_manager();
+    let collector = sender();
+    let mut engine = QueryExecutor::new(storage.clone(), collector.clone(), SUPERUSER);
+    engine.execute("create schema schema_name;").expect("no system errors");
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint, column_2 varchar(10));")
+        .expect("no system errors");
+    engine
+        .execute("create table schema_name.other_table (column_1 smallint);")
+        .expect("no system errors");
+    (engine, collector, storage)
+}
+
+fn received(stream: &ChangeStream) -> Vec<RowChange> {
+    std::iter::from_fn(|| stream.try_recv()).collect()
+}
+
+fn change(kind: ChangeKind, old: Option<Vec<Option<&str>>>, new: Option<Vec<Option<&str>>>) -> RowChange {
+    let values = |row: Vec<Option<&str>>| row.into_iter().map(|value| value.map(str::to_owned)).collect();
+    RowChange {
+        schema_name: "schema_name".to_owned(),
+        table_name: "table_name".to_owned(),
+        kind,
+        old: old.map(values),
+        new: new.map(values),
+    }
+}
+
+#[rstest::rstest]
+fn subscriber_receives_row_changes_of_published_table() {
+    let (mut engine, collector, storage) = engine_with_table();
+    engine
+        .execute("create publication changes for table schema_name.table_name;")
+        .expect("no system errors");
+    let stream = storage.subscribe("changes").expect("publication exists");
+
+    engine
+        .execute("insert into schema_name.table_name values (1, 'a');")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.other_table values (1);")
+        .expect("no system errors");
+    engine
+        .execute("update schema_name.table_name set column_2 = 'b';")
+        .expect("no system errors");
+    engine
+        .execute("delete from schema_name.table_name;")
+        .expect("no system errors");
+
+    assert_eq!(
+        received(&stream),
+        vec![
+            change(ChangeKind::Insert, None, Some(vec![Some("1"), Some("a")])),
+            change(
+                ChangeKind::Update,
+                Some(vec![Some("1"), Some("a")]),
+                Some(vec![Some("1"), Some("b")])
+            ),
+            change(ChangeKind::Delete, Some(vec![Some("1"), Some("b")]), None),
+        ]
+    );
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::PublicationCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(1)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(1)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsUpdated(1)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsDeleted(1)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn publication_for_all_tables() {
+    let (mut engine, _collector, storage) = engine_with_table();
+    engine
+        .execute("create publication changes for all tables;")
+        .expect("no system errors");
+    let stream = storage.subscribe("changes").expect("publication exists");
+
+    engine
+        .execute("insert into schema_name.other_table values (1);")
+        .expect("no system errors");
+
+    assert_eq!(
+        received(&stream)
+            .into_iter()
+            .map(|change| change.table_name)
+            .collect::<Vec<String>>(),
+        vec!["other_table".to_owned()]
+    );
+}
+
+#[rstest::rstest]
+fn changes_of_transaction_block_are_published_on_commit() {
+    let (mut engine, _collector, storage) = engine_with_table();
+    engine
+        .execute("create publication changes for table schema_name.table_name;")
+        .expect("no system errors");
+    engine
+        .execute("create replication slot subscriber for publication changes;")
+        .expect("no system errors");
+    let stream = storage.subscribe("changes").expect("publication exists");
+
+    engine.execute("begin;").expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name values (1, 'a');")
+        .expect("no system errors");
+    engine.execute("savepoint before_update;").expect("no system errors");
+    engine
+        .execute("update schema_name.table_name set column_2 = 'b';")
+        .expect("no system errors");
+    engine
+        .execute("rollback to savepoint before_update;")
+        .expect("no system errors");
+    assert_eq!(received(&stream), vec![]);
+
+    engine.execute("commit;").expect("no system errors");
+
+    let inserted = vec![change(ChangeKind::Insert, None, Some(vec![Some("1"), Some("a")]))];
+    assert_eq!(received(&stream), inserted);
+    let slot = storage.consume_replication_slot("subscriber").expect("slot exists");
+    assert_eq!(received(&slot), inserted);
+}
+
+#[rstest::rstest]
+fn rolled_back_changes_are_not_published() {
+    let (mut engine, _collector, storage) = engine_with_table();
+    engine
+        .execute("create publication changes for table schema_name.table_name;")
+        .expect("no system errors");
+    engine
+        .execute("create replication slot subscriber for publication changes;")
+        .expect("no system errors");
+    let stream = storage.subscribe("changes").expect("publication exists");
+
+    engine.execute("begin;").expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name values (1, 'a');")
+        .expect("no system errors");
+    engine
+        .execute("delete from schema_name.table_name;")
+        .expect("no system errors");
+    engine.execute("rollback;").expect("no system errors");
+
+    assert_eq!(received(&stream), vec![]);
+    assert_eq!(
+        storage.replication_slots(),
+        vec![ReplicationSlotInfo {
+            name: "subscriber".to_owned(),
+            publication: "changes".to_owned(),
+            active: false,
+            retained_changes: 0,
+            confirmed_position: 0,
+        }]
+    );
+}
+
+#[rstest::rstest]
+fn stream_ends_when_publication_is_dropped() {
+    let (mut engine, collector, storage) = engine_with_table();
+    engine.execute("create publication changes;").expect("no system errors");
+    let stream = storage.subscribe("changes").expect("publication exists");
+    engine.execute("drop publication changes;").expect("no system errors");
+
+    assert_eq!(stream.recv(), None);
+    assert!(storage.subscribe("changes").is_none());
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::PublicationCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::PublicationDropped),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn publication_errors() {
+    let (mut engine, collector, _storage) = engine_with_table();
+    engine
+        .execute("create publication changes for table schema_name.non_existent;")
+        .expect("no system errors");
+    engine.execute("create publication changes;").expect("no system errors");
+    engine.execute("create publication changes;").expect("no system errors");
+    engine
+        .execute("drop publication non_existent;")
+        .expect("no system errors");
+    engine
+        .execute("drop publication if exists non_existent;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::table_does_not_exist("schema_name.non_existent".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::PublicationCreated),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::publication_already_exists("changes".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::publication_does_not_exist("non_existent".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::PublicationDropped),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn only_superuser_can_create_publications() {
+    let collector = sender();
+    let mut engine = QueryExecutor::new(in_memory_catalog_manager(), collector.clone(), "other");
+    engine.execute("create publication changes;").expect("no system errors");
+
+    collector.assert_content(vec![
+        Err(QueryError::must_be_superuser("create or drop publications")),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn replication_slot_keeps_changes_while_subscriber_is_away() {
+    let (mut engine, collector, storage) = engine_with_table();
+    engine
+        .execute("create publication changes for table schema_name.table_name;")
+        .expect("no system errors");
+    engine
+        .execute("create replication slot subscriber for publication changes;")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name values (1, 'a');")
+        .expect("no system errors");
+
+    let stream = storage.consume_replication_slot("subscriber").expect("slot exists");
+    assert_eq!(
+        received(&stream),
+        vec![change(ChangeKind::Insert, None, Some(vec![Some("1"), Some("a")]))]
+    );
+    drop(stream);
+
+    engine
+        .execute("delete from schema_name.table_name;")
+        .expect("no system errors");
+    assert_eq!(
+        storage.replication_slots(),
+        vec![ReplicationSlotInfo {
+            name: "subscriber".to_owned(),
+            publication: "changes".to_owned(),
+            active: false,
+            retained_changes: 1,
+            confirmed_position: 1,
+        }]
+    );
+
+    let stream = storage.consume_replication_slot("subscriber").expect("slot exists");
+    assert_eq!(
+        received(&stream),
+        vec![change(ChangeKind::Delete, Some(vec![Some("1"), Some("a")]), None)]
+    );
+    engine
+        .execute("drop replication slot subscriber;")
+        .expect("no system errors");
+    assert_eq!(stream.recv(), None);
+    assert!(storage.consume_replication_slot("subscriber").is_none());
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::PublicationCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::ReplicationSlotCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(1)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsDeleted(1)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::ReplicationSlotDropped),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn replication_slot_errors() {
+    let (mut engine, collector, _storage) = engine_with_table();
+    engine
+        .execute("create replication slot subscriber for publication changes;")
+        .expect("no system errors");
+    engine.execute("create publication changes;").expect("no system errors");
+    engine
+        .execute("create replication slot subscriber for publication changes;")
+        .expect("no system errors");
+    engine
+        .execute("create replication slot subscriber for publication changes;")
+        .expect("no system errors");
+    engine
+        .execute("drop replication slot non_existent;")
+        .expect("no system errors");
+    engine
+        .execute("drop replication slot if exists non_existent;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::publication_does_not_exist("changes".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::PublicationCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::ReplicationSlotCreated),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::replication_slot_already_exists("subscriber".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::replication_slot_does_not_exist("non_existent".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::ReplicationSlotDropped),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn only_superuser_can_create_replication_slots() {
+    let collector = sender();
+    let mut engine = QueryExecutor::new(in_memory_catalog_manager(), collector.clone(), "other");
+    engine
+        .execute("create replication slot subscriber for publication changes;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Err(QueryError::must_be_superuser("create or drop replication slots")),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
@@ -0,0 +1,139 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use protocol::sql_types::PostgreSqlType;
+
+#[rstest::fixture]
+fn with_employees(sql_engine_with_schema: (QueryExecutor, ResultCollector)) -> (QueryExecutor, ResultCollector) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.employees (id integer, manager_id integer, name varchar(10));")
+        .expect("no system errors");
+    engine
+        .execute(
+            "insert into schema_name.employees values \
+             (1, 0, 'ceo'), (2, 1, 'cto'), (3, 2, 'developer'), (4, 0, 'founder');",
+        )
+        .expect("no system errors");
+
+    (engine, collector)
+}
+
+fn created() -> Vec<QueryResult> {
+    vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(4)),
+        Ok(QueryEvent::QueryComplete),
+    ]
+}
+
+#[rstest::rstest]
+fn non_recursive_expression(with_employees: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = with_employees;
+    engine
+        .execute(
+            "with managers as (select id, name from schema_name.employees where manager_id = 0) \
+             select name from managers;",
+        )
+        .expect("no system errors");
+
+    let mut expected = created();
+    expected.extend(vec![
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "name",
+            PostgreSqlType::VarChar,
+        )])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("ceo".to_owned())],
+            vec![Some("founder".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(2)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+    collector.assert_content_for_single_queries(expected);
+}
+
+#[rstest::rstest]
+fn hierarchy(with_employees: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = with_employees;
+    engine
+        .execute(
+            "with recursive reports (id, name) as ( \
+             select id, name from schema_name.employees where id = 1 \
+             union all \
+             select e.id, e.name from schema_name.employees as e join reports as r on e.manager_id = r.id) \
+             select name from reports;",
+        )
+        .expect("no system errors");
+
+    let mut expected = created();
+    expected.extend(vec![
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "name",
+            PostgreSqlType::VarChar,
+        )])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("ceo".to_owned())],
+            vec![Some("cto".to_owned())],
+            vec![Some("developer".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(3)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+    collector.assert_content_for_single_queries(expected);
+}
+
+#[rstest::rstest]
+fn counting(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    engine
+        .execute("with recursive t (n) as (select 1 union all select n + 1 from t where n < 3) select n from t;")
+        .expect("no system errors");
+
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "n",
+            PostgreSqlType::Integer,
+        )])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("1".to_owned())],
+            vec![Some("2".to_owned())],
+            vec![Some("3".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(3)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn recursion_limit(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    engine
+        .execute("set max_recursive_iterations = 10;")
+        .expect("no system errors");
+    engine
+        .execute("with recursive t (n) as (select 1 union all select n + 1 from t) select n from t;")
+        .expect("no system errors");
+
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::VariableSet),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::recursion_limit_exceeded("t", 10)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
@@ -0,0 +1,120 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use crate::result_cache::ResultCache;
+use protocol::sql_types::PostgreSqlType;
+
+fn selected(values: &[&str]) -> Vec<QueryResult> {
+    vec![
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "column_1",
+            PostgreSqlType::SmallInt,
+        )
+        .with_source(16384, 1)])),
+        Ok(QueryEvent::DataRows(
+            values.iter().map(|value| vec![Some((*value).to_owned())]).collect(),
+        )),
+        Ok(QueryEvent::SelectComplete(values.len())),
+        Ok(QueryEvent::QueryComplete),
+    ]
+}
+
+#[rstest::rstest]
+fn repeated_query_is_served_from_cache() {
+    let cache = Arc::new(ResultCache::new(8));
+    let collector = sender();
+    let mut engine =
+        QueryExecutor::new(in_memory_catalog_manager(), collector.clone(), SUPERUSER).with_result_cache(cache.clone());
+    engine.execute("create schema schema_name;").expect("no system errors");
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint);")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name values (1);")
+        .expect("no system errors");
+    collector.0.lock().expect("locked").clear();
+
+    engine
+        .execute("select column_1 from schema_name.table_name;")
+        .expect("no system errors");
+    engine
+        .execute("SELECT   column_1\nFROM schema_name.table_name")
+        .expect("no system errors");
+
+    assert_eq!(cache.len(), 1);
+    collector.assert_content(selected(&["1"]).into_iter().chain(selected(&["1"])).collect());
+}
+
+#[rstest::rstest]
+fn writes_of_other_sessions_invalidate_cached_results() {
+    let storage = in_memory_catalog_manager();
+    let cache = Arc::new(ResultCache::new(8));
+    let collector = sender();
+    let mut reader = QueryExecutor::new(storage.clone(), collector.clone(), SUPERUSER).with_result_cache(cache.clone());
+    let mut writer = QueryExecutor::new(storage, sender(), SUPERUSER).with_result_cache(cache.clone());
+    writer.execute("create schema schema_name;").expect("no system errors");
+    writer
+        .execute("create table schema_name.table_name (column_1 smallint);")
+        .expect("no system errors");
+    writer
+        .execute("insert into schema_name.table_name values (1);")
+        .expect("no system errors");
+
+    reader
+        .execute("select column_1 from schema_name.table_name;")
+        .expect("no system errors");
+    writer
+        .execute("update schema_name.table_name set column_1 = 2;")
+        .expect("no system errors");
+    reader
+        .execute("select column_1 from schema_name.table_name;")
+        .expect("no system errors");
+    writer
+        .execute("insert into schema_name.table_name values (3);")
+        .expect("no system errors");
+    reader
+        .execute("select column_1 from schema_name.table_name;")
+        .expect("no system errors");
+
+    collector.assert_content(
+        selected(&["1"])
+            .into_iter()
+            .chain(selected(&["2"]))
+            .chain(selected(&["2", "3"]))
+            .collect(),
+    );
+}
+
+#[rstest::rstest]
+fn dropped_tables_invalidate_cached_results() {
+    let cache = Arc::new(ResultCache::new(8));
+    let collector = sender();
+    let mut engine =
+        QueryExecutor::new(in_memory_catalog_manager(), collector.clone(), SUPERUSER).with_result_cache(cache.clone());
+    engine.execute("create schema schema_name;").expect("no system errors");
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint);")
+        .expect("no system errors");
+    engine
+        .execute("select column_1 from schema_name.table_name;")
+        .expect("no system errors");
+    assert_eq!(cache.len(), 1);
+
+    engine
+        .execute("drop schema schema_name cascade;")
+        .expect("no system errors");
+
+    assert!(cache.is_empty());
+}
@@ -0,0 +1,188 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use crate::connections::Connections;
+use protocol::{sql_types::PostgreSqlType, PasswordStore};
+
+#[rstest::rstest]
+fn superuser_sets_password_of_role() {
+    let storage = in_memory_catalog_manager();
+    let collector = sender();
+    let mut engine = QueryExecutor::new(storage.clone(), collector.clone(), SUPERUSER);
+    engine
+        .execute("alter user role_1 with password 'secret';")
+        .expect("no system errors");
+
+    collector.assert_content(vec![Ok(QueryEvent::RoleAltered), Ok(QueryEvent::QueryComplete)]);
+    let verifier = storage.password_verifier("role_1").expect("password is set");
+    assert!(verifier.matches("secret"));
+    assert!(!verifier.to_string().contains("secret"));
+}
+
+#[rstest::rstest]
+fn role_changes_own_password() {
+    let storage = in_memory_catalog_manager();
+    let collector = sender();
+    let mut engine = QueryExecutor::new(storage.clone(), collector.clone(), "role_1");
+    engine
+        .execute("alter role role_1 password 'old';")
+        .expect("no system errors");
+    engine
+        .execute("alter role role_1 password 'new';")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::RoleAltered),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RoleAltered),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+    let verifier = storage.password_verifier("role_1").expect("password is set");
+    assert!(verifier.matches("new"));
+    assert!(!verifier.matches("old"));
+}
+
+#[rstest::rstest]
+fn role_can_not_change_password_of_other_role() {
+    let storage = in_memory_catalog_manager();
+    let collector = sender();
+    let mut engine = QueryExecutor::new(storage.clone(), collector.clone(), "role_1");
+    engine
+        .execute("alter user role_2 password 'secret';")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Err(QueryError::permission_denied("role", "role_2".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+    assert_eq!(storage.password_verifier("role_2"), None);
+}
+
+#[rstest::rstest]
+fn password_is_removed() {
+    let storage = in_memory_catalog_manager();
+    let collector = sender();
+    let mut engine = QueryExecutor::new(storage.clone(), collector.clone(), SUPERUSER);
+    engine
+        .execute("alter user role_1 password 'secret';")
+        .expect("no system errors");
+    engine
+        .execute("alter user role_1 password null;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::RoleAltered),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RoleAltered),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+    assert_eq!(storage.password_verifier("role_1"), None);
+}
+
+#[rstest::rstest]
+fn read_only_role_can_not_modify_data() {
+    let storage = in_memory_catalog_manager();
+    let mut superuser = QueryExecutor::new(storage.clone(), sender(), SUPERUSER);
+    for sql in &[
+        "create schema schema_name;",
+        "create table schema_name.table_name (column_1 smallint);",
+        "grant all on schema schema_name to role_1;",
+        "grant all on table schema_name.table_name to role_1;",
+        "alter role role_1 with readonly;",
+    ] {
+        superuser.execute(sql).expect("no system errors");
+    }
+    let collector = sender();
+    let mut engine = QueryExecutor::new(storage, collector.clone(), "role_1");
+    engine
+        .execute("insert into schema_name.table_name values (1);")
+        .expect("no system errors");
+    engine
+        .execute("create table schema_name.other_table (column_1 smallint);")
+        .expect("no system errors");
+    engine
+        .execute("grant select on table schema_name.table_name to public;")
+        .expect("no system errors");
+    engine
+        .execute("select * from schema_name.table_name;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Err(QueryError::read_only_sql_transaction("INSERT")),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::read_only_sql_transaction("CREATE TABLE")),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::read_only_sql_transaction("GRANT")),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "column_1",
+            PostgreSqlType::SmallInt,
+        )
+        .with_source(16384, 1)])),
+        Ok(QueryEvent::SelectComplete(0)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn read_only_role_is_made_writable_again() {
+    let storage = in_memory_catalog_manager();
+    let mut superuser = QueryExecutor::new(storage.clone(), sender(), SUPERUSER);
+    superuser
+        .execute("alter role role_1 readonly;")
+        .expect("no system errors");
+    superuser
+        .execute("alter role role_1 noreadonly;")
+        .expect("no system errors");
+    let collector = sender();
+    let mut engine = QueryExecutor::new(storage, collector.clone(), "role_1");
+    engine.execute("create schema schema_name;").expect("no system errors");
+
+    collector.assert_content(vec![Ok(QueryEvent::SchemaCreated), Ok(QueryEvent::QueryComplete)]);
+}
+
+#[rstest::rstest]
+fn only_superuser_makes_roles_read_only() {
+    let storage = in_memory_catalog_manager();
+    let collector = sender();
+    let mut engine = QueryExecutor::new(storage.clone(), collector.clone(), "role_1");
+    engine
+        .execute("alter role role_1 password 'secret' readonly;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Err(QueryError::permission_denied("role", "role_1".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+    assert_eq!(storage.password_verifier("role_1"), None);
+}
+
+#[rstest::rstest]
+fn connection_limit_of_role() {
+    let storage = in_memory_catalog_manager();
+    let collector = sender();
+    let mut engine = QueryExecutor::new(storage.clone(), collector.clone(), SUPERUSER);
+    engine
+        .execute("alter role role_1 with connection limit 1;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![Ok(QueryEvent::RoleAltered), Ok(QueryEvent::QueryComplete)]);
+    let connections = Connections::new(storage, 10);
+    let _slot = connections.open("role_1").expect("connection is open");
+    assert!(matches!(
+        connections.open("role_1"),
+        Err(error) if error == QueryError::too_many_connections_for_role("role_1".to_owned())
+    ));
+}
@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use super::*;
+use crate::catalog_manager::CatalogObject;
 
 #[rstest::rstest]
 fn create_schema(sql_engine: (QueryExecutor, ResultCollector)) {
@@ -50,6 +51,69 @@ fn drop_schema(sql_engine: (QueryExecutor, ResultCollector)) {
     ]);
 }
 
+#[rstest::rstest]
+fn drop_schema_with_dependent_table(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint);")
+        .expect("no system errors");
+    engine.execute("drop schema schema_name;").expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name values (1);")
+        .expect("no system errors");
+
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::schema_has_dependent_objects("schema_name".to_owned())
+            .with_detail("table schema_name.table_name depends on schema schema_name".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(1)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn drop_schema_cascades_to_tables_and_their_publications(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint);")
+        .expect("no system errors");
+    engine
+        .execute("create publication changes for table schema_name.table_name;")
+        .expect("no system errors");
+    engine
+        .execute("drop schema schema_name cascade;")
+        .expect("no system errors");
+    engine.execute("create schema schema_name;").expect("no system errors");
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint);")
+        .expect("no system errors");
+
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::PublicationCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::SchemaDropped),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+    assert_eq!(
+        engine
+            .storage
+            .dependents(&CatalogObject::Table("schema_name".to_owned(), "table_name".to_owned())),
+        vec![]
+    );
+}
+
 #[rstest::rstest]
 fn drop_non_existent_schema(sql_engine: (QueryExecutor, ResultCollector)) {
     let (mut engine, collector) = sql_engine;
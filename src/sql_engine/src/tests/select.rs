@@ -65,29 +65,667 @@ fn select_all_from_table_with_multiple_columns(sql_engine_with_schema: (QueryExe
         Ok(QueryEvent::QueryComplete),
         Ok(QueryEvent::RecordsInserted(1)),
         Ok(QueryEvent::QueryComplete),
-        Ok(QueryEvent::RecordsSelected((
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("column_1", PostgreSqlType::SmallInt).with_source(16384, 1),
+            ColumnDescription::new("column_2", PostgreSqlType::SmallInt).with_source(16384, 2),
+            ColumnDescription::new("column_3", PostgreSqlType::SmallInt).with_source(16384, 3),
+        ])),
+        Ok(QueryEvent::DataRows(vec![vec![
+            Some("123".to_owned()),
+            Some("456".to_owned()),
+            Some("789".to_owned()),
+        ]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn select_not_all_columns(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint, column_2 smallint, column_3 smallint);")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name values (1, 4, 7), (2, 5, 8), (3, 6, 9);")
+        .expect("no system errors");
+    engine
+        .execute("select column_3, column_2 from schema_name.table_name;")
+        .expect("no system errors");
+
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(3)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("column_3", PostgreSqlType::SmallInt).with_source(16384, 3),
+            ColumnDescription::new("column_2", PostgreSqlType::SmallInt).with_source(16384, 2),
+        ])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("7".to_owned()), Some("4".to_owned())],
+            vec![Some("8".to_owned()), Some("5".to_owned())],
+            vec![Some("9".to_owned()), Some("6".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(3)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn select_non_existing_columns_from_table(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_in_table smallint);")
+        .expect("no system errors");
+    engine
+        .execute("select column_not_in_table1, column_not_in_table2 from schema_name.table_name;")
+        .expect("no system errors");
+
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::column_does_not_exist(vec![
+            "column_not_in_table1".to_owned(),
+            "column_not_in_table2".to_owned(),
+        ])),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn select_misspelled_column_from_table(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_in_table smallint);")
+        .expect("no system errors");
+    engine
+        .execute("select column_in_tabel from schema_name.table_name;")
+        .expect("no system errors");
+
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::column_does_not_exist(vec!["column_in_tabel".to_owned()])
+            .with_detail("Perhaps you meant to reference the column \"column_in_table\".".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn select_ambiguous_column_from_table(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (id smallint, id integer);")
+        .expect("no system errors");
+    engine
+        .execute("select id from schema_name.table_name;")
+        .expect("no system errors");
+
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::ambiguous_column("id".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn select_qualified_wildcard(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint, column_2 smallint);")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name values (1, 2);")
+        .expect("no system errors");
+    engine
+        .execute("select table_name.*, schema_name.table_name.column_1 from schema_name.table_name;")
+        .expect("no system errors");
+    engine
+        .execute("select schema_name.table_name.* from schema_name.table_name;")
+        .expect("no system errors");
+
+    let description = vec![
+        ColumnDescription::new("column_1", PostgreSqlType::SmallInt).with_source(16384, 1),
+        ColumnDescription::new("column_2", PostgreSqlType::SmallInt).with_source(16384, 2),
+    ];
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(1)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("column_1", PostgreSqlType::SmallInt).with_source(16384, 1),
+            ColumnDescription::new("column_2", PostgreSqlType::SmallInt).with_source(16384, 2),
+            ColumnDescription::new("column_1", PostgreSqlType::SmallInt).with_source(16384, 1),
+        ])),
+        Ok(QueryEvent::DataRows(vec![vec![
+            Some("1".to_owned()),
+            Some("2".to_owned()),
+            Some("1".to_owned()),
+        ]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(description)),
+        Ok(QueryEvent::DataRows(vec![vec![
+            Some("1".to_owned()),
+            Some("2".to_owned()),
+        ]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn select_wildcard_qualified_by_alias(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint, column_2 smallint);")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name values (1, 2);")
+        .expect("no system errors");
+    engine
+        .execute("select t.column_2, t.* from schema_name.table_name as t;")
+        .expect("no system errors");
+    engine
+        .execute("select table_name.* from schema_name.table_name as t;")
+        .expect("no system errors");
+
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(1)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("column_2", PostgreSqlType::SmallInt).with_source(16384, 2),
+            ColumnDescription::new("column_1", PostgreSqlType::SmallInt).with_source(16384, 1),
+            ColumnDescription::new("column_2", PostgreSqlType::SmallInt).with_source(16384, 2),
+        ])),
+        Ok(QueryEvent::DataRows(vec![vec![
+            Some("2".to_owned()),
+            Some("1".to_owned()),
+            Some("2".to_owned()),
+        ]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::missing_from_clause_entry("table_name".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn select_with_column_aliases(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint, column_2 varchar(10));")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name values (1, 'a'), (2, 'b');")
+        .expect("no system errors");
+    engine
+        .execute(
+            "select column_2 as name, t.column_1 as id, column_1 from schema_name.table_name as t where column_1 = 2;",
+        )
+        .expect("no system errors");
+    engine
+        .execute("select column_2 as name from schema_name.table_name where name = 'b';")
+        .expect("no system errors");
+    engine
+        .execute("select count(*) as total, max(column_1) from schema_name.table_name;")
+        .expect("no system errors");
+    engine
+        .execute("select column_3 as missing from schema_name.table_name;")
+        .expect("no system errors");
+
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(2)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("name", PostgreSqlType::VarChar).with_source(16384, 2),
+            ColumnDescription::new("id", PostgreSqlType::SmallInt).with_source(16384, 1),
+            ColumnDescription::new("column_1", PostgreSqlType::SmallInt).with_source(16384, 1),
+        ])),
+        Ok(QueryEvent::DataRows(vec![vec![
+            Some("b".to_owned()),
+            Some("2".to_owned()),
+            Some("2".to_owned()),
+        ]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::column_does_not_exist(vec!["name".to_owned()])),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("total", PostgreSqlType::BigInt),
+            ColumnDescription::new("max", PostgreSqlType::SmallInt),
+        ])),
+        Ok(QueryEvent::DataRows(vec![vec![
+            Some("2".to_owned()),
+            Some("2".to_owned()),
+        ]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::column_does_not_exist(vec!["column_3".to_owned()])),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn select_arithmetic_expressions(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint, column_2 integer, column_3 bigint);")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name values (1, 10, 100), (2, 20, null);")
+        .expect("no system errors");
+    engine
+        .execute(
+            "select column_1 + column_2, column_2 * 2 as doubled, -column_3, column_1 % 2 from schema_name.table_name;",
+        )
+        .expect("no system errors");
+    engine
+        .execute("select column_2 / (column_1 - 1) from schema_name.table_name;")
+        .expect("no system errors");
+    engine
+        .execute("select column_4 + 1 from schema_name.table_name;")
+        .expect("no system errors");
+
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(2)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("?column?", PostgreSqlType::Integer),
+            ColumnDescription::new("doubled", PostgreSqlType::Integer),
+            ColumnDescription::new("?column?", PostgreSqlType::BigInt),
+            ColumnDescription::new("?column?", PostgreSqlType::Integer),
+        ])),
+        Ok(QueryEvent::DataRows(vec![
             vec![
-                ("column_1".to_owned(), PostgreSqlType::SmallInt),
-                ("column_2".to_owned(), PostgreSqlType::SmallInt),
-                ("column_3".to_owned(), PostgreSqlType::SmallInt),
+                Some("11".to_owned()),
+                Some("20".to_owned()),
+                Some("-100".to_owned()),
+                Some("1".to_owned()),
             ],
-            vec![vec!["123".to_owned(), "456".to_owned(), "789".to_owned()]],
-        ))),
+            vec![Some("22".to_owned()), Some("40".to_owned()), None, Some("0".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(2)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "?column?",
+            PostgreSqlType::Integer,
+        )])),
+        Err(QueryError::division_by_zero()),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::column_does_not_exist(vec!["column_4".to_owned()])),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn select_case_expressions(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint, column_2 varchar(10));")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name values (1, 'a'), (5, 'b'), (null, 'c');")
+        .expect("no system errors");
+    engine
+        .execute(
+            "select case when column_1 > 2 then 'big' when column_1 > 0 then 'small' end as size, \
+             case column_2 when 'a' then column_1 else 2.5 end as value \
+             from schema_name.table_name;",
+        )
+        .expect("no system errors");
+    engine
+        .execute(
+            "select column_2 from schema_name.table_name \
+             where case when column_1 is null then true else column_1 > 2 end;",
+        )
+        .expect("no system errors");
+    engine
+        .execute("select case when column_1 = 1 then column_1 else column_2 end from schema_name.table_name;")
+        .expect("no system errors");
+
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(3)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("size", PostgreSqlType::VarChar),
+            ColumnDescription::new("value", PostgreSqlType::Decimal),
+        ])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("small".to_owned()), Some("1".to_owned())],
+            vec![Some("big".to_owned()), Some("2.5".to_owned())],
+            vec![None, Some("2.5".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(3)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "column_2",
+            PostgreSqlType::VarChar,
+        )
+        .with_source(16384, 2)])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("b".to_owned())],
+            vec![Some("c".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(2)),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::datatype_mismatch(
+            "CASE types smallint and varchar cannot be matched".to_owned(),
+        )),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn select_with_between_and_in_list(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint, column_2 varchar(10));")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name values (1, 'a'), (5, 'b'), (9, 'c'), (null, 'd');")
+        .expect("no system errors");
+    engine
+        .execute("select column_2 from schema_name.table_name where column_1 between 2 and '9';")
+        .expect("no system errors");
+    engine
+        .execute("select column_2 from schema_name.table_name where column_1 not between 2 and 8;")
+        .expect("no system errors");
+    engine
+        .execute("select column_1 from schema_name.table_name where column_2 in ('a', 'c', 'e');")
+        .expect("no system errors");
+    engine
+        .execute("select column_2 from schema_name.table_name where column_1 not in ('1', 9);")
+        .expect("no system errors");
+    engine
+        .execute("select column_2 from schema_name.table_name where column_1 in (1, 'one');")
+        .expect("no system errors");
+
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(4)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "column_2",
+            PostgreSqlType::VarChar,
+        )
+        .with_source(16384, 2)])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("b".to_owned())],
+            vec![Some("c".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(2)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "column_2",
+            PostgreSqlType::VarChar,
+        )
+        .with_source(16384, 2)])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("a".to_owned())],
+            vec![Some("c".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(2)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "column_1",
+            PostgreSqlType::SmallInt,
+        )
+        .with_source(16384, 1)])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("1".to_owned())],
+            vec![Some("9".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(2)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "column_2",
+            PostgreSqlType::VarChar,
+        )
+        .with_source(16384, 2)])),
+        Ok(QueryEvent::DataRows(vec![vec![Some("b".to_owned())]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::datatype_mismatch(
+            "invalid input syntax for type smallint: \"one\"".to_owned(),
+        )),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn select_with_row_comparisons(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (id smallint, score smallint, name varchar(10));")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name values (1, 5, 'x'), (2, 0, 'x'), (2, 3, 'y'), (3, null, 'z');")
+        .expect("no system errors");
+    engine
+        .execute("select id from schema_name.table_name where (id, name) = (1, 'x');")
+        .expect("no system errors");
+    engine
+        .execute("select id from schema_name.table_name where (id, score) in ((1, 5), (2, 3), (3, 1));")
+        .expect("no system errors");
+    engine
+        .execute("select id from schema_name.table_name where (id, score) < (2, 3);")
+        .expect("no system errors");
+    engine
+        .execute(
+            "select id from schema_name.table_name where (id, score) >= (2, 3) or (id, score) <> (1, 5) and false;",
+        )
+        .expect("no system errors");
+    engine
+        .execute("select id from schema_name.table_name where (id, score) = (1, 5, 'x');")
+        .expect("no system errors");
+    engine
+        .execute("select id from schema_name.table_name where (id, score) = 1;")
+        .expect("no system errors");
+
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(4)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "id",
+            PostgreSqlType::SmallInt,
+        )
+        .with_source(16384, 1)])),
+        Ok(QueryEvent::DataRows(vec![vec![Some("1".to_owned())]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "id",
+            PostgreSqlType::SmallInt,
+        )
+        .with_source(16384, 1)])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("1".to_owned())],
+            vec![Some("2".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(2)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "id",
+            PostgreSqlType::SmallInt,
+        )
+        .with_source(16384, 1)])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("1".to_owned())],
+            vec![Some("2".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(2)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "id",
+            PostgreSqlType::SmallInt,
+        )
+        .with_source(16384, 1)])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("2".to_owned())],
+            vec![Some("3".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(2)),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::datatype_mismatch(
+            "unequal number of entries in row expressions".to_owned(),
+        )),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::undefined_function(
+            "=".to_owned(),
+            "record".to_owned(),
+            "integer".to_owned(),
+        )),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn select_with_limit_and_offset(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint);")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name values (1), (2), (3), (4), (5);")
+        .expect("no system errors");
+    engine
+        .execute("select column_1 from schema_name.table_name limit 2 offset 1;")
+        .expect("no system errors");
+    engine
+        .execute("select column_1 from schema_name.table_name offset 2 * 2 rows fetch next (1 + 1) rows only;")
+        .expect("no system errors");
+    engine
+        .execute("select count(*) from schema_name.table_name fetch first row only;")
+        .expect("no system errors");
+    engine
+        .execute("select column_1 from schema_name.table_name limit -1;")
+        .expect("no system errors");
+    engine
+        .execute("select column_1 from schema_name.table_name offset column_1;")
+        .expect("no system errors");
+
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(5)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "column_1",
+            PostgreSqlType::SmallInt,
+        )
+        .with_source(16384, 1)])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("2".to_owned())],
+            vec![Some("3".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(2)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "column_1",
+            PostgreSqlType::SmallInt,
+        )
+        .with_source(16384, 1)])),
+        Ok(QueryEvent::DataRows(vec![vec![Some("5".to_owned())]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "count",
+            PostgreSqlType::BigInt,
+        )])),
+        Ok(QueryEvent::DataRows(vec![vec![Some("5".to_owned())]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::invalid_row_count_in_limit_clause()),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::feature_not_supported("column_1".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn select_wildcard_of_relation_not_in_from_clause(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint);")
+        .expect("no system errors");
+    engine
+        .execute("select other.* from schema_name.table_name;")
+        .expect("no system errors");
+    engine
+        .execute("select other.column_1 from schema_name.table_name;")
+        .expect("no system errors");
+
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::missing_from_clause_entry("other".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::missing_from_clause_entry("other".to_owned())),
         Ok(QueryEvent::QueryComplete),
     ]);
 }
 
 #[rstest::rstest]
-fn select_not_all_columns(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+fn select_from_joined_tables(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
     let (mut engine, collector) = sql_engine_with_schema;
     engine
-        .execute("create table schema_name.table_name (column_1 smallint, column_2 smallint, column_3 smallint);")
+        .execute("create table schema_name.table_1 (column_1 smallint, column_2 varchar(10));")
         .expect("no system errors");
     engine
-        .execute("insert into schema_name.table_name values (1, 4, 7), (2, 5, 8), (3, 6, 9);")
+        .execute("create table schema_name.table_2 (column_1 smallint, column_3 integer);")
         .expect("no system errors");
     engine
-        .execute("select column_3, column_2 from schema_name.table_name;")
+        .execute("insert into schema_name.table_1 values (1, 'a'), (2, 'b'), (3, 'c');")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_2 values (2, 20), (3, 30), (3, 31), (4, 40);")
+        .expect("no system errors");
+    engine
+        .execute("select t1.*, t2.column_1 from schema_name.table_1 as t1 join schema_name.table_2 as t2 on t1.column_1 = t2.column_1;")
+        .expect("no system errors");
+    engine
+        .execute("select column_2, column_3 from schema_name.table_1 inner join schema_name.table_2 on table_1.column_1 = table_2.column_1 where column_3 > 20;")
         .expect("no system errors");
 
     collector.assert_content_for_single_queries(vec![
@@ -95,31 +733,189 @@ fn select_not_all_columns(sql_engine_with_schema: (QueryExecutor, ResultCollecto
         Ok(QueryEvent::QueryComplete),
         Ok(QueryEvent::TableCreated),
         Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
         Ok(QueryEvent::RecordsInserted(3)),
         Ok(QueryEvent::QueryComplete),
-        Ok(QueryEvent::RecordsSelected((
+        Ok(QueryEvent::RecordsInserted(4)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("column_1", PostgreSqlType::SmallInt).with_source(16384, 1),
+            ColumnDescription::new("column_2", PostgreSqlType::VarChar).with_source(16384, 2),
+            ColumnDescription::new("column_1", PostgreSqlType::SmallInt).with_source(16385, 1),
+        ])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("2".to_owned()), Some("b".to_owned()), Some("2".to_owned())],
+            vec![Some("3".to_owned()), Some("c".to_owned()), Some("3".to_owned())],
+            vec![Some("3".to_owned()), Some("c".to_owned()), Some("3".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(3)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("column_2", PostgreSqlType::VarChar).with_source(16384, 2),
+            ColumnDescription::new("column_3", PostgreSqlType::Integer).with_source(16385, 2),
+        ])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("c".to_owned()), Some("30".to_owned())],
+            vec![Some("c".to_owned()), Some("31".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(2)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn select_from_cross_joined_tables(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_1 (column_1 smallint);")
+        .expect("no system errors");
+    engine
+        .execute("create table schema_name.table_2 (column_2 smallint);")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_1 values (1), (2);")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_2 values (3);")
+        .expect("no system errors");
+    engine
+        .execute("select * from schema_name.table_1 cross join schema_name.table_2;")
+        .expect("no system errors");
+
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(2)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(1)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("column_1", PostgreSqlType::SmallInt).with_source(16384, 1),
+            ColumnDescription::new("column_2", PostgreSqlType::SmallInt).with_source(16385, 1),
+        ])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("1".to_owned()), Some("3".to_owned())],
+            vec![Some("2".to_owned()), Some("3".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(2)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn select_from_outer_joined_tables(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_1 (column_1 smallint, column_2 varchar(10));")
+        .expect("no system errors");
+    engine
+        .execute("create table schema_name.table_2 (column_3 smallint, column_4 integer);")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_1 values (1, 'a'), (2, 'b');")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_2 values (2, 20), (3, 30);")
+        .expect("no system errors");
+    engine
+        .execute("select * from schema_name.table_1 left join schema_name.table_2 on column_1 = column_3;")
+        .expect("no system errors");
+    engine
+        .execute("select * from schema_name.table_1 right outer join schema_name.table_2 on column_1 = column_3;")
+        .expect("no system errors");
+    engine
+        .execute(
+            "select column_2, column_4 from schema_name.table_1 full join schema_name.table_2 on column_1 = column_3;",
+        )
+        .expect("no system errors");
+    engine
+        .execute("select column_2 from schema_name.table_1 left join schema_name.table_2 on column_1 = column_3 where column_4 is null;")
+        .expect("no system errors");
+
+    let description = vec![
+        ColumnDescription::new("column_1", PostgreSqlType::SmallInt).with_source(16384, 1),
+        ColumnDescription::new("column_2", PostgreSqlType::VarChar).with_source(16384, 2),
+        ColumnDescription::new("column_3", PostgreSqlType::SmallInt).with_source(16385, 1),
+        ColumnDescription::new("column_4", PostgreSqlType::Integer).with_source(16385, 2),
+    ];
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(2)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(2)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(description.clone())),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("1".to_owned()), Some("a".to_owned()), None, None],
             vec![
-                ("column_3".to_owned(), PostgreSqlType::SmallInt),
-                ("column_2".to_owned(), PostgreSqlType::SmallInt),
+                Some("2".to_owned()),
+                Some("b".to_owned()),
+                Some("2".to_owned()),
+                Some("20".to_owned()),
             ],
+        ])),
+        Ok(QueryEvent::SelectComplete(2)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(description)),
+        Ok(QueryEvent::DataRows(vec![
             vec![
-                vec!["7".to_owned(), "4".to_owned()],
-                vec!["8".to_owned(), "5".to_owned()],
-                vec!["9".to_owned(), "6".to_owned()],
+                Some("2".to_owned()),
+                Some("b".to_owned()),
+                Some("2".to_owned()),
+                Some("20".to_owned()),
             ],
-        ))),
+            vec![None, None, Some("3".to_owned()), Some("30".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(2)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("column_2", PostgreSqlType::VarChar).with_source(16384, 2),
+            ColumnDescription::new("column_4", PostgreSqlType::Integer).with_source(16385, 2),
+        ])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("a".to_owned()), None],
+            vec![Some("b".to_owned()), Some("20".to_owned())],
+            vec![None, Some("30".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(3)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "column_2",
+            PostgreSqlType::VarChar,
+        )
+        .with_source(16384, 2)])),
+        Ok(QueryEvent::DataRows(vec![vec![Some("a".to_owned())]])),
+        Ok(QueryEvent::SelectComplete(1)),
         Ok(QueryEvent::QueryComplete),
     ]);
 }
 
 #[rstest::rstest]
-fn select_non_existing_columns_from_table(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+fn describe_nullability_and_source_of_selected_columns(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
     let (mut engine, collector) = sql_engine_with_schema;
     engine
-        .execute("create table schema_name.table_name (column_in_table smallint);")
+        .execute("create table schema_name.table_1 (column_1 smallint not null, column_2 varchar(10));")
         .expect("no system errors");
     engine
-        .execute("select column_not_in_table1, column_not_in_table2 from schema_name.table_name;")
+        .execute("create table schema_name.table_2 (column_3 smallint not null);")
+        .expect("no system errors");
+    engine
+        .execute("select column_1, column_2, column_1 + 1 from schema_name.table_1;")
+        .expect("no system errors");
+    engine
+        .execute(
+            "select column_1, column_3 from schema_name.table_1 left join schema_name.table_2 on column_1 = column_3;",
+        )
         .expect("no system errors");
 
     collector.assert_content_for_single_queries(vec![
@@ -127,10 +923,133 @@ fn select_non_existing_columns_from_table(sql_engine_with_schema: (QueryExecutor
         Ok(QueryEvent::QueryComplete),
         Ok(QueryEvent::TableCreated),
         Ok(QueryEvent::QueryComplete),
-        Err(QueryError::column_does_not_exist(vec![
-            "column_not_in_table1".to_owned(),
-            "column_not_in_table2".to_owned(),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("column_1", PostgreSqlType::SmallInt)
+                .with_source(16384, 1)
+                .with_nullable(false),
+            ColumnDescription::new("column_2", PostgreSqlType::VarChar).with_source(16384, 2),
+            ColumnDescription::new("?column?", PostgreSqlType::Integer),
+        ])),
+        Ok(QueryEvent::SelectComplete(0)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("column_1", PostgreSqlType::SmallInt)
+                .with_source(16384, 1)
+                .with_nullable(false),
+            ColumnDescription::new("column_3", PostgreSqlType::SmallInt).with_source(16385, 1),
+        ])),
+        Ok(QueryEvent::SelectComplete(0)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn select_ambiguous_column_from_joined_tables(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_1 (column_1 smallint);")
+        .expect("no system errors");
+    engine
+        .execute("create table schema_name.table_2 (column_1 smallint);")
+        .expect("no system errors");
+    engine
+        .execute("select column_1 from schema_name.table_1 as t1 join schema_name.table_2 as t2 on t1.column_1 = t2.column_1;")
+        .expect("no system errors");
+    engine
+        .execute("select t1.column_1 from schema_name.table_1 as t1 join schema_name.table_2 as t2 on t1.column_1 = t3.column_1;")
+        .expect("no system errors");
+    engine
+        .execute("select t1.column_1 from schema_name.table_1 as t1 join schema_name.table_2 as t2 using (column_1);")
+        .expect("no system errors");
+
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::ambiguous_column("column_1".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::missing_from_clause_entry("t3".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::feature_not_supported(
+            "select t1.column_1 from schema_name.table_1 as t1 join schema_name.table_2 as t2 using (column_1);"
+                .to_owned(),
+        )),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn select_from_sampled_table(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint);")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name values (1), (2);")
+        .expect("no system errors");
+    engine
+        .execute("select * from schema_name.table_name tablesample system (100);")
+        .expect("no system errors");
+    engine
+        .execute("select t.column_1 from schema_name.table_name as t tablesample bernoulli (0) repeatable (7);")
+        .expect("no system errors");
+
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(2)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "column_1",
+            PostgreSqlType::SmallInt,
+        )
+        .with_source(16384, 1)])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("1".to_owned())],
+            vec![Some("2".to_owned())],
         ])),
+        Ok(QueryEvent::SelectComplete(2)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "column_1",
+            PostgreSqlType::SmallInt,
+        )
+        .with_source(16384, 1)])),
+        Ok(QueryEvent::SelectComplete(0)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn select_from_table_with_invalid_sample(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint);")
+        .expect("no system errors");
+    engine
+        .execute("select * from schema_name.table_name tablesample random (10);")
+        .expect("no system errors");
+    engine
+        .execute("select * from schema_name.table_name tablesample system (150);")
+        .expect("no system errors");
+
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::tablesample_method_does_not_exist("random".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::invalid_tablesample_argument(
+            "sample percentage must be between 0 and 100".to_owned(),
+        )),
         Ok(QueryEvent::QueryComplete),
     ]);
 }
@@ -168,17 +1087,16 @@ fn select_first_and_last_columns_from_table_with_multiple_columns(
         Ok(QueryEvent::QueryComplete),
         Ok(QueryEvent::RecordsInserted(1)),
         Ok(QueryEvent::QueryComplete),
-        Ok(QueryEvent::RecordsSelected((
-            vec![
-                ("column_3".to_owned(), PostgreSqlType::SmallInt),
-                ("column_1".to_owned(), PostgreSqlType::SmallInt),
-            ],
-            vec![
-                vec!["3".to_owned(), "1".to_owned()],
-                vec!["6".to_owned(), "4".to_owned()],
-                vec!["9".to_owned(), "7".to_owned()],
-            ],
-        ))),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("column_3", PostgreSqlType::SmallInt).with_source(16384, 3),
+            ColumnDescription::new("column_1", PostgreSqlType::SmallInt).with_source(16384, 1),
+        ])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("3".to_owned()), Some("1".to_owned())],
+            vec![Some("6".to_owned()), Some("4".to_owned())],
+            vec![Some("9".to_owned()), Some("7".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(3)),
         Ok(QueryEvent::QueryComplete),
     ]);
 }
@@ -217,18 +1135,17 @@ fn select_all_columns_reordered_from_table_with_multiple_columns(
         Ok(QueryEvent::QueryComplete),
         Ok(QueryEvent::RecordsInserted(1)),
         Ok(QueryEvent::QueryComplete),
-        Ok(QueryEvent::RecordsSelected((
-            vec![
-                ("column_3".to_owned(), PostgreSqlType::SmallInt),
-                ("column_1".to_owned(), PostgreSqlType::SmallInt),
-                ("column_2".to_owned(), PostgreSqlType::SmallInt),
-            ],
-            vec![
-                vec!["3".to_owned(), "1".to_owned(), "2".to_owned()],
-                vec!["6".to_owned(), "4".to_owned(), "5".to_owned()],
-                vec!["9".to_owned(), "7".to_owned(), "8".to_owned()],
-            ],
-        ))),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("column_3", PostgreSqlType::SmallInt).with_source(16384, 3),
+            ColumnDescription::new("column_1", PostgreSqlType::SmallInt).with_source(16384, 1),
+            ColumnDescription::new("column_2", PostgreSqlType::SmallInt).with_source(16384, 2),
+        ])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("3".to_owned()), Some("1".to_owned()), Some("2".to_owned())],
+            vec![Some("6".to_owned()), Some("4".to_owned()), Some("5".to_owned())],
+            vec![Some("9".to_owned()), Some("7".to_owned()), Some("8".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(3)),
         Ok(QueryEvent::QueryComplete),
     ]);
 }
@@ -264,38 +1181,37 @@ fn select_with_column_name_duplication(sql_engine_with_schema: (QueryExecutor, R
         Ok(QueryEvent::QueryComplete),
         Ok(QueryEvent::RecordsInserted(1)),
         Ok(QueryEvent::QueryComplete),
-        Ok(QueryEvent::RecordsSelected((
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("column_3", PostgreSqlType::SmallInt).with_source(16384, 3),
+            ColumnDescription::new("column_2", PostgreSqlType::SmallInt).with_source(16384, 2),
+            ColumnDescription::new("column_1", PostgreSqlType::SmallInt).with_source(16384, 1),
+            ColumnDescription::new("column_3", PostgreSqlType::SmallInt).with_source(16384, 3),
+            ColumnDescription::new("column_2", PostgreSqlType::SmallInt).with_source(16384, 2),
+        ])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![
+                Some("3".to_owned()),
+                Some("2".to_owned()),
+                Some("1".to_owned()),
+                Some("3".to_owned()),
+                Some("2".to_owned()),
+            ],
             vec![
-                ("column_3".to_owned(), PostgreSqlType::SmallInt),
-                ("column_2".to_owned(), PostgreSqlType::SmallInt),
-                ("column_1".to_owned(), PostgreSqlType::SmallInt),
-                ("column_3".to_owned(), PostgreSqlType::SmallInt),
-                ("column_2".to_owned(), PostgreSqlType::SmallInt),
+                Some("6".to_owned()),
+                Some("5".to_owned()),
+                Some("4".to_owned()),
+                Some("6".to_owned()),
+                Some("5".to_owned()),
             ],
             vec![
-                vec![
-                    "3".to_owned(),
-                    "2".to_owned(),
-                    "1".to_owned(),
-                    "3".to_owned(),
-                    "2".to_owned(),
-                ],
-                vec![
-                    "6".to_owned(),
-                    "5".to_owned(),
-                    "4".to_owned(),
-                    "6".to_owned(),
-                    "5".to_owned(),
-                ],
-                vec![
-                    "9".to_owned(),
-                    "8".to_owned(),
-                    "7".to_owned(),
-                    "9".to_owned(),
-                    "8".to_owned(),
-                ],
+                Some("9".to_owned()),
+                Some("8".to_owned()),
+                Some("7".to_owned()),
+                Some("9".to_owned()),
+                Some("8".to_owned()),
             ],
-        ))),
+        ])),
+        Ok(QueryEvent::SelectComplete(3)),
         Ok(QueryEvent::QueryComplete),
     ]);
 }
@@ -333,18 +1249,29 @@ fn select_different_integer_types(sql_engine_with_schema: (QueryExecutor, Result
         Ok(QueryEvent::QueryComplete),
         Ok(QueryEvent::RecordsInserted(1)),
         Ok(QueryEvent::QueryComplete),
-        Ok(QueryEvent::RecordsSelected((
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("column_si", PostgreSqlType::SmallInt).with_source(16384, 1),
+            ColumnDescription::new("column_i", PostgreSqlType::Integer).with_source(16384, 2),
+            ColumnDescription::new("column_bi", PostgreSqlType::BigInt).with_source(16384, 3),
+        ])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![
+                Some("1000".to_owned()),
+                Some("2000000".to_owned()),
+                Some("3000000000".to_owned()),
+            ],
             vec![
-                ("column_si".to_owned(), PostgreSqlType::SmallInt),
-                ("column_i".to_owned(), PostgreSqlType::Integer),
-                ("column_bi".to_owned(), PostgreSqlType::BigInt),
+                Some("4000".to_owned()),
+                Some("5000000".to_owned()),
+                Some("6000000000".to_owned()),
             ],
             vec![
-                vec!["1000".to_owned(), "2000000".to_owned(), "3000000000".to_owned()],
-                vec!["4000".to_owned(), "5000000".to_owned(), "6000000000".to_owned()],
-                vec!["7000".to_owned(), "8000000".to_owned(), "9000000000".to_owned()],
+                Some("7000".to_owned()),
+                Some("8000000".to_owned()),
+                Some("9000000000".to_owned()),
             ],
-        ))),
+        ])),
+        Ok(QueryEvent::SelectComplete(3)),
         Ok(QueryEvent::QueryComplete),
     ]);
 }
@@ -382,17 +1309,100 @@ fn select_different_character_strings_types(sql_engine_with_schema: (QueryExecut
         Ok(QueryEvent::QueryComplete),
         Ok(QueryEvent::RecordsInserted(1)),
         Ok(QueryEvent::QueryComplete),
-        Ok(QueryEvent::RecordsSelected((
-            vec![
-                ("char_10".to_owned(), PostgreSqlType::Char),
-                ("var_char_20".to_owned(), PostgreSqlType::VarChar),
-            ],
-            vec![
-                vec!["1234567890".to_owned(), "12345678901234567890".to_owned()],
-                vec!["12345".to_owned(), "1234567890".to_owned()],
-                vec!["12345".to_owned(), "1234567890".to_owned()],
-            ],
-        ))),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("char_10", PostgreSqlType::Char).with_source(16384, 1),
+            ColumnDescription::new("var_char_20", PostgreSqlType::VarChar).with_source(16384, 2),
+        ])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("1234567890".to_owned()), Some("12345678901234567890".to_owned())],
+            vec![Some("12345".to_owned()), Some("1234567890".to_owned())],
+            vec![Some("12345".to_owned()), Some("1234567890".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(3)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn select_with_where_clause(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint, column_2 varchar(10));")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name values (1, 'a'), (5, 'b'), (10, 'c');")
+        .expect("no system errors");
+    engine
+        .execute("select column_2 from schema_name.table_name where column_1 = 5;")
+        .expect("no system errors");
+    engine
+        .execute(
+            "select t.column_1 from schema_name.table_name as t \
+             where (t.column_1 > 1 and column_2 <> 'b') or column_1 = '1';",
+        )
+        .expect("no system errors");
+
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(3)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "column_2",
+            PostgreSqlType::VarChar,
+        )
+        .with_source(16384, 2)])),
+        Ok(QueryEvent::DataRows(vec![vec![Some("b".to_owned())]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "column_1",
+            PostgreSqlType::SmallInt,
+        )
+        .with_source(16384, 1)])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("1".to_owned())],
+            vec![Some("10".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(2)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn select_with_invalid_where_clause(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint, column_2 varchar(10));")
+        .expect("no system errors");
+    engine
+        .execute("select * from schema_name.table_name where column_2 = 5;")
+        .expect("no system errors");
+    engine
+        .execute("select * from schema_name.table_name where column_3 = 5;")
+        .expect("no system errors");
+    engine
+        .execute("select * from schema_name.table_name where column_1 = 'one';")
+        .expect("no system errors");
+
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::undefined_function(
+            "=".to_owned(),
+            "varchar".to_owned(),
+            "integer".to_owned(),
+        )),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::column_does_not_exist(vec!["column_3".to_owned()])),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::datatype_mismatch(
+            "invalid input syntax for type smallint: \"one\"".to_owned(),
+        )),
         Ok(QueryEvent::QueryComplete),
     ]);
 }
@@ -0,0 +1,157 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use protocol::sql_types::PostgreSqlType;
+
+#[rstest::fixture]
+fn with_tables(sql_engine_with_schema: (QueryExecutor, ResultCollector)) -> (QueryExecutor, ResultCollector) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.current (id smallint, name varchar(10));")
+        .expect("no system errors");
+    engine
+        .execute("create table schema_name.archived (id integer, name char(10));")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.current values (1, 'a'), (2, 'b'), (2, 'b');")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.archived values (2, 'b'), (3, 'c');")
+        .expect("no system errors");
+
+    (engine, collector)
+}
+
+fn created() -> Vec<QueryResult> {
+    vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(3)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(2)),
+        Ok(QueryEvent::QueryComplete),
+    ]
+}
+
+fn ids(ids: &[&str]) -> Vec<QueryResult> {
+    let mut results = vec![Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+        "id",
+        PostgreSqlType::Integer,
+    )]))];
+    if !ids.is_empty() {
+        results.push(Ok(QueryEvent::DataRows(
+            ids.iter().map(|id| vec![Some((*id).to_owned())]).collect(),
+        )));
+    }
+    results.push(Ok(QueryEvent::SelectComplete(ids.len())));
+    results.push(Ok(QueryEvent::QueryComplete));
+    results
+}
+
+#[rstest::rstest]
+fn union(with_tables: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = with_tables;
+    engine
+        .execute("select id from schema_name.current union select id from schema_name.archived;")
+        .expect("no system errors");
+    engine
+        .execute("select id from schema_name.current union all select id from schema_name.archived;")
+        .expect("no system errors");
+
+    let mut expected = created();
+    expected.extend(ids(&["1", "2", "3"]));
+    expected.extend(ids(&["1", "2", "2", "2", "3"]));
+    collector.assert_content_for_single_queries(expected);
+}
+
+#[rstest::rstest]
+fn intersect(with_tables: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = with_tables;
+    engine
+        .execute("select id from schema_name.current intersect select id from schema_name.archived;")
+        .expect("no system errors");
+    engine
+        .execute("select id from schema_name.current intersect all select id from schema_name.current;")
+        .expect("no system errors");
+
+    let mut expected = created();
+    expected.extend(ids(&["2"]));
+    let mut all = ids(&["1", "2", "2"]);
+    all[0] = Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+        "id",
+        PostgreSqlType::SmallInt,
+    )]));
+    expected.extend(all);
+    collector.assert_content_for_single_queries(expected);
+}
+
+#[rstest::rstest]
+fn except(with_tables: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = with_tables;
+    engine
+        .execute("select id from schema_name.archived except select id from schema_name.current;")
+        .expect("no system errors");
+    engine
+        .execute(
+            "select id from schema_name.archived except all select id from schema_name.current \
+             union select id from schema_name.archived where id = 2;",
+        )
+        .expect("no system errors");
+
+    let mut expected = created();
+    expected.extend(ids(&["3"]));
+    expected.extend(ids(&["3", "2"]));
+    collector.assert_content_for_single_queries(expected);
+}
+
+#[rstest::rstest]
+fn columns_of_different_types(with_tables: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = with_tables;
+    engine
+        .execute("select name from schema_name.current union select name from schema_name.archived;")
+        .expect("no system errors");
+    engine
+        .execute("select id from schema_name.current union select name from schema_name.archived;")
+        .expect("no system errors");
+    engine
+        .execute("select id, name from schema_name.current except select id from schema_name.archived;")
+        .expect("no system errors");
+
+    let mut expected = created();
+    expected.extend(vec![
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "name",
+            PostgreSqlType::VarChar,
+        )])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("a".to_owned())],
+            vec![Some("b".to_owned())],
+            vec![Some("c".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(3)),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::datatype_mismatch(
+            "UNION types smallint and character cannot be matched".to_owned(),
+        )),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::set_operation_columns_mismatch("EXCEPT")),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+    collector.assert_content_for_single_queries(expected);
+}
@@ -0,0 +1,425 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+#[rstest::rstest]
+fn set_log_min_duration_statement(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    engine
+        .execute("set log_min_duration_statement = '250ms';")
+        .expect("no system errors");
+
+    collector.assert_content(vec![Ok(QueryEvent::VariableSet), Ok(QueryEvent::QueryComplete)]);
+    assert_eq!(
+        engine.session.log_min_duration_statement(),
+        Some(Duration::from_millis(250))
+    );
+}
+
+#[rstest::rstest]
+fn disable_log_min_duration_statement(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (engine, collector) = sql_engine;
+    let mut engine = engine.with_log_min_duration_statement(Some(Duration::from_secs(1)));
+    engine
+        .execute("set log_min_duration_statement = '-1';")
+        .expect("no system errors");
+
+    collector.assert_content(vec![Ok(QueryEvent::VariableSet), Ok(QueryEvent::QueryComplete)]);
+    assert_eq!(engine.session.log_min_duration_statement(), None);
+}
+
+#[rstest::rstest]
+fn reset_log_min_duration_statement_to_default(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (engine, collector) = sql_engine;
+    let mut engine = engine.with_log_min_duration_statement(Some(Duration::from_secs(1)));
+    engine
+        .execute("set log_min_duration_statement = 10;")
+        .expect("no system errors");
+    engine
+        .execute("set log_min_duration_statement = default;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::VariableSet),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::VariableSet),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+    assert_eq!(
+        engine.session.log_min_duration_statement(),
+        Some(Duration::from_secs(1))
+    );
+}
+
+#[rstest::rstest]
+fn set_statement_timeout(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (engine, collector) = sql_engine;
+    let mut engine = engine.with_statement_timeout(Some(Duration::from_secs(1)));
+    engine
+        .execute("set statement_timeout = '5s';")
+        .expect("no system errors");
+    assert_eq!(engine.session.statement_timeout(), Some(Duration::from_secs(5)));
+    engine
+        .execute("set statement_timeout = default;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::VariableSet),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::VariableSet),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+    assert_eq!(engine.session.statement_timeout(), Some(Duration::from_secs(1)));
+}
+
+#[rstest::rstest]
+fn invalid_log_min_duration_statement(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    engine
+        .execute("set log_min_duration_statement = 'soon';")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Err(QueryError::invalid_parameter_value(
+            "invalid value for parameter \"log_min_duration_statement\": \"soon\"".to_owned(),
+        )),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+    assert_eq!(engine.session.log_min_duration_statement(), None);
+}
+
+#[rstest::rstest]
+fn set_auto_explain_log_min_duration(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    engine
+        .execute("set \"auto_explain.log_min_duration\" = '1s';")
+        .expect("no system errors");
+
+    collector.assert_content(vec![Ok(QueryEvent::VariableSet), Ok(QueryEvent::QueryComplete)]);
+    assert_eq!(
+        engine.session.auto_explain_log_min_duration(),
+        Some(Duration::from_secs(1))
+    );
+    assert_eq!(engine.session.log_min_duration_statement(), None);
+}
+
+#[rstest::rstest]
+fn unknown_variables_are_accepted(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    engine
+        .execute("set client_encoding = 'UTF8';")
+        .expect("no system errors");
+
+    collector.assert_content(vec![Ok(QueryEvent::VariableSet), Ok(QueryEvent::QueryComplete)]);
+}
+
+#[rstest::rstest]
+fn select_with_parallel_workers(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (engine, collector) = sql_engine_with_schema;
+    let mut engine = engine.with_max_parallel_workers(1);
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint);")
+        .expect("no system errors");
+    engine
+        .execute("set max_parallel_workers = 3;")
+        .expect("no system errors");
+    assert_eq!(engine.session.max_parallel_workers(), 3);
+    engine
+        .execute("insert into schema_name.table_name values (1), (2), (3);")
+        .expect("no system errors");
+    engine
+        .execute("select column_1 from schema_name.table_name;")
+        .expect("no system errors");
+    engine
+        .execute("set max_parallel_workers = default;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::VariableSet),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(3)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "column_1",
+            PostgreSqlType::SmallInt,
+        )
+        .with_source(16384, 1)])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("1".to_owned())],
+            vec![Some("2".to_owned())],
+            vec![Some("3".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(3)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::VariableSet),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+    assert_eq!(engine.session.max_parallel_workers(), 1);
+}
+
+#[rstest::rstest]
+fn projection_exceeds_work_mem(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 varchar(100));")
+        .expect("no system errors");
+    engine.execute("set work_mem = '64kB';").expect("no system errors");
+    assert_eq!(engine.session.work_mem(), 64);
+    let values = (0..1000)
+        .map(|_| format!("('{}')", "a".repeat(100)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    engine
+        .execute(format!("insert into schema_name.table_name values {};", values).as_str())
+        .expect("no system errors");
+    engine
+        .execute("select column_1 from schema_name.table_name;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::VariableSet),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(1000)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "column_1",
+            PostgreSqlType::VarChar,
+        )
+        .with_source(16384, 1)])),
+        Err(QueryError::out_of_memory("projection", 64)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn reloaded_thresholds_replace_defaults() {
+    let settings = Arc::new(ServerSettings::default());
+    settings.set_log_min_duration_statement(Some(Duration::from_secs(1)));
+    let mut engine =
+        QueryExecutor::new(in_memory_catalog_manager(), sender(), SUPERUSER).with_server_settings(settings.clone());
+    assert_eq!(
+        engine.session.log_min_duration_statement(),
+        Some(Duration::from_secs(1))
+    );
+
+    settings.set_log_min_duration_statement(Some(Duration::from_millis(10)));
+    settings.set_auto_explain_log_min_duration(Some(Duration::from_millis(20)));
+    engine.execute("create schema schema_name;").expect("no system errors");
+
+    assert_eq!(
+        engine.session.log_min_duration_statement(),
+        Some(Duration::from_millis(10))
+    );
+    assert_eq!(
+        engine.session.auto_explain_log_min_duration(),
+        Some(Duration::from_millis(20))
+    );
+}
+
+#[rstest::rstest]
+fn reloaded_thresholds_keep_session_values() {
+    let settings = Arc::new(ServerSettings::default());
+    let mut engine =
+        QueryExecutor::new(in_memory_catalog_manager(), sender(), SUPERUSER).with_server_settings(settings.clone());
+    engine
+        .execute("set log_min_duration_statement = 250;")
+        .expect("no system errors");
+
+    settings.set_log_min_duration_statement(Some(Duration::from_millis(10)));
+    engine.execute("create schema schema_name;").expect("no system errors");
+    assert_eq!(
+        engine.session.log_min_duration_statement(),
+        Some(Duration::from_millis(250))
+    );
+
+    engine
+        .execute("set log_min_duration_statement = default;")
+        .expect("no system errors");
+    assert_eq!(
+        engine.session.log_min_duration_statement(),
+        Some(Duration::from_millis(10))
+    );
+}
+
+#[rstest::rstest]
+fn reload_configuration() {
+    let settings = Arc::new(ServerSettings::default());
+    let reloaded = Arc::new(AtomicBool::new(false));
+    let flag = reloaded.clone();
+    settings.set_reload(move || flag.store(true, Ordering::SeqCst));
+    let collector = sender();
+    let mut engine =
+        QueryExecutor::new(in_memory_catalog_manager(), collector.clone(), SUPERUSER).with_server_settings(settings);
+    engine.execute("select pg_reload_conf();").expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::RecordsSelected((
+            vec![ColumnDescription::new("pg_reload_conf", PostgreSqlType::Bool)],
+            vec![vec![Some("t".to_owned())]],
+        ))),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+    assert!(reloaded.load(Ordering::SeqCst));
+}
+
+#[rstest::rstest]
+fn only_superuser_reloads_configuration() {
+    let settings = Arc::new(ServerSettings::default());
+    let reloaded = Arc::new(AtomicBool::new(false));
+    let flag = reloaded.clone();
+    settings.set_reload(move || flag.store(true, Ordering::SeqCst));
+    let collector = sender();
+    let mut engine =
+        QueryExecutor::new(in_memory_catalog_manager(), collector.clone(), "role_1").with_server_settings(settings);
+    engine.execute("select pg_reload_conf();").expect("no system errors");
+
+    collector.assert_content(vec![
+        Err(QueryError::permission_denied("function", "pg_reload_conf".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+    assert!(!reloaded.load(Ordering::SeqCst));
+}
+
+#[rstest::rstest]
+fn session_starts_with_settings_of_database_and_role() {
+    let storage = in_memory_catalog_manager();
+    let collector = sender();
+    let mut engine = QueryExecutor::new(storage.clone(), collector.clone(), SUPERUSER);
+    engine
+        .execute("alter database db_1 set work_mem = '1MB';")
+        .expect("no system errors");
+    engine
+        .execute("alter database db_1 set statement_timeout = '5s';")
+        .expect("no system errors");
+    engine
+        .execute("alter role role_1 set work_mem = '2MB';")
+        .expect("no system errors");
+    engine
+        .execute("alter role role_1 set search_path to schema_name;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::DatabaseAltered),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::DatabaseAltered),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RoleAltered),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RoleAltered),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+    let session = QueryExecutor::new(storage.clone(), sender(), "role_1")
+        .with_work_mem(512)
+        .with_database("db_1");
+    assert_eq!(session.session.work_mem(), 2048);
+    assert_eq!(session.session.statement_timeout(), Some(Duration::from_secs(5)));
+    let session = QueryExecutor::new(storage.clone(), sender(), "role_2")
+        .with_work_mem(512)
+        .with_database("db_1");
+    assert_eq!(session.session.work_mem(), 1024);
+    let session = QueryExecutor::new(storage, sender(), "role_2")
+        .with_work_mem(512)
+        .with_database("db_2");
+    assert_eq!(session.session.work_mem(), 512);
+    assert_eq!(session.session.statement_timeout(), None);
+}
+
+#[rstest::rstest]
+fn reset_settings_of_role() {
+    let storage = in_memory_catalog_manager();
+    let collector = sender();
+    let mut engine = QueryExecutor::new(storage.clone(), collector.clone(), "role_1");
+    engine
+        .execute("alter role role_1 set max_parallel_workers = 4;")
+        .expect("no system errors");
+    engine
+        .execute("alter role role_1 set statement_timeout = '1s';")
+        .expect("no system errors");
+    engine
+        .execute("alter role role_1 reset max_parallel_workers;")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::RoleAltered),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RoleAltered),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RoleAltered),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+    let session = QueryExecutor::new(storage.clone(), sender(), "role_1").with_database("role_1");
+    assert_eq!(session.session.max_parallel_workers(), 0);
+    assert_eq!(session.session.statement_timeout(), Some(Duration::from_secs(1)));
+
+    engine
+        .execute("alter role role_1 reset all;")
+        .expect("no system errors");
+    let session = QueryExecutor::new(storage, sender(), "role_1").with_database("role_1");
+    assert_eq!(session.session.statement_timeout(), None);
+}
+
+#[rstest::rstest]
+fn invalid_default_setting() {
+    let storage = in_memory_catalog_manager();
+    let collector = sender();
+    let mut engine = QueryExecutor::new(storage.clone(), collector.clone(), SUPERUSER);
+    engine
+        .execute("alter database db_1 set work_mem = '1TB';")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Err(QueryError::invalid_parameter_value(
+            "invalid value for parameter \"work_mem\": \"1TB\"".to_owned(),
+        )),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+    let session = QueryExecutor::new(storage, sender(), SUPERUSER)
+        .with_work_mem(512)
+        .with_database("db_1");
+    assert_eq!(session.session.work_mem(), 512);
+}
+
+#[rstest::rstest]
+fn only_superuser_changes_settings_of_other_roles_and_databases() {
+    let storage = in_memory_catalog_manager();
+    let collector = sender();
+    let mut engine = QueryExecutor::new(storage.clone(), collector.clone(), "role_1");
+    engine
+        .execute("alter role role_2 set work_mem = '1MB';")
+        .expect("no system errors");
+    engine
+        .execute("alter database db_1 set work_mem = '1MB';")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Err(QueryError::permission_denied("role", "role_2".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::permission_denied("database", "db_1".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+    assert_eq!(storage.default_settings().of_session("db_1", "role_2"), vec![]);
+}
@@ -0,0 +1,330 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use crate::statistics::{fingerprint, StatementStatistics};
+use protocol::{sql_formats::PostgreSqlFormat, sql_types::PostgreSqlType};
+
+fn statistics_description() -> Vec<ColumnDescription> {
+    vec![
+        ColumnDescription::new("role", PostgreSqlType::VarChar),
+        ColumnDescription::new("queryid", PostgreSqlType::BigInt),
+        ColumnDescription::new("query", PostgreSqlType::VarChar),
+        ColumnDescription::new("calls", PostgreSqlType::BigInt),
+        ColumnDescription::new("total_time", PostgreSqlType::DoublePrecision),
+        ColumnDescription::new("mean_time", PostgreSqlType::DoublePrecision),
+        ColumnDescription::new("rows", PostgreSqlType::BigInt),
+    ]
+}
+
+fn calls_and_rows(collector: &Collector) -> Vec<(String, String, String)> {
+    let results = collector.0.lock().expect("locked");
+    match results
+        .iter()
+        .rev()
+        .find(|result| matches!(result, Ok(QueryEvent::RecordsSelected(_))))
+    {
+        Some(Ok(QueryEvent::RecordsSelected((description, records)))) => {
+            assert_eq!(description, &statistics_description());
+            records
+                .iter()
+                .map(|record| {
+                    let field = |index: usize| record[index].clone().expect("statistics are not NULL");
+                    (field(2), field(3), field(6))
+                })
+                .collect()
+        }
+        _ => panic!("no statistics were selected"),
+    }
+}
+
+#[rstest::rstest]
+fn statements_with_different_literals_are_accumulated() {
+    let collector = sender();
+    let mut engine = QueryExecutor::new(in_memory_catalog_manager(), collector.clone(), SUPERUSER)
+        .with_statement_statistics(Arc::new(StatementStatistics::default()));
+    engine.execute("create schema schema_name;").expect("no system errors");
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint);")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name values (1), (2);")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name values (3), (4);")
+        .expect("no system errors");
+    engine
+        .execute("select * from schema_name.table_name;")
+        .expect("no system errors");
+    engine
+        .execute("select * from pg_stat_statements;")
+        .expect("no system errors");
+
+    assert_eq!(
+        calls_and_rows(&collector),
+        vec![
+            ("create schema schema_name".to_owned(), "1".to_owned(), "0".to_owned()),
+            (
+                "create table schema_name.table_name (column_1 smallint)".to_owned(),
+                "1".to_owned(),
+                "0".to_owned()
+            ),
+            (
+                "insert into schema_name.table_name values ($1), ($2)".to_owned(),
+                "2".to_owned(),
+                "4".to_owned()
+            ),
+            (
+                "select * from schema_name.table_name".to_owned(),
+                "1".to_owned(),
+                "4".to_owned()
+            ),
+        ]
+    );
+}
+
+#[rstest::rstest]
+fn statements_with_same_fingerprint_are_accumulated() {
+    let collector = sender();
+    let mut engine = QueryExecutor::new(in_memory_catalog_manager(), collector.clone(), SUPERUSER)
+        .with_statement_statistics(Arc::new(StatementStatistics::default()));
+    engine.execute("create schema schema_name;").expect("no system errors");
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint);")
+        .expect("no system errors");
+    engine
+        .execute("select column_1 from schema_name.table_name where column_1 = 1;")
+        .expect("no system errors");
+    engine
+        .execute("SELECT column_1\n  FROM schema_name.table_name\n  WHERE column_1 = 2;")
+        .expect("no system errors");
+    engine
+        .execute("select * from pg_stat_statements;")
+        .expect("no system errors");
+
+    let results = collector.0.lock().expect("locked");
+    let records = match results
+        .iter()
+        .rev()
+        .find(|result| matches!(result, Ok(QueryEvent::RecordsSelected(_))))
+    {
+        Some(Ok(QueryEvent::RecordsSelected((_, records)))) => records,
+        _ => panic!("no statistics were selected"),
+    };
+    assert_eq!(
+        records
+            .iter()
+            .map(|record| (record[1].clone(), record[2].clone(), record[3].clone()))
+            .collect::<Vec<_>>(),
+        vec![
+            (
+                Some(fingerprint("create schema schema_name").to_string()),
+                Some("create schema schema_name".to_owned()),
+                Some("1".to_owned())
+            ),
+            (
+                Some(fingerprint("create table schema_name.table_name (column_1 smallint)").to_string()),
+                Some("create table schema_name.table_name (column_1 smallint)".to_owned()),
+                Some("1".to_owned())
+            ),
+            (
+                Some(fingerprint("select column_1 from schema_name.table_name where column_1 = 1").to_string()),
+                Some("select column_1 from schema_name.table_name where column_1 = $1".to_owned()),
+                Some("2".to_owned())
+            ),
+        ]
+    );
+}
+
+#[rstest::rstest]
+fn failed_statements_are_not_accumulated() {
+    let collector = sender();
+    let mut engine = QueryExecutor::new(in_memory_catalog_manager(), collector.clone(), SUPERUSER)
+        .with_statement_statistics(Arc::new(StatementStatistics::default()));
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint);")
+        .expect("no system errors");
+    engine
+        .execute("select * from pg_catalog.pg_stat_statements;")
+        .expect("no system errors");
+
+    assert_eq!(calls_and_rows(&collector), vec![]);
+}
+
+#[rstest::rstest]
+fn reset_statistics() {
+    let collector = sender();
+    let mut engine = QueryExecutor::new(in_memory_catalog_manager(), collector.clone(), SUPERUSER)
+        .with_statement_statistics(Arc::new(StatementStatistics::default()));
+    engine.execute("create schema schema_name;").expect("no system errors");
+    engine
+        .execute("select pg_stat_statements_reset();")
+        .expect("no system errors");
+    engine
+        .execute("select * from pg_stat_statements;")
+        .expect("no system errors");
+
+    assert_eq!(
+        calls_and_rows(&collector),
+        vec![(
+            "select pg_stat_statements_reset()".to_owned(),
+            "1".to_owned(),
+            "1".to_owned()
+        )]
+    );
+}
+
+#[rstest::rstest]
+fn only_superuser_resets_statistics() {
+    let statistics = Arc::new(StatementStatistics::default());
+    let collector = sender();
+    let mut engine = QueryExecutor::new(in_memory_catalog_manager(), collector.clone(), "role_1")
+        .with_statement_statistics(statistics);
+    engine
+        .execute("select pg_stat_statements_reset();")
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Err(QueryError::permission_denied(
+            "function",
+            "pg_stat_statements_reset".to_owned(),
+        )),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn table_access_statistics(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint);")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name values (1), (2), (3);")
+        .expect("no system errors");
+    engine
+        .execute("update schema_name.table_name set column_1 = 4;")
+        .expect("no system errors");
+    engine
+        .execute("select * from schema_name.table_name;")
+        .expect("no system errors");
+    engine
+        .execute("delete from schema_name.table_name;")
+        .expect("no system errors");
+    engine
+        .execute("select * from pg_stat_user_tables;")
+        .expect("no system errors");
+
+    let results = collector.0.lock().expect("locked");
+    match results.iter().rev().nth(1) {
+        Some(Ok(QueryEvent::RecordsSelected((description, records)))) => {
+            assert_eq!(
+                description
+                    .iter()
+                    .map(|column| column.name.as_str())
+                    .collect::<Vec<&str>>(),
+                vec![
+                    "schemaname",
+                    "relname",
+                    "seq_scan",
+                    "seq_tup_read",
+                    "idx_scan",
+                    "idx_tup_fetch",
+                    "n_tup_ins",
+                    "n_tup_upd",
+                    "n_tup_del",
+                    "n_live_tup",
+                    "n_dead_tup",
+                    "n_mod_since_analyze",
+                    "last_autovacuum",
+                    "last_autoanalyze",
+                    "autovacuum_count",
+                    "autoanalyze_count"
+                ]
+            );
+            assert_eq!(
+                records,
+                &vec![vec![
+                    Some("schema_name"),
+                    Some("table_name"),
+                    Some("3"),
+                    Some("9"),
+                    Some("0"),
+                    Some("0"),
+                    Some("3"),
+                    Some("3"),
+                    Some("3"),
+                    Some("0"),
+                    Some("6"),
+                    Some("9"),
+                    None,
+                    None,
+                    Some("0"),
+                    Some("0")
+                ]
+                .into_iter()
+                .map(|field| field.map(str::to_owned))
+                .collect::<Vec<Option<String>>>()]
+            );
+        }
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[rstest::rstest]
+fn portals_are_accumulated_by_text_of_prepared_statement(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (engine, collector) = sql_engine_with_schema;
+    let mut engine = engine.with_statement_statistics(Arc::new(StatementStatistics::default()));
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint);")
+        .expect("no system errors");
+    engine
+        .parse_prepared_statement(
+            "statement_name",
+            "insert into schema_name.table_name values ($1);",
+            &[Some(PostgreSqlType::SmallInt)],
+        )
+        .expect("no system errors");
+    for value in &["1", "2"] {
+        engine
+            .bind_prepared_statement_to_portal(
+                "portal_name",
+                "statement_name",
+                &[PostgreSqlFormat::Text],
+                &[Some(value.as_bytes().to_vec())],
+                &[],
+            )
+            .expect("no system errors");
+        engine.execute_portal("portal_name", 0).expect("no system errors");
+    }
+    engine
+        .execute("select * from pg_stat_statements;")
+        .expect("no system errors");
+
+    assert_eq!(
+        calls_and_rows(&collector),
+        vec![
+            (
+                "create table schema_name.table_name (column_1 smallint)".to_owned(),
+                "1".to_owned(),
+                "0".to_owned()
+            ),
+            (
+                "insert into schema_name.table_name values ($1)".to_owned(),
+                "2".to_owned(),
+                "2".to_owned()
+            ),
+        ]
+    );
+}
@@ -0,0 +1,128 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use protocol::sql_types::PostgreSqlType;
+
+#[rstest::fixture]
+fn with_names(sql_engine_with_schema: (QueryExecutor, ResultCollector)) -> (QueryExecutor, ResultCollector) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.names (id smallint, name varchar(20));")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.names values (1, 'Alice'), (2, 'bob'), (3, null);")
+        .expect("no system errors");
+
+    (engine, collector)
+}
+
+#[rstest::rstest]
+fn string_functions_in_select_list(with_names: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = with_names;
+    engine
+        .execute(
+            "select upper(name), lower(trim(name, 'Ae')) as trimmed, length(name), substring(name, 2, 2) \
+             from schema_name.names;",
+        )
+        .expect("no system errors");
+
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(3)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("upper", PostgreSqlType::VarChar),
+            ColumnDescription::new("trimmed", PostgreSqlType::VarChar),
+            ColumnDescription::new("length", PostgreSqlType::Integer),
+            ColumnDescription::new("substring", PostgreSqlType::VarChar),
+        ])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![
+                Some("ALICE".to_owned()),
+                Some("lic".to_owned()),
+                Some("5".to_owned()),
+                Some("li".to_owned()),
+            ],
+            vec![
+                Some("BOB".to_owned()),
+                Some("bob".to_owned()),
+                Some("3".to_owned()),
+                Some("ob".to_owned()),
+            ],
+            vec![None, None, None, None],
+        ])),
+        Ok(QueryEvent::SelectComplete(3)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn string_functions_in_where_clause(with_names: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = with_names;
+    engine
+        .execute("select id from schema_name.names where upper(trim(name, 'A')) = 'LICE' or length(name) = 3;")
+        .expect("no system errors");
+
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(3)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "id",
+            PostgreSqlType::SmallInt,
+        )
+        .with_source(16384, 1)])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("1".to_owned())],
+            vec![Some("2".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(2)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn invalid_calls_of_string_functions(with_names: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = with_names;
+    engine
+        .execute("select upper(id) from schema_name.names;")
+        .expect("no system errors");
+    engine
+        .execute("select substring(name, 1, -1) from schema_name.names;")
+        .expect("no system errors");
+
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(3)),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::function_does_not_exist("upper(smallint)".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "substring",
+            PostgreSqlType::VarChar,
+        )])),
+        Err(QueryError::substring_error()),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
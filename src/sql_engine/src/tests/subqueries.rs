@@ -0,0 +1,186 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use protocol::sql_types::PostgreSqlType;
+
+#[rstest::fixture]
+fn with_orders(sql_engine_with_schema: (QueryExecutor, ResultCollector)) -> (QueryExecutor, ResultCollector) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.customers (id smallint, name varchar(10));")
+        .expect("no system errors");
+    engine
+        .execute("create table schema_name.orders (customer_id smallint, amount integer);")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.customers values (1, 'a'), (2, 'b'), (3, 'c');")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.orders values (1, 10), (1, 20), (3, 30), (null, 40);")
+        .expect("no system errors");
+
+    (engine, collector)
+}
+
+fn names(names: &[&str]) -> Vec<QueryResult> {
+    let mut results = vec![Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+        "name",
+        PostgreSqlType::VarChar,
+    )
+    .with_source(16384, 2)]))];
+    if !names.is_empty() {
+        results.push(Ok(QueryEvent::DataRows(
+            names.iter().map(|name| vec![Some((*name).to_owned())]).collect(),
+        )));
+    }
+    results.push(Ok(QueryEvent::SelectComplete(names.len())));
+    results.push(Ok(QueryEvent::QueryComplete));
+    results
+}
+
+fn created() -> Vec<QueryResult> {
+    vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(3)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(4)),
+        Ok(QueryEvent::QueryComplete),
+    ]
+}
+
+#[rstest::rstest]
+fn in_subquery(with_orders: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = with_orders;
+    engine
+        .execute("select name from schema_name.customers where id in (select customer_id from schema_name.orders);")
+        .expect("no system errors");
+    engine
+        .execute(
+            "select name from schema_name.customers \
+             where id in (select customer_id from schema_name.orders where amount > 15) and name <> 'c';",
+        )
+        .expect("no system errors");
+
+    let mut expected = created();
+    expected.extend(names(&["a", "c"]));
+    expected.extend(names(&["a"]));
+    collector.assert_content_for_single_queries(expected);
+}
+
+#[rstest::rstest]
+fn not_in_subquery_with_null_is_unknown(with_orders: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = with_orders;
+    engine
+        .execute("select name from schema_name.customers where id not in (select customer_id from schema_name.orders);")
+        .expect("no system errors");
+    engine
+        .execute(
+            "select name from schema_name.customers \
+             where id not in (select customer_id from schema_name.orders where customer_id is not null);",
+        )
+        .expect("no system errors");
+    engine
+        .execute(
+            "select name from schema_name.customers \
+             where id not in (select customer_id from schema_name.orders where amount > 100);",
+        )
+        .expect("no system errors");
+
+    let mut expected = created();
+    expected.extend(names(&[]));
+    expected.extend(names(&["b"]));
+    expected.extend(names(&["a", "b", "c"]));
+    collector.assert_content_for_single_queries(expected);
+}
+
+#[rstest::rstest]
+fn correlated_exists(with_orders: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = with_orders;
+    engine
+        .execute(
+            "select name from schema_name.customers \
+             where exists (select amount from schema_name.orders where customer_id = id);",
+        )
+        .expect("no system errors");
+    engine
+        .execute(
+            "select name from schema_name.customers \
+             where not exists (select * from schema_name.orders where orders.customer_id = customers.id);",
+        )
+        .expect("no system errors");
+    engine
+        .execute(
+            "select name from schema_name.customers \
+             where exists (select * from schema_name.orders where customer_id = id and amount > 15);",
+        )
+        .expect("no system errors");
+
+    let mut expected = created();
+    expected.extend(names(&["a", "c"]));
+    expected.extend(names(&["b"]));
+    expected.extend(names(&["a", "c"]));
+    collector.assert_content_for_single_queries(expected);
+}
+
+#[rstest::rstest]
+fn correlated_in_subquery(with_orders: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = with_orders;
+    engine
+        .execute(
+            "select name from schema_name.customers \
+             where 20 in (select amount from schema_name.orders where customer_id = id);",
+        )
+        .expect("no system errors");
+
+    let mut expected = created();
+    expected.extend(names(&["a"]));
+    collector.assert_content_for_single_queries(expected);
+}
+
+#[rstest::rstest]
+fn invalid_subqueries(with_orders: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = with_orders;
+    engine
+        .execute(
+            "select name from schema_name.customers where id in (select customer_id, amount from schema_name.orders);",
+        )
+        .expect("no system errors");
+    engine
+        .execute("select name from schema_name.customers where name in (select amount from schema_name.orders);")
+        .expect("no system errors");
+    engine
+        .execute("select name from schema_name.customers where exists (select * from schema_name.non_existent);")
+        .expect("no system errors");
+
+    let mut expected = created();
+    expected.extend(vec![
+        Err(QueryError::subquery_too_many_columns()),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::undefined_function(
+            "=".to_owned(),
+            "varchar".to_owned(),
+            "integer".to_owned(),
+        )),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::table_does_not_exist("schema_name.non_existent".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+    collector.assert_content_for_single_queries(expected);
+}
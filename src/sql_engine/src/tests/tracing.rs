@@ -0,0 +1,106 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use ::tracing::{
+    field::{Field, Visit},
+    span::{Attributes, Id, Record},
+    Event, Metadata, Subscriber,
+};
+use std::{
+    fmt::Debug,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+type SpanFields = Vec<(String, String)>;
+
+/// Collects names of created spans together with their fields
+#[derive(Default, Clone)]
+struct Spans {
+    next_id: Arc<AtomicU64>,
+    spans: Arc<Mutex<Vec<(String, SpanFields)>>>,
+}
+
+struct Fields(SpanFields);
+
+impl Visit for Fields {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0.push((field.name().to_owned(), format!("{:?}", value)));
+    }
+}
+
+impl Subscriber for Spans {
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let mut fields = Fields(vec![]);
+        span.record(&mut fields);
+        self.spans
+            .lock()
+            .expect("locked")
+            .push((span.metadata().name().to_owned(), fields.0));
+        Id::from_u64(self.next_id.fetch_add(1, Ordering::SeqCst) + 1)
+    }
+
+    fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, _event: &Event<'_>) {}
+
+    fn enter(&self, _span: &Id) {}
+
+    fn exit(&self, _span: &Id) {}
+}
+
+impl Spans {
+    fn names(&self) -> Vec<String> {
+        self.spans
+            .lock()
+            .expect("locked")
+            .iter()
+            .map(|(name, _fields)| name.clone())
+            .collect()
+    }
+}
+
+#[rstest::rstest]
+fn statement_pipeline_is_traced(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (engine, _collector) = sql_engine_with_schema;
+    let mut engine = engine.with_session_id(7);
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint);")
+        .expect("no system errors");
+
+    let spans = Spans::default();
+    ::tracing::subscriber::with_default(spans.clone(), || {
+        engine
+            .execute("insert into schema_name.table_name values (1);")
+            .expect("no system errors");
+    });
+
+    assert_eq!(
+        spans.names(),
+        vec!["statement", "parse", "parse", "plan", "execute", "write_into"]
+    );
+    assert_eq!(
+        spans.spans.lock().expect("locked")[0].1,
+        vec![
+            ("session_id".to_owned(), "7".to_owned()),
+            ("statement_id".to_owned(), "3".to_owned())
+        ]
+    );
+}
@@ -0,0 +1,344 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use protocol::sql_types::PostgreSqlType;
+
+#[rstest::fixture]
+fn with_table(sql_engine_with_schema: (QueryExecutor, ResultCollector)) -> (QueryExecutor, ResultCollector) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.items (id smallint);")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.items values (1), (2);")
+        .expect("no system errors");
+
+    (engine, collector)
+}
+
+fn created() -> Vec<QueryResult> {
+    vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(2)),
+        Ok(QueryEvent::QueryComplete),
+    ]
+}
+
+fn ids(ids: &[&str]) -> Vec<QueryResult> {
+    let mut results = vec![Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+        "id",
+        PostgreSqlType::SmallInt,
+    )
+    .with_source(16384, 1)]))];
+    if !ids.is_empty() {
+        results.push(Ok(QueryEvent::DataRows(
+            ids.iter().map(|id| vec![Some((*id).to_owned())]).collect(),
+        )));
+    }
+    results.push(Ok(QueryEvent::SelectComplete(ids.len())));
+    results.push(Ok(QueryEvent::QueryComplete));
+    results
+}
+
+fn completed(event: QueryResult) -> Vec<QueryResult> {
+    vec![event, Ok(QueryEvent::QueryComplete)]
+}
+
+#[rstest::rstest]
+fn rollback_reverts_changes_of_block(with_table: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = with_table;
+    engine.execute("begin;").expect("no system errors");
+    engine
+        .execute("insert into schema_name.items values (3);")
+        .expect("no system errors");
+    engine
+        .execute("update schema_name.items set id = 5;")
+        .expect("no system errors");
+    engine
+        .execute("delete from schema_name.items;")
+        .expect("no system errors");
+    engine.execute("rollback;").expect("no system errors");
+    engine
+        .execute("select id from schema_name.items;")
+        .expect("no system errors");
+
+    let mut expected = created();
+    expected.extend(completed(Ok(QueryEvent::TransactionStarted)));
+    expected.extend(completed(Ok(QueryEvent::RecordsInserted(1))));
+    expected.extend(completed(Ok(QueryEvent::RecordsUpdated(3))));
+    expected.extend(completed(Ok(QueryEvent::RecordsDeleted(3))));
+    expected.extend(completed(Ok(QueryEvent::TransactionRolledBack)));
+    expected.extend(ids(&["1", "2"]));
+    collector.assert_content_for_single_queries(expected);
+}
+
+#[rstest::rstest]
+fn commit_keeps_changes_of_block(with_table: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = with_table;
+    engine.execute("begin;").expect("no system errors");
+    engine
+        .execute("delete from schema_name.items;")
+        .expect("no system errors");
+    engine.execute("commit;").expect("no system errors");
+    engine.execute("rollback;").expect("no system errors");
+    engine
+        .execute("select id from schema_name.items;")
+        .expect("no system errors");
+
+    let mut expected = created();
+    expected.extend(completed(Ok(QueryEvent::TransactionStarted)));
+    expected.extend(completed(Ok(QueryEvent::RecordsDeleted(2))));
+    expected.extend(completed(Ok(QueryEvent::TransactionCommitted)));
+    expected.extend(completed(Ok(QueryEvent::TransactionRolledBack)));
+    expected.extend(ids(&[]));
+    collector.assert_content_for_single_queries(expected);
+}
+
+#[rstest::rstest]
+fn rollback_to_savepoint_keeps_changes_of_parent(with_table: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = with_table;
+    engine.execute("begin;").expect("no system errors");
+    engine
+        .execute("insert into schema_name.items values (3);")
+        .expect("no system errors");
+    engine.execute("savepoint before_delete;").expect("no system errors");
+    engine
+        .execute("delete from schema_name.items;")
+        .expect("no system errors");
+    engine
+        .execute("rollback to savepoint before_delete;")
+        .expect("no system errors");
+    engine.execute("commit;").expect("no system errors");
+    engine
+        .execute("select id from schema_name.items;")
+        .expect("no system errors");
+
+    let mut expected = created();
+    expected.extend(completed(Ok(QueryEvent::TransactionStarted)));
+    expected.extend(completed(Ok(QueryEvent::RecordsInserted(1))));
+    expected.extend(completed(Ok(QueryEvent::SavepointCreated)));
+    expected.extend(completed(Ok(QueryEvent::RecordsDeleted(3))));
+    expected.extend(completed(Ok(QueryEvent::TransactionRolledBack)));
+    expected.extend(completed(Ok(QueryEvent::TransactionCommitted)));
+    expected.extend(ids(&["1", "2", "3"]));
+    collector.assert_content_for_single_queries(expected);
+}
+
+#[rstest::rstest]
+fn failed_subtransaction_is_rolled_back_without_parent(with_table: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = with_table;
+    engine.execute("begin;").expect("no system errors");
+    engine
+        .execute("insert into schema_name.items values (3);")
+        .expect("no system errors");
+    engine.execute("savepoint attempt;").expect("no system errors");
+    engine
+        .execute("insert into schema_name.items values (4);")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.missing values (5);")
+        .expect("no system errors");
+    engine
+        .execute("select id from schema_name.items;")
+        .expect("no system errors");
+    engine.execute("rollback to attempt;").expect("no system errors");
+    engine.execute("commit;").expect("no system errors");
+    engine
+        .execute("select id from schema_name.items;")
+        .expect("no system errors");
+
+    let mut expected = created();
+    expected.extend(completed(Ok(QueryEvent::TransactionStarted)));
+    expected.extend(completed(Ok(QueryEvent::RecordsInserted(1))));
+    expected.extend(completed(Ok(QueryEvent::SavepointCreated)));
+    expected.extend(completed(Ok(QueryEvent::RecordsInserted(1))));
+    expected.extend(completed(Err(QueryError::table_does_not_exist(
+        "schema_name.missing".to_owned(),
+    ))));
+    expected.extend(completed(Err(QueryError::in_failed_sql_transaction())));
+    expected.extend(completed(Ok(QueryEvent::TransactionRolledBack)));
+    expected.extend(completed(Ok(QueryEvent::TransactionCommitted)));
+    expected.extend(ids(&["1", "2", "3"]));
+    collector.assert_content_for_single_queries(expected);
+}
+
+#[rstest::rstest]
+fn commit_of_failed_block_rolls_it_back(with_table: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = with_table;
+    engine.execute("begin;").expect("no system errors");
+    engine
+        .execute("delete from schema_name.items;")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.missing values (5);")
+        .expect("no system errors");
+    engine.execute("commit;").expect("no system errors");
+    engine
+        .execute("select id from schema_name.items;")
+        .expect("no system errors");
+
+    let mut expected = created();
+    expected.extend(completed(Ok(QueryEvent::TransactionStarted)));
+    expected.extend(completed(Ok(QueryEvent::RecordsDeleted(2))));
+    expected.extend(completed(Err(QueryError::table_does_not_exist(
+        "schema_name.missing".to_owned(),
+    ))));
+    expected.extend(completed(Ok(QueryEvent::TransactionRolledBack)));
+    expected.extend(ids(&["1", "2"]));
+    collector.assert_content_for_single_queries(expected);
+}
+
+#[rstest::rstest]
+fn released_savepoint_can_not_be_rolled_back_to(with_table: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = with_table;
+    engine.execute("begin;").expect("no system errors");
+    engine.execute("savepoint first;").expect("no system errors");
+    engine
+        .execute("delete from schema_name.items;")
+        .expect("no system errors");
+    engine.execute("release savepoint first;").expect("no system errors");
+    engine.execute("rollback to first;").expect("no system errors");
+    engine.execute("rollback;").expect("no system errors");
+    engine
+        .execute("select id from schema_name.items;")
+        .expect("no system errors");
+
+    let mut expected = created();
+    expected.extend(completed(Ok(QueryEvent::TransactionStarted)));
+    expected.extend(completed(Ok(QueryEvent::SavepointCreated)));
+    expected.extend(completed(Ok(QueryEvent::RecordsDeleted(2))));
+    expected.extend(completed(Ok(QueryEvent::SavepointReleased)));
+    expected.extend(completed(Err(QueryError::savepoint_does_not_exist("first".to_owned()))));
+    expected.extend(completed(Ok(QueryEvent::TransactionRolledBack)));
+    expected.extend(ids(&["1", "2"]));
+    collector.assert_content_for_single_queries(expected);
+}
+
+#[rstest::rstest]
+fn savepoints_outside_of_block(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    engine.execute("savepoint first;").expect("no system errors");
+    engine.execute("release savepoint first;").expect("no system errors");
+    engine
+        .execute("rollback to savepoint first;")
+        .expect("no system errors");
+
+    let mut expected = completed(Err(QueryError::no_active_sql_transaction("SAVEPOINT")));
+    expected.extend(completed(Err(QueryError::no_active_sql_transaction(
+        "RELEASE SAVEPOINT",
+    ))));
+    expected.extend(completed(Err(QueryError::no_active_sql_transaction(
+        "ROLLBACK TO SAVEPOINT",
+    ))));
+    collector.assert_content_for_single_queries(expected);
+}
+
+#[rstest::rstest]
+fn caught_error_of_anonymous_block_does_not_fail_block(with_table: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = with_table;
+    engine.execute("begin;").expect("no system errors");
+    engine
+        .execute(
+            "do $$
+            begin
+                delete from schema_name.items;
+                insert into schema_name.missing values (1);
+            exception
+                when undefined_table then
+                    null;
+            end $$;",
+        )
+        .expect("no system errors");
+    engine.execute("commit;").expect("no system errors");
+    engine
+        .execute("select id from schema_name.items;")
+        .expect("no system errors");
+
+    let mut expected = created();
+    expected.extend(completed(Ok(QueryEvent::TransactionStarted)));
+    expected.extend(completed(Ok(QueryEvent::BlockExecuted)));
+    expected.extend(completed(Ok(QueryEvent::TransactionCommitted)));
+    expected.extend(ids(&[]));
+    collector.assert_content_for_single_queries(expected);
+}
+
+#[test]
+fn rows_of_block_are_visible_only_to_its_session() {
+    let storage = in_memory_catalog_manager();
+    let writer_results = sender();
+    let mut writer = QueryExecutor::new(storage.clone(), writer_results.clone(), SUPERUSER);
+    let reader_results = sender();
+    let mut reader = QueryExecutor::new(storage, reader_results.clone(), SUPERUSER);
+    writer.execute("create schema schema_name;").expect("no system errors");
+    writer
+        .execute("create table schema_name.items (id smallint);")
+        .expect("no system errors");
+    writer
+        .execute("insert into schema_name.items values (1), (2);")
+        .expect("no system errors");
+
+    writer.execute("begin;").expect("no system errors");
+    writer
+        .execute("insert into schema_name.items values (3);")
+        .expect("no system errors");
+    writer
+        .execute("delete from schema_name.items where id = 1;")
+        .expect("no system errors");
+    writer
+        .execute("select id from schema_name.items;")
+        .expect("no system errors");
+    reader
+        .execute("select id from schema_name.items;")
+        .expect("no system errors");
+    writer.execute("commit;").expect("no system errors");
+    reader
+        .execute("select id from schema_name.items;")
+        .expect("no system errors");
+
+    let mut expected = created();
+    expected.extend(completed(Ok(QueryEvent::TransactionStarted)));
+    expected.extend(completed(Ok(QueryEvent::RecordsInserted(1))));
+    expected.extend(completed(Ok(QueryEvent::RecordsDeleted(1))));
+    expected.extend(ids(&["2", "3"]));
+    expected.extend(completed(Ok(QueryEvent::TransactionCommitted)));
+    writer_results.assert_content_for_single_queries(expected);
+
+    let mut expected = ids(&["1", "2"]);
+    expected.extend(ids(&["2", "3"]));
+    reader_results.assert_content_for_single_queries(expected);
+}
+
+#[rstest::rstest]
+fn ddl_is_rejected_in_block(with_table: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = with_table;
+    engine.execute("begin;").expect("no system errors");
+    engine
+        .execute("drop table schema_name.items;")
+        .expect("no system errors");
+    engine.execute("rollback;").expect("no system errors");
+    engine
+        .execute("select id from schema_name.items;")
+        .expect("no system errors");
+
+    let mut expected = created();
+    expected.extend(completed(Ok(QueryEvent::TransactionStarted)));
+    expected.extend(completed(Err(QueryError::active_sql_transaction("DROP TABLE"))));
+    expected.extend(completed(Ok(QueryEvent::TransactionRolledBack)));
+    expected.extend(ids(&["1", "2"]));
+    collector.assert_content_for_single_queries(expected);
+}
@@ -46,17 +46,29 @@ fn update_all_records(sql_engine_with_schema: (QueryExecutor, ResultCollector))
         Ok(QueryEvent::QueryComplete),
         Ok(QueryEvent::RecordsInserted(1)),
         Ok(QueryEvent::QueryComplete),
-        Ok(QueryEvent::RecordsSelected((
-            vec![("column_test".to_owned(), PostgreSqlType::SmallInt)],
-            vec![vec!["123".to_owned()], vec!["456".to_owned()]],
-        ))),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "column_test",
+            PostgreSqlType::SmallInt,
+        )
+        .with_source(16384, 1)])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("123".to_owned())],
+            vec![Some("456".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(2)),
         Ok(QueryEvent::QueryComplete),
         Ok(QueryEvent::RecordsUpdated(2)),
         Ok(QueryEvent::QueryComplete),
-        Ok(QueryEvent::RecordsSelected((
-            vec![("column_test".to_owned(), PostgreSqlType::SmallInt)],
-            vec![vec!["789".to_owned()], vec!["789".to_owned()]],
-        ))),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "column_test",
+            PostgreSqlType::SmallInt,
+        )
+        .with_source(16384, 1)])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("789".to_owned())],
+            vec![Some("789".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(2)),
         Ok(QueryEvent::QueryComplete),
     ]);
 }
@@ -92,29 +104,27 @@ fn update_single_column_of_all_records(sql_engine_with_schema: (QueryExecutor, R
         Ok(QueryEvent::QueryComplete),
         Ok(QueryEvent::RecordsInserted(1)),
         Ok(QueryEvent::QueryComplete),
-        Ok(QueryEvent::RecordsSelected((
-            vec![
-                ("col1".to_owned(), PostgreSqlType::SmallInt),
-                ("col2".to_owned(), PostgreSqlType::SmallInt),
-            ],
-            vec![
-                vec!["123".to_owned(), "789".to_owned()],
-                vec!["456".to_owned(), "789".to_owned()],
-            ],
-        ))),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("col1", PostgreSqlType::SmallInt).with_source(16384, 1),
+            ColumnDescription::new("col2", PostgreSqlType::SmallInt).with_source(16384, 2),
+        ])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("123".to_owned()), Some("789".to_owned())],
+            vec![Some("456".to_owned()), Some("789".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(2)),
         Ok(QueryEvent::QueryComplete),
         Ok(QueryEvent::RecordsUpdated(2)),
         Ok(QueryEvent::QueryComplete),
-        Ok(QueryEvent::RecordsSelected((
-            vec![
-                ("col1".to_owned(), PostgreSqlType::SmallInt),
-                ("col2".to_owned(), PostgreSqlType::SmallInt),
-            ],
-            vec![
-                vec!["123".to_owned(), "357".to_owned()],
-                vec!["456".to_owned(), "357".to_owned()],
-            ],
-        ))),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("col1", PostgreSqlType::SmallInt).with_source(16384, 1),
+            ColumnDescription::new("col2", PostgreSqlType::SmallInt).with_source(16384, 2),
+        ])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("123".to_owned()), Some("357".to_owned())],
+            vec![Some("456".to_owned()), Some("357".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(2)),
         Ok(QueryEvent::QueryComplete),
     ]);
 }
@@ -150,31 +160,29 @@ fn update_multiple_columns_of_all_records(sql_engine_with_schema: (QueryExecutor
         Ok(QueryEvent::QueryComplete),
         Ok(QueryEvent::RecordsInserted(1)),
         Ok(QueryEvent::QueryComplete),
-        Ok(QueryEvent::RecordsSelected((
-            vec![
-                ("col1".to_owned(), PostgreSqlType::SmallInt),
-                ("col2".to_owned(), PostgreSqlType::SmallInt),
-                ("col3".to_owned(), PostgreSqlType::SmallInt),
-            ],
-            vec![
-                vec!["111".to_owned(), "222".to_owned(), "333".to_owned()],
-                vec!["444".to_owned(), "555".to_owned(), "666".to_owned()],
-            ],
-        ))),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("col1", PostgreSqlType::SmallInt).with_source(16384, 1),
+            ColumnDescription::new("col2", PostgreSqlType::SmallInt).with_source(16384, 2),
+            ColumnDescription::new("col3", PostgreSqlType::SmallInt).with_source(16384, 3),
+        ])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("111".to_owned()), Some("222".to_owned()), Some("333".to_owned())],
+            vec![Some("444".to_owned()), Some("555".to_owned()), Some("666".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(2)),
         Ok(QueryEvent::QueryComplete),
         Ok(QueryEvent::RecordsUpdated(2)),
         Ok(QueryEvent::QueryComplete),
-        Ok(QueryEvent::RecordsSelected((
-            vec![
-                ("col1".to_owned(), PostgreSqlType::SmallInt),
-                ("col2".to_owned(), PostgreSqlType::SmallInt),
-                ("col3".to_owned(), PostgreSqlType::SmallInt),
-            ],
-            vec![
-                vec!["999".to_owned(), "222".to_owned(), "777".to_owned()],
-                vec!["999".to_owned(), "555".to_owned(), "777".to_owned()],
-            ],
-        ))),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("col1", PostgreSqlType::SmallInt).with_source(16384, 1),
+            ColumnDescription::new("col2", PostgreSqlType::SmallInt).with_source(16384, 2),
+            ColumnDescription::new("col3", PostgreSqlType::SmallInt).with_source(16384, 3),
+        ])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("999".to_owned()), Some("222".to_owned()), Some("777".to_owned())],
+            vec![Some("999".to_owned()), Some("555".to_owned()), Some("777".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(2)),
         Ok(QueryEvent::QueryComplete),
     ]);
 }
@@ -205,33 +213,31 @@ fn update_all_records_in_multiple_columns(sql_engine_with_schema: (QueryExecutor
         Ok(QueryEvent::QueryComplete),
         Ok(QueryEvent::RecordsInserted(3)),
         Ok(QueryEvent::QueryComplete),
-        Ok(QueryEvent::RecordsSelected((
-            vec![
-                ("column_1".to_owned(), PostgreSqlType::SmallInt),
-                ("column_2".to_owned(), PostgreSqlType::SmallInt),
-                ("column_3".to_owned(), PostgreSqlType::SmallInt),
-            ],
-            vec![
-                vec!["1".to_owned(), "2".to_owned(), "3".to_owned()],
-                vec!["4".to_owned(), "5".to_owned(), "6".to_owned()],
-                vec!["7".to_owned(), "8".to_owned(), "9".to_owned()],
-            ],
-        ))),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("column_1", PostgreSqlType::SmallInt).with_source(16384, 1),
+            ColumnDescription::new("column_2", PostgreSqlType::SmallInt).with_source(16384, 2),
+            ColumnDescription::new("column_3", PostgreSqlType::SmallInt).with_source(16384, 3),
+        ])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("1".to_owned()), Some("2".to_owned()), Some("3".to_owned())],
+            vec![Some("4".to_owned()), Some("5".to_owned()), Some("6".to_owned())],
+            vec![Some("7".to_owned()), Some("8".to_owned()), Some("9".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(3)),
         Ok(QueryEvent::QueryComplete),
         Ok(QueryEvent::RecordsUpdated(3)),
         Ok(QueryEvent::QueryComplete),
-        Ok(QueryEvent::RecordsSelected((
-            vec![
-                ("column_1".to_owned(), PostgreSqlType::SmallInt),
-                ("column_2".to_owned(), PostgreSqlType::SmallInt),
-                ("column_3".to_owned(), PostgreSqlType::SmallInt),
-            ],
-            vec![
-                vec!["10".to_owned(), "-20".to_owned(), "30".to_owned()],
-                vec!["10".to_owned(), "-20".to_owned(), "30".to_owned()],
-                vec!["10".to_owned(), "-20".to_owned(), "30".to_owned()],
-            ],
-        ))),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("column_1", PostgreSqlType::SmallInt).with_source(16384, 1),
+            ColumnDescription::new("column_2", PostgreSqlType::SmallInt).with_source(16384, 2),
+            ColumnDescription::new("column_3", PostgreSqlType::SmallInt).with_source(16384, 3),
+        ])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("10".to_owned()), Some("-20".to_owned()), Some("30".to_owned())],
+            vec![Some("10".to_owned()), Some("-20".to_owned()), Some("30".to_owned())],
+            vec![Some("10".to_owned()), Some("-20".to_owned()), Some("30".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(3)),
         Ok(QueryEvent::QueryComplete),
     ]);
 }
@@ -274,10 +280,13 @@ fn update_non_existent_columns_of_records(sql_engine_with_schema: (QueryExecutor
         Ok(QueryEvent::QueryComplete),
         Ok(QueryEvent::RecordsInserted(1)),
         Ok(QueryEvent::QueryComplete),
-        Ok(QueryEvent::RecordsSelected((
-            vec![("column_test".to_owned(), PostgreSqlType::SmallInt)],
-            vec![vec!["123".to_owned()]],
-        ))),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "column_test",
+            PostgreSqlType::SmallInt,
+        )
+        .with_source(16384, 1)])),
+        Ok(QueryEvent::DataRows(vec![vec![Some("123".to_owned())]])),
+        Ok(QueryEvent::SelectComplete(1)),
         Ok(QueryEvent::QueryComplete),
         Err(QueryError::column_does_not_exist(vec![
             "col1".to_owned(),
@@ -334,10 +343,13 @@ mod operators {
                     Ok(QueryEvent::QueryComplete),
                     Ok(QueryEvent::RecordsUpdated(1)),
                     Ok(QueryEvent::QueryComplete),
-                    Ok(QueryEvent::RecordsSelected((
-                        vec![("column_si".to_owned(), PostgreSqlType::SmallInt)],
-                        vec![vec!["3".to_owned()]],
-                    ))),
+                    Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+                        "column_si",
+                        PostgreSqlType::SmallInt,
+                    )
+                    .with_source(16384, 1)])),
+                    Ok(QueryEvent::DataRows(vec![vec![Some("3".to_owned())]])),
+                    Ok(QueryEvent::SelectComplete(1)),
                     Ok(QueryEvent::QueryComplete),
                 ]);
             }
@@ -361,10 +373,13 @@ mod operators {
                     Ok(QueryEvent::QueryComplete),
                     Ok(QueryEvent::RecordsUpdated(1)),
                     Ok(QueryEvent::QueryComplete),
-                    Ok(QueryEvent::RecordsSelected((
-                        vec![("column_si".to_owned(), PostgreSqlType::SmallInt)],
-                        vec![vec!["-1".to_owned()]],
-                    ))),
+                    Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+                        "column_si",
+                        PostgreSqlType::SmallInt,
+                    )
+                    .with_source(16384, 1)])),
+                    Ok(QueryEvent::DataRows(vec![vec![Some("-1".to_owned())]])),
+                    Ok(QueryEvent::SelectComplete(1)),
                     Ok(QueryEvent::QueryComplete),
                 ]);
             }
@@ -388,10 +403,13 @@ mod operators {
                     Ok(QueryEvent::QueryComplete),
                     Ok(QueryEvent::RecordsUpdated(1)),
                     Ok(QueryEvent::QueryComplete),
-                    Ok(QueryEvent::RecordsSelected((
-                        vec![("column_si".to_owned(), PostgreSqlType::SmallInt)],
-                        vec![vec!["6".to_owned()]],
-                    ))),
+                    Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+                        "column_si",
+                        PostgreSqlType::SmallInt,
+                    )
+                    .with_source(16384, 1)])),
+                    Ok(QueryEvent::DataRows(vec![vec![Some("6".to_owned())]])),
+                    Ok(QueryEvent::SelectComplete(1)),
                     Ok(QueryEvent::QueryComplete),
                 ]);
             }
@@ -415,10 +433,13 @@ mod operators {
                     Ok(QueryEvent::QueryComplete),
                     Ok(QueryEvent::RecordsUpdated(1)),
                     Ok(QueryEvent::QueryComplete),
-                    Ok(QueryEvent::RecordsSelected((
-                        vec![("column_si".to_owned(), PostgreSqlType::SmallInt)],
-                        vec![vec!["4".to_owned()]],
-                    ))),
+                    Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+                        "column_si",
+                        PostgreSqlType::SmallInt,
+                    )
+                    .with_source(16384, 1)])),
+                    Ok(QueryEvent::DataRows(vec![vec![Some("4".to_owned())]])),
+                    Ok(QueryEvent::SelectComplete(1)),
                     Ok(QueryEvent::QueryComplete),
                 ]);
             }
@@ -442,10 +463,13 @@ mod operators {
                     Ok(QueryEvent::QueryComplete),
                     Ok(QueryEvent::RecordsUpdated(1)),
                     Ok(QueryEvent::QueryComplete),
-                    Ok(QueryEvent::RecordsSelected((
-                        vec![("column_si".to_owned(), PostgreSqlType::SmallInt)],
-                        vec![vec!["0".to_owned()]],
-                    ))),
+                    Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+                        "column_si",
+                        PostgreSqlType::SmallInt,
+                    )
+                    .with_source(16384, 1)])),
+                    Ok(QueryEvent::DataRows(vec![vec![Some("0".to_owned())]])),
+                    Ok(QueryEvent::SelectComplete(1)),
                     Ok(QueryEvent::QueryComplete),
                 ]);
             }
@@ -472,10 +496,12 @@ mod operators {
                     Ok(QueryEvent::QueryComplete),
                     Ok(QueryEvent::RecordsUpdated(1)),
                     Ok(QueryEvent::QueryComplete),
-                    Ok(QueryEvent::RecordsSelected((
-                        vec![("column_si".to_owned(), PostgreSqlType::SmallInt)],
-                        vec![vec!["64".to_owned()]],
-                    ))),
+                    Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+                        "column_si",
+                        PostgreSqlType::SmallInt,
+                    )])),
+                    Ok(QueryEvent::DataRows(vec![vec![Some("64".to_owned())]])),
+                    Ok(QueryEvent::SelectComplete(1)),
                     Ok(QueryEvent::QueryComplete),
                 ]);
             }
@@ -501,10 +527,12 @@ mod operators {
                     Ok(QueryEvent::QueryComplete),
                     Ok(QueryEvent::RecordsUpdated(1)),
                     Ok(QueryEvent::QueryComplete),
-                    Ok(QueryEvent::RecordsSelected((
-                        vec![("column_si".to_owned(), PostgreSqlType::SmallInt)],
-                        vec![vec!["4".to_owned()]],
-                    ))),
+                    Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+                        "column_si",
+                        PostgreSqlType::SmallInt,
+                    )])),
+                    Ok(QueryEvent::DataRows(vec![vec![Some("4".to_owned())]])),
+                    Ok(QueryEvent::SelectComplete(1)),
                     Ok(QueryEvent::QueryComplete),
                 ]);
             }
@@ -530,10 +558,12 @@ mod operators {
                     Ok(QueryEvent::QueryComplete),
                     Ok(QueryEvent::RecordsUpdated(1)),
                     Ok(QueryEvent::QueryComplete),
-                    Ok(QueryEvent::RecordsSelected((
-                        vec![("column_si".to_owned(), PostgreSqlType::SmallInt)],
-                        vec![vec!["2".to_owned()]],
-                    ))),
+                    Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+                        "column_si",
+                        PostgreSqlType::SmallInt,
+                    )])),
+                    Ok(QueryEvent::DataRows(vec![vec![Some("2".to_owned())]])),
+                    Ok(QueryEvent::SelectComplete(1)),
                     Ok(QueryEvent::QueryComplete),
                 ]);
             }
@@ -559,10 +589,12 @@ mod operators {
                     Ok(QueryEvent::QueryComplete),
                     Ok(QueryEvent::RecordsUpdated(1)),
                     Ok(QueryEvent::QueryComplete),
-                    Ok(QueryEvent::RecordsSelected((
-                        vec![("column_si".to_owned(), PostgreSqlType::SmallInt)],
-                        vec![vec!["120".to_owned()]],
-                    ))),
+                    Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+                        "column_si",
+                        PostgreSqlType::SmallInt,
+                    )])),
+                    Ok(QueryEvent::DataRows(vec![vec![Some("120".to_owned())]])),
+                    Ok(QueryEvent::SelectComplete(1)),
                     Ok(QueryEvent::QueryComplete),
                 ]);
             }
@@ -588,10 +620,12 @@ mod operators {
                     Ok(QueryEvent::QueryComplete),
                     Ok(QueryEvent::RecordsUpdated(1)),
                     Ok(QueryEvent::QueryComplete),
-                    Ok(QueryEvent::RecordsSelected((
-                        vec![("column_si".to_owned(), PostgreSqlType::SmallInt)],
-                        vec![vec!["120".to_owned()]],
-                    ))),
+                    Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+                        "column_si",
+                        PostgreSqlType::SmallInt,
+                    )])),
+                    Ok(QueryEvent::DataRows(vec![vec![Some("120".to_owned())]])),
+                    Ok(QueryEvent::SelectComplete(1)),
                     Ok(QueryEvent::QueryComplete),
                 ]);
             }
@@ -617,10 +651,12 @@ mod operators {
                     Ok(QueryEvent::QueryComplete),
                     Ok(QueryEvent::RecordsUpdated(1)),
                     Ok(QueryEvent::QueryComplete),
-                    Ok(QueryEvent::RecordsSelected((
-                        vec![("column_si".to_owned(), PostgreSqlType::SmallInt)],
-                        vec![vec!["5".to_owned()]],
-                    ))),
+                    Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+                        "column_si",
+                        PostgreSqlType::SmallInt,
+                    )])),
+                    Ok(QueryEvent::DataRows(vec![vec![Some("5".to_owned())]])),
+                    Ok(QueryEvent::SelectComplete(1)),
                     Ok(QueryEvent::QueryComplete),
                 ]);
             }
@@ -644,10 +680,13 @@ mod operators {
                     Ok(QueryEvent::QueryComplete),
                     Ok(QueryEvent::RecordsUpdated(1)),
                     Ok(QueryEvent::QueryComplete),
-                    Ok(QueryEvent::RecordsSelected((
-                        vec![("column_si".to_owned(), PostgreSqlType::SmallInt)],
-                        vec![vec!["1".to_owned()]],
-                    ))),
+                    Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+                        "column_si",
+                        PostgreSqlType::SmallInt,
+                    )
+                    .with_source(16384, 1)])),
+                    Ok(QueryEvent::DataRows(vec![vec![Some("1".to_owned())]])),
+                    Ok(QueryEvent::SelectComplete(1)),
                     Ok(QueryEvent::QueryComplete),
                 ]);
             }
@@ -671,10 +710,13 @@ mod operators {
                     Ok(QueryEvent::QueryComplete),
                     Ok(QueryEvent::RecordsUpdated(1)),
                     Ok(QueryEvent::QueryComplete),
-                    Ok(QueryEvent::RecordsSelected((
-                        vec![("column_si".to_owned(), PostgreSqlType::SmallInt)],
-                        vec![vec!["7".to_owned()]],
-                    ))),
+                    Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+                        "column_si",
+                        PostgreSqlType::SmallInt,
+                    )
+                    .with_source(16384, 1)])),
+                    Ok(QueryEvent::DataRows(vec![vec![Some("7".to_owned())]])),
+                    Ok(QueryEvent::SelectComplete(1)),
                     Ok(QueryEvent::QueryComplete),
                 ]);
             }
@@ -700,10 +742,12 @@ mod operators {
                     Ok(QueryEvent::QueryComplete),
                     Ok(QueryEvent::RecordsUpdated(1)),
                     Ok(QueryEvent::QueryComplete),
-                    Ok(QueryEvent::RecordsSelected((
-                        vec![("column_si".to_owned(), PostgreSqlType::SmallInt)],
-                        vec![vec!["-2".to_owned()]],
-                    ))),
+                    Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+                        "column_si",
+                        PostgreSqlType::SmallInt,
+                    )])),
+                    Ok(QueryEvent::DataRows(vec![vec![Some("-2".to_owned())]])),
+                    Ok(QueryEvent::SelectComplete(1)),
                     Ok(QueryEvent::QueryComplete),
                 ]);
             }
@@ -729,10 +773,12 @@ mod operators {
                     Ok(QueryEvent::QueryComplete),
                     Ok(QueryEvent::RecordsUpdated(1)),
                     Ok(QueryEvent::QueryComplete),
-                    Ok(QueryEvent::RecordsSelected((
-                        vec![("column_si".to_owned(), PostgreSqlType::SmallInt)],
-                        vec![vec!["16".to_owned()]],
-                    ))),
+                    Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+                        "column_si",
+                        PostgreSqlType::SmallInt,
+                    )])),
+                    Ok(QueryEvent::DataRows(vec![vec![Some("16".to_owned())]])),
+                    Ok(QueryEvent::SelectComplete(1)),
                     Ok(QueryEvent::QueryComplete),
                 ]);
             }
@@ -758,10 +804,12 @@ mod operators {
                     Ok(QueryEvent::QueryComplete),
                     Ok(QueryEvent::RecordsUpdated(1)),
                     Ok(QueryEvent::QueryComplete),
-                    Ok(QueryEvent::RecordsSelected((
-                        vec![("column_si".to_owned(), PostgreSqlType::SmallInt)],
-                        vec![vec!["2".to_owned()]],
-                    ))),
+                    Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+                        "column_si",
+                        PostgreSqlType::SmallInt,
+                    )])),
+                    Ok(QueryEvent::DataRows(vec![vec![Some("2".to_owned())]])),
+                    Ok(QueryEvent::SelectComplete(1)),
                     Ok(QueryEvent::QueryComplete),
                 ]);
             }
@@ -785,10 +833,13 @@ mod operators {
                     Ok(QueryEvent::QueryComplete),
                     Ok(QueryEvent::RecordsUpdated(1)),
                     Ok(QueryEvent::QueryComplete),
-                    Ok(QueryEvent::RecordsSelected((
-                        vec![("column_si".to_owned(), PostgreSqlType::SmallInt)],
-                        vec![vec!["5".to_owned()]],
-                    ))),
+                    Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+                        "column_si",
+                        PostgreSqlType::SmallInt,
+                    )
+                    .with_source(16384, 1)])),
+                    Ok(QueryEvent::DataRows(vec![vec![Some("5".to_owned())]])),
+                    Ok(QueryEvent::SelectComplete(1)),
                     Ok(QueryEvent::QueryComplete),
                 ]);
             }
@@ -832,10 +883,13 @@ mod operators {
                 Ok(QueryEvent::QueryComplete),
                 Ok(QueryEvent::RecordsUpdated(1)),
                 Ok(QueryEvent::QueryComplete),
-                Ok(QueryEvent::RecordsSelected((
-                    vec![("strings".to_owned(), PostgreSqlType::Char)],
-                    vec![vec!["12345".to_owned()]],
-                ))),
+                Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+                    "strings",
+                    PostgreSqlType::Char,
+                )
+                .with_source(16384, 1)])),
+                Ok(QueryEvent::DataRows(vec![vec![Some("12345".to_owned())]])),
+                Ok(QueryEvent::SelectComplete(1)),
                 Ok(QueryEvent::QueryComplete),
             ]);
         }
@@ -865,17 +919,23 @@ mod operators {
                 Ok(QueryEvent::QueryComplete),
                 Ok(QueryEvent::RecordsUpdated(1)),
                 Ok(QueryEvent::QueryComplete),
-                Ok(QueryEvent::RecordsSelected((
-                    vec![("strings".to_owned(), PostgreSqlType::Char)],
-                    vec![vec!["145".to_owned()]],
-                ))),
+                Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+                    "strings",
+                    PostgreSqlType::Char,
+                )
+                .with_source(16384, 1)])),
+                Ok(QueryEvent::DataRows(vec![vec![Some("145".to_owned())]])),
+                Ok(QueryEvent::SelectComplete(1)),
                 Ok(QueryEvent::QueryComplete),
                 Ok(QueryEvent::RecordsUpdated(1)),
                 Ok(QueryEvent::QueryComplete),
-                Ok(QueryEvent::RecordsSelected((
-                    vec![("strings".to_owned(), PostgreSqlType::Char)],
-                    vec![vec!["451".to_owned()]],
-                ))),
+                Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+                    "strings",
+                    PostgreSqlType::Char,
+                )
+                .with_source(16384, 1)])),
+                Ok(QueryEvent::DataRows(vec![vec![Some("451".to_owned())]])),
+                Ok(QueryEvent::SelectComplete(1)),
                 Ok(QueryEvent::QueryComplete),
             ]);
         }
@@ -904,3 +964,47 @@ mod operators {
         }
     }
 }
+
+#[rstest::rstest]
+fn update_records_that_match_condition(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (id smallint, name varchar(10));")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name values (1, 'a'), (2, 'b'), (3, 'c');")
+        .expect("no system errors");
+    engine
+        .execute("update schema_name.table_name set name = 'z' where id between 2 and 3 and name <> 'c';")
+        .expect("no system errors");
+    engine
+        .execute("select * from schema_name.table_name;")
+        .expect("no system errors");
+    engine
+        .execute("update schema_name.table_name set name = 'z' where other.id = 1;")
+        .expect("no system errors");
+
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::TableCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsInserted(3)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RecordsUpdated(1)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("id", PostgreSqlType::SmallInt).with_source(16384, 1),
+            ColumnDescription::new("name", PostgreSqlType::VarChar).with_source(16384, 2),
+        ])),
+        Ok(QueryEvent::DataRows(vec![
+            vec![Some("1".to_owned()), Some("a".to_owned())],
+            vec![Some("2".to_owned()), Some("z".to_owned())],
+            vec![Some("3".to_owned()), Some("c".to_owned())],
+        ])),
+        Ok(QueryEvent::SelectComplete(3)),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::missing_from_clause_entry("other".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
@@ -0,0 +1,162 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+use protocol::sql_types::PostgreSqlType;
+
+fn rows(values: &[&[Option<&str>]]) -> Vec<Vec<Option<String>>> {
+    values
+        .iter()
+        .map(|row| row.iter().map(|value| value.map(ToOwned::to_owned)).collect())
+        .collect()
+}
+
+#[rstest::fixture]
+fn with_table(sql_engine_with_schema: (QueryExecutor, ResultCollector)) -> (QueryExecutor, ResultCollector) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("create table schema_name.table_name (column_1 smallint);")
+        .expect("no system errors");
+    engine
+        .execute("insert into schema_name.table_name values (1), (2), (3);")
+        .expect("no system errors");
+    (engine, collector)
+}
+
+const TABLE_CREATED: [QueryResult; 6] = [
+    Ok(QueryEvent::SchemaCreated),
+    Ok(QueryEvent::QueryComplete),
+    Ok(QueryEvent::TableCreated),
+    Ok(QueryEvent::QueryComplete),
+    Ok(QueryEvent::RecordsInserted(3)),
+    Ok(QueryEvent::QueryComplete),
+];
+
+#[rstest::rstest]
+fn top_level_values(with_table: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = with_table;
+    engine.execute("values (1, 'a'), (2, 'b');").expect("no system errors");
+    engine
+        .execute("values (2, null), (1, 'x') order by 1 limit 1;")
+        .expect("no system errors");
+    engine
+        .execute("select column_1 from schema_name.table_name union values (5);")
+        .expect("no system errors");
+
+    let mut expected = TABLE_CREATED.to_vec();
+    expected.extend(vec![
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("column1", PostgreSqlType::Integer),
+            ColumnDescription::new("column2", PostgreSqlType::VarChar),
+        ])),
+        Ok(QueryEvent::DataRows(rows(&[
+            &[Some("1"), Some("a")],
+            &[Some("2"), Some("b")],
+        ]))),
+        Ok(QueryEvent::SelectComplete(2)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("column1", PostgreSqlType::Integer),
+            ColumnDescription::new("column2", PostgreSqlType::VarChar),
+        ])),
+        Ok(QueryEvent::DataRows(rows(&[&[Some("1"), Some("x")]]))),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "column_1",
+            PostgreSqlType::Integer,
+        )])),
+        Ok(QueryEvent::DataRows(rows(&[
+            &[Some("1")],
+            &[Some("2")],
+            &[Some("3")],
+            &[Some("5")],
+        ]))),
+        Ok(QueryEvent::SelectComplete(4)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+    collector.assert_content_for_single_queries(expected);
+}
+
+#[rstest::rstest]
+fn values_in_from_clause(with_table: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = with_table;
+    engine
+        .execute("select * from (values (1, 'a'), (2, 'b')) as t (id, name) where id > 1;")
+        .expect("no system errors");
+    engine
+        .execute("select t.id, column2 from (values (1, 'a')) as t (id);")
+        .expect("no system errors");
+    engine
+        .execute(
+            "select column_1, name from schema_name.table_name \
+            join (values (1, 'one'), (3, 'three')) as t (id, name) on column_1 = id;",
+        )
+        .expect("no system errors");
+
+    let mut expected = TABLE_CREATED.to_vec();
+    expected.extend(vec![
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("id", PostgreSqlType::Integer),
+            ColumnDescription::new("name", PostgreSqlType::VarChar),
+        ])),
+        Ok(QueryEvent::DataRows(rows(&[&[Some("2"), Some("b")]]))),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("id", PostgreSqlType::Integer),
+            ColumnDescription::new("column2", PostgreSqlType::VarChar),
+        ])),
+        Ok(QueryEvent::DataRows(rows(&[&[Some("1"), Some("a")]]))),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnDescription::new("column_1", PostgreSqlType::SmallInt).with_source(16384, 1),
+            ColumnDescription::new("name", PostgreSqlType::VarChar),
+        ])),
+        Ok(QueryEvent::DataRows(rows(&[
+            &[Some("1"), Some("one")],
+            &[Some("3"), Some("three")],
+        ]))),
+        Ok(QueryEvent::SelectComplete(2)),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+    collector.assert_content_for_single_queries(expected);
+}
+
+#[rstest::rstest]
+fn invalid_values(sql_engine_with_schema: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine_with_schema;
+    engine
+        .execute("select * from (values (1)) as t (a, b);")
+        .expect("no system errors");
+    engine.execute("values (1), ('a');").expect("no system errors");
+    engine.execute("values (1, 2), (3);").expect("no system errors");
+
+    collector.assert_content_for_single_queries(vec![
+        Ok(QueryEvent::SchemaCreated),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::invalid_column_reference(
+            "table \"t\" has 1 columns available but 2 columns specified".to_owned(),
+        )),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::datatype_mismatch(
+            "VALUES types integer and variable character cannot be matched".to_owned(),
+        )),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::syntax_error("VALUES (1, 2), (3)".to_owned())),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
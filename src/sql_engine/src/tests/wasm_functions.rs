@@ -0,0 +1,270 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use protocol::sql_types::PostgreSqlType;
+use std::{fs, path::PathBuf};
+use tempfile::TempDir;
+
+const MODULE: &str = r#"(module
+    (memory (export "memory") 1)
+    (global $next (mut i32) (i32.const 1024))
+    (func (export "alloc") (param $size i32) (result i32)
+        (local $address i32)
+        (local.set $address (global.get $next))
+        (global.set $next (i32.add (global.get $next) (local.get $size)))
+        (local.get $address))
+    (func (export "add") (param i32 i32) (result i32)
+        (i32.add (local.get 0) (local.get 1)))
+    (func (export "square") (param i64) (result i64)
+        (i64.mul (local.get 0) (local.get 0)))
+    (func (export "spin") (param i32) (result i32)
+        (loop $forever (br $forever))
+        (local.get 0))
+    (func (export "upper") (param $address i32) (param $length i32) (result i64)
+        (local $index i32)
+        (local $byte i32)
+        (block $done
+            (loop $next_byte
+                (br_if $done (i32.ge_u (local.get $index) (local.get $length)))
+                (local.set $byte (i32.load8_u (i32.add (local.get $address) (local.get $index))))
+                (if (i32.and (i32.ge_u (local.get $byte) (i32.const 97)) (i32.le_u (local.get $byte) (i32.const 122)))
+                    (then (i32.store8
+                        (i32.add (local.get $address) (local.get $index))
+                        (i32.sub (local.get $byte) (i32.const 32)))))
+                (local.set $index (i32.add (local.get $index) (i32.const 1)))
+                (br $next_byte)))
+        (i64.or
+            (i64.shl (i64.extend_i32_u (local.get $address)) (i64.const 32))
+            (i64.extend_i32_u (local.get $length)))))"#;
+
+fn module(dir: &TempDir, wat: &str) -> PathBuf {
+    let path = dir.path().join("functions.wasm");
+    fs::write(&path, wat::parse_str(wat).expect("valid module")).expect("to write module");
+    path
+}
+
+/// Events of a query that selects a single value
+fn selected(name: &str, pg_type: PostgreSqlType, value: Option<&str>) -> Vec<QueryResult> {
+    vec![
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(name, pg_type)])),
+        Ok(QueryEvent::DataRows(vec![vec![value.map(str::to_owned)]])),
+        Ok(QueryEvent::SelectComplete(1)),
+        Ok(QueryEvent::QueryComplete),
+    ]
+}
+
+#[rstest::rstest]
+fn call_integer_functions(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    let dir = tempfile::tempdir().expect("to create temporary folder");
+    let path = module(&dir, MODULE);
+    engine
+        .execute(&format!(
+            "create function add(a integer, b integer) returns integer as '{}' language wasm;",
+            path.display()
+        ))
+        .expect("no system errors");
+    engine
+        .execute(&format!(
+            "create function square(n bigint) returns bigint as '{}', 'square' language wasm;",
+            path.display()
+        ))
+        .expect("no system errors");
+    engine.execute("select add(40, 2);").expect("no system errors");
+    engine.execute("select square(3000000000);").expect("no system errors");
+
+    collector.assert_content(
+        [
+            vec![
+                Ok(QueryEvent::FunctionCreated),
+                Ok(QueryEvent::QueryComplete),
+                Ok(QueryEvent::FunctionCreated),
+                Ok(QueryEvent::QueryComplete),
+            ],
+            selected("add", PostgreSqlType::Integer, Some("42")),
+            selected("square", PostgreSqlType::BigInt, Some("9000000000000000000")),
+        ]
+        .concat(),
+    );
+}
+
+#[rstest::rstest]
+fn pass_text_through_memory(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    let dir = tempfile::tempdir().expect("to create temporary folder");
+    let path = module(&dir, MODULE);
+    engine
+        .execute(&format!(
+            "create function upper_case(value varchar(20)) returns varchar(20) as '{}', 'upper' language wasm;",
+            path.display()
+        ))
+        .expect("no system errors");
+    engine.execute("select upper_case('hello');").expect("no system errors");
+
+    collector.assert_content(
+        [
+            vec![Ok(QueryEvent::FunctionCreated), Ok(QueryEvent::QueryComplete)],
+            selected("upper_case", PostgreSqlType::VarChar, Some("HELLO")),
+        ]
+        .concat(),
+    );
+}
+
+#[rstest::rstest]
+fn null_argument_gives_null(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    let dir = tempfile::tempdir().expect("to create temporary folder");
+    let path = module(&dir, MODULE);
+    engine
+        .execute(&format!(
+            "create function add(a integer, b integer) returns integer as '{}' language wasm;",
+            path.display()
+        ))
+        .expect("no system errors");
+    engine.execute("select add(1, null);").expect("no system errors");
+
+    collector.assert_content(
+        [
+            vec![Ok(QueryEvent::FunctionCreated), Ok(QueryEvent::QueryComplete)],
+            selected("add", PostgreSqlType::Integer, None),
+        ]
+        .concat(),
+    );
+}
+
+#[rstest::rstest]
+fn signature_has_to_match_export(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    let dir = tempfile::tempdir().expect("to create temporary folder");
+    let path = module(&dir, MODULE);
+    engine
+        .execute(&format!(
+            "create function add(a bigint) returns integer as '{}' language wasm;",
+            path.display()
+        ))
+        .expect("no system errors");
+    engine
+        .execute(&format!(
+            "create function subtract(a integer, b integer) returns integer as '{}' language wasm;",
+            path.display()
+        ))
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Err(QueryError::invalid_function_definition(format!(
+            "function \"add\" of module \"{}\" has to be of type (i64) -> i32",
+            path.display()
+        ))),
+        Ok(QueryEvent::QueryComplete),
+        Err(QueryError::invalid_function_definition(format!(
+            "module \"{}\" does not export \"subtract\"",
+            path.display()
+        ))),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn imports_are_rejected(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    let dir = tempfile::tempdir().expect("to create temporary folder");
+    let path = module(
+        &dir,
+        r#"(module
+            (import "env" "exit" (func $exit (param i32)))
+            (func (export "add") (param i32 i32) (result i32)
+                (i32.add (local.get 0) (local.get 1))))"#,
+    );
+    engine
+        .execute(&format!(
+            "create function add(a integer, b integer) returns integer as '{}' language wasm;",
+            path.display()
+        ))
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Err(QueryError::invalid_function_definition(format!(
+            "module \"{}\" imports \"exit\", functions can't import anything",
+            path.display()
+        ))),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn endless_loop_runs_out_of_fuel(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    let dir = tempfile::tempdir().expect("to create temporary folder");
+    let path = module(&dir, MODULE);
+    engine
+        .execute(&format!(
+            "create function spin(a integer) returns integer as '{}' language wasm;",
+            path.display()
+        ))
+        .expect("no system errors");
+    engine.execute("select spin(1);").expect("no system errors");
+
+    collector.assert_content(vec![
+        Ok(QueryEvent::FunctionCreated),
+        Ok(QueryEvent::QueryComplete),
+        Ok(QueryEvent::RowDescription(vec![ColumnDescription::new(
+            "spin",
+            PostgreSqlType::Integer,
+        )])),
+        Err(QueryError::external_routine_exception(
+            "all fuel consumed by WebAssembly".to_owned(),
+        )),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn missing_file(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (mut engine, collector) = sql_engine;
+    let dir = tempfile::tempdir().expect("to create temporary folder");
+    let path = dir.path().join("missing.wasm");
+    engine
+        .execute(&format!(
+            "create function add(a integer, b integer) returns integer as '{}' language wasm;",
+            path.display()
+        ))
+        .expect("no system errors");
+
+    collector.assert_content(vec![
+        Err(QueryError::io_error(format!(
+            "could not open file \"{}\" for reading: No such file or directory (os error 2)",
+            path.display()
+        ))),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn only_superuser_creates_wasm_functions(sql_engine: (QueryExecutor, ResultCollector)) {
+    let (engine, collector) = sql_engine;
+    let dir = tempfile::tempdir().expect("to create temporary folder");
+    let path = module(&dir, MODULE);
+    let mut user = QueryExecutor::new(engine.storage.clone(), collector.clone(), "role_1");
+    user.execute(&format!(
+        "create function add(a integer, b integer) returns integer as '{}' language wasm;",
+        path.display()
+    ))
+    .expect("no system errors");
+
+    collector.assert_content(vec![
+        Err(QueryError::must_be_superuser("create functions in language wasm")),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
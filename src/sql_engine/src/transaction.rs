@@ -0,0 +1,580 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Transaction blocks of a session. Rows that statements of a block write
+//! are kept in the write set of the block and reach storage only when it is
+//! committed, so other sessions see them only after the commit while the
+//! session reads them merged with rows of storage. `ROLLBACK` discards the
+//! write set. `SAVEPOINT` starts a subtransaction with a copy of the write
+//! set. `ROLLBACK TO SAVEPOINT` restores the copy and resumes the parent
+//! even if the subtransaction failed, `RELEASE SAVEPOINT` merges the
+//! subtransaction into its parent. Creation and removal of schemas, tables
+//! and other objects can't be undone, so they are rejected in a block.
+//! Locks taken by statements of a block are held until the block ends. Row
+//! changes of a block reach subscribers, replication slots and change hooks
+//! only when it is committed, changes that are rolled back are never
+//! published.
+use crate::{
+    cancellation::CancellationToken,
+    catalog_manager::{CatalogManager, LockMode, Locks, Owner, Resource, RowChange},
+};
+use kernel::SystemResult;
+use protocol::results::QueryError;
+use std::{
+    collections::{BTreeMap, HashSet},
+    sync::{Arc, Mutex},
+};
+use storage::{Key, ReadCursor, Row, RowResult, Values};
+
+/// State of the transaction block of a session
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Status {
+    /// statements are not executed in a transaction block
+    Idle,
+    InProgress,
+    /// a statement of the block failed, statements are ignored until the
+    /// block or the failed subtransaction is rolled back
+    Failed,
+}
+
+/// Rows of a table that a block wrote by their keys, `None` for deleted
+/// rows. Scans of the table share them with the block.
+type Written = Arc<BTreeMap<Key, Option<Values>>>;
+
+/// Rows written by a block by schema and table names
+type WriteSet = BTreeMap<(String, String), Written>;
+
+/// Row changes of a table that are published once the block is committed
+struct Unpublished {
+    schema_name: String,
+    table_name: String,
+    changes: Vec<RowChange>,
+}
+
+struct Savepoint {
+    name: String,
+    /// write set of the parent when the savepoint was set
+    writes: WriteSet,
+    /// number of unpublished changes of the parent
+    unpublished: usize,
+}
+
+struct Block {
+    status: Status,
+    writes: WriteSet,
+    unpublished: Vec<Unpublished>,
+    savepoints: Vec<Savepoint>,
+}
+
+/// Transaction block shared by a session and nested sessions of its `DO`
+/// blocks
+pub(crate) struct Transaction {
+    locks: Arc<Locks>,
+    owner: Owner,
+    block: Mutex<Block>,
+}
+
+impl Transaction {
+    pub(crate) fn new(locks: Arc<Locks>) -> Transaction {
+        Transaction {
+            owner: locks.owner(),
+            locks,
+            block: Mutex::new(Block {
+                status: Status::Idle,
+                writes: WriteSet::new(),
+                unpublished: vec![],
+                savepoints: vec![],
+            }),
+        }
+    }
+
+    pub(crate) fn status(&self) -> Status {
+        self.block().status
+    }
+
+    /// Whether the block wrote rows that other sessions don't see yet
+    pub(crate) fn has_writes(&self) -> bool {
+        !self.block().writes.is_empty()
+    }
+
+    /// Starts a transaction block, a block that is in progress goes on
+    pub(crate) fn begin(&self) {
+        let mut block = self.block();
+        if block.status == Status::Idle {
+            block.status = Status::InProgress;
+        }
+    }
+
+    /// Marks the block as failed, nothing happens outside of a block
+    pub(crate) fn fail(&self) {
+        let mut block = self.block();
+        if block.status == Status::InProgress {
+            block.status = Status::Failed;
+        }
+    }
+
+    /// Ends the block, writes its rows to storage, publishes its changes
+    /// and releases its locks. Returns names of tables whose rows were
+    /// written.
+    pub(crate) fn commit(&self, storage: &CatalogManager) -> SystemResult<Vec<String>> {
+        let (writes, unpublished) = {
+            let mut block = self.block();
+            block.status = Status::Idle;
+            block.savepoints.clear();
+            (
+                std::mem::take(&mut block.writes),
+                std::mem::take(&mut block.unpublished),
+            )
+        };
+        let written = apply(storage, writes);
+        self.locks.release(self.owner);
+        let written = written?;
+        for Unpublished {
+            schema_name,
+            table_name,
+            changes,
+        } in unpublished
+        {
+            storage.publications().publish(&schema_name, &table_name, changes)?;
+        }
+        Ok(written)
+    }
+
+    /// Ends the block, discards its rows and changes and releases its locks
+    pub(crate) fn rollback(&self) {
+        {
+            let mut block = self.block();
+            block.status = Status::Idle;
+            block.savepoints.clear();
+            block.unpublished.clear();
+            block.writes.clear();
+        }
+        self.locks.release(self.owner);
+    }
+
+    /// Starts a subtransaction, a savepoint with the same name is hidden
+    /// until the new one is released
+    pub(crate) fn savepoint(&self, name: String) -> Result<(), QueryError> {
+        let mut block = self.block();
+        if block.status == Status::Idle {
+            return Err(QueryError::no_active_sql_transaction("SAVEPOINT"));
+        }
+        let savepoint = Savepoint {
+            name,
+            writes: block.writes.clone(),
+            unpublished: block.unpublished.len(),
+        };
+        block.savepoints.push(savepoint);
+        Ok(())
+    }
+
+    /// Merges the subtransaction that `name` started and the ones started
+    /// after it into their parent
+    pub(crate) fn release(&self, name: &str) -> Result<(), QueryError> {
+        let mut block = self.block();
+        if block.status == Status::Idle {
+            return Err(QueryError::no_active_sql_transaction("RELEASE SAVEPOINT"));
+        }
+        let position = savepoint(&block, name)?;
+        block.savepoints.truncate(position);
+        Ok(())
+    }
+
+    /// Discards rows written after the savepoint was set and resumes the
+    /// transaction, the savepoint stays and later ones are removed
+    pub(crate) fn rollback_to(&self, name: &str) -> Result<(), QueryError> {
+        let mut block = self.block();
+        if block.status == Status::Idle {
+            return Err(QueryError::no_active_sql_transaction("ROLLBACK TO SAVEPOINT"));
+        }
+        let position = savepoint(&block, name)?;
+        block.savepoints.truncate(position + 1);
+        let (writes, unpublished) = {
+            let savepoint = &block.savepoints[position];
+            (savepoint.writes.clone(), savepoint.unpublished)
+        };
+        block.status = Status::InProgress;
+        block.writes = writes;
+        block.unpublished.truncate(unpublished);
+        Ok(())
+    }
+
+    /// Rows of the table as the session sees them, rows written by the block
+    /// replace rows of storage with the same keys
+    pub(crate) fn full_scan(
+        &self,
+        storage: &CatalogManager,
+        schema_name: &str,
+        table_name: &str,
+    ) -> SystemResult<ReadCursor> {
+        let written = self
+            .block()
+            .writes
+            .get(&(schema_name.to_owned(), table_name.to_owned()))
+            .cloned();
+        let stored = storage.full_scan(schema_name, table_name)?;
+        Ok(match written {
+            Some(written) => Box::new(Merged {
+                stored,
+                written,
+                replaced: HashSet::new(),
+                added: None,
+            }),
+            None => stored,
+        })
+    }
+
+    /// Writes rows into the table, a block keeps them until it is committed
+    pub(crate) fn write_into(
+        &self,
+        storage: &CatalogManager,
+        schema_name: &str,
+        table_name: &str,
+        rows: Vec<Row>,
+    ) -> SystemResult<usize> {
+        if self.status() == Status::Idle {
+            return storage.write_into(schema_name, table_name, rows);
+        }
+        let written = rows.len();
+        self.write(
+            schema_name,
+            table_name,
+            rows.into_iter().map(|(key, values)| (key, Some(values))),
+        );
+        Ok(written)
+    }
+
+    /// Deletes rows with `keys` from the table, a block keeps them until it
+    /// is committed
+    pub(crate) fn delete_from(
+        &self,
+        storage: &CatalogManager,
+        schema_name: &str,
+        table_name: &str,
+        keys: Vec<Key>,
+    ) -> SystemResult<usize> {
+        if self.status() == Status::Idle {
+            return storage.delete_from(schema_name, table_name, keys);
+        }
+        let deleted = keys.len();
+        self.write(schema_name, table_name, keys.into_iter().map(|key| (key, None)));
+        Ok(deleted)
+    }
+
+    /// Publishes row changes of the table right away outside of a block,
+    /// changes of a block are held back until it is committed
+    pub(crate) fn publish(
+        &self,
+        storage: &CatalogManager,
+        schema_name: &str,
+        table_name: &str,
+        changes: Vec<RowChange>,
+    ) -> SystemResult<()> {
+        if changes.is_empty() {
+            return Ok(());
+        }
+        {
+            let mut block = self.block();
+            if block.status != Status::Idle {
+                block.unpublished.push(Unpublished {
+                    schema_name: schema_name.to_owned(),
+                    table_name: table_name.to_owned(),
+                    changes,
+                });
+                return Ok(());
+            }
+        }
+        storage.publications().publish(schema_name, table_name, changes)
+    }
+
+    /// Locks the schema or the table with qualified `name` in `mode`, the
+    /// schema of a table is locked in shared mode, so that it can't be
+    /// dropped while the table is used
+    pub(crate) fn lock(
+        &self,
+        name: String,
+        mode: LockMode,
+        cancellation: &CancellationToken,
+    ) -> Result<(), QueryError> {
+        if let Some((schema_name, _)) = name.split_once('.') {
+            self.locks.acquire(
+                self.owner,
+                Resource::Object(schema_name.to_owned()),
+                LockMode::Shared,
+                cancellation,
+            )?;
+        }
+        self.locks
+            .acquire(self.owner, Resource::Object(name), mode, cancellation)
+    }
+
+    /// Locks rows of the table with `keys` before they are updated or
+    /// deleted
+    pub(crate) fn lock_rows(
+        &self,
+        schema_name: &str,
+        table_name: &str,
+        keys: impl IntoIterator<Item = Key>,
+        cancellation: &CancellationToken,
+    ) -> Result<(), QueryError> {
+        self.locks
+            .lock_rows(self.owner, schema_name, table_name, keys, cancellation)
+    }
+
+    /// Releases locks once a statement outside of a block completes or the
+    /// block ends
+    pub(crate) fn release_locks(&self) {
+        if self.status() == Status::Idle {
+            self.locks.release(self.owner);
+        }
+    }
+
+    fn write(&self, schema_name: &str, table_name: &str, rows: impl Iterator<Item = (Key, Option<Values>)>) {
+        let mut block = self.block();
+        let written = block
+            .writes
+            .entry((schema_name.to_owned(), table_name.to_owned()))
+            .or_default();
+        Arc::make_mut(written).extend(rows);
+    }
+
+    fn block(&self) -> std::sync::MutexGuard<'_, Block> {
+        self.block.lock().expect("to acquire transaction lock")
+    }
+}
+
+impl Drop for Transaction {
+    fn drop(&mut self) {
+        self.locks.release(self.owner);
+    }
+}
+
+/// Position of the latest savepoint with `name`
+fn savepoint(block: &Block, name: &str) -> Result<usize, QueryError> {
+    block
+        .savepoints
+        .iter()
+        .rposition(|savepoint| savepoint.name == name)
+        .ok_or_else(|| QueryError::savepoint_does_not_exist(name.to_owned()))
+}
+
+/// Writes rows of `writes` into storage, rows of dropped tables are
+/// skipped. Returns qualified names of written tables.
+fn apply(storage: &CatalogManager, writes: WriteSet) -> SystemResult<Vec<String>> {
+    let mut applied = vec![];
+    for ((schema_name, table_name), written) in writes {
+        if !matches!(storage.table_exists(&schema_name, &table_name), Some((_, Some(_)))) {
+            continue;
+        }
+        let mut deleted = vec![];
+        let mut rows = vec![];
+        for (key, values) in written.iter() {
+            match values {
+                Some(values) => rows.push((key.clone(), values.clone())),
+                None => deleted.push(key.clone()),
+            }
+        }
+        if !deleted.is_empty() {
+            storage.delete_from(&schema_name, &table_name, deleted)?;
+        }
+        if !rows.is_empty() {
+            storage.write_into(&schema_name, &table_name, rows)?;
+        }
+        applied.push(schema_name + "." + table_name.as_str());
+    }
+    Ok(applied)
+}
+
+/// Rows of storage merged with rows that a block wrote into the table
+struct Merged {
+    stored: ReadCursor,
+    written: Written,
+    /// keys of stored rows that the block replaced or deleted
+    replaced: HashSet<Key>,
+    /// rows that the block added follow rows of storage
+    added: Option<std::vec::IntoIter<Row>>,
+}
+
+impl Iterator for Merged {
+    type Item = RowResult;
+
+    fn next(&mut self) -> Option<RowResult> {
+        if self.added.is_none() {
+            for row in self.stored.by_ref() {
+                let (key, values) = match row {
+                    Ok(Ok(row)) => row,
+                    error => return Some(error),
+                };
+                match self.written.get(&key) {
+                    Some(written) => {
+                        let written = written.clone();
+                        self.replaced.insert(key.clone());
+                        if let Some(values) = written {
+                            return Some(Ok(Ok((key, values))));
+                        }
+                    }
+                    None => return Some(Ok(Ok((key, values)))),
+                }
+            }
+            let added = self
+                .written
+                .iter()
+                .filter(|(key, _values)| !self.replaced.contains(*key))
+                .filter_map(|(key, values)| values.clone().map(|values| (key.clone(), values)))
+                .collect::<Vec<Row>>();
+            self.added = Some(added.into_iter());
+        }
+        self.added.as_mut().and_then(Iterator::next).map(|row| Ok(Ok(row)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ColumnDefinition;
+    use representation::{Binary, Datum};
+    use sql_types::SqlType;
+
+    fn storage() -> CatalogManager {
+        let storage = CatalogManager::default();
+        storage.create_schema("schema_name").expect("schema created");
+        let schema_id = storage.schema_exists("schema_name").expect("schema exists");
+        storage
+            .create_table(
+                schema_id,
+                "table_name",
+                &[ColumnDefinition::new("column_1", SqlType::SmallInt(i16::MIN))],
+            )
+            .expect("table created");
+        storage
+    }
+
+    fn row(key: u64, value: i16) -> Row {
+        (
+            Binary::pack(&[Datum::from_u64(key)]),
+            Binary::pack(&[Datum::from_i16(value)]),
+        )
+    }
+
+    fn insert(storage: &CatalogManager, transaction: &Transaction, key: u64) {
+        transaction
+            .write_into(storage, "schema_name", "table_name", vec![row(key, 1)])
+            .expect("no system errors");
+    }
+
+    fn stored(storage: &CatalogManager) -> usize {
+        storage.full_scan("schema_name", "table_name").expect("scan").count()
+    }
+
+    fn seen(storage: &CatalogManager, transaction: &Transaction) -> Vec<Row> {
+        transaction
+            .full_scan(storage, "schema_name", "table_name")
+            .expect("scan")
+            .map(|row| row.expect("no io errors").expect("no storage errors"))
+            .collect()
+    }
+
+    #[test]
+    fn changes_outside_of_block_are_written_at_once() {
+        let storage = storage();
+        let transaction = Transaction::new(storage.locks().clone());
+        insert(&storage, &transaction, 1);
+
+        assert_eq!(stored(&storage), 1);
+        assert!(!transaction.has_writes());
+    }
+
+    #[test]
+    fn rows_of_block_are_written_when_it_is_committed() {
+        let storage = storage();
+        let transaction = Transaction::new(storage.locks().clone());
+        insert(&storage, &transaction, 1);
+        insert(&storage, &transaction, 2);
+        transaction.begin();
+        transaction
+            .write_into(&storage, "schema_name", "table_name", vec![row(1, 5)])
+            .expect("no system errors");
+        transaction
+            .delete_from(&storage, "schema_name", "table_name", vec![row(2, 1).0])
+            .expect("no system errors");
+        insert(&storage, &transaction, 3);
+
+        assert_eq!(seen(&storage, &transaction), vec![row(1, 5), row(3, 1)]);
+        assert_eq!(
+            seen(&storage, &Transaction::new(storage.locks().clone())),
+            vec![row(1, 1), row(2, 1)]
+        );
+
+        assert_eq!(
+            transaction.commit(&storage),
+            Ok(vec!["schema_name.table_name".to_owned()])
+        );
+        assert_eq!(
+            seen(&storage, &Transaction::new(storage.locks().clone())),
+            vec![row(1, 5), row(3, 1)]
+        );
+    }
+
+    #[test]
+    fn rollback_discards_rows_of_block() {
+        let storage = storage();
+        let transaction = Transaction::new(storage.locks().clone());
+        transaction.begin();
+        insert(&storage, &transaction, 1);
+        transaction.rollback();
+
+        assert_eq!(stored(&storage), 0);
+        assert_eq!(transaction.commit(&storage), Ok(vec![]));
+        assert_eq!(stored(&storage), 0);
+    }
+
+    #[test]
+    fn rollback_to_savepoint_keeps_it() {
+        let storage = storage();
+        let transaction = Transaction::new(storage.locks().clone());
+        transaction.begin();
+        insert(&storage, &transaction, 1);
+        transaction.savepoint("first".to_owned()).expect("savepoint set");
+        insert(&storage, &transaction, 2);
+        transaction.fail();
+
+        assert_eq!(transaction.rollback_to("first"), Ok(()));
+        assert_eq!(transaction.status(), Status::InProgress);
+        assert_eq!(seen(&storage, &transaction).len(), 1);
+
+        insert(&storage, &transaction, 3);
+        assert_eq!(transaction.rollback_to("first"), Ok(()));
+        assert_eq!(seen(&storage, &transaction).len(), 1);
+        assert_eq!(stored(&storage), 0);
+    }
+
+    #[test]
+    fn latest_savepoint_with_name_is_used() {
+        let storage = storage();
+        let transaction = Transaction::new(storage.locks().clone());
+        transaction.begin();
+        transaction.savepoint("point".to_owned()).expect("savepoint set");
+        insert(&storage, &transaction, 1);
+        transaction.savepoint("point".to_owned()).expect("savepoint set");
+        insert(&storage, &transaction, 2);
+
+        assert_eq!(transaction.release("point"), Ok(()));
+        assert_eq!(transaction.rollback_to("point"), Ok(()));
+        assert!(!transaction.has_writes());
+        assert_eq!(
+            transaction.release("other"),
+            Err(QueryError::savepoint_does_not_exist("other".to_owned()))
+        );
+    }
+}
@@ -15,6 +15,9 @@
 use protocol::sql_types::PostgreSqlType;
 use serde::{Deserialize, Serialize};
 use std::convert::TryInto;
+use text_search::{TsQuery, TsVector};
+
+pub mod text_search;
 
 #[derive(PartialEq, Eq, Debug, Copy, Clone, Serialize, Deserialize, Hash, Ord, PartialOrd)]
 pub enum SqlType {
@@ -33,6 +36,9 @@ pub enum SqlType {
     TimestampWithTimeZone,
     Date,
     Interval,
+    TsVector,
+    TsQuery,
+    Void,
 }
 
 impl ToString for SqlType {
@@ -60,6 +66,9 @@ impl Into<&'static str> for &SqlType {
             SqlType::TimestampWithTimeZone => "timestamp with time zone",
             SqlType::Date => "date",
             SqlType::Interval => "interval",
+            SqlType::TsVector => "tsvector",
+            SqlType::TsQuery => "tsquery",
+            SqlType::Void => "void",
         }
     }
 }
@@ -77,6 +86,8 @@ impl SqlType {
             Self::Integer(min) => Box::new(IntegerSqlTypeConstraint { min }),
             Self::BigInt(min) => Box::new(BigIntTypeConstraint { min }),
             Self::Bool => Box::new(BoolSqlTypeConstraint),
+            Self::TsVector => Box::new(TsVectorConstraint),
+            Self::TsQuery => Box::new(TsQueryConstraint),
             sql_type => unimplemented!("Type constraint for {:?} is not currently implemented", sql_type),
         }
     }
@@ -89,6 +100,8 @@ impl SqlType {
             Self::Integer(_min) => Box::new(IntegerSqlTypeSerializer),
             Self::BigInt(_min) => Box::new(BigIntTypeSerializer),
             Self::Bool => Box::new(BoolSqlTypeSerializer),
+            Self::TsVector => Box::new(TsVectorSerializer),
+            Self::TsQuery => Box::new(TsQuerySerializer),
             sql_type => unimplemented!("Type Serializer for {:?} is not currently implemented", sql_type),
         }
     }
@@ -110,6 +123,9 @@ impl SqlType {
             Self::TimestampWithTimeZone => PostgreSqlType::TimestampWithTimeZone,
             Self::Date => PostgreSqlType::Date,
             Self::Interval => PostgreSqlType::Interval,
+            Self::TsVector => PostgreSqlType::TsVector,
+            Self::TsQuery => PostgreSqlType::TsQuery,
+            Self::Void => PostgreSqlType::Void,
         }
     }
 }
@@ -132,6 +148,9 @@ impl Into<PostgreSqlType> for &SqlType {
             SqlType::TimestampWithTimeZone => PostgreSqlType::TimestampWithTimeZone,
             SqlType::Date => PostgreSqlType::Date,
             SqlType::Interval => PostgreSqlType::Interval,
+            SqlType::TsVector => PostgreSqlType::TsVector,
+            SqlType::TsQuery => PostgreSqlType::TsQuery,
+            SqlType::Void => PostgreSqlType::Void,
         }
     }
 }
@@ -359,6 +378,58 @@ impl Serializer for BoolSqlTypeSerializer {
     }
 }
 
+/// `tsvector` and `tsquery` values are stored in their normalized text
+/// representation
+struct TsVectorConstraint;
+
+impl Constraint for TsVectorConstraint {
+    fn validate(&self, in_value: &str) -> Result<(), ConstraintError> {
+        TsVector::parse(in_value)
+            .map(|_| ())
+            .map_err(|_| ConstraintError::TypeMismatch(in_value.to_owned()))
+    }
+}
+
+struct TsVectorSerializer;
+
+impl Serializer for TsVectorSerializer {
+    fn ser(&self, in_value: &str) -> Vec<u8> {
+        match TsVector::parse(in_value) {
+            Ok(vector) => vector.to_string().into_bytes(),
+            Err(_) => unreachable!(),
+        }
+    }
+
+    fn des(&self, out_value: &[u8]) -> String {
+        String::from_utf8(out_value.to_vec()).unwrap()
+    }
+}
+
+struct TsQueryConstraint;
+
+impl Constraint for TsQueryConstraint {
+    fn validate(&self, in_value: &str) -> Result<(), ConstraintError> {
+        TsQuery::parse(in_value)
+            .map(|_| ())
+            .map_err(|_| ConstraintError::TypeMismatch(in_value.to_owned()))
+    }
+}
+
+struct TsQuerySerializer;
+
+impl Serializer for TsQuerySerializer {
+    fn ser(&self, in_value: &str) -> Vec<u8> {
+        match TsQuery::parse(in_value) {
+            Ok(query) => query.to_string().into_bytes(),
+            Err(_) => unreachable!(),
+        }
+    }
+
+    fn des(&self, out_value: &[u8]) -> String {
+        String::from_utf8(out_value.to_vec()).unwrap()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
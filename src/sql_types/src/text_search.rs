@@ -0,0 +1,364 @@
+// Copyright 2020 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Values of `tsvector` and `tsquery` types of full-text search. Documents
+//! are split into lexemes by the `simple` configuration: words of letters
+//! and digits are lowercased and nothing is dropped or stemmed.
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{self, Display, Formatter},
+    iter::Peekable,
+    str::Chars,
+};
+
+/// Positions above the limit are stored as the limit
+const MAX_POSITION: u16 = 16383;
+
+/// Sorted lexemes of a document with positions they occur at
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TsVector {
+    lexemes: BTreeMap<String, BTreeSet<u16>>,
+}
+
+impl TsVector {
+    /// Parses text representation, e.g. `'fat':2 'rat':3`
+    pub fn parse(text: &str) -> Result<TsVector, String> {
+        let error = || format!("syntax error in tsvector: \"{}\"", text);
+        let mut vector = TsVector::default();
+        let mut chars = text.chars().peekable();
+        loop {
+            skip_whitespace(&mut chars);
+            if chars.peek().is_none() {
+                return Ok(vector);
+            }
+            let lexeme = lexeme(&mut chars, |c| c == ':').ok_or_else(error)?;
+            let positions = vector.lexemes.entry(lexeme).or_default();
+            if chars.peek() == Some(&':') {
+                chars.next();
+                loop {
+                    let mut digits = String::new();
+                    while let Some(digit) = chars.peek().filter(|c| c.is_ascii_digit()) {
+                        digits.push(*digit);
+                        chars.next();
+                    }
+                    match digits.parse::<u64>() {
+                        Ok(0) | Err(_) => return Err(error()),
+                        Ok(position) => positions.insert(position.min(u64::from(MAX_POSITION)) as u16),
+                    };
+                    if chars.peek() != Some(&',') {
+                        break;
+                    }
+                    chars.next();
+                }
+            }
+            match chars.peek() {
+                Some(c) if !c.is_whitespace() => return Err(error()),
+                _ => {}
+            }
+        }
+    }
+
+    /// Lexemes of words of `document` in the `simple` configuration
+    pub fn from_document(document: &str) -> TsVector {
+        let mut vector = TsVector::default();
+        let words = document
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty());
+        for (index, word) in words.enumerate() {
+            let position = (index + 1).min(usize::from(MAX_POSITION)) as u16;
+            vector.lexemes.entry(word.to_lowercase()).or_default().insert(position);
+        }
+        vector
+    }
+
+    pub fn contains(&self, lexeme: &str) -> bool {
+        self.lexemes.contains_key(lexeme)
+    }
+}
+
+impl Display for TsVector {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (index, (lexeme, positions)) in self.lexemes.iter().enumerate() {
+            if index > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", Quoted(lexeme))?;
+            for (index, position) in positions.iter().enumerate() {
+                write!(f, "{}{}", if index == 0 { ':' } else { ',' }, position)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Lexemes combined with `!`, `&` and `|`, `&` binds tighter than `|`
+#[derive(Debug, Clone, PartialEq)]
+pub enum TsQuery {
+    Lexeme(String),
+    Not(Box<TsQuery>),
+    And(Box<TsQuery>, Box<TsQuery>),
+    Or(Box<TsQuery>, Box<TsQuery>),
+}
+
+impl TsQuery {
+    /// Parses text representation, e.g. `'fat' & ( 'rat' | 'cat' )`
+    pub fn parse(text: &str) -> Result<TsQuery, String> {
+        let mut chars = text.chars().peekable();
+        let query = QueryParser { chars: &mut chars }.or();
+        skip_whitespace(&mut chars);
+        match query {
+            Some(query) if chars.peek().is_none() => Ok(query),
+            _ => Err(format!("syntax error in tsquery: \"{}\"", text)),
+        }
+    }
+
+    /// Query of `to_tsquery`, lexemes are normalized like words of documents
+    pub fn from_text(text: &str) -> Result<TsQuery, String> {
+        TsQuery::parse(text).map(TsQuery::lowercase)
+    }
+
+    pub fn matches(&self, vector: &TsVector) -> bool {
+        match self {
+            TsQuery::Lexeme(lexeme) => vector.contains(lexeme),
+            TsQuery::Not(query) => !query.matches(vector),
+            TsQuery::And(left, right) => left.matches(vector) && right.matches(vector),
+            TsQuery::Or(left, right) => left.matches(vector) || right.matches(vector),
+        }
+    }
+
+    fn lowercase(self) -> TsQuery {
+        match self {
+            TsQuery::Lexeme(lexeme) => TsQuery::Lexeme(lexeme.to_lowercase()),
+            TsQuery::Not(query) => TsQuery::Not(Box::new(query.lowercase())),
+            TsQuery::And(left, right) => TsQuery::And(Box::new(left.lowercase()), Box::new(right.lowercase())),
+            TsQuery::Or(left, right) => TsQuery::Or(Box::new(left.lowercase()), Box::new(right.lowercase())),
+        }
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            TsQuery::Or(..) => 1,
+            TsQuery::And(..) => 2,
+            TsQuery::Not(_) => 3,
+            TsQuery::Lexeme(_) => 4,
+        }
+    }
+
+    /// Writes an operand of an operator of `precedence`
+    fn fmt_operand(&self, f: &mut Formatter<'_>, precedence: u8) -> fmt::Result {
+        if self.precedence() < precedence {
+            write!(f, "( {} )", self)
+        } else {
+            write!(f, "{}", self)
+        }
+    }
+}
+
+impl Display for TsQuery {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TsQuery::Lexeme(lexeme) => write!(f, "{}", Quoted(lexeme)),
+            TsQuery::Not(query) => {
+                write!(f, "!")?;
+                query.fmt_operand(f, self.precedence())
+            }
+            TsQuery::And(left, right) | TsQuery::Or(left, right) => {
+                left.fmt_operand(f, self.precedence())?;
+                write!(f, " {} ", if let TsQuery::And(..) = self { '&' } else { '|' })?;
+                right.fmt_operand(f, self.precedence())
+            }
+        }
+    }
+}
+
+/// Recursive descent parser of queries, `None` for syntax errors
+struct QueryParser<'p, 'c> {
+    chars: &'p mut Peekable<Chars<'c>>,
+}
+
+impl QueryParser<'_, '_> {
+    fn or(&mut self) -> Option<TsQuery> {
+        let mut query = self.and()?;
+        while self.consume('|') {
+            query = TsQuery::Or(Box::new(query), Box::new(self.and()?));
+        }
+        Some(query)
+    }
+
+    fn and(&mut self) -> Option<TsQuery> {
+        let mut query = self.unary()?;
+        while self.consume('&') {
+            query = TsQuery::And(Box::new(query), Box::new(self.unary()?));
+        }
+        Some(query)
+    }
+
+    fn unary(&mut self) -> Option<TsQuery> {
+        if self.consume('!') {
+            Some(TsQuery::Not(Box::new(self.unary()?)))
+        } else if self.consume('(') {
+            let query = self.or()?;
+            if self.consume(')') {
+                Some(query)
+            } else {
+                None
+            }
+        } else {
+            skip_whitespace(self.chars);
+            lexeme(self.chars, |c| "&|!():".contains(c)).map(TsQuery::Lexeme)
+        }
+    }
+
+    fn consume(&mut self, expected: char) -> bool {
+        skip_whitespace(self.chars);
+        if self.chars.peek() == Some(&expected) {
+            self.chars.next();
+            true
+        } else {
+            false
+        }
+    }
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars>) {
+    while chars.peek().map(|c| c.is_whitespace()).unwrap_or(false) {
+        chars.next();
+    }
+}
+
+/// Lexeme in single quotes or up to a whitespace or a `stop` character, a
+/// backslash escapes the next character. `None` if it is empty or not closed.
+fn lexeme(chars: &mut Peekable<Chars>, stop: impl Fn(char) -> bool) -> Option<String> {
+    let mut lexeme = String::new();
+    if chars.peek() == Some(&'\'') {
+        chars.next();
+        loop {
+            match chars.next()? {
+                '\'' if chars.peek() == Some(&'\'') => {
+                    chars.next();
+                    lexeme.push('\'');
+                }
+                '\'' => break,
+                '\\' => lexeme.push(chars.next()?),
+                c => lexeme.push(c),
+            }
+        }
+    } else {
+        while let Some(c) = chars
+            .peek()
+            .copied()
+            .filter(|c| !c.is_whitespace() && !stop(*c) && *c != '\'')
+        {
+            chars.next();
+            if c == '\\' {
+                lexeme.push(chars.next()?);
+            } else {
+                lexeme.push(c);
+            }
+        }
+    }
+    if lexeme.is_empty() {
+        None
+    } else {
+        Some(lexeme)
+    }
+}
+
+/// Lexeme in single quotes, quotes and backslashes in it are escaped
+struct Quoted<'q>(&'q str);
+
+impl Display for Quoted<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "'{}'", self.0.replace('\\', "\\\\").replace('\'', "''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vectors_are_normalized() {
+        assert_eq!(
+            TsVector::parse("rat:3 'fat':2,1 a  rat:1").map(|vector| vector.to_string()),
+            Ok("'a' 'fat':1,2 'rat':1,3".to_owned())
+        );
+        assert_eq!(
+            TsVector::parse("'it''s' back\\ slash:99999").map(|vector| vector.to_string()),
+            Ok("'back slash':16383 'it''s'".to_owned())
+        );
+        assert_eq!(TsVector::parse("").map(|vector| vector.to_string()), Ok("".to_owned()));
+    }
+
+    #[test]
+    fn invalid_vectors() {
+        assert_eq!(
+            TsVector::parse("fat:0"),
+            Err("syntax error in tsvector: \"fat:0\"".to_owned())
+        );
+        assert!(TsVector::parse("fat:").is_err());
+        assert!(TsVector::parse("'fat").is_err());
+        assert!(TsVector::parse("fat:1x").is_err());
+    }
+
+    #[test]
+    fn documents() {
+        assert_eq!(
+            TsVector::from_document("The fat rat, the FAT cat!").to_string(),
+            "'cat':6 'fat':2,5 'rat':3 'the':1,4"
+        );
+    }
+
+    #[test]
+    fn queries() {
+        assert_eq!(
+            TsQuery::parse("fat & (rat | !cat)").map(|query| query.to_string()),
+            Ok("'fat' & ( 'rat' | !'cat' )".to_owned())
+        );
+        assert_eq!(
+            TsQuery::parse("a | b & c").map(|query| query.to_string()),
+            Ok("'a' | 'b' & 'c'".to_owned())
+        );
+        assert_eq!(
+            TsQuery::parse("!(a & b)").map(|query| query.to_string()),
+            Ok("!( 'a' & 'b' )".to_owned())
+        );
+        assert_eq!(
+            TsQuery::from_text("Fat & 'Rat'").map(|query| query.to_string()),
+            Ok("'fat' & 'rat'".to_owned())
+        );
+    }
+
+    #[test]
+    fn invalid_queries() {
+        assert_eq!(
+            TsQuery::parse("fat &"),
+            Err("syntax error in tsquery: \"fat &\"".to_owned())
+        );
+        assert!(TsQuery::parse("(fat").is_err());
+        assert!(TsQuery::parse("fat rat").is_err());
+        assert!(TsQuery::parse("").is_err());
+    }
+
+    #[test]
+    fn matching() {
+        let vector = TsVector::from_document("a fat rat");
+        let matches = |query: &str| TsQuery::parse(query).expect("valid query").matches(&vector);
+        assert!(matches("fat & rat"));
+        assert!(!matches("fat & cat"));
+        assert!(matches("cat | rat"));
+        assert!(matches("fat & !cat"));
+        assert!(!matches("!(fat | cat)"));
+    }
+}
@@ -16,14 +16,37 @@ use crate::{Database, DefinitionError, Key, ObjectId, ReadCursor, RowResult, Sch
 use std::{
     collections::{BTreeMap, HashMap},
     io::{self},
-    sync::RwLock,
+    ops::Bound,
+    sync::{Arc, RwLock},
 };
 
 type Name = String;
 
 #[derive(Default, Debug)]
 struct StorageObject {
-    records: BTreeMap<Key, Values>,
+    /// records are shared with scans that read them, changes copy records
+    /// only while a scan is in progress
+    records: Arc<BTreeMap<Key, Values>>,
+}
+
+/// Scan of records as they were when it started, records are read one by
+/// one without copying all of them
+struct Records {
+    records: Arc<BTreeMap<Key, Values>>,
+    last: Option<Key>,
+}
+
+impl Iterator for Records {
+    type Item = RowResult;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, values) = match &self.last {
+            Some(last) => self.records.range((Bound::Excluded(last), Bound::Unbounded)).next(),
+            None => self.records.iter().next(),
+        }?;
+        self.last = Some(key.clone());
+        Some(Ok(Ok((key.clone(), values.clone()))))
+    }
 }
 
 #[derive(Default, Debug)]
@@ -118,8 +141,9 @@ impl Database for InMemoryDatabase {
             Some(schema) => match schema.objects.get_mut(object_name) {
                 Some(object) => {
                     let len = rows.len();
+                    let records = Arc::make_mut(&mut object.records);
                     for (key, value) in rows {
-                        object.records.insert(key, value);
+                        records.insert(key, value);
                     }
                     Ok(Ok(Ok(len)))
                 }
@@ -136,15 +160,10 @@ impl Database for InMemoryDatabase {
     ) -> io::Result<Result<Result<ReadCursor, DefinitionError>, StorageError>> {
         match self.schemas.read().expect("to acquire read lock").get(schema_name) {
             Some(schema) => match schema.objects.get(object_name) {
-                Some(object) => Ok(Ok(Ok(Box::new(
-                    object
-                        .records
-                        .clone()
-                        .into_iter()
-                        .map(|value| Ok(Ok(value)))
-                        .collect::<Vec<RowResult>>()
-                        .into_iter(),
-                )))),
+                Some(object) => Ok(Ok(Ok(Box::new(Records {
+                    records: object.records.clone(),
+                    last: None,
+                })))),
                 None => Ok(Ok(Err(DefinitionError::ObjectDoesNotExist))),
             },
             None => Ok(Ok(Err(DefinitionError::SchemaDoesNotExist))),
@@ -165,12 +184,10 @@ impl Database for InMemoryDatabase {
         {
             Some(schema) => match schema.objects.get_mut(object_name) {
                 Some(object) => {
-                    object.records = object
-                        .records
-                        .clone()
-                        .into_iter()
-                        .filter(|(key, _values)| !keys.contains(key))
-                        .collect();
+                    let records = Arc::make_mut(&mut object.records);
+                    for key in &keys {
+                        records.remove(key);
+                    }
                     Ok(Ok(Ok(keys.len())))
                 }
                 None => Ok(Ok(Err(DefinitionError::ObjectDoesNotExist))),
@@ -178,4 +195,8 @@ impl Database for InMemoryDatabase {
             None => Ok(Ok(Err(DefinitionError::SchemaDoesNotExist))),
         }
     }
+
+    fn checkpoint(&self) -> io::Result<Result<usize, StorageError>> {
+        Ok(Ok(0))
+    }
 }
@@ -85,6 +85,9 @@ pub trait Database {
         object_name: ObjectId,
         keys: Vec<Key>,
     ) -> io::Result<Result<Result<usize, DefinitionError>, StorageError>>;
+
+    /// Makes all written data durable, returns the number of flushed bytes
+    fn checkpoint(&self) -> io::Result<Result<usize, StorageError>>;
 }
 
 #[cfg(test)]
@@ -14,7 +14,7 @@
 
 use crate::{Database, DefinitionError, InitStatus, Key, ObjectId, ReadCursor, Row, RowResult, SchemaId, StorageError};
 use representation::Binary;
-use sled::{Db as Schema, DiskPtr, Error as SledError, IVec, Tree};
+use sled::{Batch, Db as Schema, DiskPtr, Error as SledError, IVec, Tree};
 use std::{
     collections::HashMap,
     io::{self, ErrorKind},
@@ -145,14 +145,13 @@ impl PersistentDatabase {
         schema.drop_tree(tree)
     }
 
-    fn insert_into_tree_with_failpoint(
-        &self,
-        tree: &Tree,
-        key: &Binary,
-        values: &Binary,
-    ) -> Result<Option<IVec>, SledError> {
+    fn insert_into_tree_with_failpoint(&self, tree: &Tree, rows: &[Row]) -> Result<(), SledError> {
         fail::fail_point!("sled-fail-to-insert-into-tree", |kind| Err(sled_error(kind)));
-        tree.insert(key.to_bytes(), values.to_bytes())
+        let mut batch = Batch::default();
+        for (key, values) in rows {
+            batch.insert(key.to_bytes(), values.to_bytes());
+        }
+        tree.apply_batch(batch)
     }
 
     fn tree_flush(
@@ -186,9 +185,13 @@ impl PersistentDatabase {
         ));
         Box::new(object.iter())
     }
-    fn remove_fro_tree_with_failpoint(&self, object: &Tree, key: Binary) -> Result<Option<IVec>, SledError> {
+    fn remove_from_tree_with_failpoint(&self, object: &Tree, keys: &[Key]) -> Result<(), SledError> {
         fail::fail_point!("sled-fail-to-remove-from-tree", |kind| Err(sled_error(kind)));
-        object.remove(key.to_bytes())
+        let mut batch = Batch::default();
+        for key in keys {
+            batch.remove(key.to_bytes());
+        }
+        object.apply_batch(batch)
     }
 
     fn empty_iterator(&self) -> Box<dyn Iterator<Item = RowResult>> {
@@ -277,22 +280,18 @@ impl Database for PersistentDatabase {
                 if schema.tree_names().contains(&(object_name.into())) {
                     match self.open_tree(schema.clone(), object_name) {
                         Ok(Ok(Ok(object))) => {
-                            let mut written_rows = 0;
-                            for (key, values) in rows.iter() {
-                                match self.insert_into_tree_with_failpoint(&object, key, values) {
-                                    Ok(_) => written_rows += 1,
-                                    Err(error) => match error {
-                                        SledError::Io(io_error) => return Err(io_error),
-                                        SledError::Corruption { .. } => return Ok(Err(StorageError::Storage)),
-                                        SledError::ReportableBug(_) => return Ok(Err(StorageError::Storage)),
-                                        SledError::Unsupported(_) => return Ok(Err(StorageError::Storage)),
-                                        SledError::CollectionNotFound(_) => {
-                                            return Ok(Ok(Err(DefinitionError::ObjectDoesNotExist)))
-                                        }
-                                    },
-                                }
+                            if let Err(error) = self.insert_into_tree_with_failpoint(&object, &rows) {
+                                return match error {
+                                    SledError::Io(io_error) => Err(io_error),
+                                    SledError::Corruption { .. } => Ok(Err(StorageError::Storage)),
+                                    SledError::ReportableBug(_) => Ok(Err(StorageError::Storage)),
+                                    SledError::Unsupported(_) => Ok(Err(StorageError::Storage)),
+                                    SledError::CollectionNotFound(_) => {
+                                        Ok(Ok(Err(DefinitionError::ObjectDoesNotExist)))
+                                    }
+                                };
                             }
-                            self.tree_flush(object, written_rows)
+                            self.tree_flush(object, rows.len())
                         }
                         otherwise => otherwise.map(|io| io.map(|storage| storage.map(|_object| 0))),
                     }
@@ -357,22 +356,18 @@ impl Database for PersistentDatabase {
                 if schema.tree_names().contains(&(object_name.into())) {
                     match self.open_tree(schema.clone(), object_name) {
                         Ok(Ok(Ok(object))) => {
-                            let mut deleted = 0;
-                            for key in keys {
-                                match self.remove_fro_tree_with_failpoint(&object, key) {
-                                    Ok(_) => deleted += 1,
-                                    Err(error) => match error {
-                                        SledError::Io(io_error) => return Err(io_error),
-                                        SledError::Corruption { .. } => return Ok(Err(StorageError::Storage)),
-                                        SledError::ReportableBug(_) => return Ok(Err(StorageError::Storage)),
-                                        SledError::Unsupported(_) => return Ok(Err(StorageError::Storage)),
-                                        SledError::CollectionNotFound(_) => {
-                                            return Ok(Ok(Err(DefinitionError::ObjectDoesNotExist)))
-                                        }
-                                    },
-                                }
+                            if let Err(error) = self.remove_from_tree_with_failpoint(&object, &keys) {
+                                return match error {
+                                    SledError::Io(io_error) => Err(io_error),
+                                    SledError::Corruption { .. } => Ok(Err(StorageError::Storage)),
+                                    SledError::ReportableBug(_) => Ok(Err(StorageError::Storage)),
+                                    SledError::Unsupported(_) => Ok(Err(StorageError::Storage)),
+                                    SledError::CollectionNotFound(_) => {
+                                        Ok(Ok(Err(DefinitionError::ObjectDoesNotExist)))
+                                    }
+                                };
                             }
-                            self.tree_flush(object, deleted)
+                            self.tree_flush(object, keys.len())
                         }
                         otherwise => otherwise.map(|io| io.map(|storage| storage.map(|_object| 0))),
                     }
@@ -383,6 +378,18 @@ impl Database for PersistentDatabase {
             None => Ok(Ok(Err(DefinitionError::SchemaDoesNotExist))),
         }
     }
+
+    fn checkpoint(&self) -> io::Result<Result<usize, StorageError>> {
+        let mut flushed = 0;
+        for schema in self.schemas.read().expect("to acquire read lock").values() {
+            match schema.flush() {
+                Ok(bytes) => flushed += bytes,
+                Err(SledError::Io(io_error)) => return Err(io_error),
+                Err(_error) => return Ok(Err(StorageError::Storage)),
+            }
+        }
+        Ok(Ok(flushed))
+    }
 }
 
 fn sled_error(kind: Option<String>) -> SledError {
@@ -310,6 +310,61 @@ mod operations_on_object {
         );
     }
 
+    #[rstest::rstest]
+    fn scan_reads_records_as_they_were_when_it_started(
+        with_object: Storage,
+        schema_name: SchemaId,
+        object_name: ObjectId,
+    ) {
+        with_object
+            .write(
+                schema_name,
+                object_name,
+                as_rows(vec![(1u8, vec!["123"]), (2u8, vec!["456"])]),
+            )
+            .expect("no io error")
+            .expect("no platform error")
+            .expect("values are written");
+        let mut scan = with_object
+            .read(schema_name, object_name)
+            .expect("no io error")
+            .expect("no platform error")
+            .expect("object exists");
+        assert_eq!(
+            scan.next().map(|ok| ok.expect("no io error")),
+            as_read_cursor(vec![(1u8, vec!["123"])])
+                .map(|ok| ok.expect("no io error"))
+                .next()
+        );
+
+        with_object
+            .write(schema_name, object_name, as_rows(vec![(3u8, vec!["789"])]))
+            .expect("no io error")
+            .expect("no platform error")
+            .expect("values are written");
+        with_object
+            .delete(schema_name, object_name, as_keys(vec![2u8]))
+            .expect("no io error")
+            .expect("no platform error")
+            .expect("values are deleted");
+
+        assert_eq!(
+            scan.map(|ok| ok.expect("no io error")).collect::<Vec<_>>(),
+            as_read_cursor(vec![(2u8, vec!["456"])])
+                .map(|ok| ok.expect("no io error"))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            with_object
+                .read(schema_name, object_name)
+                .expect("no io error")
+                .expect("no platform error")
+                .expect("object exists")
+                .count(),
+            2
+        );
+    }
+
     #[rstest::rstest]
     fn write_delete_read_records_from_object(with_object: Storage, schema_name: SchemaId, object_name: ObjectId) {
         with_object
@@ -419,3 +474,28 @@ mod operations_on_object {
         );
     }
 }
+
+#[cfg(test)]
+mod checkpoint {
+    use super::*;
+
+    #[rstest::rstest]
+    fn checkpoint_written_rows(with_object: Storage, schema_name: SchemaId, object_name: ObjectId) {
+        with_object
+            .write(schema_name, object_name, as_rows(vec![(1u8, vec!["123"])]))
+            .expect("no io error")
+            .expect("no storage error")
+            .expect("values are written");
+
+        assert!(with_object.checkpoint().expect("no io error").is_ok());
+        assert_eq!(
+            with_object
+                .read(schema_name, object_name)
+                .expect("no io error")
+                .expect("no storage error")
+                .expect("no platform error")
+                .count(),
+            1
+        );
+    }
+}
@@ -421,3 +421,28 @@ mod operations_on_object {
         );
     }
 }
+
+#[cfg(test)]
+mod checkpoint {
+    use super::*;
+
+    #[rstest::rstest]
+    fn checkpoint_written_rows(with_object: Storage, schema_name: SchemaId, object_name: ObjectId) {
+        with_object
+            .write(schema_name, object_name, as_rows(vec![(1u8, vec!["123"])]))
+            .expect("no io error")
+            .expect("no storage error")
+            .expect("values are written");
+
+        assert!(with_object.checkpoint().expect("no io error").is_ok());
+        assert_eq!(
+            with_object
+                .read(schema_name, object_name)
+                .expect("no io error")
+                .expect("no storage error")
+                .expect("no platform error")
+                .count(),
+            1
+        );
+    }
+}